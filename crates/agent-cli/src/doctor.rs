@@ -0,0 +1,307 @@
+//! Environment diagnostics for `agent-cli doctor`
+//!
+//! Runs the data source checks from [`agent_stock::doctor`] and adds the
+//! checks that live outside the stock crate: whether the configured LLM model
+//! is served, and whether every configured MCP server accepts a connection.
+
+use agent_llm::LLMError;
+use agent_llm::providers::{
+    AnthropicProvider, OllamaConfig, OllamaProvider, OpenAIConfig, OpenAIProvider,
+};
+use agent_mcp::MCPServerConfig;
+use agent_mcp::client::MCPClient;
+use agent_mcp::client::http::HttpMCPClient;
+use agent_mcp::client::stdio::StdioMCPClient;
+use agent_mcp::config::MCPConfig;
+use agent_stock::StockConfig;
+use agent_stock::doctor::{CheckResult, CheckStatus, Doctor, DoctorReport};
+use std::time::{Duration, Instant};
+
+/// How long to wait for an MCP server to finish its handshake
+const MCP_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Timeout for the model listing request
+const MODEL_TIMEOUT_SECS: u64 = 15;
+
+/// Run every check
+pub async fn run() -> anyhow::Result<DoctorReport> {
+    let config = StockConfig::builder()
+        .with_env_all_keys()
+        .from_env_model()
+        .build()?;
+
+    let doctor = Doctor::from_config(&config);
+    let (mut report, model, mcp) = futures::join!(
+        doctor.run(),
+        check_model(&config.model),
+        check_mcp_servers(),
+    );
+    report.extend([model]);
+    report.extend(mcp);
+    Ok(report)
+}
+
+/// Check that the configured model is served by the configured provider
+///
+/// Uses the same environment as `stock-bot`: Ollama when `OLLAMA_MODEL` is
+/// set, an OpenAI-compatible server when `OPENAI_API_BASE` or
+/// `OPENAI_API_KEY` is set, Anthropic when only `ANTHROPIC_API_KEY` is set.
+/// `OPENAI_MODEL` overrides the configured model.
+async fn check_model(configured_model: &str) -> CheckResult {
+    let env = |name| std::env::var(name).ok();
+    let started = Instant::now();
+
+    if let Some(model) = env("OLLAMA_MODEL") {
+        let config = match OllamaConfig::from_env() {
+            Ok(config) => config.with_timeout(MODEL_TIMEOUT_SECS),
+            Err(e) => return CheckResult::new("LLM", CheckStatus::Fail, e.to_string()),
+        };
+        let endpoint = config.api_base.clone();
+        let models = match OllamaProvider::with_config(config) {
+            Ok(provider) => provider.list_models().await,
+            Err(e) => Err(e),
+        };
+        // Ollama lists untagged models under their `latest` tag
+        let model = if model.contains(':') {
+            model
+        } else {
+            format!("{model}:latest")
+        };
+        return model_result(&model, &endpoint, "OLLAMA_HOST", "OLLAMA_MODEL", models)
+            .with_latency(started);
+    }
+
+    if env("OPENAI_API_BASE").is_some() || env("OPENAI_API_KEY").is_some() {
+        let model = env("OPENAI_MODEL").unwrap_or_else(|| configured_model.to_string());
+        let mut config = OpenAIConfig::new(env("OPENAI_API_KEY").unwrap_or_default())
+            .with_timeout(MODEL_TIMEOUT_SECS);
+        if let Some(api_base) = env("OPENAI_API_BASE") {
+            config = config.with_api_base(api_base);
+        }
+        let endpoint = config.api_base.clone();
+        let models = match OpenAIProvider::with_config(config) {
+            Ok(provider) => provider.list_models().await,
+            Err(e) => Err(e),
+        };
+        return model_result(&model, &endpoint, "OPENAI_API_KEY", "OPENAI_MODEL", models)
+            .with_latency(started);
+    }
+
+    if let Some(api_key) = env("ANTHROPIC_API_KEY") {
+        let models = match AnthropicProvider::new(api_key) {
+            Ok(provider) => provider.list_models().await,
+            Err(e) => Err(e),
+        };
+        return model_result(
+            configured_model,
+            "api.anthropic.com",
+            "ANTHROPIC_API_KEY",
+            "STOCK_MODEL",
+            models,
+        )
+        .with_latency(started);
+    }
+
+    CheckResult::new("LLM", CheckStatus::Fail, "no LLM provider configured").with_fix(
+        "Export OLLAMA_MODEL for a local Ollama server, OPENAI_API_BASE (plus \
+         OPENAI_API_KEY and OPENAI_MODEL) for an OpenAI-compatible server, or \
+         ANTHROPIC_API_KEY",
+    )
+}
+
+/// Turn a model listing into a check result
+fn model_result(
+    model: &str,
+    endpoint: &str,
+    key_var: &str,
+    model_var: &str,
+    models: agent_llm::Result<Vec<String>>,
+) -> CheckResult {
+    match models {
+        Ok(models) if models.iter().any(|m| m == model) => CheckResult::new(
+            "LLM",
+            CheckStatus::Ok,
+            format!("{model} available at {endpoint}"),
+        ),
+        Ok(models) if models.is_empty() => CheckResult::new(
+            "LLM",
+            CheckStatus::Warn,
+            format!("{endpoint} lists no models; cannot confirm {model}"),
+        )
+        .with_fix("Make sure a model is loaded on the server"),
+        Ok(models) => {
+            let shown: Vec<&str> = models.iter().take(5).map(String::as_str).collect();
+            CheckResult::new(
+                "LLM",
+                CheckStatus::Fail,
+                format!("{model} is not served by {endpoint}"),
+            )
+            .with_fix(format!(
+                "Set {model_var} to an available model, e.g. {}",
+                shown.join(", ")
+            ))
+        }
+        Err(LLMError::AuthenticationFailed) => CheckResult::new(
+            "LLM",
+            CheckStatus::Fail,
+            format!("{endpoint} rejected the API key"),
+        )
+        .with_fix(format!("Check {key_var}")),
+        Err(LLMError::HttpError(e)) if e.is_connect() || e.is_timeout() => {
+            CheckResult::new("LLM", CheckStatus::Fail, format!("cannot reach {endpoint}")).with_fix(
+                format!(
+                    "Check your network connection and that {endpoint} is up (for a local server, \
+             start it or fix OPENAI_API_BASE / OLLAMA_HOST)"
+                ),
+            )
+        }
+        Err(e) => CheckResult::new("LLM", CheckStatus::Fail, e.to_string()),
+    }
+}
+
+/// Connect to every configured MCP server and list its tools
+async fn check_mcp_servers() -> Vec<CheckResult> {
+    let config = match MCPConfig::load_merged() {
+        Ok(config) => config,
+        Err(e) => {
+            return vec![
+                CheckResult::new("MCP", CheckStatus::Fail, e.to_string())
+                    .with_fix("Fix the syntax of .mcp.json or ~/.config/agent-rs/mcp.json"),
+            ];
+        }
+    };
+    if config.mcp_servers.is_empty() {
+        return vec![CheckResult::new(
+            "MCP",
+            CheckStatus::Skipped,
+            "no servers in .mcp.json or ~/.config/agent-rs/mcp.json",
+        )];
+    }
+
+    let mut servers: Vec<_> = config.mcp_servers.iter().collect();
+    servers.sort_by_key(|(name, _)| name.as_str());
+    futures::future::join_all(
+        servers
+            .into_iter()
+            .map(|(name, server)| check_mcp_server(name, server)),
+    )
+    .await
+}
+
+async fn check_mcp_server(name: &str, server: &MCPServerConfig) -> CheckResult {
+    let source = format!("MCP {name}");
+    let started = Instant::now();
+    let client: Box<dyn MCPClient> = match server {
+        MCPServerConfig::Stdio { .. } => match StdioMCPClient::from_config(server) {
+            Ok(client) => Box::new(client),
+            Err(e) => return CheckResult::new(source, CheckStatus::Fail, e.to_string()),
+        },
+        MCPServerConfig::Http { .. } | MCPServerConfig::Sse { .. } => {
+            match HttpMCPClient::from_config(server) {
+                Ok(client) => Box::new(client),
+                Err(e) => return CheckResult::new(source, CheckStatus::Fail, e.to_string()),
+            }
+        }
+    };
+
+    let outcome = tokio::time::timeout(MCP_CONNECT_TIMEOUT, async {
+        client.connect().await?;
+        client.list_tools().await
+    })
+    .await;
+    let _ = client.disconnect().await;
+
+    let result = match outcome {
+        Ok(Ok(tools)) => {
+            return CheckResult::new(
+                source,
+                CheckStatus::Ok,
+                format!("connected, {} tools", tools.len()),
+            )
+            .with_latency(started);
+        }
+        Ok(Err(e)) => CheckResult::new(source, CheckStatus::Fail, e.to_string()),
+        Err(_) => CheckResult::new(
+            source,
+            CheckStatus::Fail,
+            format!("no response within {}s", MCP_CONNECT_TIMEOUT.as_secs()),
+        ),
+    };
+    let fix = match server {
+        MCPServerConfig::Stdio { command, .. } => {
+            format!("Check that `{command}` is installed, on PATH and speaks MCP over stdio")
+        }
+        MCPServerConfig::Http { url, .. } | MCPServerConfig::Sse { url, .. } => {
+            format!("Check that the server at {url} is running and reachable")
+        }
+    };
+    result.with_fix(fix).with_latency(started)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn models(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_model_available() {
+        let result = model_result(
+            "gpt-4o",
+            "local",
+            "OPENAI_API_KEY",
+            "OPENAI_MODEL",
+            Ok(models(&["gpt-4o"])),
+        );
+        assert_eq!(result.status, CheckStatus::Ok);
+    }
+
+    #[test]
+    fn test_missing_model_suggests_alternatives() {
+        let result = model_result(
+            "gpt-4o",
+            "local",
+            "OPENAI_API_KEY",
+            "OPENAI_MODEL",
+            Ok(models(&["qwen2.5-7b", "llama-3.1-8b"])),
+        );
+        assert_eq!(result.status, CheckStatus::Fail);
+        assert_eq!(
+            result.fix.as_deref(),
+            Some("Set OPENAI_MODEL to an available model, e.g. qwen2.5-7b, llama-3.1-8b")
+        );
+    }
+
+    #[test]
+    fn test_rejected_key_names_env_var() {
+        let result = model_result(
+            "claude",
+            "api.anthropic.com",
+            "ANTHROPIC_API_KEY",
+            "STOCK_MODEL",
+            Err(LLMError::AuthenticationFailed),
+        );
+        assert_eq!(result.status, CheckStatus::Fail);
+        assert_eq!(result.fix.as_deref(), Some("Check ANTHROPIC_API_KEY"));
+    }
+
+    #[tokio::test]
+    async fn test_unstartable_stdio_server_fails_with_fix() {
+        let server = MCPServerConfig::Stdio {
+            command: "agent-cli-doctor-no-such-command".to_string(),
+            args: Vec::new(),
+            env: std::collections::HashMap::new(),
+            cwd: None,
+        };
+        let result = check_mcp_server("missing", &server).await;
+        assert_eq!(result.source, "MCP missing");
+        assert_eq!(result.status, CheckStatus::Fail);
+        assert!(
+            result
+                .fix
+                .is_some_and(|fix| fix.contains("agent-cli-doctor-no-such-command"))
+        );
+    }
+}
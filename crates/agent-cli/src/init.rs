@@ -0,0 +1,713 @@
+//! Interactive first-run setup for `agent-cli init`
+//!
+//! Asks which LLM server, data sources and chat platforms to use, verifies
+//! every key as it is entered, then writes two files to the target directory:
+//!
+//! - `.env`: keys and settings, loaded by `agent-cli` and `stock-bot` at
+//!   startup. Existing lines for variables the wizard does not touch are kept.
+//! - `agent-rs.json`: the non-secret choices, used as defaults when the wizard
+//!   runs again. Safe to commit.
+//!
+//! Telegram bots can optionally register their command menu and webhook.
+
+use agent_llm::providers::{OpenAIConfig, OpenAIProvider};
+use agent_stock::StockConfig;
+use agent_stock::bot::Command;
+use agent_stock::doctor::{CheckResult, CheckStatus, Doctor};
+use agent_stock::platforms::TelegramApi;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+/// Environment file written by the wizard
+pub const ENV_FILE: &str = ".env";
+
+/// Non-secret record of the wizard's choices
+pub const SETUP_FILE: &str = "agent-rs.json";
+
+/// How many times a rejected key may be re-entered
+const MAX_ATTEMPTS: usize = 3;
+
+/// OpenAI-compatible servers offered by the wizard: (label, API base)
+const LLM_PRESETS: &[(&str, &str)] = &[
+    ("LM Studio", "http://localhost:1234/v1"),
+    ("Ollama", "http://localhost:11434/v1"),
+    ("OpenAI", "https://api.openai.com/v1"),
+];
+
+/// Data sources that need a key: (name, environment variable)
+const DATA_SOURCES: &[(&str, &str)] = &[
+    ("FRED", "FRED_API_KEY"),
+    ("Finnhub", "FINNHUB_API_KEY"),
+    ("Alpha Vantage", "ALPHA_VANTAGE_API_KEY"),
+];
+
+/// Choices recorded in `agent-rs.json`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SetupRecord {
+    /// OpenAI-compatible API base
+    pub llm_api_base: Option<String>,
+    /// Model served at `llm_api_base`
+    pub model: Option<String>,
+    /// Configured data sources, e.g. "FRED"
+    #[serde(default)]
+    pub data_sources: Vec<String>,
+    /// Configured chat platforms, e.g. "telegram"
+    #[serde(default)]
+    pub platforms: Vec<String>,
+}
+
+/// Everything the wizard collected
+#[derive(Debug, Default)]
+pub struct Setup {
+    /// Variables for `.env`, in prompt order
+    pub env: Vec<(String, String)>,
+    pub record: SetupRecord,
+}
+
+impl Setup {
+    fn set(&mut self, key: &str, value: impl Into<String>) {
+        self.env.push((key.to_string(), value.into()));
+    }
+}
+
+/// Checks keys and tokens against the real services
+#[async_trait]
+pub trait Verifier: Send + Sync {
+    /// Models served by an OpenAI-compatible server
+    async fn llm_models(&self, api_base: &str, api_key: &str) -> Result<Vec<String>, String>;
+
+    /// Check a data source key; `source` is a name from [`DATA_SOURCES`]
+    async fn data_key(&self, source: &str, key: &str) -> CheckResult;
+
+    /// Verify a Telegram bot token; returns the bot's username
+    async fn telegram_bot(&self, token: &str) -> Result<String, String>;
+
+    /// Register the command menu and, if given, the webhook
+    async fn telegram_register(&self, token: &str, webhook: Option<&str>) -> Result<(), String>;
+}
+
+/// [`Verifier`] that calls the real services
+pub struct LiveVerifier;
+
+#[async_trait]
+impl Verifier for LiveVerifier {
+    async fn llm_models(&self, api_base: &str, api_key: &str) -> Result<Vec<String>, String> {
+        let config = OpenAIConfig::new(api_key)
+            .with_api_base(api_base)
+            .with_timeout(15);
+        let provider = OpenAIProvider::with_config(config).map_err(|e| e.to_string())?;
+        provider.list_models().await.map_err(|e| e.to_string())
+    }
+
+    async fn data_key(&self, source: &str, key: &str) -> CheckResult {
+        let mut config = StockConfig::default();
+        match source {
+            "FRED" => config.fred_api_key = Some(key.to_string()),
+            "Finnhub" => config.finnhub_api_key = Some(key.to_string()),
+            _ => config.alpha_vantage_api_key = Some(key.to_string()),
+        }
+        let doctor = Doctor::from_config(&config);
+        match source {
+            "FRED" => doctor.check_fred().await,
+            "Finnhub" => doctor.check_finnhub().await,
+            _ => doctor.check_alpha_vantage().await,
+        }
+    }
+
+    async fn telegram_bot(&self, token: &str) -> Result<String, String> {
+        TelegramApi::new(token)
+            .get_me()
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn telegram_register(&self, token: &str, webhook: Option<&str>) -> Result<(), String> {
+        let api = TelegramApi::new(token);
+        api.set_my_commands(Command::menu())
+            .await
+            .map_err(|e| e.to_string())?;
+        if let Some(url) = webhook {
+            api.set_webhook(url).await.map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+/// Interactive prompts over any input and output
+pub struct Wizard<'a, R, W> {
+    input: R,
+    output: W,
+    verifier: &'a dyn Verifier,
+    /// Values from an existing `.env`, offered as defaults
+    existing: HashMap<String, String>,
+    /// Choices from an existing `agent-rs.json`, offered as defaults
+    previous: SetupRecord,
+}
+
+impl<'a, R: BufRead, W: Write> Wizard<'a, R, W> {
+    pub fn new(input: R, output: W, verifier: &'a dyn Verifier) -> Self {
+        Self {
+            input,
+            output,
+            verifier,
+            existing: HashMap::new(),
+            previous: SetupRecord::default(),
+        }
+    }
+
+    /// Offer values from a previous run as defaults
+    pub fn with_previous(mut self, env: HashMap<String, String>, record: SetupRecord) -> Self {
+        self.existing = env;
+        self.previous = record;
+        self
+    }
+
+    /// Ask every question and collect the answers
+    pub async fn run(&mut self) -> io::Result<Setup> {
+        let mut setup = Setup::default();
+        writeln!(self.output, "agent-rs setup\n==============")?;
+        writeln!(self.output, "Press Enter to accept the [default].\n")?;
+
+        self.llm(&mut setup).await?;
+        self.data_sources(&mut setup).await?;
+        self.platforms(&mut setup).await?;
+        Ok(setup)
+    }
+
+    async fn llm(&mut self, setup: &mut Setup) -> io::Result<()> {
+        writeln!(self.output, "LLM server (OpenAI-compatible)")?;
+        for (i, (label, api_base)) in LLM_PRESETS.iter().enumerate() {
+            writeln!(self.output, "  {}) {label} ({api_base})", i + 1)?;
+        }
+        writeln!(self.output, "  {}) Other", LLM_PRESETS.len() + 1)?;
+
+        let previous_base = self
+            .previous
+            .llm_api_base
+            .clone()
+            .or_else(|| self.existing.get("OPENAI_API_BASE").cloned());
+        let default_choice = previous_base
+            .as_deref()
+            .map_or(Some(0), |base| {
+                LLM_PRESETS.iter().position(|(_, b)| *b == base)
+            })
+            .map_or(LLM_PRESETS.len() + 1, |i| i + 1);
+        let choice = self.ask("Choice", Some(&default_choice.to_string()))?;
+        let api_base = match choice.parse::<usize>() {
+            Ok(n) if (1..=LLM_PRESETS.len()).contains(&n) => LLM_PRESETS[n - 1].1.to_string(),
+            _ => self.ask("API base URL", previous_base.as_deref())?,
+        };
+
+        let mut api_key = self
+            .existing
+            .get("OPENAI_API_KEY")
+            .cloned()
+            .unwrap_or_default();
+        let mut models = Vec::new();
+        for attempt in 1..=MAX_ATTEMPTS {
+            let local = api_base.contains("localhost") || api_base.contains("127.0.0.1");
+            if !local || attempt > 1 {
+                let default = (!api_key.is_empty()).then_some(api_key.as_str());
+                api_key = self.ask_secret("API key", default)?;
+            }
+            match self.verifier.llm_models(&api_base, &api_key).await {
+                Ok(found) => {
+                    writeln!(self.output, "  ✓ {} models available", found.len())?;
+                    models = found;
+                    break;
+                }
+                Err(e) => {
+                    writeln!(self.output, "  ✗ {e}")?;
+                    if attempt == MAX_ATTEMPTS || !self.confirm("Try again?", true)? {
+                        writeln!(
+                            self.output,
+                            "  Saving anyway; run `agent-cli doctor` once the server is up"
+                        )?;
+                        break;
+                    }
+                }
+            }
+        }
+
+        if !models.is_empty() {
+            let shown: Vec<&str> = models.iter().take(10).map(String::as_str).collect();
+            writeln!(self.output, "  Models: {}", shown.join(", "))?;
+        }
+        let default_model = self
+            .previous
+            .model
+            .clone()
+            .or_else(|| self.existing.get("OPENAI_MODEL").cloned())
+            .filter(|m| models.is_empty() || models.contains(m))
+            .or_else(|| models.first().cloned());
+        let model = loop {
+            let model = self.ask("Model", default_model.as_deref())?;
+            if models.is_empty() || models.contains(&model) {
+                break model;
+            }
+            writeln!(self.output, "  ✗ {model} is not served by {api_base}")?;
+        };
+
+        setup.set("OPENAI_API_BASE", &api_base);
+        if !api_key.is_empty() {
+            setup.set("OPENAI_API_KEY", &api_key);
+        }
+        setup.set("OPENAI_MODEL", &model);
+        setup.record.llm_api_base = Some(api_base);
+        setup.record.model = Some(model);
+        writeln!(self.output)?;
+        Ok(())
+    }
+
+    async fn data_sources(&mut self, setup: &mut Setup) -> io::Result<()> {
+        writeln!(
+            self.output,
+            "Data sources (Yahoo Finance and SEC EDGAR need no key)"
+        )?;
+        for (source, env_var) in DATA_SOURCES {
+            let configured = self.previous.data_sources.iter().any(|s| s == source)
+                || self.existing.contains_key(*env_var);
+            if !self.confirm(&format!("Use {source}?"), configured)? {
+                continue;
+            }
+            if let Some(key) = self.verified_key(source, env_var).await? {
+                setup.set(env_var, key);
+                setup.record.data_sources.push((*source).to_string());
+            }
+        }
+        writeln!(self.output)?;
+        Ok(())
+    }
+
+    /// Prompt for a data source key until it verifies or the user gives up
+    async fn verified_key(&mut self, source: &str, env_var: &str) -> io::Result<Option<String>> {
+        let previous = self.default_of(env_var);
+        for attempt in 1..=MAX_ATTEMPTS {
+            let key = self.ask_secret(&format!("{source} key ({env_var})"), previous.as_deref())?;
+            if key.is_empty() {
+                return Ok(None);
+            }
+            let check = self.verifier.data_key(source, &key).await;
+            if check.status == CheckStatus::Ok {
+                writeln!(self.output, "  ✓ {}", check.detail)?;
+                return Ok(Some(key));
+            }
+            writeln!(self.output, "  ✗ {}", check.detail)?;
+            if let Some(fix) = &check.fix {
+                writeln!(self.output, "    {fix}")?;
+            }
+            if attempt == MAX_ATTEMPTS || !self.confirm("Try another key?", true)? {
+                return Ok(self.confirm("Save this key anyway?", false)?.then_some(key));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn platforms(&mut self, setup: &mut Setup) -> io::Result<()> {
+        writeln!(
+            self.output,
+            "Chat platforms (the terminal chat always works)"
+        )?;
+        let previously = |platform: &str, env_var: &str| {
+            self.previous.platforms.iter().any(|p| p == platform)
+                || self.existing.contains_key(env_var)
+        };
+        let telegram = previously("telegram", "TELEGRAM_BOT_TOKEN");
+        let dingtalk = previously("dingtalk", "DINGTALK_WEBHOOK");
+        let feishu = previously("feishu", "FEISHU_APP_ID");
+
+        if self.confirm("Set up Telegram?", telegram)? {
+            self.telegram(setup).await?;
+        }
+
+        if self.confirm("Set up DingTalk?", dingtalk)? {
+            let default = self.default_of("DINGTALK_WEBHOOK");
+            let webhook = self.ask("DingTalk webhook URL", default.as_deref())?;
+            let default = self.default_of("DINGTALK_SECRET");
+            let secret = self.ask_secret("DingTalk signing secret", default.as_deref())?;
+            setup.set("DINGTALK_WEBHOOK", webhook);
+            if !secret.is_empty() {
+                setup.set("DINGTALK_SECRET", secret);
+            }
+            setup.record.platforms.push("dingtalk".to_string());
+        }
+
+        if self.confirm("Set up Feishu?", feishu)? {
+            for (label, env_var) in [
+                ("Feishu app ID", "FEISHU_APP_ID"),
+                ("Feishu app secret", "FEISHU_APP_SECRET"),
+                ("Feishu verification token", "FEISHU_VERIFICATION_TOKEN"),
+            ] {
+                let default = self.default_of(env_var);
+                let value = self.ask_secret(label, default.as_deref())?;
+                setup.set(env_var, value);
+            }
+            setup.record.platforms.push("feishu".to_string());
+        }
+        Ok(())
+    }
+
+    async fn telegram(&mut self, setup: &mut Setup) -> io::Result<()> {
+        let mut token = String::new();
+        let default = self.default_of("TELEGRAM_BOT_TOKEN");
+        for attempt in 1..=MAX_ATTEMPTS {
+            token = self.ask_secret("Telegram bot token (from @BotFather)", default.as_deref())?;
+            match self.verifier.telegram_bot(&token).await {
+                Ok(username) => {
+                    writeln!(self.output, "  ✓ Connected to @{username}")?;
+                    break;
+                }
+                Err(e) => {
+                    writeln!(self.output, "  ✗ {e}")?;
+                    if attempt == MAX_ATTEMPTS || !self.confirm("Try another token?", true)? {
+                        break;
+                    }
+                }
+            }
+        }
+
+        let default = self.default_of("TELEGRAM_WEBHOOK_URL");
+        let webhook = self.ask("Webhook URL (empty for long polling)", default.as_deref())?;
+        let webhook = (!webhook.is_empty()).then_some(webhook);
+
+        if self.confirm("Register the bot's command menu with Telegram now?", true)? {
+            match self
+                .verifier
+                .telegram_register(&token, webhook.as_deref())
+                .await
+            {
+                Ok(()) if webhook.is_some() => {
+                    writeln!(self.output, "  ✓ Commands and webhook registered")?;
+                }
+                Ok(()) => writeln!(self.output, "  ✓ Commands registered")?,
+                Err(e) => writeln!(self.output, "  ✗ {e}")?,
+            }
+        }
+
+        setup.set("TELEGRAM_BOT_TOKEN", token);
+        if let Some(webhook) = webhook {
+            setup.set("TELEGRAM_WEBHOOK_URL", webhook);
+        }
+        setup.record.platforms.push("telegram".to_string());
+        Ok(())
+    }
+
+    /// Value of `env_var` from an existing `.env`
+    fn default_of(&self, env_var: &str) -> Option<String> {
+        self.existing.get(env_var).cloned()
+    }
+
+    /// Ask a question; an empty answer selects the default
+    fn ask(&mut self, prompt: &str, default: Option<&str>) -> io::Result<String> {
+        match default {
+            Some(default) if !default.is_empty() => write!(self.output, "{prompt} [{default}]: ")?,
+            _ => write!(self.output, "{prompt}: ")?,
+        }
+        self.output.flush()?;
+
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "setup cancelled",
+            ));
+        }
+        let answer = line.trim();
+        Ok(if answer.is_empty() {
+            default.unwrap_or_default().to_string()
+        } else {
+            answer.to_string()
+        })
+    }
+
+    /// Ask for a secret; the default is shown masked
+    fn ask_secret(&mut self, prompt: &str, default: Option<&str>) -> io::Result<String> {
+        let Some(default) = default.filter(|d| !d.is_empty()) else {
+            return self.ask(prompt, None);
+        };
+        let answer = self.ask(prompt, Some(&mask(default)))?;
+        Ok(if answer == mask(default) {
+            default.to_string()
+        } else {
+            answer
+        })
+    }
+
+    /// Ask a yes/no question
+    fn confirm(&mut self, prompt: &str, default: bool) -> io::Result<bool> {
+        let hint = if default { "Y/n" } else { "y/N" };
+        loop {
+            let answer = self.ask(&format!("{prompt} [{hint}]"), None)?;
+            match answer.to_lowercase().as_str() {
+                "" => return Ok(default),
+                "y" | "yes" => return Ok(true),
+                "n" | "no" => return Ok(false),
+                _ => writeln!(self.output, "  Please answer y or n")?,
+            }
+        }
+    }
+}
+
+/// Show only the last four characters of a secret
+fn mask(secret: &str) -> String {
+    let chars: Vec<char> = secret.chars().collect();
+    let visible: String = chars[chars.len().saturating_sub(4)..].iter().collect();
+    format!("****{visible}")
+}
+
+/// Parse `KEY=VALUE` lines, ignoring comments and blank lines
+pub fn parse_env(contents: &str) -> HashMap<String, String> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if line.starts_with('#') {
+                return None;
+            }
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (key, value) = line.split_once('=')?;
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .unwrap_or(value);
+            Some((key.trim().to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Apply `updates` to the contents of an env file
+///
+/// Lines for updated variables are replaced in place, new variables are
+/// appended, and every other line (including comments) is kept.
+pub fn merge_env(existing: &str, updates: &[(String, String)]) -> String {
+    let values: HashMap<&str, &str> = updates
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect();
+    let mut written = std::collections::HashSet::new();
+
+    let mut lines: Vec<String> = existing
+        .lines()
+        .map(|line| {
+            let key = line
+                .trim()
+                .strip_prefix("export ")
+                .unwrap_or(line.trim())
+                .split_once('=')
+                .map(|(key, _)| key.trim());
+            match key.and_then(|key| values.get_key_value(key)) {
+                Some((key, value)) if !line.trim_start().starts_with('#') => {
+                    written.insert(*key);
+                    env_line(key, value)
+                }
+                _ => line.to_string(),
+            }
+        })
+        .collect();
+
+    let new: Vec<_> = updates
+        .iter()
+        .filter(|(key, _)| !written.contains(key.as_str()))
+        .collect();
+    if !new.is_empty() {
+        if lines.is_empty() {
+            lines.push("# Written by `agent-cli init`".to_string());
+        }
+        lines.extend(new.iter().map(|(key, value)| env_line(key, value)));
+    }
+    let mut contents = lines.join("\n");
+    contents.push('\n');
+    contents
+}
+
+fn env_line(key: &str, value: &str) -> String {
+    if value.contains(char::is_whitespace) || value.contains('#') {
+        format!("{key}=\"{value}\"")
+    } else {
+        format!("{key}={value}")
+    }
+}
+
+/// Write `.env` and `agent-rs.json` into `dir`
+pub fn write_files(dir: &Path, setup: &Setup) -> io::Result<()> {
+    let env_path = dir.join(ENV_FILE);
+    let existing = std::fs::read_to_string(&env_path).unwrap_or_default();
+    std::fs::write(&env_path, merge_env(&existing, &setup.env))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&env_path, std::fs::Permissions::from_mode(0o600))?;
+    }
+
+    let record = serde_json::to_string_pretty(&setup.record).map_err(io::Error::other)?;
+    std::fs::write(dir.join(SETUP_FILE), record + "\n")
+}
+
+/// Run the wizard on the terminal and write its files into `dir`
+pub async fn run(dir: &Path) -> anyhow::Result<()> {
+    let existing = std::fs::read_to_string(dir.join(ENV_FILE))
+        .map(|contents| parse_env(&contents))
+        .unwrap_or_default();
+    let previous = std::fs::read_to_string(dir.join(SETUP_FILE))
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default();
+
+    let stdin = io::stdin();
+    let setup = Wizard::new(stdin.lock(), io::stdout(), &LiveVerifier)
+        .with_previous(existing, previous)
+        .run()
+        .await?;
+
+    write_files(dir, &setup)?;
+    println!(
+        "\nWrote {} and {} in {}",
+        ENV_FILE,
+        SETUP_FILE,
+        dir.display()
+    );
+    println!("Next: run `agent-cli doctor` to check everything end to end.");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Accepts keys that start with "good"
+    struct FakeVerifier;
+
+    #[async_trait]
+    impl Verifier for FakeVerifier {
+        async fn llm_models(&self, _api_base: &str, _api_key: &str) -> Result<Vec<String>, String> {
+            Ok(vec!["qwen2.5-7b".to_string(), "llama-3.1-8b".to_string()])
+        }
+
+        async fn data_key(&self, source: &str, key: &str) -> CheckResult {
+            if key.starts_with("good") {
+                CheckResult::new(source, CheckStatus::Ok, "key works")
+            } else {
+                CheckResult::new(source, CheckStatus::Fail, "401 Unauthorized")
+                    .with_fix("check the key")
+            }
+        }
+
+        async fn telegram_bot(&self, token: &str) -> Result<String, String> {
+            if token.starts_with("good") {
+                Ok("stock_bot".to_string())
+            } else {
+                Err("Telegram rejected the bot token".to_string())
+            }
+        }
+
+        async fn telegram_register(
+            &self,
+            _token: &str,
+            _webhook: Option<&str>,
+        ) -> Result<(), String> {
+            Ok(())
+        }
+    }
+
+    async fn run_wizard(answers: &[&str]) -> (Setup, String) {
+        let input = answers.join("\n") + "\n";
+        let mut output = Vec::new();
+        let setup = Wizard::new(input.as_bytes(), &mut output, &FakeVerifier)
+            .run()
+            .await
+            .unwrap_or_else(|e| panic!("wizard failed: {e}"));
+        (setup, String::from_utf8_lossy(&output).into_owned())
+    }
+
+    fn env_value<'a>(setup: &'a Setup, key: &str) -> Option<&'a str> {
+        setup
+            .env
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    #[tokio::test]
+    async fn test_wizard_collects_verified_keys() {
+        let (setup, output) = run_wizard(&[
+            "1", // LM Studio
+            "",  // first listed model
+            "y",
+            "bad-key", // FRED, rejected
+            "y",
+            "good-fred", // try again, accepted
+            "n",         // Finnhub
+            "n",         // Alpha Vantage
+            "y",
+            "good-token",
+            "",
+            "y", // Telegram, polling, register commands
+            "n", // DingTalk
+            "n", // Feishu
+        ])
+        .await;
+
+        assert_eq!(
+            env_value(&setup, "OPENAI_API_BASE"),
+            Some("http://localhost:1234/v1")
+        );
+        assert_eq!(env_value(&setup, "OPENAI_MODEL"), Some("qwen2.5-7b"));
+        assert_eq!(env_value(&setup, "FRED_API_KEY"), Some("good-fred"));
+        assert_eq!(env_value(&setup, "FINNHUB_API_KEY"), None);
+        assert_eq!(env_value(&setup, "TELEGRAM_BOT_TOKEN"), Some("good-token"));
+        assert_eq!(setup.record.data_sources, vec!["FRED"]);
+        assert_eq!(setup.record.platforms, vec!["telegram"]);
+
+        assert!(output.contains("✗ 401 Unauthorized"));
+        assert!(output.contains("✓ Connected to @stock_bot"));
+        assert!(output.contains("✓ Commands registered"));
+    }
+
+    #[tokio::test]
+    async fn test_rejected_key_is_dropped_unless_confirmed() {
+        let (setup, _) = run_wizard(&[
+            "1", "", "y", "bad-1", "n", "n", // FRED: give up, don't save
+            "y", "bad-2", "n", "y", // Finnhub: give up, save anyway
+            "n", "n", "n", "n",
+        ])
+        .await;
+
+        assert_eq!(env_value(&setup, "FRED_API_KEY"), None);
+        assert_eq!(env_value(&setup, "FINNHUB_API_KEY"), Some("bad-2"));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_model_is_asked_again() {
+        let (setup, output) =
+            run_wizard(&["1", "gpt-4o", "llama-3.1-8b", "n", "n", "n", "n", "n", "n"]).await;
+        assert_eq!(env_value(&setup, "OPENAI_MODEL"), Some("llama-3.1-8b"));
+        assert!(output.contains("gpt-4o is not served"));
+    }
+
+    #[test]
+    fn test_merge_env_keeps_unrelated_lines() {
+        let existing = "# mine\nRUST_LOG=debug\nFRED_API_KEY=old\n";
+        let merged = merge_env(
+            existing,
+            &[
+                ("FRED_API_KEY".to_string(), "new".to_string()),
+                ("OPENAI_MODEL".to_string(), "my model".to_string()),
+            ],
+        );
+        assert_eq!(
+            merged,
+            "# mine\nRUST_LOG=debug\nFRED_API_KEY=new\nOPENAI_MODEL=\"my model\"\n"
+        );
+        assert_eq!(parse_env(&merged)["OPENAI_MODEL"], "my model");
+    }
+
+    #[test]
+    fn test_mask() {
+        assert_eq!(mask("sk-abcdef1234"), "****1234");
+        assert_eq!(mask("ab"), "****ab");
+    }
+}
@@ -1,8 +1,16 @@
 //! Command-line interface for agent-rs
 
-use clap::Parser;
+use agent_stock::api_keys::{API_KEYS_FILE_ENV, ApiKeyStore, KeyLimits, Scope};
+use agent_stock::backup::{BackupArchive, RestoreOutcome, StoreKind, StorePaths};
+use agent_stock::migrations::Migrator;
+use agent_stock::storage::StoreCipher;
+use clap::{Parser, Subcommand};
+use std::path::{Path, PathBuf};
 use tracing::info;
 
+mod doctor;
+mod init;
+
 #[derive(Parser, Debug)]
 #[command(name = "agent-cli")]
 #[command(about = "CLI for agent-rs framework", long_about = None)]
@@ -10,10 +18,87 @@ struct Args {
     /// Command to run
     #[arg(short, long)]
     command: Option<String>,
+
+    #[command(subcommand)]
+    subcommand: Option<Commands>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Diagnose the environment: network, API keys, data sources, cache,
+    /// LLM model and MCP servers, with suggested fixes
+    Doctor,
+    /// Interactive first-run setup: choose providers and platforms, test
+    /// keys, and write `.env` and `agent-rs.json`
+    Init,
+    /// Export every persisted store (predictions, usage stats) to one
+    /// versioned archive
+    Backup {
+        /// Archive to write (default: agent-rs-backup-<timestamp>.json)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Restore stores from an archive written by `backup`
+    Restore {
+        /// Archive to read
+        archive: PathBuf,
+        /// Replace stores that already exist
+        #[arg(long)]
+        force: bool,
+    },
+    /// Show or apply schema migrations of persisted stores
+    Migrate {
+        /// Only show each store's schema version
+        #[arg(long)]
+        status: bool,
+        /// List the migrations that would run without applying them
+        #[arg(long)]
+        dry_run: bool,
+        /// Store to move to `--to` (predictions, usage)
+        #[arg(long, requires = "to")]
+        store: Option<String>,
+        /// Target version; lower than the current one rolls back
+        #[arg(long, requires = "store")]
+        to: Option<u32>,
+    },
+    /// Create and revoke the API keys services built on the engine accept,
+    /// and show their usage (kept in STOCK_API_KEYS_FILE)
+    ApiKeys {
+        #[command(subcommand)]
+        action: ApiKeysAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ApiKeysAction {
+    /// Issue a key; its secret is printed once and never stored
+    Create {
+        /// Who or what the key is for
+        name: String,
+        /// Scopes the key may use: read-quotes, run-analysis, manage-keys
+        #[arg(long, value_delimiter = ',', default_value = "read-quotes")]
+        scopes: Vec<String>,
+        /// Requests allowed per minute
+        #[arg(long)]
+        per_minute: Option<u32>,
+        /// Estimated LLM cost allowed per day, in US dollars
+        #[arg(long)]
+        daily_budget: Option<f64>,
+    },
+    /// Revoke a key by its id
+    Revoke {
+        /// Key id, e.g. key_1a2b3c4d5e6f
+        id: String,
+    },
+    /// Show requests and estimated cost per key
+    Usage,
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    // Load settings written by `agent-cli init`
+    dotenvy::dotenv().ok();
+
     // Initialize tracing
     agent_utils::init_tracing();
 
@@ -21,6 +106,21 @@ async fn main() -> anyhow::Result<()> {
 
     info!("Starting agent-cli");
 
+    match args.subcommand {
+        Some(Commands::Doctor) => return doctor().await,
+        Some(Commands::Init) => return init::run(&std::env::current_dir()?).await,
+        Some(Commands::Backup { output }) => return backup(output),
+        Some(Commands::Restore { archive, force }) => return restore(&archive, force),
+        Some(Commands::Migrate {
+            status,
+            dry_run,
+            store,
+            to,
+        }) => return migrate(status, dry_run, store.as_deref().zip(to)),
+        Some(Commands::ApiKeys { action }) => return api_keys(action),
+        None => {}
+    }
+
     if let Some(command) = args.command {
         info!("Running command: {}", command);
         // TODO: Implement command execution
@@ -31,3 +131,158 @@ async fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Run the environment checks and exit non-zero if any failed
+async fn doctor() -> anyhow::Result<()> {
+    let report = doctor::run().await?;
+    println!("{report}");
+
+    if !report.is_healthy() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Write every configured store to an archive
+fn backup(output: Option<PathBuf>) -> anyhow::Result<()> {
+    let paths = StorePaths::from_env();
+    let cipher = StoreCipher::from_env()?;
+    let output = output.unwrap_or_else(|| {
+        PathBuf::from(format!(
+            "agent-rs-backup-{}.json",
+            chrono::Utc::now().format("%Y%m%d-%H%M%S")
+        ))
+    });
+
+    let archive = BackupArchive::collect(&paths, cipher.as_ref())?;
+    if archive.stores.is_empty() {
+        anyhow::bail!(
+            "No stores to back up; set a store path such as STOCK_PREDICTIONS_FILE or STOCK_ALERTS_FILE"
+        );
+    }
+    archive.write(&output, cipher.as_ref())?;
+
+    let kinds: Vec<String> = archive.kinds().iter().map(ToString::to_string).collect();
+    println!(
+        "Backed up {} to {}{}",
+        kinds.join(", "),
+        output.display(),
+        if cipher.is_some() { " (encrypted)" } else { "" }
+    );
+    Ok(())
+}
+
+/// Restore stores from an archive to their configured paths
+fn restore(archive: &Path, force: bool) -> anyhow::Result<()> {
+    let paths = StorePaths::from_env();
+    let cipher = StoreCipher::from_env()?;
+
+    let archive = BackupArchive::read(archive, cipher.as_ref())?;
+    println!(
+        "Archive from {} (agent-rs {}, format v{})",
+        archive.created_at.format("%Y-%m-%d %H:%M UTC"),
+        archive.app_version,
+        archive.version
+    );
+    for (kind, outcome) in archive.restore(&paths, cipher.as_ref(), force)? {
+        match outcome {
+            RestoreOutcome::Restored(path) => println!("  {kind}: restored to {}", path.display()),
+            RestoreOutcome::NotConfigured => {
+                println!("  {kind}: skipped, no path configured for this store");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Show migration status, or migrate stores to the latest (or a given) version
+fn migrate(status: bool, dry_run: bool, target: Option<(&str, u32)>) -> anyhow::Result<()> {
+    let paths = StorePaths::from_env();
+    let cipher = StoreCipher::from_env()?;
+    let migrator = Migrator::new(&paths, cipher.as_ref());
+
+    let statuses = migrator.status()?;
+    if statuses.is_empty() {
+        anyhow::bail!(
+            "No stores configured; set a store path such as STOCK_PREDICTIONS_FILE or STOCK_ALERTS_FILE"
+        );
+    }
+    for store in &statuses {
+        println!("{store}");
+    }
+    if status {
+        return Ok(());
+    }
+
+    let steps = match target {
+        Some((store, version)) => {
+            let kind = StoreKind::ALL
+                .iter()
+                .copied()
+                .find(|kind| kind.as_str() == store)
+                .ok_or_else(|| anyhow::anyhow!("Unknown store: {store}"))?;
+            migrator.migrate_to(kind, version, dry_run)?
+        }
+        None => migrator.migrate(dry_run)?,
+    };
+    if steps.is_empty() {
+        println!("\nNothing to migrate.");
+    } else {
+        println!("\n{}", if dry_run { "Would apply:" } else { "Applied:" });
+        for step in steps {
+            println!("  {step}");
+        }
+    }
+    Ok(())
+}
+
+/// Create or revoke an API key, or show their usage
+fn api_keys(action: ApiKeysAction) -> anyhow::Result<()> {
+    let path = std::env::var(API_KEYS_FILE_ENV)
+        .map_err(|_| anyhow::anyhow!("Set {API_KEYS_FILE_ENV} to the API key store"))?;
+    let keys = ApiKeyStore::open_with_cipher(path, StoreCipher::from_env()?)?;
+
+    match action {
+        ApiKeysAction::Create {
+            name,
+            scopes,
+            per_minute,
+            daily_budget,
+        } => {
+            let scopes = scopes
+                .iter()
+                .map(|scope| {
+                    Scope::parse(scope).ok_or_else(|| {
+                        let known: Vec<&str> = Scope::ALL.iter().map(|s| s.as_str()).collect();
+                        anyhow::anyhow!("Unknown scope: {scope} (use {})", known.join(", "))
+                    })
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            let mut limits = KeyLimits::default();
+            if let Some(requests) = per_minute {
+                limits = limits.per_minute(requests);
+            }
+            if let Some(usd) = daily_budget {
+                limits = limits.daily_budget(usd);
+            }
+
+            let issued = keys.create(&name, &scopes, limits)?;
+            println!("Created {} for {name}", issued.id);
+            println!("Secret (shown only now): {}", issued.secret);
+        }
+        ApiKeysAction::Revoke { id } => {
+            if !keys.revoke(&id)? {
+                anyhow::bail!("No active API key {id}");
+            }
+            println!("Revoked {id}");
+        }
+        ApiKeysAction::Usage => {
+            if keys.list().is_empty() {
+                println!("No API keys yet; create one with `agent-cli api-keys create <name>`");
+            } else {
+                print!("{}", keys.usage_report());
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,6 +1,6 @@
 //! Core Agent trait definition
 
-use crate::{Context, Result};
+use crate::{AgentCapabilities, Context, Result};
 use async_trait::async_trait;
 
 /// Core trait that all agents must implement
@@ -19,6 +19,14 @@ pub trait Agent: Send + Sync {
     /// Get the agent's name
     fn name(&self) -> &str;
 
+    /// Describe what the agent handles and what it needs (optional)
+    ///
+    /// Routers and registries use this to build routing tables; the default
+    /// declares nothing.
+    fn capabilities(&self) -> AgentCapabilities {
+        AgentCapabilities::default()
+    }
+
     /// Initialize the agent (optional)
     async fn initialize(&mut self) -> Result<()> {
         Ok(())
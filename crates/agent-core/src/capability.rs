@@ -0,0 +1,122 @@
+//! Agent capability self-description
+//!
+//! Agents describe what they handle and what they need through
+//! [`Agent::capabilities`](crate::Agent::capabilities), so routers can build
+//! routing tables and bots can tell users what currently works given the
+//! configured API keys.
+
+use serde::{Deserialize, Serialize};
+
+/// What an agent can do and what it needs to do it
+///
+/// # Example
+///
+/// ```
+/// use agent_core::AgentCapabilities;
+///
+/// let caps = AgentCapabilities::new("Macroeconomic analysis")
+///     .with_intent("macro")
+///     .with_required_key("FRED_API_KEY")
+///     .with_input("question about the economy");
+///
+/// assert!(caps.handles("macro"));
+/// assert_eq!(caps.missing_keys(|_| false), vec!["FRED_API_KEY"]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentCapabilities {
+    /// One-line description of what the agent does
+    pub description: String,
+
+    /// Intents the agent handles (e.g. "technical", "news")
+    pub intents: Vec<String>,
+
+    /// API keys the agent cannot work without, by environment variable name
+    pub required_keys: Vec<String>,
+
+    /// Inputs the agent expects (e.g. "stock symbol")
+    pub inputs: Vec<String>,
+}
+
+impl AgentCapabilities {
+    /// Create capabilities with a description
+    pub fn new(description: impl Into<String>) -> Self {
+        Self {
+            description: description.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Add a handled intent
+    pub fn with_intent(mut self, intent: impl Into<String>) -> Self {
+        self.intents.push(intent.into());
+        self
+    }
+
+    /// Add a required API key
+    pub fn with_required_key(mut self, env_var: impl Into<String>) -> Self {
+        self.required_keys.push(env_var.into());
+        self
+    }
+
+    /// Add an expected input
+    pub fn with_input(mut self, input: impl Into<String>) -> Self {
+        self.inputs.push(input.into());
+        self
+    }
+
+    /// Whether the agent handles an intent
+    pub fn handles(&self, intent: &str) -> bool {
+        self.intents.iter().any(|i| i == intent)
+    }
+
+    /// Required keys that are not configured
+    ///
+    /// `is_configured` is called with each key's environment variable name.
+    pub fn missing_keys(&self, is_configured: impl Fn(&str) -> bool) -> Vec<&str> {
+        self.required_keys
+            .iter()
+            .map(String::as_str)
+            .filter(|key| !is_configured(key))
+            .collect()
+    }
+
+    /// Merge another agent's capabilities into these, skipping duplicates
+    pub fn merge(&mut self, other: AgentCapabilities) {
+        for (ours, theirs) in [
+            (&mut self.intents, other.intents),
+            (&mut self.required_keys, other.required_keys),
+            (&mut self.inputs, other.inputs),
+        ] {
+            for item in theirs {
+                if !ours.contains(&item) {
+                    ours.push(item);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_skips_duplicates() {
+        let mut caps = AgentCapabilities::new("Manager")
+            .with_intent("news")
+            .with_input("stock symbol");
+        caps.merge(
+            AgentCapabilities::new("News")
+                .with_intent("news")
+                .with_intent("sentiment")
+                .with_required_key("FINNHUB_API_KEY")
+                .with_input("stock symbol"),
+        );
+
+        assert_eq!(caps.description, "Manager");
+        assert_eq!(caps.intents, vec!["news", "sentiment"]);
+        assert_eq!(caps.required_keys, vec!["FINNHUB_API_KEY"]);
+        assert_eq!(caps.inputs, vec!["stock symbol"]);
+        assert!(caps.missing_keys(|key| key == "FINNHUB_API_KEY").is_empty());
+    }
+}
@@ -2,9 +2,18 @@
 //!
 //! The `Context` struct provides a flexible key-value store for passing
 //! runtime configuration and state to agents during execution.
+//!
+//! Contexts live as long as a conversation, so they can be bounded:
+//! [`ContextLimits`] caps the total size and entry count, evicting the
+//! least recently written entries first, and entries inserted with
+//! [`Context::insert_with_ttl`] disappear once their time is up. Keys are
+//! namespaced by convention (`"stock.analysis_depth"`), and [`ContextKey`]
+//! gives a namespaced key a fixed value type.
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::time::{Duration, Instant};
 
 /// Well-known context keys for common configuration
 pub mod keys {
@@ -18,6 +27,100 @@ pub mod keys {
     pub const SESSION_ID: &str = "session_id";
     /// Timezone for date/time formatting
     pub const TIMEZONE: &str = "timezone";
+    /// Names of the delegating agents the current request has passed through
+    pub const DELEGATION_PATH: &str = "delegation_path";
+}
+
+/// Separator between a key's namespace and its name
+pub const NAMESPACE_SEPARATOR: char = '.';
+
+/// A context key with a fixed value type
+///
+/// ```
+/// use agent_core::context::{Context, ContextKey};
+///
+/// const RETRIES: ContextKey<u32> = ContextKey::new("http.retries");
+///
+/// let mut ctx = Context::new();
+/// ctx.set(&RETRIES, &3).unwrap();
+/// assert_eq!(ctx.get_key(&RETRIES).unwrap(), Some(3));
+/// assert_eq!(RETRIES.namespace(), Some("http"));
+/// ```
+#[derive(Debug)]
+pub struct ContextKey<T> {
+    name: &'static str,
+    _type: PhantomData<fn() -> T>,
+}
+
+impl<T> ContextKey<T> {
+    /// Create a key; use `"namespace.name"` for anything not in [`keys`]
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            _type: PhantomData,
+        }
+    }
+
+    /// Full key name
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Namespace of the key, if it has one
+    pub fn namespace(&self) -> Option<&'static str> {
+        self.name
+            .split_once(NAMESPACE_SEPARATOR)
+            .map(|(namespace, _)| namespace)
+    }
+}
+
+/// Bounds on a context's size
+///
+/// When an insert takes the context over a limit, expired entries are
+/// dropped first, then the least recently written ones, until it fits. The
+/// entry just written is never evicted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ContextLimits {
+    /// Maximum total size in bytes (keys plus serialized values)
+    pub max_bytes: Option<usize>,
+    /// Maximum number of entries
+    pub max_entries: Option<usize>,
+}
+
+impl ContextLimits {
+    /// No limits
+    pub fn unbounded() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum total size in bytes
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Set the maximum number of entries
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = Some(max_entries);
+        self
+    }
+}
+
+/// A stored value with its bookkeeping
+#[derive(Debug, Clone)]
+struct Entry {
+    value: serde_json::Value,
+    /// Key length plus serialized value length
+    size: usize,
+    /// Write sequence number, for least-recently-written eviction
+    written: u64,
+    expires_at: Option<Instant>,
+}
+
+impl Entry {
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|at| at <= now)
+    }
 }
 
 /// Context passed to agents during execution
@@ -40,7 +143,12 @@ pub mod keys {
 #[derive(Debug, Clone, Default)]
 pub struct Context {
     /// Key-value storage for context data
-    data: HashMap<String, serde_json::Value>,
+    data: HashMap<String, Entry>,
+    /// Total size of all entries in bytes
+    size: usize,
+    /// Next write sequence number
+    writes: u64,
+    limits: ContextLimits,
 }
 
 impl Context {
@@ -49,6 +157,14 @@ impl Context {
         Self::default()
     }
 
+    /// Create a new empty context with size limits
+    pub fn with_limits(limits: ContextLimits) -> Self {
+        Self {
+            limits,
+            ..Self::default()
+        }
+    }
+
     // =========== Builder Methods ===========
 
     /// Set the language preference
@@ -102,16 +218,58 @@ impl Context {
         self.get(keys::TIMEZONE).and_then(|v| v.as_str())
     }
 
+    /// Get the delegating agents the current request has passed through,
+    /// outermost first
+    pub fn delegation_path(&self) -> Vec<String> {
+        self.get(keys::DELEGATION_PATH)
+            .and_then(|v| v.as_array())
+            .map(|path| {
+                path.iter()
+                    .filter_map(|name| name.as_str().map(ToString::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Get the current delegation depth (0 outside any delegating agent)
+    pub fn delegation_depth(&self) -> usize {
+        self.delegation_path().len()
+    }
+
+    /// Set the delegation path, removing the key when it is empty
+    pub fn set_delegation_path(&mut self, path: Vec<String>) {
+        if path.is_empty() {
+            self.remove(keys::DELEGATION_PATH);
+        } else {
+            self.insert(keys::DELEGATION_PATH, serde_json::json!(path));
+        }
+    }
+
     // =========== Generic Key-Value Operations ===========
 
     /// Insert a value into the context
+    ///
+    /// May evict older entries if the context has [`ContextLimits`].
     pub fn insert(&mut self, key: impl Into<String>, value: serde_json::Value) {
-        self.data.insert(key.into(), value);
+        self.insert_entry(key.into(), value, None);
+    }
+
+    /// Insert a value that expires after `ttl`
+    ///
+    /// Once expired the value is no longer returned, and it is dropped at
+    /// the next write to the context.
+    pub fn insert_with_ttl(
+        &mut self,
+        key: impl Into<String>,
+        value: serde_json::Value,
+        ttl: Duration,
+    ) {
+        self.insert_entry(key.into(), value, Some(Instant::now() + ttl));
     }
 
     /// Get a value from the context
     pub fn get(&self, key: &str) -> Option<&serde_json::Value> {
-        self.data.get(key)
+        self.live_entry(key).map(|entry| &entry.value)
     }
 
     /// Insert a typed value into the context
@@ -125,7 +283,7 @@ impl Context {
         let json_value = serde_json::to_value(value).map_err(|e| {
             crate::Error::ProcessingFailed(format!("Failed to serialize context value: {e}"))
         })?;
-        self.data.insert(key.into(), json_value);
+        self.insert(key, json_value);
         Ok(())
     }
 
@@ -133,7 +291,7 @@ impl Context {
     ///
     /// Deserializes the JSON value into the specified type.
     pub fn get_typed<T: for<'de> Deserialize<'de>>(&self, key: &str) -> crate::Result<Option<T>> {
-        match self.data.get(key) {
+        match self.get(key) {
             None => Ok(None),
             Some(value) => {
                 let typed = serde_json::from_value(value.clone()).map_err(|e| {
@@ -146,35 +304,195 @@ impl Context {
         }
     }
 
+    /// Store a value under a typed key
+    pub fn set<T: Serialize>(&mut self, key: &ContextKey<T>, value: &T) -> crate::Result<()> {
+        self.insert_typed(key.name(), value)
+    }
+
+    /// Get the value stored under a typed key
+    pub fn get_key<T: for<'de> Deserialize<'de>>(
+        &self,
+        key: &ContextKey<T>,
+    ) -> crate::Result<Option<T>> {
+        self.get_typed(key.name())
+    }
+
     /// Check if a key exists in the context
     pub fn contains_key(&self, key: &str) -> bool {
-        self.data.contains_key(key)
+        self.live_entry(key).is_some()
     }
 
     /// Remove a value from the context
     pub fn remove(&mut self, key: &str) -> Option<serde_json::Value> {
-        self.data.remove(key)
+        let entry = self.data.remove(key)?;
+        self.size -= entry.size;
+        (!entry.is_expired(Instant::now())).then_some(entry.value)
     }
 
     /// Clear all values from the context
     pub fn clear(&mut self) {
         self.data.clear();
+        self.size = 0;
     }
 
     /// Get the number of entries in the context
     pub fn len(&self) -> usize {
-        self.data.len()
+        let now = Instant::now();
+        self.data
+            .values()
+            .filter(|entry| !entry.is_expired(now))
+            .count()
     }
 
     /// Check if the context is empty
     pub fn is_empty(&self) -> bool {
-        self.data.is_empty()
+        self.len() == 0
     }
 
     /// Merge another context into this one (other values override)
+    ///
+    /// Entries keep their expiry times.
     pub fn merge(&mut self, other: Context) {
-        self.data.extend(other.data);
+        let mut entries: Vec<_> = other.data.into_iter().collect();
+        entries.sort_by_key(|(_, entry)| entry.written);
+        for (key, entry) in entries {
+            self.insert_entry(key, entry.value, entry.expires_at);
+        }
+    }
+
+    // =========== Size and Namespaces ===========
+
+    /// Approximate size in bytes: keys plus serialized values
+    pub fn size_bytes(&self) -> usize {
+        self.size
+    }
+
+    /// Size limits of this context
+    pub fn limits(&self) -> ContextLimits {
+        self.limits
+    }
+
+    /// Change the size limits, evicting entries if the context no longer fits
+    pub fn set_limits(&mut self, limits: ContextLimits) {
+        self.limits = limits;
+        self.enforce_limits(None);
+    }
+
+    /// All live keys, sorted
+    pub fn keys(&self) -> Vec<&str> {
+        let now = Instant::now();
+        let mut keys: Vec<&str> = self
+            .data
+            .iter()
+            .filter(|(_, entry)| !entry.is_expired(now))
+            .map(|(key, _)| key.as_str())
+            .collect();
+        keys.sort_unstable();
+        keys
+    }
+
+    /// Live keys in a namespace (`"stock"` matches `"stock.depth"`), sorted
+    pub fn namespace_keys(&self, namespace: &str) -> Vec<&str> {
+        self.keys()
+            .into_iter()
+            .filter(|key| in_namespace(key, namespace))
+            .collect()
+    }
+
+    /// Size in bytes of the entries in a namespace
+    pub fn namespace_size(&self, namespace: &str) -> usize {
+        self.data
+            .iter()
+            .filter(|(key, _)| in_namespace(key, namespace))
+            .map(|(_, entry)| entry.size)
+            .sum()
     }
+
+    /// Remove every entry in a namespace; returns how many were removed
+    pub fn clear_namespace(&mut self, namespace: &str) -> usize {
+        let keys: Vec<String> = self
+            .data
+            .keys()
+            .filter(|key| in_namespace(key, namespace))
+            .cloned()
+            .collect();
+        for key in &keys {
+            self.remove(key);
+        }
+        keys.len()
+    }
+
+    /// Drop expired entries; returns how many were dropped
+    pub fn purge_expired(&mut self) -> usize {
+        let now = Instant::now();
+        let expired: Vec<String> = self
+            .data
+            .iter()
+            .filter(|(_, entry)| entry.is_expired(now))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &expired {
+            self.remove(key);
+        }
+        expired.len()
+    }
+
+    fn live_entry(&self, key: &str) -> Option<&Entry> {
+        self.data
+            .get(key)
+            .filter(|entry| !entry.is_expired(Instant::now()))
+    }
+
+    fn insert_entry(&mut self, key: String, value: serde_json::Value, expires_at: Option<Instant>) {
+        let size = key.len() + serde_json::to_vec(&value).map_or(0, |bytes| bytes.len());
+        let written = self.writes;
+        self.writes += 1;
+
+        let entry = Entry {
+            value,
+            size,
+            written,
+            expires_at,
+        };
+        if let Some(old) = self.data.insert(key.clone(), entry) {
+            self.size -= old.size;
+        }
+        self.size += size;
+        self.enforce_limits(Some(&key));
+    }
+
+    /// Evict entries until the context fits its limits, sparing `keep`
+    fn enforce_limits(&mut self, keep: Option<&str>) {
+        if !self.over_limits() {
+            return;
+        }
+        self.purge_expired();
+
+        while self.over_limits() {
+            let oldest = self
+                .data
+                .iter()
+                .filter(|(key, _)| Some(key.as_str()) != keep)
+                .min_by_key(|(_, entry)| entry.written)
+                .map(|(key, _)| key.clone());
+            let Some(oldest) = oldest else { break };
+            tracing::debug!(key = %oldest, "Evicting context entry to stay within limits");
+            self.remove(&oldest);
+        }
+    }
+
+    fn over_limits(&self) -> bool {
+        self.limits.max_bytes.is_some_and(|max| self.size > max)
+            || self
+                .limits
+                .max_entries
+                .is_some_and(|max| self.data.len() > max)
+    }
+}
+
+fn in_namespace(key: &str, namespace: &str) -> bool {
+    key.strip_prefix(namespace)
+        .is_some_and(|rest| rest.starts_with(NAMESPACE_SEPARATOR))
 }
 
 #[cfg(test)]
@@ -256,6 +574,90 @@ mod tests {
         assert_eq!(ctx1.session_id(), Some("sess")); // merged
     }
 
+    #[test]
+    fn test_delegation_path() {
+        let mut ctx = Context::new();
+        assert_eq!(ctx.delegation_depth(), 0);
+
+        ctx.set_delegation_path(vec!["manager".to_string(), "research".to_string()]);
+        assert_eq!(ctx.delegation_path(), vec!["manager", "research"]);
+        assert_eq!(ctx.delegation_depth(), 2);
+
+        ctx.set_delegation_path(Vec::new());
+        assert!(!ctx.contains_key(keys::DELEGATION_PATH));
+    }
+
+    #[test]
+    fn test_size_limit_evicts_oldest() {
+        let mut ctx = Context::with_limits(ContextLimits::unbounded().with_max_bytes(30));
+        ctx.insert("a", serde_json::json!("0123456789"));
+        ctx.insert("b", serde_json::json!("0123456789"));
+        assert_eq!(ctx.size_bytes(), 26);
+
+        ctx.insert("c", serde_json::json!("0123456789"));
+        assert!(!ctx.contains_key("a"));
+        assert_eq!(ctx.keys(), vec!["b", "c"]);
+        assert!(ctx.size_bytes() <= 30);
+
+        // A value larger than the limit is still kept
+        ctx.insert("big", serde_json::json!("x".repeat(100)));
+        assert_eq!(ctx.keys(), vec!["big"]);
+    }
+
+    #[test]
+    fn test_entry_limit_and_overwrite() {
+        let mut ctx = Context::with_limits(ContextLimits::unbounded().with_max_entries(2));
+        ctx.insert("a", serde_json::json!(1));
+        ctx.insert("b", serde_json::json!(2));
+        // Rewriting makes "a" the most recently written
+        ctx.insert("a", serde_json::json!(3));
+        ctx.insert("c", serde_json::json!(4));
+
+        assert_eq!(ctx.keys(), vec!["a", "c"]);
+        assert_eq!(ctx.size_bytes(), 4);
+    }
+
+    #[test]
+    fn test_ttl_expiry() {
+        let mut ctx = Context::new();
+        ctx.insert_with_ttl("quote", serde_json::json!(178.25), Duration::ZERO);
+        ctx.insert_with_ttl(
+            "news",
+            serde_json::json!("headline"),
+            Duration::from_secs(60),
+        );
+
+        assert!(ctx.get("quote").is_none());
+        assert!(!ctx.contains_key("quote"));
+        assert_eq!(ctx.len(), 1);
+        assert_eq!(ctx.purge_expired(), 1);
+        assert_eq!(ctx.get("news"), Some(&serde_json::json!("headline")));
+    }
+
+    #[test]
+    fn test_namespaces_and_typed_keys() {
+        const DEPTH: ContextKey<String> = ContextKey::new("stock.depth");
+
+        let mut ctx = Context::new().with_language("en");
+        ctx.set(&DEPTH, &"deep".to_string()).unwrap();
+        ctx.insert("stock.symbol", serde_json::json!("AAPL"));
+        ctx.insert("stocks.other", serde_json::json!(1));
+
+        assert_eq!(ctx.get_key(&DEPTH).unwrap().as_deref(), Some("deep"));
+        assert_eq!(DEPTH.namespace(), Some("stock"));
+        assert_eq!(
+            ctx.namespace_keys("stock"),
+            vec!["stock.depth", "stock.symbol"]
+        );
+        assert_eq!(
+            ctx.namespace_size("stock"),
+            "stock.depth".len() + 6 + "stock.symbol".len() + 6
+        );
+
+        assert_eq!(ctx.clear_namespace("stock"), 2);
+        assert_eq!(ctx.keys(), vec!["language", "stocks.other"]);
+    }
+
     #[test]
     fn test_get_typed_missing_key() {
         let ctx = Context::new();
@@ -3,9 +3,11 @@
 //! This crate defines the fundamental traits and types used throughout the agent-rs framework.
 
 pub mod agent;
+pub mod capability;
 pub mod context;
 pub mod error;
 
 pub use agent::Agent;
-pub use context::{keys as context_keys, Context};
+pub use capability::AgentCapabilities;
+pub use context::{Context, ContextKey, ContextLimits, keys as context_keys};
 pub use error::{Error, Result};
@@ -1,6 +1,6 @@
 //! Completion request and response types
 
-use crate::{Message, ToolDefinition};
+use crate::{LLMError, Message, Result, ToolChoice, ToolDefinition};
 use serde::{Deserialize, Serialize};
 
 /// Request for LLM completion with full conversation history
@@ -23,10 +23,38 @@ pub struct CompletionRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub temperature: Option<f32>,
 
+    /// Nucleus sampling: only sample from the most likely tokens whose
+    /// probabilities add up to this (0.0-1.0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+
+    /// Only sample from this many most likely tokens (Anthropic)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+
+    /// Penalize tokens by how often they already appeared (-2.0-2.0, OpenAI)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+
+    /// Penalize tokens that already appeared at all (-2.0-2.0, OpenAI)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+
     /// Tools available for the LLM to call
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tools: Option<Vec<ToolDefinition>>,
 
+    /// Whether and which tools the LLM may call; `None` leaves it to the
+    /// provider default (auto)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<ToolChoice>,
+
+    /// Whether the LLM may request several tool calls in one turn; `false`
+    /// makes it call tools strictly one at a time. `None` leaves it to the
+    /// provider default (allowed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
+
     /// Stop sequences
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stop_sequences: Option<Vec<String>>,
@@ -63,7 +91,7 @@ pub enum StopReason {
 }
 
 /// Token usage statistics
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TokenUsage {
     /// Number of input tokens
     pub input_tokens: usize,
@@ -79,11 +107,81 @@ impl TokenUsage {
     }
 }
 
+impl std::ops::AddAssign for TokenUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+    }
+}
+
+/// Optional generation parameter that not every provider supports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GenerationParam {
+    TopP,
+    TopK,
+    FrequencyPenalty,
+    PresencePenalty,
+}
+
+impl GenerationParam {
+    /// Request field name, e.g. "top_p"
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::TopP => "top_p",
+            Self::TopK => "top_k",
+            Self::FrequencyPenalty => "frequency_penalty",
+            Self::PresencePenalty => "presence_penalty",
+        }
+    }
+}
+
 impl CompletionRequest {
     /// Create a builder for completion requests
     pub fn builder(model: impl Into<String>) -> CompletionRequestBuilder {
         CompletionRequestBuilder::new(model)
     }
+
+    /// Optional generation parameters set on this request
+    pub fn generation_params(&self) -> Vec<GenerationParam> {
+        [
+            (GenerationParam::TopP, self.top_p.is_some()),
+            (GenerationParam::TopK, self.top_k.is_some()),
+            (
+                GenerationParam::FrequencyPenalty,
+                self.frequency_penalty.is_some(),
+            ),
+            (
+                GenerationParam::PresencePenalty,
+                self.presence_penalty.is_some(),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(param, set)| set.then_some(param))
+        .collect()
+    }
+
+    /// Fail with [`LLMError::InvalidRequest`] if the request sets a
+    /// generation parameter `provider` does not support
+    ///
+    /// Rejecting is preferred over silently dropping the parameter, since a
+    /// caller tuning e.g. a deterministic extraction step would otherwise get
+    /// different output than asked for without noticing.
+    pub fn check_supported(&self, provider: &str, supported: &[GenerationParam]) -> Result<()> {
+        let unsupported: Vec<_> = self
+            .generation_params()
+            .into_iter()
+            .filter(|param| !supported.contains(param))
+            .map(|param| param.name())
+            .collect();
+        if unsupported.is_empty() {
+            Ok(())
+        } else {
+            Err(LLMError::InvalidRequest(format!(
+                "{provider} does not support {}",
+                unsupported.join(", ")
+            )))
+        }
+    }
 }
 
 /// Builder for CompletionRequest
@@ -93,7 +191,13 @@ pub struct CompletionRequestBuilder {
     system: Option<String>,
     max_tokens: usize,
     temperature: Option<f32>,
+    top_p: Option<f32>,
+    top_k: Option<u32>,
+    frequency_penalty: Option<f32>,
+    presence_penalty: Option<f32>,
     tools: Option<Vec<ToolDefinition>>,
+    tool_choice: Option<ToolChoice>,
+    parallel_tool_calls: Option<bool>,
     stop_sequences: Option<Vec<String>>,
 }
 
@@ -106,7 +210,13 @@ impl CompletionRequestBuilder {
             system: None,
             max_tokens: 1024,
             temperature: None,
+            top_p: None,
+            top_k: None,
+            frequency_penalty: None,
+            presence_penalty: None,
             tools: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
             stop_sequences: None,
         }
     }
@@ -141,12 +251,48 @@ impl CompletionRequestBuilder {
         self
     }
 
+    /// Set nucleus sampling (top-p)
+    pub fn top_p(mut self, top_p: f32) -> Self {
+        self.top_p = Some(top_p);
+        self
+    }
+
+    /// Set top-k sampling (Anthropic only)
+    pub fn top_k(mut self, top_k: u32) -> Self {
+        self.top_k = Some(top_k);
+        self
+    }
+
+    /// Set the frequency penalty (OpenAI only)
+    pub fn frequency_penalty(mut self, penalty: f32) -> Self {
+        self.frequency_penalty = Some(penalty);
+        self
+    }
+
+    /// Set the presence penalty (OpenAI only)
+    pub fn presence_penalty(mut self, penalty: f32) -> Self {
+        self.presence_penalty = Some(penalty);
+        self
+    }
+
     /// Set the available tools
     pub fn tools(mut self, tools: Vec<ToolDefinition>) -> Self {
         self.tools = Some(tools);
         self
     }
 
+    /// Force, forbid or pick tool use
+    pub fn tool_choice(mut self, choice: ToolChoice) -> Self {
+        self.tool_choice = Some(choice);
+        self
+    }
+
+    /// Allow or forbid several tool calls in one turn
+    pub fn parallel_tool_calls(mut self, enabled: bool) -> Self {
+        self.parallel_tool_calls = Some(enabled);
+        self
+    }
+
     /// Set stop sequences
     pub fn stop_sequences(mut self, sequences: Vec<String>) -> Self {
         self.stop_sequences = Some(sequences);
@@ -161,7 +307,13 @@ impl CompletionRequestBuilder {
             system: self.system,
             max_tokens: self.max_tokens,
             temperature: self.temperature,
+            top_p: self.top_p,
+            top_k: self.top_k,
+            frequency_penalty: self.frequency_penalty,
+            presence_penalty: self.presence_penalty,
             tools: self.tools,
+            tool_choice: self.tool_choice,
+            parallel_tool_calls: self.parallel_tool_calls,
             stop_sequences: self.stop_sequences,
         }
     }
@@ -187,6 +339,52 @@ mod tests {
         assert_eq!(request.temperature, Some(0.7));
     }
 
+    #[test]
+    fn test_tool_choice_serialization() {
+        let request = CompletionRequest::builder("test-model")
+            .tool_choice(ToolChoice::tool("extract"))
+            .build();
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(
+            json["tool_choice"],
+            serde_json::json!({ "type": "tool", "name": "extract" })
+        );
+
+        let request = CompletionRequest::builder("test-model").build();
+        assert!(
+            serde_json::to_value(&request)
+                .unwrap()
+                .get("tool_choice")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_check_supported_params() {
+        let request = CompletionRequest::builder("test-model")
+            .top_p(0.9)
+            .frequency_penalty(0.5)
+            .build();
+        assert_eq!(
+            request.generation_params(),
+            vec![GenerationParam::TopP, GenerationParam::FrequencyPenalty]
+        );
+
+        let supported = [GenerationParam::TopP, GenerationParam::FrequencyPenalty];
+        assert!(request.check_supported("openai", &supported).is_ok());
+
+        let err = request
+            .check_supported("anthropic", &[GenerationParam::TopP, GenerationParam::TopK])
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid request: anthropic does not support frequency_penalty"
+        );
+
+        let plain = CompletionRequest::builder("test-model").build();
+        assert!(plain.check_supported("mock", &[]).is_ok());
+    }
+
     #[test]
     fn test_token_usage() {
         let usage = TokenUsage {
@@ -194,5 +392,10 @@ mod tests {
             output_tokens: 50,
         };
         assert_eq!(usage.total(), 150);
+
+        let mut sum = TokenUsage::default();
+        sum += usage;
+        sum += usage;
+        assert_eq!(sum.total(), 300);
     }
 }
@@ -7,20 +7,25 @@
 //! - Completion request/response types
 //! - Tool definitions for function calling
 //! - Provider trait for LLM implementations
+//! - Embedding provider trait for vector representations of text
 //! - Concrete provider implementations (behind feature flags)
 
 pub mod completion;
+pub mod embedding;
 pub mod error;
 pub mod messages;
 pub mod provider;
 pub mod tools;
 
 // Re-export main types
-pub use completion::{CompletionRequest, CompletionResponse, StopReason, TokenUsage};
+pub use completion::{
+    CompletionRequest, CompletionResponse, GenerationParam, StopReason, TokenUsage,
+};
+pub use embedding::{EmbeddingProvider, cosine_similarity};
 pub use error::{LLMError, Result};
 pub use messages::{ContentBlock, ImageSource, Message, MessageContent, Role};
 pub use provider::LLMProvider;
-pub use tools::ToolDefinition;
+pub use tools::{ToolChoice, ToolDefinition};
 
 // Provider implementations (feature-gated)
 #[cfg(any(feature = "anthropic", feature = "openai", feature = "ollama"))]
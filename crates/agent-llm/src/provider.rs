@@ -1,7 +1,10 @@
 //! LLM provider trait definition
 
-use crate::{CompletionRequest, CompletionResponse, Result};
+use crate::{
+    CompletionRequest, CompletionResponse, ContentBlock, GenerationParam, MessageContent, Result,
+};
 use async_trait::async_trait;
+use tokio::sync::mpsc::UnboundedSender;
 
 /// Trait for LLM providers
 ///
@@ -20,6 +23,40 @@ pub trait LLMProvider: Send + Sync {
     /// The completion response with the assistant's message and metadata
     async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse>;
 
+    /// Generate a completion, sending each content block to `blocks` as soon
+    /// as it has been fully received
+    ///
+    /// Lets callers start on a tool call while the model is still writing
+    /// the rest of its response. The returned response holds every block,
+    /// whether or not it was sent early. The default implementation waits
+    /// for [`complete`](Self::complete) and then sends all blocks; providers
+    /// that stream override it.
+    async fn complete_streaming(
+        &self,
+        request: CompletionRequest,
+        blocks: &UnboundedSender<ContentBlock>,
+    ) -> Result<CompletionResponse> {
+        let response = self.complete(request).await?;
+        let sent = match &response.message.content {
+            Some(MessageContent::Blocks(content)) => content.clone(),
+            Some(MessageContent::Text(text)) => vec![ContentBlock::Text { text: text.clone() }],
+            None => Vec::new(),
+        };
+        for block in sent {
+            // Sending only fails when the caller stopped listening
+            let _ = blocks.send(block);
+        }
+        Ok(response)
+    }
+
     /// Get the provider name (e.g., "anthropic", "openai")
     fn name(&self) -> &str;
+
+    /// Optional generation parameters this provider accepts
+    ///
+    /// Requests setting any other [`GenerationParam`] are rejected; see
+    /// [`CompletionRequest::check_supported`].
+    fn supported_params(&self) -> &'static [GenerationParam] {
+        &[]
+    }
 }
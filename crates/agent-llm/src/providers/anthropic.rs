@@ -3,13 +3,15 @@
 //! This module implements the LLMProvider trait for Anthropic's Claude models.
 //! See: https://docs.anthropic.com/en/api/messages
 
+use super::sse::{self, SseDecoder};
 use crate::{
-    CompletionRequest, CompletionResponse, ContentBlock, LLMProvider, Message, MessageContent,
-    Result, Role, StopReason, TokenUsage, ToolDefinition,
+    CompletionRequest, CompletionResponse, ContentBlock, GenerationParam, LLMProvider, Message,
+    MessageContent, Result, Role, StopReason, TokenUsage, ToolChoice, ToolDefinition,
 };
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::UnboundedSender;
 use tracing::{debug, instrument};
 
 const ANTHROPIC_API_BASE: &str = "https://api.anthropic.com/v1";
@@ -55,33 +57,71 @@ impl AnthropicProvider {
         })?;
         Self::new(api_key)
     }
-}
 
-#[async_trait]
-impl LLMProvider for AnthropicProvider {
-    #[instrument(skip(self, request), fields(model = %request.model))]
-    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse> {
-        debug!("Sending request to Anthropic API");
+    /// List the model IDs available to this API key
+    ///
+    /// Calls `GET /v1/models`, which does not consume tokens, so it doubles as
+    /// a cheap check that the API key works.
+    pub async fn list_models(&self) -> Result<Vec<String>> {
+        let response = self
+            .client
+            .get(format!("{ANTHROPIC_API_BASE}/models"))
+            .query(&[("limit", "1000")])
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .send()
+            .await?;
 
-        // Build Anthropic-specific request
-        let anthropic_request = AnthropicRequest {
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await?;
+
+            return Err(match status.as_u16() {
+                401 | 403 => crate::LLMError::AuthenticationFailed,
+                429 => crate::LLMError::RateLimitExceeded(error_text),
+                _ => crate::LLMError::RequestFailed(format!("HTTP {status}: {error_text}")),
+            });
+        }
+
+        let models: ModelList = response.json().await.map_err(|e| {
+            crate::LLMError::UnexpectedResponse(format!("Failed to parse model list: {e}"))
+        })?;
+        Ok(models.data.into_iter().map(|m| m.id).collect())
+    }
+
+    /// Convert a request to Anthropic's format
+    fn build_request(request: CompletionRequest, stream: bool) -> AnthropicRequest {
+        // Anthropic rejects tool_choice without tools
+        let tool_choice = if request.tools.is_some() {
+            AnthropicToolChoice::new(request.tool_choice, request.parallel_tool_calls)
+        } else {
+            None
+        };
+
+        AnthropicRequest {
             model: request.model,
             messages: request.messages,
             system: request.system,
             max_tokens: request.max_tokens,
             temperature: request.temperature,
+            top_p: request.top_p,
+            top_k: request.top_k,
             tools: request.tools,
+            tool_choice,
             stop_sequences: request.stop_sequences,
-        };
+            stream,
+        }
+    }
 
-        // Send request
+    /// Send a request to the Messages API, mapping HTTP errors
+    async fn send(&self, anthropic_request: &AnthropicRequest) -> Result<reqwest::Response> {
         let response = self
             .client
             .post(format!("{ANTHROPIC_API_BASE}/messages"))
             .header("x-api-key", &self.api_key)
             .header("anthropic-version", ANTHROPIC_VERSION)
             .header("content-type", "application/json")
-            .json(&anthropic_request)
+            .json(anthropic_request)
             .send()
             .await?;
 
@@ -94,11 +134,24 @@ impl LLMProvider for AnthropicProvider {
                 401 => crate::LLMError::AuthenticationFailed,
                 429 => crate::LLMError::RateLimitExceeded(error_text),
                 400 => crate::LLMError::InvalidRequest(error_text),
-                404 => crate::LLMError::ModelNotFound(anthropic_request.model),
+                404 => crate::LLMError::ModelNotFound(anthropic_request.model.clone()),
                 _ => crate::LLMError::RequestFailed(format!("HTTP {status}: {error_text}")),
             });
         }
 
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl LLMProvider for AnthropicProvider {
+    #[instrument(skip(self, request), fields(model = %request.model))]
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse> {
+        debug!("Sending request to Anthropic API");
+        request.check_supported(self.name(), self.supported_params())?;
+
+        let response = self.send(&Self::build_request(request, false)).await?;
+
         // Parse response
         let anthropic_response: AnthropicResponse = response.json().await.map_err(|e| {
             crate::LLMError::UnexpectedResponse(format!("Failed to parse response: {e}"))
@@ -117,16 +170,7 @@ impl LLMProvider for AnthropicProvider {
                 role: Role::Assistant,
                 content: Some(MessageContent::Blocks(anthropic_response.content)),
             },
-            stop_reason: match anthropic_response.stop_reason.as_str() {
-                "end_turn" => StopReason::EndTurn,
-                "max_tokens" => StopReason::MaxTokens,
-                "stop_sequence" => StopReason::StopSequence,
-                "tool_use" => StopReason::ToolUse,
-                _ => {
-                    debug!("Unknown stop reason: {}", anthropic_response.stop_reason);
-                    StopReason::EndTurn
-                }
-            },
+            stop_reason: map_stop_reason(&anthropic_response.stop_reason),
             usage: TokenUsage {
                 input_tokens: anthropic_response.usage.input_tokens,
                 output_tokens: anthropic_response.usage.output_tokens,
@@ -134,9 +178,39 @@ impl LLMProvider for AnthropicProvider {
         })
     }
 
+    #[instrument(skip(self, request, blocks), fields(model = %request.model))]
+    async fn complete_streaming(
+        &self,
+        request: CompletionRequest,
+        blocks: &UnboundedSender<ContentBlock>,
+    ) -> Result<CompletionResponse> {
+        debug!("Streaming request to Anthropic API");
+        request.check_supported(self.name(), self.supported_params())?;
+
+        let mut response = self.send(&Self::build_request(request, true)).await?;
+
+        let mut decoder = SseDecoder::default();
+        let mut stream = MessageStream::default();
+        while let Some(events) = sse::next_events(&mut response, &mut decoder).await? {
+            for event in events {
+                if let Some(block) = stream.apply(&event.data)? {
+                    debug!("Streamed content block complete");
+                    // Sending only fails when the caller stopped listening
+                    let _ = blocks.send(block);
+                }
+            }
+        }
+
+        stream.finish()
+    }
+
     fn name(&self) -> &'static str {
         "anthropic"
     }
+
+    fn supported_params(&self) -> &'static [GenerationParam] {
+        &[GenerationParam::TopP, GenerationParam::TopK]
+    }
 }
 
 // Anthropic-specific request/response types
@@ -152,9 +226,60 @@ struct AnthropicRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<ToolDefinition>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<AnthropicToolChoice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     stop_sequences: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+}
+
+/// Anthropic's `tool_choice`, which also carries the parallel tool use
+/// switch: `{"type": ..., "disable_parallel_tool_use": true}`
+#[derive(Debug, PartialEq, Serialize)]
+struct AnthropicToolChoice {
+    #[serde(flatten)]
+    mode: AnthropicToolMode,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    disable_parallel_tool_use: bool,
+}
+
+impl AnthropicToolChoice {
+    /// `None` when neither the tool choice nor parallel calling is set,
+    /// leaving both to Anthropic's defaults
+    fn new(choice: Option<ToolChoice>, parallel_tool_calls: Option<bool>) -> Option<Self> {
+        let disable_parallel = parallel_tool_calls == Some(false);
+        if choice.is_none() && !disable_parallel {
+            return None;
+        }
+        let mode = match choice.unwrap_or_default() {
+            ToolChoice::Auto => AnthropicToolMode::Auto,
+            ToolChoice::None => AnthropicToolMode::None,
+            ToolChoice::Required => AnthropicToolMode::Any,
+            ToolChoice::Tool { name } => AnthropicToolMode::Tool { name },
+        };
+        // Only meaningful when tools may be called
+        let disable_parallel_tool_use = disable_parallel && mode != AnthropicToolMode::None;
+        Some(Self {
+            mode,
+            disable_parallel_tool_use,
+        })
+    }
+}
+
+/// `{"type": "auto" | "any" | "none"}` or `{"type": "tool", "name": ...}`
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AnthropicToolMode {
+    Auto,
+    Any,
+    None,
+    Tool { name: String },
 }
 
 #[derive(Debug, Deserialize)]
@@ -164,12 +289,220 @@ struct AnthropicResponse {
     usage: UsageResponse,
 }
 
+/// Response of `GET /v1/models`
+#[derive(Debug, Deserialize)]
+struct ModelList {
+    data: Vec<ModelEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelEntry {
+    id: String,
+}
+
 #[derive(Debug, Deserialize)]
 struct UsageResponse {
     input_tokens: usize,
     output_tokens: usize,
 }
 
+fn map_stop_reason(reason: &str) -> StopReason {
+    match reason {
+        "end_turn" => StopReason::EndTurn,
+        "max_tokens" => StopReason::MaxTokens,
+        "stop_sequence" => StopReason::StopSequence,
+        "tool_use" => StopReason::ToolUse,
+        _ => {
+            debug!("Unknown stop reason: {}", reason);
+            StopReason::EndTurn
+        }
+    }
+}
+
+// Streaming events
+// See: https://docs.anthropic.com/en/api/messages-streaming
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamEvent {
+    MessageStart {
+        message: StreamMessage,
+    },
+    ContentBlockStart {
+        index: usize,
+        content_block: ContentBlock,
+    },
+    ContentBlockDelta {
+        index: usize,
+        delta: BlockDelta,
+    },
+    ContentBlockStop {
+        index: usize,
+    },
+    MessageDelta {
+        delta: MessageDelta,
+        #[serde(default)]
+        usage: Option<DeltaUsage>,
+    },
+    Error {
+        error: StreamError,
+    },
+    /// `message_stop`, `ping` and event types added later
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamMessage {
+    usage: UsageResponse,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum BlockDelta {
+    TextDelta {
+        text: String,
+    },
+    InputJsonDelta {
+        partial_json: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct MessageDelta {
+    stop_reason: Option<String>,
+}
+
+/// Usage in `message_delta`, where output tokens are the running total
+#[derive(Debug, Deserialize)]
+struct DeltaUsage {
+    output_tokens: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamError {
+    #[serde(rename = "type")]
+    error_type: String,
+    message: String,
+}
+
+/// Assembles a message from its stream of events
+#[derive(Debug, Default)]
+struct MessageStream {
+    blocks: Vec<ContentBlock>,
+    /// Tool input JSON received so far, by block index
+    tool_inputs: Vec<String>,
+    stop_reason: Option<String>,
+    usage: TokenUsage,
+}
+
+impl MessageStream {
+    /// Apply one event's data, returning the content block it completes
+    fn apply(&mut self, data: &str) -> Result<Option<ContentBlock>> {
+        let event: StreamEvent = serde_json::from_str(data).map_err(|e| {
+            crate::LLMError::UnexpectedResponse(format!("Failed to parse stream event: {e}"))
+        })?;
+
+        match event {
+            StreamEvent::MessageStart { message } => {
+                self.usage.input_tokens = message.usage.input_tokens;
+                self.usage.output_tokens = message.usage.output_tokens;
+            }
+            StreamEvent::ContentBlockStart {
+                index,
+                content_block,
+            } => {
+                if index != self.blocks.len() {
+                    return Err(crate::LLMError::UnexpectedResponse(format!(
+                        "Stream started content block {index} out of order"
+                    )));
+                }
+                self.blocks.push(content_block);
+                self.tool_inputs.push(String::new());
+            }
+            StreamEvent::ContentBlockDelta { index, delta } => {
+                match (self.blocks.get_mut(index), delta) {
+                    (Some(ContentBlock::Text { text }), BlockDelta::TextDelta { text: delta }) => {
+                        text.push_str(&delta);
+                    }
+                    (
+                        Some(ContentBlock::ToolUse { .. }),
+                        BlockDelta::InputJsonDelta { partial_json },
+                    ) => {
+                        self.tool_inputs[index].push_str(&partial_json);
+                    }
+                    (None, _) => return Err(unknown_block(index)),
+                    _ => {}
+                }
+            }
+            StreamEvent::ContentBlockStop { index } => {
+                let block = self
+                    .blocks
+                    .get_mut(index)
+                    .ok_or_else(|| unknown_block(index))?;
+                if let ContentBlock::ToolUse { input, .. } = block {
+                    let json = std::mem::take(&mut self.tool_inputs[index]);
+                    // Tools without parameters stream no input at all
+                    if !json.trim().is_empty() {
+                        *input = serde_json::from_str(&json).map_err(|e| {
+                            crate::LLMError::UnexpectedResponse(format!(
+                                "Failed to parse tool input: {e}"
+                            ))
+                        })?;
+                    }
+                }
+                return Ok(Some(block.clone()));
+            }
+            StreamEvent::MessageDelta { delta, usage } => {
+                if let Some(reason) = delta.stop_reason {
+                    self.stop_reason = Some(reason);
+                }
+                if let Some(usage) = usage {
+                    self.usage.output_tokens = usage.output_tokens;
+                }
+            }
+            StreamEvent::Error { error } => {
+                return Err(match error.error_type.as_str() {
+                    "rate_limit_error" => crate::LLMError::RateLimitExceeded(error.message),
+                    _ => crate::LLMError::RequestFailed(format!(
+                        "{}: {}",
+                        error.error_type, error.message
+                    )),
+                });
+            }
+            StreamEvent::Other => {}
+        }
+        Ok(None)
+    }
+
+    /// The complete response, once the stream has ended
+    fn finish(self) -> Result<CompletionResponse> {
+        let stop_reason = self.stop_reason.ok_or_else(|| {
+            crate::LLMError::UnexpectedResponse("Stream ended without a stop reason".to_string())
+        })?;
+
+        debug!(
+            "Received streamed response - stop_reason: {}, tokens: {}/{}",
+            stop_reason, self.usage.input_tokens, self.usage.output_tokens
+        );
+
+        Ok(CompletionResponse {
+            message: Message {
+                role: Role::Assistant,
+                content: Some(MessageContent::Blocks(self.blocks)),
+            },
+            stop_reason: map_stop_reason(&stop_reason),
+            usage: self.usage,
+        })
+    }
+}
+
+fn unknown_block(index: usize) -> crate::LLMError {
+    crate::LLMError::UnexpectedResponse(format!("Stream referenced unknown content block {index}"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -181,6 +514,104 @@ mod tests {
         assert_eq!(provider.unwrap().name(), "anthropic");
     }
 
+    #[tokio::test]
+    async fn test_rejects_openai_only_params() {
+        let provider = AnthropicProvider::new("test-key".to_string()).unwrap();
+        let request = CompletionRequest::builder("claude-sonnet-4-5-20250929")
+            .add_message(Message::user("Hi"))
+            .presence_penalty(0.5)
+            .build();
+
+        let err = provider.complete(request).await.unwrap_err();
+        assert!(
+            matches!(err, crate::LLMError::InvalidRequest(msg) if msg.contains("presence_penalty"))
+        );
+    }
+
+    #[test]
+    fn test_tool_choice_mapping() {
+        let to_json = |choice, parallel| {
+            serde_json::to_value(AnthropicToolChoice::new(choice, parallel)).unwrap()
+        };
+        assert_eq!(
+            to_json(Some(ToolChoice::Required), None),
+            serde_json::json!({ "type": "any" })
+        );
+        assert_eq!(
+            to_json(Some(ToolChoice::None), Some(false)),
+            serde_json::json!({ "type": "none" })
+        );
+        assert_eq!(
+            to_json(Some(ToolChoice::tool("extract")), None),
+            serde_json::json!({ "type": "tool", "name": "extract" })
+        );
+        assert_eq!(
+            to_json(None, Some(false)),
+            serde_json::json!({ "type": "auto", "disable_parallel_tool_use": true })
+        );
+        assert_eq!(to_json(None, Some(true)), serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_message_stream() {
+        let events = [
+            r#"{"type":"message_start","message":{"id":"msg_1","type":"message","role":"assistant","content":[],"usage":{"input_tokens":25,"output_tokens":1}}}"#,
+            r#"{"type":"content_block_start","index":0,"content_block":{"type":"text","text":""}}"#,
+            r#"{"type":"ping"}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Checking "}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"prices."}}"#,
+            r#"{"type":"content_block_stop","index":0}"#,
+            r#"{"type":"content_block_start","index":1,"content_block":{"type":"tool_use","id":"toolu_1","name":"price","input":{}}}"#,
+            r#"{"type":"content_block_delta","index":1,"delta":{"type":"input_json_delta","partial_json":""}}"#,
+            r#"{"type":"content_block_delta","index":1,"delta":{"type":"input_json_delta","partial_json":"{\"symbol\": \"AA"}}"#,
+            r#"{"type":"content_block_delta","index":1,"delta":{"type":"input_json_delta","partial_json":"PL\"}"}}"#,
+            r#"{"type":"content_block_stop","index":1}"#,
+            r#"{"type":"content_block_start","index":2,"content_block":{"type":"tool_use","id":"toolu_2","name":"market_status","input":{}}}"#,
+            r#"{"type":"content_block_stop","index":2}"#,
+            r#"{"type":"message_delta","delta":{"stop_reason":"tool_use","stop_sequence":null},"usage":{"output_tokens":42}}"#,
+            r#"{"type":"message_stop"}"#,
+        ];
+
+        let mut stream = MessageStream::default();
+        let mut completed = Vec::new();
+        for event in events {
+            if let Some(block) = stream.apply(event).unwrap() {
+                completed.push(block);
+            }
+        }
+
+        // Each block is handed over as soon as it stops
+        assert_eq!(completed.len(), 3);
+        assert!(matches!(
+            &completed[1],
+            ContentBlock::ToolUse { id, input, .. }
+                if id == "toolu_1" && input == &serde_json::json!({ "symbol": "AAPL" })
+        ));
+        assert!(matches!(
+            &completed[2],
+            ContentBlock::ToolUse { input, .. } if input == &serde_json::json!({})
+        ));
+
+        let response = stream.finish().unwrap();
+        assert_eq!(response.stop_reason, StopReason::ToolUse);
+        assert_eq!(response.usage.input_tokens, 25);
+        assert_eq!(response.usage.output_tokens, 42);
+        assert_eq!(response.message.text(), Some("Checking prices."));
+        assert_eq!(response.message.tool_uses().len(), 2);
+    }
+
+    #[test]
+    fn test_message_stream_errors() {
+        let mut stream = MessageStream::default();
+        let err = stream
+            .apply(r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#)
+            .unwrap_err();
+        assert!(matches!(err, crate::LLMError::RequestFailed(msg) if msg.contains("Overloaded")));
+
+        // A stream cut off before the message ends is not a response
+        assert!(MessageStream::default().finish().is_err());
+    }
+
     #[test]
     fn test_from_env_without_key() {
         // This will fail if ANTHROPIC_API_KEY is not set
@@ -3,6 +3,9 @@
 //! This module contains implementations of the LLMProvider trait for
 //! various LLM services.
 
+#[cfg(any(feature = "anthropic", feature = "openai"))]
+mod sse;
+
 #[cfg(feature = "anthropic")]
 pub mod anthropic;
 
@@ -14,3 +17,9 @@ pub mod openai;
 
 #[cfg(feature = "openai")]
 pub use openai::{OpenAIConfig, OpenAIProvider};
+
+#[cfg(feature = "ollama")]
+pub mod ollama;
+
+#[cfg(feature = "ollama")]
+pub use ollama::{KeepAlive, OllamaConfig, OllamaProvider, RunningModel};
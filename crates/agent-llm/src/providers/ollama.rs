@@ -0,0 +1,1124 @@
+//! Ollama provider implementation
+//!
+//! This module implements the LLMProvider trait for models served by a local
+//! Ollama server, through its native API rather than the OpenAI-compatible
+//! one, so keep-alive, context size and model management are available.
+//! See: https://github.com/ollama/ollama/blob/main/docs/api.md
+//!
+//! # Example
+//!
+//! ```no_run
+//! use agent_llm::{CompletionRequest, Message, LLMProvider};
+//! use agent_llm::providers::{KeepAlive, OllamaConfig, OllamaProvider};
+//! use std::time::Duration;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let config = OllamaConfig::new()
+//!         .with_keep_alive(KeepAlive::For(Duration::from_secs(30 * 60)))
+//!         .with_num_ctx(16384);
+//!     let provider = OllamaProvider::with_config(config)?;
+//!
+//!     println!("Installed: {:?}", provider.list_models().await?);
+//!
+//!     let request = CompletionRequest::builder("qwen2.5:14b")
+//!         .add_message(Message::user("Hello!"))
+//!         .max_tokens(100)
+//!         .build();
+//!
+//!     let response = provider.complete(request).await?;
+//!     println!("{}", response.message.text().unwrap());
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use crate::{
+    CompletionRequest, CompletionResponse, ContentBlock, GenerationParam, ImageSource, LLMProvider,
+    Message, MessageContent, Result, Role, StopReason, TokenUsage, ToolChoice, ToolDefinition,
+};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::{debug, instrument};
+
+const DEFAULT_OLLAMA_API_BASE: &str = "http://localhost:11434";
+/// Local models can take a while to load and generate
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+
+/// Source of tool call IDs, which Ollama does not assign
+static NEXT_TOOL_CALL: AtomicU64 = AtomicU64::new(0);
+
+/// How long Ollama keeps a model loaded after a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeepAlive {
+    /// Unload the model this long after the last request
+    For(Duration),
+    /// Keep the model loaded until the server stops
+    Forever,
+    /// Unload the model as soon as the request completes
+    Unload,
+}
+
+impl KeepAlive {
+    /// Parse Ollama's own `OLLAMA_KEEP_ALIVE` format: seconds (`"600"`),
+    /// a duration with a unit (`"10m"`, `"1h"`, `"30s"`), or a negative
+    /// number for forever
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if let Ok(secs) = value.parse::<i64>() {
+            return Some(match secs {
+                0 => Self::Unload,
+                s if s < 0 => Self::Forever,
+                s => Self::For(Duration::from_secs(s.unsigned_abs())),
+            });
+        }
+
+        let split = value.find(|c: char| !c.is_ascii_digit() && c != '-')?;
+        let (amount, unit) = value.split_at(split);
+        let amount: i64 = amount.parse().ok()?;
+        if amount < 0 {
+            return Some(Self::Forever);
+        }
+        let secs = match unit {
+            "s" => amount,
+            "m" => amount * 60,
+            "h" => amount * 3600,
+            _ => return None,
+        };
+        Some(if secs == 0 {
+            Self::Unload
+        } else {
+            Self::For(Duration::from_secs(secs.unsigned_abs()))
+        })
+    }
+}
+
+impl Serialize for KeepAlive {
+    /// Ollama takes a number of seconds, with negative meaning forever
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            Self::For(duration) => serializer.serialize_u64(duration.as_secs()),
+            Self::Forever => serializer.serialize_i64(-1),
+            Self::Unload => serializer.serialize_u64(0),
+        }
+    }
+}
+
+/// Configuration for Ollama provider
+#[derive(Debug, Clone)]
+pub struct OllamaConfig {
+    /// Base URL of the Ollama server (default: "http://localhost:11434")
+    pub api_base: String,
+
+    /// Request timeout in seconds (default: 300)
+    pub timeout_secs: u64,
+
+    /// How long the model stays loaded after each request
+    /// If None, the server's default (5 minutes) applies
+    pub keep_alive: Option<KeepAlive>,
+
+    /// Context window in tokens
+    /// If None, the model's default applies, which is often too small for
+    /// tool definitions and long conversations
+    pub num_ctx: Option<u32>,
+}
+
+impl OllamaConfig {
+    /// Create a config for a server on localhost with default settings
+    pub fn new() -> Self {
+        Self {
+            api_base: DEFAULT_OLLAMA_API_BASE.to_string(),
+            timeout_secs: DEFAULT_TIMEOUT_SECS,
+            keep_alive: None,
+            num_ctx: None,
+        }
+    }
+
+    /// Create config from environment variables
+    ///
+    /// Reads the server address from `OLLAMA_HOST` (as the Ollama CLI does,
+    /// e.g. `127.0.0.1:11434`), the keep-alive from `OLLAMA_KEEP_ALIVE` and
+    /// the context window from `OLLAMA_NUM_CTX`. All are optional.
+    pub fn from_env() -> Result<Self> {
+        let mut config = Self::new();
+        if let Ok(host) = std::env::var("OLLAMA_HOST") {
+            config = config.with_api_base(host);
+        }
+        if let Ok(value) = std::env::var("OLLAMA_KEEP_ALIVE") {
+            let keep_alive = KeepAlive::parse(&value).ok_or_else(|| {
+                crate::LLMError::ConfigurationError(format!(
+                    "OLLAMA_KEEP_ALIVE must be seconds or a duration like '10m', got '{value}'"
+                ))
+            })?;
+            config.keep_alive = Some(keep_alive);
+        }
+        if let Ok(value) = std::env::var("OLLAMA_NUM_CTX") {
+            let num_ctx = value.parse().map_err(|_| {
+                crate::LLMError::ConfigurationError(format!(
+                    "OLLAMA_NUM_CTX must be a number of tokens, got '{value}'"
+                ))
+            })?;
+            config.num_ctx = Some(num_ctx);
+        }
+        Ok(config)
+    }
+
+    /// Set the server address
+    ///
+    /// Accepts a full URL or a bare `host:port`, which is taken as `http`.
+    pub fn with_api_base(mut self, api_base: impl Into<String>) -> Self {
+        let api_base = api_base.into();
+        let api_base = api_base.trim_end_matches('/');
+        self.api_base = if api_base.contains("://") {
+            api_base.to_string()
+        } else {
+            format!("http://{api_base}")
+        };
+        self
+    }
+
+    /// Set request timeout in seconds
+    pub fn with_timeout(mut self, timeout_secs: u64) -> Self {
+        self.timeout_secs = timeout_secs;
+        self
+    }
+
+    /// Set how long the model stays loaded after each request
+    pub fn with_keep_alive(mut self, keep_alive: KeepAlive) -> Self {
+        self.keep_alive = Some(keep_alive);
+        self
+    }
+
+    /// Set the context window in tokens
+    pub fn with_num_ctx(mut self, num_ctx: u32) -> Self {
+        self.num_ctx = Some(num_ctx);
+        self
+    }
+}
+
+impl Default for OllamaConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A model loaded into memory, as reported by `GET /api/ps`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct RunningModel {
+    /// Model name, e.g. "llama3.1:8b"
+    pub name: String,
+    /// Memory used, in bytes
+    #[serde(default)]
+    pub size: u64,
+    /// Of which in GPU memory, in bytes
+    #[serde(default)]
+    pub size_vram: u64,
+    /// When the model will be unloaded (RFC 3339)
+    #[serde(default)]
+    pub expires_at: Option<String>,
+}
+
+/// Ollama provider
+///
+/// Supports any model pulled into the local Ollama server, e.g.:
+/// - llama3.1
+/// - qwen2.5
+/// - mistral-nemo
+///
+/// Tool calling needs a model trained for it; Ollama lists these under
+/// "tools" in its model library.
+pub struct OllamaProvider {
+    client: Client,
+    config: OllamaConfig,
+}
+
+impl OllamaProvider {
+    /// Create a new Ollama provider with custom configuration
+    pub fn with_config(config: OllamaConfig) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()?;
+
+        Ok(Self { client, config })
+    }
+
+    /// Create a provider for a server on localhost with default settings
+    pub fn new() -> Result<Self> {
+        Self::with_config(OllamaConfig::new())
+    }
+
+    /// Create a provider from environment variables
+    ///
+    /// See [`OllamaConfig::from_env`].
+    pub fn from_env() -> Result<Self> {
+        Self::with_config(OllamaConfig::from_env()?)
+    }
+
+    /// Get the current configuration
+    pub fn config(&self) -> &OllamaConfig {
+        &self.config
+    }
+
+    /// List the models pulled into the server
+    ///
+    /// Calls `GET /api/tags`, which loads nothing, so it doubles as a cheap
+    /// check that the server is running.
+    pub async fn list_models(&self) -> Result<Vec<String>> {
+        let models: ModelList = self.get("tags").await?;
+        Ok(models.models.into_iter().map(|m| m.name).collect())
+    }
+
+    /// List the models currently loaded into memory
+    pub async fn running_models(&self) -> Result<Vec<RunningModel>> {
+        let models: RunningModelList = self.get("ps").await?;
+        Ok(models.models)
+    }
+
+    /// Load `model` into memory ahead of the first request, keeping it
+    /// loaded for the configured keep-alive
+    pub async fn load(&self, model: &str) -> Result<()> {
+        self.keep_model(model, self.config.keep_alive).await
+    }
+
+    /// Unload `model` from memory now
+    pub async fn unload(&self, model: &str) -> Result<()> {
+        self.keep_model(model, Some(KeepAlive::Unload)).await
+    }
+
+    /// A chat request without messages only loads or unloads the model
+    async fn keep_model(&self, model: &str, keep_alive: Option<KeepAlive>) -> Result<()> {
+        let request = OllamaRequest {
+            model: model.to_string(),
+            messages: Vec::new(),
+            tools: None,
+            stream: false,
+            options: OllamaOptions::default(),
+            keep_alive,
+        };
+        self.send(&request).await?;
+        Ok(())
+    }
+
+    async fn get<T: for<'de> Deserialize<'de>>(&self, endpoint: &str) -> Result<T> {
+        let response = self
+            .client
+            .get(format!("{}/api/{endpoint}", self.config.api_base))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await?;
+            return Err(crate::LLMError::RequestFailed(format!(
+                "HTTP {status}: {error_text}"
+            )));
+        }
+
+        response.json().await.map_err(|e| {
+            crate::LLMError::UnexpectedResponse(format!("Failed to parse {endpoint} response: {e}"))
+        })
+    }
+
+    /// Validate a request and convert it to Ollama's format
+    fn build_request(&self, request: CompletionRequest, stream: bool) -> Result<OllamaRequest> {
+        request.check_supported(self.name(), self.supported_params())?;
+
+        let messages = build_ollama_messages(request.system, request.messages)?;
+
+        // Ollama has no tool_choice, so it is approximated by which tools are sent
+        let tools = match (request.tools, request.tool_choice) {
+            (Some(_), Some(ToolChoice::None)) | (None, _) => None,
+            (Some(tools), Some(ToolChoice::Tool { name })) => Some(
+                tools
+                    .iter()
+                    .filter(|t| t.name == name)
+                    .map(convert_tool)
+                    .collect(),
+            ),
+            (Some(tools), choice) => {
+                if choice == Some(ToolChoice::Required) {
+                    debug!("Ollama cannot require a tool call; the model may answer directly");
+                }
+                Some(tools.iter().map(convert_tool).collect())
+            }
+        };
+
+        Ok(OllamaRequest {
+            model: request.model,
+            messages,
+            tools,
+            stream,
+            options: OllamaOptions {
+                num_predict: Some(request.max_tokens),
+                num_ctx: self.config.num_ctx,
+                temperature: request.temperature,
+                top_p: request.top_p,
+                top_k: request.top_k,
+                frequency_penalty: request.frequency_penalty,
+                presence_penalty: request.presence_penalty,
+                stop: request.stop_sequences,
+            },
+            keep_alive: self.config.keep_alive,
+        })
+    }
+
+    /// Send a chat request, mapping HTTP errors
+    async fn send(&self, ollama_request: &OllamaRequest) -> Result<reqwest::Response> {
+        let response = self
+            .client
+            .post(format!("{}/api/chat", self.config.api_base))
+            .json(ollama_request)
+            .send()
+            .await?;
+
+        // Handle errors
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await?;
+
+            return Err(match status.as_u16() {
+                400 => crate::LLMError::InvalidRequest(error_text),
+                404 => crate::LLMError::ModelNotFound(ollama_request.model.clone()),
+                _ => crate::LLMError::RequestFailed(format!("HTTP {status}: {error_text}")),
+            });
+        }
+
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl LLMProvider for OllamaProvider {
+    #[instrument(skip(self, request), fields(model = %request.model, api_base = %self.config.api_base))]
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse> {
+        debug!("Sending request to Ollama at {}", self.config.api_base);
+
+        let response = self.send(&self.build_request(request, false)?).await?;
+
+        let chunk: OllamaChatChunk = response.json().await.map_err(|e| {
+            crate::LLMError::UnexpectedResponse(format!("Failed to parse response: {e}"))
+        })?;
+
+        let mut stream = ChatStream::default();
+        stream.apply_chunk(chunk)?;
+        stream.finish()
+    }
+
+    #[instrument(skip(self, request, blocks), fields(model = %request.model, api_base = %self.config.api_base))]
+    async fn complete_streaming(
+        &self,
+        request: CompletionRequest,
+        blocks: &UnboundedSender<ContentBlock>,
+    ) -> Result<CompletionResponse> {
+        debug!("Streaming request to Ollama at {}", self.config.api_base);
+
+        let mut response = self.send(&self.build_request(request, true)?).await?;
+
+        // Ollama streams one JSON object per line rather than server-sent events
+        let mut buffer = Vec::new();
+        let mut stream = ChatStream::default();
+        while let Some(chunk) = response.chunk().await? {
+            buffer.extend_from_slice(&chunk);
+            while let Some(end) = buffer.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line);
+                if line.trim().is_empty() {
+                    continue;
+                }
+                for block in stream.apply(&line)? {
+                    debug!("Streamed block complete");
+                    // Sending only fails when the caller stopped listening
+                    let _ = blocks.send(block);
+                }
+            }
+        }
+        if !buffer.is_empty() {
+            for block in stream.apply(&String::from_utf8_lossy(&buffer))? {
+                let _ = blocks.send(block);
+            }
+        }
+
+        stream.finish()
+    }
+
+    fn name(&self) -> &'static str {
+        "ollama"
+    }
+
+    fn supported_params(&self) -> &'static [GenerationParam] {
+        &[
+            GenerationParam::TopP,
+            GenerationParam::TopK,
+            GenerationParam::FrequencyPenalty,
+            GenerationParam::PresencePenalty,
+        ]
+    }
+}
+
+// ============================================================================
+// Ollama-specific request types
+// ============================================================================
+
+#[derive(Debug, Serialize)]
+struct OllamaRequest {
+    model: String,
+    messages: Vec<OllamaMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<OllamaTool>>,
+    /// Always sent, since Ollama streams unless told otherwise
+    stream: bool,
+    options: OllamaOptions,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<KeepAlive>,
+}
+
+/// Model parameters, which Ollama takes apart from the request itself
+#[derive(Debug, Default, Serialize)]
+struct OllamaOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_predict: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_ctx: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct OllamaMessage {
+    role: String,
+    #[serde(default)]
+    content: String,
+    /// Base64-encoded images, without a data URL prefix
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    images: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<OllamaToolCall>,
+    /// Name of the tool a `tool` message answers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tool_name: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaTool {
+    #[serde(rename = "type")]
+    tool_type: String,
+    function: OllamaFunction,
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaFunction {
+    name: String,
+    description: String,
+    parameters: serde_json::Value,
+}
+
+/// A tool call; unlike OpenAI the arguments are a JSON object, not a string
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaToolCall {
+    function: OllamaFunctionCall,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaFunctionCall {
+    name: String,
+    #[serde(default)]
+    arguments: serde_json::Value,
+}
+
+// ============================================================================
+// Ollama-specific response types
+// ============================================================================
+
+/// Response of `GET /api/tags`
+#[derive(Debug, Deserialize)]
+struct ModelList {
+    #[serde(default)]
+    models: Vec<ModelEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelEntry {
+    name: String,
+}
+
+/// Response of `GET /api/ps`
+#[derive(Debug, Deserialize)]
+struct RunningModelList {
+    #[serde(default)]
+    models: Vec<RunningModel>,
+}
+
+/// A chat response, or one line of a streamed one
+#[derive(Debug, Deserialize)]
+struct OllamaChatChunk {
+    #[serde(default)]
+    message: Option<OllamaMessage>,
+    #[serde(default)]
+    done: bool,
+    done_reason: Option<String>,
+    prompt_eval_count: Option<usize>,
+    eval_count: Option<usize>,
+    /// Set instead of a message when generation fails mid-stream
+    error: Option<String>,
+}
+
+/// Assembles a response from its chunks
+///
+/// Ollama sends each tool call whole in a single chunk, so tool calls are
+/// complete as soon as they arrive. Text comes before them, so it is
+/// complete once the first tool call arrives or the response is done.
+#[derive(Debug, Default)]
+struct ChatStream {
+    text: String,
+    /// Whether the text was already handed over as a block
+    text_sent: bool,
+    tool_uses: Vec<ContentBlock>,
+    done_reason: Option<String>,
+    usage: TokenUsage,
+}
+
+impl ChatStream {
+    /// Apply one line of a streamed response, returning the text and tool
+    /// calls it completes
+    fn apply(&mut self, line: &str) -> Result<Vec<ContentBlock>> {
+        let chunk: OllamaChatChunk = serde_json::from_str(line).map_err(|e| {
+            crate::LLMError::UnexpectedResponse(format!("Failed to parse stream chunk: {e}"))
+        })?;
+        self.apply_chunk(chunk)
+    }
+
+    fn apply_chunk(&mut self, chunk: OllamaChatChunk) -> Result<Vec<ContentBlock>> {
+        if let Some(error) = chunk.error {
+            return Err(crate::LLMError::RequestFailed(error));
+        }
+
+        let mut blocks = Vec::new();
+        if let Some(message) = chunk.message {
+            self.text.push_str(&message.content);
+            if !message.tool_calls.is_empty() {
+                blocks.extend(self.take_text());
+            }
+            for call in message.tool_calls {
+                let id = format!(
+                    "ollama_call_{}",
+                    NEXT_TOOL_CALL.fetch_add(1, Ordering::Relaxed)
+                );
+                // Tools without parameters may come without arguments
+                let input = match call.function.arguments {
+                    serde_json::Value::Null => serde_json::json!({}),
+                    arguments => arguments,
+                };
+                let tool_use = ContentBlock::ToolUse {
+                    id,
+                    name: call.function.name,
+                    input,
+                };
+                self.tool_uses.push(tool_use.clone());
+                blocks.push(tool_use);
+            }
+        }
+
+        if chunk.done {
+            self.done_reason = Some(chunk.done_reason.unwrap_or_else(|| "stop".to_string()));
+            self.usage = TokenUsage {
+                input_tokens: chunk.prompt_eval_count.unwrap_or_default(),
+                output_tokens: chunk.eval_count.unwrap_or_default(),
+            };
+            blocks.extend(self.take_text());
+        }
+        Ok(blocks)
+    }
+
+    /// The text as a block, unless it is empty or was already handed over
+    fn take_text(&mut self) -> Option<ContentBlock> {
+        if self.text_sent || self.text.is_empty() {
+            return None;
+        }
+        self.text_sent = true;
+        Some(ContentBlock::Text {
+            text: self.text.clone(),
+        })
+    }
+
+    /// The complete response, once the final chunk has arrived
+    fn finish(self) -> Result<CompletionResponse> {
+        let done_reason = self.done_reason.ok_or_else(|| {
+            crate::LLMError::UnexpectedResponse("Response ended before it was done".to_string())
+        })?;
+
+        debug!(
+            "Received response - stop_reason: {}, tokens: {}/{}",
+            done_reason, self.usage.input_tokens, self.usage.output_tokens
+        );
+
+        // Ollama reports "stop" when the model calls tools
+        let stop_reason = if self.tool_uses.is_empty() {
+            map_stop_reason(&done_reason)
+        } else {
+            StopReason::ToolUse
+        };
+
+        let mut blocks = Vec::new();
+        if !self.text.is_empty() || self.tool_uses.is_empty() {
+            blocks.push(ContentBlock::Text { text: self.text });
+        }
+        blocks.extend(self.tool_uses);
+
+        Ok(CompletionResponse {
+            message: Message {
+                role: Role::Assistant,
+                content: Some(MessageContent::Blocks(blocks)),
+            },
+            stop_reason,
+            usage: self.usage,
+        })
+    }
+}
+
+// ============================================================================
+// Conversion functions
+// ============================================================================
+
+/// Build Ollama messages from our generic format
+///
+/// Ollama answers tool calls by tool name rather than ID, so the name of
+/// each tool use is remembered for the results that follow it.
+fn build_ollama_messages(
+    system: Option<String>,
+    messages: Vec<Message>,
+) -> Result<Vec<OllamaMessage>> {
+    let mut result = Vec::new();
+
+    if let Some(sys) = system {
+        result.push(OllamaMessage {
+            role: "system".to_string(),
+            content: sys,
+            ..OllamaMessage::default()
+        });
+    }
+
+    let mut tool_names = HashMap::new();
+    for msg in messages {
+        result.extend(convert_message(msg, &mut tool_names)?);
+    }
+
+    Ok(result)
+}
+
+/// Convert a single message to Ollama format
+///
+/// Tool results become separate messages with role "tool", after the
+/// message's own text, images and tool calls.
+fn convert_message(
+    msg: Message,
+    tool_names: &mut HashMap<String, String>,
+) -> Result<Vec<OllamaMessage>> {
+    let role = match msg.role {
+        Role::User => "user",
+        Role::Assistant => "assistant",
+        Role::System => "system",
+    };
+
+    let blocks = match msg.content {
+        Some(MessageContent::Text(text)) => vec![ContentBlock::Text { text }],
+        Some(MessageContent::Blocks(blocks)) => blocks,
+        None => Vec::new(),
+    };
+
+    let mut main = OllamaMessage {
+        role: role.to_string(),
+        ..OllamaMessage::default()
+    };
+    let mut results = Vec::new();
+    for block in blocks {
+        match block {
+            ContentBlock::Text { text } => {
+                if !main.content.is_empty() {
+                    main.content.push('\n');
+                }
+                main.content.push_str(&text);
+            }
+            ContentBlock::Image { source } => match source {
+                ImageSource::Base64 { data, .. } => main.images.push(data),
+                ImageSource::Url { url } => {
+                    return Err(crate::LLMError::InvalidRequest(format!(
+                        "Ollama only accepts base64 images, not URLs: {url}"
+                    )));
+                }
+            },
+            ContentBlock::ToolUse { id, name, input } => {
+                tool_names.insert(id, name.clone());
+                main.tool_calls.push(OllamaToolCall {
+                    function: OllamaFunctionCall {
+                        name,
+                        arguments: input,
+                    },
+                });
+            }
+            ContentBlock::ToolResult {
+                tool_use_id,
+                content,
+                ..
+            } => {
+                results.push(OllamaMessage {
+                    role: "tool".to_string(),
+                    content,
+                    tool_name: tool_names.get(&tool_use_id).cloned(),
+                    ..OllamaMessage::default()
+                });
+            }
+        }
+    }
+
+    let has_main = !main.content.is_empty()
+        || !main.images.is_empty()
+        || !main.tool_calls.is_empty()
+        || results.is_empty();
+    Ok(has_main
+        .then_some(main)
+        .into_iter()
+        .chain(results)
+        .collect())
+}
+
+/// Convert a tool definition to Ollama format, which matches OpenAI's
+fn convert_tool(tool: &ToolDefinition) -> OllamaTool {
+    OllamaTool {
+        tool_type: "function".to_string(),
+        function: OllamaFunction {
+            name: tool.name.clone(),
+            description: tool.description.clone(),
+            parameters: tool.input_schema.clone(),
+        },
+    }
+}
+
+/// Map Ollama's `done_reason` to our format
+fn map_stop_reason(reason: &str) -> StopReason {
+    match reason {
+        "stop" => StopReason::EndTurn,
+        "length" => StopReason::MaxTokens,
+        _ => {
+            debug!("Unknown stop reason: {}", reason);
+            StopReason::EndTurn
+        }
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_provider_creation() {
+        let provider = OllamaProvider::new().unwrap();
+        assert_eq!(provider.name(), "ollama");
+        assert_eq!(provider.config().api_base, "http://localhost:11434");
+        assert_eq!(provider.config().timeout_secs, 300);
+    }
+
+    #[test]
+    fn test_config_builder() {
+        let config = OllamaConfig::new()
+            .with_api_base("192.168.1.20:11434/")
+            .with_timeout(60)
+            .with_keep_alive(KeepAlive::Forever)
+            .with_num_ctx(8192);
+        assert_eq!(config.api_base, "http://192.168.1.20:11434");
+        assert_eq!(config.timeout_secs, 60);
+        assert_eq!(config.keep_alive, Some(KeepAlive::Forever));
+        assert_eq!(config.num_ctx, Some(8192));
+
+        let config = OllamaConfig::new().with_api_base("https://ollama.internal");
+        assert_eq!(config.api_base, "https://ollama.internal");
+    }
+
+    #[test]
+    fn test_keep_alive() {
+        assert_eq!(
+            KeepAlive::parse("600"),
+            Some(KeepAlive::For(Duration::from_secs(600)))
+        );
+        assert_eq!(
+            KeepAlive::parse("10m"),
+            Some(KeepAlive::For(Duration::from_secs(600)))
+        );
+        assert_eq!(
+            KeepAlive::parse("1h"),
+            Some(KeepAlive::For(Duration::from_secs(3600)))
+        );
+        assert_eq!(KeepAlive::parse("-1"), Some(KeepAlive::Forever));
+        assert_eq!(KeepAlive::parse("-1m"), Some(KeepAlive::Forever));
+        assert_eq!(KeepAlive::parse("0"), Some(KeepAlive::Unload));
+        assert_eq!(KeepAlive::parse("soon"), None);
+        assert_eq!(KeepAlive::parse("5d"), None);
+
+        let to_json = |k: KeepAlive| serde_json::to_value(k).unwrap();
+        assert_eq!(to_json(KeepAlive::For(Duration::from_secs(90))), json!(90));
+        assert_eq!(to_json(KeepAlive::Forever), json!(-1));
+        assert_eq!(to_json(KeepAlive::Unload), json!(0));
+    }
+
+    #[test]
+    fn test_config_from_env() {
+        // SAFETY: This is a test that modifies env vars, which is safe in single-threaded test context
+        unsafe {
+            std::env::set_var("OLLAMA_HOST", "0.0.0.0:11434");
+            std::env::set_var("OLLAMA_KEEP_ALIVE", "30m");
+            std::env::remove_var("OLLAMA_NUM_CTX");
+        }
+        let config = OllamaConfig::from_env().unwrap();
+        assert_eq!(config.api_base, "http://0.0.0.0:11434");
+        assert_eq!(
+            config.keep_alive,
+            Some(KeepAlive::For(Duration::from_secs(1800)))
+        );
+        assert_eq!(config.num_ctx, None);
+
+        // SAFETY: as above
+        unsafe {
+            std::env::set_var("OLLAMA_KEEP_ALIVE", "a while");
+        }
+        assert!(OllamaConfig::from_env().is_err());
+
+        // SAFETY: as above
+        unsafe {
+            std::env::remove_var("OLLAMA_HOST");
+            std::env::remove_var("OLLAMA_KEEP_ALIVE");
+        }
+    }
+
+    #[test]
+    fn test_request_conversion() {
+        let provider = OllamaProvider::with_config(
+            OllamaConfig::new()
+                .with_keep_alive(KeepAlive::For(Duration::from_secs(600)))
+                .with_num_ctx(16384),
+        )
+        .unwrap();
+        let request = CompletionRequest::builder("llama3.1")
+            .system("You are helpful")
+            .add_message(Message::user("Price of AAPL?"))
+            .max_tokens(256)
+            .temperature(0.2)
+            .tools(vec![ToolDefinition::new(
+                "price",
+                "Get a price",
+                json!({ "type": "object" }),
+            )])
+            .build();
+
+        let json = serde_json::to_value(provider.build_request(request, false).unwrap()).unwrap();
+        assert_eq!(json["model"], "llama3.1");
+        assert_eq!(json["stream"], false);
+        assert_eq!(json["keep_alive"], 600);
+        assert_eq!(
+            json["messages"][0],
+            json!({ "role": "system", "content": "You are helpful" })
+        );
+        assert_eq!(
+            json["messages"][1],
+            json!({ "role": "user", "content": "Price of AAPL?" })
+        );
+        assert_eq!(json["options"]["num_predict"], 256);
+        assert_eq!(json["options"]["num_ctx"], 16384);
+        assert!((json["options"]["temperature"].as_f64().unwrap() - 0.2).abs() < 1e-6);
+        assert_eq!(json["tools"][0]["function"]["name"], "price");
+    }
+
+    #[test]
+    fn test_tool_choice_selects_tools() {
+        let provider = OllamaProvider::new().unwrap();
+        let tools = vec![
+            ToolDefinition::new("price", "Get a price", json!({})),
+            ToolDefinition::new("news", "Get news", json!({})),
+        ];
+        let request = |choice| {
+            CompletionRequest::builder("llama3.1")
+                .add_message(Message::user("Hi"))
+                .tools(tools.clone())
+                .tool_choice(choice)
+                .build()
+        };
+
+        let only_news = provider
+            .build_request(request(ToolChoice::tool("news")), false)
+            .unwrap();
+        let names: Vec<_> = only_news
+            .tools
+            .unwrap()
+            .into_iter()
+            .map(|t| t.function.name)
+            .collect();
+        assert_eq!(names, vec!["news"]);
+
+        let none = provider
+            .build_request(request(ToolChoice::None), false)
+            .unwrap();
+        assert!(none.tools.is_none());
+    }
+
+    #[test]
+    fn test_tool_round_trip_conversion() {
+        let messages = vec![
+            Message {
+                role: Role::Assistant,
+                content: Some(MessageContent::Blocks(vec![
+                    ContentBlock::Text {
+                        text: "Checking".to_string(),
+                    },
+                    ContentBlock::ToolUse {
+                        id: "call_1".to_string(),
+                        name: "price".to_string(),
+                        input: json!({ "symbol": "AAPL" }),
+                    },
+                ])),
+            },
+            Message {
+                role: Role::User,
+                content: Some(MessageContent::Blocks(vec![ContentBlock::ToolResult {
+                    tool_use_id: "call_1".to_string(),
+                    content: "190.5".to_string(),
+                    is_error: None,
+                }])),
+            },
+        ];
+
+        let json = serde_json::to_value(build_ollama_messages(None, messages).unwrap()).unwrap();
+        assert_eq!(
+            json,
+            json!([
+                {
+                    "role": "assistant",
+                    "content": "Checking",
+                    "tool_calls": [{ "function": { "name": "price", "arguments": { "symbol": "AAPL" } } }]
+                },
+                { "role": "tool", "content": "190.5", "tool_name": "price" }
+            ])
+        );
+    }
+
+    #[test]
+    fn test_image_conversion() {
+        let message = |source| Message {
+            role: Role::User,
+            content: Some(MessageContent::Blocks(vec![
+                ContentBlock::Text {
+                    text: "What is this chart?".to_string(),
+                },
+                ContentBlock::Image { source },
+            ])),
+        };
+
+        let converted = build_ollama_messages(
+            None,
+            vec![message(ImageSource::Base64 {
+                media_type: "image/png".to_string(),
+                data: "iVBORw0KGgo=".to_string(),
+            })],
+        )
+        .unwrap();
+        assert_eq!(converted[0].images, vec!["iVBORw0KGgo="]);
+
+        let err = build_ollama_messages(
+            None,
+            vec![message(ImageSource::Url {
+                url: "https://example.com/chart.png".to_string(),
+            })],
+        )
+        .unwrap_err();
+        assert!(matches!(err, crate::LLMError::InvalidRequest(_)));
+    }
+
+    #[test]
+    fn test_chat_stream() {
+        let lines = [
+            r#"{"model":"llama3.1","message":{"role":"assistant","content":"Let me "},"done":false}"#,
+            r#"{"model":"llama3.1","message":{"role":"assistant","content":"check."},"done":false}"#,
+            r#"{"model":"llama3.1","message":{"role":"assistant","content":"","tool_calls":[{"function":{"name":"price","arguments":{"symbol":"NVDA"}}}]},"done":false}"#,
+            r#"{"model":"llama3.1","message":{"role":"assistant","content":"","tool_calls":[{"function":{"name":"market_status","arguments":{}}}]},"done":false}"#,
+            r#"{"model":"llama3.1","message":{"role":"assistant","content":""},"done":true,"done_reason":"stop","prompt_eval_count":120,"eval_count":31}"#,
+        ];
+
+        let mut stream = ChatStream::default();
+        let mut completed = Vec::new();
+        for line in lines {
+            completed.extend(stream.apply(line).unwrap());
+        }
+
+        // The text is handed over once the first tool call arrives, and
+        // tool calls as soon as their chunk does
+        assert_eq!(completed.len(), 3);
+        assert!(matches!(&completed[0], ContentBlock::Text { text } if text == "Let me check."));
+        assert!(matches!(
+            &completed[1],
+            ContentBlock::ToolUse { name, input, .. }
+                if name == "price" && input == &json!({ "symbol": "NVDA" })
+        ));
+
+        let response = stream.finish().unwrap();
+        assert_eq!(response.stop_reason, StopReason::ToolUse);
+        assert_eq!(response.usage.input_tokens, 120);
+        assert_eq!(response.usage.output_tokens, 31);
+        assert_eq!(response.message.text(), Some("Let me check."));
+
+        let ids: Vec<_> = response
+            .message
+            .tool_uses()
+            .into_iter()
+            .filter_map(|block| match block {
+                ContentBlock::ToolUse { id, .. } => Some(id.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(ids.len(), 2);
+        assert_ne!(ids[0], ids[1]);
+    }
+
+    #[test]
+    fn test_chat_stream_errors() {
+        let mut stream = ChatStream::default();
+        let err = stream
+            .apply(r#"{"error":"model requires more system memory"}"#)
+            .unwrap_err();
+        assert!(matches!(err, crate::LLMError::RequestFailed(msg) if msg.contains("memory")));
+
+        // A stream cut off before the final chunk is not a response
+        let mut stream = ChatStream::default();
+        stream
+            .apply(r#"{"message":{"role":"assistant","content":"Hi"},"done":false}"#)
+            .unwrap();
+        assert!(stream.finish().is_err());
+    }
+
+    #[test]
+    fn test_stop_reason_mapping() {
+        let mut stream = ChatStream::default();
+        stream
+            .apply(r#"{"message":{"role":"assistant","content":"Long"},"done":true,"done_reason":"length"}"#)
+            .unwrap();
+        assert_eq!(stream.finish().unwrap().stop_reason, StopReason::MaxTokens);
+        assert_eq!(map_stop_reason("stop"), StopReason::EndTurn);
+        assert_eq!(map_stop_reason("unload"), StopReason::EndTurn);
+    }
+}
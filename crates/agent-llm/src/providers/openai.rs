@@ -80,18 +80,22 @@
 //! # }
 //! ```
 
+use super::sse::{self, SseDecoder};
 use crate::{
-    CompletionRequest, CompletionResponse, ContentBlock, ImageSource, LLMProvider, Message,
-    MessageContent, Result, Role, StopReason, TokenUsage, ToolDefinition,
+    CompletionRequest, CompletionResponse, ContentBlock, EmbeddingProvider, GenerationParam,
+    ImageSource, LLMProvider, Message, MessageContent, Result, Role, StopReason, TokenUsage,
+    ToolChoice, ToolDefinition,
 };
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
 use tracing::{debug, instrument};
 
 const DEFAULT_OPENAI_API_BASE: &str = "https://api.openai.com/v1";
 const DEFAULT_TIMEOUT_SECS: u64 = 120;
+const DEFAULT_EMBEDDING_MODEL: &str = "text-embedding-3-small";
 
 /// Configuration for OpenAI provider
 #[derive(Debug, Clone)]
@@ -109,6 +113,9 @@ pub struct OpenAIConfig {
     /// Optional list of supported models
     /// If None, any model string is accepted
     pub supported_models: Option<Vec<String>>,
+
+    /// Model used by [`EmbeddingProvider::embed`] (default: "text-embedding-3-small")
+    pub embedding_model: String,
 }
 
 impl OpenAIConfig {
@@ -119,6 +126,7 @@ impl OpenAIConfig {
             api_base: DEFAULT_OPENAI_API_BASE.to_string(),
             timeout_secs: DEFAULT_TIMEOUT_SECS,
             supported_models: None,
+            embedding_model: DEFAULT_EMBEDDING_MODEL.to_string(),
         }
     }
 
@@ -141,6 +149,7 @@ impl OpenAIConfig {
             api_base,
             timeout_secs: DEFAULT_TIMEOUT_SECS,
             supported_models: None,
+            embedding_model: DEFAULT_EMBEDDING_MODEL.to_string(),
         })
     }
 
@@ -170,6 +179,12 @@ impl OpenAIConfig {
         self
     }
 
+    /// Set the model used for embeddings
+    pub fn with_embedding_model(mut self, model: impl Into<String>) -> Self {
+        self.embedding_model = model.into();
+        self
+    }
+
     /// Add a single supported model
     pub fn add_supported_model(mut self, model: impl Into<String>) -> Self {
         let model = model.into();
@@ -188,6 +203,7 @@ impl Default for OpenAIConfig {
             api_base: DEFAULT_OPENAI_API_BASE.to_string(),
             timeout_secs: DEFAULT_TIMEOUT_SECS,
             supported_models: None,
+            embedding_model: DEFAULT_EMBEDDING_MODEL.to_string(),
         }
     }
 }
@@ -268,6 +284,35 @@ impl OpenAIProvider {
         &self.config
     }
 
+    /// List the model IDs served at the configured API base
+    ///
+    /// Calls `GET {api_base}/models`, which does not consume tokens, so it
+    /// doubles as a cheap check that the API key and endpoint work.
+    pub async fn list_models(&self) -> Result<Vec<String>> {
+        let response = self
+            .client
+            .get(format!("{}/models", self.config.api_base))
+            .header("Authorization", format!("Bearer {}", self.config.api_key))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await?;
+
+            return Err(match status.as_u16() {
+                401 | 403 => crate::LLMError::AuthenticationFailed,
+                429 => crate::LLMError::RateLimitExceeded(error_text),
+                _ => crate::LLMError::RequestFailed(format!("HTTP {status}: {error_text}")),
+            });
+        }
+
+        let models: ModelList = response.json().await.map_err(|e| {
+            crate::LLMError::UnexpectedResponse(format!("Failed to parse model list: {e}"))
+        })?;
+        Ok(models.data.into_iter().map(|m| m.id).collect())
+    }
+
     /// Validate model name against supported models list (if configured)
     fn validate_model(&self, model: &str) -> Result<()> {
         if let Some(supported) = &self.config.supported_models {
@@ -279,40 +324,55 @@ impl OpenAIProvider {
         }
         Ok(())
     }
-}
-
-#[async_trait]
-impl LLMProvider for OpenAIProvider {
-    #[instrument(skip(self, request), fields(model = %request.model, api_base = %self.config.api_base))]
-    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse> {
-        debug!("Sending request to OpenAI API at {}", self.config.api_base);
 
-        // Validate model if configured
+    /// Validate a request and convert it to OpenAI's format
+    fn build_request(&self, request: CompletionRequest, stream: bool) -> Result<OpenAIRequest> {
+        // Validate model and parameters
         self.validate_model(&request.model)?;
+        request.check_supported(self.name(), self.supported_params())?;
 
         // Convert messages (system prompt goes into messages array for OpenAI)
-        let openai_messages = build_openai_messages(request.system.clone(), request.messages);
+        let openai_messages = build_openai_messages(request.system, request.messages);
 
         // Convert tools if present
         let openai_tools = request.tools.as_ref().map(|tools| convert_tools(tools));
+        // OpenAI rejects tool_choice without tools
+        let tool_choice = request
+            .tool_choice
+            .as_ref()
+            .filter(|_| openai_tools.is_some())
+            .map(convert_tool_choice);
+        let parallel_tool_calls = request
+            .parallel_tool_calls
+            .filter(|_| openai_tools.is_some());
 
         // Build OpenAI-specific request
-        let openai_request = OpenAIRequest {
-            model: request.model.clone(),
+        Ok(OpenAIRequest {
+            model: request.model,
             messages: openai_messages,
             max_tokens: request.max_tokens,
             temperature: request.temperature,
+            top_p: request.top_p,
+            frequency_penalty: request.frequency_penalty,
+            presence_penalty: request.presence_penalty,
             tools: openai_tools,
+            tool_choice,
+            parallel_tool_calls,
             stop: request.stop_sequences,
-        };
+            stream,
+            // Usage is only reported at the end of a stream when asked for
+            stream_options: stream.then(|| serde_json::json!({ "include_usage": true })),
+        })
+    }
 
-        // Send request
+    /// Send a chat completion request, mapping HTTP errors
+    async fn send(&self, openai_request: &OpenAIRequest) -> Result<reqwest::Response> {
         let response = self
             .client
             .post(format!("{}/chat/completions", self.config.api_base))
             .header("Authorization", format!("Bearer {}", self.config.api_key))
             .header("Content-Type", "application/json")
-            .json(&openai_request)
+            .json(openai_request)
             .send()
             .await?;
 
@@ -325,11 +385,23 @@ impl LLMProvider for OpenAIProvider {
                 401 => crate::LLMError::AuthenticationFailed,
                 429 => crate::LLMError::RateLimitExceeded(error_text),
                 400 => crate::LLMError::InvalidRequest(error_text),
-                404 => crate::LLMError::ModelNotFound(request.model),
+                404 => crate::LLMError::ModelNotFound(openai_request.model.clone()),
                 _ => crate::LLMError::RequestFailed(format!("HTTP {status}: {error_text}")),
             });
         }
 
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl LLMProvider for OpenAIProvider {
+    #[instrument(skip(self, request), fields(model = %request.model, api_base = %self.config.api_base))]
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse> {
+        debug!("Sending request to OpenAI API at {}", self.config.api_base);
+
+        let response = self.send(&self.build_request(request, false)?).await?;
+
         // Parse response
         let openai_response: OpenAIResponse = response.json().await.map_err(|e| {
             crate::LLMError::UnexpectedResponse(format!("Failed to parse response: {e}"))
@@ -364,15 +436,133 @@ impl LLMProvider for OpenAIProvider {
         })
     }
 
+    #[instrument(skip(self, request, blocks), fields(model = %request.model, api_base = %self.config.api_base))]
+    async fn complete_streaming(
+        &self,
+        request: CompletionRequest,
+        blocks: &UnboundedSender<ContentBlock>,
+    ) -> Result<CompletionResponse> {
+        debug!(
+            "Streaming request to OpenAI API at {}",
+            self.config.api_base
+        );
+
+        let mut response = self.send(&self.build_request(request, true)?).await?;
+
+        let mut decoder = SseDecoder::default();
+        let mut stream = ChatStream::default();
+        'read: while let Some(events) = sse::next_events(&mut response, &mut decoder).await? {
+            for event in events {
+                if event.data == "[DONE]" {
+                    break 'read;
+                }
+                for block in stream.apply(&event.data)? {
+                    debug!("Streamed block complete");
+                    // Sending only fails when the caller stopped listening
+                    let _ = blocks.send(block);
+                }
+            }
+        }
+
+        stream.finish()
+    }
+
     fn name(&self) -> &'static str {
         "openai"
     }
+
+    fn supported_params(&self) -> &'static [GenerationParam] {
+        &[
+            GenerationParam::TopP,
+            GenerationParam::FrequencyPenalty,
+            GenerationParam::PresencePenalty,
+        ]
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for OpenAIProvider {
+    #[instrument(skip(self, texts), fields(model = %self.config.embedding_model, count = texts.len()))]
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let response = self
+            .client
+            .post(format!("{}/embeddings", self.config.api_base))
+            .header("Authorization", format!("Bearer {}", self.config.api_key))
+            .json(&EmbeddingRequest {
+                model: &self.config.embedding_model,
+                input: texts,
+            })
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await?;
+
+            return Err(match status.as_u16() {
+                401 => crate::LLMError::AuthenticationFailed,
+                429 => crate::LLMError::RateLimitExceeded(error_text),
+                400 => crate::LLMError::InvalidRequest(error_text),
+                404 => crate::LLMError::ModelNotFound(self.config.embedding_model.clone()),
+                _ => crate::LLMError::RequestFailed(format!("HTTP {status}: {error_text}")),
+            });
+        }
+
+        let list: EmbeddingList = response.json().await.map_err(|e| {
+            crate::LLMError::UnexpectedResponse(format!("Failed to parse embeddings: {e}"))
+        })?;
+        order_embeddings(list, texts.len())
+    }
+}
+
+/// Put embeddings back in input order, checking one came back per input
+fn order_embeddings(mut list: EmbeddingList, expected: usize) -> Result<Vec<Vec<f32>>> {
+    if list.data.len() != expected {
+        return Err(crate::LLMError::UnexpectedResponse(format!(
+            "Expected {expected} embeddings, got {}",
+            list.data.len()
+        )));
+    }
+    list.data.sort_by_key(|entry| entry.index);
+    Ok(list.data.into_iter().map(|entry| entry.embedding).collect())
 }
 
 // ============================================================================
 // OpenAI-specific request types
 // ============================================================================
 
+/// Response of `GET /models`
+#[derive(Debug, Deserialize)]
+struct ModelList {
+    data: Vec<ModelEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelEntry {
+    id: String,
+}
+
+#[derive(Debug, Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingList {
+    data: Vec<EmbeddingEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingEntry {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
 #[derive(Debug, Serialize)]
 struct OpenAIRequest {
     model: String,
@@ -381,9 +571,23 @@ struct OpenAIRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<OpenAITool>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parallel_tool_calls: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     stop: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Serialize)]
@@ -494,6 +698,185 @@ struct OpenAIUsage {
     completion_tokens: usize,
 }
 
+// ============================================================================
+// OpenAI streaming types
+// ============================================================================
+
+/// One `chat.completion.chunk` of a streamed response
+#[derive(Debug, Deserialize)]
+struct OpenAIStreamChunk {
+    #[serde(default)]
+    choices: Vec<OpenAIStreamChoice>,
+    /// Only set on the final chunk, and only with `include_usage`
+    usage: Option<OpenAIUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIStreamChoice {
+    #[serde(default)]
+    delta: OpenAIStreamDelta,
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct OpenAIStreamDelta {
+    content: Option<String>,
+    tool_calls: Option<Vec<OpenAIStreamToolCall>>,
+}
+
+/// A fragment of a tool call; the id and name come with the first one and
+/// the arguments are spread over the rest
+#[derive(Debug, Deserialize)]
+struct OpenAIStreamToolCall {
+    index: usize,
+    id: Option<String>,
+    function: Option<OpenAIStreamFunctionCall>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIStreamFunctionCall {
+    name: Option<String>,
+    arguments: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct PartialToolCall {
+    id: String,
+    name: String,
+    arguments: String,
+}
+
+/// Assembles a response from its stream of chunks
+///
+/// Text comes before the tool calls, so it is complete once the first call
+/// starts or the choice finishes. Tool calls stream one after another, so a
+/// call is complete once the next one starts or the choice finishes.
+#[derive(Debug, Default)]
+struct ChatStream {
+    text: String,
+    /// Whether the text was already handed over as a block
+    text_sent: bool,
+    tool_calls: Vec<PartialToolCall>,
+    /// Tool calls already handed over as blocks
+    completed: usize,
+    finish_reason: Option<String>,
+    usage: Option<OpenAIUsage>,
+}
+
+impl ChatStream {
+    /// Apply one chunk, returning the text and tool calls it completes
+    fn apply(&mut self, data: &str) -> Result<Vec<ContentBlock>> {
+        let chunk: OpenAIStreamChunk = serde_json::from_str(data).map_err(|e| {
+            crate::LLMError::UnexpectedResponse(format!("Failed to parse stream chunk: {e}"))
+        })?;
+        if chunk.usage.is_some() {
+            self.usage = chunk.usage;
+        }
+
+        // Only the first choice is used, as in non-streamed responses
+        let Some(choice) = chunk.choices.into_iter().next() else {
+            return Ok(Vec::new());
+        };
+        if let Some(content) = choice.delta.content {
+            self.text.push_str(&content);
+        }
+        for fragment in choice.delta.tool_calls.unwrap_or_default() {
+            if fragment.index >= self.tool_calls.len() {
+                self.tool_calls
+                    .resize_with(fragment.index + 1, PartialToolCall::default);
+            }
+            let call = &mut self.tool_calls[fragment.index];
+            if let Some(id) = fragment.id {
+                call.id = id;
+            }
+            if let Some(function) = fragment.function {
+                call.name.push_str(&function.name.unwrap_or_default());
+                call.arguments
+                    .push_str(&function.arguments.unwrap_or_default());
+            }
+        }
+
+        let mut blocks = Vec::new();
+        if !self.tool_calls.is_empty() || choice.finish_reason.is_some() {
+            blocks.extend(self.take_text());
+        }
+
+        let ready = if choice.finish_reason.is_some() {
+            self.tool_calls.len()
+        } else {
+            // The last call may still be receiving arguments
+            self.tool_calls.len().saturating_sub(1)
+        };
+        if choice.finish_reason.is_some() {
+            self.finish_reason = choice.finish_reason;
+        }
+
+        for call in &self.tool_calls[self.completed.min(ready)..ready] {
+            blocks.push(tool_use_block(
+                call.id.clone(),
+                call.name.clone(),
+                &call.arguments,
+            )?);
+        }
+        self.completed = self.completed.max(ready);
+        Ok(blocks)
+    }
+
+    /// The text as a block, unless it is empty or was already handed over
+    fn take_text(&mut self) -> Option<ContentBlock> {
+        if self.text_sent || self.text.is_empty() {
+            return None;
+        }
+        self.text_sent = true;
+        Some(ContentBlock::Text {
+            text: self.text.clone(),
+        })
+    }
+
+    /// The complete response, once the stream has ended
+    fn finish(self) -> Result<CompletionResponse> {
+        let finish_reason = self.finish_reason.ok_or_else(|| {
+            crate::LLMError::UnexpectedResponse("Stream ended without a finish reason".to_string())
+        })?;
+        // Some OpenAI-compatible servers do not report usage when streaming
+        let usage = self
+            .usage
+            .map_or_else(TokenUsage::default, |usage| TokenUsage {
+                input_tokens: usage.prompt_tokens,
+                output_tokens: usage.completion_tokens,
+            });
+
+        debug!(
+            "Received streamed response - stop_reason: {}, tokens: {}/{}",
+            finish_reason, usage.input_tokens, usage.output_tokens
+        );
+
+        let message = parse_openai_response(OpenAIResponseMessage {
+            role: "assistant".to_string(),
+            content: Some(self.text),
+            tool_calls: Some(
+                self.tool_calls
+                    .into_iter()
+                    .map(|call| OpenAIResponseToolCall {
+                        id: call.id,
+                        tool_type: "function".to_string(),
+                        function: OpenAIResponseFunctionCall {
+                            name: call.name,
+                            arguments: call.arguments,
+                        },
+                    })
+                    .collect(),
+            ),
+        })?;
+
+        Ok(CompletionResponse {
+            message,
+            stop_reason: map_stop_reason(&finish_reason),
+            usage,
+        })
+    }
+}
+
 // ============================================================================
 // Conversion functions
 // ============================================================================
@@ -658,6 +1041,19 @@ fn convert_tools(tools: &[ToolDefinition]) -> Vec<OpenAITool> {
         .collect()
 }
 
+/// Convert a tool choice to OpenAI's `tool_choice`: `"auto"`, `"none"`,
+/// `"required"` or `{"type": "function", "function": {"name": ...}}`
+fn convert_tool_choice(choice: &ToolChoice) -> serde_json::Value {
+    match choice {
+        ToolChoice::Auto => serde_json::json!("auto"),
+        ToolChoice::None => serde_json::json!("none"),
+        ToolChoice::Required => serde_json::json!("required"),
+        ToolChoice::Tool { name } => {
+            serde_json::json!({ "type": "function", "function": { "name": name } })
+        }
+    }
+}
+
 /// Parse OpenAI response message to our format
 fn parse_openai_response(msg: OpenAIResponseMessage) -> Result<Message> {
     let mut blocks = Vec::new();
@@ -672,19 +1068,11 @@ fn parse_openai_response(msg: OpenAIResponseMessage) -> Result<Message> {
     // Parse tool calls
     if let Some(tool_calls) = msg.tool_calls {
         for call in tool_calls {
-            // Parse arguments from JSON string
-            let input: serde_json::Value =
-                serde_json::from_str(&call.function.arguments).map_err(|e| {
-                    crate::LLMError::UnexpectedResponse(format!(
-                        "Failed to parse tool arguments: {e}"
-                    ))
-                })?;
-
-            blocks.push(ContentBlock::ToolUse {
-                id: call.id,
-                name: call.function.name,
-                input,
-            });
+            blocks.push(tool_use_block(
+                call.id,
+                call.function.name,
+                &call.function.arguments,
+            )?);
         }
     }
 
@@ -701,6 +1089,15 @@ fn parse_openai_response(msg: OpenAIResponseMessage) -> Result<Message> {
     })
 }
 
+/// Build a tool use block, parsing the arguments from their JSON string
+fn tool_use_block(id: String, name: String, arguments: &str) -> Result<ContentBlock> {
+    let input: serde_json::Value = serde_json::from_str(arguments).map_err(|e| {
+        crate::LLMError::UnexpectedResponse(format!("Failed to parse tool arguments: {e}"))
+    })?;
+
+    Ok(ContentBlock::ToolUse { id, name, input })
+}
+
 /// Map OpenAI stop reason to our format
 fn map_stop_reason(reason: &str) -> StopReason {
     match reason {
@@ -737,6 +1134,24 @@ mod tests {
         assert_eq!(provider.config().api_base, "https://api.openai.com/v1");
     }
 
+    #[test]
+    fn test_embeddings_ordered_by_index() {
+        let list: EmbeddingList = serde_json::from_value(json!({
+            "object": "list",
+            "data": [
+                { "object": "embedding", "index": 1, "embedding": [0.0, 1.0] },
+                { "object": "embedding", "index": 0, "embedding": [1.0, 0.0] }
+            ],
+            "model": "text-embedding-3-small"
+        }))
+        .unwrap();
+        let ordered = order_embeddings(list, 2).unwrap();
+        assert_eq!(ordered, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+
+        let list: EmbeddingList = serde_json::from_value(json!({ "data": [] })).unwrap();
+        assert!(order_embeddings(list, 1).is_err());
+    }
+
     #[test]
     fn test_provider_with_custom_config() {
         let config = OpenAIConfig::new("test-key")
@@ -782,6 +1197,24 @@ mod tests {
         assert!(matches!(result, Err(crate::LLMError::InvalidRequest(_))));
     }
 
+    #[test]
+    fn test_generation_param_support() {
+        let provider = OpenAIProvider::new("test-key").unwrap();
+        let request = CompletionRequest::builder("gpt-4")
+            .top_p(0.9)
+            .presence_penalty(0.6)
+            .build();
+        assert!(
+            request
+                .check_supported(provider.name(), provider.supported_params())
+                .is_ok()
+        );
+
+        let request = CompletionRequest::builder("gpt-4").top_k(40).build();
+        let result = request.check_supported(provider.name(), provider.supported_params());
+        assert!(matches!(result, Err(crate::LLMError::InvalidRequest(_))));
+    }
+
     #[test]
     fn test_no_model_validation_when_not_configured() {
         let provider = OpenAIProvider::new("test-key").unwrap();
@@ -863,6 +1296,98 @@ mod tests {
         assert_eq!(openai_tools[0].function.description, "Search the web");
     }
 
+    #[test]
+    fn test_tool_choice_conversion() {
+        assert_eq!(convert_tool_choice(&ToolChoice::Auto), json!("auto"));
+        assert_eq!(
+            convert_tool_choice(&ToolChoice::Required),
+            json!("required")
+        );
+        assert_eq!(
+            convert_tool_choice(&ToolChoice::tool("search")),
+            json!({ "type": "function", "function": { "name": "search" } })
+        );
+    }
+
+    #[test]
+    fn test_parallel_tool_calls_serialization() {
+        let request = OpenAIRequest {
+            model: "gpt-4".to_string(),
+            messages: Vec::new(),
+            max_tokens: 100,
+            temperature: None,
+            top_p: None,
+            frequency_penalty: None,
+            presence_penalty: None,
+            tools: Some(Vec::new()),
+            tool_choice: None,
+            parallel_tool_calls: Some(false),
+            stop: None,
+            stream: false,
+            stream_options: None,
+        };
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["parallel_tool_calls"], json!(false));
+        assert!(json.get("tool_choice").is_none());
+    }
+
+    #[test]
+    fn test_chat_stream() {
+        let chunks = [
+            r#"{"choices":[{"index":0,"delta":{"role":"assistant","content":null},"finish_reason":null}]}"#,
+            r#"{"choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"id":"call_1","type":"function","function":{"name":"price","arguments":""}}]},"finish_reason":null}]}"#,
+            r#"{"choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"{\"symbol\":"}}]},"finish_reason":null}]}"#,
+            r#"{"choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"\"AAPL\"}"}}]},"finish_reason":null}]}"#,
+            r#"{"choices":[{"index":0,"delta":{"tool_calls":[{"index":1,"id":"call_2","type":"function","function":{"name":"price","arguments":"{\"symbol\":\"MSFT\"}"}}]},"finish_reason":null}]}"#,
+            r#"{"choices":[{"index":0,"delta":{},"finish_reason":"tool_calls"}]}"#,
+            r#"{"choices":[],"usage":{"prompt_tokens":30,"completion_tokens":12,"total_tokens":42}}"#,
+        ];
+
+        let mut stream = ChatStream::default();
+        let completed: Vec<Vec<ContentBlock>> = chunks
+            .iter()
+            .map(|chunk| stream.apply(chunk).unwrap())
+            .collect();
+
+        // The first call is handed over when the second starts
+        assert!(completed[..4].iter().all(Vec::is_empty));
+        assert!(matches!(
+            completed[4].as_slice(),
+            [ContentBlock::ToolUse { id, input, .. }]
+                if id == "call_1" && input == &json!({"symbol": "AAPL"})
+        ));
+        assert!(matches!(
+            completed[5].as_slice(),
+            [ContentBlock::ToolUse { id, .. }] if id == "call_2"
+        ));
+
+        let response = stream.finish().unwrap();
+        assert_eq!(response.stop_reason, StopReason::ToolUse);
+        assert_eq!(response.usage.input_tokens, 30);
+        assert_eq!(response.usage.output_tokens, 12);
+        assert_eq!(response.message.tool_uses().len(), 2);
+    }
+
+    #[test]
+    fn test_chat_stream_text() {
+        let mut stream = ChatStream::default();
+        let first = r#"{"choices":[{"index":0,"delta":{"content":"Hello"},"finish_reason":null}]}"#;
+        assert!(stream.apply(first).unwrap().is_empty());
+
+        // The text is handed over once the choice finishes
+        let last =
+            r#"{"choices":[{"index":0,"delta":{"content":" there"},"finish_reason":"stop"}]}"#;
+        assert!(matches!(
+            stream.apply(last).unwrap().as_slice(),
+            [ContentBlock::Text { text }] if text == "Hello there"
+        ));
+
+        let response = stream.finish().unwrap();
+        assert_eq!(response.stop_reason, StopReason::EndTurn);
+        assert_eq!(response.message.text(), Some("Hello there"));
+        assert_eq!(response.usage.total(), 0);
+    }
+
     #[test]
     fn test_stop_reason_mapping() {
         assert_eq!(map_stop_reason("stop"), StopReason::EndTurn);
@@ -0,0 +1,106 @@
+//! Server-sent events decoding for streamed completions
+//!
+//! Both Anthropic and OpenAI stream completions as `text/event-stream`
+//! bodies. [`SseDecoder`] turns the raw body chunks, which may split lines
+//! and even UTF-8 characters anywhere, into whole events.
+//! See: https://html.spec.whatwg.org/multipage/server-sent-events.html
+
+use crate::Result;
+
+/// One server-sent event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SseEvent {
+    /// Value of the `event:` field, if any
+    pub event: Option<String>,
+    /// The `data:` lines, joined with newlines
+    pub data: String,
+}
+
+/// Incremental decoder for a `text/event-stream` body
+#[derive(Debug, Default)]
+pub(crate) struct SseDecoder {
+    buffer: Vec<u8>,
+    event: Option<String>,
+    data: Vec<String>,
+}
+
+impl SseDecoder {
+    /// Feed a chunk of the body, returning the events it completes
+    pub(crate) fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+
+            if line.is_empty() {
+                // A blank line dispatches the event
+                let event = self.event.take();
+                if !self.data.is_empty() {
+                    events.push(SseEvent {
+                        event,
+                        data: std::mem::take(&mut self.data).join("\n"),
+                    });
+                }
+                continue;
+            }
+
+            let (field, value) = line.split_once(':').unwrap_or((line, ""));
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match field {
+                "event" => self.event = Some(value.to_string()),
+                "data" => self.data.push(value.to_string()),
+                // Comments (": ping") and fields we have no use for
+                _ => {}
+            }
+        }
+        events
+    }
+}
+
+/// Read the next events from a streaming response, or `None` at its end
+pub(crate) async fn next_events(
+    response: &mut reqwest::Response,
+    decoder: &mut SseDecoder,
+) -> Result<Option<Vec<SseEvent>>> {
+    Ok(response.chunk().await?.map(|chunk| decoder.push(&chunk)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_split_across_chunks() {
+        let mut decoder = SseDecoder::default();
+        assert!(decoder.push(b"event: ping\ndata: {}\n").is_empty());
+
+        let events = decoder.push(b"\n: comment\ndata: {\"a\":\ndata: 1}\r\n\r\ndata: [DO");
+        assert_eq!(
+            events,
+            vec![
+                SseEvent {
+                    event: Some("ping".to_string()),
+                    data: "{}".to_string(),
+                },
+                SseEvent {
+                    event: None,
+                    data: "{\"a\":\n1}".to_string(),
+                },
+            ]
+        );
+
+        let events = decoder.push(b"NE]\n\n");
+        assert_eq!(events[0].data, "[DONE]");
+    }
+
+    #[test]
+    fn test_split_utf8_character() {
+        let mut decoder = SseDecoder::default();
+        let text = "data: 比特币\n\n".as_bytes();
+        assert!(decoder.push(&text[..8]).is_empty());
+        assert_eq!(decoder.push(&text[8..])[0].data, "比特币");
+    }
+}
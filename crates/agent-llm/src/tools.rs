@@ -34,6 +34,34 @@ impl ToolDefinition {
     }
 }
 
+/// Whether and which tools the LLM may call in a completion
+///
+/// Providers map this to their own `tool_choice` parameter. It only applies
+/// when the request carries tools; requests without tools never call any.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ToolChoice {
+    /// The model decides whether to call tools (provider default)
+    #[default]
+    Auto,
+    /// The model must not call tools, e.g. for a final synthesis turn
+    None,
+    /// The model must call at least one tool
+    Required,
+    /// The model must call the named tool, e.g. for structured extraction
+    Tool {
+        /// Tool name (must match one of the request's tools)
+        name: String,
+    },
+}
+
+impl ToolChoice {
+    /// Force a call to the named tool
+    pub fn tool(name: impl Into<String>) -> Self {
+        Self::Tool { name: name.into() }
+    }
+}
+
 /// Helper module to build JSON schemas for tools
 pub mod schema {
     use serde_json::{Value, json};
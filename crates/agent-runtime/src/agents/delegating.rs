@@ -1,9 +1,9 @@
 //! Delegating agent implementation (routes to sub-agents)
 
+use crate::registry::AgentRegistry;
 use crate::runtime::AgentRuntime;
-use agent_core::{Agent, Context, Error, Result};
+use agent_core::{Agent, AgentCapabilities, Context, Error, Result};
 use async_trait::async_trait;
-use std::collections::HashMap;
 use std::sync::Arc;
 
 /// Router function type for delegating agents
@@ -17,6 +17,14 @@ type RouterFn = Box<dyn Fn(&str, &Context) -> String + Send + Sync>;
 /// - Specialized task routing
 /// - Dynamic agent selection
 ///
+/// Delegating agents may be nested. Each one records its name in the
+/// context's delegation path on entry, so a request that would reach the
+/// same delegating agent twice fails with the cycle spelled out
+/// (`manager → research → manager`), and nesting deeper than
+/// [`RuntimeConfig::max_delegation_depth`] fails instead of recursing.
+///
+/// [`RuntimeConfig::max_delegation_depth`]: crate::RuntimeConfig::max_delegation_depth
+///
 /// # Example
 ///
 /// ```no_run
@@ -47,9 +55,8 @@ type RouterFn = Box<dyn Fn(&str, &Context) -> String + Send + Sync>;
 /// # }
 /// ```
 pub struct DelegatingAgent {
-    #[allow(dead_code)]
     runtime: Arc<AgentRuntime>,
-    sub_agents: HashMap<String, Arc<dyn Agent>>,
+    sub_agents: AgentRegistry,
     router: RouterFn,
     name: String,
 }
@@ -72,13 +79,69 @@ impl DelegatingAgent {
 
     /// Get the list of available agent names
     pub fn agent_names(&self) -> Vec<&str> {
-        self.sub_agents.keys().map(std::string::String::as_str).collect()
+        self.sub_agents.keys()
+    }
+
+    /// Get the sub-agents, for introspecting their capabilities
+    pub fn registry(&self) -> &AgentRegistry {
+        &self.sub_agents
+    }
+
+    /// Add this agent to the context's delegation path
+    ///
+    /// Returns the previous path so it can be restored once the sub-agent
+    /// returns.
+    fn enter(&self, context: &mut Context) -> Result<Vec<String>> {
+        let outer = context.delegation_path();
+        let mut path = outer.clone();
+        path.push(self.name.clone());
+
+        if outer.contains(&self.name) {
+            return Err(Error::ProcessingFailed(format!(
+                "Delegation cycle detected: {}",
+                path.join(" → ")
+            )));
+        }
+
+        let max_depth = self.runtime.config().max_delegation_depth;
+        if path.len() > max_depth {
+            return Err(Error::ProcessingFailed(format!(
+                "Delegation depth limit of {max_depth} exceeded: {}",
+                path.join(" → ")
+            )));
+        }
+
+        context.set_delegation_path(path);
+        Ok(outer)
     }
 }
 
 #[async_trait]
 impl Agent for DelegatingAgent {
     async fn process(&self, input: String, context: &mut Context) -> Result<String> {
+        let outer = self.enter(context)?;
+        let result = self.delegate(input, context).await;
+        context.set_delegation_path(outer);
+        result
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The union of the sub-agents' capabilities
+    fn capabilities(&self) -> AgentCapabilities {
+        let mut capabilities =
+            AgentCapabilities::new(format!("Delegates to {}", self.agent_names().join(", ")));
+        for (_, sub_agent) in self.sub_agents.capabilities() {
+            capabilities.merge(sub_agent);
+        }
+        capabilities
+    }
+}
+
+impl DelegatingAgent {
+    async fn delegate(&self, input: String, context: &mut Context) -> Result<String> {
         // Use router to determine which agent to delegate to
         let agent_name = (self.router)(&input, context);
 
@@ -94,16 +157,12 @@ impl Agent for DelegatingAgent {
         // Delegate to the selected agent
         agent.process(input, context).await
     }
-
-    fn name(&self) -> &str {
-        &self.name
-    }
 }
 
 /// Builder for DelegatingAgent
 pub struct DelegatingAgentBuilder {
     runtime: Arc<AgentRuntime>,
-    sub_agents: HashMap<String, Arc<dyn Agent>>,
+    sub_agents: AgentRegistry,
     router: Option<RouterFn>,
     name: String,
 }
@@ -113,7 +172,7 @@ impl DelegatingAgentBuilder {
     pub fn new(runtime: Arc<AgentRuntime>, name: impl Into<String>) -> Self {
         Self {
             runtime,
-            sub_agents: HashMap::new(),
+            sub_agents: AgentRegistry::new(),
             router: None,
             name: name.into(),
         }
@@ -126,7 +185,17 @@ impl DelegatingAgentBuilder {
     /// * `key` - The key to identify this agent in routing
     /// * `agent` - The agent to add
     pub fn add_agent(mut self, key: impl Into<String>, agent: Arc<dyn Agent>) -> Self {
-        self.sub_agents.insert(key.into(), agent);
+        self.sub_agents.register_as(key, agent);
+        self
+    }
+
+    /// Add every agent in a registry, under its registry key
+    pub fn add_registry(mut self, registry: &AgentRegistry) -> Self {
+        for key in registry.keys() {
+            if let Some(agent) = registry.get(key) {
+                self.sub_agents.register_as(key, Arc::clone(agent));
+            }
+        }
         self
     }
 
@@ -183,4 +252,105 @@ mod tests {
         let runtime = Arc::new(AgentRuntime::builder());
         // Note: This would require mock agents to fully test
     }
+
+    struct EchoAgent;
+
+    #[async_trait]
+    impl Agent for EchoAgent {
+        async fn process(&self, input: String, _context: &mut Context) -> Result<String> {
+            Ok(input)
+        }
+
+        fn name(&self) -> &str {
+            "echo"
+        }
+    }
+
+    fn test_runtime(max_delegation_depth: usize) -> Arc<AgentRuntime> {
+        use agent_llm::{CompletionRequest, CompletionResponse, LLMProvider};
+
+        struct MockProvider;
+        #[async_trait]
+        impl LLMProvider for MockProvider {
+            async fn complete(
+                &self,
+                _request: CompletionRequest,
+            ) -> agent_llm::Result<CompletionResponse> {
+                unimplemented!()
+            }
+            fn name(&self) -> &str {
+                "mock"
+            }
+        }
+
+        Arc::new(
+            AgentRuntime::builder()
+                .provider(Arc::new(MockProvider))
+                .max_delegation_depth(max_delegation_depth)
+                .build()
+                .unwrap(),
+        )
+    }
+
+    /// Delegating agent forwarding everything to `next`
+    fn forward(runtime: &Arc<AgentRuntime>, name: &str, next: Arc<dyn Agent>) -> Arc<dyn Agent> {
+        Arc::new(
+            DelegatingAgent::builder(runtime.clone(), name)
+                .add_agent("next", next)
+                .router(|_, _| "next".to_string())
+                .build()
+                .unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_nested_delegation_restores_path() {
+        let runtime = test_runtime(5);
+        let inner = forward(&runtime, "research", Arc::new(EchoAgent));
+        let outer = forward(&runtime, "manager", inner);
+
+        let mut context = Context::new();
+        let response = outer.process("hi".to_string(), &mut context).await.unwrap();
+        assert_eq!(response, "hi");
+        assert_eq!(context.delegation_depth(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_delegation_cycle_detected() {
+        let runtime = test_runtime(5);
+        let again = forward(&runtime, "manager", Arc::new(EchoAgent));
+        let research = forward(&runtime, "research", again);
+        let manager = forward(&runtime, "manager", research);
+
+        let mut context = Context::new();
+        let err = manager
+            .process("hi".to_string(), &mut context)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("manager → research → manager"),
+            "{err}"
+        );
+        assert_eq!(context.delegation_depth(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_delegation_depth_limit() {
+        let runtime = test_runtime(2);
+        let c = forward(&runtime, "c", Arc::new(EchoAgent));
+        let b = forward(&runtime, "b", c);
+        let a = forward(&runtime, "a", b.clone());
+
+        let mut context = Context::new();
+        assert_eq!(
+            b.process("hi".to_string(), &mut context).await.unwrap(),
+            "hi"
+        );
+
+        let err = a.process("hi".to_string(), &mut context).await.unwrap_err();
+        assert!(
+            err.to_string().contains("limit of 2 exceeded: a → b → c"),
+            "{err}"
+        );
+    }
 }
@@ -5,6 +5,8 @@ use agent_llm::{CompletionRequest, LLMProvider, Message};
 use async_trait::async_trait;
 use std::sync::Arc;
 
+use crate::usage::{self, ModelPricing, UsageSummary, UsageTracker};
+
 /// Configuration for a simple agent
 #[derive(Debug, Clone)]
 pub struct SimpleConfig {
@@ -65,6 +67,7 @@ pub struct SimpleAgent {
     provider: Arc<dyn LLMProvider>,
     config: SimpleConfig,
     name: String,
+    token_tracker: Option<Arc<UsageTracker>>,
 }
 
 impl SimpleAgent {
@@ -80,9 +83,16 @@ impl SimpleAgent {
             provider,
             config,
             name,
+            token_tracker: None,
         }
     }
 
+    /// Set the tracker that adds up the tokens of every LLM request
+    pub fn with_token_tracker(mut self, tracker: Arc<UsageTracker>) -> Self {
+        self.token_tracker = Some(tracker);
+        self
+    }
+
     /// Get the agent's configuration
     pub fn config(&self) -> &SimpleConfig {
         &self.config
@@ -91,7 +101,7 @@ impl SimpleAgent {
 
 #[async_trait]
 impl Agent for SimpleAgent {
-    async fn process(&self, input: String, _context: &mut Context) -> Result<String> {
+    async fn process(&self, input: String, context: &mut Context) -> Result<String> {
         // Build completion request
         let request = CompletionRequest::builder(&self.config.model)
             .messages(vec![Message::user(input)])
@@ -107,6 +117,19 @@ impl Agent for SimpleAgent {
             .await
             .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))?;
 
+        // Account for the tokens in the tracker and the request's context
+        let provider = self.provider.name();
+        let pricing = match &self.token_tracker {
+            Some(tracker) => {
+                tracker.record(provider, &self.config.model, &response.usage);
+                tracker.pricing_for(provider, &self.config.model)
+            }
+            None => ModelPricing::for_model(provider, &self.config.model),
+        };
+        let mut summary = UsageSummary::default();
+        summary.record(provider, &self.config.model, 1, response.usage, pricing);
+        usage::add_to_context(context, &summary);
+
         // Extract text from response
         Ok(response.message.text().unwrap_or("No response").to_string())
     }
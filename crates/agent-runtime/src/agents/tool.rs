@@ -1,8 +1,10 @@
 //! Tool agent implementation (wraps AgentExecutor)
 
-use crate::executor::AgentExecutor;
+use crate::executor::{AgentExecutor, PINNED_PARAMS_CONTEXT_KEY, RunResult, with_pinned_params};
+use crate::usage;
 use agent_core::{Agent, Context, Result};
 use async_trait::async_trait;
+use tracing::info;
 
 /// An agent that uses the LLM loop with tool execution
 ///
@@ -69,13 +71,39 @@ impl ToolAgent {
     pub fn executor(&self) -> &AgentExecutor {
         &self.executor
     }
+
+    /// Run the agent, returning the answer with its usage metadata
+    pub async fn run(&self, input: String) -> Result<RunResult> {
+        let result = self.executor.run(input).await?;
+        info!(
+            agent = %self.name,
+            iterations = result.iterations,
+            input_tokens = result.usage.input_tokens,
+            output_tokens = result.usage.output_tokens,
+            tool_calls = result.tool_calls.len(),
+            stop_reason = ?result.stop_reason,
+            "Agent run finished"
+        );
+        Ok(result)
+    }
 }
 
 #[async_trait]
 impl Agent for ToolAgent {
-    async fn process(&self, input: String, _context: &mut Context) -> Result<String> {
-        // Delegate to the executor's run method
-        self.executor.run(input).await
+    /// Run the agent, adding the run's token usage to the context
+    ///
+    /// An object under [`PINNED_PARAMS_CONTEXT_KEY`] is pinned on every tool
+    /// call of the run (see [`with_pinned_params`]).
+    async fn process(&self, input: String, context: &mut Context) -> Result<String> {
+        let pinned = context
+            .get(PINNED_PARAMS_CONTEXT_KEY)
+            .and_then(serde_json::Value::as_object);
+        let result = match pinned {
+            Some(pinned) => with_pinned_params(pinned.clone(), self.run(input)).await?,
+            None => self.run(input).await?,
+        };
+        usage::add_to_context(context, &self.executor.usage_summary(&result));
+        Ok(result.into())
     }
 
     fn name(&self) -> &str {
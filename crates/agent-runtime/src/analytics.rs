@@ -0,0 +1,414 @@
+//! Tool usage analytics
+//!
+//! The [`ToolUsageTracker`] records how often the LLM calls each tool, how
+//! long the calls take, and whether the tool's output actually shows up in the
+//! final answer. The collected statistics can be turned into a report, into
+//! "prefer X" hints for the system prompt, or used to trim tools that are
+//! rarely useful from the definitions sent to the LLM.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{PoisonError, RwLock};
+
+/// Minimum length of a string fragment to be matched against the answer
+const MIN_FRAGMENT_LEN: usize = 4;
+
+/// Maximum length of a string fragment to be matched against the answer
+const MAX_FRAGMENT_LEN: usize = 80;
+
+/// Aggregated usage statistics for a single tool
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ToolUsageStats {
+    /// Number of times the tool was called
+    pub calls: u64,
+
+    /// Number of calls that returned an error
+    pub errors: u64,
+
+    /// Number of successful calls whose output was reflected in the final answer
+    pub used_in_answer: u64,
+
+    /// Sum of all call latencies in milliseconds
+    pub total_latency_ms: u64,
+}
+
+impl ToolUsageStats {
+    /// Average call latency in milliseconds
+    pub fn avg_latency_ms(&self) -> f64 {
+        if self.calls == 0 {
+            0.0
+        } else {
+            self.total_latency_ms as f64 / self.calls as f64
+        }
+    }
+
+    /// Fraction of calls whose output was used in the final answer
+    pub fn usage_rate(&self) -> f64 {
+        if self.calls == 0 {
+            0.0
+        } else {
+            self.used_in_answer as f64 / self.calls as f64
+        }
+    }
+
+    /// Fraction of calls that failed
+    pub fn error_rate(&self) -> f64 {
+        if self.calls == 0 {
+            0.0
+        } else {
+            self.errors as f64 / self.calls as f64
+        }
+    }
+}
+
+/// A successful tool call made during a single agent run
+#[derive(Debug, Clone)]
+pub struct ToolCallOutcome {
+    /// Tool name
+    pub name: String,
+
+    /// Input the LLM passed to the tool
+    pub input: Value,
+
+    /// Output returned by the tool
+    pub output: Value,
+}
+
+/// Thresholds controlling selection hints and tool trimming
+#[derive(Debug, Clone)]
+pub struct ToolUsagePolicy {
+    /// Minimum number of calls before a tool is judged at all
+    pub min_calls: u64,
+
+    /// Usage rate at or above which a tool is recommended
+    pub prefer_above: f64,
+
+    /// Usage rate below which a tool is flagged as rarely useful
+    pub avoid_below: f64,
+
+    /// Append selection hints to the system prompt
+    pub prompt_hints: bool,
+
+    /// Remove rarely useful tools from the definitions sent to the LLM
+    pub trim_tools: bool,
+}
+
+impl Default for ToolUsagePolicy {
+    fn default() -> Self {
+        Self {
+            min_calls: 5,
+            prefer_above: 0.6,
+            avoid_below: 0.2,
+            prompt_hints: false,
+            trim_tools: false,
+        }
+    }
+}
+
+/// Collects tool usage statistics across agent runs
+///
+/// A tracker is usually shared between all executors of a runtime via
+/// `AgentRuntime::with_usage_tracker`.
+#[derive(Debug, Default)]
+pub struct ToolUsageTracker {
+    stats: RwLock<HashMap<String, ToolUsageStats>>,
+    policy: ToolUsagePolicy,
+}
+
+impl ToolUsageTracker {
+    /// Create a tracker that only collects statistics
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a tracker with a custom policy
+    pub fn with_policy(policy: ToolUsagePolicy) -> Self {
+        Self {
+            stats: RwLock::default(),
+            policy,
+        }
+    }
+
+    /// Get the tracker's policy
+    pub fn policy(&self) -> &ToolUsagePolicy {
+        &self.policy
+    }
+
+    /// Record a single tool call
+    pub fn record_call(&self, name: &str, duration_ms: u64, success: bool) {
+        let mut stats = self.stats.write().unwrap_or_else(PoisonError::into_inner);
+        let entry = stats.entry(name.to_string()).or_default();
+        entry.calls += 1;
+        entry.total_latency_ms += duration_ms;
+        if !success {
+            entry.errors += 1;
+        }
+    }
+
+    /// Record the final answer of a run and credit the tools it drew on
+    pub fn record_answer(&self, outcomes: &[ToolCallOutcome], answer: &str) {
+        let answer = answer.to_lowercase();
+        let mut stats = self.stats.write().unwrap_or_else(PoisonError::into_inner);
+        for outcome in outcomes {
+            if output_used_in_answer(&outcome.input, &outcome.output, &answer) {
+                stats
+                    .entry(outcome.name.clone())
+                    .or_default()
+                    .used_in_answer += 1;
+            }
+        }
+    }
+
+    /// Get statistics for a single tool
+    pub fn stats(&self, name: &str) -> Option<ToolUsageStats> {
+        self.stats
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(name)
+            .cloned()
+    }
+
+    /// Build a report of all tracked tools, most called first
+    pub fn report(&self) -> ToolUsageReport {
+        let stats = self.stats.read().unwrap_or_else(PoisonError::into_inner);
+        let mut tools: Vec<(String, ToolUsageStats)> = stats
+            .iter()
+            .map(|(name, s)| (name.clone(), s.clone()))
+            .collect();
+        tools.sort_by(|a, b| b.1.calls.cmp(&a.1.calls).then_with(|| a.0.cmp(&b.0)));
+        ToolUsageReport { tools }
+    }
+
+    /// Whether a tool has enough history to be considered rarely useful
+    pub fn is_rarely_useful(&self, name: &str) -> bool {
+        self.stats(name).is_some_and(|s| {
+            s.calls >= self.policy.min_calls && s.usage_rate() < self.policy.avoid_below
+        })
+    }
+
+    /// Whether the tool should be left out of the definitions sent to the LLM
+    pub fn should_trim(&self, name: &str) -> bool {
+        self.policy.trim_tools && self.is_rarely_useful(name)
+    }
+
+    /// Tool selection hints for the system prompt, if any apply
+    pub fn selection_hints(&self) -> Option<String> {
+        let report = self.report();
+        let mut lines = Vec::new();
+
+        for (name, stats) in &report.tools {
+            if stats.calls < self.policy.min_calls {
+                continue;
+            }
+            let rate = stats.usage_rate();
+            if rate >= self.policy.prefer_above {
+                lines.push(format!(
+                    "- Prefer `{name}` when it applies: its results were used in {:.0}% of answers.",
+                    rate * 100.0
+                ));
+            } else if rate < self.policy.avoid_below && !self.policy.trim_tools {
+                lines.push(format!(
+                    "- Use `{name}` sparingly: its results were rarely used ({:.0}% of {} calls).",
+                    rate * 100.0,
+                    stats.calls
+                ));
+            }
+        }
+
+        if lines.is_empty() {
+            None
+        } else {
+            Some(format!(
+                "Tool usage guidance (from previous runs):\n{}",
+                lines.join("\n")
+            ))
+        }
+    }
+
+    /// Clear all collected statistics
+    pub fn reset(&self) {
+        self.stats
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+}
+
+/// Snapshot of tool usage statistics
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolUsageReport {
+    /// Tools and their statistics, most called first
+    pub tools: Vec<(String, ToolUsageStats)>,
+}
+
+impl ToolUsageReport {
+    /// Total number of tool calls
+    pub fn total_calls(&self) -> u64 {
+        self.tools.iter().map(|(_, s)| s.calls).sum()
+    }
+
+    /// Whether no tool calls have been recorded
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty()
+    }
+}
+
+impl fmt::Display for ToolUsageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<28} {:>6} {:>6} {:>7} {:>10}",
+            "tool", "calls", "errors", "used %", "avg ms"
+        )?;
+        for (name, stats) in &self.tools {
+            writeln!(
+                f,
+                "{:<28} {:>6} {:>6} {:>6.0}% {:>10.1}",
+                name,
+                stats.calls,
+                stats.errors,
+                stats.usage_rate() * 100.0,
+                stats.avg_latency_ms()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Heuristically decide whether a tool's output is reflected in the answer
+///
+/// Numbers and short strings from the output are searched for in the
+/// (lowercased) answer. Values that were already part of the input, such as
+/// the ticker symbol, are ignored since they prove nothing.
+fn output_used_in_answer(input: &Value, output: &Value, answer: &str) -> bool {
+    let mut input_fragments = Vec::new();
+    collect_fragments(input, &mut input_fragments);
+
+    let mut output_fragments = Vec::new();
+    collect_fragments(output, &mut output_fragments);
+
+    output_fragments
+        .iter()
+        .filter(|fragment| !input_fragments.contains(fragment))
+        .any(|fragment| answer.contains(fragment.as_str()))
+}
+
+/// Collect lowercase textual fragments of a JSON value worth matching
+fn collect_fragments(value: &Value, out: &mut Vec<String>) {
+    match value {
+        Value::String(s) => {
+            let len = s.chars().count();
+            if (MIN_FRAGMENT_LEN..=MAX_FRAGMENT_LEN).contains(&len) {
+                out.push(s.to_lowercase());
+            }
+        }
+        Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                // Small integers (periods, counts) match too easily
+                if i.abs() >= 10 {
+                    out.push(i.to_string());
+                }
+            } else if let Some(f) = n.as_f64() {
+                out.push(format!("{f:.2}"));
+                out.push(format!("{f:.1}"));
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|v| collect_fragments(v, out)),
+        Value::Object(map) => map.values().for_each(|v| collect_fragments(v, out)),
+        Value::Bool(_) | Value::Null => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn outcome(name: &str, input: Value, output: Value) -> ToolCallOutcome {
+        ToolCallOutcome {
+            name: name.to_string(),
+            input,
+            output,
+        }
+    }
+
+    #[test]
+    fn test_record_call_stats() {
+        let tracker = ToolUsageTracker::new();
+        tracker.record_call("quote", 100, true);
+        tracker.record_call("quote", 300, false);
+
+        let stats = tracker.stats("quote").unwrap();
+        assert_eq!(stats.calls, 2);
+        assert_eq!(stats.errors, 1);
+        assert!((stats.avg_latency_ms() - 200.0).abs() < f64::EPSILON);
+        assert!((stats.error_rate() - 0.5).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_record_answer_ignores_input_values() {
+        let tracker = ToolUsageTracker::new();
+        let used = outcome(
+            "technical",
+            json!({"symbol": "AAPL"}),
+            json!({"symbol": "AAPL", "rsi": 71.234}),
+        );
+        let unused = outcome(
+            "news",
+            json!({"symbol": "AAPL"}),
+            json!({"symbol": "AAPL", "headline": "Something unrelated"}),
+        );
+        tracker.record_call("technical", 10, true);
+        tracker.record_call("news", 10, true);
+        tracker.record_answer(&[used, unused], "AAPL looks overbought with RSI at 71.23.");
+
+        assert_eq!(tracker.stats("technical").unwrap().used_in_answer, 1);
+        assert_eq!(tracker.stats("news").unwrap().used_in_answer, 0);
+    }
+
+    #[test]
+    fn test_selection_hints_and_trimming() {
+        let tracker = ToolUsageTracker::with_policy(ToolUsagePolicy {
+            min_calls: 2,
+            trim_tools: true,
+            ..ToolUsagePolicy::default()
+        });
+        for _ in 0..3 {
+            tracker.record_call("good", 5, true);
+            tracker.record_call("noise", 5, true);
+            tracker.record_answer(
+                &[outcome(
+                    "good",
+                    json!({}),
+                    json!({"value": "useful insight"}),
+                )],
+                "A useful insight.",
+            );
+        }
+
+        let hints = tracker.selection_hints().unwrap();
+        assert!(hints.contains("Prefer `good`"));
+        assert!(!hints.contains("noise"));
+        assert!(tracker.should_trim("noise"));
+        assert!(!tracker.should_trim("good"));
+        assert!(!tracker.should_trim("unknown"));
+    }
+
+    #[test]
+    fn test_report() {
+        let tracker = ToolUsageTracker::new();
+        tracker.record_call("a", 1, true);
+        tracker.record_call("b", 1, true);
+        tracker.record_call("b", 1, true);
+
+        let report = tracker.report();
+        assert_eq!(report.total_calls(), 3);
+        assert_eq!(report.tools[0].0, "b");
+        assert!(report.to_string().contains("avg ms"));
+
+        tracker.reset();
+        assert!(tracker.report().is_empty());
+    }
+}
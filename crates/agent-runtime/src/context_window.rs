@@ -0,0 +1,346 @@
+//! Context window accounting for the agent loop
+//!
+//! Providers reject requests whose prompt does not fit the model's context
+//! window, usually with an opaque 400 error. The [`ContextWindowManager`]
+//! knows the context size of common models, estimates how many tokens the
+//! next request will use, and compacts the conversation (by summarizing or
+//! truncating older turns) before the limit is reached.
+
+use agent_llm::{ContentBlock, Message, MessageContent, Role, ToolDefinition};
+use std::collections::HashMap;
+
+/// Context size used for models that are not recognized
+pub const DEFAULT_CONTEXT_SIZE: usize = 128_000;
+
+/// Approximate number of characters per token used for estimation
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Fixed per-message overhead (role markers, separators)
+const MESSAGE_OVERHEAD_TOKENS: usize = 4;
+
+/// Known context sizes, matched by model name prefix (most specific first)
+const MODEL_CONTEXT_SIZES: &[(&str, usize)] = &[
+    ("claude-", 200_000),
+    ("gpt-4o", 128_000),
+    ("gpt-4.1", 1_047_576),
+    ("gpt-4-turbo", 128_000),
+    ("gpt-4-32k", 32_768),
+    ("gpt-4", 8_192),
+    ("gpt-3.5-turbo", 16_385),
+    ("gpt-5", 400_000),
+    ("o1", 200_000),
+    ("o3", 200_000),
+    ("o4", 200_000),
+    ("deepseek", 64_000),
+    ("qwen", 128_000),
+    ("llama3", 8_192),
+    ("gemini", 1_000_000),
+];
+
+/// How to make room when the conversation approaches the context limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ContextStrategy {
+    /// Summarize older turns with the LLM, falling back to truncation
+    #[default]
+    Summarize,
+    /// Drop the oldest turns
+    Truncate,
+}
+
+/// Configuration for context window management
+#[derive(Debug, Clone)]
+pub struct ContextWindowConfig {
+    /// Compaction strategy
+    pub strategy: ContextStrategy,
+
+    /// Fraction of the context window that may be used before compacting
+    pub threshold: f64,
+
+    /// Number of most recent messages kept verbatim when summarizing
+    pub keep_recent: usize,
+
+    /// Context sizes for specific models, overriding the built-in table
+    pub model_overrides: HashMap<String, usize>,
+}
+
+impl Default for ContextWindowConfig {
+    fn default() -> Self {
+        Self {
+            strategy: ContextStrategy::default(),
+            threshold: 0.9,
+            keep_recent: 6,
+            model_overrides: HashMap::new(),
+        }
+    }
+}
+
+/// Token accounting for a single request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContextUsage {
+    /// Estimated prompt tokens (system, tools and conversation)
+    pub estimated_tokens: usize,
+
+    /// Tokens available for the prompt after reserving room for the response
+    pub budget: usize,
+
+    /// Total context size of the model
+    pub context_size: usize,
+}
+
+impl ContextUsage {
+    /// Whether the prompt fits within the budget
+    pub fn fits(&self) -> bool {
+        self.estimated_tokens <= self.budget
+    }
+}
+
+/// Tracks conversation size against a model's context window
+#[derive(Debug, Clone, Default)]
+pub struct ContextWindowManager {
+    config: ContextWindowConfig,
+}
+
+impl ContextWindowManager {
+    /// Create a manager with the given configuration
+    pub fn new(config: ContextWindowConfig) -> Self {
+        Self { config }
+    }
+
+    /// Get the configuration
+    pub fn config(&self) -> &ContextWindowConfig {
+        &self.config
+    }
+
+    /// Context size of a model in tokens
+    pub fn context_size(&self, model: &str) -> usize {
+        if let Some(size) = self.config.model_overrides.get(model) {
+            return *size;
+        }
+
+        let model = model.to_lowercase();
+        // Strip vendor prefixes such as "openai/gpt-4o" or "anthropic/claude-..."
+        let model = model.rsplit('/').next().unwrap_or(&model);
+        MODEL_CONTEXT_SIZES
+            .iter()
+            .find(|(prefix, _)| model.starts_with(prefix))
+            .map_or(DEFAULT_CONTEXT_SIZE, |(_, size)| *size)
+    }
+
+    /// Tokens available for the prompt, reserving `max_tokens` for the response
+    pub fn budget(&self, model: &str, max_tokens: usize) -> usize {
+        let usable = (self.context_size(model) as f64 * self.config.threshold) as usize;
+        usable.saturating_sub(max_tokens)
+    }
+
+    /// Estimate the token usage of a request
+    pub fn usage(
+        &self,
+        model: &str,
+        max_tokens: usize,
+        system: &str,
+        tools: &[ToolDefinition],
+        conversation: &[Message],
+    ) -> ContextUsage {
+        let estimated_tokens = fixed_tokens(system, tools) + conversation_tokens(conversation);
+        ContextUsage {
+            estimated_tokens,
+            budget: self.budget(model, max_tokens),
+            context_size: self.context_size(model),
+        }
+    }
+
+    /// Index up to which messages should be summarized, if summarization can help
+    ///
+    /// The split always lands on the start of a user turn so tool calls and
+    /// their results are never separated.
+    pub fn summary_split(&self, conversation: &[Message]) -> Option<usize> {
+        let max_split = conversation.len().saturating_sub(self.config.keep_recent);
+        (1..=max_split)
+            .rev()
+            .find(|&i| is_turn_start(&conversation[i]))
+    }
+
+    /// Drop the oldest turns until the conversation fits in `available` tokens
+    ///
+    /// Returns the number of removed messages, or `None` if even the most
+    /// recent turn does not fit.
+    pub fn truncate(&self, conversation: &mut Vec<Message>, available: usize) -> Option<usize> {
+        let mut remaining = conversation_tokens(conversation);
+        for i in 0..conversation.len() {
+            if is_turn_start(&conversation[i]) && remaining <= available {
+                conversation.drain(..i);
+                return Some(i);
+            }
+            remaining -= message_tokens(&conversation[i]);
+        }
+        None
+    }
+}
+
+/// Estimate tokens of the parts of a request that cannot be compacted
+pub fn fixed_tokens(system: &str, tools: &[ToolDefinition]) -> usize {
+    let tools_chars: usize = tools
+        .iter()
+        .map(|t| t.name.len() + t.description.len() + t.input_schema.to_string().len())
+        .sum();
+    (system.len() + tools_chars).div_ceil(CHARS_PER_TOKEN)
+}
+
+/// Estimate tokens of a single message
+pub fn message_tokens(message: &Message) -> usize {
+    let chars = match &message.content {
+        None => 0,
+        Some(MessageContent::Text(text)) => text.len(),
+        Some(MessageContent::Blocks(blocks)) => blocks
+            .iter()
+            .map(|block| match block {
+                ContentBlock::Text { text } => text.len(),
+                // Images are billed by size; use a conservative flat estimate
+                ContentBlock::Image { .. } => 1_600 * CHARS_PER_TOKEN,
+                ContentBlock::ToolUse { name, input, .. } => name.len() + input.to_string().len(),
+                ContentBlock::ToolResult { content, .. } => content.len(),
+            })
+            .sum(),
+    };
+    chars.div_ceil(CHARS_PER_TOKEN) + MESSAGE_OVERHEAD_TOKENS
+}
+
+/// Estimate tokens of a conversation
+pub fn conversation_tokens(conversation: &[Message]) -> usize {
+    conversation.iter().map(message_tokens).sum()
+}
+
+/// Whether a message starts a user turn (a user message that is not a tool result)
+fn is_turn_start(message: &Message) -> bool {
+    message.role == Role::User
+        && !matches!(
+            &message.content,
+            Some(MessageContent::Blocks(blocks))
+                if blocks.iter().any(|b| matches!(b, ContentBlock::ToolResult { .. }))
+        )
+}
+
+/// Render messages as a plain-text transcript for summarization
+pub(crate) fn render_transcript(conversation: &[Message], max_chars: usize) -> String {
+    let mut transcript = String::new();
+    for message in conversation {
+        let role = match message.role {
+            Role::User => "User",
+            Role::Assistant => "Assistant",
+            Role::System => "System",
+        };
+        let body = match &message.content {
+            None => String::new(),
+            Some(MessageContent::Text(text)) => text.clone(),
+            Some(MessageContent::Blocks(blocks)) => blocks
+                .iter()
+                .map(|block| match block {
+                    ContentBlock::Text { text } => text.clone(),
+                    ContentBlock::Image { .. } => "[image]".to_string(),
+                    ContentBlock::ToolUse { name, input, .. } => {
+                        format!("[called tool {name} with {input}]")
+                    }
+                    ContentBlock::ToolResult { content, .. } => {
+                        let preview: String = content.chars().take(2_000).collect();
+                        format!("[tool result: {preview}]")
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        };
+        transcript.push_str(role);
+        transcript.push_str(": ");
+        transcript.push_str(&body);
+        transcript.push_str("\n\n");
+    }
+
+    if transcript.len() > max_chars {
+        // Keep the most recent part of the transcript
+        let start = transcript.len() - max_chars;
+        let start = (start..transcript.len())
+            .find(|&i| transcript.is_char_boundary(i))
+            .unwrap_or(transcript.len());
+        transcript.drain(..start);
+    }
+    transcript
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn turn(text: &str) -> Vec<Message> {
+        vec![
+            Message::user(text.to_string()),
+            Message::assistant(format!("reply to {text}")),
+        ]
+    }
+
+    #[test]
+    fn test_context_size_lookup() {
+        let manager = ContextWindowManager::default();
+        assert_eq!(manager.context_size("claude-sonnet-4-5-20250929"), 200_000);
+        assert_eq!(manager.context_size("gpt-4o-mini"), 128_000);
+        assert_eq!(manager.context_size("gpt-4"), 8_192);
+        assert_eq!(manager.context_size("openai/gpt-3.5-turbo"), 16_385);
+        assert_eq!(manager.context_size("unknown-model"), DEFAULT_CONTEXT_SIZE);
+
+        let mut config = ContextWindowConfig::default();
+        config
+            .model_overrides
+            .insert("unknown-model".to_string(), 4_000);
+        let manager = ContextWindowManager::new(config);
+        assert_eq!(manager.context_size("unknown-model"), 4_000);
+    }
+
+    #[test]
+    fn test_budget_reserves_response_tokens() {
+        let manager = ContextWindowManager::default();
+        assert_eq!(manager.budget("gpt-4", 1_000), 8_192 * 9 / 10 - 1_000);
+        assert_eq!(manager.budget("gpt-4", 100_000), 0);
+    }
+
+    #[test]
+    fn test_truncate_keeps_whole_turns() {
+        let manager = ContextWindowManager::default();
+        let mut conversation = Vec::new();
+        for i in 0..5 {
+            conversation.extend(turn(&format!("question {i} {}", "x".repeat(400))));
+        }
+        let last_two = conversation_tokens(&conversation[6..]);
+
+        let removed = manager.truncate(&mut conversation, last_two).unwrap();
+        assert_eq!(removed, 6);
+        assert_eq!(conversation.len(), 4);
+        assert_eq!(conversation[0].role, Role::User);
+
+        assert!(manager.truncate(&mut conversation, 1).is_none());
+    }
+
+    #[test]
+    fn test_split_skips_tool_results() {
+        let manager = ContextWindowManager::new(ContextWindowConfig {
+            keep_recent: 2,
+            ..ContextWindowConfig::default()
+        });
+        let mut conversation = turn("first");
+        conversation.push(Message::user("second".to_string()));
+        conversation.push(Message::assistant("calling tool".to_string()));
+        conversation.push(Message::tool_result("id".to_string(), "42".to_string()));
+        conversation.push(Message::assistant("done".to_string()));
+
+        // Index 4 is a tool result, so the split falls back to index 2
+        assert_eq!(manager.summary_split(&conversation), Some(2));
+        assert_eq!(manager.summary_split(&conversation[..2]), None);
+    }
+
+    #[test]
+    fn test_render_transcript_limits_length() {
+        let conversation = turn("hello");
+        let transcript = render_transcript(&conversation, 1_000);
+        assert!(transcript.starts_with("User: hello"));
+
+        let short = render_transcript(&conversation, 10);
+        assert!(short.len() <= 10);
+    }
+}
@@ -8,20 +8,65 @@
 
 use agent_core::{Context, Result};
 use agent_llm::{
-    CompletionRequest, ContentBlock, LLMProvider, Message, StopReason, ToolDefinition,
+    CompletionRequest, CompletionResponse, ContentBlock, LLMProvider, Message, StopReason,
+    TokenUsage, ToolDefinition,
 };
-use agent_tools::ToolRegistry;
+use agent_tools::{Tool, ToolRegistry};
 use async_trait::async_trait;
-use serde_json::Value;
+use serde_json::{Map, Value};
+use std::fmt;
+use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio::task::JoinHandle;
 use tracing::{debug, info, warn};
 
+use crate::analytics::{ToolCallOutcome, ToolUsageTracker};
+use crate::context_window::{self, ContextStrategy, ContextWindowManager};
+use crate::llm_log::LlmLogger;
+use crate::usage::{UsageSummary, UsageTracker};
+
+/// Maximum tokens requested when summarizing older conversation turns
+const SUMMARY_MAX_TOKENS: usize = 1024;
+
+/// System prompt used when summarizing older conversation turns
+const SUMMARY_SYSTEM_PROMPT: &str = "Summarize the conversation below so it can replace the \
+original messages. Keep every fact, number, ticker symbol, tool result and user preference \
+that later turns may rely on. Be concise and do not add commentary.";
+
 /// Event handler for agent execution events
 ///
 /// Implement this trait to receive callbacks during agent execution,
-/// useful for streaming tool call status to clients.
+/// useful for streaming tool call status and step progress to clients.
+/// Per iteration the order is `on_iteration_start`, `on_llm_request`,
+/// `on_llm_response`, then the tool events if the model called tools. With
+/// providers that stream tool calls, `on_tool_start` can come before
+/// `on_llm_response`, since tools start while the response is generating.
 #[async_trait]
 pub trait ExecutorEventHandler: Send + Sync {
+    /// Called when an iteration of the agent loop starts (1-based); see
+    /// [`progress_label`] for a ready-made "Step 3/10: ..." label
+    async fn on_iteration_start(&self, _iteration: usize, _max_iterations: usize) {}
+
+    /// Called before a request is sent to the LLM, with the estimated
+    /// prompt size in tokens
+    async fn on_llm_request(&self, _iteration: usize, _estimated_input_tokens: usize) {}
+
+    /// Called when the LLM responds, with the tokens the request used
+    async fn on_llm_response(
+        &self,
+        _iteration: usize,
+        _usage: &TokenUsage,
+        _stop_reason: StopReason,
+    ) {
+    }
+
+    /// Called with each block of text the LLM writes, as soon as the
+    /// provider has streamed it; the final answer arrives here too before
+    /// `on_complete`
+    async fn on_text(&self, _iteration: usize, _text: &str) {}
+
     /// Called when a tool execution starts
     async fn on_tool_start(&self, _id: &str, _name: &str, _input: &Value) {}
 
@@ -42,6 +87,82 @@ pub trait ExecutorEventHandler: Send + Sync {
     async fn on_error(&self, _error: &str) {}
 }
 
+/// Progress label for an iteration, e.g. "Step 3/10: analyzing news"
+pub fn progress_label(iteration: usize, max_iterations: usize, activity: &str) -> String {
+    format!("Step {iteration}/{max_iterations}: {activity}")
+}
+
+tokio::task_local! {
+    static SCOPED_HANDLER: Arc<dyn ExecutorEventHandler>;
+    static PINNED_PARAMS: Arc<Map<String, Value>>;
+}
+
+/// Run `future` with `handler` receiving the events of every agent run
+/// inside it
+///
+/// Agents nested several layers deep (a delegating agent running its
+/// sub-agents concurrently, say) can then be observed per request without
+/// reconfiguring their executors. Runs given a handler explicitly, such as
+/// [`AgentExecutor::run_with_history_and_handler`], keep their own. Work
+/// moved to another task with `tokio::spawn` is outside the scope.
+pub async fn with_event_handler<F: Future>(
+    handler: Arc<dyn ExecutorEventHandler>,
+    future: F,
+) -> F::Output {
+    SCOPED_HANDLER.scope(handler, future).await
+}
+
+/// Context key of the tool parameters a [`ToolAgent`](crate::ToolAgent)
+/// pins for its runs (see [`with_pinned_params`])
+pub const PINNED_PARAMS_CONTEXT_KEY: &str = "pinned_tool_params";
+
+/// Run `future` with `params` set on every tool call of the agent runs
+/// inside it
+///
+/// Pinned values replace whatever the model passed for them, so a request
+/// can constrain its tools (to data known at a past date, say) without
+/// relying on the model to ask. Tools whose input schema does not declare
+/// every pinned parameter cannot honour them: they are left out of the tool
+/// definitions, and calls to them fail. Parameters a tool takes from its
+/// caller only ([`Tool::caller_params`]), such as the user a request is for,
+/// are exempt: tools that do not take them are still offered. Like
+/// [`with_event_handler`], work moved to another task with `tokio::spawn` is
+/// outside the scope.
+pub async fn with_pinned_params<F: Future>(params: Map<String, Value>, future: F) -> F::Output {
+    PINNED_PARAMS.scope(Arc::new(params), future).await
+}
+
+/// Parameters pinned by an enclosing [`with_pinned_params`], if any
+fn pinned_params() -> Option<Arc<Map<String, Value>>> {
+    PINNED_PARAMS.try_with(Arc::clone).ok()
+}
+
+/// Pinned parameters a tool must declare to honour them
+///
+/// Keys that a registered tool takes from its caller only (see
+/// [`Tool::caller_params`]) describe the request, like the user it is for,
+/// rather than limit the data; tools that do not take them ignore them.
+fn pinned_constraints(registry: &ToolRegistry, pinned: &Map<String, Value>) -> Vec<String> {
+    let tools = registry.list_tools();
+    pinned
+        .keys()
+        .filter(|key| {
+            !tools
+                .iter()
+                .any(|tool| tool.caller_params().contains(&key.as_str()))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Whether `tool` declares every parameter in `constraints`
+fn accepts_pinned(tool: &dyn Tool, constraints: &[String]) -> bool {
+    let schema = tool.input_schema();
+    constraints
+        .iter()
+        .all(|key| schema["properties"].get(key).is_some())
+}
+
 /// No-op event handler for when events are not needed
 pub struct NoOpEventHandler;
 
@@ -79,6 +200,63 @@ impl Default for ExecutorConfig {
     }
 }
 
+/// Outcome of an agent run: the final text plus what it took to produce it
+///
+/// Displays as the text, so callers that only need the answer can keep
+/// treating it like a string (`result.to_string()`, `format!("{result}")`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunResult {
+    /// Final response text
+    pub text: String,
+    /// Tokens used across all LLM requests of the run, including
+    /// context-window summaries
+    pub usage: TokenUsage,
+    /// Tools executed, in call order; duplicate calls answered from an
+    /// earlier result are not listed
+    pub tool_calls: Vec<ToolCallRecord>,
+    /// LLM round trips made by the agent loop
+    pub iterations: usize,
+    /// Why the last LLM response stopped; `ToolUse` means the run hit the
+    /// iteration limit while the model still wanted to call tools
+    pub stop_reason: StopReason,
+}
+
+impl RunResult {
+    /// Whether the model finished its answer (as opposed to hitting the
+    /// token or iteration limit)
+    pub fn is_complete(&self) -> bool {
+        matches!(
+            self.stop_reason,
+            StopReason::EndTurn | StopReason::StopSequence
+        )
+    }
+}
+
+impl fmt::Display for RunResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl From<RunResult> for String {
+    fn from(result: RunResult) -> Self {
+        result.text
+    }
+}
+
+/// A tool call made during an agent run
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolCallRecord {
+    /// Tool name
+    pub name: String,
+    /// Input the model passed to the tool
+    pub input: Value,
+    /// Whether the tool returned a result rather than an error
+    pub success: bool,
+    /// Execution time in milliseconds
+    pub duration_ms: u64,
+}
+
 /// Executes an agent loop: LLM → tool calls → execution → loop back
 ///
 /// The AgentExecutor orchestrates the interaction between an LLM provider
@@ -88,6 +266,10 @@ pub struct AgentExecutor {
     tool_registry: Arc<ToolRegistry>,
     config: ExecutorConfig,
     event_handler: Option<Arc<dyn ExecutorEventHandler>>,
+    usage_tracker: Option<Arc<ToolUsageTracker>>,
+    token_tracker: Option<Arc<UsageTracker>>,
+    context_window: ContextWindowManager,
+    llm_logger: Option<(Arc<LlmLogger>, String)>,
 }
 
 impl AgentExecutor {
@@ -102,6 +284,10 @@ impl AgentExecutor {
             tool_registry,
             config,
             event_handler: None,
+            usage_tracker: None,
+            token_tracker: None,
+            context_window: ContextWindowManager::default(),
+            llm_logger: None,
         }
     }
 
@@ -116,6 +302,69 @@ impl AgentExecutor {
         self.event_handler = Some(handler);
     }
 
+    /// Set the tracker used to record tool usage analytics
+    ///
+    /// Depending on the tracker's policy, its statistics are also used to add
+    /// tool selection hints to the system prompt and to trim rarely useful tools.
+    pub fn with_usage_tracker(mut self, tracker: Arc<ToolUsageTracker>) -> Self {
+        self.usage_tracker = Some(tracker);
+        self
+    }
+
+    /// Get the tool usage tracker, if one is attached
+    pub fn usage_tracker(&self) -> Option<&Arc<ToolUsageTracker>> {
+        self.usage_tracker.as_ref()
+    }
+
+    /// Set the tracker that adds up the tokens of every LLM request
+    pub fn with_token_tracker(mut self, tracker: Arc<UsageTracker>) -> Self {
+        self.token_tracker = Some(tracker);
+        self
+    }
+
+    /// Get the token usage tracker, if one is attached
+    pub fn token_tracker(&self) -> Option<&Arc<UsageTracker>> {
+        self.token_tracker.as_ref()
+    }
+
+    /// Usage of a run of this executor, priced like its token tracker does
+    ///
+    /// Requests are counted as the run's iterations; summaries made to fit
+    /// the context window are included in the tokens but not the count.
+    pub fn usage_summary(&self, result: &RunResult) -> UsageSummary {
+        let provider = self.provider.name();
+        let model = &self.config.model;
+        let pricing = match &self.token_tracker {
+            Some(tracker) => tracker.pricing_for(provider, model),
+            None => crate::usage::ModelPricing::for_model(provider, model),
+        };
+        let mut summary = UsageSummary::default();
+        summary.record(
+            provider,
+            model,
+            result.iterations as u64,
+            result.usage,
+            pricing,
+        );
+        summary
+    }
+
+    /// Set the context window manager used to keep requests within model limits
+    pub fn with_context_window(mut self, manager: ContextWindowManager) -> Self {
+        self.context_window = manager;
+        self
+    }
+
+    /// Log complete LLM exchanges of this executor under the given agent name
+    pub fn with_llm_logger(
+        mut self,
+        logger: Arc<LlmLogger>,
+        agent_name: impl Into<String>,
+    ) -> Self {
+        self.llm_logger = Some((logger, agent_name.into()));
+        self
+    }
+
     /// Execute the agent loop with a user query
     ///
     /// # Arguments
@@ -124,8 +373,9 @@ impl AgentExecutor {
     ///
     /// # Returns
     ///
-    /// The final response from the agent after all tool calls are complete
-    pub async fn run(&self, user_message: String) -> Result<String> {
+    /// The final response from the agent after all tool calls are complete,
+    /// with the tokens, tool calls and iterations it took
+    pub async fn run(&self, user_message: String) -> Result<RunResult> {
         let conversation = vec![Message::user(user_message)];
         self.run_conversation(conversation).await
     }
@@ -139,12 +389,13 @@ impl AgentExecutor {
     ///
     /// # Returns
     ///
-    /// The final response from the agent after all tool calls are complete
+    /// The final response from the agent after all tool calls are complete,
+    /// with the tokens, tool calls and iterations it took
     pub async fn run_with_history(
         &self,
         user_message: String,
         history: Vec<Message>,
-    ) -> Result<String> {
+    ) -> Result<RunResult> {
         let mut conversation = history;
         conversation.push(Message::user(user_message));
         self.run_conversation_with_handler(conversation, self.event_handler.clone())
@@ -160,7 +411,7 @@ impl AgentExecutor {
         user_message: String,
         history: Vec<Message>,
         handler: Arc<dyn ExecutorEventHandler>,
-    ) -> Result<String> {
+    ) -> Result<RunResult> {
         let mut conversation = history;
         conversation.push(Message::user(user_message));
         self.run_conversation_with_handler(conversation, Some(handler))
@@ -183,7 +434,7 @@ impl AgentExecutor {
         history: Vec<Message>,
         handler: Arc<dyn ExecutorEventHandler>,
         context: &Context,
-    ) -> Result<String> {
+    ) -> Result<RunResult> {
         let mut conversation = history;
 
         // Prepend language instruction if language is set and not Chinese (default)
@@ -192,7 +443,10 @@ impl AgentExecutor {
                 // Insert language instruction at the beginning
                 conversation.insert(
                     0,
-                    Message::user("[System: Please respond in English. All responses must be in English.]".to_string()),
+                    Message::user(
+                        "[System: Please respond in English. All responses must be in English.]"
+                            .to_string(),
+                    ),
                 );
                 conversation.insert(
                     1,
@@ -206,9 +460,14 @@ impl AgentExecutor {
             .await
     }
 
-    /// Internal method to run the agent loop with a conversation
-    async fn run_conversation(&self, initial_conversation: Vec<Message>) -> Result<String> {
-        self.run_conversation_with_handler(initial_conversation, self.event_handler.clone())
+    /// Internal method to run the agent loop with a conversation, reporting
+    /// to the handler of an enclosing [`with_event_handler`] if there is one
+    async fn run_conversation(&self, initial_conversation: Vec<Message>) -> Result<RunResult> {
+        let handler = SCOPED_HANDLER
+            .try_with(Arc::clone)
+            .ok()
+            .or_else(|| self.event_handler.clone());
+        self.run_conversation_with_handler(initial_conversation, handler)
             .await
     }
 
@@ -217,9 +476,13 @@ impl AgentExecutor {
         &self,
         initial_conversation: Vec<Message>,
         event_handler: Option<Arc<dyn ExecutorEventHandler>>,
-    ) -> Result<String> {
+    ) -> Result<RunResult> {
         let mut conversation = initial_conversation;
         let mut iteration = 0;
+        let mut tool_outcomes: Vec<ToolCallOutcome> = Vec::new();
+        let mut tool_calls: Vec<ToolCallRecord> = Vec::new();
+        let mut usage = TokenUsage::default();
+        let mut stop_reason = StopReason::EndTurn;
 
         loop {
             iteration += 1;
@@ -228,7 +491,13 @@ impl AgentExecutor {
                     "Max iterations ({}) reached, stopping",
                     self.config.max_iterations
                 );
-                return Ok("Max iterations reached without completion".to_string());
+                return Ok(RunResult {
+                    text: "Max iterations reached without completion".to_string(),
+                    usage,
+                    tool_calls,
+                    iterations: self.config.max_iterations,
+                    stop_reason,
+                });
             }
 
             info!(
@@ -236,6 +505,11 @@ impl AgentExecutor {
                 max_iterations = self.config.max_iterations,
                 "Agent iteration started"
             );
+            if let Some(handler) = &event_handler {
+                handler
+                    .on_iteration_start(iteration, self.config.max_iterations)
+                    .await;
+            }
 
             // Build tool definitions from registry
             let tools = self.build_tool_definitions();
@@ -251,6 +525,23 @@ impl AgentExecutor {
                 );
             }
 
+            let system_prompt = self.build_system_prompt();
+            self.fit_context_window(&mut conversation, &system_prompt, &tools, &mut usage)
+                .await?;
+            if let Some(handler) = &event_handler {
+                let estimated_tokens = self
+                    .context_window
+                    .usage(
+                        &self.config.model,
+                        self.config.max_tokens,
+                        &system_prompt,
+                        &tools,
+                        &conversation,
+                    )
+                    .estimated_tokens;
+                handler.on_llm_request(iteration, estimated_tokens).await;
+            }
+
             // Call LLM
             info!(
                 model = %self.config.model,
@@ -261,12 +552,7 @@ impl AgentExecutor {
             );
             let mut request_builder = CompletionRequest::builder(&self.config.model)
                 .messages(conversation.clone())
-                .system(
-                    self.config
-                        .system_prompt
-                        .clone()
-                        .unwrap_or_else(|| "You are a helpful assistant.".to_string()),
-                )
+                .system(system_prompt)
                 .max_tokens(self.config.max_tokens)
                 .temperature(self.config.temperature.unwrap_or(0.7));
 
@@ -277,11 +563,58 @@ impl AgentExecutor {
 
             let request = request_builder.build();
 
-            let response = self
-                .provider
-                .complete(request)
-                .await
-                .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))?;
+            // Tool calls streamed before the response is complete start
+            // right away, so they run while the model writes the rest
+            let (blocks, mut streamed) = mpsc::unbounded_channel();
+            let mut started: Vec<PendingTool> = Vec::new();
+            let response = {
+                let completion = self.complete(request, Some(&blocks));
+                tokio::pin!(completion);
+                loop {
+                    tokio::select! {
+                        biased;
+                        Some(block) = streamed.recv() => match block {
+                            ContentBlock::ToolUse { id, name, input } => {
+                                let tool = self
+                                    .start_tool(id, name, input, &started, event_handler.as_ref())
+                                    .await?;
+                                started.push(tool);
+                            }
+                            ContentBlock::Text { text } => {
+                                if let Some(handler) = &event_handler {
+                                    handler.on_text(iteration, &text).await;
+                                }
+                            }
+                            _ => {}
+                        },
+                        response = &mut completion => break response?,
+                    }
+                }
+            };
+            // Blocks still queued when the response completed; tool calls
+            // among them run with the others below
+            while let Ok(block) = streamed.try_recv() {
+                if let (ContentBlock::Text { text }, Some(handler)) = (block, &event_handler) {
+                    handler.on_text(iteration, &text).await;
+                }
+            }
+            if !started.is_empty() {
+                debug!(started = started.len(), "Tools started while streaming");
+            }
+            usage += response.usage;
+            stop_reason = response.stop_reason;
+            if let Some(handler) = &event_handler {
+                handler
+                    .on_llm_response(iteration, &response.usage, response.stop_reason)
+                    .await;
+            }
+            let finish = |text: String, tool_calls: Vec<ToolCallRecord>| RunResult {
+                text,
+                usage,
+                tool_calls,
+                iterations: iteration,
+                stop_reason,
+            };
 
             // Log detailed response information
             info!(
@@ -292,7 +625,9 @@ impl AgentExecutor {
             );
 
             // Log response preview
-            let response_preview: String = response.message.text()
+            let response_preview: String = response
+                .message
+                .text()
                 .unwrap_or("")
                 .chars()
                 .take(300)
@@ -316,28 +651,35 @@ impl AgentExecutor {
                         "Agent completed naturally"
                     );
 
+                    if let Some(tracker) = &self.usage_tracker {
+                        tracker.record_answer(&tool_outcomes, &text);
+                    }
+
                     // Emit complete event
                     if let Some(handler) = &event_handler {
                         handler.on_complete(&text).await;
                     }
 
-                    return Ok(text);
+                    return Ok(finish(text, tool_calls));
                 }
 
                 StopReason::ToolUse => {
                     // Extract and execute tool calls
                     let tool_uses = response.message.tool_uses();
-                    info!(
-                        tool_count = tool_uses.len(),
-                        "Agent requested tool use"
-                    );
+                    info!(tool_count = tool_uses.len(), "Agent requested tool use");
                     let tool_results = self
-                        .execute_tools(&response.message, event_handler.as_ref())
+                        .finish_tools(
+                            &response.message,
+                            started,
+                            event_handler.as_ref(),
+                            &mut tool_outcomes,
+                            &mut tool_calls,
+                        )
                         .await?;
 
                     if tool_results.is_empty() {
                         warn!("No tool results despite ToolUse stop reason");
-                        return Ok("Tool execution failed".to_string());
+                        return Ok(finish("Tool execution failed".to_string(), tool_calls));
                     }
 
                     info!(
@@ -356,109 +698,500 @@ impl AgentExecutor {
 
                 StopReason::MaxTokens => {
                     warn!("Hit max tokens in LLM response");
-                    return Ok("Response truncated due to token limit".to_string());
+                    return Ok(finish(
+                        "Response truncated due to token limit".to_string(),
+                        tool_calls,
+                    ));
                 }
 
                 StopReason::StopSequence => {
                     debug!("Stop sequence encountered");
                     let text = response.message.text().unwrap_or("No response").to_string();
-                    return Ok(text);
+                    return Ok(finish(text, tool_calls));
+                }
+            }
+        }
+    }
+
+    /// Send a request to the provider, logging the exchange if enabled and
+    /// recording its tokens with the token tracker
+    ///
+    /// With `blocks`, content blocks are sent there as soon as the provider
+    /// has streamed them.
+    async fn complete(
+        &self,
+        request: CompletionRequest,
+        blocks: Option<&UnboundedSender<ContentBlock>>,
+    ) -> Result<CompletionResponse> {
+        let model = request.model.clone();
+        let response = self.logged_complete(request, blocks).await?;
+        if let Some(tracker) = &self.token_tracker {
+            tracker.record(self.provider.name(), &model, &response.usage);
+        }
+        Ok(response)
+    }
+
+    async fn logged_complete(
+        &self,
+        request: CompletionRequest,
+        blocks: Option<&UnboundedSender<ContentBlock>>,
+    ) -> Result<CompletionResponse> {
+        let Some((logger, agent_name)) = &self.llm_logger else {
+            return self
+                .provider_complete(request, blocks)
+                .await
+                .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()));
+        };
+
+        let start_time = std::time::Instant::now();
+        let result = self.provider_complete(request.clone(), blocks).await;
+        let duration_ms = start_time.elapsed().as_millis() as u64;
+
+        match result {
+            Ok(response) => {
+                logger.log_exchange(agent_name, &request, Ok(&response), duration_ms);
+                Ok(response)
+            }
+            Err(e) => {
+                let error = e.to_string();
+                logger.log_exchange(agent_name, &request, Err(&error), duration_ms);
+                Err(agent_core::Error::ProcessingFailed(error))
+            }
+        }
+    }
+
+    async fn provider_complete(
+        &self,
+        request: CompletionRequest,
+        blocks: Option<&UnboundedSender<ContentBlock>>,
+    ) -> agent_llm::Result<CompletionResponse> {
+        match blocks {
+            Some(blocks) => self.provider.complete_streaming(request, blocks).await,
+            None => self.provider.complete(request).await,
+        }
+    }
+
+    /// Make sure the next request fits the model's context window
+    ///
+    /// When the estimated prompt size exceeds the budget, older turns are
+    /// summarized (if configured) and then truncated. Fails with a descriptive
+    /// error if even the most recent turn does not fit.
+    async fn fit_context_window(
+        &self,
+        conversation: &mut Vec<Message>,
+        system_prompt: &str,
+        tools: &[ToolDefinition],
+        run_usage: &mut TokenUsage,
+    ) -> Result<()> {
+        let manager = &self.context_window;
+        let model = &self.config.model;
+        let max_tokens = self.config.max_tokens;
+
+        let usage = manager.usage(model, max_tokens, system_prompt, tools, conversation);
+        debug!(
+            estimated_tokens = usage.estimated_tokens,
+            budget = usage.budget,
+            context_size = usage.context_size,
+            "Context window usage"
+        );
+        if usage.fits() {
+            return Ok(());
+        }
+
+        warn!(
+            estimated_tokens = usage.estimated_tokens,
+            budget = usage.budget,
+            "Conversation exceeds context budget, compacting"
+        );
+
+        if manager.config().strategy == ContextStrategy::Summarize {
+            if let Some(split) = manager.summary_split(conversation) {
+                match self
+                    .summarize_messages(&conversation[..split], run_usage)
+                    .await
+                {
+                    Ok(summary) => {
+                        info!(
+                            summarized_messages = split,
+                            "Summarized older conversation turns"
+                        );
+                        conversation.splice(
+                            ..split,
+                            [
+                                Message::user(format!(
+                                    "[Summary of the earlier conversation]\n{summary}"
+                                )),
+                                Message::assistant(
+                                    "Understood. I will continue from this summary.".to_string(),
+                                ),
+                            ],
+                        );
+                        let usage =
+                            manager.usage(model, max_tokens, system_prompt, tools, conversation);
+                        if usage.fits() {
+                            return Ok(());
+                        }
+                    }
+                    Err(e) => warn!(error = %e, "Summarization failed, falling back to truncation"),
                 }
             }
         }
+
+        let available = usage
+            .budget
+            .saturating_sub(context_window::fixed_tokens(system_prompt, tools));
+        match manager.truncate(conversation, available) {
+            Some(removed) => {
+                info!(
+                    removed_messages = removed,
+                    "Truncated oldest conversation turns"
+                );
+                Ok(())
+            }
+            None => Err(agent_core::Error::ProcessingFailed(format!(
+                "Request does not fit the context window of model '{model}': \
+                 about {} tokens needed, {} available",
+                manager
+                    .usage(model, max_tokens, system_prompt, tools, conversation)
+                    .estimated_tokens,
+                usage.budget
+            ))),
+        }
+    }
+
+    /// Ask the LLM for a summary of the given messages
+    async fn summarize_messages(
+        &self,
+        messages: &[Message],
+        run_usage: &mut TokenUsage,
+    ) -> Result<String> {
+        // Leave half of the budget for the transcript (about 4 characters per token)
+        let max_chars = self
+            .context_window
+            .budget(&self.config.model, SUMMARY_MAX_TOKENS)
+            * 2;
+        let transcript = context_window::render_transcript(messages, max_chars);
+
+        let request = CompletionRequest::builder(&self.config.model)
+            .messages(vec![Message::user(transcript)])
+            .system(SUMMARY_SYSTEM_PROMPT)
+            .max_tokens(SUMMARY_MAX_TOKENS)
+            .temperature(0.0)
+            .build();
+
+        let response = self.complete(request, None).await?;
+        *run_usage += response.usage;
+
+        response
+            .message
+            .text()
+            .map(ToString::to_string)
+            .ok_or_else(|| {
+                agent_core::Error::ProcessingFailed("Empty summary from LLM".to_string())
+            })
+    }
+
+    /// Build the system prompt, including tool selection hints if enabled
+    fn build_system_prompt(&self) -> String {
+        let base = self
+            .config
+            .system_prompt
+            .clone()
+            .unwrap_or_else(|| "You are a helpful assistant.".to_string());
+
+        let hints = self
+            .usage_tracker
+            .as_ref()
+            .filter(|tracker| tracker.policy().prompt_hints)
+            .and_then(|tracker| tracker.selection_hints());
+
+        match hints {
+            Some(hints) => format!("{base}\n\n{hints}"),
+            None => base,
+        }
     }
 
     /// Build tool definitions from the registry
+    ///
+    /// Tools flagged by the usage tracker as rarely useful are left out when
+    /// its policy enables trimming, and so are tools that cannot take the
+    /// parameters pinned with [`with_pinned_params`].
     fn build_tool_definitions(&self) -> Vec<ToolDefinition> {
+        let constraints = pinned_params()
+            .map(|pinned| pinned_constraints(&self.tool_registry, &pinned))
+            .unwrap_or_default();
         self.tool_registry
             .list_tools()
             .iter()
+            .filter(|tool| {
+                self.usage_tracker
+                    .as_ref()
+                    .is_none_or(|tracker| !tracker.should_trim(tool.name()))
+            })
+            .filter(|tool| accepts_pinned(tool.as_ref(), &constraints))
             .map(|tool| ToolDefinition::new(tool.name(), tool.description(), tool.input_schema()))
             .collect()
     }
 
-    /// Execute tool calls from an assistant message
-    async fn execute_tools(
+    /// Start a tool call in the background
+    ///
+    /// A call with the same name and input as one already started this turn
+    /// is not run again; it gets a copy of that call's result. Parameters
+    /// pinned with [`with_pinned_params`] replace the model's, and whatever
+    /// the model passed for the tool's [caller parameters](Tool::caller_params)
+    /// is dropped. A call to a tool that is not registered is not started at
+    /// all; it gets an error result so the model can pick another tool.
+    async fn start_tool(
+        &self,
+        id: String,
+        name: String,
+        mut input: Value,
+        started: &[PendingTool],
+        event_handler: Option<&Arc<dyn ExecutorEventHandler>>,
+    ) -> Result<PendingTool> {
+        let Some(tool) = self.tool_registry.get(&name) else {
+            warn!(tool_name = %name, tool_id = %id, "Model called an unknown tool");
+            return Ok(PendingTool {
+                id,
+                name,
+                input,
+                run: ToolRun::Unknown,
+            });
+        };
+
+        let pinned = pinned_params();
+        if let Some(fields) = input.as_object_mut() {
+            for key in tool.caller_params() {
+                fields.remove(*key);
+            }
+            if let Some(pinned) = &pinned {
+                fields.extend(
+                    pinned
+                        .iter()
+                        .map(|(key, value)| (key.clone(), value.clone())),
+                );
+            }
+        }
+
+        let duplicate = started.iter().any(|tool| {
+            matches!(tool.run, ToolRun::Started(_)) && tool.name == name && tool.input == input
+        });
+        if duplicate {
+            info!(tool_name = %name, tool_id = %id, "Skipping duplicate tool call");
+            return Ok(PendingTool {
+                id,
+                name,
+                input,
+                run: ToolRun::Duplicate,
+            });
+        }
+
+        // Log tool input (truncated for safety)
+        let input_preview: String = input.to_string().chars().take(500).collect();
+        info!(
+            tool_name = %name,
+            tool_id = %id,
+            input_preview = %input_preview,
+            "Executing tool"
+        );
+
+        // Emit tool start event
+        if let Some(handler) = event_handler {
+            handler.on_tool_start(&id, &name, &input).await;
+        }
+
+        // A tool that cannot take the pinned parameters would ignore them
+        let refused = pinned
+            .map(|pinned| pinned_constraints(&self.tool_registry, &pinned))
+            .filter(|constraints| !accepts_pinned(tool.as_ref(), constraints))
+            .map(|constraints| {
+                agent_core::Error::ProcessingFailed(format!(
+                    "Tool {name} does not support {}",
+                    constraints.join(", ")
+                ))
+            });
+
+        // Execute tool and measure time
+        let params = input.clone();
+        let task = tokio::spawn(async move {
+            let start_time = std::time::Instant::now();
+            let result = match refused {
+                Some(error) => Err(error),
+                None => tool.execute(params).await,
+            };
+            (result, start_time.elapsed())
+        });
+
+        Ok(PendingTool {
+            id,
+            name,
+            input,
+            run: ToolRun::Started(task),
+        })
+    }
+
+    /// Wait for the tool calls of an assistant message, in order
+    ///
+    /// Calls in `started` were begun while the message was streaming; the
+    /// rest start now. Returns one tool result message per call.
+    async fn finish_tools(
         &self,
         message: &Message,
+        mut started: Vec<PendingTool>,
         event_handler: Option<&Arc<dyn ExecutorEventHandler>>,
+        outcomes: &mut Vec<ToolCallOutcome>,
+        calls: &mut Vec<ToolCallRecord>,
     ) -> Result<Vec<Message>> {
-        let mut results = Vec::new();
-
         // Extract tool uses
         let tool_uses = message.tool_uses();
         info!(tool_count = tool_uses.len(), "Starting tool execution");
 
+        let mut pending: Vec<PendingTool> = Vec::new();
         for tool_use in tool_uses {
             if let ContentBlock::ToolUse { id, name, input } = tool_use {
-                // Log tool input (truncated for safety)
-                let input_preview: String = input.to_string().chars().take(500).collect();
-                info!(
-                    tool_name = %name,
-                    tool_id = %id,
-                    input_preview = %input_preview,
-                    "Executing tool"
-                );
+                let tool = match started.iter().position(|tool| tool.id == *id) {
+                    Some(index) => started.remove(index),
+                    None => {
+                        self.start_tool(
+                            id.clone(),
+                            name.clone(),
+                            input.clone(),
+                            &pending,
+                            event_handler,
+                        )
+                        .await?
+                    }
+                };
+                pending.push(tool);
+            }
+        }
+        // Anything else started while streaming is no longer wanted
+        drop(started);
 
-                // Emit tool start event
-                if let Some(handler) = event_handler {
-                    handler.on_tool_start(id, name, input).await;
+        let mut results = Vec::new();
+        // Calls already made this turn, with their results, so identical
+        // calls are only executed once
+        let mut executed: Vec<(String, Value, std::result::Result<String, String>)> = Vec::new();
+
+        for mut tool in pending {
+            let task = match &mut tool.run {
+                ToolRun::Started(task) => task,
+                ToolRun::Duplicate => {
+                    // Every tool use needs a result, so duplicates get a copy
+                    let result = executed
+                        .iter()
+                        .find(|(name, input, _)| *name == tool.name && *input == tool.input)
+                        .map(|(_, _, result)| result);
+                    results.push(match result {
+                        Some(Ok(content)) => Message::tool_result(tool.id.clone(), content.clone()),
+                        Some(Err(content)) => Message::tool_error(tool.id.clone(), content.clone()),
+                        None => Message::tool_error(
+                            tool.id.clone(),
+                            "Error: duplicate of a tool call that did not run".to_string(),
+                        ),
+                    });
+                    continue;
                 }
+                ToolRun::Unknown => {
+                    let available: Vec<String> = self
+                        .build_tool_definitions()
+                        .into_iter()
+                        .map(|tool| tool.name)
+                        .collect();
+                    results.push(Message::tool_error(
+                        tool.id.clone(),
+                        format!(
+                            "Error: Tool not found: {}. Available tools: {}",
+                            tool.name,
+                            available.join(", ")
+                        ),
+                    ));
+                    continue;
+                }
+            };
+            let (result, duration) = task.await.unwrap_or_else(|e| {
+                (
+                    Err(agent_core::Error::ProcessingFailed(format!(
+                        "Tool task failed: {e}"
+                    ))),
+                    Duration::ZERO,
+                )
+            });
+            let duration_ms = duration.as_millis() as u64;
+            let PendingTool {
+                id, name, input, ..
+            } = &tool;
+
+            match result {
+                Ok(result) => {
+                    // Convert result to string
+                    let result_str =
+                        serde_json::to_string(&result).unwrap_or_else(|_| result.to_string());
+                    let result_preview: String = result_str.chars().take(500).collect();
 
-                // Get tool from registry
-                let tool = self.tool_registry.get(name).ok_or_else(|| {
-                    agent_core::Error::ProcessingFailed(format!("Tool not found: {name}"))
-                })?;
-
-                // Execute tool and measure time
-                let start_time = std::time::Instant::now();
-                match tool.execute(input.clone()).await {
-                    Ok(result) => {
-                        let duration = start_time.elapsed();
-                        let duration_ms = duration.as_millis() as u64;
-                        // Convert result to string
-                        let result_str =
-                            serde_json::to_string(&result).unwrap_or_else(|_| result.to_string());
-                        let result_preview: String = result_str.chars().take(500).collect();
-
-                        info!(
-                            tool_name = %name,
-                            duration_ms = duration_ms,
-                            result_length = result_str.len(),
-                            result_preview = %result_preview,
-                            "Tool execution succeeded"
-                        );
+                    info!(
+                        tool_name = %name,
+                        duration_ms = duration_ms,
+                        result_length = result_str.len(),
+                        result_preview = %result_preview,
+                        "Tool execution succeeded"
+                    );
 
-                        // Emit tool done event
-                        if let Some(handler) = event_handler {
-                            handler
-                                .on_tool_done(id, name, Ok(&result), duration_ms)
-                                .await;
-                        }
+                    // Emit tool done event
+                    if let Some(handler) = event_handler {
+                        handler
+                            .on_tool_done(id, name, Ok(&result), duration_ms)
+                            .await;
+                    }
 
-                        results.push(Message::tool_result(id.clone(), result_str));
+                    calls.push(ToolCallRecord {
+                        name: name.clone(),
+                        input: input.clone(),
+                        success: true,
+                        duration_ms,
+                    });
+                    if let Some(tracker) = &self.usage_tracker {
+                        tracker.record_call(name, duration_ms, true);
+                        outcomes.push(ToolCallOutcome {
+                            name: name.clone(),
+                            input: input.clone(),
+                            output: result,
+                        });
                     }
-                    Err(e) => {
-                        let duration = start_time.elapsed();
-                        let duration_ms = duration.as_millis() as u64;
-                        let error_str = e.to_string();
-                        warn!(
-                            tool_name = %name,
-                            duration_ms = duration_ms,
-                            error = %e,
-                            "Tool execution failed"
-                        );
 
-                        // Emit tool done event with error
-                        if let Some(handler) = event_handler {
-                            handler
-                                .on_tool_done(id, name, Err(&error_str), duration_ms)
-                                .await;
-                        }
+                    executed.push((name.clone(), input.clone(), Ok(result_str.clone())));
+                    results.push(Message::tool_result(id.clone(), result_str));
+                }
+                Err(e) => {
+                    let error_str = e.to_string();
+                    warn!(
+                        tool_name = %name,
+                        duration_ms = duration_ms,
+                        error = %e,
+                        "Tool execution failed"
+                    );
 
-                        // Return error as tool result
-                        results.push(Message::tool_error(id.clone(), format!("Error: {e}")));
+                    // Emit tool done event with error
+                    if let Some(handler) = event_handler {
+                        handler
+                            .on_tool_done(id, name, Err(&error_str), duration_ms)
+                            .await;
                     }
+
+                    calls.push(ToolCallRecord {
+                        name: name.clone(),
+                        input: input.clone(),
+                        success: false,
+                        duration_ms,
+                    });
+                    if let Some(tracker) = &self.usage_tracker {
+                        tracker.record_call(name, duration_ms, false);
+                    }
+
+                    // Return error as tool result
+                    let error_content = format!("Error: {e}");
+                    executed.push((name.clone(), input.clone(), Err(error_content.clone())));
+                    results.push(Message::tool_error(id.clone(), error_content));
                 }
             }
         }
@@ -467,11 +1200,39 @@ impl AgentExecutor {
     }
 }
 
+/// A tool call that has been started but whose result is not collected yet
+struct PendingTool {
+    id: String,
+    name: String,
+    input: Value,
+    run: ToolRun,
+}
+
+enum ToolRun {
+    /// Running in a background task, which reports how long it took
+    Started(JoinHandle<(Result<Value>, Duration)>),
+    /// Same call as one started earlier this turn, sharing its result
+    Duplicate,
+    /// Call to a tool that is not registered; answered with an error
+    Unknown,
+}
+
+impl Drop for PendingTool {
+    /// Stop tools whose results are no longer wanted, e.g. when the
+    /// response they came from fails partway through
+    fn drop(&mut self) {
+        if let ToolRun::Started(task) = &self.run {
+            task.abort();
+        }
+    }
+}
+
 /// Builder for AgentExecutor
 pub struct AgentExecutorBuilder {
     provider: Option<Arc<dyn LLMProvider>>,
     tool_registry: Arc<ToolRegistry>,
     config: ExecutorConfig,
+    context_window: ContextWindowManager,
 }
 
 impl AgentExecutorBuilder {
@@ -481,6 +1242,7 @@ impl AgentExecutorBuilder {
             provider: None,
             tool_registry: Arc::new(ToolRegistry::new()),
             config: ExecutorConfig::default(),
+            context_window: ContextWindowManager::default(),
         }
     }
 
@@ -532,17 +1294,22 @@ impl AgentExecutorBuilder {
         self
     }
 
+    /// Set the context window manager
+    pub fn context_window(mut self, manager: ContextWindowManager) -> Self {
+        self.context_window = manager;
+        self
+    }
+
     /// Build the executor
     pub fn build(self) -> Result<AgentExecutor> {
         let provider = self.provider.ok_or_else(|| {
             agent_core::Error::InitializationFailed("Provider not set".to_string())
         })?;
 
-        Ok(AgentExecutor::new(
-            provider,
-            self.tool_registry,
-            self.config,
-        ))
+        Ok(
+            AgentExecutor::new(provider, self.tool_registry, self.config)
+                .with_context_window(self.context_window),
+        )
     }
 }
 
@@ -577,4 +1344,735 @@ mod tests {
         assert_eq!(config.max_iterations, 10);
         assert_eq!(config.model, "claude-sonnet-4-5-20250929");
     }
+
+    mod scripted {
+        use super::*;
+        use agent_llm::{CompletionResponse, MessageContent, Role, TokenUsage};
+        use agent_tools::Tool;
+        use serde_json::json;
+        use std::sync::Mutex;
+
+        /// Provider that returns a fixed sequence of responses
+        pub struct ScriptedProvider {
+            pub responses: Mutex<Vec<CompletionResponse>>,
+            pub systems: Mutex<Vec<Option<String>>>,
+        }
+
+        #[async_trait]
+        impl LLMProvider for ScriptedProvider {
+            async fn complete(
+                &self,
+                request: CompletionRequest,
+            ) -> agent_llm::Result<CompletionResponse> {
+                self.systems.lock().unwrap().push(request.system.clone());
+                Ok(self.responses.lock().unwrap().remove(0))
+            }
+
+            fn name(&self) -> &str {
+                "scripted"
+            }
+        }
+
+        pub struct PriceTool;
+
+        #[async_trait]
+        impl Tool for PriceTool {
+            async fn execute(&self, _params: Value) -> Result<Value> {
+                Ok(json!({"price": 187.42}))
+            }
+
+            fn name(&self) -> &'static str {
+                "price"
+            }
+
+            fn description(&self) -> &'static str {
+                "Get a price"
+            }
+
+            fn input_schema(&self) -> Value {
+                json!({"type": "object"})
+            }
+        }
+
+        pub fn tool_use_response() -> CompletionResponse {
+            CompletionResponse {
+                message: Message {
+                    role: Role::Assistant,
+                    content: Some(MessageContent::Blocks(vec![ContentBlock::ToolUse {
+                        id: "call_1".to_string(),
+                        name: "price".to_string(),
+                        input: json!({"symbol": "AAPL"}),
+                    }])),
+                },
+                stop_reason: StopReason::ToolUse,
+                usage: TokenUsage {
+                    input_tokens: 10,
+                    output_tokens: 5,
+                },
+            }
+        }
+
+        pub fn final_response(text: &str) -> CompletionResponse {
+            CompletionResponse {
+                message: Message::assistant(text.to_string()),
+                stop_reason: StopReason::EndTurn,
+                usage: TokenUsage {
+                    input_tokens: 10,
+                    output_tokens: 5,
+                },
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_usage_tracker_records_tool_calls() {
+        use crate::analytics::{ToolUsagePolicy, ToolUsageTracker};
+        use scripted::*;
+        use std::sync::Mutex;
+
+        let provider = Arc::new(ScriptedProvider {
+            responses: Mutex::new(vec![
+                tool_use_response(),
+                final_response("AAPL trades at 187.42."),
+                final_response("Done."),
+            ]),
+            systems: Mutex::new(Vec::new()),
+        });
+        let registry = Arc::new(ToolRegistry::new());
+        registry.register(Arc::new(PriceTool));
+
+        let tracker = Arc::new(ToolUsageTracker::with_policy(ToolUsagePolicy {
+            min_calls: 1,
+            prompt_hints: true,
+            ..ToolUsagePolicy::default()
+        }));
+        let executor = AgentExecutor::new(provider.clone(), registry, ExecutorConfig::default())
+            .with_usage_tracker(tracker.clone());
+
+        let answer = executor.run("Price of AAPL?".to_string()).await.unwrap();
+        assert_eq!(answer.to_string(), "AAPL trades at 187.42.");
+        assert!(answer.is_complete());
+        assert_eq!(answer.iterations, 2);
+        assert_eq!(answer.usage.total(), 30);
+        assert_eq!(answer.tool_calls.len(), 1);
+        assert_eq!(answer.tool_calls[0].name, "price");
+        assert!(answer.tool_calls[0].success);
+
+        let stats = tracker.stats("price").unwrap();
+        assert_eq!(stats.calls, 1);
+        assert_eq!(stats.used_in_answer, 1);
+
+        // The next run sees the selection hint in its system prompt
+        executor.run("Anything else?".to_string()).await.unwrap();
+        let systems = provider.systems.lock().unwrap();
+        let last = systems.last().unwrap().as_deref().unwrap();
+        assert!(last.contains("Prefer `price`"));
+    }
+
+    #[tokio::test]
+    async fn test_token_usage_reaches_tracker_and_context() {
+        use crate::agents::DelegatingAgent;
+        use crate::runtime::AgentRuntime;
+        use crate::usage;
+        use agent_core::Agent;
+        use scripted::*;
+        use std::sync::Mutex;
+
+        let provider = Arc::new(ScriptedProvider {
+            responses: Mutex::new(vec![tool_use_response(), final_response("187.42")]),
+            systems: Mutex::new(Vec::new()),
+        });
+        let runtime = Arc::new(AgentRuntime::builder().provider(provider).build().unwrap());
+        runtime.tools().register(Arc::new(PriceTool));
+        let worker = runtime.create_tool_agent(ExecutorConfig::default(), "worker");
+        let manager = DelegatingAgent::builder(runtime.clone(), "manager")
+            .add_agent("worker", Arc::new(worker))
+            .router(|_, _| "worker".to_string())
+            .build()
+            .unwrap();
+
+        let mut context = Context::new();
+        manager
+            .process("Price of AAPL?".to_string(), &mut context)
+            .await
+            .unwrap();
+
+        // The request's usage comes back up through the delegating agent
+        let request = usage::from_context(&context);
+        assert_eq!(request.requests(), 2);
+        assert_eq!(request.total().total(), 30);
+        assert_eq!(request.models[0].provider, "scripted");
+        // Priced at the list price of the default Sonnet model
+        assert!((request.total_cost_usd() - 0.000_21).abs() < 1e-12);
+
+        assert_eq!(runtime.token_tracker().summary(), request);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_tool_calls_run_once() {
+        use agent_llm::{MessageContent, Role};
+        use agent_tools::Tool;
+        use scripted::*;
+        use serde_json::json;
+        use std::sync::Mutex;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        struct CountingTool(AtomicUsize);
+
+        #[async_trait]
+        impl Tool for CountingTool {
+            async fn execute(&self, _params: Value) -> Result<Value> {
+                Ok(json!({"call": self.0.fetch_add(1, Ordering::SeqCst) + 1}))
+            }
+
+            fn name(&self) -> &'static str {
+                "price"
+            }
+
+            fn description(&self) -> &'static str {
+                "Get a price"
+            }
+
+            fn input_schema(&self) -> Value {
+                json!({"type": "object"})
+            }
+        }
+
+        let call = |id: &str, symbol: &str| ContentBlock::ToolUse {
+            id: id.to_string(),
+            name: "price".to_string(),
+            input: json!({"symbol": symbol}),
+        };
+        let message = Message {
+            role: Role::Assistant,
+            content: Some(MessageContent::Blocks(vec![
+                call("call_1", "AAPL"),
+                call("call_2", "AAPL"),
+                call("call_3", "MSFT"),
+            ])),
+        };
+
+        let provider = Arc::new(ScriptedProvider {
+            responses: Mutex::new(Vec::new()),
+            systems: Mutex::new(Vec::new()),
+        });
+        let registry = Arc::new(ToolRegistry::new());
+        let tool = Arc::new(CountingTool(AtomicUsize::new(0)));
+        registry.register(tool.clone());
+        let executor = AgentExecutor::new(provider, registry, ExecutorConfig::default());
+
+        let results = executor
+            .finish_tools(&message, Vec::new(), None, &mut Vec::new(), &mut Vec::new())
+            .await
+            .unwrap();
+
+        assert_eq!(tool.0.load(Ordering::SeqCst), 2);
+        assert_eq!(results.len(), 3);
+        let content = |message: &Message| serde_json::to_value(message).unwrap().to_string();
+        assert!(content(&results[1]).contains("call_2"));
+        assert!(content(&results[1]).contains(r#"{\"call\":1}"#));
+        assert!(content(&results[2]).contains(r#"{\"call\":2}"#));
+    }
+
+    #[tokio::test]
+    async fn test_streamed_tool_calls_start_early() {
+        use agent_tools::Tool;
+        use scripted::*;
+        use serde_json::json;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tokio::sync::Notify;
+
+        /// Streams a tool call, then only finishes the response once the
+        /// tool is running
+        struct StreamingProvider {
+            tool_running: Arc<Notify>,
+            turns: AtomicUsize,
+        }
+
+        #[async_trait]
+        impl LLMProvider for StreamingProvider {
+            async fn complete(
+                &self,
+                _request: CompletionRequest,
+            ) -> agent_llm::Result<CompletionResponse> {
+                Ok(final_response("AAPL trades at 187.42."))
+            }
+
+            async fn complete_streaming(
+                &self,
+                request: CompletionRequest,
+                blocks: &UnboundedSender<ContentBlock>,
+            ) -> agent_llm::Result<CompletionResponse> {
+                if self.turns.fetch_add(1, Ordering::SeqCst) > 0 {
+                    return self.complete(request).await;
+                }
+                let response = tool_use_response();
+                for block in response.message.tool_uses() {
+                    blocks.send(block.clone()).unwrap();
+                }
+                self.tool_running.notified().await;
+                Ok(response)
+            }
+
+            fn name(&self) -> &str {
+                "streaming"
+            }
+        }
+
+        struct SignalingTool(Arc<Notify>);
+
+        #[async_trait]
+        impl Tool for SignalingTool {
+            async fn execute(&self, _params: Value) -> Result<Value> {
+                self.0.notify_one();
+                Ok(json!({"price": 187.42}))
+            }
+
+            fn name(&self) -> &'static str {
+                "price"
+            }
+
+            fn description(&self) -> &'static str {
+                "Get a price"
+            }
+
+            fn input_schema(&self) -> Value {
+                json!({"type": "object"})
+            }
+        }
+
+        let tool_running = Arc::new(Notify::new());
+        let provider = Arc::new(StreamingProvider {
+            tool_running: tool_running.clone(),
+            turns: AtomicUsize::new(0),
+        });
+        let registry = Arc::new(ToolRegistry::new());
+        registry.register(Arc::new(SignalingTool(tool_running)));
+        let executor = AgentExecutor::new(provider, registry, ExecutorConfig::default());
+
+        // Waiting for the full response before running the tool would hang
+        let answer = tokio::time::timeout(
+            Duration::from_secs(5),
+            executor.run("Price of AAPL?".to_string()),
+        )
+        .await
+        .expect("tool should start before the response completes")
+        .unwrap();
+
+        assert_eq!(answer.to_string(), "AAPL trades at 187.42.");
+        // The streamed call is not run a second time for the final message
+        assert_eq!(answer.tool_calls.len(), 1);
+        assert!(answer.tool_calls[0].success);
+    }
+
+    #[tokio::test]
+    async fn test_progress_events() {
+        use scripted::*;
+        use std::sync::Mutex;
+
+        #[derive(Default)]
+        struct Recorder(Mutex<Vec<String>>);
+
+        #[async_trait]
+        impl ExecutorEventHandler for Recorder {
+            async fn on_iteration_start(&self, iteration: usize, max_iterations: usize) {
+                let label = progress_label(iteration, max_iterations, "analyzing");
+                self.0.lock().unwrap().push(label);
+            }
+
+            async fn on_llm_request(&self, iteration: usize, estimated_input_tokens: usize) {
+                assert!(estimated_input_tokens > 0);
+                self.0.lock().unwrap().push(format!("request {iteration}"));
+            }
+
+            async fn on_llm_response(
+                &self,
+                iteration: usize,
+                usage: &TokenUsage,
+                stop_reason: StopReason,
+            ) {
+                self.0.lock().unwrap().push(format!(
+                    "response {iteration} {} {stop_reason:?}",
+                    usage.total()
+                ));
+            }
+
+            async fn on_tool_start(&self, _id: &str, name: &str, _input: &Value) {
+                self.0.lock().unwrap().push(format!("tool {name}"));
+            }
+        }
+
+        let provider = Arc::new(ScriptedProvider {
+            responses: Mutex::new(vec![tool_use_response(), final_response("Done.")]),
+            systems: Mutex::new(Vec::new()),
+        });
+        let registry = Arc::new(ToolRegistry::new());
+        registry.register(Arc::new(PriceTool));
+        let executor = AgentExecutorBuilder::new()
+            .provider(provider)
+            .tool_registry(registry)
+            .max_iterations(5)
+            .build()
+            .unwrap();
+
+        let recorder = Arc::new(Recorder::default());
+        executor
+            .run_with_history_and_handler("Price?".to_string(), Vec::new(), recorder.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            [
+                "Step 1/5: analyzing",
+                "request 1",
+                "response 1 15 ToolUse",
+                "tool price",
+                "Step 2/5: analyzing",
+                "request 2",
+                "response 2 15 EndTurn",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_scoped_event_handler() {
+        use scripted::*;
+        use std::sync::Mutex;
+
+        #[derive(Default)]
+        struct Recorder(Mutex<Vec<String>>);
+
+        #[async_trait]
+        impl ExecutorEventHandler for Recorder {
+            async fn on_text(&self, iteration: usize, text: &str) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("text {iteration} {text}"));
+            }
+
+            async fn on_tool_start(&self, _id: &str, name: &str, _input: &Value) {
+                self.0.lock().unwrap().push(format!("tool {name}"));
+            }
+        }
+
+        let provider = Arc::new(ScriptedProvider {
+            responses: Mutex::new(vec![tool_use_response(), final_response("Done.")]),
+            systems: Mutex::new(Vec::new()),
+        });
+        let registry = Arc::new(ToolRegistry::new());
+        registry.register(Arc::new(PriceTool));
+        let executor = AgentExecutor::new(provider, registry, ExecutorConfig::default());
+
+        // A plain run inside the scope reports to the scoped handler
+        let recorder = Arc::new(Recorder::default());
+        let answer = with_event_handler(recorder.clone(), executor.run("Price?".to_string()))
+            .await
+            .unwrap();
+
+        assert_eq!(answer.to_string(), "Done.");
+        assert_eq!(*recorder.0.lock().unwrap(), ["tool price", "text 2 Done."]);
+    }
+
+    #[tokio::test]
+    async fn test_pinned_params() {
+        use agent_llm::{MessageContent, Role};
+        use scripted::*;
+        use serde_json::json;
+        use std::sync::Mutex;
+
+        /// Echoes its parameters; declares `as_of`
+        struct EchoTool;
+
+        #[async_trait]
+        impl Tool for EchoTool {
+            async fn execute(&self, params: Value) -> Result<Value> {
+                Ok(params)
+            }
+
+            fn name(&self) -> &'static str {
+                "history"
+            }
+
+            fn description(&self) -> &'static str {
+                "Get price history"
+            }
+
+            fn input_schema(&self) -> Value {
+                json!({"type": "object", "properties": {"symbol": {}, "as_of": {}}})
+            }
+        }
+
+        let call = |id: &str, name: &str, input: Value| ContentBlock::ToolUse {
+            id: id.to_string(),
+            name: name.to_string(),
+            input,
+        };
+        let message = Message {
+            role: Role::Assistant,
+            content: Some(MessageContent::Blocks(vec![
+                call(
+                    "call_1",
+                    "history",
+                    json!({"symbol": "NVDA", "as_of": "2099-01-01"}),
+                ),
+                call("call_2", "price", json!({"symbol": "NVDA"})),
+            ])),
+        };
+
+        let provider = Arc::new(ScriptedProvider {
+            responses: Mutex::new(Vec::new()),
+            systems: Mutex::new(Vec::new()),
+        });
+        let registry = Arc::new(ToolRegistry::new());
+        registry.register(Arc::new(EchoTool));
+        registry.register(Arc::new(PriceTool));
+        let executor = AgentExecutor::new(provider, registry, ExecutorConfig::default());
+
+        let mut pinned = Map::new();
+        pinned.insert("as_of".to_string(), json!("2023-01-31"));
+        let (tools, results) = with_pinned_params(pinned, async {
+            let tools = executor.build_tool_definitions();
+            let results = executor
+                .finish_tools(&message, Vec::new(), None, &mut Vec::new(), &mut Vec::new())
+                .await;
+            (tools, results)
+        })
+        .await;
+
+        // The price tool cannot be limited to the date, so it is not offered
+        let names: Vec<&str> = tools.iter().map(|tool| tool.name.as_str()).collect();
+        assert_eq!(names, ["history"]);
+        let results = results.unwrap();
+        let content = |message: &Message| serde_json::to_value(message).unwrap().to_string();
+        assert!(content(&results[0]).contains(r#"\"as_of\":\"2023-01-31\""#));
+        assert!(content(&results[1]).contains("Tool price does not support as_of"));
+
+        // Outside the scope nothing is pinned
+        assert_eq!(executor.build_tool_definitions().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_caller_params_are_not_taken_from_the_model() {
+        use agent_llm::{MessageContent, Role};
+        use scripted::*;
+        use serde_json::json;
+        use std::sync::Mutex;
+
+        /// Echoes its parameters; takes `user_id` from its caller only
+        struct AccountTool;
+
+        #[async_trait]
+        impl Tool for AccountTool {
+            async fn execute(&self, params: Value) -> Result<Value> {
+                Ok(params)
+            }
+
+            fn name(&self) -> &'static str {
+                "account"
+            }
+
+            fn description(&self) -> &'static str {
+                "Get the user's account"
+            }
+
+            fn input_schema(&self) -> Value {
+                json!({"type": "object", "properties": {}})
+            }
+
+            fn caller_params(&self) -> &[&'static str] {
+                &["user_id"]
+            }
+        }
+
+        let message = Message {
+            role: Role::Assistant,
+            content: Some(MessageContent::Blocks(vec![ContentBlock::ToolUse {
+                id: "call_1".to_string(),
+                name: "account".to_string(),
+                input: json!({"user_id": "someone-else"}),
+            }])),
+        };
+
+        let provider = Arc::new(ScriptedProvider {
+            responses: Mutex::new(Vec::new()),
+            systems: Mutex::new(Vec::new()),
+        });
+        let registry = Arc::new(ToolRegistry::new());
+        registry.register(Arc::new(AccountTool));
+        registry.register(Arc::new(PriceTool));
+        let executor = AgentExecutor::new(provider, registry, ExecutorConfig::default());
+        let content = |message: &Message| serde_json::to_value(message).unwrap().to_string();
+
+        // The pinned user replaces the model's, and tools without it stay offered
+        let mut pinned = Map::new();
+        pinned.insert("user_id".to_string(), json!("42"));
+        let (tools, results) = with_pinned_params(pinned, async {
+            let tools = executor.build_tool_definitions();
+            let results = executor
+                .finish_tools(&message, Vec::new(), None, &mut Vec::new(), &mut Vec::new())
+                .await;
+            (tools, results)
+        })
+        .await;
+        assert_eq!(tools.len(), 2);
+        let result = content(&results.unwrap()[0]);
+        assert!(result.contains(r#"\"user_id\":\"42\""#), "{result}");
+        assert!(!result.contains("someone-else"), "{result}");
+
+        // Without a pin the model's value is dropped all the same
+        let results = executor
+            .finish_tools(&message, Vec::new(), None, &mut Vec::new(), &mut Vec::new())
+            .await
+            .unwrap();
+        assert!(!content(&results[0]).contains("user_id"));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_tool_returns_an_error_result() {
+        use agent_llm::{CompletionResponse, MessageContent, Role};
+        use scripted::*;
+        use serde_json::json;
+        use std::sync::Mutex;
+
+        #[derive(Default)]
+        struct Recorder(Mutex<Vec<String>>);
+
+        #[async_trait]
+        impl ExecutorEventHandler for Recorder {
+            async fn on_tool_start(&self, _id: &str, name: &str, _input: &Value) {
+                self.0.lock().unwrap().push(name.to_string());
+            }
+        }
+
+        let message = Message {
+            role: Role::Assistant,
+            content: Some(MessageContent::Blocks(vec![ContentBlock::ToolUse {
+                id: "call_1".to_string(),
+                name: "quote".to_string(),
+                input: json!({"symbol": "AAPL"}),
+            }])),
+        };
+
+        let provider = Arc::new(ScriptedProvider {
+            responses: Mutex::new(vec![
+                CompletionResponse {
+                    message: message.clone(),
+                    ..tool_use_response()
+                },
+                tool_use_response(),
+                final_response("AAPL trades at 187.42."),
+            ]),
+            systems: Mutex::new(Vec::new()),
+        });
+        let registry = Arc::new(ToolRegistry::new());
+        registry.register(Arc::new(PriceTool));
+        let executor = AgentExecutor::new(provider, registry, ExecutorConfig::default());
+
+        // The model is told which tools exist, and no tool start is reported
+        let recorder: Arc<dyn ExecutorEventHandler> = Arc::new(Recorder::default());
+        let results = executor
+            .finish_tools(
+                &message,
+                Vec::new(),
+                Some(&recorder),
+                &mut Vec::new(),
+                &mut Vec::new(),
+            )
+            .await
+            .unwrap();
+        let result = serde_json::to_value(&results[0]).unwrap().to_string();
+        assert!(
+            result.contains("Tool not found: quote. Available tools: price"),
+            "{result}"
+        );
+        assert!(result.contains(r#""is_error":true"#), "{result}");
+
+        // The run goes on, and the model recovers with a tool that exists
+        let recorder = Arc::new(Recorder::default());
+        let answer = executor
+            .run_with_history_and_handler("Price?".to_string(), Vec::new(), recorder.clone())
+            .await
+            .unwrap();
+        assert_eq!(answer.to_string(), "AAPL trades at 187.42.");
+        assert_eq!(answer.tool_calls.len(), 1);
+        assert_eq!(*recorder.0.lock().unwrap(), ["price"]);
+    }
+
+    fn tiny_context_window() -> ContextWindowManager {
+        use crate::context_window::ContextWindowConfig;
+
+        let mut config = ContextWindowConfig::default();
+        config.model_overrides.insert("tiny".to_string(), 400);
+        ContextWindowManager::new(config)
+    }
+
+    #[tokio::test]
+    async fn test_context_window_summarizes_long_history() {
+        use scripted::*;
+        use std::sync::Mutex;
+
+        let provider = Arc::new(ScriptedProvider {
+            responses: Mutex::new(vec![
+                final_response("Earlier the user asked about AAPL."),
+                final_response("Final answer."),
+            ]),
+            systems: Mutex::new(Vec::new()),
+        });
+        let executor = AgentExecutorBuilder::new()
+            .provider(provider.clone())
+            .model("tiny")
+            .max_tokens(100)
+            .context_window(tiny_context_window())
+            .build()
+            .unwrap();
+
+        let mut history = Vec::new();
+        for i in 0..4 {
+            history.push(Message::user(format!("question {i} {}", "q".repeat(200))));
+            history.push(Message::assistant(format!(
+                "answer {i} {}",
+                "a".repeat(200)
+            )));
+        }
+
+        let answer = executor
+            .run_with_history("And now?".to_string(), history)
+            .await
+            .unwrap();
+        assert_eq!(answer.text, "Final answer.");
+        // The summary request counts towards the run's usage
+        assert_eq!(answer.iterations, 1);
+        assert_eq!(answer.usage.input_tokens, 20);
+
+        let systems = provider.systems.lock().unwrap();
+        assert_eq!(systems.len(), 2);
+        assert_eq!(systems[0].as_deref(), Some(SUMMARY_SYSTEM_PROMPT));
+    }
+
+    #[tokio::test]
+    async fn test_context_window_rejects_oversized_turn() {
+        use scripted::*;
+        use std::sync::Mutex;
+
+        let provider = Arc::new(ScriptedProvider {
+            responses: Mutex::new(Vec::new()),
+            systems: Mutex::new(Vec::new()),
+        });
+        let executor = AgentExecutorBuilder::new()
+            .provider(provider.clone())
+            .model("tiny")
+            .max_tokens(100)
+            .context_window(tiny_context_window())
+            .build()
+            .unwrap();
+
+        let result = executor.run("x".repeat(5_000)).await;
+        let Err(agent_core::Error::ProcessingFailed(message)) = result else {
+            panic!("expected context window error");
+        };
+        assert!(message.contains("context window"));
+        assert!(provider.systems.lock().unwrap().is_empty());
+    }
 }
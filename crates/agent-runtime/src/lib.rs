@@ -5,12 +5,28 @@
 //! management, and concrete agent implementations.
 
 pub mod agents;
+pub mod analytics;
+pub mod context_window;
 pub mod executor;
+pub mod llm_log;
+pub mod registry;
 pub mod runtime;
+pub mod usage;
 
 // Re-export key types
 pub use agents::{DelegatingAgent, DelegatingAgentBuilder, SimpleAgent, SimpleConfig, ToolAgent};
+pub use analytics::{
+    ToolCallOutcome, ToolUsagePolicy, ToolUsageReport, ToolUsageStats, ToolUsageTracker,
+};
+pub use context_window::{
+    ContextStrategy, ContextUsage, ContextWindowConfig, ContextWindowManager,
+};
 pub use executor::{
     AgentExecutor, AgentExecutorBuilder, ExecutorConfig, ExecutorEventHandler, NoOpEventHandler,
+    PINNED_PARAMS_CONTEXT_KEY, RunResult, ToolCallRecord, progress_label, with_event_handler,
+    with_pinned_params,
 };
+pub use llm_log::{LlmLogConfig, LlmLogger};
+pub use registry::{AgentAvailability, AgentRegistry, RoutingTable};
 pub use runtime::{AgentRuntime, AgentRuntimeBuilder, RuntimeConfig};
+pub use usage::{ModelPricing, ModelUsage, UsageSummary, UsageTracker};
@@ -0,0 +1,219 @@
+//! Registry of agents and their capabilities
+//!
+//! The AgentRegistry collects agents under routing keys and answers questions
+//! about them from their [`AgentCapabilities`]: which agents handle an
+//! intent, which are usable given the configured API keys, and what the
+//! resulting routing table looks like.
+
+use agent_core::{Agent, AgentCapabilities};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Intent → agent keys, for the agents able to handle each intent
+pub type RoutingTable = BTreeMap<String, Vec<String>>;
+
+/// Agents keyed by routing key
+///
+/// # Example
+///
+/// ```no_run
+/// use agent_runtime::AgentRegistry;
+/// use std::sync::Arc;
+///
+/// let mut registry = AgentRegistry::new();
+/// registry.register(Arc::new(news_agent));
+/// registry.register_as("macro", Arc::new(macro_agent));
+///
+/// let table = registry.routing_table(|key| std::env::var(key).is_ok());
+/// ```
+#[derive(Clone, Default)]
+pub struct AgentRegistry {
+    agents: BTreeMap<String, Arc<dyn Agent>>,
+}
+
+/// An agent's capabilities and whether its required keys are configured
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgentAvailability {
+    /// Routing key of the agent
+    pub key: String,
+    /// What the agent declares it can do
+    pub capabilities: AgentCapabilities,
+    /// Required API keys that are not configured
+    pub missing_keys: Vec<String>,
+}
+
+impl AgentAvailability {
+    /// Whether every required key is configured
+    pub fn is_available(&self) -> bool {
+        self.missing_keys.is_empty()
+    }
+}
+
+impl AgentRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register an agent under its own name
+    pub fn register(&mut self, agent: Arc<dyn Agent>) {
+        let key = agent.name().to_string();
+        self.agents.insert(key, agent);
+    }
+
+    /// Register an agent under a routing key
+    pub fn register_as(&mut self, key: impl Into<String>, agent: Arc<dyn Agent>) {
+        self.agents.insert(key.into(), agent);
+    }
+
+    /// Get an agent by routing key
+    pub fn get(&self, key: &str) -> Option<&Arc<dyn Agent>> {
+        self.agents.get(key)
+    }
+
+    /// Number of registered agents
+    pub fn len(&self) -> usize {
+        self.agents.len()
+    }
+
+    /// Whether no agents are registered
+    pub fn is_empty(&self) -> bool {
+        self.agents.is_empty()
+    }
+
+    /// Routing keys of all agents, sorted
+    pub fn keys(&self) -> Vec<&str> {
+        self.agents.keys().map(String::as_str).collect()
+    }
+
+    /// Capabilities of every agent, by routing key
+    pub fn capabilities(&self) -> Vec<(&str, AgentCapabilities)> {
+        self.agents
+            .iter()
+            .map(|(key, agent)| (key.as_str(), agent.capabilities()))
+            .collect()
+    }
+
+    /// Keys of the agents that handle an intent
+    pub fn handlers(&self, intent: &str) -> Vec<&str> {
+        self.agents
+            .iter()
+            .filter(|(_, agent)| agent.capabilities().handles(intent))
+            .map(|(key, _)| key.as_str())
+            .collect()
+    }
+
+    /// Capabilities and availability of every agent
+    ///
+    /// `is_configured` is called with each required key's environment
+    /// variable name.
+    pub fn availability(&self, is_configured: impl Fn(&str) -> bool) -> Vec<AgentAvailability> {
+        self.agents
+            .iter()
+            .map(|(key, agent)| {
+                let capabilities = agent.capabilities();
+                let missing_keys = capabilities
+                    .missing_keys(&is_configured)
+                    .into_iter()
+                    .map(ToString::to_string)
+                    .collect();
+                AgentAvailability {
+                    key: key.clone(),
+                    capabilities,
+                    missing_keys,
+                }
+            })
+            .collect()
+    }
+
+    /// Routing table over the agents whose required keys are configured
+    pub fn routing_table(&self, is_configured: impl Fn(&str) -> bool) -> RoutingTable {
+        let mut table = RoutingTable::new();
+        for agent in self.availability(is_configured) {
+            if !agent.is_available() {
+                continue;
+            }
+            for intent in agent.capabilities.intents {
+                table.entry(intent).or_default().push(agent.key.clone());
+            }
+        }
+        table
+    }
+}
+
+impl std::fmt::Debug for AgentRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AgentRegistry")
+            .field("agents", &self.keys())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent_core::{Context, Result};
+    use async_trait::async_trait;
+
+    struct StubAgent {
+        name: &'static str,
+        capabilities: AgentCapabilities,
+    }
+
+    #[async_trait]
+    impl Agent for StubAgent {
+        async fn process(&self, input: String, _context: &mut Context) -> Result<String> {
+            Ok(input)
+        }
+
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn capabilities(&self) -> AgentCapabilities {
+            self.capabilities.clone()
+        }
+    }
+
+    fn registry() -> AgentRegistry {
+        let mut registry = AgentRegistry::new();
+        registry.register(Arc::new(StubAgent {
+            name: "news",
+            capabilities: AgentCapabilities::new("News")
+                .with_intent("news")
+                .with_required_key("FINNHUB_API_KEY"),
+        }));
+        registry.register(Arc::new(StubAgent {
+            name: "quotes",
+            capabilities: AgentCapabilities::new("Quotes")
+                .with_intent("price")
+                .with_intent("news"),
+        }));
+        registry
+    }
+
+    #[test]
+    fn test_handlers_and_availability() {
+        let registry = registry();
+        assert_eq!(registry.keys(), vec!["news", "quotes"]);
+        assert_eq!(registry.handlers("news"), vec!["news", "quotes"]);
+        assert!(registry.handlers("macro").is_empty());
+
+        let availability = registry.availability(|_| false);
+        assert_eq!(availability[0].missing_keys, vec!["FINNHUB_API_KEY"]);
+        assert!(!availability[0].is_available());
+        assert!(availability[1].is_available());
+    }
+
+    #[test]
+    fn test_routing_table_skips_unconfigured_agents() {
+        let registry = registry();
+
+        let table = registry.routing_table(|_| false);
+        assert_eq!(table["news"], vec!["quotes"]);
+        assert_eq!(table["price"], vec!["quotes"]);
+
+        let table = registry.routing_table(|_| true);
+        assert_eq!(table["news"], vec!["news", "quotes"]);
+    }
+}
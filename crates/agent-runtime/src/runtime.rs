@@ -12,7 +12,10 @@ use std::sync::Arc;
 use tracing::{info, warn};
 
 use crate::agents::{SimpleAgent, SimpleConfig, ToolAgent};
+use crate::analytics::ToolUsageTracker;
 use crate::executor::{AgentExecutor, ExecutorConfig};
+use crate::llm_log::LlmLogger;
+use crate::usage::UsageTracker;
 
 /// Configuration for the agent runtime
 #[derive(Debug, Clone)]
@@ -25,6 +28,9 @@ pub struct RuntimeConfig {
 
     /// Path to MCP configuration file
     pub mcp_config_path: Option<PathBuf>,
+
+    /// Maximum number of nested delegating agents a request may pass through
+    pub max_delegation_depth: usize,
 }
 
 impl Default for RuntimeConfig {
@@ -33,6 +39,7 @@ impl Default for RuntimeConfig {
             default_max_iterations: 10,
             default_model: "claude-sonnet-4-5-20250929".to_string(),
             mcp_config_path: None,
+            max_delegation_depth: 5,
         }
     }
 }
@@ -73,6 +80,9 @@ pub struct AgentRuntime {
     tool_registry: Arc<ToolRegistry>,
     config: RuntimeConfig,
     mcp_config: Option<Arc<MCPConfig>>,
+    usage_tracker: Option<Arc<ToolUsageTracker>>,
+    token_tracker: Arc<UsageTracker>,
+    llm_logger: Option<Arc<LlmLogger>>,
 }
 
 impl AgentRuntime {
@@ -88,9 +98,25 @@ impl AgentRuntime {
             tool_registry,
             config,
             mcp_config,
+            usage_tracker: None,
+            token_tracker: Arc::new(UsageTracker::new()),
+            llm_logger: None,
         }
     }
 
+    /// Use a token usage tracker shared with other runtimes, or one with
+    /// custom prices, for all agents created afterwards
+    pub fn with_token_tracker(mut self, tracker: Arc<UsageTracker>) -> Self {
+        self.token_tracker = tracker;
+        self
+    }
+
+    /// Attach a tool usage tracker shared by all tool agents created afterwards
+    pub fn with_usage_tracker(mut self, tracker: Arc<ToolUsageTracker>) -> Self {
+        self.usage_tracker = Some(tracker);
+        self
+    }
+
     /// Create a new runtime builder
     pub fn builder() -> AgentRuntimeBuilder {
         AgentRuntimeBuilder::new()
@@ -116,6 +142,47 @@ impl AgentRuntime {
         self.mcp_config.as_ref()
     }
 
+    /// Log complete LLM exchanges of all tool agents created afterwards
+    pub fn with_llm_logger(mut self, logger: Arc<LlmLogger>) -> Self {
+        self.llm_logger = Some(logger);
+        self
+    }
+
+    /// Get a reference to the LLM exchange logger
+    pub fn llm_logger(&self) -> Option<&Arc<LlmLogger>> {
+        self.llm_logger.as_ref()
+    }
+
+    /// Get a reference to the tool usage tracker
+    pub fn usage_tracker(&self) -> Option<&Arc<ToolUsageTracker>> {
+        self.usage_tracker.as_ref()
+    }
+
+    /// Get the tracker adding up the tokens and cost of every LLM request
+    /// made by this runtime's agents
+    pub fn token_tracker(&self) -> &Arc<UsageTracker> {
+        &self.token_tracker
+    }
+
+    /// Create an executor with the token tracker, attaching the tool usage
+    /// tracker and LLM logger if configured
+    fn create_executor(
+        &self,
+        registry: Arc<ToolRegistry>,
+        config: ExecutorConfig,
+        name: &str,
+    ) -> AgentExecutor {
+        let mut executor = AgentExecutor::new(self.provider.clone(), registry, config)
+            .with_token_tracker(self.token_tracker.clone());
+        if let Some(tracker) = &self.usage_tracker {
+            executor = executor.with_usage_tracker(tracker.clone());
+        }
+        if let Some(logger) = &self.llm_logger {
+            executor = executor.with_llm_logger(logger.clone(), name);
+        }
+        executor
+    }
+
     /// Create a simple agent (LLM only, no tools)
     ///
     /// # Arguments
@@ -132,6 +199,7 @@ impl AgentRuntime {
         name: impl Into<String>,
     ) -> SimpleAgent {
         SimpleAgent::new(self.provider.clone(), config, name.into())
+            .with_token_tracker(self.token_tracker.clone())
     }
 
     /// Create a tool-using agent (with LLM loop and tool execution)
@@ -145,9 +213,9 @@ impl AgentRuntime {
     ///
     /// A new ToolAgent instance
     pub fn create_tool_agent(&self, config: ExecutorConfig, name: impl Into<String>) -> ToolAgent {
-        let executor =
-            AgentExecutor::new(self.provider.clone(), self.tool_registry.clone(), config);
-        ToolAgent::new(executor, name.into())
+        let name = name.into();
+        let executor = self.create_executor(self.tool_registry.clone(), config, &name);
+        ToolAgent::new(executor, name)
     }
 
     /// Create a tool-using agent with MCP support
@@ -243,7 +311,7 @@ impl AgentRuntime {
 
         // Create tool agent with the enhanced registry
         let registry = Arc::new(registry);
-        let executor = AgentExecutor::new(self.provider.clone(), registry, config);
+        let executor = self.create_executor(registry, config, &agent_name);
         Ok(ToolAgent::new(executor, agent_name))
     }
 }
@@ -254,6 +322,9 @@ pub struct AgentRuntimeBuilder {
     tool_registry: Option<Arc<ToolRegistry>>,
     config: RuntimeConfig,
     mcp_config: Option<Arc<MCPConfig>>,
+    usage_tracker: Option<Arc<ToolUsageTracker>>,
+    token_tracker: Option<Arc<UsageTracker>>,
+    llm_logger: Option<Arc<LlmLogger>>,
 }
 
 impl AgentRuntimeBuilder {
@@ -264,6 +335,9 @@ impl AgentRuntimeBuilder {
             tool_registry: None,
             config: RuntimeConfig::default(),
             mcp_config: None,
+            usage_tracker: None,
+            token_tracker: None,
+            llm_logger: None,
         }
     }
 
@@ -291,6 +365,27 @@ impl AgentRuntimeBuilder {
         self
     }
 
+    /// Set the tool usage tracker
+    pub fn usage_tracker(mut self, tracker: Arc<ToolUsageTracker>) -> Self {
+        self.usage_tracker = Some(tracker);
+        self
+    }
+
+    /// Set the token usage tracker; a fresh one is created if not set
+    pub fn token_tracker(mut self, tracker: Arc<UsageTracker>) -> Self {
+        self.token_tracker = Some(tracker);
+        self
+    }
+
+    /// Set the LLM exchange logger
+    ///
+    /// If not set, a logger is created from the `AGENT_LLM_LOG*` environment
+    /// variables when `AGENT_LLM_LOG` is present.
+    pub fn llm_logger(mut self, logger: Arc<LlmLogger>) -> Self {
+        self.llm_logger = Some(logger);
+        self
+    }
+
     /// Load MCP configuration from file
     ///
     /// # Errors
@@ -315,6 +410,12 @@ impl AgentRuntimeBuilder {
         self
     }
 
+    /// Set the maximum delegation depth
+    pub fn max_delegation_depth(mut self, max: usize) -> Self {
+        self.config.max_delegation_depth = max;
+        self
+    }
+
     /// Set the MCP configuration path
     pub fn mcp_config_path(mut self, path: PathBuf) -> Self {
         self.config.mcp_config_path = Some(path);
@@ -335,12 +436,21 @@ impl AgentRuntimeBuilder {
             .tool_registry
             .unwrap_or_else(|| Arc::new(ToolRegistry::new()));
 
-        Ok(AgentRuntime::new(
-            provider,
-            tool_registry,
-            self.config,
-            self.mcp_config,
-        ))
+        let mut runtime = AgentRuntime::new(provider, tool_registry, self.config, self.mcp_config);
+        if let Some(tracker) = self.usage_tracker {
+            runtime = runtime.with_usage_tracker(tracker);
+        }
+        if let Some(tracker) = self.token_tracker {
+            runtime = runtime.with_token_tracker(tracker);
+        }
+        if let Some(logger) = self
+            .llm_logger
+            .or_else(|| LlmLogger::from_env().map(Arc::new))
+        {
+            runtime = runtime.with_llm_logger(logger);
+        }
+
+        Ok(runtime)
     }
 }
 
@@ -360,6 +470,7 @@ mod tests {
         assert_eq!(config.default_max_iterations, 10);
         assert_eq!(config.default_model, "claude-sonnet-4-5-20250929");
         assert!(config.mcp_config_path.is_none());
+        assert_eq!(config.max_delegation_depth, 5);
     }
 
     #[test]
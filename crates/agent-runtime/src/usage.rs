@@ -0,0 +1,389 @@
+//! Token usage accounting and cost estimates
+//!
+//! The [`UsageTracker`] adds up the tokens of every LLM request per provider
+//! and model, and prices them with [`ModelPricing`]. A runtime shares one
+//! tracker between all the executors it creates, so it holds process-wide
+//! totals. The usage of a single request travels in its [`Context`]: tool
+//! agents add their runs with [`add_to_context`], delegating agents pass the
+//! context down to their sub-agents, and the caller reads the total back
+//! with [`from_context`].
+
+use agent_core::Context;
+use agent_core::context::ContextKey;
+use agent_llm::TokenUsage;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{PoisonError, RwLock};
+
+/// Context key holding the usage of the current request
+pub const USAGE_KEY: ContextKey<UsageSummary> = ContextKey::new("runtime.token_usage");
+
+/// Price of a model in US dollars per million tokens
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+}
+
+/// List prices by model name prefix, more specific prefixes first
+const LIST_PRICES: &[(&str, ModelPricing)] = &[
+    ("claude-opus-4-5", ModelPricing::new(5.0, 25.0)),
+    ("claude-opus-4", ModelPricing::new(15.0, 75.0)),
+    ("claude-sonnet-4", ModelPricing::new(3.0, 15.0)),
+    ("claude-3-7-sonnet", ModelPricing::new(3.0, 15.0)),
+    ("claude-3-5-sonnet", ModelPricing::new(3.0, 15.0)),
+    ("claude-haiku-4-5", ModelPricing::new(1.0, 5.0)),
+    ("claude-3-5-haiku", ModelPricing::new(0.8, 4.0)),
+    ("claude-3-haiku", ModelPricing::new(0.25, 1.25)),
+    ("gpt-5-nano", ModelPricing::new(0.05, 0.4)),
+    ("gpt-5-mini", ModelPricing::new(0.25, 2.0)),
+    ("gpt-5", ModelPricing::new(1.25, 10.0)),
+    ("gpt-4.1-nano", ModelPricing::new(0.1, 0.4)),
+    ("gpt-4.1-mini", ModelPricing::new(0.4, 1.6)),
+    ("gpt-4.1", ModelPricing::new(2.0, 8.0)),
+    ("gpt-4o-mini", ModelPricing::new(0.15, 0.6)),
+    ("gpt-4o", ModelPricing::new(2.5, 10.0)),
+    ("gpt-3.5-turbo", ModelPricing::new(0.5, 1.5)),
+    ("o4-mini", ModelPricing::new(1.1, 4.4)),
+    ("o3-mini", ModelPricing::new(1.1, 4.4)),
+    ("o3", ModelPricing::new(2.0, 8.0)),
+];
+
+impl ModelPricing {
+    /// Free, e.g. for models served locally
+    pub const FREE: Self = Self::new(0.0, 0.0);
+
+    pub const fn new(input_per_million: f64, output_per_million: f64) -> Self {
+        Self {
+            input_per_million,
+            output_per_million,
+        }
+    }
+
+    /// Estimated cost of the given usage in US dollars
+    pub fn cost(&self, usage: &TokenUsage) -> f64 {
+        (usage.input_tokens as f64 * self.input_per_million
+            + usage.output_tokens as f64 * self.output_per_million)
+            / 1_000_000.0
+    }
+
+    /// List price of a model, if known
+    ///
+    /// Models are matched by prefix, so dated snapshots such as
+    /// `claude-sonnet-4-5-20250929` share their family's price, and a
+    /// router prefix such as `anthropic/` is ignored. Ollama models run
+    /// locally and are free.
+    pub fn for_model(provider: &str, model: &str) -> Option<Self> {
+        if provider == "ollama" {
+            return Some(Self::FREE);
+        }
+        let model = model.rsplit('/').next().unwrap_or(model);
+        LIST_PRICES
+            .iter()
+            .find(|(prefix, _)| model.starts_with(prefix))
+            .map(|(_, pricing)| *pricing)
+    }
+}
+
+/// Tokens used by one provider and model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelUsage {
+    pub provider: String,
+    pub model: String,
+    /// LLM requests made
+    pub requests: u64,
+    pub usage: TokenUsage,
+    /// Estimated cost in US dollars; `None` when the model's price is unknown
+    pub cost_usd: Option<f64>,
+}
+
+/// Token usage and cost per provider and model
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageSummary {
+    /// Most expensive first, then by tokens
+    pub models: Vec<ModelUsage>,
+}
+
+impl UsageSummary {
+    /// Add requests to a model's usage
+    pub fn record(
+        &mut self,
+        provider: &str,
+        model: &str,
+        requests: u64,
+        usage: TokenUsage,
+        pricing: Option<ModelPricing>,
+    ) {
+        self.add(ModelUsage {
+            provider: provider.to_string(),
+            model: model.to_string(),
+            requests,
+            usage,
+            cost_usd: pricing.map(|pricing| pricing.cost(&usage)),
+        });
+    }
+
+    /// Add another summary's usage to this one
+    pub fn merge(&mut self, other: &UsageSummary) {
+        for model in &other.models {
+            self.add(model.clone());
+        }
+    }
+
+    fn add(&mut self, usage: ModelUsage) {
+        match self
+            .models
+            .iter_mut()
+            .find(|m| m.provider == usage.provider && m.model == usage.model)
+        {
+            Some(existing) => {
+                existing.requests += usage.requests;
+                existing.usage += usage.usage;
+                existing.cost_usd = match (existing.cost_usd, usage.cost_usd) {
+                    (Some(a), Some(b)) => Some(a + b),
+                    (a, b) => a.or(b),
+                };
+            }
+            None => self.models.push(usage),
+        }
+        self.models.sort_by(|a, b| {
+            b.cost_usd
+                .unwrap_or(0.0)
+                .total_cmp(&a.cost_usd.unwrap_or(0.0))
+                .then_with(|| b.usage.total().cmp(&a.usage.total()))
+        });
+    }
+
+    /// Tokens used across all models
+    pub fn total(&self) -> TokenUsage {
+        let mut total = TokenUsage::default();
+        for model in &self.models {
+            total += model.usage;
+        }
+        total
+    }
+
+    /// LLM requests made across all models
+    pub fn requests(&self) -> u64 {
+        self.models.iter().map(|model| model.requests).sum()
+    }
+
+    /// Estimated cost of the models with a known price, in US dollars
+    pub fn total_cost_usd(&self) -> f64 {
+        self.models.iter().filter_map(|model| model.cost_usd).sum()
+    }
+
+    /// Whether some of the usage could not be priced
+    pub fn has_unpriced(&self) -> bool {
+        self.models.iter().any(|model| model.cost_usd.is_none())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.models.is_empty()
+    }
+}
+
+impl fmt::Display for UsageSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return f.write_str("No LLM usage recorded");
+        }
+        for model in &self.models {
+            writeln!(
+                f,
+                "{}/{}: {} ({})",
+                model.provider,
+                model.model,
+                format_tokens(model.requests, &model.usage),
+                model.cost_usd.map_or_else(
+                    || "price unknown".to_string(),
+                    |cost| format!("~${cost:.4}")
+                )
+            )?;
+        }
+        write!(
+            f,
+            "Total: {} (~${:.4}{})",
+            format_tokens(self.requests(), &self.total()),
+            self.total_cost_usd(),
+            if self.has_unpriced() {
+                " + unpriced models"
+            } else {
+                ""
+            }
+        )
+    }
+}
+
+/// "3 requests, 12034 in / 1850 out tokens"
+fn format_tokens(requests: u64, usage: &TokenUsage) -> String {
+    format!(
+        "{requests} request{}, {} in / {} out tokens",
+        if requests == 1 { "" } else { "s" },
+        usage.input_tokens,
+        usage.output_tokens
+    )
+}
+
+/// Collects token usage across agent runs
+///
+/// A runtime shares one tracker between all the executors it creates; see
+/// `AgentRuntime::token_tracker`.
+#[derive(Debug, Default)]
+pub struct UsageTracker {
+    summary: RwLock<UsageSummary>,
+    /// Prices overriding the list prices, by model name prefix
+    pricing: HashMap<String, ModelPricing>,
+}
+
+impl UsageTracker {
+    /// Create a tracker pricing models at their list prices
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Price models starting with `model` differently, e.g. for negotiated
+    /// rates or models missing from the list
+    pub fn with_pricing(mut self, model: impl Into<String>, pricing: ModelPricing) -> Self {
+        self.pricing.insert(model.into(), pricing);
+        self
+    }
+
+    /// Price of a model: the longest matching override, else the list price
+    pub fn pricing_for(&self, provider: &str, model: &str) -> Option<ModelPricing> {
+        self.pricing
+            .iter()
+            .filter(|(prefix, _)| model.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, pricing)| *pricing)
+            .or_else(|| ModelPricing::for_model(provider, model))
+    }
+
+    /// Record one LLM request
+    pub fn record(&self, provider: &str, model: &str, usage: &TokenUsage) {
+        let pricing = self.pricing_for(provider, model);
+        self.summary
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .record(provider, model, 1, *usage, pricing);
+    }
+
+    /// Usage recorded so far
+    pub fn summary(&self) -> UsageSummary {
+        self.summary
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Clear all recorded usage
+    pub fn reset(&self) {
+        *self.summary.write().unwrap_or_else(PoisonError::into_inner) = UsageSummary::default();
+    }
+}
+
+/// Add usage to the current request's total in `context`
+pub fn add_to_context(context: &mut Context, usage: &UsageSummary) {
+    if usage.is_empty() {
+        return;
+    }
+    let mut total = from_context(context);
+    total.merge(usage);
+    if let Err(e) = context.set(&USAGE_KEY, &total) {
+        tracing::warn!("Failed to record token usage in context: {}", e);
+    }
+}
+
+/// Usage of the current request so far
+pub fn from_context(context: &Context) -> UsageSummary {
+    context
+        .get_key(&USAGE_KEY)
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
+
+/// Remove the usage recorded in `context`, returning it
+///
+/// Used before handing a copy of a context to work running in parallel, so
+/// the copies' usage can be added back without counting the original twice.
+pub fn take_from_context(context: &mut Context) -> UsageSummary {
+    let usage = from_context(context);
+    context.remove(USAGE_KEY.name());
+    usage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(input_tokens: usize, output_tokens: usize) -> TokenUsage {
+        TokenUsage {
+            input_tokens,
+            output_tokens,
+        }
+    }
+
+    #[test]
+    fn test_list_prices() {
+        let sonnet = ModelPricing::for_model("anthropic", "claude-sonnet-4-5-20250929").unwrap();
+        assert_eq!(sonnet, ModelPricing::new(3.0, 15.0));
+        assert!((sonnet.cost(&tokens(1_000_000, 100_000)) - 4.5).abs() < 1e-9);
+
+        let mini = ModelPricing::for_model("openai", "openai/gpt-4o-mini").unwrap();
+        assert_eq!(mini, ModelPricing::new(0.15, 0.6));
+        assert_eq!(
+            ModelPricing::for_model("ollama", "llama3.1:8b"),
+            Some(ModelPricing::FREE)
+        );
+        assert_eq!(ModelPricing::for_model("openai", "my-finetune"), None);
+    }
+
+    #[test]
+    fn test_tracker_summary() {
+        let tracker = UsageTracker::new().with_pricing("my-", ModelPricing::new(1.0, 1.0));
+        tracker.record("anthropic", "claude-sonnet-4-5", &tokens(1000, 100));
+        tracker.record("anthropic", "claude-sonnet-4-5", &tokens(2000, 200));
+        tracker.record("openai", "my-finetune", &tokens(500_000, 500_000));
+        tracker.record("openai", "unknown-model", &tokens(10, 10));
+
+        let summary = tracker.summary();
+        assert_eq!(summary.requests(), 4);
+        assert_eq!(summary.total(), tokens(503_010, 500_310));
+        // Most expensive first
+        assert_eq!(summary.models[0].model, "my-finetune");
+        assert_eq!(summary.models[1].requests, 2);
+        assert!((summary.models[1].cost_usd.unwrap() - 0.0135).abs() < 1e-9);
+        assert!((summary.total_cost_usd() - 1.0135).abs() < 1e-9);
+        assert!(summary.has_unpriced());
+
+        let report = summary.to_string();
+        assert!(
+            report.contains("anthropic/claude-sonnet-4-5: 2 requests, 3000 in / 300 out tokens")
+        );
+        assert!(report.contains("price unknown"));
+        assert!(report.ends_with("(~$1.0135 + unpriced models)"), "{report}");
+
+        tracker.reset();
+        assert!(tracker.summary().is_empty());
+    }
+
+    #[test]
+    fn test_context_accumulates() {
+        let mut context = Context::new();
+        assert!(from_context(&context).is_empty());
+
+        let mut run = UsageSummary::default();
+        run.record("anthropic", "claude-haiku-4-5", 2, tokens(100, 10), None);
+        add_to_context(&mut context, &run);
+        add_to_context(&mut context, &run);
+
+        let total = from_context(&context);
+        assert_eq!(total.requests(), 4);
+        assert_eq!(total.total(), tokens(200, 20));
+
+        assert_eq!(take_from_context(&mut context), total);
+        assert!(from_context(&context).is_empty());
+    }
+}
@@ -46,7 +46,7 @@
 //! cargo run --example openai_local_analysis -p agent-stock -- --macro
 //! ```
 
-use agent_core::Agent;
+use agent_core::{Agent, Context};
 use agent_llm::providers::{OpenAIConfig, OpenAIProvider};
 use agent_runtime::AgentRuntime;
 use agent_stock::{StockAnalysisAgent, StockConfig};
@@ -113,9 +113,18 @@ async fn main() -> anyhow::Result<()> {
 
     println!("Stock Data Configuration:");
     println!("  - Primary provider: {:?}", stock_config.default_provider);
-    println!("  - Response language: {:?}", stock_config.response_language);
-    println!("  - Cache TTL (realtime): {:?}", stock_config.cache_ttl_realtime);
-    println!("  - Cache TTL (earnings): {:?}", stock_config.cache_ttl_earnings);
+    println!(
+        "  - Response language: {:?}",
+        stock_config.response_language
+    );
+    println!(
+        "  - Cache TTL (realtime): {:?}",
+        stock_config.cache_ttl_realtime
+    );
+    println!(
+        "  - Cache TTL (earnings): {:?}",
+        stock_config.cache_ttl_earnings
+    );
     println!("  - Cache TTL (macro): {:?}", stock_config.cache_ttl_macro);
     println!("  - Max retries: {}\n", stock_config.max_retries);
 
@@ -142,14 +151,14 @@ async fn main() -> anyhow::Result<()> {
     let result = agent
         .process(
             format!("What is the current price of {}?", symbol),
-            &mut agent_core::Context::new(),
+            &mut Context::new(),
         )
         .await?;
     println!("{}\n", result);
 
     // Example 2: Technical analysis
     println!("=== 2. Technical Analysis ===");
-    match agent.analyze_technical(symbol).await {
+    match agent.analyze_technical(symbol, &mut Context::new()).await {
         Ok(result) => println!("{}\n", result),
         Err(e) => println!("Error: {}\n", e),
     }
@@ -157,7 +166,7 @@ async fn main() -> anyhow::Result<()> {
     // Example 3: Fundamental analysis (requires Alpha Vantage API key)
     if env::var("ALPHA_VANTAGE_API_KEY").is_ok() {
         println!("=== 3. Fundamental Analysis ===");
-        match agent.analyze_fundamental(symbol).await {
+        match agent.analyze_fundamental(symbol, &mut Context::new()).await {
             Ok(result) => println!("{}\n", result),
             Err(e) => println!("Error: {}\n", e),
         }
@@ -168,7 +177,7 @@ async fn main() -> anyhow::Result<()> {
 
     // Example 4: News and sentiment
     println!("=== 4. News & Sentiment ===");
-    match agent.analyze_news(symbol).await {
+    match agent.analyze_news(symbol, &mut Context::new()).await {
         Ok(result) => println!("{}\n", result),
         Err(e) => println!("Error: {}\n", e),
     }
@@ -176,7 +185,7 @@ async fn main() -> anyhow::Result<()> {
     // Example 5: Earnings Analysis (NEW)
     println!("=== 5. Earnings & Financial Reports ===");
     println!("Analyzing SEC filings and earnings data for {}...", symbol);
-    match agent.analyze_earnings(symbol).await {
+    match agent.analyze_earnings(symbol, &mut Context::new()).await {
         Ok(result) => println!("{}\n", result),
         Err(e) => println!("Error: {}\n", e),
     }
@@ -184,7 +193,7 @@ async fn main() -> anyhow::Result<()> {
     // Example 6: Macro Economic Analysis (NEW)
     println!("=== 6. Macro Economic Environment ===");
     println!("Analyzing Fed policy, inflation, and economic indicators...");
-    match agent.analyze_macro().await {
+    match agent.analyze_macro(&mut Context::new()).await {
         Ok(result) => println!("{}\n", result),
         Err(e) => println!("Error: {}\n", e),
     }
@@ -192,7 +201,7 @@ async fn main() -> anyhow::Result<()> {
     // Example 7: Geopolitical Analysis (NEW)
     println!("=== 7. Geopolitical Risks ===");
     println!("Analyzing trade tensions, sanctions, and global risks...");
-    match agent.analyze_geopolitical().await {
+    match agent.analyze_geopolitical(&mut Context::new()).await {
         Ok(result) => println!("{}\n", result),
         Err(e) => println!("Error: {}\n", e),
     }
@@ -200,7 +209,10 @@ async fn main() -> anyhow::Result<()> {
     // Example 8: Comprehensive Analysis (NEW)
     println!("=== 8. Comprehensive Investment Analysis ===");
     println!("Synthesizing all analysis for {}...", symbol);
-    match agent.analyze_comprehensive(symbol).await {
+    match agent
+        .analyze_comprehensive(symbol, &mut Context::new())
+        .await
+    {
         Ok(result) => println!("{}\n", result),
         Err(e) => println!("Error: {}\n", e),
     }
@@ -214,7 +226,7 @@ async fn main() -> anyhow::Result<()> {
                  Give me a brief recommendation in 2-3 sentences.",
                 symbol
             ),
-            &mut agent_core::Context::new(),
+            &mut Context::new(),
         )
         .await?;
     println!("{}\n", result);
@@ -1,13 +1,14 @@
 //! Data fetching agent for stock information
 
-use agent_core::{Agent, Context, Result};
+use agent_core::{Agent, AgentCapabilities, Context, Result};
 use agent_runtime::{AgentRuntime, ExecutorConfig};
 use async_trait::async_trait;
 use std::sync::Arc;
 
 use crate::cache::CacheManager;
 use crate::config::StockConfig;
-use crate::tools::{FundamentalDataTool, StockDataTool};
+use crate::router::QueryIntent;
+use crate::tools::{FundamentalDataTool, GlossaryTool, StockDataTool};
 
 /// Agent specialized in fetching stock data
 pub struct DataFetcherAgent {
@@ -18,11 +19,7 @@ impl DataFetcherAgent {
     /// Create a new data fetcher agent
     pub async fn new(runtime: Arc<AgentRuntime>, config: Arc<StockConfig>) -> Result<Self> {
         // Create cache manager
-        let cache_mgr = CacheManager::new(
-            config.cache_ttl_realtime,
-            config.cache_ttl_fundamental,
-            config.cache_ttl_news,
-        );
+        let cache_mgr = CacheManager::from_config(&config);
 
         // Create tools
         let stock_data_tool = Arc::new(StockDataTool::new(
@@ -37,6 +34,7 @@ impl DataFetcherAgent {
         // Register tools
         runtime.tools().register(stock_data_tool);
         runtime.tools().register(fundamental_tool);
+        runtime.tools().register(Arc::new(GlossaryTool::new()));
 
         // Get system prompt from registry
         let system_prompt = config
@@ -46,10 +44,10 @@ impl DataFetcherAgent {
 
         // Create executor config
         let executor_config = ExecutorConfig {
-            model: config.model.clone(),
+            model: config.model_for("data-fetcher"),
             system_prompt: Some(system_prompt),
-            max_tokens: config.max_tokens,
-            temperature: Some(config.temperature),
+            max_tokens: config.max_tokens_for("data-fetcher"),
+            temperature: Some(config.temperature_for("data-fetcher")),
             max_iterations: 5,
         };
 
@@ -69,4 +67,11 @@ impl Agent for DataFetcherAgent {
     fn name(&self) -> &'static str {
         "DataFetcherAgent"
     }
+
+    fn capabilities(&self) -> AgentCapabilities {
+        AgentCapabilities::new("Quotes, key fundamentals and financial term explanations")
+            .with_intent(QueryIntent::PriceQuery.as_str())
+            .with_intent(QueryIntent::Explain.as_str())
+            .with_input("stock symbol or financial term")
+    }
 }
@@ -1,29 +1,45 @@
 //! Agent specialized in analyzing company earnings and financial reports
 
-use agent_core::{Agent, Context, Result};
+use agent_core::{Agent, AgentCapabilities, Context, Result};
 use agent_runtime::{AgentRuntime, ExecutorConfig};
 use async_trait::async_trait;
 use std::sync::Arc;
 
-use crate::cache::StockCache;
+use crate::cache::{CacheCategory, StockCache};
 use crate::config::StockConfig;
-use crate::tools::EarningsReportTool;
+use crate::router::QueryIntent;
+use crate::tools::{
+    EarningsCalendarTool, EarningsQualityTool, EarningsReportTool, EventStudyTool,
+    SecFullTextSearchTool,
+};
 
 /// Agent specialized in analyzing company earnings reports
 pub struct EarningsAnalyzerAgent {
     agent: agent_runtime::agents::ToolAgent,
     config: Arc<StockConfig>,
+    quality_tool: Arc<EarningsQualityTool>,
 }
 
 impl EarningsAnalyzerAgent {
     /// Create a new earnings analyzer agent
     pub async fn new(runtime: Arc<AgentRuntime>, config: Arc<StockConfig>) -> Result<Self> {
         // Create cache for earnings data (24h TTL)
-        let cache = StockCache::new(config.cache_ttl_earnings);
-
-        // Register earnings report tool
-        let earnings_tool = Arc::new(EarningsReportTool::new(Arc::clone(&config), cache));
+        let cache = StockCache::from_config(&config, CacheCategory::Earnings);
+
+        // Register earnings report, quality, filing search and event study tools
+        let earnings_tool = Arc::new(EarningsReportTool::new(Arc::clone(&config), cache.clone()));
+        let quality_tool = Arc::new(EarningsQualityTool::new(Arc::clone(&config), cache.clone()));
+        let search_tool = Arc::new(SecFullTextSearchTool::new(
+            Arc::clone(&config),
+            cache.clone(),
+        ));
+        let event_study_tool = Arc::new(EventStudyTool::new(Arc::clone(&config), cache.clone()));
+        let calendar_tool = Arc::new(EarningsCalendarTool::new(Arc::clone(&config), cache));
         runtime.tools().register(earnings_tool);
+        runtime.tools().register(quality_tool.clone());
+        runtime.tools().register(search_tool);
+        runtime.tools().register(event_study_tool);
+        runtime.tools().register(calendar_tool);
 
         // Get system prompt from registry
         let system_prompt = config
@@ -33,17 +49,21 @@ impl EarningsAnalyzerAgent {
 
         // Create executor config
         let executor_config = ExecutorConfig {
-            model: config.model.clone(),
+            model: config.model_for("earnings-analyzer"),
             system_prompt: Some(system_prompt),
-            max_tokens: config.max_tokens,
-            temperature: Some(config.temperature),
+            max_tokens: config.max_tokens_for("earnings-analyzer"),
+            temperature: Some(config.temperature_for("earnings-analyzer")),
             max_iterations: 5,
         };
 
         // Create tool agent
         let agent = runtime.create_tool_agent(executor_config, "earnings-analyzer");
 
-        Ok(Self { agent, config })
+        Ok(Self {
+            agent,
+            config,
+            quality_tool,
+        })
     }
 
     /// Analyze earnings for a specific symbol
@@ -75,14 +95,24 @@ impl EarningsAnalyzerAgent {
     }
 
     /// Analyze earnings quality
+    ///
+    /// Red flags and the numbers behind them are computed up front from SEC
+    /// filings; without filings the agent works from its tools alone.
     pub async fn analyze_quality(&self, symbol: &str) -> Result<String> {
         let mut context = Context::new();
+        let evidence = match self.quality_tool.report(symbol, 5).await {
+            Ok(report) => Some(report.evidence()),
+            Err(e) => {
+                tracing::warn!("No earnings quality evidence for {}: {}", symbol, e);
+                None
+            }
+        };
         let input = self
             .config
             .prompt_registry
             .render(
                 "stock.user.analyze_quality",
-                &serde_json::json!({ "symbol": symbol }),
+                &serde_json::json!({ "symbol": symbol, "evidence": evidence }),
             )
             .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))?;
         self.process(input, &mut context).await
@@ -98,12 +128,18 @@ impl Agent for EarningsAnalyzerAgent {
     fn name(&self) -> &'static str {
         "EarningsAnalyzerAgent"
     }
+
+    fn capabilities(&self) -> AgentCapabilities {
+        AgentCapabilities::new("Earnings reports and SEC filings")
+            .with_intent(QueryIntent::EarningsAnalysis.as_str())
+            .with_input("stock symbol")
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use agent_prompt::{Language, PromptRegistry};
     use crate::prompts::register_prompts;
+    use agent_prompt::{Language, PromptRegistry};
 
     #[test]
     fn test_prompts_registered() {
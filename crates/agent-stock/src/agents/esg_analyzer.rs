@@ -0,0 +1,76 @@
+//! ESG and sustainability analysis agent
+
+use agent_core::{Agent, AgentCapabilities, Context, Result};
+use agent_runtime::{AgentRuntime, ExecutorConfig};
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::cache::CacheManager;
+use crate::config::StockConfig;
+use crate::router::QueryIntent;
+use crate::tools::EsgTool;
+
+/// Agent specialized in ESG scores, controversies and sustainability
+pub struct EsgAnalyzerAgent {
+    agent: agent_runtime::agents::ToolAgent,
+    /// API key the configured ESG provider needs, if any
+    required_key: Option<&'static str>,
+}
+
+impl EsgAnalyzerAgent {
+    /// Create a new ESG analyzer agent
+    pub async fn new(runtime: Arc<AgentRuntime>, config: Arc<StockConfig>) -> Result<Self> {
+        let cache_mgr = CacheManager::from_config(&config);
+
+        // Create tools
+        let esg_tool = Arc::new(EsgTool::new(
+            Arc::clone(&config),
+            cache_mgr.fundamental.clone(),
+        ));
+
+        // Register tools
+        runtime.tools().register(esg_tool);
+
+        // Get system prompt from registry
+        let system_prompt = config
+            .prompt_registry
+            .render("stock.esg_analyzer", &serde_json::json!({}))
+            .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))?;
+
+        let executor_config = ExecutorConfig {
+            model: config.model_for("esg-analyzer"),
+            system_prompt: Some(system_prompt),
+            max_tokens: config.max_tokens_for("esg-analyzer"),
+            temperature: Some(config.temperature_for("esg-analyzer")),
+            max_iterations: 5,
+        };
+
+        let agent = runtime.create_tool_agent(executor_config, "esg-analyzer");
+
+        Ok(Self {
+            agent,
+            required_key: config.esg_provider.api_key_env(),
+        })
+    }
+}
+
+#[async_trait]
+impl Agent for EsgAnalyzerAgent {
+    async fn process(&self, input: String, context: &mut Context) -> Result<String> {
+        self.agent.process(input, context).await
+    }
+
+    fn name(&self) -> &'static str {
+        "EsgAnalyzerAgent"
+    }
+
+    fn capabilities(&self) -> AgentCapabilities {
+        let capabilities = AgentCapabilities::new("ESG scores, controversies and sustainability")
+            .with_intent(QueryIntent::EsgAnalysis.as_str())
+            .with_input("stock symbol");
+        match self.required_key {
+            Some(key) => capabilities.with_required_key(key),
+            None => capabilities,
+        }
+    }
+}
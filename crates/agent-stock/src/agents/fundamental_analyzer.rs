@@ -1,13 +1,16 @@
 //! Fundamental analysis agent
 
-use agent_core::{Agent, Context, Result};
+use agent_core::{Agent, AgentCapabilities, Context, Result};
 use agent_runtime::{AgentRuntime, ExecutorConfig};
 use async_trait::async_trait;
 use std::sync::Arc;
 
 use crate::cache::CacheManager;
-use crate::config::StockConfig;
-use crate::tools::FundamentalDataTool;
+use crate::config::{ALPHA_VANTAGE_API_KEY_ENV, StockConfig};
+use crate::router::QueryIntent;
+use crate::tools::{
+    DividendTool, FundamentalDataTool, RevenueBreakdownTool, ScreenerTool, TimeComparisonTool,
+};
 
 /// Agent specialized in fundamental analysis
 pub struct FundamentalAnalyzerAgent {
@@ -17,11 +20,7 @@ pub struct FundamentalAnalyzerAgent {
 impl FundamentalAnalyzerAgent {
     /// Create a new fundamental analyzer agent
     pub async fn new(runtime: Arc<AgentRuntime>, config: Arc<StockConfig>) -> Result<Self> {
-        let cache_mgr = CacheManager::new(
-            config.cache_ttl_realtime,
-            config.cache_ttl_fundamental,
-            config.cache_ttl_news,
-        );
+        let cache_mgr = CacheManager::from_config(&config);
 
         // Create tools
         let fundamental_tool = Arc::new(FundamentalDataTool::new(
@@ -29,8 +28,30 @@ impl FundamentalAnalyzerAgent {
             cache_mgr.fundamental.clone(),
         ));
 
+        let time_comparison_tool = Arc::new(TimeComparisonTool::new(
+            Arc::clone(&config),
+            cache_mgr.fundamental.clone(),
+        ));
+
+        let revenue_breakdown_tool = Arc::new(RevenueBreakdownTool::new(
+            Arc::clone(&config),
+            cache_mgr.fundamental.clone(),
+        ));
+
+        let dividend_tool = Arc::new(DividendTool::new(cache_mgr.fundamental.clone()));
+
+        let screener_tool = Arc::new(ScreenerTool::new(
+            &config,
+            cache_mgr.fundamental.clone(),
+            cache_mgr.realtime.clone(),
+        ));
+
         // Register tools
         runtime.tools().register(fundamental_tool);
+        runtime.tools().register(time_comparison_tool);
+        runtime.tools().register(revenue_breakdown_tool);
+        runtime.tools().register(dividend_tool);
+        runtime.tools().register(screener_tool);
 
         // Get system prompt from registry
         let system_prompt = config
@@ -39,10 +60,10 @@ impl FundamentalAnalyzerAgent {
             .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))?;
 
         let executor_config = ExecutorConfig {
-            model: config.model.clone(),
+            model: config.model_for("fundamental-analyzer"),
             system_prompt: Some(system_prompt),
-            max_tokens: config.max_tokens,
-            temperature: Some(config.temperature),
+            max_tokens: config.max_tokens_for("fundamental-analyzer"),
+            temperature: Some(config.temperature_for("fundamental-analyzer")),
             max_iterations: 5,
         };
 
@@ -61,4 +82,12 @@ impl Agent for FundamentalAnalyzerAgent {
     fn name(&self) -> &'static str {
         "FundamentalAnalyzerAgent"
     }
+
+    fn capabilities(&self) -> AgentCapabilities {
+        AgentCapabilities::new("Valuation, profitability and balance sheet analysis")
+            .with_intent(QueryIntent::FundamentalAnalysis.as_str())
+            .with_intent(QueryIntent::TimeComparison.as_str())
+            .with_required_key(ALPHA_VANTAGE_API_KEY_ENV)
+            .with_input("stock symbol")
+    }
 }
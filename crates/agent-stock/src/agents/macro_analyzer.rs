@@ -1,35 +1,66 @@
 //! Agent specialized in macroeconomic analysis and Fed policy interpretation
 
-use agent_core::{Agent, Context, Result};
+use agent_core::{Agent, AgentCapabilities, Context, Result};
 use agent_runtime::{AgentRuntime, ExecutorConfig};
+use agent_tools::Tool;
 use async_trait::async_trait;
 use std::sync::Arc;
 
-use crate::cache::StockCache;
-use crate::config::StockConfig;
-use crate::tools::{GeopoliticalTool, MacroEconomicTool};
+use crate::cache::CacheManager;
+use crate::config::{FRED_API_KEY_ENV, StockConfig};
+use crate::router::QueryIntent;
+use crate::tools::{
+    GeoExposureTool, GeopoliticalTool, MacroEconomicTool, SupplyChainTool, ThemeAnalysisTool,
+};
 
 /// Agent specialized in macroeconomic analysis
 pub struct MacroAnalyzerAgent {
     agent: agent_runtime::agents::ToolAgent,
     config: Arc<StockConfig>,
+    macro_tool: Arc<MacroEconomicTool>,
 }
 
 impl MacroAnalyzerAgent {
     /// Create a new macro analyzer agent
     pub async fn new(runtime: Arc<AgentRuntime>, config: Arc<StockConfig>) -> Result<Self> {
         // Create caches
-        let macro_cache = StockCache::new(config.cache_ttl_macro);
-        let geopolitical_cache = StockCache::new(config.cache_ttl_news);
+        let caches = CacheManager::from_config(&config);
+        let macro_cache = caches.macro_data.clone();
+        let geopolitical_cache = caches.news.clone();
+        let supply_chain_cache = caches.earnings.clone();
+        let theme_cache = caches.realtime.clone();
 
         // Register macro economic tool
         let macro_tool = Arc::new(MacroEconomicTool::new(Arc::clone(&config), macro_cache));
-        runtime.tools().register(macro_tool);
+        runtime
+            .tools()
+            .register(Arc::clone(&macro_tool) as Arc<dyn Tool>);
 
         // Register geopolitical tool
-        let geo_tool = Arc::new(GeopoliticalTool::new(Arc::clone(&config), geopolitical_cache));
+        let geo_tool = Arc::new(GeopoliticalTool::new(
+            Arc::clone(&config),
+            geopolitical_cache.clone(),
+        ));
         runtime.tools().register(geo_tool);
 
+        // Register geographic exposure tool for stock-specific geopolitical risk
+        let geo_exposure_tool = Arc::new(GeoExposureTool::new(
+            Arc::clone(&config),
+            geopolitical_cache,
+        ));
+        runtime.tools().register(geo_exposure_tool);
+
+        // Register supply chain tool for second-order impacts
+        let supply_chain_tool = Arc::new(SupplyChainTool::new(
+            Arc::clone(&config),
+            supply_chain_cache,
+        ));
+        runtime.tools().register(supply_chain_tool);
+
+        // Register theme tool for thematic baskets (AI, EV, semis)
+        let theme_tool = Arc::new(ThemeAnalysisTool::new(Arc::clone(&config), theme_cache));
+        runtime.tools().register(theme_tool);
+
         // Get system prompt from registry
         let system_prompt = config
             .prompt_registry
@@ -38,17 +69,29 @@ impl MacroAnalyzerAgent {
 
         // Create executor config
         let executor_config = ExecutorConfig {
-            model: config.model.clone(),
+            model: config.model_for("macro-analyzer"),
             system_prompt: Some(system_prompt),
-            max_tokens: config.max_tokens,
-            temperature: Some(config.temperature),
+            max_tokens: config.max_tokens_for("macro-analyzer"),
+            temperature: Some(config.temperature_for("macro-analyzer")),
             max_iterations: 5,
         };
 
         // Create tool agent
         let agent = runtime.create_tool_agent(executor_config, "macro-analyzer");
 
-        Ok(Self { agent, config })
+        Ok(Self {
+            agent,
+            config,
+            macro_tool,
+        })
+    }
+
+    /// Keep the US economic summary in the macro tool's cache warm, refreshing
+    /// after FRED release times and before the cached copy expires
+    ///
+    /// Returns `None` without a FRED API key.
+    pub fn spawn_summary_refresh(&self) -> Option<tokio::task::JoinHandle<()>> {
+        Arc::clone(&self.macro_tool).spawn_summary_refresh(self.config.cache_ttl_macro * 9 / 10)
     }
 
     /// Get comprehensive economic overview
@@ -144,12 +187,21 @@ impl Agent for MacroAnalyzerAgent {
     fn name(&self) -> &'static str {
         "MacroAnalyzerAgent"
     }
+
+    fn capabilities(&self) -> AgentCapabilities {
+        AgentCapabilities::new("Fed policy, rates, inflation, geopolitical risk and themes")
+            .with_intent(QueryIntent::MacroAnalysis.as_str())
+            .with_intent(QueryIntent::GeopoliticalAnalysis.as_str())
+            .with_intent(QueryIntent::ThemeAnalysis.as_str())
+            .with_required_key(FRED_API_KEY_ENV)
+            .with_input("question about the economy")
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use agent_prompt::{Language, PromptRegistry};
     use crate::prompts::register_prompts;
+    use agent_prompt::{Language, PromptRegistry};
 
     #[test]
     fn test_prompts_registered() {
@@ -164,8 +216,13 @@ mod tests {
         assert!(registry.get("stock.user.analyze_fed_policy").is_some());
         assert!(registry.get("stock.user.analyze_rates").is_some());
         assert!(registry.get("stock.user.analyze_inflation").is_some());
-        assert!(registry.get("stock.user.analyze_geopolitical_risks").is_some());
+        assert!(
+            registry
+                .get("stock.user.analyze_geopolitical_risks")
+                .is_some()
+        );
         assert!(registry.get("stock.user.get_market_outlook").is_some());
         assert!(registry.get("stock.user.analyze_impact").is_some());
+        assert!(registry.get("stock.user.analyze_theme").is_some());
     }
 }
@@ -2,16 +2,24 @@
 
 pub mod data_fetcher;
 pub mod earnings_analyzer;
+pub mod esg_analyzer;
 pub mod fundamental_analyzer;
 pub mod macro_analyzer;
 pub mod news_analyzer;
+pub mod portfolio;
+pub mod query_builder;
 pub mod stock_analysis;
 pub mod technical_analyzer;
+pub mod translator;
 
 pub use data_fetcher::DataFetcherAgent;
 pub use earnings_analyzer::EarningsAnalyzerAgent;
+pub use esg_analyzer::EsgAnalyzerAgent;
 pub use fundamental_analyzer::FundamentalAnalyzerAgent;
 pub use macro_analyzer::MacroAnalyzerAgent;
 pub use news_analyzer::NewsAnalyzerAgent;
+pub use portfolio::PortfolioAgent;
+pub use query_builder::QueryBuilderAgent;
 pub use stock_analysis::{ParallelAnalysisResult, StockAnalysisAgent};
 pub use technical_analyzer::TechnicalAnalyzerAgent;
+pub use translator::TranslatorAgent;
@@ -1,33 +1,47 @@
 //! News and sentiment analysis agent
 
-use agent_core::{Agent, Context, Result};
+use agent_core::{Agent, AgentCapabilities, Context, Result};
 use agent_runtime::{AgentRuntime, ExecutorConfig};
 use async_trait::async_trait;
 use std::sync::Arc;
 
 use crate::cache::CacheManager;
 use crate::config::StockConfig;
-use crate::tools::NewsTool;
+use crate::router::QueryIntent;
+use crate::source_translation::SourceTranslator;
+use crate::tools::{EarningsCalendarTool, NewsTool, SupplyChainTool};
 
 /// Agent specialized in news and sentiment analysis
 pub struct NewsAnalyzerAgent {
     agent: agent_runtime::agents::ToolAgent,
+    /// API key the configured news provider needs, if any
+    required_key: Option<&'static str>,
 }
 
 impl NewsAnalyzerAgent {
     /// Create a new news analyzer agent
     pub async fn new(runtime: Arc<AgentRuntime>, config: Arc<StockConfig>) -> Result<Self> {
-        let cache_mgr = CacheManager::new(
-            config.cache_ttl_realtime,
-            config.cache_ttl_fundamental,
-            config.cache_ttl_news,
-        );
+        let cache_mgr = CacheManager::from_config(&config);
 
         // Create tools
-        let news_tool = Arc::new(NewsTool::new(Arc::clone(&config), cache_mgr.news.clone()));
+        let news_tool = Arc::new(
+            NewsTool::new(Arc::clone(&config), cache_mgr.news.clone())
+                .with_translator(SourceTranslator::from_config(&runtime, &config)),
+        );
+
+        let supply_chain_tool = Arc::new(SupplyChainTool::new(
+            Arc::clone(&config),
+            cache_mgr.earnings.clone(),
+        ));
+        let calendar_tool = Arc::new(EarningsCalendarTool::new(
+            Arc::clone(&config),
+            cache_mgr.news.clone(),
+        ));
 
         // Register tools
         runtime.tools().register(news_tool);
+        runtime.tools().register(supply_chain_tool);
+        runtime.tools().register(calendar_tool);
 
         // Get system prompt from registry
         let system_prompt = config
@@ -36,16 +50,19 @@ impl NewsAnalyzerAgent {
             .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))?;
 
         let executor_config = ExecutorConfig {
-            model: config.model.clone(),
+            model: config.model_for("news-analyzer"),
             system_prompt: Some(system_prompt),
-            max_tokens: config.max_tokens,
-            temperature: Some(config.temperature),
+            max_tokens: config.max_tokens_for("news-analyzer"),
+            temperature: Some(config.temperature_for("news-analyzer")),
             max_iterations: 5,
         };
 
         let agent = runtime.create_tool_agent(executor_config, "news-analyzer");
 
-        Ok(Self { agent })
+        Ok(Self {
+            agent,
+            required_key: config.news_provider.api_key_env(),
+        })
     }
 }
 
@@ -58,4 +75,14 @@ impl Agent for NewsAnalyzerAgent {
     fn name(&self) -> &'static str {
         "NewsAnalyzerAgent"
     }
+
+    fn capabilities(&self) -> AgentCapabilities {
+        let capabilities = AgentCapabilities::new("News headlines and market sentiment")
+            .with_intent(QueryIntent::NewsAnalysis.as_str())
+            .with_input("stock symbol");
+        match self.required_key {
+            Some(key) => capabilities.with_required_key(key),
+            None => capabilities,
+        }
+    }
 }
@@ -0,0 +1,227 @@
+//! Portfolio analysis agent
+
+use agent_core::{Agent, AgentCapabilities, Context, Result};
+use agent_runtime::{AgentRuntime, ExecutorConfig, PINNED_PARAMS_CONTEXT_KEY};
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+use crate::cache::{CacheCategory, StockCache};
+use crate::config::StockConfig;
+use crate::portfolio::{PortfolioStore, USER_ID_PARAM};
+use crate::tools::{PortfolioTool, SqlQueryTool};
+use crate::units::NormalizedTool;
+
+/// Agent answering questions about a user's recorded portfolio
+///
+/// Positions live in a [`PortfolioStore`] shared with the bot commands that
+/// edit them. The user is taken from the context's user id and pinned on
+/// the tool calls, so the model cannot read another user's positions.
+pub struct PortfolioAgent {
+    agent: agent_runtime::agents::ToolAgent,
+    config: Arc<StockConfig>,
+    store: Arc<PortfolioStore>,
+}
+
+impl PortfolioAgent {
+    /// Create a new portfolio agent over the positions in `store`
+    pub async fn new(
+        runtime: Arc<AgentRuntime>,
+        config: Arc<StockConfig>,
+        store: Arc<PortfolioStore>,
+    ) -> Result<Self> {
+        // Quotes change through the day; reuse them only briefly
+        let portfolio_tool = Arc::new(PortfolioTool::new(
+            Arc::clone(&store),
+            StockCache::from_config(&config, CacheCategory::Realtime),
+        ));
+        runtime
+            .tools()
+            .register(Arc::new(NormalizedTool::new(portfolio_tool)));
+
+        // Ad-hoc questions over holdings, price history and news sentiment
+        let sql_tool = Arc::new(SqlQueryTool::new(
+            Arc::clone(&store),
+            StockCache::from_config(&config, CacheCategory::Fundamental),
+        ));
+        runtime
+            .tools()
+            .register(Arc::new(NormalizedTool::new(sql_tool)));
+
+        // Get system prompt from registry
+        let system_prompt = config
+            .prompt_registry
+            .render("stock.portfolio_analyzer", &json!({}))
+            .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))?;
+
+        let executor_config = ExecutorConfig {
+            model: config.model_for("portfolio-analyzer"),
+            system_prompt: Some(system_prompt),
+            max_tokens: config.max_tokens_for("portfolio-analyzer"),
+            temperature: Some(config.temperature_for("portfolio-analyzer")),
+            max_iterations: 5,
+        };
+
+        let agent = runtime.create_tool_agent(executor_config, "portfolio-analyzer");
+
+        Ok(Self {
+            agent,
+            config,
+            store,
+        })
+    }
+
+    /// The positions the agent analyzes
+    pub fn store(&self) -> &Arc<PortfolioStore> {
+        &self.store
+    }
+
+    /// Answer `question` about the portfolio of `user_id`
+    pub async fn analyze(
+        &self,
+        user_id: &str,
+        question: &str,
+        context: &mut Context,
+    ) -> Result<String> {
+        let input = self
+            .config
+            .prompt_registry
+            .render(
+                "stock.user.analyze_portfolio",
+                &json!({ "question": question }),
+            )
+            .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))?;
+
+        // Pinned for this run only, alongside whatever the caller pinned
+        let outer = context.get(PINNED_PARAMS_CONTEXT_KEY).cloned();
+        let mut pinned = outer
+            .as_ref()
+            .and_then(Value::as_object)
+            .cloned()
+            .unwrap_or_default();
+        pinned.insert(USER_ID_PARAM.to_string(), json!(user_id));
+        context.insert(PINNED_PARAMS_CONTEXT_KEY, Value::Object(pinned));
+
+        let result = self.agent.process(input, context).await;
+        match outer {
+            Some(outer) => context.insert(PINNED_PARAMS_CONTEXT_KEY, outer),
+            None => {
+                context.remove(PINNED_PARAMS_CONTEXT_KEY);
+            }
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl Agent for PortfolioAgent {
+    async fn process(&self, input: String, context: &mut Context) -> Result<String> {
+        let user_id = context.user_id().map(ToString::to_string).ok_or_else(|| {
+            agent_core::Error::ProcessingFailed(
+                "Portfolio questions need the user id in the context".to_string(),
+            )
+        })?;
+        self.analyze(&user_id, &input, context).await
+    }
+
+    fn name(&self) -> &'static str {
+        "PortfolioAgent"
+    }
+
+    fn capabilities(&self) -> AgentCapabilities {
+        AgentCapabilities::new("Portfolio value, P&L, YTD return, beta and sector exposure")
+            .with_input("question about the user's holdings")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent_llm::{
+        CompletionRequest, CompletionResponse, ContentBlock, LLMProvider, Message, MessageContent,
+        Role, StopReason, TokenUsage,
+    };
+    use std::sync::Mutex;
+
+    /// Asks for another user's portfolio, then answers; records every request
+    #[derive(Default)]
+    struct Snooping {
+        requests: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl LLMProvider for Snooping {
+        async fn complete(
+            &self,
+            request: CompletionRequest,
+        ) -> agent_llm::Result<CompletionResponse> {
+            let mut requests = self.requests.lock().unwrap();
+            requests.push(serde_json::to_string(&request.messages).unwrap());
+            let (message, stop_reason) = if requests.len() == 1 {
+                let call = ContentBlock::ToolUse {
+                    id: "call_1".to_string(),
+                    name: "get_portfolio".to_string(),
+                    input: json!({ "user_id": "7" }),
+                };
+                let message = Message {
+                    role: Role::Assistant,
+                    content: Some(MessageContent::Blocks(vec![call])),
+                };
+                (message, StopReason::ToolUse)
+            } else {
+                (
+                    Message::assistant("You hold nothing yet."),
+                    StopReason::EndTurn,
+                )
+            };
+            Ok(CompletionResponse {
+                message,
+                stop_reason,
+                usage: TokenUsage {
+                    input_tokens: 0,
+                    output_tokens: 0,
+                },
+            })
+        }
+
+        fn name(&self) -> &str {
+            "snooping"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_model_cannot_pick_the_user() {
+        let provider = Arc::new(Snooping::default());
+        let runtime = Arc::new(
+            AgentRuntime::builder()
+                .provider(Arc::clone(&provider) as Arc<dyn LLMProvider>)
+                .build()
+                .unwrap(),
+        );
+        let store = Arc::new(PortfolioStore::in_memory());
+        store.add("7", "AAPL", 10.0, 150.0).unwrap();
+        let agent = PortfolioAgent::new(runtime, Arc::new(StockConfig::default()), store)
+            .await
+            .unwrap();
+
+        let mut context = Context::new();
+        let answer = agent
+            .analyze("42", "What do I hold?", &mut context)
+            .await
+            .unwrap();
+        assert_eq!(answer, "You hold nothing yet.");
+
+        // The tool valued the requesting user's (empty) portfolio, not user 7's
+        let requests = provider.requests.lock().unwrap();
+        assert!(!requests[0].contains("42"), "{}", requests[0]);
+        assert!(
+            requests[1].contains(r#"\"user_id\":\"42\""#),
+            "{}",
+            requests[1]
+        );
+        assert!(!requests[1].contains("AAPL"), "{}", requests[1]);
+
+        // The pin does not outlive the run
+        assert!(context.get(PINNED_PARAMS_CONTEXT_KEY).is_none());
+    }
+}
@@ -0,0 +1,504 @@
+//! Natural-language query builder agent
+//!
+//! Translates analytical questions ("tech stocks under 20x earnings",
+//! "average RSI of my holdings by sector") into a validated [`Screen`] or a
+//! read-only SQL query for the [`SqlQueryTool`], shows it for confirmation,
+//! runs it and explains the results. Generated queries that fail validation
+//! are sent back to the model once with the error.
+
+use agent_core::{Agent, AgentCapabilities, Context, Result};
+use agent_runtime::agents::SimpleAgent;
+use agent_runtime::{AgentRuntime, SimpleConfig};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::bot::Command;
+use crate::cache::CacheManager;
+use crate::config::StockConfig;
+use crate::error::StockError;
+use crate::portfolio::PortfolioStore;
+use crate::screener::Screen;
+use crate::tools::{ScreenerTool, SqlQueryTool, sql_query};
+
+/// Attempts at generating a query that passes validation
+const MAX_ATTEMPTS: usize = 2;
+
+/// Characters of query results included in the explanation prompt
+const MAX_RESULT_CHARS: usize = 8_000;
+
+/// A query generated from a question, validated and ready to run
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeneratedQuery {
+    /// A stock screen over `universe` (see [`ScreenerTool::resolve_universe`])
+    Screen {
+        screen: Screen,
+        universe: Vec<String>,
+    },
+    /// A read-only SQL query loading `symbols` besides the user's holdings
+    Sql {
+        query: String,
+        symbols: Vec<String>,
+        days: Option<u32>,
+    },
+}
+
+impl GeneratedQuery {
+    /// Parse and validate the model's JSON reply, tolerating surrounding
+    /// prose or code fences
+    pub fn parse(reply: &str) -> crate::error::Result<Self> {
+        #[derive(Deserialize)]
+        #[serde(tag = "kind", rename_all = "snake_case")]
+        enum RawQuery {
+            Screen {
+                screen: String,
+                #[serde(default)]
+                universe: Vec<String>,
+            },
+            Sql {
+                query: String,
+                #[serde(default)]
+                symbols: Vec<String>,
+                #[serde(default)]
+                days: Option<u32>,
+            },
+        }
+
+        let invalid = || StockError::CommandError(format!("Expected a JSON query, got: {reply}"));
+        let start = reply.find('{').ok_or_else(invalid)?;
+        let end = reply.rfind('}').ok_or_else(invalid)?;
+        let raw: RawQuery = serde_json::from_str(reply.get(start..=end).ok_or_else(invalid)?)
+            .map_err(|e| StockError::CommandError(format!("Invalid query JSON: {e}")))?;
+
+        match raw {
+            RawQuery::Screen { screen, universe } => Ok(Self::Screen {
+                screen: screen.parse()?,
+                universe,
+            }),
+            RawQuery::Sql {
+                query,
+                symbols,
+                days,
+            } => {
+                sql_query::validate(&query)?;
+                Ok(Self::Sql {
+                    query: query.trim().to_string(),
+                    symbols,
+                    days,
+                })
+            }
+        }
+    }
+}
+
+impl fmt::Display for GeneratedQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Screen { screen, universe } => {
+                write!(f, "Screen: {screen}")?;
+                if let Some(order) = screen.sort {
+                    let direction = if order.descending {
+                        "highest"
+                    } else {
+                        "lowest"
+                    };
+                    write!(f, ", sorted by {} {direction} first", order.metric.as_str())?;
+                }
+                write!(f, ", top {}", screen.limit)?;
+                if universe.is_empty() {
+                    f.write_str(" (default universe)")
+                } else {
+                    write!(f, " over {}", universe.join(", "))
+                }
+            }
+            Self::Sql {
+                query,
+                symbols,
+                days,
+            } => {
+                write!(f, "```sql\n{query}\n```")?;
+                if !symbols.is_empty() {
+                    write!(f, "\nLoading: {} and your holdings", symbols.join(", "))?;
+                }
+                if let Some(days) = days {
+                    write!(f, "\nPrice history: {days} days")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A generated query waiting for the user's confirmation
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingQuery {
+    pub question: String,
+    pub query: GeneratedQuery,
+}
+
+/// Agent turning questions into screens or SQL, then explaining the results
+pub struct QueryBuilderAgent {
+    translator: SimpleAgent,
+    explainer: SimpleAgent,
+    config: Arc<StockConfig>,
+    screener: ScreenerTool,
+    sql: SqlQueryTool,
+    /// Queries shown to each user and not yet run
+    pending: Mutex<HashMap<String, PendingQuery>>,
+}
+
+impl QueryBuilderAgent {
+    /// Create a new query builder; SQL queries see the positions in `store`
+    pub fn new(
+        runtime: &AgentRuntime,
+        config: Arc<StockConfig>,
+        store: Arc<PortfolioStore>,
+    ) -> Result<Self> {
+        let caches = CacheManager::from_config(&config);
+        let simple_agent = |prompt: &str, agent: &str| -> Result<SimpleAgent> {
+            let system_prompt = config
+                .prompt_registry
+                .render(prompt, &json!({}))
+                .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))?;
+            let simple_config = SimpleConfig {
+                model: config.model_for(agent),
+                system_prompt,
+                max_tokens: config.max_tokens_for(agent),
+                temperature: config.temperature_for(agent),
+            };
+            Ok(runtime.create_simple_agent(simple_config, agent))
+        };
+
+        Ok(Self {
+            translator: simple_agent("stock.query_builder", "query-builder")?,
+            explainer: simple_agent("stock.query_explainer", "query-builder")?,
+            screener: ScreenerTool::new(
+                &config,
+                caches.fundamental.clone(),
+                caches.realtime.clone(),
+            ),
+            sql: SqlQueryTool::new(store, caches.fundamental.clone()),
+            config,
+            pending: Mutex::default(),
+        })
+    }
+
+    /// Run screens with `screener` instead of the default one
+    pub fn with_screener(mut self, screener: ScreenerTool) -> Self {
+        self.screener = screener;
+        self
+    }
+
+    /// Run SQL with `sql` instead of the default tool
+    pub fn with_sql_tool(mut self, sql: SqlQueryTool) -> Self {
+        self.sql = sql;
+        self
+    }
+
+    /// Translate `question` into a validated query
+    ///
+    /// A query failing validation is sent back to the model with the error;
+    /// the last error is returned if no attempt passes.
+    pub async fn translate(&self, question: &str, context: &mut Context) -> Result<GeneratedQuery> {
+        let mut rejected: Option<(String, String)> = None;
+        for _ in 0..MAX_ATTEMPTS {
+            let (query, error) = rejected.clone().unwrap_or_default();
+            let input = self.render(
+                "stock.user.build_query",
+                &json!({ "question": question, "query": query, "error": error }),
+            )?;
+            let reply = self.translator.process(input, context).await?;
+            match GeneratedQuery::parse(&reply) {
+                Ok(query) => return Ok(query),
+                Err(e) => {
+                    tracing::debug!("Generated query rejected: {}", e);
+                    rejected = Some((reply, e.to_string()));
+                }
+            }
+        }
+        let (_, error) = rejected.unwrap_or_default();
+        Err(StockError::CommandError(format!("Could not build a valid query: {error}")).into())
+    }
+
+    /// Translate `question` and keep the query until `user_id` confirms it
+    /// with [`QueryBuilderAgent::confirm`]
+    pub async fn propose(
+        &self,
+        user_id: &str,
+        question: &str,
+        context: &mut Context,
+    ) -> Result<GeneratedQuery> {
+        let query = self.translate(question, context).await?;
+        let pending = PendingQuery {
+            question: question.to_string(),
+            query: query.clone(),
+        };
+        self.lock().insert(user_id.to_string(), pending);
+        Ok(query)
+    }
+
+    /// The query waiting for `user_id` to confirm it
+    pub fn pending(&self, user_id: &str) -> Option<PendingQuery> {
+        self.lock().get(user_id).cloned()
+    }
+
+    /// Run the query proposed to `user_id` and explain its results
+    pub async fn confirm(&self, user_id: &str, context: &mut Context) -> Result<String> {
+        let pending = self.lock().remove(user_id).ok_or_else(|| {
+            StockError::CommandError(
+                "No query to run. Ask a question with /ask <question> first".to_string(),
+            )
+        })?;
+        let results = self.execute(user_id, &pending.query).await?;
+        self.explain(&pending.question, &pending.query, &results, context)
+            .await
+    }
+
+    /// Run `query` for `user_id`, returning its results as JSON
+    pub async fn execute(&self, user_id: &str, query: &GeneratedQuery) -> Result<Value> {
+        let results = match query {
+            GeneratedQuery::Screen { screen, universe } => {
+                let universe = self.screener.resolve_universe(universe);
+                self.screener
+                    .screen_universe(screen.clone(), &universe)
+                    .await
+                    .map(|result| result.to_value())
+            }
+            GeneratedQuery::Sql {
+                query,
+                symbols,
+                days,
+            } => self.sql.run(user_id, query, symbols, *days).await,
+        };
+        Ok(results?)
+    }
+
+    /// Explain `results` of `query` as an answer to `question`
+    pub async fn explain(
+        &self,
+        question: &str,
+        query: &GeneratedQuery,
+        results: &Value,
+        context: &mut Context,
+    ) -> Result<String> {
+        let mut results = results.to_string();
+        if results.len() > MAX_RESULT_CHARS {
+            let cut = (0..=MAX_RESULT_CHARS)
+                .rev()
+                .find(|&i| results.is_char_boundary(i))
+                .unwrap_or(0);
+            results.truncate(cut);
+            results.push_str("...");
+        }
+        let input = self.render(
+            "stock.user.explain_query",
+            &json!({ "question": question, "query": query.to_string(), "results": results }),
+        )?;
+        self.explainer.process(input, context).await
+    }
+
+    fn render(&self, template: &str, vars: &Value) -> Result<String> {
+        self.config
+            .prompt_registry
+            .render(template, vars)
+            .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, PendingQuery>> {
+        self.pending.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[async_trait]
+impl Agent for QueryBuilderAgent {
+    /// Translate, run and explain in one go, without confirmation; the
+    /// answer starts with the query that was run
+    async fn process(&self, input: String, context: &mut Context) -> Result<String> {
+        let user_id = context.user_id().unwrap_or_default().to_string();
+        let query = self.translate(&input, context).await?;
+        let results = self.execute(&user_id, &query).await?;
+        let explanation = self.explain(&input, &query, &results, context).await?;
+        Ok(format!("{query}\n\n{explanation}"))
+    }
+
+    fn name(&self) -> &'static str {
+        "QueryBuilderAgent"
+    }
+
+    fn capabilities(&self) -> AgentCapabilities {
+        AgentCapabilities::new("Screens and SQL queries built from plain-language questions")
+            .with_input("analytical question about stocks, prices, news or holdings")
+    }
+}
+
+/// Reply to `/ask <question>` or `/ask run` from `user_id`, for bots with
+/// the query builder
+pub async fn command_reply(
+    builder: Option<&QueryBuilderAgent>,
+    user_id: &str,
+    command: &Command,
+    context: &mut Context,
+) -> crate::error::Result<String> {
+    let Some(builder) = builder else {
+        return Ok("🧮 The query builder is not enabled on this bot".to_string());
+    };
+    match command {
+        Command::Ask { question } => {
+            let query = builder.propose(user_id, question, context).await?;
+            Ok(format!(
+                "🧮 {query}\n\nReply /ask run to run it, or ask again to change it."
+            ))
+        }
+        Command::AskRun => Ok(builder.confirm(user_id, context).await?),
+        _ => Err(StockError::CommandError(format!(
+            "Not a query builder command: {}",
+            command.name()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::testing::MockApi;
+    use agent_llm::{
+        CompletionRequest, CompletionResponse, LLMProvider, Message, StopReason, TokenUsage,
+    };
+    use std::time::Duration;
+
+    /// Replies with `replies` in order, recording every prompt
+    struct Scripted {
+        replies: Mutex<Vec<&'static str>>,
+        prompts: Mutex<Vec<String>>,
+    }
+
+    impl Scripted {
+        fn new(replies: &[&'static str]) -> Arc<Self> {
+            Arc::new(Self {
+                replies: Mutex::new(replies.iter().rev().copied().collect()),
+                prompts: Mutex::default(),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for Scripted {
+        async fn complete(
+            &self,
+            request: CompletionRequest,
+        ) -> agent_llm::Result<CompletionResponse> {
+            let prompt = request.messages[0].text().unwrap_or_default().to_string();
+            self.prompts.lock().unwrap().push(prompt);
+            let reply = self.replies.lock().unwrap().pop().unwrap_or("{}");
+            Ok(CompletionResponse {
+                message: Message::assistant(reply),
+                stop_reason: StopReason::EndTurn,
+                usage: TokenUsage {
+                    input_tokens: 0,
+                    output_tokens: 0,
+                },
+            })
+        }
+
+        fn name(&self) -> &str {
+            "scripted"
+        }
+    }
+
+    fn builder(provider: Arc<Scripted>, api: &MockApi) -> QueryBuilderAgent {
+        let runtime = AgentRuntime::builder().provider(provider).build().unwrap();
+        let config = Arc::new(StockConfig::default());
+        let store = Arc::new(PortfolioStore::in_memory());
+        store.add("42", "AAPL", 10.0, 150.0).unwrap();
+        let sql = SqlQueryTool::new(
+            Arc::clone(&store),
+            crate::cache::StockCache::new(Duration::from_secs(60)),
+        )
+        .with_client(api.yahoo());
+        QueryBuilderAgent::new(&runtime, config, store)
+            .unwrap()
+            .with_sql_tool(sql)
+    }
+
+    #[test]
+    fn test_parse_generated_queries() {
+        let reply = "```json\n{\"kind\": \"screen\", \"screen\": \"pe<20, sector=Technology, \
+                     sort=-market_cap, limit=5\", \"universe\": [\"ai\"]}\n```";
+        let query = GeneratedQuery::parse(reply).unwrap();
+        assert_eq!(
+            query.to_string(),
+            "Screen: pe < 20, sector = Technology, sorted by market_cap highest first, top 5 over ai"
+        );
+
+        let query = GeneratedQuery::parse(
+            r#"{"kind": "sql", "query": "SELECT symbol, AVG(rsi_14) FROM prices GROUP BY symbol", "symbols": ["MSFT"]}"#,
+        )
+        .unwrap();
+        assert!(matches!(&query, GeneratedQuery::Sql { days: None, .. }));
+        assert!(
+            query
+                .to_string()
+                .ends_with("Loading: MSFT and your holdings")
+        );
+
+        // Writes, unknown tables and bad filters never reach execution
+        for reply in [
+            r#"{"kind": "sql", "query": "DELETE FROM portfolio"}"#,
+            r#"{"kind": "sql", "query": "SELECT * FROM trades"}"#,
+            r#"{"kind": "screen", "screen": "moat>5"}"#,
+            "I cannot answer that",
+        ] {
+            assert!(GeneratedQuery::parse(reply).is_err(), "{reply}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_propose_confirm_and_explain() {
+        let api = MockApi::recorded().await;
+        let provider = Scripted::new(&[
+            r#"{"kind": "sql", "query": "DELETE FROM portfolio"}"#,
+            r#"{"kind": "sql", "query": "SELECT symbol, quantity FROM portfolio"}"#,
+            "You hold 10 shares of AAPL.",
+        ]);
+        let agent = builder(Arc::clone(&provider), &api);
+        let mut context = Context::new();
+
+        let reply = command_reply(
+            Some(&agent),
+            "42",
+            &Command::Ask {
+                question: "How many shares do I hold?".to_string(),
+            },
+            &mut context,
+        )
+        .await
+        .unwrap();
+        assert!(
+            reply.contains("SELECT symbol, quantity FROM portfolio"),
+            "{reply}"
+        );
+        assert!(agent.pending("42").is_some());
+        assert!(agent.pending("7").is_none());
+
+        let answer = command_reply(Some(&agent), "42", &Command::AskRun, &mut context)
+            .await
+            .unwrap();
+        assert_eq!(answer, "You hold 10 shares of AAPL.");
+        assert!(agent.pending("42").is_none());
+
+        let prompts = provider.prompts.lock().unwrap();
+        // The rejected query went back to the model with its error
+        assert!(prompts[1].contains("read-only"), "{}", prompts[1]);
+        // The explanation saw the rows
+        assert!(prompts[2].contains(r#"[["AAPL",10.0]]"#), "{}", prompts[2]);
+
+        // Nothing left to confirm
+        let err = command_reply(Some(&agent), "42", &Command::AskRun, &mut context)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("/ask"), "{err}");
+    }
+}
@@ -3,36 +3,77 @@
 //! This module provides the main entry point for stock analysis, with support for:
 //! - Smart routing based on query intent
 //! - Parallel execution of multiple agents for comprehensive analysis
+//! - Quick, standard and deep analysis levels (see [`AnalysisDepth`])
 //! - Context-aware processing
 
 use agent_core::{Agent, Context, Result};
-use agent_runtime::{AgentRuntime, agents::DelegatingAgentBuilder};
+use agent_llm::EmbeddingProvider;
+use agent_runtime::usage::{self, UsageSummary};
+use agent_runtime::{
+    AgentAvailability, AgentRegistry, AgentRuntime, agents::DelegatingAgentBuilder,
+};
 use async_trait::async_trait;
+use chrono::NaiveDate;
 use std::sync::Arc;
 
 use super::{
-    DataFetcherAgent, EarningsAnalyzerAgent, FundamentalAnalyzerAgent,
+    DataFetcherAgent, EarningsAnalyzerAgent, EsgAnalyzerAgent, FundamentalAnalyzerAgent,
     MacroAnalyzerAgent, NewsAnalyzerAgent, TechnicalAnalyzerAgent,
 };
+use crate::as_of;
 use crate::config::StockConfig;
-use crate::router::{QueryIntent, SmartRouter};
+use crate::crypto;
+use crate::depth::AnalysisDepth;
+use crate::macro_alerts::MacroAlert;
+use crate::market_wrap::MarketWrapData;
+use crate::news_digest::NewsDigestData;
+use crate::plugin::{AnalyzerPlugin, install_plugin, validate_plugins};
+use crate::router::{QueryIntent, RoutingResult, SmartRouter};
+use crate::style::{self, ResponseStyle};
+use crate::tools::ThemeBasket;
+use crate::tools::glossary::{GLOSSARY, TermCategory};
+use crate::units;
+
+/// Stock used for worked examples when explaining financial terms
+const EXPLAIN_EXAMPLE_SYMBOL: &str = "AAPL";
 
 /// Top-level stock analysis agent that delegates to specialists
 pub struct StockAnalysisAgent {
     agent: agent_runtime::agents::DelegatingAgent,
     router: SmartRouter,
-    // Store individual agents for parallel execution (reserved for future use)
-    _data_fetcher: Arc<DataFetcherAgent>,
+    config: Arc<StockConfig>,
+    // Individual agents for parallel execution and direct routing
+    data_fetcher: Arc<DataFetcherAgent>,
     technical_analyzer: Arc<TechnicalAnalyzerAgent>,
     fundamental_analyzer: Arc<FundamentalAnalyzerAgent>,
     news_analyzer: Arc<NewsAnalyzerAgent>,
     earnings_analyzer: Arc<EarningsAnalyzerAgent>,
     macro_analyzer: Arc<MacroAnalyzerAgent>,
+    esg_analyzer: Arc<EsgAnalyzerAgent>,
 }
 
 impl StockAnalysisAgent {
     /// Create a new stock analysis agent
     pub async fn new(runtime: Arc<AgentRuntime>, config: Arc<StockConfig>) -> Result<Self> {
+        Self::with_plugins(runtime, config, &[]).await
+    }
+
+    /// Create a stock analysis agent with third-party analyzers
+    ///
+    /// Each plugin's agent joins the delegation registry under its key, and
+    /// queries matching its keywords are routed to it.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a plugin key clashes with a specialist or another plugin, or
+    /// if a plugin fails to install.
+    pub async fn with_plugins(
+        runtime: Arc<AgentRuntime>,
+        config: Arc<StockConfig>,
+        plugins: &[Arc<dyn AnalyzerPlugin>],
+    ) -> Result<Self> {
+        validate_plugins(plugins)?;
+
         // Create specialist agents
         let data_fetcher =
             Arc::new(DataFetcherAgent::new(Arc::clone(&runtime), Arc::clone(&config)).await?);
@@ -47,54 +88,203 @@ impl StockAnalysisAgent {
             Arc::new(EarningsAnalyzerAgent::new(Arc::clone(&runtime), Arc::clone(&config)).await?);
         let macro_analyzer =
             Arc::new(MacroAnalyzerAgent::new(Arc::clone(&runtime), Arc::clone(&config)).await?);
+        let esg_analyzer =
+            Arc::new(EsgAnalyzerAgent::new(Arc::clone(&runtime), Arc::clone(&config)).await?);
 
-        // Create smart router
-        let smart_router = SmartRouter::new();
+        // Register sub-agents under their routing keys
+        // Clone as Arc<dyn Agent> for the registry
+        let mut registry = AgentRegistry::new();
+        registry.register_as("data-fetcher", Arc::clone(&data_fetcher) as Arc<dyn Agent>);
+        registry.register_as(
+            "technical-analyzer",
+            Arc::clone(&technical_analyzer) as Arc<dyn Agent>,
+        );
+        registry.register_as(
+            "fundamental-analyzer",
+            Arc::clone(&fundamental_analyzer) as Arc<dyn Agent>,
+        );
+        registry.register_as(
+            "news-analyzer",
+            Arc::clone(&news_analyzer) as Arc<dyn Agent>,
+        );
+        registry.register_as(
+            "earnings-analyzer",
+            Arc::clone(&earnings_analyzer) as Arc<dyn Agent>,
+        );
+        registry.register_as(
+            "macro-analyzer",
+            Arc::clone(&macro_analyzer) as Arc<dyn Agent>,
+        );
+        registry.register_as("esg-analyzer", Arc::clone(&esg_analyzer) as Arc<dyn Agent>);
+
+        // Install plugin analyzers alongside the specialists
+        let mut smart_router = SmartRouter::new();
+        for plugin in plugins {
+            let agent = install_plugin(plugin.as_ref(), &runtime, &config).await?;
+            registry.register_as(plugin.key(), agent);
+            smart_router = smart_router.with_plugin_route(plugin.key(), plugin.keywords());
+        }
+
+        // Label the units of every tool result the sub-agents see
+        units::normalize_tools(runtime.tools());
+
+        // Route to the agents usable with the configured keys
+        let smart_router =
+            smart_router.with_routing_table(registry.routing_table(|key| config.has_api_key(key)));
 
         // Create routing function using smart router
-        let routing_fn = |input: &str, _context: &Context| -> String {
-            let router = SmartRouter::new();
+        let router = smart_router.clone();
+        let routing_fn = move |input: &str, _context: &Context| -> String {
+            if let Some(key) = router.plugin_route(input) {
+                return key.to_string();
+            }
             let intent = router.classify(input);
-            intent.agent_name().to_string()
+            router
+                .get_agents(intent)
+                .first()
+                .map_or(intent.agent_name(), |agent| agent)
+                .to_string()
         };
 
         // Build delegating agent with all sub-agents
-        // Clone as Arc<dyn Agent> for the delegating agent builder
-        let agent = DelegatingAgentBuilder::new(Arc::clone(&runtime), "stock-analysis")
-            .add_agent("data-fetcher", Arc::clone(&data_fetcher) as Arc<dyn Agent>)
-            .add_agent("technical-analyzer", Arc::clone(&technical_analyzer) as Arc<dyn Agent>)
-            .add_agent("fundamental-analyzer", Arc::clone(&fundamental_analyzer) as Arc<dyn Agent>)
-            .add_agent("news-analyzer", Arc::clone(&news_analyzer) as Arc<dyn Agent>)
-            .add_agent("earnings-analyzer", Arc::clone(&earnings_analyzer) as Arc<dyn Agent>)
-            .add_agent("macro-analyzer", Arc::clone(&macro_analyzer) as Arc<dyn Agent>)
+        let agent = DelegatingAgentBuilder::new(runtime, "stock-analysis")
+            .add_registry(&registry)
             .router(routing_fn)
             .build()?;
 
         Ok(Self {
             agent,
             router: smart_router,
-            _data_fetcher: data_fetcher,
+            config,
+            data_fetcher,
             technical_analyzer,
             fundamental_analyzer,
             news_analyzer,
             earnings_analyzer,
             macro_analyzer,
+            esg_analyzer,
         })
     }
 
+    /// Response style for a request: from the context, else the configured default
+    pub fn response_style(&self, context: &Context) -> ResponseStyle {
+        ResponseStyle::from_context(context).unwrap_or(self.config.response_style)
+    }
+
+    /// Prepend the rendered response style instructions to an agent input
+    ///
+    /// Analyses as of a past date also get the point-in-time instructions.
+    fn styled_input(&self, input: String, context: &Context) -> String {
+        let input = self.dated_input(input, context);
+        let style = self.response_style(context);
+        match self
+            .config
+            .prompt_registry
+            .render("stock.response_style", &style.template_vars())
+        {
+            Ok(instructions) => format!("{instructions}\n\n{input}"),
+            Err(e) => {
+                tracing::warn!("Failed to render response style '{}': {}", style, e);
+                input
+            }
+        }
+    }
+
+    /// Prepend the point-in-time instructions when analysing as of a past date
+    ///
+    /// The tools are limited to the date by the context (see [`as_of`]); the
+    /// instructions keep the model from filling in what it knows happened later.
+    fn dated_input(&self, input: String, context: &Context) -> String {
+        let Some(date) = as_of::from_context(context) else {
+            return input;
+        };
+        match self.config.prompt_registry.render(
+            "stock.as_of",
+            &serde_json::json!({ "as_of": date.to_string() }),
+        ) {
+            Ok(instructions) => format!("{instructions}\n\n{input}"),
+            Err(e) => {
+                tracing::warn!("Failed to render point-in-time instructions: {}", e);
+                input
+            }
+        }
+    }
+
+    /// Analysis depth for a request: from the context, else the configured default
+    pub fn analysis_depth(&self, context: &Context) -> AnalysisDepth {
+        AnalysisDepth::from_context(context).unwrap_or(self.config.analysis_depth)
+    }
+
+    /// Whether teaching mode is on: from the context, else the configured default
+    pub fn teaching_mode(&self, context: &Context) -> bool {
+        style::teaching_mode(context).unwrap_or(self.config.teaching_mode)
+    }
+
+    /// Prepend educational walkthrough instructions when teaching mode is on
+    ///
+    /// The instructions list the glossary formulas for the given category so
+    /// the agent shows the same formulas the glossary explains.
+    fn teaching_input(&self, input: String, context: &Context, category: TermCategory) -> String {
+        if !self.teaching_mode(context) {
+            return input;
+        }
+
+        let formulas: Vec<_> = GLOSSARY
+            .iter()
+            .filter(|entry| entry.category == category)
+            .filter_map(|entry| {
+                entry
+                    .formula
+                    .map(|formula| serde_json::json!({ "term": entry.term, "formula": formula }))
+            })
+            .collect();
+        match self.config.prompt_registry.render(
+            "stock.teaching_mode",
+            &serde_json::json!({ "formulas": formulas }),
+        ) {
+            Ok(instructions) => format!("{instructions}\n\n{input}"),
+            Err(e) => {
+                tracing::warn!("Failed to render teaching mode instructions: {}", e);
+                input
+            }
+        }
+    }
+
     /// Execute parallel analysis across all agents for comprehensive results
-    async fn parallel_analysis(&self, symbol: &str) -> Result<ParallelAnalysisResult> {
+    async fn parallel_analysis(
+        &self,
+        symbol: &str,
+        context: &Context,
+    ) -> Result<ParallelAnalysisResult> {
         tracing::info!("Starting parallel analysis for {}", symbol);
 
+        // Each agent gets its own copy of the context
+        let mut technical_ctx = fork(context);
+        let mut fundamental_ctx = fork(context);
+        let mut news_ctx = fork(context);
+        let mut earnings_ctx = fork(context);
+        let mut macro_ctx = fork(context);
+
         // Execute all analyses in parallel
         let (technical, fundamental, news, earnings, macro_result) = tokio::join!(
-            self.run_technical(symbol),
-            self.run_fundamental(symbol),
-            self.run_news(symbol),
-            self.run_earnings(symbol),
-            self.run_macro(),
+            self.run_technical(symbol, &mut technical_ctx),
+            self.run_fundamental(symbol, &mut fundamental_ctx),
+            self.run_news(symbol, &mut news_ctx),
+            self.run_earnings(symbol, &mut earnings_ctx),
+            self.run_macro(&mut macro_ctx),
         );
 
+        let mut usage = UsageSummary::default();
+        for ctx in [
+            &technical_ctx,
+            &fundamental_ctx,
+            &news_ctx,
+            &earnings_ctx,
+            &macro_ctx,
+        ] {
+            usage.merge(&usage::from_context(ctx));
+        }
+
         Ok(ParallelAnalysisResult {
             symbol: symbol.to_string(),
             technical: technical.ok(),
@@ -102,37 +292,111 @@ impl StockAnalysisAgent {
             news: news.ok(),
             earnings: earnings.ok(),
             macro_analysis: macro_result.ok(),
+            filings: None,
+            peers: None,
+            scenarios: None,
+            usage,
         })
     }
 
-    async fn run_technical(&self, symbol: &str) -> Result<String> {
-        let mut ctx = Context::new();
-        let input = format!("Perform technical analysis on {symbol} using RSI, MACD, and moving averages.");
-        self.technical_analyzer.process(input, &mut ctx).await
+    /// Execute the standard parallel analysis plus the deep-only sections
+    ///
+    /// Filings and peer comparison run alongside the standard specialists;
+    /// the scenario analysis runs last so it can build on their findings.
+    async fn deep_analysis(
+        &self,
+        symbol: &str,
+        context: &Context,
+    ) -> Result<ParallelAnalysisResult> {
+        tracing::info!("Starting deep analysis for {}", symbol);
+
+        let mut filings_ctx = fork(context);
+        let mut peers_ctx = fork(context);
+        let (standard, filings, peers) = tokio::join!(
+            self.parallel_analysis(symbol, context),
+            self.run_deep(symbol, "filings", None, &mut filings_ctx),
+            self.run_deep(symbol, "peers", None, &mut peers_ctx),
+        );
+        let mut result = standard?;
+        result.filings = filings.ok();
+        result.peers = peers.ok();
+
+        let findings = result.format_summary();
+        let mut scenarios_ctx = fork(context);
+        result.scenarios = self
+            .run_deep(symbol, "scenarios", Some(&findings), &mut scenarios_ctx)
+            .await
+            .ok();
+        for ctx in [&filings_ctx, &peers_ctx, &scenarios_ctx] {
+            result.usage.merge(&usage::from_context(ctx));
+        }
+        Ok(result)
+    }
+
+    /// Run one deep-only section: SEC filings review, peer comparison or scenarios
+    async fn run_deep(
+        &self,
+        symbol: &str,
+        focus: &str,
+        findings: Option<&str>,
+        ctx: &mut Context,
+    ) -> Result<String> {
+        if focus != "scenarios" {
+            require_company(symbol, focus)?;
+        }
+        let input = self
+            .config
+            .prompt_registry
+            .render(
+                "stock.user.deep_analysis",
+                &serde_json::json!({ "symbol": symbol, "focus": focus, "findings": findings }),
+            )
+            .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))?;
+        let input = self.styled_input(input, ctx);
+        match focus {
+            "filings" => self.earnings_analyzer.process(input, ctx).await,
+            _ => self.fundamental_analyzer.process(input, ctx).await,
+        }
+    }
+
+    async fn run_technical(&self, symbol: &str, ctx: &mut Context) -> Result<String> {
+        let input =
+            format!("Perform technical analysis on {symbol} using RSI, MACD, and moving averages.");
+        let input = self.teaching_input(input, ctx, TermCategory::Technical);
+        let input = self.styled_input(input, ctx);
+        self.technical_analyzer.process(input, ctx).await
     }
 
-    async fn run_fundamental(&self, symbol: &str) -> Result<String> {
-        let mut ctx = Context::new();
+    async fn run_fundamental(&self, symbol: &str, ctx: &mut Context) -> Result<String> {
+        require_company(symbol, "fundamental")?;
         let input = format!("Analyze the fundamental metrics and valuation of {symbol}.");
-        self.fundamental_analyzer.process(input, &mut ctx).await
+        let input = self.teaching_input(input, ctx, TermCategory::Fundamental);
+        let input = self.styled_input(input, ctx);
+        self.fundamental_analyzer.process(input, ctx).await
     }
 
-    async fn run_news(&self, symbol: &str) -> Result<String> {
-        let mut ctx = Context::new();
+    async fn run_news(&self, symbol: &str, ctx: &mut Context) -> Result<String> {
         let input = format!("Analyze recent news and market sentiment for {symbol}.");
-        self.news_analyzer.process(input, &mut ctx).await
+        let input = self.styled_input(input, ctx);
+        self.news_analyzer.process(input, ctx).await
     }
 
-    async fn run_earnings(&self, symbol: &str) -> Result<String> {
-        let mut ctx = Context::new();
+    async fn run_earnings(&self, symbol: &str, ctx: &mut Context) -> Result<String> {
+        require_company(symbol, "earnings")?;
         let input = format!("Analyze the earnings reports and financial statements for {symbol}.");
-        self.earnings_analyzer.process(input, &mut ctx).await
+        let input = self.styled_input(input, ctx);
+        self.earnings_analyzer.process(input, ctx).await
     }
 
-    async fn run_macro(&self) -> Result<String> {
-        let mut ctx = Context::new();
+    async fn run_macro(&self, ctx: &mut Context) -> Result<String> {
         let input = "Analyze the current macroeconomic environment, including Fed policy, inflation, and economic indicators.".to_string();
-        self.macro_analyzer.process(input, &mut ctx).await
+        let input = self.styled_input(input, ctx);
+        self.macro_analyzer.process(input, ctx).await
+    }
+
+    /// Get the macro analyzer, e.g. to keep its data warm in the background
+    pub fn macro_analyzer(&self) -> &Arc<MacroAnalyzerAgent> {
+        &self.macro_analyzer
     }
 
     /// Get the router for external use
@@ -140,89 +404,367 @@ impl StockAnalysisAgent {
         &self.router
     }
 
-    /// Analyze a stock symbol with comprehensive analysis
-    pub async fn analyze(&self, symbol: &str) -> Result<String> {
-        let mut context = Context::new();
-        let input = format!(
-            "Provide a comprehensive analysis of {symbol} including current price, \
-             technical indicators, fundamental metrics, recent earnings, and news."
-        );
-        self.process(input, &mut context).await
+    /// Classify queries the keywords leave ambiguous by embedding
+    /// similarity in [`smart_process`](Self::smart_process)
+    pub fn with_embeddings(mut self, provider: Arc<dyn EmbeddingProvider>) -> Self {
+        self.router = self.router.with_embeddings(provider);
+        self
+    }
+
+    /// Specialist agents, keyed by routing key
+    pub fn registry(&self) -> &AgentRegistry {
+        self.agent.registry()
+    }
+
+    /// What the bot can currently do given the configured API keys
+    pub fn capabilities_report(&self) -> String {
+        format_capabilities(
+            &self
+                .registry()
+                .availability(|key| self.config.has_api_key(key)),
+        )
+    }
+
+    /// Analyze a stock symbol at the requested [`AnalysisDepth`]
+    ///
+    /// All analysis methods take a context carrying per-request options such
+    /// as the [`ResponseStyle`] and depth; pass `Context::new()` to use the
+    /// defaults.
+    pub async fn analyze(&self, symbol: &str, context: &mut Context) -> Result<String> {
+        match self.analysis_depth(context) {
+            AnalysisDepth::Quick => self.analyze_quick(symbol, context).await,
+            AnalysisDepth::Standard => {
+                let input = format!(
+                    "Provide a comprehensive analysis of {symbol} including current price, \
+                     technical indicators, fundamental metrics, recent earnings, and news."
+                );
+                self.process(input, context).await
+            }
+            AnalysisDepth::Deep => {
+                let result = self.deep_analysis(symbol, context).await?;
+                usage::add_to_context(context, &result.usage);
+                Ok(result.format_report())
+            }
+        }
+    }
+
+    /// Short summary from the latest quote and fundamentals
+    ///
+    /// A single data-fetcher call whose tool reads are served from the cache
+    /// when warm; see [`AnalysisDepth::Quick`].
+    pub async fn analyze_quick(&self, symbol: &str, context: &mut Context) -> Result<String> {
+        let input = self
+            .config
+            .prompt_registry
+            .render(
+                "stock.user.quick_summary",
+                &serde_json::json!({ "symbol": symbol }),
+            )
+            .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))?;
+        let input = self.styled_input(input, context);
+        self.data_fetcher.process(input, context).await
     }
 
     /// Get technical analysis only
-    pub async fn analyze_technical(&self, symbol: &str) -> Result<String> {
-        self.run_technical(symbol).await
+    pub async fn analyze_technical(&self, symbol: &str, context: &mut Context) -> Result<String> {
+        self.run_technical(symbol, context).await
     }
 
     /// Get fundamental analysis only
-    pub async fn analyze_fundamental(&self, symbol: &str) -> Result<String> {
-        self.run_fundamental(symbol).await
+    pub async fn analyze_fundamental(&self, symbol: &str, context: &mut Context) -> Result<String> {
+        self.run_fundamental(symbol, context).await
     }
 
     /// Get news and sentiment analysis only
-    pub async fn analyze_news(&self, symbol: &str) -> Result<String> {
-        self.run_news(symbol).await
+    pub async fn analyze_news(&self, symbol: &str, context: &mut Context) -> Result<String> {
+        self.run_news(symbol, context).await
+    }
+
+    /// Digest of the ranked news stories for a whole watchlist
+    ///
+    /// See [`crate::news_digest`] for how stories are gathered and ranked.
+    pub async fn analyze_news_digest(
+        &self,
+        data: &NewsDigestData,
+        context: &mut Context,
+    ) -> Result<String> {
+        let input = self
+            .config
+            .prompt_registry
+            .render(
+                "stock.user.news_digest",
+                &serde_json::json!({
+                    "date": data.date.to_string(),
+                    "data": format!("{:#}", data.to_prompt_json()),
+                }),
+            )
+            .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))?;
+        let input = self.styled_input(input, context);
+        self.news_analyzer.process(input, context).await
     }
 
     /// Get earnings analysis
-    pub async fn analyze_earnings(&self, symbol: &str) -> Result<String> {
-        self.run_earnings(symbol).await
+    pub async fn analyze_earnings(&self, symbol: &str, context: &mut Context) -> Result<String> {
+        self.run_earnings(symbol, context).await
     }
 
     /// Get macro economic analysis
-    pub async fn analyze_macro(&self) -> Result<String> {
-        self.run_macro().await
+    pub async fn analyze_macro(&self, context: &mut Context) -> Result<String> {
+        self.run_macro(context).await
     }
 
     /// Get geopolitical analysis
-    pub async fn analyze_geopolitical(&self) -> Result<String> {
-        let mut context = Context::new();
-        let input = "Analyze current geopolitical risks and their potential market impact.".to_string();
-        self.macro_analyzer.process(input, &mut context).await
+    pub async fn analyze_geopolitical(&self, context: &mut Context) -> Result<String> {
+        let input =
+            "Analyze current geopolitical risks and their potential market impact.".to_string();
+        let input = self.styled_input(input, context);
+        self.macro_analyzer.process(input, context).await
+    }
+
+    /// Get ESG and sustainability analysis
+    pub async fn analyze_esg(&self, symbol: &str, context: &mut Context) -> Result<String> {
+        let input = self
+            .config
+            .prompt_registry
+            .render(
+                "stock.user.analyze_esg",
+                &serde_json::json!({ "symbol": symbol }),
+            )
+            .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))?;
+        let input = self.styled_input(input, context);
+        self.esg_analyzer.process(input, context).await
+    }
+
+    /// Get performance, movers and news for a thematic basket
+    pub async fn analyze_theme(
+        &self,
+        theme: &ThemeBasket,
+        context: &mut Context,
+    ) -> Result<String> {
+        let input = self
+            .config
+            .prompt_registry
+            .render(
+                "stock.user.analyze_theme",
+                &serde_json::json!({
+                    "key": theme.key,
+                    "name": theme.name,
+                    "name_zh": theme.name_zh,
+                }),
+            )
+            .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))?;
+        let input = self.styled_input(input, context);
+        self.macro_analyzer.process(input, context).await
+    }
+
+    /// Compare a stock's current state against `date`
+    ///
+    /// Covers P/E compression or expansion, the RSI regime, the position
+    /// against the moving averages and analyst estimate revisions.
+    pub async fn compare_over_time(
+        &self,
+        symbol: &str,
+        date: NaiveDate,
+        context: &mut Context,
+    ) -> Result<String> {
+        let input = self
+            .config
+            .prompt_registry
+            .render(
+                "stock.user.compare_over_time",
+                &serde_json::json!({ "symbol": symbol, "date": date.to_string() }),
+            )
+            .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))?;
+        let input = self.styled_input(input, context);
+        self.fundamental_analyzer.process(input, context).await
+    }
+
+    /// Write the market wrap narrative for a day's collected data
+    ///
+    /// The narrative has three sections: what happened, why, and what to
+    /// watch tomorrow.
+    pub async fn market_wrap(
+        &self,
+        data: &MarketWrapData,
+        context: &mut Context,
+    ) -> Result<String> {
+        let input = self
+            .config
+            .prompt_registry
+            .render(
+                "stock.user.market_wrap",
+                &serde_json::json!({
+                    "date": data.date.to_string(),
+                    "data": format!("{:#}", data.to_prompt_json()),
+                }),
+            )
+            .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))?;
+        let input = self.styled_input(input, context);
+        self.macro_analyzer.process(input, context).await
+    }
+
+    /// One-line take on the market implications of a watched macro release
+    pub async fn macro_alert(&self, alert: &MacroAlert, context: &mut Context) -> Result<String> {
+        let input = self
+            .config
+            .prompt_registry
+            .render(
+                "stock.user.macro_alert",
+                &serde_json::json!({
+                    "title": alert.title,
+                    "series_id": alert.watch.series_id,
+                    "observation_date": alert.observation_date,
+                    "value": alert.value.to_string(),
+                    "prior": alert.prior.map(|prior| prior.to_string()),
+                    "condition": alert.watch.condition.as_str(),
+                    "threshold": alert.watch.threshold.to_string(),
+                }),
+            )
+            .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))?;
+        self.macro_analyzer.process(input, context).await
     }
 
     /// Get comprehensive analysis including macro factors using parallel execution
     ///
     /// This method executes all analyses in parallel for better performance,
-    /// then synthesizes the results into a comprehensive report.
-    pub async fn analyze_comprehensive(&self, symbol: &str) -> Result<String> {
-        let result = self.parallel_analysis(symbol).await?;
+    /// then synthesizes the results into a comprehensive report. Deep requests
+    /// add the filings, peer and scenario sections; quick requests get the
+    /// short summary instead. The tokens used by all agents involved are
+    /// added to the context; read them with [`usage::from_context`].
+    pub async fn analyze_comprehensive(
+        &self,
+        symbol: &str,
+        context: &mut Context,
+    ) -> Result<String> {
+        let result = match self.analysis_depth(context) {
+            AnalysisDepth::Quick => return self.analyze_quick(symbol, context).await,
+            AnalysisDepth::Standard => self.parallel_analysis(symbol, context).await?,
+            AnalysisDepth::Deep => self.deep_analysis(symbol, context).await?,
+        };
+        usage::add_to_context(context, &result.usage);
         Ok(result.format_report())
     }
 
     /// Smart process: automatically determines the best way to handle a query
     pub async fn smart_process(&self, query: &str, context: &mut Context) -> Result<String> {
-        let intent = self.router.classify(query);
+        // Plugin analyzers are reached through the delegating agent's router
+        if self.router.plugin_route(query).is_some() {
+            return self.process(query.to_string(), context).await;
+        }
 
-        match intent {
+        let routing = self.router.route_semantic(query).await;
+
+        match routing.intent {
             QueryIntent::ComprehensiveAnalysis => {
                 // Extract symbol from query
                 let symbols = self.router.extract_symbols(query);
                 if let Some(symbol) = symbols.first() {
-                    self.analyze_comprehensive(symbol).await
+                    self.analyze_comprehensive(symbol, context).await
                 } else {
                     // No symbol found, use standard processing
                     self.process(query.to_string(), context).await
                 }
             }
+            QueryIntent::Explain => self.explain_term(query, context).await,
+            QueryIntent::TechnicalAnalysis => {
+                let input =
+                    self.teaching_input(query.to_string(), context, TermCategory::Technical);
+                self.dispatch(&routing, input, context).await
+            }
+            QueryIntent::FundamentalAnalysis => {
+                let input =
+                    self.teaching_input(query.to_string(), context, TermCategory::Fundamental);
+                self.dispatch(&routing, input, context).await
+            }
+            QueryIntent::ThemeAnalysis => match self.router.theme(query) {
+                Some(theme) => self.analyze_theme(theme, context).await,
+                None => self.process(query.to_string(), context).await,
+            },
+            QueryIntent::TimeComparison => {
+                let today = chrono::Utc::now().date_naive();
+                match self
+                    .router
+                    .time_comparison(query)
+                    .and_then(|(symbol, lookback)| Some((symbol, lookback.before(today)?)))
+                {
+                    Some((symbol, date)) => self.compare_over_time(&symbol, date, context).await,
+                    None => self.process(query.to_string(), context).await,
+                }
+            }
             QueryIntent::Comparison => {
                 let symbols = self.router.extract_symbols(query);
                 if symbols.len() >= 2 {
-                    self.compare_stocks(&symbols).await
+                    self.compare_stocks(&symbols, context).await
                 } else {
                     self.process(query.to_string(), context).await
                 }
             }
             _ => {
                 // Single agent processing via delegating agent
-                self.process(query.to_string(), context).await
+                self.dispatch(&routing, query.to_string(), context).await
+            }
+        }
+    }
+
+    /// Send a single-agent query to its routed agent
+    ///
+    /// The delegating agent's router only knows keywords, so intents found
+    /// by embedding similarity go straight to their agent instead.
+    async fn dispatch(
+        &self,
+        routing: &RoutingResult,
+        input: String,
+        context: &mut Context,
+    ) -> Result<String> {
+        let agent = routing
+            .agents
+            .first()
+            .filter(|_| routing.semantic)
+            .and_then(|key| self.agent.registry().get(key));
+        match agent {
+            Some(agent) => {
+                let input = self.styled_input(input, context);
+                agent.process(input, context).await
             }
+            None => self.process(input, context).await,
         }
     }
 
+    /// Explain the financial term a query asks about
+    ///
+    /// Uses the glossary definition and asks the data fetcher for a worked
+    /// example on real data, instead of running a full analysis. Falls back
+    /// to standard processing when no glossary term is found.
+    pub async fn explain_term(&self, query: &str, context: &mut Context) -> Result<String> {
+        let Some(entry) = self.router.explain_term(query) else {
+            return self.process(query.to_string(), context).await;
+        };
+
+        let input = self
+            .config
+            .prompt_registry
+            .render(
+                "stock.user.explain_term",
+                &serde_json::json!({
+                    "term": entry.term,
+                    "term_zh": entry.term_zh,
+                    "definition": entry.definition,
+                    "formula": entry.formula,
+                    "example_data": entry.example_data,
+                    "symbol": EXPLAIN_EXAMPLE_SYMBOL,
+                }),
+            )
+            .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))?;
+        let input = self.styled_input(input, context);
+        self.data_fetcher.process(input, context).await
+    }
+
     /// Compare multiple stocks
-    pub async fn compare_stocks(&self, symbols: &[String]) -> Result<String> {
+    pub async fn compare_stocks(
+        &self,
+        symbols: &[String],
+        context: &mut Context,
+    ) -> Result<String> {
         if symbols.is_empty() {
             return Err(agent_core::Error::ProcessingFailed(
                 "No symbols provided for comparison".to_string(),
@@ -232,24 +774,26 @@ impl StockAnalysisAgent {
         // Execute analyses in parallel for all symbols
         let futures: Vec<_> = symbols
             .iter()
-            .map(|s| self.parallel_analysis(s))
+            .map(|s| self.parallel_analysis(s, context))
             .collect();
 
         let results = futures::future::join_all(futures).await;
 
         // Format comparison report
         let mut report = String::new();
-        report.push_str(&format!("# Stock Comparison: {}\n\n", symbols.join(" vs ")));
+        let names: Vec<String> = symbols.iter().map(|s| crypto::display_name(s)).collect();
+        report.push_str(&format!("# Stock Comparison: {}\n\n", names.join(" vs ")));
 
         for (i, result) in results.into_iter().enumerate() {
             match result {
                 Ok(analysis) => {
-                    report.push_str(&format!("## {}\n\n", symbols[i]));
+                    usage::add_to_context(context, &analysis.usage);
+                    report.push_str(&format!("## {}\n\n", names[i]));
                     report.push_str(&analysis.format_summary());
                     report.push_str("\n\n");
                 }
                 Err(e) => {
-                    report.push_str(&format!("## {} (Error)\n\n", symbols[i]));
+                    report.push_str(&format!("## {} (Error)\n\n", names[i]));
                     report.push_str(&format!("Failed to analyze: {e}\n\n"));
                 }
             }
@@ -259,6 +803,27 @@ impl StockAnalysisAgent {
     }
 }
 
+/// Copy of a context for an agent running in parallel with others
+///
+/// The copy starts without the token usage recorded so far, so the usage
+/// of the parallel runs can be merged back without counting it twice.
+fn fork(context: &Context) -> Context {
+    let mut fork = context.clone();
+    usage::take_from_context(&mut fork);
+    fork
+}
+
+/// Fail fast on company-only analyses (fundamentals, earnings, filings, peers)
+/// for crypto assets, which have no company behind them
+fn require_company(symbol: &str, analysis: &str) -> Result<()> {
+    if crypto::is_crypto(symbol) {
+        return Err(agent_core::Error::ProcessingFailed(format!(
+            "{analysis} analysis does not apply to crypto asset {symbol}"
+        )));
+    }
+    Ok(())
+}
+
 /// Result of parallel analysis across multiple agents
 #[derive(Debug, Clone)]
 pub struct ParallelAnalysisResult {
@@ -274,6 +839,14 @@ pub struct ParallelAnalysisResult {
     pub earnings: Option<String>,
     /// Macro analysis result
     pub macro_analysis: Option<String>,
+    /// SEC filings review (deep analysis only)
+    pub filings: Option<String>,
+    /// Peer comparison (deep analysis only)
+    pub peers: Option<String>,
+    /// Bull/base/bear scenarios (deep analysis only)
+    pub scenarios: Option<String>,
+    /// Tokens used by the agents, per provider and model
+    pub usage: UsageSummary,
 }
 
 impl ParallelAnalysisResult {
@@ -281,7 +854,10 @@ impl ParallelAnalysisResult {
     pub fn format_report(&self) -> String {
         let mut report = String::new();
 
-        report.push_str(&format!("# Comprehensive Analysis: {}\n\n", self.symbol));
+        report.push_str(&format!(
+            "# Comprehensive Analysis: {}\n\n",
+            crypto::display_name(&self.symbol)
+        ));
 
         if let Some(ref technical) = self.technical {
             report.push_str("## Technical Analysis\n\n");
@@ -295,12 +871,24 @@ impl ParallelAnalysisResult {
             report.push_str("\n\n");
         }
 
+        if let Some(ref peers) = self.peers {
+            report.push_str("## Peer Comparison\n\n");
+            report.push_str(peers);
+            report.push_str("\n\n");
+        }
+
         if let Some(ref earnings) = self.earnings {
             report.push_str("## Earnings Analysis\n\n");
             report.push_str(earnings);
             report.push_str("\n\n");
         }
 
+        if let Some(ref filings) = self.filings {
+            report.push_str("## SEC Filings Review\n\n");
+            report.push_str(filings);
+            report.push_str("\n\n");
+        }
+
         if let Some(ref news) = self.news {
             report.push_str("## News & Sentiment\n\n");
             report.push_str(news);
@@ -313,6 +901,12 @@ impl ParallelAnalysisResult {
             report.push_str("\n\n");
         }
 
+        if let Some(ref scenarios) = self.scenarios {
+            report.push_str("## Scenarios\n\n");
+            report.push_str(scenarios);
+            report.push_str("\n\n");
+        }
+
         report
     }
 
@@ -322,19 +916,31 @@ impl ParallelAnalysisResult {
 
         if let Some(ref technical) = self.technical {
             // Extract first paragraph or first 200 chars
-            let excerpt = technical.lines().next().unwrap_or("").chars().take(200).collect::<String>();
+            let excerpt = technical
+                .lines()
+                .next()
+                .unwrap_or("")
+                .chars()
+                .take(200)
+                .collect::<String>();
             summary.push_str(&format!("**Technical**: {excerpt}\n"));
         }
 
         if let Some(ref fundamental) = self.fundamental {
-            let excerpt = fundamental.lines().next().unwrap_or("").chars().take(200).collect::<String>();
+            let excerpt = fundamental
+                .lines()
+                .next()
+                .unwrap_or("")
+                .chars()
+                .take(200)
+                .collect::<String>();
             summary.push_str(&format!("**Fundamental**: {excerpt}\n"));
         }
 
         summary
     }
 
-    /// Check if all analyses succeeded
+    /// Check if all standard analyses succeeded
     pub fn is_complete(&self) -> bool {
         self.technical.is_some()
             && self.fundamental.is_some()
@@ -351,6 +957,9 @@ impl ParallelAnalysisResult {
             self.news.is_some(),
             self.earnings.is_some(),
             self.macro_analysis.is_some(),
+            self.filings.is_some(),
+            self.peers.is_some(),
+            self.scenarios.is_some(),
         ]
         .iter()
         .filter(|&&x| x)
@@ -358,15 +967,44 @@ impl ParallelAnalysisResult {
     }
 }
 
+/// Reply to `/capabilities`: each specialist with what it handles and any
+/// missing API keys
+pub fn format_capabilities(agents: &[AgentAvailability]) -> String {
+    let mut report = String::from("🧭 Capabilities\n");
+    for agent in agents {
+        let caps = &agent.capabilities;
+        let status = if agent.is_available() {
+            "✅"
+        } else {
+            "⚠️"
+        };
+        report.push_str(&format!(
+            "\n{status} {}: {} ({})",
+            agent.key,
+            caps.description,
+            caps.intents.join(", ")
+        ));
+        if !agent.is_available() {
+            report.push_str(&format!(" - needs {}", agent.missing_keys.join(", ")));
+        }
+    }
+    report
+}
+
 #[async_trait]
 impl Agent for StockAnalysisAgent {
     async fn process(&self, input: String, context: &mut Context) -> Result<String> {
+        let input = self.styled_input(input, context);
         self.agent.process(input, context).await
     }
 
     fn name(&self) -> &'static str {
         "StockAnalysisAgent"
     }
+
+    fn capabilities(&self) -> agent_core::AgentCapabilities {
+        self.agent.capabilities()
+    }
 }
 
 #[cfg(test)]
@@ -414,6 +1052,10 @@ mod tests {
             news: None,
             earnings: Some("Q4 beat estimates".to_string()),
             macro_analysis: None,
+            filings: None,
+            peers: None,
+            scenarios: None,
+            usage: UsageSummary::default(),
         };
 
         assert!(!result.is_complete());
@@ -423,5 +1065,78 @@ mod tests {
         assert!(report.contains("AAPL"));
         assert!(report.contains("Technical Analysis"));
         assert!(report.contains("RSI: 55"));
+        assert!(!report.contains("Scenarios"));
+    }
+
+    #[test]
+    fn test_deep_sections_in_report() {
+        let result = ParallelAnalysisResult {
+            symbol: "AAPL".to_string(),
+            technical: None,
+            fundamental: Some("P/E: 28".to_string()),
+            news: None,
+            earnings: None,
+            macro_analysis: None,
+            filings: Some("No restatements".to_string()),
+            peers: Some("Premium to MSFT".to_string()),
+            scenarios: Some("Bull: $250".to_string()),
+            usage: UsageSummary::default(),
+        };
+
+        assert_eq!(result.success_count(), 4);
+        let report = result.format_report();
+        let peers = report.find("## Peer Comparison").unwrap();
+        assert!(report.find("## Fundamental Analysis").unwrap() < peers);
+        assert!(report.contains("## SEC Filings Review\n\nNo restatements"));
+        assert!(report.trim_end().ends_with("Bull: $250"));
+    }
+
+    #[test]
+    fn test_crypto_report() {
+        assert!(require_company("BTC-USD", "fundamental").is_err());
+        assert!(require_company("AAPL", "fundamental").is_ok());
+
+        let result = ParallelAnalysisResult {
+            symbol: "BTC-USD".to_string(),
+            technical: Some("RSI: 71".to_string()),
+            fundamental: None,
+            news: Some("ETF inflows".to_string()),
+            earnings: None,
+            macro_analysis: None,
+            filings: None,
+            peers: None,
+            scenarios: None,
+            usage: UsageSummary::default(),
+        };
+        let report = result.format_report();
+        assert!(report.starts_with("# Comprehensive Analysis: BTC-USD (Bitcoin)"));
+        assert!(!report.contains("Fundamental Analysis"));
+    }
+
+    #[test]
+    fn test_format_capabilities() {
+        let agents = vec![
+            AgentAvailability {
+                key: "technical-analyzer".to_string(),
+                capabilities: agent_core::AgentCapabilities::new("Technical indicators")
+                    .with_intent("technical"),
+                missing_keys: Vec::new(),
+            },
+            AgentAvailability {
+                key: "macro-analyzer".to_string(),
+                capabilities: agent_core::AgentCapabilities::new("Fed policy")
+                    .with_intent("macro")
+                    .with_intent("geopolitical")
+                    .with_required_key("FRED_API_KEY"),
+                missing_keys: vec!["FRED_API_KEY".to_string()],
+            },
+        ];
+
+        assert_eq!(
+            format_capabilities(&agents),
+            "🧭 Capabilities\n\n\
+             ✅ technical-analyzer: Technical indicators (technical)\n\
+             ⚠️ macro-analyzer: Fed policy (macro, geopolitical) - needs FRED_API_KEY"
+        );
     }
 }
@@ -1,13 +1,16 @@
 //! Technical analysis agent
 
-use agent_core::{Agent, Context, Result};
+use agent_core::{Agent, AgentCapabilities, Context, Result};
 use agent_runtime::{AgentRuntime, ExecutorConfig};
 use async_trait::async_trait;
 use std::sync::Arc;
 
 use crate::cache::CacheManager;
 use crate::config::StockConfig;
-use crate::tools::{ChartDataTool, StockDataTool, TechnicalIndicatorTool};
+use crate::router::QueryIntent;
+use crate::tools::{
+    BacktestTool, ChartDataTool, EventStudyTool, StockDataTool, TechnicalIndicatorTool,
+};
 
 /// Agent specialized in technical analysis
 pub struct TechnicalAnalyzerAgent {
@@ -17,11 +20,7 @@ pub struct TechnicalAnalyzerAgent {
 impl TechnicalAnalyzerAgent {
     /// Create a new technical analyzer agent
     pub async fn new(runtime: Arc<AgentRuntime>, config: Arc<StockConfig>) -> Result<Self> {
-        let cache_mgr = CacheManager::new(
-            config.cache_ttl_realtime,
-            config.cache_ttl_fundamental,
-            config.cache_ttl_news,
-        );
+        let cache_mgr = CacheManager::from_config(&config);
 
         // Create tools
         let stock_data_tool = Arc::new(StockDataTool::new(
@@ -36,11 +35,21 @@ impl TechnicalAnalyzerAgent {
             Arc::clone(&config),
             cache_mgr.realtime.clone(),
         ));
+        let backtest_tool = Arc::new(BacktestTool::new(
+            Arc::clone(&config),
+            cache_mgr.fundamental.clone(),
+        ));
+        let event_study_tool = Arc::new(EventStudyTool::new(
+            Arc::clone(&config),
+            cache_mgr.fundamental.clone(),
+        ));
 
         // Register tools
         runtime.tools().register(stock_data_tool);
         runtime.tools().register(technical_tool);
         runtime.tools().register(chart_tool);
+        runtime.tools().register(backtest_tool);
+        runtime.tools().register(event_study_tool);
 
         // Get system prompt from registry
         let system_prompt = config
@@ -49,10 +58,10 @@ impl TechnicalAnalyzerAgent {
             .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))?;
 
         let executor_config = ExecutorConfig {
-            model: config.model.clone(),
+            model: config.model_for("technical-analyzer"),
             system_prompt: Some(system_prompt),
-            max_tokens: config.max_tokens,
-            temperature: Some(config.temperature),
+            max_tokens: config.max_tokens_for("technical-analyzer"),
+            temperature: Some(config.temperature_for("technical-analyzer")),
             max_iterations: 10, // More iterations for comprehensive analysis
         };
 
@@ -71,4 +80,10 @@ impl Agent for TechnicalAnalyzerAgent {
     fn name(&self) -> &'static str {
         "TechnicalAnalyzerAgent"
     }
+
+    fn capabilities(&self) -> AgentCapabilities {
+        AgentCapabilities::new("Technical indicators, trends and chart patterns")
+            .with_intent(QueryIntent::TechnicalAnalysis.as_str())
+            .with_input("stock symbol")
+    }
 }
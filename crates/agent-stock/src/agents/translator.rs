@@ -0,0 +1,115 @@
+//! Translator agent for bilingual replies
+//!
+//! Translates a finished reply into the other response language, keeping
+//! every number of the original (see [`crate::language`]). It runs as the
+//! `translator` agent, so `STOCK_MODEL_TRANSLATOR` can point it at a cheaper
+//! model than the analysts use.
+
+use agent_core::{Agent, Context, Result};
+use agent_prompt::Language;
+use agent_runtime::agents::SimpleAgent;
+use agent_runtime::{AgentRuntime, SimpleConfig};
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::config::StockConfig;
+use crate::language::{self, ResponseLanguage};
+
+/// Agent name, for per-agent model overrides
+pub const TRANSLATOR_AGENT: &str = "translator";
+
+/// Attempts at a translation that keeps every number
+const MAX_ATTEMPTS: usize = 2;
+
+/// Agent translating replies for bilingual sessions
+pub struct TranslatorAgent {
+    agent: SimpleAgent,
+    config: Arc<StockConfig>,
+}
+
+impl TranslatorAgent {
+    /// Create a new translator
+    pub fn new(runtime: &AgentRuntime, config: Arc<StockConfig>) -> Result<Self> {
+        let system_prompt = config
+            .prompt_registry
+            .render("stock.translator", &json!({}))
+            .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))?;
+        let simple_config = SimpleConfig {
+            model: config.model_for(TRANSLATOR_AGENT),
+            system_prompt,
+            max_tokens: config.max_tokens_for(TRANSLATOR_AGENT),
+            temperature: config.temperature_for(TRANSLATOR_AGENT),
+        };
+        Ok(Self {
+            agent: runtime.create_simple_agent(simple_config, TRANSLATOR_AGENT),
+            config,
+        })
+    }
+
+    /// Whether replies made with `context` are bilingual, falling back to
+    /// the configured default
+    pub fn is_bilingual(&self, context: &Context) -> bool {
+        ResponseLanguage::from_context(context).map_or(self.config.bilingual, |language| {
+            language == ResponseLanguage::Bilingual
+        })
+    }
+
+    /// Language replies are written in before translation
+    pub fn source_language(&self) -> Language {
+        self.config.response_language.clone()
+    }
+
+    /// Language replies are translated into
+    pub fn target_language(&self) -> Language {
+        language::translation_language(&self.config.response_language)
+    }
+
+    /// Translate `text` into the [target language](Self::target_language)
+    ///
+    /// A translation dropping or changing a number is sent back once with
+    /// the numbers it lacks; the last attempt's missing numbers are returned
+    /// as an error if none keeps them all.
+    pub async fn translate(&self, text: &str, context: &mut Context) -> Result<String> {
+        let target = self.target_language();
+        let mut missing = Vec::new();
+        for _ in 0..MAX_ATTEMPTS {
+            let input = self
+                .config
+                .prompt_registry
+                .render(
+                    "stock.user.translate",
+                    &json!({
+                        "language": target.name(),
+                        "text": text,
+                        "missing": missing.join(", "),
+                    }),
+                )
+                .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))?;
+            let translation = self.agent.process(input, context).await?;
+            missing = language::missing_numbers(text, &translation);
+            if missing.is_empty() {
+                return Ok(translation.trim().to_string());
+            }
+            tracing::debug!("Translation dropped numbers: {}", missing.join(", "));
+        }
+        Err(agent_core::Error::ProcessingFailed(format!(
+            "Translation changed numbers: {}",
+            missing.join(", ")
+        )))
+    }
+
+    /// `text` followed by its translation, or `text` alone if the
+    /// translation fails
+    pub async fn bilingual(&self, text: &str, context: &mut Context) -> String {
+        match self.translate(text, context).await {
+            Ok(translation) => language::two_sections(
+                (&self.source_language(), text),
+                (&self.target_language(), &translation),
+            ),
+            Err(e) => {
+                tracing::warn!("Bilingual reply without translation: {}", e);
+                text.to_string()
+            }
+        }
+    }
+}
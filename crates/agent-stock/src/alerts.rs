@@ -0,0 +1,1296 @@
+//! Price and indicator alerts with background monitoring
+//!
+//! Users register conditions such as `AAPL > 200` or `RSI(NVDA) < 30` with
+//! `/alert`. [`AlertEngine`] polls the quotes they depend on in a background
+//! tokio task and pushes a notification through the user's platform bot
+//! (Telegram, DingTalk, Feishu or the console) when a condition is met.
+//!
+//! Alerts are edge-triggered: once an alert fires it stays quiet until its
+//! condition stops holding, so a stock sitting above its threshold does not
+//! notify on every poll. An [`AlertPolicy`] keeps a stock hovering around
+//! its threshold from notifying on every crossing: each alert can wait out a
+//! cooldown after firing, re-arm only once the value has moved a band away
+//! from the threshold, and count towards a daily cap per user, counted in the
+//! user's local day. Alerts and their daily counts are kept in an
+//! [`AlertStore`].
+//!
+//! With a [`DeliveryStore`], alerts firing during a user's quiet hours are
+//! held back and sent as one summary when the quiet hours end.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::alerts::{AlertEngine, AlertStore};
+//! use agent_stock::interface::BotPlatform;
+//!
+//! let store = Arc::new(AlertStore::open("alerts.json")?);
+//! store.add("12345", BotPlatform::Telegram, "RSI(NVDA) < 30".parse()?)?;
+//!
+//! let engine = Arc::new(AlertEngine::new(
+//!     store,
+//!     Arc::new(YahooFinanceClient::new()),
+//!     StockCache::new(Duration::from_secs(30)),
+//! ));
+//! engine.register_notifier(BotPlatform::Telegram, Arc::new(TelegramApi::new(token)));
+//! Arc::clone(&engine).spawn(Duration::from_secs(60));
+//! ```
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::Duration;
+use ta::Next;
+use ta::indicators::RelativeStrengthIndex;
+
+use crate::api::YahooFinanceClient;
+use crate::bot::Command;
+use crate::cache::{CacheKey, StockCache};
+use crate::delivery::DeliveryStore;
+use crate::error::{Result, StockError};
+use crate::interface::BotPlatform;
+use crate::macro_alerts::WatchCondition;
+use crate::storage::{self, StoreCipher};
+use crate::timezone::TimeZone;
+
+/// RSI period used by RSI alerts
+pub const RSI_PERIOD: usize = 14;
+
+/// Seconds an alert stays quiet after firing
+const COOLDOWN_ENV: &str = "STOCK_ALERT_COOLDOWN";
+
+/// Percent of the threshold the value must move back before an alert re-arms
+const REARM_BAND_ENV: &str = "STOCK_ALERT_REARM_PCT";
+
+/// Most alerts pushed to one user per local day
+const DAILY_CAP_ENV: &str = "STOCK_ALERT_DAILY_CAP";
+
+/// Value an alert watches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertMetric {
+    /// Last traded price
+    Price,
+    /// 14-day RSI of daily closes
+    Rsi,
+}
+
+impl AlertMetric {
+    /// Parse a metric name, e.g. "rsi" or "价格"
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "price" | "px" | "价格" | "股价" => Some(AlertMetric::Price),
+            "rsi" => Some(AlertMetric::Rsi),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AlertMetric::Price => "price",
+            AlertMetric::Rsi => "rsi",
+        }
+    }
+}
+
+/// What an alert watches for, e.g. `AAPL > 200` or `RSI(NVDA) < 30`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertCondition {
+    pub symbol: String,
+    pub metric: AlertMetric,
+    pub condition: WatchCondition,
+    pub threshold: f64,
+}
+
+impl AlertCondition {
+    /// Whether `value` of the metric meets the condition
+    pub fn is_met(&self, value: f64) -> bool {
+        self.condition.is_met(value, self.threshold)
+    }
+
+    /// Whether `value` has moved at least `band_pct` percent of the
+    /// threshold back from it, on the side where the condition does not hold
+    ///
+    /// With a band of 0 this is just the condition not holding.
+    pub fn is_clear(&self, value: f64, band_pct: f64) -> bool {
+        let band = self.threshold.abs() * band_pct / 100.0;
+        match self.condition {
+            WatchCondition::Above => value <= self.threshold - band,
+            WatchCondition::Below => value >= self.threshold + band,
+        }
+    }
+}
+
+impl fmt::Display for AlertCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let operator = match self.condition {
+            WatchCondition::Above => ">",
+            WatchCondition::Below => "<",
+        };
+        match self.metric {
+            AlertMetric::Price => write!(f, "{} {operator} {}", self.symbol, self.threshold),
+            AlertMetric::Rsi => write!(f, "RSI({}) {operator} {}", self.symbol, self.threshold),
+        }
+    }
+}
+
+impl FromStr for AlertCondition {
+    type Err = StockError;
+
+    /// Parse `AAPL > 200`, `RSI(NVDA) < 30`, `NVDA rsi below 30` or `AAPL 高于 200`
+    fn from_str(s: &str) -> Result<Self> {
+        let usage = || {
+            StockError::CommandError(format!(
+                "Invalid alert: {s}. Use e.g. \"AAPL > 200\" or \"RSI(NVDA) < 30\""
+            ))
+        };
+        let spaced = s.replace('>', " > ").replace('<', " < ");
+        let tokens: Vec<&str> = spaced.split_whitespace().collect();
+        let (subject, condition, threshold) = match tokens.as_slice() {
+            [subject, condition, threshold] => ((*subject).to_string(), condition, threshold),
+            [symbol, metric, condition, threshold] => {
+                (format!("{metric}({symbol})"), condition, threshold)
+            }
+            _ => return Err(usage()),
+        };
+
+        let (metric, symbol) = match subject.split_once('(') {
+            Some((metric, rest)) => (
+                AlertMetric::parse(metric).ok_or_else(usage)?,
+                rest.strip_suffix(')').ok_or_else(usage)?,
+            ),
+            None => (AlertMetric::Price, subject.as_str()),
+        };
+        let is_ticker_char = |c: char| c.is_ascii_alphanumeric() || ".-^=".contains(c);
+        if symbol.is_empty() || !symbol.chars().all(is_ticker_char) {
+            return Err(usage());
+        }
+
+        Ok(Self {
+            symbol: symbol.to_uppercase(),
+            metric,
+            condition: condition.parse()?,
+            threshold: threshold
+                .parse()
+                .map_err(|_| StockError::CommandError(format!("Invalid threshold: {threshold}")))?,
+        })
+    }
+}
+
+/// A user's alert
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceAlert {
+    /// Number the user removes the alert by
+    pub id: u64,
+    /// Chat or conversation the notification is pushed to
+    pub user_id: String,
+    pub platform: BotPlatform,
+    pub condition: AlertCondition,
+    pub created_at: DateTime<Utc>,
+    /// Whether the alert fires the next time its condition is met; cleared
+    /// when it fires and set again once the condition stops holding
+    #[serde(default = "default_armed")]
+    pub armed: bool,
+    #[serde(default)]
+    pub last_triggered: Option<DateTime<Utc>>,
+    /// Notifications pushed on the user's local day, for the daily cap
+    #[serde(default)]
+    pub notified: Option<DailyCount>,
+}
+
+/// Notifications an alert pushed on one local day
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DailyCount {
+    pub date: NaiveDate,
+    pub count: u32,
+}
+
+fn default_armed() -> bool {
+    true
+}
+
+impl fmt::Display for PriceAlert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{} {}", self.id, self.condition)
+    }
+}
+
+/// An alert whose condition was met
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertNotification {
+    pub alert: PriceAlert,
+    /// Value of the metric that met the condition
+    pub value: f64,
+    pub triggered_at: DateTime<Utc>,
+}
+
+impl fmt::Display for AlertNotification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let condition = &self.alert.condition;
+        write!(f, "🔔 Alert #{}: {}", self.alert.id, condition)?;
+        match condition.metric {
+            AlertMetric::Price => write!(f, " | Price: {:.2}", self.value),
+            AlertMetric::Rsi => write!(f, " | RSI({RSI_PERIOD}): {:.1}", self.value),
+        }
+    }
+}
+
+/// Limits on how often alerts notify
+///
+/// The default has no limits: alerts fire whenever their condition starts
+/// to hold again.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AlertPolicy {
+    /// Time an alert stays quiet after firing, even if it re-arms
+    pub cooldown: Option<Duration>,
+    /// Percent of the threshold the value must move back from it before a
+    /// fired alert re-arms
+    pub rearm_band_pct: f64,
+    /// Most alerts pushed to one user per day, in the user's time zone;
+    /// alerts over the cap are disarmed without notifying
+    pub daily_cap: Option<u32>,
+}
+
+impl AlertPolicy {
+    /// Policy from `STOCK_ALERT_COOLDOWN` (seconds), `STOCK_ALERT_REARM_PCT`
+    /// and `STOCK_ALERT_DAILY_CAP`; unset or invalid values leave that limit
+    /// off
+    pub fn from_env() -> Self {
+        fn var<T: FromStr>(name: &str) -> Option<T> {
+            std::env::var(name)
+                .ok()
+                .and_then(|value| value.trim().parse().ok())
+        }
+        Self {
+            cooldown: var(COOLDOWN_ENV).map(Duration::from_secs),
+            rearm_band_pct: var::<f64>(REARM_BAND_ENV)
+                .filter(|pct| *pct >= 0.0)
+                .unwrap_or(0.0),
+            daily_cap: var(DAILY_CAP_ENV),
+        }
+    }
+
+    /// Keep alerts quiet for `cooldown` after they fire
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = Some(cooldown);
+        self
+    }
+
+    /// Re-arm alerts only once the value is `pct` percent of the threshold
+    /// back from it
+    pub fn with_rearm_band(mut self, pct: f64) -> Self {
+        self.rearm_band_pct = pct;
+        self
+    }
+
+    /// Push at most `cap` alerts to each user per local day
+    pub fn with_daily_cap(mut self, cap: u32) -> Self {
+        self.daily_cap = Some(cap);
+        self
+    }
+
+    /// Whether `alert` is still cooling down from its last firing at `now`
+    fn is_cooling_down(&self, alert: &PriceAlert, now: DateTime<Utc>) -> bool {
+        match (self.cooldown, alert.last_triggered) {
+            (Some(cooldown), Some(last)) => (now - last).to_std().is_ok_and(|age| age < cooldown),
+            _ => false,
+        }
+    }
+}
+
+/// Persisted alerts of all users
+///
+/// Kept in memory and, when opened with a path, saved as JSON after every
+/// change (encrypted when opened with a cipher).
+pub struct AlertStore {
+    alerts: RwLock<Vec<PriceAlert>>,
+    path: Option<PathBuf>,
+    cipher: Option<StoreCipher>,
+}
+
+impl Default for AlertStore {
+    fn default() -> Self {
+        Self::in_memory()
+    }
+}
+
+impl AlertStore {
+    /// Create a store that is not persisted
+    pub fn in_memory() -> Self {
+        Self {
+            alerts: RwLock::new(Vec::new()),
+            path: None,
+            cipher: None,
+        }
+    }
+
+    /// Open a store persisted at `path`, loading existing alerts
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        Self::open_with_cipher(path, None)
+    }
+
+    /// Open a store persisted at `path`, encrypted with `cipher` if given
+    pub fn open_with_cipher(path: impl Into<PathBuf>, cipher: Option<StoreCipher>) -> Result<Self> {
+        let path = path.into();
+        let alerts = match storage::read_store(&path, cipher.as_ref())? {
+            Some(json) => serde_json::from_str(&json)?,
+            None => Vec::new(),
+        };
+
+        Ok(Self {
+            alerts: RwLock::new(alerts),
+            path: Some(path),
+            cipher,
+        })
+    }
+
+    /// File the alerts are persisted to, if any
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Add an alert for `user_id` on `platform`
+    pub fn add(
+        &self,
+        user_id: &str,
+        platform: BotPlatform,
+        condition: AlertCondition,
+    ) -> Result<PriceAlert> {
+        let alert = {
+            let mut alerts = self.write();
+            let alert = PriceAlert {
+                id: alerts.iter().map(|alert| alert.id).max().unwrap_or(0) + 1,
+                user_id: user_id.to_string(),
+                platform,
+                condition,
+                created_at: Utc::now(),
+                armed: true,
+                last_triggered: None,
+                notified: None,
+            };
+            alerts.push(alert.clone());
+            alert
+        };
+        self.save()?;
+        Ok(alert)
+    }
+
+    /// Remove alert `id` of `user_id`; returns whether there was one
+    pub fn remove(&self, user_id: &str, id: u64) -> Result<bool> {
+        let removed = {
+            let mut alerts = self.write();
+            let before = alerts.len();
+            alerts.retain(|alert| !(alert.id == id && alert.user_id == user_id));
+            alerts.len() < before
+        };
+        if removed {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    /// Alerts of `user_id`, in the order they were added
+    pub fn for_user(&self, user_id: &str) -> Vec<PriceAlert> {
+        self.read()
+            .iter()
+            .filter(|alert| alert.user_id == user_id)
+            .cloned()
+            .collect()
+    }
+
+    /// All alerts, in the order they were added
+    pub fn alerts(&self) -> Vec<PriceAlert> {
+        self.read().clone()
+    }
+
+    /// Whether there are no alerts
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    /// Record that alert `id` fired at `at`, disarming it
+    pub fn mark_triggered(&self, id: u64, at: DateTime<Utc>) -> Result<()> {
+        self.update(id, |alert| {
+            alert.armed = false;
+            alert.last_triggered = Some(at);
+        })
+    }
+
+    /// Count a notification of alert `id` on the user's local day `date`
+    pub fn record_notified(&self, id: u64, date: NaiveDate) -> Result<()> {
+        self.update(id, |alert| {
+            let count = match alert.notified {
+                Some(notified) if notified.date == date => notified.count + 1,
+                _ => 1,
+            };
+            alert.notified = Some(DailyCount { date, count });
+        })
+    }
+
+    /// Notifications pushed to `user_id` on `platform` on their local day
+    /// `date`, across all their alerts
+    pub fn notified_on(&self, user_id: &str, platform: BotPlatform, date: NaiveDate) -> u32 {
+        self.read()
+            .iter()
+            .filter(|alert| alert.user_id == user_id && alert.platform == platform)
+            .filter_map(|alert| alert.notified)
+            .filter(|notified| notified.date == date)
+            .map(|notified| notified.count)
+            .sum()
+    }
+
+    /// Arm alert `id` again after its condition stopped holding
+    pub fn rearm(&self, id: u64) -> Result<()> {
+        self.update(id, |alert| alert.armed = true)
+    }
+
+    fn update(&self, id: u64, change: impl FnOnce(&mut PriceAlert)) -> Result<()> {
+        {
+            let mut alerts = self.write();
+            if let Some(alert) = alerts.iter_mut().find(|alert| alert.id == id) {
+                change(alert);
+            }
+        }
+        self.save()
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, Vec<PriceAlert>> {
+        self.alerts.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, Vec<PriceAlert>> {
+        self.alerts.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let json = serde_json::to_string_pretty(&*self.read())?;
+        storage::write_store(path, &json, self.cipher.as_ref())
+    }
+}
+
+/// Source of the quotes alerts are checked against
+#[async_trait]
+pub trait QuoteSource: Send + Sync {
+    /// Latest traded price of `symbol`
+    async fn last_price(&self, symbol: &str) -> Result<f64>;
+
+    /// Recent daily closes of `symbol`, oldest first, enough for a 14-day RSI
+    async fn daily_closes(&self, symbol: &str) -> Result<Vec<f64>>;
+}
+
+#[async_trait]
+impl QuoteSource for YahooFinanceClient {
+    async fn last_price(&self, symbol: &str) -> Result<f64> {
+        Ok(self.get_quote(symbol).await?.close)
+    }
+
+    async fn daily_closes(&self, symbol: &str) -> Result<Vec<f64>> {
+        let quotes = self.get_historical_range(symbol, "3mo").await?;
+        Ok(quotes.iter().map(|quote| quote.close).collect())
+    }
+}
+
+/// Delivers alert notifications to users of one platform
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Send `message` to `user_id`, the chat or conversation the alert was
+    /// set from
+    async fn notify(&self, user_id: &str, message: &str) -> Result<()>;
+}
+
+/// RSI of `closes` over `period` days, if there are enough closes
+pub fn rsi(closes: &[f64], period: usize) -> Option<f64> {
+    if closes.len() <= period {
+        return None;
+    }
+    let mut rsi = RelativeStrengthIndex::new(period).ok()?;
+    closes.iter().map(|&close| rsi.next(close)).last()
+}
+
+/// Checks alerts against live quotes and pushes notifications
+///
+/// Polling is best effort: symbols whose quotes fail are skipped until the
+/// next check, and notifications for platforms without a registered
+/// [`Notifier`] are only returned from [`AlertEngine::check`].
+pub struct AlertEngine {
+    store: Arc<AlertStore>,
+    quotes: Arc<dyn QuoteSource>,
+    /// Shares quotes between alerts on the same symbol within a check
+    cache: StockCache,
+    notifiers: RwLock<HashMap<BotPlatform, Arc<dyn Notifier>>>,
+    /// Quiet hours notifications are held back during
+    delivery: Option<Arc<DeliveryStore>>,
+    policy: AlertPolicy,
+}
+
+impl AlertEngine {
+    /// Create an engine checking `store` against `quotes`
+    ///
+    /// `cache` should expire well within the polling interval.
+    pub fn new(store: Arc<AlertStore>, quotes: Arc<dyn QuoteSource>, cache: StockCache) -> Self {
+        Self {
+            store,
+            quotes,
+            cache,
+            notifiers: RwLock::new(HashMap::new()),
+            delivery: None,
+            policy: AlertPolicy::default(),
+        }
+    }
+
+    /// Limit how often alerts notify
+    pub fn with_policy(mut self, policy: AlertPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Limits on how often alerts notify
+    pub fn policy(&self) -> &AlertPolicy {
+        &self.policy
+    }
+
+    /// Hold notifications back during the quiet hours kept in `delivery`
+    pub fn with_delivery(mut self, delivery: Arc<DeliveryStore>) -> Self {
+        self.delivery = Some(delivery);
+        self
+    }
+
+    /// Quiet hours notifications are held back during, if any
+    pub fn delivery(&self) -> Option<&Arc<DeliveryStore>> {
+        self.delivery.as_ref()
+    }
+
+    /// The alerts being checked
+    pub fn store(&self) -> &Arc<AlertStore> {
+        &self.store
+    }
+
+    /// Push notifications for alerts set on `platform` through `notifier`
+    pub fn register_notifier(&self, platform: BotPlatform, notifier: Arc<dyn Notifier>) {
+        self.notifiers
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(platform, notifier);
+    }
+
+    /// Check every alert once, notifying users of those that fired
+    pub async fn check(&self) -> Vec<AlertNotification> {
+        let mut fired = Vec::new();
+        for alert in self.store.alerts() {
+            let value = match self.value(&alert.condition).await {
+                Ok(value) => value,
+                Err(e) => {
+                    tracing::debug!("Skipping alert {}: {}", alert, e);
+                    continue;
+                }
+            };
+            let met = alert.condition.is_met(value);
+
+            if !met {
+                if !alert.armed
+                    && alert.condition.is_clear(value, self.policy.rearm_band_pct)
+                    && let Err(e) = self.store.rearm(alert.id)
+                {
+                    tracing::warn!("Failed to save alert {}: {}", alert, e);
+                }
+                continue;
+            }
+            let triggered_at = Utc::now();
+            if !alert.armed || self.policy.is_cooling_down(&alert, triggered_at) {
+                continue;
+            }
+
+            if let Err(e) = self.store.mark_triggered(alert.id, triggered_at) {
+                tracing::warn!("Failed to save alert {}: {}", alert, e);
+            }
+            let Some(fired_today) = self.count_towards_cap(&alert, triggered_at) else {
+                tracing::debug!(
+                    "Daily cap reached for {}; alert {} muted",
+                    alert.user_id,
+                    alert
+                );
+                continue;
+            };
+            let notification = AlertNotification {
+                alert,
+                value,
+                triggered_at,
+            };
+            self.notify(&notification).await;
+            if self.policy.daily_cap == Some(fired_today) {
+                self.notify_cap_reached(&notification.alert, fired_today, triggered_at)
+                    .await;
+            }
+            fired.push(notification);
+        }
+        fired
+    }
+
+    /// Count an alert fired at `now` towards its user's daily cap
+    ///
+    /// Returns the user's alerts today including this one, or `None` when
+    /// the cap was already reached. Days start at midnight in the user's
+    /// time zone.
+    fn count_towards_cap(&self, alert: &PriceAlert, now: DateTime<Utc>) -> Option<u32> {
+        let today = self.time_zone(alert).to_local(now).date();
+        let fired_today = self
+            .store
+            .notified_on(&alert.user_id, alert.platform, today);
+        if self.policy.daily_cap.is_some_and(|cap| fired_today >= cap) {
+            return None;
+        }
+        if let Err(e) = self.store.record_notified(alert.id, today) {
+            tracing::warn!("Failed to save alert {}: {}", alert, e);
+        }
+        Some(fired_today + 1)
+    }
+
+    /// Tell the user further alerts are muted for the rest of the day
+    async fn notify_cap_reached(&self, alert: &PriceAlert, cap: u32, at: DateTime<Utc>) {
+        let message = format!(
+            "🔕 Daily limit of {cap} alerts reached; further alerts are muted until midnight ({})",
+            self.time_zone(alert)
+        );
+        self.push(&alert.user_id, alert.platform, &message, at)
+            .await;
+    }
+
+    /// Time zone of the user `alert` belongs to; UTC without a delivery store
+    fn time_zone(&self, alert: &PriceAlert) -> TimeZone {
+        self.delivery
+            .as_ref()
+            .map(|delivery| delivery.time_zone(&alert.user_id, alert.platform))
+            .unwrap_or_default()
+    }
+
+    /// Send the notifications held back for users whose quiet hours ended
+    /// by `now`, one summary per user; returns how many summaries were sent
+    ///
+    /// Summaries that cannot be pushed stay queued for the next call.
+    pub async fn deliver_queued(&self, now: DateTime<Utc>) -> usize {
+        let Some(delivery) = &self.delivery else {
+            return 0;
+        };
+        let summaries = match delivery.take_due(now) {
+            Ok(summaries) => summaries,
+            Err(e) => {
+                tracing::warn!("Failed to save delivery queue: {}", e);
+                return 0;
+            }
+        };
+        let mut sent = 0;
+        for summary in summaries {
+            let pushed = match self.notifier(summary.platform) {
+                Some(notifier) => {
+                    notifier
+                        .notify(&summary.user_id, &summary.to_string())
+                        .await
+                }
+                None => Err(StockError::Other(format!(
+                    "No notifier for {:?}",
+                    summary.platform
+                ))),
+            };
+            match pushed {
+                Ok(()) => sent += 1,
+                Err(e) => {
+                    tracing::warn!(
+                        "Failed to push quiet hours summary to {}: {}",
+                        summary.user_id,
+                        e
+                    );
+                    if let Err(e) = delivery.requeue(summary) {
+                        tracing::warn!("Failed to save delivery queue: {}", e);
+                    }
+                }
+            }
+        }
+        sent
+    }
+
+    /// Check alerts every `interval` in the background
+    pub fn spawn(self: Arc<Self>, interval: std::time::Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                self.deliver_queued(Utc::now()).await;
+                if self.store.is_empty() {
+                    continue;
+                }
+                let fired = self.check().await;
+                if !fired.is_empty() {
+                    tracing::info!("{} alerts fired", fired.len());
+                }
+            }
+        })
+    }
+
+    /// Current value of the condition's metric
+    async fn value(&self, condition: &AlertCondition) -> Result<f64> {
+        let symbol = &condition.symbol;
+        let key = CacheKey::new(
+            symbol,
+            format!("alert_{}", condition.metric.as_str()),
+            json!({}),
+        );
+        let value = self
+            .cache
+            .get_or_fetch(key, || async {
+                let value = match condition.metric {
+                    AlertMetric::Price => self.quotes.last_price(symbol).await?,
+                    AlertMetric::Rsi => {
+                        let closes = self.quotes.daily_closes(symbol).await?;
+                        rsi(&closes, RSI_PERIOD).ok_or_else(|| {
+                            StockError::data_unavailable(symbol, "Not enough closes for RSI")
+                        })?
+                    }
+                };
+                Ok::<_, StockError>(json!(value))
+            })
+            .await?;
+        value
+            .as_f64()
+            .ok_or_else(|| StockError::data_unavailable(symbol, "No quote"))
+    }
+
+    fn notifier(&self, platform: BotPlatform) -> Option<Arc<dyn Notifier>> {
+        self.notifiers
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&platform)
+            .cloned()
+    }
+
+    async fn notify(&self, notification: &AlertNotification) {
+        let alert = &notification.alert;
+        self.push(
+            &alert.user_id,
+            alert.platform,
+            &notification.to_string(),
+            notification.triggered_at,
+        )
+        .await;
+    }
+
+    /// Push `message` from another job, such as a macro alert, to `user_id`
+    /// on `platform` through its registered notifier, held back while they
+    /// are in quiet hours
+    pub async fn push_message(&self, user_id: &str, platform: BotPlatform, message: &str) {
+        self.push(user_id, platform, message, Utc::now()).await;
+    }
+
+    /// Push `message` to `user_id`, or hold it back if they are in quiet
+    /// hours at `at`
+    async fn push(&self, user_id: &str, platform: BotPlatform, message: &str, at: DateTime<Utc>) {
+        let Some(notifier) = self.notifier(platform) else {
+            tracing::debug!(
+                "No notifier for {:?}; alert to {} not pushed",
+                platform,
+                user_id
+            );
+            return;
+        };
+        if let Some(delivery) = &self.delivery {
+            match delivery.hold_if_quiet(user_id, platform, message, at) {
+                Ok(true) => return,
+                Ok(false) => {}
+                Err(e) => {
+                    tracing::warn!("Failed to queue alert for {}: {}", user_id, e);
+                    return;
+                }
+            }
+        }
+        if let Err(e) = notifier.notify(user_id, message).await {
+            tracing::warn!("Failed to push alert to {}: {}", user_id, e);
+        }
+    }
+}
+
+/// Reply to an alert command (`/alert`, `/alert remove`, `/alerts`) from
+/// `user_id` on `platform`, for bots with an alert `store`
+pub fn command_reply(
+    store: Option<&AlertStore>,
+    user_id: &str,
+    platform: BotPlatform,
+    command: &Command,
+) -> Result<String> {
+    let Some(store) = store else {
+        return Ok("🔕 Alerts are not enabled on this bot".to_string());
+    };
+    match command {
+        Command::Alert { condition } => {
+            let alert = store.add(user_id, platform, condition.clone())?;
+            Ok(format!(
+                "🔔 Alert {alert} set. Use /alert remove {} to stop it.",
+                alert.id
+            ))
+        }
+        Command::AlertRemove { id } => {
+            if store.remove(user_id, *id)? {
+                Ok(format!("✅ Removed alert #{id}"))
+            } else {
+                Ok(format!("❌ No alert #{id}"))
+            }
+        }
+        Command::Alerts => {
+            let alerts = store.for_user(user_id);
+            if alerts.is_empty() {
+                return Ok(
+                    "🔕 No alerts. Use /alert AAPL > 200 or /alert RSI(NVDA) < 30 to add one."
+                        .to_string(),
+                );
+            }
+            let lines: Vec<String> = alerts
+                .iter()
+                .map(|alert| {
+                    let state = if alert.armed { "" } else { " (triggered)" };
+                    format!("{alert}{state}")
+                })
+                .collect();
+            Ok(format!("🔔 Alerts:\n{}", lines.join("\n")))
+        }
+        _ => Err(StockError::CommandError(format!(
+            "Not an alert command: /{}",
+            command.name()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    /// Prices set by the test; RSI closes fall steadily
+    struct FakeQuotes {
+        prices: Mutex<HashMap<String, f64>>,
+    }
+
+    #[async_trait]
+    impl QuoteSource for FakeQuotes {
+        async fn last_price(&self, symbol: &str) -> Result<f64> {
+            self.prices
+                .lock()
+                .unwrap()
+                .get(symbol)
+                .copied()
+                .ok_or_else(|| StockError::data_unavailable(symbol, "unknown"))
+        }
+
+        async fn daily_closes(&self, _symbol: &str) -> Result<Vec<f64>> {
+            Ok((0..30).map(|i| 100.0 - f64::from(i)).collect())
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingNotifier {
+        sent: Mutex<Vec<(String, String)>>,
+    }
+
+    #[async_trait]
+    impl Notifier for RecordingNotifier {
+        async fn notify(&self, user_id: &str, message: &str) -> Result<()> {
+            self.sent
+                .lock()
+                .unwrap()
+                .push((user_id.to_string(), message.to_string()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_parse_condition() {
+        let condition: AlertCondition = "aapl > 200".parse().unwrap();
+        assert_eq!(condition.symbol, "AAPL");
+        assert_eq!(condition.metric, AlertMetric::Price);
+        assert_eq!(condition.condition, WatchCondition::Above);
+        assert_eq!(condition.to_string(), "AAPL > 200");
+
+        let condition: AlertCondition = "RSI(NVDA)<30".parse().unwrap();
+        assert_eq!(condition.metric, AlertMetric::Rsi);
+        assert_eq!(condition.to_string(), "RSI(NVDA) < 30");
+        assert_eq!(
+            "NVDA rsi below 30".parse::<AlertCondition>().unwrap(),
+            condition
+        );
+        assert_eq!(
+            "BRK.B 低于 400"
+                .parse::<AlertCondition>()
+                .unwrap()
+                .condition,
+            WatchCondition::Below
+        );
+
+        assert!("AAPL 200".parse::<AlertCondition>().is_err());
+        assert!("MACD(AAPL) > 0".parse::<AlertCondition>().is_err());
+        assert!("AAPL > high".parse::<AlertCondition>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_engine_fires_once_until_rearmed() {
+        let store = Arc::new(AlertStore::in_memory());
+        store
+            .add("42", BotPlatform::Telegram, "AAPL > 200".parse().unwrap())
+            .unwrap();
+        store
+            .add(
+                "7",
+                BotPlatform::DingTalk,
+                "RSI(NVDA) < 30".parse().unwrap(),
+            )
+            .unwrap();
+        let quotes = Arc::new(FakeQuotes {
+            prices: Mutex::new(HashMap::from([("AAPL".to_string(), 195.0)])),
+        });
+        let notifier = Arc::new(RecordingNotifier::default());
+        // No caching, so each check sees the latest price
+        let engine = AlertEngine::new(
+            Arc::clone(&store),
+            quotes.clone(),
+            StockCache::new(Duration::ZERO),
+        );
+        engine.register_notifier(BotPlatform::Telegram, notifier.clone());
+
+        // Closes falling every day put RSI near zero
+        let fired = engine.check().await;
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].alert.id, 2);
+        assert!(
+            fired[0]
+                .to_string()
+                .starts_with("🔔 Alert #2: RSI(NVDA) < 30")
+        );
+        // DingTalk has no notifier registered
+        assert!(notifier.sent.lock().unwrap().is_empty());
+
+        quotes
+            .prices
+            .lock()
+            .unwrap()
+            .insert("AAPL".to_string(), 201.5);
+        let fired = engine.check().await;
+        assert_eq!(fired.len(), 1);
+        assert_eq!(
+            notifier.sent.lock().unwrap().as_slice(),
+            [(
+                "42".to_string(),
+                "🔔 Alert #1: AAPL > 200 | Price: 201.50".to_string()
+            )]
+        );
+
+        // Still above: quiet. Dips below and back: fires again
+        assert!(engine.check().await.is_empty());
+        quotes
+            .prices
+            .lock()
+            .unwrap()
+            .insert("AAPL".to_string(), 199.0);
+        assert!(engine.check().await.is_empty());
+        assert!(store.alerts()[0].armed);
+        quotes
+            .prices
+            .lock()
+            .unwrap()
+            .insert("AAPL".to_string(), 203.0);
+        assert_eq!(engine.check().await.len(), 1);
+        assert_eq!(notifier.sent.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_condition_clears_past_band() {
+        let above: AlertCondition = "AAPL > 200".parse().unwrap();
+        assert!(above.is_clear(200.0, 0.0));
+        assert!(!above.is_clear(199.0, 2.0));
+        assert!(above.is_clear(195.0, 2.0));
+
+        let below: AlertCondition = "RSI(NVDA) < 30".parse().unwrap();
+        assert!(!below.is_clear(31.0, 10.0));
+        assert!(below.is_clear(33.5, 10.0));
+    }
+
+    #[tokio::test]
+    async fn test_policy_limits_repeated_alerts() {
+        let store = Arc::new(AlertStore::in_memory());
+        store
+            .add("42", BotPlatform::Telegram, "AAPL > 200".parse().unwrap())
+            .unwrap();
+        let quotes = Arc::new(FakeQuotes {
+            prices: Mutex::new(HashMap::from([("AAPL".to_string(), 201.0)])),
+        });
+        let set_price = |price: f64| {
+            quotes
+                .prices
+                .lock()
+                .unwrap()
+                .insert("AAPL".to_string(), price);
+        };
+        let engine = AlertEngine::new(
+            Arc::clone(&store),
+            quotes.clone(),
+            StockCache::new(Duration::ZERO),
+        )
+        .with_policy(AlertPolicy::default().with_rearm_band(2.0));
+
+        assert_eq!(engine.check().await.len(), 1);
+        // Dipping just under the threshold does not re-arm
+        set_price(199.0);
+        engine.check().await;
+        assert!(!store.alerts()[0].armed);
+        set_price(201.0);
+        assert!(engine.check().await.is_empty());
+        // Moving 2% away does
+        set_price(195.0);
+        engine.check().await;
+        assert!(store.alerts()[0].armed);
+
+        // Re-armed, but still cooling down from the last firing
+        let engine = AlertEngine::new(
+            Arc::clone(&store),
+            quotes.clone(),
+            StockCache::new(Duration::ZERO),
+        )
+        .with_policy(AlertPolicy::default().with_cooldown(Duration::from_secs(3600)));
+        set_price(201.0);
+        assert!(engine.check().await.is_empty());
+        assert!(store.alerts()[0].armed);
+    }
+
+    #[tokio::test]
+    async fn test_policy_daily_cap() {
+        let store = Arc::new(AlertStore::in_memory());
+        for condition in ["AAPL > 200", "AAPL > 190", "AAPL > 180"] {
+            store
+                .add("42", BotPlatform::Telegram, condition.parse().unwrap())
+                .unwrap();
+        }
+        store
+            .add("7", BotPlatform::Telegram, "AAPL > 150".parse().unwrap())
+            .unwrap();
+        let quotes = Arc::new(FakeQuotes {
+            prices: Mutex::new(HashMap::from([("AAPL".to_string(), 201.0)])),
+        });
+        let notifier = Arc::new(RecordingNotifier::default());
+        let engine = AlertEngine::new(Arc::clone(&store), quotes, StockCache::new(Duration::ZERO))
+            .with_policy(AlertPolicy::default().with_daily_cap(2));
+        engine.register_notifier(BotPlatform::Telegram, notifier.clone());
+
+        let fired = engine.check().await;
+        let ids: Vec<u64> = fired.iter().map(|n| n.alert.id).collect();
+        assert_eq!(ids, vec![1, 2, 4]);
+        // The muted alert is disarmed, so it does not fire tomorrow unprompted
+        assert!(!store.alerts()[2].armed);
+
+        let sent = notifier.sent.lock().unwrap();
+        let to_42: Vec<&str> = sent
+            .iter()
+            .filter(|(user, _)| user == "42")
+            .map(|(_, message)| message.as_str())
+            .collect();
+        assert_eq!(to_42.len(), 3);
+        assert!(
+            to_42[2].starts_with("🔕 Daily limit of 2 alerts"),
+            "{}",
+            to_42[2]
+        );
+    }
+
+    #[test]
+    fn test_daily_count_persisted_per_local_day() {
+        let path = std::env::temp_dir().join(format!("alerts-{}.json", uuid::Uuid::new_v4()));
+        let store = AlertStore::open(&path).unwrap();
+        let first = store
+            .add("42", BotPlatform::Telegram, "AAPL > 200".parse().unwrap())
+            .unwrap();
+        let second = store
+            .add("42", BotPlatform::Telegram, "MSFT > 400".parse().unwrap())
+            .unwrap();
+        let monday = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        store.record_notified(first.id, monday).unwrap();
+        store.record_notified(first.id, monday).unwrap();
+        store.record_notified(second.id, monday).unwrap();
+
+        // The count survives a restart, and starts over the next day
+        let store = AlertStore::open(&path).unwrap();
+        assert_eq!(store.notified_on("42", BotPlatform::Telegram, monday), 3);
+        assert_eq!(store.notified_on("42", BotPlatform::Slack, monday), 0);
+        let tuesday = monday.succ_opt().unwrap();
+        assert_eq!(store.notified_on("42", BotPlatform::Telegram, tuesday), 0);
+        store.record_notified(first.id, tuesday).unwrap();
+        assert_eq!(store.notified_on("42", BotPlatform::Telegram, tuesday), 1);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_daily_cap_uses_local_day() {
+        let store = Arc::new(AlertStore::in_memory());
+        let alert = store
+            .add("42", BotPlatform::Telegram, "AAPL > 200".parse().unwrap())
+            .unwrap();
+        let quotes = Arc::new(FakeQuotes {
+            prices: Mutex::new(HashMap::new()),
+        });
+        let delivery = Arc::new(DeliveryStore::in_memory());
+        delivery
+            .set_time_zone("42", BotPlatform::Telegram, TimeZone::Fixed(8 * 60))
+            .unwrap();
+        let engine = AlertEngine::new(Arc::clone(&store), quotes, StockCache::new(Duration::ZERO))
+            .with_policy(AlertPolicy::default().with_daily_cap(1))
+            .with_delivery(delivery);
+
+        // 15:00 and 17:00 UTC are either side of midnight in UTC+8
+        let afternoon = DateTime::parse_from_rfc3339("2024-03-04T15:00:00Z").unwrap();
+        let evening = DateTime::parse_from_rfc3339("2024-03-04T17:00:00Z").unwrap();
+        assert_eq!(engine.count_towards_cap(&alert, afternoon.into()), Some(1));
+        assert_eq!(engine.count_towards_cap(&alert, afternoon.into()), None);
+        assert_eq!(engine.count_towards_cap(&alert, evening.into()), Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_engine_holds_alerts_during_quiet_hours() {
+        let store = Arc::new(AlertStore::in_memory());
+        store
+            .add("42", BotPlatform::Telegram, "AAPL > 200".parse().unwrap())
+            .unwrap();
+        let quotes = Arc::new(FakeQuotes {
+            prices: Mutex::new(HashMap::from([("AAPL".to_string(), 201.0)])),
+        });
+        let delivery = Arc::new(DeliveryStore::in_memory());
+        // Quiet for the two hours around now
+        let now = Utc::now();
+        let start = (now - chrono::Duration::hours(1)).time();
+        let end = (now + chrono::Duration::hours(1)).time();
+        delivery
+            .set_quiet_hours(
+                "42",
+                BotPlatform::Telegram,
+                crate::delivery::QuietHours { start, end },
+                None,
+            )
+            .unwrap();
+        let notifier = Arc::new(RecordingNotifier::default());
+        let engine = AlertEngine::new(Arc::clone(&store), quotes, StockCache::new(Duration::ZERO))
+            .with_delivery(Arc::clone(&delivery));
+        engine.register_notifier(BotPlatform::Telegram, notifier.clone());
+
+        // Fires, but is held back
+        assert_eq!(engine.check().await.len(), 1);
+        assert!(notifier.sent.lock().unwrap().is_empty());
+        assert_eq!(engine.deliver_queued(now).await, 0);
+
+        // Sent as one summary once the quiet hours are over
+        assert_eq!(
+            engine
+                .deliver_queued(now + chrono::Duration::hours(2))
+                .await,
+            1
+        );
+        let sent = notifier.sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert!(
+            sent[0].1.starts_with("🌅 1 notification(s)"),
+            "{}",
+            sent[0].1
+        );
+        assert!(sent[0].1.contains("Alert #1: AAPL > 200"));
+    }
+
+    #[tokio::test]
+    async fn test_undelivered_summary_stays_queued() {
+        struct FailingNotifier;
+
+        #[async_trait]
+        impl Notifier for FailingNotifier {
+            async fn notify(&self, _user_id: &str, _message: &str) -> Result<()> {
+                Err(StockError::Other("chat unreachable".to_string()))
+            }
+        }
+
+        let store = Arc::new(AlertStore::in_memory());
+        let quotes = Arc::new(FakeQuotes {
+            prices: Mutex::new(HashMap::new()),
+        });
+        let delivery = Arc::new(DeliveryStore::in_memory());
+        let now = Utc::now();
+        let start = (now - chrono::Duration::hours(1)).time();
+        let end = (now + chrono::Duration::hours(1)).time();
+        delivery
+            .set_quiet_hours(
+                "42",
+                BotPlatform::Telegram,
+                crate::delivery::QuietHours { start, end },
+                None,
+            )
+            .unwrap();
+        delivery
+            .queue("42", BotPlatform::Telegram, "🔔 Alert #1", now)
+            .unwrap();
+        let engine = AlertEngine::new(store, quotes, StockCache::new(Duration::ZERO))
+            .with_delivery(Arc::clone(&delivery));
+        let later = now + chrono::Duration::hours(2);
+
+        // No notifier yet, then a failing one: the summary is kept
+        assert_eq!(engine.deliver_queued(later).await, 0);
+        engine.register_notifier(BotPlatform::Telegram, Arc::new(FailingNotifier));
+        assert_eq!(engine.deliver_queued(later).await, 0);
+        assert_eq!(delivery.queued_for("42", BotPlatform::Telegram), 1);
+
+        let notifier = Arc::new(RecordingNotifier::default());
+        engine.register_notifier(BotPlatform::Telegram, notifier.clone());
+        assert_eq!(engine.deliver_queued(later).await, 1);
+        assert_eq!(delivery.queued_for("42", BotPlatform::Telegram), 0);
+        assert!(notifier.sent.lock().unwrap()[0].1.contains("🔔 Alert #1"));
+    }
+
+    #[test]
+    fn test_store_and_commands() {
+        let path = std::env::temp_dir().join(format!("alerts-{}.json", uuid::Uuid::new_v4()));
+        let store = AlertStore::open(&path).unwrap();
+
+        let reply = command_reply(
+            Some(&store),
+            "42",
+            BotPlatform::Telegram,
+            &Command::parse("/alert AAPL > 200").unwrap(),
+        )
+        .unwrap();
+        assert!(reply.contains("#1 AAPL > 200"), "{reply}");
+        store
+            .add("7", BotPlatform::Feishu, "TSLA < 150".parse().unwrap())
+            .unwrap();
+
+        // Alerts persist, and users only see and remove their own
+        let store = AlertStore::open(&path).unwrap();
+        let list =
+            command_reply(Some(&store), "42", BotPlatform::Telegram, &Command::Alerts).unwrap();
+        assert!(
+            list.contains("#1 AAPL > 200") && !list.contains("TSLA"),
+            "{list}"
+        );
+        assert!(!store.remove("42", 2).unwrap());
+        assert!(store.remove("7", 2).unwrap());
+        assert_eq!(store.alerts().len(), 1);
+
+        let disabled = command_reply(None, "42", BotPlatform::Telegram, &Command::Alerts).unwrap();
+        assert!(disabled.contains("not enabled"));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_rsi() {
+        assert_eq!(rsi(&[1.0; 10], RSI_PERIOD), None);
+        let rising: Vec<f64> = (0..30).map(f64::from).collect();
+        assert!(rsi(&rising, RSI_PERIOD).unwrap() > 90.0);
+    }
+}
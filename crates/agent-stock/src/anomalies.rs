@@ -0,0 +1,701 @@
+//! Intraday anomaly alerts for watchlist symbols
+//!
+//! Once a symbol is on a user's watchlist, [`AnomalyMonitor`] follows its
+//! trades from the [`QuoteStreamer`] and pushes a notification as soon as
+//! something unusual happens:
+//!
+//! - **Halt**: the symbol stops trading for several minutes while the
+//!   market keeps trading (checked against SPY), and again when it resumes
+//! - **Limit up / limit down**: the price moves outside a band around the
+//!   average of the last five minutes, like the US limit up-limit down
+//!   mechanism that pauses trading
+//! - **Gap**: a single trade jumps far from the last known price, as at the
+//!   open or when trading reopens after a halt
+//!
+//! Each notification carries the latest headlines for the symbol, fetched
+//! when the anomaly is detected, so the user sees the likely cause at once.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::anomalies::AnomalyMonitor;
+//! use agent_stock::live::LiveQuotes;
+//!
+//! let monitor = AnomalyMonitor::new(Arc::new(YahooFinanceClient::new()), news::from_config(&config));
+//! let live = Arc::new(LiveQuotes::new(streamer).with_anomaly_alerts(monitor));
+//! live.register_notifier(BotPlatform::Telegram, notifier);
+//! live.watch_anomalies("42", BotPlatform::Telegram, "NVDA")?;
+//! ```
+
+use chrono::{DateTime, Duration, Utc};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::task::JoinHandle;
+
+use crate::alerts::{Notifier, QuoteSource};
+use crate::api::stream::{QuoteStream, QuoteStreamer, Tick};
+use crate::news::NewsProvider;
+
+/// Symbol whose trades show the market is open, for halt detection
+const HEARTBEAT_SYMBOL: &str = "SPY";
+
+/// How recently the heartbeat symbol must have traded for the market to
+/// count as open
+const HEARTBEAT_WINDOW: Duration = Duration::seconds(90);
+
+/// How often quiet symbols are checked for halts
+const HALT_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Window the limit band's reference price is averaged over
+const REFERENCE_WINDOW: Duration = Duration::minutes(5);
+
+/// Headlines attached to each notification
+const HEADLINES: usize = 3;
+
+/// When an anomaly is reported
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnomalyThresholds {
+    /// Time without trades, while the market trades, before a halt is reported
+    pub halt_after: Duration,
+    /// Move from the five-minute average price, in percent, reported as a
+    /// limit move
+    pub limit_band_pct: f64,
+    /// Jump from the last known price in a single trade, in percent,
+    /// reported as a gap
+    pub gap_pct: f64,
+}
+
+impl Default for AnomalyThresholds {
+    /// A 5 minute halt, the 5% limit up-limit down band of large caps and a
+    /// 3% gap
+    fn default() -> Self {
+        Self {
+            halt_after: Duration::minutes(5),
+            limit_band_pct: 5.0,
+            gap_pct: 3.0,
+        }
+    }
+}
+
+/// What kind of anomaly was detected
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnomalyKind {
+    /// No trades for `silent_for` while the market traded
+    Halt { silent_for: Duration },
+    /// Trading resumed after a halt lasting `halted_for`
+    Resumed { halted_for: Duration },
+    /// The price moved `change_pct` from the five-minute average `reference`
+    LimitMove { change_pct: f64, reference: f64 },
+    /// The price jumped `change_pct` from the last known price `from`
+    Gap { change_pct: f64, from: f64 },
+}
+
+/// An anomaly on one symbol
+#[derive(Debug, Clone, PartialEq)]
+pub struct Anomaly {
+    pub symbol: String,
+    pub kind: AnomalyKind,
+    /// Last traded price
+    pub price: f64,
+    /// When the anomaly was detected
+    pub at: DateTime<Utc>,
+    /// Latest headlines about the symbol, "title (source)"
+    pub headlines: Vec<String>,
+}
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = &self.symbol;
+        let time = self.at.format("%H:%M UTC");
+        match &self.kind {
+            AnomalyKind::Halt { silent_for } => write!(
+                f,
+                "⏸️ {symbol} may be halted: no trades for {} min while the market trades \
+                 (last {:.2}, {time})",
+                silent_for.num_minutes(),
+                self.price
+            )?,
+            AnomalyKind::Resumed { halted_for } => write!(
+                f,
+                "▶️ {symbol} resumed trading at {:.2} after {} min ({time})",
+                self.price,
+                halted_for.num_minutes()
+            )?,
+            AnomalyKind::LimitMove {
+                change_pct,
+                reference,
+            } => {
+                let (icon, direction) = if *change_pct < 0.0 {
+                    ("🔻", "down")
+                } else {
+                    ("🚀", "up")
+                };
+                write!(
+                    f,
+                    "{icon} {symbol} limit {direction}: {change_pct:+.1}% from its 5-min \
+                     average {reference:.2}, now {:.2} ({time})",
+                    self.price
+                )?;
+            }
+            AnomalyKind::Gap { change_pct, from } => {
+                let direction = if *change_pct < 0.0 { "down" } else { "up" };
+                write!(
+                    f,
+                    "⚡ {symbol} gapped {direction} {:.1}%: {:.2} after {from:.2} ({time})",
+                    change_pct.abs(),
+                    self.price
+                )?;
+            }
+        }
+        if !self.headlines.is_empty() {
+            write!(f, "\n\nLatest news:")?;
+            for headline in &self.headlines {
+                write!(f, "\n• {headline}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Detects anomalies in one symbol's trades
+///
+/// Feed every trade to [`AnomalyDetector::observe`] and call
+/// [`AnomalyDetector::check_halt`] periodically; both return what they
+/// detected. Each limit move is reported once until the price comes back
+/// within half the band, and each halt once until trading resumes.
+#[derive(Debug, Clone)]
+pub struct AnomalyDetector {
+    thresholds: AnomalyThresholds,
+    /// Last known price: the last trade, or the quote the detector started from
+    last_price: Option<f64>,
+    last_trade_at: Option<DateTime<Utc>>,
+    /// Trades of the last [`REFERENCE_WINDOW`]
+    window: VecDeque<(DateTime<Utc>, f64)>,
+    limit_armed: bool,
+    halted_since: Option<DateTime<Utc>>,
+}
+
+impl AnomalyDetector {
+    /// Detector starting from `last_price`, e.g. the latest quote
+    pub fn new(last_price: Option<f64>, thresholds: AnomalyThresholds) -> Self {
+        Self {
+            thresholds,
+            last_price,
+            last_trade_at: None,
+            window: VecDeque::new(),
+            limit_armed: true,
+            halted_since: None,
+        }
+    }
+
+    /// Last known price
+    pub fn last_price(&self) -> Option<f64> {
+        self.last_price
+    }
+
+    /// Anomalies revealed by `tick`
+    pub fn observe(&mut self, tick: &Tick) -> Vec<AnomalyKind> {
+        let mut found = Vec::new();
+        if let Some(since) = self.halted_since.take() {
+            found.push(AnomalyKind::Resumed {
+                halted_for: tick.timestamp - since,
+            });
+        }
+
+        if let Some(from) = self.last_price.filter(|&p| p > 0.0) {
+            let change_pct = (tick.price / from - 1.0) * 100.0;
+            if change_pct.abs() >= self.thresholds.gap_pct {
+                found.push(AnomalyKind::Gap { change_pct, from });
+            }
+        }
+
+        let cutoff = tick.timestamp - REFERENCE_WINDOW;
+        while self.window.front().is_some_and(|&(at, _)| at < cutoff) {
+            self.window.pop_front();
+        }
+        if !self.window.is_empty() {
+            #[allow(clippy::cast_precision_loss)] // a few thousand trades at most
+            let reference =
+                self.window.iter().map(|&(_, price)| price).sum::<f64>() / self.window.len() as f64;
+            let change_pct = (tick.price / reference - 1.0) * 100.0;
+            if change_pct.abs() >= self.thresholds.limit_band_pct {
+                if self.limit_armed {
+                    self.limit_armed = false;
+                    found.push(AnomalyKind::LimitMove {
+                        change_pct,
+                        reference,
+                    });
+                }
+            } else if change_pct.abs() < self.thresholds.limit_band_pct / 2.0 {
+                self.limit_armed = true;
+            }
+        }
+
+        self.window.push_back((tick.timestamp, tick.price));
+        self.last_price = Some(tick.price);
+        self.last_trade_at = Some(tick.timestamp);
+        found
+    }
+
+    /// A halt, if the symbol has been quiet too long while the market was
+    /// trading (`market_active`)
+    ///
+    /// Only symbols that traded earlier the same day can be halted.
+    pub fn check_halt(&mut self, now: DateTime<Utc>, market_active: bool) -> Option<AnomalyKind> {
+        let last = self.last_trade_at?;
+        if !market_active
+            || self.halted_since.is_some()
+            || last.date_naive() != now.date_naive()
+            || now - last < self.thresholds.halt_after
+        {
+            return None;
+        }
+        self.halted_since = Some(last);
+        Some(AnomalyKind::Halt {
+            silent_for: now - last,
+        })
+    }
+}
+
+/// Who gets a symbol's anomalies
+#[derive(Clone)]
+struct Watcher {
+    user_id: String,
+    notifier: Arc<dyn Notifier>,
+}
+
+type Watchers = Arc<Mutex<HashMap<String, Vec<Watcher>>>>;
+
+/// Follows watchlist symbols on the quote stream and pushes their anomalies
+///
+/// One task per symbol serves every user watching it. Notifications are
+/// best effort: a failed news fetch leaves the headlines out and a failed
+/// push is logged.
+pub struct AnomalyMonitor {
+    quotes: Arc<dyn QuoteSource>,
+    news: Arc<dyn NewsProvider>,
+    thresholds: AnomalyThresholds,
+    watchers: Watchers,
+    tasks: Mutex<HashMap<String, JoinHandle<()>>>,
+    /// Follows [`HEARTBEAT_SYMBOL`] while any symbol is monitored
+    heartbeat: Mutex<Option<JoinHandle<()>>>,
+    /// Time of the last heartbeat trade
+    market_trade: Arc<Mutex<Option<DateTime<Utc>>>>,
+}
+
+impl AnomalyMonitor {
+    /// Monitor starting from `quotes`' latest prices, with headlines from
+    /// `news`
+    pub fn new(quotes: Arc<dyn QuoteSource>, news: Arc<dyn NewsProvider>) -> Self {
+        Self {
+            quotes,
+            news,
+            thresholds: AnomalyThresholds::default(),
+            watchers: Watchers::default(),
+            tasks: Mutex::new(HashMap::new()),
+            heartbeat: Mutex::new(None),
+            market_trade: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Report anomalies at `thresholds` instead of the defaults
+    pub fn with_thresholds(mut self, thresholds: AnomalyThresholds) -> Self {
+        self.thresholds = thresholds;
+        self
+    }
+
+    /// Push anomalies of `symbol` to `user_id` through `notifier`; false if
+    /// already watched
+    pub fn watch(
+        &self,
+        streamer: &QuoteStreamer,
+        user_id: &str,
+        notifier: Arc<dyn Notifier>,
+        symbol: &str,
+    ) -> bool {
+        let symbol = symbol.trim().to_uppercase();
+        {
+            let mut watchers = self.watchers.lock().unwrap_or_else(PoisonError::into_inner);
+            let entry = watchers.entry(symbol.clone()).or_default();
+            if entry.iter().any(|w| w.user_id == user_id) {
+                return false;
+            }
+            entry.push(Watcher {
+                user_id: user_id.to_string(),
+                notifier,
+            });
+        }
+
+        let mut tasks = self.tasks.lock().unwrap_or_else(PoisonError::into_inner);
+        tasks.retain(|_, task| !task.is_finished());
+        if let Entry::Vacant(entry) = tasks.entry(symbol) {
+            let stream = streamer.subscribe(entry.key());
+            entry.insert(tokio::spawn(monitor(
+                stream,
+                Arc::clone(&self.quotes),
+                Arc::clone(&self.news),
+                self.thresholds,
+                Arc::clone(&self.watchers),
+                Arc::clone(&self.market_trade),
+            )));
+        }
+
+        let mut heartbeat = self
+            .heartbeat
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if heartbeat.as_ref().is_none_or(JoinHandle::is_finished) {
+            *heartbeat = Some(tokio::spawn(follow_market(
+                streamer.subscribe(HEARTBEAT_SYMBOL),
+                Arc::clone(&self.market_trade),
+            )));
+        }
+        true
+    }
+
+    /// Stop pushing anomalies of `symbol` to `user_id`; false if not watched
+    pub fn unwatch(&self, user_id: &str, symbol: &str) -> bool {
+        let symbol = symbol.trim().to_uppercase();
+        let mut watchers = self.watchers.lock().unwrap_or_else(PoisonError::into_inner);
+        let Some(entry) = watchers.get_mut(&symbol) else {
+            return false;
+        };
+        let before = entry.len();
+        entry.retain(|w| w.user_id != user_id);
+        let removed = entry.len() < before;
+        if entry.is_empty() {
+            watchers.remove(&symbol);
+            if let Some(task) = self
+                .tasks
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .remove(&symbol)
+            {
+                task.abort();
+            }
+        }
+        if watchers.is_empty()
+            && let Some(heartbeat) = self
+                .heartbeat
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .take()
+        {
+            heartbeat.abort();
+        }
+        removed
+    }
+
+    /// Symbols monitored for `user_id`, sorted
+    pub fn symbols_for(&self, user_id: &str) -> Vec<String> {
+        let mut symbols: Vec<String> = self
+            .watchers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .filter(|(_, watchers)| watchers.iter().any(|w| w.user_id == user_id))
+            .map(|(symbol, _)| symbol.clone())
+            .collect();
+        symbols.sort();
+        symbols
+    }
+}
+
+impl Drop for AnomalyMonitor {
+    fn drop(&mut self) {
+        for task in self
+            .tasks
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .values()
+        {
+            task.abort();
+        }
+        if let Some(heartbeat) = self
+            .heartbeat
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+        {
+            heartbeat.abort();
+        }
+    }
+}
+
+/// Record when the market last traded until `stream` ends
+async fn follow_market(mut stream: QuoteStream, market_trade: Arc<Mutex<Option<DateTime<Utc>>>>) {
+    while let Some(tick) = stream.recv().await {
+        *market_trade.lock().unwrap_or_else(PoisonError::into_inner) = Some(tick.timestamp);
+    }
+}
+
+/// Detect anomalies in `stream` and push them to the symbol's watchers
+async fn monitor(
+    mut stream: QuoteStream,
+    quotes: Arc<dyn QuoteSource>,
+    news: Arc<dyn NewsProvider>,
+    thresholds: AnomalyThresholds,
+    watchers: Watchers,
+    market_trade: Arc<Mutex<Option<DateTime<Utc>>>>,
+) {
+    let symbol = stream.symbol().to_string();
+    let start = match quotes.last_price(&symbol).await {
+        Ok(price) => Some(price),
+        Err(e) => {
+            tracing::debug!("No starting quote for {}: {}", symbol, e);
+            None
+        }
+    };
+    let mut detector = AnomalyDetector::new(start, thresholds);
+    let mut halt_checks = tokio::time::interval(HALT_CHECK_INTERVAL);
+
+    loop {
+        let (found, at) = tokio::select! {
+            tick = stream.recv() => {
+                let Some(tick) = tick else { return };
+                (detector.observe(&tick), tick.timestamp)
+            }
+            _ = halt_checks.tick() => {
+                let now = Utc::now();
+                let market_active = market_trade
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .is_some_and(|at| now - at < HEARTBEAT_WINDOW);
+                (detector.check_halt(now, market_active).into_iter().collect(), now)
+            }
+        };
+        if found.is_empty() {
+            continue;
+        }
+
+        let headlines = latest_headlines(news.as_ref(), &symbol).await;
+        let recipients = watchers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&symbol)
+            .cloned()
+            .unwrap_or_default();
+        for kind in found {
+            let anomaly = Anomaly {
+                symbol: symbol.clone(),
+                kind,
+                price: detector.last_price().unwrap_or_default(),
+                at,
+                headlines: headlines.clone(),
+            };
+            let message = anomaly.to_string();
+            tracing::info!("{}", message.lines().next().unwrap_or_default());
+            for watcher in &recipients {
+                if let Err(e) = watcher.notifier.notify(&watcher.user_id, &message).await {
+                    tracing::warn!(
+                        "Failed to push {} anomaly to {}: {}",
+                        symbol,
+                        watcher.user_id,
+                        e
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// "title (source)" of the newest articles about `symbol`; empty when the
+/// fetch fails
+async fn latest_headlines(news: &dyn NewsProvider, symbol: &str) -> Vec<String> {
+    match news.get_company_news(symbol, HEADLINES).await {
+        Ok(items) => items
+            .iter()
+            .take(HEADLINES)
+            .map(|item| format!("{} ({})", item.title, item.source))
+            .collect(),
+        Err(e) => {
+            tracing::debug!("No news for {} anomaly: {}", symbol, e);
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::stream::{StreamConfig, StreamProvider};
+    use crate::error::Result;
+    use crate::news::MockNewsProvider;
+    use async_trait::async_trait;
+
+    fn at(minutes: i64) -> DateTime<Utc> {
+        // 2024-03-14 14:00 UTC
+        DateTime::from_timestamp(1_710_424_800, 0).unwrap() + Duration::minutes(minutes)
+    }
+
+    fn tick(minutes: i64, price: f64) -> Tick {
+        Tick {
+            symbol: "NVDA".to_string(),
+            price,
+            volume: Some(100.0),
+            timestamp: at(minutes),
+        }
+    }
+
+    #[test]
+    fn test_gap_from_last_known_price() {
+        let mut detector = AnomalyDetector::new(Some(100.0), AnomalyThresholds::default());
+        let found = detector.observe(&tick(0, 104.0));
+        assert!(
+            matches!(found.as_slice(), [AnomalyKind::Gap { change_pct, from }]
+                if (*change_pct - 4.0).abs() < 1e-9 && *from == 100.0),
+            "{found:?}"
+        );
+        assert!(detector.observe(&tick(1, 104.5)).is_empty());
+        assert!(matches!(
+            detector.observe(&tick(2, 100.0))[0],
+            AnomalyKind::Gap { change_pct, .. } if change_pct < -4.0
+        ));
+    }
+
+    #[test]
+    fn test_limit_move_reported_once_until_back_in_band() {
+        let mut detector = AnomalyDetector::new(None, AnomalyThresholds::default());
+        // A steady climb of 2% a minute never gaps...
+        for (minute, price) in [(0, 100.0), (1, 102.0), (2, 104.0), (3, 106.0), (4, 108.0)] {
+            assert!(detector.observe(&tick(minute, price)).is_empty());
+        }
+        // ...but leaves 110 5.8% above the 5-min average of 104
+        let found = detector.observe(&tick(5, 110.0));
+        assert!(
+            matches!(found.as_slice(), [AnomalyKind::LimitMove { change_pct, reference }]
+                if *change_pct > 5.0 && (*reference - 104.0).abs() < 1e-9),
+            "{found:?}"
+        );
+        assert!(detector.observe(&tick(5, 110.5)).is_empty());
+
+        // Holding near the average re-arms the band
+        for minute in 6..11 {
+            detector.observe(&tick(minute, 110.5));
+        }
+        let found = detector.observe(&tick(11, 104.0));
+        assert!(
+            found.iter().any(|kind| matches!(kind, AnomalyKind::LimitMove { change_pct, .. } if *change_pct < -5.0)),
+            "{found:?}"
+        );
+    }
+
+    #[test]
+    fn test_halt_needs_an_active_market() {
+        let mut detector = AnomalyDetector::new(None, AnomalyThresholds::default());
+        assert_eq!(detector.check_halt(at(10), true), None);
+
+        detector.observe(&tick(0, 100.0));
+        assert_eq!(detector.check_halt(at(4), true), None);
+        assert_eq!(detector.check_halt(at(6), false), None);
+        assert_eq!(
+            detector.check_halt(at(6), true),
+            Some(AnomalyKind::Halt {
+                silent_for: Duration::minutes(6)
+            })
+        );
+        assert_eq!(detector.check_halt(at(7), true), None);
+
+        let found = detector.observe(&tick(12, 100.2));
+        assert_eq!(
+            found,
+            vec![AnomalyKind::Resumed {
+                halted_for: Duration::minutes(12)
+            }]
+        );
+        // The next day's quiet pre-market is not a halt
+        assert_eq!(detector.check_halt(at(24 * 60), true), None);
+    }
+
+    #[test]
+    fn test_anomaly_message() {
+        let anomaly = Anomaly {
+            symbol: "NVDA".to_string(),
+            kind: AnomalyKind::LimitMove {
+                change_pct: -5.4,
+                reference: 900.0,
+            },
+            price: 851.4,
+            at: at(35),
+            headlines: vec!["NVDA export curbs widen (Reuters)".to_string()],
+        };
+        assert_eq!(
+            anomaly.to_string(),
+            "🔻 NVDA limit down: -5.4% from its 5-min average 900.00, now 851.40 (14:35 UTC)\n\n\
+             Latest news:\n• NVDA export curbs widen (Reuters)"
+        );
+
+        let gap = Anomaly {
+            kind: AnomalyKind::Gap {
+                change_pct: 3.2,
+                from: 880.0,
+            },
+            price: 908.2,
+            headlines: Vec::new(),
+            ..anomaly
+        };
+        assert_eq!(
+            gap.to_string(),
+            "⚡ NVDA gapped up 3.2%: 908.20 after 880.00 (14:35 UTC)"
+        );
+    }
+
+    struct FixedQuotes;
+
+    #[async_trait]
+    impl QuoteSource for FixedQuotes {
+        async fn last_price(&self, _symbol: &str) -> Result<f64> {
+            Ok(100.0)
+        }
+
+        async fn daily_closes(&self, _symbol: &str) -> Result<Vec<f64>> {
+            Ok(Vec::new())
+        }
+    }
+
+    struct Silent;
+
+    #[async_trait]
+    impl Notifier for Silent {
+        async fn notify(&self, _user_id: &str, _message: &str) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_watch_and_unwatch() {
+        // Nothing listens here; monitors start and stop without a connection
+        let streamer = QuoteStreamer::spawn(
+            StreamConfig::new(StreamProvider::Finnhub, "key").with_url("ws://127.0.0.1:9"),
+        );
+        let monitor = AnomalyMonitor::new(Arc::new(FixedQuotes), Arc::new(MockNewsProvider));
+
+        assert!(monitor.watch(&streamer, "42", Arc::new(Silent), "nvda"));
+        assert!(!monitor.watch(&streamer, "42", Arc::new(Silent), "NVDA"));
+        assert!(monitor.watch(&streamer, "7", Arc::new(Silent), "NVDA"));
+        assert!(monitor.watch(&streamer, "42", Arc::new(Silent), "TSLA"));
+        assert_eq!(monitor.symbols_for("42"), vec!["NVDA", "TSLA"]);
+        assert!(streamer.symbols().contains(&HEARTBEAT_SYMBOL.to_string()));
+
+        assert!(monitor.unwatch("42", "NVDA"));
+        assert!(!monitor.unwatch("42", "NVDA"));
+        assert_eq!(monitor.symbols_for("7"), vec!["NVDA"]);
+        assert!(monitor.unwatch("7", "NVDA"));
+        assert!(monitor.unwatch("42", "TSLA"));
+        assert!(monitor.symbols_for("42").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_headlines_from_news() {
+        let headlines = latest_headlines(&MockNewsProvider, "NVDA").await;
+        assert_eq!(
+            headlines,
+            vec![
+                "NVDA Stock Analysis Update (Market News)",
+                "NVDA Quarterly Earnings Report (Financial Times)"
+            ]
+        );
+    }
+}
@@ -1,16 +1,48 @@
 //! Alpha Vantage API client
-
+//!
+//! Requests are paced per API key: every client built with the same key
+//! shares one limiter, since Alpha Vantage counts quota per key rather than
+//! per connection. The limiter starts at the configured requests per minute
+//! (5 on the free tier, [`PREMIUM_RATE_LIMIT`] by default on premium plans)
+//! and then follows the API's own rate-limit messages: a per-second notice
+//! pauses the key for a second, a per-minute notice pauses it for a minute and
+//! lowers the quota to the stated limit, and a daily notice stops requests
+//! until the quota resets at midnight UTC.
+//!
+//! Premium plans also unlock realtime quotes and month-by-month intraday
+//! history ([`AlphaVantageClient::get_realtime_quote`] and
+//! [`AlphaVantageClient::get_intraday_month`]).
+
+use super::circuit::{self, CircuitBreaker};
+use crate::config::StockConfig;
 use crate::error::{Result, StockError};
+use chrono::{DateTime, Days, Utc};
 use governor::clock::DefaultClock;
 use governor::state::{InMemoryState, NotKeyed};
 use governor::{Quota, RateLimiter};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
 use std::num::NonZeroU32;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError, RwLock};
+use std::time::Duration;
+use tokio::time::Instant;
+
+const BASE_URL: &str = "https://www.alphavantage.co";
+
+const PROVIDER: &str = "Alpha Vantage";
+
+/// Requests per minute allowed on the free tier
+pub const FREE_RATE_LIMIT: u32 = 5;
 
-const BASE_URL: &str = "https://www.alphavantage.co/query";
+/// Requests per minute assumed for premium plans when no limit is configured
+/// (the entry-level plan)
+pub const PREMIUM_RATE_LIMIT: u32 = 75;
+
+/// Longest a request waits out a pause before failing fast instead
+const MAX_PAUSE_WAIT: Duration = Duration::from_secs(60);
 
 type SharedRateLimiter = Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock>>;
 
@@ -19,7 +51,162 @@ type SharedRateLimiter = Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock>>
 pub struct AlphaVantageClient {
     client: Client,
     api_key: String,
-    rate_limiter: SharedRateLimiter,
+    base_url: String,
+    premium: bool,
+    throttle: Arc<Throttle>,
+}
+
+/// Limits stated in an Alpha Vantage rate-limit message
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RateLimitNotice {
+    /// Requests allowed per second, for burst warnings
+    pub per_second: Option<u32>,
+    /// Requests allowed per minute
+    pub per_minute: Option<u32>,
+    /// Requests allowed per day
+    pub per_day: Option<u32>,
+}
+
+impl RateLimitNotice {
+    /// Parse a "Note" or "Information" message, returning `None` when it is
+    /// not about rate limits
+    ///
+    /// Recognizes phrases like "5 calls per minute", "25 requests per day" and
+    /// "1 request per second". Messages about rate limits that state no
+    /// number still return a notice, with every limit unset.
+    pub fn parse(message: &str) -> Option<Self> {
+        let lower = message.to_lowercase();
+        let words: Vec<&str> = lower
+            .split(|c: char| c.is_whitespace() || "(),.;".contains(c))
+            .filter(|word| !word.is_empty())
+            .collect();
+
+        let mut notice = Self::default();
+        for window in words.windows(4) {
+            let [count, unit, "per", period] = window else {
+                continue;
+            };
+            let Ok(count) = count.parse::<u32>() else {
+                continue;
+            };
+            if !matches!(*unit, "call" | "calls" | "request" | "requests") {
+                continue;
+            }
+            match *period {
+                "second" => notice.per_second = Some(count),
+                "minute" => notice.per_minute = Some(count),
+                "day" => notice.per_day = Some(count),
+                _ => {}
+            }
+        }
+
+        let about_limits = notice != Self::default()
+            || ["rate limit", "call frequency", "sparingly"]
+                .iter()
+                .any(|hint| lower.contains(hint));
+        about_limits.then_some(notice)
+    }
+
+    /// How long to stop sending requests after this notice
+    ///
+    /// Burst warnings mention the daily limit too, so the shortest stated
+    /// period wins; a daily limit alone waits for midnight UTC.
+    pub fn backoff(&self, now: DateTime<Utc>) -> Duration {
+        if self.per_second.is_some() {
+            Duration::from_secs(1)
+        } else if self.per_minute.is_some() || self.per_day.is_none() {
+            Duration::from_secs(60)
+        } else {
+            now.date_naive()
+                .checked_add_days(Days::new(1))
+                .and_then(|tomorrow| tomorrow.and_hms_opt(0, 0, 0))
+                .and_then(|midnight| (midnight.and_utc() - now).to_std().ok())
+                .unwrap_or(Duration::from_secs(3600))
+        }
+    }
+}
+
+/// Request pacing shared by every client using the same API key
+#[derive(Debug)]
+struct Throttle {
+    limiter: RwLock<SharedRateLimiter>,
+    per_minute: AtomicU32,
+    paused_until: Mutex<Option<Instant>>,
+}
+
+impl Throttle {
+    fn new(per_minute: u32) -> Self {
+        Self {
+            limiter: RwLock::new(rate_limiter(per_minute)),
+            per_minute: AtomicU32::new(per_minute),
+            paused_until: Mutex::new(None),
+        }
+    }
+
+    /// The throttle for `api_key`, set to `per_minute`
+    fn for_key(api_key: &str, per_minute: u32) -> Arc<Self> {
+        static THROTTLES: OnceLock<Mutex<HashMap<String, Arc<Throttle>>>> = OnceLock::new();
+
+        let mut throttles = THROTTLES
+            .get_or_init(Mutex::default)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let throttle = throttles
+            .entry(api_key.to_string())
+            .or_insert_with(|| Arc::new(Self::new(per_minute)));
+        throttle.set_limit(per_minute);
+        Arc::clone(throttle)
+    }
+
+    fn set_limit(&self, per_minute: u32) {
+        if self.per_minute.swap(per_minute, Ordering::Relaxed) != per_minute {
+            *self.limiter.write().unwrap_or_else(PoisonError::into_inner) =
+                rate_limiter(per_minute);
+        }
+    }
+
+    /// Wait for a request slot, failing fast while the key is paused for
+    /// longer than [`MAX_PAUSE_WAIT`]
+    async fn wait(&self) -> Result<()> {
+        let paused_until = *self
+            .paused_until
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(until) = paused_until {
+            if until > Instant::now() + MAX_PAUSE_WAIT {
+                return Err(StockError::rate_limited(PROVIDER));
+            }
+            tokio::time::sleep_until(until).await;
+        }
+
+        let limiter = Arc::clone(&self.limiter.read().unwrap_or_else(PoisonError::into_inner));
+        limiter.until_ready().await;
+        Ok(())
+    }
+
+    /// Adapt to a rate-limit message from the API
+    fn record(&self, notice: &RateLimitNotice) {
+        if let Some(limit) = notice.per_minute
+            && limit > 0
+            && limit < self.per_minute.load(Ordering::Relaxed)
+        {
+            tracing::warn!("Alpha Vantage allows {limit} requests/minute, lowering the limit");
+            self.set_limit(limit);
+        }
+
+        let until = Instant::now() + notice.backoff(Utc::now());
+        let mut paused_until = self
+            .paused_until
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        *paused_until = Some(paused_until.map_or(until, |paused| paused.max(until)));
+    }
+}
+
+fn rate_limiter(per_minute: u32) -> SharedRateLimiter {
+    const FREE_QUOTA: NonZeroU32 = NonZeroU32::new(FREE_RATE_LIMIT).unwrap();
+    let per_minute = NonZeroU32::new(per_minute).unwrap_or(FREE_QUOTA);
+    Arc::new(RateLimiter::direct(Quota::per_minute(per_minute)))
 }
 
 /// Time series data point
@@ -104,15 +291,15 @@ impl AlphaVantageClient {
     /// * `api_key` - Alpha Vantage API key
     /// * `rate_limit` - Maximum requests per minute (default: 5 for free tier)
     pub fn new(api_key: impl Into<String>, rate_limit: u32) -> Self {
-        // Create rate limiter quota (requests per minute)
-        let quota =
-            Quota::per_minute(NonZeroU32::new(rate_limit).unwrap_or(NonZeroU32::new(5).unwrap()));
-        let rate_limiter = Arc::new(RateLimiter::direct(quota));
+        let api_key = api_key.into();
+        let throttle = Throttle::for_key(&api_key, rate_limit);
 
         Self {
             client: Client::new(),
-            api_key: api_key.into(),
-            rate_limiter,
+            api_key,
+            base_url: BASE_URL.to_string(),
+            premium: false,
+            throttle,
         }
     }
 
@@ -124,7 +311,37 @@ impl AlphaVantageClient {
             )
         })?;
 
-        Ok(Self::new(api_key, 5)) // Default to free tier limit
+        Ok(Self::new(api_key, FREE_RATE_LIMIT)) // Default to free tier limit
+    }
+
+    /// Create from the configured key, rate limit and plan, or `None` without a key
+    pub fn from_config(config: &StockConfig) -> Option<Self> {
+        config.alpha_vantage_api_key.as_ref().map(|key| {
+            Self::new(key.clone(), config.alpha_vantage_rate_limit)
+                .with_premium(config.alpha_vantage_premium)
+        })
+    }
+
+    /// Mark the key as belonging to a premium plan, unlocking premium endpoints
+    pub fn with_premium(mut self, premium: bool) -> Self {
+        self.premium = premium;
+        self
+    }
+
+    /// Send requests to `base_url` (e.g. a mock server) instead of Alpha Vantage
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Circuit breaker shared by every client of this endpoint
+    pub fn breaker(&self) -> Arc<CircuitBreaker> {
+        circuit::breaker(PROVIDER, &self.base_url)
+    }
+
+    /// Whether premium endpoints are enabled
+    pub fn is_premium(&self) -> bool {
+        self.premium
     }
 
     /// Get intraday time series data
@@ -133,183 +350,74 @@ impl AlphaVantageClient {
         symbol: &str,
         interval: &str, // 1min, 5min, 15min, 30min, 60min
     ) -> Result<Vec<TimeSeriesData>> {
-        // Wait for rate limiter
-        self.rate_limiter.until_ready().await;
-
-        let mut params = HashMap::new();
-        params.insert("function", "TIME_SERIES_INTRADAY");
-        params.insert("symbol", symbol);
-        params.insert("interval", interval);
-        params.insert("apikey", &self.api_key);
-
-        let response = self.client.get(BASE_URL).query(&params).send().await?;
-
-        if !response.status().is_success() {
-            return Err(StockError::AlphaVantageError(format!(
-                "HTTP error: {}",
-                response.status()
-            )));
-        }
-
-        let data: serde_json::Value = response.json().await?;
-
-        // Check for API error messages
-        if let Some(error) = data.get("Error Message") {
-            return Err(StockError::AlphaVantageError(error.to_string()));
-        }
-
-        if let Some(_note) = data.get("Note") {
-            return Err(StockError::RateLimitExceeded {
-                provider: "Alpha Vantage".to_string(),
-            });
-        }
-
-        // Parse time series data
-        let series_key = format!("Time Series ({interval})");
-        let series = data.get(&series_key).ok_or_else(|| {
-            StockError::AlphaVantageError("No time series data found".to_string())
-        })?;
-
-        let mut result = Vec::new();
-        if let Some(obj) = series.as_object() {
-            for (timestamp, values) in obj {
-                let open = values["1. open"]
-                    .as_str()
-                    .unwrap_or("0")
-                    .parse()
-                    .unwrap_or(0.0);
-                let high = values["2. high"]
-                    .as_str()
-                    .unwrap_or("0")
-                    .parse()
-                    .unwrap_or(0.0);
-                let low = values["3. low"]
-                    .as_str()
-                    .unwrap_or("0")
-                    .parse()
-                    .unwrap_or(0.0);
-                let close = values["4. close"]
-                    .as_str()
-                    .unwrap_or("0")
-                    .parse()
-                    .unwrap_or(0.0);
-                let volume = values["5. volume"]
-                    .as_str()
-                    .unwrap_or("0")
-                    .parse()
-                    .unwrap_or(0);
-
-                result.push(TimeSeriesData {
-                    timestamp: timestamp.clone(),
-                    open,
-                    high,
-                    low,
-                    close,
-                    volume,
-                });
-            }
-        }
+        let data = self
+            .query(&[
+                ("function", "TIME_SERIES_INTRADAY"),
+                ("symbol", symbol),
+                ("interval", interval),
+            ])
+            .await?;
+
+        parse_series(&data, &format!("Time Series ({interval})"))
+            .ok_or_else(|| StockError::AlphaVantageError("No time series data found".to_string()))
+    }
 
-        Ok(result)
+    /// Get a full month of intraday bars (premium)
+    ///
+    /// # Arguments
+    /// * `interval` - 1min, 5min, 15min, 30min or 60min
+    /// * `month` - Month in YYYY-MM format, back to 2000-01
+    pub async fn get_intraday_month(
+        &self,
+        symbol: &str,
+        interval: &str,
+        month: &str,
+    ) -> Result<Vec<TimeSeriesData>> {
+        self.require_premium("extended intraday history")?;
+
+        let data = self
+            .query(&[
+                ("function", "TIME_SERIES_INTRADAY"),
+                ("symbol", symbol),
+                ("interval", interval),
+                ("month", month),
+                ("outputsize", "full"),
+            ])
+            .await?;
+
+        parse_series(&data, &format!("Time Series ({interval})")).ok_or_else(|| {
+            StockError::AlphaVantageError(format!("No intraday data found for {month}"))
+        })
     }
 
-    /// Get daily time series data
+    /// Get daily time series data (the latest 100 days)
     pub async fn get_daily(&self, symbol: &str) -> Result<Vec<TimeSeriesData>> {
-        // Wait for rate limiter
-        self.rate_limiter.until_ready().await;
-
-        let mut params = HashMap::new();
-        params.insert("function", "TIME_SERIES_DAILY");
-        params.insert("symbol", symbol);
-        params.insert("apikey", &self.api_key);
-
-        let response = self.client.get(BASE_URL).query(&params).send().await?;
-
-        let data: serde_json::Value = response.json().await?;
-
-        // Check for errors
-        if let Some(error) = data.get("Error Message") {
-            return Err(StockError::AlphaVantageError(error.to_string()));
-        }
-
-        if let Some(_note) = data.get("Note") {
-            return Err(StockError::RateLimitExceeded {
-                provider: "Alpha Vantage".to_string(),
-            });
-        }
-
-        // Parse time series data
-        let series = data
-            .get("Time Series (Daily)")
-            .ok_or_else(|| StockError::AlphaVantageError("No daily data found".to_string()))?;
+        self.daily_series(symbol, "compact").await
+    }
 
-        let mut result = Vec::new();
-        if let Some(obj) = series.as_object() {
-            for (timestamp, values) in obj {
-                let open = values["1. open"]
-                    .as_str()
-                    .unwrap_or("0")
-                    .parse()
-                    .unwrap_or(0.0);
-                let high = values["2. high"]
-                    .as_str()
-                    .unwrap_or("0")
-                    .parse()
-                    .unwrap_or(0.0);
-                let low = values["3. low"]
-                    .as_str()
-                    .unwrap_or("0")
-                    .parse()
-                    .unwrap_or(0.0);
-                let close = values["4. close"]
-                    .as_str()
-                    .unwrap_or("0")
-                    .parse()
-                    .unwrap_or(0.0);
-                let volume = values["5. volume"]
-                    .as_str()
-                    .unwrap_or("0")
-                    .parse()
-                    .unwrap_or(0);
-
-                result.push(TimeSeriesData {
-                    timestamp: timestamp.clone(),
-                    open,
-                    high,
-                    low,
-                    close,
-                    volume,
-                });
-            }
-        }
+    /// Get the full daily history, 20+ years where available
+    pub async fn get_daily_full(&self, symbol: &str) -> Result<Vec<TimeSeriesData>> {
+        self.daily_series(symbol, "full").await
+    }
 
-        Ok(result)
+    async fn daily_series(&self, symbol: &str, output_size: &str) -> Result<Vec<TimeSeriesData>> {
+        let data = self
+            .query(&[
+                ("function", "TIME_SERIES_DAILY"),
+                ("symbol", symbol),
+                ("outputsize", output_size),
+            ])
+            .await?;
+
+        parse_series(&data, "Time Series (Daily)")
+            .ok_or_else(|| StockError::AlphaVantageError("No daily data found".to_string()))
     }
 
     /// Get company overview and fundamental data
     pub async fn get_company_overview(&self, symbol: &str) -> Result<CompanyOverview> {
-        // Wait for rate limiter
-        self.rate_limiter.until_ready().await;
-
-        let mut params = HashMap::new();
-        params.insert("function", "OVERVIEW");
-        params.insert("symbol", symbol);
-        params.insert("apikey", &self.api_key);
-
-        let response = self.client.get(BASE_URL).query(&params).send().await?;
-
-        let data: serde_json::Value = response.json().await?;
-
-        // Check for errors
-        if let Some(error) = data.get("Error Message") {
-            return Err(StockError::AlphaVantageError(error.to_string()));
-        }
-
-        if let Some(_note) = data.get("Note") {
-            return Err(StockError::RateLimitExceeded {
-                provider: "Alpha Vantage".to_string(),
-            });
-        }
+        let data = self
+            .query(&[("function", "OVERVIEW"), ("symbol", symbol)])
+            .await?;
 
         // Check if data is empty (symbol not found)
         if data.as_object().is_none_or(serde_json::Map::is_empty) {
@@ -321,54 +429,37 @@ impl AlphaVantageClient {
     }
 
     /// Get global quote (current price data)
-    pub async fn get_quote(&self, symbol: &str) -> Result<serde_json::Value> {
-        // Wait for rate limiter
-        self.rate_limiter.until_ready().await;
-
-        let mut params = HashMap::new();
-        params.insert("function", "GLOBAL_QUOTE");
-        params.insert("symbol", symbol);
-        params.insert("apikey", &self.api_key);
-
-        let response = self.client.get(BASE_URL).query(&params).send().await?;
-
-        let data: serde_json::Value = response.json().await?;
-
-        // Check for errors
-        if let Some(error) = data.get("Error Message") {
-            return Err(StockError::AlphaVantageError(error.to_string()));
-        }
+    ///
+    /// Free keys get end-of-day prices; see [`Self::get_realtime_quote`] for
+    /// live prices on premium plans.
+    pub async fn get_quote(&self, symbol: &str) -> Result<Value> {
+        self.query(&[("function", "GLOBAL_QUOTE"), ("symbol", symbol)])
+            .await
+    }
 
-        if let Some(_note) = data.get("Note") {
-            return Err(StockError::RateLimitExceeded {
-                provider: "Alpha Vantage".to_string(),
-            });
-        }
+    /// Get a realtime US quote (premium)
+    pub async fn get_realtime_quote(&self, symbol: &str) -> Result<Value> {
+        self.require_premium("realtime quotes")?;
 
-        Ok(data)
+        self.query(&[
+            ("function", "GLOBAL_QUOTE"),
+            ("symbol", symbol),
+            ("entitlement", "realtime"),
+        ])
+        .await
     }
 
     /// Search for symbols
-    pub async fn search_symbol(&self, keywords: &str) -> Result<Vec<serde_json::Value>> {
-        // Wait for rate limiter
-        self.rate_limiter.until_ready().await;
-
-        let mut params = HashMap::new();
-        params.insert("function", "SYMBOL_SEARCH");
-        params.insert("keywords", keywords);
-        params.insert("apikey", &self.api_key);
-
-        let response = self.client.get(BASE_URL).query(&params).send().await?;
-
-        let data: serde_json::Value = response.json().await?;
-
-        if let Some(matches) = data.get("bestMatches") {
-            if let Some(arr) = matches.as_array() {
-                return Ok(arr.clone());
-            }
-        }
-
-        Ok(vec![])
+    pub async fn search_symbol(&self, keywords: &str) -> Result<Vec<Value>> {
+        let data = self
+            .query(&[("function", "SYMBOL_SEARCH"), ("keywords", keywords)])
+            .await?;
+
+        Ok(data
+            .get("bestMatches")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default())
     }
 
     /// Get news sentiment data for a ticker
@@ -385,48 +476,156 @@ impl AlphaVantageClient {
         time_to: Option<&str>,
         limit: Option<u32>,
     ) -> Result<NewsSentimentResponse> {
-        // Wait for rate limiter
-        self.rate_limiter.until_ready().await;
-
-        let mut params = HashMap::new();
-        params.insert("function".to_string(), "NEWS_SENTIMENT".to_string());
-        params.insert("tickers".to_string(), tickers.to_string());
-        params.insert("apikey".to_string(), self.api_key.clone());
-
+        let limit = limit.map(|lim| lim.to_string());
+        let mut params = vec![("function", "NEWS_SENTIMENT"), ("tickers", tickers)];
         if let Some(from) = time_from {
-            params.insert("time_from".to_string(), from.to_string());
+            params.push(("time_from", from));
         }
         if let Some(to) = time_to {
-            params.insert("time_to".to_string(), to.to_string());
+            params.push(("time_to", to));
+        }
+        if let Some(lim) = &limit {
+            params.push(("limit", lim));
+        }
+
+        let data = self.query(&params).await?;
+
+        let sentiment_response: NewsSentimentResponse = serde_json::from_value(data)?;
+
+        Ok(sentiment_response)
+    }
+
+    /// Get news sentiment data for topics rather than tickers
+    ///
+    /// # Arguments
+    /// * `topics` - Comma-separated topics (e.g., "financial_markets,economy_macro")
+    /// * `limit` - Optional limit on number of results (default: 50, max: 1000)
+    pub async fn get_topic_news(
+        &self,
+        topics: &str,
+        limit: Option<u32>,
+    ) -> Result<NewsSentimentResponse> {
+        let limit = limit.map(|lim| lim.to_string());
+        let mut params = vec![("function", "NEWS_SENTIMENT"), ("topics", topics)];
+        if let Some(lim) = &limit {
+            params.push(("limit", lim));
         }
-        if let Some(lim) = limit {
-            params.insert("limit".to_string(), lim.to_string());
+
+        let data = self.query(&params).await?;
+        Ok(serde_json::from_value(data)?)
+    }
+
+    fn require_premium(&self, feature: &str) -> Result<()> {
+        if self.premium {
+            Ok(())
+        } else {
+            Err(StockError::ConfigError(format!(
+                "Alpha Vantage premium plan required for {feature}: set {}=true",
+                crate::config::ALPHA_VANTAGE_PREMIUM_ENV
+            )))
+        }
+    }
+
+    /// Send a request and check the response for Alpha Vantage's in-band
+    /// errors, adapting the throttle to any rate-limit message
+    ///
+    /// Burst warnings are retried once after the pause they ask for.
+    async fn query(&self, params: &[(&str, &str)]) -> Result<Value> {
+        let mut retried = false;
+        loop {
+            match self.send(params).await? {
+                Ok(data) => return Ok(data),
+                Err(notice) if !retried && notice.per_second.is_some() => retried = true,
+                Err(_) => return Err(StockError::rate_limited(PROVIDER)),
+            }
         }
+    }
+
+    /// Send one request, returning the rate-limit notice in place of the data
+    /// when the API refuses it
+    async fn send(
+        &self,
+        params: &[(&str, &str)],
+    ) -> Result<std::result::Result<Value, RateLimitNotice>> {
+        self.throttle.wait().await?;
+
+        let request = self
+            .client
+            .get(format!("{}/query", self.base_url))
+            .query(params)
+            .query(&[("apikey", &self.api_key)]);
+        let response = self.breaker().send(request).await?;
 
-        let response = self.client.get(BASE_URL).query(&params).send().await?;
+        if !response.status().is_success() {
+            return Err(StockError::AlphaVantageError(format!(
+                "HTTP error: {}",
+                response.status()
+            )));
+        }
 
-        let data: serde_json::Value = response.json().await?;
+        let data: Value = response.json().await?;
 
-        // Check for errors
+        // Check for API error messages
         if let Some(error) = data.get("Error Message") {
             return Err(StockError::AlphaVantageError(error.to_string()));
         }
 
-        if let Some(_note) = data.get("Note") {
-            return Err(StockError::RateLimitExceeded {
-                provider: "Alpha Vantage".to_string(),
-            });
+        let message = ["Note", "Information"]
+            .iter()
+            .find_map(|key| data.get(*key).and_then(Value::as_str));
+        if let Some(message) = message {
+            if message.contains("is a premium endpoint") {
+                return Err(StockError::AlphaVantageError(format!(
+                    "{message} (set {}=true if your key is on a premium plan)",
+                    crate::config::ALPHA_VANTAGE_PREMIUM_ENV
+                )));
+            }
+            // Legacy quota messages always came as a "Note"
+            let notice = RateLimitNotice::parse(message)
+                .or_else(|| data.get("Note").map(|_| RateLimitNotice::default()));
+            if let Some(notice) = notice {
+                tracing::warn!("Alpha Vantage rate limit: {message}");
+                self.throttle.record(&notice);
+                return Ok(Err(notice));
+            }
         }
 
-        let sentiment_response: NewsSentimentResponse = serde_json::from_value(data)?;
-
-        Ok(sentiment_response)
+        Ok(Ok(data))
     }
 }
 
+/// Parse the bars under `series_key`, or `None` when the response has none
+fn parse_series(data: &Value, series_key: &str) -> Option<Vec<TimeSeriesData>> {
+    let series = data.get(series_key)?.as_object()?;
+
+    let field = |values: &Value, key: &str| -> f64 {
+        values[key].as_str().unwrap_or("0").parse().unwrap_or(0.0)
+    };
+    Some(
+        series
+            .iter()
+            .map(|(timestamp, values)| TimeSeriesData {
+                timestamp: timestamp.clone(),
+                open: field(values, "1. open"),
+                high: field(values, "2. high"),
+                low: field(values, "3. low"),
+                close: field(values, "4. close"),
+                volume: values["5. volume"]
+                    .as_str()
+                    .unwrap_or("0")
+                    .parse()
+                    .unwrap_or(0),
+            })
+            .collect(),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::api::testing::MockApi;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, ResponseTemplate};
 
     #[test]
     fn test_client_creation() {
@@ -456,4 +655,160 @@ mod tests {
         let data = data.unwrap();
         assert!(!data.is_empty());
     }
+
+    const DAILY_LIMIT: &str = "We have detected your API key as demo and our standard API rate \
+        limit is 25 requests per day. Please subscribe to any of the premium plans at \
+        https://www.alphavantage.co/premium/ to instantly remove all daily rate limits.";
+    const BURST_LIMIT: &str = "Thank you for using Alpha Vantage! Please consider spreading out \
+        your free API requests more sparingly (1 request per second). You may subscribe to any \
+        of the premium plans at https://www.alphavantage.co/premium/ to lift the free key rate \
+        limit (25 requests per day) and instantly unlock all premium endpoints";
+    const LEGACY_NOTE: &str = "Thank you for using Alpha Vantage! Our standard API call \
+        frequency is 5 calls per minute and 500 calls per day.";
+    const PREMIUM_ONLY: &str = "Thank you for using Alpha Vantage! This is a premium endpoint. \
+        You may subscribe to any of the premium plans at https://www.alphavantage.co/premium/ \
+        to instantly unlock all premium endpoints";
+
+    #[test]
+    fn test_parse_rate_limit_notice() {
+        assert_eq!(
+            RateLimitNotice::parse(DAILY_LIMIT),
+            Some(RateLimitNotice {
+                per_day: Some(25),
+                ..RateLimitNotice::default()
+            })
+        );
+        assert_eq!(
+            RateLimitNotice::parse(BURST_LIMIT),
+            Some(RateLimitNotice {
+                per_second: Some(1),
+                per_day: Some(25),
+                ..RateLimitNotice::default()
+            })
+        );
+        assert_eq!(
+            RateLimitNotice::parse(LEGACY_NOTE),
+            Some(RateLimitNotice {
+                per_minute: Some(5),
+                per_day: Some(500),
+                ..RateLimitNotice::default()
+            })
+        );
+        assert_eq!(
+            RateLimitNotice::parse("You have exceeded the rate limit of your plan"),
+            Some(RateLimitNotice::default())
+        );
+        assert_eq!(RateLimitNotice::parse(PREMIUM_ONLY), None);
+    }
+
+    #[test]
+    fn test_rate_limit_backoff() {
+        let now = DateTime::parse_from_rfc3339("2024-05-01T23:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let notice = |json: &str| RateLimitNotice::parse(json).unwrap();
+
+        assert_eq!(notice(BURST_LIMIT).backoff(now), Duration::from_secs(1));
+        assert_eq!(notice(LEGACY_NOTE).backoff(now), Duration::from_secs(60));
+        assert_eq!(
+            notice(DAILY_LIMIT).backoff(now),
+            Duration::from_secs(30 * 60)
+        );
+    }
+
+    fn information(message: &str) -> String {
+        serde_json::json!({ "Information": message }).to_string()
+    }
+
+    #[tokio::test]
+    async fn test_daily_limit_pauses_key() {
+        let api = MockApi::start().await;
+        api.mount_json("/query", 200, &information(DAILY_LIMIT))
+            .await;
+        let client = api.alpha_vantage(false);
+
+        let first = client.get_quote("AAPL").await;
+        assert!(matches!(first, Err(StockError::RateLimitExceeded { .. })));
+
+        // Later calls on the same key fail without reaching the API
+        let same_key =
+            AlphaVantageClient::new(client.api_key.clone(), 600).with_base_url(api.uri());
+        let second = same_key.get_daily("AAPL").await;
+        assert!(matches!(second, Err(StockError::RateLimitExceeded { .. })));
+        assert_eq!(api.request_count().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_burst_limit_retries_once() {
+        let api = MockApi::start().await;
+        api.mount_json("/query", 200, &information(BURST_LIMIT))
+            .await;
+        let client = api.alpha_vantage(false);
+
+        let result = client.get_quote("AAPL").await;
+        assert!(matches!(result, Err(StockError::RateLimitExceeded { .. })));
+        assert_eq!(api.request_count().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_premium_endpoints() {
+        let api = MockApi::start().await;
+        Mock::given(method("GET"))
+            .and(path("/query"))
+            .and(query_param("entitlement", "realtime"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "Global Quote": { "01. symbol": "AAPL", "05. price": "171.1300" }
+            })))
+            .mount(api.server())
+            .await;
+        api.mount_json("/query", 200, &information(PREMIUM_ONLY))
+            .await;
+
+        // Free keys are refused before any request is sent
+        let free = api.alpha_vantage(false);
+        assert!(matches!(
+            free.get_realtime_quote("AAPL").await,
+            Err(StockError::ConfigError(_))
+        ));
+        assert!(matches!(
+            free.get_intraday_month("AAPL", "5min", "2024-01").await,
+            Err(StockError::ConfigError(_))
+        ));
+        assert_eq!(api.request_count().await, 0);
+
+        let premium = api.alpha_vantage(true);
+        let quote = premium.get_realtime_quote("AAPL").await.unwrap();
+        assert_eq!(
+            quote.pointer("/Global Quote/05. price").unwrap(),
+            "171.1300"
+        );
+
+        // A key that is not actually premium gets a hint instead of a rate limit
+        match premium.get_intraday_month("AAPL", "5min", "2024-01").await {
+            Err(StockError::AlphaVantageError(message)) => {
+                assert!(message.contains(crate::config::ALPHA_VANTAGE_PREMIUM_ENV));
+            }
+            other => panic!("expected a premium hint, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_daily_series_parsing() {
+        let api = MockApi::start().await;
+        let body = serde_json::json!({
+            "Time Series (Daily)": {
+                "2024-05-01": {
+                    "1. open": "169.58", "2. high": "172.71", "3. low": "169.11",
+                    "4. close": "169.30", "5. volume": "50383147"
+                }
+            }
+        });
+        api.mount_json("/query", 200, &body.to_string()).await;
+
+        let bars = api.alpha_vantage(false).get_daily("AAPL").await.unwrap();
+        assert_eq!(bars.len(), 1);
+        assert_eq!(bars[0].timestamp, "2024-05-01");
+        assert!((bars[0].close - 169.30).abs() < f64::EPSILON);
+        assert_eq!(bars[0].volume, 50_383_147);
+    }
 }
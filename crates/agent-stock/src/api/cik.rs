@@ -0,0 +1,481 @@
+//! Ticker to CIK mapping shared by the SEC features
+//!
+//! SEC EDGAR identifies companies by their Central Index Key (CIK), and the
+//! only way from a ticker to a CIK is the `company_tickers.json` file listing
+//! every registrant (about a megabyte). [`CikResolver`] keeps that map in
+//! memory, caches it on disk and refreshes it once a day, so a lookup only
+//! downloads it when the cached copy is stale.
+//!
+//! When SEC cannot be reached and nothing is cached, lookups fall back to a
+//! gzipped snapshot bundled with the crate. It lists only 60 large US
+//! companies, not the roughly 10,000 of the live file, so a ticker missing
+//! from it is reported as unavailable rather than unknown. Regenerate it from
+//! the live file with
+//!
+//! ```text
+//! curl -A "agent-stock (you@example.com)" https://www.sec.gov/files/company_tickers.json \
+//!     | gzip -9 > crates/agent-stock/data/sec_company_tickers.json.gz
+//! ```
+
+use flate2::read::GzDecoder;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::{Mutex, RwLock};
+
+use super::sec_edgar::CompanyInfo;
+use crate::error::{Result, StockError};
+
+/// Environment variable naming the on-disk copy of the ticker map
+pub const SEC_TICKERS_FILE_ENV: &str = "STOCK_SEC_TICKERS_FILE";
+
+/// How long a downloaded ticker map is used before it is fetched again
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Wait before retrying SEC after a failed download
+const RETRY_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Ticker map of 60 large US companies, bundled as the last resort
+const SNAPSHOT: &[u8] = include_bytes!("../../data/sec_company_tickers.json.gz");
+
+/// Companies listed in an SEC ticker map, by ticker
+#[derive(Debug, Clone, Default)]
+pub struct CikMap {
+    companies: HashMap<String, CompanyInfo>,
+}
+
+impl CikMap {
+    /// Parse the `company_tickers.json` format: an object of
+    /// `{"cik_str", "ticker", "title"}` entries
+    pub fn from_json(json: &str) -> Result<Self> {
+        let data: Value = serde_json::from_str(json)
+            .map_err(|e| StockError::ApiError(format!("Failed to parse SEC ticker map: {e}")))?;
+        let entries = data.as_object().ok_or_else(|| {
+            StockError::ApiError("SEC ticker map is not an object of companies".to_string())
+        })?;
+
+        let mut companies = HashMap::with_capacity(entries.len());
+        for entry in entries.values() {
+            let Some(ticker) = entry.get("ticker").and_then(Value::as_str) else {
+                continue;
+            };
+            // cik_str is a number in the published file
+            let cik = match entry.get("cik_str") {
+                Some(Value::Number(cik)) => cik.to_string(),
+                Some(Value::String(cik)) => cik.clone(),
+                _ => continue,
+            };
+            let ticker = normalize_ticker(ticker);
+            // The first listing of a ticker is the primary one
+            companies
+                .entry(ticker.clone())
+                .or_insert_with(|| CompanyInfo {
+                    cik,
+                    name: entry
+                        .get("title")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string(),
+                    ticker,
+                    exchange: None,
+                });
+        }
+        Ok(Self { companies })
+    }
+
+    /// The snapshot bundled with the crate
+    pub fn bundled() -> Result<Self> {
+        let mut json = String::new();
+        GzDecoder::new(SNAPSHOT)
+            .read_to_string(&mut json)
+            .map_err(|e| StockError::ApiError(format!("Bundled SEC ticker map is corrupt: {e}")))?;
+        Self::from_json(&json)
+    }
+
+    /// Company listed under `ticker` (case-insensitive, `BRK.B` or `BRK-B`)
+    pub fn get(&self, ticker: &str) -> Option<&CompanyInfo> {
+        self.companies.get(&normalize_ticker(ticker))
+    }
+
+    /// Number of tickers in the map
+    pub fn len(&self) -> usize {
+        self.companies.len()
+    }
+
+    /// Whether the map lists no tickers
+    pub fn is_empty(&self) -> bool {
+        self.companies.is_empty()
+    }
+}
+
+/// SEC writes share classes with a dash (`BRK-B`)
+fn normalize_ticker(ticker: &str) -> String {
+    ticker.trim().to_uppercase().replace('.', "-")
+}
+
+/// Where the map in use came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CikMapSource {
+    /// Downloaded from SEC by this process
+    Sec,
+    /// Read from the on-disk cache
+    Disk,
+    /// The snapshot bundled with the crate
+    Bundled,
+}
+
+struct LoadedMap {
+    map: Arc<CikMap>,
+    source: CikMapSource,
+    /// When to try SEC for a newer map
+    refresh_at: Instant,
+}
+
+/// Shared ticker to CIK lookup with disk cache and offline fallback
+///
+/// The map is downloaded through the `fetch` callback given to
+/// [`resolve`](Self::resolve), so requests go through the caller's rate
+/// limiter and User-Agent. A stale map stays in use while SEC is unreachable,
+/// and SEC is retried every few minutes.
+pub struct CikResolver {
+    cache_path: Option<PathBuf>,
+    refresh_interval: Duration,
+    use_snapshot: bool,
+    loaded: RwLock<Option<LoadedMap>>,
+    refreshing: Mutex<()>,
+}
+
+impl CikResolver {
+    /// Resolver keeping the map in memory only, falling back to the snapshot
+    pub fn new() -> Self {
+        Self {
+            cache_path: None,
+            refresh_interval: DEFAULT_REFRESH_INTERVAL,
+            use_snapshot: true,
+            loaded: RwLock::new(None),
+            refreshing: Mutex::new(()),
+        }
+    }
+
+    /// Cache the downloaded map in `path`, reused across restarts
+    pub fn with_cache_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.cache_path = Some(path.into());
+        self
+    }
+
+    /// Download the map again after `interval` (default one day)
+    pub fn with_refresh_interval(mut self, interval: Duration) -> Self {
+        self.refresh_interval = interval;
+        self
+    }
+
+    /// Fail instead of using the bundled snapshot when SEC is unreachable
+    pub fn without_snapshot(mut self) -> Self {
+        self.use_snapshot = false;
+        self
+    }
+
+    /// Process-wide resolver used by every [`SecEdgarClient`](super::SecEdgarClient)
+    /// talking to SEC itself
+    ///
+    /// The map is cached in the file named by `STOCK_SEC_TICKERS_FILE`, or in
+    /// the system temp directory.
+    pub fn shared() -> Arc<Self> {
+        static SHARED: OnceLock<Arc<CikResolver>> = OnceLock::new();
+        Arc::clone(SHARED.get_or_init(|| {
+            let path = std::env::var(SEC_TICKERS_FILE_ENV).map_or_else(
+                |_| {
+                    std::env::temp_dir()
+                        .join("agent-stock")
+                        .join("sec_company_tickers.json")
+                },
+                PathBuf::from,
+            );
+            Arc::new(Self::new().with_cache_file(path))
+        }))
+    }
+
+    /// Company listed under `ticker`, downloading the map with `fetch` when
+    /// the cached one is missing or stale
+    ///
+    /// # Errors
+    ///
+    /// [`StockError::InvalidSymbol`] when the ticker is not listed,
+    /// [`StockError::DataUnavailable`] when it is missing from the bundled
+    /// snapshot in use while SEC is unreachable, or the download error when no
+    /// map is available at all.
+    pub async fn resolve<F, Fut>(&self, ticker: &str, fetch: F) -> Result<CompanyInfo>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<String>>,
+    {
+        let map = match self.current().await {
+            Some(map) => map,
+            None => self.refresh(fetch).await?,
+        };
+        if let Some(company) = map.get(ticker) {
+            return Ok(company.clone());
+        }
+        // The snapshot is a small subset; a miss says nothing about the ticker
+        if self.source().await == Some(CikMapSource::Bundled) {
+            return Err(StockError::DataUnavailable {
+                symbol: ticker.to_string(),
+                reason: format!(
+                    "SEC is unreachable and the bundled ticker map lists only {} large US \
+                     companies; try again once SEC is reachable",
+                    map.len()
+                ),
+            });
+        }
+        Err(StockError::InvalidSymbol(ticker.to_string()))
+    }
+
+    /// Where the map in use came from, if one is loaded
+    pub async fn source(&self) -> Option<CikMapSource> {
+        self.loaded
+            .read()
+            .await
+            .as_ref()
+            .map(|loaded| loaded.source)
+    }
+
+    /// The loaded map, unless it is due for a refresh
+    async fn current(&self) -> Option<Arc<CikMap>> {
+        self.loaded
+            .read()
+            .await
+            .as_ref()
+            .filter(|loaded| Instant::now() < loaded.refresh_at)
+            .map(|loaded| Arc::clone(&loaded.map))
+    }
+
+    async fn refresh<F, Fut>(&self, fetch: F) -> Result<Arc<CikMap>>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<String>>,
+    {
+        // One download at a time; waiters use the map it loaded
+        let _refreshing = self.refreshing.lock().await;
+        if let Some(map) = self.current().await {
+            return Ok(map);
+        }
+
+        let stale = self
+            .loaded
+            .read()
+            .await
+            .as_ref()
+            .map(|loaded| Arc::clone(&loaded.map));
+        if stale.is_none()
+            && let Some((map, age)) = self.read_cache_file()
+            && age < self.refresh_interval
+        {
+            return Ok(self
+                .set(
+                    map,
+                    CikMapSource::Disk,
+                    Instant::now() + self.refresh_interval.saturating_sub(age),
+                )
+                .await);
+        }
+
+        let error = match fetch()
+            .await
+            .and_then(|json| Ok((CikMap::from_json(&json)?, json)))
+        {
+            Ok((map, json)) => {
+                self.write_cache_file(&json);
+                return Ok(self
+                    .set(
+                        map,
+                        CikMapSource::Sec,
+                        Instant::now() + self.refresh_interval,
+                    )
+                    .await);
+            }
+            Err(e) => e,
+        };
+
+        // Keep serving what we have until SEC answers again
+        let retry_at = Instant::now() + RETRY_INTERVAL;
+        if let Some(stale) = stale {
+            tracing::warn!(
+                "SEC ticker map refresh failed, keeping the cached map: {}",
+                error
+            );
+            if let Some(loaded) = self.loaded.write().await.as_mut() {
+                loaded.refresh_at = retry_at;
+            }
+            return Ok(stale);
+        }
+        let (map, source) = if let Some((map, _)) = self.read_cache_file() {
+            (map, CikMapSource::Disk)
+        } else if self.use_snapshot {
+            (CikMap::bundled()?, CikMapSource::Bundled)
+        } else {
+            return Err(error);
+        };
+        tracing::warn!(
+            "SEC ticker map download failed, using the {:?} copy: {}",
+            source,
+            error
+        );
+        Ok(self.set(map, source, retry_at).await)
+    }
+
+    async fn set(&self, map: CikMap, source: CikMapSource, refresh_at: Instant) -> Arc<CikMap> {
+        let map = Arc::new(map);
+        *self.loaded.write().await = Some(LoadedMap {
+            map: Arc::clone(&map),
+            source,
+            refresh_at,
+        });
+        map
+    }
+
+    /// The cached map and its age, if the cache file is readable
+    fn read_cache_file(&self) -> Option<(CikMap, Duration)> {
+        let path = self.cache_path.as_deref()?;
+        let json = std::fs::read_to_string(path).ok()?;
+        let map = CikMap::from_json(&json)
+            .inspect_err(|e| tracing::warn!("Ignoring SEC ticker cache {}: {}", path.display(), e))
+            .ok()?;
+        let age = std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .unwrap_or(Duration::MAX);
+        Some((map, age))
+    }
+
+    fn write_cache_file(&self, json: &str) {
+        let Some(path) = self.cache_path.as_deref() else {
+            return;
+        };
+        if let Err(e) = write_atomically(path, json) {
+            tracing::warn!(
+                "Failed to cache SEC ticker map in {}: {}",
+                path.display(),
+                e
+            );
+        }
+    }
+}
+
+impl Default for CikResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Write through a temporary file so readers never see half a map
+fn write_atomically(path: &Path, contents: &str) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let temp = path.with_extension(format!("tmp-{}", uuid::Uuid::new_v4()));
+    std::fs::write(&temp, contents)?;
+    std::fs::rename(&temp, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&temp);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::testing::fixtures;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_cik_map() {
+        let map = CikMap::from_json(fixtures::SEC_COMPANY_TICKERS).unwrap();
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get("aapl").unwrap().cik, "320193");
+        assert_eq!(map.get("MSFT").unwrap().name, "MICROSOFT CORP");
+        assert!(map.get("NOPE").is_none());
+        assert!(CikMap::from_json("[]").is_err());
+
+        let bundled = CikMap::bundled().unwrap();
+        assert!(bundled.len() >= 50);
+        assert_eq!(bundled.get("NVDA").unwrap().cik, "1045810");
+        assert_eq!(bundled.get("BRK.B").unwrap().ticker, "BRK-B");
+    }
+
+    #[tokio::test]
+    async fn test_resolver_caching() {
+        let path = std::env::temp_dir().join(format!("cik-{}.json", uuid::Uuid::new_v4()));
+        let downloads = AtomicUsize::new(0);
+        let download = || async {
+            downloads.fetch_add(1, Ordering::SeqCst);
+            Ok(fixtures::SEC_COMPANY_TICKERS.to_string())
+        };
+
+        // Downloaded once, then served from memory
+        let resolver = CikResolver::new().with_cache_file(&path);
+        assert_eq!(
+            resolver.resolve("AAPL", download).await.unwrap().cik,
+            "320193"
+        );
+        assert_eq!(
+            resolver.resolve("nvda", download).await.unwrap().cik,
+            "1045810"
+        );
+        let err = resolver.resolve("NOPE", download).await.unwrap_err();
+        assert!(matches!(err, StockError::InvalidSymbol(_)), "{err}");
+        assert_eq!(downloads.load(Ordering::SeqCst), 1);
+        assert_eq!(resolver.source().await, Some(CikMapSource::Sec));
+
+        // A new process reads the fresh disk copy
+        let restarted = CikResolver::new().with_cache_file(&path);
+        assert_eq!(
+            restarted.resolve("MSFT", download).await.unwrap().cik,
+            "789019"
+        );
+        assert_eq!(downloads.load(Ordering::SeqCst), 1);
+        assert_eq!(restarted.source().await, Some(CikMapSource::Disk));
+
+        // Once stale it is downloaded again
+        let expired = CikResolver::new()
+            .with_cache_file(&path)
+            .with_refresh_interval(Duration::ZERO);
+        expired.resolve("MSFT", download).await.unwrap();
+        assert_eq!(downloads.load(Ordering::SeqCst), 2);
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_resolver_offline() {
+        let offline = || async { Err(StockError::rate_limited("SEC")) };
+
+        // Nothing cached: the bundled snapshot, or the error without one
+        let resolver = CikResolver::new();
+        assert_eq!(
+            resolver.resolve("TSLA", offline).await.unwrap().cik,
+            "1318605"
+        );
+        assert_eq!(resolver.source().await, Some(CikMapSource::Bundled));
+        // Tickers outside the snapshot are unavailable, not unknown
+        let err = resolver.resolve("ZZZZ", offline).await.unwrap_err();
+        assert!(matches!(err, StockError::DataUnavailable { .. }), "{err}");
+        assert!(err.to_string().contains("bundled ticker map"), "{err}");
+        let err = CikResolver::new()
+            .without_snapshot()
+            .resolve("TSLA", offline)
+            .await;
+        assert!(matches!(err, Err(StockError::RateLimitExceeded { .. })));
+
+        // A stale map stays in use
+        let resolver = CikResolver::new().with_refresh_interval(Duration::ZERO);
+        let download = || async { Ok(fixtures::SEC_COMPANY_TICKERS.to_string()) };
+        resolver.resolve("AAPL", download).await.unwrap();
+        assert_eq!(
+            resolver.resolve("AAPL", offline).await.unwrap().cik,
+            "320193"
+        );
+        assert_eq!(resolver.source().await, Some(CikMapSource::Sec));
+    }
+}
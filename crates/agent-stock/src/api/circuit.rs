@@ -0,0 +1,441 @@
+//! Circuit breakers for the data providers
+//!
+//! When a provider starts failing, every analysis would otherwise keep
+//! sending it requests and waiting for each one to time out. Each endpoint
+//! (provider and base URL) has one [`CircuitBreaker`], shared by every client
+//! pointed at it:
+//!
+//! - **Closed**: requests go through; consecutive failures are counted.
+//! - **Open**: after [`BreakerConfig::failure_threshold`] failures in a row,
+//!   requests fail at once with [`StockError::ProviderUnavailable`] for
+//!   [`BreakerConfig::cooldown`].
+//! - **Half-open**: after the cooldown one probe request goes through. Its
+//!   success closes the circuit; its failure opens it for another cooldown.
+//!
+//! Transport errors, timeouts, 5xx responses and 429s count as failures.
+//! Other 4xx responses (unknown symbol, rejected key) mean the provider is
+//! up and count as successes.
+//!
+//! Each breaker also tracks the endpoint's latency, which sets adaptive
+//! request timeouts (see [`super::latency`]).
+
+use reqwest::{RequestBuilder, Response};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::time::{Duration, Instant};
+
+use super::latency::LatencyTracker;
+use crate::error::{Result, StockError};
+
+/// Environment variable with the consecutive failures that open a circuit
+pub const BREAKER_THRESHOLD_ENV: &str = "STOCK_BREAKER_THRESHOLD";
+
+/// Environment variable with the seconds a circuit stays open before a probe
+pub const BREAKER_COOLDOWN_ENV: &str = "STOCK_BREAKER_COOLDOWN_SECS";
+
+/// When circuits open and how long they stay open
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BreakerConfig {
+    /// Consecutive failures that open the circuit
+    pub failure_threshold: u32,
+    /// How long the circuit stays open before a probe is let through
+    pub cooldown: Duration,
+}
+
+impl Default for BreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+impl BreakerConfig {
+    /// Defaults overridden by [`BREAKER_THRESHOLD_ENV`] and
+    /// [`BREAKER_COOLDOWN_ENV`]
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let var = |name: &str| std::env::var(name).ok()?.trim().parse::<u64>().ok();
+        Self {
+            failure_threshold: var(BREAKER_THRESHOLD_ENV)
+                .and_then(|n| u32::try_from(n).ok())
+                .filter(|n| *n > 0)
+                .unwrap_or(defaults.failure_threshold),
+            cooldown: var(BREAKER_COOLDOWN_ENV).map_or(defaults.cooldown, Duration::from_secs),
+        }
+    }
+}
+
+/// State of a circuit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+impl fmt::Display for CircuitState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CircuitState::Closed => "closed",
+            CircuitState::Open => "open",
+            CircuitState::HalfOpen => "half-open",
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: CircuitState,
+    consecutive_failures: u32,
+    /// When the circuit last opened, or when the probe was let through
+    since: Instant,
+}
+
+/// Failure tracking for one provider endpoint
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    provider: String,
+    endpoint: String,
+    config: BreakerConfig,
+    inner: Mutex<Inner>,
+    latency: LatencyTracker,
+}
+
+/// Snapshot of a breaker, for health checks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BreakerStatus {
+    pub provider: String,
+    pub endpoint: String,
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    /// Time until the next probe, while open
+    pub retry_in: Option<Duration>,
+}
+
+impl fmt::Display for BreakerStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.provider, self.state)?;
+        match (self.state, self.retry_in) {
+            (CircuitState::Open, Some(retry_in)) => write!(
+                f,
+                " after {} failures, next probe in {}s",
+                self.consecutive_failures,
+                ceil_secs(retry_in)
+            ),
+            (CircuitState::HalfOpen, _) => write!(f, ", probing"),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Whole seconds in `duration`, rounded up
+fn ceil_secs(duration: Duration) -> u64 {
+    duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
+
+/// Breakers by endpoint
+fn registry() -> &'static Mutex<BTreeMap<String, Arc<CircuitBreaker>>> {
+    static BREAKERS: OnceLock<Mutex<BTreeMap<String, Arc<CircuitBreaker>>>> = OnceLock::new();
+    BREAKERS.get_or_init(Mutex::default)
+}
+
+/// The breaker shared by every client of `provider` at `base_url`
+pub fn breaker(provider: &str, base_url: &str) -> Arc<CircuitBreaker> {
+    let endpoint = endpoint(base_url);
+    let mut breakers = registry().lock().unwrap_or_else(PoisonError::into_inner);
+    let breaker = breakers
+        .entry(format!("{provider}@{endpoint}"))
+        .or_insert_with(|| {
+            Arc::new(CircuitBreaker::new(
+                provider,
+                endpoint,
+                BreakerConfig::from_env(),
+            ))
+        });
+    Arc::clone(breaker)
+}
+
+/// Status of every breaker created so far
+pub fn statuses() -> Vec<BreakerStatus> {
+    let breakers: Vec<Arc<CircuitBreaker>> = registry()
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .values()
+        .cloned()
+        .collect();
+    breakers.iter().map(|breaker| breaker.status()).collect()
+}
+
+/// Forget the breakers of every provider at `base_url`
+#[cfg(any(test, feature = "test-util"))]
+pub(crate) fn reset(base_url: &str) {
+    let endpoint = endpoint(base_url);
+    registry()
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .retain(|_, breaker| breaker.endpoint != endpoint);
+}
+
+/// Scheme and host of `url`, so every path of a provider shares a breaker
+fn endpoint(url: &str) -> String {
+    match url.split_once("://") {
+        Some((scheme, rest)) => {
+            let host = rest.split('/').next().unwrap_or(rest);
+            format!("{scheme}://{host}")
+        }
+        None => url.split('/').next().unwrap_or(url).to_string(),
+    }
+}
+
+impl CircuitBreaker {
+    /// A closed breaker
+    pub fn new(
+        provider: impl Into<String>,
+        endpoint: impl Into<String>,
+        config: BreakerConfig,
+    ) -> Self {
+        Self {
+            provider: provider.into(),
+            endpoint: endpoint.into(),
+            config,
+            inner: Mutex::new(Inner {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                since: Instant::now(),
+            }),
+            latency: LatencyTracker::new(),
+        }
+    }
+
+    /// Provider name, e.g. "Yahoo Finance"
+    pub fn provider(&self) -> &str {
+        &self.provider
+    }
+
+    /// Latencies of recent successful requests to this endpoint
+    pub fn latency(&self) -> &LatencyTracker {
+        &self.latency
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Whether a request may be sent now
+    ///
+    /// Once the cooldown has passed, the first caller becomes the probe and
+    /// the circuit goes half-open; others keep failing fast until the probe
+    /// reports back. A probe that never reports (e.g. its request was
+    /// cancelled) is replaced after another cooldown.
+    pub fn check(&self) -> Result<()> {
+        let mut inner = self.lock();
+        match inner.state {
+            CircuitState::Closed => Ok(()),
+            CircuitState::Open | CircuitState::HalfOpen
+                if inner.since.elapsed() >= self.config.cooldown =>
+            {
+                inner.state = CircuitState::HalfOpen;
+                inner.since = Instant::now();
+                tracing::info!("Probing {} after its circuit opened", self.provider);
+                Ok(())
+            }
+            CircuitState::Open | CircuitState::HalfOpen => Err(StockError::ProviderUnavailable {
+                provider: self.provider.clone(),
+                retry_in_secs: ceil_secs(
+                    self.config.cooldown.saturating_sub(inner.since.elapsed()),
+                )
+                .max(1),
+            }),
+        }
+    }
+
+    /// Record a request that reached the provider and got a usable answer
+    pub fn record_success(&self) {
+        let mut inner = self.lock();
+        if inner.state != CircuitState::Closed {
+            tracing::info!("{} recovered, closing its circuit", self.provider);
+        }
+        inner.state = CircuitState::Closed;
+        inner.consecutive_failures = 0;
+    }
+
+    /// Record a failed request, opening the circuit at the threshold or
+    /// when a probe fails
+    pub fn record_failure(&self) {
+        let mut inner = self.lock();
+        inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+        let trips = match inner.state {
+            CircuitState::Closed => inner.consecutive_failures >= self.config.failure_threshold,
+            CircuitState::HalfOpen => true,
+            // A request sent before the circuit opened
+            CircuitState::Open => false,
+        };
+        if trips {
+            tracing::warn!(
+                "{} failed {} times in a row, pausing requests for {}s",
+                self.provider,
+                inner.consecutive_failures,
+                self.config.cooldown.as_secs()
+            );
+            inner.state = CircuitState::Open;
+            inner.since = Instant::now();
+        }
+    }
+
+    /// Send `request` unless the circuit is open, and record the outcome
+    ///
+    /// The request times out after the endpoint's adaptive timeout once
+    /// enough latencies are known. The response is returned whatever its
+    /// status, for the caller to interpret as before.
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        self.check()?;
+        let request = match self.latency.timeout() {
+            Some(timeout) => request.timeout(timeout),
+            None => request,
+        };
+        let started = Instant::now();
+        match request.send().await {
+            Ok(response) => {
+                let status = response.status();
+                if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                    self.record_failure();
+                } else {
+                    self.latency.record(started.elapsed());
+                    self.record_success();
+                }
+                Ok(response)
+            }
+            Err(e) => {
+                self.record_failure();
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Current state
+    pub fn status(&self) -> BreakerStatus {
+        let inner = self.lock();
+        BreakerStatus {
+            provider: self.provider.clone(),
+            endpoint: self.endpoint.clone(),
+            state: inner.state,
+            consecutive_failures: inner.consecutive_failures,
+            retry_in: (inner.state == CircuitState::Open)
+                .then(|| self.config.cooldown.saturating_sub(inner.since.elapsed())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::testing::MockApi;
+
+    fn config(cooldown: Duration) -> BreakerConfig {
+        BreakerConfig {
+            failure_threshold: 3,
+            cooldown,
+        }
+    }
+
+    #[test]
+    fn test_opens_after_threshold() {
+        let breaker = CircuitBreaker::new("Test", "http://test", config(Duration::from_secs(60)));
+        breaker.record_failure();
+        breaker.record_failure();
+        // A success resets the count
+        breaker.record_success();
+        breaker.record_failure();
+        breaker.record_failure();
+        assert!(breaker.check().is_ok());
+
+        breaker.record_failure();
+        let status = breaker.status();
+        assert_eq!(status.state, CircuitState::Open);
+        assert_eq!(status.consecutive_failures, 3);
+        assert!(status.to_string().starts_with("Test open after 3 failures"));
+        assert!(matches!(
+            breaker.check(),
+            Err(StockError::ProviderUnavailable {
+                retry_in_secs: 60,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_half_open_probe() {
+        let breaker = CircuitBreaker::new("Test", "http://test", config(Duration::ZERO));
+        for _ in 0..3 {
+            breaker.record_failure();
+        }
+        assert_eq!(breaker.status().state, CircuitState::Open);
+
+        // The cooldown has passed: one probe goes through
+        assert!(breaker.check().is_ok());
+        assert_eq!(breaker.status().state, CircuitState::HalfOpen);
+
+        // A failed probe opens the circuit again at once
+        breaker.record_failure();
+        assert_eq!(breaker.status().state, CircuitState::Open);
+
+        assert!(breaker.check().is_ok());
+        breaker.record_success();
+        let status = breaker.status();
+        assert_eq!(status.state, CircuitState::Closed);
+        assert_eq!(status.consecutive_failures, 0);
+    }
+
+    #[tokio::test]
+    async fn test_send_counts_server_errors() {
+        let api = MockApi::start().await;
+        api.mount_json("/down", 503, "{}").await;
+        api.mount_json("/missing", 404, "{}").await;
+        let breaker = CircuitBreaker::new("Test", api.uri(), config(Duration::from_secs(60)));
+        let client = reqwest::Client::new();
+
+        // A 404 means the provider is up
+        let response = breaker
+            .send(client.get(format!("{}/missing", api.uri())))
+            .await;
+        assert_eq!(response.unwrap().status(), 404);
+
+        for _ in 0..3 {
+            let response = breaker
+                .send(client.get(format!("{}/down", api.uri())))
+                .await;
+            assert_eq!(response.unwrap().status(), 503);
+        }
+        assert_eq!(breaker.status().state, CircuitState::Open);
+        // Only the answered request counts towards latency
+        assert_eq!(breaker.latency().stats().samples, 1);
+
+        // Open: no request is sent
+        let response = breaker
+            .send(client.get(format!("{}/missing", api.uri())))
+            .await;
+        assert!(matches!(
+            response,
+            Err(StockError::ProviderUnavailable { .. })
+        ));
+        assert_eq!(api.request_count().await, 4);
+    }
+
+    #[test]
+    fn test_shared_by_endpoint() {
+        let a = breaker("Shared", "http://127.0.0.1:9/v8/finance");
+        let b = breaker("Shared", "http://127.0.0.1:9");
+        assert!(Arc::ptr_eq(&a, &b));
+        assert!(!Arc::ptr_eq(&a, &breaker("Shared", "http://127.0.0.1:10")));
+        assert!(
+            statuses()
+                .iter()
+                .any(|s| s.endpoint == "http://127.0.0.1:9")
+        );
+    }
+}
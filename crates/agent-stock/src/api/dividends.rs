@@ -0,0 +1,111 @@
+//! Dividend history
+//!
+//! Yahoo's chart API returns dividend events alongside the bars when asked
+//! for them (`events=div`). Each event is keyed by its ex-dividend timestamp
+//! and carries the cash amount per share, adjusted for later splits.
+
+use crate::error::{Result, StockError};
+use chrono::{DateTime, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::yahoo_schema;
+
+/// One cash dividend
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DividendEvent {
+    /// Ex-dividend date
+    pub ex_date: NaiveDate,
+    /// Cash amount per share
+    pub amount: f64,
+}
+
+/// Dividends paid by a stock, oldest first
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DividendHistory {
+    /// Stock symbol
+    pub symbol: String,
+    /// Currency of the price and the amounts
+    pub currency: Option<String>,
+    /// Latest price
+    pub price: Option<f64>,
+    /// Dividends, oldest first
+    pub events: Vec<DividendEvent>,
+}
+
+impl DividendHistory {
+    /// Parse a chart response requested with `events=div`
+    ///
+    /// A stock that never paid a dividend has no `events` and parses to an
+    /// empty history.
+    pub fn from_yahoo(symbol: &str, body: &Value) -> Result<Self> {
+        if let Some(reason) = yahoo_schema::chart_error(body) {
+            return Err(StockError::YahooFinanceError(reason));
+        }
+        let result = &body["chart"]["result"][0];
+        if !result.is_object() {
+            return Err(StockError::YahooFinanceError(format!(
+                "No dividend data for {symbol}"
+            )));
+        }
+
+        let mut events: Vec<DividendEvent> = result["events"]["dividends"]
+            .as_object()
+            .map(|dividends| {
+                dividends
+                    .values()
+                    .filter_map(|event| {
+                        let amount = event["amount"].as_f64().filter(|a| *a > 0.0)?;
+                        let ex_date = DateTime::from_timestamp(event["date"].as_i64()?, 0)?;
+                        Some(DividendEvent {
+                            ex_date: ex_date.date_naive(),
+                            amount,
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        events.sort_by_key(|event| event.ex_date);
+
+        let meta = &result["meta"];
+        Ok(Self {
+            symbol: symbol.to_string(),
+            currency: meta["currency"].as_str().map(ToString::to_string),
+            price: meta["regularMarketPrice"].as_f64(),
+            events,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::testing::fixtures;
+
+    #[test]
+    fn test_from_yahoo() {
+        let body: Value = serde_json::from_str(fixtures::YAHOO_CHART_KO_DIVIDENDS).unwrap();
+        let history = DividendHistory::from_yahoo("KO", &body).unwrap();
+
+        assert_eq!(history.currency.as_deref(), Some("USD"));
+        assert_eq!(history.price, Some(62.3));
+        assert_eq!(history.events.len(), 16);
+        assert_eq!(
+            history.events[0].ex_date,
+            NaiveDate::from_ymd_opt(2021, 3, 14).unwrap()
+        );
+        assert_eq!(history.events[15].amount, 0.485);
+
+        // Bars without events: no dividends
+        let body: Value = serde_json::from_str(fixtures::YAHOO_CHART_AAPL).unwrap();
+        assert!(
+            DividendHistory::from_yahoo("AAPL", &body)
+                .unwrap()
+                .events
+                .is_empty()
+        );
+
+        let body: Value = serde_json::from_str(fixtures::YAHOO_CHART_NOT_FOUND).unwrap();
+        assert!(DividendHistory::from_yahoo("ZZZZ", &body).is_err());
+    }
+}
@@ -0,0 +1,412 @@
+//! Resumable downloads of large files
+//!
+//! Filing documents (a 10-K is often tens of MB of HTML) are too large to
+//! hold in a response buffer and refetch whenever a connection drops.
+//! [`Downloader`] streams them to disk instead:
+//!
+//! - the body goes to `{name}.part` as it arrives, and a failed transfer is
+//!   resumed with a `Range` request from where it stopped, in the same call
+//!   or the next one;
+//! - transfers larger than [`Downloader::max_bytes`] are abandoned;
+//! - an expected SHA-256 is verified before the file takes its final name;
+//! - a completed file is reused, so only name files by immutable content
+//!   (e.g. an accession number).
+
+use futures::StreamExt;
+use reqwest::{RequestBuilder, StatusCode, header};
+use sha2::{Digest, Sha256};
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use super::CircuitBreaker;
+use super::http_error;
+use crate::error::{Result, StockError};
+
+/// Environment variable naming the directory downloads are kept in
+pub const DOWNLOAD_DIR_ENV: &str = "STOCK_DOWNLOAD_DIR";
+
+/// Largest file downloaded by default
+const DEFAULT_MAX_BYTES: u64 = 200 * 1024 * 1024;
+
+/// Transfers started per download before giving up
+const DEFAULT_ATTEMPTS: u32 = 3;
+
+/// Wait before resuming a failed transfer, multiplied by the attempt number
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Streams large files to disk, resuming interrupted transfers
+#[derive(Debug, Clone)]
+pub struct Downloader {
+    dir: PathBuf,
+    max_bytes: u64,
+    attempts: u32,
+}
+
+impl Downloader {
+    /// Keep downloads under `dir`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            max_bytes: DEFAULT_MAX_BYTES,
+            attempts: DEFAULT_ATTEMPTS,
+        }
+    }
+
+    /// Keep downloads in [`DOWNLOAD_DIR_ENV`], by default a directory under
+    /// the system temp directory
+    pub fn from_env() -> Self {
+        let dir = std::env::var(DOWNLOAD_DIR_ENV).map_or_else(
+            |_| std::env::temp_dir().join("agent-stock").join("downloads"),
+            PathBuf::from,
+        );
+        Self::new(dir)
+    }
+
+    /// Abandon files larger than `max_bytes`
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Start at most `attempts` transfers per download (at least one)
+    pub fn with_attempts(mut self, attempts: u32) -> Self {
+        self.attempts = attempts.max(1);
+        self
+    }
+
+    /// Directory downloads are kept in
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Largest file downloaded
+    pub fn max_bytes(&self) -> u64 {
+        self.max_bytes
+    }
+
+    /// Download `request` through `breaker` to `name` (relative to
+    /// [`dir`](Self::dir)) and return the file's path
+    ///
+    /// An existing file is returned without a request. With `sha256` (hex),
+    /// the file must match it; a mismatch discards the download.
+    pub async fn fetch(
+        &self,
+        breaker: &CircuitBreaker,
+        request: RequestBuilder,
+        name: impl AsRef<Path>,
+        sha256: Option<&str>,
+    ) -> Result<PathBuf> {
+        let path = self.path(name.as_ref())?;
+        if tokio::fs::try_exists(&path).await.unwrap_or(false) {
+            match sha256 {
+                Some(expected) if !sha256_matches(&path, expected).await? => {
+                    tracing::warn!(
+                        "{} does not match its checksum, downloading again",
+                        path.display()
+                    );
+                }
+                _ => return Ok(path),
+            }
+        }
+
+        let partial = partial_path(&path);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| io_error("create", parent, &e))?;
+        }
+
+        let mut attempt = 1;
+        loop {
+            let request = request.try_clone().ok_or_else(|| {
+                StockError::Other("Downloads need a request without a streaming body".to_string())
+            })?;
+            match self.transfer(breaker, request, &partial).await {
+                Ok(Transfer::Complete) => break,
+                // No range is sent for the restart, so this cannot repeat
+                Ok(Transfer::Restart) => {}
+                Err(e) if e.is_retryable() && attempt < self.attempts => {
+                    tracing::warn!(
+                        "Download of {} interrupted ({e}), resuming (attempt {}/{})",
+                        path.display(),
+                        attempt + 1,
+                        self.attempts
+                    );
+                    tokio::time::sleep(RETRY_DELAY * attempt).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        if let Some(expected) = sha256 {
+            if !sha256_matches(&partial, expected).await? {
+                let _ = tokio::fs::remove_file(&partial).await;
+                return Err(StockError::ApiError(format!(
+                    "Download of {} does not match SHA-256 {expected}",
+                    path.display()
+                )));
+            }
+        }
+        tokio::fs::rename(&partial, &path)
+            .await
+            .map_err(|e| io_error("write", &path, &e))?;
+        Ok(path)
+    }
+
+    /// `name` under the download directory; it may not leave it
+    fn path(&self, name: &Path) -> Result<PathBuf> {
+        let inside = name.components().next().is_some()
+            && name.components().all(|c| matches!(c, Component::Normal(_)));
+        if !inside {
+            return Err(StockError::Other(format!(
+                "Invalid download name: {}",
+                name.display()
+            )));
+        }
+        Ok(self.dir.join(name))
+    }
+
+    /// One transfer into `partial`, resuming from its current length
+    async fn transfer(
+        &self,
+        breaker: &CircuitBreaker,
+        request: RequestBuilder,
+        partial: &Path,
+    ) -> Result<Transfer> {
+        let mut offset = match tokio::fs::metadata(partial).await {
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        };
+        let request = if offset > 0 {
+            request.header(header::RANGE, format!("bytes={offset}-"))
+        } else {
+            request
+        };
+
+        let response = breaker.send(request).await?;
+        let status = response.status();
+        let mut file = match status {
+            StatusCode::PARTIAL_CONTENT if offset > 0 => {
+                tracing::debug!("Resuming {} at byte {offset}", partial.display());
+                tokio::fs::OpenOptions::new()
+                    .append(true)
+                    .open(partial)
+                    .await
+            }
+            // The partial file is already complete (or stale): start over
+            StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => {
+                tracing::debug!("{} could not be resumed, restarting", partial.display());
+                let _ = tokio::fs::remove_file(partial).await;
+                return Ok(Transfer::Restart);
+            }
+            status if status.is_success() => {
+                // The server ignored the range and sent the whole file
+                offset = 0;
+                tokio::fs::File::create(partial).await
+            }
+            status => return Err(http_error(breaker.provider(), status, "")),
+        }
+        .map_err(|e| io_error("write", partial, &e))?;
+
+        if response
+            .content_length()
+            .is_some_and(|length| offset + length > self.max_bytes)
+        {
+            drop(file);
+            let _ = tokio::fs::remove_file(partial).await;
+            return Err(self.too_large(partial));
+        }
+
+        let mut body = response.bytes_stream();
+        while let Some(chunk) = body.next().await {
+            let chunk = chunk?;
+            offset += chunk.len() as u64;
+            if offset > self.max_bytes {
+                drop(file);
+                let _ = tokio::fs::remove_file(partial).await;
+                return Err(self.too_large(partial));
+            }
+            file.write_all(&chunk)
+                .await
+                .map_err(|e| io_error("write", partial, &e))?;
+        }
+        file.flush()
+            .await
+            .map_err(|e| io_error("write", partial, &e))?;
+        Ok(Transfer::Complete)
+    }
+
+    fn too_large(&self, partial: &Path) -> StockError {
+        StockError::ApiError(format!(
+            "Download of {} exceeds the {} byte limit",
+            partial.display(),
+            self.max_bytes
+        ))
+    }
+}
+
+/// Outcome of one transfer that did not fail
+enum Transfer {
+    Complete,
+    /// The partial file was discarded; transfer again from the start
+    Restart,
+}
+
+/// Where the download of `path` is written until it completes
+fn partial_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".part");
+    PathBuf::from(name)
+}
+
+/// Whether the SHA-256 of the file at `path` is `expected` (hex)
+async fn sha256_matches(path: &Path, expected: &str) -> Result<bool> {
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| io_error("read", path, &e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .await
+            .map_err(|e| io_error("read", path, &e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()).eq_ignore_ascii_case(expected.trim()))
+}
+
+fn io_error(action: &str, path: &Path, error: &std::io::Error) -> StockError {
+    StockError::Other(format!("Failed to {action} {}: {error}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::circuit::BreakerConfig;
+    use crate::api::testing::MockApi;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    const BODY: &str = "<html>Annual report on Form 10-K</html>";
+
+    fn downloader() -> Downloader {
+        let dir = std::env::temp_dir().join(format!("downloads-{}", uuid::Uuid::new_v4()));
+        Downloader::new(dir)
+    }
+
+    fn sha256(data: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(data))
+    }
+
+    async fn setup() -> (MockApi, CircuitBreaker, reqwest::RequestBuilder) {
+        let api = MockApi::start().await;
+        let breaker = CircuitBreaker::new("SEC EDGAR", api.uri(), BreakerConfig::default());
+        let request = reqwest::Client::new().get(format!("{}/10k.htm", api.uri()));
+        (api, breaker, request)
+    }
+
+    #[tokio::test]
+    async fn test_download_is_verified_and_reused() {
+        let (api, breaker, request) = setup().await;
+        Mock::given(method("GET"))
+            .and(path("/10k.htm"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(BODY))
+            .expect(1)
+            .mount(api.server())
+            .await;
+        let downloader = downloader();
+        let checksum = sha256(BODY.as_bytes());
+
+        let file = downloader
+            .fetch(
+                &breaker,
+                request.try_clone().unwrap(),
+                "aapl/10k.htm",
+                Some(&checksum),
+            )
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), BODY);
+        assert!(!partial_path(&file).exists());
+
+        // Already on disk: no second request
+        let again = downloader
+            .fetch(&breaker, request, "aapl/10k.htm", Some(&checksum))
+            .await
+            .unwrap();
+        assert_eq!(again, file);
+        std::fs::remove_dir_all(downloader.dir()).ok();
+    }
+
+    #[tokio::test]
+    async fn test_partial_download_is_resumed() {
+        let (api, breaker, request) = setup().await;
+        Mock::given(method("GET"))
+            .and(path("/10k.htm"))
+            .and(header("Range", "bytes=6-"))
+            .respond_with(ResponseTemplate::new(206).set_body_string(&BODY[6..]))
+            .expect(1)
+            .mount(api.server())
+            .await;
+        let downloader = downloader();
+        let target = downloader.dir().join("10k.htm");
+        std::fs::create_dir_all(downloader.dir()).unwrap();
+        std::fs::write(partial_path(&target), &BODY[..6]).unwrap();
+
+        let file = downloader
+            .fetch(&breaker, request, "10k.htm", Some(&sha256(BODY.as_bytes())))
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(file).unwrap(), BODY);
+        std::fs::remove_dir_all(downloader.dir()).ok();
+    }
+
+    #[tokio::test]
+    async fn test_ignored_range_restarts_the_file() {
+        let (api, breaker, request) = setup().await;
+        api.mount_json("/10k.htm", 200, BODY).await;
+        let downloader = downloader();
+        let target = downloader.dir().join("10k.htm");
+        std::fs::create_dir_all(downloader.dir()).unwrap();
+        std::fs::write(partial_path(&target), "stale bytes").unwrap();
+
+        let file = downloader
+            .fetch(&breaker, request, "10k.htm", None)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(file).unwrap(), BODY);
+        std::fs::remove_dir_all(downloader.dir()).ok();
+    }
+
+    #[tokio::test]
+    async fn test_limits_and_checksums_reject_downloads() {
+        let (api, breaker, request) = setup().await;
+        api.mount_json("/10k.htm", 200, BODY).await;
+        let downloader = downloader().with_max_bytes(10);
+
+        let err = downloader
+            .fetch(&breaker, request.try_clone().unwrap(), "10k.htm", None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("10 byte limit"), "{err}");
+
+        let downloader = downloader.with_max_bytes(DEFAULT_MAX_BYTES);
+        let err = downloader
+            .fetch(&breaker, request, "10k.htm", Some(&sha256(b"other")))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("does not match"), "{err}");
+
+        let target = downloader.dir().join("10k.htm");
+        assert!(!target.exists() && !partial_path(&target).exists());
+        assert!(downloader.path(Path::new("../escape")).is_err());
+        assert!(downloader.path(Path::new("/etc/passwd")).is_err());
+        std::fs::remove_dir_all(downloader.dir()).ok();
+    }
+}
@@ -0,0 +1,98 @@
+//! Upcoming earnings dates
+//!
+//! Finnhub's earnings calendar lists scheduled reports with the time of day
+//! ("bmo" before the open, "amc" after the close, "dmh" during market hours)
+//! and the consensus EPS and revenue estimates; past entries also carry the
+//! reported figures.
+
+use crate::error::{Result, StockError};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// One scheduled (or past) earnings report
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EarningsEvent {
+    /// Stock symbol
+    pub symbol: String,
+    /// Report date
+    pub date: NaiveDate,
+    /// "bmo", "amc" or "dmh", when announced
+    pub hour: Option<String>,
+    /// Fiscal quarter being reported
+    pub quarter: Option<u8>,
+    /// Fiscal year being reported
+    pub year: Option<i32>,
+    /// Consensus EPS estimate
+    pub eps_estimate: Option<f64>,
+    /// Reported EPS, once out
+    pub eps_actual: Option<f64>,
+    /// Consensus revenue estimate
+    pub revenue_estimate: Option<f64>,
+    /// Reported revenue, once out
+    pub revenue_actual: Option<f64>,
+}
+
+impl EarningsEvent {
+    /// When in the trading day the report comes out
+    pub fn timing(&self) -> &'static str {
+        match self.hour.as_deref() {
+            Some("bmo") => "before market open",
+            Some("amc") => "after market close",
+            Some("dmh") => "during market hours",
+            _ => "time not announced",
+        }
+    }
+
+    /// Parse a Finnhub `/calendar/earnings` response, ordered by date
+    pub fn from_finnhub(body: &Value) -> Result<Vec<Self>> {
+        let entries = body["earningsCalendar"].as_array().ok_or_else(|| {
+            StockError::ApiError("Finnhub earnings calendar response has no entries".to_string())
+        })?;
+
+        let mut events: Vec<Self> = entries
+            .iter()
+            .filter_map(|entry| {
+                Some(Self {
+                    symbol: entry["symbol"].as_str()?.to_string(),
+                    date: NaiveDate::parse_from_str(entry["date"].as_str()?, "%Y-%m-%d").ok()?,
+                    hour: entry["hour"]
+                        .as_str()
+                        .filter(|hour| !hour.is_empty())
+                        .map(ToString::to_string),
+                    quarter: entry["quarter"].as_u64().and_then(|q| u8::try_from(q).ok()),
+                    year: entry["year"].as_i64().and_then(|y| i32::try_from(y).ok()),
+                    eps_estimate: entry["epsEstimate"].as_f64(),
+                    eps_actual: entry["epsActual"].as_f64(),
+                    revenue_estimate: entry["revenueEstimate"].as_f64(),
+                    revenue_actual: entry["revenueActual"].as_f64(),
+                })
+            })
+            .collect();
+        events.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.symbol.cmp(&b.symbol)));
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::testing::fixtures;
+
+    #[test]
+    fn test_from_finnhub() {
+        let body: Value = serde_json::from_str(fixtures::FINNHUB_EARNINGS_CALENDAR).unwrap();
+        let events = EarningsEvent::from_finnhub(&body).unwrap();
+
+        let symbols: Vec<&str> = events.iter().map(|e| e.symbol.as_str()).collect();
+        assert_eq!(symbols, vec!["GE", "XYZW", "MSFT"]);
+        assert_eq!(events[0].timing(), "before market open");
+        assert_eq!(events[0].eps_actual, Some(0.9541));
+        assert_eq!(events[1].hour, None);
+        assert_eq!(events[1].timing(), "time not announced");
+        assert_eq!(events[2].quarter, Some(3));
+        assert_eq!(events[2].revenue_estimate, Some(60_852_456_784.0));
+
+        assert!(EarningsEvent::from_finnhub(&serde_json::json!({"error": "x"})).is_err());
+    }
+}
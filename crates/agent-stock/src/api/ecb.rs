@@ -0,0 +1,192 @@
+//! European Central Bank Data Portal client
+//!
+//! The ECB publishes euro area statistics — HICP inflation, policy rates,
+//! yields, and Eurostat's unemployment and national accounts — through an
+//! SDMX REST API that needs no key.
+//!
+//! Series are addressed as `FLOW/KEY`, e.g. `ICP/M.U2.N.000000.4.ANR` for
+//! headline HICP inflation.
+//! API docs: https://data.ecb.europa.eu/help/api/data
+
+use super::circuit::{self, CircuitBreaker};
+use super::http_error;
+use crate::error::{Result, StockError};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+
+const ECB_BASE_URL: &str = "https://data-api.ecb.europa.eu/service";
+
+/// Euro area series on the ECB Data Portal
+pub mod series {
+    /// HICP, overall index, annual rate of change (%)
+    pub const HICP: &str = "ICP/M.U2.N.000000.4.ANR";
+    /// HICP excluding energy, food, alcohol and tobacco, annual rate of change (%)
+    pub const CORE_HICP: &str = "ICP/M.U2.N.XEF000.4.ANR";
+    /// Deposit facility rate, the ECB's main policy rate (%)
+    pub const DEPOSIT_FACILITY_RATE: &str = "FM/D.U2.EUR.4F.KR.DFR.LEV";
+    /// Main refinancing operations fixed rate (%)
+    pub const MAIN_REFINANCING_RATE: &str = "FM/D.U2.EUR.4F.KR.MRR_FR.LEV";
+    /// 10-year AAA euro area government bond yield (%)
+    pub const YIELD_10Y: &str = "YC/B.U2.EUR.4F.G_N_A.SV_C_YM.SR_10Y";
+    /// 2-year AAA euro area government bond yield (%)
+    pub const YIELD_2Y: &str = "YC/B.U2.EUR.4F.G_N_A.SV_C_YM.SR_2Y";
+    /// Unemployment rate, seasonally adjusted (%)
+    pub const UNEMPLOYMENT_RATE: &str = "LFSI/M.I9.S.UNEHRT.TOTAL0.15_74.T";
+    /// Real GDP, growth over the same quarter of the previous year (%)
+    pub const GDP_GROWTH: &str = "MNA/Q.Y.I9.W2.S1.S1.B.B1GQ._Z._Z._Z.EUR.LR.GY";
+}
+
+/// One observation of an ECB series
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EcbObservation {
+    /// Period the value refers to, e.g. "2024-02", "2024-Q1" or "2024-03-15"
+    pub period: String,
+    pub value: f64,
+}
+
+/// ECB Data Portal client
+pub struct EcbClient {
+    client: Client,
+    base_url: String,
+}
+
+impl Default for EcbClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EcbClient {
+    /// Create a new ECB client
+    pub fn new() -> Self {
+        Self {
+            client: Client::new(),
+            base_url: ECB_BASE_URL.to_string(),
+        }
+    }
+
+    /// Send requests to `base_url` (e.g. a mirror or a mock server)
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Circuit breaker shared by every client of this endpoint
+    pub fn breaker(&self) -> Arc<CircuitBreaker> {
+        circuit::breaker("ECB", &self.base_url)
+    }
+
+    /// The last `limit` observations of `series_key`, newest first
+    pub async fn get_observations(
+        &self,
+        series_key: &str,
+        limit: usize,
+    ) -> Result<Vec<EcbObservation>> {
+        let url = format!("{}/data/{series_key}", self.base_url);
+        let request = self.client.get(&url).query(&[
+            ("format", "jsondata".to_string()),
+            ("lastNObservations", limit.to_string()),
+            ("detail", "dataonly".to_string()),
+        ]);
+        let response = self.breaker().send(request).await?;
+
+        if !response.status().is_success() {
+            return Err(http_error("ECB", response.status(), ""));
+        }
+
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| StockError::ApiError(format!("Failed to parse ECB response: {e}")))?;
+        let mut observations = parse_observations(&body);
+        observations.reverse();
+        Ok(observations)
+    }
+
+    /// Latest observation of `series_key`
+    pub async fn get_latest(&self, series_key: &str) -> Result<EcbObservation> {
+        self.get_observations(series_key, 1)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| StockError::ApiError(format!("No ECB observations for {series_key}")))
+    }
+}
+
+/// Observations of the first series in an SDMX-JSON response, oldest first
+///
+/// Observations are keyed by their index into the `TIME_PERIOD` dimension
+/// values; missing values are skipped.
+pub(crate) fn parse_observations(body: &Value) -> Vec<EcbObservation> {
+    let periods = body["structure"]["dimensions"]["observation"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|dimension| dimension["id"] == "TIME_PERIOD")
+        .and_then(|dimension| dimension["values"].as_array());
+    let Some(periods) = periods else {
+        return Vec::new();
+    };
+    let Some(observations) = body["dataSets"][0]["series"]
+        .as_object()
+        .and_then(|series| series.values().next())
+        .and_then(|series| series["observations"].as_object())
+    else {
+        return Vec::new();
+    };
+
+    let mut parsed: Vec<(usize, EcbObservation)> = observations
+        .iter()
+        .filter_map(|(index, values)| {
+            let index: usize = index.parse().ok()?;
+            Some((
+                index,
+                EcbObservation {
+                    period: periods.get(index)?["id"].as_str()?.to_string(),
+                    value: values[0].as_f64()?,
+                },
+            ))
+        })
+        .collect();
+    parsed.sort_by_key(|(index, _)| *index);
+    parsed
+        .into_iter()
+        .map(|(_, observation)| observation)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::testing::{MockApi, fixtures};
+
+    #[test]
+    fn test_parse_observations() {
+        let body: Value = serde_json::from_str(fixtures::ECB_HICP_EURO_AREA).unwrap();
+        let observations = parse_observations(&body);
+        assert_eq!(observations.len(), 3);
+        assert_eq!(observations[0].period, "2023-12");
+        assert_eq!(observations[2].period, "2024-02");
+        assert!((observations[2].value - 2.6).abs() < 1e-9);
+
+        assert!(parse_observations(&serde_json::json!({})).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_contract_observations() {
+        let api = MockApi::recorded().await;
+        let client = api.ecb();
+
+        let latest = client.get_latest(series::HICP).await.unwrap();
+        assert_eq!(latest.period, "2024-02");
+        assert!((latest.value - 2.6).abs() < 1e-9);
+
+        let observations = client.get_observations(series::HICP, 3).await.unwrap();
+        assert_eq!(observations[1].period, "2024-01");
+
+        let err = client.get_latest(series::CORE_HICP).await.unwrap_err();
+        assert!(err.to_string().contains("404"), "{err}");
+    }
+}
@@ -0,0 +1,192 @@
+//! ESG (environmental, social, governance) scores
+//!
+//! Providers use different scales: Yahoo Finance reports Sustainalytics risk
+//! scores, where lower is better, while Finnhub reports 0-100 scores, where
+//! higher is better. [`EsgScores`] keeps the provider's numbers and records
+//! which way they point.
+
+use crate::config::EsgProvider;
+use crate::error::{Result, StockError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// ESG scores and controversies for a company
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EsgScores {
+    /// Stock symbol
+    pub symbol: String,
+    /// Provider the scores came from
+    pub provider: EsgProvider,
+    /// Total ESG score
+    pub total: Option<f64>,
+    /// Environmental pillar score
+    pub environment: Option<f64>,
+    /// Social pillar score
+    pub social: Option<f64>,
+    /// Governance pillar score
+    pub governance: Option<f64>,
+    /// Whether higher scores are better (false for risk scores)
+    pub higher_is_better: bool,
+    /// Highest controversy level, from 0 (none) to 5 (severe)
+    pub controversy_level: Option<u8>,
+    /// Areas the company's controversies relate to
+    pub controversies: Vec<String>,
+    /// Peer group the scores are benchmarked against
+    pub peer_group: Option<String>,
+}
+
+impl EsgScores {
+    /// Sustainalytics risk category of the total score, for risk scores only
+    pub fn risk_category(&self) -> Option<&'static str> {
+        if self.higher_is_better {
+            return None;
+        }
+        let total = self.total?;
+        Some(if total < 10.0 {
+            "Negligible"
+        } else if total < 20.0 {
+            "Low"
+        } else if total < 30.0 {
+            "Medium"
+        } else if total < 40.0 {
+            "High"
+        } else {
+            "Severe"
+        })
+    }
+
+    /// Parse the `esgScores` module of a Yahoo Finance quoteSummary response
+    pub fn from_yahoo(symbol: &str, body: &Value) -> Result<Self> {
+        let summary = &body["quoteSummary"];
+        if let Some(description) = summary["error"]["description"].as_str() {
+            return Err(StockError::YahooFinanceError(format!(
+                "No ESG data for {symbol}: {description}"
+            )));
+        }
+        let esg = &summary["result"][0]["esgScores"];
+        if !esg.is_object() {
+            return Err(StockError::YahooFinanceError(format!(
+                "No ESG data for {symbol}"
+            )));
+        }
+
+        // Numbers come wrapped as {"raw": 25.1, "fmt": "25.1"}
+        let raw = |field: &str| esg[field]["raw"].as_f64().or_else(|| esg[field].as_f64());
+
+        Ok(Self {
+            symbol: symbol.to_string(),
+            provider: EsgProvider::Yahoo,
+            total: raw("totalEsg"),
+            environment: raw("environmentScore"),
+            social: raw("socialScore"),
+            governance: raw("governanceScore"),
+            higher_is_better: false,
+            controversy_level: esg["highestControversy"]
+                .as_f64()
+                .map(|level| level.clamp(0.0, 5.0) as u8),
+            controversies: esg["relatedControversy"]
+                .as_array()
+                .map(|areas| {
+                    areas
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .map(ToString::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            peer_group: esg["peerGroup"].as_str().map(ToString::to_string),
+        })
+    }
+
+    /// Parse a Finnhub `/stock/esg` response
+    pub fn from_finnhub(symbol: &str, body: &Value) -> Result<Self> {
+        let total = body["totalESGScore"].as_f64();
+        if total.is_none() {
+            return Err(StockError::ApiError(format!(
+                "Finnhub returned no ESG data for {symbol}"
+            )));
+        }
+
+        Ok(Self {
+            symbol: symbol.to_string(),
+            provider: EsgProvider::Finnhub,
+            total,
+            environment: body["environmentScore"].as_f64(),
+            social: body["socialScore"].as_f64(),
+            governance: body["governanceScore"].as_f64(),
+            higher_is_better: true,
+            controversy_level: None,
+            controversies: Vec::new(),
+            peer_group: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_from_yahoo() {
+        let body = json!({
+            "quoteSummary": {
+                "result": [{
+                    "esgScores": {
+                        "totalEsg": {"raw": 25.02, "fmt": "25.0"},
+                        "environmentScore": {"raw": 3.1, "fmt": "3.1"},
+                        "socialScore": {"raw": 14.5, "fmt": "14.5"},
+                        "governanceScore": {"raw": 7.42, "fmt": "7.4"},
+                        "highestControversy": 3,
+                        "relatedControversy": ["Employee Incidents", "Product & Service Incidents"],
+                        "peerGroup": "Automobiles"
+                    }
+                }],
+                "error": null
+            }
+        });
+
+        let scores = EsgScores::from_yahoo("TSLA", &body).unwrap();
+        assert_eq!(scores.total, Some(25.02));
+        assert_eq!(scores.social, Some(14.5));
+        assert_eq!(scores.controversy_level, Some(3));
+        assert_eq!(scores.controversies.len(), 2);
+        assert_eq!(scores.peer_group.as_deref(), Some("Automobiles"));
+        assert!(!scores.higher_is_better);
+        assert_eq!(scores.risk_category(), Some("Medium"));
+    }
+
+    #[test]
+    fn test_from_yahoo_missing_data() {
+        let body = json!({
+            "quoteSummary": {
+                "result": null,
+                "error": {"code": "Not Found", "description": "No fundamentals data found"}
+            }
+        });
+        let err = EsgScores::from_yahoo("NOPE", &body).unwrap_err();
+        assert!(err.to_string().contains("No fundamentals data"), "{err}");
+
+        let body = json!({"quoteSummary": {"result": [{}], "error": null}});
+        assert!(EsgScores::from_yahoo("NOPE", &body).is_err());
+    }
+
+    #[test]
+    fn test_from_finnhub() {
+        let body = json!({
+            "symbol": "AAPL",
+            "totalESGScore": 61.35,
+            "environmentScore": 79.57,
+            "socialScore": 57.98,
+            "governanceScore": 46.5
+        });
+
+        let scores = EsgScores::from_finnhub("AAPL", &body).unwrap();
+        assert_eq!(scores.total, Some(61.35));
+        assert_eq!(scores.governance, Some(46.5));
+        assert!(scores.higher_is_better);
+        assert_eq!(scores.risk_category(), None);
+
+        assert!(EsgScores::from_finnhub("AAPL", &json!({})).is_err());
+    }
+}
@@ -0,0 +1,125 @@
+//! Analyst EPS estimates and their revisions
+//!
+//! Yahoo Finance reports the consensus EPS estimate for the current and next
+//! quarter and fiscal year, together with what the consensus was 7, 30, 60
+//! and 90 days ago. Comparing the two gives the direction of estimate
+//! revisions.
+
+use crate::error::{Result, StockError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Consensus EPS estimate for one period and its recent history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EpsTrend {
+    /// Period code: "0q" (current quarter), "+1q", "0y" (current fiscal year), "+1y"
+    pub period: String,
+    /// Last day of the period, "YYYY-MM-DD"
+    pub end_date: Option<String>,
+    /// Current consensus EPS
+    pub current: Option<f64>,
+    pub days_ago_7: Option<f64>,
+    pub days_ago_30: Option<f64>,
+    pub days_ago_60: Option<f64>,
+    pub days_ago_90: Option<f64>,
+    /// Analysts revising up over the last 30 days
+    pub up_last_30_days: Option<u32>,
+    /// Analysts revising down over the last 30 days
+    pub down_last_30_days: Option<u32>,
+}
+
+impl EpsTrend {
+    /// Change of the consensus over the last 90 days, in percent
+    pub fn revision_pct_90d(&self) -> Option<f64> {
+        let (current, before) = (self.current?, self.days_ago_90?);
+        (before.abs() > f64::EPSILON).then(|| (current - before) / before.abs() * 100.0)
+    }
+
+    /// Parse the `earningsTrend` module of a Yahoo Finance quoteSummary response
+    pub fn from_yahoo(symbol: &str, body: &Value) -> Result<Vec<Self>> {
+        let summary = &body["quoteSummary"];
+        if let Some(description) = summary["error"]["description"].as_str() {
+            return Err(StockError::YahooFinanceError(format!(
+                "No estimates for {symbol}: {description}"
+            )));
+        }
+        let trends = summary["result"][0]["earningsTrend"]["trend"]
+            .as_array()
+            .ok_or_else(|| StockError::YahooFinanceError(format!("No estimates for {symbol}")))?;
+
+        // Numbers come wrapped as {"raw": 6.58, "fmt": "6.58"}
+        let raw = |value: &Value| value["raw"].as_f64().or_else(|| value.as_f64());
+
+        Ok(trends
+            .iter()
+            .filter_map(|trend| {
+                let eps = &trend["epsTrend"];
+                let revisions = &trend["epsRevisions"];
+                Some(Self {
+                    period: trend["period"].as_str()?.to_string(),
+                    end_date: trend["endDate"].as_str().map(ToString::to_string),
+                    current: raw(&eps["current"]),
+                    days_ago_7: raw(&eps["7daysAgo"]),
+                    days_ago_30: raw(&eps["30daysAgo"]),
+                    days_ago_60: raw(&eps["60daysAgo"]),
+                    days_ago_90: raw(&eps["90daysAgo"]),
+                    up_last_30_days: raw(&revisions["upLast30days"]).map(|n| n as u32),
+                    down_last_30_days: raw(&revisions["downLast30days"]).map(|n| n as u32),
+                })
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_from_yahoo() {
+        let body = json!({
+            "quoteSummary": {
+                "result": [{
+                    "earningsTrend": {
+                        "trend": [
+                            {
+                                "period": "0y",
+                                "endDate": "2024-09-30",
+                                "epsTrend": {
+                                    "current": {"raw": 6.58, "fmt": "6.58"},
+                                    "7daysAgo": {"raw": 6.58},
+                                    "30daysAgo": {"raw": 6.55},
+                                    "60daysAgo": {"raw": 6.52},
+                                    "90daysAgo": {"raw": 6.4}
+                                },
+                                "epsRevisions": {
+                                    "upLast30days": {"raw": 4},
+                                    "downLast30days": {"raw": 1}
+                                }
+                            },
+                            {"endDate": "2025-09-30"}
+                        ]
+                    }
+                }],
+                "error": null
+            }
+        });
+
+        let trends = EpsTrend::from_yahoo("AAPL", &body).unwrap();
+        assert_eq!(trends.len(), 1);
+        let trend = &trends[0];
+        assert_eq!(trend.period, "0y");
+        assert_eq!(trend.current, Some(6.58));
+        assert_eq!(trend.up_last_30_days, Some(4));
+        assert!((trend.revision_pct_90d().unwrap() - 2.8125).abs() < 1e-9);
+
+        let body = json!({
+            "quoteSummary": {
+                "result": null,
+                "error": {"code": "Not Found", "description": "No fundamentals data found"}
+            }
+        });
+        assert!(EpsTrend::from_yahoo("NOPE", &body).is_err());
+    }
+}
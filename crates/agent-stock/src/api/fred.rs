@@ -6,7 +6,10 @@
 //! API Key: Free registration at https://fred.stlouisfed.org/docs/api/api_key.html
 //! Rate Limit: 120 requests per minute
 
+use super::circuit::{self, CircuitBreaker};
+use super::http_cache::HttpCache;
 use crate::error::{Result, StockError};
+use futures::stream::{self, StreamExt};
 use governor::clock::DefaultClock;
 use governor::state::{InMemoryState, NotKeyed};
 use governor::{Quota, RateLimiter};
@@ -20,6 +23,9 @@ type SharedRateLimiter = Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock>>
 
 const FRED_BASE_URL: &str = "https://api.stlouisfed.org/fred";
 
+/// Requests a batch keeps in flight at once; the rate limiter still applies
+const MAX_CONCURRENT_REQUESTS: usize = 8;
+
 /// Common FRED series IDs for economic indicators
 pub mod series {
     /// Federal Funds Effective Rate
@@ -68,6 +74,12 @@ pub mod series {
     pub const OIL_WTI: &str = "DCOILWTICO";
     /// Gold Price
     pub const GOLD: &str = "GOLDAMGBD228NLBM";
+    /// China CPI, growth over the same month a year earlier (OECD)
+    pub const CHINA_CPI_YOY: &str = "CPALTT01CNM659N";
+    /// China 3-month interbank rate (OECD)
+    pub const CHINA_INTERBANK_3M: &str = "IR3TIB01CNM156N";
+    /// China real GDP, growth over the same quarter a year earlier (OECD)
+    pub const CHINA_GDP_GROWTH: &str = "NAEXKP01CNQ659S";
 }
 
 /// Economic indicator category
@@ -164,7 +176,9 @@ pub struct EconomicSummary {
 pub struct FredClient {
     client: Client,
     api_key: String,
+    base_url: String,
     rate_limiter: SharedRateLimiter,
+    http_cache: Arc<HttpCache>,
 }
 
 impl FredClient {
@@ -182,10 +196,25 @@ impl FredClient {
         Self {
             client: Client::new(),
             api_key: api_key.into(),
+            base_url: FRED_BASE_URL.to_string(),
             rate_limiter,
+            http_cache: HttpCache::shared(),
         }
     }
 
+    /// Send requests to `base_url` (e.g. a mirror or a mock server), with
+    /// an HTTP cache of its own
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self.http_cache = Arc::new(HttpCache::default());
+        self
+    }
+
+    /// Circuit breaker shared by every client of this endpoint
+    pub fn breaker(&self) -> Arc<CircuitBreaker> {
+        circuit::breaker("FRED", &self.base_url)
+    }
+
     /// Create from environment variable FRED_API_KEY
     pub fn from_env() -> Result<Self> {
         let api_key = std::env::var("FRED_API_KEY").map_err(|_| {
@@ -204,25 +233,14 @@ impl FredClient {
         params.insert("api_key", &self.api_key);
         params.insert("file_type", "json");
 
-        let url = format!("{FRED_BASE_URL}/series");
-        let response = self
-            .client
-            .get(&url)
-            .query(&params)
-            .send()
-            .await
-            .map_err(|e| StockError::ApiError(format!("FRED request failed: {e}")))?;
-
-        if !response.status().is_success() {
-            return Err(StockError::ApiError(format!(
-                "FRED API error: {}",
-                response.status()
-            )));
-        }
+        let url = format!("{}/series", self.base_url);
+        let request = self.client.get(&url).query(&params);
+        let body = self
+            .http_cache
+            .get(&self.breaker(), "FRED", request)
+            .await?;
 
-        let data: SeriesResponse = response
-            .json()
-            .await
+        let data: SeriesResponse = serde_json::from_slice(&body)
             .map_err(|e| StockError::ApiError(format!("Failed to parse FRED response: {e}")))?;
 
         data.seriess
@@ -258,25 +276,14 @@ impl FredClient {
             params.insert("limit", lim.to_string());
         }
 
-        let url = format!("{FRED_BASE_URL}/series/observations");
-        let response = self
-            .client
-            .get(&url)
-            .query(&params)
-            .send()
-            .await
-            .map_err(|e| StockError::ApiError(format!("FRED request failed: {e}")))?;
-
-        if !response.status().is_success() {
-            return Err(StockError::ApiError(format!(
-                "FRED API error: {}",
-                response.status()
-            )));
-        }
+        let url = format!("{}/series/observations", self.base_url);
+        let request = self.client.get(&url).query(&params);
+        let body = self
+            .http_cache
+            .get(&self.breaker(), "FRED", request)
+            .await?;
 
-        let data: ObservationsResponse = response
-            .json()
-            .await
+        let data: ObservationsResponse = serde_json::from_slice(&body)
             .map_err(|e| StockError::ApiError(format!("Failed to parse FRED response: {e}")))?;
 
         Ok(data.observations)
@@ -284,7 +291,9 @@ impl FredClient {
 
     /// Get latest value for a series
     pub async fn get_latest(&self, series_id: &str) -> Result<ParsedObservation> {
-        let observations = self.get_observations(series_id, None, None, Some(1)).await?;
+        let observations = self
+            .get_observations(series_id, None, None, Some(1))
+            .await?;
 
         let obs = observations
             .into_iter()
@@ -303,11 +312,24 @@ impl FredClient {
     }
 
     /// Get multiple latest values for efficiency
-    pub async fn get_latest_batch(&self, series_ids: &[&str]) -> Result<HashMap<String, ParsedObservation>> {
-        let mut results = HashMap::new();
+    ///
+    /// Up to [`MAX_CONCURRENT_REQUESTS`] series are requested at once; the
+    /// rate limiter still spaces the requests out.
+    pub async fn get_latest_batch(
+        &self,
+        series_ids: &[&str],
+    ) -> Result<HashMap<String, ParsedObservation>> {
+        // Collected first so the batch future stays `Send` for callers that
+        // spawn it
+        let requests: Vec<_> = series_ids
+            .iter()
+            .map(|&series_id| async move { (series_id, self.get_latest(series_id).await) })
+            .collect();
+        let mut latest = stream::iter(requests).buffer_unordered(MAX_CONCURRENT_REQUESTS);
 
-        for series_id in series_ids {
-            match self.get_latest(series_id).await {
+        let mut results = HashMap::new();
+        while let Some((series_id, result)) = latest.next().await {
+            match result {
                 Ok(obs) => {
                     results.insert(series_id.to_string(), obs);
                 }
@@ -362,6 +384,8 @@ impl FredClient {
     }
 
     /// Get comprehensive economic summary
+    ///
+    /// The ten underlying series are fetched concurrently.
     pub async fn get_economic_summary(&self) -> Result<EconomicSummary> {
         let series_ids = [
             series::FED_FUNDS_RATE,
@@ -373,7 +397,13 @@ impl FredClient {
             series::VIX,
         ];
 
-        let batch = self.get_latest_batch(&series_ids).await?;
+        let (batch, cpi, core_pce, gdp) = tokio::join!(
+            self.get_latest_batch(&series_ids),
+            self.get_yoy_change(series::CPI),
+            self.get_yoy_change(series::CORE_PCE),
+            self.get_latest(series::GDP_GROWTH),
+        );
+        let batch = batch?;
 
         let fed_funds = batch.get(series::FED_FUNDS_RATE).map(|o| o.value);
         let treasury_10y = batch.get(series::TREASURY_10Y).map(|o| o.value);
@@ -383,31 +413,16 @@ impl FredClient {
         let sentiment = batch.get(series::CONSUMER_SENTIMENT).map(|o| o.value);
         let vix = batch.get(series::VIX).map(|o| o.value);
 
-        // Get inflation data (needs YoY calculation)
-        let cpi_yoy = self
-            .get_yoy_change(series::CPI)
-            .await
-            .ok()
-            .map(|(_, _, pct)| pct);
-        let core_pce_yoy = self
-            .get_yoy_change(series::CORE_PCE)
-            .await
-            .ok()
-            .map(|(_, _, pct)| pct);
-
-        // Get GDP growth
-        let gdp_growth = self.get_latest(series::GDP_GROWTH).await.ok().map(|o| o.value);
+        // Inflation needs a YoY calculation
+        let cpi_yoy = cpi.ok().map(|(_, _, pct)| pct);
+        let core_pce_yoy = core_pce.ok().map(|(_, _, pct)| pct);
+        let gdp_growth = gdp.ok().map(|o| o.value);
 
         let yield_curve_inverted = yield_spread.is_some_and(|s| s < 0.0);
 
         // Generate assessment
-        let assessment = self.generate_assessment(
-            fed_funds,
-            yield_curve_inverted,
-            cpi_yoy,
-            unemployment,
-            vix,
-        );
+        let assessment =
+            self.generate_assessment(fed_funds, yield_curve_inverted, cpi_yoy, unemployment, vix);
 
         Ok(EconomicSummary {
             fed_funds_rate: fed_funds,
@@ -506,7 +521,7 @@ impl FredClient {
         ];
 
         let mut rates = serde_json::Map::new();
-        
+
         for (id, name) in series {
             if let Ok(obs) = self.get_latest(id).await {
                 rates.insert(
@@ -544,6 +559,76 @@ impl FredClient {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::api::testing::MockApi;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_contract_parsing() {
+        let api = MockApi::recorded().await;
+        let client = api.fred(None);
+
+        let info = client
+            .get_series_info(series::FED_FUNDS_RATE)
+            .await
+            .unwrap();
+        assert_eq!(info.title, "Federal Funds Effective Rate");
+        assert_eq!(info.units_short, "%");
+
+        let latest = client.get_latest(series::FED_FUNDS_RATE).await.unwrap();
+        assert_eq!(latest.date, "2024-02-01");
+        assert!((latest.value - 5.33).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_contract_error_mapping() {
+        let api = MockApi::recorded().await;
+        let client = api.fred(None);
+
+        // Missing values are reported as "."
+        let err = client.get_latest("MISSING").await.unwrap_err();
+        assert!(err.to_string().contains("Invalid numeric value"), "{err}");
+
+        let err = client.get_latest("BOGUS").await.unwrap_err();
+        assert!(err.to_string().contains("400"), "{err}");
+
+        let api = MockApi::start().await;
+        api.mount_json("/fred/series/observations", 429, "{}").await;
+        let err = api.fred(None).get_latest("FEDFUNDS").await.unwrap_err();
+        assert!(matches!(err, StockError::RateLimitExceeded { .. }), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_contract_rate_limiting() {
+        let api = MockApi::recorded().await;
+        let client = api.fred(Some(1));
+
+        client.get_latest(series::FED_FUNDS_RATE).await.unwrap();
+        let second = tokio::time::timeout(
+            Duration::from_millis(300),
+            client.get_latest(series::FED_FUNDS_RATE),
+        )
+        .await;
+        assert!(
+            second.is_err(),
+            "second request should wait for the limiter"
+        );
+        assert_eq!(api.request_count().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_latest_batch() {
+        let api = MockApi::recorded().await;
+        let client = api.fred(None);
+
+        let batch = client
+            .get_latest_batch(&[series::FED_FUNDS_RATE, "MISSING", "BOGUS"])
+            .await
+            .unwrap();
+        // Series that fail are left out
+        assert_eq!(batch.len(), 1);
+        assert!((batch[series::FED_FUNDS_RATE].value - 5.33).abs() < 1e-9);
+        assert_eq!(api.request_count().await, 3);
+    }
 
     #[test]
     fn test_series_constants() {
@@ -564,7 +649,7 @@ mod tests {
         let client = FredClient::from_env().unwrap();
         let result = client.get_latest(series::FED_FUNDS_RATE).await;
         assert!(result.is_ok());
-        
+
         let obs = result.unwrap();
         assert!(!obs.date.is_empty());
         assert!(obs.value >= 0.0);
@@ -576,7 +661,7 @@ mod tests {
         let client = FredClient::from_env().unwrap();
         let summary = client.get_economic_summary().await;
         assert!(summary.is_ok());
-        
+
         let summary = summary.unwrap();
         assert!(!summary.as_of_date.is_empty());
         assert!(!summary.assessment.is_empty());
@@ -0,0 +1,312 @@
+//! HTTP-level cache with conditional requests
+//!
+//! SEC EDGAR and FRED send `ETag` and `Last-Modified` headers. [`HttpCache`]
+//! keeps the last body of each URL with its validators and sends them back as
+//! `If-None-Match` / `If-Modified-Since`, so an unchanged company facts file
+//! or ticker map costs a `304 Not Modified` instead of a multi-MB download.
+//!
+//! This sits below [`crate::cache`]: the [`StockCache`](crate::cache::StockCache)
+//! tiers decide how long data is fresh, this cache makes the refresh cheap
+//! once they expire.
+
+use reqwest::{RequestBuilder, StatusCode, header};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+
+use super::CircuitBreaker;
+use super::http_error;
+use crate::error::{Result, StockError};
+
+/// Bodies kept by default, in bytes; the oldest are dropped first
+const DEFAULT_CAPACITY: usize = 64 * 1024 * 1024;
+
+/// A cached body and the validators to revalidate it
+struct Entry {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: Arc<[u8]>,
+}
+
+#[derive(Default)]
+struct Entries {
+    by_url: HashMap<String, Entry>,
+    /// URLs from oldest to newest insertion, for eviction
+    order: VecDeque<String>,
+    bytes: usize,
+}
+
+impl Entries {
+    fn insert(&mut self, url: String, entry: Entry, capacity: usize) {
+        if let Some(old) = self.by_url.remove(&url) {
+            self.bytes -= old.body.len();
+            self.order.retain(|u| u != &url);
+        }
+        self.bytes += entry.body.len();
+        self.order.push_back(url.clone());
+        self.by_url.insert(url, entry);
+
+        while self.bytes > capacity {
+            let Some(oldest) = self.order.pop_front() else {
+                break;
+            };
+            if let Some(old) = self.by_url.remove(&oldest) {
+                self.bytes -= old.body.len();
+            }
+        }
+    }
+}
+
+/// Cache of response bodies revalidated with conditional GETs
+pub struct HttpCache {
+    entries: Mutex<Entries>,
+    capacity: usize,
+    revalidated: AtomicU64,
+}
+
+impl Default for HttpCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+/// Summary of an [`HttpCache`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HttpCacheStats {
+    pub entries: usize,
+    pub bytes: usize,
+    /// Requests answered with `304 Not Modified` from the cached body
+    pub revalidated: u64,
+}
+
+impl HttpCache {
+    /// An empty cache keeping up to `capacity` bytes of bodies
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::default(),
+            capacity,
+            revalidated: AtomicU64::new(0),
+        }
+    }
+
+    /// The cache shared by every client in this process
+    pub fn shared() -> Arc<Self> {
+        static SHARED: OnceLock<Arc<HttpCache>> = OnceLock::new();
+        Arc::clone(SHARED.get_or_init(|| Arc::new(Self::default())))
+    }
+
+    /// GET through `breaker`, revalidating a cached body when there is one
+    ///
+    /// Returns the body of a successful response, or the cached body on a
+    /// `304`. Other statuses become errors attributed to `provider`.
+    pub async fn get(
+        &self,
+        breaker: &CircuitBreaker,
+        provider: &str,
+        request: RequestBuilder,
+    ) -> Result<Arc<[u8]>> {
+        let (client, request) = request.build_split();
+        let mut request = request?;
+        let url = request.url().to_string();
+
+        let cached = self
+            .lock()
+            .by_url
+            .get(&url)
+            .map(|entry| (entry.etag.clone(), entry.last_modified.clone()));
+        if let Some((etag, last_modified)) = cached {
+            let headers = request.headers_mut();
+            if let Some(value) = etag.and_then(|v| header::HeaderValue::from_str(&v).ok()) {
+                headers.insert(header::IF_NONE_MATCH, value);
+            }
+            if let Some(value) = last_modified.and_then(|v| header::HeaderValue::from_str(&v).ok())
+            {
+                headers.insert(header::IF_MODIFIED_SINCE, value);
+            }
+        }
+
+        let response = breaker
+            .send(RequestBuilder::from_parts(client, request))
+            .await?;
+        let status = response.status();
+
+        if status == StatusCode::NOT_MODIFIED {
+            if let Some(entry) = self.lock().by_url.get(&url) {
+                tracing::debug!("{provider} response not modified, using the cached body");
+                self.revalidated.fetch_add(1, Ordering::Relaxed);
+                return Ok(Arc::clone(&entry.body));
+            }
+            // Evicted between the request and the response
+            return Err(StockError::ApiError(format!(
+                "{provider} API returned 304 for an uncached response"
+            )));
+        }
+        if !status.is_success() {
+            return Err(http_error(provider, status, ""));
+        }
+
+        let validator = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v: &header::HeaderValue| v.to_str().ok())
+                .map(str::to_string)
+        };
+        let etag = validator(header::ETAG);
+        let last_modified = validator(header::LAST_MODIFIED);
+
+        let body: Arc<[u8]> = response
+            .bytes()
+            .await
+            .map_err(|e| StockError::ApiError(format!("Failed to read {provider} response: {e}")))?
+            .as_ref()
+            .into();
+
+        if (etag.is_some() || last_modified.is_some()) && body.len() <= self.capacity {
+            let entry = Entry {
+                etag,
+                last_modified,
+                body: Arc::clone(&body),
+            };
+            self.lock().insert(url, entry, self.capacity);
+        }
+        Ok(body)
+    }
+
+    /// Current summary
+    pub fn stats(&self) -> HttpCacheStats {
+        let entries = self.lock();
+        HttpCacheStats {
+            entries: entries.by_url.len(),
+            bytes: entries.bytes,
+            revalidated: self.revalidated.load(Ordering::Relaxed),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Entries> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::circuit::BreakerConfig;
+    use crate::api::testing::MockApi;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    fn body(len: usize) -> Arc<[u8]> {
+        vec![b'x'; len].into()
+    }
+
+    #[test]
+    fn test_oldest_entries_are_evicted() {
+        let mut entries = Entries::default();
+        let entry = |len| Entry {
+            etag: None,
+            last_modified: None,
+            body: body(len),
+        };
+        entries.insert("a".into(), entry(40), 100);
+        entries.insert("b".into(), entry(40), 100);
+        entries.insert("a".into(), entry(50), 100);
+        assert_eq!(entries.bytes, 90);
+
+        entries.insert("c".into(), entry(30), 100);
+        assert!(!entries.by_url.contains_key("b"));
+        assert_eq!(entries.bytes, 80);
+        assert_eq!(entries.order, ["a", "c"]);
+    }
+
+    /// Serve `route` with `validator`, answering 304 once the client sends
+    /// it back in `conditional`
+    async fn mount_revalidated(
+        api: &MockApi,
+        route: &str,
+        validator: (&'static str, &'static str),
+        conditional: &'static str,
+    ) {
+        let (name, value) = validator;
+        // Matched by hand: wiremock's header matcher splits values on commas
+        let sent_back = move |request: &wiremock::Request| {
+            request.headers.get(conditional).is_some_and(|v| v == value)
+        };
+        Mock::given(method("GET"))
+            .and(path(route))
+            .and(sent_back)
+            .respond_with(ResponseTemplate::new(304))
+            .with_priority(1)
+            .mount(api.server())
+            .await;
+        Mock::given(method("GET"))
+            .and(path(route))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header(name, value)
+                    .set_body_string(r#"{"cik":320193}"#),
+            )
+            .mount(api.server())
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_body_is_revalidated() {
+        let api = MockApi::start().await;
+        mount_revalidated(&api, "/etag.json", ("ETag", "\"v1\""), "If-None-Match").await;
+        mount_revalidated(
+            &api,
+            "/dated.json",
+            ("Last-Modified", "Fri, 03 Nov 2023 06:01:40 GMT"),
+            "If-Modified-Since",
+        )
+        .await;
+
+        let cache = HttpCache::default();
+        let breaker = CircuitBreaker::new("Test", api.uri(), BreakerConfig::default());
+        let client = reqwest::Client::new();
+
+        for route in ["/etag.json", "/dated.json"] {
+            let url = format!("{}{route}", api.uri());
+            let first = cache.get(&breaker, "Test", client.get(&url)).await.unwrap();
+            let second = cache.get(&breaker, "Test", client.get(&url)).await.unwrap();
+            assert_eq!(&*first, br#"{"cik":320193}"#);
+            assert_eq!(first, second);
+        }
+        assert_eq!(
+            cache.stats(),
+            HttpCacheStats {
+                entries: 2,
+                bytes: 2 * br#"{"cik":320193}"#.len(),
+                revalidated: 2,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_responses_without_validators_are_not_kept() {
+        let api = MockApi::start().await;
+        api.mount_json("/plain.json", 200, "{}").await;
+        api.mount_json("/missing.json", 404, "").await;
+
+        let cache = HttpCache::default();
+        let breaker = CircuitBreaker::new("Test", api.uri(), BreakerConfig::default());
+        let client = reqwest::Client::new();
+
+        let plain = format!("{}/plain.json", api.uri());
+        assert_eq!(
+            &*cache
+                .get(&breaker, "Test", client.get(&plain))
+                .await
+                .unwrap(),
+            b"{}"
+        );
+        let missing = format!("{}/missing.json", api.uri());
+        let err = cache
+            .get(&breaker, "Test", client.get(&missing))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("404"), "{err}");
+        assert_eq!(cache.stats().entries, 0);
+    }
+}
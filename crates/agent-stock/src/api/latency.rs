@@ -0,0 +1,255 @@
+//! Rolling latency statistics, adaptive timeouts and hedged requests
+//!
+//! Every endpoint's [`CircuitBreaker`](super::CircuitBreaker) keeps a
+//! [`LatencyTracker`] of its recent successful requests. Once it has enough
+//! samples, requests time out after a multiple of the endpoint's p95 latency
+//! instead of the client's fixed timeout, so a stalled connection fails in
+//! seconds and the retry or fallback starts sooner.
+//!
+//! [`hedge`] goes further for idempotent reads that have a fallback provider:
+//! when the primary request is slower than its p95, the fallback request is
+//! sent too and whichever answers first wins.
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+use crate::error::Result;
+
+/// Samples kept per endpoint
+const WINDOW: usize = 100;
+
+/// Samples needed before percentiles (and so adaptive timeouts) are used
+const MIN_SAMPLES: usize = 20;
+
+/// Adaptive timeouts allow this many times the p95 latency
+const TIMEOUT_MULTIPLIER: u32 = 4;
+
+/// Adaptive timeouts never go below this, so large responses still fit
+const MIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Adaptive timeouts never go above this
+const MAX_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Recent request latencies of one endpoint
+#[derive(Debug, Default)]
+pub struct LatencyTracker {
+    samples: Mutex<VecDeque<Duration>>,
+}
+
+/// Summary of a [`LatencyTracker`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencyStats {
+    pub samples: usize,
+    pub p50: Option<Duration>,
+    pub p95: Option<Duration>,
+    /// Timeout applied to the next request; `None` keeps the client's own
+    pub timeout: Option<Duration>,
+}
+
+impl LatencyTracker {
+    /// An empty tracker
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the latency of a successful request
+    pub fn record(&self, latency: Duration) {
+        let mut samples = self.samples.lock().unwrap_or_else(PoisonError::into_inner);
+        if samples.len() == WINDOW {
+            samples.pop_front();
+        }
+        samples.push_back(latency);
+    }
+
+    /// The `quantile` (0.0 to 1.0) latency, once there are enough samples
+    pub fn percentile(&self, quantile: f64) -> Option<Duration> {
+        let mut sorted: Vec<Duration> = self
+            .samples
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .copied()
+            .collect();
+        if sorted.len() < MIN_SAMPLES {
+            return None;
+        }
+        sorted.sort_unstable();
+        // Nearest rank; the window is small, so the cast cannot truncate
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            clippy::cast_precision_loss
+        )]
+        let rank = (quantile.clamp(0.0, 1.0) * sorted.len() as f64).ceil() as usize;
+        Some(sorted[rank.saturating_sub(1).min(sorted.len() - 1)])
+    }
+
+    /// The 95th percentile latency, once there are enough samples
+    pub fn p95(&self) -> Option<Duration> {
+        self.percentile(0.95)
+    }
+
+    /// Timeout for the next request: a multiple of the p95 latency within
+    /// bounds, or `None` (the client's own timeout) until there are enough
+    /// samples
+    pub fn timeout(&self) -> Option<Duration> {
+        self.p95()
+            .map(|p95| (p95 * TIMEOUT_MULTIPLIER).clamp(MIN_TIMEOUT, MAX_TIMEOUT))
+    }
+
+    /// Current summary
+    pub fn stats(&self) -> LatencyStats {
+        let samples = self
+            .samples
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len();
+        LatencyStats {
+            samples,
+            p50: self.percentile(0.5),
+            p95: self.p95(),
+            timeout: self.timeout(),
+        }
+    }
+}
+
+/// Run `primary`, also starting `fallback` if `primary` is still running
+/// after `delay` or fails with a retryable error
+///
+/// The first success wins and the other request is dropped. If both fail,
+/// the primary's error is returned. `fallback` runs at most once, and never
+/// when `primary` succeeds within `delay`; a `delay` of `None` only falls
+/// back on errors. Use it only for idempotent reads.
+pub async fn hedge<T, P, F, Fut>(primary: P, delay: Option<Duration>, fallback: F) -> Result<T>
+where
+    P: Future<Output = Result<T>>,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    tokio::pin!(primary);
+    let wait = async {
+        match delay {
+            Some(delay) => tokio::time::sleep(delay).await,
+            None => std::future::pending().await,
+        }
+    };
+
+    tokio::select! {
+        result = &mut primary => {
+            return match result {
+                Err(e) if e.is_retryable() => {
+                    tracing::debug!("Primary request failed ({e}), trying the fallback");
+                    fallback().await.map_err(|_| e)
+                }
+                result => result,
+            };
+        }
+        () = wait => {}
+    }
+
+    tracing::debug!("Primary request slower than {delay:?}, hedging with the fallback");
+    let fallback = fallback();
+    tokio::pin!(fallback);
+    tokio::select! {
+        result = &mut primary => match result {
+            Ok(value) => Ok(value),
+            Err(e) => fallback.await.map_err(|_| e),
+        },
+        result = &mut fallback => match result {
+            Ok(value) => Ok(value),
+            Err(_) => primary.await,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::StockError;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn millis(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn test_percentiles_and_timeout() {
+        let tracker = LatencyTracker::new();
+        for ms in 1..MIN_SAMPLES as u64 {
+            tracker.record(millis(ms * 100));
+        }
+        assert_eq!(tracker.p95(), None);
+        assert_eq!(tracker.timeout(), None);
+
+        tracker.record(millis(2000));
+        assert_eq!(tracker.p95(), Some(millis(1900)));
+        assert_eq!(tracker.percentile(0.5), Some(millis(1000)));
+        // 4 × 1.9s
+        assert_eq!(tracker.timeout(), Some(millis(7600)));
+
+        // Fast endpoints still get the minimum, and the window rolls over
+        for _ in 0..WINDOW {
+            tracker.record(millis(10));
+        }
+        let stats = tracker.stats();
+        assert_eq!(stats.samples, WINDOW);
+        assert_eq!(stats.p95, Some(millis(10)));
+        assert_eq!(stats.timeout, Some(MIN_TIMEOUT));
+    }
+
+    #[tokio::test]
+    async fn test_fast_primary_skips_fallback() {
+        let calls = AtomicUsize::new(0);
+        let result = hedge(async { Ok(1) }, Some(millis(50)), || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Ok(2)
+        })
+        .await;
+        assert_eq!(result.unwrap(), 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_slow_primary_is_hedged() {
+        let slow = async {
+            tokio::time::sleep(millis(500)).await;
+            Ok(1)
+        };
+        let fast_fallback = || async {
+            tokio::time::sleep(millis(10)).await;
+            Ok(2)
+        };
+        assert_eq!(
+            hedge(slow, Some(millis(50)), fast_fallback).await.unwrap(),
+            2
+        );
+
+        // A failed fallback waits for the slow primary
+        let slow = async {
+            tokio::time::sleep(millis(500)).await;
+            Ok(1)
+        };
+        let failing = || async { Err::<i32, _>(StockError::Timeout("fallback".into())) };
+        assert_eq!(hedge(slow, Some(millis(50)), failing).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_errors_fall_back_only_when_retryable() {
+        let down = async { Err::<i32, _>(StockError::Timeout("primary".into())) };
+        assert_eq!(hedge(down, None, || async { Ok(2) }).await.unwrap(), 2);
+
+        let unknown = async { Err::<i32, _>(StockError::InvalidSymbol("XYZ".into())) };
+        let result = hedge(unknown, None, || async { Ok(2) }).await;
+        assert!(matches!(result, Err(StockError::InvalidSymbol(_))));
+
+        // Both fail: the primary's error is reported
+        let down = async { Err::<i32, _>(StockError::Timeout("primary".into())) };
+        let result = hedge(down, None, || async {
+            Err(StockError::Timeout("fallback".into()))
+        })
+        .await;
+        assert!(result.unwrap_err().to_string().contains("primary"));
+    }
+}
@@ -1,15 +1,60 @@
 //! API clients for stock data providers
 
 pub mod alpha_vantage;
+pub mod cik;
+pub mod circuit;
+pub mod dividends;
+pub mod download;
+pub mod earnings_calendar;
+pub mod ecb;
+pub mod esg;
+pub mod estimates;
 pub mod fred;
+pub mod http_cache;
+pub mod latency;
 pub mod news_apis;
 pub mod sec_edgar;
+pub mod segments;
+pub mod stream;
+pub mod xbrl;
 pub mod yahoo;
+pub mod yahoo_schema;
+
+#[cfg(any(test, feature = "test-util"))]
+pub mod testing;
 
 pub use alpha_vantage::{
     AlphaVantageClient, NewsArticle, NewsSentimentResponse, NewsTopic, TickerSentiment,
 };
-pub use fred::{FredClient, EconomicSummary, series as fred_series};
+pub use cik::{CikMap, CikMapSource, CikResolver};
+pub use circuit::{BreakerStatus, CircuitBreaker, CircuitState};
+pub use dividends::{DividendEvent, DividendHistory};
+pub use download::Downloader;
+pub use earnings_calendar::EarningsEvent;
+pub use ecb::{EcbClient, EcbObservation, series as ecb_series};
+pub use esg::EsgScores;
+pub use estimates::EpsTrend;
+pub use fred::{EconomicSummary, FredClient, series as fred_series};
+pub use http_cache::{HttpCache, HttpCacheStats};
+pub use latency::{LatencyStats, LatencyTracker, hedge};
 pub use news_apis::FinnhubClient;
-pub use sec_edgar::{SecEdgarClient, SecFiling, FinancialData, FilingType};
-pub use yahoo::YahooFinanceClient;
+pub use sec_edgar::{
+    FilingType, FinancialData, FullTextHit, FullTextSearchResults, SecEdgarClient, SecFiling,
+};
+pub use segments::{BreakdownAxis, RevenueBreakdown, RevenueSlice};
+pub use stream::{QuoteStream, QuoteStreamer, StreamConfig, StreamProvider, Tick};
+pub use yahoo::{AssetProfile, YahooFinanceClient};
+
+use crate::error::StockError;
+use reqwest::StatusCode;
+
+/// Error for a non-success HTTP response; 429 maps to rate limiting
+pub(crate) fn http_error(provider: &str, status: StatusCode, body: &str) -> StockError {
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        StockError::rate_limited(provider)
+    } else if body.is_empty() {
+        StockError::ApiError(format!("{provider} API error: {status}"))
+    } else {
+        StockError::ApiError(format!("{provider} API error {status}: {body}"))
+    }
+}
@@ -1,5 +1,9 @@
 //! News API clients for market news and sentiment data
 
+use super::circuit::{self, CircuitBreaker};
+use super::earnings_calendar::EarningsEvent;
+use super::esg::EsgScores;
+use super::http_error;
 use crate::error::{Result, StockError};
 use governor::clock::DefaultClock;
 use governor::state::{InMemoryState, NotKeyed};
@@ -11,6 +15,8 @@ use std::sync::Arc;
 
 type SharedRateLimiter = Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock>>;
 
+const FINNHUB_BASE_URL: &str = "https://finnhub.io/api/v1";
+
 /// Finnhub news article
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FinnhubNewsArticle {
@@ -38,6 +44,7 @@ pub struct FinnhubNewsArticle {
 pub struct FinnhubClient {
     client: Client,
     api_key: String,
+    base_url: String,
     rate_limiter: SharedRateLimiter,
 }
 
@@ -55,10 +62,22 @@ impl FinnhubClient {
         Self {
             client: Client::new(),
             api_key: api_key.into(),
+            base_url: FINNHUB_BASE_URL.to_string(),
             rate_limiter,
         }
     }
 
+    /// Send requests to `base_url` (e.g. a mirror or a mock server)
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Circuit breaker shared by every client of this endpoint
+    pub fn breaker(&self) -> Arc<CircuitBreaker> {
+        circuit::breaker("Finnhub", &self.base_url)
+    }
+
     /// Get company news for a specific symbol
     ///
     /// # Arguments
@@ -73,24 +92,20 @@ impl FinnhubClient {
     ) -> Result<Vec<FinnhubNewsArticle>> {
         self.rate_limiter.until_ready().await;
 
-        let url = format!(
-            "https://finnhub.io/api/v1/company-news?symbol={}&from={}&to={}&token={}",
-            symbol, from, to, self.api_key
-        );
+        let url = format!("{}/company-news", self.base_url);
 
-        let response = self
-            .client
-            .get(&url)
-            .send()
-            .await
-            .map_err(|e| StockError::ApiError(format!("Finnhub request failed: {e}")))?;
+        let request = self.client.get(&url).query(&[
+            ("symbol", symbol),
+            ("from", from),
+            ("to", to),
+            ("token", &self.api_key),
+        ]);
+        let response = self.breaker().send(request).await?;
 
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
-            return Err(StockError::ApiError(format!(
-                "Finnhub API error {status}: {body}"
-            )));
+            return Err(http_error("Finnhub", status, &body));
         }
 
         response
@@ -106,24 +121,18 @@ impl FinnhubClient {
     pub async fn get_market_news(&self, category: &str) -> Result<Vec<FinnhubNewsArticle>> {
         self.rate_limiter.until_ready().await;
 
-        let url = format!(
-            "https://finnhub.io/api/v1/news?category={}&token={}",
-            category, self.api_key
-        );
+        let url = format!("{}/news", self.base_url);
 
-        let response = self
+        let request = self
             .client
             .get(&url)
-            .send()
-            .await
-            .map_err(|e| StockError::ApiError(format!("Finnhub request failed: {e}")))?;
+            .query(&[("category", category), ("token", &self.api_key)]);
+        let response = self.breaker().send(request).await?;
 
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
-            return Err(StockError::ApiError(format!(
-                "Finnhub API error {status}: {body}"
-            )));
+            return Err(http_error("Finnhub", status, &body));
         }
 
         response
@@ -131,11 +140,166 @@ impl FinnhubClient {
             .await
             .map_err(|e| StockError::ApiError(format!("Failed to parse Finnhub response: {e}")))
     }
+
+    /// Get the earnings calendar between two dates
+    ///
+    /// # Arguments
+    /// * `from` - Start date (YYYY-MM-DD)
+    /// * `to` - End date (YYYY-MM-DD)
+    /// * `symbol` - Only this symbol's reports; every company's when `None`
+    pub async fn get_earnings_calendar(
+        &self,
+        from: &str,
+        to: &str,
+        symbol: Option<&str>,
+    ) -> Result<Vec<EarningsEvent>> {
+        self.rate_limiter.until_ready().await;
+
+        let url = format!("{}/calendar/earnings", self.base_url);
+        let mut query = vec![("from", from), ("to", to), ("token", self.api_key.as_str())];
+        if let Some(symbol) = symbol {
+            query.push(("symbol", symbol));
+        }
+
+        let request = self.client.get(&url).query(&query);
+        let response = self.breaker().send(request).await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(http_error("Finnhub", status, &body));
+        }
+
+        let body = response
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| StockError::ApiError(format!("Failed to parse Finnhub response: {e}")))?;
+        EarningsEvent::from_finnhub(&body)
+    }
+
+    /// Get ESG scores for a specific symbol (premium endpoint)
+    pub async fn get_esg_scores(&self, symbol: &str) -> Result<EsgScores> {
+        self.rate_limiter.until_ready().await;
+
+        let url = format!("{}/stock/esg", self.base_url);
+
+        let request = self
+            .client
+            .get(&url)
+            .query(&[("symbol", symbol), ("token", &self.api_key)]);
+        let response = self.breaker().send(request).await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(http_error("Finnhub", status, &body));
+        }
+
+        let body = response
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| StockError::ApiError(format!("Failed to parse Finnhub response: {e}")))?;
+        EsgScores::from_finnhub(symbol, &body)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::api::testing::{MockApi, fixtures};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_contract_parsing() {
+        let api = MockApi::recorded().await;
+        let client = api.finnhub(60);
+
+        let news = client
+            .get_company_news("AAPL", "2024-03-01", "2024-03-14")
+            .await
+            .unwrap();
+        assert_eq!(news.len(), 2);
+        assert_eq!(news[0].source, "Reuters");
+        assert_eq!(news[0].datetime, 1_710_340_200);
+
+        let requests = api.server().received_requests().await.unwrap();
+        let query = requests[0].url.query().unwrap();
+        assert!(query.contains("symbol=AAPL") && query.contains("from=2024-03-01"));
+
+        assert_eq!(client.get_market_news("general").await.unwrap().len(), 2);
+
+        let calendar = client
+            .get_earnings_calendar("2024-04-22", "2024-04-26", Some("MSFT"))
+            .await
+            .unwrap();
+        assert_eq!(calendar.len(), 3);
+        let requests = api.server().received_requests().await.unwrap();
+        let query = requests.last().unwrap().url.query().unwrap();
+        assert!(
+            query.contains("symbol=MSFT") && query.contains("to=2024-04-26"),
+            "{query}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_contract_error_mapping() {
+        let api = MockApi::start().await;
+        api.mount_json(
+            "/api/v1/company-news",
+            429,
+            fixtures::FINNHUB_ERROR_RATE_LIMIT,
+        )
+        .await;
+        api.mount_json("/api/v1/news", 401, r#"{"error":"Invalid API key"}"#)
+            .await;
+        let client = api.finnhub(60);
+
+        let err = client
+            .get_company_news("AAPL", "2024-03-01", "2024-03-14")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, StockError::RateLimitExceeded { .. }), "{err}");
+
+        let err = client.get_market_news("general").await.unwrap_err();
+        assert!(err.to_string().contains("Invalid API key"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_contract_rate_limiting() {
+        let api = MockApi::recorded().await;
+        let client = api.finnhub(1);
+
+        client.get_market_news("general").await.unwrap();
+        let second = tokio::time::timeout(
+            Duration::from_millis(300),
+            client.get_market_news("general"),
+        )
+        .await;
+        assert!(
+            second.is_err(),
+            "second request should wait for the limiter"
+        );
+        assert_eq!(api.request_count().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_contract_esg_scores() {
+        let api = MockApi::start().await;
+        api.mount_json(
+            "/api/v1/stock/esg",
+            200,
+            r#"{"symbol":"AAPL","totalESGScore":61.35,"environmentScore":79.57,"socialScore":57.98,"governanceScore":46.5}"#,
+        )
+        .await;
+        let client = api.finnhub(60);
+
+        let scores = client.get_esg_scores("AAPL").await.unwrap();
+        assert_eq!(scores.total, Some(61.35));
+        assert!(scores.higher_is_better);
+
+        let requests = api.server().received_requests().await.unwrap();
+        assert!(requests[0].url.query().unwrap().contains("symbol=AAPL"));
+    }
 
     #[test]
     fn test_finnhub_client_creation() {
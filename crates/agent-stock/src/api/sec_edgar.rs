@@ -6,7 +6,14 @@
 //! Rate limit: 10 requests per second (as per SEC fair access policy)
 //! User-Agent requirement: Must include company name and contact email
 
+use super::cik::CikResolver;
+use super::circuit::{self, CircuitBreaker};
+use super::download::Downloader;
+use super::http_cache::HttpCache;
+use super::http_error;
+use super::segments::{RevenueBreakdown, revenue_breakdowns};
 use crate::error::{Result, StockError};
+use chrono::NaiveDate;
 use governor::clock::DefaultClock;
 use governor::state::{InMemoryState, NotKeyed};
 use governor::{Quota, RateLimiter};
@@ -19,6 +26,8 @@ type SharedRateLimiter = Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock>>
 
 const SEC_BASE_URL: &str = "https://data.sec.gov";
 const SEC_COMPANY_TICKERS_URL: &str = "https://www.sec.gov/files/company_tickers.json";
+const SEC_ARCHIVES_URL: &str = "https://www.sec.gov/Archives";
+const SEC_SEARCH_URL: &str = "https://efts.sec.gov/LATEST/search-index";
 
 /// SEC filing type
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -111,12 +120,144 @@ pub struct FinancialData {
     pub gross_profit: Option<f64>,
     /// Operating Cash Flow
     pub operating_cash_flow: Option<f64>,
+    /// Capital Expenditure (purchases of property, plant and equipment)
+    #[serde(default)]
+    pub capital_expenditure: Option<f64>,
+    /// Free Cash Flow (operating cash flow minus capital expenditure)
+    #[serde(default)]
+    pub free_cash_flow: Option<f64>,
+    /// Research and Development expense
+    #[serde(default)]
+    pub research_and_development: Option<f64>,
+    /// Selling, General and Administrative expense
+    #[serde(default)]
+    pub selling_general_admin: Option<f64>,
+    /// Common shares outstanding at period end
+    #[serde(default)]
+    pub shares_outstanding: Option<f64>,
+    /// Weighted average diluted shares over the period
+    #[serde(default)]
+    pub diluted_shares: Option<f64>,
+    /// Depreciation and Amortization
+    #[serde(default)]
+    pub depreciation: Option<f64>,
+    /// Total Current Assets
+    #[serde(default)]
+    pub current_assets: Option<f64>,
+    /// Total Current Liabilities
+    #[serde(default)]
+    pub current_liabilities: Option<f64>,
+    /// Accounts Receivable, net
+    #[serde(default)]
+    pub accounts_receivable: Option<f64>,
+    /// Inventory, net
+    #[serde(default)]
+    pub inventory: Option<f64>,
+    /// Property, Plant and Equipment, net
+    #[serde(default)]
+    pub property_plant_equipment: Option<f64>,
+    /// Long-term Debt, noncurrent
+    #[serde(default)]
+    pub long_term_debt: Option<f64>,
     /// Fiscal year
     pub fiscal_year: String,
-    /// Fiscal quarter (if quarterly)
+    /// Fiscal quarter ("Q1".."Q4"), None for full-year figures
     pub fiscal_quarter: Option<String>,
+    /// First day of the period, "YYYY-MM-DD"
+    #[serde(default)]
+    pub period_start: Option<String>,
+    /// Last day of the period, "YYYY-MM-DD"
+    #[serde(default)]
+    pub period_end: String,
     /// Filing date
     pub filing_date: String,
+    /// Whether values were derived by differencing year-to-date totals
+    #[serde(default)]
+    pub derived: bool,
+}
+
+/// Filing matched by a full-text search
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FullTextHit {
+    /// Company name
+    pub company: String,
+    /// Ticker symbol, when the company has one
+    pub ticker: Option<String>,
+    /// Central Index Key (CIK), without leading zeros
+    pub cik: String,
+    /// Filing type (10-K, 10-Q, 8-K, etc.)
+    pub form: String,
+    /// Filing date
+    pub filing_date: String,
+    /// Period covered, if any
+    pub period_ending: Option<String>,
+    /// Accession number
+    pub accession_number: String,
+    /// Document the match was found in
+    pub document: String,
+}
+
+/// Result of a full-text search
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FullTextSearchResults {
+    /// Number of matching documents (may be more than `hits`)
+    pub total: u64,
+    /// First page of matching documents, most relevant first
+    pub hits: Vec<FullTextHit>,
+}
+
+impl FullTextSearchResults {
+    /// Parse an EDGAR full-text search response
+    pub fn from_response(body: &serde_json::Value) -> Result<Self> {
+        let hits = body["hits"]["hits"].as_array().ok_or_else(|| {
+            StockError::ApiError("Invalid SEC full-text search response".to_string())
+        })?;
+
+        let hits = hits
+            .iter()
+            .filter_map(|hit| {
+                let source = &hit["_source"];
+                // _id is "{accession number}:{document}"
+                let (accession_number, document) = hit["_id"].as_str()?.split_once(':')?;
+                // display names look like "Apple Inc.  (AAPL)  (CIK 0000320193)"
+                let display_name = source["display_names"][0].as_str().unwrap_or_default();
+                let company = display_name
+                    .split("  (")
+                    .next()
+                    .unwrap_or(display_name)
+                    .trim()
+                    .to_string();
+                let ticker = display_name
+                    .split("  (")
+                    .nth(1)
+                    .filter(|part| !part.starts_with("CIK"))
+                    .map(|part| part.trim_end_matches(')').to_string());
+
+                Some(FullTextHit {
+                    company,
+                    ticker,
+                    cik: source["ciks"][0]
+                        .as_str()
+                        .unwrap_or_default()
+                        .trim_start_matches('0')
+                        .to_string(),
+                    form: source["form"].as_str().unwrap_or_default().to_string(),
+                    filing_date: source["file_date"].as_str().unwrap_or_default().to_string(),
+                    period_ending: source["period_ending"]
+                        .as_str()
+                        .filter(|date| !date.is_empty())
+                        .map(ToString::to_string),
+                    accession_number: accession_number.to_string(),
+                    document: document.to_string(),
+                })
+            })
+            .collect();
+
+        Ok(Self {
+            total: body["hits"]["total"]["value"].as_u64().unwrap_or_default(),
+            hits,
+        })
+    }
 }
 
 /// Company facts response from SEC
@@ -161,7 +302,9 @@ pub struct RecentFilings {
     pub primary_document: Vec<String>,
     pub primary_doc_description: Vec<Option<String>>,
     pub size: Vec<Option<u64>>,
+    #[serde(rename = "isXBRL")]
     pub is_xbrl: Vec<i32>,
+    #[serde(rename = "isInlineXBRL")]
     pub is_inline_xbrl: Vec<i32>,
 }
 
@@ -169,7 +312,14 @@ pub struct RecentFilings {
 pub struct SecEdgarClient {
     client: Client,
     user_agent: String,
+    base_url: String,
+    tickers_url: String,
+    archives_url: String,
+    search_url: String,
     rate_limiter: SharedRateLimiter,
+    cik_resolver: Arc<CikResolver>,
+    http_cache: Arc<HttpCache>,
+    downloader: Downloader,
 }
 
 impl SecEdgarClient {
@@ -184,21 +334,9 @@ impl SecEdgarClient {
     /// let client = SecEdgarClient::new("MyApp", "contact@example.com");
     /// ```
     pub fn new(company_name: impl Into<String>, contact_email: impl Into<String>) -> Self {
-        let user_agent = format!(
-            "{} ({})",
-            company_name.into(),
-            contact_email.into()
-        );
-        
-        // SEC allows 10 requests per second
-        let quota = Quota::per_second(NonZeroU32::new(10).unwrap());
-        let rate_limiter = Arc::new(RateLimiter::direct(quota));
+        let user_agent = format!("{} ({})", company_name.into(), contact_email.into());
 
-        Self {
-            client: Client::new(),
-            user_agent,
-            rate_limiter,
-        }
+        Self::with_user_agent(user_agent)
     }
 
     /// Create from environment variables
@@ -206,54 +344,92 @@ impl SecEdgarClient {
     pub fn from_env() -> Self {
         let user_agent = std::env::var("SEC_USER_AGENT")
             .unwrap_or_else(|_| "agent-stock (agent-stock@example.com)".to_string());
-        
+
+        Self::with_user_agent(user_agent)
+    }
+
+    fn with_user_agent(user_agent: String) -> Self {
+        // SEC allows 10 requests per second
         let quota = Quota::per_second(NonZeroU32::new(10).unwrap());
         let rate_limiter = Arc::new(RateLimiter::direct(quota));
 
         Self {
             client: Client::new(),
             user_agent,
+            base_url: SEC_BASE_URL.to_string(),
+            tickers_url: SEC_COMPANY_TICKERS_URL.to_string(),
+            archives_url: SEC_ARCHIVES_URL.to_string(),
+            search_url: SEC_SEARCH_URL.to_string(),
             rate_limiter,
+            cik_resolver: CikResolver::shared(),
+            http_cache: HttpCache::shared(),
+            downloader: Downloader::from_env(),
         }
     }
 
-    /// Get CIK number from stock ticker
-    pub async fn get_cik(&self, ticker: &str) -> Result<String> {
-        self.rate_limiter.until_ready().await;
+    /// Send all requests to `base_url` (e.g. a mirror or a mock server)
+    ///
+    /// data.sec.gov paths are served from `base_url`, the ticker map from
+    /// `{base_url}/files/company_tickers.json`, filing documents from
+    /// `{base_url}/Archives` and full-text search from
+    /// `{base_url}/LATEST/search-index`. The ticker map gets a resolver of
+    /// its own, kept in memory without the bundled fallback, and responses
+    /// get an HTTP cache of their own.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        let base_url = base_url.into().trim_end_matches('/').to_string();
+        self.tickers_url = format!("{base_url}/files/company_tickers.json");
+        self.archives_url = format!("{base_url}/Archives");
+        self.search_url = format!("{base_url}/LATEST/search-index");
+        self.base_url = base_url;
+        self.cik_resolver = Arc::new(CikResolver::new().without_snapshot());
+        self.http_cache = Arc::new(HttpCache::default());
+        self
+    }
 
-        let response = self
-            .client
-            .get(SEC_COMPANY_TICKERS_URL)
-            .header("User-Agent", &self.user_agent)
-            .send()
-            .await
-            .map_err(|e| StockError::ApiError(format!("SEC request failed: {e}")))?;
+    /// Circuit breaker shared by every client of this endpoint
+    pub fn breaker(&self) -> Arc<CircuitBreaker> {
+        circuit::breaker("SEC EDGAR", &self.base_url)
+    }
 
-        if !response.status().is_success() {
-            return Err(StockError::ApiError(format!(
-                "SEC API error: {}",
-                response.status()
-            )));
-        }
+    /// Keep filing documents with `downloader` instead of one configured by
+    /// [`DOWNLOAD_DIR_ENV`](super::download::DOWNLOAD_DIR_ENV)
+    pub fn with_downloader(mut self, downloader: Downloader) -> Self {
+        self.downloader = downloader;
+        self
+    }
 
-        let data: serde_json::Value = response.json().await
-            .map_err(|e| StockError::ApiError(format!("Failed to parse SEC response: {e}")))?;
+    /// Resolve tickers with `resolver` instead of the shared one
+    pub fn with_cik_resolver(mut self, resolver: Arc<CikResolver>) -> Self {
+        self.cik_resolver = resolver;
+        self
+    }
 
-        // Search for ticker in company list
-        let ticker_upper = ticker.to_uppercase();
-        if let Some(companies) = data.as_object() {
-            for (_, company) in companies {
-                if let Some(company_ticker) = company.get("ticker").and_then(|t| t.as_str()) {
-                    if company_ticker.to_uppercase() == ticker_upper {
-                        if let Some(cik) = company.get("cik_str").and_then(|c| c.as_str()) {
-                            return Ok(cik.to_string());
-                        }
-                    }
-                }
-            }
-        }
+    /// Get CIK number from stock ticker
+    ///
+    /// The ticker map is cached and refreshed daily by the client's
+    /// [`CikResolver`], shared by default across all clients.
+    pub async fn resolve_cik(&self, ticker: &str) -> Result<String> {
+        let company = self
+            .cik_resolver
+            .resolve(ticker, || self.fetch_ticker_map())
+            .await?;
+        Ok(company.cik)
+    }
+
+    /// Download the full ticker map (`company_tickers.json`)
+    ///
+    /// Revalidated against the cached copy, so an unchanged map costs a 304.
+    async fn fetch_ticker_map(&self) -> Result<String> {
+        self.rate_limiter.until_ready().await;
 
-        Err(StockError::InvalidSymbol(ticker.to_string()))
+        let request = self
+            .client
+            .get(&self.tickers_url)
+            .header("User-Agent", &self.user_agent);
+        let body = self.http_cache.get(&self.breaker(), "SEC", request).await?;
+
+        String::from_utf8(body.to_vec())
+            .map_err(|e| StockError::ApiError(format!("Failed to read SEC response: {e}")))
     }
 
     /// Get company submissions (filing history)
@@ -262,25 +438,13 @@ impl SecEdgarClient {
 
         // Pad CIK to 10 digits
         let cik_padded = format!("{:0>10}", cik.trim_start_matches('0'));
-        
-        let url = format!("{SEC_BASE_URL}/submissions/CIK{cik_padded}.json");
 
-        let response = self
-            .client
-            .get(&url)
-            .header("User-Agent", &self.user_agent)
-            .send()
-            .await
-            .map_err(|e| StockError::ApiError(format!("SEC request failed: {e}")))?;
+        let url = format!("{}/submissions/CIK{cik_padded}.json", self.base_url);
 
-        if !response.status().is_success() {
-            return Err(StockError::ApiError(format!(
-                "SEC API error: {}",
-                response.status()
-            )));
-        }
+        let request = self.client.get(&url).header("User-Agent", &self.user_agent);
+        let body = self.http_cache.get(&self.breaker(), "SEC", request).await?;
 
-        let submissions: CompanySubmissions = response.json().await
+        let submissions: CompanySubmissions = serde_json::from_slice(&body)
             .map_err(|e| StockError::ApiError(format!("Failed to parse SEC response: {e}")))?;
 
         Ok(submissions)
@@ -299,9 +463,9 @@ impl SecEdgarClient {
         let mut filings = Vec::new();
         let limit = limit.unwrap_or(10);
 
-        for i in 0..recent.accession_number.len().min(limit * 2) {
+        for i in 0..recent.accession_number.len() {
             let form = &recent.form[i];
-            
+
             // Filter by form type if specified
             if let Some(ref ft) = form_type {
                 if form != ft.as_str() {
@@ -313,7 +477,8 @@ impl SecEdgarClient {
                 accession_number: recent.accession_number[i].clone(),
                 form_type: form.clone(),
                 filing_date: recent.filing_date[i].clone(),
-                report_date: recent.report_date[i].clone(),
+                // Filings without a period (e.g. 8-K) have an empty report date
+                report_date: recent.report_date[i].clone().filter(|d| !d.is_empty()),
                 primary_document: recent.primary_document[i].clone(),
                 primary_doc_description: recent.primary_doc_description[i].clone(),
                 size: recent.size[i],
@@ -334,122 +499,38 @@ impl SecEdgarClient {
         self.rate_limiter.until_ready().await;
 
         let cik_padded = format!("{:0>10}", cik.trim_start_matches('0'));
-        let url = format!("{SEC_BASE_URL}/api/xbrl/companyfacts/CIK{cik_padded}.json");
-
-        let response = self
-            .client
-            .get(&url)
-            .header("User-Agent", &self.user_agent)
-            .send()
-            .await
-            .map_err(|e| StockError::ApiError(format!("SEC request failed: {e}")))?;
+        let url = format!(
+            "{}/api/xbrl/companyfacts/CIK{cik_padded}.json",
+            self.base_url
+        );
 
-        if !response.status().is_success() {
-            return Err(StockError::ApiError(format!(
-                "SEC API error: {}",
-                response.status()
-            )));
-        }
+        let request = self.client.get(&url).header("User-Agent", &self.user_agent);
+        let body = self.http_cache.get(&self.breaker(), "SEC", request).await?;
 
-        let facts: CompanyFacts = response.json().await
+        let facts: CompanyFacts = serde_json::from_slice(&body)
             .map_err(|e| StockError::ApiError(format!("Failed to parse SEC response: {e}")))?;
 
         Ok(facts)
     }
 
-    /// Extract financial data from company facts
+    /// Extract annual and quarterly financial data from company facts
+    ///
+    /// See [`xbrl`](super::xbrl) for how concepts and periods are aligned.
     pub fn extract_financial_data(
         &self,
         facts: &CompanyFacts,
         years: Option<u32>,
     ) -> Result<Vec<FinancialData>> {
-        let us_gaap = facts.facts.us_gaap.as_ref().ok_or_else(|| {
-            StockError::ApiError("No US-GAAP data available".to_string())
-        })?;
-
-        let mut financials = Vec::new();
-        let years_limit = years.unwrap_or(5) as usize;
-
-        // Helper to extract values from XBRL
-        let extract_values = |concept: &str| -> Vec<(String, f64, String, Option<String>)> {
-            let mut values = Vec::new();
-            if let Some(concept_data) = us_gaap.get(concept) {
-                if let Some(units) = concept_data.get("units") {
-                    // Try USD first
-                    let unit_data = units.get("USD")
-                        .or_else(|| units.get("USD/shares"))
-                        .or_else(|| units.get("shares"));
-                    
-                    if let Some(entries) = unit_data.and_then(|u| u.as_array()) {
-                        for entry in entries {
-                            if let (Some(val), Some(fy), Some(filed)) = (
-                                entry.get("val").and_then(serde_json::Value::as_f64),
-                                entry.get("fy").and_then(serde_json::Value::as_i64),
-                                entry.get("filed").and_then(|f| f.as_str()),
-                            ) {
-                                let fp = entry.get("fp").and_then(|f| f.as_str())
-                                    .map(std::string::ToString::to_string);
-                                values.push((fy.to_string(), val, filed.to_string(), fp));
-                            }
-                        }
-                    }
-                }
-            }
-            values
-        };
-
-        // Extract key financial metrics
-        let revenues = extract_values("Revenues");
-        let net_incomes = extract_values("NetIncomeLoss");
-        let eps_basic_vals = extract_values("EarningsPerShareBasic");
-        let eps_diluted_vals = extract_values("EarningsPerShareDiluted");
-        let total_assets_vals = extract_values("Assets");
-        let total_liabilities_vals = extract_values("Liabilities");
-        let equity_vals = extract_values("StockholdersEquity");
-        let operating_income_vals = extract_values("OperatingIncomeLoss");
-
-        // Group by fiscal year/quarter
-        let mut seen_periods: std::collections::HashSet<String> = std::collections::HashSet::new();
-        
-        for (fy, revenue, filed, fp) in &revenues {
-            let period_key = format!("{fy}-{fp:?}");
-            if seen_periods.contains(&period_key) {
-                continue;
-            }
-            seen_periods.insert(period_key);
-
-            // Find matching values for this period
-            let find_match = |vals: &[(String, f64, String, Option<String>)]| -> Option<f64> {
-                vals.iter()
-                    .find(|(y, _, f, q)| y == fy && f == filed && q == fp)
-                    .map(|(_, v, _, _)| *v)
-            };
-
-            financials.push(FinancialData {
-                revenue: Some(*revenue),
-                net_income: find_match(&net_incomes),
-                eps_basic: find_match(&eps_basic_vals),
-                eps_diluted: find_match(&eps_diluted_vals),
-                total_assets: find_match(&total_assets_vals),
-                total_liabilities: find_match(&total_liabilities_vals),
-                stockholders_equity: find_match(&equity_vals),
-                operating_income: find_match(&operating_income_vals),
-                gross_profit: None, // Often needs calculation
-                operating_cash_flow: None, // In different taxonomy
-                fiscal_year: fy.clone(),
-                fiscal_quarter: fp.clone(),
-                filing_date: filed.clone(),
-            });
-
-            if financials.len() >= years_limit * 4 {
-                break;
-            }
-        }
-
-        // Sort by date (most recent first)
-        financials.sort_by(|a, b| b.filing_date.cmp(&a.filing_date));
-
-        Ok(financials)
+        let us_gaap = facts
+            .facts
+            .us_gaap
+            .as_ref()
+            .ok_or_else(|| StockError::ApiError("No US-GAAP data available".to_string()))?;
+
+        Ok(super::xbrl::financial_data(
+            us_gaap,
+            years.unwrap_or(5) as usize,
+        ))
     }
 
     /// Get financial data for a ticker symbol
@@ -458,17 +539,145 @@ impl SecEdgarClient {
         ticker: &str,
         years: Option<u32>,
     ) -> Result<Vec<FinancialData>> {
-        let cik = self.get_cik(ticker).await?;
+        let cik = self.resolve_cik(ticker).await?;
         let facts = self.get_company_facts(&cik).await?;
         self.extract_financial_data(&facts, years)
     }
 
+    /// Get financial data for a ticker symbol as reported by the end of `date`
+    ///
+    /// See [`xbrl::known_at`](super::xbrl::known_at).
+    pub async fn get_financial_data_at(
+        &self,
+        ticker: &str,
+        years: Option<u32>,
+        date: NaiveDate,
+    ) -> Result<Vec<FinancialData>> {
+        let cik = self.resolve_cik(ticker).await?;
+        let facts = self.get_company_facts(&cik).await?;
+        let us_gaap = facts
+            .facts
+            .us_gaap
+            .as_ref()
+            .ok_or_else(|| StockError::ApiError("No US-GAAP data available".to_string()))?;
+
+        let known = super::xbrl::known_at(us_gaap, date);
+        Ok(super::xbrl::financial_data(
+            &known,
+            years.unwrap_or(5) as usize,
+        ))
+    }
+
+    /// Get the raw contents (usually HTML) of a filing document
+    ///
+    /// Filed documents never change, so they are downloaded once with the
+    /// client's [`Downloader`] (resuming interrupted transfers) and read from
+    /// disk afterwards.
+    pub async fn get_filing_document(
+        &self,
+        cik: &str,
+        accession_number: &str,
+        document: &str,
+    ) -> Result<String> {
+        let url = self.get_filing_url(cik, accession_number, document);
+        let name = std::path::Path::new("sec")
+            .join(cik.trim_start_matches('0'))
+            .join(accession_number.replace('-', ""))
+            .join(document);
+
+        self.rate_limiter.until_ready().await;
+        let request = self.client.get(&url).header("User-Agent", &self.user_agent);
+        let path = self
+            .downloader
+            .fetch(&self.breaker(), request, name, None)
+            .await?;
+
+        let contents = tokio::fs::read(&path)
+            .await
+            .map_err(|e| StockError::ApiError(format!("Failed to read SEC filing: {e}")))?;
+        Ok(String::from_utf8_lossy(&contents).into_owned())
+    }
+
+    /// Get revenue by segment, region and product from the latest 10-K
+    ///
+    /// Returns the filing together with the breakdowns parsed from its inline
+    /// XBRL; see [`segments`](super::segments).
+    pub async fn get_revenue_breakdowns(
+        &self,
+        ticker: &str,
+    ) -> Result<(SecFiling, Vec<RevenueBreakdown>)> {
+        let cik = self.resolve_cik(ticker).await?;
+        let filing = self
+            .get_filings(&cik, Some(FilingType::Form10K), Some(1))
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| StockError::data_unavailable(ticker, "no 10-K filings"))?;
+        if !filing.is_inline_xbrl {
+            return Err(StockError::data_unavailable(
+                ticker,
+                format!("10-K filed {} is not inline XBRL", filing.filing_date),
+            ));
+        }
+
+        let document = self
+            .get_filing_document(&cik, &filing.accession_number, &filing.primary_document)
+            .await?;
+        Ok((filing, revenue_breakdowns(&document)))
+    }
+
+    /// Search the text of filings for an exact phrase
+    ///
+    /// Covers filings since 2001. `forms` restricts the search to filing
+    /// types such as "10-Q"; dates bound the filing date.
+    pub async fn full_text_search(
+        &self,
+        phrase: &str,
+        forms: &[String],
+        start: Option<NaiveDate>,
+        end: Option<NaiveDate>,
+    ) -> Result<FullTextSearchResults> {
+        self.rate_limiter.until_ready().await;
+
+        let mut query = vec![("q", format!("\"{}\"", phrase.trim().trim_matches('"')))];
+        if !forms.is_empty() {
+            query.push(("forms", forms.join(",")));
+        }
+        if start.is_some() || end.is_some() {
+            query.push(("dateRange", "custom".to_string()));
+        }
+        if let Some(start) = start {
+            query.push(("startdt", start.to_string()));
+        }
+        if let Some(end) = end {
+            query.push(("enddt", end.to_string()));
+        }
+
+        let request = self
+            .client
+            .get(&self.search_url)
+            .header("User-Agent", &self.user_agent)
+            .query(&query);
+        let response = self.breaker().send(request).await?;
+
+        if !response.status().is_success() {
+            return Err(http_error("SEC", response.status(), ""));
+        }
+
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| StockError::ApiError(format!("Failed to parse SEC response: {e}")))?;
+        FullTextSearchResults::from_response(&body)
+    }
+
     /// Build URL to access a filing document
     pub fn get_filing_url(&self, cik: &str, accession_number: &str, document: &str) -> String {
         let cik_padded = format!("{:0>10}", cik.trim_start_matches('0'));
         let accession_clean = accession_number.replace('-', "");
         format!(
-            "https://www.sec.gov/Archives/edgar/data/{cik_padded}/{accession_clean}/{document}"
+            "{}/edgar/data/{cik_padded}/{accession_clean}/{document}",
+            self.archives_url
         )
     }
 }
@@ -476,6 +685,115 @@ impl SecEdgarClient {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::api::testing::{MockApi, fixtures};
+
+    #[tokio::test]
+    async fn test_contract_parsing() {
+        let api = MockApi::recorded().await;
+        let client = api.sec();
+
+        // cik_str is a number in the published ticker map
+        let cik = client.resolve_cik("aapl").await.unwrap();
+        assert_eq!(cik, "320193");
+
+        let filings = client.get_filings(&cik, None, Some(10)).await.unwrap();
+        assert_eq!(filings.len(), 4);
+        assert!(filings[0].is_xbrl && filings[0].is_inline_xbrl);
+        assert_eq!(filings[3].form_type, "SC 13G/A");
+        assert_eq!(filings[3].report_date, None);
+
+        let annual = client
+            .get_filings(&cik, Some(FilingType::Form10K), Some(1))
+            .await
+            .unwrap();
+        assert_eq!(annual[0].primary_document, "aapl-20230930.htm");
+        assert_eq!(
+            client.get_filing_url(&cik, &annual[0].accession_number, "aapl-20230930.htm"),
+            format!(
+                "{}/Archives/edgar/data/0000320193/000032019323000106/aapl-20230930.htm",
+                api.uri()
+            )
+        );
+
+        let financials = client.get_financial_data("AAPL", None).await.unwrap();
+        assert_eq!(financials[0].fiscal_year, "2023");
+        assert_eq!(financials[0].revenue, Some(383_285_000_000.0));
+        assert_eq!(financials[0].eps_diluted, Some(6.13));
+    }
+
+    #[tokio::test]
+    async fn test_contract_filing_document() {
+        let api = MockApi::start().await;
+        api.mount_json(
+            "/Archives/edgar/data/0000320193/000032019323000106/aapl-20230930.htm",
+            200,
+            "<html>Annual report on Form 10-K</html>",
+        )
+        .await;
+        let client = api.sec();
+
+        let document = client
+            .get_filing_document("320193", "0000320193-23-000106", "aapl-20230930.htm")
+            .await
+            .unwrap();
+        assert!(document.contains("Form 10-K"));
+
+        let err = client
+            .get_filing_document("320193", "0000320193-23-000107", "missing.htm")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("404"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_contract_revenue_breakdowns() {
+        let api = MockApi::recorded().await;
+        api.mount_json(
+            "/Archives/edgar/data/0000320193/000032019323000106/aapl-20230930.htm",
+            200,
+            fixtures::SEC_10K_AAPL_SEGMENTS,
+        )
+        .await;
+        let client = api.sec();
+
+        let (filing, breakdowns) = client.get_revenue_breakdowns("AAPL").await.unwrap();
+        assert_eq!(filing.form_type, "10-K");
+        assert_eq!(filing.report_date.as_deref(), Some("2023-09-30"));
+        assert_eq!(breakdowns.len(), 3);
+        assert_eq!(breakdowns[0].axis, crate::api::BreakdownAxis::Segment);
+    }
+
+    #[tokio::test]
+    async fn test_contract_error_mapping() {
+        let api = MockApi::recorded().await;
+        let client = api.sec();
+
+        let err = client.resolve_cik("NOPE").await.unwrap_err();
+        assert!(matches!(err, StockError::InvalidSymbol(_)), "{err}");
+
+        let err = client.get_company_facts("1").await.unwrap_err();
+        assert!(err.to_string().contains("404"), "{err}");
+
+        let api = MockApi::start().await;
+        api.mount_json("/files/company_tickers.json", 429, "{}")
+            .await;
+        let err = api.sec().resolve_cik("AAPL").await.unwrap_err();
+        assert!(matches!(err, StockError::RateLimitExceeded { .. }), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_contract_rate_limiting() {
+        let api = MockApi::recorded().await;
+        let client = api.sec();
+
+        // 10 requests per second: the 11th waits for the next slot
+        let start = std::time::Instant::now();
+        for _ in 0..11 {
+            client.get_company_submissions("320193").await.unwrap();
+        }
+        assert!(start.elapsed() >= std::time::Duration::from_millis(80));
+        assert_eq!(api.request_count().await, 11);
+    }
 
     #[test]
     fn test_client_creation() {
@@ -484,6 +802,36 @@ mod tests {
         assert!(client.user_agent.contains("test@example.com"));
     }
 
+    #[tokio::test]
+    async fn test_contract_full_text_search() {
+        let api = MockApi::recorded().await;
+        let client = api.sec();
+
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1);
+        let results = client
+            .full_text_search("supply constraints", &["10-Q".to_string()], start, None)
+            .await
+            .unwrap();
+        assert_eq!(results.total, 2);
+        let apple = &results.hits[0];
+        assert_eq!(apple.company, "Apple Inc.");
+        assert_eq!(apple.ticker.as_deref(), Some("AAPL"));
+        assert_eq!(apple.cik, "320193");
+        assert_eq!(apple.accession_number, "0000320193-24-000069");
+        assert_eq!(apple.document, "aapl-20240330.htm");
+        assert_eq!(apple.period_ending.as_deref(), Some("2024-03-30"));
+        assert_eq!(results.hits[1].company, "Example Holdings LLC");
+        assert_eq!(results.hits[1].ticker, None);
+
+        // The phrase is quoted for an exact match and dates switch to a custom range
+        let requests = api.server().received_requests().await.unwrap();
+        let url = &requests.last().unwrap().url;
+        let query: Vec<_> = url.query_pairs().collect();
+        assert!(query.contains(&("q".into(), "\"supply constraints\"".into())));
+        assert!(query.contains(&("dateRange".into(), "custom".into())));
+        assert!(query.contains(&("startdt".into(), "2024-01-01".into())));
+    }
+
     #[test]
     fn test_filing_type() {
         assert_eq!(FilingType::Form10K.as_str(), "10-K");
@@ -491,11 +839,44 @@ mod tests {
         assert_eq!(FilingType::Form8K.as_str(), "8-K");
     }
 
+    #[tokio::test]
+    async fn test_unchanged_company_facts_are_revalidated() {
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, ResponseTemplate};
+
+        let api = MockApi::start().await;
+        let facts_path = "/api/xbrl/companyfacts/CIK0000320193.json";
+        Mock::given(method("GET"))
+            .and(path(facts_path))
+            .and(header("If-None-Match", "\"facts-2023\""))
+            .respond_with(ResponseTemplate::new(304))
+            .with_priority(1)
+            .expect(1)
+            .mount(api.server())
+            .await;
+        Mock::given(method("GET"))
+            .and(path(facts_path))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("ETag", "\"facts-2023\"")
+                    .set_body_raw(fixtures::SEC_COMPANYFACTS_AAPL, "application/json"),
+            )
+            .expect(1)
+            .mount(api.server())
+            .await;
+        let client = api.sec();
+
+        let first = client.get_company_facts("320193").await.unwrap();
+        let second = client.get_company_facts("320193").await.unwrap();
+        assert_eq!(first.entity_name, second.entity_name);
+        assert_eq!(client.http_cache.stats().revalidated, 1);
+    }
+
     #[tokio::test]
     #[ignore] // Requires network access
-    async fn test_get_cik() {
+    async fn test_resolve_cik() {
         let client = SecEdgarClient::from_env();
-        let cik = client.get_cik("AAPL").await;
+        let cik = client.resolve_cik("AAPL").await;
         assert!(cik.is_ok());
         // Apple's CIK is 320193
         assert_eq!(cik.unwrap(), "320193");
@@ -505,8 +886,10 @@ mod tests {
     #[ignore] // Requires network access
     async fn test_get_filings() {
         let client = SecEdgarClient::from_env();
-        let cik = client.get_cik("AAPL").await.unwrap();
-        let filings = client.get_filings(&cik, Some(FilingType::Form10K), Some(3)).await;
+        let cik = client.resolve_cik("AAPL").await.unwrap();
+        let filings = client
+            .get_filings(&cik, Some(FilingType::Form10K), Some(3))
+            .await;
         assert!(filings.is_ok());
         let filings = filings.unwrap();
         assert!(!filings.is_empty());
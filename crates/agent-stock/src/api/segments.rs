@@ -0,0 +1,488 @@
+//! Segment, geographic and product revenue from inline XBRL filings
+//!
+//! Breakdowns are dimensional facts: a revenue value whose context names a
+//! member of an axis such as `us-gaap:StatementBusinessSegmentsAxis`. The
+//! company facts API drops them, but 10-K documents filed as inline XBRL
+//! carry them as `ix:nonFraction` elements next to the `xbrli:context`
+//! definitions, so they are read from the filing itself.
+//!
+//! Only contexts with a single explicit member are used; cross-tabulations
+//! (segment × product) and consolidation adjustments are skipped. Members
+//! have no labels in the document, so names are derived from the member
+//! identifiers ("aapl:GreaterChinaSegmentMember" → "Greater China").
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::xbrl::REVENUE;
+
+/// Dimension a revenue breakdown is reported along
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BreakdownAxis {
+    /// Operating segments (`us-gaap:StatementBusinessSegmentsAxis`)
+    Segment,
+    /// Countries and regions (`srt:StatementGeographicalAxis`)
+    Geographic,
+    /// Products and services (`srt:ProductOrServiceAxis`)
+    Product,
+}
+
+impl BreakdownAxis {
+    pub const ALL: [Self; 3] = [Self::Segment, Self::Geographic, Self::Product];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Segment => "segment",
+            Self::Geographic => "geographic",
+            Self::Product => "product",
+        }
+    }
+
+    /// Axis for an XBRL dimension name, ignoring its prefix
+    pub fn from_dimension(dimension: &str) -> Option<Self> {
+        match local_name(dimension) {
+            "StatementBusinessSegmentsAxis" => Some(Self::Segment),
+            "StatementGeographicalAxis" => Some(Self::Geographic),
+            "ProductOrServiceAxis" => Some(Self::Product),
+            _ => None,
+        }
+    }
+}
+
+impl std::str::FromStr for BreakdownAxis {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "segment" | "segments" | "business" => Ok(Self::Segment),
+            "geographic" | "geography" | "region" | "country" => Ok(Self::Geographic),
+            "product" | "products" | "service" => Ok(Self::Product),
+            other => Err(format!("Unknown breakdown: {other}")),
+        }
+    }
+}
+
+/// Revenue of one segment, region or product line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RevenueSlice {
+    /// Readable name, e.g. "Greater China"
+    pub name: String,
+    /// XBRL member, e.g. "aapl:GreaterChinaSegmentMember"
+    pub member: String,
+    pub revenue: f64,
+    /// Revenue of the prior fiscal year, when the filing compares
+    pub prior_revenue: Option<f64>,
+    /// Share of total revenue, in percent
+    pub share_pct: Option<f64>,
+    /// Growth over the prior fiscal year, in percent
+    pub growth_pct: Option<f64>,
+}
+
+/// Revenue split along one axis for one fiscal year
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RevenueBreakdown {
+    pub axis: BreakdownAxis,
+    /// Revenue concept the values are tagged with
+    pub concept: String,
+    pub period_start: String,
+    pub period_end: String,
+    /// Consolidated revenue for the period, when tagged
+    pub total_revenue: Option<f64>,
+    /// Largest first
+    pub slices: Vec<RevenueSlice>,
+}
+
+impl RevenueBreakdown {
+    /// Slice whose name or member contains `needle`, case-insensitively
+    pub fn find(&self, needle: &str) -> Option<&RevenueSlice> {
+        let needle = needle.to_lowercase();
+        self.slices.iter().find(|slice| {
+            slice.name.to_lowercase().contains(&needle)
+                || slice.member.to_lowercase().contains(&needle)
+        })
+    }
+}
+
+/// Reporting period of a context
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Period {
+    start: chrono::NaiveDate,
+    end: chrono::NaiveDate,
+}
+
+impl Period {
+    fn is_annual(&self) -> bool {
+        (350..=380).contains(&(self.end - self.start).num_days())
+    }
+}
+
+/// What a context qualifies
+#[derive(Debug, Clone, PartialEq)]
+struct Context {
+    period: Option<Period>,
+    /// Explicit `(dimension, member)` pairs
+    members: Vec<(String, String)>,
+    /// Whether the context also has typed members
+    typed: bool,
+}
+
+/// An element found by [`elements`]
+struct Element<'a> {
+    attrs: &'a str,
+    inner: &'a str,
+}
+
+/// Elements with local name `local`, in any namespace prefix
+///
+/// A small scanner for the well-formed, machine-generated markup of EDGAR
+/// filings; it does not handle comments or CDATA.
+fn elements<'a>(document: &'a str, local: &str) -> Vec<Element<'a>> {
+    let mut found = Vec::new();
+    let mut from = 0;
+    while let Some(offset) = document[from..].find('<') {
+        let start = from + offset + 1;
+        from = start;
+        let rest = &document[start..];
+        let name_end = rest
+            .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+            .unwrap_or(rest.len());
+        let name = &rest[..name_end];
+        if name.is_empty() || !local_name(name).eq_ignore_ascii_case(local) {
+            continue;
+        }
+        let Some(tag_end) = rest.find('>') else {
+            break;
+        };
+        let attrs = &rest[name_end..tag_end];
+        if attrs.ends_with('/') {
+            found.push(Element { attrs, inner: "" });
+            continue;
+        }
+        let body = &rest[tag_end + 1..];
+        if let Some(close) = body.find(&format!("</{name}>")) {
+            found.push(Element {
+                attrs,
+                inner: &body[..close],
+            });
+        }
+    }
+    found
+}
+
+/// Value of attribute `key` in a start tag's attributes
+fn attr<'a>(attrs: &'a str, key: &str) -> Option<&'a str> {
+    let pattern = format!("{key}=\"");
+    let mut from = 0;
+    while let Some(offset) = attrs[from..].find(&pattern) {
+        let start = from + offset;
+        from = start + pattern.len();
+        if start == 0 || attrs[..start].ends_with(char::is_whitespace) {
+            let value = &attrs[from..];
+            return value.find('"').map(|end| &value[..end]);
+        }
+    }
+    None
+}
+
+/// Name without its namespace prefix
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+/// Text content with nested tags removed
+fn text(inner: &str) -> String {
+    let mut text = String::with_capacity(inner.len());
+    let mut in_tag = false;
+    for c in inner.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.trim().to_string()
+}
+
+fn parse_contexts(document: &str) -> HashMap<String, Context> {
+    let date = |inner: &str| chrono::NaiveDate::parse_from_str(&text(inner), "%Y-%m-%d").ok();
+    elements(document, "context")
+        .into_iter()
+        .filter_map(|context| {
+            let id = attr(context.attrs, "id")?.to_string();
+            let start = elements(context.inner, "startDate")
+                .first()
+                .and_then(|e| date(e.inner));
+            let end = elements(context.inner, "endDate")
+                .first()
+                .and_then(|e| date(e.inner));
+            let members = elements(context.inner, "explicitMember")
+                .into_iter()
+                .filter_map(|member| {
+                    Some((
+                        attr(member.attrs, "dimension")?.to_string(),
+                        text(member.inner),
+                    ))
+                })
+                .collect();
+            Some((
+                id,
+                Context {
+                    period: start.zip(end).map(|(start, end)| Period { start, end }),
+                    members,
+                    typed: !elements(context.inner, "typedMember").is_empty(),
+                },
+            ))
+        })
+        .collect()
+}
+
+/// A revenue fact: concept, context id and value
+fn parse_revenue_facts(document: &str) -> Vec<(String, String, f64)> {
+    elements(document, "nonFraction")
+        .into_iter()
+        .filter_map(|fact| {
+            let concept = local_name(attr(fact.attrs, "name")?);
+            if !REVENUE.contains(&concept) {
+                return None;
+            }
+            let context = attr(fact.attrs, "contextRef")?;
+
+            let shown = text(fact.inner);
+            let mut value = if attr(fact.attrs, "format")
+                .is_some_and(|f| f.ends_with("fixed-zero") || f.ends_with("zerodash"))
+                || shown == "—"
+                || shown == "-"
+            {
+                0.0
+            } else {
+                shown.replace([',', ' '], "").parse::<f64>().ok()?
+            };
+            if let Some(scale) = attr(fact.attrs, "scale").and_then(|s| s.parse::<i32>().ok()) {
+                value *= 10f64.powi(scale);
+            }
+            if attr(fact.attrs, "sign") == Some("-") {
+                value = -value;
+            }
+            Some((concept.to_string(), context.to_string(), value))
+        })
+        .collect()
+}
+
+/// Readable name for an XBRL member
+///
+/// "aapl:GreaterChinaSegmentMember" becomes "Greater China"; ISO country
+/// members ("country:CN") become country names.
+pub fn member_name(member: &str) -> String {
+    if let Some(code) = member.strip_prefix("country:") {
+        return country_name(code).map_or_else(|| code.to_string(), ToString::to_string);
+    }
+    let local = local_name(member);
+    let base = ["SegmentMember", "Member", "Segment"]
+        .iter()
+        .find_map(|suffix| local.strip_suffix(suffix))
+        .unwrap_or(local);
+
+    // Split camel case at lower-to-upper transitions, keeping "IPhone" whole
+    let mut name = String::with_capacity(base.len() + 4);
+    let mut previous: Option<char> = None;
+    for c in base.chars() {
+        if c.is_uppercase() && previous.is_some_and(|p| p.is_lowercase() || p.is_ascii_digit()) {
+            name.push(' ');
+        }
+        name.push(c);
+        previous = Some(c);
+    }
+    name
+}
+
+fn country_name(code: &str) -> Option<&'static str> {
+    Some(match code {
+        "US" => "United States",
+        "CN" => "China",
+        "HK" => "Hong Kong",
+        "TW" => "Taiwan",
+        "JP" => "Japan",
+        "KR" => "South Korea",
+        "IN" => "India",
+        "SG" => "Singapore",
+        "DE" => "Germany",
+        "GB" => "United Kingdom",
+        "FR" => "France",
+        "IE" => "Ireland",
+        "NL" => "Netherlands",
+        "CH" => "Switzerland",
+        "CA" => "Canada",
+        "MX" => "Mexico",
+        "BR" => "Brazil",
+        "AU" => "Australia",
+        _ => return None,
+    })
+}
+
+/// `(member, value)` pairs of one period
+type MemberValues = Vec<(String, f64)>;
+
+/// Revenue breakdowns of the latest fiscal year in an inline XBRL document
+///
+/// Returns one breakdown per axis the filing reports, each compared with
+/// the prior fiscal year when the filing includes it.
+pub fn revenue_breakdowns(document: &str) -> Vec<RevenueBreakdown> {
+    let contexts = parse_contexts(document);
+    let facts = parse_revenue_facts(document);
+
+    // Consolidated revenue by concept and period
+    let mut totals: HashMap<(&str, Period), f64> = HashMap::new();
+    // Member values by axis, concept and period
+    let mut sliced: HashMap<(BreakdownAxis, &str), HashMap<Period, MemberValues>> = HashMap::new();
+
+    for (concept, context_id, value) in &facts {
+        let Some(context) = contexts.get(context_id) else {
+            continue;
+        };
+        let Some(period) = context.period.filter(Period::is_annual) else {
+            continue;
+        };
+        match (context.members.as_slice(), context.typed) {
+            ([], false) => {
+                totals.insert((concept.as_str(), period), *value);
+            }
+            ([(dimension, member)], false) => {
+                if let Some(axis) = BreakdownAxis::from_dimension(dimension) {
+                    let members = sliced
+                        .entry((axis, concept.as_str()))
+                        .or_default()
+                        .entry(period)
+                        .or_default();
+                    // Facts repeat across tables; keep one per member
+                    if !members.iter().any(|(known, _)| known == member) {
+                        members.push((member.clone(), *value));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut breakdowns = Vec::new();
+    for axis in BreakdownAxis::ALL {
+        // The most specific revenue concept the filing slices by this axis
+        let Some((concept, periods)) = REVENUE
+            .iter()
+            .find_map(|concept| sliced.get(&(axis, *concept)).map(|p| (*concept, p)))
+        else {
+            continue;
+        };
+        let Some((&period, members)) = periods.iter().max_by_key(|(period, _)| period.end) else {
+            continue;
+        };
+        let prior = periods
+            .iter()
+            .filter(|(p, _)| p.end < period.end && (period.end - p.end).num_days() >= 350)
+            .max_by_key(|(p, _)| p.end)
+            .map(|(_, members)| members);
+        let total_revenue = totals.get(&(concept, period)).copied();
+
+        let mut slices: Vec<RevenueSlice> = members
+            .iter()
+            .map(|(member, revenue)| {
+                let prior_revenue = prior.and_then(|prior| {
+                    prior
+                        .iter()
+                        .find(|(m, _)| m == member)
+                        .map(|(_, value)| *value)
+                });
+                RevenueSlice {
+                    name: member_name(member),
+                    member: member.clone(),
+                    revenue: *revenue,
+                    prior_revenue,
+                    share_pct: total_revenue
+                        .filter(|total| total.abs() > f64::EPSILON)
+                        .map(|total| revenue / total * 100.0),
+                    growth_pct: prior_revenue
+                        .filter(|prior| prior.abs() > f64::EPSILON)
+                        .map(|prior| (revenue - prior) / prior.abs() * 100.0),
+                }
+            })
+            .collect();
+        slices.sort_by(|a, b| b.revenue.total_cmp(&a.revenue));
+
+        breakdowns.push(RevenueBreakdown {
+            axis,
+            concept: concept.to_string(),
+            period_start: period.start.to_string(),
+            period_end: period.end.to_string(),
+            total_revenue,
+            slices,
+        });
+    }
+    breakdowns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::testing::fixtures;
+
+    #[test]
+    fn test_member_name() {
+        assert_eq!(
+            member_name("aapl:GreaterChinaSegmentMember"),
+            "Greater China"
+        );
+        assert_eq!(
+            member_name("aapl:RestOfAsiaPacificSegmentMember"),
+            "Rest Of Asia Pacific"
+        );
+        assert_eq!(member_name("aapl:IPhoneMember"), "IPhone");
+        assert_eq!(member_name("us-gaap:ServiceMember"), "Service");
+        assert_eq!(member_name("country:CN"), "China");
+        assert_eq!(member_name("country:ZZ"), "ZZ");
+    }
+
+    #[test]
+    fn test_attr() {
+        let attrs = r#" unitRef="usd" contextRef="c-20" name="us-gaap:Revenues" scale="6""#;
+        assert_eq!(attr(attrs, "name"), Some("us-gaap:Revenues"));
+        assert_eq!(attr(attrs, "contextRef"), Some("c-20"));
+        assert_eq!(attr(attrs, "Ref"), None);
+        assert_eq!(attr(attrs, "sign"), None);
+    }
+
+    #[test]
+    fn test_revenue_breakdowns() {
+        let breakdowns = revenue_breakdowns(fixtures::SEC_10K_AAPL_SEGMENTS);
+        let axis = |axis: BreakdownAxis| {
+            breakdowns
+                .iter()
+                .find(|b| b.axis == axis)
+                .unwrap_or_else(|| panic!("missing {}", axis.as_str()))
+        };
+
+        let segments = axis(BreakdownAxis::Segment);
+        assert_eq!(segments.period_end, "2023-09-30");
+        assert_eq!(segments.total_revenue, Some(383_285_000_000.0));
+        assert_eq!(segments.slices.len(), 5);
+        assert_eq!(segments.slices[0].name, "Americas");
+
+        let china = segments.find("china").unwrap();
+        assert_eq!(china.revenue, 72_559_000_000.0);
+        assert_eq!(china.prior_revenue, Some(74_200_000_000.0));
+        assert!((china.share_pct.unwrap() - 18.93).abs() < 0.01);
+        assert!((china.growth_pct.unwrap() + 2.21).abs() < 0.01);
+
+        // Country disclosures cover only some countries; the context with a
+        // second dimension is ignored
+        let geographic = axis(BreakdownAxis::Geographic);
+        assert_eq!(geographic.slices.len(), 2);
+        assert_eq!(geographic.slices[0].name, "United States");
+
+        let products = axis(BreakdownAxis::Product);
+        assert_eq!(products.slices[0].name, "IPhone");
+        // Negative-signed and dash values are read, not dropped
+        assert_eq!(products.find("Other").unwrap().revenue, -1_000_000.0);
+        assert_eq!(products.find("Legacy").unwrap().revenue, 0.0);
+    }
+}
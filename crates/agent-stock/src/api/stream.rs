@@ -0,0 +1,617 @@
+//! Real-time quote streaming over WebSocket
+//!
+//! Finnhub and Polygon push trades over a WebSocket rather than being
+//! polled. [`QuoteStreamer`] keeps one connection to the configured
+//! provider, subscribes to the symbols callers are watching and fans the
+//! trades out to a [`QuoteStream`] per caller. The connection is opened for
+//! the first subscription, closed once the last stream is dropped, and
+//! re-established with backoff when the provider drops it.
+//!
+//! Finnhub docs: https://finnhub.io/docs/api/websocket-trades
+//! Polygon docs: https://polygon.io/docs/stocks/ws_stocks_t
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::api::stream::{QuoteStreamer, StreamConfig, StreamProvider};
+//!
+//! let streamer = QuoteStreamer::spawn(StreamConfig::new(StreamProvider::Finnhub, api_key));
+//! let mut aapl = streamer.subscribe("AAPL");
+//! while let Some(tick) = aapl.recv().await {
+//!     println!("{tick}");
+//! }
+//! ```
+
+use chrono::{DateTime, Utc};
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::{broadcast, mpsc};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use crate::config::{POLYGON_API_KEY_ENV, StockConfig};
+use crate::error::{Result, StockError};
+
+const FINNHUB_WS_URL: &str = "wss://ws.finnhub.io";
+const POLYGON_WS_URL: &str = "wss://socket.polygon.io/stocks";
+
+/// Trades buffered per symbol for a slow reader before it skips ahead
+const TICK_BUFFER: usize = 64;
+
+/// Wait before the first reconnection attempt; doubled on each failure
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest wait between reconnection attempts
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// WebSocket trade feed provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamProvider {
+    /// Finnhub trades (free tier covers US stocks)
+    Finnhub,
+    /// Polygon.io trades (needs a plan with real-time stocks data)
+    Polygon,
+}
+
+impl StreamProvider {
+    pub fn name(self) -> &'static str {
+        match self {
+            StreamProvider::Finnhub => "Finnhub",
+            StreamProvider::Polygon => "Polygon",
+        }
+    }
+
+    fn default_url(self) -> &'static str {
+        match self {
+            StreamProvider::Finnhub => FINNHUB_WS_URL,
+            StreamProvider::Polygon => POLYGON_WS_URL,
+        }
+    }
+
+    /// Messages sent right after connecting; Finnhub takes the key in the URL
+    fn auth_messages(self, api_key: &str) -> Vec<String> {
+        match self {
+            StreamProvider::Finnhub => Vec::new(),
+            StreamProvider::Polygon => {
+                vec![json!({ "action": "auth", "params": api_key }).to_string()]
+            }
+        }
+    }
+
+    fn subscribe_message(self, symbol: &str, subscribe: bool) -> String {
+        match self {
+            StreamProvider::Finnhub => {
+                let kind = if subscribe {
+                    "subscribe"
+                } else {
+                    "unsubscribe"
+                };
+                json!({ "type": kind, "symbol": symbol }).to_string()
+            }
+            StreamProvider::Polygon => {
+                let action = if subscribe {
+                    "subscribe"
+                } else {
+                    "unsubscribe"
+                };
+                json!({ "action": action, "params": format!("T.{symbol}") }).to_string()
+            }
+        }
+    }
+
+    /// Trades in a text message from the provider
+    ///
+    /// Pings and status messages carry no trades. Fails on an error message,
+    /// e.g. a rejected API key.
+    pub fn parse_message(self, text: &str) -> Result<Vec<Tick>> {
+        let message: Value = serde_json::from_str(text)?;
+        match self {
+            StreamProvider::Finnhub => parse_finnhub(&message),
+            StreamProvider::Polygon => parse_polygon(&message),
+        }
+    }
+}
+
+impl fmt::Display for StreamProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// `{"type":"trade","data":[{"s":"AAPL","p":189.84,"t":1710426605123,"v":100}]}`
+fn parse_finnhub(message: &Value) -> Result<Vec<Tick>> {
+    match message["type"].as_str() {
+        Some("trade") => Ok(message["data"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|trade| {
+                Some(Tick {
+                    symbol: trade["s"].as_str()?.to_string(),
+                    price: trade["p"].as_f64()?,
+                    volume: trade["v"].as_f64(),
+                    timestamp: DateTime::from_timestamp_millis(trade["t"].as_i64()?)?,
+                })
+            })
+            .collect()),
+        Some("error") => Err(StockError::ApiError(format!(
+            "Finnhub stream error: {}",
+            message["msg"].as_str().unwrap_or("unknown error")
+        ))),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// `[{"ev":"T","sym":"AAPL","p":189.84,"s":100,"t":1710426605123}]`
+fn parse_polygon(message: &Value) -> Result<Vec<Tick>> {
+    let mut ticks = Vec::new();
+    for event in message.as_array().into_iter().flatten() {
+        match event["ev"].as_str() {
+            Some("T") => {
+                let tick = (|| {
+                    Some(Tick {
+                        symbol: event["sym"].as_str()?.to_string(),
+                        price: event["p"].as_f64()?,
+                        volume: event["s"].as_f64(),
+                        timestamp: DateTime::from_timestamp_millis(event["t"].as_i64()?)?,
+                    })
+                })();
+                ticks.extend(tick);
+            }
+            Some("status") if event["status"] == "auth_failed" => {
+                return Err(StockError::ConfigError(format!(
+                    "Polygon rejected the API key (check {POLYGON_API_KEY_ENV}): {}",
+                    event["message"].as_str().unwrap_or("authentication failed")
+                )));
+            }
+            _ => {}
+        }
+    }
+    Ok(ticks)
+}
+
+/// One trade from a quote stream
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tick {
+    pub symbol: String,
+    pub price: f64,
+    /// Shares traded, when the provider reports it
+    pub volume: Option<f64>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl fmt::Display for Tick {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {:.2} @ {}",
+            self.symbol,
+            self.price,
+            self.timestamp.format("%H:%M:%S UTC")
+        )
+    }
+}
+
+/// Where and how to connect for streaming quotes
+#[derive(Debug, Clone)]
+pub struct StreamConfig {
+    pub provider: StreamProvider,
+    pub api_key: String,
+    /// WebSocket endpoint; the provider's own unless overridden
+    pub url: String,
+}
+
+impl StreamConfig {
+    /// Stream from `provider`'s endpoint with `api_key`
+    pub fn new(provider: StreamProvider, api_key: impl Into<String>) -> Self {
+        Self {
+            provider,
+            api_key: api_key.into(),
+            url: provider.default_url().to_string(),
+        }
+    }
+
+    /// Connect to `url` instead (e.g. a proxy or a test server)
+    pub fn with_url(mut self, url: impl Into<String>) -> Self {
+        self.url = url.into();
+        self
+    }
+
+    /// Finnhub with the configured key, else Polygon with `POLYGON_API_KEY`
+    ///
+    /// `None` when neither key is set.
+    pub fn from_config(config: &StockConfig) -> Option<Self> {
+        if let Some(key) = &config.finnhub_api_key {
+            return Some(Self::new(StreamProvider::Finnhub, key.clone()));
+        }
+        std::env::var(POLYGON_API_KEY_ENV)
+            .ok()
+            .filter(|key| !key.is_empty())
+            .map(|key| Self::new(StreamProvider::Polygon, key))
+    }
+
+    fn connect_url(&self) -> String {
+        match self.provider {
+            StreamProvider::Finnhub => {
+                format!("{}/?token={}", self.url.trim_end_matches('/'), self.api_key)
+            }
+            StreamProvider::Polygon => self.url.clone(),
+        }
+    }
+}
+
+/// Requests from streams and the streamer to the connection task
+#[derive(Debug)]
+enum Control {
+    Subscribe(String),
+    /// A stream for the symbol was dropped
+    Release(String),
+}
+
+/// Per-symbol channels trades are published on
+type Channels = Arc<Mutex<HashMap<String, broadcast::Sender<Tick>>>>;
+
+/// Shared WebSocket connection handing out per-symbol [`QuoteStream`]s
+pub struct QuoteStreamer {
+    provider: StreamProvider,
+    channels: Channels,
+    control: mpsc::UnboundedSender<Control>,
+}
+
+impl QuoteStreamer {
+    /// Start the connection task; it connects once a symbol is subscribed
+    /// and stops when the streamer and all its streams are dropped
+    pub fn spawn(config: StreamConfig) -> Self {
+        let channels = Channels::default();
+        let (control, requests) = mpsc::unbounded_channel();
+        let provider = config.provider;
+        tokio::spawn(run(config, Arc::clone(&channels), requests));
+        Self {
+            provider,
+            channels,
+            control,
+        }
+    }
+
+    /// Provider the trades come from
+    pub fn provider(&self) -> StreamProvider {
+        self.provider
+    }
+
+    /// Trades of `symbol` from now on
+    pub fn subscribe(&self, symbol: &str) -> QuoteStream {
+        let symbol = symbol.trim().to_uppercase();
+        let mut channels = self.channels.lock().unwrap_or_else(PoisonError::into_inner);
+        let receiver = if let Some(sender) = channels.get(&symbol) {
+            sender.subscribe()
+        } else {
+            let (sender, receiver) = broadcast::channel(TICK_BUFFER);
+            channels.insert(symbol.clone(), sender);
+            let _ = self.control.send(Control::Subscribe(symbol.clone()));
+            receiver
+        };
+        QuoteStream {
+            symbol,
+            receiver: Some(receiver),
+            control: self.control.clone(),
+        }
+    }
+
+    /// Symbols with at least one open stream
+    pub fn symbols(&self) -> Vec<String> {
+        watched(&self.channels)
+    }
+}
+
+/// Trades of one symbol; unsubscribes when dropped
+pub struct QuoteStream {
+    symbol: String,
+    receiver: Option<broadcast::Receiver<Tick>>,
+    control: mpsc::UnboundedSender<Control>,
+}
+
+impl QuoteStream {
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    /// Next trade, or `None` once the streamer has stopped
+    ///
+    /// A reader that falls more than [`TICK_BUFFER`] trades behind skips
+    /// ahead to the most recent ones.
+    pub async fn recv(&mut self) -> Option<Tick> {
+        let receiver = self.receiver.as_mut()?;
+        loop {
+            match receiver.recv().await {
+                Ok(tick) => return Some(tick),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::debug!("{} stream skipped {} trades", self.symbol, skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+impl Drop for QuoteStream {
+    fn drop(&mut self) {
+        // Drop the receiver first so the task sees it gone
+        self.receiver.take();
+        let _ = self.control.send(Control::Release(self.symbol.clone()));
+    }
+}
+
+fn watched(channels: &Channels) -> Vec<String> {
+    let mut symbols: Vec<String> = channels
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .keys()
+        .cloned()
+        .collect();
+    symbols.sort();
+    symbols
+}
+
+/// Forget `symbol` if no stream reads it any more; true if it was removed
+fn release(channels: &Channels, symbol: &str) -> bool {
+    let mut channels = channels.lock().unwrap_or_else(PoisonError::into_inner);
+    if channels
+        .get(symbol)
+        .is_some_and(|sender| sender.receiver_count() == 0)
+    {
+        channels.remove(symbol);
+        true
+    } else {
+        false
+    }
+}
+
+fn publish(channels: &Channels, ticks: Vec<Tick>) {
+    let channels = channels.lock().unwrap_or_else(PoisonError::into_inner);
+    for tick in ticks {
+        if let Some(sender) = channels.get(&tick.symbol) {
+            let _ = sender.send(tick);
+        }
+    }
+}
+
+/// Why a connection ended without an error
+enum Disconnect {
+    /// The last stream was dropped
+    Idle,
+    /// The provider closed a working connection
+    Dropped,
+    /// The streamer and all streams are gone
+    Shutdown,
+}
+
+/// Connection task: connect while symbols are watched, reconnect on failure
+async fn run(
+    config: StreamConfig,
+    channels: Channels,
+    mut requests: mpsc::UnboundedReceiver<Control>,
+) {
+    let mut delay = RECONNECT_DELAY;
+    loop {
+        // Stay disconnected while nobody is watching
+        while watched(&channels).is_empty() {
+            match requests.recv().await {
+                Some(Control::Release(symbol)) => {
+                    release(&channels, &symbol);
+                }
+                Some(Control::Subscribe(_)) => {}
+                None => return,
+            }
+        }
+
+        match session(&config, &channels, &mut requests).await {
+            Ok(Disconnect::Shutdown) => return,
+            Ok(Disconnect::Idle) => {
+                delay = RECONNECT_DELAY;
+                continue;
+            }
+            Ok(Disconnect::Dropped) => {
+                tracing::info!("{} stream closed; reconnecting", config.provider);
+                delay = RECONNECT_DELAY;
+            }
+            Err(e) => tracing::warn!(
+                "{} stream failed: {}; retrying in {:?}",
+                config.provider,
+                e,
+                delay
+            ),
+        }
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+    }
+}
+
+/// One connection, until it drops or is no longer needed
+async fn session(
+    config: &StreamConfig,
+    channels: &Channels,
+    requests: &mut mpsc::UnboundedReceiver<Control>,
+) -> Result<Disconnect> {
+    let provider = config.provider;
+    let (mut socket, _) = tokio_tungstenite::connect_async(config.connect_url())
+        .await
+        .map_err(|e| StockError::ApiError(format!("{provider} stream connection failed: {e}")))?;
+    for message in provider.auth_messages(&config.api_key) {
+        send(&mut socket, message).await?;
+    }
+    for symbol in watched(channels) {
+        send(&mut socket, provider.subscribe_message(&symbol, true)).await?;
+    }
+    tracing::debug!("{} stream connected", provider);
+
+    let mut received = false;
+    loop {
+        tokio::select! {
+            message = socket.next() => {
+                let message = match message {
+                    Some(Ok(message)) => message,
+                    Some(Err(e)) if received => {
+                        tracing::debug!("{} stream error: {}", provider, e);
+                        return Ok(Disconnect::Dropped);
+                    }
+                    Some(Err(e)) => {
+                        return Err(StockError::ApiError(format!("{provider} stream error: {e}")));
+                    }
+                    None => return Ok(Disconnect::Dropped),
+                };
+                received = true;
+                match message {
+                    Message::Text(text) => publish(channels, provider.parse_message(&text)?),
+                    Message::Close(_) => return Ok(Disconnect::Dropped),
+                    // Pings are answered by tungstenite
+                    _ => {}
+                }
+            }
+            request = requests.recv() => match request {
+                Some(Control::Subscribe(symbol)) => {
+                    send(&mut socket, provider.subscribe_message(&symbol, true)).await?;
+                }
+                Some(Control::Release(symbol)) => {
+                    if release(channels, &symbol) {
+                        send(&mut socket, provider.subscribe_message(&symbol, false)).await?;
+                    }
+                    if watched(channels).is_empty() {
+                        let _ = socket.close(None).await;
+                        return Ok(Disconnect::Idle);
+                    }
+                }
+                None => {
+                    let _ = socket.close(None).await;
+                    return Ok(Disconnect::Shutdown);
+                }
+            },
+        }
+    }
+}
+
+async fn send(socket: &mut Socket, message: String) -> Result<()> {
+    socket
+        .send(Message::text(message))
+        .await
+        .map_err(|e| StockError::ApiError(format!("Stream send failed: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_parse_messages() {
+        let ticks = StreamProvider::Finnhub
+            .parse_message(
+                r#"{"type":"trade","data":[
+                    {"s":"AAPL","p":189.84,"t":1710426605123,"v":100,"c":["1"]},
+                    {"s":"MSFT","p":421.5,"t":1710426605200,"v":20}
+                ]}"#,
+            )
+            .unwrap();
+        assert_eq!(ticks.len(), 2);
+        assert_eq!(ticks[0].symbol, "AAPL");
+        assert_eq!(ticks[0].volume, Some(100.0));
+        assert_eq!(ticks[0].to_string(), "AAPL 189.84 @ 14:30:05 UTC");
+        assert!(
+            StreamProvider::Finnhub
+                .parse_message(r#"{"type":"ping"}"#)
+                .unwrap()
+                .is_empty()
+        );
+        let err = StreamProvider::Finnhub
+            .parse_message(r#"{"type":"error","msg":"Invalid token"}"#)
+            .unwrap_err();
+        assert!(err.to_string().contains("Invalid token"), "{err}");
+
+        let ticks = StreamProvider::Polygon
+            .parse_message(
+                r#"[{"ev":"status","status":"auth_success","message":"authenticated"},
+                    {"ev":"T","sym":"NVDA","p":878.35,"s":5,"t":1710426605123}]"#,
+            )
+            .unwrap();
+        assert_eq!(ticks.len(), 1);
+        assert_eq!(ticks[0].symbol, "NVDA");
+        let err = StreamProvider::Polygon
+            .parse_message(r#"[{"ev":"status","status":"auth_failed","message":"bad key"}]"#)
+            .unwrap_err();
+        assert!(err.to_string().contains(POLYGON_API_KEY_ENV), "{err}");
+
+        assert_eq!(
+            StreamProvider::Polygon.subscribe_message("AAPL", false),
+            r#"{"action":"unsubscribe","params":"T.AAPL"}"#
+        );
+    }
+
+    /// A Finnhub-like server that echoes each subscription as one trade
+    async fn mock_finnhub() -> (String, tokio::sync::mpsc::UnboundedReceiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let (received, requests) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Ok((tcp, _)) = listener.accept().await {
+                let received = received.clone();
+                tokio::spawn(async move {
+                    let mut socket = tokio_tungstenite::accept_async(tcp).await.unwrap();
+                    while let Some(Ok(Message::Text(text))) = socket.next().await {
+                        let _ = received.send(text.to_string());
+                        let request: Value = serde_json::from_str(&text).unwrap();
+                        if request["type"] == "subscribe" {
+                            let trade = json!({
+                                "type": "trade",
+                                "data": [{ "s": request["symbol"], "p": 100.5, "t": 1_710_426_605_123_i64, "v": 1 }],
+                            });
+                            socket.send(Message::text(trade.to_string())).await.unwrap();
+                        }
+                    }
+                });
+            }
+        });
+        (url, requests)
+    }
+
+    #[tokio::test]
+    async fn test_stream_subscribe() {
+        let (url, mut requests) = mock_finnhub().await;
+        let streamer =
+            QuoteStreamer::spawn(StreamConfig::new(StreamProvider::Finnhub, "key").with_url(url));
+        assert_eq!(streamer.provider(), StreamProvider::Finnhub);
+
+        let mut stream = streamer.subscribe("aapl");
+        assert_eq!(stream.symbol(), "AAPL");
+        let tick = tokio::time::timeout(Duration::from_secs(5), stream.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(tick.symbol, "AAPL");
+        assert!((tick.price - 100.5).abs() < 1e-9);
+        assert_eq!(streamer.symbols(), vec!["AAPL"]);
+
+        // Dropping the last stream unsubscribes
+        drop(stream);
+        let mut seen = Vec::new();
+        while !seen
+            .iter()
+            .any(|request: &String| request.contains("unsubscribe"))
+        {
+            let request = tokio::time::timeout(Duration::from_secs(5), requests.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            seen.push(request);
+        }
+        assert!(seen[0].contains(r#""symbol":"AAPL""#), "{seen:?}");
+        assert!(streamer.symbols().is_empty());
+    }
+}
@@ -0,0 +1,281 @@
+//! Test harness for the API clients
+//!
+//! [`MockApi`] starts a local wiremock server that serves recorded responses
+//! from the Yahoo Finance, FRED, ECB, SEC EDGAR and Finnhub APIs, and builds
+//! clients pointed at it (Alpha Vantage too, for routes you mount yourself).
+//! Enable the `test-util` feature to use it from other crates.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::api::testing::MockApi;
+//!
+//! let api = MockApi::recorded().await;
+//! let quote = api.yahoo().get_quote("AAPL").await?;
+//! assert_eq!(quote.close, 171.13);
+//! ```
+
+use wiremock::matchers::{method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use super::{
+    AlphaVantageClient, Downloader, EcbClient, FinnhubClient, FredClient, SecEdgarClient,
+    YahooFinanceClient,
+};
+
+/// Recorded API responses
+pub mod fixtures {
+    /// Yahoo chart for AAPL: three daily bars, last close 171.13
+    pub const YAHOO_CHART_AAPL: &str = include_str!("../../fixtures/api/yahoo_chart_aapl.json");
+    /// Yahoo chart for AAPL in an older shape: `quote` as an object, no
+    /// `adjclose`, sparse `meta` and a null bar
+    pub const YAHOO_CHART_LEGACY: &str = include_str!("../../fixtures/api/yahoo_chart_legacy.json");
+    /// Yahoo monthly chart for KO with dividend events: four quarterly
+    /// payments a year from 2021 to 2024, raised every March
+    pub const YAHOO_CHART_KO_DIVIDENDS: &str =
+        include_str!("../../fixtures/api/yahoo_chart_ko_dividends.json");
+    /// Yahoo chart error for an unknown symbol (served with 404)
+    pub const YAHOO_CHART_NOT_FOUND: &str =
+        include_str!("../../fixtures/api/yahoo_chart_not_found.json");
+    /// FRED series metadata for FEDFUNDS
+    pub const FRED_SERIES_FEDFUNDS: &str =
+        include_str!("../../fixtures/api/fred_series_fedfunds.json");
+    /// FRED observations for FEDFUNDS, newest first (5.33)
+    pub const FRED_OBSERVATIONS_FEDFUNDS: &str =
+        include_str!("../../fixtures/api/fred_observations_fedfunds.json");
+    /// FRED observations whose latest value is missing (".")
+    pub const FRED_OBSERVATIONS_MISSING: &str =
+        include_str!("../../fixtures/api/fred_observations_missing.json");
+    /// FRED error for an unknown series (served with 400)
+    pub const FRED_ERROR_BAD_SERIES: &str =
+        include_str!("../../fixtures/api/fred_error_bad_series.json");
+    /// ECB euro area HICP inflation, three months to 2024-02 (2.6%)
+    pub const ECB_HICP_EURO_AREA: &str = include_str!("../../fixtures/api/ecb_hicp_euro_area.json");
+    /// SEC ticker to CIK map (AAPL is 320193)
+    pub const SEC_COMPANY_TICKERS: &str =
+        include_str!("../../fixtures/api/sec_company_tickers.json");
+    /// SEC submissions for Apple: 10-Q, 8-K, 10-K and an SC 13G/A
+    pub const SEC_SUBMISSIONS_AAPL: &str =
+        include_str!("../../fixtures/api/sec_submissions_aapl.json");
+    /// SEC XBRL company facts for Apple, fiscal 2023
+    pub const SEC_COMPANYFACTS_AAPL: &str =
+        include_str!("../../fixtures/api/sec_companyfacts_aapl.json");
+    /// Excerpt of Apple's fiscal 2023 10-K as inline XBRL: revenue by
+    /// segment, country and product, with prior-year comparatives
+    pub const SEC_10K_AAPL_SEGMENTS: &str =
+        include_str!("../../fixtures/api/sec_10k_aapl_segments.htm");
+    /// SEC full-text search for "supply constraints" in 10-Qs: Apple and a
+    /// company without a ticker
+    pub const SEC_FULL_TEXT_SEARCH: &str =
+        include_str!("../../fixtures/api/sec_full_text_search.json");
+    /// Finnhub company news for AAPL (two articles)
+    pub const FINNHUB_COMPANY_NEWS_AAPL: &str =
+        include_str!("../../fixtures/api/finnhub_company_news_aapl.json");
+    /// Finnhub earnings calendar: a past GE report, an upcoming MSFT report
+    /// and an entry without estimates
+    pub const FINNHUB_EARNINGS_CALENDAR: &str =
+        include_str!("../../fixtures/api/finnhub_earnings_calendar.json");
+    /// Finnhub rate limit error (served with 429)
+    pub const FINNHUB_ERROR_RATE_LIMIT: &str =
+        include_str!("../../fixtures/api/finnhub_error_rate_limit.json");
+}
+
+/// Path prefix of FRED routes on the mock server
+const FRED_PREFIX: &str = "/fred";
+/// Path prefix of ECB routes on the mock server
+const ECB_PREFIX: &str = "/ecb";
+/// Path prefix of Finnhub routes on the mock server
+const FINNHUB_PREFIX: &str = "/api/v1";
+
+/// Local server replaying recorded API responses
+pub struct MockApi {
+    server: MockServer,
+}
+
+impl MockApi {
+    /// Start a server with no routes
+    ///
+    /// wiremock reuses servers across tests, so breakers left open by an
+    /// earlier test on the same address are reset.
+    pub async fn start() -> Self {
+        let server = MockServer::start().await;
+        super::circuit::reset(&server.uri());
+        Self { server }
+    }
+
+    /// Start a server serving every recorded fixture
+    ///
+    /// Routes:
+    /// - Yahoo: chart for `AAPL`, chart with dividends for `KO`; any other
+    ///   symbol gets the 404 not-found body
+    /// - FRED: `FEDFUNDS` series and observations, `MISSING` observations,
+    ///   400 for any other series
+    /// - ECB: euro area HICP; 404 for any other series
+    /// - SEC: ticker map, Apple submissions and company facts, full-text search
+    /// - Finnhub: company and market news
+    pub async fn recorded() -> Self {
+        let api = Self::start().await;
+        api.mount_recorded().await;
+        api
+    }
+
+    /// Base URL of the server
+    pub fn uri(&self) -> String {
+        self.server.uri()
+    }
+
+    /// The underlying wiremock server, for custom routes and request inspection
+    pub fn server(&self) -> &MockServer {
+        &self.server
+    }
+
+    /// Serve `body` as JSON with `status` for GET requests to `route`
+    pub async fn mount_json(&self, route: &str, status: u16, body: &str) {
+        Mock::given(method("GET"))
+            .and(path(route))
+            .respond_with(json_response(status, body))
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Number of requests received so far
+    pub async fn request_count(&self) -> usize {
+        self.server
+            .received_requests()
+            .await
+            .map_or(0, |requests| requests.len())
+    }
+
+    /// Yahoo Finance client pointed at this server
+    pub fn yahoo(&self) -> YahooFinanceClient {
+        YahooFinanceClient::new().with_base_url(self.uri())
+    }
+
+    /// FRED client pointed at this server
+    pub fn fred(&self, rate_limit: Option<u32>) -> FredClient {
+        FredClient::new("test-key", rate_limit)
+            .with_base_url(format!("{}{FRED_PREFIX}", self.uri()))
+    }
+
+    /// ECB client pointed at this server
+    pub fn ecb(&self) -> EcbClient {
+        EcbClient::new().with_base_url(format!("{}{ECB_PREFIX}", self.uri()))
+    }
+
+    /// SEC EDGAR client pointed at this server
+    ///
+    /// Filing documents are downloaded to a fresh temporary directory, so
+    /// documents kept by earlier tests are never reused.
+    pub fn sec(&self) -> SecEdgarClient {
+        let downloads = std::env::temp_dir().join(format!("agent-stock-{}", uuid::Uuid::new_v4()));
+        SecEdgarClient::new("agent-stock-tests", "tests@example.com")
+            .with_base_url(self.uri())
+            .with_downloader(Downloader::new(downloads))
+    }
+
+    /// Finnhub client pointed at this server
+    pub fn finnhub(&self, rate_limit: u32) -> FinnhubClient {
+        FinnhubClient::new("test-key", rate_limit)
+            .with_base_url(format!("{}{FINNHUB_PREFIX}", self.uri()))
+    }
+
+    /// Alpha Vantage client pointed at this server, with a fresh API key so
+    /// its rate limiter is not shared with other clients
+    pub fn alpha_vantage(&self, premium: bool) -> AlphaVantageClient {
+        AlphaVantageClient::new(format!("test-key-{}", uuid::Uuid::new_v4()), 600)
+            .with_premium(premium)
+            .with_base_url(self.uri())
+    }
+
+    async fn mount_recorded(&self) {
+        // Specific routes are mounted before their catch-alls, which wiremock
+        // matches in mount order
+        self.mount_json("/v8/finance/chart/AAPL", 200, fixtures::YAHOO_CHART_AAPL)
+            .await;
+        self.mount_json(
+            "/v8/finance/chart/KO",
+            200,
+            fixtures::YAHOO_CHART_KO_DIVIDENDS,
+        )
+        .await;
+        Mock::given(method("GET"))
+            .and(wiremock::matchers::path_regex("^/v8/finance/chart/"))
+            .respond_with(json_response(404, fixtures::YAHOO_CHART_NOT_FOUND))
+            .mount(&self.server)
+            .await;
+
+        for (route, series, body) in [
+            ("series", "FEDFUNDS", fixtures::FRED_SERIES_FEDFUNDS),
+            (
+                "series/observations",
+                "FEDFUNDS",
+                fixtures::FRED_OBSERVATIONS_FEDFUNDS,
+            ),
+            (
+                "series/observations",
+                "MISSING",
+                fixtures::FRED_OBSERVATIONS_MISSING,
+            ),
+        ] {
+            Mock::given(method("GET"))
+                .and(path(format!("{FRED_PREFIX}/{route}")))
+                .and(query_param("series_id", series))
+                .and(query_param("file_type", "json"))
+                .respond_with(json_response(200, body))
+                .mount(&self.server)
+                .await;
+        }
+        Mock::given(method("GET"))
+            .and(wiremock::matchers::path_regex(format!("^{FRED_PREFIX}/")))
+            .respond_with(json_response(400, fixtures::FRED_ERROR_BAD_SERIES))
+            .mount(&self.server)
+            .await;
+
+        self.mount_json(
+            &format!("{ECB_PREFIX}/data/ICP/M.U2.N.000000.4.ANR"),
+            200,
+            fixtures::ECB_HICP_EURO_AREA,
+        )
+        .await;
+
+        self.mount_json(
+            "/files/company_tickers.json",
+            200,
+            fixtures::SEC_COMPANY_TICKERS,
+        )
+        .await;
+        self.mount_json(
+            "/submissions/CIK0000320193.json",
+            200,
+            fixtures::SEC_SUBMISSIONS_AAPL,
+        )
+        .await;
+        self.mount_json(
+            "/api/xbrl/companyfacts/CIK0000320193.json",
+            200,
+            fixtures::SEC_COMPANYFACTS_AAPL,
+        )
+        .await;
+        self.mount_json("/LATEST/search-index", 200, fixtures::SEC_FULL_TEXT_SEARCH)
+            .await;
+
+        for route in ["company-news", "news"] {
+            Mock::given(method("GET"))
+                .and(path(format!("{FINNHUB_PREFIX}/{route}")))
+                .and(query_param("token", "test-key"))
+                .respond_with(json_response(200, fixtures::FINNHUB_COMPANY_NEWS_AAPL))
+                .mount(&self.server)
+                .await;
+        }
+        Mock::given(method("GET"))
+            .and(path(format!("{FINNHUB_PREFIX}/calendar/earnings")))
+            .and(query_param("token", "test-key"))
+            .respond_with(json_response(200, fixtures::FINNHUB_EARNINGS_CALENDAR))
+            .mount(&self.server)
+            .await;
+    }
+}
+
+fn json_response(status: u16, body: &str) -> ResponseTemplate {
+    ResponseTemplate::new(status).set_body_raw(body.to_owned(), "application/json")
+}
@@ -0,0 +1,677 @@
+//! Financial statements from SEC XBRL company facts
+//!
+//! Company facts list every value a company has tagged, keyed by concept and
+//! reporting period. Turning them into statements takes care on two fronts:
+//!
+//! - **Concepts**: filers use different concepts for the same line item, and
+//!   switch over time (`Revenues` before 2018,
+//!   `RevenueFromContractWithCustomerExcludingAssessedTax` after ASC 606).
+//!   Each line item lists its concepts in priority order; per period, the
+//!   first concept with a value wins.
+//! - **Periods**: the `fy`/`fp` fields describe the filing a value came from,
+//!   not the period it covers, so values are matched on their start and end
+//!   dates instead. 10-Qs report cash flows year to date and nobody files a
+//!   Q4 report, so missing quarters are derived by differencing year-to-date
+//!   totals (Q4 = annual − nine months). Derived quarters are flagged, and
+//!   derived EPS is approximate since share counts move within a year.
+//!
+//! Segment breakdowns are dimensional facts, which the company facts API
+//! omits.
+
+use chrono::{Days, NaiveDate};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use super::sec_edgar::FinancialData;
+
+/// Revenue concepts, newest standard first
+pub const REVENUE: &[&str] = &[
+    "RevenueFromContractWithCustomerExcludingAssessedTax",
+    "Revenues",
+    "RevenueFromContractWithCustomerIncludingAssessedTax",
+    "SalesRevenueNet",
+    "SalesRevenueGoodsNet",
+];
+pub const COST_OF_REVENUE: &[&str] = &[
+    "CostOfGoodsAndServicesSold",
+    "CostOfRevenue",
+    "CostOfGoodsSold",
+];
+pub const GROSS_PROFIT: &[&str] = &["GrossProfit"];
+pub const OPERATING_INCOME: &[&str] = &["OperatingIncomeLoss"];
+pub const NET_INCOME: &[&str] = &["NetIncomeLoss", "ProfitLoss"];
+pub const EPS_BASIC: &[&str] = &["EarningsPerShareBasic"];
+pub const EPS_DILUTED: &[&str] = &["EarningsPerShareDiluted", "EarningsPerShareBasicAndDiluted"];
+pub const RESEARCH_AND_DEVELOPMENT: &[&str] = &[
+    "ResearchAndDevelopmentExpense",
+    "ResearchAndDevelopmentExpenseExcludingAcquiredInProcessCost",
+];
+pub const SELLING_GENERAL_ADMIN: &[&str] = &["SellingGeneralAndAdministrativeExpense"];
+pub const OPERATING_CASH_FLOW: &[&str] = &[
+    "NetCashProvidedByUsedInOperatingActivities",
+    "NetCashProvidedByUsedInOperatingActivitiesContinuingOperations",
+];
+pub const CAPITAL_EXPENDITURE: &[&str] = &[
+    "PaymentsToAcquirePropertyPlantAndEquipment",
+    "PaymentsToAcquireProductiveAssets",
+];
+pub const DILUTED_SHARES: &[&str] = &["WeightedAverageNumberOfDilutedSharesOutstanding"];
+pub const DEPRECIATION: &[&str] = &[
+    "DepreciationDepletionAndAmortization",
+    "DepreciationAmortizationAndAccretionNet",
+    "DepreciationAndAmortization",
+    "Depreciation",
+];
+pub const TOTAL_ASSETS: &[&str] = &["Assets"];
+pub const TOTAL_LIABILITIES: &[&str] = &["Liabilities"];
+pub const STOCKHOLDERS_EQUITY: &[&str] = &[
+    "StockholdersEquity",
+    "StockholdersEquityIncludingPortionAttributableToNoncontrollingInterest",
+];
+pub const SHARES_OUTSTANDING: &[&str] = &["CommonStockSharesOutstanding"];
+pub const CURRENT_ASSETS: &[&str] = &["AssetsCurrent"];
+pub const CURRENT_LIABILITIES: &[&str] = &["LiabilitiesCurrent"];
+pub const ACCOUNTS_RECEIVABLE: &[&str] = &[
+    "AccountsReceivableNetCurrent",
+    "ReceivablesNetCurrent",
+    "AccountsNotesAndLoansReceivableNetCurrent",
+];
+pub const INVENTORY: &[&str] = &["InventoryNet", "InventoryGross"];
+pub const PROPERTY_PLANT_EQUIPMENT: &[&str] = &[
+    "PropertyPlantAndEquipmentNet",
+    "PropertyPlantAndEquipmentAndFinanceLeaseRightOfUseAssetAfterAccumulatedDepreciationAndAmortization",
+];
+pub const LONG_TERM_DEBT: &[&str] = &["LongTermDebtNoncurrent", "LongTermDebt"];
+
+/// Units a concept may be reported in, in the order they are tried
+const UNITS: &[&str] = &["USD", "USD/shares", "shares"];
+
+/// Fiscal years run 52 or 53 weeks
+fn is_annual(days: i64) -> bool {
+    (350..=380).contains(&days)
+}
+
+/// Fiscal quarters run 13 or 14 weeks
+fn is_quarter(days: i64) -> bool {
+    (80..=100).contains(&days)
+}
+
+/// One value of a line item for one period
+#[derive(Debug, Clone, PartialEq)]
+struct Fact {
+    /// None for balance sheet (instant) values
+    start: Option<NaiveDate>,
+    end: NaiveDate,
+    /// Latest filed value, so restatements win
+    value: f64,
+    /// Date the period was first reported
+    first_filed: NaiveDate,
+    /// Fiscal year and period of the first filing
+    fy: Option<i64>,
+    fp: Option<String>,
+}
+
+impl Fact {
+    fn days(&self) -> Option<i64> {
+        self.start.map(|start| (self.end - start).num_days())
+    }
+}
+
+/// All periods reported for one line item
+#[derive(Debug, Default)]
+struct Series {
+    facts: HashMap<(Option<NaiveDate>, NaiveDate), Fact>,
+}
+
+impl Series {
+    /// Merge `concepts` in priority order
+    fn load(us_gaap: &Value, concepts: &[&str]) -> Self {
+        let mut series = Self::default();
+        for concept in concepts {
+            let units = &us_gaap[*concept]["units"];
+            let Some(entries) = UNITS.iter().find_map(|unit| units[*unit].as_array()) else {
+                continue;
+            };
+
+            let mut own: HashMap<(Option<NaiveDate>, NaiveDate), (Fact, NaiveDate)> =
+                HashMap::new();
+            for entry in entries {
+                let date = |field: &str| {
+                    entry[field]
+                        .as_str()
+                        .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+                };
+                let (Some(end), Some(filed), Some(value)) =
+                    (date("end"), date("filed"), entry["val"].as_f64())
+                else {
+                    continue;
+                };
+                let fact = Fact {
+                    start: date("start"),
+                    end,
+                    value,
+                    first_filed: filed,
+                    fy: entry["fy"].as_i64(),
+                    fp: entry["fp"].as_str().map(ToString::to_string),
+                };
+
+                match own.get_mut(&(fact.start, end)) {
+                    Some((known, last_filed)) => {
+                        if filed >= *last_filed {
+                            known.value = value;
+                            *last_filed = filed;
+                        }
+                        if filed < known.first_filed {
+                            known.first_filed = filed;
+                            known.fy = fact.fy;
+                            known.fp = fact.fp;
+                        }
+                    }
+                    None => {
+                        own.insert((fact.start, end), (fact, filed));
+                    }
+                }
+            }
+
+            for (key, (fact, _)) in own {
+                series.facts.entry(key).or_insert(fact);
+            }
+        }
+        series
+    }
+
+    fn duration(&self, start: NaiveDate, end: NaiveDate) -> Option<f64> {
+        self.facts.get(&(Some(start), end)).map(|fact| fact.value)
+    }
+
+    fn instant(&self, end: NaiveDate) -> Option<f64> {
+        self.facts.get(&(None, end)).map(|fact| fact.value)
+    }
+
+    /// The three-month value ending on `end`
+    fn quarter_ending(&self, end: NaiveDate) -> Option<f64> {
+        self.facts
+            .values()
+            .find(|fact| fact.end == end && fact.days().is_some_and(is_quarter))
+            .map(|fact| fact.value)
+    }
+
+    fn durations(&self) -> impl Iterator<Item = &Fact> {
+        self.facts.values().filter(|fact| fact.start.is_some())
+    }
+}
+
+/// Line items of the income, cash flow and balance sheet statements
+struct Statements {
+    revenue: Series,
+    cost_of_revenue: Series,
+    gross_profit: Series,
+    operating_income: Series,
+    net_income: Series,
+    eps_basic: Series,
+    eps_diluted: Series,
+    research_and_development: Series,
+    selling_general_admin: Series,
+    operating_cash_flow: Series,
+    capital_expenditure: Series,
+    diluted_shares: Series,
+    depreciation: Series,
+    total_assets: Series,
+    total_liabilities: Series,
+    stockholders_equity: Series,
+    shares_outstanding: Series,
+    current_assets: Series,
+    current_liabilities: Series,
+    accounts_receivable: Series,
+    inventory: Series,
+    property_plant_equipment: Series,
+    long_term_debt: Series,
+}
+
+/// Values of one period, before derived metrics
+#[derive(Default)]
+struct PeriodValues {
+    revenue: Option<f64>,
+    cost_of_revenue: Option<f64>,
+    gross_profit: Option<f64>,
+    operating_income: Option<f64>,
+    net_income: Option<f64>,
+    eps_basic: Option<f64>,
+    eps_diluted: Option<f64>,
+    research_and_development: Option<f64>,
+    selling_general_admin: Option<f64>,
+    operating_cash_flow: Option<f64>,
+    capital_expenditure: Option<f64>,
+    diluted_shares: Option<f64>,
+    depreciation: Option<f64>,
+}
+
+impl Statements {
+    fn load(us_gaap: &Value) -> Self {
+        Self {
+            revenue: Series::load(us_gaap, REVENUE),
+            cost_of_revenue: Series::load(us_gaap, COST_OF_REVENUE),
+            gross_profit: Series::load(us_gaap, GROSS_PROFIT),
+            operating_income: Series::load(us_gaap, OPERATING_INCOME),
+            net_income: Series::load(us_gaap, NET_INCOME),
+            eps_basic: Series::load(us_gaap, EPS_BASIC),
+            eps_diluted: Series::load(us_gaap, EPS_DILUTED),
+            research_and_development: Series::load(us_gaap, RESEARCH_AND_DEVELOPMENT),
+            selling_general_admin: Series::load(us_gaap, SELLING_GENERAL_ADMIN),
+            operating_cash_flow: Series::load(us_gaap, OPERATING_CASH_FLOW),
+            capital_expenditure: Series::load(us_gaap, CAPITAL_EXPENDITURE),
+            diluted_shares: Series::load(us_gaap, DILUTED_SHARES),
+            depreciation: Series::load(us_gaap, DEPRECIATION),
+            total_assets: Series::load(us_gaap, TOTAL_ASSETS),
+            total_liabilities: Series::load(us_gaap, TOTAL_LIABILITIES),
+            stockholders_equity: Series::load(us_gaap, STOCKHOLDERS_EQUITY),
+            shares_outstanding: Series::load(us_gaap, SHARES_OUTSTANDING),
+            current_assets: Series::load(us_gaap, CURRENT_ASSETS),
+            current_liabilities: Series::load(us_gaap, CURRENT_LIABILITIES),
+            accounts_receivable: Series::load(us_gaap, ACCOUNTS_RECEIVABLE),
+            inventory: Series::load(us_gaap, INVENTORY),
+            property_plant_equipment: Series::load(us_gaap, PROPERTY_PLANT_EQUIPMENT),
+            long_term_debt: Series::load(us_gaap, LONG_TERM_DEBT),
+        }
+    }
+
+    /// Flow line items, which accumulate over a period
+    fn flows(&self) -> [&Series; 13] {
+        [
+            &self.revenue,
+            &self.cost_of_revenue,
+            &self.gross_profit,
+            &self.operating_income,
+            &self.net_income,
+            &self.eps_basic,
+            &self.eps_diluted,
+            &self.research_and_development,
+            &self.selling_general_admin,
+            &self.operating_cash_flow,
+            &self.capital_expenditure,
+            &self.diluted_shares,
+            &self.depreciation,
+        ]
+    }
+
+    /// Flow values over one period, computed by `value`
+    fn period_values(&self, mut value: impl FnMut(&Series) -> Option<f64>) -> PeriodValues {
+        PeriodValues {
+            revenue: value(&self.revenue),
+            cost_of_revenue: value(&self.cost_of_revenue),
+            gross_profit: value(&self.gross_profit),
+            operating_income: value(&self.operating_income),
+            net_income: value(&self.net_income),
+            eps_basic: value(&self.eps_basic),
+            eps_diluted: value(&self.eps_diluted),
+            research_and_development: value(&self.research_and_development),
+            selling_general_admin: value(&self.selling_general_admin),
+            operating_cash_flow: value(&self.operating_cash_flow),
+            capital_expenditure: value(&self.capital_expenditure),
+            diluted_shares: value(&self.diluted_shares),
+            depreciation: value(&self.depreciation),
+        }
+    }
+
+    /// Fiscal years reported, newest first, plus the year in progress
+    ///
+    /// Years are `(start, end)`; the year in progress has no end yet.
+    fn fiscal_years(&self) -> Vec<(NaiveDate, Option<NaiveDate>)> {
+        let mut years: Vec<(NaiveDate, Option<NaiveDate>)> = self
+            .flows()
+            .iter()
+            .flat_map(|series| series.durations())
+            .filter(|fact| fact.days().is_some_and(is_annual))
+            .filter_map(|fact| Some((fact.start?, Some(fact.end))))
+            .collect();
+        years.sort();
+        years.dedup();
+        // Overlapping 52/53-week variants of the same year keep the first seen
+        years.dedup_by(|later, earlier| later.0 <= earlier.1.unwrap_or(later.0));
+
+        if let Some(next_start) = years
+            .last()
+            .and_then(|(_, end)| end.and_then(|end| end.checked_add_days(Days::new(1))))
+        {
+            let started = self
+                .flows()
+                .iter()
+                .flat_map(|series| series.durations())
+                .any(|fact| fact.start == Some(next_start));
+            if started {
+                years.push((next_start, None));
+            }
+        }
+
+        years.reverse();
+        years
+    }
+
+    /// Quarter end dates within a fiscal year
+    fn quarter_ends(&self, start: NaiveDate, end: Option<NaiveDate>) -> Vec<NaiveDate> {
+        let within = |date: NaiveDate| end.is_none_or(|end| date <= end);
+        let mut ends: Vec<NaiveDate> = self
+            .flows()
+            .iter()
+            .flat_map(|series| series.durations())
+            .filter(|fact| within(fact.end))
+            .filter(|fact| {
+                // Year-to-date totals and discrete quarters both mark quarter ends
+                fact.start == Some(start)
+                    || (fact.start.is_some_and(|s| s >= start)
+                        && fact.days().is_some_and(is_quarter))
+            })
+            .map(|fact| fact.end)
+            .chain(end)
+            .collect();
+        ends.sort();
+        ends.dedup();
+
+        // Keep ends at least a quarter apart
+        let mut quarters: Vec<NaiveDate> = Vec::new();
+        for date in ends {
+            let previous = quarters.last().copied().unwrap_or(start);
+            if (date - previous).num_days() >= 80 {
+                quarters.push(date);
+            }
+        }
+        quarters.truncate(4);
+        // A year with only annual figures has no quarters to show
+        if end.is_some() && quarters.len() == 1 {
+            quarters.clear();
+        }
+        quarters
+    }
+
+    /// First-filing label and date of a period, from the facts ending on `end`
+    fn first_report(&self, start: Option<NaiveDate>, end: NaiveDate) -> Option<&Fact> {
+        self.flows()
+            .into_iter()
+            .flat_map(Series::durations)
+            .filter(|fact| fact.end == end && start.is_none_or(|start| fact.start == Some(start)))
+            .min_by_key(|fact| fact.first_filed)
+    }
+
+    fn financial_data(
+        &self,
+        values: PeriodValues,
+        (start, end): (NaiveDate, NaiveDate),
+        fiscal_year: String,
+        fiscal_quarter: Option<String>,
+        filed: Option<NaiveDate>,
+        derived: bool,
+    ) -> FinancialData {
+        let gross_profit = values.gross_profit.or_else(|| {
+            values
+                .revenue
+                .zip(values.cost_of_revenue)
+                .map(|(revenue, cost)| revenue - cost)
+        });
+        let free_cash_flow = values
+            .operating_cash_flow
+            .zip(values.capital_expenditure)
+            .map(|(cash_flow, capex)| cash_flow - capex);
+
+        FinancialData {
+            revenue: values.revenue,
+            net_income: values.net_income,
+            eps_basic: values.eps_basic,
+            eps_diluted: values.eps_diluted,
+            total_assets: self.total_assets.instant(end),
+            total_liabilities: self.total_liabilities.instant(end),
+            stockholders_equity: self.stockholders_equity.instant(end),
+            operating_income: values.operating_income,
+            gross_profit,
+            operating_cash_flow: values.operating_cash_flow,
+            capital_expenditure: values.capital_expenditure,
+            free_cash_flow,
+            research_and_development: values.research_and_development,
+            selling_general_admin: values.selling_general_admin,
+            shares_outstanding: self.shares_outstanding.instant(end),
+            diluted_shares: values.diluted_shares,
+            depreciation: values.depreciation,
+            current_assets: self.current_assets.instant(end),
+            current_liabilities: self.current_liabilities.instant(end),
+            accounts_receivable: self.accounts_receivable.instant(end),
+            inventory: self.inventory.instant(end),
+            property_plant_equipment: self.property_plant_equipment.instant(end),
+            long_term_debt: self.long_term_debt.instant(end),
+            fiscal_year,
+            fiscal_quarter,
+            period_start: Some(start.to_string()),
+            period_end: end.to_string(),
+            filing_date: filed.map(|date| date.to_string()).unwrap_or_default(),
+            derived,
+        }
+    }
+}
+
+/// Company facts as they stood at the end of `date`
+///
+/// Drops every value filed later, so statements built from the result show
+/// the figures then reported rather than later restatements.
+pub fn known_at(us_gaap: &Value, date: NaiveDate) -> Value {
+    let mut known = us_gaap.clone();
+    let filed_by = |entry: &Value| {
+        entry["filed"]
+            .as_str()
+            .and_then(|filed| NaiveDate::parse_from_str(filed, "%Y-%m-%d").ok())
+            .is_some_and(|filed| filed <= date)
+    };
+    if let Some(concepts) = known.as_object_mut() {
+        for concept in concepts.values_mut() {
+            if let Some(units) = concept["units"].as_object_mut() {
+                for entries in units.values_mut() {
+                    if let Some(entries) = entries.as_array_mut() {
+                        entries.retain(filed_by);
+                    }
+                }
+            }
+        }
+    }
+    known
+}
+
+/// Annual and quarterly statements for the last `years` fiscal years, newest first
+///
+/// Each fiscal year contributes its annual figures (`fiscal_quarter` None)
+/// and up to four quarters; the year in progress contributes the quarters
+/// reported so far. A quarter sorts before the annual figures ending on the
+/// same day.
+pub fn financial_data(us_gaap: &Value, years: usize) -> Vec<FinancialData> {
+    let statements = Statements::load(us_gaap);
+    let mut data = Vec::new();
+
+    for (start, end) in statements.fiscal_years().into_iter().take(years) {
+        let report = statements.first_report(Some(start), end.unwrap_or(start));
+        let label_year = end
+            .and_then(|end| statements.first_report(Some(start), end))
+            .or(report)
+            .and_then(|fact| fact.fy)
+            .or_else(|| end.map(|end| i64::from(chrono::Datelike::year(&end))));
+
+        if let Some(end) = end {
+            let values = statements.period_values(|series| series.duration(start, end));
+            data.push(
+                statements.financial_data(
+                    values,
+                    (start, end),
+                    label_year.map(|fy| fy.to_string()).unwrap_or_default(),
+                    None,
+                    statements
+                        .first_report(Some(start), end)
+                        .map(|fact| fact.first_filed),
+                    false,
+                ),
+            );
+        }
+
+        let mut previous_end: Option<NaiveDate> = None;
+        for (index, quarter_end) in statements.quarter_ends(start, end).into_iter().enumerate() {
+            let quarter_start = previous_end
+                .and_then(|date| date.checked_add_days(Days::new(1)))
+                .unwrap_or(start);
+
+            let mut derived = false;
+            let values = statements.period_values(|series| {
+                if let Some(value) = series.quarter_ending(quarter_end) {
+                    return Some(value);
+                }
+                // Difference of year-to-date totals
+                let to_date = series.duration(start, quarter_end)?;
+                let before = previous_end.and_then(|date| series.duration(start, date))?;
+                derived = true;
+                Some(to_date - before)
+            });
+            let derived = derived;
+
+            let fiscal_year = statements
+                .first_report(None, quarter_end)
+                .and_then(|fact| fact.fy)
+                .filter(|_| end.is_none())
+                .or(label_year);
+            data.push(
+                statements.financial_data(
+                    values,
+                    (quarter_start, quarter_end),
+                    fiscal_year.map(|fy| fy.to_string()).unwrap_or_default(),
+                    Some(format!("Q{}", index + 1)),
+                    statements
+                        .first_report(None, quarter_end)
+                        .map(|fact| fact.first_filed),
+                    derived,
+                ),
+            );
+            previous_end = Some(quarter_end);
+        }
+    }
+
+    data.sort_by(|a, b| {
+        b.period_end
+            .cmp(&a.period_end)
+            .then(a.fiscal_quarter.is_none().cmp(&b.fiscal_quarter.is_none()))
+    });
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fact(start: Option<&str>, end: &str, val: f64, filed: &str, fy: i64, fp: &str) -> Value {
+        let mut fact = json!({ "end": end, "val": val, "filed": filed, "fy": fy, "fp": fp });
+        if let Some(start) = start {
+            fact["start"] = json!(start);
+        }
+        fact
+    }
+
+    fn concept(unit: &str, facts: Vec<Value>) -> Value {
+        json!({ "units": { unit: facts } })
+    }
+
+    /// A fiscal year ending in September, like Apple's, with revenue tagged
+    /// under the old concept for Q1 and the new one afterwards
+    fn us_gaap() -> Value {
+        json!({
+            "Revenues": concept("USD", vec![
+                fact(Some("2022-09-25"), "2022-12-31", 117.0, "2023-02-03", 2023, "Q1"),
+            ]),
+            "RevenueFromContractWithCustomerExcludingAssessedTax": concept("USD", vec![
+                fact(Some("2023-01-01"), "2023-04-01", 95.0, "2023-05-05", 2023, "Q2"),
+                fact(Some("2022-09-25"), "2023-04-01", 212.0, "2023-05-05", 2023, "Q2"),
+                fact(Some("2023-04-02"), "2023-07-01", 82.0, "2023-08-04", 2023, "Q3"),
+                fact(Some("2022-09-25"), "2023-07-01", 294.0, "2023-08-04", 2023, "Q3"),
+                fact(Some("2022-09-25"), "2023-09-30", 383.0, "2023-11-03", 2023, "FY"),
+                // Comparative in the next 10-K, with a later fy
+                fact(Some("2022-09-25"), "2023-09-30", 383.0, "2024-11-01", 2024, "FY"),
+                // Year in progress
+                fact(Some("2023-10-01"), "2023-12-30", 120.0, "2024-02-02", 2024, "Q1"),
+            ]),
+            "NetCashProvidedByUsedInOperatingActivities": concept("USD", vec![
+                // Cash flows are reported year to date only
+                fact(Some("2022-09-25"), "2022-12-31", 34.0, "2023-02-03", 2023, "Q1"),
+                fact(Some("2022-09-25"), "2023-04-01", 62.0, "2023-05-05", 2023, "Q2"),
+                fact(Some("2022-09-25"), "2023-07-01", 88.0, "2023-08-04", 2023, "Q3"),
+                fact(Some("2022-09-25"), "2023-09-30", 110.0, "2023-11-03", 2023, "FY"),
+            ]),
+            "PaymentsToAcquirePropertyPlantAndEquipment": concept("USD", vec![
+                fact(Some("2022-09-25"), "2023-09-30", 11.0, "2023-11-03", 2023, "FY"),
+            ]),
+            "EarningsPerShareDiluted": concept("USD/shares", vec![
+                fact(Some("2022-09-25"), "2023-09-30", 6.13, "2023-11-03", 2023, "FY"),
+                // Restated in the next 10-K
+                fact(Some("2022-09-25"), "2023-09-30", 6.1, "2024-11-01", 2024, "FY"),
+            ]),
+            "Assets": concept("USD", vec![
+                fact(None, "2023-09-30", 352.0, "2023-11-03", 2023, "FY"),
+            ]),
+        })
+    }
+
+    #[test]
+    fn test_annual_figures() {
+        let data = financial_data(&us_gaap(), 5);
+        let annual = data.iter().find(|d| d.fiscal_quarter.is_none()).unwrap();
+
+        assert_eq!(annual.fiscal_year, "2023");
+        assert_eq!(annual.period_end, "2023-09-30");
+        assert_eq!(annual.filing_date, "2023-11-03");
+        assert_eq!(annual.revenue, Some(383.0));
+        assert_eq!(annual.free_cash_flow, Some(99.0));
+        assert_eq!(annual.total_assets, Some(352.0));
+        // Restatements replace the original value
+        assert_eq!(annual.eps_diluted, Some(6.1));
+        assert!(!annual.derived);
+    }
+
+    #[test]
+    fn test_quarterly_alignment() {
+        let data = financial_data(&us_gaap(), 5);
+        let quarter = |fy: &str, q: &str| {
+            data.iter()
+                .find(|d| d.fiscal_year == fy && d.fiscal_quarter.as_deref() == Some(q))
+                .unwrap_or_else(|| panic!("missing {fy} {q}"))
+        };
+
+        // Revenue falls back across concepts
+        assert_eq!(quarter("2023", "Q1").revenue, Some(117.0));
+        assert_eq!(quarter("2023", "Q2").revenue, Some(95.0));
+
+        // Q4 is the annual total minus nine months
+        let q4 = quarter("2023", "Q4");
+        assert_eq!(q4.revenue, Some(89.0));
+        assert_eq!(q4.period_start.as_deref(), Some("2023-07-02"));
+        assert_eq!(q4.filing_date, "2023-11-03");
+        assert!(q4.derived);
+
+        // Year-to-date cash flows become quarterly
+        assert_eq!(quarter("2023", "Q1").operating_cash_flow, Some(34.0));
+        assert_eq!(quarter("2023", "Q2").operating_cash_flow, Some(28.0));
+        assert_eq!(quarter("2023", "Q3").operating_cash_flow, Some(26.0));
+        assert_eq!(q4.operating_cash_flow, Some(22.0));
+        assert!(quarter("2023", "Q2").derived);
+
+        // The year in progress is labeled from its own filing
+        assert_eq!(quarter("2024", "Q1").revenue, Some(120.0));
+
+        // Newest first, quarters before the annual figures of the same day
+        assert_eq!(data[0].fiscal_quarter.as_deref(), Some("Q1"));
+        assert_eq!(data[1].fiscal_quarter.as_deref(), Some("Q4"));
+        assert_eq!(data[2].fiscal_quarter, None);
+        assert_eq!(data.len(), 6);
+    }
+
+    #[test]
+    fn test_known_at() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+        let data = financial_data(&known_at(&us_gaap(), date), 5);
+
+        // As first reported, and nothing filed after the date
+        let annual = data.iter().find(|d| d.fiscal_quarter.is_none()).unwrap();
+        assert_eq!(annual.eps_diluted, Some(6.13));
+        assert!(data.iter().all(|d| d.fiscal_year != "2024"));
+        assert_eq!(data.len(), 5);
+    }
+}
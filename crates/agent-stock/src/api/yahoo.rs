@@ -1,13 +1,42 @@
 //! Yahoo Finance API client
 
+use super::circuit::{self, CircuitBreaker};
+use super::dividends::DividendHistory;
+use super::esg::EsgScores;
+use super::estimates::EpsTrend;
+use super::yahoo_schema::{self, ParsedChart};
 use crate::error::{Result, StockError};
 use chrono::{DateTime, Datelike, Utc};
 use serde::{Deserialize, Serialize};
-use time::OffsetDateTime;
-use yahoo_finance_api as yahoo;
+use std::sync::Arc;
+
+/// Chart API base URL
+const YAHOO_BASE_URL: &str = "https://query1.finance.yahoo.com";
+
+/// Yahoo rejects requests without a browser User-Agent
+const USER_AGENT: &str = "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/122.0.0.0 Safari/537.36";
 
 /// Yahoo Finance API client
-pub struct YahooFinanceClient {}
+///
+/// Chart responses are validated against the expected schema before parsing;
+/// see [`yahoo_schema`] for drift detection and the fallback parser.
+#[derive(Clone)]
+pub struct YahooFinanceClient {
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl Default for YahooFinanceClient {
+    fn default() -> Self {
+        Self {
+            base_url: YAHOO_BASE_URL.to_string(),
+            client: reqwest::Client::builder()
+                .user_agent(USER_AGENT)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+}
 
 /// Stock quote data
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,36 +64,72 @@ pub struct CompanyInfo {
     pub dividend_yield: Option<f64>,
 }
 
+/// Sector and industry classification of a company
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssetProfile {
+    pub symbol: String,
+    pub sector: Option<String>,
+    pub industry: Option<String>,
+}
+
+impl AssetProfile {
+    /// Parse the `assetProfile` module of a quoteSummary response
+    ///
+    /// Funds have a profile without a sector, so both fields may be missing.
+    pub fn from_yahoo(symbol: &str, body: &serde_json::Value) -> Result<Self> {
+        let summary = &body["quoteSummary"];
+        if let Some(description) = summary["error"]["description"].as_str() {
+            return Err(StockError::YahooFinanceError(format!(
+                "No profile for {symbol}: {description}"
+            )));
+        }
+        let profile = &summary["result"][0]["assetProfile"];
+        if !profile.is_object() {
+            return Err(StockError::YahooFinanceError(format!(
+                "No profile for {symbol}"
+            )));
+        }
+
+        let text = |field: &str| {
+            profile[field]
+                .as_str()
+                .filter(|value| !value.is_empty())
+                .map(ToString::to_string)
+        };
+        Ok(Self {
+            symbol: symbol.to_string(),
+            sector: text("sector"),
+            industry: text("industry"),
+        })
+    }
+}
+
 impl YahooFinanceClient {
     /// Create a new Yahoo Finance client
     pub fn new() -> Self {
-        Self {}
+        Self::default()
     }
 
-    /// Get the latest quote for a symbol
-    pub async fn get_quote(&self, symbol: &str) -> Result<Quote> {
-        let provider = yahoo::YahooConnector::new()
-            .map_err(|e| StockError::YahooFinanceError(e.to_string()))?;
-
-        let response = provider
-            .get_latest_quotes(symbol, "1d")
-            .await
-            .map_err(|e| StockError::YahooFinanceError(e.to_string()))?;
+    /// Send chart requests to `base_url` (e.g. a mirror or a mock server)
+    /// instead of query1.finance.yahoo.com
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
 
-        let quote = response
-            .last_quote()
-            .map_err(|e| StockError::YahooFinanceError(e.to_string()))?;
+    /// Circuit breaker shared by every client of this endpoint
+    pub fn breaker(&self) -> Arc<CircuitBreaker> {
+        circuit::breaker("Yahoo Finance", &self.base_url)
+    }
 
-        Ok(Quote {
-            symbol: symbol.to_string(),
-            timestamp: DateTime::from_timestamp(quote.timestamp, 0).unwrap_or_else(Utc::now),
-            open: quote.open,
-            high: quote.high,
-            low: quote.low,
-            close: quote.close,
-            volume: quote.volume,
-            adjclose: quote.adjclose,
-        })
+    /// Get the latest quote for a symbol
+    pub async fn get_quote(&self, symbol: &str) -> Result<Quote> {
+        let query = [("interval", "1d".to_string()), ("range", "1mo".to_string())];
+        self.fetch_chart(symbol, &query)
+            .await?
+            .quotes
+            .pop()
+            .ok_or_else(|| StockError::YahooFinanceError(format!("No quotes found for {symbol}")))
     }
 
     /// Get historical quotes for a symbol
@@ -74,38 +139,70 @@ impl YahooFinanceClient {
         start: DateTime<Utc>,
         end: DateTime<Utc>,
     ) -> Result<Vec<Quote>> {
-        let provider = yahoo::YahooConnector::new()
-            .map_err(|e| StockError::YahooFinanceError(e.to_string()))?;
+        let query = [
+            ("period1", start.timestamp().to_string()),
+            ("period2", end.timestamp().to_string()),
+            ("interval", "1d".to_string()),
+        ];
+        Ok(self.fetch_chart(symbol, &query).await?.quotes)
+    }
 
-        // Convert chrono DateTime to time OffsetDateTime
-        let start_odt = OffsetDateTime::from_unix_timestamp(start.timestamp()).map_err(|e| {
-            StockError::YahooFinanceError(format!("Invalid start timestamp: {e}"))
-        })?;
-        let end_odt = OffsetDateTime::from_unix_timestamp(end.timestamp())
-            .map_err(|e| StockError::YahooFinanceError(format!("Invalid end timestamp: {e}")))?;
+    /// Fetch a month of daily bars for `symbol` and report schema drift
+    ///
+    /// Used by health checks: succeeds whenever quotes can be recovered, with
+    /// the schema report and parser path describing how.
+    pub async fn check_chart(&self, symbol: &str) -> Result<ParsedChart> {
+        let query = [("interval", "1d".to_string()), ("range", "1mo".to_string())];
+        self.fetch_chart(symbol, &query).await
+    }
 
-        let response = provider
-            .get_quote_history(symbol, start_odt, end_odt)
-            .await
-            .map_err(|e| StockError::YahooFinanceError(e.to_string()))?;
-
-        let quotes = response
-            .quotes()
-            .map_err(|e| StockError::YahooFinanceError(e.to_string()))?;
-
-        Ok(quotes
-            .iter()
-            .map(|q| Quote {
-                symbol: symbol.to_string(),
-                timestamp: DateTime::from_timestamp(q.timestamp, 0).unwrap_or_else(Utc::now),
-                open: q.open,
-                high: q.high,
-                low: q.low,
-                close: q.close,
-                volume: q.volume,
-                adjclose: q.adjclose,
-            })
-            .collect())
+    /// Get up to ten years of dividends for a symbol, with its latest price
+    pub async fn get_dividends(&self, symbol: &str) -> Result<DividendHistory> {
+        let query = [
+            ("interval", "1mo".to_string()),
+            ("range", "10y".to_string()),
+            ("events", "div".to_string()),
+        ];
+        let body = self.fetch_chart_body(symbol, &query).await?;
+        DividendHistory::from_yahoo(symbol, &body)
+    }
+
+    /// Fetch and parse a chart response
+    async fn fetch_chart(&self, symbol: &str, query: &[(&str, String)]) -> Result<ParsedChart> {
+        let body = self.fetch_chart_body(symbol, query).await?;
+        yahoo_schema::parse_chart(symbol, &body)
+    }
+
+    /// Fetch a chart response, failing on errors reported in its body
+    async fn fetch_chart_body(
+        &self,
+        symbol: &str,
+        query: &[(&str, String)],
+    ) -> Result<serde_json::Value> {
+        let request = self
+            .client
+            .get(format!("{}/v8/finance/chart/{symbol}", self.base_url))
+            .query(query);
+        let response = self.breaker().send(request).await?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(StockError::rate_limited("Yahoo Finance"));
+        }
+
+        // Yahoo reports unknown symbols as a 404 with an error in the chart body
+        let body: serde_json::Value = response.json().await.map_err(|e| {
+            StockError::YahooFinanceError(format!("Invalid chart response ({status}): {e}"))
+        })?;
+        if let Some(reason) = yahoo_schema::chart_error(&body) {
+            return Err(StockError::YahooFinanceError(reason));
+        }
+        if !status.is_success() {
+            return Err(StockError::YahooFinanceError(format!(
+                "Yahoo Finance API error: {status}"
+            )));
+        }
+        Ok(body)
     }
 
     /// Get historical quotes with a specific range
@@ -114,7 +211,20 @@ impl YahooFinanceClient {
         symbol: &str,
         range: &str, // e.g., "1mo", "3mo", "1y"
     ) -> Result<Vec<Quote>> {
-        let end = Utc::now();
+        self.get_historical_range_at(symbol, range, Utc::now())
+            .await
+    }
+
+    /// Get historical quotes with a specific range ending at `end`
+    ///
+    /// Bars after `end` are dropped, so the result is what was known then;
+    /// see [`crate::as_of`].
+    pub async fn get_historical_range_at(
+        &self,
+        symbol: &str,
+        range: &str,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<Quote>> {
         let start = match range {
             "1d" => end - chrono::Duration::days(1),
             "5d" => end - chrono::Duration::days(5),
@@ -135,13 +245,80 @@ impl YahooFinanceClient {
             }
             "max" => end - chrono::Duration::days(36500), // ~100 years
             _ => {
-                return Err(StockError::InvalidSymbol(format!(
-                    "Invalid range: {range}"
-                )));
+                return Err(StockError::InvalidSymbol(format!("Invalid range: {range}")));
             }
         };
 
-        self.get_historical_quotes(symbol, start, end).await
+        let mut quotes = self.get_historical_quotes(symbol, start, end).await?;
+        quotes.retain(|quote| quote.timestamp <= end);
+        Ok(quotes)
+    }
+
+    /// Get ESG risk scores and controversies for a symbol
+    pub async fn get_esg_scores(&self, symbol: &str) -> Result<EsgScores> {
+        let request = self
+            .client
+            .get(format!(
+                "{}/v10/finance/quoteSummary/{symbol}",
+                self.base_url
+            ))
+            .query(&[("modules", "esgScores")]);
+        let response = self.breaker().send(request).await?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(StockError::rate_limited("Yahoo Finance"));
+        }
+
+        // Like the chart API, missing data comes back as a 404 with an error body
+        let body: serde_json::Value = response.json().await.map_err(|e| {
+            StockError::YahooFinanceError(format!("Invalid ESG response ({status}): {e}"))
+        })?;
+        EsgScores::from_yahoo(symbol, &body)
+    }
+
+    /// Get consensus EPS estimates and their revisions for a symbol
+    pub async fn get_eps_trend(&self, symbol: &str) -> Result<Vec<EpsTrend>> {
+        let request = self
+            .client
+            .get(format!(
+                "{}/v10/finance/quoteSummary/{symbol}",
+                self.base_url
+            ))
+            .query(&[("modules", "earningsTrend")]);
+        let response = self.breaker().send(request).await?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(StockError::rate_limited("Yahoo Finance"));
+        }
+
+        let body: serde_json::Value = response.json().await.map_err(|e| {
+            StockError::YahooFinanceError(format!("Invalid estimates response ({status}): {e}"))
+        })?;
+        EpsTrend::from_yahoo(symbol, &body)
+    }
+
+    /// Get the sector and industry of a symbol
+    pub async fn get_asset_profile(&self, symbol: &str) -> Result<AssetProfile> {
+        let request = self
+            .client
+            .get(format!(
+                "{}/v10/finance/quoteSummary/{symbol}",
+                self.base_url
+            ))
+            .query(&[("modules", "assetProfile")]);
+        let response = self.breaker().send(request).await?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(StockError::rate_limited("Yahoo Finance"));
+        }
+
+        let body: serde_json::Value = response.json().await.map_err(|e| {
+            StockError::YahooFinanceError(format!("Invalid profile response ({status}): {e}"))
+        })?;
+        AssetProfile::from_yahoo(symbol, &body)
     }
 
     /// Get company information (basic implementation - Yahoo Finance API has limited support)
@@ -170,21 +347,116 @@ impl YahooFinanceClient {
     }
 }
 
-impl Default for YahooFinanceClient {
-    fn default() -> Self {
-        Self::new()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::testing::{MockApi, fixtures};
+    use crate::api::yahoo_schema::ParserPath;
+
+    #[tokio::test]
+    async fn test_contract_quote_parsing() {
+        let api = MockApi::recorded().await;
+        let client = api.yahoo();
+
+        let quote = client.get_quote("AAPL").await.unwrap();
+        assert_eq!(quote.symbol, "AAPL");
+        assert!((quote.close - 171.13).abs() < 1e-9);
+        assert_eq!(quote.volume, 51_948_951);
+        assert_eq!(quote.timestamp.timestamp(), 1_710_336_600);
+
+        let end = DateTime::from_timestamp(1_710_400_000, 0).unwrap();
+        let quotes = client
+            .get_historical_quotes("AAPL", end - chrono::Duration::days(7), end)
+            .await
+            .unwrap();
+        assert_eq!(quotes.len(), 3);
+        assert!((quotes[0].adjclose - 172.2).abs() < 1e-9);
     }
-}
 
-impl Clone for YahooFinanceClient {
-    fn clone(&self) -> Self {
-        Self::new()
+    #[tokio::test]
+    async fn test_contract_error_mapping() {
+        let api = MockApi::recorded().await;
+        let client = api.yahoo();
+
+        let err = client.get_quote("NOPE").await.unwrap_err();
+        assert!(
+            matches!(&err, StockError::YahooFinanceError(msg) if msg.contains("delisted")),
+            "{err}"
+        );
+        assert!(!client.validate_symbol("NOPE").await.unwrap());
+
+        let api = MockApi::start().await;
+        api.mount_json("/v8/finance/chart/AAPL", 429, "Too Many Requests")
+            .await;
+        let err = api.yahoo().get_quote("AAPL").await.unwrap_err();
+        assert!(matches!(err, StockError::RateLimitExceeded { .. }), "{err}");
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[tokio::test]
+    async fn test_contract_legacy_shape_falls_back() {
+        let api = MockApi::start().await;
+        api.mount_json("/v8/finance/chart/AAPL", 200, fixtures::YAHOO_CHART_LEGACY)
+            .await;
+        let client = api.yahoo();
+
+        let quote = client.get_quote("AAPL").await.unwrap();
+        assert!((quote.close - 171.13).abs() < 1e-9);
+
+        let parsed = client.check_chart("AAPL").await.unwrap();
+        assert_eq!(parsed.parser, ParserPath::Fallback);
+        assert!(!parsed.report.is_valid());
+    }
+
+    #[tokio::test]
+    async fn test_contract_esg_scores() {
+        let api = MockApi::start().await;
+        api.mount_json(
+            "/v10/finance/quoteSummary/TSLA",
+            200,
+            r#"{"quoteSummary":{"result":[{"esgScores":{"totalEsg":{"raw":25.02},"peerGroup":"Automobiles"}}],"error":null}}"#,
+        )
+        .await;
+        api.mount_json(
+            "/v10/finance/quoteSummary/NOPE",
+            404,
+            r#"{"quoteSummary":{"result":null,"error":{"code":"Not Found","description":"Quote not found"}}}"#,
+        )
+        .await;
+        let client = api.yahoo();
+
+        let scores = client.get_esg_scores("TSLA").await.unwrap();
+        assert_eq!(scores.total, Some(25.02));
+        assert_eq!(scores.peer_group.as_deref(), Some("Automobiles"));
+
+        let err = client.get_esg_scores("NOPE").await.unwrap_err();
+        assert!(matches!(err, StockError::YahooFinanceError(_)), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_contract_asset_profile() {
+        let api = MockApi::start().await;
+        api.mount_json(
+            "/v10/finance/quoteSummary/AAPL",
+            200,
+            r#"{"quoteSummary":{"result":[{"assetProfile":{"sector":"Technology","industry":"Consumer Electronics","fullTimeEmployees":161000}}],"error":null}}"#,
+        )
+        .await;
+        api.mount_json(
+            "/v10/finance/quoteSummary/SPY",
+            200,
+            r#"{"quoteSummary":{"result":[{"assetProfile":{"longBusinessSummary":"The Trust seeks to achieve its investment objective..."}}],"error":null}}"#,
+        )
+        .await;
+        let client = api.yahoo();
+
+        let profile = client.get_asset_profile("AAPL").await.unwrap();
+        assert_eq!(profile.sector.as_deref(), Some("Technology"));
+        assert_eq!(profile.industry.as_deref(), Some("Consumer Electronics"));
+
+        let fund = client.get_asset_profile("SPY").await.unwrap();
+        assert_eq!(fund.sector, None);
+        assert!(client.get_asset_profile("NOPE").await.is_err());
+    }
 
     #[tokio::test]
     #[ignore] // Requires network access
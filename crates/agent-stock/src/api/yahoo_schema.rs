@@ -0,0 +1,545 @@
+//! Schema validation and drift detection for Yahoo Finance chart responses
+//!
+//! Yahoo's chart endpoint is unofficial and changes shape without notice.
+//! [`validate_chart`] checks a raw response against the shape the strict
+//! parser expects and reports every field that disappeared or changed type.
+//! [`parse_chart`] tries the strict parser first and falls back to a lenient
+//! parser that understands known older shapes:
+//!
+//! - `indicators.quote` as a single object instead of a one-element array
+//! - `indicators.adjclose` as a bare array instead of `[{ "adjclose": [...] }]`
+//! - `meta` without the trading-period and range fields
+//! - no `adjclose` block at all (intraday intervals); `close` is used instead
+
+use serde_json::Value;
+use std::fmt;
+use yahoo_finance_api as yahoo;
+
+use super::yahoo::Quote;
+use crate::error::{Result, StockError};
+use chrono::{DateTime, Utc};
+
+/// JSON type expected at a path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JsonType {
+    Object,
+    Array,
+    String,
+    Number,
+    Bool,
+}
+
+impl JsonType {
+    fn matches(self, value: &Value) -> bool {
+        match self {
+            Self::Object => value.is_object(),
+            Self::Array => value.is_array(),
+            Self::String => value.is_string(),
+            Self::Number => value.is_number(),
+            Self::Bool => value.is_boolean(),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Object => "object",
+            Self::Array => "array",
+            Self::String => "string",
+            Self::Number => "number",
+            Self::Bool => "bool",
+        }
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// What is wrong at a path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IssueKind {
+    /// The field is absent (or null)
+    Missing,
+    /// The field has a different JSON type
+    WrongType {
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// An indicator series does not line up with the timestamps
+    LengthMismatch { expected: usize, found: usize },
+}
+
+/// A single deviation from the expected schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaIssue {
+    /// JSON path, e.g. `chart.result[0].indicators.quote[0].close`
+    pub path: String,
+    pub kind: IssueKind,
+}
+
+impl fmt::Display for SchemaIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            IssueKind::Missing => write!(f, "{} is missing", self.path),
+            IssueKind::WrongType { expected, found } => {
+                write!(f, "{} is {found}, expected {expected}", self.path)
+            }
+            IssueKind::LengthMismatch { expected, found } => {
+                write!(f, "{} has {found} values, expected {expected}", self.path)
+            }
+        }
+    }
+}
+
+/// Result of validating a chart response
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchemaReport {
+    pub issues: Vec<SchemaIssue>,
+}
+
+impl SchemaReport {
+    /// Whether the response matches the expected schema exactly
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl fmt::Display for SchemaReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.issues.is_empty() {
+            return write!(f, "schema ok");
+        }
+        for (i, issue) in self.issues.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{issue}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Which parser produced the quotes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParserPath {
+    /// The response matched the current schema
+    Strict,
+    /// The strict parser failed and the lenient parser recovered the quotes
+    Fallback,
+}
+
+/// Quotes parsed from a chart response, with schema diagnostics
+#[derive(Debug, Clone)]
+pub struct ParsedChart {
+    pub quotes: Vec<Quote>,
+    pub report: SchemaReport,
+    pub parser: ParserPath,
+}
+
+/// `meta` fields the strict parser requires
+const META_FIELDS: &[(&str, JsonType)] = &[
+    ("symbol", JsonType::String),
+    ("instrumentType", JsonType::String),
+    ("exchangeName", JsonType::String),
+    ("fullExchangeName", JsonType::String),
+    ("gmtoffset", JsonType::Number),
+    ("timezone", JsonType::String),
+    ("exchangeTimezoneName", JsonType::String),
+    ("hasPrePostMarketData", JsonType::Bool),
+    ("priceHint", JsonType::Number),
+    ("currentTradingPeriod", JsonType::Object),
+    ("dataGranularity", JsonType::String),
+    ("range", JsonType::String),
+    ("validRanges", JsonType::Array),
+];
+
+/// Per-bar indicator series
+const QUOTE_SERIES: &[&str] = &["open", "high", "low", "close", "volume"];
+
+#[derive(Default)]
+struct Validator {
+    issues: Vec<SchemaIssue>,
+}
+
+impl Validator {
+    fn field<'a>(
+        &mut self,
+        parent: &'a Value,
+        parent_path: &str,
+        key: &str,
+        expected: JsonType,
+    ) -> Option<&'a Value> {
+        self.check(parent.get(key), format!("{parent_path}.{key}"), expected)
+    }
+
+    fn first<'a>(&mut self, array: &'a Value, path: &str, expected: JsonType) -> Option<&'a Value> {
+        self.check(array.get(0), format!("{path}[0]"), expected)
+    }
+
+    fn check<'a>(
+        &mut self,
+        value: Option<&'a Value>,
+        path: String,
+        expected: JsonType,
+    ) -> Option<&'a Value> {
+        match value {
+            None | Some(Value::Null) => {
+                self.issues.push(SchemaIssue {
+                    path,
+                    kind: IssueKind::Missing,
+                });
+                None
+            }
+            Some(value) if !expected.matches(value) => {
+                self.issues.push(SchemaIssue {
+                    path,
+                    kind: IssueKind::WrongType {
+                        expected: expected.name(),
+                        found: type_name(value),
+                    },
+                });
+                None
+            }
+            Some(value) => Some(value),
+        }
+    }
+
+    /// Check a numeric series: numbers or nulls, one per timestamp
+    fn series(&mut self, series: &Value, path: &str, expected_len: Option<usize>) {
+        let Some(values) = series.as_array() else {
+            return;
+        };
+        if let Some((i, value)) = values
+            .iter()
+            .enumerate()
+            .find(|(_, v)| !v.is_null() && !v.is_number())
+        {
+            self.issues.push(SchemaIssue {
+                path: format!("{path}[{i}]"),
+                kind: IssueKind::WrongType {
+                    expected: "number",
+                    found: type_name(value),
+                },
+            });
+        }
+        if let Some(expected) = expected_len
+            && values.len() != expected
+        {
+            self.issues.push(SchemaIssue {
+                path: path.to_string(),
+                kind: IssueKind::LengthMismatch {
+                    expected,
+                    found: values.len(),
+                },
+            });
+        }
+    }
+}
+
+/// Validate a chart response against the schema the strict parser expects
+pub fn validate_chart(json: &Value) -> SchemaReport {
+    let mut v = Validator::default();
+
+    let Some(chart) = v.check(json.get("chart"), "chart".to_string(), JsonType::Object) else {
+        return SchemaReport { issues: v.issues };
+    };
+    let Some(results) = v.field(chart, "chart", "result", JsonType::Array) else {
+        return SchemaReport { issues: v.issues };
+    };
+    let Some(result) = v.first(results, "chart.result", JsonType::Object) else {
+        return SchemaReport { issues: v.issues };
+    };
+    let path = "chart.result[0]";
+
+    if let Some(meta) = v.field(result, path, "meta", JsonType::Object) {
+        for (key, expected) in META_FIELDS {
+            v.field(meta, &format!("{path}.meta"), key, *expected);
+        }
+    }
+
+    let timestamps = v.field(result, path, "timestamp", JsonType::Array);
+    if let Some(timestamps) = timestamps {
+        v.series(timestamps, &format!("{path}.timestamp"), None);
+    }
+    let expected_len = timestamps.and_then(Value::as_array).map(Vec::len);
+
+    let Some(indicators) = v.field(result, path, "indicators", JsonType::Object) else {
+        return SchemaReport { issues: v.issues };
+    };
+    let indicators_path = format!("{path}.indicators");
+
+    if let Some(quotes) = v.field(indicators, &indicators_path, "quote", JsonType::Array) {
+        let quotes_path = format!("{indicators_path}.quote");
+        if let Some(quote) = v.first(quotes, &quotes_path, JsonType::Object) {
+            for key in QUOTE_SERIES {
+                let series_path = format!("{quotes_path}[0]");
+                if let Some(series) = v.field(quote, &series_path, key, JsonType::Array) {
+                    v.series(series, &format!("{series_path}.{key}"), expected_len);
+                }
+            }
+        }
+    }
+
+    // adjclose is optional (intraday charts omit it), but must be well formed
+    if let Some(adjclose) = indicators.get("adjclose").filter(|a| !a.is_null()) {
+        let adj_path = format!("{indicators_path}.adjclose");
+        if let Some(block) = v.check(Some(adjclose), adj_path.clone(), JsonType::Array)
+            && let Some(block) = v.first(block, &adj_path, JsonType::Object)
+            && let Some(series) = v.field(
+                block,
+                &format!("{adj_path}[0]"),
+                "adjclose",
+                JsonType::Array,
+            )
+        {
+            v.series(series, &format!("{adj_path}[0].adjclose"), expected_len);
+        }
+    }
+
+    SchemaReport { issues: v.issues }
+}
+
+/// Error reported in the chart body (e.g. unknown symbols), if any
+pub fn chart_error(json: &Value) -> Option<String> {
+    let chart = json.get("chart")?;
+    if chart.get("result").is_some_and(|r| !r.is_null()) {
+        return None;
+    }
+    let error = chart.get("error").filter(|e| !e.is_null())?;
+    error
+        .get("description")
+        .or_else(|| error.get("code"))
+        .and_then(Value::as_str)
+        .map(ToString::to_string)
+}
+
+/// Parse quotes from a chart response
+///
+/// Bars with missing prices are skipped. When the response has drifted from
+/// the expected schema, the diagnostics are logged and the lenient parser is
+/// tried; if that also fails, the error lists every schema issue.
+pub fn parse_chart(symbol: &str, json: &Value) -> Result<ParsedChart> {
+    if let Some(reason) = chart_error(json) {
+        return Err(StockError::YahooFinanceError(reason));
+    }
+
+    let report = validate_chart(json);
+    if report.is_valid()
+        && let Some(quotes) = parse_strict(symbol, json)
+    {
+        return Ok(ParsedChart {
+            quotes,
+            report,
+            parser: ParserPath::Strict,
+        });
+    }
+
+    tracing::warn!(
+        symbol,
+        "Yahoo chart response drifted from the expected schema: {report}"
+    );
+
+    match parse_lenient(symbol, json) {
+        Some(quotes) => Ok(ParsedChart {
+            quotes,
+            report,
+            parser: ParserPath::Fallback,
+        }),
+        None => Err(StockError::YahooFinanceError(format!(
+            "Unrecognized chart response for {symbol}: {report}"
+        ))),
+    }
+}
+
+fn parse_strict(symbol: &str, json: &Value) -> Option<Vec<Quote>> {
+    let response = yahoo::YResponse::from_json(json.clone()).ok()?;
+    let quotes = response.quotes().ok()?;
+    Some(
+        quotes
+            .iter()
+            .map(|q| Quote {
+                symbol: symbol.to_string(),
+                timestamp: timestamp(q.timestamp),
+                open: q.open,
+                high: q.high,
+                low: q.low,
+                close: q.close,
+                volume: q.volume,
+                adjclose: q.adjclose,
+            })
+            .collect(),
+    )
+}
+
+/// Parse whatever quote data is recognisable, ignoring `meta` entirely
+fn parse_lenient(symbol: &str, json: &Value) -> Option<Vec<Quote>> {
+    let result = json.pointer("/chart/result/0")?;
+    let timestamps = result.get("timestamp")?.as_array()?;
+    let indicators = result.get("indicators")?;
+    let quote = match indicators.get("quote")? {
+        Value::Array(quotes) => quotes.first()?,
+        quote @ Value::Object(_) => quote,
+        _ => return None,
+    };
+
+    let series = |value: Option<&Value>| -> Vec<Option<f64>> {
+        value
+            .and_then(Value::as_array)
+            .map(|values| values.iter().map(Value::as_f64).collect())
+            .unwrap_or_default()
+    };
+    let open = series(quote.get("open"));
+    let high = series(quote.get("high"));
+    let low = series(quote.get("low"));
+    let close = series(quote.get("close"));
+    let volume = series(quote.get("volume"));
+    let adjclose = series(
+        indicators
+            .pointer("/adjclose/0/adjclose")
+            .or_else(|| indicators.get("adjclose")),
+    );
+    if close.is_empty() {
+        return None;
+    }
+
+    let at = |values: &[Option<f64>], i: usize| values.get(i).copied().flatten();
+    let quotes = timestamps
+        .iter()
+        .enumerate()
+        .filter_map(|(i, ts)| {
+            let ts = ts.as_i64()?;
+            let close = at(&close, i)?;
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let volume = at(&volume, i).unwrap_or(0.0).max(0.0) as u64;
+            Some(Quote {
+                symbol: symbol.to_string(),
+                timestamp: timestamp(ts),
+                open: at(&open, i)?,
+                high: at(&high, i)?,
+                low: at(&low, i)?,
+                close,
+                volume,
+                adjclose: at(&adjclose, i).unwrap_or(close),
+            })
+        })
+        .collect();
+    Some(quotes)
+}
+
+fn timestamp(ts: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(ts, 0).unwrap_or_else(Utc::now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::testing::fixtures;
+
+    fn chart(fixture: &str) -> Value {
+        serde_json::from_str(fixture).unwrap()
+    }
+
+    #[test]
+    fn test_current_shape_is_valid() {
+        let json = chart(fixtures::YAHOO_CHART_AAPL);
+        assert!(validate_chart(&json).is_valid());
+
+        let parsed = parse_chart("AAPL", &json).unwrap();
+        assert_eq!(parsed.parser, ParserPath::Strict);
+        assert_eq!(parsed.quotes.len(), 3);
+    }
+
+    #[test]
+    fn test_reports_missing_and_retyped_fields() {
+        let mut json = chart(fixtures::YAHOO_CHART_AAPL);
+        let result = json.pointer_mut("/chart/result/0").unwrap();
+        result["meta"]
+            .as_object_mut()
+            .unwrap()
+            .remove("exchangeTimezoneName");
+        result["indicators"]["quote"][0]["close"] = Value::String("171.13".into());
+        result["indicators"]["quote"][0]["volume"]
+            .as_array_mut()
+            .unwrap()
+            .pop();
+
+        let report = validate_chart(&json);
+        assert_eq!(
+            report.issues,
+            vec![
+                SchemaIssue {
+                    path: "chart.result[0].meta.exchangeTimezoneName".into(),
+                    kind: IssueKind::Missing,
+                },
+                SchemaIssue {
+                    path: "chart.result[0].indicators.quote[0].close".into(),
+                    kind: IssueKind::WrongType {
+                        expected: "array",
+                        found: "string",
+                    },
+                },
+                SchemaIssue {
+                    path: "chart.result[0].indicators.quote[0].volume".into(),
+                    kind: IssueKind::LengthMismatch {
+                        expected: 3,
+                        found: 2,
+                    },
+                },
+            ]
+        );
+        assert!(
+            report
+                .to_string()
+                .contains("meta.exchangeTimezoneName is missing")
+        );
+    }
+
+    #[test]
+    fn test_legacy_shape_uses_fallback_parser() {
+        let json = chart(fixtures::YAHOO_CHART_LEGACY);
+        let report = validate_chart(&json);
+        assert!(!report.is_valid());
+        assert!(
+            report
+                .issues
+                .iter()
+                .any(|i| i.path == "chart.result[0].indicators.quote"),
+            "{report}"
+        );
+
+        let parsed = parse_chart("AAPL", &json).unwrap();
+        assert_eq!(parsed.parser, ParserPath::Fallback);
+        // The null bar is skipped and adjclose falls back to close
+        assert_eq!(parsed.quotes.len(), 2);
+        let last = parsed.quotes.last().unwrap();
+        assert!((last.close - 171.13).abs() < 1e-9);
+        assert!((last.adjclose - last.close).abs() < 1e-9);
+        assert_eq!(last.volume, 51_948_951);
+    }
+
+    #[test]
+    fn test_unrecognized_shape_lists_issues() {
+        let json = serde_json::json!({ "chart": { "result": [{ "meta": {}, "data": [] }] } });
+        let err = parse_chart("AAPL", &json).unwrap_err();
+        assert!(
+            matches!(&err, StockError::YahooFinanceError(msg)
+                if msg.contains("chart.result[0].timestamp is missing")
+                    && msg.contains("chart.result[0].indicators is missing")),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_chart_error_body() {
+        let json = chart(fixtures::YAHOO_CHART_NOT_FOUND);
+        assert!(chart_error(&json).unwrap().contains("delisted"));
+        assert!(chart_error(&chart(fixtures::YAHOO_CHART_AAPL)).is_none());
+    }
+}
@@ -0,0 +1,741 @@
+//! API keys with scopes, rate limits and daily budgets
+//!
+//! Services exposing the engine over HTTP hand each client an API key. A key
+//! carries the [`Scope`]s it may use (reading quotes is cheap, running an
+//! analysis spends LLM tokens), an optional per-minute request limit and an
+//! optional daily budget in US dollars of estimated LLM cost.
+//!
+//! The crate has no HTTP server of its own. A service built on it calls
+//! [`ApiKeyStore::authorize_request`] from its middleware before a handler:
+//! it reads the `Authorization: Bearer` header, resolves the secret, checks
+//! the scope the route needs, the rate limit and the day's budget, and
+//! counts the request. Handlers report what a request cost with
+//! [`ApiKeyStore::record_cost`]. Operators create and revoke keys and read
+//! their usage with `agent-cli api-keys`.
+//!
+//! Keys and their usage are persisted like the other stores (encrypted when
+//! opened with a cipher). Only a SHA-256 hash of each secret is stored; the
+//! secret itself is shown once, in the [`IssuedKey`]. Secrets embed their
+//! key's id, so a request looks up one key instead of comparing against all
+//! of them. Usage counted per request is written at most every
+//! [`USAGE_FLUSH_INTERVAL`], and when the store is flushed or dropped.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::api_keys::{ApiKeyStore, KeyLimits, Scope};
+//!
+//! let keys = ApiKeyStore::open_with_cipher("api_keys.json", StoreCipher::from_env()?)?;
+//! let issued = keys.create(
+//!     "dashboard",
+//!     &[Scope::ReadQuotes, Scope::RunAnalysis],
+//!     KeyLimits::default().per_minute(30).daily_budget(5.0),
+//! )?;
+//!
+//! // In middleware, for POST /analyze
+//! let key = keys.authorize_request("POST", "/analyze", headers.get("authorization"))?;
+//! // ... after the analysis
+//! keys.record_cost(&key.id, cost_usd)?;
+//! ```
+
+use chrono::{DateTime, NaiveDate, Utc};
+use governor::clock::DefaultClock;
+use governor::state::{InMemoryState, NotKeyed};
+use governor::{Quota, RateLimiter};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, PoisonError, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::error::{Result, StockError};
+use crate::storage::{self, StoreCipher};
+
+/// Environment variable naming the file API keys are persisted to
+pub const API_KEYS_FILE_ENV: &str = "STOCK_API_KEYS_FILE";
+
+/// Prefix of every issued secret, so leaked keys are easy to spot
+const SECRET_PREFIX: &str = "sk_";
+
+/// First path segments of routes that run an analysis, even through `GET`
+const ANALYSIS_ROUTES: [&str; 3] = ["analyze", "analysis", "analyses"];
+
+/// How often usage counted per request is written to the store at most
+pub const USAGE_FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+type KeyRateLimiter = RateLimiter<NotKeyed, InMemoryState, DefaultClock>;
+
+/// What a key is allowed to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Scope {
+    /// Quotes, snapshots and other market data
+    ReadQuotes,
+    /// Analyses that run the agents and spend LLM tokens
+    RunAnalysis,
+    /// Creating, revoking and listing keys
+    ManageKeys,
+}
+
+impl Scope {
+    /// Every scope
+    pub const ALL: [Self; 3] = [Self::ReadQuotes, Self::RunAnalysis, Self::ManageKeys];
+
+    /// Name used in requests and the store, e.g. `read-quotes`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::ReadQuotes => "read-quotes",
+            Self::RunAnalysis => "run-analysis",
+            Self::ManageKeys => "manage-keys",
+        }
+    }
+
+    /// Parse a scope name
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().replace('_', "-").as_str() {
+            "read-quotes" | "read" => Some(Self::ReadQuotes),
+            "run-analysis" | "analysis" => Some(Self::RunAnalysis),
+            "manage-keys" | "admin" => Some(Self::ManageKeys),
+            _ => None,
+        }
+    }
+
+    /// Scope a request to `path` needs
+    ///
+    /// Key management lives under `/keys`; anything that writes, or reads
+    /// under `/analyze`, `/analysis` or `/analyses`, needs
+    /// [`Scope::RunAnalysis`]; other reads need [`Scope::ReadQuotes`].
+    pub fn for_route(method: &str, path: &str) -> Self {
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+        let first = path
+            .split('/')
+            .find(|segment| !segment.is_empty())
+            .unwrap_or_default();
+        if first == "keys" {
+            Self::ManageKeys
+        } else if !method.eq_ignore_ascii_case("GET") || ANALYSIS_ROUTES.contains(&first) {
+            Self::RunAnalysis
+        } else {
+            Self::ReadQuotes
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Rate limit and budget of a key; `None` means unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct KeyLimits {
+    /// Requests allowed per minute
+    pub requests_per_minute: Option<u32>,
+    /// Estimated LLM cost allowed per UTC day, in US dollars
+    pub daily_budget_usd: Option<f64>,
+}
+
+impl KeyLimits {
+    /// Allow `requests` requests per minute
+    pub fn per_minute(mut self, requests: u32) -> Self {
+        self.requests_per_minute = Some(requests);
+        self
+    }
+
+    /// Allow `usd` of estimated LLM cost per day
+    pub fn daily_budget(mut self, usd: f64) -> Self {
+        self.daily_budget_usd = Some(usd);
+        self
+    }
+}
+
+/// Requests and cost charged to a key
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct KeyUsage {
+    /// UTC day the daily counters belong to
+    pub day: Option<NaiveDate>,
+    /// Requests on `day`
+    pub requests_today: u64,
+    /// Estimated cost on `day`, in US dollars
+    pub cost_today_usd: f64,
+    /// Requests since the key was created
+    pub total_requests: u64,
+    /// Estimated cost since the key was created, in US dollars
+    pub total_cost_usd: f64,
+    /// When the key was last used
+    pub last_used: Option<DateTime<Utc>>,
+}
+
+impl KeyUsage {
+    /// Start a new day's counters if `today` is not the current day
+    fn roll_over(&mut self, today: NaiveDate) {
+        if self.day != Some(today) {
+            self.day = Some(today);
+            self.requests_today = 0;
+            self.cost_today_usd = 0.0;
+        }
+    }
+}
+
+/// A stored API key
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiKey {
+    /// Public identifier, safe to log
+    pub id: String,
+    /// Who or what the key was issued to
+    pub name: String,
+    /// Hex SHA-256 of the secret; the secret itself is never stored
+    secret_sha256: String,
+    /// Scopes the key may use
+    pub scopes: Vec<Scope>,
+    /// Rate limit and daily budget
+    pub limits: KeyLimits,
+    /// When the key was created
+    pub created_at: DateTime<Utc>,
+    /// When the key was revoked, if it was
+    pub revoked_at: Option<DateTime<Utc>>,
+    /// Requests and cost so far
+    pub usage: KeyUsage,
+}
+
+impl ApiKey {
+    /// Whether the key may use `scope`
+    pub fn allows(&self, scope: Scope) -> bool {
+        self.scopes.contains(&scope)
+    }
+
+    /// Whether the key has been revoked
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at.is_some()
+    }
+
+    /// Budget left today, if the key has one
+    pub fn budget_left_today(&self) -> Option<f64> {
+        let spent = if self.usage.day == Some(Utc::now().date_naive()) {
+            self.usage.cost_today_usd
+        } else {
+            0.0
+        };
+        self.limits
+            .daily_budget_usd
+            .map(|budget| (budget - spent).max(0.0))
+    }
+}
+
+// Not even the secret's hash appears in logs
+impl fmt::Debug for ApiKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiKey")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("scopes", &self.scopes)
+            .field("limits", &self.limits)
+            .field("created_at", &self.created_at)
+            .field("revoked_at", &self.revoked_at)
+            .field("usage", &self.usage)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A newly created key; the secret is only shown here
+#[derive(Debug, Clone, PartialEq)]
+pub struct IssuedKey {
+    /// Public identifier
+    pub id: String,
+    /// Secret the client presents, e.g. in an `Authorization: Bearer` header
+    pub secret: String,
+}
+
+/// API keys persisted to a JSON file
+pub struct ApiKeyStore {
+    keys: RwLock<BTreeMap<String, ApiKey>>,
+    limiters: Mutex<HashMap<String, KeyRateLimiter>>,
+    path: Option<PathBuf>,
+    cipher: Option<StoreCipher>,
+    /// When the store was last written; held while writing so writes
+    /// happen in order
+    saved: Mutex<Instant>,
+    /// Whether usage was counted since the last write
+    dirty: AtomicBool,
+}
+
+impl Default for ApiKeyStore {
+    fn default() -> Self {
+        Self::in_memory()
+    }
+}
+
+impl ApiKeyStore {
+    /// Create a store that is not persisted
+    pub fn in_memory() -> Self {
+        Self {
+            keys: RwLock::new(BTreeMap::new()),
+            limiters: Mutex::new(HashMap::new()),
+            path: None,
+            cipher: None,
+            saved: Mutex::new(Instant::now()),
+            dirty: AtomicBool::new(false),
+        }
+    }
+
+    /// Open a store persisted at `path`, loading existing keys
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        Self::open_with_cipher(path, None)
+    }
+
+    /// Open a store persisted at `path`, encrypted with `cipher` if given
+    pub fn open_with_cipher(path: impl Into<PathBuf>, cipher: Option<StoreCipher>) -> Result<Self> {
+        let path = path.into();
+        let keys = match storage::read_store(&path, cipher.as_ref())? {
+            Some(json) => serde_json::from_str(&json)?,
+            None => BTreeMap::new(),
+        };
+
+        Ok(Self {
+            keys: RwLock::new(keys),
+            limiters: Mutex::new(HashMap::new()),
+            path: Some(path),
+            cipher,
+            saved: Mutex::new(Instant::now()),
+            dirty: AtomicBool::new(false),
+        })
+    }
+
+    /// File the store is persisted to, if any
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Write every key and its usage
+    fn persist(&self) -> Result<()> {
+        let mut saved = self.saved.lock().unwrap_or_else(PoisonError::into_inner);
+        self.persist_locked(&mut saved)
+    }
+
+    fn persist_locked(&self, saved: &mut Instant) -> Result<()> {
+        *saved = Instant::now();
+        self.dirty.store(false, Ordering::Relaxed);
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let json = {
+            let keys = self.keys.read().unwrap_or_else(PoisonError::into_inner);
+            serde_json::to_string_pretty(&*keys)?
+        };
+        storage::write_store(path, &json, self.cipher.as_ref())
+    }
+
+    /// Note counted usage, writing it if the last write is older than
+    /// [`USAGE_FLUSH_INTERVAL`]
+    fn persist_usage(&self) -> Result<()> {
+        self.dirty.store(true, Ordering::Relaxed);
+        let mut saved = self.saved.lock().unwrap_or_else(PoisonError::into_inner);
+        if saved.elapsed() < USAGE_FLUSH_INTERVAL {
+            return Ok(());
+        }
+        self.persist_locked(&mut saved)
+    }
+
+    /// Write usage counted since the last write; done on drop as well
+    pub fn flush(&self) -> Result<()> {
+        if !self.dirty.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        self.persist()
+    }
+
+    /// Issue a new key
+    pub fn create(&self, name: &str, scopes: &[Scope], limits: KeyLimits) -> Result<IssuedKey> {
+        if scopes.is_empty() {
+            return Err(StockError::ConfigError(
+                "An API key needs at least one scope".to_string(),
+            ));
+        }
+        let mut scopes = scopes.to_vec();
+        scopes.sort();
+        scopes.dedup();
+
+        let id = format!("key_{}", &uuid::Uuid::new_v4().simple().to_string()[..12]);
+        let secret = format!(
+            "{SECRET_PREFIX}{id}_{}{}",
+            uuid::Uuid::new_v4().simple(),
+            uuid::Uuid::new_v4().simple()
+        );
+        let key = ApiKey {
+            id: id.clone(),
+            name: name.trim().to_string(),
+            secret_sha256: sha256_hex(&secret),
+            scopes,
+            limits,
+            created_at: Utc::now(),
+            revoked_at: None,
+            usage: KeyUsage::default(),
+        };
+
+        self.keys
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(id.clone(), key);
+        self.persist()?;
+        Ok(IssuedKey { id, secret })
+    }
+
+    /// Revoke a key; returns whether an active key was revoked
+    pub fn revoke(&self, id: &str) -> Result<bool> {
+        {
+            let mut keys = self.keys.write().unwrap_or_else(PoisonError::into_inner);
+            let Some(key) = keys.get_mut(id).filter(|key| !key.is_revoked()) else {
+                return Ok(false);
+            };
+            key.revoked_at = Some(Utc::now());
+        }
+        self.persist()?;
+        self.limiters
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(id);
+        Ok(true)
+    }
+
+    /// Key `id`, if it exists
+    pub fn get(&self, id: &str) -> Option<ApiKey> {
+        let keys = self.keys.read().unwrap_or_else(PoisonError::into_inner);
+        keys.get(id).cloned()
+    }
+
+    /// Every key, revoked ones included, with its usage
+    pub fn list(&self) -> Vec<ApiKey> {
+        let keys = self.keys.read().unwrap_or_else(PoisonError::into_inner);
+        keys.values().cloned().collect()
+    }
+
+    /// Check a request made with `secret` that needs `scope`, and count it
+    ///
+    /// Fails with [`StockError::Unauthorized`] for an unknown or revoked key,
+    /// [`StockError::Forbidden`] when the key lacks the scope,
+    /// [`StockError::RateLimitExceeded`] when it is over its per-minute limit
+    /// and [`StockError::QuotaExceeded`] once the day's budget is spent.
+    pub fn authorize(&self, secret: &str, scope: Scope) -> Result<ApiKey> {
+        let mut keys = self.keys.write().unwrap_or_else(PoisonError::into_inner);
+        let hash = sha256_hex(secret);
+        let key = key_id(secret)
+            .and_then(|id| keys.get_mut(id))
+            .filter(|key| constant_time_eq(&key.secret_sha256, &hash))
+            .filter(|key| !key.is_revoked())
+            .ok_or_else(|| StockError::Unauthorized("invalid or revoked API key".to_string()))?;
+
+        if !key.allows(scope) {
+            return Err(StockError::Forbidden(format!(
+                "API key {} lacks scope {scope}",
+                key.id
+            )));
+        }
+        if key.budget_left_today() == Some(0.0) {
+            return Err(StockError::QuotaExceeded(format!(
+                "API key {} has spent its daily budget of ${:.2}",
+                key.id,
+                key.limits.daily_budget_usd.unwrap_or_default()
+            )));
+        }
+        if let Some(per_minute) = key.limits.requests_per_minute.and_then(NonZeroU32::new) {
+            let mut limiters = self.limiters.lock().unwrap_or_else(PoisonError::into_inner);
+            let limiter = limiters
+                .entry(key.id.clone())
+                .or_insert_with(|| RateLimiter::direct(Quota::per_minute(per_minute)));
+            if limiter.check().is_err() {
+                return Err(StockError::rate_limited(format!("API key {}", key.id)));
+            }
+        }
+
+        let now = Utc::now();
+        key.usage.roll_over(now.date_naive());
+        key.usage.requests_today += 1;
+        key.usage.total_requests += 1;
+        key.usage.last_used = Some(now);
+        let key = key.clone();
+        drop(keys);
+        self.persist_usage()?;
+        Ok(key)
+    }
+
+    /// Check an HTTP request to `path` with the given `Authorization` header
+    ///
+    /// The middleware entry point: takes the secret from a `Bearer` header
+    /// and the scope from [`Scope::for_route`], then checks and counts the
+    /// request like [`authorize`](Self::authorize). A missing or malformed
+    /// header is [`StockError::Unauthorized`].
+    pub fn authorize_request(
+        &self,
+        method: &str,
+        path: &str,
+        authorization: Option<&str>,
+    ) -> Result<ApiKey> {
+        let secret = authorization
+            .and_then(|header| header.trim().split_once(' '))
+            .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("bearer"))
+            .map(|(_, secret)| secret.trim())
+            .ok_or_else(|| {
+                StockError::Unauthorized("expected an Authorization: Bearer API key".to_string())
+            })?;
+        self.authorize(secret, Scope::for_route(method, path))
+    }
+
+    /// Charge `cost_usd` of estimated LLM cost to key `id`
+    pub fn record_cost(&self, id: &str, cost_usd: f64) -> Result<()> {
+        if cost_usd <= 0.0 {
+            return Ok(());
+        }
+        {
+            let mut keys = self.keys.write().unwrap_or_else(PoisonError::into_inner);
+            let Some(key) = keys.get_mut(id) else {
+                return Err(StockError::Other(format!("Unknown API key {id}")));
+            };
+            key.usage.roll_over(Utc::now().date_naive());
+            key.usage.cost_today_usd += cost_usd;
+            key.usage.total_cost_usd += cost_usd;
+        }
+        self.persist_usage()
+    }
+
+    /// Per-key usage as a plain-text table, active keys first
+    pub fn usage_report(&self) -> String {
+        let mut keys = self.list();
+        keys.sort_by_key(|key| {
+            (
+                key.is_revoked(),
+                std::cmp::Reverse(key.usage.total_requests),
+            )
+        });
+
+        let mut report = String::from(
+            "key                name              today  cost today     total  total cost\n",
+        );
+        for key in keys {
+            let today = key.usage.day == Some(Utc::now().date_naive());
+            let (requests, cost) = if today {
+                (key.usage.requests_today, key.usage.cost_today_usd)
+            } else {
+                (0, 0.0)
+            };
+            report.push_str(&format!(
+                "{:<18} {:<16} {:>6} {:>11} {:>9} {:>11}{}\n",
+                key.id,
+                key.name,
+                requests,
+                format!("${cost:.2}"),
+                key.usage.total_requests,
+                format!("${:.2}", key.usage.total_cost_usd),
+                if key.is_revoked() { "  (revoked)" } else { "" },
+            ));
+        }
+        report
+    }
+}
+
+impl Drop for ApiKeyStore {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            tracing::warn!("Could not save API key usage: {e}");
+        }
+    }
+}
+
+/// Id of the key a secret was issued for, e.g. `key_1a2b3c4d5e6f` in
+/// `sk_key_1a2b3c4d5e6f_<random>`
+fn key_id(secret: &str) -> Option<&str> {
+    let (id, _random) = secret.strip_prefix(SECRET_PREFIX)?.rsplit_once('_')?;
+    Some(id)
+}
+
+/// Hex SHA-256 of a secret, as stored
+fn sha256_hex(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
+
+/// Compare secret hashes without leaking where they differ through timing
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path() -> PathBuf {
+        std::env::temp_dir().join(format!("api-keys-{}.json", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn test_scopes() {
+        assert_eq!(Scope::parse("run_analysis"), Some(Scope::RunAnalysis));
+        assert_eq!(Scope::parse("read-quotes"), Some(Scope::ReadQuotes));
+        assert_eq!(Scope::parse("write"), None);
+
+        assert_eq!(Scope::for_route("GET", "/quote/AAPL"), Scope::ReadQuotes);
+        assert_eq!(Scope::for_route("POST", "/analyze"), Scope::RunAnalysis);
+        assert_eq!(Scope::for_route("GET", "/analysis/123"), Scope::RunAnalysis);
+        assert_eq!(
+            Scope::for_route("GET", "/analyze/AAPL/stream"),
+            Scope::RunAnalysis
+        );
+        assert_eq!(Scope::for_route("DELETE", "/keys/key_1"), Scope::ManageKeys);
+
+        // Only whole segments count
+        assert_eq!(Scope::for_route("GET", "/analytics"), Scope::ReadQuotes);
+        assert_eq!(Scope::for_route("GET", "/keystats"), Scope::ReadQuotes);
+        assert_eq!(
+            Scope::for_route("GET", "/quote/analysis"),
+            Scope::ReadQuotes
+        );
+    }
+
+    #[test]
+    fn test_authorize_checks_key_and_scope() {
+        let store = ApiKeyStore::in_memory();
+        let issued = store
+            .create("dashboard", &[Scope::ReadQuotes], KeyLimits::default())
+            .unwrap();
+        assert!(issued.secret.starts_with(SECRET_PREFIX));
+        assert_eq!(key_id(&issued.secret), Some(issued.id.as_str()));
+
+        let key = store.authorize(&issued.secret, Scope::ReadQuotes).unwrap();
+        assert_eq!(key.id, issued.id);
+        assert_eq!(key.usage.requests_today, 1);
+        assert!(!format!("{key:?}").contains(&issued.secret));
+
+        let err = store
+            .authorize(&issued.secret, Scope::RunAnalysis)
+            .unwrap_err();
+        assert!(matches!(err, StockError::Forbidden(_)), "{err}");
+        let err = store.authorize("sk_wrong", Scope::ReadQuotes).unwrap_err();
+        assert!(matches!(err, StockError::Unauthorized(_)), "{err}");
+        let forged = format!("{SECRET_PREFIX}{}_{}", issued.id, "0".repeat(64));
+        let err = store.authorize(&forged, Scope::ReadQuotes).unwrap_err();
+        assert!(matches!(err, StockError::Unauthorized(_)), "{err}");
+
+        // As middleware: bearer header, scope from the route
+        let header = format!("Bearer {}", issued.secret);
+        let key = store
+            .authorize_request("GET", "/quote/AAPL", Some(&header))
+            .unwrap();
+        assert_eq!(key.usage.requests_today, 2);
+        let err = store
+            .authorize_request("POST", "/analyze", Some(&header))
+            .unwrap_err();
+        assert!(matches!(err, StockError::Forbidden(_)), "{err}");
+        for header in [
+            None,
+            Some(issued.secret.as_str()),
+            Some("Basic dXNlcjpwYXNz"),
+        ] {
+            let err = store
+                .authorize_request("GET", "/quote/AAPL", header)
+                .unwrap_err();
+            assert!(matches!(err, StockError::Unauthorized(_)), "{err}");
+        }
+
+        assert!(store.revoke(&issued.id).unwrap());
+        assert!(!store.revoke(&issued.id).unwrap());
+        let err = store
+            .authorize(&issued.secret, Scope::ReadQuotes)
+            .unwrap_err();
+        assert!(matches!(err, StockError::Unauthorized(_)), "{err}");
+        assert!(store.create("empty", &[], KeyLimits::default()).is_err());
+    }
+
+    #[test]
+    fn test_rate_limit_and_daily_budget() {
+        let store = ApiKeyStore::in_memory();
+        let limits = KeyLimits::default().per_minute(2).daily_budget(1.0);
+        let issued = store.create("bot", &[Scope::RunAnalysis], limits).unwrap();
+
+        store.authorize(&issued.secret, Scope::RunAnalysis).unwrap();
+        store.authorize(&issued.secret, Scope::RunAnalysis).unwrap();
+        let err = store
+            .authorize(&issued.secret, Scope::RunAnalysis)
+            .unwrap_err();
+        assert!(matches!(err, StockError::RateLimitExceeded { .. }), "{err}");
+
+        let budgeted = store
+            .create(
+                "batch",
+                &[Scope::RunAnalysis],
+                KeyLimits::default().daily_budget(1.0),
+            )
+            .unwrap();
+        store
+            .authorize(&budgeted.secret, Scope::RunAnalysis)
+            .unwrap();
+        store.record_cost(&budgeted.id, 0.6).unwrap();
+        assert!(
+            (store
+                .get(&budgeted.id)
+                .unwrap()
+                .budget_left_today()
+                .unwrap()
+                - 0.4)
+                .abs()
+                < 1e-9
+        );
+        store
+            .authorize(&budgeted.secret, Scope::RunAnalysis)
+            .unwrap();
+        store.record_cost(&budgeted.id, 0.6).unwrap();
+        let err = store
+            .authorize(&budgeted.secret, Scope::RunAnalysis)
+            .unwrap_err();
+        assert!(matches!(err, StockError::QuotaExceeded(_)), "{err}");
+    }
+
+    #[test]
+    fn test_keys_and_usage_persist() {
+        let path = temp_path();
+        let issued = {
+            let store = ApiKeyStore::open(&path).unwrap();
+            let issued = store
+                .create(
+                    "dashboard",
+                    &[Scope::RunAnalysis, Scope::ReadQuotes],
+                    KeyLimits::default(),
+                )
+                .unwrap();
+            store.authorize(&issued.secret, Scope::RunAnalysis).unwrap();
+            store.record_cost(&issued.id, 0.25).unwrap();
+
+            // Usage is written in batches, not on every request
+            let stored = std::fs::read_to_string(&path).unwrap();
+            assert!(stored.contains("\"total_requests\": 0"), "{stored}");
+            issued
+        };
+
+        // Only the secret's hash is stored
+        let stored = std::fs::read_to_string(&path).unwrap();
+        assert!(!stored.contains(&issued.secret));
+        assert!(stored.contains(&sha256_hex(&issued.secret)));
+
+        let reopened = ApiKeyStore::open(&path).unwrap();
+        let key = reopened
+            .authorize(&issued.secret, Scope::ReadQuotes)
+            .unwrap();
+        assert_eq!(key.scopes, vec![Scope::ReadQuotes, Scope::RunAnalysis]);
+        assert_eq!(key.usage.total_requests, 2);
+        assert!((key.usage.total_cost_usd - 0.25).abs() < 1e-9);
+
+        let report = reopened.usage_report();
+        assert!(
+            report.contains(&issued.id) && report.contains("$0.25"),
+            "{report}"
+        );
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
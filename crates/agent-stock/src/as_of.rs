@@ -0,0 +1,126 @@
+//! Point-in-time ("as of") analysis
+//!
+//! An analysis as of a past date sees only what was known at the close of
+//! that day: price bars up to it, SEC filings filed by then (without later
+//! restatements) and news published by then. That makes retrospective
+//! analyses honest ("what would the agent have said about NVDA in January
+//! 2023?") and lets the [prediction tracker](crate::predictions) be
+//! evaluated on past dates without lookahead bias.
+//!
+//! [`apply_to`] puts the date on an agent context. The agent then tells the
+//! model which day it is (the `stock.as_of` template) and every tool call of
+//! the request gets an [`AS_OF_PARAM`] parameter pinned to the date (see
+//! [`agent_runtime::with_pinned_params`]), whatever the model asks for. Tools
+//! that cannot limit their data to a date do not declare the parameter, so
+//! they are not offered and calls to them fail instead of leaking what
+//! happened later.
+//!
+//! Prices are split-adjusted as Yahoo Finance reports them today, so price
+//! levels before a later split differ from the quotes of the day; returns
+//! and indicators are unaffected.
+
+use agent_core::Context;
+use agent_runtime::PINNED_PARAMS_CONTEXT_KEY;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Utc};
+use serde_json::{Value, json};
+
+use crate::error::{Result, StockError};
+
+/// Context key under which the analysis date is stored
+pub const AS_OF_CONTEXT_KEY: &str = "as_of";
+
+/// Tool parameter limiting data to what was known at the end of a day
+pub const AS_OF_PARAM: &str = "as_of";
+
+/// Analyse as of the end of `date`: store it in an agent context and pin it
+/// on the tool calls made with the context
+pub fn apply_to(date: NaiveDate, context: &mut Context) {
+    context.insert(AS_OF_CONTEXT_KEY, json!(date.to_string()));
+    context.insert(
+        PINNED_PARAMS_CONTEXT_KEY,
+        json!({ AS_OF_PARAM: date.to_string() }),
+    );
+}
+
+/// Analysis date stored in an agent context, if any
+pub fn from_context(context: &Context) -> Option<NaiveDate> {
+    context
+        .get(AS_OF_CONTEXT_KEY)
+        .and_then(Value::as_str)
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+}
+
+/// Last moment of `date` in UTC; anything stamped later was not yet known
+pub fn cutoff(date: NaiveDate) -> DateTime<Utc> {
+    date.and_time(NaiveTime::MIN).and_utc() + Duration::days(1) - Duration::seconds(1)
+}
+
+/// Parse a date to analyse as of: `2023-01-31`, or `2023-01` for the last
+/// day of the month
+pub fn parse(s: &str) -> Option<NaiveDate> {
+    let s = s.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Some(date);
+    }
+    let first = NaiveDate::parse_from_str(&format!("{s}-01"), "%Y-%m-%d").ok()?;
+    let next_month = first.checked_add_months(chrono::Months::new(1))?;
+    next_month
+        .pred_opt()
+        .filter(|date| date.month() == first.month())
+}
+
+/// Check that `date` is in the past; an analysis as of today is a normal one
+pub fn validate(date: NaiveDate) -> Result<NaiveDate> {
+    if date >= Utc::now().date_naive() {
+        return Err(StockError::CommandError(format!(
+            "{date} is not in the past; point-in-time analyses need an earlier date"
+        )));
+    }
+    Ok(date)
+}
+
+/// JSON schema of the [`AS_OF_PARAM`] tool parameter
+pub fn param_schema() -> Value {
+    json!({
+        "type": "string",
+        "description": "Only use data known at the end of this day (YYYY-MM-DD); omit for the latest data"
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_apply_to_context() {
+        let mut context = Context::new();
+        assert_eq!(from_context(&context), None);
+
+        apply_to(date("2023-01-31"), &mut context);
+        assert_eq!(from_context(&context), Some(date("2023-01-31")));
+        assert_eq!(
+            context.get(PINNED_PARAMS_CONTEXT_KEY),
+            Some(&json!({ "as_of": "2023-01-31" }))
+        );
+    }
+
+    #[test]
+    fn test_parse_and_cutoff() {
+        assert_eq!(parse("2023-01-15"), Some(date("2023-01-15")));
+        assert_eq!(parse("2023-01"), Some(date("2023-01-31")));
+        assert_eq!(parse("2024-02"), Some(date("2024-02-29")));
+        assert_eq!(parse("Jan 2023"), None);
+        assert_eq!(parse("deep"), None);
+
+        assert_eq!(
+            cutoff(date("2023-01-31")).to_rfc3339(),
+            "2023-01-31T23:59:59+00:00"
+        );
+        assert!(validate(date("2023-01-31")).is_ok());
+        assert!(validate(Utc::now().date_naive()).is_err());
+    }
+}
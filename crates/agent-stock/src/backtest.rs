@@ -0,0 +1,682 @@
+//! Backtesting of technical trading strategies
+//!
+//! [`run_backtest`] replays daily candles through a [`Strategy`] and reports
+//! P&L, drawdown, Sharpe ratio and the trade log, so questions like "how
+//! would an RSI cross have done on AAPL over the last two years" get numbers
+//! rather than a guess.
+//!
+//! The simulation is long-only and fully invested while in a position.
+//! Signals are computed on a bar's close and filled at the next bar's open, so
+//! a strategy never trades on a price it could not have known.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::backtest::{BacktestConfig, Candle, Strategy, run_backtest};
+//!
+//! let quotes = YahooFinanceClient::new().get_historical_range("AAPL", "2y").await?;
+//! let candles: Vec<Candle> = quotes.iter().map(Candle::from).collect();
+//! let report = run_backtest("AAPL", &candles, &"rsi".parse()?, &BacktestConfig::default())?;
+//! println!("{report}");
+//! ```
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use ta::Next;
+use ta::indicators::{
+    MovingAverageConvergenceDivergence, RelativeStrengthIndex, SimpleMovingAverage,
+};
+
+use crate::api::alpha_vantage::TimeSeriesData;
+use crate::api::yahoo::Quote;
+use crate::error::{Result, StockError};
+
+/// Trading days per year, for annualizing the Sharpe ratio
+const TRADING_DAYS_PER_YEAR: f64 = 252.0;
+
+/// One daily bar
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Candle {
+    pub date: NaiveDate,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: u64,
+}
+
+impl From<&Quote> for Candle {
+    fn from(quote: &Quote) -> Self {
+        Self {
+            date: quote.timestamp.date_naive(),
+            open: quote.open,
+            high: quote.high,
+            low: quote.low,
+            close: quote.close,
+            volume: quote.volume,
+        }
+    }
+}
+
+impl Candle {
+    /// Candle from an Alpha Vantage daily bar, `None` if its date is malformed
+    pub fn from_time_series(bar: &TimeSeriesData) -> Option<Self> {
+        Some(Self {
+            date: NaiveDate::parse_from_str(bar.timestamp.get(..10)?, "%Y-%m-%d").ok()?,
+            open: bar.open,
+            high: bar.high,
+            low: bar.low,
+            close: bar.close,
+            volume: bar.volume,
+        })
+    }
+
+    /// Price orders placed the previous day are filled at: the open, or the
+    /// close when the open is missing
+    fn fill_price(&self) -> f64 {
+        if self.open > 0.0 {
+            self.open
+        } else {
+            self.close
+        }
+    }
+}
+
+/// What a strategy wants to do after a bar closes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Signal {
+    Buy,
+    Sell,
+    Hold,
+}
+
+/// A rule turning closing prices into buy and sell signals
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Strategy {
+    /// Buy when RSI crosses up through `oversold`, sell when it crosses down
+    /// through `overbought`
+    RsiCross {
+        period: usize,
+        oversold: f64,
+        overbought: f64,
+    },
+    /// Buy when the MACD line crosses above its signal line, sell when it
+    /// crosses below
+    MacdSignal {
+        fast: usize,
+        slow: usize,
+        signal: usize,
+    },
+    /// Buy when the fast SMA crosses above the slow SMA, sell on the cross
+    /// below
+    SmaCross { fast: usize, slow: usize },
+}
+
+impl Strategy {
+    /// RSI(14) crossing 30 and 70
+    pub const RSI_CROSS: Self = Strategy::RsiCross {
+        period: 14,
+        oversold: 30.0,
+        overbought: 70.0,
+    };
+    /// MACD(12, 26) against its 9-day signal line
+    pub const MACD_SIGNAL: Self = Strategy::MacdSignal {
+        fast: 12,
+        slow: 26,
+        signal: 9,
+    };
+    /// 50-day SMA crossing the 200-day SMA (golden and death crosses)
+    pub const SMA_CROSS: Self = Strategy::SmaCross {
+        fast: 50,
+        slow: 200,
+    };
+
+    /// Bars before the strategy's indicators settle; no signals are taken
+    /// until then
+    pub fn warmup(&self) -> usize {
+        match *self {
+            Strategy::RsiCross { period, .. } => period,
+            Strategy::MacdSignal { slow, signal, .. } => slow + signal,
+            Strategy::SmaCross { slow, .. } => slow,
+        }
+    }
+
+    /// Check the parameters make sense
+    pub fn validate(&self) -> Result<()> {
+        let problem = match *self {
+            Strategy::RsiCross { period: 0, .. }
+            | Strategy::MacdSignal { fast: 0, .. }
+            | Strategy::MacdSignal { signal: 0, .. }
+            | Strategy::SmaCross { fast: 0, .. } => Some("periods must be positive"),
+            Strategy::RsiCross {
+                oversold,
+                overbought,
+                ..
+            } if !(0.0..overbought).contains(&oversold) || overbought > 100.0 => {
+                Some("RSI levels must satisfy 0 <= oversold < overbought <= 100")
+            }
+            Strategy::MacdSignal { fast, slow, .. } | Strategy::SmaCross { fast, slow }
+                if fast >= slow =>
+            {
+                Some("the fast period must be shorter than the slow period")
+            }
+            _ => None,
+        };
+        match problem {
+            Some(problem) => Err(StockError::IndicatorError(format!(
+                "Invalid {self}: {problem}"
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Signal after each close
+    pub fn signals(&self, closes: &[f64]) -> Result<Vec<Signal>> {
+        self.validate()?;
+        let indicator_error = |e: ta::errors::TaError| StockError::IndicatorError(e.to_string());
+
+        let signals = match *self {
+            Strategy::RsiCross {
+                period,
+                oversold,
+                overbought,
+            } => {
+                let mut rsi = RelativeStrengthIndex::new(period).map_err(indicator_error)?;
+                let values: Vec<f64> = closes.iter().map(|&close| rsi.next(close)).collect();
+                values
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &value)| {
+                        let Some(&previous) = i.checked_sub(1).and_then(|p| values.get(p)) else {
+                            return Signal::Hold;
+                        };
+                        if previous <= oversold && value > oversold {
+                            Signal::Buy
+                        } else if previous >= overbought && value < overbought {
+                            Signal::Sell
+                        } else {
+                            Signal::Hold
+                        }
+                    })
+                    .collect()
+            }
+            Strategy::MacdSignal { fast, slow, signal } => {
+                let mut macd = MovingAverageConvergenceDivergence::new(fast, slow, signal)
+                    .map_err(indicator_error)?;
+                let spread: Vec<f64> = closes
+                    .iter()
+                    .map(|&close| {
+                        let output = macd.next(close);
+                        output.macd - output.signal
+                    })
+                    .collect();
+                zero_crosses(&spread)
+            }
+            Strategy::SmaCross { fast, slow } => {
+                let mut fast_sma = SimpleMovingAverage::new(fast).map_err(indicator_error)?;
+                let mut slow_sma = SimpleMovingAverage::new(slow).map_err(indicator_error)?;
+                let spread: Vec<f64> = closes
+                    .iter()
+                    .map(|&close| fast_sma.next(close) - slow_sma.next(close))
+                    .collect();
+                zero_crosses(&spread)
+            }
+        };
+
+        let warmup = self.warmup();
+        Ok(signals
+            .into_iter()
+            .enumerate()
+            .map(|(i, signal)| if i < warmup { Signal::Hold } else { signal })
+            .collect())
+    }
+}
+
+/// Buy where `spread` turns positive, sell where it turns negative
+fn zero_crosses(spread: &[f64]) -> Vec<Signal> {
+    std::iter::once(Signal::Hold)
+        .chain(spread.windows(2).map(|pair| match pair {
+            [previous, value] if *previous <= 0.0 && *value > 0.0 => Signal::Buy,
+            [previous, value] if *previous >= 0.0 && *value < 0.0 => Signal::Sell,
+            _ => Signal::Hold,
+        }))
+        .take(spread.len())
+        .collect()
+}
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Strategy::RsiCross {
+                period,
+                oversold,
+                overbought,
+            } => write!(f, "RSI({period}) cross {oversold}/{overbought}"),
+            Strategy::MacdSignal { fast, slow, signal } => {
+                write!(f, "MACD({fast},{slow}) vs signal({signal})")
+            }
+            Strategy::SmaCross { fast, slow } => write!(f, "SMA({fast}) x SMA({slow})"),
+        }
+    }
+}
+
+impl FromStr for Strategy {
+    type Err = StockError;
+
+    /// Strategy with its default parameters by name
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().replace('-', "_").as_str() {
+            "rsi" | "rsi_cross" => Ok(Self::RSI_CROSS),
+            "macd" | "macd_signal" => Ok(Self::MACD_SIGNAL),
+            "sma" | "sma_cross" | "golden_cross" => Ok(Self::SMA_CROSS),
+            _ => Err(StockError::IndicatorError(format!(
+                "Unknown strategy: {s}. Supported: rsi_cross, macd_signal, sma_cross"
+            ))),
+        }
+    }
+}
+
+/// Simulation settings
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BacktestConfig {
+    /// Starting cash
+    pub initial_capital: f64,
+    /// Commission charged on each buy and sell, in percent of the traded value
+    pub commission_pct: f64,
+}
+
+impl Default for BacktestConfig {
+    fn default() -> Self {
+        Self {
+            initial_capital: 10_000.0,
+            commission_pct: 0.0,
+        }
+    }
+}
+
+/// A round trip, or the position still held at the end of the test
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Trade {
+    pub entry_date: NaiveDate,
+    pub entry_price: f64,
+    /// Exit date, or the last bar's date for an open position
+    pub exit_date: NaiveDate,
+    /// Exit price, or the last close for an open position
+    pub exit_price: f64,
+    /// Profit after commissions
+    pub pnl: f64,
+    /// Return on the capital committed, after commissions
+    pub return_pct: f64,
+    pub bars_held: usize,
+    /// Whether the position was still held at the end of the test
+    pub open: bool,
+}
+
+/// Outcome of replaying a strategy over a price history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BacktestReport {
+    pub symbol: String,
+    pub strategy: Strategy,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub bars: usize,
+    pub initial_capital: f64,
+    pub final_equity: f64,
+    pub total_return_pct: f64,
+    /// Compound annual growth rate
+    pub annualized_return_pct: Option<f64>,
+    /// Return of buying on the first bar and holding to the last
+    pub buy_and_hold_return_pct: f64,
+    /// Largest peak-to-trough fall of the equity curve
+    pub max_drawdown_pct: f64,
+    /// Annualized Sharpe ratio of daily equity returns, risk-free rate zero
+    pub sharpe_ratio: Option<f64>,
+    /// Share of closed trades that made money
+    pub win_rate_pct: Option<f64>,
+    /// Share of bars spent in a position
+    pub exposure_pct: f64,
+    pub trades: Vec<Trade>,
+}
+
+impl BacktestReport {
+    /// Round trips that were closed before the end of the test
+    pub fn closed_trades(&self) -> impl Iterator<Item = &Trade> {
+        self.trades.iter().filter(|trade| !trade.open)
+    }
+}
+
+impl fmt::Display for BacktestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} {} backtest, {} to {} ({} bars)",
+            self.symbol, self.strategy, self.start, self.end, self.bars
+        )?;
+        writeln!(
+            f,
+            "Return: {:+.1}% (buy and hold {:+.1}%), max drawdown -{:.1}%",
+            self.total_return_pct, self.buy_and_hold_return_pct, self.max_drawdown_pct
+        )?;
+        let sharpe = self
+            .sharpe_ratio
+            .map_or_else(|| "n/a".to_string(), |sharpe| format!("{sharpe:.2}"));
+        let win_rate = self
+            .win_rate_pct
+            .map_or_else(|| "n/a".to_string(), |rate| format!("{rate:.0}%"));
+        write!(
+            f,
+            "Sharpe: {sharpe}, trades: {}, win rate: {win_rate}, exposure: {:.0}%",
+            self.trades.len(),
+            self.exposure_pct
+        )
+    }
+}
+
+/// Replay `candles`, oldest first, through `strategy`
+///
+/// # Errors
+///
+/// Fails if the strategy's parameters are invalid or there are not enough
+/// bars to get past its warm-up.
+pub fn run_backtest(
+    symbol: &str,
+    candles: &[Candle],
+    strategy: &Strategy,
+    config: &BacktestConfig,
+) -> Result<BacktestReport> {
+    let (Some(first), Some(last)) = (candles.first(), candles.last()) else {
+        return Err(StockError::data_unavailable(
+            symbol,
+            "No price history to backtest",
+        ));
+    };
+    if candles.len() <= strategy.warmup() + 1 {
+        return Err(StockError::data_unavailable(
+            symbol,
+            format!(
+                "{strategy} needs more than {} daily bars, got {}",
+                strategy.warmup() + 1,
+                candles.len()
+            ),
+        ));
+    }
+
+    let closes: Vec<f64> = candles.iter().map(|candle| candle.close).collect();
+    let signals = strategy.signals(&closes)?;
+    let commission = config.commission_pct / 100.0;
+
+    let mut cash = config.initial_capital;
+    let mut shares = 0.0;
+    // Bar index, fill price and capital committed of the open position
+    let mut entry: Option<(usize, f64, f64)> = None;
+    let mut trades = Vec::new();
+    let mut equity = Vec::with_capacity(candles.len());
+    let mut bars_in_market: usize = 0;
+
+    for (i, candle) in candles.iter().enumerate() {
+        let previous_signal = i.checked_sub(1).map_or(Signal::Hold, |p| signals[p]);
+        match (previous_signal, entry) {
+            (Signal::Buy, None) => {
+                let price = candle.fill_price();
+                shares = cash * (1.0 - commission) / price;
+                entry = Some((i, price, cash));
+                cash = 0.0;
+            }
+            (Signal::Sell, Some((entry_bar, entry_price, committed))) => {
+                let price = candle.fill_price();
+                cash = shares * price * (1.0 - commission);
+                shares = 0.0;
+                entry = None;
+                trades.push(Trade {
+                    entry_date: candles[entry_bar].date,
+                    entry_price,
+                    exit_date: candle.date,
+                    exit_price: price,
+                    pnl: cash - committed,
+                    return_pct: (cash / committed - 1.0) * 100.0,
+                    bars_held: i - entry_bar,
+                    open: false,
+                });
+            }
+            _ => {}
+        }
+        if entry.is_some() {
+            bars_in_market += 1;
+        }
+        equity.push(cash + shares * candle.close);
+    }
+
+    let final_equity = equity.last().copied().unwrap_or(config.initial_capital);
+    if let Some((entry_bar, entry_price, committed)) = entry {
+        trades.push(Trade {
+            entry_date: candles[entry_bar].date,
+            entry_price,
+            exit_date: last.date,
+            exit_price: last.close,
+            pnl: final_equity - committed,
+            return_pct: (final_equity / committed - 1.0) * 100.0,
+            bars_held: candles.len() - 1 - entry_bar,
+            open: true,
+        });
+    }
+
+    let closed: Vec<&Trade> = trades.iter().filter(|trade| !trade.open).collect();
+    let win_rate_pct = (!closed.is_empty()).then(|| {
+        closed.iter().filter(|trade| trade.pnl > 0.0).count() as f64 / closed.len() as f64 * 100.0
+    });
+    let days = (last.date - first.date).num_days();
+    let growth = final_equity / config.initial_capital;
+
+    Ok(BacktestReport {
+        symbol: symbol.to_string(),
+        strategy: *strategy,
+        start: first.date,
+        end: last.date,
+        bars: candles.len(),
+        initial_capital: config.initial_capital,
+        final_equity,
+        total_return_pct: (growth - 1.0) * 100.0,
+        annualized_return_pct: (days > 0 && growth > 0.0)
+            .then(|| (growth.powf(365.25 / days as f64) - 1.0) * 100.0),
+        buy_and_hold_return_pct: if first.close > 0.0 {
+            (last.close / first.close - 1.0) * 100.0
+        } else {
+            0.0
+        },
+        max_drawdown_pct: max_drawdown_pct(&equity),
+        sharpe_ratio: sharpe_ratio(&equity),
+        win_rate_pct,
+        exposure_pct: bars_in_market as f64 / candles.len() as f64 * 100.0,
+        trades,
+    })
+}
+
+/// Largest fall from a running peak of `equity`, in percent
+pub fn max_drawdown_pct(equity: &[f64]) -> f64 {
+    let mut peak = f64::MIN;
+    let mut drawdown: f64 = 0.0;
+    for &value in equity {
+        peak = peak.max(value);
+        if peak > 0.0 {
+            drawdown = drawdown.max((peak - value) / peak * 100.0);
+        }
+    }
+    drawdown
+}
+
+/// Annualized Sharpe ratio of the daily returns of `equity`, risk-free rate
+/// zero; `None` when returns never vary
+pub fn sharpe_ratio(equity: &[f64]) -> Option<f64> {
+    let returns: Vec<f64> = equity
+        .windows(2)
+        .filter(|pair| pair[0] > 0.0)
+        .map(|pair| pair[1] / pair[0] - 1.0)
+        .collect();
+    if returns.len() < 2 {
+        return None;
+    }
+    let n = returns.len() as f64;
+    let mean = returns.iter().sum::<f64>() / n;
+    let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    let std_dev = variance.sqrt();
+    (std_dev > f64::EPSILON).then(|| mean / std_dev * TRADING_DAYS_PER_YEAR.sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Daily candles closing at `closes`, opening at the previous close
+    fn candles(closes: &[f64]) -> Vec<Candle> {
+        let start = NaiveDate::from_ymd_opt(2023, 1, 2).unwrap();
+        closes
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| Candle {
+                date: start + chrono::Duration::days(i as i64),
+                open: if i == 0 { close } else { closes[i - 1] },
+                high: close,
+                low: close,
+                close,
+                volume: 1_000,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_strategy_parse_and_validate() {
+        assert_eq!("RSI".parse::<Strategy>().unwrap(), Strategy::RSI_CROSS);
+        assert_eq!(
+            "golden-cross".parse::<Strategy>().unwrap(),
+            Strategy::SMA_CROSS
+        );
+        assert!("bollinger".parse::<Strategy>().is_err());
+        assert_eq!(
+            Strategy::MACD_SIGNAL.to_string(),
+            "MACD(12,26) vs signal(9)"
+        );
+        assert_eq!(Strategy::MACD_SIGNAL.warmup(), 35);
+
+        assert!(
+            Strategy::SmaCross { fast: 20, slow: 10 }
+                .validate()
+                .is_err()
+        );
+        assert!(
+            Strategy::RsiCross {
+                period: 14,
+                oversold: 70.0,
+                overbought: 30.0
+            }
+            .validate()
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_sma_cross_fills_next_open() {
+        // Down, up, then down again: one entry on the way up, one exit after
+        let mut closes: Vec<f64> = (0..10).map(|i| 100.0 - f64::from(i)).collect();
+        closes.extend((1..=10).map(|i| 91.0 + 2.0 * f64::from(i)));
+        closes.extend((1..=10).map(|i| 111.0 - 3.0 * f64::from(i)));
+        let candles = candles(&closes);
+        let strategy = Strategy::SmaCross { fast: 2, slow: 5 };
+
+        let signals = strategy.signals(&closes).unwrap();
+        let buy = signals.iter().position(|s| *s == Signal::Buy).unwrap();
+        let sell = signals.iter().position(|s| *s == Signal::Sell).unwrap();
+        assert!(buy >= strategy.warmup() && buy < sell);
+
+        let report = run_backtest("TEST", &candles, &strategy, &BacktestConfig::default()).unwrap();
+        assert_eq!(report.trades.len(), 1);
+        let trade = &report.trades[0];
+        assert!(!trade.open);
+        assert_eq!(trade.entry_date, candles[buy + 1].date);
+        assert!((trade.entry_price - candles[buy + 1].open).abs() < 1e-9);
+        assert_eq!(trade.exit_date, candles[sell + 1].date);
+        assert_eq!(trade.bars_held, sell - buy);
+        assert!(
+            (report.final_equity - (report.initial_capital + trade.pnl)).abs() < 1e-6,
+            "{report:?}"
+        );
+        assert_eq!(
+            report.win_rate_pct,
+            Some(if trade.pnl > 0.0 { 100.0 } else { 0.0 })
+        );
+        assert!(report.exposure_pct > 0.0 && report.exposure_pct < 100.0);
+        assert!(
+            report
+                .to_string()
+                .starts_with("TEST SMA(2) x SMA(5) backtest")
+        );
+    }
+
+    #[test]
+    fn test_open_position_and_commission() {
+        // A steady climb after a dip leaves the position open at the end
+        let mut closes: Vec<f64> = (0..8).map(|i| 100.0 - f64::from(i)).collect();
+        closes.extend((1..=12).map(|i| 93.0 + f64::from(i)));
+        let candles = candles(&closes);
+        let strategy = Strategy::SmaCross { fast: 2, slow: 4 };
+
+        let free = run_backtest("TEST", &candles, &strategy, &BacktestConfig::default()).unwrap();
+        let costly = run_backtest(
+            "TEST",
+            &candles,
+            &strategy,
+            &BacktestConfig {
+                commission_pct: 1.0,
+                ..BacktestConfig::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(free.trades.len(), 1);
+        assert!(free.trades[0].open);
+        assert_eq!(free.trades[0].exit_date, candles.last().unwrap().date);
+        assert_eq!(free.win_rate_pct, None);
+        assert_eq!(free.closed_trades().count(), 0);
+        assert!(free.total_return_pct > 0.0);
+        assert!(costly.final_equity < free.final_equity);
+    }
+
+    #[test]
+    fn test_not_enough_bars() {
+        let candles = candles(&[100.0; 30]);
+        let err = run_backtest(
+            "TEST",
+            &candles,
+            &Strategy::SMA_CROSS,
+            &BacktestConfig::default(),
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string().contains("needs more than 201 daily bars"),
+            "{err}"
+        );
+        assert!(
+            run_backtest(
+                "TEST",
+                &[],
+                &Strategy::RSI_CROSS,
+                &BacktestConfig::default()
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_risk_metrics() {
+        assert!((max_drawdown_pct(&[100.0, 120.0, 90.0, 130.0, 117.0]) - 25.0).abs() < 1e-9);
+        assert!(max_drawdown_pct(&[100.0, 101.0, 102.0]).abs() < 1e-9);
+
+        assert_eq!(sharpe_ratio(&[100.0, 100.0, 100.0]), None);
+        let sharpe = sharpe_ratio(&[100.0, 101.0, 100.0, 102.0, 103.0]).unwrap();
+        assert!(sharpe > 0.0);
+        assert!(sharpe_ratio(&[100.0, 99.0, 100.0, 98.0, 97.0]).unwrap() < 0.0);
+    }
+}
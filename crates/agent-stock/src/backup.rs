@@ -0,0 +1,431 @@
+//! Backup and restore of persisted stores
+//!
+//! [`BackupArchive`] bundles every configured store into one versioned JSON
+//! document. Archives are decrypted on collection and, when a store key is
+//! set, written encrypted like the stores themselves (see [`crate::storage`]).
+//!
+//! Archives from older releases are upgraded on load by the migration hooks
+//! in [`MIGRATIONS`], so data survives changes to the archive layout.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::backup::{BackupArchive, StorePaths};
+//! use agent_stock::storage::StoreCipher;
+//!
+//! let paths = StorePaths::from_env();
+//! let cipher = StoreCipher::from_env()?;
+//!
+//! BackupArchive::collect(&paths, cipher.as_ref())?.write("backup.json", cipher.as_ref())?;
+//! BackupArchive::read("backup.json", cipher.as_ref())?.restore(&paths, cipher.as_ref(), false)?;
+//! ```
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::conversation_store;
+use crate::error::{Result, StockError};
+use crate::migrations;
+use crate::storage::{self, StoreCipher};
+use crate::usage::UsageSink;
+
+/// Identifies a backup archive
+pub const BACKUP_FORMAT: &str = "agent-rs-backup";
+
+/// Archive layout version written by this release
+pub const BACKUP_VERSION: u32 = 1;
+
+/// Upgrades an archive from one version to the next, in place
+pub type Migration = fn(&mut Value) -> Result<()>;
+
+/// Migration hooks: `MIGRATIONS[i]` upgrades version `i + 1` to `i + 2`
+///
+/// Add a hook here whenever [`BACKUP_VERSION`] is bumped.
+pub const MIGRATIONS: &[Migration] = &[];
+
+/// A persisted store
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StoreKind {
+    /// Directional predictions (`STOCK_PREDICTIONS_FILE`)
+    Predictions,
+    /// Usage statistics file (`STOCK_USAGE_STATS`)
+    Usage,
+    /// Price and RSI alerts (`STOCK_ALERTS_FILE`)
+    Alerts,
+    /// Quiet hours and held alerts (`STOCK_DELIVERY_FILE`)
+    Delivery,
+    /// Portfolio positions (`STOCK_PORTFOLIO_FILE`)
+    Portfolio,
+    /// Conversation history (`STOCK_CONVERSATION_FILE`)
+    Conversations,
+}
+
+impl StoreKind {
+    /// Every store kind
+    pub const ALL: &'static [StoreKind] = &[
+        Self::Predictions,
+        Self::Usage,
+        Self::Alerts,
+        Self::Delivery,
+        Self::Portfolio,
+        Self::Conversations,
+    ];
+
+    /// Key of the store in an archive
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Predictions => "predictions",
+            Self::Usage => "usage",
+            Self::Alerts => "alerts",
+            Self::Delivery => "delivery",
+            Self::Portfolio => "portfolio",
+            Self::Conversations => "conversations",
+        }
+    }
+
+    /// The store at `path` as JSON, without writing to it
+    ///
+    /// Conversations are read through [`conversation_store::export`], which
+    /// also covers SQLite databases and journaled changes.
+    pub(crate) fn peek(self, path: &Path, cipher: Option<&StoreCipher>) -> Result<Option<String>> {
+        match self {
+            Self::Conversations => conversation_store::export(path, cipher),
+            _ => storage::peek_store(path, cipher),
+        }
+    }
+
+    /// Replace the store at `path` with `json`
+    pub(crate) fn write(self, path: &Path, json: &str, cipher: Option<&StoreCipher>) -> Result<()> {
+        match self {
+            Self::Conversations => conversation_store::import(path, json, cipher),
+            _ => storage::write_store(path, json, cipher),
+        }
+    }
+}
+
+impl fmt::Display for StoreKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Where each store lives; unset stores are not persisted
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StorePaths {
+    /// Predictions file
+    pub predictions: Option<PathBuf>,
+    /// Usage statistics file
+    pub usage: Option<PathBuf>,
+    /// Alerts file
+    pub alerts: Option<PathBuf>,
+    /// Delivery preferences file
+    pub delivery: Option<PathBuf>,
+    /// Portfolio file
+    pub portfolio: Option<PathBuf>,
+    /// Conversation history file or database
+    pub conversations: Option<PathBuf>,
+}
+
+impl StorePaths {
+    /// Paths from the same environment variables the bot reads
+    pub fn from_env() -> Self {
+        Self {
+            predictions: std::env::var("STOCK_PREDICTIONS_FILE")
+                .ok()
+                .map(PathBuf::from),
+            usage: match UsageSink::from_env() {
+                Some(UsageSink::File(path)) => Some(path),
+                _ => None,
+            },
+            alerts: std::env::var("STOCK_ALERTS_FILE").ok().map(PathBuf::from),
+            delivery: std::env::var("STOCK_DELIVERY_FILE").ok().map(PathBuf::from),
+            portfolio: std::env::var("STOCK_PORTFOLIO_FILE")
+                .ok()
+                .map(PathBuf::from),
+            conversations: std::env::var("STOCK_CONVERSATION_FILE")
+                .ok()
+                .map(PathBuf::from),
+        }
+    }
+
+    /// Path of a store, if configured
+    pub fn get(&self, kind: StoreKind) -> Option<&Path> {
+        match kind {
+            StoreKind::Predictions => self.predictions.as_deref(),
+            StoreKind::Usage => self.usage.as_deref(),
+            StoreKind::Alerts => self.alerts.as_deref(),
+            StoreKind::Delivery => self.delivery.as_deref(),
+            StoreKind::Portfolio => self.portfolio.as_deref(),
+            StoreKind::Conversations => self.conversations.as_deref(),
+        }
+    }
+}
+
+/// Outcome of restoring one store
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RestoreOutcome {
+    /// Written to the path
+    Restored(PathBuf),
+    /// In the archive but no path is configured for it
+    NotConfigured,
+}
+
+/// A versioned snapshot of every persisted store
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupArchive {
+    /// Always [`BACKUP_FORMAT`]
+    pub format: String,
+    /// Archive layout version
+    pub version: u32,
+    /// When the backup was taken
+    pub created_at: DateTime<Utc>,
+    /// Release that wrote the archive
+    pub app_version: String,
+    /// Store contents by [`StoreKind::as_str`]
+    pub stores: BTreeMap<String, Value>,
+    /// Schema version of each store (see [`crate::migrations`])
+    #[serde(default)]
+    pub schemas: BTreeMap<String, u32>,
+}
+
+impl BackupArchive {
+    /// Read every configured store that exists
+    pub fn collect(paths: &StorePaths, cipher: Option<&StoreCipher>) -> Result<Self> {
+        let mut stores = BTreeMap::new();
+        let mut schemas = BTreeMap::new();
+        for kind in StoreKind::ALL {
+            let Some(path) = paths.get(*kind) else {
+                continue;
+            };
+            if let Some(json) = kind.peek(path, cipher)? {
+                stores.insert(kind.as_str().to_string(), serde_json::from_str(&json)?);
+                schemas.insert(kind.as_str().to_string(), migrations::read_version(path)?);
+            }
+        }
+
+        Ok(Self {
+            format: BACKUP_FORMAT.to_string(),
+            version: BACKUP_VERSION,
+            created_at: Utc::now(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            stores,
+            schemas,
+        })
+    }
+
+    /// Store kinds included in the archive
+    pub fn kinds(&self) -> Vec<StoreKind> {
+        StoreKind::ALL
+            .iter()
+            .copied()
+            .filter(|kind| self.stores.contains_key(kind.as_str()))
+            .collect()
+    }
+
+    /// Write the archive, encrypted when a cipher is given
+    pub fn write(&self, path: impl AsRef<Path>, cipher: Option<&StoreCipher>) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        storage::write_store(path, &json, cipher)
+    }
+
+    /// Load an archive, upgrading it to [`BACKUP_VERSION`]
+    pub fn read(path: impl AsRef<Path>, cipher: Option<&StoreCipher>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| StockError::Other(format!("Failed to read {}: {e}", path.display())))?;
+        let json = if storage::is_encrypted(&contents) {
+            let cipher = cipher.ok_or_else(|| {
+                StockError::ConfigError(format!(
+                    "{} is encrypted; set {} to restore it",
+                    path.display(),
+                    storage::STORE_KEY_ENV
+                ))
+            })?;
+            cipher.decrypt(&contents)?
+        } else {
+            contents
+        };
+        Self::from_json(serde_json::from_str(&json)?, MIGRATIONS)
+    }
+
+    /// Parse an archive, applying `migrations` to reach [`BACKUP_VERSION`]
+    pub fn from_json(mut value: Value, migrations: &[Migration]) -> Result<Self> {
+        if value.get("format").and_then(Value::as_str) != Some(BACKUP_FORMAT) {
+            return Err(StockError::Other(
+                "Not an agent-rs backup archive".to_string(),
+            ));
+        }
+        let mut version = value
+            .get("version")
+            .and_then(Value::as_u64)
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| StockError::Other("Backup archive has no version".to_string()))?;
+        if version < 1 {
+            return Err(StockError::Other(format!(
+                "Backup archive version {version} is invalid; versions start at 1"
+            )));
+        }
+
+        let target = u32::try_from(migrations.len()).map_or(u32::MAX, |n| n + 1);
+        if version > target {
+            return Err(StockError::Other(format!(
+                "Backup archive version {version} is newer than this release supports ({target}); upgrade agent-rs to restore it"
+            )));
+        }
+        while version < target {
+            migrations[version as usize - 1](&mut value)?;
+            version += 1;
+            value["version"] = Value::from(version);
+            tracing::info!("Migrated backup archive to version {}", version);
+        }
+
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Write each store back to its configured path
+    ///
+    /// Existing files are only replaced when `overwrite` is set; otherwise
+    /// nothing is written and an error names the files in the way. Schema
+    /// versions are restored too, so older stores are migrated on next start.
+    pub fn restore(
+        &self,
+        paths: &StorePaths,
+        cipher: Option<&StoreCipher>,
+        overwrite: bool,
+    ) -> Result<Vec<(StoreKind, RestoreOutcome)>> {
+        if !overwrite {
+            let existing: Vec<String> = self
+                .kinds()
+                .into_iter()
+                .filter_map(|kind| paths.get(kind))
+                .filter(|path| path.exists())
+                .map(|path| path.display().to_string())
+                .collect();
+            if !existing.is_empty() {
+                return Err(StockError::Other(format!(
+                    "Refusing to overwrite {}; use --force to replace",
+                    existing.join(", ")
+                )));
+            }
+        }
+
+        let mut outcomes = Vec::new();
+        for kind in self.kinds() {
+            let Some(path) = paths.get(kind) else {
+                outcomes.push((kind, RestoreOutcome::NotConfigured));
+                continue;
+            };
+            let json = serde_json::to_string_pretty(&self.stores[kind.as_str()])?;
+            kind.write(path, &json, cipher)?;
+            let schema = self.schemas.get(kind.as_str()).copied().unwrap_or_default();
+            migrations::write_version(path, schema)?;
+            outcomes.push((kind, RestoreOutcome::Restored(path.to_path_buf())));
+        }
+        Ok(outcomes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("backup-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_backup_and_restore_round_trip() {
+        let dir = temp_dir();
+        let paths = StorePaths {
+            predictions: Some(dir.join("predictions.json")),
+            usage: Some(dir.join("usage.json")),
+            ..StorePaths::default()
+        };
+        let cipher = StoreCipher::from_key(&[9; 32]).unwrap();
+        storage::write_store(
+            dir.join("predictions.json"),
+            r#"[{"id":"1"}]"#,
+            Some(&cipher),
+        )
+        .unwrap();
+
+        let archive = BackupArchive::collect(&paths, Some(&cipher)).unwrap();
+        assert_eq!(archive.kinds(), vec![StoreKind::Predictions]);
+        archive
+            .write(dir.join("backup.json"), Some(&cipher))
+            .unwrap();
+
+        let restored = BackupArchive::read(dir.join("backup.json"), Some(&cipher)).unwrap();
+        assert_eq!(restored, archive);
+        assert!(restored.restore(&paths, Some(&cipher), false).is_err());
+
+        let target = StorePaths {
+            predictions: Some(dir.join("restored.json")),
+            ..StorePaths::default()
+        };
+        let outcomes = restored.restore(&target, Some(&cipher), false).unwrap();
+        assert_eq!(
+            outcomes,
+            vec![(
+                StoreKind::Predictions,
+                RestoreOutcome::Restored(dir.join("restored.json"))
+            )]
+        );
+        assert_eq!(
+            storage::read_store(dir.join("restored.json"), Some(&cipher))
+                .unwrap()
+                .map(|json| serde_json::from_str::<Value>(&json).unwrap()),
+            Some(json!([{ "id": "1" }]))
+        );
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_migrations_upgrade_old_archives() {
+        fn rename_scores(value: &mut Value) -> Result<()> {
+            if let Some(stores) = value["stores"].as_object_mut()
+                && let Some(scores) = stores.remove("scores")
+            {
+                stores.insert("predictions".to_string(), scores);
+            }
+            Ok(())
+        }
+
+        let old = json!({
+            "format": BACKUP_FORMAT,
+            "version": 1,
+            "created_at": "2024-03-15T14:30:00Z",
+            "app_version": "0.0.1",
+            "stores": { "scores": [] }
+        });
+        let archive = BackupArchive::from_json(old.clone(), &[rename_scores]).unwrap();
+        assert_eq!(archive.version, 2);
+        assert_eq!(archive.kinds(), vec![StoreKind::Predictions]);
+
+        // Archives newer than the release are rejected
+        let mut newer = old;
+        newer["version"] = json!(BACKUP_VERSION + 1);
+        assert!(BackupArchive::from_json(newer, MIGRATIONS).is_err());
+    }
+
+    #[test]
+    fn test_rejects_version_zero() {
+        let archive = json!({
+            "format": BACKUP_FORMAT,
+            "version": 0,
+            "created_at": "2024-03-15T14:30:00Z",
+            "app_version": "0.0.1",
+            "stores": {}
+        });
+        let err = BackupArchive::from_json(archive, MIGRATIONS).unwrap_err();
+        assert!(err.to_string().contains("version 0"), "{err}");
+    }
+}
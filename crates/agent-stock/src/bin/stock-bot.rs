@@ -9,15 +9,29 @@
 //! export OPENAI_API_BASE="http://localhost:1234/v1"
 //! export OPENAI_MODEL="your-model-name"
 //!
+//! # Or run fully offline against a local Ollama server
+//! export OLLAMA_MODEL="qwen2.5:14b"
+//!
 //! # Run the bot
 //! cargo run --bin stock-bot -p agent-stock
 //! ```
 
-use agent_llm::providers::{OpenAIConfig, OpenAIProvider};
-use agent_stock::bot::{BotConfig, StockBot};
+use agent_llm::LLMProvider;
+use agent_llm::providers::{OllamaConfig, OllamaProvider, OpenAIConfig, OpenAIProvider};
+use agent_stock::alerts::{AlertPolicy, Notifier};
+use agent_stock::api::YahooFinanceClient;
+use agent_stock::bot::{BotConfig, CLI_USER, StockBot};
+use agent_stock::delivery::QuietNotifier;
+use agent_stock::interface::BotPlatform;
+use agent_stock::news_digest;
+use agent_stock::platforms::ConsoleNotifier;
+use agent_stock::scheduler::{DailySchedule, spawn_daily};
+use agent_stock::storage::StoreCipher;
+use agent_stock::usage::UsageSink;
 use std::env;
 use std::io::{self, BufRead, Write};
 use std::sync::Arc;
+use std::time::Duration;
 
 fn print_banner() {
     println!(
@@ -60,8 +74,35 @@ fn get_provider_config() -> (OpenAIConfig, String) {
     (config, model)
 }
 
+/// Ollama's native API when `OLLAMA_MODEL` is set, otherwise an
+/// OpenAI-compatible server
+fn create_provider() -> anyhow::Result<(Arc<dyn LLMProvider>, String)> {
+    if let Ok(model) = env::var("OLLAMA_MODEL") {
+        let config = OllamaConfig::from_env()?;
+
+        println!("Configuration:");
+        println!("  Ollama: {}", config.api_base);
+        println!("  Model: {model}");
+        println!();
+
+        return Ok((Arc::new(OllamaProvider::with_config(config)?), model));
+    }
+
+    let (openai_config, model) = get_provider_config();
+
+    println!("Configuration:");
+    println!("  API Base: {}", openai_config.api_base);
+    println!("  Model: {model}");
+    println!();
+
+    Ok((Arc::new(OpenAIProvider::with_config(openai_config)?), model))
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    // Load settings written by `agent-cli init`
+    dotenvy::dotenv().ok();
+
     // Initialize tracing
     tracing_subscriber::fmt()
         .with_env_filter(
@@ -71,31 +112,173 @@ async fn main() -> anyhow::Result<()> {
 
     print_banner();
 
-    // Get LLM provider configuration
-    let (openai_config, model) = get_provider_config();
-
-    println!("Configuration:");
-    println!("  API Base: {}", openai_config.api_base);
-    println!("  Model: {model}");
-    println!();
-
-    // Create OpenAI provider
-    let provider = Arc::new(OpenAIProvider::with_config(openai_config)?);
+    // Create the LLM provider
+    let (provider, model) = create_provider()?;
 
     // Create bot configuration
-    let bot_config = BotConfig::builder()
-        .stock_config(
-            agent_stock::StockConfig::builder()
-                .with_env_all_keys()
-                .from_env_model()
-                .model(model)
-                .build()?,
-        )
-        .build();
+    let mut bot_config = BotConfig::builder().stock_config(
+        agent_stock::StockConfig::builder()
+            .with_env_all_keys()
+            .from_env_model()
+            .model(model)
+            .build()?,
+    );
+    if let Ok(path) = env::var("STOCK_PREDICTIONS_FILE") {
+        bot_config = bot_config.predictions_path(path);
+    }
+    if let Ok(path) = env::var("STOCK_MARKET_WRAP_FILE") {
+        bot_config = bot_config.market_wrap_path(path);
+    }
+    if let Ok(path) = env::var("STOCK_MACRO_WATCH_FILE") {
+        bot_config = bot_config.macro_watch_path(path);
+    }
+    if let Ok(path) = env::var("STOCK_ALERTS_FILE") {
+        bot_config = bot_config.alerts_path(path);
+    }
+    if let Ok(path) = env::var("STOCK_DELIVERY_FILE") {
+        bot_config = bot_config.delivery_path(path);
+    }
+    bot_config = bot_config.alert_policy(AlertPolicy::from_env());
+    if let Ok(path) = env::var("STOCK_PORTFOLIO_FILE") {
+        bot_config = bot_config.portfolio_path(path);
+    }
+    if let Ok(path) = env::var("STOCK_CONVERSATION_FILE") {
+        bot_config = bot_config.conversation_path(path);
+    }
+    if let Some(cipher) = StoreCipher::from_env()? {
+        println!("  Stores: encrypted at rest");
+        bot_config = bot_config.store_cipher(cipher);
+    }
+    if let Some(sink) = UsageSink::from_env() {
+        println!("  Usage stats: {sink}");
+        bot_config = bot_config.usage_sink(sink);
+    }
+    let bot_config = bot_config.build();
 
     // Create the bot
     println!("Initializing stock analysis agent...");
     let mut bot = StockBot::with_provider(provider, bot_config).await?;
+
+    // Score predictions in the background as their horizons end
+    Arc::clone(bot.predictions()).spawn_scoring(
+        Arc::new(YahooFinanceClient::new()),
+        Duration::from_secs(3600),
+    );
+    Arc::clone(bot.usage()).spawn_reporting(Duration::from_secs(3600));
+
+    // Keep the FRED economic summary warm across release days
+    if bot
+        .agent()
+        .macro_analyzer()
+        .spawn_summary_refresh()
+        .is_some()
+    {
+        println!("  Economic summary: refreshed after FRED releases");
+    }
+
+    // Check price alerts against live quotes and print those that fire
+    let alert_interval = env::var("STOCK_ALERT_INTERVAL")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .unwrap_or(60);
+    bot.alerts()
+        .register_notifier(BotPlatform::CLI, Arc::new(ConsoleNotifier));
+    Arc::clone(bot.alerts()).spawn(Duration::from_secs(alert_interval));
+    if let Some(live) = bot.live() {
+        live.register_notifier(BotPlatform::CLI, Arc::new(ConsoleNotifier));
+        println!("  Live quotes: /live <symbol>");
+    }
+
+    // Print the market wrap after each close
+    if let Ok(time) = env::var("STOCK_MARKET_WRAP_TIME") {
+        let schedule = DailySchedule::parse(&time)?.weekdays_only(true);
+        let watchlist: Vec<String> = env::var("STOCK_MARKET_WRAP_WATCHLIST")
+            .unwrap_or_default()
+            .split(',')
+            .map(|symbol| symbol.trim().to_uppercase())
+            .filter(|symbol| !symbol.is_empty())
+            .collect();
+        let job = Arc::clone(bot.market_wrap());
+        spawn_daily(schedule, move || {
+            let job = Arc::clone(&job);
+            let watchlist = watchlist.clone();
+            async move {
+                match job.run(&watchlist).await {
+                    Ok(wrap) => println!("\n{wrap}\n"),
+                    Err(e) => eprintln!("Market wrap failed: {e}"),
+                }
+            }
+        });
+        println!("  Market wrap: weekdays at {time} UTC");
+    }
+
+    // Print a news digest for the watchlist and portfolio before the open,
+    // held until the end of quiet hours
+    if let Ok(time) = env::var("STOCK_MORNING_BRIEFING_TIME") {
+        let schedule = DailySchedule::parse(&time)?.weekdays_only(true);
+        let watchlist: Vec<String> = env::var("STOCK_MORNING_BRIEFING_WATCHLIST")
+            .unwrap_or_default()
+            .split(',')
+            .map(|symbol| symbol.trim().to_uppercase())
+            .filter(|symbol| !symbol.is_empty())
+            .collect();
+        let job = Arc::clone(bot.news_digest());
+        let portfolio = Arc::clone(bot.portfolio());
+        let notifier: Arc<dyn Notifier> = match bot.alerts().delivery() {
+            Some(delivery) => Arc::new(QuietNotifier::new(
+                Arc::new(ConsoleNotifier),
+                BotPlatform::CLI,
+                Arc::clone(delivery),
+            )),
+            None => Arc::new(ConsoleNotifier),
+        };
+        spawn_daily(schedule, move || {
+            let job = Arc::clone(&job);
+            let notifier = Arc::clone(&notifier);
+            let positions = portfolio.store().positions(CLI_USER);
+            let weights = news_digest::exposure_weights(&watchlist, &positions);
+            async move {
+                if weights.is_empty() {
+                    return;
+                }
+                let sent = match job.run(&weights).await {
+                    Ok(digest) => notifier.notify(CLI_USER, &digest.to_string()).await,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = sent {
+                    eprintln!("Morning briefing failed: {e}");
+                }
+            }
+        });
+        println!("  Morning briefing: weekdays at {time} UTC");
+    }
+
+    // Check watched FRED series once the day's releases are out and push
+    // each alert to the user who set the watch, held until the end of quiet
+    // hours like price alerts
+    if let Ok(time) = env::var("STOCK_MACRO_ALERT_TIME") {
+        let schedule = DailySchedule::parse(&time)?.weekdays_only(true);
+        let job = Arc::clone(bot.macro_alerts());
+        let engine = Arc::clone(bot.alerts());
+        spawn_daily(schedule, move || {
+            let job = Arc::clone(&job);
+            let engine = Arc::clone(&engine);
+            async move {
+                match job.run().await {
+                    Ok(alerts) => {
+                        for alert in alerts {
+                            let watch = &alert.watch;
+                            engine
+                                .push_message(&watch.user_id, watch.platform, &alert.to_string())
+                                .await;
+                        }
+                    }
+                    Err(e) => eprintln!("Macro alerts failed: {e}"),
+                }
+            }
+        });
+        println!("  Macro alerts: weekdays at {time} UTC");
+    }
     println!("Ready!\n");
 
     // Run REPL
@@ -128,7 +311,9 @@ async fn main() -> anyhow::Result<()> {
         }
 
         // Process input
-        match bot.process_input(input).await {
+        // Comprehensive analyses print a quick snapshot while they run
+        let interim = |snapshot: &str| println!("{snapshot}\n");
+        match bot.process_input_progressive(input, interim).await {
             Ok(response) => {
                 println!("{response}\n");
             }
@@ -143,5 +328,9 @@ async fn main() -> anyhow::Result<()> {
         }
     }
 
+    if let Err(e) = bot.usage().flush().await {
+        eprintln!("Failed to report usage stats: {e}");
+    }
+
     Ok(())
 }
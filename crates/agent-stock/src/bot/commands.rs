@@ -2,25 +2,105 @@
 //!
 //! This module provides command-line interface commands for the bot.
 
+use crate::alerts::AlertCondition;
+use crate::as_of;
+use crate::delivery::QuietHours;
+use crate::depth::AnalysisDepth;
 use crate::error::{Result, StockError};
+use crate::language::ResponseLanguage;
+use crate::macro_alerts::WatchCondition;
+use crate::notebook::NotebookKind;
+use crate::report::ReportFormat;
+use crate::screener::Screen;
+use crate::style::ResponseStyle;
+use crate::timezone::TimeZone;
+use crate::tools::ThemeBasket;
+use crate::tools::theme::{theme_by_key, theme_keys};
+use chrono::NaiveDate;
 
 /// Parsed command from user input
 #[derive(Debug, Clone, PartialEq)]
 pub enum Command {
-    /// Comprehensive analysis of a stock
-    Analyze { symbol: String },
+    /// Comprehensive analysis of a stock, optionally at an explicit depth
+    /// and as of a past date
+    Analyze {
+        symbol: String,
+        depth: Option<AnalysisDepth>,
+        as_of: Option<NaiveDate>,
+    },
     /// Technical analysis only
     Technical { symbol: String },
     /// Fundamental analysis only
     Fundamental { symbol: String },
     /// News and sentiment analysis
     News { symbol: String },
+    /// One news digest for the whole watchlist (`/news all`)
+    NewsDigest,
     /// Earnings analysis
     Earnings { symbol: String },
+    /// Dividend yield, ex-dividend dates and payout history
+    Dividends { symbol: String },
+    /// Scan the screener universe for stocks passing every filter
+    Screen { screen: Screen },
     /// Macro economic analysis
     Macro,
+    /// Alert when a FRED series is released above or below a threshold
+    MacroWatch {
+        series_id: String,
+        condition: WatchCondition,
+        threshold: f64,
+    },
+    /// Stop watching a FRED series
+    MacroUnwatch { series_id: String },
+    /// Show the watched FRED series
+    MacroWatches,
+    /// Push a notification when a price or RSI condition is met
+    Alert { condition: AlertCondition },
+    /// Remove a price alert
+    AlertRemove { id: u64 },
+    /// Show the user's price alerts
+    Alerts,
+    /// Show the user's quiet hours
+    Quiet,
+    /// Hold notifications during `hours`, in local time in `time_zone`
+    /// (the user's time zone when `None`)
+    QuietSet {
+        hours: QuietHours,
+        time_zone: Option<TimeZone>,
+    },
+    /// Push notifications at any time again
+    QuietOff,
+    /// Show the user's time zone, or set it to `zone`
+    TimeZone { zone: Option<TimeZone> },
+    /// Push live price updates for a symbol to the chat
+    Live { symbol: String },
+    /// Stop live updates for a symbol, or for all symbols
+    LiveStop { symbol: Option<String> },
+    /// Record a purchase of `quantity` shares at `cost_basis` per share
+    PortfolioAdd {
+        symbol: String,
+        quantity: f64,
+        cost_basis: f64,
+    },
+    /// Sell some shares of a position, or all of it
+    PortfolioRemove {
+        symbol: String,
+        quantity: Option<f64>,
+    },
+    /// Show the portfolio valued at the latest close
+    Portfolio,
+    /// Question about the portfolio, answered by the portfolio agent
+    PortfolioAsk { question: String },
+    /// Translate a question into a screen or SQL query and show it
+    Ask { question: String },
+    /// Run the query shown by the last `/ask` and explain the results
+    AskRun,
     /// Geopolitical analysis
     Geopolitical,
+    /// Thematic basket analysis (AI, EV, semis)
+    Theme { theme: &'static ThemeBasket },
+    /// Daily market wrap: what happened, why, what to watch tomorrow
+    Wrap,
     /// Compare multiple stocks
     Compare { symbols: Vec<String> },
     /// Add stock to watchlist
@@ -29,6 +109,36 @@ pub enum Command {
     Unwatch { symbol: String },
     /// Show watchlist
     Watchlist,
+    /// Sector performance heatmap
+    Market,
+    /// Watchlist performance heatmap
+    Summary,
+    /// Show or set the response style
+    Style { style: Option<ResponseStyle> },
+    /// Turn teaching mode on or off (toggle when no value is given)
+    Teach { enabled: Option<bool> },
+    /// Show or set the reply language
+    Language { language: Option<ResponseLanguage> },
+    /// Turn audio summaries of long replies on or off (toggle when no value
+    /// is given)
+    Voice { enabled: Option<bool> },
+    /// Show prediction accuracy per agent and model
+    Scoreboard,
+    /// List what the bot can do given the configured API keys
+    Capabilities,
+    /// Show LLM tokens used and their estimated cost
+    Usage,
+    /// Export the conversation as a runnable notebook
+    Notebook { kind: NotebookKind },
+    /// Analysis of one symbol, or comparison of several, as a document
+    Report {
+        symbols: Vec<String>,
+        format: ReportFormat,
+    },
+    /// Show the next page of a long reply
+    More,
+    /// Cancel queued and running requests
+    Cancel,
     /// Clear conversation history
     Clear,
     /// Show help
@@ -68,8 +178,24 @@ impl Command {
                 let symbol = args.first().ok_or_else(|| {
                     StockError::CommandError("Missing symbol for analyze command".to_string())
                 })?;
+                // A date and a depth, in either order
+                let mut depth = None;
+                let mut as_of = None;
+                for arg in &args[1..] {
+                    if let Some(date) = as_of::parse(arg) {
+                        as_of = Some(as_of::validate(date)?);
+                    } else {
+                        depth = Some(AnalysisDepth::parse(arg).ok_or_else(|| {
+                            StockError::CommandError(format!(
+                                "Unknown depth: {arg}. Available: quick, standard, deep"
+                            ))
+                        })?);
+                    }
+                }
                 Ok(Command::Analyze {
                     symbol: symbol.to_uppercase(),
+                    depth,
+                    as_of,
                 })
             }
             "technical" | "tech" | "t" | "技术" => {
@@ -92,6 +218,9 @@ impl Command {
                 let symbol = args.first().ok_or_else(|| {
                     StockError::CommandError("Missing symbol for news command".to_string())
                 })?;
+                if symbol.eq_ignore_ascii_case("all") || *symbol == "全部" {
+                    return Ok(Command::NewsDigest);
+                }
                 Ok(Command::News {
                     symbol: symbol.to_uppercase(),
                 })
@@ -104,8 +233,60 @@ impl Command {
                     symbol: symbol.to_uppercase(),
                 })
             }
-            "macro" | "m" | "宏观" => Ok(Command::Macro),
+            "dividends" | "dividend" | "div" | "股息" => {
+                let symbol = args.first().ok_or_else(|| {
+                    StockError::CommandError("Missing symbol for dividends command".to_string())
+                })?;
+                Ok(Command::Dividends {
+                    symbol: symbol.to_uppercase(),
+                })
+            }
+            "screen" | "screener" | "选股" => Ok(Command::Screen {
+                screen: args.join(" ").parse()?,
+            }),
+            "macro" | "m" | "宏观" => parse_macro(args),
+            "alert" | "提醒" => parse_alert(args),
+            "alerts" | "提醒列表" => Ok(Command::Alerts),
+            "quiet" | "免打扰" => parse_quiet(args, false),
+            "deliver" | "推送时段" => parse_quiet(args, true),
+            "timezone" | "tz" | "时区" => match args {
+                [] => Ok(Command::TimeZone { zone: None }),
+                [zone] => Ok(Command::TimeZone {
+                    zone: Some(parse_time_zone(zone)?),
+                }),
+                _ => Err(StockError::CommandError(
+                    "Usage: /timezone America/New_York or /timezone UTC+8".to_string(),
+                )),
+            },
+            "live" | "实时" => parse_live(args),
+            "portfolio" | "pf" | "持仓" => parse_portfolio(args),
+            "ask" | "问数" => match args {
+                [] => Err(StockError::CommandError(
+                    "Missing question. Use e.g. /ask which tech stocks trade below 20x earnings?"
+                        .to_string(),
+                )),
+                ["run" | "yes" | "执行"] => Ok(Command::AskRun),
+                _ => Ok(Command::Ask {
+                    question: args.join(" "),
+                }),
+            },
             "geopolitical" | "geo" | "地缘" => Ok(Command::Geopolitical),
+            "theme" | "主题" => {
+                let name = args.first().ok_or_else(|| {
+                    StockError::CommandError(format!(
+                        "Missing theme for theme command. Available: {}",
+                        theme_keys()
+                    ))
+                })?;
+                let theme = theme_by_key(name).ok_or_else(|| {
+                    StockError::CommandError(format!(
+                        "Unknown theme: {name}. Available: {}",
+                        theme_keys()
+                    ))
+                })?;
+                Ok(Command::Theme { theme })
+            }
+            "wrap" | "收盘" => Ok(Command::Wrap),
             "compare" | "cmp" | "比较" => {
                 if args.len() < 2 {
                     return Err(StockError::CommandError(
@@ -132,6 +313,57 @@ impl Command {
                 })
             }
             "watchlist" | "list" | "关注列表" => Ok(Command::Watchlist),
+            "market" | "mkt" | "大盘" => Ok(Command::Market),
+            "summary" | "sum" | "汇总" => Ok(Command::Summary),
+            "style" | "风格" => {
+                let style = args
+                    .first()
+                    .map(|name| {
+                        ResponseStyle::parse(name).ok_or_else(|| {
+                            StockError::CommandError(format!(
+                                "Unknown style: {name}. Available: concise, detailed, beginner"
+                            ))
+                        })
+                    })
+                    .transpose()?;
+                Ok(Command::Style { style })
+            }
+            "teach" | "learn" | "教学" => Ok(Command::Teach {
+                enabled: parse_switch("teach", args.first().copied())?,
+            }),
+            "language" | "lang" | "语言" => {
+                let language = args
+                    .first()
+                    .map(|name| {
+                        ResponseLanguage::parse(name).ok_or_else(|| {
+                            StockError::CommandError(format!(
+                                "Unknown language: {name}. Available: zh, en, bilingual"
+                            ))
+                        })
+                    })
+                    .transpose()?;
+                Ok(Command::Language { language })
+            }
+            "voice" | "audio" | "语音" => Ok(Command::Voice {
+                enabled: parse_switch("voice", args.first().copied())?,
+            }),
+            "scoreboard" | "score" | "战绩" => Ok(Command::Scoreboard),
+            "capabilities" | "caps" | "能力" => Ok(Command::Capabilities),
+            "usage" | "cost" | "用量" => Ok(Command::Usage),
+            "notebook" | "nb" | "导出" => {
+                let kind = match args.first() {
+                    Some(name) => NotebookKind::parse(name).ok_or_else(|| {
+                        StockError::CommandError(format!(
+                            "Unknown notebook kind: {name}. Available: python, rust"
+                        ))
+                    })?,
+                    None => NotebookKind::default(),
+                };
+                Ok(Command::Notebook { kind })
+            }
+            "report" | "报告" => parse_report(args),
+            "more" | "next" | "更多" => Ok(Command::More),
+            "cancel" | "stop" | "取消" => Ok(Command::Cancel),
             "clear" | "cls" | "清空" => Ok(Command::Clear),
             "help" | "h" | "?" | "帮助" => Ok(Command::Help),
             "exit" | "quit" | "q" | "退出" => Ok(Command::Exit),
@@ -146,21 +378,64 @@ Stock Analysis Bot Commands
 ============================
 
 Analysis Commands:
-  /analyze <symbol>      综合分析股票 (Comprehensive analysis)
+  /analyze <symbol> [depth] 综合分析 quick/standard/deep (Comprehensive analysis)
+  /analyze <symbol> <date>  时点分析 YYYY-MM-DD 或 YYYY-MM (Analysis as of a past date)
   /technical <symbol>    技术分析 (Technical analysis)
   /fundamental <symbol>  基本面分析 (Fundamental analysis)
   /news <symbol>         新闻情绪分析 (News & sentiment)
+  /news all              关注列表新闻摘要 (News digest for the watchlist)
   /earnings <symbol>     财报分析 (Earnings analysis)
+  /dividends <symbol>    股息率与派息历史 (Dividend yield, ex-dates and history)
+  /screen <filters>      选股 (Screen stocks, e.g. pe<20 market_cap>10B rsi<30 sector=Technology)
   /macro                 宏观经济分析 (Macro economic analysis)
+  /macro watch <series> above|below <value>
+                         宏观数据提醒 (Alert on a FRED release, e.g. UNRATE above 4.5)
+  /macro unwatch <series> 取消宏观提醒 (Stop a macro alert)
+  /macro watches         宏观提醒列表 (Show macro alerts)
+  /alert <condition>     价格提醒 (Price alert, e.g. AAPL > 200 or RSI(NVDA) < 30)
+  /alert remove <id>     删除价格提醒 (Remove a price alert)
+  /alerts                价格提醒列表 (Show price alerts)
+  /quiet 22:00-07:00 [zone] 免打扰时段 (Quiet hours; alerts arrive as one summary after)
+  /deliver 08:00-20:00 [zone] 推送时段 (Only push notifications in this window)
+  /quiet off             关闭免打扰 (Turn quiet hours off)
+  /timezone [zone]       时区 (Your time zone, e.g. Asia/Shanghai or UTC+8)
+  /live <symbol>         实时价格推送 (Stream live prices to the chat)
+  /live stop [symbol]    停止实时推送 (Stop live prices)
+  /portfolio [show]      持仓估值 (Portfolio value, P&L, beta, sectors)
+  /portfolio add <symbol> <qty> <cost>
+                         记录买入 (Record shares bought at a cost per share)
+  /portfolio remove <symbol> [qty]
+                         卖出或删除持仓 (Sell shares or remove a position)
+  /portfolio <question>  持仓问答 (e.g. "what's my exposure to tech?")
+  /ask <question>        问题转为选股或SQL查询 (Build a screen or SQL query from a question)
+  /ask run               执行上一条查询并解读结果 (Run the shown query and explain it)
   /geopolitical          地缘政治分析 (Geopolitical analysis)
+  /theme <name>          主题板块分析 ai/ev/semis (Thematic basket analysis)
+  /wrap                  收盘市场综述 (Daily market wrap)
   /compare <s1> <s2> ... 比较多只股票 (Compare stocks)
 
 Watchlist Commands:
   /watch <symbol>        添加到关注列表 (Add to watchlist)
   /unwatch <symbol>      从关注列表移除 (Remove from watchlist)
   /watchlist             显示关注列表 (Show watchlist)
+  /summary               关注列表涨跌热力图 (Watchlist performance heatmap)
+  /market                板块涨跌热力图 (Sector performance heatmap)
 
 Other Commands:
+  /style [name]          回答风格 concise/detailed/beginner (Response style)
+  /teach [on|off]        教学模式,解释推理和公式 (Teaching mode)
+  /language [zh|en|bilingual]
+                         回复语言,bilingual 为中英双语 (Reply language)
+  /voice [on|off]        长回复附带语音摘要 (Audio summaries of long replies)
+  /scoreboard            预测准确率 (Prediction accuracy by agent/model)
+  /capabilities          当前可用功能 (What works with the configured API keys)
+  /usage                 模型用量与费用 (LLM tokens and estimated cost)
+  /notebook [python|rust]
+                         导出为可运行的笔记本 (Export the session as a notebook)
+  /report <symbol>... [md|html|pdf]
+                         分析报告文件 (Analysis report as a document)
+  /more                  显示下一页 (Show the next page of a long reply)
+  /cancel                取消排队中的请求 (Cancel queued and running requests)
   /clear                 清空对话历史 (Clear conversation history)
   /help                  显示帮助 (Show help)
   /exit                  退出 (Exit)
@@ -178,6 +453,127 @@ Natural Language:
 "#
     }
 
+    /// Command names and descriptions for chat platform command menus
+    /// (e.g. Telegram's `setMyCommands`), without the leading slash
+    pub fn menu() -> &'static [(&'static str, &'static str)] {
+        &[
+            ("analyze", "Comprehensive stock analysis"),
+            ("technical", "Technical analysis"),
+            ("fundamental", "Fundamental analysis"),
+            ("news", "News and sentiment analysis"),
+            ("earnings", "Earnings analysis"),
+            ("dividends", "Dividend yield and history"),
+            ("screen", "Screen stocks by P/E, market cap, RSI, sector"),
+            ("macro", "Macro economic analysis"),
+            ("alert", "Set a price or RSI alert"),
+            ("alerts", "Show price alerts"),
+            ("quiet", "Show or set quiet hours for alerts"),
+            ("timezone", "Show or set your time zone"),
+            ("live", "Stream live prices"),
+            ("portfolio", "Show or ask about your portfolio"),
+            ("ask", "Turn a question into a screen or SQL query"),
+            ("geopolitical", "Geopolitical risk analysis"),
+            ("theme", "Thematic basket analysis (ai, ev, semis)"),
+            ("wrap", "Daily market wrap"),
+            ("compare", "Compare stocks"),
+            ("watch", "Add to watchlist"),
+            ("unwatch", "Remove from watchlist"),
+            ("watchlist", "Show watchlist"),
+            ("summary", "Watchlist performance heatmap"),
+            ("market", "Sector performance heatmap"),
+            ("style", "Show or set response style"),
+            ("teach", "Toggle teaching mode"),
+            ("voice", "Toggle audio summaries"),
+            ("scoreboard", "Show prediction accuracy"),
+            ("capabilities", "Show what the bot can currently do"),
+            ("usage", "Show LLM tokens used and estimated cost"),
+            ("notebook", "Export the session as a notebook"),
+            ("report", "Analysis report as a PDF, HTML or Markdown file"),
+            ("more", "Show the next page of a long reply"),
+            ("cancel", "Cancel queued and running requests"),
+            ("clear", "Clear conversation history"),
+            ("help", "Show help"),
+        ]
+    }
+
+    /// Command name without arguments, e.g. "technical"
+    ///
+    /// Natural language queries are all named "query".
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::Analyze { .. } => "analyze",
+            Command::Technical { .. } => "technical",
+            Command::Fundamental { .. } => "fundamental",
+            Command::News { .. } => "news",
+            Command::NewsDigest => "news_digest",
+            Command::Earnings { .. } => "earnings",
+            Command::Dividends { .. } => "dividends",
+            Command::Screen { .. } => "screen",
+            Command::Macro => "macro",
+            Command::MacroWatch { .. } => "macro_watch",
+            Command::MacroUnwatch { .. } => "macro_unwatch",
+            Command::MacroWatches => "macro_watches",
+            Command::Alert { .. } => "alert",
+            Command::AlertRemove { .. } => "alert_remove",
+            Command::Alerts => "alerts",
+            Command::Quiet => "quiet",
+            Command::QuietSet { .. } => "quiet_set",
+            Command::QuietOff => "quiet_off",
+            Command::TimeZone { .. } => "timezone",
+            Command::Live { .. } => "live",
+            Command::LiveStop { .. } => "live_stop",
+            Command::PortfolioAdd { .. } => "portfolio_add",
+            Command::PortfolioRemove { .. } => "portfolio_remove",
+            Command::Portfolio => "portfolio",
+            Command::PortfolioAsk { .. } => "portfolio_ask",
+            Command::Ask { .. } => "ask",
+            Command::AskRun => "ask_run",
+            Command::Geopolitical => "geopolitical",
+            Command::Theme { .. } => "theme",
+            Command::Wrap => "wrap",
+            Command::Compare { .. } => "compare",
+            Command::Watch { .. } => "watch",
+            Command::Unwatch { .. } => "unwatch",
+            Command::Watchlist => "watchlist",
+            Command::Market => "market",
+            Command::Summary => "summary",
+            Command::Style { .. } => "style",
+            Command::Teach { .. } => "teach",
+            Command::Language { .. } => "language",
+            Command::Voice { .. } => "voice",
+            Command::Scoreboard => "scoreboard",
+            Command::Capabilities => "capabilities",
+            Command::Usage => "usage",
+            Command::Notebook { .. } => "notebook",
+            Command::Report { .. } => "report",
+            Command::More => "more",
+            Command::Cancel => "cancel",
+            Command::Clear => "clear",
+            Command::Help => "help",
+            Command::Exit => "exit",
+            Command::Query { .. } => "query",
+        }
+    }
+
+    /// Whether the command replies with an agent's analysis, which bilingual
+    /// sessions translate
+    pub fn is_analysis(&self) -> bool {
+        matches!(
+            self,
+            Command::Analyze { .. }
+                | Command::Technical { .. }
+                | Command::Fundamental { .. }
+                | Command::News { .. }
+                | Command::NewsDigest
+                | Command::Earnings { .. }
+                | Command::Macro
+                | Command::Geopolitical
+                | Command::Theme { .. }
+                | Command::Compare { .. }
+                | Command::Query { .. }
+        )
+    }
+
     /// Get a short description of the command
     pub fn description(&self) -> &'static str {
         match self {
@@ -185,19 +581,291 @@ Natural Language:
             Command::Technical { .. } => "Technical analysis",
             Command::Fundamental { .. } => "Fundamental analysis",
             Command::News { .. } => "News and sentiment analysis",
+            Command::NewsDigest => "News digest for the watchlist",
             Command::Earnings { .. } => "Earnings analysis",
+            Command::Dividends { .. } => "Dividend yield and history",
+            Command::Screen { .. } => "Stock screen",
             Command::Macro => "Macro economic analysis",
+            Command::MacroWatch { .. } => "Alert on a FRED series release",
+            Command::MacroUnwatch { .. } => "Stop a macro alert",
+            Command::MacroWatches => "Show macro alerts",
+            Command::Alert { .. } => "Set a price or RSI alert",
+            Command::AlertRemove { .. } => "Remove a price alert",
+            Command::Alerts => "Show price alerts",
+            Command::Quiet => "Show quiet hours",
+            Command::QuietSet { .. } => "Set quiet hours",
+            Command::QuietOff => "Turn quiet hours off",
+            Command::TimeZone { .. } => "Show or set your time zone",
+            Command::Live { .. } => "Stream live prices",
+            Command::LiveStop { .. } => "Stop live prices",
+            Command::PortfolioAdd { .. } => "Add to portfolio",
+            Command::PortfolioRemove { .. } => "Sell or remove a position",
+            Command::Portfolio => "Show portfolio",
+            Command::PortfolioAsk { .. } => "Portfolio question",
+            Command::Ask { .. } => "Build a query from a question",
+            Command::AskRun => "Run and explain the built query",
             Command::Geopolitical => "Geopolitical risk analysis",
+            Command::Theme { .. } => "Thematic basket analysis",
+            Command::Wrap => "Daily market wrap",
             Command::Compare { .. } => "Stock comparison",
             Command::Watch { .. } => "Add to watchlist",
             Command::Unwatch { .. } => "Remove from watchlist",
             Command::Watchlist => "Show watchlist",
+            Command::Market => "Sector performance heatmap",
+            Command::Summary => "Watchlist performance heatmap",
+            Command::Style { .. } => "Show or set response style",
+            Command::Teach { .. } => "Toggle teaching mode",
+            Command::Language { .. } => "Show or set reply language",
+            Command::Voice { .. } => "Toggle audio summaries",
+            Command::Scoreboard => "Show prediction accuracy",
+            Command::Capabilities => "Show what the bot can currently do",
+            Command::Usage => "Show LLM tokens used and estimated cost",
+            Command::Notebook { .. } => "Export the session as a notebook",
+            Command::Report { .. } => "Analysis report as a document",
+            Command::More => "Show the next page of a long reply",
+            Command::Cancel => "Cancel queued and running requests",
             Command::Clear => "Clear conversation history",
             Command::Help => "Show help",
             Command::Exit => "Exit the bot",
             Command::Query { .. } => "Natural language query",
         }
     }
+
+    /// Whether the command runs an LLM analysis or scans many symbols,
+    /// taking seconds to minutes; these go through the per-user request queue
+    pub fn is_heavy(&self) -> bool {
+        matches!(
+            self,
+            Command::Analyze { .. }
+                | Command::Technical { .. }
+                | Command::Fundamental { .. }
+                | Command::News { .. }
+                | Command::NewsDigest
+                | Command::Earnings { .. }
+                | Command::Screen { .. }
+                | Command::Macro
+                | Command::Geopolitical
+                | Command::Theme { .. }
+                | Command::Wrap
+                | Command::Compare { .. }
+                | Command::Report { .. }
+                | Command::PortfolioAsk { .. }
+                | Command::Ask { .. }
+                | Command::AskRun
+                | Command::Query { .. }
+        )
+    }
+
+    /// Symbols an analysis command is about, for the conversation context
+    pub fn symbols(&self) -> Vec<String> {
+        match self {
+            Command::Analyze { symbol, .. }
+            | Command::Technical { symbol }
+            | Command::Fundamental { symbol }
+            | Command::News { symbol }
+            | Command::Earnings { symbol } => vec![symbol.clone()],
+            Command::Compare { symbols } | Command::Report { symbols, .. } => symbols.clone(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Parse `/report <symbol>... [md|html|pdf]`; the format defaults to PDF
+fn parse_report(args: &[&str]) -> Result<Command> {
+    let (format, symbols) = match args.split_last() {
+        Some((last, rest)) => match ReportFormat::parse(last) {
+            Some(format) => (format, rest),
+            None => (ReportFormat::default(), args),
+        },
+        None => (ReportFormat::default(), args),
+    };
+    if symbols.is_empty() {
+        return Err(StockError::CommandError(
+            "Usage: /report <symbol>... [md|html|pdf]".to_string(),
+        ));
+    }
+    Ok(Command::Report {
+        symbols: symbols.iter().map(|s| s.to_uppercase()).collect(),
+        format,
+    })
+}
+
+/// Parse `/macro` and its `watch`, `unwatch` and `watches` subcommands
+fn parse_macro(args: &[&str]) -> Result<Command> {
+    let Some(subcommand) = args.first() else {
+        return Ok(Command::Macro);
+    };
+    let usage = || {
+        StockError::CommandError(
+            "Usage: /macro watch <series> above|below <value>, /macro unwatch <series>, \
+             /macro watches"
+                .to_string(),
+        )
+    };
+    match (subcommand.to_lowercase().as_str(), &args[1..]) {
+        ("watch" | "关注", [series_id, condition, threshold]) => Ok(Command::MacroWatch {
+            series_id: series_id.to_uppercase(),
+            condition: condition.parse()?,
+            threshold: threshold
+                .parse()
+                .map_err(|_| StockError::CommandError(format!("Invalid threshold: {threshold}")))?,
+        }),
+        ("unwatch" | "取消", [series_id]) => Ok(Command::MacroUnwatch {
+            series_id: series_id.to_uppercase(),
+        }),
+        ("watches" | "list" | "列表", []) => Ok(Command::MacroWatches),
+        _ => Err(usage()),
+    }
+}
+
+/// Parse `/live <symbol>` and `/live stop [symbol]`
+fn parse_live(args: &[&str]) -> Result<Command> {
+    match args {
+        [subcommand, rest @ ..]
+            if matches!(subcommand.to_lowercase().as_str(), "stop" | "off" | "停止") =>
+        {
+            Ok(Command::LiveStop {
+                symbol: rest.first().map(|symbol| symbol.to_uppercase()),
+            })
+        }
+        [symbol] => Ok(Command::Live {
+            symbol: symbol.to_uppercase(),
+        }),
+        _ => Err(StockError::CommandError(
+            "Usage: /live AAPL, /live stop AAPL or /live stop".to_string(),
+        )),
+    }
+}
+
+/// Parse `/portfolio [show]`, `/portfolio add <symbol> <qty> <cost>`,
+/// `/portfolio remove <symbol> [qty]` and `/portfolio <question>`
+fn parse_portfolio(args: &[&str]) -> Result<Command> {
+    let number = |name: &str, value: &str| {
+        value
+            .trim_start_matches(['$', '@'])
+            .parse::<f64>()
+            .ok()
+            .filter(|number| number.is_finite() && *number > 0.0)
+            .ok_or_else(|| StockError::CommandError(format!("Invalid {name}: {value}")))
+    };
+    let Some(subcommand) = args.first() else {
+        return Ok(Command::Portfolio);
+    };
+    // "AAPL 10 @ 150" reads naturally; the @ is optional
+    let rest: Vec<&str> = args[1..]
+        .iter()
+        .copied()
+        .filter(|arg| *arg != "@")
+        .collect();
+    match (subcommand.to_lowercase().as_str(), rest.as_slice()) {
+        ("show" | "list" | "显示", []) => Ok(Command::Portfolio),
+        ("add" | "buy" | "买入", [symbol, quantity, cost_basis]) => Ok(Command::PortfolioAdd {
+            symbol: symbol.to_uppercase(),
+            quantity: number("quantity", quantity)?,
+            cost_basis: number("cost basis", cost_basis)?,
+        }),
+        ("add" | "buy" | "买入", _) => Err(StockError::CommandError(
+            "Usage: /portfolio add <symbol> <quantity> <cost per share>, e.g. /portfolio add AAPL 10 150"
+                .to_string(),
+        )),
+        ("remove" | "rm" | "sell" | "删除" | "卖出", [symbol, quantity @ ..])
+            if quantity.len() <= 1 =>
+        {
+            Ok(Command::PortfolioRemove {
+                symbol: symbol.to_uppercase(),
+                quantity: quantity
+                    .first()
+                    .map(|quantity| number("quantity", quantity))
+                    .transpose()?,
+            })
+        }
+        ("remove" | "rm" | "sell" | "删除" | "卖出", _) => Err(StockError::CommandError(
+            "Usage: /portfolio remove <symbol> [quantity]".to_string(),
+        )),
+        _ => Ok(Command::PortfolioAsk {
+            question: args.join(" "),
+        }),
+    }
+}
+
+/// Parse `/alert <condition>`, `/alert remove <id>` and `/alert list`
+fn parse_alert(args: &[&str]) -> Result<Command> {
+    match args {
+        [] => Err(StockError::CommandError(
+            "Usage: /alert AAPL > 200, /alert RSI(NVDA) < 30, /alert remove <id>, /alerts"
+                .to_string(),
+        )),
+        [subcommand] if matches!(subcommand.to_lowercase().as_str(), "list" | "列表") => {
+            Ok(Command::Alerts)
+        }
+        [subcommand, id]
+            if matches!(
+                subcommand.to_lowercase().as_str(),
+                "remove" | "rm" | "删除" | "取消"
+            ) =>
+        {
+            let id = id.trim_start_matches('#');
+            Ok(Command::AlertRemove {
+                id: id
+                    .parse()
+                    .map_err(|_| StockError::CommandError(format!("Invalid alert id: {id}")))?,
+            })
+        }
+        _ => Ok(Command::Alert {
+            condition: args.join(" ").parse()?,
+        }),
+    }
+}
+
+/// Parse `/quiet [<start>-<end>] [<time zone>]`, `/quiet off`, and
+/// `/deliver <start>-<end> [<time zone>]` when `window` is set
+fn parse_quiet(args: &[&str], window: bool) -> Result<Command> {
+    let usage = || {
+        StockError::CommandError(
+            "Usage: /quiet 22:00-07:00 Asia/Shanghai, /deliver 08:00-20:00 UTC+8, /quiet off"
+                .to_string(),
+        )
+    };
+    let (range, zone) = match args {
+        [] if !window => return Ok(Command::Quiet),
+        [off] if !window && matches!(off.to_lowercase().as_str(), "off" | "关" | "关闭") => {
+            return Ok(Command::QuietOff);
+        }
+        [range] => (range, None),
+        [range, zone] => (range, Some(zone)),
+        _ => return Err(usage()),
+    };
+    let hours: QuietHours = range.parse()?;
+    Ok(Command::QuietSet {
+        hours: if window {
+            QuietHours::outside(hours)
+        } else {
+            hours
+        },
+        time_zone: zone.map(|zone| parse_time_zone(zone)).transpose()?,
+    })
+}
+
+/// Parse an IANA time zone or UTC offset argument
+fn parse_time_zone(zone: &str) -> Result<TimeZone> {
+    zone.parse().map_err(|_| {
+        StockError::CommandError(format!(
+            "Invalid time zone: {zone}. Use e.g. America/New_York, Asia/Shanghai or UTC+8"
+        ))
+    })
+}
+
+/// Parse an optional on/off argument of `command`
+fn parse_switch(command: &str, value: Option<&str>) -> Result<Option<bool>> {
+    value
+        .map(|value| match value.to_lowercase().as_str() {
+            "on" | "true" | "1" | "开" | "开启" => Ok(true),
+            "off" | "false" | "0" | "关" | "关闭" => Ok(false),
+            _ => Err(StockError::CommandError(format!(
+                "Invalid value for {command}: {value}. Use on or off"
+            ))),
+        })
+        .transpose()
 }
 
 #[cfg(test)]
@@ -210,7 +878,9 @@ mod tests {
         assert_eq!(
             cmd,
             Command::Analyze {
-                symbol: "AAPL".to_string()
+                symbol: "AAPL".to_string(),
+                depth: None,
+                as_of: None,
             }
         );
 
@@ -218,9 +888,54 @@ mod tests {
         assert_eq!(
             cmd,
             Command::Analyze {
-                symbol: "AAPL".to_string()
+                symbol: "AAPL".to_string(),
+                depth: None,
+                as_of: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_analyze_depth() {
+        assert_eq!(
+            Command::parse("/analyze AAPL deep").unwrap(),
+            Command::Analyze {
+                symbol: "AAPL".to_string(),
+                depth: Some(AnalysisDepth::Deep),
+                as_of: None,
             }
         );
+        assert_eq!(
+            Command::parse("/分析 aapl 快速").unwrap(),
+            Command::Analyze {
+                symbol: "AAPL".to_string(),
+                depth: Some(AnalysisDepth::Quick),
+                as_of: None,
+            }
+        );
+        assert!(Command::parse("/analyze AAPL extreme").is_err());
+    }
+
+    #[test]
+    fn test_parse_analyze_as_of() {
+        let date = NaiveDate::from_ymd_opt(2023, 1, 31).unwrap();
+        assert_eq!(
+            Command::parse("/analyze nvda 2023-01").unwrap(),
+            Command::Analyze {
+                symbol: "NVDA".to_string(),
+                depth: None,
+                as_of: Some(date),
+            }
+        );
+        assert_eq!(
+            Command::parse("/analyze NVDA deep 2023-01-31").unwrap(),
+            Command::Analyze {
+                symbol: "NVDA".to_string(),
+                depth: Some(AnalysisDepth::Deep),
+                as_of: Some(date),
+            }
+        );
+        assert!(Command::parse("/analyze NVDA 2999-01-01").is_err());
     }
 
     #[test]
@@ -234,6 +949,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_dividends() {
+        let expected = Command::Dividends {
+            symbol: "KO".to_string(),
+        };
+        assert_eq!(Command::parse("/dividends ko").unwrap(), expected);
+        assert_eq!(Command::parse("/股息 KO").unwrap(), expected);
+        assert!(!expected.is_heavy());
+        assert!(Command::parse("/div").is_err());
+    }
+
+    #[test]
+    fn test_parse_screen() {
+        let Command::Screen { screen } =
+            Command::parse("/screen pe < 20 market_cap>10B sector=Technology").unwrap()
+        else {
+            panic!("expected a screen");
+        };
+        assert_eq!(screen.filters.len(), 3);
+        assert!(Command::parse("/选股 rsi<30").unwrap().is_heavy());
+        assert!(Command::parse("/screen").is_err());
+        assert!(Command::parse("/screen cheap").is_err());
+    }
+
     #[test]
     fn test_parse_natural_language() {
         let cmd = Command::parse("What is the price of AAPL?").unwrap();
@@ -251,7 +990,9 @@ mod tests {
         assert_eq!(
             cmd,
             Command::Analyze {
-                symbol: "AAPL".to_string()
+                symbol: "AAPL".to_string(),
+                depth: None,
+                as_of: None,
             }
         );
     }
@@ -268,6 +1009,375 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_parse_style() {
+        assert_eq!(
+            Command::parse("/style concise").unwrap(),
+            Command::Style {
+                style: Some(ResponseStyle::Concise)
+            }
+        );
+        assert_eq!(
+            Command::parse("/风格 新手").unwrap(),
+            Command::Style {
+                style: Some(ResponseStyle::Beginner)
+            }
+        );
+        assert_eq!(
+            Command::parse("/style").unwrap(),
+            Command::Style { style: None }
+        );
+        assert!(Command::parse("/style verbose").is_err());
+    }
+
+    #[test]
+    fn test_parse_language() {
+        assert_eq!(
+            Command::parse("/language bilingual").unwrap(),
+            Command::Language {
+                language: Some(ResponseLanguage::Bilingual)
+            }
+        );
+        assert_eq!(
+            Command::parse("/语言 英文").unwrap(),
+            Command::Language {
+                language: Some(ResponseLanguage::English)
+            }
+        );
+        assert_eq!(
+            Command::parse("/lang").unwrap(),
+            Command::Language { language: None }
+        );
+        assert!(Command::parse("/language klingon").is_err());
+    }
+
+    #[test]
+    fn test_parse_teach() {
+        assert_eq!(
+            Command::parse("/teach on").unwrap(),
+            Command::Teach {
+                enabled: Some(true)
+            }
+        );
+        assert_eq!(
+            Command::parse("/教学 关").unwrap(),
+            Command::Teach {
+                enabled: Some(false)
+            }
+        );
+        assert_eq!(
+            Command::parse("/teach").unwrap(),
+            Command::Teach { enabled: None }
+        );
+        assert!(Command::parse("/teach maybe").is_err());
+        assert_eq!(
+            Command::parse("/语音 开").unwrap(),
+            Command::Voice {
+                enabled: Some(true)
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_scoreboard() {
+        assert_eq!(Command::parse("/scoreboard").unwrap(), Command::Scoreboard);
+        assert_eq!(Command::parse("/战绩").unwrap(), Command::Scoreboard);
+    }
+
+    #[test]
+    fn test_parse_theme() {
+        let cmd = Command::parse("/theme AI").unwrap();
+        assert_eq!(cmd.name(), "theme");
+        assert!(cmd.is_heavy());
+        let Command::Theme { theme } = cmd else {
+            panic!("expected theme command");
+        };
+        assert_eq!(theme.key, "ai");
+
+        assert!(matches!(
+            Command::parse("/主题 semis").unwrap(),
+            Command::Theme { theme } if theme.key == "semis"
+        ));
+
+        let err = Command::parse("/theme crypto").unwrap_err();
+        assert!(err.to_string().contains("ai, ev, semis"), "{err}");
+        assert!(Command::parse("/theme").is_err());
+    }
+
+    #[test]
+    fn test_parse_macro_watch() {
+        assert_eq!(Command::parse("/macro").unwrap(), Command::Macro);
+        assert_eq!(
+            Command::parse("/macro watch unrate above 4.5").unwrap(),
+            Command::MacroWatch {
+                series_id: "UNRATE".to_string(),
+                condition: WatchCondition::Above,
+                threshold: 4.5,
+            }
+        );
+        assert_eq!(
+            Command::parse("/宏观 取消 UNRATE").unwrap(),
+            Command::MacroUnwatch {
+                series_id: "UNRATE".to_string()
+            }
+        );
+        assert_eq!(Command::parse("/m watches").unwrap(), Command::MacroWatches);
+        assert!(!Command::MacroWatches.is_heavy());
+
+        assert!(Command::parse("/macro watch UNRATE near 4.5").is_err());
+        assert!(Command::parse("/macro watch UNRATE above high").is_err());
+        assert!(Command::parse("/macro watch UNRATE").is_err());
+        assert!(Command::parse("/macro outlook").is_err());
+    }
+
+    #[test]
+    fn test_parse_alert() {
+        let Command::Alert { condition } = Command::parse("/alert RSI(NVDA) < 30").unwrap() else {
+            panic!("expected alert command");
+        };
+        assert_eq!(condition.to_string(), "RSI(NVDA) < 30");
+        assert!(matches!(
+            Command::parse("/提醒 aapl > 200").unwrap(),
+            Command::Alert { condition } if condition.symbol == "AAPL"
+        ));
+        assert_eq!(
+            Command::parse("/alert remove #3").unwrap(),
+            Command::AlertRemove { id: 3 }
+        );
+        assert_eq!(Command::parse("/alerts").unwrap(), Command::Alerts);
+        assert_eq!(Command::parse("/alert list").unwrap(), Command::Alerts);
+        assert!(!Command::Alerts.is_heavy());
+
+        assert!(Command::parse("/alert").is_err());
+        assert!(Command::parse("/alert remove three").is_err());
+        assert!(Command::parse("/alert AAPL soon").is_err());
+    }
+
+    #[test]
+    fn test_parse_quiet() {
+        assert_eq!(Command::parse("/quiet").unwrap(), Command::Quiet);
+        assert_eq!(Command::parse("/免打扰 关闭").unwrap(), Command::QuietOff);
+        assert_eq!(
+            Command::parse("/quiet 22:00-07:00 UTC+8").unwrap(),
+            Command::QuietSet {
+                hours: "22:00-07:00".parse().unwrap(),
+                time_zone: Some(TimeZone::Fixed(480)),
+            }
+        );
+        assert_eq!(
+            Command::parse("/deliver 08:00-20:00").unwrap(),
+            Command::QuietSet {
+                hours: "20:00-08:00".parse().unwrap(),
+                time_zone: None,
+            }
+        );
+        assert!(!Command::Quiet.is_heavy());
+        assert_eq!(
+            Command::parse("/timezone utc-5").unwrap(),
+            Command::TimeZone {
+                zone: Some(TimeZone::Fixed(-300)),
+            }
+        );
+        assert_eq!(
+            Command::parse("/时区").unwrap(),
+            Command::TimeZone { zone: None }
+        );
+        assert!(Command::parse("/timezone Mars/Olympus").is_err());
+
+        assert!(Command::parse("/deliver").is_err());
+        assert!(Command::parse("/quiet 22:00-07:00 Mars").is_err());
+        assert!(Command::parse("/quiet late").is_err());
+    }
+
+    #[test]
+    fn test_parse_live() {
+        assert_eq!(
+            Command::parse("/live nvda").unwrap(),
+            Command::Live {
+                symbol: "NVDA".to_string()
+            }
+        );
+        assert_eq!(
+            Command::parse("/实时 停止 aapl").unwrap(),
+            Command::LiveStop {
+                symbol: Some("AAPL".to_string())
+            }
+        );
+        assert_eq!(
+            Command::parse("/live off").unwrap(),
+            Command::LiveStop { symbol: None }
+        );
+        assert!(Command::parse("/live").is_err());
+        assert!(Command::parse("/live AAPL MSFT").is_err());
+    }
+
+    #[test]
+    fn test_parse_portfolio() {
+        assert_eq!(Command::parse("/portfolio").unwrap(), Command::Portfolio);
+        assert_eq!(Command::parse("/pf show").unwrap(), Command::Portfolio);
+        assert_eq!(
+            Command::parse("/portfolio add aapl 10 @ $150.5").unwrap(),
+            Command::PortfolioAdd {
+                symbol: "AAPL".to_string(),
+                quantity: 10.0,
+                cost_basis: 150.5
+            }
+        );
+        assert_eq!(
+            Command::parse("/持仓 卖出 nvda 2").unwrap(),
+            Command::PortfolioRemove {
+                symbol: "NVDA".to_string(),
+                quantity: Some(2.0)
+            }
+        );
+        assert_eq!(
+            Command::parse("/portfolio remove MSFT").unwrap(),
+            Command::PortfolioRemove {
+                symbol: "MSFT".to_string(),
+                quantity: None
+            }
+        );
+        let ask = Command::parse("/portfolio what's my exposure to tech?").unwrap();
+        assert!(ask.is_heavy());
+        assert_eq!(
+            ask,
+            Command::PortfolioAsk {
+                question: "what's my exposure to tech?".to_string()
+            }
+        );
+
+        assert!(Command::parse("/portfolio add AAPL 10").is_err());
+        assert!(Command::parse("/portfolio add AAPL -10 150").is_err());
+        assert!(Command::parse("/portfolio remove AAPL ten").is_err());
+    }
+
+    #[test]
+    fn test_parse_wrap() {
+        assert_eq!(Command::parse("/wrap").unwrap(), Command::Wrap);
+        assert_eq!(Command::parse("/收盘").unwrap(), Command::Wrap);
+        assert!(Command::Wrap.is_heavy());
+    }
+
+    #[test]
+    fn test_parse_capabilities() {
+        assert_eq!(
+            Command::parse("/capabilities").unwrap(),
+            Command::Capabilities
+        );
+        assert_eq!(Command::parse("/能力").unwrap(), Command::Capabilities);
+        assert!(!Command::Capabilities.is_heavy());
+    }
+
+    #[test]
+    fn test_parse_news_digest() {
+        assert_eq!(Command::parse("/news all").unwrap(), Command::NewsDigest);
+        assert_eq!(Command::parse("/news ALL").unwrap(), Command::NewsDigest);
+        assert_eq!(Command::parse("/新闻 全部").unwrap(), Command::NewsDigest);
+        assert_eq!(
+            Command::parse("/news aapl").unwrap(),
+            Command::News {
+                symbol: "AAPL".to_string()
+            }
+        );
+        assert!(Command::NewsDigest.is_heavy());
+    }
+
+    #[test]
+    fn test_parse_usage() {
+        assert_eq!(Command::parse("/usage").unwrap(), Command::Usage);
+        assert_eq!(Command::parse("/cost").unwrap(), Command::Usage);
+        assert_eq!(Command::parse("/用量").unwrap(), Command::Usage);
+        assert!(!Command::Usage.is_heavy());
+    }
+
+    #[test]
+    fn test_parse_notebook() {
+        assert_eq!(
+            Command::parse("/notebook").unwrap(),
+            Command::Notebook {
+                kind: NotebookKind::Python
+            }
+        );
+        assert_eq!(
+            Command::parse("/导出 evcxr").unwrap(),
+            Command::Notebook {
+                kind: NotebookKind::Rust
+            }
+        );
+        assert!(Command::parse("/nb julia").is_err());
+        assert!(
+            !Command::Notebook {
+                kind: NotebookKind::Rust
+            }
+            .is_heavy()
+        );
+    }
+
+    #[test]
+    fn test_parse_report() {
+        assert_eq!(
+            Command::parse("/report aapl").unwrap(),
+            Command::Report {
+                symbols: vec!["AAPL".to_string()],
+                format: ReportFormat::Pdf
+            }
+        );
+        let report = Command::parse("/报告 AAPL msft html").unwrap();
+        assert_eq!(
+            report,
+            Command::Report {
+                symbols: vec!["AAPL".to_string(), "MSFT".to_string()],
+                format: ReportFormat::Html
+            }
+        );
+        assert!(report.is_heavy());
+        assert_eq!(report.symbols(), ["AAPL", "MSFT"]);
+        assert!(Command::parse("/report").is_err());
+        assert!(Command::parse("/report md").is_err());
+    }
+
+    #[test]
+    fn test_parse_heatmaps() {
+        assert_eq!(Command::parse("/market").unwrap(), Command::Market);
+        assert_eq!(Command::parse("/大盘").unwrap(), Command::Market);
+        assert_eq!(Command::parse("/sum").unwrap(), Command::Summary);
+    }
+
+    #[test]
+    fn test_parse_cancel() {
+        assert_eq!(Command::parse("/cancel").unwrap(), Command::Cancel);
+        assert_eq!(Command::parse("/取消").unwrap(), Command::Cancel);
+        assert!(!Command::Cancel.is_heavy());
+        assert!(Command::parse("/analyze AAPL").unwrap().is_heavy());
+    }
+
+    #[test]
+    fn test_command_symbols() {
+        assert_eq!(
+            Command::parse("/technical nvda").unwrap().symbols(),
+            vec!["NVDA"]
+        );
+        assert_eq!(
+            Command::parse("/compare AAPL MSFT").unwrap().symbols(),
+            vec!["AAPL", "MSFT"]
+        );
+        assert!(Command::Macro.symbols().is_empty());
+    }
+
+    #[test]
+    fn test_parse_ask() {
+        assert_eq!(
+            Command::parse("/ask average RSI of my holdings by sector").unwrap(),
+            Command::Ask {
+                question: "average RSI of my holdings by sector".to_string()
+            }
+        );
+        assert_eq!(Command::parse("/ask run").unwrap(), Command::AskRun);
+        assert_eq!(Command::parse("/问数 执行").unwrap(), Command::AskRun);
+        assert!(Command::parse("/ask").is_err());
+        assert!(Command::AskRun.is_heavy());
+    }
+
     #[test]
     fn test_parse_help() {
         let cmd = Command::parse("/help").unwrap();
@@ -276,4 +1386,24 @@ mod tests {
         let cmd = Command::parse("/帮助").unwrap();
         assert_eq!(cmd, Command::Help);
     }
+
+    #[test]
+    fn test_menu_commands_parse() {
+        for (name, _) in Command::menu() {
+            let input = match *name {
+                "analyze" | "technical" | "fundamental" | "news" | "earnings" | "dividends"
+                | "watch" | "unwatch" => format!("/{name} AAPL"),
+                "compare" => "/compare AAPL MSFT".to_string(),
+                "report" => "/report AAPL".to_string(),
+                "theme" => "/theme ai".to_string(),
+                "alert" => "/alert AAPL > 200".to_string(),
+                "live" => "/live AAPL".to_string(),
+                "screen" => "/screen rsi<30".to_string(),
+                "ask" => "/ask stocks with rsi below 30".to_string(),
+                _ => format!("/{name}"),
+            };
+            let command = Command::parse(&input).unwrap();
+            assert_eq!(command.name(), *name);
+        }
+    }
 }
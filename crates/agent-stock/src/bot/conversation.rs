@@ -4,13 +4,16 @@
 //! for multi-turn interactions with the stock analysis agent.
 
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
+use crate::conversation_store::ConversationRecord;
+
 /// Maximum number of conversation turns to keep in history
 const MAX_HISTORY_SIZE: usize = 50;
 
 /// A single turn in the conversation
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ConversationTurn {
     /// User's input
     pub user_input: String,
@@ -35,7 +38,7 @@ impl ConversationTurn {
 }
 
 /// Context for the current conversation
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct ConversationContext {
     /// Current stock symbol being discussed
     pub current_symbol: Option<String>,
@@ -195,9 +198,10 @@ impl ConversationManager {
             }
 
             // If query doesn't contain any symbol, prepend the current one
-            let has_symbol = resolved
-                .split_whitespace()
-                .any(|word| word.chars().all(|c| c.is_ascii_uppercase()) && word.len() <= 5);
+            let has_symbol = resolved.split_whitespace().any(|word| {
+                (word.chars().all(|c| c.is_ascii_uppercase()) && word.len() <= 5)
+                    || crate::crypto::is_crypto(word)
+            });
 
             if !has_symbol && self.is_follow_up(query) {
                 resolved = format!("{symbol}: {resolved}");
@@ -209,6 +213,18 @@ impl ConversationManager {
         }
     }
 
+    /// Snapshot of the history and context for a conversation store
+    pub fn to_record(&self) -> ConversationRecord {
+        ConversationRecord::from_manager(self)
+    }
+
+    /// Replace the history and context with a saved conversation
+    pub fn restore(&mut self, record: ConversationRecord) {
+        let skip = record.turns.len().saturating_sub(self.max_history);
+        self.history = record.turns.into_iter().skip(skip).collect();
+        self.context = record.context;
+    }
+
     /// Clear conversation history
     pub fn clear(&mut self) {
         self.history.clear();
@@ -311,11 +327,7 @@ mod tests {
         let mut manager = ConversationManager::with_max_history(3);
 
         for i in 0..5 {
-            manager.add_turn(
-                format!("Query {}", i),
-                format!("Response {}", i),
-                vec![],
-            );
+            manager.add_turn(format!("Query {}", i), format!("Response {}", i), vec![]);
         }
 
         assert_eq!(manager.len(), 3);
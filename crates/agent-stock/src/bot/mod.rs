@@ -8,6 +8,20 @@
 //! - **Natural language**: Ask questions in natural language
 //! - **Conversation context**: Follow-up questions are handled intelligently
 //! - **Watchlist**: Track stocks of interest
+//! - **Scoreboard**: Directional calls are recorded and scored in hindsight
+//! - **Market wrap**: A daily close summary, on demand or on a schedule
+//! - **Macro alerts**: Notifications when watched FRED series are released
+//!   above or below a threshold
+//! - **Quiet hours**: Alerts firing overnight held back and sent as one
+//!   summary in the morning
+//! - **Portfolio**: Recorded positions valued with P&L, beta and sector
+//!   exposure, and questions about them answered by the portfolio agent
+//! - **Query builder**: Questions turned into a screen or SQL query, shown
+//!   for confirmation, then run and explained
+//! - **Notebook export**: The session saved as a runnable Jupyter notebook
+//! - **Reports**: An analysis saved as a Markdown, HTML or PDF document
+//! - **Progressive replies**: A quick price snapshot is shown while a full
+//!   analysis runs
 //!
 //! # Example
 //!
@@ -25,12 +39,47 @@
 pub mod commands;
 pub mod conversation;
 
-use crate::agents::StockAnalysisAgent;
+use crate::agents::{
+    PortfolioAgent, QueryBuilderAgent, StockAnalysisAgent, TranslatorAgent, query_builder,
+};
+use crate::alerts::{self, AlertEngine, AlertPolicy, AlertStore};
+use crate::anomalies::AnomalyMonitor;
+use crate::api::YahooFinanceClient;
+use crate::api::stream::{QuoteStreamer, StreamConfig};
+use crate::as_of;
+use crate::backup::StorePaths;
+use crate::cache::{CacheManager, StockCache};
 use crate::config::StockConfig;
+use crate::conversation_store::{
+    self, ConversationRecord, ConversationStore, InMemoryConversationStore, conversation_key,
+};
+use crate::delivery::{self, DeliveryStore};
+use crate::depth::AnalysisDepth;
+use crate::engine::{AnalysisContext, SnapshotSource, StockAnalysisEngine};
 use crate::error::{Result, StockError};
+use crate::interface::{BotPlatform, heatmap};
+use crate::language::ResponseLanguage;
+use crate::live::{self, LiveQuotes};
+use crate::macro_alerts::{self, MacroAlertJob, MacroWatchlist};
+use crate::market_wrap::{MarketWrapArchive, MarketWrapJob};
+use crate::migrations::Migrator;
+use crate::news;
+use crate::news_digest::{self, NewsDigestJob};
+use crate::notebook::{self, NotebookExporter};
+use crate::portfolio::{self, PortfolioStore};
+use crate::predictions::PredictionTracker;
+use crate::report;
+use crate::router::QueryIntent;
+use crate::storage::StoreCipher;
+use crate::style::{self, ResponseStyle};
+use crate::tools::{DividendTool, ScreenerTool};
+use crate::usage::{UsageSink, UsageStats};
 use agent_core::Context;
 use agent_llm::LLMProvider;
 use agent_runtime::AgentRuntime;
+use agent_runtime::usage::{self as token_usage, UsageSummary, UsageTracker};
+use chrono::{DateTime, Utc};
+use std::path::PathBuf;
 use std::sync::Arc;
 
 pub use commands::Command;
@@ -49,6 +98,28 @@ pub struct BotConfig {
     pub show_timestamps: bool,
     /// Maximum history size
     pub max_history: usize,
+    /// File predictions are persisted to (in memory when unset)
+    pub predictions_path: Option<PathBuf>,
+    /// File market wraps are archived to (in memory when unset)
+    pub market_wrap_path: Option<PathBuf>,
+    /// File macro watches are persisted to (in memory when unset)
+    pub macro_watch_path: Option<PathBuf>,
+    /// File price alerts are persisted to (in memory when unset)
+    pub alerts_path: Option<PathBuf>,
+    /// File quiet hours and held-back alerts are persisted to (in memory
+    /// when unset)
+    pub delivery_path: Option<PathBuf>,
+    /// Cooldown, re-arm band and daily cap applied to price alerts
+    pub alert_policy: AlertPolicy,
+    /// File portfolio positions are persisted to (in memory when unset)
+    pub portfolio_path: Option<PathBuf>,
+    /// File conversation history is persisted to, a SQLite database when it
+    /// ends in `.db` (in memory when unset)
+    pub conversation_path: Option<PathBuf>,
+    /// Where anonymous usage statistics are reported (disabled when unset)
+    pub usage_sink: Option<UsageSink>,
+    /// Key persisted stores are encrypted with (plain text when unset)
+    pub store_cipher: Option<StoreCipher>,
 }
 
 impl Default for BotConfig {
@@ -59,6 +130,16 @@ impl Default for BotConfig {
             prompt: ">>> ".to_string(),
             show_timestamps: false,
             max_history: 50,
+            predictions_path: None,
+            market_wrap_path: None,
+            macro_watch_path: None,
+            alerts_path: None,
+            delivery_path: None,
+            alert_policy: AlertPolicy::default(),
+            portfolio_path: None,
+            conversation_path: None,
+            usage_sink: None,
+            store_cipher: None,
         }
     }
 }
@@ -71,11 +152,32 @@ impl BotConfig {
             .with_env_finnhub_key()
             .with_env_fred_key()
             .with_env_news_provider()
+            .with_env_esg_provider()
             .from_env_model()
             .build()?;
 
         Ok(Self {
             stock_config,
+            predictions_path: std::env::var("STOCK_PREDICTIONS_FILE")
+                .ok()
+                .map(PathBuf::from),
+            market_wrap_path: std::env::var("STOCK_MARKET_WRAP_FILE")
+                .ok()
+                .map(PathBuf::from),
+            macro_watch_path: std::env::var("STOCK_MACRO_WATCH_FILE")
+                .ok()
+                .map(PathBuf::from),
+            alerts_path: std::env::var("STOCK_ALERTS_FILE").ok().map(PathBuf::from),
+            delivery_path: std::env::var("STOCK_DELIVERY_FILE").ok().map(PathBuf::from),
+            alert_policy: AlertPolicy::from_env(),
+            portfolio_path: std::env::var("STOCK_PORTFOLIO_FILE")
+                .ok()
+                .map(PathBuf::from),
+            conversation_path: std::env::var("STOCK_CONVERSATION_FILE")
+                .ok()
+                .map(PathBuf::from),
+            usage_sink: UsageSink::from_env(),
+            store_cipher: StoreCipher::from_env()?,
             ..Default::default()
         })
     }
@@ -94,6 +196,16 @@ pub struct BotConfigBuilder {
     prompt: Option<String>,
     show_timestamps: Option<bool>,
     max_history: Option<usize>,
+    predictions_path: Option<PathBuf>,
+    market_wrap_path: Option<PathBuf>,
+    macro_watch_path: Option<PathBuf>,
+    alerts_path: Option<PathBuf>,
+    delivery_path: Option<PathBuf>,
+    alert_policy: Option<AlertPolicy>,
+    portfolio_path: Option<PathBuf>,
+    conversation_path: Option<PathBuf>,
+    usage_sink: Option<UsageSink>,
+    store_cipher: Option<StoreCipher>,
 }
 
 impl BotConfigBuilder {
@@ -127,6 +239,66 @@ impl BotConfigBuilder {
         self
     }
 
+    /// Set the file predictions are persisted to
+    pub fn predictions_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.predictions_path = Some(path.into());
+        self
+    }
+
+    /// Set the file market wraps are archived to
+    pub fn market_wrap_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.market_wrap_path = Some(path.into());
+        self
+    }
+
+    /// Set the file macro watches are persisted to
+    pub fn macro_watch_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.macro_watch_path = Some(path.into());
+        self
+    }
+
+    /// Persist price alerts to `path`
+    pub fn alerts_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.alerts_path = Some(path.into());
+        self
+    }
+
+    /// Persist quiet hours and the alerts held back during them to `path`
+    pub fn delivery_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.delivery_path = Some(path.into());
+        self
+    }
+
+    /// Limit how often price alerts notify
+    pub fn alert_policy(mut self, policy: AlertPolicy) -> Self {
+        self.alert_policy = Some(policy);
+        self
+    }
+
+    /// Persist portfolio positions to `path`
+    pub fn portfolio_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.portfolio_path = Some(path.into());
+        self
+    }
+
+    /// Persist conversation history to `path`
+    pub fn conversation_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.conversation_path = Some(path.into());
+        self
+    }
+
+    /// Opt in to anonymous usage statistics reported to `sink`
+    pub fn usage_sink(mut self, sink: UsageSink) -> Self {
+        self.usage_sink = Some(sink);
+        self
+    }
+
+    /// Encrypt persisted stores with `cipher`
+    pub fn store_cipher(mut self, cipher: StoreCipher) -> Self {
+        self.store_cipher = Some(cipher);
+        self
+    }
+
     /// Build the config
     pub fn build(self) -> BotConfig {
         let defaults = BotConfig::default();
@@ -136,6 +308,16 @@ impl BotConfigBuilder {
             prompt: self.prompt.unwrap_or(defaults.prompt),
             show_timestamps: self.show_timestamps.unwrap_or(defaults.show_timestamps),
             max_history: self.max_history.unwrap_or(defaults.max_history),
+            predictions_path: self.predictions_path,
+            market_wrap_path: self.market_wrap_path,
+            macro_watch_path: self.macro_watch_path,
+            alerts_path: self.alerts_path,
+            delivery_path: self.delivery_path,
+            alert_policy: self.alert_policy.unwrap_or(defaults.alert_policy),
+            portfolio_path: self.portfolio_path,
+            conversation_path: self.conversation_path,
+            usage_sink: self.usage_sink,
+            store_cipher: self.store_cipher,
         }
     }
 }
@@ -143,33 +325,189 @@ impl BotConfigBuilder {
 /// Stock Analysis Bot
 pub struct StockBot {
     /// The underlying stock analysis agent
-    agent: StockAnalysisAgent,
+    agent: Arc<StockAnalysisAgent>,
+    /// Analyses with chart data, as the chat platforms get them, for `/report`
+    engine: StockAnalysisEngine,
     /// Conversation manager
     conversation: ConversationManager,
+    /// Keeps the conversation across restarts
+    conversation_store: Arc<dyn ConversationStore>,
     /// Watchlist
     watchlist: Vec<String>,
+    /// Response style for this session
+    style: ResponseStyle,
+    /// Whether teaching mode is on for this session
+    teaching: bool,
+    /// Reply language for this session
+    language: ResponseLanguage,
+    /// Translates analyses when the session is bilingual
+    translator: TranslatorAgent,
+    /// Directional calls made by the agents
+    predictions: Arc<PredictionTracker>,
+    /// Anonymous usage counters (no-op unless opted in)
+    usage: Arc<UsageStats>,
+    /// Tokens used by all LLM requests of the session
+    token_tracker: Arc<UsageTracker>,
+    /// Tokens used by the last request that called the LLM
+    last_usage: UsageSummary,
+    /// Writes and archives daily market wraps
+    market_wrap: Arc<MarketWrapJob>,
+    /// Writes news digests for the watchlist and portfolio
+    news_digest: Arc<NewsDigestJob>,
+    /// Checks watched FRED series for alerts
+    macro_alerts: Arc<MacroAlertJob>,
+    /// Checks price and RSI alerts against live quotes
+    alerts: Arc<AlertEngine>,
+    /// Live price feeds (needs a Finnhub or Polygon key)
+    live: Option<Arc<LiveQuotes>>,
+    /// Recorded positions and the agent answering questions about them
+    portfolio: Arc<PortfolioAgent>,
+    /// Screens and SQL queries built from questions for `/ask`
+    query_builder: Arc<QueryBuilderAgent>,
+    /// Quick quotes shown while comprehensive analyses run
+    snapshots: SnapshotSource,
+    /// Dividend yields and history for `/dividends`
+    dividends: DividendTool,
+    /// Stock screens for `/screen`
+    screener: ScreenerTool,
     /// Bot configuration
     config: BotConfig,
 }
 
 impl StockBot {
     /// Create a new stock bot with the given provider
-    pub async fn with_provider(
-        provider: Arc<dyn LLMProvider>,
-        config: BotConfig,
-    ) -> Result<Self> {
+    pub async fn with_provider(provider: Arc<dyn LLMProvider>, config: BotConfig) -> Result<Self> {
         let runtime = AgentRuntime::builder().provider(provider).build()?;
         let runtime = Arc::new(runtime);
 
-        let agent =
-            StockAnalysisAgent::new(runtime, Arc::new(config.stock_config.clone())).await?;
+        let stock_config = Arc::new(config.stock_config.clone());
+        let agent = Arc::new(
+            StockAnalysisAgent::new(Arc::clone(&runtime), Arc::clone(&stock_config)).await?,
+        );
 
-        let conversation = ConversationManager::with_max_history(config.max_history);
+        // Upgrade stores written by older releases before opening them
+        let store_paths = StorePaths {
+            predictions: config.predictions_path.clone(),
+            usage: match &config.usage_sink {
+                Some(UsageSink::File(path)) => Some(path.clone()),
+                _ => None,
+            },
+            alerts: config.alerts_path.clone(),
+            delivery: config.delivery_path.clone(),
+            portfolio: config.portfolio_path.clone(),
+            conversations: config.conversation_path.clone(),
+        };
+        for step in Migrator::new(&store_paths, config.store_cipher.as_ref()).migrate(false)? {
+            tracing::info!("Applied migration {}", step);
+        }
+
+        let conversation_store: Arc<dyn ConversationStore> = match &config.conversation_path {
+            Some(path) => conversation_store::open(path, config.store_cipher.clone())?,
+            None => Arc::new(InMemoryConversationStore::new()),
+        };
+        let mut conversation = ConversationManager::with_max_history(config.max_history);
+        if let Some(record) =
+            conversation_store.load(&conversation_key(BotPlatform::CLI, CLI_USER))?
+        {
+            conversation.restore(record);
+        }
+
+        let predictions = match &config.predictions_path {
+            Some(path) => PredictionTracker::open_with_cipher(path, config.store_cipher.clone())?,
+            None => PredictionTracker::in_memory(),
+        };
+        let usage = match &config.usage_sink {
+            Some(sink) => UsageStats::open_with_cipher(sink.clone(), config.store_cipher.clone())?,
+            None => UsageStats::disabled(),
+        };
+        let wraps = match &config.market_wrap_path {
+            Some(path) => MarketWrapArchive::open_with_cipher(path, config.store_cipher.clone())?,
+            None => MarketWrapArchive::in_memory(),
+        };
+        let market_wrap = MarketWrapJob::new(
+            Arc::clone(&agent),
+            Arc::clone(&stock_config),
+            Arc::new(wraps),
+        );
+        let news_digest = NewsDigestJob::new(Arc::clone(&agent), &stock_config);
+        let macro_watches = match &config.macro_watch_path {
+            Some(path) => MacroWatchlist::open_with_cipher(path, config.store_cipher.clone())?,
+            None => MacroWatchlist::in_memory(),
+        };
+        let macro_alerts = MacroAlertJob::new(
+            Arc::clone(&agent),
+            Arc::clone(&stock_config),
+            Arc::new(macro_watches),
+        );
+        let alert_store = match &config.alerts_path {
+            Some(path) => AlertStore::open_with_cipher(path, config.store_cipher.clone())?,
+            None => AlertStore::in_memory(),
+        };
+        let delivery = match &config.delivery_path {
+            Some(path) => DeliveryStore::open_with_cipher(path, config.store_cipher.clone())?,
+            None => DeliveryStore::in_memory(),
+        };
+        let delivery = Arc::new(delivery);
+        let alerts = AlertEngine::new(
+            Arc::new(alert_store),
+            Arc::new(YahooFinanceClient::new()),
+            StockCache::new(ALERT_QUOTE_TTL),
+        )
+        .with_delivery(Arc::clone(&delivery))
+        .with_policy(config.alert_policy.clone());
+        let live = StreamConfig::from_config(&config.stock_config).map(|stream| {
+            let monitor = AnomalyMonitor::new(
+                Arc::new(YahooFinanceClient::new()),
+                news::from_config(&config.stock_config),
+            );
+            Arc::new(
+                LiveQuotes::new(QuoteStreamer::spawn(stream))
+                    .with_anomaly_alerts(monitor)
+                    .with_delivery(Arc::clone(&delivery)),
+            )
+        });
+        let positions = match &config.portfolio_path {
+            Some(path) => PortfolioStore::open_with_cipher(path, config.store_cipher.clone())?,
+            None => PortfolioStore::in_memory(),
+        };
+        let positions = Arc::new(positions);
+        let token_tracker = Arc::clone(runtime.token_tracker());
+        let query_builder =
+            QueryBuilderAgent::new(&runtime, Arc::clone(&stock_config), Arc::clone(&positions))?;
+        let translator = TranslatorAgent::new(&runtime, Arc::clone(&stock_config))?;
+        let engine =
+            StockAnalysisEngine::new(Arc::clone(&runtime), Arc::clone(&stock_config)).await?;
+        let portfolio = PortfolioAgent::new(runtime, stock_config, positions).await?;
+        let caches = CacheManager::from_config(&config.stock_config);
 
         Ok(Self {
             agent,
+            engine,
             conversation,
+            conversation_store,
             watchlist: Vec::new(),
+            style: config.stock_config.response_style,
+            teaching: config.stock_config.teaching_mode,
+            language: ResponseLanguage::for_config(&config.stock_config),
+            translator,
+            predictions: Arc::new(predictions),
+            usage: Arc::new(usage),
+            token_tracker,
+            last_usage: UsageSummary::default(),
+            market_wrap: Arc::new(market_wrap),
+            news_digest: Arc::new(news_digest),
+            macro_alerts: Arc::new(macro_alerts),
+            alerts: Arc::new(alerts),
+            live,
+            portfolio: Arc::new(portfolio),
+            query_builder: Arc::new(query_builder),
+            snapshots: SnapshotSource::new(Arc::new(config.stock_config.clone())),
+            dividends: DividendTool::new(caches.fundamental.clone()),
+            screener: ScreenerTool::new(
+                &config.stock_config,
+                caches.fundamental.clone(),
+                caches.realtime.clone(),
+            ),
             config,
         })
     }
@@ -190,19 +528,182 @@ impl StockBot {
         self.execute_command(command).await
     }
 
+    /// Process user input in two phases
+    ///
+    /// For standard and deep analyses, which take 30 seconds or more,
+    /// `interim` is first called with a quick price and headline snapshot; the full
+    /// analysis is returned when ready. Other input is handled like
+    /// [`StockBot::process_input`].
+    pub async fn process_input_progressive(
+        &mut self,
+        input: &str,
+        interim: impl FnOnce(&str),
+    ) -> Result<String> {
+        let command = Command::parse(input)?;
+        // Quick analyses arrive about as fast as a snapshot would, and
+        // today's price has no place in an analysis of the past
+        if let Command::Analyze {
+            symbol,
+            depth,
+            as_of: None,
+        } = &command
+            && depth.unwrap_or(self.config.stock_config.analysis_depth) != AnalysisDepth::Quick
+        {
+            match self.snapshots.snapshot(symbol).await {
+                Ok(snapshot) => interim(&snapshot.to_string()),
+                Err(e) => tracing::debug!("No snapshot for {}: {}", symbol, e),
+            }
+        }
+        self.execute_command(command).await
+    }
+
+    /// Agent context carrying the session's per-request options
+    fn agent_context(&self) -> Context {
+        let mut context = Context::new();
+        self.style.apply_to(&mut context);
+        style::set_teaching_mode(&mut context, self.teaching);
+        self.language.apply_to(&mut context);
+        context
+    }
+
+    /// Record any directional call in an analysis of `symbol` by `agent`
+    fn track_predictions(&self, symbol: &str, analysis: &str, agent: &str) {
+        self.track_predictions_at(symbol, analysis, agent, Utc::now());
+    }
+
+    /// Record the directional calls of an analysis made as of `made_at`
+    fn track_predictions_at(
+        &self,
+        symbol: &str,
+        analysis: &str,
+        agent: &str,
+        made_at: DateTime<Utc>,
+    ) {
+        let model = self.config.stock_config.model_for(agent);
+        if let Err(e) = self
+            .predictions
+            .record_analysis_at(symbol, analysis, agent, &model, made_at)
+        {
+            tracing::warn!("Failed to record predictions for {}: {}", symbol, e);
+        }
+    }
+
     /// Execute a parsed command
     pub async fn execute_command(&mut self, command: Command) -> Result<String> {
+        self.record_usage(&command);
+        let is_exit = matches!(command, Command::Exit);
+        let is_analysis = command.is_analysis();
+        let mut context = self.agent_context();
+        let mut result = self.run_command(command, &mut context).await;
+        if let Ok(text) = &result
+            && is_analysis
+            && self.translator.is_bilingual(&context)
+        {
+            result = Ok(self.translator.bilingual(text, &mut context).await);
+        }
+        let request_usage = token_usage::from_context(&context);
+        if !request_usage.is_empty() {
+            self.last_usage = request_usage;
+        }
+        if result.is_err() && !is_exit {
+            self.usage.record_error();
+        }
+        self.save_conversation();
+        result
+    }
+
+    /// Persist the conversation so it survives a restart
+    fn save_conversation(&self) {
+        let key = conversation_key(BotPlatform::CLI, CLI_USER);
+        if let Err(e) = self
+            .conversation_store
+            .save(&key, &self.conversation.to_record())
+        {
+            tracing::warn!("Failed to save conversation: {}", e);
+        }
+    }
+
+    /// Count the command and the providers serving it; never its arguments
+    fn record_usage(&self, command: &Command) {
+        if !self.usage.is_enabled() || matches!(command, Command::Exit) {
+            return;
+        }
+        self.usage.record_command(command.name());
+
+        let agent = match command {
+            Command::Analyze { .. } | Command::Compare { .. } | Command::Query { .. } => {
+                COMPREHENSIVE_AGENT
+            }
+            Command::Technical { .. } => "technical-analyzer",
+            Command::Ask { .. } | Command::AskRun => "query-builder",
+            Command::Fundamental { .. } => "fundamental-analyzer",
+            Command::News { .. } | Command::NewsDigest => "news-analyzer",
+            Command::Earnings { .. } => "earnings-analyzer",
+            Command::Macro | Command::Geopolitical | Command::Theme { .. } | Command::Wrap => {
+                "macro-analyzer"
+            }
+            _ => return,
+        };
+        let stock_config = &self.config.stock_config;
+        self.usage
+            .record_provider("llm", &stock_config.model_for(agent));
+        self.usage.record_provider(
+            "data",
+            &format!("{:?}", stock_config.default_provider).to_lowercase(),
+        );
+        if matches!(
+            command,
+            Command::News { .. } | Command::NewsDigest | Command::Analyze { .. }
+        ) {
+            self.usage.record_provider(
+                "news",
+                &format!("{:?}", stock_config.news_provider).to_lowercase(),
+            );
+        }
+    }
+
+    async fn run_command(&mut self, command: Command, context: &mut Context) -> Result<String> {
         match command {
-            Command::Analyze { symbol } => {
+            Command::Analyze {
+                symbol,
+                depth,
+                as_of,
+            } => {
                 self.conversation.set_current_symbol(&symbol);
-                let result = self.agent.analyze_comprehensive(&symbol).await?;
-                self.conversation
-                    .add_turn(format!("/analyze {symbol}"), result.clone(), vec![symbol]);
+                if let Some(depth) = depth {
+                    depth.apply_to(context);
+                }
+                let Some(date) = as_of else {
+                    let result = self.agent.analyze_comprehensive(&symbol, context).await?;
+                    self.track_predictions(&symbol, &result, COMPREHENSIVE_AGENT);
+                    self.conversation.add_turn(
+                        format!("/analyze {symbol}"),
+                        result.clone(),
+                        vec![symbol],
+                    );
+                    return Ok(result);
+                };
+
+                as_of::apply_to(date, context);
+                let result = self.agent.analyze_comprehensive(&symbol, context).await?;
+                self.track_predictions_at(
+                    &symbol,
+                    &result,
+                    COMPREHENSIVE_AGENT,
+                    as_of::cutoff(date),
+                );
+                let result = format!("🕰️ As of {date}\n\n{result}");
+                self.conversation.add_turn(
+                    format!("/analyze {symbol} {date}"),
+                    result.clone(),
+                    vec![symbol],
+                );
                 Ok(result)
             }
             Command::Technical { symbol } => {
                 self.conversation.set_current_symbol(&symbol);
-                let result = self.agent.analyze_technical(&symbol).await?;
+                let result = self.agent.analyze_technical(&symbol, context).await?;
+                self.track_predictions(&symbol, &result, "technical-analyzer");
                 self.conversation.add_turn(
                     format!("/technical {symbol}"),
                     result.clone(),
@@ -212,7 +713,8 @@ impl StockBot {
             }
             Command::Fundamental { symbol } => {
                 self.conversation.set_current_symbol(&symbol);
-                let result = self.agent.analyze_fundamental(&symbol).await?;
+                let result = self.agent.analyze_fundamental(&symbol, context).await?;
+                self.track_predictions(&symbol, &result, "fundamental-analyzer");
                 self.conversation.add_turn(
                     format!("/fundamental {symbol}"),
                     result.clone(),
@@ -222,14 +724,16 @@ impl StockBot {
             }
             Command::News { symbol } => {
                 self.conversation.set_current_symbol(&symbol);
-                let result = self.agent.analyze_news(&symbol).await?;
+                let result = self.agent.analyze_news(&symbol, context).await?;
+                self.track_predictions(&symbol, &result, "news-analyzer");
                 self.conversation
                     .add_turn(format!("/news {symbol}"), result.clone(), vec![symbol]);
                 Ok(result)
             }
             Command::Earnings { symbol } => {
                 self.conversation.set_current_symbol(&symbol);
-                let result = self.agent.analyze_earnings(&symbol).await?;
+                let result = self.agent.analyze_earnings(&symbol, context).await?;
+                self.track_predictions(&symbol, &result, "earnings-analyzer");
                 self.conversation.add_turn(
                     format!("/earnings {symbol}"),
                     result.clone(),
@@ -237,20 +741,90 @@ impl StockBot {
                 );
                 Ok(result)
             }
+            Command::Dividends { symbol } => Ok(self.dividends.report(&symbol).await?.to_string()),
+            Command::Screen { screen } => Ok(self.screener.screen(screen).await?.to_string()),
             Command::Macro => {
-                let result = self.agent.analyze_macro().await?;
+                let result = self.agent.analyze_macro(context).await?;
                 self.conversation
                     .add_turn("/macro".to_string(), result.clone(), vec![]);
                 Ok(result)
             }
+            Command::MacroWatch { .. } | Command::MacroUnwatch { .. } | Command::MacroWatches => {
+                macro_alerts::command_reply(
+                    Some(self.macro_alerts.watches()),
+                    CLI_USER,
+                    BotPlatform::CLI,
+                    &command,
+                )
+            }
+            Command::Alert { .. } | Command::AlertRemove { .. } | Command::Alerts => {
+                alerts::command_reply(
+                    Some(self.alerts.store()),
+                    CLI_USER,
+                    BotPlatform::CLI,
+                    &command,
+                )
+            }
+            Command::Quiet
+            | Command::QuietSet { .. }
+            | Command::QuietOff
+            | Command::TimeZone { .. } => delivery::command_reply(
+                self.alerts.delivery().map(AsRef::as_ref),
+                CLI_USER,
+                BotPlatform::CLI,
+                &command,
+            ),
+            Command::Live { .. } | Command::LiveStop { .. } => {
+                live::command_reply(self.live.as_deref(), CLI_USER, BotPlatform::CLI, &command)
+            }
+            Command::PortfolioAsk { ref question } => {
+                let result =
+                    portfolio::command_reply(Some(&self.portfolio), CLI_USER, &command, context)
+                        .await?;
+                self.conversation.add_turn(
+                    format!("/portfolio {question}"),
+                    result.clone(),
+                    vec![],
+                );
+                Ok(result)
+            }
+            Command::Ask { .. } | Command::AskRun => {
+                query_builder::command_reply(Some(&self.query_builder), CLI_USER, &command, context)
+                    .await
+            }
+            Command::PortfolioAdd { .. } | Command::PortfolioRemove { .. } | Command::Portfolio => {
+                portfolio::command_reply(Some(&self.portfolio), CLI_USER, &command, context).await
+            }
             Command::Geopolitical => {
-                let result = self.agent.analyze_geopolitical().await?;
+                let result = self.agent.analyze_geopolitical(context).await?;
                 self.conversation
                     .add_turn("/geopolitical".to_string(), result.clone(), vec![]);
                 Ok(result)
             }
+            Command::Theme { theme } => {
+                let result = self.agent.analyze_theme(theme, context).await?;
+                self.conversation
+                    .add_turn(format!("/theme {}", theme.key), result.clone(), vec![]);
+                Ok(result)
+            }
+            Command::Wrap => {
+                let result = self.market_wrap.run(&self.watchlist).await?.to_string();
+                self.conversation
+                    .add_turn("/wrap".to_string(), result.clone(), vec![]);
+                Ok(result)
+            }
+            Command::NewsDigest => {
+                let weights = self.news_digest_weights();
+                if weights.is_empty() {
+                    return Ok("Watchlist is empty. Use /watch <symbol> to add stocks.".to_string());
+                }
+                let result = self.news_digest.run(&weights).await?.to_string();
+                self.conversation
+                    .add_turn("/news all".to_string(), result.clone(), vec![]);
+                Ok(result)
+            }
             Command::Compare { symbols } => {
-                let result = self.agent.compare_stocks(&symbols).await?;
+                let result = self.agent.compare_stocks(&symbols, context).await?;
                 self.conversation.add_turn(
                     format!("/compare {}", symbols.join(" ")),
                     result.clone(),
@@ -263,10 +837,27 @@ impl StockBot {
                     Ok(format!("{symbol} is already in watchlist"))
                 } else {
                     self.watchlist.push(symbol.clone());
-                    Ok(format!("Added {symbol} to watchlist"))
+                    let live = self
+                        .live
+                        .as_deref()
+                        .filter(|live| live.anomaly_alerts_enabled());
+                    match live.map(|live| live.watch_anomalies(CLI_USER, BotPlatform::CLI, &symbol))
+                    {
+                        Some(Ok(_)) => Ok(format!(
+                            "Added {symbol} to watchlist; halts, limit moves and gaps will be printed"
+                        )),
+                        Some(Err(e)) => {
+                            tracing::warn!("No anomaly alerts for {}: {}", symbol, e);
+                            Ok(format!("Added {symbol} to watchlist"))
+                        }
+                        None => Ok(format!("Added {symbol} to watchlist")),
+                    }
                 }
             }
             Command::Unwatch { symbol } => {
+                if let Some(live) = &self.live {
+                    live.unwatch_anomalies(CLI_USER, &symbol);
+                }
                 if let Some(pos) = self.watchlist.iter().position(|s| s == &symbol) {
                     self.watchlist.remove(pos);
                     Ok(format!("Removed {symbol} from watchlist"))
@@ -281,10 +872,136 @@ impl StockBot {
                     Ok(format!("Watchlist:\n  {}", self.watchlist.join("\n  ")))
                 }
             }
+            // The terminal shows the heatmap data as text
+            Command::Market => {
+                let tiles = heatmap::sector_tiles(&YahooFinanceClient::new()).await?;
+                Ok(format!(
+                    "S&P 500 sectors today:\n  {}",
+                    heatmap::summary_lines(&tiles).join("\n  ")
+                ))
+            }
+            Command::Summary => {
+                if self.watchlist.is_empty() {
+                    return Ok("Watchlist is empty. Use /watch <symbol> to add stocks.".to_string());
+                }
+                let tiles =
+                    heatmap::watchlist_tiles(&YahooFinanceClient::new(), &self.watchlist).await?;
+                Ok(format!(
+                    "Watchlist today:\n  {}",
+                    heatmap::summary_lines(&tiles).join("\n  ")
+                ))
+            }
             Command::Clear => {
                 self.conversation.clear();
                 Ok("Conversation history cleared.".to_string())
             }
+            Command::Style { style: Some(style) } => {
+                self.style = style;
+                Ok(format!(
+                    "Response style set to {style}: {}",
+                    style.description()
+                ))
+            }
+            Command::Style { style: None } => {
+                let options: Vec<String> = ResponseStyle::ALL
+                    .iter()
+                    .map(|s| format!("  {s:<10} {}", s.description()))
+                    .collect();
+                Ok(format!(
+                    "Current response style: {}\n\nAvailable styles:\n{}",
+                    self.style,
+                    options.join("\n")
+                ))
+            }
+            Command::Teach { enabled } => {
+                self.teaching = enabled.unwrap_or(!self.teaching);
+                if self.teaching {
+                    Ok(
+                        "Teaching mode on: analyses will explain their reasoning and formulas."
+                            .to_string(),
+                    )
+                } else {
+                    Ok("Teaching mode off.".to_string())
+                }
+            }
+            Command::Language {
+                language: Some(language),
+            } => {
+                self.language = language;
+                Ok(format!(
+                    "Reply language set to {language}: {}",
+                    language.description()
+                ))
+            }
+            Command::Language { language: None } => {
+                let options: Vec<String> = ResponseLanguage::ALL
+                    .iter()
+                    .map(|l| format!("  {l:<10} {}", l.description()))
+                    .collect();
+                Ok(format!(
+                    "Current reply language: {}\n\nAvailable languages:\n{}",
+                    self.language,
+                    options.join("\n")
+                ))
+            }
+            // Audio replies need a chat platform; the terminal prints text only
+            Command::Voice { .. } => {
+                Ok("Audio summaries are not available in the terminal.".to_string())
+            }
+            Command::Scoreboard => {
+                let scored = self
+                    .predictions
+                    .score_due(&YahooFinanceClient::new())
+                    .await?;
+                if scored > 0 {
+                    tracing::info!("Scored {} predictions", scored);
+                }
+                Ok(self.predictions.scoreboard().to_string())
+            }
+            // The REPL handles one request at a time, so nothing is ever queued
+            Command::Cancel => Ok("Nothing to cancel.".to_string()),
+            // The CLI prints replies in full, so there is never a next page
+            Command::More => Ok("Nothing more to show.".to_string()),
+            Command::Capabilities => Ok(self.agent.capabilities_report()),
+            Command::Usage => Ok(self.usage_report()),
+            Command::Notebook { kind } => {
+                let record = ConversationRecord::from_manager(&self.conversation);
+                if record.turns.is_empty() {
+                    return Ok("Nothing to export yet.".to_string());
+                }
+                let symbols = notebook::session_symbols(&record);
+                let charts = notebook::fetch_charts(&YahooFinanceClient::new(), &symbols).await;
+                let exporter = NotebookExporter::new().with_kind(kind).with_charts(charts);
+                let path = exporter.filename(&record);
+                std::fs::write(&path, exporter.to_json(&record)?)
+                    .map_err(|e| StockError::Other(format!("Failed to write {path}: {e}")))?;
+                Ok(format!("📓 Saved the session as {path}"))
+            }
+            Command::Report { symbols, format } => {
+                // Through the engine, like the chat platforms, so the report
+                // carries its price chart
+                let mut report_context = AnalysisContext::new();
+                report_context.preferences.language = self.language.as_str().to_string();
+                report_context.preferences.style = self.style;
+                report_context.preferences.teaching_mode = Some(self.teaching);
+                let (text, document) = report::command_reply(
+                    &self.engine,
+                    &symbols,
+                    format,
+                    BotPlatform::CLI,
+                    &mut report_context,
+                )
+                .await?;
+                let Some(document) = document else {
+                    return Ok(text);
+                };
+                let path = document
+                    .filename
+                    .unwrap_or_else(|| format!("report.{}", format.extension()));
+                std::fs::write(&path, document.content)
+                    .map_err(|e| StockError::Other(format!("Failed to write {path}: {e}")))?;
+                Ok(format!("📄 Saved the report as {path}"))
+            }
             Command::Help => Ok(Command::help_text().to_string()),
             Command::Exit => Err(StockError::Other("exit".to_string())),
             Command::Query { text } => {
@@ -296,8 +1013,14 @@ impl StockBot {
                     self.conversation.set_current_symbol(symbol);
                 }
 
-                let mut context = Context::new();
-                let result = self.agent.smart_process(&resolved, &mut context).await?;
+                let result = self.agent.smart_process(&resolved, context).await?;
+
+                if let ([symbol], Some(agent)) = (
+                    symbols.as_slice(),
+                    prediction_agent(self.agent.router().classify(&resolved)),
+                ) {
+                    self.track_predictions(symbol, &result, agent);
+                }
 
                 self.conversation.add_turn(text, result.clone(), symbols);
                 Ok(result)
@@ -305,6 +1028,82 @@ impl StockBot {
         }
     }
 
+    /// Get the current response style
+    pub fn style(&self) -> ResponseStyle {
+        self.style
+    }
+
+    /// Whether teaching mode is on
+    pub fn teaching_mode(&self) -> bool {
+        self.teaching
+    }
+
+    /// Get the current reply language
+    pub fn language(&self) -> ResponseLanguage {
+        self.language
+    }
+
+    /// Get the prediction tracker
+    pub fn predictions(&self) -> &Arc<PredictionTracker> {
+        &self.predictions
+    }
+
+    /// Get the market wrap job, e.g. to run it on a schedule
+    pub fn market_wrap(&self) -> &Arc<MarketWrapJob> {
+        &self.market_wrap
+    }
+
+    /// Get the news digest job, e.g. to run the morning briefing
+    pub fn news_digest(&self) -> &Arc<NewsDigestJob> {
+        &self.news_digest
+    }
+
+    /// Watchlist and portfolio symbols, weighted for the news digest
+    pub fn news_digest_weights(&self) -> Vec<(String, f64)> {
+        let positions = self.portfolio.store().positions(CLI_USER);
+        news_digest::exposure_weights(&self.watchlist, &positions)
+    }
+
+    /// Get the macro alert job, e.g. to run it on a schedule
+    pub fn macro_alerts(&self) -> &Arc<MacroAlertJob> {
+        &self.macro_alerts
+    }
+
+    /// Get the analysis agent
+    pub fn agent(&self) -> &Arc<StockAnalysisAgent> {
+        &self.agent
+    }
+
+    /// Get the price alert engine, e.g. to start background checks
+    pub fn alerts(&self) -> &Arc<AlertEngine> {
+        &self.alerts
+    }
+
+    /// Get the live price feeds, if a streaming provider is configured
+    pub fn live(&self) -> Option<&Arc<LiveQuotes>> {
+        self.live.as_ref()
+    }
+
+    /// Get the portfolio agent and the positions it analyzes
+    pub fn portfolio(&self) -> &Arc<PortfolioAgent> {
+        &self.portfolio
+    }
+
+    /// Tokens and estimated cost of the last request and of the session
+    pub fn usage_report(&self) -> String {
+        let mut report = String::new();
+        if !self.last_usage.is_empty() {
+            report.push_str(&format!("Last request:\n{}\n\n", self.last_usage));
+        }
+        report.push_str(&format!("Session total:\n{}", self.token_tracker.summary()));
+        report
+    }
+
+    /// Get the usage statistics collector
+    pub fn usage(&self) -> &Arc<UsageStats> {
+        &self.usage
+    }
+
     /// Get the watchlist
     pub fn watchlist(&self) -> &[String] {
         &self.watchlist
@@ -321,6 +1120,28 @@ impl StockBot {
     }
 }
 
+/// User id alerts and positions set from the console are stored under
+pub const CLI_USER: &str = "cli";
+
+/// How long a quote is reused across alerts on the same symbol
+const ALERT_QUOTE_TTL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Agent name credited with comprehensive analyses
+const COMPREHENSIVE_AGENT: &str = "stock-analysis";
+
+/// Agent credited with a natural-language answer, if it may contain a call
+/// on a single stock
+fn prediction_agent(intent: QueryIntent) -> Option<&'static str> {
+    match intent {
+        QueryIntent::TechnicalAnalysis
+        | QueryIntent::FundamentalAnalysis
+        | QueryIntent::NewsAnalysis
+        | QueryIntent::EarningsAnalysis => Some(intent.agent_name()),
+        QueryIntent::ComprehensiveAnalysis => Some(COMPREHENSIVE_AGENT),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2,13 +2,33 @@
 //!
 //! Provides a shared, thread-safe caching system for stock data with different TTLs
 //! for various data types (realtime, fundamental, news, earnings, macro, sector).
+//!
+//! Entries live in a [`CacheBackend`]. The default keeps them in process
+//! memory; with the `redis` feature and [`REDIS_URL_ENV`] set, they live in
+//! Redis instead, so several bot instances (Telegram, Feishu, the HTTP API)
+//! share cached quotes and macro data.
+//!
+//! [`StockCache::get_or_fetch`] coalesces concurrent misses for the same key:
+//! the first caller fetches and the others wait for its result, so a burst of
+//! identical requests (e.g. a popular ticker right after its entry expires)
+//! costs one API call instead of one per caller.
 
+use async_trait::async_trait;
 use cached::{Cached, TimedCache};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
 use std::hash::Hash;
-use std::sync::{Arc, OnceLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
 use std::time::Duration;
-use tokio::sync::RwLock;
+use tokio::sync::{RwLock, watch};
+
+use crate::config::StockConfig;
+
+/// Environment variable with the Redis URL for a shared cache
+/// (e.g. `redis://127.0.0.1:6379`); needs the `redis` feature
+pub const REDIS_URL_ENV: &str = "STOCK_REDIS_URL";
 
 /// Cache key for stock data requests
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -36,35 +56,154 @@ impl CacheKey {
     }
 }
 
+/// Storage for one cache tier
+///
+/// Entries expire after the tier's TTL. Backends that can fail (e.g. a
+/// remote store) log the error and behave as a miss, so an outage costs API
+/// calls rather than failing analyses.
+#[async_trait]
+pub trait CacheBackend: Send + Sync {
+    /// Short name for logs and health checks, e.g. "memory"
+    fn name(&self) -> &'static str;
+
+    /// The value stored under `key`, unless missing or expired
+    async fn get(&self, key: &CacheKey) -> Option<serde_json::Value>;
+
+    /// Store `value` under `key` until the TTL elapses
+    async fn set(&self, key: CacheKey, value: serde_json::Value);
+
+    /// Remove `key`
+    async fn remove(&self, key: &CacheKey);
+
+    /// Remove every entry of this tier
+    async fn clear(&self);
+
+    /// Number of live entries in this tier
+    async fn len(&self) -> usize;
+
+    /// Whether this tier holds no live entries
+    async fn is_empty(&self) -> bool {
+        self.len().await == 0
+    }
+}
+
+/// In-process backend; entries are lost on restart and not shared
+pub struct MemoryBackend {
+    cache: RwLock<TimedCache<CacheKey, serde_json::Value>>,
+}
+
+impl MemoryBackend {
+    /// An empty backend whose entries live for `ttl`
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            cache: RwLock::new(TimedCache::with_lifespan(ttl)),
+        }
+    }
+}
+
+#[async_trait]
+impl CacheBackend for MemoryBackend {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    async fn get(&self, key: &CacheKey) -> Option<serde_json::Value> {
+        let mut cache = self.cache.write().await;
+        cache.cache_get(key).cloned()
+    }
+
+    async fn set(&self, key: CacheKey, value: serde_json::Value) {
+        let mut cache = self.cache.write().await;
+        let _ = cache.cache_set(key, value);
+    }
+
+    async fn remove(&self, key: &CacheKey) {
+        let mut cache = self.cache.write().await;
+        let _ = cache.cache_remove(key);
+    }
+
+    async fn clear(&self) {
+        let mut cache = self.cache.write().await;
+        cache.cache_clear();
+    }
+
+    async fn len(&self) -> usize {
+        let cache = self.cache.read().await;
+        cache.cache_size()
+    }
+}
+
 /// Thread-safe cache for stock data
 pub struct StockCache {
-    cache: Arc<RwLock<TimedCache<CacheKey, serde_json::Value>>>,
+    cache: Arc<dyn CacheBackend>,
+    /// Fetches in progress; waiters receive the value once it is stored
+    inflight: Arc<Mutex<HashMap<CacheKey, InflightFetch>>>,
+    /// Requests answered by another caller's in-flight fetch
+    coalesced: Arc<AtomicU64>,
+}
+
+/// Result of an in-flight fetch: `None` until it succeeds. The sender is
+/// dropped without a value when the fetch fails or is cancelled.
+type InflightFetch = watch::Receiver<Option<serde_json::Value>>;
+
+/// Removes a leader's in-flight entry however its fetch ends
+struct InflightGuard<'a> {
+    inflight: &'a Mutex<HashMap<CacheKey, InflightFetch>>,
+    key: &'a CacheKey,
+}
+
+impl Drop for InflightGuard<'_> {
+    fn drop(&mut self) {
+        self.inflight
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(self.key);
+    }
 }
 
 impl StockCache {
-    /// Create a new cache with specified TTL
+    /// Create a new in-memory cache with specified TTL
     pub fn new(ttl: Duration) -> Self {
+        Self::with_backend(Arc::new(MemoryBackend::new(ttl)))
+    }
+
+    /// Create a cache stored in `backend`
+    pub fn with_backend(backend: Arc<dyn CacheBackend>) -> Self {
         Self {
-            cache: Arc::new(RwLock::new(TimedCache::with_lifespan(ttl))),
+            cache: backend,
+            inflight: Arc::default(),
+            coalesced: Arc::default(),
         }
     }
 
+    /// Create the `category` tier with the TTL of `config`, shared through
+    /// Redis when configured (see [`CacheManager::from_env`])
+    pub fn from_config(config: &StockConfig, category: CacheCategory) -> Self {
+        CacheManager::from_config(config).tier(category).clone()
+    }
+
+    /// Name of the backend, e.g. "memory" or "redis"
+    pub fn backend_name(&self) -> &'static str {
+        self.cache.name()
+    }
+
     /// Get a value from the cache
     pub async fn get(&self, key: &CacheKey) -> Option<serde_json::Value> {
-        let mut cache = self.cache.write().await;
-        cache.cache_get(key).cloned()
+        self.cache.get(key).await
     }
 
     /// Insert a value into the cache
     pub async fn insert(&self, key: CacheKey, value: serde_json::Value) {
-        let mut cache = self.cache.write().await;
-        let _ = cache.cache_set(key, value);
+        self.cache.set(key, value).await;
     }
 
     /// Get or fetch a value using the provided fetcher function
     ///
     /// If the value exists in cache, it's returned immediately.
     /// Otherwise, the fetcher function is called and the result is cached.
+    /// Concurrent misses for the same key share one fetch: later callers wait
+    /// for the first one's result instead of calling their own fetcher. If
+    /// that fetch fails, each waiter falls back to its own fetcher.
     pub async fn get_or_fetch<F, Fut, E>(
         &self,
         key: CacheKey,
@@ -82,31 +221,68 @@ impl StockCache {
 
         tracing::debug!("Cache miss for key: {:?}", key);
 
+        // Either join the fetch already in flight or become its leader
+        let leader = {
+            let mut inflight = self.inflight.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Some(receiver) = inflight.get(&key) {
+                Err(receiver.clone())
+            } else {
+                let (sender, receiver) = watch::channel(None);
+                inflight.insert(key.clone(), receiver);
+                Ok(sender)
+            }
+        };
+
+        let sender = match leader {
+            Ok(sender) => sender,
+            Err(mut receiver) => {
+                if let Ok(value) = receiver.wait_for(Option::is_some).await {
+                    if let Some(value) = value.clone() {
+                        tracing::debug!("Coalesced request for key: {:?}", key);
+                        self.coalesced.fetch_add(1, Ordering::Relaxed);
+                        return Ok(value);
+                    }
+                }
+                tracing::debug!("In-flight fetch failed for key: {:?}, fetching again", key);
+                let value = fetcher().await?;
+                self.insert(key, value.clone()).await;
+                return Ok(value);
+            }
+        };
+
+        let _guard = InflightGuard {
+            inflight: &self.inflight,
+            key: &key,
+        };
+
         // Fetch the value
         let value = fetcher().await?;
 
-        // Store in cache
-        self.insert(key, value.clone()).await;
+        // Store in cache, then wake the waiters
+        self.insert(key.clone(), value.clone()).await;
+        sender.send_replace(Some(value.clone()));
 
         Ok(value)
     }
 
+    /// Number of requests answered by another caller's in-flight fetch
+    pub fn coalesced(&self) -> u64 {
+        self.coalesced.load(Ordering::Relaxed)
+    }
+
     /// Invalidate a specific cache entry
     pub async fn invalidate(&self, key: &CacheKey) {
-        let mut cache = self.cache.write().await;
-        let _ = cache.cache_remove(key);
+        self.cache.remove(key).await;
     }
 
     /// Clear all cached entries
     pub async fn clear(&self) {
-        let mut cache = self.cache.write().await;
-        cache.cache_clear();
+        self.cache.clear().await;
     }
 
     /// Get the number of cached entries
     pub async fn len(&self) -> usize {
-        let cache = self.cache.read().await;
-        cache.cache_size()
+        self.cache.len().await
     }
 
     /// Check if the cache is empty
@@ -119,6 +295,8 @@ impl Clone for StockCache {
     fn clone(&self) -> Self {
         Self {
             cache: Arc::clone(&self.cache),
+            inflight: Arc::clone(&self.inflight),
+            coalesced: Arc::clone(&self.coalesced),
         }
     }
 }
@@ -140,6 +318,47 @@ pub struct CacheManager {
     pub sector: StockCache,
 }
 
+/// A cache tier, each with its own TTL
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CacheCategory {
+    Realtime,
+    Fundamental,
+    News,
+    Earnings,
+    Macro,
+    Sector,
+}
+
+impl CacheCategory {
+    /// Every tier
+    pub const ALL: [CacheCategory; 6] = [
+        CacheCategory::Realtime,
+        CacheCategory::Fundamental,
+        CacheCategory::News,
+        CacheCategory::Earnings,
+        CacheCategory::Macro,
+        CacheCategory::Sector,
+    ];
+
+    /// Name used in shared cache keys and health checks
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CacheCategory::Realtime => "realtime",
+            CacheCategory::Fundamental => "fundamental",
+            CacheCategory::News => "news",
+            CacheCategory::Earnings => "earnings",
+            CacheCategory::Macro => "macro",
+            CacheCategory::Sector => "sector",
+        }
+    }
+}
+
+impl fmt::Display for CacheCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// Configuration for cache TTLs
 #[derive(Debug, Clone)]
 pub struct CacheTtlConfig {
@@ -154,12 +373,38 @@ pub struct CacheTtlConfig {
 impl Default for CacheTtlConfig {
     fn default() -> Self {
         Self {
-            realtime: Duration::from_secs(60),     // 1 minute
+            realtime: Duration::from_secs(60),      // 1 minute
             fundamental: Duration::from_secs(3600), // 1 hour
-            news: Duration::from_secs(300),        // 5 minutes
-            earnings: Duration::from_secs(86400),  // 24 hours
-            macro_data: Duration::from_secs(3600), // 1 hour
-            sector: Duration::from_secs(1800),     // 30 minutes
+            news: Duration::from_secs(300),         // 5 minutes
+            earnings: Duration::from_secs(86400),   // 24 hours
+            macro_data: Duration::from_secs(3600),  // 1 hour
+            sector: Duration::from_secs(1800),      // 30 minutes
+        }
+    }
+}
+
+impl CacheTtlConfig {
+    /// TTLs configured in `config`
+    pub fn from_stock_config(config: &StockConfig) -> Self {
+        Self {
+            realtime: config.cache_ttl_realtime,
+            fundamental: config.cache_ttl_fundamental,
+            news: config.cache_ttl_news,
+            earnings: config.cache_ttl_earnings,
+            macro_data: config.cache_ttl_macro,
+            sector: config.cache_ttl_sector,
+        }
+    }
+
+    /// TTL of `category`
+    pub fn ttl(&self, category: CacheCategory) -> Duration {
+        match category {
+            CacheCategory::Realtime => self.realtime,
+            CacheCategory::Fundamental => self.fundamental,
+            CacheCategory::News => self.news,
+            CacheCategory::Earnings => self.earnings,
+            CacheCategory::Macro => self.macro_data,
+            CacheCategory::Sector => self.sector,
         }
     }
 }
@@ -175,16 +420,83 @@ impl CacheManager {
         })
     }
 
-    /// Create a cache manager with full configuration
+    /// Create an in-memory cache manager with full configuration
     pub fn with_config(config: CacheTtlConfig) -> Self {
+        Self::with_backends(&config, |_, ttl| Arc::new(MemoryBackend::new(ttl)))
+    }
+
+    /// Create a cache manager whose tiers are stored in the backends built by
+    /// `backend`, called with each tier and its TTL from `config`
+    pub fn with_backends(
+        config: &CacheTtlConfig,
+        mut backend: impl FnMut(CacheCategory, Duration) -> Arc<dyn CacheBackend>,
+    ) -> Self {
+        let mut tier = |category| StockCache::with_backend(backend(category, config.ttl(category)));
         Self {
-            realtime: StockCache::new(config.realtime),
-            fundamental: StockCache::new(config.fundamental),
-            news: StockCache::new(config.news),
-            earnings: StockCache::new(config.earnings),
-            macro_data: StockCache::new(config.macro_data),
-            sector: StockCache::new(config.sector),
+            realtime: tier(CacheCategory::Realtime),
+            fundamental: tier(CacheCategory::Fundamental),
+            news: tier(CacheCategory::News),
+            earnings: tier(CacheCategory::Earnings),
+            macro_data: tier(CacheCategory::Macro),
+            sector: tier(CacheCategory::Sector),
+        }
+    }
+
+    /// Create a cache manager in Redis when [`REDIS_URL_ENV`] is set (and
+    /// the `redis` feature is enabled), in memory otherwise
+    ///
+    /// An invalid URL, or a URL set without the feature, is logged and falls
+    /// back to memory.
+    pub fn from_env(config: CacheTtlConfig) -> Self {
+        let Some(url) = std::env::var(REDIS_URL_ENV)
+            .ok()
+            .filter(|url| !url.trim().is_empty())
+        else {
+            return Self::with_config(config);
+        };
+
+        #[cfg(feature = "redis")]
+        match crate::redis_cache::RedisStore::shared(url.trim()) {
+            Ok(store) => {
+                tracing::debug!("Sharing the cache through Redis");
+                return Self::with_backends(&config, |category, ttl| {
+                    Arc::new(store.backend(category, ttl))
+                });
+            }
+            Err(e) => tracing::warn!("Invalid {REDIS_URL_ENV}, caching in memory: {e}"),
+        }
+        #[cfg(not(feature = "redis"))]
+        {
+            let _ = url;
+            tracing::warn!(
+                "{REDIS_URL_ENV} is set but agent-stock was built without the redis feature; \
+                 caching in memory"
+            );
         }
+        Self::with_config(config)
+    }
+
+    /// Create a cache manager with the TTLs of `config`, shared through Redis
+    /// when configured (see [`Self::from_env`])
+    pub fn from_config(config: &StockConfig) -> Self {
+        Self::from_env(CacheTtlConfig::from_stock_config(config))
+    }
+
+    /// The tier for `category`
+    pub fn tier(&self, category: CacheCategory) -> &StockCache {
+        match category {
+            CacheCategory::Realtime => &self.realtime,
+            CacheCategory::Fundamental => &self.fundamental,
+            CacheCategory::News => &self.news,
+            CacheCategory::Earnings => &self.earnings,
+            CacheCategory::Macro => &self.macro_data,
+            CacheCategory::Sector => &self.sector,
+        }
+    }
+
+    /// Name of the backend storing the tiers, e.g. "memory" or "redis"
+    pub fn backend_name(&self) -> &'static str {
+        self.realtime.backend_name()
     }
 
     /// Create a default cache manager
@@ -211,6 +523,10 @@ impl CacheManager {
             earnings_entries: self.earnings.len().await,
             macro_entries: self.macro_data.len().await,
             sector_entries: self.sector.len().await,
+            coalesced_requests: CacheCategory::ALL
+                .iter()
+                .map(|&category| self.tier(category).coalesced())
+                .sum(),
         }
     }
 }
@@ -224,6 +540,8 @@ pub struct CacheStats {
     pub earnings_entries: usize,
     pub macro_entries: usize,
     pub sector_entries: usize,
+    /// Requests that awaited another caller's fetch instead of their own
+    pub coalesced_requests: u64,
 }
 
 impl CacheStats {
@@ -246,7 +564,7 @@ static SHARED_CACHE: OnceLock<CacheManager> = OnceLock::new();
 /// This function returns a reference to a global cache manager that is shared
 /// across all agents. This ensures data consistency and reduces memory usage.
 pub fn shared_cache() -> &'static CacheManager {
-    SHARED_CACHE.get_or_init(CacheManager::default_config)
+    SHARED_CACHE.get_or_init(|| CacheManager::from_env(CacheTtlConfig::default()))
 }
 
 /// Initialize the global cache with custom configuration
@@ -255,7 +573,7 @@ pub fn shared_cache() -> &'static CacheManager {
 /// Returns an error if the cache has already been initialized.
 pub fn init_shared_cache(config: CacheTtlConfig) -> Result<(), &'static str> {
     SHARED_CACHE
-        .set(CacheManager::with_config(config))
+        .set(CacheManager::from_env(config))
         .map_err(|_| "Shared cache already initialized")
 }
 
@@ -312,6 +630,51 @@ mod tests {
         assert_eq!(call_count, 1); // Should not have incremented
     }
 
+    #[tokio::test]
+    async fn test_concurrent_misses_share_one_fetch() {
+        let cache = StockCache::new(Duration::from_secs(60));
+        let key = CacheKey::new("AAPL", "quote", serde_json::json!({}));
+        let calls = AtomicU64::new(0);
+
+        let fetch = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok::<_, String>(serde_json::json!({"price": 150.0}))
+        };
+        let results =
+            futures::future::join_all((0..5).map(|_| cache.get_or_fetch(key.clone(), fetch))).await;
+
+        assert!(
+            results
+                .iter()
+                .all(|r| r.as_ref().unwrap()["price"] == 150.0)
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(cache.coalesced(), 4);
+        assert!(cache.inflight.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_fetch_lets_waiters_fetch() {
+        let cache = StockCache::new(Duration::from_secs(60));
+        let key = CacheKey::new("AAPL", "quote", serde_json::json!({}));
+
+        let failing = cache.get_or_fetch(key.clone(), || async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Err::<serde_json::Value, _>("rate limited".to_string())
+        });
+        let waiting = cache.get_or_fetch(key.clone(), || async {
+            Ok::<_, String>(serde_json::json!({"price": 151.0}))
+        });
+        let (failed, fetched) = tokio::join!(failing, waiting);
+
+        assert_eq!(failed.unwrap_err(), "rate limited");
+        assert_eq!(fetched.unwrap()["price"], 151.0);
+        assert_eq!(cache.coalesced(), 0);
+        assert_eq!(cache.get(&key).await.unwrap()["price"], 151.0);
+        assert!(cache.inflight.lock().unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn test_cache_invalidation() {
         let cache = StockCache::new(Duration::from_secs(60));
@@ -341,6 +704,57 @@ mod tests {
         assert!(cache.is_empty().await);
     }
 
+    /// Memory backend that records the TTL it was built with
+    struct TtlBackend(Duration, MemoryBackend);
+
+    #[async_trait]
+    impl CacheBackend for TtlBackend {
+        fn name(&self) -> &'static str {
+            "test"
+        }
+        async fn get(&self, key: &CacheKey) -> Option<serde_json::Value> {
+            self.1.get(key).await
+        }
+        async fn set(&self, key: CacheKey, value: serde_json::Value) {
+            let value = serde_json::json!({ "ttl": self.0.as_secs(), "value": value });
+            self.1.set(key, value).await;
+        }
+        async fn remove(&self, key: &CacheKey) {
+            self.1.remove(key).await;
+        }
+        async fn clear(&self) {
+            self.1.clear().await;
+        }
+        async fn len(&self) -> usize {
+            self.1.len().await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_backend_gets_category_ttls() {
+        let config = CacheTtlConfig {
+            news: Duration::from_secs(42),
+            ..CacheTtlConfig::default()
+        };
+        let manager = CacheManager::with_backends(&config, |_, ttl| {
+            Arc::new(TtlBackend(ttl, MemoryBackend::new(ttl)))
+        });
+        assert_eq!(manager.backend_name(), "test");
+
+        let key = CacheKey::new("AAPL", "news", serde_json::json!({}));
+        for category in CacheCategory::ALL {
+            manager
+                .tier(category)
+                .insert(key.clone(), serde_json::json!(1))
+                .await;
+            let stored = manager.tier(category).get(&key).await.unwrap();
+            assert_eq!(stored["ttl"], config.ttl(category).as_secs(), "{category}");
+        }
+        let stats = manager.stats().await;
+        assert_eq!(stats.total(), 6);
+        assert_eq!(stats.coalesced_requests, 0);
+    }
+
     #[tokio::test]
     async fn test_cache_manager() {
         let manager = CacheManager::default_config();
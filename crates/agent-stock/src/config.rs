@@ -1,11 +1,39 @@
 //! Configuration for stock analysis operations
 
+use crate::agents::translator::TRANSLATOR_AGENT;
+use crate::api::alpha_vantage::{FREE_RATE_LIMIT, PREMIUM_RATE_LIMIT};
+use crate::depth::AnalysisDepth;
 use crate::error::{Result, StockError};
+use crate::language::ResponseLanguage;
+use crate::style::ResponseStyle;
 use agent_prompt::{Language, PromptRegistry};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 
+/// Environment variable holding the Alpha Vantage API key
+pub const ALPHA_VANTAGE_API_KEY_ENV: &str = "ALPHA_VANTAGE_API_KEY";
+
+/// Environment variable overriding the Alpha Vantage requests per minute
+pub const ALPHA_VANTAGE_RATE_LIMIT_ENV: &str = "ALPHA_VANTAGE_RATE_LIMIT";
+
+/// Environment variable marking the Alpha Vantage key as premium (`true`/`false`)
+pub const ALPHA_VANTAGE_PREMIUM_ENV: &str = "ALPHA_VANTAGE_PREMIUM";
+
+/// Environment variable holding the Finnhub API key
+pub const FINNHUB_API_KEY_ENV: &str = "FINNHUB_API_KEY";
+
+/// Environment variable holding the FRED API key
+pub const FRED_API_KEY_ENV: &str = "FRED_API_KEY";
+
+/// Environment variable holding the Polygon.io API key, used for streaming
+/// quotes when no Finnhub key is set
+pub const POLYGON_API_KEY_ENV: &str = "POLYGON_API_KEY";
+
+/// Environment variable holding the DeepL API key, for translating sources
+pub const DEEPL_API_KEY_ENV: &str = "DEEPL_API_KEY";
+
 /// Data provider for stock information
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub enum DataProvider {
@@ -28,6 +56,89 @@ pub enum NewsProvider {
     AlphaVantage,
 }
 
+impl NewsProvider {
+    /// Environment variable of the API key this provider needs, if any
+    pub fn api_key_env(&self) -> Option<&'static str> {
+        match self {
+            Self::Mock => None,
+            Self::Finnhub => Some(FINNHUB_API_KEY_ENV),
+            Self::AlphaVantage => Some(ALPHA_VANTAGE_API_KEY_ENV),
+        }
+    }
+}
+
+/// How English news headlines and summaries are translated for replies in
+/// another language (see [`crate::source_translation`])
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum SourceTranslation {
+    /// Sources are quoted in English
+    #[default]
+    Off,
+    /// The `translator` agent's model
+    Llm,
+    /// The DeepL API (requires API key)
+    DeepL,
+}
+
+impl SourceTranslation {
+    /// Parse a setting such as "llm" or "deepl"
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "0" | "false" | "off" | "no" | "none" => Some(Self::Off),
+            "1" | "true" | "on" | "yes" | "llm" => Some(Self::Llm),
+            "deepl" => Some(Self::DeepL),
+            _ => None,
+        }
+    }
+}
+
+/// Provider of ESG (environmental, social, governance) scores
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum EsgProvider {
+    /// Yahoo Finance Sustainalytics risk scores (default, no API key required)
+    #[default]
+    Yahoo,
+    /// Finnhub.io ESG scores (premium endpoint)
+    Finnhub,
+}
+
+impl EsgProvider {
+    /// Environment variable of the API key this provider needs, if any
+    pub fn api_key_env(&self) -> Option<&'static str> {
+        match self {
+            Self::Yahoo => None,
+            Self::Finnhub => Some(FINNHUB_API_KEY_ENV),
+        }
+    }
+}
+
+/// Names of the specialist agents that accept per-agent overrides
+pub const SPECIALIST_AGENTS: &[&str] = &[
+    "data-fetcher",
+    "technical-analyzer",
+    "fundamental-analyzer",
+    "news-analyzer",
+    "earnings-analyzer",
+    "macro-analyzer",
+    "esg-analyzer",
+    "portfolio-analyzer",
+];
+
+/// Per-agent override of the global LLM settings
+///
+/// Unset fields fall back to the values in [`StockConfig`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AgentModelOverride {
+    /// LLM model for this agent
+    pub model: Option<String>,
+
+    /// Temperature for this agent
+    pub temperature: Option<f32>,
+
+    /// Maximum tokens per response for this agent
+    pub max_tokens: Option<usize>,
+}
+
 /// Configuration for stock analysis operations
 #[derive(Debug, Clone)]
 pub struct StockConfig {
@@ -61,21 +172,34 @@ pub struct StockConfig {
     /// Request timeout duration
     pub request_timeout: Duration,
 
+    /// How long results are kept for replay under their idempotency key
+    pub idempotency_retention: Duration,
+
     /// Alpha Vantage API key (optional)
     pub alpha_vantage_api_key: Option<String>,
 
     /// Alpha Vantage API rate limit (requests per minute)
     pub alpha_vantage_rate_limit: u32,
 
+    /// Whether the Alpha Vantage key is on a premium plan, unlocking realtime
+    /// quotes and extended intraday history
+    pub alpha_vantage_premium: bool,
+
     /// News data provider
     pub news_provider: NewsProvider,
 
+    /// ESG score provider
+    pub esg_provider: EsgProvider,
+
     /// Finnhub.io API key (optional)
     pub finnhub_api_key: Option<String>,
 
     /// FRED API key (for macroeconomic data)
     pub fred_api_key: Option<String>,
 
+    /// DeepL API key (for translating sources)
+    pub deepl_api_key: Option<String>,
+
     /// SEC EDGAR User-Agent (required for SEC API)
     pub sec_user_agent: String,
 
@@ -91,9 +215,29 @@ pub struct StockConfig {
     /// Maximum tokens per response
     pub max_tokens: usize,
 
+    /// Per-agent LLM overrides, keyed by agent name (e.g. "technical-analyzer")
+    pub agent_overrides: HashMap<String, AgentModelOverride>,
+
     /// Language for agent responses
     pub response_language: Language,
 
+    /// Whether replies add a translation into the other language by default
+    /// (see [`crate::language`])
+    pub bilingual: bool,
+
+    /// How news headlines and summaries are translated into the response
+    /// language
+    pub source_translation: SourceTranslation,
+
+    /// Default response style, used when a request does not specify one
+    pub response_style: ResponseStyle,
+
+    /// Whether analyses explain their reasoning and formulas by default
+    pub teaching_mode: bool,
+
+    /// Default analysis depth, used when a request does not specify one
+    pub analysis_depth: AnalysisDepth,
+
     /// Prompt registry for template management
     pub prompt_registry: Arc<PromptRegistry>,
 }
@@ -114,17 +258,27 @@ impl Default for StockConfig {
             max_retries: 3,
             retry_backoff_base: Duration::from_secs(1),
             request_timeout: Duration::from_secs(30),
+            idempotency_retention: Duration::from_secs(86400), // 24 hours
             alpha_vantage_api_key: None,
-            alpha_vantage_rate_limit: 5, // Free tier: 5 requests/minute
+            alpha_vantage_rate_limit: FREE_RATE_LIMIT, // Free tier: 5 requests/minute
+            alpha_vantage_premium: false,
             news_provider: NewsProvider::Mock,
+            esg_provider: EsgProvider::Yahoo,
             finnhub_api_key: None,
             fred_api_key: None,
+            deepl_api_key: None,
             sec_user_agent: "agent-stock".to_string(),
             sec_contact_email: "agent-stock@example.com".to_string(),
             model: "claude-opus-4-5-20251101".to_string(),
             temperature: 0.5,
             max_tokens: 4096,
+            agent_overrides: HashMap::new(),
             response_language: Language::Chinese,
+            bilingual: false,
+            source_translation: SourceTranslation::Off,
+            response_style: ResponseStyle::default(),
+            teaching_mode: false,
+            analysis_depth: AnalysisDepth::default(),
             prompt_registry: Arc::new(registry),
         }
     }
@@ -136,14 +290,34 @@ impl StockConfig {
         StockConfigBuilder::default()
     }
 
-    /// Load Alpha Vantage API key from environment
+    /// Load Alpha Vantage API key, rate limit and plan from environment
     pub fn with_env_api_key(mut self) -> Result<Self> {
-        if let Ok(key) = std::env::var("ALPHA_VANTAGE_API_KEY") {
+        if let Ok(key) = std::env::var(ALPHA_VANTAGE_API_KEY_ENV) {
             self.alpha_vantage_api_key = Some(key);
         }
+        let (rate_limit, premium) = alpha_vantage_plan_from_env();
+        if let Some(premium) = premium {
+            self.alpha_vantage_premium = premium;
+        }
+        self.alpha_vantage_rate_limit = rate_limit.unwrap_or(if self.alpha_vantage_premium {
+            PREMIUM_RATE_LIMIT.max(self.alpha_vantage_rate_limit)
+        } else {
+            self.alpha_vantage_rate_limit
+        });
         Ok(self)
     }
 
+    /// Whether the API key named by its environment variable (e.g.
+    /// [`FRED_API_KEY_ENV`]) is configured
+    pub fn has_api_key(&self, env_var: &str) -> bool {
+        match env_var {
+            ALPHA_VANTAGE_API_KEY_ENV => self.alpha_vantage_api_key.is_some(),
+            FINNHUB_API_KEY_ENV => self.finnhub_api_key.is_some(),
+            FRED_API_KEY_ENV => self.fred_api_key.is_some(),
+            _ => false,
+        }
+    }
+
     /// Validate the configuration
     pub fn validate(&self) -> Result<()> {
         if self.default_provider == DataProvider::AlphaVantage
@@ -167,15 +341,66 @@ impl StockConfig {
             ));
         }
 
+        if self.esg_provider == EsgProvider::Finnhub && self.finnhub_api_key.is_none() {
+            return Err(StockError::ConfigError(
+                "Finnhub API key required when using Finnhub ESG provider. Set FINNHUB_API_KEY environment variable.".to_string(),
+            ));
+        }
+
+        if self.source_translation == SourceTranslation::DeepL && self.deepl_api_key.is_none() {
+            return Err(StockError::ConfigError(
+                "DeepL API key required when translating sources with DeepL. Set DEEPL_API_KEY environment variable.".to_string(),
+            ));
+        }
+
         if self.max_retries == 0 {
             return Err(StockError::ConfigError(
                 "max_retries must be greater than 0".to_string(),
             ));
         }
 
+        for (agent, overrides) in &self.agent_overrides {
+            if let Some(temperature) = overrides.temperature {
+                if !(0.0..=2.0).contains(&temperature) {
+                    return Err(StockError::ConfigError(format!(
+                        "Temperature override for '{agent}' must be between 0.0 and 2.0, got {temperature}"
+                    )));
+                }
+            }
+            if overrides.max_tokens == Some(0) {
+                return Err(StockError::ConfigError(format!(
+                    "max_tokens override for '{agent}' must be greater than 0"
+                )));
+            }
+        }
+
         Ok(())
     }
 
+    /// LLM model for the given agent, honoring per-agent overrides
+    pub fn model_for(&self, agent: &str) -> String {
+        self.agent_overrides
+            .get(agent)
+            .and_then(|o| o.model.clone())
+            .unwrap_or_else(|| self.model.clone())
+    }
+
+    /// Temperature for the given agent, honoring per-agent overrides
+    pub fn temperature_for(&self, agent: &str) -> f32 {
+        self.agent_overrides
+            .get(agent)
+            .and_then(|o| o.temperature)
+            .unwrap_or(self.temperature)
+    }
+
+    /// Maximum tokens for the given agent, honoring per-agent overrides
+    pub fn max_tokens_for(&self, agent: &str) -> usize {
+        self.agent_overrides
+            .get(agent)
+            .and_then(|o| o.max_tokens)
+            .unwrap_or(self.max_tokens)
+    }
+
     /// Get retry backoff duration for attempt number
     pub fn retry_backoff(&self, attempt: u32) -> Duration {
         self.retry_backoff_base * 2_u32.pow(attempt)
@@ -195,17 +420,27 @@ pub struct StockConfigBuilder {
     max_retries: Option<u32>,
     retry_backoff_base: Option<Duration>,
     request_timeout: Option<Duration>,
+    idempotency_retention: Option<Duration>,
     alpha_vantage_api_key: Option<String>,
     alpha_vantage_rate_limit: Option<u32>,
+    alpha_vantage_premium: Option<bool>,
     news_provider: Option<NewsProvider>,
+    esg_provider: Option<EsgProvider>,
     finnhub_api_key: Option<String>,
     fred_api_key: Option<String>,
+    deepl_api_key: Option<String>,
     sec_user_agent: Option<String>,
     sec_contact_email: Option<String>,
     model: Option<String>,
     temperature: Option<f32>,
     max_tokens: Option<usize>,
+    agent_overrides: HashMap<String, AgentModelOverride>,
     response_language: Option<Language>,
+    bilingual: Option<bool>,
+    source_translation: Option<SourceTranslation>,
+    response_style: Option<ResponseStyle>,
+    teaching_mode: Option<bool>,
+    analysis_depth: Option<AnalysisDepth>,
 }
 
 impl StockConfigBuilder {
@@ -251,17 +486,26 @@ impl StockConfigBuilder {
         self
     }
 
+    /// Set how long results are kept under their idempotency key
+    pub fn idempotency_retention(mut self, duration: Duration) -> Self {
+        self.idempotency_retention = Some(duration);
+        self
+    }
+
     /// Set Alpha Vantage API key
     pub fn alpha_vantage_api_key(mut self, key: impl Into<String>) -> Self {
         self.alpha_vantage_api_key = Some(key.into());
         self
     }
 
-    /// Load Alpha Vantage API key from environment
+    /// Load Alpha Vantage API key, rate limit and plan from environment
     pub fn with_env_api_key(mut self) -> Self {
-        if let Ok(key) = std::env::var("ALPHA_VANTAGE_API_KEY") {
+        if let Ok(key) = std::env::var(ALPHA_VANTAGE_API_KEY_ENV) {
             self.alpha_vantage_api_key = Some(key);
         }
+        let (rate_limit, premium) = alpha_vantage_plan_from_env();
+        self.alpha_vantage_rate_limit = rate_limit.or(self.alpha_vantage_rate_limit);
+        self.alpha_vantage_premium = premium.or(self.alpha_vantage_premium);
         self
     }
 
@@ -271,12 +515,25 @@ impl StockConfigBuilder {
         self
     }
 
+    /// Mark the Alpha Vantage key as premium; without an explicit rate limit
+    /// this also raises it to the entry-level premium plan's
+    pub fn alpha_vantage_premium(mut self, premium: bool) -> Self {
+        self.alpha_vantage_premium = Some(premium);
+        self
+    }
+
     /// Set news provider
     pub fn news_provider(mut self, provider: NewsProvider) -> Self {
         self.news_provider = Some(provider);
         self
     }
 
+    /// Set ESG score provider
+    pub fn esg_provider(mut self, provider: EsgProvider) -> Self {
+        self.esg_provider = Some(provider);
+        self
+    }
+
     /// Set Finnhub API key
     pub fn finnhub_api_key(mut self, key: impl Into<String>) -> Self {
         self.finnhub_api_key = Some(key.into());
@@ -285,7 +542,7 @@ impl StockConfigBuilder {
 
     /// Load Finnhub API key from environment
     pub fn with_env_finnhub_key(mut self) -> Self {
-        if let Ok(key) = std::env::var("FINNHUB_API_KEY") {
+        if let Ok(key) = std::env::var(FINNHUB_API_KEY_ENV) {
             self.finnhub_api_key = Some(key);
         }
         self
@@ -299,12 +556,26 @@ impl StockConfigBuilder {
 
     /// Load FRED API key from environment
     pub fn with_env_fred_key(mut self) -> Self {
-        if let Ok(key) = std::env::var("FRED_API_KEY") {
+        if let Ok(key) = std::env::var(FRED_API_KEY_ENV) {
             self.fred_api_key = Some(key);
         }
         self
     }
 
+    /// Set DeepL API key
+    pub fn deepl_api_key(mut self, key: impl Into<String>) -> Self {
+        self.deepl_api_key = Some(key.into());
+        self
+    }
+
+    /// Load DeepL API key from environment
+    pub fn with_env_deepl_key(mut self) -> Self {
+        if let Ok(key) = std::env::var(DEEPL_API_KEY_ENV) {
+            self.deepl_api_key = Some(key);
+        }
+        self
+    }
+
     /// Set SEC User-Agent
     pub fn sec_user_agent(mut self, agent: impl Into<String>) -> Self {
         self.sec_user_agent = Some(agent.into());
@@ -322,6 +593,7 @@ impl StockConfigBuilder {
         self.with_env_api_key()
             .with_env_finnhub_key()
             .with_env_fred_key()
+            .with_env_deepl_key()
     }
 
     /// Load news provider from environment (NEWS_PROVIDER=Mock|Finnhub|AlphaVantage)
@@ -337,6 +609,18 @@ impl StockConfigBuilder {
         self
     }
 
+    /// Load ESG provider from environment (ESG_PROVIDER=Yahoo|Finnhub)
+    pub fn with_env_esg_provider(mut self) -> Self {
+        if let Ok(provider) = std::env::var("ESG_PROVIDER") {
+            self.esg_provider = match provider.to_lowercase().as_str() {
+                "finnhub" => Some(EsgProvider::Finnhub),
+                "yahoo" => Some(EsgProvider::Yahoo),
+                _ => None,
+            };
+        }
+        self
+    }
+
     /// Set the LLM model
     pub fn model(mut self, model: impl Into<String>) -> Self {
         self.model = Some(model.into());
@@ -355,13 +639,92 @@ impl StockConfigBuilder {
         self
     }
 
+    /// Set the LLM overrides for a single agent
+    pub fn agent_override(
+        mut self,
+        agent: impl Into<String>,
+        overrides: AgentModelOverride,
+    ) -> Self {
+        self.agent_overrides.insert(agent.into(), overrides);
+        self
+    }
+
+    /// Set the LLM model for a single agent
+    pub fn agent_model(mut self, agent: impl Into<String>, model: impl Into<String>) -> Self {
+        self.agent_overrides.entry(agent.into()).or_default().model = Some(model.into());
+        self
+    }
+
+    /// Set the temperature for a single agent
+    pub fn agent_temperature(mut self, agent: impl Into<String>, temp: f32) -> Self {
+        self.agent_overrides
+            .entry(agent.into())
+            .or_default()
+            .temperature = Some(temp);
+        self
+    }
+
+    /// Set the maximum tokens for a single agent
+    pub fn agent_max_tokens(mut self, agent: impl Into<String>, tokens: usize) -> Self {
+        self.agent_overrides
+            .entry(agent.into())
+            .or_default()
+            .max_tokens = Some(tokens);
+        self
+    }
+
     /// Set the response language
-    pub fn response_language(mut self, language: Language) -> Self {
-        self.response_language = Some(language);
+    ///
+    /// [`ResponseLanguage::Bilingual`] keeps the prompt language and adds a
+    /// translation, like [`StockConfigBuilder::bilingual`].
+    pub fn response_language(mut self, language: ResponseLanguage) -> Self {
+        match language {
+            ResponseLanguage::Chinese => self.response_language = Some(Language::Chinese),
+            ResponseLanguage::English => self.response_language = Some(Language::English),
+            ResponseLanguage::Bilingual => {}
+        }
+        self.bilingual = Some(language == ResponseLanguage::Bilingual);
+        self
+    }
+
+    /// Reply in the response language and its translation by default
+    pub fn bilingual(mut self, enabled: bool) -> Self {
+        self.bilingual = Some(enabled);
+        self
+    }
+
+    /// Translate news headlines and summaries into the response language
+    pub fn source_translation(mut self, translation: SourceTranslation) -> Self {
+        self.source_translation = Some(translation);
+        self
+    }
+
+    /// Set the default response style
+    pub fn response_style(mut self, style: ResponseStyle) -> Self {
+        self.response_style = Some(style);
+        self
+    }
+
+    /// Enable the educational walkthrough mode by default
+    pub fn teaching_mode(mut self, enabled: bool) -> Self {
+        self.teaching_mode = Some(enabled);
+        self
+    }
+
+    /// Set the default analysis depth
+    pub fn analysis_depth(mut self, depth: AnalysisDepth) -> Self {
+        self.analysis_depth = Some(depth);
         self
     }
 
     /// Load model configuration from environment variables
+    ///
+    /// Per-agent overrides are read from `STOCK_MODEL_<AGENT>`,
+    /// `STOCK_TEMPERATURE_<AGENT>` and `STOCK_MAX_TOKENS_<AGENT>`, where
+    /// `<AGENT>` is the agent name in upper snake case (e.g. `TECHNICAL_ANALYZER`,
+    /// or `TRANSLATOR` for the translator of bilingual replies).
+    /// `STOCK_RESPONSE_LANGUAGE=bilingual` replies in Chinese and English;
+    /// `STOCK_TRANSLATE_SOURCES=llm` or `deepl` translates news for it.
     pub fn from_env_model(mut self) -> Self {
         if let Ok(model) = std::env::var("STOCK_MODEL") {
             self.model = Some(model);
@@ -377,12 +740,52 @@ impl StockConfigBuilder {
             }
         }
         if let Ok(lang) = std::env::var("STOCK_RESPONSE_LANGUAGE") {
-            self.response_language = match lang.to_lowercase().as_str() {
-                "chinese" | "zh" | "中文" => Some(Language::Chinese),
-                "english" | "en" => Some(Language::English),
+            // Bilingual replies are written in Chinese and translated
+            self.response_language = None;
+            self.bilingual = None;
+            if let Some(language) = ResponseLanguage::parse(&lang) {
+                self = self.response_language(language);
+            }
+        }
+        if let Ok(translation) = std::env::var("STOCK_TRANSLATE_SOURCES") {
+            self.source_translation = SourceTranslation::parse(&translation);
+        }
+        if let Ok(style) = std::env::var("STOCK_RESPONSE_STYLE") {
+            self.response_style = ResponseStyle::parse(&style);
+        }
+        if let Ok(teaching) = std::env::var("STOCK_TEACHING_MODE") {
+            self.teaching_mode = match teaching.to_lowercase().as_str() {
+                "1" | "true" | "on" | "yes" => Some(true),
+                "0" | "false" | "off" | "no" => Some(false),
                 _ => None,
             };
         }
+        if let Ok(depth) = std::env::var("STOCK_ANALYSIS_DEPTH") {
+            self.analysis_depth = AnalysisDepth::parse(&depth);
+        }
+        if let Ok(secs) = std::env::var("STOCK_IDEMPOTENCY_RETENTION_SECS") {
+            if let Ok(secs) = secs.parse() {
+                self.idempotency_retention = Some(Duration::from_secs(secs));
+            }
+        }
+        for agent in SPECIALIST_AGENTS.iter().chain([&TRANSLATOR_AGENT]) {
+            let suffix = agent.to_uppercase().replace('-', "_");
+            if let Ok(model) = std::env::var(format!("STOCK_MODEL_{suffix}")) {
+                self = self.agent_model(*agent, model);
+            }
+            if let Some(temp) = std::env::var(format!("STOCK_TEMPERATURE_{suffix}"))
+                .ok()
+                .and_then(|t| t.parse().ok())
+            {
+                self = self.agent_temperature(*agent, temp);
+            }
+            if let Some(tokens) = std::env::var(format!("STOCK_MAX_TOKENS_{suffix}"))
+                .ok()
+                .and_then(|t| t.parse().ok())
+            {
+                self = self.agent_max_tokens(*agent, tokens);
+            }
+        }
         self
     }
 
@@ -396,6 +799,9 @@ impl StockConfigBuilder {
             .map_err(|e| StockError::ConfigError(format!("Failed to register prompts: {e}")))?;
 
         let defaults = StockConfig::default();
+        let alpha_vantage_premium = self
+            .alpha_vantage_premium
+            .unwrap_or(defaults.alpha_vantage_premium);
 
         let config = StockConfig {
             default_provider: self.default_provider.unwrap_or(defaults.default_provider),
@@ -406,7 +812,9 @@ impl StockConfigBuilder {
                 .cache_ttl_fundamental
                 .unwrap_or(defaults.cache_ttl_fundamental),
             cache_ttl_news: self.cache_ttl_news.unwrap_or(defaults.cache_ttl_news),
-            cache_ttl_earnings: self.cache_ttl_earnings.unwrap_or(defaults.cache_ttl_earnings),
+            cache_ttl_earnings: self
+                .cache_ttl_earnings
+                .unwrap_or(defaults.cache_ttl_earnings),
             cache_ttl_macro: self.cache_ttl_macro.unwrap_or(defaults.cache_ttl_macro),
             cache_ttl_sector: self.cache_ttl_sector.unwrap_or(defaults.cache_ttl_sector),
             max_retries: self.max_retries.unwrap_or(defaults.max_retries),
@@ -414,19 +822,37 @@ impl StockConfigBuilder {
                 .retry_backoff_base
                 .unwrap_or(defaults.retry_backoff_base),
             request_timeout: self.request_timeout.unwrap_or(defaults.request_timeout),
+            idempotency_retention: self
+                .idempotency_retention
+                .unwrap_or(defaults.idempotency_retention),
             alpha_vantage_api_key: self.alpha_vantage_api_key,
-            alpha_vantage_rate_limit: self
-                .alpha_vantage_rate_limit
-                .unwrap_or(defaults.alpha_vantage_rate_limit),
+            alpha_vantage_rate_limit: self.alpha_vantage_rate_limit.unwrap_or(
+                if alpha_vantage_premium {
+                    PREMIUM_RATE_LIMIT
+                } else {
+                    defaults.alpha_vantage_rate_limit
+                },
+            ),
+            alpha_vantage_premium,
             news_provider: self.news_provider.unwrap_or(defaults.news_provider),
+            esg_provider: self.esg_provider.unwrap_or(defaults.esg_provider),
             finnhub_api_key: self.finnhub_api_key,
             fred_api_key: self.fred_api_key,
+            deepl_api_key: self.deepl_api_key,
             sec_user_agent: self.sec_user_agent.unwrap_or(defaults.sec_user_agent),
             sec_contact_email: self.sec_contact_email.unwrap_or(defaults.sec_contact_email),
             model: self.model.unwrap_or(defaults.model),
             temperature: self.temperature.unwrap_or(defaults.temperature),
             max_tokens: self.max_tokens.unwrap_or(defaults.max_tokens),
+            agent_overrides: self.agent_overrides,
             response_language,
+            bilingual: self.bilingual.unwrap_or(defaults.bilingual),
+            source_translation: self
+                .source_translation
+                .unwrap_or(defaults.source_translation),
+            response_style: self.response_style.unwrap_or(defaults.response_style),
+            teaching_mode: self.teaching_mode.unwrap_or(defaults.teaching_mode),
+            analysis_depth: self.analysis_depth.unwrap_or(defaults.analysis_depth),
             prompt_registry: Arc::new(registry),
         };
 
@@ -435,6 +861,23 @@ impl StockConfigBuilder {
     }
 }
 
+/// Read [`ALPHA_VANTAGE_RATE_LIMIT_ENV`] and [`ALPHA_VANTAGE_PREMIUM_ENV`],
+/// ignoring values that don't parse
+fn alpha_vantage_plan_from_env() -> (Option<u32>, Option<bool>) {
+    let rate_limit = std::env::var(ALPHA_VANTAGE_RATE_LIMIT_ENV)
+        .ok()
+        .and_then(|limit| limit.trim().parse().ok())
+        .filter(|&limit| limit > 0);
+    let premium = std::env::var(ALPHA_VANTAGE_PREMIUM_ENV)
+        .ok()
+        .and_then(|premium| match premium.trim().to_lowercase().as_str() {
+            "1" | "true" | "on" | "yes" => Some(true),
+            "0" | "false" | "off" | "no" => Some(false),
+            _ => None,
+        });
+    (rate_limit, premium)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -460,6 +903,69 @@ mod tests {
         assert_eq!(config.request_timeout, Duration::from_secs(60));
     }
 
+    #[test]
+    fn test_response_language() {
+        let config = StockConfig::builder()
+            .response_language(ResponseLanguage::English)
+            .build()
+            .unwrap();
+        assert_eq!(config.response_language, Language::English);
+        assert!(!config.bilingual);
+
+        let config = StockConfig::builder()
+            .response_language(ResponseLanguage::Bilingual)
+            .build()
+            .unwrap();
+        assert_eq!(config.response_language, Language::Chinese);
+        assert!(config.bilingual);
+        assert_eq!(
+            ResponseLanguage::for_config(&config),
+            ResponseLanguage::Bilingual
+        );
+    }
+
+    #[test]
+    fn test_source_translation() {
+        assert_eq!(
+            SourceTranslation::parse("DeepL"),
+            Some(SourceTranslation::DeepL)
+        );
+        assert_eq!(SourceTranslation::parse("on"), Some(SourceTranslation::Llm));
+        assert_eq!(SourceTranslation::parse("google"), None);
+
+        let missing_key = StockConfig::builder()
+            .source_translation(SourceTranslation::DeepL)
+            .build();
+        assert!(matches!(missing_key, Err(StockError::ConfigError(_))));
+        let config = StockConfig::builder()
+            .source_translation(SourceTranslation::DeepL)
+            .deepl_api_key("key:fx")
+            .build()
+            .unwrap();
+        assert_eq!(config.source_translation, SourceTranslation::DeepL);
+    }
+
+    #[test]
+    fn test_alpha_vantage_premium_rate_limit() {
+        let free = StockConfig::builder().build().unwrap();
+        assert!(!free.alpha_vantage_premium);
+        assert_eq!(free.alpha_vantage_rate_limit, FREE_RATE_LIMIT);
+
+        let premium = StockConfig::builder()
+            .alpha_vantage_premium(true)
+            .build()
+            .unwrap();
+        assert_eq!(premium.alpha_vantage_rate_limit, PREMIUM_RATE_LIMIT);
+
+        // An explicit limit wins over the plan default
+        let custom = StockConfig::builder()
+            .alpha_vantage_premium(true)
+            .alpha_vantage_rate_limit(300)
+            .build()
+            .unwrap();
+        assert_eq!(custom.alpha_vantage_rate_limit, 300);
+    }
+
     #[test]
     fn test_validation_alpha_vantage_no_key() {
         let config = StockConfig {
@@ -482,6 +988,24 @@ mod tests {
         assert!(config.validate().is_ok());
     }
 
+    #[test]
+    fn test_has_api_key() {
+        let config = StockConfig {
+            fred_api_key: Some("fred".to_string()),
+            news_provider: NewsProvider::Finnhub,
+            ..Default::default()
+        };
+
+        assert!(config.has_api_key(FRED_API_KEY_ENV));
+        assert!(!config.has_api_key(FINNHUB_API_KEY_ENV));
+        assert!(!config.has_api_key("UNKNOWN_API_KEY"));
+        assert_eq!(
+            config.news_provider.api_key_env(),
+            Some(FINNHUB_API_KEY_ENV)
+        );
+        assert_eq!(NewsProvider::Mock.api_key_env(), None);
+    }
+
     #[test]
     fn test_retry_backoff() {
         let config = StockConfig::default();
@@ -489,4 +1013,37 @@ mod tests {
         assert_eq!(config.retry_backoff(1), Duration::from_secs(2));
         assert_eq!(config.retry_backoff(2), Duration::from_secs(4));
     }
+
+    #[test]
+    fn test_agent_overrides() {
+        let config = StockConfig::builder()
+            .model("global-model")
+            .temperature(0.5)
+            .agent_temperature("technical-analyzer", 0.1)
+            .agent_model("news-analyzer", "news-model")
+            .agent_temperature("news-analyzer", 0.9)
+            .build()
+            .unwrap();
+
+        assert_eq!(config.model_for("technical-analyzer"), "global-model");
+        assert!((config.temperature_for("technical-analyzer") - 0.1).abs() < f32::EPSILON);
+        assert_eq!(config.model_for("news-analyzer"), "news-model");
+        assert!((config.temperature_for("news-analyzer") - 0.9).abs() < f32::EPSILON);
+        assert_eq!(config.model_for("macro-analyzer"), "global-model");
+        assert!((config.temperature_for("macro-analyzer") - 0.5).abs() < f32::EPSILON);
+        assert_eq!(config.max_tokens_for("macro-analyzer"), config.max_tokens);
+    }
+
+    #[test]
+    fn test_agent_override_validation() {
+        let result = StockConfig::builder()
+            .agent_temperature("news-analyzer", 3.0)
+            .build();
+        assert!(result.is_err());
+
+        let result = StockConfig::builder()
+            .agent_max_tokens("news-analyzer", 0)
+            .build();
+        assert!(result.is_err());
+    }
 }
@@ -0,0 +1,771 @@
+//! Persistent conversation history
+//!
+//! The terminal bot's [`ConversationManager`] and the platform session
+//! managers keep multi-turn context — the symbol under discussion, recently
+//! mentioned symbols, the last analyses — in memory. A [`ConversationStore`]
+//! saves that context as a [`ConversationRecord`] after every exchange and
+//! hands it back on restart, so "what about its margins?" still resolves
+//! after the bot is redeployed.
+//!
+//! Records are keyed by [`conversation_key`] (platform plus user id), so a
+//! single store can be shared by the Telegram, Feishu and DingTalk session
+//! managers. [`JsonConversationStore`] persists every record to one JSON file,
+//! journaling changes between compactions; [`SqliteConversationStore`] keeps
+//! each conversation in a row of a SQLite database. Both are encrypted when
+//! opened with a cipher, and [`open`] picks one by file extension.
+//! [`InMemoryConversationStore`] is the default when nothing is configured.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::conversation_store::JsonConversationStore;
+//! use agent_stock::interface::{BotPlatform, SessionManager};
+//!
+//! let store = Arc::new(JsonConversationStore::open("conversations.json")?);
+//! let telegram = SessionManager::new(BotPlatform::Telegram).with_conversations(store.clone());
+//! let feishu = SessionManager::new(BotPlatform::Feishu).with_conversations(store);
+//! ```
+
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock};
+
+use crate::bot::{ConversationContext, ConversationManager, ConversationTurn};
+use crate::engine::AnalysisContext;
+use crate::engine::context::{self, AnalysisPreferences};
+use crate::error::{Result, StockError};
+use crate::interface::BotPlatform;
+use crate::storage::{self, StoreCipher};
+
+/// Most turns kept per conversation when a record is saved
+pub const MAX_STORED_TURNS: usize = 50;
+
+/// Key a user's conversation is stored under on `platform`
+pub fn conversation_key(platform: BotPlatform, user_id: &str) -> String {
+    format!("{}:{user_id}", platform.to_string().to_lowercase())
+}
+
+/// Saved multi-turn context for one conversation
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConversationRecord {
+    /// Current symbol, analysis type and recently mentioned symbols
+    pub context: ConversationContext,
+    /// Prior exchanges, oldest first
+    pub turns: Vec<ConversationTurn>,
+    /// Depth, style, reply language and the other session preferences;
+    /// `None` for terminal bot conversations
+    #[serde(default)]
+    pub preferences: Option<AnalysisPreferences>,
+    /// When the record was last saved
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ConversationRecord {
+    /// Record of a terminal bot conversation
+    pub fn from_manager(manager: &ConversationManager) -> Self {
+        Self::new(
+            manager.context().clone(),
+            manager.history().iter().cloned().collect(),
+        )
+    }
+
+    /// Record of a platform session's analysis context
+    pub fn from_analysis_context(context: &AnalysisContext) -> Self {
+        let recent_symbols = context.current_symbols.clone();
+        let turns = context
+            .conversation_turns
+            .iter()
+            .map(|turn| ConversationTurn {
+                user_input: turn.input.clone(),
+                assistant_response: turn.response.clone(),
+                symbols: turn.symbols.clone(),
+                timestamp: turn.timestamp,
+            })
+            .collect();
+        Self {
+            preferences: Some(context.preferences.clone()),
+            ..Self::new(
+                ConversationContext {
+                    current_symbol: context.current_symbol().map(str::to_string),
+                    last_analysis_type: None,
+                    recent_symbols,
+                },
+                turns,
+            )
+        }
+    }
+
+    fn new(context: ConversationContext, mut turns: Vec<ConversationTurn>) -> Self {
+        let excess = turns.len().saturating_sub(MAX_STORED_TURNS);
+        turns.drain(..excess);
+        Self {
+            context,
+            turns,
+            preferences: None,
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Whether both records hold the same context and turns
+    fn same_conversation(&self, other: &Self) -> bool {
+        self.context == other.context
+            && self.turns == other.turns
+            && self.preferences == other.preferences
+    }
+
+    /// Whether there is nothing worth restoring
+    pub fn is_empty(&self) -> bool {
+        self.turns.is_empty()
+            && self.context.recent_symbols.is_empty()
+            && self.preferences.is_none()
+    }
+
+    /// Restore the symbols, turns and preferences into a platform session's
+    /// context
+    ///
+    /// The current symbol is kept last, where [`AnalysisContext::current_symbol`]
+    /// looks for it.
+    pub fn apply_to(&self, target: &mut AnalysisContext) {
+        let mut symbols = self.context.recent_symbols.clone();
+        if let Some(current) = &self.context.current_symbol {
+            symbols.retain(|s| s != current);
+            symbols.push(current.clone());
+        }
+        target.current_symbols = symbols;
+        target.conversation_turns = self
+            .turns
+            .iter()
+            .map(|turn| context::ConversationTurn {
+                input: turn.user_input.clone(),
+                response: turn.assistant_response.clone(),
+                symbols: turn.symbols.clone(),
+                timestamp: turn.timestamp,
+            })
+            .collect();
+        if let Some(preferences) = &self.preferences {
+            target.preferences = preferences.clone();
+        }
+    }
+}
+
+/// Backend that keeps conversation records across restarts
+pub trait ConversationStore: Send + Sync {
+    /// Saved conversation for `key`, if any
+    fn load(&self, key: &str) -> Result<Option<ConversationRecord>>;
+
+    /// Save the conversation for `key`, replacing any earlier record
+    fn save(&self, key: &str, record: &ConversationRecord) -> Result<()>;
+
+    /// Forget the conversation for `key`; returns whether one was stored
+    fn delete(&self, key: &str) -> Result<bool>;
+}
+
+/// Conversation store that lives only as long as the process
+#[derive(Default)]
+pub struct InMemoryConversationStore {
+    records: RwLock<HashMap<String, ConversationRecord>>,
+}
+
+impl InMemoryConversationStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ConversationStore for InMemoryConversationStore {
+    fn load(&self, key: &str) -> Result<Option<ConversationRecord>> {
+        let records = self.records.read().unwrap_or_else(PoisonError::into_inner);
+        Ok(records.get(key).cloned())
+    }
+
+    fn save(&self, key: &str, record: &ConversationRecord) -> Result<()> {
+        self.records
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(key.to_string(), record.clone());
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> Result<bool> {
+        let mut records = self.records.write().unwrap_or_else(PoisonError::into_inner);
+        Ok(records.remove(key).is_some())
+    }
+}
+
+/// Changes [`JsonConversationStore`] journals before folding them into its file
+pub const COMPACT_AFTER: usize = 100;
+
+/// Open the store persisted at `path`, encrypted with `cipher` if given
+///
+/// A `.db`, `.sqlite` or `.sqlite3` file is a [`SqliteConversationStore`];
+/// anything else a [`JsonConversationStore`].
+pub fn open(path: &Path, cipher: Option<StoreCipher>) -> Result<Arc<dyn ConversationStore>> {
+    Ok(if is_sqlite(path) {
+        Arc::new(SqliteConversationStore::open_with_cipher(path, cipher)?)
+    } else {
+        Arc::new(JsonConversationStore::open_with_cipher(path, cipher)?)
+    })
+}
+
+/// Whether the store at `path` is a SQLite database
+fn is_sqlite(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| matches!(ext, "db" | "sqlite" | "sqlite3"))
+}
+
+/// Every conversation in the store at `path` as a JSON object by key, or
+/// `None` if there is no store yet
+///
+/// Backups and migrations read the store through this, so they see the
+/// journal of a JSON store and the rows of a SQLite one. Records are kept as
+/// they are stored, whatever their schema version.
+pub fn export(path: &Path, cipher: Option<&StoreCipher>) -> Result<Option<String>> {
+    let records: BTreeMap<String, Value> = if is_sqlite(path) {
+        if !path.exists() {
+            return Ok(None);
+        }
+        SqliteConversationStore::open_with_cipher(path, cipher.cloned())?.records()?
+    } else {
+        let snapshot = storage::peek_store(path, cipher)?;
+        if snapshot.is_none() && !journal_path(path).exists() {
+            return Ok(None);
+        }
+        let mut records = match snapshot {
+            Some(json) => serde_json::from_str(&json)?,
+            None => BTreeMap::new(),
+        };
+        replay(path, cipher, &mut records)?;
+        records
+    };
+    Ok(Some(serde_json::to_string_pretty(&records)?))
+}
+
+/// Replace the store at `path` with conversations read by [`export`]
+pub fn import(path: &Path, json: &str, cipher: Option<&StoreCipher>) -> Result<()> {
+    if is_sqlite(path) {
+        let records: BTreeMap<String, Value> = serde_json::from_str(json)?;
+        return SqliteConversationStore::open_with_cipher(path, cipher.cloned())?
+            .replace_records(&records);
+    }
+    storage::write_store(path, json, cipher)?;
+    remove_journal(path)
+}
+
+/// File a JSON store at `path` journals its changes to between compactions
+pub fn journal_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".journal");
+    PathBuf::from(name)
+}
+
+fn remove_journal(path: &Path) -> Result<()> {
+    let journal = journal_path(path);
+    match std::fs::remove_file(&journal) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(StockError::Other(format!(
+            "Failed to remove {}: {e}",
+            journal.display()
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// One journaled change: the record saved under `key`, or `None` once it is
+/// deleted
+#[derive(Serialize, Deserialize)]
+struct JournalEntry<R> {
+    key: String,
+    record: Option<R>,
+}
+
+/// Apply the journal of the JSON store at `path` to `records`, returning how
+/// many changes it held
+///
+/// A line that cannot be read, such as one cut short by a crash, is skipped.
+fn replay<R: DeserializeOwned>(
+    path: &Path,
+    cipher: Option<&StoreCipher>,
+    records: &mut BTreeMap<String, R>,
+) -> Result<usize> {
+    let journal = journal_path(path);
+    if !journal.exists() {
+        return Ok(0);
+    }
+    let contents = std::fs::read_to_string(&journal)
+        .map_err(|e| StockError::Other(format!("Failed to read {}: {e}", journal.display())))?;
+
+    let mut changes = 0;
+    for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+        let entry = match decode(line, cipher).and_then(|json| Ok(serde_json::from_str(&json)?)) {
+            Ok(entry) => entry,
+            Err(StockError::ConfigError(e)) => return Err(StockError::ConfigError(e)),
+            Err(e) => {
+                tracing::warn!("Skipping unreadable entry in {}: {e}", journal.display());
+                continue;
+            }
+        };
+        let JournalEntry { key, record } = entry;
+        match record {
+            Some(record) => records.insert(key, record),
+            None => records.remove(&key),
+        };
+        changes += 1;
+    }
+    Ok(changes)
+}
+
+/// Plain JSON of a journal line or database value, decrypting it if needed
+fn decode(contents: &str, cipher: Option<&StoreCipher>) -> Result<String> {
+    if !storage::is_encrypted(contents) {
+        return Ok(contents.to_string());
+    }
+    let cipher = cipher.ok_or_else(|| {
+        StockError::ConfigError(format!(
+            "Conversations are encrypted; set {} or {} to read them",
+            storage::STORE_KEY_ENV,
+            storage::STORE_KEY_FILE_ENV
+        ))
+    })?;
+    cipher.decrypt(contents)
+}
+
+/// Conversation store persisted to a JSON file
+///
+/// Each change is appended to a journal next to the file (see
+/// [`journal_path`]) rather than rewriting every conversation; the journal
+/// is folded into the file on open and every [`COMPACT_AFTER`] changes.
+/// Both are encrypted when opened with a cipher.
+pub struct JsonConversationStore {
+    records: RwLock<BTreeMap<String, ConversationRecord>>,
+    journaled: AtomicUsize,
+    path: PathBuf,
+    cipher: Option<StoreCipher>,
+}
+
+impl JsonConversationStore {
+    /// Open a store persisted at `path`, loading existing conversations
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        Self::open_with_cipher(path, None)
+    }
+
+    /// Open a store persisted at `path`, encrypted with `cipher` if given
+    pub fn open_with_cipher(path: impl Into<PathBuf>, cipher: Option<StoreCipher>) -> Result<Self> {
+        let path = path.into();
+        let mut records = match storage::read_store(&path, cipher.as_ref())? {
+            Some(json) => serde_json::from_str(&json)?,
+            None => BTreeMap::new(),
+        };
+        let journaled = replay(&path, cipher.as_ref(), &mut records)?;
+
+        let store = Self {
+            records: RwLock::new(BTreeMap::new()),
+            journaled: AtomicUsize::new(0),
+            path,
+            cipher,
+        };
+        if journaled > 0 {
+            store.compact(&records)?;
+        }
+        *store
+            .records
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = records;
+        Ok(store)
+    }
+
+    /// File the store is persisted to
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Journal the change to `key`, or compact once the journal is full
+    fn persist(&self, records: &BTreeMap<String, ConversationRecord>, key: &str) -> Result<()> {
+        if self.journaled.load(Ordering::Relaxed) + 1 >= COMPACT_AFTER {
+            return self.compact(records);
+        }
+
+        let entry = JournalEntry {
+            key: key.to_string(),
+            record: records.get(key),
+        };
+        let json = serde_json::to_string(&entry)?;
+        let line = match &self.cipher {
+            Some(cipher) => cipher.encrypt_line(&json)?,
+            None => json,
+        };
+        let journal = journal_path(&self.path);
+        if let Some(parent) = journal.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                StockError::Other(format!("Failed to create {}: {e}", parent.display()))
+            })?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&journal)
+            .and_then(|mut file| writeln!(file, "{line}"))
+            .map_err(|e| {
+                StockError::Other(format!("Failed to write {}: {e}", journal.display()))
+            })?;
+        self.journaled.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Write every conversation to the file and start a new journal
+    fn compact(&self, records: &BTreeMap<String, ConversationRecord>) -> Result<()> {
+        let json = serde_json::to_string_pretty(records)?;
+        storage::write_store(&self.path, &json, self.cipher.as_ref())?;
+        remove_journal(&self.path)?;
+        self.journaled.store(0, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+impl ConversationStore for JsonConversationStore {
+    fn load(&self, key: &str) -> Result<Option<ConversationRecord>> {
+        let records = self.records.read().unwrap_or_else(PoisonError::into_inner);
+        Ok(records.get(key).cloned())
+    }
+
+    fn save(&self, key: &str, record: &ConversationRecord) -> Result<()> {
+        let mut records = self.records.write().unwrap_or_else(PoisonError::into_inner);
+        if records
+            .get(key)
+            .is_some_and(|saved| saved.same_conversation(record))
+        {
+            return Ok(());
+        }
+        records.insert(key.to_string(), record.clone());
+        self.persist(&records, key)
+    }
+
+    fn delete(&self, key: &str) -> Result<bool> {
+        let mut records = self.records.write().unwrap_or_else(PoisonError::into_inner);
+        if records.remove(key).is_none() {
+            return Ok(false);
+        }
+        self.persist(&records, key)?;
+        Ok(true)
+    }
+}
+
+/// Conversation store kept in a SQLite database
+///
+/// Each conversation is a row of its own, so saving one writes only that
+/// row. Records are encrypted when opened with a cipher.
+pub struct SqliteConversationStore {
+    db: Mutex<Connection>,
+    path: PathBuf,
+    cipher: Option<StoreCipher>,
+}
+
+/// Table the conversations are kept in
+const SQLITE_SCHEMA: &str =
+    "CREATE TABLE IF NOT EXISTS conversations (key TEXT PRIMARY KEY, record TEXT NOT NULL)";
+
+impl SqliteConversationStore {
+    /// Open the database at `path`, creating it if needed
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        Self::open_with_cipher(path, None)
+    }
+
+    /// Open the database at `path`, encrypting records with `cipher` if given
+    pub fn open_with_cipher(path: impl Into<PathBuf>, cipher: Option<StoreCipher>) -> Result<Self> {
+        let path = path.into();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                StockError::Other(format!("Failed to create {}: {e}", parent.display()))
+            })?;
+        }
+        let db = Connection::open(&path).map_err(db_error)?;
+        db.execute_batch(SQLITE_SCHEMA).map_err(db_error)?;
+
+        Ok(Self {
+            db: Mutex::new(db),
+            path,
+            cipher,
+        })
+    }
+
+    /// Database the store is kept in
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn db(&self) -> MutexGuard<'_, Connection> {
+        self.db.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn encode(&self, json: String) -> Result<String> {
+        match &self.cipher {
+            Some(cipher) => cipher.encrypt(&json),
+            None => Ok(json),
+        }
+    }
+
+    /// Every record as stored, by key
+    fn records(&self) -> Result<BTreeMap<String, Value>> {
+        let db = self.db();
+        let mut statement = db
+            .prepare("SELECT key, record FROM conversations")
+            .map_err(db_error)?;
+        let rows = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get::<_, String>(1)?)))
+            .map_err(db_error)?;
+        let mut records = BTreeMap::new();
+        for row in rows {
+            let (key, record) = row.map_err(db_error)?;
+            let json = decode(&record, self.cipher.as_ref())?;
+            records.insert(key, serde_json::from_str(&json)?);
+        }
+        Ok(records)
+    }
+
+    /// Replace every record with `records`
+    fn replace_records(&self, records: &BTreeMap<String, Value>) -> Result<()> {
+        let mut db = self.db();
+        let tx = db.transaction().map_err(db_error)?;
+        tx.execute("DELETE FROM conversations", [])
+            .map_err(db_error)?;
+        for (key, record) in records {
+            let record = self.encode(serde_json::to_string(record)?)?;
+            tx.execute(
+                "INSERT INTO conversations (key, record) VALUES (?1, ?2)",
+                params![key, record],
+            )
+            .map_err(db_error)?;
+        }
+        tx.commit().map_err(db_error)
+    }
+}
+
+impl ConversationStore for SqliteConversationStore {
+    fn load(&self, key: &str) -> Result<Option<ConversationRecord>> {
+        let record: Option<String> = self
+            .db()
+            .query_row(
+                "SELECT record FROM conversations WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(db_error)?;
+        record
+            .map(|record| {
+                Ok(serde_json::from_str(&decode(
+                    &record,
+                    self.cipher.as_ref(),
+                )?)?)
+            })
+            .transpose()
+    }
+
+    fn save(&self, key: &str, record: &ConversationRecord) -> Result<()> {
+        let record = self.encode(serde_json::to_string(record)?)?;
+        self.db()
+            .execute(
+                "INSERT INTO conversations (key, record) VALUES (?1, ?2)
+                 ON CONFLICT (key) DO UPDATE SET record = excluded.record",
+                params![key, record],
+            )
+            .map_err(db_error)?;
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> Result<bool> {
+        let deleted = self
+            .db()
+            .execute("DELETE FROM conversations WHERE key = ?1", params![key])
+            .map_err(db_error)?;
+        Ok(deleted > 0)
+    }
+}
+
+fn db_error(error: rusqlite::Error) -> StockError {
+    StockError::ConversationError(format!("Database error: {error}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::ResponseStyle;
+
+    fn temp_path() -> PathBuf {
+        std::env::temp_dir().join(format!("conversations-{}.json", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn test_conversation_key() {
+        assert_eq!(conversation_key(BotPlatform::Telegram, "42"), "telegram:42");
+        assert_eq!(conversation_key(BotPlatform::Feishu, "ou_1"), "feishu:ou_1");
+    }
+
+    #[test]
+    fn test_manager_round_trip_through_json_store() {
+        let path = temp_path();
+        let mut manager = ConversationManager::new();
+        manager.add_turn(
+            "Analyze AAPL".into(),
+            "Apple looks strong.".into(),
+            vec!["AAPL".into()],
+        );
+        manager.add_turn(
+            "/news MSFT".into(),
+            "Microsoft news.".into(),
+            vec!["MSFT".into()],
+        );
+
+        let store = JsonConversationStore::open(&path).unwrap();
+        store
+            .save("cli:local", &ConversationRecord::from_manager(&manager))
+            .unwrap();
+
+        let reopened = JsonConversationStore::open(&path).unwrap();
+        let record = reopened.load("cli:local").unwrap().unwrap();
+        let mut restored = ConversationManager::new();
+        restored.restore(record);
+
+        assert_eq!(restored.len(), 2);
+        assert_eq!(restored.current_symbol(), Some("MSFT"));
+        assert_eq!(restored.context().recent_symbols, vec!["AAPL", "MSFT"]);
+        assert!(
+            restored
+                .resolve_references("这只股票的PE是多少?")
+                .contains("MSFT")
+        );
+
+        assert!(reopened.delete("cli:local").unwrap());
+        assert!(!reopened.delete("cli:local").unwrap());
+        assert!(
+            JsonConversationStore::open(&path)
+                .unwrap()
+                .load("cli:local")
+                .unwrap()
+                .is_none()
+        );
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    fn record(input: &str) -> ConversationRecord {
+        let mut manager = ConversationManager::new();
+        manager.add_turn(input.into(), "Reply".into(), vec!["AAPL".into()]);
+        ConversationRecord::from_manager(&manager)
+    }
+
+    #[test]
+    fn test_json_store_journals_changes() {
+        let path = temp_path();
+        let cipher = StoreCipher::from_key(&[3; 32]).unwrap();
+        let store = JsonConversationStore::open_with_cipher(&path, Some(cipher.clone())).unwrap();
+        store.save("telegram:1", &record("Analyze AAPL")).unwrap();
+        store.save("telegram:2", &record("Analyze MSFT")).unwrap();
+        assert!(store.delete("telegram:1").unwrap());
+
+        // Nothing is rewritten; the changes are lines of the journal
+        assert!(!path.exists());
+        let journal = std::fs::read_to_string(journal_path(&path)).unwrap();
+        assert_eq!(journal.lines().count(), 3);
+        assert!(!journal.contains("MSFT"));
+
+        let exported = export(&path, Some(&cipher)).unwrap().unwrap();
+        assert!(exported.contains("telegram:2") && !exported.contains("telegram:1"));
+
+        // Reopening folds the journal into the file
+        let reopened = JsonConversationStore::open_with_cipher(&path, Some(cipher)).unwrap();
+        assert!(reopened.load("telegram:1").unwrap().is_none());
+        let saved = reopened.load("telegram:2").unwrap().unwrap();
+        assert_eq!(saved.turns[0].user_input, "Analyze MSFT");
+        assert!(!journal_path(&path).exists());
+
+        for i in 0..COMPACT_AFTER {
+            reopened
+                .save("telegram:3", &record(&format!("Query {i}")))
+                .unwrap();
+        }
+        assert!(!journal_path(&path).exists());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_sqlite_store_round_trip() {
+        let path = std::env::temp_dir().join(format!("conversations-{}.db", uuid::Uuid::new_v4()));
+        let cipher = StoreCipher::from_key(&[5; 32]).unwrap();
+        let store = open(&path, Some(cipher.clone())).unwrap();
+        store.save("slack:U1", &record("Analyze AAPL")).unwrap();
+        store.save("slack:U1", &record("Analyze NVDA")).unwrap();
+        store.save("slack:U2", &record("Analyze MSFT")).unwrap();
+        assert!(store.delete("slack:U2").unwrap());
+        assert!(!store.delete("slack:U2").unwrap());
+        drop(store);
+
+        let reopened =
+            SqliteConversationStore::open_with_cipher(&path, Some(cipher.clone())).unwrap();
+        let saved = reopened.load("slack:U1").unwrap().unwrap();
+        assert_eq!(saved.turns[0].user_input, "Analyze NVDA");
+
+        // Backups see the rows, and restore them into an empty database
+        let exported = export(&path, Some(&cipher)).unwrap().unwrap();
+        assert!(exported.contains("Analyze NVDA"));
+        reopened.delete("slack:U1").unwrap();
+        import(&path, &exported, Some(&cipher)).unwrap();
+        assert!(reopened.load("slack:U1").unwrap().is_some());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_analysis_context_round_trip() {
+        let mut context = AnalysisContext::with_user("42");
+        context.add_symbol("TSLA");
+        context.add_symbol("NVDA");
+        context.add_turn(
+            "/technical NVDA".into(),
+            "NVDA is overbought.".into(),
+            vec!["NVDA".into()],
+        );
+        context.preferences.style = ResponseStyle::Concise;
+        context.preferences.response_language = Some("zh".into());
+
+        let record = ConversationRecord::from_analysis_context(&context);
+        assert_eq!(record.context.current_symbol.as_deref(), Some("NVDA"));
+
+        let mut restored = AnalysisContext::with_user("42");
+        record.apply_to(&mut restored);
+        assert_eq!(restored.current_symbols, vec!["TSLA", "NVDA"]);
+        assert_eq!(restored.current_symbol(), Some("NVDA"));
+        assert_eq!(restored.conversation_turns.len(), 1);
+        assert_eq!(
+            restored.conversation_turns[0].response,
+            "NVDA is overbought."
+        );
+        assert_eq!(restored.preferences.style, ResponseStyle::Concise);
+        assert_eq!(
+            restored.preferences.response_language.as_deref(),
+            Some("zh")
+        );
+    }
+
+    #[test]
+    fn test_record_keeps_latest_turns() {
+        let mut manager = ConversationManager::with_max_history(MAX_STORED_TURNS + 10);
+        for i in 0..MAX_STORED_TURNS + 10 {
+            manager.add_turn(format!("Query {i}"), format!("Response {i}"), vec![]);
+        }
+
+        let record = ConversationRecord::from_manager(&manager);
+        assert_eq!(record.turns.len(), MAX_STORED_TURNS);
+        assert_eq!(record.turns[0].user_input, "Query 10");
+        assert!(ConversationRecord::default().is_empty());
+    }
+}
@@ -0,0 +1,290 @@
+//! Crypto asset symbols
+//!
+//! Yahoo Finance quotes crypto assets as `BASE-QUOTE` pairs (`BTC-USD`,
+//! `ETH-EUR`), so the price, chart, technical and comparison flows work on
+//! them unchanged once a symbol is in that form. [`CryptoPair`] parses the
+//! spellings users and the LLM write (`BTC-USD`, `btc/usd`, `BTCUSDT`),
+//! [`normalize_symbol`] is what tools call in place of `to_uppercase`, and
+//! [`find_in_query`] lets the router pick up coins named in plain words
+//! ("bitcoin", "以太坊").
+//!
+//! Crypto assets have no company behind them, so fundamental, earnings and
+//! filings analysis skip them.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::crypto::{self, CryptoPair};
+//!
+//! assert_eq!(crypto::normalize_symbol("eth/usd"), "ETH-USD");
+//! assert_eq!(crypto::normalize_symbol("bitcoin"), "BTC-USD");
+//! assert_eq!(crypto::normalize_symbol("aapl"), "AAPL");
+//! assert_eq!(CryptoPair::parse("BTC-USD").unwrap().name(), "Bitcoin");
+//! ```
+
+use std::fmt;
+
+/// A well-known coin
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Coin {
+    /// Ticker, the base of its pairs (e.g. "BTC")
+    pub ticker: &'static str,
+    /// English name
+    pub name: &'static str,
+    /// Lowercase names users write for it, in English and Chinese
+    pub aliases: &'static [&'static str],
+    /// Whether the bare ticker means the coin rather than a listed stock
+    pub bare_ticker: bool,
+}
+
+/// Coins recognized by name and as bare tickers
+pub const COINS: &[Coin] = &[
+    coin("BTC", "Bitcoin", &["bitcoin", "比特币"], true),
+    coin("ETH", "Ethereum", &["ethereum", "ether", "以太坊"], true),
+    coin("SOL", "Solana", &["solana", "索拉纳"], false),
+    coin("XRP", "XRP", &["ripple", "瑞波币"], true),
+    coin("DOGE", "Dogecoin", &["dogecoin", "狗狗币"], true),
+    coin("ADA", "Cardano", &["cardano", "艾达币"], false),
+    coin("BNB", "BNB", &["币安币"], false),
+    coin("LTC", "Litecoin", &["litecoin", "莱特币"], false),
+    coin("DOT", "Polkadot", &["polkadot", "波卡"], false),
+    coin("LINK", "Chainlink", &["chainlink"], false),
+    coin("AVAX", "Avalanche", &["雪崩币"], false),
+    coin("TRX", "TRON", &["tron", "波场"], false),
+    coin("USDT", "Tether", &["tether", "泰达币"], true),
+    coin("USDC", "USD Coin", &[], true),
+];
+
+const fn coin(
+    ticker: &'static str,
+    name: &'static str,
+    aliases: &'static [&'static str],
+    bare_ticker: bool,
+) -> Coin {
+    Coin {
+        ticker,
+        name,
+        aliases,
+        bare_ticker,
+    }
+}
+
+/// Currencies a pair can be quoted in
+const QUOTE_CURRENCIES: &[&str] = &[
+    "USD", "USDT", "USDC", "EUR", "GBP", "JPY", "CNY", "HKD", "BTC", "ETH",
+];
+
+/// Fiat currencies, which are never the base of a crypto pair
+const FIAT: &[&str] = &["USD", "EUR", "GBP", "JPY", "CNY", "HKD"];
+
+/// Quote currency of pairs made from a coin name or bare ticker
+const DEFAULT_QUOTE: &str = "USD";
+
+/// A crypto asset priced in a quote currency, e.g. BTC in USD
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CryptoPair {
+    /// Asset ticker (e.g. "BTC")
+    pub base: String,
+    /// Currency the asset is priced in (e.g. "USD")
+    pub quote: String,
+}
+
+impl CryptoPair {
+    /// Pair for `base` quoted in USD
+    pub fn usd(base: &str) -> Self {
+        Self {
+            base: base.to_uppercase(),
+            quote: DEFAULT_QUOTE.to_string(),
+        }
+    }
+
+    /// Parse a pair written as `BTC-USD`, `BTC/USD` or `BTCUSD`, in any case
+    ///
+    /// Separated pairs accept any base that is not a fiat currency, as long
+    /// as the quote is a known currency; unseparated ones only known coins.
+    /// Stock share classes like `BRK-B` are not pairs.
+    pub fn parse(symbol: &str) -> Option<Self> {
+        let symbol = symbol.trim().to_uppercase();
+
+        if let Some((base, quote)) = symbol.split_once(['-', '/']) {
+            let valid_base = (2..=10).contains(&base.len())
+                && base.chars().all(|c| c.is_ascii_alphanumeric())
+                && !FIAT.contains(&base);
+            return (valid_base && QUOTE_CURRENCIES.contains(&quote)).then(|| Self {
+                base: base.to_string(),
+                quote: quote.to_string(),
+            });
+        }
+
+        COINS.iter().find_map(|coin| {
+            let quote = symbol.strip_prefix(coin.ticker)?;
+            QUOTE_CURRENCIES.contains(&quote).then(|| Self {
+                base: coin.ticker.to_string(),
+                quote: quote.to_string(),
+            })
+        })
+    }
+
+    /// The coin this pair trades, when it is a well-known one
+    pub fn coin(&self) -> Option<&'static Coin> {
+        COINS.iter().find(|coin| coin.ticker == self.base)
+    }
+
+    /// Human-readable asset name, falling back to the ticker
+    pub fn name(&self) -> &str {
+        self.coin().map_or(&self.base, |coin| coin.name)
+    }
+
+    /// Format a price in the quote currency
+    ///
+    /// Prices under one unit keep four significant digits, so a coin worth
+    /// $0.000012 does not print as $0.00.
+    pub fn format_price(&self, price: f64) -> String {
+        let abs = price.abs();
+        let decimals = if abs >= 1.0 || abs == 0.0 {
+            2
+        } else {
+            // Leading zeros after the point, plus four significant digits
+            (-abs.log10()).floor() as usize + 4
+        };
+        let amount = format!("{abs:.decimals$}");
+        let sign = if price < 0.0 { "-" } else { "" };
+        match self.quote.as_str() {
+            "USD" => format!("{sign}${amount}"),
+            "EUR" => format!("{sign}€{amount}"),
+            "GBP" => format!("{sign}£{amount}"),
+            quote => format!("{sign}{amount} {quote}"),
+        }
+    }
+}
+
+impl fmt::Display for CryptoPair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.base, self.quote)
+    }
+}
+
+/// Find a coin by lowercase alias or, for unambiguous ones, bare ticker
+fn find_coin(word: &str) -> Option<&'static Coin> {
+    let lower = word.to_lowercase();
+    COINS.iter().find(|coin| {
+        coin.aliases.contains(&lower.as_str())
+            || (coin.bare_ticker && coin.ticker.eq_ignore_ascii_case(word))
+    })
+}
+
+/// Whether `symbol` is a crypto pair
+pub fn is_crypto(symbol: &str) -> bool {
+    CryptoPair::parse(symbol).is_some()
+}
+
+/// Canonical symbol for a tool input
+///
+/// Crypto pairs become Yahoo's `BASE-QUOTE` form, coin names and unambiguous
+/// bare tickers ("bitcoin", "BTC") become their USD pair, and anything else
+/// is uppercased as a stock ticker.
+pub fn normalize_symbol(symbol: &str) -> String {
+    let symbol = symbol.trim();
+    if let Some(pair) = CryptoPair::parse(symbol) {
+        return pair.to_string();
+    }
+    match find_coin(symbol) {
+        Some(coin) => CryptoPair::usd(coin.ticker).to_string(),
+        None => symbol.to_uppercase(),
+    }
+}
+
+/// Label for report headings: the pair with its coin name, or the symbol
+pub fn display_name(symbol: &str) -> String {
+    match CryptoPair::parse(symbol) {
+        Some(pair) if pair.coin().is_some() => format!("{pair} ({})", pair.name()),
+        _ => symbol.to_string(),
+    }
+}
+
+/// USD pairs of the coins a query names in words, in order of appearance
+///
+/// English names match whole words; Chinese names match anywhere, since
+/// Chinese text has no spaces.
+pub fn find_in_query(query: &str) -> Vec<String> {
+    let lower = query.to_lowercase();
+    let words: Vec<&str> = lower
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    let mut found: Vec<(usize, String)> = COINS
+        .iter()
+        .filter_map(|coin| {
+            let position = coin.aliases.iter().find_map(|alias| {
+                if alias.is_ascii() {
+                    words
+                        .iter()
+                        .position(|word| word == alias)
+                        .and_then(|index| lower.find(words[index]))
+                } else {
+                    lower.find(alias)
+                }
+            })?;
+            Some((position, CryptoPair::usd(coin.ticker).to_string()))
+        })
+        .collect();
+    found.sort();
+    found.into_iter().map(|(_, symbol)| symbol).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pairs() {
+        let btc = CryptoPair::parse("btc/usd").unwrap();
+        assert_eq!(btc.to_string(), "BTC-USD");
+        assert_eq!(btc.name(), "Bitcoin");
+        assert_eq!(
+            CryptoPair::parse("ETHUSDT").unwrap().to_string(),
+            "ETH-USDT"
+        );
+        assert_eq!(CryptoPair::parse("PEPE-USD").unwrap().name(), "PEPE");
+
+        // Share classes, forex and stocks are not crypto
+        assert!(CryptoPair::parse("BRK-B").is_none());
+        assert!(CryptoPair::parse("EUR-USD").is_none());
+        assert!(CryptoPair::parse("AAPL").is_none());
+        assert!(CryptoPair::parse("BTC").is_none());
+    }
+
+    #[test]
+    fn test_normalize_symbol() {
+        assert_eq!(normalize_symbol(" eth-usd "), "ETH-USD");
+        assert_eq!(normalize_symbol("Bitcoin"), "BTC-USD");
+        assert_eq!(normalize_symbol("btc"), "BTC-USD");
+        // SOL is also a listed stock, so the bare ticker stays one
+        assert_eq!(normalize_symbol("sol"), "SOL");
+        assert_eq!(normalize_symbol("brk-b"), "BRK-B");
+        assert_eq!(display_name("ETH-USD"), "ETH-USD (Ethereum)");
+        assert_eq!(display_name("AAPL"), "AAPL");
+    }
+
+    #[test]
+    fn test_find_in_query() {
+        assert_eq!(
+            find_in_query("Compare Ethereum and bitcoin"),
+            vec!["ETH-USD", "BTC-USD"]
+        );
+        assert_eq!(find_in_query("比特币的技术分析"), vec!["BTC-USD"]);
+        // Whole words only
+        assert!(find_in_query("Etherington Industries").is_empty());
+    }
+
+    #[test]
+    fn test_format_price() {
+        let usd = CryptoPair::usd("BTC");
+        assert_eq!(usd.format_price(67_234.5), "$67234.50");
+        assert_eq!(usd.format_price(0.123_456), "$0.1235");
+        assert_eq!(usd.format_price(0.000_012_344), "$0.00001234");
+        let usdt = CryptoPair::parse("ETH-USDT").unwrap();
+        assert_eq!(usdt.format_price(3_100.0), "3100.00 USDT");
+    }
+}
@@ -0,0 +1,786 @@
+//! Quiet hours and delivery windows for pushed notifications
+//!
+//! Users set their time zone with `/timezone America/New_York`, then quiet
+//! hours with `/quiet 22:00-07:00` or the window they want notifications in
+//! with `/deliver 08:00-20:00`. Notifications due during quiet hours are
+//! queued in a [`DeliveryStore`] instead of being pushed, and go out as one
+//! [`QuietSummary`] once the user's quiet hours end. Price alerts are held by
+//! the [`AlertEngine`](crate::alerts::AlertEngine); other pushes (anomalies,
+//! digests) go through a [`QuietNotifier`].
+//!
+//! Time zones are IANA zones or fixed UTC offsets (see [`TimeZone`]), so
+//! quiet hours follow daylight saving time in zones that have it.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::delivery::{DeliveryStore, QuietHours};
+//!
+//! let delivery = Arc::new(DeliveryStore::open("delivery.json")?);
+//! let zone = "Asia/Shanghai".parse()?;
+//! delivery.set_quiet_hours("12345", BotPlatform::Telegram, "22:00-07:00".parse()?, Some(zone))?;
+//!
+//! let engine = AlertEngine::new(store, quotes, cache).with_delivery(delivery);
+//! ```
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, PoisonError, RwLock};
+
+use crate::alerts::Notifier;
+use crate::bot::Command;
+use crate::error::{Result, StockError};
+use crate::interface::BotPlatform;
+use crate::storage::{self, StoreCipher};
+use crate::timezone::TimeZone;
+
+/// Local times of day during which notifications are held back
+///
+/// The range may wrap past midnight (`22:00-07:00`); `end` is exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    /// Quiet hours outside the delivery window `window`, e.g. `20:00-08:00`
+    /// for a window of `08:00-20:00`
+    pub fn outside(window: QuietHours) -> Self {
+        Self {
+            start: window.end,
+            end: window.start,
+        }
+    }
+
+    /// Whether the local time `time` falls in the quiet hours
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+impl fmt::Display for QuietHours {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M")
+        )
+    }
+}
+
+impl FromStr for QuietHours {
+    type Err = StockError;
+
+    /// Parse `HH:MM-HH:MM`
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid =
+            || StockError::CommandError(format!("Invalid time range: {s}. Use e.g. 22:00-07:00"));
+        let (start, end) = s.split_once('-').ok_or_else(invalid)?;
+        let parse =
+            |time: &str| NaiveTime::parse_from_str(time.trim(), "%H:%M").map_err(|_| invalid());
+        let hours = Self {
+            start: parse(start)?,
+            end: parse(end)?,
+        };
+        if hours.start == hours.end {
+            return Err(invalid());
+        }
+        Ok(hours)
+    }
+}
+
+/// A user's time zone and quiet hours
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeliveryPreferences {
+    /// Chat or conversation notifications are pushed to
+    pub user_id: String,
+    pub platform: BotPlatform,
+    /// Time zone quiet hours and daily alert limits are read in
+    #[serde(default)]
+    pub time_zone: TimeZone,
+    /// Local times notifications are held back during, if any
+    #[serde(default, alias = "hours")]
+    pub quiet_hours: Option<QuietHours>,
+}
+
+impl DeliveryPreferences {
+    fn new(user_id: &str, platform: BotPlatform) -> Self {
+        Self {
+            user_id: user_id.to_string(),
+            platform,
+            time_zone: TimeZone::UTC,
+            quiet_hours: None,
+        }
+    }
+
+    /// Whether the user is in quiet hours at `now`
+    pub fn is_quiet(&self, now: DateTime<Utc>) -> bool {
+        self.quiet_hours
+            .is_some_and(|hours| hours.contains(self.time_zone.to_local(now).time()))
+    }
+
+    /// First end of the quiet hours after `now`, if the user has quiet hours
+    pub fn ends_after(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let hours = self.quiet_hours?;
+        let local = self.time_zone.to_local(now);
+        let mut date = local.date();
+        if local.time() >= hours.end {
+            date += Duration::days(1);
+        }
+        Some(self.time_zone.from_local(date.and_time(hours.end)))
+    }
+
+    /// The user's local date at `now`
+    pub fn local_date(&self, now: DateTime<Utc>) -> NaiveDate {
+        self.time_zone.to_local(now).date()
+    }
+}
+
+impl fmt::Display for DeliveryPreferences {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.quiet_hours {
+            Some(hours) => write!(f, "{hours} ({})", self.time_zone),
+            None => write!(f, "no quiet hours ({})", self.time_zone),
+        }
+    }
+}
+
+/// A notification held back during quiet hours
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueuedNotification {
+    pub user_id: String,
+    pub platform: BotPlatform,
+    pub message: String,
+    pub queued_at: DateTime<Utc>,
+}
+
+/// Notifications a user missed during quiet hours, sent as one message
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuietSummary {
+    pub user_id: String,
+    pub platform: BotPlatform,
+    /// Notifications in the order they were queued
+    pub notifications: Vec<QueuedNotification>,
+}
+
+impl QuietSummary {
+    /// Messages in the order they were queued
+    pub fn messages(&self) -> impl Iterator<Item = &str> {
+        self.notifications.iter().map(|n| n.message.as_str())
+    }
+}
+
+impl fmt::Display for QuietSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "🌅 {} notification(s) during your quiet hours:\n{}",
+            self.notifications.len(),
+            self.messages().collect::<Vec<_>>().join("\n")
+        )
+    }
+}
+
+/// Persisted contents of a [`DeliveryStore`]
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DeliveryState {
+    #[serde(default)]
+    settings: Vec<DeliveryPreferences>,
+    #[serde(default)]
+    queued: Vec<QueuedNotification>,
+}
+
+/// Time zones and quiet hours of all users, and the notifications held back
+/// for them
+///
+/// Kept in memory and, when opened with a path, saved as JSON after every
+/// change (encrypted when opened with a cipher), so queued notifications
+/// survive a restart.
+pub struct DeliveryStore {
+    state: RwLock<DeliveryState>,
+    path: Option<PathBuf>,
+    cipher: Option<StoreCipher>,
+}
+
+impl Default for DeliveryStore {
+    fn default() -> Self {
+        Self::in_memory()
+    }
+}
+
+impl DeliveryStore {
+    /// Create a store that is not persisted
+    pub fn in_memory() -> Self {
+        Self {
+            state: RwLock::new(DeliveryState::default()),
+            path: None,
+            cipher: None,
+        }
+    }
+
+    /// Open a store persisted at `path`, loading existing settings
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        Self::open_with_cipher(path, None)
+    }
+
+    /// Open a store persisted at `path`, encrypted with `cipher` if given
+    pub fn open_with_cipher(path: impl Into<PathBuf>, cipher: Option<StoreCipher>) -> Result<Self> {
+        let path = path.into();
+        let state = match storage::read_store(&path, cipher.as_ref())? {
+            Some(json) => serde_json::from_str(&json)?,
+            None => DeliveryState::default(),
+        };
+
+        Ok(Self {
+            state: RwLock::new(state),
+            path: Some(path),
+            cipher,
+        })
+    }
+
+    /// File the store is persisted to, if any
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Set the quiet hours of `user_id` on `platform`
+    ///
+    /// Without `time_zone` the user's time zone is kept (UTC if they never
+    /// set one).
+    pub fn set_quiet_hours(
+        &self,
+        user_id: &str,
+        platform: BotPlatform,
+        hours: QuietHours,
+        time_zone: Option<TimeZone>,
+    ) -> Result<DeliveryPreferences> {
+        self.update(user_id, platform, |preferences| {
+            preferences.quiet_hours = Some(hours);
+            if let Some(time_zone) = time_zone {
+                preferences.time_zone = time_zone;
+            }
+        })
+    }
+
+    /// Set the time zone of `user_id` on `platform`
+    pub fn set_time_zone(
+        &self,
+        user_id: &str,
+        platform: BotPlatform,
+        time_zone: TimeZone,
+    ) -> Result<DeliveryPreferences> {
+        self.update(user_id, platform, |preferences| {
+            preferences.time_zone = time_zone
+        })
+    }
+
+    /// Remove the quiet hours of `user_id`, keeping their time zone; returns
+    /// whether there were any
+    ///
+    /// Notifications already queued go out on the next delivery.
+    pub fn clear_quiet_hours(&self, user_id: &str, platform: BotPlatform) -> Result<bool> {
+        let had_hours = self
+            .preferences(user_id, platform)
+            .is_some_and(|preferences| preferences.quiet_hours.is_some());
+        if had_hours {
+            self.update(user_id, platform, |preferences| {
+                preferences.quiet_hours = None
+            })?;
+        }
+        Ok(had_hours)
+    }
+
+    fn update(
+        &self,
+        user_id: &str,
+        platform: BotPlatform,
+        change: impl FnOnce(&mut DeliveryPreferences),
+    ) -> Result<DeliveryPreferences> {
+        let preferences = {
+            let mut state = self.write();
+            let existing = state
+                .settings
+                .iter()
+                .position(|s| s.user_id == user_id && s.platform == platform);
+            let index = existing.unwrap_or_else(|| {
+                state
+                    .settings
+                    .push(DeliveryPreferences::new(user_id, platform));
+                state.settings.len() - 1
+            });
+            change(&mut state.settings[index]);
+            state.settings[index].clone()
+        };
+        self.save()?;
+        Ok(preferences)
+    }
+
+    /// Time zone and quiet hours of `user_id` on `platform`, if set
+    pub fn preferences(&self, user_id: &str, platform: BotPlatform) -> Option<DeliveryPreferences> {
+        self.read()
+            .settings
+            .iter()
+            .find(|s| s.user_id == user_id && s.platform == platform)
+            .cloned()
+    }
+
+    /// Time zone of `user_id` on `platform`; UTC if they never set one
+    pub fn time_zone(&self, user_id: &str, platform: BotPlatform) -> TimeZone {
+        self.preferences(user_id, platform)
+            .map(|preferences| preferences.time_zone)
+            .unwrap_or_default()
+    }
+
+    /// Whether `user_id` on `platform` is in quiet hours at `now`
+    pub fn is_quiet(&self, user_id: &str, platform: BotPlatform, now: DateTime<Utc>) -> bool {
+        self.preferences(user_id, platform)
+            .is_some_and(|preferences| preferences.is_quiet(now))
+    }
+
+    /// Queue `message` if `user_id` is in quiet hours at `now`; returns
+    /// whether it was queued
+    pub fn hold_if_quiet(
+        &self,
+        user_id: &str,
+        platform: BotPlatform,
+        message: &str,
+        now: DateTime<Utc>,
+    ) -> Result<bool> {
+        if !self.is_quiet(user_id, platform, now) {
+            return Ok(false);
+        }
+        self.queue(user_id, platform, message, now)?;
+        Ok(true)
+    }
+
+    /// Hold `message` for `user_id` until their quiet hours end
+    pub fn queue(
+        &self,
+        user_id: &str,
+        platform: BotPlatform,
+        message: &str,
+        at: DateTime<Utc>,
+    ) -> Result<()> {
+        self.write().queued.push(QueuedNotification {
+            user_id: user_id.to_string(),
+            platform,
+            message: message.to_string(),
+            queued_at: at,
+        });
+        self.save()
+    }
+
+    /// Number of notifications held for `user_id` on `platform`
+    pub fn queued_for(&self, user_id: &str, platform: BotPlatform) -> usize {
+        self.read()
+            .queued
+            .iter()
+            .filter(|n| n.user_id == user_id && n.platform == platform)
+            .count()
+    }
+
+    /// Remove and return the held notifications of users no longer in
+    /// quiet hours at `now`, one summary per user
+    ///
+    /// Put summaries that could not be sent back with
+    /// [`DeliveryStore::requeue`].
+    pub fn take_due(&self, now: DateTime<Utc>) -> Result<Vec<QuietSummary>> {
+        let summaries = {
+            let mut state = self.write();
+            if state.queued.is_empty() {
+                return Ok(Vec::new());
+            }
+            let queued = std::mem::take(&mut state.queued);
+            let (held, due): (Vec<_>, Vec<_>) = queued.into_iter().partition(|notification| {
+                state.settings.iter().any(|s| {
+                    s.user_id == notification.user_id
+                        && s.platform == notification.platform
+                        && s.is_quiet(now)
+                })
+            });
+            state.queued = held;
+
+            let mut summaries: Vec<QuietSummary> = Vec::new();
+            for notification in due {
+                match summaries.iter_mut().find(|summary| {
+                    summary.user_id == notification.user_id
+                        && summary.platform == notification.platform
+                }) {
+                    Some(summary) => summary.notifications.push(notification),
+                    None => summaries.push(QuietSummary {
+                        user_id: notification.user_id.clone(),
+                        platform: notification.platform,
+                        notifications: vec![notification],
+                    }),
+                }
+            }
+            summaries
+        };
+        if !summaries.is_empty() {
+            self.save()?;
+        }
+        Ok(summaries)
+    }
+
+    /// Queue the notifications of a summary that could not be sent again,
+    /// ahead of anything queued since
+    pub fn requeue(&self, summary: QuietSummary) -> Result<()> {
+        {
+            let mut state = self.write();
+            let later = std::mem::take(&mut state.queued);
+            state.queued = summary.notifications;
+            state.queued.extend(later);
+        }
+        self.save()
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, DeliveryState> {
+        self.state.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, DeliveryState> {
+        self.state.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let json = serde_json::to_string_pretty(&*self.read())?;
+        storage::write_store(path, &json, self.cipher.as_ref())
+    }
+}
+
+/// Notifier that holds messages back during the user's quiet hours
+///
+/// Held messages join the user's [`QuietSummary`], which the
+/// [`AlertEngine`](crate::alerts::AlertEngine) sharing the store sends when
+/// the quiet hours end.
+pub struct QuietNotifier {
+    inner: Arc<dyn Notifier>,
+    platform: BotPlatform,
+    store: Arc<DeliveryStore>,
+}
+
+impl QuietNotifier {
+    /// Push through `inner` for users on `platform`, outside their quiet
+    /// hours in `store`
+    pub fn new(inner: Arc<dyn Notifier>, platform: BotPlatform, store: Arc<DeliveryStore>) -> Self {
+        Self {
+            inner,
+            platform,
+            store,
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for QuietNotifier {
+    async fn notify(&self, user_id: &str, message: &str) -> Result<()> {
+        if self
+            .store
+            .hold_if_quiet(user_id, self.platform, message, Utc::now())?
+        {
+            return Ok(());
+        }
+        self.inner.notify(user_id, message).await
+    }
+}
+
+/// Reply to a quiet hours or time zone command (`/quiet`, `/deliver`,
+/// `/quiet off`, `/timezone`) from `user_id` on `platform`, for bots with a
+/// delivery `store`
+pub fn command_reply(
+    store: Option<&DeliveryStore>,
+    user_id: &str,
+    platform: BotPlatform,
+    command: &Command,
+) -> Result<String> {
+    let Some(store) = store else {
+        return Ok("🔔 Quiet hours are not enabled on this bot".to_string());
+    };
+    match command {
+        Command::QuietSet { hours, time_zone } => {
+            let preferences =
+                store.set_quiet_hours(user_id, platform, *hours, time_zone.clone())?;
+            Ok(format!(
+                "🌙 Quiet hours set to {preferences}. Notifications in that time arrive as one \
+                 summary at {}.",
+                hours.end.format("%H:%M")
+            ))
+        }
+        Command::QuietOff => {
+            if store.clear_quiet_hours(user_id, platform)? {
+                Ok("🔔 Quiet hours off".to_string())
+            } else {
+                Ok("🔔 No quiet hours were set".to_string())
+            }
+        }
+        Command::Quiet => match store
+            .preferences(user_id, platform)
+            .filter(|preferences| preferences.quiet_hours.is_some())
+        {
+            Some(preferences) => {
+                let queued = store.queued_for(user_id, platform);
+                let mut reply = format!("🌙 Quiet hours: {preferences}");
+                if let (true, Some(hours)) = (queued > 0, preferences.quiet_hours) {
+                    reply.push_str(&format!(
+                        "\n{queued} notification(s) waiting until {}",
+                        hours.end.format("%H:%M")
+                    ));
+                }
+                Ok(reply)
+            }
+            None => Ok(
+                "🔔 No quiet hours. Use /quiet 22:00-07:00 or /deliver 08:00-20:00 to set them, \
+                 in the time zone from /timezone."
+                    .to_string(),
+            ),
+        },
+        Command::TimeZone { zone: Some(zone) } => {
+            store.set_time_zone(user_id, platform, zone.clone())?;
+            let local = zone.to_local(Utc::now());
+            Ok(format!(
+                "🕒 Time zone set to {zone} (now {}). Quiet hours and daily alert limits use it.",
+                local.format("%H:%M")
+            ))
+        }
+        Command::TimeZone { zone: None } => {
+            let zone = store.time_zone(user_id, platform);
+            Ok(format!(
+                "🕒 Time zone: {zone}. Set it with e.g. /timezone America/New_York or /timezone UTC+8."
+            ))
+        }
+        _ => Err(StockError::CommandError(format!(
+            "Not a quiet hours command: /{}",
+            command.name()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone as _;
+
+    fn preferences(hours: &str, time_zone: &str) -> DeliveryPreferences {
+        DeliveryPreferences {
+            user_id: "42".to_string(),
+            platform: BotPlatform::Telegram,
+            time_zone: time_zone.parse().unwrap(),
+            quiet_hours: Some(hours.parse().unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_parse_quiet_hours() {
+        let hours: QuietHours = "22:00-07:00".parse().unwrap();
+        assert_eq!(hours.to_string(), "22:00-07:00");
+        assert!(hours.contains(NaiveTime::from_hms_opt(3, 0, 0).unwrap()));
+        assert!(hours.contains(NaiveTime::from_hms_opt(22, 0, 0).unwrap()));
+        assert!(!hours.contains(NaiveTime::from_hms_opt(7, 0, 0).unwrap()));
+        assert!(!hours.contains(NaiveTime::from_hms_opt(12, 0, 0).unwrap()));
+
+        let window: QuietHours = "08:00-20:00".parse().unwrap();
+        assert_eq!(QuietHours::outside(window).to_string(), "20:00-08:00");
+        assert!("7-22".parse::<QuietHours>().is_err());
+        assert!("07:00-07:00".parse::<QuietHours>().is_err());
+    }
+
+    #[test]
+    fn test_quiet_hours_in_local_time() {
+        let settings = preferences("22:00-07:00", "UTC+8");
+        // 19:00 UTC is 03:00 the next morning in UTC+8
+        let night = Utc.with_ymd_and_hms(2024, 3, 4, 19, 0, 0).unwrap();
+        assert!(settings.is_quiet(night));
+        assert_eq!(
+            settings.local_date(night),
+            NaiveDate::from_ymd_opt(2024, 3, 5).unwrap()
+        );
+        assert_eq!(
+            settings.ends_after(night),
+            Some(Utc.with_ymd_and_hms(2024, 3, 4, 23, 0, 0).unwrap())
+        );
+        // 04:00 UTC is noon in UTC+8
+        let noon = Utc.with_ymd_and_hms(2024, 3, 5, 4, 0, 0).unwrap();
+        assert!(!settings.is_quiet(noon));
+        assert_eq!(
+            settings.ends_after(noon),
+            Some(Utc.with_ymd_and_hms(2024, 3, 5, 23, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_quiet_hours_follow_daylight_saving() {
+        let Ok(new_york) = "America/New_York".parse::<TimeZone>() else {
+            return; // no tzdata on this machine
+        };
+        let settings = DeliveryPreferences {
+            time_zone: new_york,
+            ..preferences("22:00-07:00", "UTC")
+        };
+        // 06:30 EST in January, 06:30 EDT in July
+        assert!(settings.is_quiet(Utc.with_ymd_and_hms(2024, 1, 15, 11, 30, 0).unwrap()));
+        assert!(settings.is_quiet(Utc.with_ymd_and_hms(2024, 7, 15, 10, 30, 0).unwrap()));
+        // 07:30 EDT: a fixed UTC-5 would still call this quiet
+        assert!(!settings.is_quiet(Utc.with_ymd_and_hms(2024, 7, 15, 11, 30, 0).unwrap()));
+    }
+
+    #[test]
+    fn test_queued_notifications_batched_after_quiet_hours() {
+        let path = std::env::temp_dir().join(format!("delivery-{}.json", uuid::Uuid::new_v4()));
+        let store = DeliveryStore::open(&path).unwrap();
+        store
+            .set_quiet_hours(
+                "42",
+                BotPlatform::Telegram,
+                "22:00-07:00".parse().unwrap(),
+                Some("UTC+8".parse().unwrap()),
+            )
+            .unwrap();
+
+        let night = Utc.with_ymd_and_hms(2024, 3, 4, 19, 0, 0).unwrap();
+        assert!(store.is_quiet("42", BotPlatform::Telegram, night));
+        assert!(!store.is_quiet("42", BotPlatform::Slack, night));
+        assert!(
+            store
+                .hold_if_quiet("42", BotPlatform::Telegram, "🔔 Alert #1", night)
+                .unwrap()
+        );
+        assert!(
+            store
+                .hold_if_quiet("42", BotPlatform::Telegram, "🔔 Alert #2", night)
+                .unwrap()
+        );
+        assert!(
+            !store
+                .hold_if_quiet("42", BotPlatform::Slack, "🔔 Alert #3", night)
+                .unwrap()
+        );
+
+        // Still quiet: nothing due. Queue survives a restart
+        assert!(
+            store
+                .take_due(night + Duration::hours(1))
+                .unwrap()
+                .is_empty()
+        );
+        let store = DeliveryStore::open(&path).unwrap();
+        assert_eq!(store.queued_for("42", BotPlatform::Telegram), 2);
+
+        let morning = Utc.with_ymd_and_hms(2024, 3, 4, 23, 5, 0).unwrap();
+        let due = store.take_due(morning).unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(
+            due[0].messages().collect::<Vec<_>>(),
+            vec!["🔔 Alert #1", "🔔 Alert #2"]
+        );
+        assert!(due[0].to_string().starts_with("🌅 2 notification(s)"));
+        assert!(store.take_due(morning).unwrap().is_empty());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_undelivered_summary_requeued_in_order() {
+        let store = DeliveryStore::in_memory();
+        store
+            .set_quiet_hours(
+                "42",
+                BotPlatform::Telegram,
+                "22:00-07:00".parse().unwrap(),
+                None,
+            )
+            .unwrap();
+        let night = Utc.with_ymd_and_hms(2024, 3, 4, 23, 0, 0).unwrap();
+        store
+            .queue("42", BotPlatform::Telegram, "first", night)
+            .unwrap();
+
+        let morning = Utc.with_ymd_and_hms(2024, 3, 5, 8, 0, 0).unwrap();
+        let mut due = store.take_due(morning).unwrap();
+        store
+            .queue("42", BotPlatform::Telegram, "second", morning)
+            .unwrap();
+        store.requeue(due.remove(0)).unwrap();
+
+        let due = store.take_due(morning).unwrap();
+        assert_eq!(
+            due[0].messages().collect::<Vec<_>>(),
+            vec!["first", "second"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_quiet_notifier_holds_messages() {
+        struct Recording(std::sync::Mutex<Vec<String>>);
+
+        #[async_trait]
+        impl Notifier for Recording {
+            async fn notify(&self, _user_id: &str, message: &str) -> Result<()> {
+                self.0.lock().unwrap().push(message.to_string());
+                Ok(())
+            }
+        }
+
+        let store = Arc::new(DeliveryStore::in_memory());
+        let inner = Arc::new(Recording(std::sync::Mutex::new(Vec::new())));
+        let notifier = QuietNotifier::new(inner.clone(), BotPlatform::Telegram, store.clone());
+
+        notifier.notify("42", "sent").await.unwrap();
+        // Quiet around the clock except one minute
+        let now = Utc::now().time();
+        let hours = QuietHours::outside(QuietHours {
+            start: now - Duration::hours(1),
+            end: now - Duration::hours(1) + Duration::minutes(1),
+        });
+        store
+            .set_quiet_hours("42", BotPlatform::Telegram, hours, None)
+            .unwrap();
+        notifier.notify("42", "held").await.unwrap();
+
+        assert_eq!(*inner.0.lock().unwrap(), vec!["sent"]);
+        assert_eq!(store.queued_for("42", BotPlatform::Telegram), 1);
+    }
+
+    #[test]
+    fn test_commands() {
+        let store = DeliveryStore::in_memory();
+        let reply = |input: &str| {
+            command_reply(
+                Some(&store),
+                "42",
+                BotPlatform::Telegram,
+                &Command::parse(input).unwrap(),
+            )
+            .unwrap()
+        };
+
+        assert!(reply("/quiet").contains("No quiet hours"));
+        let set = reply("/quiet 23:00-06:30 UTC+8");
+        assert!(set.contains("23:00-06:30 (UTC+08:00)"), "{set}");
+        // The time zone is kept when only the hours change
+        let set = reply("/deliver 08:00-21:00");
+        assert!(set.contains("21:00-08:00 (UTC+08:00)"), "{set}");
+        assert!(reply("/quiet").contains("21:00-08:00"));
+        assert!(reply("/timezone UTC-5").starts_with("🕒 Time zone set to UTC-05:00"));
+        assert!(reply("/quiet").contains("21:00-08:00 (UTC-05:00)"));
+        assert_eq!(reply("/quiet off"), "🔔 Quiet hours off");
+        assert!(reply("/quiet").contains("No quiet hours"));
+        // Turning quiet hours off keeps the time zone
+        assert!(reply("/timezone").contains("UTC-05:00"));
+
+        let disabled = command_reply(None, "42", BotPlatform::Telegram, &Command::Quiet).unwrap();
+        assert!(disabled.contains("not enabled"));
+    }
+}
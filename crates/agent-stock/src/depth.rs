@@ -0,0 +1,156 @@
+//! Analysis depth levels
+//!
+//! The depth trades answer quality against cost and latency. It is chosen
+//! per request (`/analyze AAPL deep`, [`AnalysisPreferences::depth`]) and
+//! falls back to [`StockConfig::analysis_depth`]:
+//!
+//! | Depth    | LLM calls | Typical latency | Covers                                        |
+//! |----------|-----------|-----------------|-----------------------------------------------|
+//! | quick    | 1         | 5–10 s          | Cached quote and key fundamentals             |
+//! | standard | 5         | 30–60 s         | Technical, fundamental, news, earnings, macro |
+//! | deep     | 8         | 1.5–3 min       | Standard plus SEC filings, peers, scenarios   |
+//!
+//! Standard runs the five specialists in parallel, so its latency is that of
+//! the slowest one. Deep runs the filings review and peer comparison
+//! alongside them, then a scenario analysis that builds on their findings:
+//! two rounds of calls whose prompts pull in filings text and peer data, at
+//! roughly three times the token cost of standard.
+//!
+//! [`AnalysisPreferences::depth`]: crate::engine::context::AnalysisPreferences::depth
+//! [`StockConfig::analysis_depth`]: crate::config::StockConfig::analysis_depth
+
+use agent_core::Context;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::StockError;
+
+/// Context key under which the analysis depth is stored
+pub const DEPTH_CONTEXT_KEY: &str = "analysis_depth";
+
+/// How thorough an analysis is, and so how long it takes and what it costs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnalysisDepth {
+    /// Short summary from the cached quote and key fundamentals
+    ///
+    /// One data-fetcher LLM call; about 5–10 seconds.
+    #[serde(alias = "Quick")]
+    Quick,
+    /// Parallel technical, fundamental, news, earnings and macro analysis
+    /// (default)
+    ///
+    /// Five specialist LLM calls in parallel; about 30–60 seconds.
+    #[default]
+    #[serde(alias = "Standard")]
+    Standard,
+    /// Standard analysis plus SEC filings review, peer comparison and
+    /// bull/base/bear scenarios
+    ///
+    /// Eight specialist LLM calls in two rounds, with larger prompts; about
+    /// 1.5–3 minutes.
+    #[serde(alias = "Deep")]
+    Deep,
+}
+
+impl AnalysisDepth {
+    /// All depth levels, shallowest first
+    pub const ALL: [AnalysisDepth; 3] = [Self::Quick, Self::Standard, Self::Deep];
+
+    /// Parse a depth name (English or Chinese, with common aliases)
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "quick" | "fast" | "brief" | "快速" | "简要" => Some(Self::Quick),
+            "standard" | "normal" | "default" | "标准" => Some(Self::Standard),
+            "deep" | "full" | "thorough" | "深度" | "深入" => Some(Self::Deep),
+            _ => None,
+        }
+    }
+
+    /// Canonical depth name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Quick => "quick",
+            Self::Standard => "standard",
+            Self::Deep => "deep",
+        }
+    }
+
+    /// Short human-readable description, including the expected wait
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Quick => "Short summary from cached data (~10s)",
+            Self::Standard => "Technical, fundamental, news, earnings and macro (~1 min)",
+            Self::Deep => "Standard plus filings, peer comparison and scenarios (~3 min)",
+        }
+    }
+
+    /// Read the depth stored in an agent context
+    pub fn from_context(context: &Context) -> Option<Self> {
+        context.get_typed(DEPTH_CONTEXT_KEY).ok().flatten()
+    }
+
+    /// Store the depth in an agent context
+    pub fn apply_to(self, context: &mut Context) {
+        context.insert(DEPTH_CONTEXT_KEY, Value::String(self.as_str().to_string()));
+    }
+}
+
+impl fmt::Display for AnalysisDepth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AnalysisDepth {
+    type Err = StockError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s).ok_or_else(|| {
+            StockError::ConfigError(format!(
+                "Unknown analysis depth: {s}. Available: quick, standard, deep"
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_depth() {
+        assert_eq!(AnalysisDepth::parse("Quick"), Some(AnalysisDepth::Quick));
+        assert_eq!(
+            AnalysisDepth::parse("normal"),
+            Some(AnalysisDepth::Standard)
+        );
+        assert_eq!(AnalysisDepth::parse("深度"), Some(AnalysisDepth::Deep));
+        assert_eq!(AnalysisDepth::parse("extreme"), None);
+        assert!("extreme".parse::<AnalysisDepth>().is_err());
+    }
+
+    #[test]
+    fn test_context_round_trip() {
+        let mut context = Context::new();
+        assert_eq!(AnalysisDepth::from_context(&context), None);
+
+        AnalysisDepth::Deep.apply_to(&mut context);
+        assert_eq!(
+            AnalysisDepth::from_context(&context),
+            Some(AnalysisDepth::Deep)
+        );
+    }
+
+    #[test]
+    fn test_serde_accepts_legacy_names() {
+        let depth: AnalysisDepth = serde_json::from_str("\"Deep\"").unwrap();
+        assert_eq!(depth, AnalysisDepth::Deep);
+        assert_eq!(
+            serde_json::to_string(&AnalysisDepth::Quick).unwrap(),
+            "\"quick\""
+        );
+    }
+}
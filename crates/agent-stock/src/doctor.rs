@@ -0,0 +1,843 @@
+//! End-to-end health checks for the configured data sources
+//!
+//! [`Doctor`] checks network reachability and the cache, then makes one cheap
+//! real request to each data source and reports whether it answered, how long
+//! it took and, for Yahoo Finance, whether the response still matches the
+//! expected schema. Sources that need an API key are skipped when the key is
+//! not configured. A final check reports any provider whose circuit breaker
+//! is open (see [`crate::api::circuit`]). Failed checks carry an actionable fix (e.g. which
+//! environment variable holds a rejected key).
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::{StockConfig, doctor::Doctor};
+//!
+//! let config = StockConfig::builder().with_env_all_keys().build()?;
+//! let report = Doctor::from_config(&config).run().await;
+//! println!("{report}");
+//! assert!(report.is_healthy());
+//! ```
+
+use chrono::Utc;
+use serde_json::json;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::api::circuit::BREAKER_COOLDOWN_ENV;
+use crate::api::yahoo_schema::ParserPath;
+use crate::api::{
+    AlphaVantageClient, BreakerStatus, CircuitState, FinnhubClient, FredClient, SecEdgarClient,
+    YahooFinanceClient,
+};
+use crate::cache::{CacheKey, CacheManager, REDIS_URL_ENV, shared_cache};
+use crate::config::{
+    ALPHA_VANTAGE_API_KEY_ENV, ALPHA_VANTAGE_PREMIUM_ENV, FINNHUB_API_KEY_ENV, FRED_API_KEY_ENV,
+    StockConfig,
+};
+use crate::error::{Result, StockError};
+
+/// Symbol used to exercise the equity data sources
+const CHECK_SYMBOL: &str = "AAPL";
+
+/// Series used to exercise FRED
+const CHECK_SERIES: &str = "FEDFUNDS";
+
+/// How long to wait for a TCP connection in the reachability check
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Host of every data source that needs no API key
+const KEYLESS_HOSTS: &[&str] = &["query1.finance.yahoo.com", "data.sec.gov", "www.sec.gov"];
+
+/// Proxy variables that make direct TCP reachability meaningless
+const PROXY_VARS: &[&str] = &["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy"];
+
+/// A data source that needs an API key
+struct KeyedSource {
+    name: &'static str,
+    env_var: &'static str,
+    host: &'static str,
+    signup_url: &'static str,
+}
+
+const FRED: KeyedSource = KeyedSource {
+    name: "FRED",
+    env_var: FRED_API_KEY_ENV,
+    host: "api.stlouisfed.org",
+    signup_url: "https://fred.stlouisfed.org/docs/api/api_key.html",
+};
+
+const FINNHUB: KeyedSource = KeyedSource {
+    name: "Finnhub",
+    env_var: FINNHUB_API_KEY_ENV,
+    host: "finnhub.io",
+    signup_url: "https://finnhub.io/register",
+};
+
+const ALPHA_VANTAGE: KeyedSource = KeyedSource {
+    name: "Alpha Vantage",
+    env_var: ALPHA_VANTAGE_API_KEY_ENV,
+    host: "www.alphavantage.co",
+    signup_url: "https://www.alphavantage.co/support/#api-key",
+};
+
+/// Outcome of a single check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    /// The source answered as expected
+    Ok,
+    /// The source answered, but something needs attention (e.g. schema drift)
+    Warn,
+    /// The source is unusable
+    Fail,
+    /// The source is not configured
+    Skipped,
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Self::Ok => "OK",
+            Self::Warn => "WARN",
+            Self::Fail => "FAIL",
+            Self::Skipped => "SKIP",
+        };
+        f.pad(label)
+    }
+}
+
+/// Result of checking one component
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    /// Component name, e.g. "Yahoo Finance" or "MCP filesystem"
+    pub source: String,
+    pub status: CheckStatus,
+    /// What was observed
+    pub detail: String,
+    /// What to do about a failure or warning
+    pub fix: Option<String>,
+    /// Round-trip time, for checks that made requests
+    pub latency: Option<Duration>,
+}
+
+impl CheckResult {
+    /// A result without a fix or latency
+    pub fn new(source: impl Into<String>, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+            status,
+            detail: detail.into(),
+            fix: None,
+            latency: None,
+        }
+    }
+
+    /// Attach an actionable fix
+    pub fn with_fix(mut self, fix: impl Into<String>) -> Self {
+        self.fix = Some(fix.into());
+        self
+    }
+
+    /// Record the time elapsed since `started`
+    pub fn with_latency(mut self, started: Instant) -> Self {
+        self.latency = Some(started.elapsed());
+        self
+    }
+
+    fn skipped(source: &KeyedSource) -> Self {
+        Self::new(
+            source.name,
+            CheckStatus::Skipped,
+            format!("{} not set", source.env_var),
+        )
+        .with_fix(format!(
+            "Optional: get a key at {} and export {}",
+            source.signup_url, source.env_var
+        ))
+    }
+
+    /// Result of a data source request; errors become failures with a fix
+    fn from_outcome(
+        source: &str,
+        key: Option<&KeyedSource>,
+        started: Instant,
+        outcome: Result<Self>,
+    ) -> Self {
+        outcome
+            .unwrap_or_else(|e| {
+                let fix = fix_for(&e, key);
+                let result = Self::new(source, CheckStatus::Fail, e.to_string());
+                match fix {
+                    Some(fix) => result.with_fix(fix),
+                    None => result,
+                }
+            })
+            .with_latency(started)
+    }
+}
+
+/// Suggest a fix for a failed data source request
+fn fix_for(error: &StockError, key: Option<&KeyedSource>) -> Option<String> {
+    match error {
+        StockError::NetworkError(e) if e.is_connect() || e.is_timeout() => Some(unreachable_fix()),
+        StockError::ProviderUnavailable { .. } => Some(breaker_fix()),
+        StockError::RateLimitExceeded { .. } => {
+            Some("Rate limited: wait a minute and retry, or lower request volume".to_string())
+        }
+        _ => {
+            let message = error.to_string();
+            let rejected = ["401", "403", "api_key", "API key", "apikey"]
+                .iter()
+                .any(|hint| message.contains(hint));
+            match key {
+                Some(key) if rejected || matches!(error, StockError::AlphaVantageError(_)) => {
+                    Some(format!(
+                        "{} was rejected: check {} for typos or get a new key at {}",
+                        key.name, key.env_var, key.signup_url
+                    ))
+                }
+                _ => None,
+            }
+        }
+    }
+}
+
+fn unreachable_fix() -> String {
+    "The host is unreachable: check your internet connection, firewall or proxy \
+     (set HTTPS_PROXY if you need one)"
+        .to_string()
+}
+
+fn breaker_fix() -> String {
+    format!(
+        "Requests resume automatically once a probe succeeds after the cooldown \
+         ({BREAKER_COOLDOWN_ENV})"
+    )
+}
+
+impl fmt::Display for CheckResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{:>4}] {:<14} {}",
+            self.status, self.source, self.detail
+        )?;
+        if let Some(latency) = self.latency {
+            write!(f, " ({} ms)", latency.as_millis())?;
+        }
+        if let Some(fix) = &self.fix
+            && self.status != CheckStatus::Ok
+        {
+            write!(f, "\n       fix: {fix}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Results of all checks
+#[derive(Debug, Clone, Default)]
+pub struct DoctorReport {
+    pub checks: Vec<CheckResult>,
+}
+
+impl DoctorReport {
+    /// Whether no check failed; warnings and skipped sources are allowed
+    pub fn is_healthy(&self) -> bool {
+        self.checks.iter().all(|c| c.status != CheckStatus::Fail)
+    }
+
+    /// Result for a component, by name
+    pub fn get(&self, source: &str) -> Option<&CheckResult> {
+        self.checks.iter().find(|c| c.source == source)
+    }
+
+    /// Add results from checks run elsewhere (e.g. LLM or MCP checks)
+    pub fn extend(&mut self, checks: impl IntoIterator<Item = CheckResult>) {
+        self.checks.extend(checks);
+    }
+}
+
+impl fmt::Display for DoctorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            writeln!(f, "{check}")?;
+        }
+        let count = |status| self.checks.iter().filter(|c| c.status == status).count();
+        write!(
+            f,
+            "{} ok, {} warnings, {} failed, {} skipped",
+            count(CheckStatus::Ok),
+            count(CheckStatus::Warn),
+            count(CheckStatus::Fail),
+            count(CheckStatus::Skipped)
+        )
+    }
+}
+
+/// Runs end-to-end checks against each data source
+pub struct Doctor {
+    yahoo: YahooFinanceClient,
+    sec: SecEdgarClient,
+    fred: Option<FredClient>,
+    finnhub: Option<FinnhubClient>,
+    alpha_vantage: Option<AlphaVantageClient>,
+    cache: CacheManager,
+    /// `host:port` pairs for the reachability check; `None` derives them from
+    /// the configured sources
+    hosts: Option<Vec<String>>,
+}
+
+impl Doctor {
+    /// Clients for every data source configured in `config`
+    pub fn from_config(config: &StockConfig) -> Self {
+        Self {
+            yahoo: YahooFinanceClient::new(),
+            sec: SecEdgarClient::new(&config.sec_user_agent, &config.sec_contact_email),
+            fred: config
+                .fred_api_key
+                .as_ref()
+                .map(|key| FredClient::new(key, None)),
+            finnhub: config
+                .finnhub_api_key
+                .as_ref()
+                .map(|key| FinnhubClient::new(key, 60)),
+            alpha_vantage: AlphaVantageClient::from_config(config),
+            cache: shared_cache().clone(),
+            hosts: None,
+        }
+    }
+
+    /// Check this Yahoo Finance client instead
+    pub fn with_yahoo(mut self, client: YahooFinanceClient) -> Self {
+        self.yahoo = client;
+        self
+    }
+
+    /// Check this SEC EDGAR client instead
+    pub fn with_sec(mut self, client: SecEdgarClient) -> Self {
+        self.sec = client;
+        self
+    }
+
+    /// Check this FRED client instead (`None` skips FRED)
+    pub fn with_fred(mut self, client: Option<FredClient>) -> Self {
+        self.fred = client;
+        self
+    }
+
+    /// Check this Finnhub client instead (`None` skips Finnhub)
+    pub fn with_finnhub(mut self, client: Option<FinnhubClient>) -> Self {
+        self.finnhub = client;
+        self
+    }
+
+    /// Check this Alpha Vantage client instead (`None` skips Alpha Vantage)
+    pub fn with_alpha_vantage(mut self, client: Option<AlphaVantageClient>) -> Self {
+        self.alpha_vantage = client;
+        self
+    }
+
+    /// Check this cache instead of the shared one
+    pub fn with_cache(mut self, cache: CacheManager) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Check reachability of these `host:port` pairs instead
+    pub fn with_hosts(mut self, hosts: Vec<String>) -> Self {
+        self.hosts = Some(hosts);
+        self
+    }
+
+    /// Run every check concurrently
+    pub async fn run(&self) -> DoctorReport {
+        let (network, cache, yahoo, sec, fred, finnhub, alpha_vantage) = futures::join!(
+            self.check_network(),
+            self.check_cache(),
+            self.check_yahoo(),
+            self.check_sec(),
+            self.check_fred(),
+            self.check_finnhub(),
+            self.check_alpha_vantage(),
+        );
+        // After the data source checks, which may have tripped or closed them
+        let breakers = self.check_breakers();
+        DoctorReport {
+            checks: vec![
+                network,
+                cache,
+                yahoo,
+                sec,
+                fred,
+                finnhub,
+                alpha_vantage,
+                breakers,
+            ],
+        }
+    }
+
+    fn check_breakers(&self) -> CheckResult {
+        let statuses: Vec<BreakerStatus> = [
+            Some(self.yahoo.breaker()),
+            Some(self.sec.breaker()),
+            self.fred.as_ref().map(FredClient::breaker),
+            self.finnhub.as_ref().map(FinnhubClient::breaker),
+            self.alpha_vantage.as_ref().map(AlphaVantageClient::breaker),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|breaker| breaker.status())
+        .collect();
+
+        let tripped: Vec<String> = statuses
+            .iter()
+            .filter(|status| status.state != CircuitState::Closed)
+            .map(ToString::to_string)
+            .collect();
+        if tripped.is_empty() {
+            CheckResult::new(
+                "Circuit breakers",
+                CheckStatus::Ok,
+                format!("{} closed", statuses.len()),
+            )
+        } else {
+            CheckResult::new("Circuit breakers", CheckStatus::Warn, tripped.join("; "))
+                .with_fix(breaker_fix())
+        }
+    }
+
+    async fn check_network(&self) -> CheckResult {
+        let hosts = self.hosts.clone().unwrap_or_else(|| self.default_hosts());
+        if self.hosts.is_none()
+            && let Some(var) = PROXY_VARS.iter().find(|v| std::env::var_os(v).is_some())
+        {
+            return CheckResult::new(
+                "Network",
+                CheckStatus::Skipped,
+                format!("{var} is set; reachability is covered by the data source checks"),
+            );
+        }
+
+        let started = Instant::now();
+        let results = futures::future::join_all(hosts.iter().map(|host| async move {
+            let reachable = matches!(
+                tokio::time::timeout(
+                    CONNECT_TIMEOUT,
+                    tokio::net::TcpStream::connect(host.as_str())
+                )
+                .await,
+                Ok(Ok(_))
+            );
+            (host, reachable)
+        }))
+        .await;
+        let unreachable: Vec<&str> = results
+            .iter()
+            .filter(|(_, reachable)| !reachable)
+            .map(|(host, _)| host.as_str())
+            .collect();
+
+        let result = if unreachable.is_empty() {
+            CheckResult::new(
+                "Network",
+                CheckStatus::Ok,
+                format!("{} hosts reachable", hosts.len()),
+            )
+        } else if unreachable.len() == hosts.len() {
+            CheckResult::new(
+                "Network",
+                CheckStatus::Fail,
+                "no data source host is reachable",
+            )
+            .with_fix("Check your internet connection and DNS; behind a proxy, export HTTPS_PROXY")
+        } else {
+            CheckResult::new(
+                "Network",
+                CheckStatus::Fail,
+                format!("unreachable: {}", unreachable.join(", ")),
+            )
+            .with_fix("A firewall may be blocking these hosts; allow outbound HTTPS (port 443)")
+        };
+        result.with_latency(started)
+    }
+
+    fn default_hosts(&self) -> Vec<String> {
+        let keyed = [
+            (self.fred.is_some(), FRED.host),
+            (self.finnhub.is_some(), FINNHUB.host),
+            (self.alpha_vantage.is_some(), ALPHA_VANTAGE.host),
+        ];
+        KEYLESS_HOSTS
+            .iter()
+            .copied()
+            .chain(keyed.iter().filter(|(on, _)| *on).map(|(_, host)| *host))
+            .map(|host| format!("{host}:443"))
+            .collect()
+    }
+
+    async fn check_cache(&self) -> CheckResult {
+        let started = Instant::now();
+        let tiers = [
+            ("realtime", &self.cache.realtime),
+            ("fundamental", &self.cache.fundamental),
+            ("news", &self.cache.news),
+            ("earnings", &self.cache.earnings),
+            ("macro", &self.cache.macro_data),
+            ("sector", &self.cache.sector),
+        ];
+        let probe = json!({ "probe": started.elapsed().as_nanos().to_string() });
+
+        let mut broken = Vec::new();
+        for (name, cache) in tiers {
+            let key = CacheKey::new("__doctor__", "probe", name);
+            cache.insert(key.clone(), probe.clone()).await;
+            if cache.get(&key).await.as_ref() != Some(&probe) {
+                broken.push(name);
+            }
+            cache.invalidate(&key).await;
+        }
+
+        let backend = self.cache.backend_name();
+        let result = if broken.is_empty() {
+            let entries = self.cache.stats().await.total();
+            CheckResult::new(
+                "Cache",
+                CheckStatus::Ok,
+                format!("6 {backend} tiers read back, {entries} entries cached"),
+            )
+        } else if backend == "redis" {
+            CheckResult::new(
+                "Cache",
+                CheckStatus::Fail,
+                format!(
+                    "values do not read back from Redis in: {}",
+                    broken.join(", ")
+                ),
+            )
+            .with_fix(format!(
+                "Check that the Redis server in {REDIS_URL_ENV} is running and reachable"
+            ))
+        } else {
+            CheckResult::new(
+                "Cache",
+                CheckStatus::Fail,
+                format!("values expire immediately in: {}", broken.join(", ")),
+            )
+            .with_fix("Cache TTLs must be at least one second; raise them with init_shared_cache")
+        };
+        result.with_latency(started)
+    }
+
+    async fn check_yahoo(&self) -> CheckResult {
+        let source = "Yahoo Finance";
+        let started = Instant::now();
+        let outcome = self.yahoo.check_chart(CHECK_SYMBOL).await.map(|chart| {
+            let Some(last) = chart.quotes.last() else {
+                return CheckResult::new(
+                    source,
+                    CheckStatus::Warn,
+                    format!("no bars returned for {CHECK_SYMBOL}"),
+                )
+                .with_fix("Yahoo may be throttling this IP; retry in a few minutes");
+            };
+            let bars = format!(
+                "{} bars for {CHECK_SYMBOL}, last close {:.2}",
+                chart.quotes.len(),
+                last.close
+            );
+            match chart.parser {
+                ParserPath::Strict => CheckResult::new(source, CheckStatus::Ok, bars),
+                ParserPath::Fallback => CheckResult::new(
+                    source,
+                    CheckStatus::Warn,
+                    format!("{bars} via fallback parser; schema drift: {}", chart.report),
+                )
+                .with_fix(
+                    "Yahoo changed its response format; quotes still work, but update agent-stock",
+                ),
+            }
+        });
+        let mut result = CheckResult::from_outcome(source, None, started, outcome);
+        if result.status == CheckStatus::Fail && result.detail.contains("Unrecognized chart") {
+            result = result.with_fix(
+                "Yahoo changed its response format beyond the fallback parser; update agent-stock",
+            );
+        }
+        result
+    }
+
+    async fn check_sec(&self) -> CheckResult {
+        let source = "SEC EDGAR";
+        let started = Instant::now();
+        let outcome = async {
+            let cik = self.sec.resolve_cik(CHECK_SYMBOL).await?;
+            let submissions = self.sec.get_company_submissions(&cik).await?;
+            Ok(CheckResult::new(
+                source,
+                CheckStatus::Ok,
+                format!(
+                    "CIK {cik} ({}), {} recent filings",
+                    submissions.name,
+                    submissions.filings.recent.form.len()
+                ),
+            ))
+        }
+        .await;
+        let result = CheckResult::from_outcome(source, None, started, outcome);
+        if result.status == CheckStatus::Fail && result.detail.contains("403") {
+            return result.with_fix(
+                "SEC blocks generic clients: set a real company name and contact email \
+                 (StockConfig::sec_user_agent / sec_contact_email)",
+            );
+        }
+        result
+    }
+
+    /// Check FRED alone, e.g. to verify a key as it is entered
+    pub async fn check_fred(&self) -> CheckResult {
+        let Some(fred) = &self.fred else {
+            return CheckResult::skipped(&FRED);
+        };
+        let started = Instant::now();
+        let outcome = fred.get_latest(CHECK_SERIES).await.map(|observation| {
+            CheckResult::new(
+                FRED.name,
+                CheckStatus::Ok,
+                format!(
+                    "{CHECK_SERIES} = {} on {}",
+                    observation.value, observation.date
+                ),
+            )
+        });
+        CheckResult::from_outcome(FRED.name, Some(&FRED), started, outcome)
+    }
+
+    /// Check Finnhub alone
+    pub async fn check_finnhub(&self) -> CheckResult {
+        let Some(finnhub) = &self.finnhub else {
+            return CheckResult::skipped(&FINNHUB);
+        };
+        let started = Instant::now();
+        let to = Utc::now().date_naive();
+        let from = to - chrono::Duration::days(7);
+        let outcome = finnhub
+            .get_company_news(CHECK_SYMBOL, &from.to_string(), &to.to_string())
+            .await
+            .map(|articles| {
+                CheckResult::new(
+                    FINNHUB.name,
+                    CheckStatus::Ok,
+                    format!(
+                        "{} articles for {CHECK_SYMBOL} in the last week",
+                        articles.len()
+                    ),
+                )
+            });
+        CheckResult::from_outcome(FINNHUB.name, Some(&FINNHUB), started, outcome)
+    }
+
+    /// Check Alpha Vantage alone
+    pub async fn check_alpha_vantage(&self) -> CheckResult {
+        let Some(alpha_vantage) = &self.alpha_vantage else {
+            return CheckResult::skipped(&ALPHA_VANTAGE);
+        };
+        let started = Instant::now();
+        let quote = match alpha_vantage.get_quote(CHECK_SYMBOL).await {
+            // An exhausted quota says nothing about whether the key works
+            Err(StockError::RateLimitExceeded { .. }) => {
+                return CheckResult::new(
+                    ALPHA_VANTAGE.name,
+                    CheckStatus::Warn,
+                    "rate limited by Alpha Vantage",
+                )
+                .with_fix(format!(
+                    "Wait for the quota to reset, or set {ALPHA_VANTAGE_PREMIUM_ENV} if your key \
+                     is on a premium plan"
+                ))
+                .with_latency(started);
+            }
+            quote => quote,
+        };
+        let outcome = quote.map(|quote| {
+            match quote
+                .pointer("/Global Quote/05. price")
+                .and_then(serde_json::Value::as_str)
+            {
+                Some(price) => CheckResult::new(
+                    ALPHA_VANTAGE.name,
+                    CheckStatus::Ok,
+                    format!("{CHECK_SYMBOL} at {price}"),
+                ),
+                // Invalid keys and exhausted daily quotas come back as a 200
+                // with an "Information" message instead of a quote
+                None => CheckResult::new(
+                    ALPHA_VANTAGE.name,
+                    CheckStatus::Warn,
+                    quote
+                        .get("Information")
+                        .and_then(serde_json::Value::as_str)
+                        .unwrap_or("response has no \"Global Quote\" price")
+                        .to_string(),
+                )
+                .with_fix(format!(
+                    "Check {} or wait for the daily quota to reset",
+                    ALPHA_VANTAGE.env_var
+                )),
+            }
+        });
+        CheckResult::from_outcome(ALPHA_VANTAGE.name, Some(&ALPHA_VANTAGE), started, outcome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::testing::{MockApi, fixtures};
+    use crate::cache::CacheTtlConfig;
+
+    fn doctor(api: &MockApi) -> Doctor {
+        Doctor::from_config(&StockConfig::default())
+            .with_yahoo(api.yahoo())
+            .with_sec(api.sec())
+            .with_fred(Some(api.fred(None)))
+            .with_finnhub(Some(api.finnhub(60)))
+            .with_alpha_vantage(None)
+            .with_cache(CacheManager::default_config())
+            .with_hosts(vec![api.uri().trim_start_matches("http://").to_string()])
+    }
+
+    #[tokio::test]
+    async fn test_all_sources_healthy() {
+        let api = MockApi::recorded().await;
+        let report = doctor(&api).run().await;
+
+        assert!(report.is_healthy(), "{report}");
+        for source in [
+            "Network",
+            "Cache",
+            "Yahoo Finance",
+            "SEC EDGAR",
+            "FRED",
+            "Finnhub",
+            "Circuit breakers",
+        ] {
+            assert_eq!(
+                report.get(source).unwrap().status,
+                CheckStatus::Ok,
+                "{report}"
+            );
+        }
+        let skipped = report.get("Alpha Vantage").unwrap();
+        assert_eq!(skipped.status, CheckStatus::Skipped);
+        assert!(skipped.detail.contains("ALPHA_VANTAGE_API_KEY"));
+        assert!(
+            report
+                .to_string()
+                .ends_with("7 ok, 0 warnings, 0 failed, 1 skipped")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_schema_drift_warns_and_outage_fails() {
+        let api = MockApi::start().await;
+        api.mount_json("/v8/finance/chart/AAPL", 200, fixtures::YAHOO_CHART_LEGACY)
+            .await;
+        let report = doctor(&api).with_fred(None).with_finnhub(None).run().await;
+
+        let yahoo = report.get("Yahoo Finance").unwrap();
+        assert_eq!(yahoo.status, CheckStatus::Warn);
+        assert!(
+            yahoo.detail.contains("indicators.quote"),
+            "{}",
+            yahoo.detail
+        );
+        assert!(yahoo.fix.is_some());
+
+        // No SEC routes are mounted, so the ticker lookup fails
+        assert_eq!(report.get("SEC EDGAR").unwrap().status, CheckStatus::Fail);
+        assert!(!report.is_healthy());
+    }
+
+    #[tokio::test]
+    async fn test_open_breaker_warns_and_fails_fast() {
+        let api = MockApi::recorded().await;
+        let breaker = api.yahoo().breaker();
+        while breaker.status().state != CircuitState::Open {
+            breaker.record_failure();
+        }
+        let requests = api.request_count().await;
+        let check = doctor(&api).check_yahoo().await;
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert!(check.fix.as_deref().unwrap().contains(BREAKER_COOLDOWN_ENV));
+        assert_eq!(api.request_count().await, requests);
+
+        let breakers = doctor(&api).check_breakers();
+        assert_eq!(breakers.status, CheckStatus::Warn);
+        assert!(
+            breakers.detail.starts_with("Yahoo Finance open"),
+            "{breakers}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejected_key_names_env_var() {
+        let api = MockApi::start().await;
+        api.mount_json(
+            "/api/v1/company-news",
+            401,
+            r#"{"error":"Invalid API key"}"#,
+        )
+        .await;
+        let report = doctor(&api).run().await;
+
+        let finnhub = report.get("Finnhub").unwrap();
+        assert_eq!(finnhub.status, CheckStatus::Fail);
+        let fix = finnhub.fix.as_deref().unwrap();
+        assert!(fix.contains("FINNHUB_API_KEY"), "{fix}");
+        assert!(report.to_string().contains("fix: Finnhub was rejected"));
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_hosts_and_expiring_cache_fail() {
+        let api = MockApi::recorded().await;
+        let zero = Duration::ZERO;
+        let report = doctor(&api)
+            .with_hosts(vec!["127.0.0.1:1".to_string()])
+            .with_cache(CacheManager::with_config(CacheTtlConfig {
+                realtime: zero,
+                ..CacheTtlConfig::default()
+            }))
+            .run()
+            .await;
+
+        let network = report.get("Network").unwrap();
+        assert_eq!(network.status, CheckStatus::Fail);
+        assert!(network.fix.as_deref().unwrap().contains("HTTPS_PROXY"));
+
+        let cache = report.get("Cache").unwrap();
+        assert_eq!(cache.status, CheckStatus::Fail, "{cache}");
+        assert!(cache.detail.ends_with("realtime"), "{}", cache.detail);
+    }
+
+    #[tokio::test]
+    async fn test_alpha_vantage_quota_warns() {
+        let api = MockApi::start().await;
+        let quota = serde_json::json!({
+            "Information": "Our standard API rate limit is 25 requests per day."
+        });
+        api.mount_json("/query", 200, &quota.to_string()).await;
+
+        let check = doctor(&api)
+            .with_alpha_vantage(Some(api.alpha_vantage(false)))
+            .check_alpha_vantage()
+            .await;
+        assert_eq!(check.status, CheckStatus::Warn, "{check}");
+        assert!(
+            check
+                .fix
+                .as_deref()
+                .unwrap()
+                .contains("ALPHA_VANTAGE_PREMIUM")
+        );
+    }
+}
@@ -1,91 +1,468 @@
 //! Stock Analysis Engine - delegates to existing StockAnalysisAgent
 
-use crate::agents::StockAnalysisAgent;
+use crate::agents::{StockAnalysisAgent, TranslatorAgent};
+use crate::as_of;
+use crate::cache::CacheManager;
 use crate::config::StockConfig;
-use crate::error::Result;
+use crate::depth::AnalysisDepth;
+use crate::error::{Result, StockError};
+use crate::idempotency::{IdempotencyCache, Idempotent};
+use crate::interface::sparkline::CHART_DATA_KEY;
+use crate::jobs::{ANALYZE_JOB, AnalyzeJob, Job, JobHandler};
+use crate::language::TRANSLATION_DATA_KEY;
+use crate::news_digest::NewsDigestCollector;
+use crate::plugin::AnalyzerPlugin;
 use crate::router::SmartRouter;
+use crate::screener::{Screen, ScreenResult};
+use crate::source_translation::SourceTranslator;
+use crate::tools::time_compare::TIME_COMPARISON_DATA_KEY;
+use crate::tools::{
+    ChartDataTool, DividendReport, DividendTool, ScreenerTool, ThemeBasket, TimeComparisonTool,
+};
 use agent_runtime::AgentRuntime;
+use agent_runtime::usage::{self, UsageTracker};
+use agent_tools::Tool;
+use async_trait::async_trait;
+use chrono::{NaiveDate, Utc};
+use serde_json::{Value, json};
 use std::sync::Arc;
 
 use super::context::AnalysisContext;
 use super::result::{AnalysisResult, AnalysisType, ComparisonResult};
+use super::snapshot::{Snapshot, SnapshotSource};
+
+/// Key of the tokens and cost of an analysis in [`AnalysisResult::data`]
+pub const USAGE_DATA_KEY: &str = "token_usage";
+
+/// Key of the date of a point-in-time analysis in [`AnalysisResult::data`]
+pub const AS_OF_DATA_KEY: &str = "as_of";
+
+/// Key of the ranked stories behind a news digest in [`AnalysisResult::data`]
+pub const NEWS_DIGEST_DATA_KEY: &str = "news_digest";
 
 /// Stock Analysis Engine - wrapper around StockAnalysisAgent
 pub struct StockAnalysisEngine {
     agent: StockAnalysisAgent,
+    translator: TranslatorAgent,
     router: SmartRouter,
+    chart_tool: ChartDataTool,
+    time_comparison_tool: TimeComparisonTool,
+    dividend_tool: DividendTool,
+    screener_tool: ScreenerTool,
+    snapshots: SnapshotSource,
+    news_digest: NewsDigestCollector,
+    token_tracker: Arc<UsageTracker>,
+    idempotency: IdempotencyCache<AnalysisResult>,
 }
 
 impl StockAnalysisEngine {
     pub async fn new(runtime: Arc<AgentRuntime>, config: Arc<StockConfig>) -> Result<Self> {
-        let agent = StockAnalysisAgent::new(runtime, config).await?;
-        let router = SmartRouter::new();
-        
-        Ok(Self { agent, router })
+        Self::with_plugins(runtime, config, &[]).await
+    }
+
+    /// Engine whose agent includes third-party analyzers
+    ///
+    /// See [`StockAnalysisAgent::with_plugins`].
+    pub async fn with_plugins(
+        runtime: Arc<AgentRuntime>,
+        config: Arc<StockConfig>,
+        plugins: &[Arc<dyn AnalyzerPlugin>],
+    ) -> Result<Self> {
+        let caches = CacheManager::from_config(&config);
+        let chart_tool = ChartDataTool::new(config.clone(), caches.realtime.clone());
+        let time_comparison_tool =
+            TimeComparisonTool::new(config.clone(), caches.fundamental.clone());
+        let dividend_tool = DividendTool::new(caches.fundamental.clone());
+        let screener_tool =
+            ScreenerTool::new(&config, caches.fundamental.clone(), caches.realtime.clone());
+        let snapshots = SnapshotSource::new(config.clone());
+        let news_digest = NewsDigestCollector::new(&config)
+            .with_translator(SourceTranslator::from_config(&runtime, &config));
+        let token_tracker = Arc::clone(runtime.token_tracker());
+        let idempotency = IdempotencyCache::new(config.idempotency_retention);
+        let translator = TranslatorAgent::new(&runtime, config.clone())?;
+        let agent = StockAnalysisAgent::with_plugins(runtime, config, plugins).await?;
+        let router = agent.router().clone();
+
+        Ok(Self {
+            agent,
+            translator,
+            router,
+            chart_tool,
+            time_comparison_tool,
+            dividend_tool,
+            screener_tool,
+            snapshots,
+            news_digest,
+            token_tracker,
+            idempotency,
+        })
+    }
+
+    /// Attach price and RSI series for inline sparklines, if available
+    async fn with_chart(&self, result: AnalysisResult, as_of: Option<NaiveDate>) -> AnalysisResult {
+        let params = json!({
+            "symbol": result.symbol,
+            "range": "3mo",
+            "indicators": ["RSI_14"],
+            "as_of": as_of,
+        });
+        match self.chart_tool.execute(params).await {
+            Ok(chart) => result.with_data(CHART_DATA_KEY, chart),
+            Err(e) => {
+                tracing::warn!("Chart data unavailable for {}: {}", result.symbol, e);
+                result
+            }
+        }
+    }
+
+    /// Attach a translation of the content for bilingual sessions
+    ///
+    /// The translation goes under [`TRANSLATION_DATA_KEY`] with the languages
+    /// it is from and to; if it fails, the result is kept in one language
+    /// with a warning.
+    async fn translated(
+        &self,
+        mut result: AnalysisResult,
+        agent_ctx: &mut agent_core::Context,
+    ) -> AnalysisResult {
+        if !self.translator.is_bilingual(agent_ctx) {
+            return result;
+        }
+        match self.translator.translate(&result.content, agent_ctx).await {
+            Ok(translation) => result.with_data(
+                TRANSLATION_DATA_KEY,
+                json!({
+                    "from": self.translator.source_language().code(),
+                    "to": self.translator.target_language().code(),
+                    "content": translation,
+                }),
+            ),
+            Err(e) => {
+                tracing::warn!("Translation unavailable for {}: {}", result.symbol, e);
+                result.add_warning(format!("Translation unavailable: {e}"));
+                result
+            }
+        }
     }
-    
+
+    /// Latest price and headline, ready in seconds while
+    /// [`StockAnalysisEngine::analyze_stock`] runs
+    pub async fn quick_snapshot(&self, symbol: &str) -> Result<Snapshot> {
+        self.snapshots.snapshot(symbol).await
+    }
+
+    /// Dividend yield and history, without calling the LLM
+    pub async fn dividends(&self, symbol: &str) -> Result<DividendReport> {
+        self.dividend_tool.report(symbol).await
+    }
+
+    /// Run a stock screen over the configured universe, without calling the LLM
+    pub async fn screen(&self, screen: Screen) -> Result<ScreenResult> {
+        self.screener_tool.screen(screen).await
+    }
+
+    /// Comprehensive analysis at the session's preferred depth
     pub async fn analyze_stock(
         &self,
         symbol: &str,
-        _ctx: &mut AnalysisContext,
+        ctx: &mut AnalysisContext,
     ) -> Result<AnalysisResult> {
-        let content = self.agent.analyze(symbol).await?;
-        Ok(AnalysisResult::new(symbol, AnalysisType::Comprehensive, content))
+        let depth = ctx.preferences.depth;
+        self.analyze_stock_at(symbol, depth, ctx).await
     }
-    
+
+    /// Comprehensive analysis at an explicit depth
+    ///
+    /// See [`AnalysisDepth`] for what each level costs and how long it takes.
+    /// The tokens and estimated cost of the analysis are attached under
+    /// [`USAGE_DATA_KEY`].
+    pub async fn analyze_stock_at(
+        &self,
+        symbol: &str,
+        depth: AnalysisDepth,
+        ctx: &mut AnalysisContext,
+    ) -> Result<AnalysisResult> {
+        self.run_analysis(symbol, depth, None, ctx).await
+    }
+
+    /// Comprehensive analysis as it would have been made at the end of `date`
+    ///
+    /// Only data known by then is used; see [`crate::as_of`]. The date is
+    /// attached under [`AS_OF_DATA_KEY`].
+    pub async fn analyze_stock_as_of(
+        &self,
+        symbol: &str,
+        depth: AnalysisDepth,
+        date: NaiveDate,
+        ctx: &mut AnalysisContext,
+    ) -> Result<AnalysisResult> {
+        let date = as_of::validate(date)?;
+        self.run_analysis(symbol, depth, Some(date), ctx).await
+    }
+
+    async fn run_analysis(
+        &self,
+        symbol: &str,
+        depth: AnalysisDepth,
+        as_of: Option<NaiveDate>,
+        ctx: &mut AnalysisContext,
+    ) -> Result<AnalysisResult> {
+        let mut agent_ctx = ctx.agent_context();
+        depth.apply_to(&mut agent_ctx);
+        if let Some(date) = as_of {
+            as_of::apply_to(date, &mut agent_ctx);
+        }
+        let content = self.agent.analyze(symbol, &mut agent_ctx).await?;
+        let mut result = match as_of {
+            Some(date) => AnalysisResult::new(
+                symbol,
+                AnalysisType::Comprehensive,
+                format!("🕰️ As of {date}\n\n{content}"),
+            )
+            .with_data(AS_OF_DATA_KEY, json!(date)),
+            None => AnalysisResult::new(symbol, AnalysisType::Comprehensive, content),
+        };
+        result = self.translated(result, &mut agent_ctx).await;
+        let usage = usage::from_context(&agent_ctx);
+        if !usage.is_empty() {
+            result = result.with_data(USAGE_DATA_KEY, json!(usage));
+        }
+        Ok(self.with_chart(result, as_of).await)
+    }
+
+    /// Comprehensive analysis run once per idempotency key
+    ///
+    /// Repeats of `key` for the same symbol and depth within
+    /// [`StockConfig::idempotency_retention`] return the stored result with
+    /// `replayed` set, instead of running the agents again.
+    pub async fn analyze_stock_once(
+        &self,
+        key: &str,
+        symbol: &str,
+        ctx: &mut AnalysisContext,
+    ) -> Result<Idempotent<AnalysisResult>> {
+        let depth = ctx.preferences.depth;
+        let fingerprint = format!("analyze:{}:{depth:?}", symbol.to_uppercase());
+        self.idempotency
+            .run(key, &fingerprint, || {
+                self.analyze_stock_at(symbol, depth, ctx)
+            })
+            .await
+    }
+
     pub async fn analyze_technical(
         &self,
         symbol: &str,
-        _ctx: &mut AnalysisContext,
+        ctx: &mut AnalysisContext,
     ) -> Result<AnalysisResult> {
-        let content = self.agent.analyze_technical(symbol).await?;
-        Ok(AnalysisResult::new(symbol, AnalysisType::Technical, content))
+        let mut agent_ctx = ctx.agent_context();
+        let content = self.agent.analyze_technical(symbol, &mut agent_ctx).await?;
+        let result = AnalysisResult::new(symbol, AnalysisType::Technical, content);
+        let result = self.translated(result, &mut agent_ctx).await;
+        Ok(self.with_chart(result, None).await)
     }
-    
+
     pub async fn analyze_fundamental(
         &self,
         symbol: &str,
-        _ctx: &mut AnalysisContext,
+        ctx: &mut AnalysisContext,
     ) -> Result<AnalysisResult> {
-        let content = self.agent.analyze_fundamental(symbol).await?;
-        Ok(AnalysisResult::new(symbol, AnalysisType::Fundamental, content))
+        let mut agent_ctx = ctx.agent_context();
+        let content = self
+            .agent
+            .analyze_fundamental(symbol, &mut agent_ctx)
+            .await?;
+        Ok(self
+            .translated(
+                AnalysisResult::new(symbol, AnalysisType::Fundamental, content),
+                &mut agent_ctx,
+            )
+            .await)
     }
-    
+
     pub async fn analyze_news(
         &self,
         symbol: &str,
-        _ctx: &mut AnalysisContext,
+        ctx: &mut AnalysisContext,
+    ) -> Result<AnalysisResult> {
+        let mut agent_ctx = ctx.agent_context();
+        let content = self.agent.analyze_news(symbol, &mut agent_ctx).await?;
+        Ok(self
+            .translated(
+                AnalysisResult::new(symbol, AnalysisType::News, content),
+                &mut agent_ctx,
+            )
+            .await)
+    }
+
+    /// One news digest across weighted symbols, typically a watchlist
+    ///
+    /// Weights come from [`crate::news_digest::exposure_weights`]; the ranked
+    /// stories are attached under [`NEWS_DIGEST_DATA_KEY`].
+    pub async fn analyze_news_digest(
+        &self,
+        weights: &[(String, f64)],
+        ctx: &mut AnalysisContext,
     ) -> Result<AnalysisResult> {
-        let content = self.agent.analyze_news(symbol).await?;
-        Ok(AnalysisResult::new(symbol, AnalysisType::News, content))
+        let data = self.news_digest.collect(weights).await;
+        let mut agent_ctx = ctx.agent_context();
+        let content = self
+            .agent
+            .analyze_news_digest(&data, &mut agent_ctx)
+            .await?;
+        let result = AnalysisResult::new("WATCHLIST", AnalysisType::News, content)
+            .with_data(NEWS_DIGEST_DATA_KEY, json!(data.stories));
+        Ok(self.translated(result, &mut agent_ctx).await)
     }
-    
+
     pub async fn analyze_earnings(
         &self,
         symbol: &str,
-        _ctx: &mut AnalysisContext,
+        ctx: &mut AnalysisContext,
+    ) -> Result<AnalysisResult> {
+        let mut agent_ctx = ctx.agent_context();
+        let content = self.agent.analyze_earnings(symbol, &mut agent_ctx).await?;
+        Ok(self
+            .translated(
+                AnalysisResult::new(symbol, AnalysisType::Earnings, content),
+                &mut agent_ctx,
+            )
+            .await)
+    }
+
+    pub async fn analyze_macro(&self, ctx: &mut AnalysisContext) -> Result<AnalysisResult> {
+        let mut agent_ctx = ctx.agent_context();
+        let content = self.agent.analyze_macro(&mut agent_ctx).await?;
+        Ok(self
+            .translated(
+                AnalysisResult::new("MARKET", AnalysisType::Macro, content),
+                &mut agent_ctx,
+            )
+            .await)
+    }
+
+    pub async fn analyze_geopolitical(&self, ctx: &mut AnalysisContext) -> Result<AnalysisResult> {
+        let mut agent_ctx = ctx.agent_context();
+        let content = self.agent.analyze_geopolitical(&mut agent_ctx).await?;
+        Ok(self
+            .translated(
+                AnalysisResult::new("MARKET", AnalysisType::Geopolitical, content),
+                &mut agent_ctx,
+            )
+            .await)
+    }
+
+    /// Performance, movers and news for a thematic basket
+    pub async fn analyze_theme(
+        &self,
+        theme: &ThemeBasket,
+        ctx: &mut AnalysisContext,
     ) -> Result<AnalysisResult> {
-        let content = self.agent.analyze_earnings(symbol).await?;
-        Ok(AnalysisResult::new(symbol, AnalysisType::Earnings, content))
+        let mut agent_ctx = ctx.agent_context();
+        let content = self.agent.analyze_theme(theme, &mut agent_ctx).await?;
+        Ok(self
+            .translated(
+                AnalysisResult::new(theme.key.to_uppercase(), AnalysisType::Theme, content),
+                &mut agent_ctx,
+            )
+            .await)
     }
-    
-    pub async fn analyze_macro(&self, _ctx: &mut AnalysisContext) -> Result<AnalysisResult> {
-        let content = self.agent.analyze_macro().await?;
-        Ok(AnalysisResult::new("MARKET", AnalysisType::Macro, content))
+
+    /// A stock now compared against `date`, with the structured diff
+    /// attached under [`TIME_COMPARISON_DATA_KEY`]
+    pub async fn compare_over_time(
+        &self,
+        symbol: &str,
+        date: NaiveDate,
+        ctx: &mut AnalysisContext,
+    ) -> Result<AnalysisResult> {
+        let mut agent_ctx = ctx.agent_context();
+        let content = self
+            .agent
+            .compare_over_time(symbol, date, &mut agent_ctx)
+            .await?;
+        let result = AnalysisResult::new(symbol, AnalysisType::TimeComparison, content);
+        let result = self.translated(result, &mut agent_ctx).await;
+
+        // Over a year or more, inflation is material to the price change
+        let long_horizon = (Utc::now().date_naive() - date).num_days() >= 365;
+        let params = json!({
+            "symbol": symbol,
+            "date": date.to_string(),
+            "inflation_adjusted": long_horizon,
+        });
+        Ok(match self.time_comparison_tool.execute(params).await {
+            Ok(diff) => result.with_data(TIME_COMPARISON_DATA_KEY, diff),
+            Err(e) => {
+                tracing::warn!("Time comparison data unavailable for {}: {}", symbol, e);
+                result
+            }
+        })
     }
-    
+
     pub async fn compare_stocks(
         &self,
         symbols: &[String],
-        _ctx: &mut AnalysisContext,
+        ctx: &mut AnalysisContext,
     ) -> Result<ComparisonResult> {
-        let content = self.agent.compare_stocks(symbols).await?;
+        let content = self
+            .agent
+            .compare_stocks(symbols, &mut ctx.agent_context())
+            .await?;
         let mut result = ComparisonResult::new(symbols.to_vec());
         result = result.with_summary(content);
         Ok(result)
     }
-    
+
+    /// Answer a natural language query, routed to the relevant agents
+    pub async fn answer_query(&self, query: &str, ctx: &mut AnalysisContext) -> Result<String> {
+        Ok(self
+            .agent
+            .smart_process(query, &mut ctx.agent_context())
+            .await?)
+    }
+
     pub fn router(&self) -> &SmartRouter {
         &self.router
     }
+
+    /// What the engine can currently do given the configured API keys
+    pub fn capabilities(&self) -> String {
+        self.agent.capabilities_report()
+    }
+
+    /// Tokens and estimated cost of all LLM requests since startup
+    pub fn usage_report(&self) -> String {
+        format!(
+            "💰 LLM usage since startup:\n{}",
+            self.token_tracker.summary()
+        )
+    }
+}
+
+/// Runs [`ANALYZE_JOB`] jobs for a [`crate::jobs::JobQueue`]
+#[async_trait]
+impl JobHandler for StockAnalysisEngine {
+    async fn run(&self, job: &Job) -> Result<Value> {
+        if job.kind != ANALYZE_JOB {
+            return Err(StockError::CommandError(format!(
+                "Unknown job kind '{}' for job {}",
+                job.kind, job.id
+            )));
+        }
+        let request = AnalyzeJob::parse(&job.request)?;
+        let mut ctx = AnalysisContext::new();
+        let result = match request.as_of {
+            Some(date) => {
+                self.analyze_stock_as_of(&request.symbol, request.depth, date, &mut ctx)
+                    .await?
+            }
+            None => {
+                self.analyze_stock_at(&request.symbol, request.depth, &mut ctx)
+                    .await?
+            }
+        };
+        Ok(result.to_output(&request.schema))
+    }
 }
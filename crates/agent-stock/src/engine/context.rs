@@ -1,5 +1,9 @@
 //! Analysis context management
 
+use crate::conversation_store::MAX_STORED_TURNS;
+pub use crate::depth::AnalysisDepth;
+use crate::language::ResponseLanguage;
+use crate::style::ResponseStyle;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -32,20 +36,27 @@ pub struct CachedData {
     pub expires_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AnalysisPreferences {
     pub language: String,
     pub parallel_execution: bool,
     pub include_macro: bool,
+    /// Default depth for analyses in this session
+    #[serde(default)]
     pub depth: AnalysisDepth,
     pub data_sources: Vec<String>,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-pub enum AnalysisDepth {
-    Quick,
-    Standard,
-    Deep,
+    #[serde(default)]
+    pub style: ResponseStyle,
+    /// Teaching mode; `None` uses the configured default
+    #[serde(default)]
+    pub teaching_mode: Option<bool>,
+    /// Reply language (e.g. detected from a voice message, or `bilingual` for
+    /// both, see [`ResponseLanguage`]); `None` leaves it to the agent
+    #[serde(default)]
+    pub response_language: Option<String>,
+    /// Attach audio summaries to long replies where the platform supports it
+    #[serde(default)]
+    pub voice_replies: bool,
 }
 
 impl Default for AnalysisContext {
@@ -69,18 +80,18 @@ impl AnalysisContext {
             metadata: HashMap::new(),
         }
     }
-    
+
     pub fn with_user(user_id: impl Into<String>) -> Self {
         let mut ctx = Self::new();
         ctx.user_id = Some(user_id.into());
         ctx
     }
-    
+
     pub fn set_symbols(&mut self, symbols: Vec<String>) {
         self.current_symbols = symbols;
         self.update_activity();
     }
-    
+
     pub fn add_symbol(&mut self, symbol: impl Into<String>) {
         let symbol = symbol.into();
         if !self.current_symbols.contains(&symbol) {
@@ -88,11 +99,11 @@ impl AnalysisContext {
         }
         self.update_activity();
     }
-    
+
     pub fn current_symbol(&self) -> Option<&str> {
         self.current_symbols.last().map(std::string::String::as_str)
     }
-    
+
     pub fn add_turn(&mut self, input: String, response: String, symbols: Vec<String>) {
         self.conversation_turns.push(ConversationTurn {
             input,
@@ -102,11 +113,45 @@ impl AnalysisContext {
         });
         self.update_activity();
     }
-    
+
+    /// Record an analysis exchange and the symbols it was about
+    ///
+    /// Only the latest [`MAX_STORED_TURNS`] turns are kept.
+    pub fn record_exchange(&mut self, input: &str, response: &str, symbols: Vec<String>) {
+        for symbol in &symbols {
+            self.add_symbol(symbol.as_str());
+        }
+        self.add_turn(input.to_string(), response.to_string(), symbols);
+        let excess = self
+            .conversation_turns
+            .len()
+            .saturating_sub(MAX_STORED_TURNS);
+        self.conversation_turns.drain(..excess);
+    }
+
     pub fn update_activity(&mut self) {
         self.last_active = Utc::now();
     }
-    
+
+    /// Agent context carrying this session's preferences
+    pub fn agent_context(&self) -> agent_core::Context {
+        let mut context = agent_core::Context::new();
+        self.preferences.style.apply_to(&mut context);
+        self.preferences.depth.apply_to(&mut context);
+        if let Some(enabled) = self.preferences.teaching_mode {
+            crate::style::set_teaching_mode(&mut context, enabled);
+        }
+        if let Some(language) = &self.preferences.response_language {
+            match ResponseLanguage::parse(language) {
+                Some(ResponseLanguage::Bilingual) => {
+                    ResponseLanguage::Bilingual.apply_to(&mut context)
+                }
+                _ => context.set_language(language),
+            }
+        }
+        context
+    }
+
     pub fn is_expired(&self, max_age_seconds: i64) -> bool {
         let max_age = chrono::Duration::seconds(max_age_seconds);
         Utc::now() - self.last_active > max_age
@@ -121,6 +166,10 @@ impl Default for AnalysisPreferences {
             include_macro: false,
             depth: AnalysisDepth::Standard,
             data_sources: vec!["yahoo".to_string()],
+            style: ResponseStyle::default(),
+            teaching_mode: None,
+            response_language: None,
+            voice_replies: false,
         }
     }
 }
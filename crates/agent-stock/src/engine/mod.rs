@@ -4,8 +4,14 @@
 
 pub mod analysis_engine;
 pub mod context;
+pub mod output;
 pub mod result;
+pub mod snapshot;
 
-pub use analysis_engine::StockAnalysisEngine;
+pub use analysis_engine::{
+    AS_OF_DATA_KEY, NEWS_DIGEST_DATA_KEY, StockAnalysisEngine, USAGE_DATA_KEY,
+};
 pub use context::AnalysisContext;
+pub use output::OutputSchema;
 pub use result::{AnalysisResult, AnalysisType, ComparisonResult};
+pub use snapshot::{Snapshot, SnapshotSource};
@@ -0,0 +1,262 @@
+//! Output schemas for API consumers
+//!
+//! Analysis results are typed, but their serde shape follows the engine's
+//! internals and changes with them. An [`OutputSchema`] lets a caller pick
+//! the shape it wants instead: one of the named schemas, or a template of
+//! its own whose string leaves name fields of the full result.
+//!
+//! A template mirrors the output. Objects nest, arrays list, and each string
+//! is a dotted path into the full result: `symbol`, `content`,
+//! `confidence`, `timestamp`, `data.technical.rsi` and so on. Numbers,
+//! booleans and null are copied as they are; paths that lead nowhere give
+//! null.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::engine::OutputSchema;
+//!
+//! let schema = OutputSchema::parse(&json!({
+//!     "ticker": "symbol",
+//!     "report": { "text": "content", "rsi": "data.technical.rsi" },
+//!     "version": 2
+//! }))?;
+//! let body = result.to_output(&schema);
+//! ```
+
+use serde_json::{Map, Value, json};
+
+use super::result::{AnalysisResult, ComparisonResult};
+use crate::error::{Result, StockError};
+
+/// Top-level fields a template path may start with
+const FIELDS: &[&str] = &[
+    "symbol",
+    "analysis_type",
+    "content",
+    "data",
+    "timestamp",
+    "data_freshness",
+    "confidence",
+    "warnings",
+    "sources",
+];
+
+/// Shape analysis results are returned in
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum OutputSchema {
+    /// Every field of the result, as serialized by the engine
+    #[default]
+    Full,
+    /// A small stable shape: symbol, type, time, confidence and the text
+    Compact,
+    /// A caller's template (see the module docs)
+    Custom(Value),
+}
+
+impl OutputSchema {
+    /// Names of the built-in schemas
+    pub const NAMES: &'static [&'static str] = &["full", "compact"];
+
+    /// Built-in schema called `name`
+    pub fn named(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "full" | "default" => Some(Self::Full),
+            "compact" => Some(Self::Compact),
+            _ => None,
+        }
+    }
+
+    /// Schema from a request: a built-in name, or a template object
+    pub fn parse(spec: &Value) -> Result<Self> {
+        match spec {
+            Value::String(name) => Self::named(name).ok_or_else(|| {
+                StockError::ConfigError(format!(
+                    "Unknown output schema '{name}'; use one of {} or a template object",
+                    Self::NAMES.join(", ")
+                ))
+            }),
+            Value::Object(_) => {
+                validate(spec, "")?;
+                Ok(Self::Custom(spec.clone()))
+            }
+            _ => Err(StockError::ConfigError(
+                "An output schema is a schema name or a template object".to_string(),
+            )),
+        }
+    }
+
+    /// `result` in this shape
+    pub fn apply(&self, result: &AnalysisResult) -> Value {
+        let full = serde_json::to_value(result).unwrap_or(Value::Null);
+        match self {
+            Self::Full => full,
+            Self::Compact => json!({
+                "symbol": result.symbol,
+                "type": format!("{:?}", result.analysis_type).to_lowercase(),
+                "as_of": full["timestamp"],
+                "confidence": result.confidence,
+                "summary": result.content,
+                "warnings": result.warnings,
+            }),
+            Self::Custom(template) => fill(template, &full),
+        }
+    }
+
+    /// `comparison` with each symbol's analysis in this shape
+    pub fn apply_comparison(&self, comparison: &ComparisonResult) -> Value {
+        let analyses: Map<String, Value> = comparison
+            .symbols
+            .iter()
+            .filter_map(|symbol| {
+                let analysis = comparison.analyses.get(symbol)?;
+                Some((symbol.clone(), self.apply(analysis)))
+            })
+            .collect();
+        let mut output = match self {
+            Self::Full => serde_json::to_value(comparison).unwrap_or(Value::Null),
+            Self::Compact | Self::Custom(_) => json!({
+                "symbols": comparison.symbols,
+                "summary": comparison.summary,
+            }),
+        };
+        output["analyses"] = Value::Object(analyses);
+        output
+    }
+}
+
+impl AnalysisResult {
+    /// This result in the shape of `schema`
+    pub fn to_output(&self, schema: &OutputSchema) -> Value {
+        schema.apply(self)
+    }
+}
+
+impl ComparisonResult {
+    /// This comparison with each analysis in the shape of `schema`
+    pub fn to_output(&self, schema: &OutputSchema) -> Value {
+        schema.apply_comparison(self)
+    }
+}
+
+/// Check every path in `template` starts at a result field
+fn validate(template: &Value, at: &str) -> Result<()> {
+    match template {
+        Value::Object(fields) => fields
+            .iter()
+            .try_for_each(|(key, value)| validate(value, &format!("{at}/{key}"))),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .try_for_each(|(i, value)| validate(value, &format!("{at}/{i}"))),
+        Value::String(path) => {
+            let root = path.split('.').next().unwrap_or_default();
+            if FIELDS.contains(&root) {
+                Ok(())
+            } else {
+                Err(StockError::ConfigError(format!(
+                    "Output schema field {at} names '{path}'; paths start with one of {}",
+                    FIELDS.join(", ")
+                )))
+            }
+        }
+        _ => Ok(()),
+    }
+}
+
+/// `template` with each path replaced by its value in `full`
+fn fill(template: &Value, full: &Value) -> Value {
+    match template {
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), fill(value, full)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(|item| fill(item, full)).collect()),
+        Value::String(path) => path
+            .split('.')
+            .try_fold(full, |value, segment| match value {
+                Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+                _ => value.get(segment),
+            })
+            .cloned()
+            .unwrap_or(Value::Null),
+        literal => literal.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::AnalysisType;
+
+    fn result() -> AnalysisResult {
+        AnalysisResult::new("NVDA", AnalysisType::Technical, "Overbought but trending.")
+            .with_data(
+                "technical",
+                json!({"rsi": 74.2, "signals": ["macd_cross", "breakout"]}),
+            )
+            .with_confidence(0.8)
+            .add_source("Yahoo Finance")
+    }
+
+    #[test]
+    fn test_custom_template() {
+        let schema = OutputSchema::parse(&json!({
+            "ticker": "symbol",
+            "report": {"text": "content", "rsi": "data.technical.rsi"},
+            "first_signal": "data.technical.signals.0",
+            "from": ["sources.0"],
+            "missing": "data.fundamental.pe",
+            "version": 2
+        }))
+        .unwrap();
+
+        assert_eq!(
+            result().to_output(&schema),
+            json!({
+                "ticker": "NVDA",
+                "report": {"text": "Overbought but trending.", "rsi": 74.2},
+                "first_signal": "macd_cross",
+                "from": ["Yahoo Finance"],
+                "missing": null,
+                "version": 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_named_schemas() {
+        let result = result();
+        let full = result.to_output(&OutputSchema::parse(&json!("full")).unwrap());
+        assert_eq!(full["analysis_type"], "Technical");
+        assert_eq!(full["data"]["technical"]["rsi"], 74.2);
+
+        let compact = result.to_output(&OutputSchema::named("Compact").unwrap());
+        assert_eq!(compact["type"], "technical");
+        assert_eq!(compact["summary"], "Overbought but trending.");
+        assert_eq!(compact["as_of"], full["timestamp"]);
+        assert!(compact.get("data").is_none());
+    }
+
+    #[test]
+    fn test_invalid_schemas() {
+        assert!(OutputSchema::parse(&json!("verbose")).is_err());
+        assert!(OutputSchema::parse(&json!(["symbol"])).is_err());
+        let err = OutputSchema::parse(&json!({"report": {"price": "quote.close"}})).unwrap_err();
+        assert!(err.to_string().contains("/report/price"), "{err}");
+    }
+
+    #[test]
+    fn test_comparison_output() {
+        let mut comparison = ComparisonResult::new(vec!["NVDA".to_string(), "AMD".to_string()])
+            .with_summary("NVDA leads.");
+        comparison.add_analysis("NVDA".to_string(), result());
+
+        let output = comparison.to_output(&OutputSchema::Compact);
+        assert_eq!(output["summary"], "NVDA leads.");
+        assert_eq!(output["analyses"]["NVDA"]["type"], "technical");
+        assert!(output["analyses"].get("AMD").is_none());
+    }
+}
@@ -12,6 +12,8 @@ pub enum AnalysisType {
     Earnings,
     Macro,
     Geopolitical,
+    Theme,
+    TimeComparison,
     Comprehensive,
 }
 
@@ -77,7 +79,11 @@ pub struct RiskMetric {
 }
 
 impl AnalysisResult {
-    pub fn new(symbol: impl Into<String>, analysis_type: AnalysisType, content: impl Into<String>) -> Self {
+    pub fn new(
+        symbol: impl Into<String>,
+        analysis_type: AnalysisType,
+        content: impl Into<String>,
+    ) -> Self {
         Self {
             symbol: symbol.into(),
             analysis_type,
@@ -90,26 +96,26 @@ impl AnalysisResult {
             sources: Vec::new(),
         }
     }
-    
+
     pub fn with_data(mut self, key: impl Into<String>, value: serde_json::Value) -> Self {
         self.data.insert(key.into(), value);
         self
     }
-    
+
     pub fn with_freshness(mut self, freshness: DataFreshness) -> Self {
         self.data_freshness = freshness;
         self
     }
-    
+
     pub fn with_confidence(mut self, confidence: f64) -> Self {
         self.confidence = Some(confidence.clamp(0.0, 1.0));
         self
     }
-    
+
     pub fn add_warning(&mut self, warning: impl Into<String>) {
         self.warnings.push(warning.into());
     }
-    
+
     pub fn add_source(mut self, source: impl Into<String>) -> Self {
         let source = source.into();
         if !self.sources.contains(&source) {
@@ -117,25 +123,33 @@ impl AnalysisResult {
         }
         self
     }
-    
+
     pub fn is_fresh(&self) -> bool {
-        matches!(self.data_freshness, DataFreshness::RealTime | DataFreshness::Recent)
+        matches!(
+            self.data_freshness,
+            DataFreshness::RealTime | DataFreshness::Recent
+        )
     }
-    
+
     pub fn summary(&self) -> String {
+        self.summary_at(&self.timestamp.format("%Y-%m-%d %H:%M UTC").to_string())
+    }
+
+    /// Summary line with the timestamp already rendered, e.g. in local time
+    pub fn summary_at(&self, time: &str) -> String {
         let freshness_indicator = match self.data_freshness {
             DataFreshness::RealTime => "🟢",
             DataFreshness::Recent => "🟡",
             DataFreshness::Stale => "🟠",
             DataFreshness::Partial => "⚠️",
         };
-        
+
         format!(
             "{} {} Analysis - {} ({})",
             freshness_indicator,
             self.symbol,
             format!("{:?}", self.analysis_type),
-            self.timestamp.format("%Y-%m-%d %H:%M UTC")
+            time
         )
     }
 }
@@ -150,20 +164,20 @@ impl ComparisonResult {
             timestamp: Utc::now(),
         }
     }
-    
+
     pub fn add_analysis(&mut self, symbol: String, analysis: AnalysisResult) {
         self.analyses.insert(symbol, analysis);
     }
-    
+
     pub fn with_summary(mut self, summary: impl Into<String>) -> Self {
         self.summary = summary.into();
         self
     }
-    
+
     pub fn is_complete(&self) -> bool {
         self.symbols.iter().all(|s| self.analyses.contains_key(s))
     }
-    
+
     pub fn success_rate(&self) -> f64 {
         if self.symbols.is_empty() {
             return 0.0;
@@ -0,0 +1,148 @@
+//! Quick snapshots shown while a full analysis runs
+//!
+//! A comprehensive analysis takes 30–60 seconds. [`SnapshotSource`] answers
+//! within a few seconds with the latest price, the day's change and the top
+//! headline, read through the [`shared_cache`] so repeated requests cost no
+//! API calls; bots send it first and replace or follow it with the analysis.
+
+use agent_tools::Tool;
+use serde_json::{Value, json};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::cache::shared_cache;
+use crate::config::StockConfig;
+use crate::error::{Result, StockError};
+use crate::tools::{NewsTool, StockDataTool};
+
+/// Snapshots slower than this are skipped
+pub const SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Price and top headline for a symbol
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub symbol: String,
+    /// Latest close
+    pub price: f64,
+    /// Change from the previous close, in percent
+    pub change_percent: Option<f64>,
+    /// Most recent news headline
+    pub headline: Option<String>,
+}
+
+impl Snapshot {
+    /// Snapshot from [`StockDataTool`] output with recent history and,
+    /// optionally, [`NewsTool`] output
+    pub fn from_data(symbol: &str, stock_data: &Value, news: Option<&Value>) -> Option<Self> {
+        let price = stock_data["current_quote"]["close"].as_f64()?;
+        let closes: Vec<f64> = stock_data["historical_data"]
+            .as_array()
+            .map(|bars| {
+                bars.iter()
+                    .filter_map(|bar| bar["close"].as_f64())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let change_percent = match closes.as_slice() {
+            [.., previous, latest] if *previous != 0.0 => {
+                Some((latest - previous) / previous * 100.0)
+            }
+            _ => None,
+        };
+        let headline = news
+            .and_then(|news| news["articles"][0]["title"].as_str())
+            .map(ToString::to_string);
+
+        Some(Self {
+            symbol: symbol.to_string(),
+            price,
+            change_percent,
+            headline,
+        })
+    }
+}
+
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "⚡ {} {:.2}", self.symbol, self.price)?;
+        if let Some(change) = self.change_percent {
+            write!(f, " ({change:+.2}%)")?;
+        }
+        if let Some(headline) = &self.headline {
+            write!(f, "\n📰 {headline}")?;
+        }
+        write!(f, "\n⏳ Full analysis on the way…")
+    }
+}
+
+/// Builds [`Snapshot`]s from cached quote and news data
+pub struct SnapshotSource {
+    stock_data: StockDataTool,
+    news: NewsTool,
+}
+
+impl SnapshotSource {
+    pub fn new(config: Arc<StockConfig>) -> Self {
+        let cache = shared_cache();
+        Self {
+            stock_data: StockDataTool::new(config.clone(), cache.realtime.clone()),
+            news: NewsTool::new(config, cache.news.clone()),
+        }
+    }
+
+    /// Snapshot of `symbol`, or an error if the quote is unavailable or takes
+    /// longer than [`SNAPSHOT_TIMEOUT`]
+    pub async fn snapshot(&self, symbol: &str) -> Result<Snapshot> {
+        tokio::time::timeout(SNAPSHOT_TIMEOUT, self.fetch(symbol))
+            .await
+            .map_err(|_| StockError::Timeout(format!("Snapshot of {symbol}")))?
+    }
+
+    async fn fetch(&self, symbol: &str) -> Result<Snapshot> {
+        let quote = json!({ "symbol": symbol, "range": "5d", "include_historical": true });
+        let news = json!({ "symbol": symbol, "limit": 1 });
+        let (stock_data, news) =
+            tokio::join!(self.stock_data.execute(quote), self.news.execute(news));
+
+        let stock_data = stock_data.map_err(|e| StockError::DataUnavailable {
+            symbol: symbol.to_string(),
+            reason: e.to_string(),
+        })?;
+        Snapshot::from_data(symbol, &stock_data, news.ok().as_ref()).ok_or_else(|| {
+            StockError::DataUnavailable {
+                symbol: symbol.to_string(),
+                reason: "Quote has no price".to_string(),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_from_tool_data() {
+        let stock_data = json!({
+            "symbol": "AAPL",
+            "current_quote": { "close": 178.25 },
+            "historical_data": [{ "close": 174.0 }, { "close": 176.0 }, { "close": 178.25 }],
+        });
+        let news = json!({ "articles": [{ "title": "Apple unveils new chips" }] });
+
+        let snapshot = Snapshot::from_data("AAPL", &stock_data, Some(&news)).unwrap();
+        assert_eq!(
+            snapshot.to_string(),
+            "⚡ AAPL 178.25 (+1.28%)\n📰 Apple unveils new chips\n⏳ Full analysis on the way…"
+        );
+
+        let quote_only = json!({ "current_quote": { "close": 10.0 } });
+        let snapshot = Snapshot::from_data("XYZ", &quote_only, None).unwrap();
+        assert_eq!(
+            snapshot.to_string(),
+            "⚡ XYZ 10.00\n⏳ Full analysis on the way…"
+        );
+        assert!(Snapshot::from_data("XYZ", &json!({}), None).is_none());
+    }
+}
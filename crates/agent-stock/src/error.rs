@@ -24,6 +24,13 @@ pub enum StockError {
     #[error("Rate limit exceeded for {provider}")]
     RateLimitExceeded { provider: String },
 
+    /// Provider skipped after repeated failures (its circuit is open)
+    #[error("{provider} is unavailable after repeated failures; retrying in {retry_in_secs}s")]
+    ProviderUnavailable {
+        provider: String,
+        retry_in_secs: u64,
+    },
+
     /// Network or HTTP error
     #[error("Network error: {0}")]
     NetworkError(#[from] reqwest::Error),
@@ -72,6 +79,26 @@ pub enum StockError {
     #[error("Operation timed out: {0}")]
     Timeout(String),
 
+    /// Missing, unknown or revoked API key
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    /// API key lacks the scope a request needs
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    /// API key has spent its budget
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(String),
+
+    /// Idempotency key reused for a different request
+    #[error("Idempotency key conflict: {0}")]
+    IdempotencyConflict(String),
+
+    /// Request cancelled by the user (e.g. `/cancel`)
+    #[error("Request cancelled")]
+    Cancelled,
+
     /// Generic error
     #[error("{0}")]
     Other(String),
@@ -110,7 +137,10 @@ impl StockError {
     pub fn is_retryable(&self) -> bool {
         matches!(
             self,
-            Self::NetworkError(_) | Self::RateLimitExceeded { .. } | Self::Timeout(_)
+            Self::NetworkError(_)
+                | Self::RateLimitExceeded { .. }
+                | Self::ProviderUnavailable { .. }
+                | Self::Timeout(_)
         )
     }
 
@@ -118,7 +148,13 @@ impl StockError {
     pub fn is_user_error(&self) -> bool {
         matches!(
             self,
-            Self::InvalidSymbol(_) | Self::CommandError(_) | Self::ConfigError(_)
+            Self::InvalidSymbol(_)
+                | Self::CommandError(_)
+                | Self::ConfigError(_)
+                | Self::Unauthorized(_)
+                | Self::Forbidden(_)
+                | Self::QuotaExceeded(_)
+                | Self::IdempotencyConflict(_)
         )
     }
 }
@@ -0,0 +1,160 @@
+//! Deterministic checks on analysis outputs
+
+use serde::{Deserialize, Serialize};
+
+use super::suite::Check;
+
+/// Outcome of a single deterministic check
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckResult {
+    /// Short description of the check
+    pub check: String,
+    /// Whether the check passed
+    pub passed: bool,
+    /// Why the check failed, if it did
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl Check {
+    /// Short description used in reports
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Symbols { symbols } => format!("mentions {}", symbols.join(", ")),
+            Self::Number {
+                label,
+                expected,
+                tolerance,
+            } => format!("{label} = {expected} (±{:.1}%)", tolerance * 100.0),
+            Self::Sections { sections } => format!("has sections {}", sections.join(", ")),
+            Self::Excludes { phrases } => format!("avoids {}", phrases.join(", ")),
+        }
+    }
+
+    /// Run the check against an output
+    pub fn evaluate(&self, output: &str) -> CheckResult {
+        let lower = output.to_lowercase();
+        let failure = match self {
+            Self::Symbols { symbols } => {
+                let missing: Vec<&str> = symbols
+                    .iter()
+                    .filter(|s| !output.contains(s.as_str()))
+                    .map(String::as_str)
+                    .collect();
+                (!missing.is_empty()).then(|| format!("missing {}", missing.join(", ")))
+            }
+            Self::Number {
+                expected,
+                tolerance,
+                ..
+            } => {
+                let numbers = extract_numbers(output);
+                let found = numbers
+                    .iter()
+                    .any(|n| (n - expected).abs() <= expected.abs() * tolerance);
+                (!found).then(|| format!("no number within tolerance of {expected}"))
+            }
+            Self::Sections { sections } => {
+                let missing: Vec<&str> = sections
+                    .iter()
+                    .filter(|section| {
+                        !section
+                            .split('|')
+                            .any(|alt| lower.contains(&alt.trim().to_lowercase()))
+                    })
+                    .map(String::as_str)
+                    .collect();
+                (!missing.is_empty()).then(|| format!("missing {}", missing.join(", ")))
+            }
+            Self::Excludes { phrases } => {
+                let present: Vec<&str> = phrases
+                    .iter()
+                    .filter(|p| lower.contains(&p.to_lowercase()))
+                    .map(String::as_str)
+                    .collect();
+                (!present.is_empty()).then(|| format!("contains {}", present.join(", ")))
+            }
+        };
+
+        CheckResult {
+            check: self.describe(),
+            passed: failure.is_none(),
+            detail: failure,
+        }
+    }
+}
+
+/// Extract all numbers from text, accepting thousands separators
+pub fn extract_numbers(text: &str) -> Vec<f64> {
+    let mut numbers = Vec::new();
+    let mut current = String::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let next_is_digit = chars.peek().is_some_and(char::is_ascii_digit);
+        if c.is_ascii_digit()
+            || (c == '.' && !current.is_empty() && next_is_digit)
+            || (c == '-' && current.is_empty() && next_is_digit)
+        {
+            current.push(c);
+        } else if c == ',' && !current.is_empty() && next_is_digit {
+            // Thousands separator
+        } else if !current.is_empty() {
+            if let Ok(n) = current.parse() {
+                numbers.push(n);
+            }
+            current.clear();
+        }
+    }
+    if let Ok(n) = current.parse() {
+        numbers.push(n);
+    }
+    numbers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_numbers() {
+        assert_eq!(
+            extract_numbers("Close $237.87, volume 39,832,000; RSI -1.5 and 58.4%."),
+            vec![237.87, 39_832_000.0, -1.5, 58.4]
+        );
+        assert!(extract_numbers("no numbers here").is_empty());
+    }
+
+    #[test]
+    fn test_number_check_tolerance() {
+        let check = Check::Number {
+            label: "P/E".to_string(),
+            expected: 39.1,
+            tolerance: 0.01,
+        };
+        assert!(check.evaluate("P/E is about 39.2").passed);
+        assert!(!check.evaluate("P/E is 41").passed);
+    }
+
+    #[test]
+    fn test_text_checks() {
+        let output = "## Technical Analysis of TSLA\nRSI is neutral.";
+
+        let symbols = Check::Symbols {
+            symbols: vec!["TSLA".to_string(), "AAPL".to_string()],
+        };
+        let result = symbols.evaluate(output);
+        assert!(!result.passed);
+        assert_eq!(result.detail.as_deref(), Some("missing AAPL"));
+
+        let sections = Check::Sections {
+            sections: vec!["rsi".to_string(), "MACD|technical analysis".to_string()],
+        };
+        assert!(sections.evaluate(output).passed);
+
+        let excludes = Check::Excludes {
+            phrases: vec!["Oversold".to_string()],
+        };
+        assert!(excludes.evaluate(output).passed);
+    }
+}
@@ -0,0 +1,164 @@
+//! Tools that replay recorded data during evaluation
+
+use agent_core::Result as AgentResult;
+use agent_tools::{Tool, ToolRegistry};
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::suite::Fixture;
+
+/// A tool that answers from recorded fixtures instead of live APIs
+///
+/// It keeps the name, description and schema of the tool it replaces so the
+/// LLM sees exactly the same tool definitions as in production. Calls that no
+/// fixture matches fail, so an evaluation never reaches a live API.
+pub struct FixtureTool {
+    name: String,
+    description: String,
+    input_schema: Value,
+    fixtures: Vec<Fixture>,
+}
+
+impl FixtureTool {
+    /// Create a fixture tool that stands in for `tool`
+    pub fn replacing(tool: &dyn Tool, fixtures: Vec<Fixture>) -> Self {
+        Self {
+            name: tool.name().to_string(),
+            description: tool.description().to_string(),
+            input_schema: tool.input_schema(),
+            fixtures,
+        }
+    }
+
+    /// Create a fixture tool for a tool that is not registered
+    pub fn new(name: impl Into<String>, fixtures: Vec<Fixture>) -> Self {
+        let name = name.into();
+        Self {
+            description: format!("Recorded data for {name}"),
+            name,
+            input_schema: json!({ "type": "object" }),
+            fixtures,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for FixtureTool {
+    async fn execute(&self, params: Value) -> AgentResult<Value> {
+        self.fixtures
+            .iter()
+            .find(|fixture| fixture.matches(&params))
+            .map(|fixture| fixture.response.clone())
+            .ok_or_else(|| {
+                agent_core::Error::ProcessingFailed(format!(
+                    "No recorded data for {} with parameters {params}",
+                    self.name
+                ))
+            })
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn input_schema(&self) -> Value {
+        self.input_schema.clone()
+    }
+}
+
+/// Replaces every registered tool with a fixture tool and restores them on drop
+pub(crate) struct FixtureGuard<'a> {
+    registry: &'a ToolRegistry,
+    originals: Vec<Arc<dyn Tool>>,
+    added: Vec<String>,
+}
+
+impl<'a> FixtureGuard<'a> {
+    /// Install fixtures into the registry
+    ///
+    /// Registered tools without fixtures are replaced too (with no recorded
+    /// data) so that nothing reaches a live API during evaluation.
+    pub(crate) fn install(
+        registry: &'a ToolRegistry,
+        mut fixtures: HashMap<String, Vec<Fixture>>,
+    ) -> Self {
+        let originals = registry.list_tools();
+        for tool in &originals {
+            let recorded = fixtures.remove(tool.name()).unwrap_or_default();
+            registry.register(Arc::new(FixtureTool::replacing(tool.as_ref(), recorded)));
+        }
+        let mut added = Vec::new();
+        for (name, recorded) in fixtures {
+            registry.register(Arc::new(FixtureTool::new(name.clone(), recorded)));
+            added.push(name);
+        }
+        Self {
+            registry,
+            originals,
+            added,
+        }
+    }
+}
+
+impl Drop for FixtureGuard<'_> {
+    fn drop(&mut self) {
+        for name in &self.added {
+            self.registry.unregister(name);
+        }
+        for tool in self.originals.drain(..) {
+            self.registry.register(tool);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::GlossaryTool;
+
+    fn fixture(symbol: &str, price: f64) -> Fixture {
+        serde_json::from_value(
+            json!({ "match": { "symbol": symbol }, "response": { "price": price } }),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_fixture_tool_replays() {
+        let tool = FixtureTool::new(
+            "stock_data",
+            vec![fixture("AAPL", 1.0), fixture("MSFT", 2.0)],
+        );
+        let result = tool.execute(json!({ "symbol": "MSFT" })).await.unwrap();
+        assert_eq!(result["price"], 2.0);
+        assert!(tool.execute(json!({ "symbol": "TSLA" })).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_guard_replaces_and_restores() {
+        let registry = ToolRegistry::new();
+        registry.register(Arc::new(GlossaryTool::new()));
+
+        {
+            let _guard = FixtureGuard::install(
+                &registry,
+                HashMap::from([("stock_data".to_string(), vec![fixture("AAPL", 1.0)])]),
+            );
+            // The glossary has no fixtures, so it must not answer live
+            let glossary = registry.get("glossary").unwrap();
+            assert_eq!(glossary.description(), GlossaryTool::new().description());
+            assert!(glossary.execute(json!({ "term": "eps" })).await.is_err());
+            assert!(registry.get("stock_data").is_some());
+        }
+
+        let glossary = registry.get("glossary").unwrap();
+        assert!(glossary.execute(json!({ "term": "eps" })).await.is_ok());
+        assert!(registry.get("stock_data").is_none());
+    }
+}
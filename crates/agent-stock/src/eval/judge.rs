@@ -0,0 +1,128 @@
+//! LLM-as-judge scoring of analysis outputs
+
+use agent_llm::{CompletionRequest, LLMProvider, Message};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use super::suite::EvalCase;
+use crate::error::{Result, StockError};
+
+/// System prompt for the judge
+const JUDGE_SYSTEM_PROMPT: &str = "You are a strict reviewer of stock analysis reports. \
+Grade the answer to the user's query using only the recorded market data provided. \
+Penalize numbers that do not match the data, invented facts, missing parts of the question, \
+and overconfident investment advice. Reply with a single JSON object: \
+{\"score\": <integer 0-10>, \"reasoning\": \"<one or two sentences>\"}";
+
+/// Maximum characters of recorded data included in the judge prompt
+const MAX_DATA_CHARS: usize = 6_000;
+
+/// Judge verdict for one output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JudgeVerdict {
+    /// Score normalized to 0.0..=1.0
+    pub score: f64,
+    /// Judge's explanation
+    pub reasoning: String,
+}
+
+/// Scores outputs with an LLM
+pub struct LlmJudge {
+    provider: Arc<dyn LLMProvider>,
+    model: String,
+}
+
+impl LlmJudge {
+    /// Create a judge using the given provider and model
+    pub fn new(provider: Arc<dyn LLMProvider>, model: impl Into<String>) -> Self {
+        Self {
+            provider,
+            model: model.into(),
+        }
+    }
+
+    /// Get the judge model
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Score an output for a case, given the recorded data the agent saw
+    pub async fn score(
+        &self,
+        case: &EvalCase,
+        recorded_data: &serde_json::Value,
+        output: &str,
+    ) -> Result<JudgeVerdict> {
+        let mut data = recorded_data.to_string();
+        if data.len() > MAX_DATA_CHARS {
+            let cut = (0..=MAX_DATA_CHARS)
+                .rev()
+                .find(|&i| data.is_char_boundary(i))
+                .unwrap_or(0);
+            data.truncate(cut);
+            data.push_str("...");
+        }
+
+        let mut prompt = format!(
+            "## Query\n{}\n\n## Recorded market data\n{data}\n\n",
+            case.query
+        );
+        if let Some(rubric) = &case.rubric {
+            prompt.push_str(&format!("## What a good answer contains\n{rubric}\n\n"));
+        }
+        prompt.push_str(&format!("## Answer to grade\n{output}"));
+
+        let request = CompletionRequest::builder(&self.model)
+            .messages(vec![Message::user(prompt)])
+            .system(JUDGE_SYSTEM_PROMPT)
+            .max_tokens(512)
+            .temperature(0.0)
+            .build();
+
+        let response = self
+            .provider
+            .complete(request)
+            .await
+            .map_err(|e| StockError::ApiError(format!("Judge request failed: {e}")))?;
+        let text = response.message.text().unwrap_or_default();
+        parse_verdict(text)
+    }
+}
+
+/// Parse the judge's JSON reply, tolerating surrounding prose or code fences
+fn parse_verdict(text: &str) -> Result<JudgeVerdict> {
+    #[derive(Deserialize)]
+    struct RawVerdict {
+        score: f64,
+        #[serde(default)]
+        reasoning: String,
+    }
+
+    let invalid = || StockError::Other(format!("Invalid judge reply: {text}"));
+    let start = text.find('{').ok_or_else(invalid)?;
+    let end = text.rfind('}').ok_or_else(invalid)?;
+    let raw: RawVerdict =
+        serde_json::from_str(text.get(start..=end).ok_or_else(invalid)?).map_err(|_| invalid())?;
+
+    Ok(JudgeVerdict {
+        score: (raw.score / 10.0).clamp(0.0, 1.0),
+        reasoning: raw.reasoning,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_verdict() {
+        let verdict =
+            parse_verdict("```json\n{\"score\": 7, \"reasoning\": \"Mostly correct\"}\n```")
+                .unwrap();
+        assert!((verdict.score - 0.7).abs() < f64::EPSILON);
+        assert_eq!(verdict.reasoning, "Mostly correct");
+
+        assert_eq!(parse_verdict("{\"score\": 15}").unwrap().score, 1.0);
+        assert!(parse_verdict("no json here").is_err());
+    }
+}
@@ -0,0 +1,313 @@
+//! Evaluation reports and regression comparison
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+use super::checks::CheckResult;
+use super::judge::JudgeVerdict;
+use crate::error::{Result, StockError};
+
+/// Result of one evaluation case
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaseResult {
+    /// Case identifier
+    pub id: String,
+    /// Agent output (empty if the agent failed)
+    pub output: String,
+    /// Agent error, if the run failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Deterministic check results
+    pub checks: Vec<CheckResult>,
+    /// LLM judge verdict, if a judge was configured and answered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub judge: Option<JudgeVerdict>,
+    /// Combined score in 0.0..=1.0
+    pub score: f64,
+    /// Whether the case passed
+    pub passed: bool,
+    /// Wall-clock duration of the agent run
+    pub duration_ms: u64,
+}
+
+impl CaseResult {
+    /// Fraction of deterministic checks that passed (1.0 when there are none)
+    pub fn check_score(&self) -> f64 {
+        if self.checks.is_empty() {
+            return 1.0;
+        }
+        let passed = self.checks.iter().filter(|c| c.passed).count();
+        passed as f64 / self.checks.len() as f64
+    }
+}
+
+/// Results of running a suite
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalReport {
+    /// Suite name
+    pub suite: String,
+    /// When the run finished
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// Per-case results, in suite order
+    pub cases: Vec<CaseResult>,
+}
+
+impl EvalReport {
+    /// Mean combined score across cases
+    pub fn mean_score(&self) -> f64 {
+        if self.cases.is_empty() {
+            return 0.0;
+        }
+        self.cases.iter().map(|c| c.score).sum::<f64>() / self.cases.len() as f64
+    }
+
+    /// Number of passed cases
+    pub fn passed(&self) -> usize {
+        self.cases.iter().filter(|c| c.passed).count()
+    }
+
+    /// Get the result of a case
+    pub fn case(&self, id: &str) -> Option<&CaseResult> {
+        self.cases.iter().find(|c| c.id == id)
+    }
+
+    /// Compare against a baseline run
+    ///
+    /// A case regresses when it passed in the baseline and fails now, or when
+    /// its score dropped by more than `tolerance`.
+    pub fn compare(&self, baseline: &EvalReport, tolerance: f64) -> RegressionReport {
+        let mut report = RegressionReport {
+            baseline_score: baseline.mean_score(),
+            current_score: self.mean_score(),
+            ..RegressionReport::default()
+        };
+
+        for case in &self.cases {
+            let Some(before) = baseline.case(&case.id) else {
+                report.new_cases.push(case.id.clone());
+                continue;
+            };
+            let change = ScoreChange {
+                id: case.id.clone(),
+                baseline: before.score,
+                current: case.score,
+            };
+            if (before.passed && !case.passed) || case.score < before.score - tolerance {
+                report.regressions.push(change);
+            } else if (!before.passed && case.passed) || case.score > before.score + tolerance {
+                report.improvements.push(change);
+            }
+        }
+        report.missing_cases = baseline
+            .cases
+            .iter()
+            .filter(|c| self.case(&c.id).is_none())
+            .map(|c| c.id.clone())
+            .collect();
+        report
+    }
+
+    /// Save the report as JSON (e.g. as the baseline for later runs)
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json).map_err(|e| {
+            StockError::Other(format!(
+                "Failed to write eval report {}: {e}",
+                path.display()
+            ))
+        })
+    }
+
+    /// Load a report saved with [`EvalReport::save`]
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|e| {
+            StockError::Other(format!(
+                "Failed to read eval report {}: {e}",
+                path.display()
+            ))
+        })?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
+impl fmt::Display for EvalReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "# Eval: {} ({}/{} passed, mean score {:.2})\n",
+            self.suite,
+            self.passed(),
+            self.cases.len(),
+            self.mean_score()
+        )?;
+        writeln!(f, "| Case | Score | Checks | Judge | Result |")?;
+        writeln!(f, "|------|-------|--------|-------|--------|")?;
+        for case in &self.cases {
+            let checks = format!(
+                "{}/{}",
+                case.checks.iter().filter(|c| c.passed).count(),
+                case.checks.len()
+            );
+            let judge = case
+                .judge
+                .as_ref()
+                .map_or_else(|| "-".to_string(), |j| format!("{:.1}", j.score * 10.0));
+            let result = if case.passed { "pass" } else { "FAIL" };
+            writeln!(
+                f,
+                "| {} | {:.2} | {checks} | {judge} | {result} |",
+                case.id, case.score
+            )?;
+        }
+
+        for case in self.cases.iter().filter(|c| !c.passed) {
+            writeln!(f, "\n## {}", case.id)?;
+            if let Some(error) = &case.error {
+                writeln!(f, "- error: {error}")?;
+            }
+            for check in case.checks.iter().filter(|c| !c.passed) {
+                writeln!(
+                    f,
+                    "- {}: {}",
+                    check.check,
+                    check.detail.as_deref().unwrap_or("failed")
+                )?;
+            }
+            if let Some(judge) = &case.judge {
+                writeln!(f, "- judge: {}", judge.reasoning)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Score of a case in the baseline and the current run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoreChange {
+    /// Case identifier
+    pub id: String,
+    /// Baseline score
+    pub baseline: f64,
+    /// Current score
+    pub current: f64,
+}
+
+/// Differences between a run and its baseline
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RegressionReport {
+    /// Mean score of the baseline
+    pub baseline_score: f64,
+    /// Mean score of the current run
+    pub current_score: f64,
+    /// Cases that got worse
+    pub regressions: Vec<ScoreChange>,
+    /// Cases that got better
+    pub improvements: Vec<ScoreChange>,
+    /// Cases not present in the baseline
+    pub new_cases: Vec<String>,
+    /// Baseline cases not present in the current run
+    pub missing_cases: Vec<String>,
+}
+
+impl RegressionReport {
+    /// Whether any case regressed
+    pub fn has_regressions(&self) -> bool {
+        !self.regressions.is_empty()
+    }
+}
+
+impl fmt::Display for RegressionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Mean score: {:.2} -> {:.2}",
+            self.baseline_score, self.current_score
+        )?;
+        for (title, changes) in [
+            ("Regressions", &self.regressions),
+            ("Improvements", &self.improvements),
+        ] {
+            if !changes.is_empty() {
+                writeln!(f, "{title}:")?;
+                for change in changes {
+                    writeln!(
+                        f,
+                        "  {}: {:.2} -> {:.2}",
+                        change.id, change.baseline, change.current
+                    )?;
+                }
+            }
+        }
+        if !self.new_cases.is_empty() {
+            writeln!(f, "New cases: {}", self.new_cases.join(", "))?;
+        }
+        if !self.missing_cases.is_empty() {
+            writeln!(f, "Missing cases: {}", self.missing_cases.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn case(id: &str, score: f64, passed: bool) -> CaseResult {
+        CaseResult {
+            id: id.to_string(),
+            output: String::new(),
+            error: None,
+            checks: Vec::new(),
+            judge: None,
+            score,
+            passed,
+            duration_ms: 0,
+        }
+    }
+
+    fn report(cases: Vec<CaseResult>) -> EvalReport {
+        EvalReport {
+            suite: "test".to_string(),
+            created_at: chrono::Utc::now(),
+            cases,
+        }
+    }
+
+    #[test]
+    fn test_compare_detects_regressions() {
+        let baseline = report(vec![
+            case("a", 0.9, true),
+            case("b", 0.8, true),
+            case("c", 0.4, false),
+            case("gone", 1.0, true),
+        ]);
+        let current = report(vec![
+            case("a", 0.88, true),
+            case("b", 0.6, false),
+            case("c", 0.9, true),
+            case("new", 1.0, true),
+        ]);
+
+        let diff = current.compare(&baseline, 0.05);
+        assert!(diff.has_regressions());
+        assert_eq!(diff.regressions[0].id, "b");
+        assert_eq!(diff.improvements[0].id, "c");
+        assert_eq!(diff.new_cases, vec!["new".to_string()]);
+        assert_eq!(diff.missing_cases, vec!["gone".to_string()]);
+    }
+
+    #[test]
+    fn test_report_round_trip() {
+        let path = std::env::temp_dir().join(format!("eval-report-{}.json", uuid::Uuid::new_v4()));
+        let original = report(vec![case("a", 0.5, false)]);
+        original.save(&path).unwrap();
+
+        let loaded = EvalReport::load(&path).unwrap();
+        assert_eq!(loaded.cases.len(), 1);
+        assert!(loaded.to_string().contains("| a | 0.50 | 0/0 | - | FAIL |"));
+        std::fs::remove_file(path).unwrap();
+    }
+}
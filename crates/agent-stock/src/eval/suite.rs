@@ -0,0 +1,196 @@
+//! Evaluation suites: queries, recorded data fixtures and expectations
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::error::{Result, StockError};
+
+/// Built-in battery of evaluation queries and fixtures
+const BUILTIN_SUITE: &str = include_str!("../../eval/suite.json");
+
+/// A recorded tool response
+///
+/// A fixture applies to a tool call when every key in `match` equals the
+/// corresponding call parameter (strings compare case-insensitively). An
+/// empty `match` applies to any call.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fixture {
+    /// Parameters the call must have for this fixture to apply
+    #[serde(rename = "match", default)]
+    pub match_params: Map<String, Value>,
+
+    /// Recorded tool output
+    pub response: Value,
+}
+
+impl Fixture {
+    /// Whether this fixture applies to a call with the given parameters
+    pub fn matches(&self, params: &Value) -> bool {
+        self.match_params
+            .iter()
+            .all(|(key, expected)| match (params.get(key), expected) {
+                (Some(Value::String(actual)), Value::String(expected)) => {
+                    actual.eq_ignore_ascii_case(expected)
+                }
+                (Some(actual), expected) => actual == expected,
+                (None, _) => false,
+            })
+    }
+}
+
+/// A deterministic check on an analysis output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Check {
+    /// Every symbol must be mentioned
+    Symbols { symbols: Vec<String> },
+    /// Some number in the output must be within `tolerance` (relative) of `expected`
+    Number {
+        label: String,
+        expected: f64,
+        #[serde(default = "default_tolerance")]
+        tolerance: f64,
+    },
+    /// Every section must be present (case-insensitive); `a|b` accepts either
+    Sections { sections: Vec<String> },
+    /// None of the phrases may appear (case-insensitive)
+    Excludes { phrases: Vec<String> },
+}
+
+fn default_tolerance() -> f64 {
+    0.02
+}
+
+/// A single evaluation query
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalCase {
+    /// Stable case identifier, used to match results across runs
+    pub id: String,
+
+    /// Query sent to the agent
+    pub query: String,
+
+    /// Recorded tool responses for this case, keyed by tool name
+    #[serde(default)]
+    pub fixtures: HashMap<String, Vec<Fixture>>,
+
+    /// Deterministic checks
+    #[serde(default)]
+    pub checks: Vec<Check>,
+
+    /// What a good answer looks like, for the LLM judge
+    #[serde(default)]
+    pub rubric: Option<String>,
+}
+
+/// A named battery of evaluation cases
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalSuite {
+    /// Suite name
+    pub name: String,
+
+    /// Recorded tool responses shared by all cases, keyed by tool name
+    #[serde(default)]
+    pub fixtures: HashMap<String, Vec<Fixture>>,
+
+    /// Evaluation cases
+    pub cases: Vec<EvalCase>,
+}
+
+impl EvalSuite {
+    /// The built-in suite bundled with the crate
+    pub fn builtin() -> Result<Self> {
+        Self::from_json(BUILTIN_SUITE)
+    }
+
+    /// Parse a suite from JSON
+    pub fn from_json(json: &str) -> Result<Self> {
+        let suite: Self = serde_json::from_str(json)?;
+        suite.validate()?;
+        Ok(suite)
+    }
+
+    /// Load a suite from a JSON file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|e| {
+            StockError::ConfigError(format!("Failed to read eval suite {}: {e}", path.display()))
+        })?;
+        Self::from_json(&json)
+    }
+
+    /// Fixtures for a case: the case's own fixtures take precedence over the suite's
+    pub fn fixtures_for(&self, case: &EvalCase) -> HashMap<String, Vec<Fixture>> {
+        let mut fixtures = self.fixtures.clone();
+        for (tool, case_fixtures) in &case.fixtures {
+            let entry = fixtures.entry(tool.clone()).or_default();
+            entry.splice(0..0, case_fixtures.iter().cloned());
+        }
+        fixtures
+    }
+
+    fn validate(&self) -> Result<()> {
+        let mut ids = std::collections::HashSet::new();
+        for case in &self.cases {
+            if !ids.insert(case.id.as_str()) {
+                return Err(StockError::ConfigError(format!(
+                    "Duplicate eval case id: {}",
+                    case.id
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_builtin_suite_parses() {
+        let suite = EvalSuite::builtin().unwrap();
+        assert!(!suite.cases.is_empty());
+        assert!(suite.cases.iter().all(|c| !c.checks.is_empty()));
+    }
+
+    #[test]
+    fn test_fixture_matching() {
+        let fixture: Fixture =
+            serde_json::from_value(json!({ "match": { "symbol": "AAPL" }, "response": {} }))
+                .unwrap();
+        assert!(fixture.matches(&json!({ "symbol": "aapl", "period": 14 })));
+        assert!(!fixture.matches(&json!({ "symbol": "MSFT" })));
+        assert!(!fixture.matches(&json!({})));
+    }
+
+    #[test]
+    fn test_case_fixtures_take_precedence() {
+        let suite = EvalSuite::from_json(
+            r#"{
+                "name": "t",
+                "fixtures": { "stock_data": [{ "response": { "price": 1 } }] },
+                "cases": [{
+                    "id": "a",
+                    "query": "q",
+                    "fixtures": { "stock_data": [{ "response": { "price": 2 } }] }
+                }]
+            }"#,
+        )
+        .unwrap();
+        let fixtures = suite.fixtures_for(&suite.cases[0]);
+        assert_eq!(fixtures["stock_data"][0].response["price"], 2);
+        assert_eq!(fixtures["stock_data"].len(), 2);
+    }
+
+    #[test]
+    fn test_duplicate_case_ids_rejected() {
+        let result = EvalSuite::from_json(
+            r#"{ "name": "t", "cases": [{ "id": "a", "query": "q" }, { "id": "a", "query": "q" }] }"#,
+        );
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,279 @@
+//! Idempotency keys for expensive requests
+//!
+//! A comprehensive analysis runs several agents and LLM calls, so a client
+//! that retries after a timeout should not pay for the pipeline twice. An
+//! [`IdempotencyCache`] runs a request once per key and hands the stored
+//! result back to every repeat within the retention window. Concurrent
+//! repeats wait for the first run instead of starting their own.
+//!
+//! Each key is bound to a fingerprint of the request it was first used with;
+//! reusing the key for a different request is an
+//! [`StockError::IdempotencyConflict`]. Failed runs are not stored, so a
+//! retry after an error runs again.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::idempotency::IdempotencyCache;
+//!
+//! let cache = IdempotencyCache::new(Duration::from_secs(86400));
+//! let first = cache.run("req-1", "analyze:AAPL", || engine.analyze("AAPL")).await?;
+//! let retry = cache.run("req-1", "analyze:AAPL", || engine.analyze("AAPL")).await?;
+//! assert!(!first.replayed && retry.replayed);
+//! ```
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+
+use crate::error::{Result, StockError};
+
+/// Result of a request run under an idempotency key
+#[derive(Debug, Clone, PartialEq)]
+pub struct Idempotent<T> {
+    /// The request's result
+    pub value: T,
+    /// Whether it was stored by an earlier run rather than computed now
+    pub replayed: bool,
+}
+
+/// One key's request fingerprint and, once finished, its result
+struct Entry<T> {
+    fingerprint: String,
+    created: Instant,
+    result: OnceCell<(T, Instant)>,
+}
+
+impl<T> Entry<T> {
+    /// Whether the entry is past `retention` at `now`
+    fn expired(&self, retention: Duration, now: Instant) -> bool {
+        let since = self
+            .result
+            .get()
+            .map_or(self.created, |(_, finished)| *finished);
+        now.saturating_duration_since(since) > retention
+    }
+}
+
+/// Results of finished requests, keyed by idempotency key
+pub struct IdempotencyCache<T> {
+    retention: Duration,
+    entries: Mutex<HashMap<String, Arc<Entry<T>>>>,
+}
+
+impl<T: Clone> IdempotencyCache<T> {
+    /// Create a cache that keeps results for `retention` after they finish
+    pub fn new(retention: Duration) -> Self {
+        Self {
+            retention,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// How long results are kept
+    pub fn retention(&self) -> Duration {
+        self.retention
+    }
+
+    /// Number of keys currently held
+    pub fn len(&self) -> usize {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    /// Whether no keys are held
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Run `request` once for `key`, or return its stored result
+    ///
+    /// `fingerprint` identifies the request (its endpoint and parameters);
+    /// a key first used with another fingerprint is rejected.
+    pub async fn run<F, Fut>(
+        &self,
+        key: &str,
+        fingerprint: &str,
+        request: F,
+    ) -> Result<Idempotent<T>>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let entry = {
+            let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+            let now = Instant::now();
+            // Entries someone is still waiting on stay, however old
+            entries.retain(|_, entry| {
+                Arc::strong_count(entry) > 1 || !entry.expired(self.retention, now)
+            });
+            Arc::clone(entries.entry(key.to_string()).or_insert_with(|| {
+                Arc::new(Entry {
+                    fingerprint: fingerprint.to_string(),
+                    created: now,
+                    result: OnceCell::new(),
+                })
+            }))
+        };
+
+        if entry.fingerprint != fingerprint {
+            return Err(StockError::IdempotencyConflict(format!(
+                "key '{key}' was already used for a different request"
+            )));
+        }
+
+        let mut ran = false;
+        let (value, _) = entry
+            .result
+            .get_or_try_init(|| {
+                ran = true;
+                async { Ok::<_, StockError>((request().await?, Instant::now())) }
+            })
+            .await?;
+
+        Ok(Idempotent {
+            value: value.clone(),
+            replayed: !ran,
+        })
+    }
+
+    /// Drop the stored result for `key`; returns whether one was held
+    pub fn forget(&self, key: &str) -> bool {
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        entries.remove(key).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_repeat_replays_stored_result() {
+        let cache = IdempotencyCache::new(Duration::from_secs(60));
+        let runs = AtomicUsize::new(0);
+        let request = || async { Ok(runs.fetch_add(1, Ordering::SeqCst) + 1) };
+
+        let first = cache.run("req-1", "analyze:AAPL", request).await.unwrap();
+        let retry = cache.run("req-1", "analyze:AAPL", request).await.unwrap();
+        assert_eq!(
+            first,
+            Idempotent {
+                value: 1,
+                replayed: false
+            }
+        );
+        assert_eq!(
+            retry,
+            Idempotent {
+                value: 1,
+                replayed: true
+            }
+        );
+
+        let other = cache.run("req-2", "analyze:AAPL", request).await.unwrap();
+        assert_eq!(other.value, 2);
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+
+        assert!(cache.forget("req-1"));
+        assert_eq!(
+            cache
+                .run("req-1", "analyze:AAPL", request)
+                .await
+                .unwrap()
+                .value,
+            3
+        );
+    }
+
+    #[tokio::test]
+    async fn test_key_reused_for_other_request() {
+        let cache = IdempotencyCache::new(Duration::from_secs(60));
+        cache
+            .run("req-1", "analyze:AAPL", || async { Ok(1) })
+            .await
+            .unwrap();
+
+        let err = cache
+            .run("req-1", "analyze:MSFT", || async { Ok(2) })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, StockError::IdempotencyConflict(_)), "{err}");
+        assert!(err.is_user_error());
+    }
+
+    #[tokio::test]
+    async fn test_failures_are_not_stored() {
+        let cache = IdempotencyCache::new(Duration::from_secs(60));
+        let failed = cache
+            .run("req-1", "analyze:AAPL", || async {
+                Err::<u32, _>(StockError::Timeout("llm".into()))
+            })
+            .await;
+        assert!(failed.is_err());
+
+        let retry = cache
+            .run("req-1", "analyze:AAPL", || async { Ok(7) })
+            .await
+            .unwrap();
+        assert_eq!(
+            retry,
+            Idempotent {
+                value: 7,
+                replayed: false
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_repeats_share_one_run() {
+        let cache = IdempotencyCache::new(Duration::from_secs(60));
+        let runs = AtomicUsize::new(0);
+        let request = || async {
+            runs.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Ok("report".to_string())
+        };
+
+        let (a, b) = tokio::join!(
+            cache.run("req-1", "analyze:AAPL", request),
+            cache.run("req-1", "analyze:AAPL", request)
+        );
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert_eq!(a.unwrap().value, "report");
+        assert!(b.unwrap().replayed);
+    }
+
+    #[tokio::test]
+    async fn test_results_expire_after_retention() {
+        let cache = IdempotencyCache::new(Duration::ZERO);
+        cache
+            .run("req-1", "analyze:AAPL", || async { Ok(1) })
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        let later = cache
+            .run("req-2", "analyze:AAPL", || async { Ok(2) })
+            .await
+            .unwrap();
+        assert!(!later.replayed);
+        assert_eq!(cache.len(), 1);
+        let rerun = cache
+            .run("req-1", "analyze:MSFT", || async { Ok(3) })
+            .await
+            .unwrap();
+        assert_eq!(
+            rerun,
+            Idempotent {
+                value: 3,
+                replayed: false
+            }
+        );
+    }
+}
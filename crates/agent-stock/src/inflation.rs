@@ -0,0 +1,263 @@
+//! Inflation-adjusted (real) returns
+//!
+//! A nominal return over a long horizon overstates what an investor gained:
+//! "is AAPL beating inflation over 10y" needs the return deflated by the
+//! change in the price level over the same window. The price level is CPI
+//! from FRED (`CPIAUCSL`), a monthly index dated the first of the month it
+//! measures and published about two weeks after that month ends. A date is
+//! matched to its own month's CPI, or to the latest published month when
+//! that is not out yet.
+
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::api::fred::Observation;
+use crate::api::{FredClient, fred_series};
+use crate::config::FRED_API_KEY_ENV;
+use crate::error::{Result, StockError};
+
+/// Monthly CPI index levels, oldest first
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CpiSeries {
+    months: Vec<(NaiveDate, f64)>,
+}
+
+impl CpiSeries {
+    /// Series from FRED observations in any order; missing values (".") are skipped
+    pub fn from_observations(observations: &[Observation]) -> Self {
+        let mut months: Vec<(NaiveDate, f64)> = observations
+            .iter()
+            .filter_map(|observation| {
+                let date = NaiveDate::parse_from_str(&observation.date, "%Y-%m-%d").ok()?;
+                let value: f64 = observation.value.parse().ok()?;
+                (value > 0.0).then_some((month_start(date), value))
+            })
+            .collect();
+        months.sort_by_key(|(month, _)| *month);
+        months.dedup_by_key(|(month, _)| *month);
+        Self { months }
+    }
+
+    /// CPI from the month of `start` to the latest release
+    pub async fn fetch(client: &FredClient, start: NaiveDate) -> Result<Self> {
+        let start = month_start(start).format("%Y-%m-%d").to_string();
+        let observations = client
+            .get_observations(fred_series::CPI, Some(&start), None, None)
+            .await?;
+        let series = Self::from_observations(&observations);
+        if series.months.is_empty() {
+            return Err(StockError::data_unavailable(
+                fred_series::CPI,
+                format!("No CPI observations since {start}"),
+            ));
+        }
+        Ok(series)
+    }
+
+    /// Latest month with a published CPI
+    pub fn latest_month(&self) -> Option<NaiveDate> {
+        self.months.last().map(|(month, _)| *month)
+    }
+
+    /// CPI month and level used for `date`: its own month, or the latest
+    /// published month before it
+    pub fn at(&self, date: NaiveDate) -> Option<(NaiveDate, f64)> {
+        let month = month_start(date);
+        self.months
+            .iter()
+            .rev()
+            .find(|(known, _)| *known <= month)
+            .copied()
+    }
+
+    /// Change in the price level between `start` and `end`
+    ///
+    /// `None` when CPI does not reach back to `start`.
+    pub fn adjustment(&self, start: NaiveDate, end: NaiveDate) -> Option<InflationAdjustment> {
+        let (first, _) = self.months.first()?;
+        if month_start(start) < *first {
+            return None;
+        }
+        let (start_month, start_cpi) = self.at(start)?;
+        let (end_month, end_cpi) = self.at(end)?;
+        let years = (end - start).num_days() as f64 / 365.25;
+        Some(InflationAdjustment {
+            start_month,
+            end_month,
+            start_cpi,
+            end_cpi,
+            inflation_pct: (end_cpi / start_cpi - 1.0) * 100.0,
+            years,
+        })
+    }
+}
+
+/// Inflation between `start` and `end`, from FRED CPI
+///
+/// Fails when no FRED client is configured or CPI does not cover `start`.
+pub async fn fetch_adjustment(
+    client: Option<&FredClient>,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<InflationAdjustment> {
+    let client = client.ok_or_else(|| {
+        StockError::ConfigError(format!(
+            "Inflation adjustment needs FRED CPI data. Set {FRED_API_KEY_ENV}."
+        ))
+    })?;
+    CpiSeries::fetch(client, start)
+        .await?
+        .adjustment(start, end)
+        .ok_or_else(|| {
+            StockError::data_unavailable(fred_series::CPI, format!("No CPI published for {start}"))
+        })
+}
+
+/// First day of the month `date` falls in
+fn month_start(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+/// CPI change over a holding period, for deflating returns over it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InflationAdjustment {
+    /// CPI month matched to the start of the period
+    pub start_month: NaiveDate,
+    /// CPI month matched to the end; earlier than the end's own month when
+    /// that CPI is not published yet
+    pub end_month: NaiveDate,
+    pub start_cpi: f64,
+    pub end_cpi: f64,
+    /// Cumulative inflation over the period
+    pub inflation_pct: f64,
+    /// Length of the holding period in years
+    pub years: f64,
+}
+
+impl InflationAdjustment {
+    /// Real return for a nominal return over the period
+    pub fn real_return_pct(&self, nominal_pct: f64) -> f64 {
+        real_return_pct(nominal_pct, self.inflation_pct)
+    }
+
+    /// Compound annual real return, for periods of at least a month
+    pub fn real_annualized_pct(&self, nominal_pct: f64) -> Option<f64> {
+        let growth = 1.0 + self.real_return_pct(nominal_pct) / 100.0;
+        (self.years >= 1.0 / 12.0 && growth > 0.0)
+            .then(|| (growth.powf(1.0 / self.years) - 1.0) * 100.0)
+    }
+
+    /// Compound annual inflation over the period
+    pub fn annualized_inflation_pct(&self) -> Option<f64> {
+        let growth = self.end_cpi / self.start_cpi;
+        (self.years >= 1.0 / 12.0).then(|| (growth.powf(1.0 / self.years) - 1.0) * 100.0)
+    }
+}
+
+/// Nominal return deflated by inflation over the same period, both in percent
+pub fn real_return_pct(nominal_pct: f64, inflation_pct: f64) -> f64 {
+    ((1.0 + nominal_pct / 100.0) / (1.0 + inflation_pct / 100.0) - 1.0) * 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::testing::MockApi;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn observation(date: &str, value: &str) -> Observation {
+        Observation {
+            date: date.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_real_return() {
+        assert!((real_return_pct(10.0, 10.0)).abs() < 1e-9);
+        assert!((real_return_pct(21.0, 10.0) - 10.0).abs() < 1e-9);
+        assert!(real_return_pct(3.0, 5.0) < 0.0);
+
+        let cpi = CpiSeries::from_observations(&[
+            observation("2024-02-01", "310.3"),
+            observation("2014-01-01", "235.3"),
+            observation("2024-01-01", "309.7"),
+            observation("2023-12-01", "."),
+        ]);
+        assert_eq!(cpi.latest_month(), Some(date("2024-02-01")));
+        assert_eq!(
+            cpi.at(date("2024-01-17")),
+            Some((date("2024-01-01"), 309.7))
+        );
+        // March CPI is not out yet: fall back to February
+        assert_eq!(
+            cpi.at(date("2024-03-14")),
+            Some((date("2024-02-01"), 310.3))
+        );
+
+        let adjustment = cpi
+            .adjustment(date("2014-01-15"), date("2024-03-14"))
+            .unwrap();
+        assert_eq!(adjustment.end_month, date("2024-02-01"));
+        assert!((adjustment.inflation_pct - (310.3 / 235.3 - 1.0) * 100.0).abs() < 1e-9);
+        // Doubling over a decade beat ~32% inflation
+        let real = adjustment.real_return_pct(100.0);
+        assert!((real - (2.0 / (310.3 / 235.3) - 1.0) * 100.0).abs() < 1e-9);
+        assert!(adjustment.real_annualized_pct(100.0).unwrap() < 7.2);
+        assert!(adjustment.annualized_inflation_pct().unwrap() > 2.0);
+
+        // CPI does not reach back far enough
+        assert!(
+            cpi.adjustment(date("2010-06-01"), date("2024-01-01"))
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_cpi() {
+        let api = MockApi::start().await;
+        api.mount_json(
+            "/fred/series/observations",
+            200,
+            r#"{"observations": [
+                {"realtime_start": "2024-03-15", "realtime_end": "2024-03-15", "date": "2024-02-01", "value": "310.326"},
+                {"realtime_start": "2024-03-15", "realtime_end": "2024-03-15", "date": "2024-01-01", "value": "309.685"}
+            ]}"#,
+        )
+        .await;
+
+        let cpi = CpiSeries::fetch(&api.fred(None), date("2024-01-10"))
+            .await
+            .unwrap();
+        assert_eq!(
+            cpi.at(date("2024-02-29")),
+            Some((date("2024-02-01"), 310.326))
+        );
+
+        let empty = MockApi::start().await;
+        empty
+            .mount_json("/fred/series/observations", 200, r#"{"observations": []}"#)
+            .await;
+        assert!(
+            CpiSeries::fetch(&empty.fred(None), date("2024-01-10"))
+                .await
+                .is_err()
+        );
+
+        let adjustment = fetch_adjustment(
+            Some(&api.fred(None)),
+            date("2024-01-10"),
+            date("2024-03-01"),
+        )
+        .await
+        .unwrap();
+        assert!((adjustment.inflation_pct - (310.326 / 309.685 - 1.0) * 100.0).abs() < 1e-9);
+        let err = fetch_adjustment(None, date("2024-01-10"), date("2024-03-01"))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains(FRED_API_KEY_ENV), "{err}");
+    }
+}
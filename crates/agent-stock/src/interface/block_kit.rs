@@ -0,0 +1,412 @@
+//! Slack Block Kit output
+//!
+//! A Slack message carries a `text` fallback, used in notifications, and the
+//! `blocks` that are displayed. [`SlackFormatter`] renders text in `mrkdwn`
+//! like the other formatters do in their markup, and lays analyses out as
+//! blocks:
+//!
+//! - a header with the symbol and analysis type
+//! - a section of fields with the key metrics: data freshness, time,
+//!   confidence and the numbers the agents attached (RSI, P/E, ...)
+//! - the [highlights](crate::interface::highlight): price moves and risk
+//! - the body, in sections of at most [`MAX_SECTION_CHARS`]
+//! - a context line with the sources and warnings
+//!
+//! Comparisons get highlights and a table of each symbol's returns,
+//! valuation and risk.
+//! Every other reply becomes plain sections with
+//! [`SlackFormatter::text_blocks`].
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::interface::block_kit::SlackFormatter;
+//!
+//! let blocks = SlackFormatter.analysis_blocks(&result, &context);
+//! let payload = json!({ "channel": channel, "text": fallback, "blocks": blocks });
+//! ```
+
+use serde_json::{Value, json};
+
+use crate::engine::result::{ComparisonResult, DataFreshness};
+use crate::engine::{AnalysisContext, AnalysisResult};
+use crate::interface::BotPlatform;
+use crate::interface::formatter::{
+    Formatter, Locale, localized_analysis, markup_analysis, split_message,
+};
+use crate::interface::highlight::{Highlights, emphasize_symbols};
+use crate::interface::markup::{Markup, escape_html, render};
+
+/// Most characters Slack accepts in a section's text
+pub const MAX_SECTION_CHARS: usize = 3000;
+
+/// Most blocks Slack accepts in one message
+pub const MAX_BLOCKS: usize = 50;
+
+/// Most fields Slack accepts in one section
+const MAX_FIELDS: usize = 10;
+
+/// Most characters Slack accepts in a header
+const MAX_HEADER_CHARS: usize = 150;
+
+/// Slack `mrkdwn` text and Block Kit output
+pub struct SlackFormatter;
+
+impl Formatter for SlackFormatter {
+    fn platform(&self) -> BotPlatform {
+        BotPlatform::Slack
+    }
+
+    fn format_analysis(&self, result: &AnalysisResult, context: &AnalysisContext) -> String {
+        markup_analysis(Markup::Slack, result, context)
+    }
+
+    fn format_table(&self, headers: &[String], rows: &[Vec<String>]) -> String {
+        Markup::Slack.table(headers, rows)
+    }
+
+    fn format_error(&self, error: &str) -> String {
+        format!("❌ *Error:* {}", escape_html(error))
+    }
+
+    fn format_help(&self) -> String {
+        "*Stock Analysis Bot*\n\
+        /analyze - Comprehensive analysis\n\
+        /technical - Technical analysis\n\
+        /help - Show help"
+            .to_string()
+    }
+}
+
+impl SlackFormatter {
+    /// Blocks of an analysis: header, key metric fields, body and notes
+    pub fn analysis_blocks(
+        &self,
+        result: &AnalysisResult,
+        context: &AnalysisContext,
+    ) -> Vec<Value> {
+        let locale = Locale::for_context(context);
+        let (_, content) = localized_analysis(result, context);
+
+        let mut blocks = vec![header(&format!(
+            "{} {:?} Analysis",
+            result.symbol, result.analysis_type
+        ))];
+        blocks.push(fields(&key_metrics(result, &locale)));
+        let highlights = Highlights::from_result(result).lines(Markup::Slack, &locale.language);
+        blocks.extend(sections(&highlights.join("\n")));
+        blocks.extend(sections(&render(&content, Markup::Slack)));
+
+        let mut notes = Vec::new();
+        if !result.sources.is_empty() {
+            notes.push(format!(
+                "Sources: {}",
+                escape_html(&result.sources.join(", "))
+            ));
+        }
+        notes.extend(
+            result
+                .warnings
+                .iter()
+                .map(|warning| format!("⚠️ {}", escape_html(warning))),
+        );
+        if !notes.is_empty() {
+            blocks.push(json!({
+                "type": "context",
+                "elements": notes
+                    .iter()
+                    .take(MAX_FIELDS)
+                    .map(|note| mrkdwn(note))
+                    .collect::<Vec<_>>(),
+            }));
+        }
+        limit(blocks)
+    }
+
+    /// Blocks of a comparison: header, metrics table and summary
+    pub fn comparison_blocks(
+        &self,
+        comparison: &ComparisonResult,
+        context: &AnalysisContext,
+    ) -> Vec<Value> {
+        let locale = Locale::for_context(context);
+        let mut blocks = vec![header(&format!(
+            "Comparison: {}",
+            comparison.symbols.join(" vs ")
+        ))];
+        let highlights = Highlights::from_comparison(comparison);
+        blocks.extend(sections(
+            &highlights.lines(Markup::Slack, &locale.language).join("\n"),
+        ));
+        if let Some((headers, rows)) = comparison_table(comparison, &locale) {
+            blocks.extend(sections(&Markup::Slack.table(&headers, &rows)));
+        }
+        let summary = emphasize_symbols(
+            &locale.localize_numbers(&comparison.summary),
+            &highlights.symbols,
+        );
+        blocks.extend(sections(&render(&summary, Markup::Slack)));
+        limit(blocks)
+    }
+
+    /// Sections of an `mrkdwn` reply
+    pub fn text_blocks(&self, text: &str) -> Vec<Value> {
+        limit(sections(text))
+    }
+}
+
+/// Label and value of each key metric of `result`
+///
+/// Numbers in the result's data follow freshness, time and confidence, in
+/// key order.
+pub(crate) fn key_metrics(result: &AnalysisResult, locale: &Locale) -> Vec<(String, String)> {
+    let freshness = match result.data_freshness {
+        DataFreshness::RealTime => "🟢 Real-time",
+        DataFreshness::Recent => "🟡 Recent",
+        DataFreshness::Stale => "🟠 Stale",
+        DataFreshness::Partial => "⚠️ Partial",
+    };
+    let mut metrics = vec![
+        ("Data".to_string(), freshness.to_string()),
+        (
+            "As of".to_string(),
+            locale.format_timestamp(result.timestamp),
+        ),
+    ];
+    if let Some(confidence) = result.confidence {
+        metrics.push((
+            "Confidence".to_string(),
+            format!("{:.0}%", confidence * 100.0),
+        ));
+    }
+
+    let mut numbers: Vec<(&String, f64)> = result
+        .data
+        .iter()
+        .filter_map(|(key, value)| Some((key, value.as_f64()?)))
+        .collect();
+    numbers.sort_by(|a, b| a.0.cmp(b.0));
+    metrics.extend(
+        numbers
+            .into_iter()
+            .map(|(key, value)| (metric_label(key), format_metric(locale, value))),
+    );
+    metrics.truncate(MAX_FIELDS);
+    metrics
+}
+
+/// `rsi` as "RSI", `pe_ratio` as "Pe ratio"
+fn metric_label(key: &str) -> String {
+    if key.len() <= 4 {
+        return key.to_uppercase();
+    }
+    let words = key.replace('_', " ");
+    let mut chars = words.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+/// Whole numbers as they are, large ones compact, others to two decimals
+fn format_metric(locale: &Locale, value: f64) -> String {
+    if value.abs() >= 1e6 {
+        locale.format_compact(value)
+    } else {
+        locale.format_number(value, if value.fract() == 0.0 { 0 } else { 2 })
+    }
+}
+
+/// Table of the metrics a comparison has, or `None` when it has none
+pub(crate) fn comparison_table(
+    comparison: &ComparisonResult,
+    locale: &Locale,
+) -> Option<(Vec<String>, Vec<Vec<String>>)> {
+    let metrics = &comparison.metrics;
+    if metrics.performance.is_empty() && metrics.valuation.is_empty() && metrics.risk.is_empty() {
+        return None;
+    }
+    let cell =
+        |value: Option<f64>, format: &dyn Fn(f64) -> String| value.map_or("—".to_string(), format);
+    let percent = |value: f64| format!("{value:+.1}%");
+    let number = |value: f64| format_metric(locale, value);
+
+    let headers = ["Symbol", "1M", "YTD", "P/E", "Market cap", "Beta"]
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    let rows = comparison
+        .symbols
+        .iter()
+        .map(|symbol| {
+            let performance = metrics.performance.get(symbol);
+            let valuation = metrics.valuation.get(symbol);
+            let risk = metrics.risk.get(symbol);
+            vec![
+                symbol.clone(),
+                cell(performance.and_then(|p| p.return_1m), &percent),
+                cell(performance.and_then(|p| p.return_ytd), &percent),
+                cell(valuation.and_then(|v| v.pe_ratio), &number),
+                cell(valuation.and_then(|v| v.market_cap), &number),
+                cell(risk.and_then(|r| r.beta), &number),
+            ]
+        })
+        .collect();
+    Some((headers, rows))
+}
+
+fn mrkdwn(text: &str) -> Value {
+    json!({ "type": "mrkdwn", "text": text })
+}
+
+fn header(text: &str) -> Value {
+    let text: String = text.chars().take(MAX_HEADER_CHARS).collect();
+    json!({ "type": "header", "text": { "type": "plain_text", "text": text, "emoji": true } })
+}
+
+fn fields(metrics: &[(String, String)]) -> Value {
+    let fields: Vec<Value> = metrics
+        .iter()
+        .map(|(label, value)| mrkdwn(&format!("*{}*\n{}", escape_html(label), escape_html(value))))
+        .collect();
+    json!({ "type": "section", "fields": fields })
+}
+
+/// `text` in sections of at most [`MAX_SECTION_CHARS`], code blocks kept
+/// balanced across them
+fn sections(text: &str) -> Vec<Value> {
+    if text.trim().is_empty() {
+        return Vec::new();
+    }
+    split_message(text, MAX_SECTION_CHARS)
+        .iter()
+        .map(|chunk| json!({ "type": "section", "text": mrkdwn(chunk) }))
+        .collect()
+}
+
+/// At most [`MAX_BLOCKS`] blocks, the last noting that the rest was cut
+fn limit(mut blocks: Vec<Value>) -> Vec<Value> {
+    if blocks.len() > MAX_BLOCKS {
+        blocks.truncate(MAX_BLOCKS - 1);
+        blocks.push(
+            json!({ "type": "context", "elements": [mrkdwn("… reply shortened to fit Slack")] }),
+        );
+    }
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::result::{PerformanceMetric, ValuationMetric};
+    use crate::interface::fixtures;
+
+    #[test]
+    fn test_analysis_blocks() {
+        let blocks = SlackFormatter.analysis_blocks(
+            &fixtures::technical_analysis(),
+            &fixtures::analysis_context(),
+        );
+        assert_eq!(blocks[0]["type"], "header");
+        assert_eq!(blocks[0]["text"]["text"], "AAPL Technical Analysis");
+
+        let fields: Vec<&str> = blocks[1]["fields"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|field| field["text"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                "*Data*\n🟢 Real-time",
+                "*As of*\n2024-03-15 14:30 UTC",
+                "*Confidence*\n72%",
+                "*RSI*\n58.30",
+            ]
+        );
+
+        assert!(
+            blocks[2]["text"]["text"]
+                .as_str()
+                .unwrap()
+                .starts_with("*AAPL* 1d ")
+        );
+        let body = blocks[3]["text"]["text"].as_str().unwrap();
+        assert!(
+            body.starts_with("*Trend*\n• RSI(14): *58.3* (neutral)"),
+            "{body}"
+        );
+        assert!(
+            body.contains("SMA_50 &amp; SMA_200 &lt;strong momentum&gt;"),
+            "{body}"
+        );
+        assert_eq!(
+            blocks.last().unwrap()["elements"][0]["text"],
+            "Sources: Yahoo Finance"
+        );
+    }
+
+    #[test]
+    fn test_comparison_table() {
+        let mut comparison = ComparisonResult::new(vec!["AAPL".into(), "MSFT".into()])
+            .with_summary("AAPL is cheaper; MSFT grows faster.");
+        comparison.metrics.performance.insert(
+            "AAPL".into(),
+            PerformanceMetric {
+                return_1m: Some(2.5),
+                ..Default::default()
+            },
+        );
+        comparison.metrics.valuation.insert(
+            "MSFT".into(),
+            ValuationMetric {
+                pe_ratio: Some(35.2),
+                market_cap: Some(3.1e12),
+                ..Default::default()
+            },
+        );
+
+        let blocks = SlackFormatter.comparison_blocks(&comparison, &fixtures::analysis_context());
+        assert_eq!(blocks[0]["text"]["text"], "Comparison: AAPL vs MSFT");
+        assert_eq!(blocks[1]["text"]["text"], "*AAPL* 1m 🟢 +2.5%");
+        assert_eq!(
+            blocks[2]["text"]["text"],
+            "```\nSymbol | 1M    | YTD | P/E   | Market cap | Beta\n\
+             -------|-------|-----|-------|------------|-----\n\
+             AAPL   | +2.5% | —   | —     | —          | —\n\
+             MSFT   | —     | —   | 35.20 | 3.1T       | —\n```"
+        );
+        assert_eq!(
+            blocks[3]["text"]["text"],
+            "*AAPL* is cheaper; *MSFT* grows faster."
+        );
+
+        // Without metrics there is no table
+        let bare = ComparisonResult::new(vec!["AAPL".into()]).with_summary("Only AAPL.");
+        assert_eq!(
+            SlackFormatter
+                .comparison_blocks(&bare, &fixtures::analysis_context())
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_long_replies_fit_slack_limits() {
+        let text = "A sentence about margins. ".repeat(300);
+        let blocks = SlackFormatter.text_blocks(&text);
+        assert_eq!(blocks.len(), 3);
+        assert!(
+            blocks
+                .iter()
+                .all(|b| b["text"]["text"].as_str().unwrap().chars().count() <= MAX_SECTION_CHARS)
+        );
+
+        let huge = "word ".repeat(200_000);
+        let blocks = SlackFormatter.text_blocks(&huge);
+        assert_eq!(blocks.len(), MAX_BLOCKS);
+        assert_eq!(blocks[MAX_BLOCKS - 1]["type"], "context");
+        assert!(SlackFormatter.text_blocks("  ").is_empty());
+    }
+}
@@ -0,0 +1,595 @@
+//! Rendered price charts
+//!
+//! [`ChartDataTool`] returns raw series; platforms that show images get them
+//! drawn as a PNG instead: candlesticks (or the close line when only closes
+//! are known) with 20- and 50-day moving averages, over an RSI(14) panel and
+//! a MACD(12, 26, 9) panel. Indicators are computed from the closes, so any
+//! chart data attached to an analysis as `chart` can be drawn.
+//! [`analysis_chart`] returns the image as an [`Attachment`] for Telegram and
+//! Feishu replies.
+//!
+//! Up candles are green and down candles red, reversed for Chinese readers
+//! as on the [`heatmap`](super::heatmap).
+//!
+//! [`ChartDataTool`]: crate::tools::ChartDataTool
+
+use agent_prompt::Language;
+use chrono::{DateTime, Utc};
+use plotters::prelude::*;
+use serde_json::Value;
+use ta::Next;
+use ta::indicators::{
+    MovingAverageConvergenceDivergence, RelativeStrengthIndex, SimpleMovingAverage,
+};
+
+use crate::engine::{AnalysisContext, AnalysisResult};
+use crate::error::{Result, StockError};
+use crate::interface::Locale;
+use crate::interface::interface::{Attachment, AttachmentType};
+use crate::interface::sparkline::CHART_DATA_KEY;
+
+/// Image width in pixels
+pub const CHART_WIDTH: u32 = 960;
+
+/// Image height in pixels
+pub const CHART_HEIGHT: u32 = 720;
+
+/// Periods of the moving averages drawn over the price
+pub const MOVING_AVERAGES: [usize; 2] = [20, 50];
+
+/// RSI period
+pub const RSI_PERIOD: usize = 14;
+
+/// MACD fast, slow and signal periods
+pub const MACD_PERIODS: (usize, usize, usize) = (12, 26, 9);
+
+/// Candles drawn at most; earlier ones only feed the indicators
+const MAX_CANDLES: usize = 130;
+
+const GAIN: RGBColor = RGBColor(26, 127, 55);
+const LOSS: RGBColor = RGBColor(207, 34, 46);
+const GRID: RGBColor = RGBColor(234, 238, 242);
+const BAND: RGBColor = RGBColor(175, 184, 193);
+const MA_COLORS: [RGBColor; 2] = [RGBColor(9, 105, 218), RGBColor(191, 135, 0)];
+const RSI_COLOR: RGBColor = RGBColor(130, 80, 223);
+const MACD_COLOR: RGBColor = RGBColor(9, 105, 218);
+const SIGNAL_COLOR: RGBColor = RGBColor(191, 135, 0);
+
+/// One trading period
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Candle {
+    pub timestamp: Option<DateTime<Utc>>,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+}
+
+impl Candle {
+    /// Candle of a close alone, as from a line series
+    pub fn from_close(timestamp: Option<DateTime<Utc>>, close: f64) -> Self {
+        Self {
+            timestamp,
+            open: close,
+            high: close,
+            low: close,
+            close,
+        }
+    }
+}
+
+/// MACD line, signal line and histogram at one candle
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MacdPoint {
+    pub macd: f64,
+    pub signal: f64,
+    pub histogram: f64,
+}
+
+/// Price history of a symbol, ready to draw
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceChart {
+    pub symbol: String,
+    /// Oldest first
+    pub candles: Vec<Candle>,
+    /// Whether the candles have open, high and low prices, or only closes
+    pub ohlc: bool,
+}
+
+impl PriceChart {
+    /// Chart of [`ChartDataTool`](crate::tools::ChartDataTool) output: its
+    /// candlesticks, or its close line if it has none; `None` with fewer
+    /// than two periods
+    pub fn from_chart_data(symbol: &str, chart: &Value) -> Option<Self> {
+        let candles: Vec<Candle> = items(&chart["candlestick"])
+            .filter_map(|item| {
+                let price = |key: &str| item[key].as_f64().filter(|v| v.is_finite());
+                Some(Candle {
+                    timestamp: timestamp(item),
+                    open: price("open")?,
+                    high: price("high")?,
+                    low: price("low")?,
+                    close: price("close")?,
+                })
+            })
+            .collect();
+        let (candles, ohlc) = if candles.len() >= 2 {
+            (candles, true)
+        } else {
+            let closes = items(&chart["line"]).filter_map(|item| {
+                let close = item["value"].as_f64().filter(|v| v.is_finite())?;
+                Some(Candle::from_close(timestamp(item), close))
+            });
+            (closes.collect(), false)
+        };
+
+        (candles.len() >= 2).then(|| Self {
+            symbol: symbol.to_string(),
+            candles,
+            ohlc,
+        })
+    }
+
+    /// Closing prices, oldest first
+    pub fn closes(&self) -> Vec<f64> {
+        self.candles.iter().map(|candle| candle.close).collect()
+    }
+
+    /// Simple moving average of the closes, `None` until `period` closes
+    pub fn sma(&self, period: usize) -> Vec<Option<f64>> {
+        let Ok(mut sma) = SimpleMovingAverage::new(period) else {
+            return vec![None; self.candles.len()];
+        };
+        self.closes()
+            .into_iter()
+            .enumerate()
+            .map(|(i, close)| Some(sma.next(close)).filter(|_| i + 1 >= period))
+            .collect()
+    }
+
+    /// RSI of the closes, `None` for the first `period` closes
+    pub fn rsi(&self, period: usize) -> Vec<Option<f64>> {
+        let Ok(mut rsi) = RelativeStrengthIndex::new(period) else {
+            return vec![None; self.candles.len()];
+        };
+        self.closes()
+            .into_iter()
+            .enumerate()
+            .map(|(i, close)| Some(rsi.next(close)).filter(|_| i >= period))
+            .collect()
+    }
+
+    /// MACD of the closes with [`MACD_PERIODS`], `None` until the signal
+    /// line has a full period
+    pub fn macd(&self) -> Vec<Option<MacdPoint>> {
+        let (fast, slow, signal) = MACD_PERIODS;
+        let Ok(mut macd) = MovingAverageConvergenceDivergence::new(fast, slow, signal) else {
+            return vec![None; self.candles.len()];
+        };
+        self.closes()
+            .into_iter()
+            .enumerate()
+            .map(|(i, close)| {
+                let output = macd.next(close);
+                (i + 2 >= slow + signal).then_some(MacdPoint {
+                    macd: output.macd,
+                    signal: output.signal,
+                    histogram: output.histogram,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Items of a JSON array, none for anything else
+fn items(value: &Value) -> impl Iterator<Item = &Value> {
+    value.as_array().into_iter().flatten()
+}
+
+fn timestamp(item: &Value) -> Option<DateTime<Utc>> {
+    let timestamp = DateTime::parse_from_rfc3339(item["timestamp"].as_str()?).ok()?;
+    Some(timestamp.with_timezone(&Utc))
+}
+
+/// Chart of an analysis' chart data as a PNG attachment
+///
+/// `None` when the analysis has no chart data or it cannot be drawn. Colors
+/// follow Chinese convention when the session language is Chinese.
+pub fn analysis_chart(result: &AnalysisResult, context: &AnalysisContext) -> Option<Attachment> {
+    let chart = PriceChart::from_chart_data(&result.symbol, result.data.get(CHART_DATA_KEY)?)?;
+    let red_for_gains = Locale::for_context(context).language == Language::Chinese;
+    match render_chart(&chart, red_for_gains) {
+        Ok(image) => Some(image),
+        Err(e) => {
+            tracing::warn!("No chart for {}: {}", result.symbol, e);
+            None
+        }
+    }
+}
+
+/// Price and indicator chart as a PNG chart attachment
+pub fn render_chart(chart: &PriceChart, red_for_gains: bool) -> Result<Attachment> {
+    let png = render_png(chart, red_for_gains, (CHART_WIDTH, CHART_HEIGHT))?;
+    Ok(Attachment {
+        attachment_type: AttachmentType::Chart,
+        content: png,
+        filename: Some(format!("{}-chart.png", chart.symbol)),
+        mime_type: "image/png".to_string(),
+    })
+}
+
+/// Draw the chart and encode it as PNG
+///
+/// The price takes the top 60% of the image, RSI and MACD share the rest.
+pub fn render_png(
+    chart: &PriceChart,
+    red_for_gains: bool,
+    (width, height): (u32, u32),
+) -> Result<Vec<u8>> {
+    let render_error =
+        |e: &dyn std::fmt::Display| StockError::Other(format!("Failed to render chart: {e}"));
+    let skip = chart.candles.len().saturating_sub(MAX_CANDLES);
+    let shown = |values: Vec<Option<f64>>| -> Vec<(f64, f64)> {
+        values
+            .into_iter()
+            .skip(skip)
+            .enumerate()
+            .filter_map(|(i, value)| Some((i as f64, value?)))
+            .collect()
+    };
+    let candles = &chart.candles[skip..];
+    let (up, down) = if red_for_gains {
+        (LOSS, GAIN)
+    } else {
+        (GAIN, LOSS)
+    };
+    let x_range = -0.5..(candles.len() as f64 - 0.5);
+
+    let mut pixels = vec![0u8; width as usize * height as usize * 3];
+    {
+        let root = BitMapBackend::with_buffer(&mut pixels, (width, height)).into_drawing_area();
+        root.fill(&WHITE).map_err(|e| render_error(&e))?;
+        let (price_area, rest) = root.split_vertically(height * 3 / 5);
+        let (rsi_area, macd_area) = rest.split_vertically((height - height * 3 / 5) * 2 / 5);
+
+        // Price, with the moving averages over it
+        let averages: Vec<(usize, Vec<(f64, f64)>)> = MOVING_AVERAGES
+            .iter()
+            .map(|&period| (period, shown(chart.sma(period))))
+            .collect();
+        let (low, high) = candles
+            .iter()
+            .map(|candle| (candle.low, candle.high))
+            .chain(
+                averages
+                    .iter()
+                    .flat_map(|(_, points)| points.iter().map(|&(_, v)| (v, v))),
+            )
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), (l, h)| {
+                (lo.min(l), hi.max(h))
+            });
+        let pad = ((high - low) * 0.05)
+            .max(high.abs() * 0.001)
+            .max(f64::EPSILON);
+        let (first, last) = (candles[0].close, candles[candles.len() - 1].close);
+        let change = if first == 0.0 {
+            0.0
+        } else {
+            (last - first) / first * 100.0
+        };
+        let mut price = ChartBuilder::on(&price_area)
+            .caption(
+                format!("{}  {first:.2} → {last:.2} ({change:+.1}%)", chart.symbol),
+                ("sans-serif", 22),
+            )
+            .margin(12)
+            .y_label_area_size(64)
+            .build_cartesian_2d(x_range.clone(), (low - pad)..(high + pad))
+            .map_err(|e| render_error(&e))?;
+        price
+            .configure_mesh()
+            .disable_x_mesh()
+            .disable_x_axis()
+            .light_line_style(GRID)
+            .y_label_formatter(&|value| format!("{value:.2}"))
+            .draw()
+            .map_err(|e| render_error(&e))?;
+        if chart.ohlc {
+            price
+                .draw_series(candles.iter().enumerate().map(|(i, candle)| {
+                    let x = i as f64;
+                    let color = if candle.close >= candle.open {
+                        up
+                    } else {
+                        down
+                    };
+                    PathElement::new(
+                        vec![(x, candle.low), (x, candle.high)],
+                        color.stroke_width(1),
+                    )
+                }))
+                .map_err(|e| render_error(&e))?;
+            price
+                .draw_series(candles.iter().enumerate().map(|(i, candle)| {
+                    let x = i as f64;
+                    let color = if candle.close >= candle.open {
+                        up
+                    } else {
+                        down
+                    };
+                    Rectangle::new(
+                        [(x - 0.35, candle.open), (x + 0.35, candle.close)],
+                        color.filled(),
+                    )
+                }))
+                .map_err(|e| render_error(&e))?;
+        } else {
+            let color = if last >= first { up } else { down };
+            let points = candles
+                .iter()
+                .enumerate()
+                .map(|(i, candle)| (i as f64, candle.close));
+            price
+                .draw_series(std::iter::once(PathElement::new(
+                    points.collect::<Vec<_>>(),
+                    color.stroke_width(2),
+                )))
+                .map_err(|e| render_error(&e))?;
+        }
+        for ((period, points), color) in averages.into_iter().zip(MA_COLORS) {
+            if points.is_empty() {
+                continue;
+            }
+            price
+                .draw_series(std::iter::once(PathElement::new(
+                    points,
+                    color.stroke_width(2),
+                )))
+                .map_err(|e| render_error(&e))?
+                .label(format!("MA{period}"))
+                .legend(move |(x, y)| {
+                    PathElement::new(vec![(x, y), (x + 16, y)], color.stroke_width(2))
+                });
+        }
+        price
+            .configure_series_labels()
+            .position(SeriesLabelPosition::UpperLeft)
+            .background_style(WHITE.mix(0.8))
+            .border_style(BAND)
+            .draw()
+            .map_err(|e| render_error(&e))?;
+
+        // RSI, with the overbought and oversold bands
+        let mut rsi = ChartBuilder::on(&rsi_area)
+            .margin_left(12)
+            .margin_right(12)
+            .y_label_area_size(64)
+            .build_cartesian_2d(x_range.clone(), 0.0..100.0)
+            .map_err(|e| render_error(&e))?;
+        rsi.configure_mesh()
+            .disable_x_mesh()
+            .disable_x_axis()
+            .light_line_style(GRID)
+            .y_labels(3)
+            .y_desc(format!("RSI({RSI_PERIOD})"))
+            .draw()
+            .map_err(|e| render_error(&e))?;
+        rsi.draw_series([30.0, 70.0].map(|level| {
+            PathElement::new(
+                vec![(x_range.start, level), (x_range.end, level)],
+                BAND.stroke_width(1),
+            )
+        }))
+        .map_err(|e| render_error(&e))?;
+        rsi.draw_series(std::iter::once(PathElement::new(
+            shown(chart.rsi(RSI_PERIOD)),
+            RSI_COLOR.stroke_width(2),
+        )))
+        .map_err(|e| render_error(&e))?;
+
+        // MACD and its signal line over the histogram, with the dates below
+        let macd: Vec<(usize, MacdPoint)> = chart
+            .macd()
+            .into_iter()
+            .skip(skip)
+            .enumerate()
+            .filter_map(|(i, point)| Some((i, point?)))
+            .collect();
+        let extent = macd
+            .iter()
+            .flat_map(|(_, p)| [p.macd, p.signal, p.histogram])
+            .fold(f64::EPSILON, |extent, v| extent.max(v.abs()));
+        let mut panel = ChartBuilder::on(&macd_area)
+            .margin_left(12)
+            .margin_right(12)
+            .margin_bottom(8)
+            .x_label_area_size(28)
+            .y_label_area_size(64)
+            .build_cartesian_2d(x_range, (-extent * 1.1)..(extent * 1.1))
+            .map_err(|e| render_error(&e))?;
+        panel
+            .configure_mesh()
+            .disable_x_mesh()
+            .light_line_style(GRID)
+            .x_labels(6)
+            .x_label_formatter(&|x| date_label(candles, *x))
+            .y_labels(3)
+            .y_label_formatter(&|value| format!("{value:.2}"))
+            .y_desc("MACD")
+            .draw()
+            .map_err(|e| render_error(&e))?;
+        panel
+            .draw_series(macd.iter().map(|&(i, point)| {
+                let x = i as f64;
+                let color = if point.histogram >= 0.0 { up } else { down };
+                Rectangle::new(
+                    [(x - 0.35, 0.0), (x + 0.35, point.histogram)],
+                    color.mix(0.6).filled(),
+                )
+            }))
+            .map_err(|e| render_error(&e))?;
+        for (color, value) in [
+            (
+                MACD_COLOR,
+                (|p: &MacdPoint| p.macd) as fn(&MacdPoint) -> f64,
+            ),
+            (SIGNAL_COLOR, |p: &MacdPoint| p.signal),
+        ] {
+            let points: Vec<(f64, f64)> = macd
+                .iter()
+                .map(|(i, point)| (*i as f64, value(point)))
+                .collect();
+            panel
+                .draw_series(std::iter::once(PathElement::new(
+                    points,
+                    color.stroke_width(2),
+                )))
+                .map_err(|e| render_error(&e))?;
+        }
+        root.present().map_err(|e| render_error(&e))?;
+    }
+
+    let image = image::RgbImage::from_raw(width, height, pixels)
+        .ok_or_else(|| StockError::Other("Failed to render chart: bad buffer size".to_string()))?;
+    let mut png = std::io::Cursor::new(Vec::new());
+    image
+        .write_to(&mut png, image::ImageFormat::Png)
+        .map_err(|e| render_error(&e))?;
+    Ok(png.into_inner())
+}
+
+/// Month and day of the candle nearest `x`, empty off the chart or without
+/// a timestamp
+fn date_label(candles: &[Candle], x: f64) -> String {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let index = x.round().max(0.0) as usize;
+    candles
+        .get(index)
+        .and_then(|candle| candle.timestamp)
+        .map(|timestamp| timestamp.format("%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::fixtures;
+    use serde_json::json;
+
+    fn candle_data(count: u32) -> Value {
+        let candles: Vec<Value> = (0..count)
+            .map(|i| {
+                let close = 170.0 + (f64::from(i) / 5.0).sin() * 6.0 + f64::from(i) * 0.1;
+                json!({
+                    "timestamp": format!("2024-{:02}-{:02}T00:00:00Z", i / 28 + 1, i % 28 + 1),
+                    "open": if i % 3 == 0 { close + 0.8 } else { close - 0.8 },
+                    "high": close + 1.5,
+                    "low": close - 2.0,
+                    "close": close,
+                    "volume": 1_000_000,
+                })
+            })
+            .collect();
+        json!({ "symbol": "AAPL", "candlestick": candles, "line": [] })
+    }
+
+    #[test]
+    fn test_price_chart_from_chart_data() {
+        let chart = PriceChart::from_chart_data("AAPL", &candle_data(63)).unwrap();
+        assert!(chart.ohlc);
+        assert_eq!(chart.candles.len(), 63);
+        assert_eq!(
+            chart.candles[1].timestamp.unwrap().to_rfc3339(),
+            "2024-01-02T00:00:00+00:00"
+        );
+
+        // Close lines stand in for missing candlesticks
+        let chart = PriceChart::from_chart_data("AAPL", &fixtures::chart_data()).unwrap();
+        assert!(!chart.ohlc);
+        assert_eq!(chart.candles.len(), 30);
+        let first = chart.candles[0];
+        assert_eq!(first, Candle::from_close(first.timestamp, first.close));
+
+        assert_eq!(
+            PriceChart::from_chart_data("AAPL", &json!({ "line": [{ "value": 1.0 }] })),
+            None
+        );
+        assert_eq!(PriceChart::from_chart_data("AAPL", &json!({})), None);
+    }
+
+    #[test]
+    fn test_indicators_wait_for_full_periods() {
+        let chart = PriceChart::from_chart_data("AAPL", &candle_data(63)).unwrap();
+
+        let sma = chart.sma(20);
+        assert_eq!(sma.len(), 63);
+        assert!(sma[18].is_none());
+        let expected: f64 = chart.closes()[..20].iter().sum::<f64>() / 20.0;
+        assert!((sma[19].unwrap() - expected).abs() < 1e-9);
+
+        let rsi = chart.rsi(RSI_PERIOD);
+        assert!(rsi[RSI_PERIOD - 1].is_none());
+        assert!(
+            rsi[RSI_PERIOD..]
+                .iter()
+                .all(|v| v.is_some_and(|v| (0.0..=100.0).contains(&v)))
+        );
+
+        let macd = chart.macd();
+        assert!(macd[32].is_none());
+        let point = macd[33].unwrap();
+        assert!((point.histogram - (point.macd - point.signal)).abs() < 1e-9);
+
+        assert!(chart.sma(0).iter().all(Option::is_none));
+        assert!(chart.sma(100).iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn test_render_chart_png() {
+        let chart = PriceChart::from_chart_data("AAPL", &candle_data(200)).unwrap();
+        let attachment = render_chart(&chart, false).unwrap();
+        assert_eq!(attachment.attachment_type, AttachmentType::Chart);
+        assert_eq!(attachment.filename.as_deref(), Some("AAPL-chart.png"));
+        assert!(attachment.content.starts_with(b"\x89PNG\r\n\x1a\n"));
+
+        let decoded = image::load_from_memory(&attachment.content)
+            .unwrap()
+            .to_rgb8();
+        assert_eq!(decoded.dimensions(), (CHART_WIDTH, CHART_HEIGHT));
+        assert!(
+            decoded
+                .pixels()
+                .any(|pixel| pixel.0 == [GAIN.0, GAIN.1, GAIN.2])
+        );
+        assert!(
+            decoded
+                .pixels()
+                .any(|pixel| pixel.0 == [LOSS.0, LOSS.1, LOSS.2])
+        );
+
+        // A flat close line is still drawn
+        let flat = PriceChart {
+            symbol: "FLAT".to_string(),
+            candles: vec![Candle::from_close(None, 10.0); 5],
+            ohlc: false,
+        };
+        assert!(render_chart(&flat, true).is_ok());
+    }
+
+    #[test]
+    fn test_analysis_chart() {
+        let chart = analysis_chart(
+            &fixtures::technical_analysis(),
+            &fixtures::analysis_context(),
+        )
+        .unwrap();
+        assert_eq!(chart.mime_type, "image/png");
+        assert!(
+            analysis_chart(
+                &fixtures::fundamental_analysis(),
+                &fixtures::analysis_context()
+            )
+            .is_none()
+        );
+    }
+}
@@ -0,0 +1,212 @@
+//! Deterministic fixtures for formatter golden tests
+//!
+//! Every fixture uses fixed timestamps and identifiers so rendered output is
+//! stable across runs. [`render_all`] renders every fixture through every
+//! [`Formatter`] method into one labelled document, which makes a single
+//! snapshot per platform enough to catch formatting regressions.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::interface::{fixtures, FormatterFactory, BotPlatform};
+//!
+//! let formatter = FormatterFactory::create(BotPlatform::Telegram);
+//! insta::assert_snapshot!("telegram", fixtures::render_all(formatter.as_ref()));
+//! ```
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde_json::json;
+use std::fmt::Write;
+
+use crate::engine::result::DataFreshness;
+use crate::engine::{AnalysisContext, AnalysisResult, AnalysisType};
+use crate::interface::{BotResponse, Formatter};
+
+/// Timestamp shared by all fixtures
+pub fn timestamp() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 3, 15, 14, 30, 0)
+        .single()
+        .unwrap_or_default()
+}
+
+/// Session context with fixed identifiers
+pub fn analysis_context() -> AnalysisContext {
+    let mut context = AnalysisContext::with_user("fixture-user");
+    context.session_id = "00000000-0000-0000-0000-000000000000".to_string();
+    context.current_symbols = vec!["AAPL".to_string()];
+    context.created_at = timestamp();
+    context.last_active = timestamp();
+    context
+}
+
+/// Session context preferring Chinese
+pub fn chinese_context() -> AnalysisContext {
+    let mut context = analysis_context();
+    context.preferences.language = "zh".to_string();
+    context
+}
+
+/// `ChartDataTool` output with 30 closes and an RSI series
+pub fn chart_data() -> serde_json::Value {
+    let point = |i: u32, value: f64| json!({ "timestamp": format!("2024-02-{:02}T00:00:00Z", i % 28 + 1), "value": value });
+    let closes: Vec<_> = (0..30)
+        .map(|i| {
+            point(
+                i,
+                172.5 + (f64::from(i) / 3.0).sin() * 4.0 + f64::from(i) * 0.2,
+            )
+        })
+        .collect();
+    let rsi: Vec<_> = (0..30)
+        .map(|i| point(i, 50.0 + (f64::from(i) / 4.0).cos() * 12.0))
+        .collect();
+    json!({ "symbol": "AAPL", "line": closes, "indicators": { "RSI_14": rsi } })
+}
+
+/// Technical analysis with Markdown, special characters and CJK text
+pub fn technical_analysis() -> AnalysisResult {
+    let mut result = AnalysisResult::new(
+        "AAPL",
+        AnalysisType::Technical,
+        "## Trend\n\
+         - RSI(14): **58.3** (neutral)\n\
+         - MACD: bullish crossover, histogram +0.42\n\
+         - Price > SMA_50 & SMA_200 <strong momentum>\n\n\
+         ## 结论\n\
+         短期偏多,支撑位 $172.50。",
+    )
+    .with_freshness(DataFreshness::RealTime)
+    .with_confidence(0.72)
+    .with_data("rsi", json!(58.3))
+    .with_data("chart", chart_data())
+    .add_source("Yahoo Finance");
+    result.timestamp = timestamp();
+    result
+}
+
+/// Fundamental analysis built from stale data, with warnings
+pub fn fundamental_analysis() -> AnalysisResult {
+    let mut result = AnalysisResult::new(
+        "MSFT",
+        AnalysisType::Fundamental,
+        "P/E 35.2 vs sector 28.1; market cap $3.1T.\nRevenue growth: 15% YoY.",
+    )
+    .with_freshness(DataFreshness::Stale)
+    .add_source("Alpha Vantage")
+    .add_source("SEC EDGAR");
+    result.add_warning("Quarterly data is 45 days old");
+    result.timestamp = timestamp();
+    result
+}
+
+/// Macro analysis with partial data and empty content
+pub fn partial_macro_analysis() -> AnalysisResult {
+    let mut result = AnalysisResult::new("MARKET", AnalysisType::Macro, "")
+        .with_freshness(DataFreshness::Partial);
+    result.timestamp = timestamp();
+    result
+}
+
+/// Earnings analysis quoting raw figures, for locale-aware number formatting
+pub fn large_numbers_analysis() -> AnalysisResult {
+    let mut result = AnalysisResult::new(
+        "AAPL",
+        AnalysisType::Earnings,
+        "Revenue 383285000000 (FY2023), net income 96995000000.\n\
+         Volume 58414460 on 20240315; EPS 6.13.",
+    )
+    .with_freshness(DataFreshness::Recent)
+    .add_source("SEC EDGAR");
+    result.timestamp = timestamp();
+    result
+}
+
+/// All analysis fixtures, labelled
+pub fn analysis_results() -> Vec<(&'static str, AnalysisResult)> {
+    vec![
+        ("technical", technical_analysis()),
+        ("fundamental_stale", fundamental_analysis()),
+        ("macro_partial_empty", partial_macro_analysis()),
+    ]
+}
+
+/// Comparison table headers and rows
+pub fn table() -> (Vec<String>, Vec<Vec<String>>) {
+    let headers = ["Symbol", "Price", "P/E", "Change"]
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    let rows = [
+        ["AAPL", "$178.25", "29.1", "+1.2%"],
+        ["MSFT", "$415.10", "35.2", "-0.4%"],
+        ["贵州茅台", "¥1,688", "27.9", "0.0%"],
+    ]
+    .iter()
+    .map(|row| row.iter().map(ToString::to_string).collect())
+    .collect();
+    (headers, rows)
+}
+
+/// Error messages, including one with markup characters
+pub fn errors() -> Vec<&'static str> {
+    vec![
+        "Invalid symbol: XYZ123",
+        "Rate limit exceeded for <alpha_vantage> *retry* in 60s",
+    ]
+}
+
+/// Bot responses of every response type
+pub fn bot_responses() -> Vec<(&'static str, BotResponse)> {
+    vec![
+        ("text", BotResponse::text("Added AAPL to watchlist")),
+        (
+            "formatted_with_actions",
+            BotResponse::formatted("*AAPL* Technical Analysis")
+                .with_action("Refresh", "/technical AAPL")
+                .with_action("Compare", "/compare AAPL MSFT")
+                .with_metadata(json!({ "symbol": "AAPL" })),
+        ),
+        ("error", BotResponse::error("Data not available for XYZ")),
+    ]
+}
+
+/// Render every fixture through every formatter method
+pub fn render_all(formatter: &dyn Formatter) -> String {
+    let context = analysis_context();
+    let mut output = String::new();
+
+    let _ = writeln!(output, "# platform: {}", formatter.platform());
+    for (label, result) in analysis_results() {
+        let _ = writeln!(
+            output,
+            "\n# analysis: {label}\n{}",
+            formatter.format_analysis(&result, &context)
+        );
+    }
+    let _ = writeln!(
+        output,
+        "\n# analysis: large_numbers\n{}",
+        formatter.format_analysis(&large_numbers_analysis(), &context)
+    );
+    let _ = writeln!(
+        output,
+        "\n# analysis: large_numbers_zh\n{}",
+        formatter.format_analysis(&large_numbers_analysis(), &chinese_context())
+    );
+    let (headers, rows) = table();
+    let _ = writeln!(
+        output,
+        "\n# table\n{}",
+        formatter.format_table(&headers, &rows)
+    );
+    let _ = writeln!(
+        output,
+        "\n# table: empty\n{}",
+        formatter.format_table(&[], &[])
+    );
+    for error in errors() {
+        let _ = writeln!(output, "\n# error\n{}", formatter.format_error(error));
+    }
+    let _ = writeln!(output, "\n# help\n{}", formatter.format_help());
+    output
+}
@@ -1,7 +1,429 @@
 //! Response formatting utilities
+//!
+//! [`Locale`] renders numbers and timestamps the way readers of the session's
+//! language expect: `12.3亿` rather than `1234567890` in Chinese, `1.23B` in
+//! English, thousands separators, and timestamps in local time. Every
+//! platform formatter applies it to analyses.
+//!
+//! [`MessageLimit`] keeps replies within each platform's message size:
+//! long text is split at paragraph, line or sentence boundaries, or, in
+//! [`Overflow::Expand`] mode, sent one page at a time behind a "Show more"
+//! action.
+//!
+//! Agent Markdown is converted to each platform's markup by
+//! [`crate::interface::markup`], and chart data attached to an analysis is
+//! shown as [`crate::interface::sparkline`]s. Tickers, price moves and risk
+//! levels stand out through [`crate::interface::highlight`].
+
+use agent_prompt::Language;
+use chrono::{DateTime, FixedOffset, NaiveDate, Offset, Utc};
 
 use crate::engine::{AnalysisContext, AnalysisResult};
 use crate::interface::BotPlatform;
+use crate::interface::block_kit::SlackFormatter;
+use crate::interface::highlight::{Highlights, emphasize_symbols};
+use crate::interface::markup::{Markup, escape_html, render};
+use crate::interface::sparkline::{CHART_DATA_KEY, SPARKLINE_POINTS, chart_lines};
+use crate::language::{self, TRANSLATION_DATA_KEY};
+
+/// Bare integers with at least this many digits are rewritten compactly
+const MIN_COMPACT_DIGITS: usize = 7;
+
+/// Number and date conventions for one language and timezone
+#[derive(Debug, Clone, PartialEq)]
+pub struct Locale {
+    /// Language numbers are written for
+    pub language: Language,
+    /// Offset timestamps are shown in
+    pub offset: FixedOffset,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self::new(Language::English)
+    }
+}
+
+impl Locale {
+    /// Locale for `language`, in China Standard Time for Chinese and UTC
+    /// otherwise
+    pub fn new(language: Language) -> Self {
+        let offset_hours = if language == Language::Chinese { 8 } else { 0 };
+        Self {
+            language,
+            offset: FixedOffset::east_opt(offset_hours * 3600).unwrap_or(Utc.fix()),
+        }
+    }
+
+    /// Locale for the language preference of a session
+    pub fn for_context(context: &AnalysisContext) -> Self {
+        Self::new(Language::from_code(&context.preferences.language))
+    }
+
+    /// Show timestamps at a different UTC offset
+    pub fn with_offset(mut self, offset: FixedOffset) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    fn is_chinese(&self) -> bool {
+        self.language == Language::Chinese
+    }
+
+    /// Number with thousands separators, e.g. `1,234,567.89`
+    pub fn format_number(&self, value: f64, decimals: usize) -> String {
+        let formatted = format!("{:.decimals$}", value.abs());
+        let (integer, fraction) = formatted
+            .split_once('.')
+            .map_or((formatted.as_str(), None), |(i, f)| (i, Some(f)));
+
+        let mut grouped = String::new();
+        for (i, digit) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                grouped.push(',');
+            }
+            grouped.push(digit);
+        }
+        let sign = if value < 0.0 && formatted.chars().any(|c| matches!(c, '1'..='9')) {
+            "-"
+        } else {
+            ""
+        };
+        match fraction {
+            Some(fraction) => format!("{sign}{grouped}.{fraction}"),
+            None => format!("{sign}{grouped}"),
+        }
+    }
+
+    /// Number in compact notation with three significant digits
+    ///
+    /// Chinese uses 万 (10⁴) and 亿 (10⁸); English uses K, M, B and T.
+    pub fn format_compact(&self, value: f64) -> String {
+        let units: &[(f64, &str)] = if self.is_chinese() {
+            &[(1e12, "万亿"), (1e8, "亿"), (1e4, "万")]
+        } else {
+            &[(1e12, "T"), (1e9, "B"), (1e6, "M"), (1e3, "K")]
+        };
+        let Some((scale, unit)) = units.iter().find(|(scale, _)| value.abs() >= *scale) else {
+            return self.format_number(value, if value.fract() == 0.0 { 0 } else { 2 });
+        };
+
+        let scaled = value / scale;
+        // log10 of a value in [1, 10000) fits comfortably in usize
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let integer_digits = scaled.abs().log10().floor() as usize + 1;
+        let decimals = 3usize.saturating_sub(integer_digits);
+        let number = format!("{scaled:.decimals$}");
+        let number = if number.contains('.') {
+            number.trim_end_matches('0').trim_end_matches('.')
+        } else {
+            &number
+        };
+        format!("{number}{unit}")
+    }
+
+    /// Timestamp in the locale's offset, e.g. `2024-03-15 22:30 UTC+8`
+    pub fn format_timestamp(&self, timestamp: DateTime<Utc>) -> String {
+        let local = timestamp.with_timezone(&self.offset);
+        let seconds = self.offset.local_minus_utc();
+        let zone = match (seconds / 3600, (seconds % 3600).abs() / 60) {
+            (0, 0) => "UTC".to_string(),
+            (hours, 0) => format!("UTC{hours:+}"),
+            (hours, minutes) => format!("UTC{hours:+}:{minutes:02}"),
+        };
+        format!("{} {zone}", local.format("%Y-%m-%d %H:%M"))
+    }
+
+    /// Rewrite bare large integers in prose compactly
+    ///
+    /// Runs of at least seven digits that stand alone (not inside an
+    /// identifier, decimal or already-separated number) become e.g. `12.3亿`.
+    /// Eight-digit runs that read as a `YYYYMMDD` date are left alone.
+    pub fn localize_numbers(&self, text: &str) -> String {
+        let chars: Vec<char> = text.chars().collect();
+        let mut output = String::with_capacity(text.len());
+        let mut i = 0;
+        while i < chars.len() {
+            if !chars[i].is_ascii_digit() {
+                output.push(chars[i]);
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            let digits: String = chars[start..i].iter().collect();
+
+            let attached = |c: Option<&char>| {
+                c.is_some_and(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | ','))
+            };
+            let before = start.checked_sub(1).and_then(|j| chars.get(j));
+            let after = chars.get(i);
+            // A trailing sentence period is not part of the number
+            let after_is_decimal =
+                after == Some(&'.') && chars.get(i + 1).is_some_and(char::is_ascii_digit);
+            let standalone = !attached(before)
+                && (!attached(after) || (after == Some(&'.') && !after_is_decimal));
+
+            match digits.parse::<f64>() {
+                Ok(value)
+                    if standalone
+                        && digits.len() >= MIN_COMPACT_DIGITS
+                        && !digits.starts_with('0')
+                        && !is_date(&digits) =>
+                {
+                    output.push_str(&self.format_compact(value));
+                }
+                _ => output.push_str(&digits),
+            }
+        }
+        output
+    }
+}
+
+/// Whether an eight-digit run reads as a `YYYYMMDD` date
+fn is_date(digits: &str) -> bool {
+    digits.len() == 8 && NaiveDate::parse_from_str(digits, "%Y%m%d").is_ok()
+}
+
+/// Summary line and body of an analysis, localized
+///
+/// The body is [`localized_content`]; chart data attached to the result is
+/// appended as sparklines.
+pub(crate) fn localized_analysis(
+    result: &AnalysisResult,
+    context: &AnalysisContext,
+) -> (String, String) {
+    let locale = Locale::for_context(context);
+    let mut content = localized_content(result, context);
+    if let Some(chart) = result.data.get(CHART_DATA_KEY) {
+        let lines = chart_lines(chart, SPARKLINE_POINTS);
+        if !lines.is_empty() {
+            content = format!("{}\n\n{}", content.trim_end(), lines.join("\n"));
+        }
+    }
+    (
+        result.summary_at(&locale.format_timestamp(result.timestamp)),
+        content,
+    )
+}
+
+/// Markdown content of an analysis, localized, with its tickers in bold
+///
+/// A bilingual analysis shows the content and its translation as two
+/// sections, each localized for its own language.
+pub(crate) fn localized_content(result: &AnalysisResult, context: &AnalysisContext) -> String {
+    let content = match translation(result) {
+        Some((from, to, translation)) => language::two_sections(
+            (
+                &from,
+                &Locale::new(from.clone()).localize_numbers(&result.content),
+            ),
+            (&to, &Locale::new(to.clone()).localize_numbers(translation)),
+        ),
+        None => Locale::for_context(context).localize_numbers(&result.content),
+    };
+    emphasize_symbols(&content, &Highlights::from_result(result).symbols)
+}
+
+/// Languages and text of the translation attached to a bilingual analysis
+fn translation(result: &AnalysisResult) -> Option<(Language, Language, &str)> {
+    let data = result.data.get(TRANSLATION_DATA_KEY)?;
+    Some((
+        Language::from_code(data.get("from")?.as_str()?),
+        Language::from_code(data.get("to")?.as_str()?),
+        data.get("content")?.as_str()?,
+    ))
+}
+
+/// Room reserved on each page for a `(12/34)` page marker
+const PAGE_MARKER_RESERVE: usize = 12;
+
+/// Room reserved on each page for closing and reopening a block
+const BLOCK_RESERVE: usize = 16;
+
+/// Blocks kept balanced across chunks, as (open, close) delimiters
+const BLOCKS: &[(&str, &str)] = &[
+    ("```", "```"),
+    ("<pre>", "</pre>"),
+    ("<blockquote>", "</blockquote>"),
+];
+
+/// Sentence endings, preferred split points after paragraph and line breaks
+const SENTENCE_ENDS: &[&str] = &["。", "！", "？", "；", ". ", "! ", "? ", "; "];
+
+/// What to do with a reply longer than the platform allows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overflow {
+    /// Send every page as a separate message
+    #[default]
+    Split,
+    /// Send the first page with a "Show more" action; `/more` sends the next
+    Expand,
+}
+
+/// Maximum message size of a platform and how to handle longer replies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageLimit {
+    /// Maximum characters per message (`None` for no limit)
+    pub max_chars: Option<usize>,
+    /// Handling of replies over the limit
+    pub overflow: Overflow,
+}
+
+impl MessageLimit {
+    /// No limit
+    pub fn unlimited() -> Self {
+        Self {
+            max_chars: None,
+            overflow: Overflow::Split,
+        }
+    }
+
+    /// Documented limit of a platform
+    ///
+    /// Telegram allows 4096 characters. DingTalk, Feishu and WeCom limit
+    /// bytes, so their limits leave room for three-byte CJK characters. Slack
+    /// truncates message text after 40,000 characters. Matrix events are
+    /// capped at 64 KiB and carry the reply both as HTML and as plain text.
+    pub fn for_platform(platform: BotPlatform) -> Self {
+        let max_chars = match platform {
+            BotPlatform::Telegram => Some(4096),
+            BotPlatform::DingTalk => Some(6000),
+            BotPlatform::Feishu => Some(10000),
+            BotPlatform::Slack => Some(40000),
+            BotPlatform::WeCom => Some(680),
+            BotPlatform::Matrix => Some(10000),
+            BotPlatform::CLI | BotPlatform::Web | BotPlatform::Custom => None,
+        };
+        Self {
+            max_chars,
+            overflow: Overflow::Split,
+        }
+    }
+
+    /// Platform limit, overridden by `<PLATFORM>_MAX_MESSAGE_LENGTH` and
+    /// `<PLATFORM>_MESSAGE_OVERFLOW` (`split` or `expand`), e.g.
+    /// `TELEGRAM_MAX_MESSAGE_LENGTH=3000`
+    pub fn from_env(platform: BotPlatform) -> Self {
+        let prefix = platform.to_string().to_uppercase();
+        let mut limit = Self::for_platform(platform);
+        if let Some(max_chars) = std::env::var(format!("{prefix}_MAX_MESSAGE_LENGTH"))
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+        {
+            limit.max_chars = Some(max_chars);
+        }
+        if let Ok(overflow) = std::env::var(format!("{prefix}_MESSAGE_OVERFLOW")) {
+            limit.overflow = match overflow.trim().to_lowercase().as_str() {
+                "expand" => Overflow::Expand,
+                _ => Overflow::Split,
+            };
+        }
+        limit
+    }
+
+    /// Set the maximum characters per message
+    pub fn with_max_chars(mut self, max_chars: usize) -> Self {
+        self.max_chars = Some(max_chars);
+        self
+    }
+
+    /// Set the overflow handling
+    pub fn with_overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Split `text` into messages within the limit
+    ///
+    /// Multi-page replies end each page with a `(1/3)` marker.
+    pub fn paginate(&self, text: &str) -> Vec<String> {
+        let Some(max_chars) = self.max_chars.filter(|max| text.chars().count() > *max) else {
+            return vec![text.to_string()];
+        };
+        let pages = split_message(text, max_chars.saturating_sub(PAGE_MARKER_RESERVE));
+        let total = pages.len();
+        pages
+            .into_iter()
+            .enumerate()
+            .map(|(i, page)| format!("{page}\n\n({}/{total})", i + 1))
+            .collect()
+    }
+}
+
+/// Split `text` into chunks of at most `max_chars` characters
+///
+/// Chunks end at the last paragraph break, line break, sentence end or space
+/// that leaves the chunk at least a quarter full, in that order of
+/// preference; only unbroken text is cut mid-word. Code fences and Telegram
+/// `<pre>`/`<blockquote>` blocks split across chunks are closed and reopened.
+pub fn split_message(text: &str, max_chars: usize) -> Vec<String> {
+    let budget = max_chars.saturating_sub(BLOCK_RESERVE).max(1);
+    let mut chunks = Vec::new();
+    let mut rest = text.trim().to_string();
+    let mut reopen: Option<(&str, &str)> = None;
+
+    while !rest.is_empty() {
+        if let Some((open, close)) = reopen {
+            rest.insert_str(
+                0,
+                &if open == close {
+                    format!("{open}\n")
+                } else {
+                    open.to_string()
+                },
+            );
+        }
+        if rest.chars().count() <= max_chars {
+            chunks.push(rest);
+            break;
+        }
+
+        let window_end = rest
+            .char_indices()
+            .nth(budget)
+            .map_or(rest.len(), |(i, _)| i);
+        let window = &rest[..window_end];
+        let min_cut = window.len() / 4;
+        let cut = ["\n\n", "\n"]
+            .iter()
+            .chain(SENTENCE_ENDS)
+            .chain(&[" "])
+            .find_map(|boundary| {
+                window
+                    .rfind(boundary)
+                    .map(|i| i + boundary.len())
+                    .filter(|i| *i > min_cut)
+            })
+            .unwrap_or(window_end);
+
+        let mut chunk = rest[..cut].trim_end().to_string();
+        reopen = unclosed_block(&chunk);
+        if let Some((open, close)) = reopen {
+            chunk.push_str(&if open == close {
+                format!("\n{close}")
+            } else {
+                close.to_string()
+            });
+        }
+        chunks.push(chunk);
+        rest = rest[cut..].trim_start().to_string();
+    }
+    chunks
+}
+
+/// Block left open at the end of `chunk`, if any
+fn unclosed_block(chunk: &str) -> Option<(&'static str, &'static str)> {
+    BLOCKS.iter().copied().find(|(open, close)| {
+        if open == close {
+            chunk.matches(open).count() % 2 == 1
+        } else {
+            chunk.matches(open).count() > chunk.matches(close).count()
+        }
+    })
+}
 
 pub trait Formatter: Send + Sync {
     fn platform(&self) -> BotPlatform;
@@ -9,6 +431,43 @@ pub trait Formatter: Send + Sync {
     fn format_table(&self, headers: &[String], rows: &[Vec<String>]) -> String;
     fn format_error(&self, error: &str) -> String;
     fn format_help(&self) -> String;
+
+    /// Message size limit of the platform
+    fn message_limit(&self) -> MessageLimit {
+        MessageLimit::for_platform(self.platform())
+    }
+}
+
+/// Formatter with a configured message limit
+struct LimitedFormatter {
+    inner: Box<dyn Formatter>,
+    limit: MessageLimit,
+}
+
+impl Formatter for LimitedFormatter {
+    fn platform(&self) -> BotPlatform {
+        self.inner.platform()
+    }
+
+    fn format_analysis(&self, result: &AnalysisResult, context: &AnalysisContext) -> String {
+        self.inner.format_analysis(result, context)
+    }
+
+    fn format_table(&self, headers: &[String], rows: &[Vec<String>]) -> String {
+        self.inner.format_table(headers, rows)
+    }
+
+    fn format_error(&self, error: &str) -> String {
+        self.inner.format_error(error)
+    }
+
+    fn format_help(&self) -> String {
+        self.inner.format_help()
+    }
+
+    fn message_limit(&self) -> MessageLimit {
+        self.limit
+    }
 }
 
 pub struct CliFormatter;
@@ -17,11 +476,13 @@ impl Formatter for CliFormatter {
     fn platform(&self) -> BotPlatform {
         BotPlatform::CLI
     }
-    
-    fn format_analysis(&self, result: &AnalysisResult, _context: &AnalysisContext) -> String {
-        format!("{}\n\n{}", result.summary(), result.content)
+
+    fn format_analysis(&self, result: &AnalysisResult, context: &AnalysisContext) -> String {
+        let (summary, content) = localized_analysis(result, context);
+        let summary = with_highlights(summary, Markup::Markdown, result, context);
+        format!("{summary}\n\n{content}")
     }
-    
+
     fn format_table(&self, headers: &[String], rows: &[Vec<String>]) -> String {
         let mut output = String::new();
         output.push_str(&headers.join(" | "));
@@ -32,53 +493,189 @@ impl Formatter for CliFormatter {
         }
         output
     }
-    
+
     fn format_error(&self, error: &str) -> String {
         format!("❌ Error: {error}")
     }
-    
+
     fn format_help(&self) -> String {
         "Stock Analysis Bot Commands:\n\
         /analyze <symbol> - Comprehensive analysis\n\
         /technical <symbol> - Technical analysis\n\
         /help - Show help\n\
-        /exit - Exit".to_string()
+        /exit - Exit"
+            .to_string()
     }
 }
 
+/// Telegram `parse_mode=HTML` output
 pub struct TelegramFormatter;
 
 impl Formatter for TelegramFormatter {
     fn platform(&self) -> BotPlatform {
         BotPlatform::Telegram
     }
-    
-    fn format_analysis(&self, result: &AnalysisResult, _context: &AnalysisContext) -> String {
-        format!("*{}*\n\n{}", result.summary(), result.content)
+
+    fn format_analysis(&self, result: &AnalysisResult, context: &AnalysisContext) -> String {
+        markup_analysis(Markup::TelegramHtml, result, context)
     }
-    
+
     fn format_table(&self, headers: &[String], rows: &[Vec<String>]) -> String {
-        let mut output = String::from("```\n");
-        output.push_str(&headers.join(" | "));
-        output.push('\n');
-        for row in rows {
-            output.push_str(&row.join(" | "));
-            output.push('\n');
-        }
-        output.push_str("```");
-        output
+        Markup::TelegramHtml.table(headers, rows)
+    }
+
+    fn format_error(&self, error: &str) -> String {
+        format!("❌ <b>Error:</b> {}", escape_html(error))
+    }
+
+    fn format_help(&self) -> String {
+        "<b>Stock Analysis Bot</b>\n\
+        /analyze - Comprehensive analysis\n\
+        /technical - Technical analysis\n\
+        /help - Show help"
+            .to_string()
+    }
+}
+
+/// DingTalk `markdown` message output
+pub struct DingTalkFormatter;
+
+impl Formatter for DingTalkFormatter {
+    fn platform(&self) -> BotPlatform {
+        BotPlatform::DingTalk
+    }
+
+    fn format_analysis(&self, result: &AnalysisResult, context: &AnalysisContext) -> String {
+        markup_analysis(Markup::DingTalk, result, context)
+    }
+
+    fn format_table(&self, headers: &[String], rows: &[Vec<String>]) -> String {
+        Markup::DingTalk.table(headers, rows)
+    }
+
+    fn format_error(&self, error: &str) -> String {
+        format!("❌ **Error:** {error}")
+    }
+
+    fn format_help(&self) -> String {
+        "**Stock Analysis Bot**\n\
+        - /analyze - Comprehensive analysis\n\
+        - /technical - Technical analysis\n\
+        - /help - Show help"
+            .to_string()
+    }
+}
+
+/// Feishu `lark_md` card output
+pub struct FeishuFormatter;
+
+impl Formatter for FeishuFormatter {
+    fn platform(&self) -> BotPlatform {
+        BotPlatform::Feishu
+    }
+
+    fn format_analysis(&self, result: &AnalysisResult, context: &AnalysisContext) -> String {
+        markup_analysis(Markup::Feishu, result, context)
+    }
+
+    fn format_table(&self, headers: &[String], rows: &[Vec<String>]) -> String {
+        Markup::Feishu.table(headers, rows)
+    }
+
+    fn format_error(&self, error: &str) -> String {
+        format!("❌ **Error:** {error}")
+    }
+
+    fn format_help(&self) -> String {
+        "**Stock Analysis Bot**\n\
+        /analyze - Comprehensive analysis\n\
+        /technical - Technical analysis\n\
+        /help - Show help"
+            .to_string()
+    }
+}
+
+/// WeCom `markdown` message output
+pub struct WeComFormatter;
+
+impl Formatter for WeComFormatter {
+    fn platform(&self) -> BotPlatform {
+        BotPlatform::WeCom
+    }
+
+    fn format_analysis(&self, result: &AnalysisResult, context: &AnalysisContext) -> String {
+        markup_analysis(Markup::WeCom, result, context)
+    }
+
+    fn format_table(&self, headers: &[String], rows: &[Vec<String>]) -> String {
+        Markup::WeCom.table(headers, rows)
     }
-    
+
     fn format_error(&self, error: &str) -> String {
-        format!("❌ *Error:* {error}")
+        format!("❌ **Error:** {error}")
     }
-    
+
     fn format_help(&self) -> String {
-        "*Stock Analysis Bot*\n\
+        "**Stock Analysis Bot**\n\
         /analyze - Comprehensive analysis\n\
         /technical - Technical analysis\n\
-        /help - Show help".to_string()
+        /help - Show help"
+            .to_string()
+    }
+}
+
+/// Matrix `org.matrix.custom.html` output
+pub struct MatrixFormatter;
+
+impl Formatter for MatrixFormatter {
+    fn platform(&self) -> BotPlatform {
+        BotPlatform::Matrix
     }
+
+    fn format_analysis(&self, result: &AnalysisResult, context: &AnalysisContext) -> String {
+        markup_analysis(Markup::TelegramHtml, result, context)
+    }
+
+    fn format_table(&self, headers: &[String], rows: &[Vec<String>]) -> String {
+        Markup::TelegramHtml.table(headers, rows)
+    }
+
+    fn format_error(&self, error: &str) -> String {
+        format!("❌ <b>Error:</b> {}", escape_html(error))
+    }
+
+    fn format_help(&self) -> String {
+        "<b>Stock Analysis Bot</b>\n\
+        /analyze - Comprehensive analysis\n\
+        /technical - Technical analysis\n\
+        /help - Show help"
+            .to_string()
+    }
+}
+
+/// Bold summary line, highlights and the body rendered from Markdown
+pub(crate) fn markup_analysis(
+    markup: Markup,
+    result: &AnalysisResult,
+    context: &AnalysisContext,
+) -> String {
+    let (summary, content) = localized_analysis(result, context);
+    let summary = with_highlights(markup.bold(&summary), markup, result, context);
+    format!("{summary}\n\n{}", render(&content, markup))
+}
+
+/// `summary` followed by one line per symbol with highlighted moves and risk
+pub(crate) fn with_highlights(
+    summary: String,
+    markup: Markup,
+    result: &AnalysisResult,
+    context: &AnalysisContext,
+) -> String {
+    let language = Locale::for_context(context).language;
+    std::iter::once(summary)
+        .chain(Highlights::from_result(result).lines(markup, &language))
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 pub struct FormatterFactory;
@@ -88,7 +685,158 @@ impl FormatterFactory {
         match platform {
             BotPlatform::CLI => Box::new(CliFormatter),
             BotPlatform::Telegram => Box::new(TelegramFormatter),
+            BotPlatform::DingTalk => Box::new(DingTalkFormatter),
+            BotPlatform::Feishu => Box::new(FeishuFormatter),
+            BotPlatform::Slack => Box::new(SlackFormatter),
+            BotPlatform::WeCom => Box::new(WeComFormatter),
+            BotPlatform::Matrix => Box::new(MatrixFormatter),
             _ => Box::new(CliFormatter),
         }
     }
+
+    /// Create a formatter with a non-default message limit
+    pub fn create_with_limit(platform: BotPlatform, limit: MessageLimit) -> Box<dyn Formatter> {
+        Box::new(LimitedFormatter {
+            inner: Self::create(platform),
+            limit,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::fixtures;
+
+    #[test]
+    fn test_compact_numbers() {
+        let zh = Locale::new(Language::Chinese);
+        let en = Locale::default();
+        assert_eq!(zh.format_compact(1_234_567_890.0), "12.3亿");
+        assert_eq!(zh.format_compact(45_600.0), "4.56万");
+        assert_eq!(zh.format_compact(3.1e12), "3.1万亿");
+        assert_eq!(en.format_compact(1_234_567_890.0), "1.23B");
+        assert_eq!(en.format_compact(-456_000_000.0), "-456M");
+        assert_eq!(en.format_compact(999.0), "999");
+        assert_eq!(en.format_number(-1_234_567.891, 2), "-1,234,567.89");
+        assert_eq!(en.format_number(-0.001, 2), "0.00");
+    }
+
+    #[test]
+    fn test_localize_numbers() {
+        let zh = Locale::new(Language::Chinese);
+        assert_eq!(
+            zh.localize_numbers("营收 383285000000 美元,成交量 58414460。"),
+            "营收 3833亿 美元,成交量 5841万。"
+        );
+        let en = Locale::default();
+        assert_eq!(
+            en.localize_numbers("Revenue $383285000000. Volume 58414460 on 20240315."),
+            "Revenue $383B. Volume 58.4M on 20240315."
+        );
+        // Identifiers, decimals, separated and short numbers are untouched
+        let untouched = "id a1234567890, 1234567.5, 1,234,567, 2024, 0012345678";
+        assert_eq!(en.localize_numbers(untouched), untouched);
+    }
+
+    #[test]
+    fn test_bilingual_analysis_sections() {
+        let result = fixtures::large_numbers_analysis().with_data(
+            TRANSLATION_DATA_KEY,
+            serde_json::json!({
+                "from": "en",
+                "to": "zh",
+                "content": "2023财年营收 383285000000 美元,净利润 96995000000 美元。",
+            }),
+        );
+        let (_, content) = localized_analysis(&result, &fixtures::analysis_context());
+        let (english, chinese) = content.split_once("\n\n---\n\n").unwrap();
+        assert!(english.starts_with("## English\n\nRevenue 383B (FY2023)"));
+        assert_eq!(
+            chinese,
+            "## 中文\n\n2023财年营收 3833亿 美元,净利润 970亿 美元。"
+        );
+    }
+
+    #[test]
+    fn test_timestamp_in_local_time() {
+        let timestamp = fixtures::timestamp();
+        assert_eq!(
+            Locale::default().format_timestamp(timestamp),
+            "2024-03-15 14:30 UTC"
+        );
+        assert_eq!(
+            Locale::new(Language::Chinese).format_timestamp(timestamp),
+            "2024-03-15 22:30 UTC+8"
+        );
+        let india = FixedOffset::east_opt(5 * 3600 + 1800).unwrap();
+        assert_eq!(
+            Locale::default()
+                .with_offset(india)
+                .format_timestamp(timestamp),
+            "2024-03-15 20:00 UTC+5:30"
+        );
+    }
+
+    #[test]
+    fn test_split_at_semantic_boundaries() {
+        let text = "First paragraph is here.\n\nSecond paragraph. It has two sentences.";
+        assert_eq!(
+            split_message(text, 48),
+            vec![
+                "First paragraph is here.",
+                "Second paragraph. It has two sentences."
+            ]
+        );
+
+        let text = "短期偏多。支撑位在172美元。阻力位在180美元附近。";
+        let chunks = split_message(text, 28);
+        assert!(chunks.iter().all(|c| c.chars().count() <= 28));
+        assert!(chunks.iter().all(|c| c.ends_with('。')), "{chunks:?}");
+        assert_eq!(chunks.concat(), text);
+    }
+
+    #[test]
+    fn test_split_keeps_code_fences_balanced() {
+        let rows: Vec<String> = (0..30).map(|i| format!("AAPL | {i:>3} | +1.2%")).collect();
+        let text = format!("Table:\n```\n{}\n```", rows.join("\n"));
+        let chunks = split_message(&text, 200);
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(chunk.chars().count() <= 200);
+            assert_eq!(chunk.matches("```").count() % 2, 0, "{chunk}");
+        }
+
+        let html = format!("<pre>{}</pre>", rows.join("\n"));
+        for chunk in split_message(&html, 200) {
+            assert!(
+                chunk.starts_with("<pre>") && chunk.ends_with("</pre>"),
+                "{chunk}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_paginate_within_platform_limit() {
+        let telegram = MessageLimit::for_platform(BotPlatform::Telegram);
+        assert_eq!(telegram.paginate("short"), vec!["short"]);
+
+        let long = "This sentence is repeated. ".repeat(400);
+        let pages = telegram.paginate(&long);
+        assert_eq!(pages.len(), 3);
+        assert!(pages.iter().all(|p| p.chars().count() <= 4096));
+        assert!(pages[0].ends_with("is repeated.\n\n(1/3)"));
+        assert!(MessageLimit::unlimited().paginate(&long).len() == 1);
+    }
+
+    #[test]
+    fn test_formatter_snapshots() {
+        for platform in BotPlatform::ALL {
+            let formatter = FormatterFactory::create(platform);
+            insta::assert_snapshot!(
+                format!("formatter_{}", platform.to_string().to_lowercase()),
+                fixtures::render_all(formatter.as_ref())
+            );
+        }
+    }
 }
@@ -0,0 +1,522 @@
+//! Performance heatmap images
+//!
+//! Renders a squarified treemap of sectors or watchlist symbols as a PNG:
+//! each tile is sized by market cap and colored by the day's change, green
+//! for gains and red for losses (reversed for Chinese readers, who expect
+//! red for gains). [`render_heatmap`] returns the image as an [`Attachment`]
+//! for `/market` and `/summary` replies, and [`summary_lines`] gives the same
+//! data as text for platforms that cannot show images.
+//!
+//! Daily changes come from Yahoo Finance ([`sector_tiles`],
+//! [`watchlist_tiles`]); symbols that fail to load are left out.
+
+use agent_prompt::Language;
+use plotters::prelude::*;
+use plotters::style::text_anchor::{HPos, Pos, VPos};
+
+use crate::api::YahooFinanceClient;
+use crate::engine::AnalysisContext;
+use crate::error::{Result, StockError};
+use crate::interface::Locale;
+use crate::interface::interface::{Attachment, AttachmentType};
+
+/// Image width in pixels
+pub const HEATMAP_WIDTH: u32 = 960;
+
+/// Image height in pixels
+pub const HEATMAP_HEIGHT: u32 = 600;
+
+/// Height of the title bar
+const TITLE_HEIGHT: u32 = 40;
+
+/// Daily change (percent) drawn at full color intensity
+const FULL_SCALE_CHANGE: f64 = 3.0;
+
+/// Tiles smaller than this (in pixels) are drawn without a label
+const MIN_LABEL_SIZE: (f64, f64) = (48.0, 28.0);
+
+const BACKGROUND: RGBColor = RGBColor(24, 26, 32);
+const NEUTRAL: RGBColor = RGBColor(65, 69, 84);
+const GAIN: RGBColor = RGBColor(48, 204, 90);
+const LOSS: RGBColor = RGBColor(246, 53, 56);
+
+/// SPDR sector ETFs with each sector's approximate share of S&P 500 market
+/// cap (percent), used to size `/market` tiles
+pub const SECTOR_ETFS: [(&str, &str, f64); 11] = [
+    ("XLK", "Technology", 31.0),
+    ("XLF", "Financials", 13.0),
+    ("XLV", "Health Care", 11.0),
+    ("XLY", "Consumer Disc.", 10.0),
+    ("XLC", "Communication", 9.0),
+    ("XLI", "Industrials", 8.5),
+    ("XLP", "Staples", 6.0),
+    ("XLE", "Energy", 3.5),
+    ("XLU", "Utilities", 2.5),
+    ("XLRE", "Real Estate", 2.3),
+    ("XLB", "Materials", 2.2),
+];
+
+/// One sector or symbol on the heatmap
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeatmapTile {
+    /// Ticker symbol
+    pub symbol: String,
+    /// Display name, e.g. the sector
+    pub name: Option<String>,
+    /// Market cap or another relative size
+    pub weight: f64,
+    /// Change since the previous close, in percent
+    pub change_percent: f64,
+}
+
+impl HeatmapTile {
+    pub fn new(symbol: impl Into<String>, weight: f64, change_percent: f64) -> Self {
+        Self {
+            symbol: symbol.into(),
+            name: None,
+            weight,
+            change_percent,
+        }
+    }
+
+    /// Show `name` above the symbol
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+}
+
+/// Axis-aligned rectangle in image coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    pub fn new(x: f64, y: f64, width: f64, height: f64) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn area(&self) -> f64 {
+        self.width * self.height
+    }
+}
+
+/// Squarified treemap layout of `weights` within `bounds`
+///
+/// Returns one rectangle per weight, in input order, with areas
+/// proportional to the weights and aspect ratios kept close to square
+/// (Bruls, Huizing and van Wijk). Weights that are not positive get an
+/// empty rectangle.
+pub fn squarify(weights: &[f64], bounds: Rect) -> Vec<Rect> {
+    let mut rects = vec![Rect::new(bounds.x, bounds.y, 0.0, 0.0); weights.len()];
+    let positive = |w: f64| w.is_finite() && w > 0.0;
+    let total: f64 = weights.iter().copied().filter(|w| positive(*w)).sum();
+    if total <= 0.0 {
+        return rects;
+    }
+
+    let scale = bounds.area() / total;
+    let mut order: Vec<usize> = (0..weights.len())
+        .filter(|&i| positive(weights[i]))
+        .collect();
+    order.sort_by(|&a, &b| weights[b].total_cmp(&weights[a]));
+
+    let mut free = bounds;
+    let mut row: Vec<(usize, f64)> = Vec::new();
+    let mut next = order.iter().map(|&i| (i, weights[i] * scale)).peekable();
+    while let Some(&item) = next.peek() {
+        let side = free.width.min(free.height);
+        let mut candidate = row.clone();
+        candidate.push(item);
+        if row.is_empty() || worst_ratio(&candidate, side) <= worst_ratio(&row, side) {
+            row = candidate;
+            next.next();
+        } else {
+            free = place_row(&row, free, &mut rects);
+            row.clear();
+        }
+    }
+    if !row.is_empty() {
+        place_row(&row, free, &mut rects);
+    }
+    rects
+}
+
+/// Largest aspect ratio of a row of areas laid along a side of length `side`
+fn worst_ratio(row: &[(usize, f64)], side: f64) -> f64 {
+    let sum: f64 = row.iter().map(|(_, area)| area).sum();
+    row.iter()
+        .map(|(_, area)| {
+            let ratio = side * side * area / (sum * sum);
+            ratio.max(1.0 / ratio)
+        })
+        .fold(0.0, f64::max)
+}
+
+/// Lay a row along the shorter side of `free`, returning the space left
+fn place_row(row: &[(usize, f64)], free: Rect, rects: &mut [Rect]) -> Rect {
+    let sum: f64 = row.iter().map(|(_, area)| area).sum();
+    if free.width >= free.height {
+        // Column on the left
+        let thickness = sum / free.height;
+        let mut y = free.y;
+        for &(index, area) in row {
+            let height = area / thickness;
+            rects[index] = Rect::new(free.x, y, thickness, height);
+            y += height;
+        }
+        Rect::new(
+            free.x + thickness,
+            free.y,
+            free.width - thickness,
+            free.height,
+        )
+    } else {
+        // Row along the top
+        let thickness = sum / free.width;
+        let mut x = free.x;
+        for &(index, area) in row {
+            let width = area / thickness;
+            rects[index] = Rect::new(x, free.y, width, thickness);
+            x += width;
+        }
+        Rect::new(
+            free.x,
+            free.y + thickness,
+            free.width,
+            free.height - thickness,
+        )
+    }
+}
+
+/// Tile color for a daily change, saturating at ±3%
+///
+/// With `red_for_gains` the colors follow Chinese market convention.
+pub fn change_color(change_percent: f64, red_for_gains: bool) -> RGBColor {
+    let (up, down) = if red_for_gains {
+        (LOSS, GAIN)
+    } else {
+        (GAIN, LOSS)
+    };
+    let target = if change_percent >= 0.0 { up } else { down };
+    let t = if change_percent.is_finite() {
+        (change_percent.abs() / FULL_SCALE_CHANGE).min(1.0)
+    } else {
+        0.0
+    };
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let mix =
+        |from: u8, to: u8| (f64::from(from) + (f64::from(to) - f64::from(from)) * t).round() as u8;
+    RGBColor(
+        mix(NEUTRAL.0, target.0),
+        mix(NEUTRAL.1, target.1),
+        mix(NEUTRAL.2, target.2),
+    )
+}
+
+/// Heatmap of `tiles` as a PNG chart attachment
+pub fn render_heatmap(
+    title: &str,
+    tiles: &[HeatmapTile],
+    red_for_gains: bool,
+) -> Result<Attachment> {
+    let png = render_png(title, tiles, red_for_gains, (HEATMAP_WIDTH, HEATMAP_HEIGHT))?;
+    Ok(Attachment {
+        attachment_type: AttachmentType::Chart,
+        content: png,
+        filename: Some("heatmap.png".to_string()),
+        mime_type: "image/png".to_string(),
+    })
+}
+
+/// Draw the heatmap and encode it as PNG
+fn render_png(
+    title: &str,
+    tiles: &[HeatmapTile],
+    red_for_gains: bool,
+    (width, height): (u32, u32),
+) -> Result<Vec<u8>> {
+    let render_error =
+        |e: &dyn std::fmt::Display| StockError::Other(format!("Failed to render heatmap: {e}"));
+    let mut pixels = vec![0u8; width as usize * height as usize * 3];
+    {
+        let root = BitMapBackend::with_buffer(&mut pixels, (width, height)).into_drawing_area();
+        root.fill(&BACKGROUND).map_err(|e| render_error(&e))?;
+
+        let centered = Pos::new(HPos::Center, VPos::Center);
+        let title_style = ("sans-serif", 22).into_font().color(&WHITE).pos(centered);
+        #[allow(clippy::cast_possible_wrap)]
+        root.draw_text(
+            title,
+            &title_style,
+            ((width / 2) as i32, (TITLE_HEIGHT / 2) as i32),
+        )
+        .map_err(|e| render_error(&e))?;
+
+        let bounds = Rect::new(
+            0.0,
+            f64::from(TITLE_HEIGHT),
+            f64::from(width),
+            f64::from(height - TITLE_HEIGHT),
+        );
+        let weights: Vec<f64> = tiles.iter().map(|tile| tile.weight).collect();
+        for (tile, rect) in tiles.iter().zip(squarify(&weights, bounds)) {
+            if rect.area() < 1.0 {
+                continue;
+            }
+            #[allow(clippy::cast_possible_truncation)]
+            let (x0, y0, x1, y1) = (
+                rect.x.round() as i32,
+                rect.y.round() as i32,
+                (rect.x + rect.width).round() as i32,
+                (rect.y + rect.height).round() as i32,
+            );
+            let color = change_color(tile.change_percent, red_for_gains);
+            root.draw(&Rectangle::new([(x0, y0), (x1, y1)], color.filled()))
+                .map_err(|e| render_error(&e))?;
+            root.draw(&Rectangle::new(
+                [(x0, y0), (x1, y1)],
+                BACKGROUND.stroke_width(2),
+            ))
+            .map_err(|e| render_error(&e))?;
+
+            if rect.width < MIN_LABEL_SIZE.0 || rect.height < MIN_LABEL_SIZE.1 {
+                continue;
+            }
+            #[allow(clippy::cast_possible_truncation)]
+            let size = (rect.width.min(rect.height) / 5.0).clamp(11.0, 26.0) as u32;
+            let style = ("sans-serif", size).into_font().color(&WHITE).pos(centered);
+            let label = match &tile.name {
+                Some(name) if rect.height >= f64::from(size) * 4.0 => {
+                    format!("{name}\n{}\n{:+.2}%", tile.symbol, tile.change_percent)
+                }
+                _ => format!("{}\n{:+.2}%", tile.symbol, tile.change_percent),
+            };
+            let lines: Vec<&str> = label.lines().collect();
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            let line_height = (f64::from(size) * 1.2) as i32;
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            let top = (y0 + y1) / 2 - line_height * (lines.len() as i32 - 1) / 2;
+            for (i, line) in lines.iter().enumerate() {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+                let y = top + line_height * i as i32;
+                root.draw_text(line, &style, ((x0 + x1) / 2, y))
+                    .map_err(|e| render_error(&e))?;
+            }
+        }
+        root.present().map_err(|e| render_error(&e))?;
+    }
+
+    let image = image::RgbImage::from_raw(width, height, pixels).ok_or_else(|| {
+        StockError::Other("Failed to render heatmap: bad buffer size".to_string())
+    })?;
+    let mut png = std::io::Cursor::new(Vec::new());
+    image
+        .write_to(&mut png, image::ImageFormat::Png)
+        .map_err(|e| render_error(&e))?;
+    Ok(png.into_inner())
+}
+
+/// Daily change of each S&P 500 sector ETF, sized by sector weight
+pub async fn sector_tiles(yahoo: &YahooFinanceClient) -> Result<Vec<HeatmapTile>> {
+    let tiles = futures::future::join_all(SECTOR_ETFS.iter().map(
+        |&(symbol, name, weight)| async move {
+            let change = daily_change(yahoo, symbol).await?;
+            Ok(HeatmapTile::new(symbol, weight, change).with_name(name))
+        },
+    ))
+    .await;
+    loaded_tiles("MARKET", tiles)
+}
+
+/// Daily change of each symbol, sized by market cap where known and
+/// equally otherwise
+pub async fn watchlist_tiles(
+    yahoo: &YahooFinanceClient,
+    symbols: &[String],
+) -> Result<Vec<HeatmapTile>> {
+    let tiles = futures::future::join_all(symbols.iter().map(|symbol| async move {
+        let change = daily_change(yahoo, symbol).await?;
+        let info = yahoo.get_company_info(symbol).await?;
+        Ok(HeatmapTile::new(
+            symbol.as_str(),
+            info.market_cap.unwrap_or(1.0),
+            change,
+        ))
+    }))
+    .await;
+    loaded_tiles("WATCHLIST", tiles)
+}
+
+/// Percent change of the latest close from the one before
+async fn daily_change(yahoo: &YahooFinanceClient, symbol: &str) -> Result<f64> {
+    let quotes = yahoo.get_historical_range(symbol, "5d").await?;
+    match quotes.as_slice() {
+        [.., previous, latest] if previous.close != 0.0 => {
+            Ok((latest.close - previous.close) / previous.close * 100.0)
+        }
+        _ => Err(StockError::DataUnavailable {
+            symbol: symbol.to_string(),
+            reason: "Need two daily closes for the change".to_string(),
+        }),
+    }
+}
+
+/// Keep the tiles that loaded, failing only when none did
+fn loaded_tiles(label: &str, tiles: Vec<Result<HeatmapTile>>) -> Result<Vec<HeatmapTile>> {
+    let mut loaded = Vec::new();
+    let mut last_error = None;
+    for tile in tiles {
+        match tile {
+            Ok(tile) => loaded.push(tile),
+            Err(e) => {
+                tracing::warn!("Heatmap tile unavailable: {}", e);
+                last_error = Some(e);
+            }
+        }
+    }
+    match last_error {
+        Some(e) if loaded.is_empty() => Err(StockError::DataUnavailable {
+            symbol: label.to_string(),
+            reason: e.to_string(),
+        }),
+        _ => Ok(loaded),
+    }
+}
+
+/// Reply to `/market`, or to `/summary` of `watchlist`: the tiles as text
+/// and, unless rendering fails, the heatmap image
+///
+/// Colors follow Chinese convention when the session language is Chinese.
+pub async fn performance_reply(
+    yahoo: &YahooFinanceClient,
+    watchlist: Option<&[String]>,
+    context: &AnalysisContext,
+) -> Result<(String, Option<Attachment>)> {
+    let (title, tiles) = match watchlist {
+        None => ("Sector performance", sector_tiles(yahoo).await?),
+        Some([]) => return Ok(("📋 Watchlist is empty".to_string(), None)),
+        Some(symbols) => (
+            "Watchlist performance",
+            watchlist_tiles(yahoo, symbols).await?,
+        ),
+    };
+    let text = format!("🗺 {title}\n{}", summary_lines(&tiles).join("\n"));
+    let red_for_gains = Locale::for_context(context).language == Language::Chinese;
+    match render_heatmap(title, &tiles, red_for_gains) {
+        Ok(image) => Ok((text, Some(image))),
+        Err(e) => {
+            tracing::warn!("{}", e);
+            Ok((text, None))
+        }
+    }
+}
+
+/// Tiles as text, best performer first, e.g. `🟢 XLK Technology +1.25%`
+pub fn summary_lines(tiles: &[HeatmapTile]) -> Vec<String> {
+    let mut sorted: Vec<&HeatmapTile> = tiles.iter().collect();
+    sorted.sort_by(|a, b| b.change_percent.total_cmp(&a.change_percent));
+    sorted
+        .into_iter()
+        .map(|tile| {
+            let marker = if tile.change_percent >= 0.0 {
+                "🟢"
+            } else {
+                "🔴"
+            };
+            match &tile.name {
+                Some(name) => format!(
+                    "{marker} {} {name} {:+.2}%",
+                    tile.symbol, tile.change_percent
+                ),
+                None => format!("{marker} {} {:+.2}%", tile.symbol, tile.change_percent),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_squarify_fills_bounds_in_proportion() {
+        let bounds = Rect::new(0.0, 40.0, 600.0, 400.0);
+        let weights = [6.0, 6.0, 4.0, 3.0, 2.0, 2.0, 1.0, 0.0];
+        let rects = squarify(&weights, bounds);
+        let total: f64 = weights.iter().sum();
+
+        assert_eq!(rects.len(), weights.len());
+        for (rect, weight) in rects.iter().zip(weights) {
+            let expected = bounds.area() * weight / total;
+            assert!(
+                (rect.area() - expected).abs() < 1e-6,
+                "{rect:?} for {weight}"
+            );
+            assert!(rect.x >= bounds.x && rect.x + rect.width <= bounds.x + bounds.width + 1e-9);
+            assert!(rect.y >= bounds.y && rect.y + rect.height <= bounds.y + bounds.height + 1e-9);
+        }
+        // The largest tiles stay close to square
+        assert!(rects[0].width / rects[0].height < 3.0 && rects[0].height / rects[0].width < 3.0);
+        assert_eq!(rects[7].area(), 0.0);
+        assert!(
+            squarify(&[0.0, -1.0], bounds)
+                .iter()
+                .all(|r| r.area() == 0.0)
+        );
+    }
+
+    #[test]
+    fn test_change_color_scales_and_saturates() {
+        assert_eq!(change_color(0.0, false), NEUTRAL);
+        assert_eq!(change_color(5.0, false), GAIN);
+        assert_eq!(change_color(-3.0, false), LOSS);
+        assert_eq!(change_color(5.0, true), LOSS);
+        assert_eq!(change_color(f64::NAN, false), NEUTRAL);
+
+        let half = change_color(1.5, false);
+        assert!(half.1 > NEUTRAL.1 && half.1 < GAIN.1);
+    }
+
+    #[test]
+    fn test_render_heatmap_png() {
+        let tiles: Vec<HeatmapTile> = SECTOR_ETFS
+            .iter()
+            .zip([1.2, -0.4, 0.3, -2.5, 0.0, 0.8, -0.1, 3.4, -1.0, 0.2, -0.6])
+            .map(|(&(symbol, name, weight), change)| {
+                HeatmapTile::new(symbol, weight, change).with_name(name)
+            })
+            .collect();
+
+        let attachment = render_heatmap("Sector performance", &tiles, false).unwrap();
+        assert_eq!(attachment.attachment_type, AttachmentType::Chart);
+        assert_eq!(attachment.mime_type, "image/png");
+        assert!(attachment.content.starts_with(b"\x89PNG\r\n\x1a\n"));
+
+        let decoded = image::load_from_memory(&attachment.content)
+            .unwrap()
+            .to_rgb8();
+        assert_eq!(decoded.dimensions(), (HEATMAP_WIDTH, HEATMAP_HEIGHT));
+    }
+
+    #[test]
+    fn test_summary_lines_best_first() {
+        let tiles = [
+            HeatmapTile::new("MSFT", 3.0, -0.52),
+            HeatmapTile::new("XLE", 1.0, 1.5).with_name("Energy"),
+        ];
+        assert_eq!(
+            summary_lines(&tiles),
+            ["🟢 XLE Energy +1.50%", "🔴 MSFT -0.52%"]
+        );
+    }
+}
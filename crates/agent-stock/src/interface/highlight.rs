@@ -0,0 +1,520 @@
+//! Highlighting of tickers, price moves and risk levels
+//!
+//! Long analyses are easier to scan when the figures that matter stand out.
+//! [`Highlights`] takes them from the structured result, not the prose: the
+//! analyzed symbol, the closes in its chart data, or a comparison's metrics.
+//! [`Highlights::lines`] renders one line per symbol, e.g.
+//!
+//! ```text
+//! **AAPL** 1d 🟢 +0.4% · 30d 🟢 +3.3% · Risk 🟢 Low
+//! ```
+//!
+//! Moves are colored text on platforms whose markup has colors (Feishu,
+//! DingTalk, WeCom) and marked with 🟢/🔴 elsewhere. Gains are green in
+//! English and red in Chinese, following mainland market convention.
+//! [`emphasize_symbols`] bolds the same tickers in the Markdown body.
+
+use agent_prompt::Language;
+use serde_json::Value;
+
+use crate::engine::result::ComparisonResult;
+use crate::engine::{AnalysisResult, NEWS_DIGEST_DATA_KEY};
+use crate::interface::markup::Markup;
+use crate::interface::sparkline::{CHART_DATA_KEY, SPARKLINE_POINTS, series};
+
+/// Results that cover more than one symbol
+const PSEUDO_SYMBOLS: [&str; 2] = ["MARKET", "WATCHLIST"];
+
+/// Fewest closes risk is estimated from
+const MIN_RISK_POINTS: usize = 10;
+
+/// Trading days per year, to annualize daily volatility
+const TRADING_DAYS: f64 = 252.0;
+
+/// Moves smaller than this many percent are shown uncolored
+const FLAT_PCT: f64 = 0.05;
+
+/// Risk of holding a symbol
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RiskLevel {
+    Low,
+    Moderate,
+    High,
+}
+
+impl RiskLevel {
+    /// Level of an annualized volatility, e.g. `0.25` for 25%
+    pub fn from_volatility(volatility: f64) -> Self {
+        if volatility < 0.2 {
+            Self::Low
+        } else if volatility <= 0.4 {
+            Self::Moderate
+        } else {
+            Self::High
+        }
+    }
+
+    /// Level of a beta against the market
+    pub fn from_beta(beta: f64) -> Self {
+        if beta < 0.8 {
+            Self::Low
+        } else if beta <= 1.3 {
+            Self::Moderate
+        } else {
+            Self::High
+        }
+    }
+
+    fn emoji(self) -> &'static str {
+        match self {
+            Self::Low => "🟢",
+            Self::Moderate => "🟠",
+            Self::High => "🔴",
+        }
+    }
+
+    fn label(self, language: &Language) -> &'static str {
+        match (self, language) {
+            (Self::Low, Language::Chinese) => "低",
+            (Self::Moderate, Language::Chinese) => "中",
+            (Self::High, Language::Chinese) => "高",
+            (Self::Low, _) => "Low",
+            (Self::Moderate, _) => "Moderate",
+            (Self::High, _) => "High",
+        }
+    }
+}
+
+/// Price change of a symbol over a period
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceMove {
+    /// Period label such as `1d`, `30d` or `YTD`
+    pub period: String,
+    /// Change in percent
+    pub change_pct: f64,
+}
+
+impl PriceMove {
+    fn new(period: impl Into<String>, change_pct: f64) -> Self {
+        Self {
+            period: period.into(),
+            change_pct,
+        }
+    }
+}
+
+/// Moves and risk of one symbol
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolHighlight {
+    pub symbol: String,
+    pub moves: Vec<PriceMove>,
+    pub risk: Option<RiskLevel>,
+}
+
+/// Figures of a result worth making stand out
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Highlights {
+    /// Tickers to emphasize in the body
+    pub symbols: Vec<String>,
+    /// One line each, for symbols with moves or risk
+    pub rows: Vec<SymbolHighlight>,
+}
+
+impl Highlights {
+    /// Highlights of an analysis
+    ///
+    /// The analyzed symbol and any symbols of news digest stories are
+    /// emphasized. Moves and risk come from chart data: the change over the
+    /// last day and over the sparkline window, and the annualized volatility
+    /// of daily returns.
+    pub fn from_result(result: &AnalysisResult) -> Self {
+        let mut highlights = Self::default();
+        if !PSEUDO_SYMBOLS.contains(&result.symbol.as_str()) {
+            highlights.add_symbol(&result.symbol);
+        }
+        if let Some(stories) = result
+            .data
+            .get(NEWS_DIGEST_DATA_KEY)
+            .and_then(Value::as_array)
+        {
+            for symbol in stories
+                .iter()
+                .filter_map(|story| story["symbols"].as_array())
+                .flatten()
+                .filter_map(Value::as_str)
+            {
+                highlights.add_symbol(symbol);
+            }
+        }
+
+        if let Some(chart) = result.data.get(CHART_DATA_KEY) {
+            let symbol = chart["symbol"].as_str().unwrap_or(&result.symbol);
+            let closes = series(&chart["line"], SPARKLINE_POINTS);
+            let moves = chart_moves(&closes);
+            let risk = volatility(&closes).map(RiskLevel::from_volatility);
+            if !moves.is_empty() || risk.is_some() {
+                highlights.rows.push(SymbolHighlight {
+                    symbol: symbol.to_string(),
+                    moves,
+                    risk,
+                });
+            }
+        }
+        highlights
+    }
+
+    /// Highlights of a comparison: every symbol, its returns and the risk
+    /// its beta implies
+    pub fn from_comparison(comparison: &ComparisonResult) -> Self {
+        let mut highlights = Self::default();
+        for symbol in &comparison.symbols {
+            highlights.add_symbol(symbol);
+            let moves: Vec<PriceMove> = comparison
+                .metrics
+                .performance
+                .get(symbol)
+                .map(|p| {
+                    [
+                        ("1d", p.return_1d),
+                        ("1w", p.return_1w),
+                        ("1m", p.return_1m),
+                        ("YTD", p.return_ytd),
+                    ]
+                    .into_iter()
+                    .filter_map(|(period, change)| Some(PriceMove::new(period, change?)))
+                    .filter(|m| m.change_pct.is_finite())
+                    .collect()
+                })
+                .unwrap_or_default();
+            let risk = comparison
+                .metrics
+                .risk
+                .get(symbol)
+                .and_then(|r| r.beta)
+                .filter(|beta| beta.is_finite())
+                .map(RiskLevel::from_beta);
+            if !moves.is_empty() || risk.is_some() {
+                highlights.rows.push(SymbolHighlight {
+                    symbol: symbol.clone(),
+                    moves,
+                    risk,
+                });
+            }
+        }
+        highlights
+    }
+
+    fn add_symbol(&mut self, symbol: &str) {
+        let symbol = symbol.trim();
+        if !symbol.is_empty() && !self.symbols.iter().any(|s| s == symbol) {
+            self.symbols.push(symbol.to_string());
+        }
+    }
+
+    /// One line per row in `markup`, labelled in `language`
+    pub fn lines(&self, markup: Markup, language: &Language) -> Vec<String> {
+        let risk_label = if *language == Language::Chinese {
+            "风险"
+        } else {
+            "Risk"
+        };
+        self.rows
+            .iter()
+            .map(|row| {
+                let mut parts: Vec<String> = row
+                    .moves
+                    .iter()
+                    .map(|m| {
+                        format!(
+                            "{} {}",
+                            m.period,
+                            paint_move(markup, language, m.change_pct)
+                        )
+                    })
+                    .collect();
+                if let Some(risk) = row.risk {
+                    parts.push(format!(
+                        "{risk_label} {} {}",
+                        risk.emoji(),
+                        risk.label(language)
+                    ));
+                }
+                format!("{} {}", markup.bold(&row.symbol), parts.join(" · "))
+            })
+            .collect()
+    }
+}
+
+/// `change_pct` signed to one decimal, colored or marked by direction
+fn paint_move(markup: Markup, language: &Language, change_pct: f64) -> String {
+    let text = format!("{change_pct:+.1}%");
+    if change_pct.abs() < FLAT_PCT {
+        return text;
+    }
+    // Mainland convention: red for gains, green for losses
+    let green = (change_pct > 0.0) != (*language == Language::Chinese);
+    match markup {
+        Markup::Feishu => format!(
+            "<font color='{}'>{text}</font>",
+            if green { "green" } else { "red" }
+        ),
+        Markup::DingTalk => format!(
+            "<font color={}>{text}</font>",
+            if green { "#008000" } else { "#FF0000" }
+        ),
+        Markup::WeCom => format!(
+            "<font color=\"{}\">{text}</font>",
+            if green { "info" } else { "warning" }
+        ),
+        Markup::Markdown | Markup::TelegramHtml | Markup::Slack => {
+            format!("{} {text}", if green { "🟢" } else { "🔴" })
+        }
+    }
+}
+
+/// Change over the last day and over all of `closes`
+fn chart_moves(closes: &[f64]) -> Vec<PriceMove> {
+    let change = |from: f64, to: f64| (from != 0.0).then(|| (to - from) / from * 100.0);
+    let mut moves = Vec::new();
+    if let [.., previous, last] = closes
+        && let Some(day) = change(*previous, *last)
+    {
+        moves.push(PriceMove::new("1d", day));
+    }
+    if closes.len() > 2
+        && let Some(period) = change(closes[0], closes[closes.len() - 1])
+    {
+        moves.push(PriceMove::new(format!("{}d", closes.len()), period));
+    }
+    moves
+}
+
+/// Annualized volatility of the daily returns of `closes`, when there are
+/// enough of them
+fn volatility(closes: &[f64]) -> Option<f64> {
+    if closes.len() < MIN_RISK_POINTS {
+        return None;
+    }
+    let returns: Vec<f64> = closes
+        .windows(2)
+        .filter(|pair| pair[0] != 0.0)
+        .map(|pair| pair[1] / pair[0] - 1.0)
+        .collect();
+    #[allow(clippy::cast_precision_loss)]
+    let count = returns.len() as f64;
+    let mean = returns.iter().sum::<f64>() / count;
+    let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (count - 1.0);
+    Some(variance.sqrt() * TRADING_DAYS.sqrt())
+}
+
+/// `markdown` with each of `symbols` in bold
+///
+/// Only whole tickers in prose are bolded: code, headings, tables, text
+/// already in bold, URLs and one-letter symbols are left alone.
+pub fn emphasize_symbols(markdown: &str, symbols: &[String]) -> String {
+    let mut symbols: Vec<&str> = symbols
+        .iter()
+        .map(String::as_str)
+        .filter(|s| s.chars().count() > 1)
+        .collect();
+    if symbols.is_empty() {
+        return markdown.to_string();
+    }
+    // Longest first so `BRK.B` wins over `BRK`
+    symbols.sort_by_key(|s| std::cmp::Reverse(s.len()));
+
+    let mut in_fence = false;
+    let mut lines = Vec::new();
+    for line in markdown.split('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            lines.push(line.to_string());
+        } else if in_fence || trimmed.starts_with('#') || trimmed.starts_with('|') {
+            lines.push(line.to_string());
+        } else {
+            lines.push(emphasize_line(line, &symbols));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Bold `symbols` outside code spans and bold text of one line
+fn emphasize_line(line: &str, symbols: &[&str]) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut in_code = false;
+    let mut in_bold = false;
+    let mut rest = line;
+    while let Some(c) = rest.chars().next() {
+        if c == '`' {
+            in_code = !in_code;
+        } else if !in_code && rest.starts_with("**") {
+            in_bold = !in_bold;
+            out.push_str("**");
+            rest = &rest[2..];
+            continue;
+        } else if !in_code && !in_bold {
+            let prev = out.chars().next_back();
+            if let Some(symbol) = symbols
+                .iter()
+                .find(|symbol| rest.starts_with(**symbol) && bounded(prev, &rest[symbol.len()..]))
+            {
+                out.push_str("**");
+                out.push_str(symbol);
+                out.push_str("**");
+                rest = &rest[symbol.len()..];
+                continue;
+            }
+        }
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+/// Whether a ticker between `prev` and `next` stands as its own word
+///
+/// CJK text around a ticker counts as a boundary; URL and identifier
+/// characters do not. A trailing `.` ends a sentence unless a letter or
+/// digit follows it.
+fn bounded(prev: Option<char>, next: &str) -> bool {
+    let joined = |c: char| c.is_ascii_alphanumeric() || "_/-=&?#@$*".contains(c);
+    if prev.is_some_and(|c| joined(c) || c == '.') {
+        return false;
+    }
+    let mut after = next.chars();
+    match after.next() {
+        None => true,
+        Some('.') => !after.next().is_some_and(|c| c.is_ascii_alphanumeric()),
+        Some(c) => !joined(c),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::result::{PerformanceMetric, RiskMetric};
+    use crate::interface::fixtures;
+    use serde_json::json;
+
+    #[test]
+    fn test_from_result_chart() {
+        let highlights = Highlights::from_result(&fixtures::technical_analysis());
+        assert_eq!(highlights.symbols, vec!["AAPL"]);
+        let row = &highlights.rows[0];
+        assert_eq!(row.symbol, "AAPL");
+        assert_eq!(
+            row.moves
+                .iter()
+                .map(|m| m.period.as_str())
+                .collect::<Vec<_>>(),
+            ["1d", "30d"]
+        );
+        assert_eq!(row.risk, Some(RiskLevel::Low));
+
+        let market = Highlights::from_result(&fixtures::partial_macro_analysis());
+        assert_eq!(market, Highlights::default());
+    }
+
+    #[test]
+    fn test_from_result_digest_symbols() {
+        let result = AnalysisResult::new("WATCHLIST", crate::engine::AnalysisType::News, "")
+            .with_data(
+                NEWS_DIGEST_DATA_KEY,
+                json!([{ "symbols": ["NVDA", "AMD"] }, { "symbols": ["NVDA"] }]),
+            );
+        let highlights = Highlights::from_result(&result);
+        assert_eq!(highlights.symbols, vec!["NVDA", "AMD"]);
+        assert!(highlights.rows.is_empty());
+    }
+
+    #[test]
+    fn test_risk_levels() {
+        assert_eq!(RiskLevel::from_volatility(0.15), RiskLevel::Low);
+        assert_eq!(RiskLevel::from_volatility(0.3), RiskLevel::Moderate);
+        assert_eq!(RiskLevel::from_volatility(0.65), RiskLevel::High);
+        assert_eq!(RiskLevel::from_beta(1.8), RiskLevel::High);
+
+        let calm: Vec<f64> = (0..30).map(|i| 100.0 + f64::from(i % 2) * 0.1).collect();
+        let wild: Vec<f64> = (0..30).map(|i| 100.0 + f64::from(i % 2) * 8.0).collect();
+        assert_eq!(
+            volatility(&calm).map(RiskLevel::from_volatility),
+            Some(RiskLevel::Low)
+        );
+        assert_eq!(
+            volatility(&wild).map(RiskLevel::from_volatility),
+            Some(RiskLevel::High)
+        );
+        assert_eq!(volatility(&calm[..5]), None);
+    }
+
+    #[test]
+    fn test_lines_by_markup_and_language() {
+        let mut comparison = ComparisonResult::new(vec!["AAPL".into(), "MSFT".into()]);
+        comparison.metrics.performance.insert(
+            "AAPL".into(),
+            PerformanceMetric {
+                return_1d: Some(1.26),
+                return_1w: None,
+                return_1m: Some(-3.0),
+                return_ytd: Some(0.0),
+            },
+        );
+        comparison.metrics.risk.insert(
+            "MSFT".into(),
+            RiskMetric {
+                beta: Some(0.9),
+                week_52_high: None,
+                week_52_low: None,
+                avg_volume: None,
+            },
+        );
+        let highlights = Highlights::from_comparison(&comparison);
+
+        assert_eq!(
+            highlights.lines(Markup::Markdown, &Language::English),
+            [
+                "**AAPL** 1d 🟢 +1.3% · 1m 🔴 -3.0% · YTD +0.0%",
+                "**MSFT** Risk 🟠 Moderate"
+            ]
+        );
+        assert_eq!(
+            highlights.lines(Markup::TelegramHtml, &Language::Chinese)[0],
+            "<b>AAPL</b> 1d 🔴 +1.3% · 1m 🟢 -3.0% · YTD +0.0%"
+        );
+        assert_eq!(
+            highlights.lines(Markup::Feishu, &Language::English)[0],
+            "**AAPL** 1d <font color='green'>+1.3%</font> · 1m <font color='red'>-3.0%</font> · YTD +0.0%"
+        );
+        assert!(
+            highlights.lines(Markup::WeCom, &Language::English)[0]
+                .contains("<font color=\"info\">+1.3%</font>")
+        );
+        assert!(highlights.lines(Markup::DingTalk, &Language::Chinese)[1].ends_with("风险 🟠 中"));
+    }
+
+    #[test]
+    fn test_emphasize_symbols() {
+        let symbols = vec!["AAPL".to_string(), "BRK.B".to_string(), "T".to_string()];
+        assert_eq!(
+            emphasize_symbols("AAPL rose; BRK.B fell. T held.", &symbols),
+            "**AAPL** rose; **BRK.B** fell. T held."
+        );
+        assert_eq!(
+            emphasize_symbols("AAPL的支撑位,看好AAPL.", &symbols),
+            "**AAPL**的支撑位,看好**AAPL**."
+        );
+        assert_eq!(
+            emphasize_symbols("**AAPL** and `AAPL` vs AAPLX, $AAPL, AAPL.US", &symbols),
+            "**AAPL** and `AAPL` vs AAPLX, $AAPL, AAPL.US"
+        );
+        assert_eq!(
+            emphasize_symbols("See https://example.com/quote/AAPL", &symbols),
+            "See https://example.com/quote/AAPL"
+        );
+        assert_eq!(
+            emphasize_symbols("## AAPL\n```\nAAPL\n```\n| AAPL |\n- AAPL (up)", &symbols),
+            "## AAPL\n```\nAAPL\n```\n| AAPL |\n- **AAPL** (up)"
+        );
+    }
+}
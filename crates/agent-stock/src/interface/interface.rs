@@ -6,25 +6,36 @@ use crate::engine::AnalysisContext;
 use crate::error::Result;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::future::Future;
+use tokio::sync::mpsc::{self, UnboundedSender};
 
 /// Platform identifier
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum BotPlatform {
     /// Command-line interface
     CLI,
-    
+
     /// Telegram bot
     Telegram,
-    
+
     /// DingTalk bot
     DingTalk,
-    
+
     /// Feishu (Lark) bot
     Feishu,
-    
+
+    /// Slack app
+    Slack,
+
+    /// WeCom (企业微信) app
+    WeCom,
+
+    /// Matrix room bot
+    Matrix,
+
     /// Web interface
     Web,
-    
+
     /// Custom platform
     Custom,
 }
@@ -34,18 +45,29 @@ pub enum BotPlatform {
 pub struct BotResponse {
     /// Response content
     pub content: String,
-    
+
     /// Response type
     pub response_type: ResponseType,
-    
+
     /// Attachments (images, files, etc.)
     pub attachments: Vec<Attachment>,
-    
+
     /// Suggested actions
     pub actions: Vec<SuggestedAction>,
-    
+
     /// Metadata for the platform
     pub metadata: serde_json::Value,
+
+    /// Further messages to send after `content` when a reply is split to fit
+    /// the platform's message limit
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub continuation: Vec<String>,
+
+    /// Replace the interim reply sent before this one (see
+    /// [`BotInterface::on_message_progressive`]) instead of sending a new
+    /// message; only `content` replaces it, `continuation` still follows
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub edit_previous: bool,
 }
 
 /// Type of bot response
@@ -53,13 +75,13 @@ pub struct BotResponse {
 pub enum ResponseType {
     /// Plain text
     Text,
-    
+
     /// Formatted text (Markdown, HTML, etc.)
     Formatted,
-    
+
     /// Interactive card/rich message
     Interactive,
-    
+
     /// Error message
     Error,
 }
@@ -69,13 +91,13 @@ pub enum ResponseType {
 pub struct Attachment {
     /// Attachment type
     pub attachment_type: AttachmentType,
-    
+
     /// Content or URL
     pub content: Vec<u8>,
-    
+
     /// File name
     pub filename: Option<String>,
-    
+
     /// MIME type
     pub mime_type: String,
 }
@@ -85,12 +107,15 @@ pub struct Attachment {
 pub enum AttachmentType {
     /// Image file
     Image,
-    
+
     /// Document
     Document,
-    
+
     /// Chart/graph
     Chart,
+
+    /// Audio, e.g. a spoken summary
+    Audio,
 }
 
 /// Suggested action for the user
@@ -98,10 +123,10 @@ pub enum AttachmentType {
 pub struct SuggestedAction {
     /// Action label
     pub label: String,
-    
+
     /// Action command or callback data
     pub action: String,
-    
+
     /// Action type
     pub action_type: ActionType,
 }
@@ -111,10 +136,10 @@ pub struct SuggestedAction {
 pub enum ActionType {
     /// Execute a command
     Command,
-    
+
     /// Follow-up query
     Query,
-    
+
     /// External link
     Link,
 }
@@ -128,9 +153,11 @@ impl BotResponse {
             attachments: Vec::new(),
             actions: Vec::new(),
             metadata: serde_json::Value::Null,
+            continuation: Vec::new(),
+            edit_previous: false,
         }
     }
-    
+
     /// Create a formatted response
     pub fn formatted(content: impl Into<String>) -> Self {
         Self {
@@ -139,9 +166,11 @@ impl BotResponse {
             attachments: Vec::new(),
             actions: Vec::new(),
             metadata: serde_json::Value::Null,
+            continuation: Vec::new(),
+            edit_previous: false,
         }
     }
-    
+
     /// Create an error response
     pub fn error(content: impl Into<String>) -> Self {
         Self {
@@ -150,15 +179,23 @@ impl BotResponse {
             attachments: Vec::new(),
             actions: Vec::new(),
             metadata: serde_json::Value::Null,
+            continuation: Vec::new(),
+            edit_previous: false,
         }
     }
-    
+
     /// Add an attachment
     pub fn with_attachment(mut self, attachment: Attachment) -> Self {
         self.attachments.push(attachment);
         self
     }
-    
+
+    /// Add each of `attachments`, e.g. an optional document
+    pub fn with_attachments(mut self, attachments: impl IntoIterator<Item = Attachment>) -> Self {
+        self.attachments.extend(attachments);
+        self
+    }
+
     /// Add a suggested action
     pub fn with_action(mut self, label: impl Into<String>, action: impl Into<String>) -> Self {
         self.actions.push(SuggestedAction {
@@ -168,58 +205,193 @@ impl BotResponse {
         });
         self
     }
-    
+
     /// Set metadata
     pub fn with_metadata(mut self, metadata: serde_json::Value) -> Self {
         self.metadata = metadata;
         self
     }
+
+    /// Add messages to send after this one
+    pub fn with_continuation(mut self, pages: Vec<String>) -> Self {
+        self.continuation.extend(pages);
+        self
+    }
+
+    /// Deliver by editing the previous message in place
+    pub fn replacing_previous(mut self) -> Self {
+        self.edit_previous = true;
+        self
+    }
+
+    /// Every message of the reply, in sending order
+    pub fn messages(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.content.as_str()).chain(self.continuation.iter().map(String::as_str))
+    }
 }
 
 /// Main bot interface trait
 ///
-/// All platform implementations must implement this trait
+/// All platform implementations must implement this trait. Handlers take
+/// `&self`, so one bot can be shared in an `Arc` and each incoming message
+/// answered in its own task; a user's heavy requests then queue behind each
+/// other and `/cancel` reaches them while they run.
 #[async_trait]
 pub trait BotInterface: Send + Sync {
     /// Get the platform identifier
     fn platform(&self) -> BotPlatform;
-    
+
     /// Handle an incoming message
     async fn on_message(
-        &mut self,
+        &self,
         user_id: &str,
         message: &str,
         context: &mut AnalysisContext,
     ) -> Result<BotResponse>;
-    
+
+    /// Handle a message in two phases: a quick interim reply sent through
+    /// `interim` as soon as it is ready, then the full reply returned
+    ///
+    /// A full reply marked [`BotResponse::edit_previous`] replaces the interim
+    /// message; otherwise it is sent after it. The default sends only what
+    /// [`on_message`](Self::on_message) passes to [`send_interim`], such as
+    /// queue acknowledgements.
+    async fn on_message_progressive(
+        &self,
+        user_id: &str,
+        message: &str,
+        context: &mut AnalysisContext,
+        interim: &UnboundedSender<BotResponse>,
+    ) -> Result<BotResponse> {
+        with_interim(interim.clone(), self.on_message(user_id, message, context)).await
+    }
+
     /// Handle a command
     async fn on_command(
-        &mut self,
+        &self,
         user_id: &str,
         command: &str,
         args: &[String],
         context: &mut AnalysisContext,
     ) -> Result<BotResponse>;
-    
+
     /// Format an analysis result for the platform
     fn format_response(&self, content: &str, context: &AnalysisContext) -> BotResponse;
-    
+
     /// Handle user joining (optional)
-    async fn on_user_join(&mut self, _user_id: &str) -> Result<()> {
+    async fn on_user_join(&self, _user_id: &str) -> Result<()> {
         Ok(())
     }
-    
+
     /// Handle user leaving (optional)
-    async fn on_user_leave(&mut self, _user_id: &str) -> Result<()> {
+    async fn on_user_leave(&self, _user_id: &str) -> Result<()> {
         Ok(())
     }
-    
+
     /// Handle platform-specific events (optional)
-    async fn on_event(&mut self, _event: serde_json::Value) -> Result<()> {
+    async fn on_event(&self, _event: serde_json::Value) -> Result<()> {
         Ok(())
     }
 }
 
+tokio::task_local! {
+    static INTERIM: UnboundedSender<BotResponse>;
+}
+
+/// Run `future` with the interim replies of the handlers inside it (see
+/// [`send_interim`]) going to `interim`
+///
+/// Callers sending the replies themselves, like the DingTalk and Feishu
+/// webhook servers, wrap [`BotInterface::on_message`] in it to tell users
+/// their request is queued while it waits. Like the scope of
+/// [`agent_runtime::with_event_handler`], it does not reach work moved to
+/// another task with `tokio::spawn`.
+pub async fn with_interim<F: Future>(
+    interim: UnboundedSender<BotResponse>,
+    future: F,
+) -> F::Output {
+    INTERIM.scope(interim, future).await
+}
+
+/// Send `response` ahead of the full reply
+///
+/// Outside [`with_interim`] nobody would receive it, so it is dropped.
+pub fn send_interim(response: BotResponse) {
+    let _ = INTERIM.try_with(|interim| interim.send(response).ok());
+}
+
+/// Answer `message` through `bot`'s
+/// [`on_message_progressive`](BotInterface::on_message_progressive),
+/// handing each interim reply to `send` as soon as it is ready
+pub async fn answer_progressively<B, S, F>(
+    bot: &B,
+    user_id: &str,
+    message: &str,
+    context: &mut AnalysisContext,
+    mut send: S,
+) -> Result<BotResponse>
+where
+    B: BotInterface + ?Sized,
+    S: FnMut(BotResponse) -> F,
+    F: Future<Output = ()>,
+{
+    let (interim, mut replies) = mpsc::unbounded_channel();
+    // The channel closes once the answer is done with its sender
+    let answer = async move {
+        let interim = interim;
+        bot.on_message_progressive(user_id, message, context, &interim)
+            .await
+    };
+    let forward = async {
+        while let Some(reply) = replies.recv().await {
+            send(reply).await;
+        }
+    };
+    let (answer, ()) = tokio::join!(answer, forward);
+    answer
+}
+
+impl BotPlatform {
+    /// All platforms
+    pub const ALL: [BotPlatform; 9] = [
+        BotPlatform::CLI,
+        BotPlatform::Telegram,
+        BotPlatform::DingTalk,
+        BotPlatform::Feishu,
+        BotPlatform::Slack,
+        BotPlatform::WeCom,
+        BotPlatform::Matrix,
+        BotPlatform::Web,
+        BotPlatform::Custom,
+    ];
+}
+
+impl BotPlatform {
+    /// Whether sent messages can be edited, e.g. Telegram's
+    /// `editMessageText` or Slack's `chat.update`
+    pub fn supports_editing(&self) -> bool {
+        matches!(
+            self,
+            BotPlatform::Telegram | BotPlatform::Feishu | BotPlatform::Slack | BotPlatform::Web
+        )
+    }
+
+    /// Whether replies can carry audio attachments
+    pub fn supports_audio(&self) -> bool {
+        matches!(
+            self,
+            BotPlatform::Telegram | BotPlatform::Feishu | BotPlatform::Web
+        )
+    }
+
+    /// Whether replies can carry document attachments, which each
+    /// platform's API client uploads in `send_response`; DingTalk robots can
+    /// only send text, Markdown and cards
+    pub fn supports_files(&self) -> bool {
+        !matches!(self, BotPlatform::DingTalk | BotPlatform::Custom)
+    }
+}
+
 impl std::fmt::Display for BotPlatform {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -227,6 +399,9 @@ impl std::fmt::Display for BotPlatform {
             BotPlatform::Telegram => write!(f, "Telegram"),
             BotPlatform::DingTalk => write!(f, "DingTalk"),
             BotPlatform::Feishu => write!(f, "Feishu"),
+            BotPlatform::Slack => write!(f, "Slack"),
+            BotPlatform::WeCom => write!(f, "WeCom"),
+            BotPlatform::Matrix => write!(f, "Matrix"),
             BotPlatform::Web => write!(f, "Web"),
             BotPlatform::Custom => write!(f, "Custom"),
         }
@@ -236,21 +411,31 @@ impl std::fmt::Display for BotPlatform {
 #[cfg(test)]
 mod tests {
     use super::*;
-    
+
     #[test]
     fn test_bot_response_creation() {
         let response = BotResponse::text("Hello, world!");
         assert_eq!(response.response_type, ResponseType::Text);
         assert_eq!(response.content, "Hello, world!");
     }
-    
+
     #[test]
     fn test_bot_response_builder() {
         let response = BotResponse::formatted("**Analysis**")
             .with_action("Refresh", "/refresh")
             .with_action("Compare", "/compare");
-        
+
         assert_eq!(response.actions.len(), 2);
         assert_eq!(response.actions[0].label, "Refresh");
     }
+
+    #[test]
+    fn test_bot_response_snapshots() {
+        for (label, response) in crate::interface::fixtures::bot_responses() {
+            insta::assert_snapshot!(
+                format!("bot_response_{label}"),
+                serde_json::to_string_pretty(&response).unwrap()
+            );
+        }
+    }
 }
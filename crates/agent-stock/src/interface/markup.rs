@@ -0,0 +1,617 @@
+//! Markdown to platform markup
+//!
+//! Agents answer in Markdown, but chat platforms each display a different
+//! dialect: Telegram takes a small HTML subset, DingTalk a Markdown subset
+//! without tables or code, WeCom an even smaller one without italics,
+//! strikethrough or code blocks, Feishu cards Markdown without headings or
+//! tables, and Slack its own `mrkdwn` with single-character styles and
+//! `<url|text>` links. [`render`] parses the Markdown with pulldown-cmark and writes it
+//! in the platform's [`Markup`], escaping text so model output can never
+//! break the message.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::interface::markup::{render, Markup};
+//!
+//! let html = render("**RSI** < 30: oversold", Markup::TelegramHtml);
+//! assert_eq!(html, "<b>RSI</b> &lt; 30: oversold");
+//! ```
+
+use pulldown_cmark::{CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+
+use crate::interface::BotPlatform;
+
+/// Markup a platform displays
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Markup {
+    /// Markdown as written (CLI, web and custom frontends)
+    Markdown,
+    /// Telegram `parse_mode=HTML`, also shown by Matrix clients as
+    /// `org.matrix.custom.html`
+    TelegramHtml,
+    /// DingTalk `markdown` messages
+    DingTalk,
+    /// Feishu `lark_md` card text
+    Feishu,
+    /// Slack `mrkdwn` message and Block Kit text
+    Slack,
+    /// WeCom `markdown` messages
+    WeCom,
+}
+
+impl Markup {
+    /// Markup displayed by a platform
+    pub fn for_platform(platform: BotPlatform) -> Self {
+        match platform {
+            BotPlatform::Telegram | BotPlatform::Matrix => Self::TelegramHtml,
+            BotPlatform::DingTalk => Self::DingTalk,
+            BotPlatform::Feishu => Self::Feishu,
+            BotPlatform::Slack => Self::Slack,
+            BotPlatform::WeCom => Self::WeCom,
+            BotPlatform::CLI | BotPlatform::Web | BotPlatform::Custom => Self::Markdown,
+        }
+    }
+
+    /// Bold text
+    pub fn bold(self, text: &str) -> String {
+        match self {
+            Self::TelegramHtml => format!("<b>{}</b>", escape_html(text)),
+            Self::Slack => format!("*{}*", escape_html(text)),
+            Self::Markdown | Self::DingTalk | Self::Feishu | Self::WeCom => format!("**{text}**"),
+        }
+    }
+
+    /// Text shown verbatim, escaped for the markup
+    pub fn text(self, text: &str) -> String {
+        match self {
+            Self::TelegramHtml | Self::Slack => escape_html(text),
+            Self::Markdown | Self::DingTalk | Self::Feishu | Self::WeCom => text.to_string(),
+        }
+    }
+
+    /// Rows as an aligned table
+    ///
+    /// Telegram, Feishu and Slack show it in monospace; DingTalk and WeCom
+    /// cannot, so they get a bold header line followed by one line per row.
+    pub fn table(self, headers: &[String], rows: &[Vec<String>]) -> String {
+        match self {
+            Self::TelegramHtml => {
+                format!("<pre>{}</pre>", escape_html(&align_table(headers, rows)))
+            }
+            Self::Markdown | Self::Feishu => format!("```\n{}\n```", align_table(headers, rows)),
+            Self::Slack => format!("```\n{}\n```", escape_html(&align_table(headers, rows))),
+            Self::DingTalk | Self::WeCom => {
+                let mut lines = Vec::with_capacity(rows.len() + 1);
+                if !headers.is_empty() {
+                    lines.push(self.bold(&headers.join(" | ")));
+                }
+                lines.extend(rows.iter().map(|row| format!("- {}", row.join(" | "))));
+                lines.join("\n")
+            }
+        }
+    }
+}
+
+/// Escape `&`, `<` and `>` for Telegram HTML and Slack mrkdwn
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Plain text of Telegram HTML written by [`render`]: tags removed and
+/// entities decoded
+pub fn html_to_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+}
+
+/// Render Markdown in `markup`
+///
+/// Raw HTML in the input is shown as text. CommonMark tables and
+/// strikethrough are supported.
+pub fn render(markdown: &str, markup: Markup) -> String {
+    if markup == Markup::Markdown {
+        return markdown.to_string();
+    }
+
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH;
+    let mut renderer = Renderer::new(markup);
+    for event in Parser::new_ext(markdown, options) {
+        renderer.event(event);
+    }
+    renderer.finish()
+}
+
+/// Columns padded to equal width, separated by ` | `
+pub(crate) fn align_table(headers: &[String], rows: &[Vec<String>]) -> String {
+    let columns = rows
+        .iter()
+        .map(Vec::len)
+        .chain(std::iter::once(headers.len()))
+        .max()
+        .unwrap_or(0);
+    let mut widths = vec![0; columns];
+    for row in std::iter::once(headers).chain(rows.iter().map(Vec::as_slice)) {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let line = |row: &[String]| {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell}{}", " ".repeat(width - cell.chars().count())))
+            .collect();
+        cells.join(" | ").trim_end().to_string()
+    };
+
+    let mut lines = Vec::with_capacity(rows.len() + 2);
+    if !headers.is_empty() {
+        lines.push(line(headers));
+        let rule: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+        lines.push(rule.join("-|-"));
+    }
+    lines.extend(rows.iter().map(|row| line(row)));
+    lines.join("\n")
+}
+
+/// Table being collected; cells are rendered as plain text
+#[derive(Default)]
+struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+    in_head: bool,
+}
+
+/// Event-by-event writer for one [`Markup`]
+struct Renderer {
+    markup: Markup,
+    /// Output buffers; block quotes and table cells render into their own
+    buffers: Vec<String>,
+    /// Next number of each open list (`None` for bullets)
+    lists: Vec<Option<u64>>,
+    table: Option<Table>,
+    links: Vec<String>,
+    in_code_block: bool,
+    /// Set after a heading or list item marker so the next block follows on
+    /// directly instead of after a blank line
+    attached: bool,
+}
+
+impl Renderer {
+    fn new(markup: Markup) -> Self {
+        Self {
+            markup,
+            buffers: vec![String::new()],
+            lists: Vec::new(),
+            table: None,
+            links: Vec::new(),
+            in_code_block: false,
+            attached: false,
+        }
+    }
+
+    fn out(&mut self) -> &mut String {
+        self.buffers
+            .last_mut()
+            .expect("renderer always has a buffer")
+    }
+
+    fn push(&mut self, text: &str) {
+        self.out().push_str(text);
+    }
+
+    /// Start a block on a new line, after a blank line unless attached
+    fn block(&mut self) {
+        let blank = !std::mem::take(&mut self.attached);
+        let out = self.out();
+        if out.is_empty() {
+            return;
+        }
+        let trimmed = out.trim_end_matches([' ', '\n']).len();
+        out.truncate(trimmed);
+        out.push_str(if blank { "\n\n" } else { "\n" });
+    }
+
+    /// Write text, escaped for the markup unless it is a table cell
+    fn text(&mut self, text: &str) {
+        self.attached = false;
+        if self.table.is_some() {
+            self.push(text);
+        } else {
+            let text = self.markup.text(text);
+            self.push(&text);
+        }
+    }
+
+    /// Inline style markers; dropped inside tables and where unsupported
+    fn style(&mut self, html: &str, markdown: &str) {
+        if self.table.is_some() {
+            return;
+        }
+        match self.markup {
+            Markup::TelegramHtml => self.push(html),
+            // Slack styles with one character: *bold*, _italic_, ~struck~
+            Markup::Slack => self.push(match markdown {
+                "**" => "*",
+                "*" => "_",
+                "~~" => "~",
+                other => other,
+            }),
+            Markup::DingTalk | Markup::Feishu | Markup::Markdown => self.push(markdown),
+            // WeCom shows bold only
+            Markup::WeCom if markdown == "**" => self.push(markdown),
+            Markup::WeCom => {}
+        }
+    }
+
+    fn event(&mut self, event: Event<'_>) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text)
+                if self.in_code_block
+                    && matches!(self.markup, Markup::DingTalk | Markup::WeCom) =>
+            {
+                // DingTalk and WeCom have no code blocks; quote the lines instead
+                let quoted: Vec<String> = text.lines().map(|line| format!("> {line}")).collect();
+                self.push(&quoted.join("\n"));
+            }
+            Event::Text(text) | Event::Html(text) | Event::InlineHtml(text) => self.text(&text),
+            Event::Code(code) => match self.markup {
+                Markup::TelegramHtml if self.table.is_none() => {
+                    self.push(&format!("<code>{}</code>", escape_html(&code)));
+                }
+                Markup::Slack if self.table.is_none() => {
+                    self.push(&format!("`{}`", escape_html(&code)));
+                }
+                Markup::Feishu | Markup::Markdown | Markup::WeCom if self.table.is_none() => {
+                    self.push(&format!("`{code}`"));
+                }
+                _ => self.text(&code),
+            },
+            Event::SoftBreak | Event::HardBreak => {
+                if self.table.is_some() {
+                    self.push(" ");
+                } else {
+                    self.push("\n");
+                }
+            }
+            Event::Rule => {
+                self.block();
+                self.push("——————");
+            }
+            Event::TaskListMarker(done) => self.push(if done { "☑ " } else { "☐ " }),
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag<'_>) {
+        match tag {
+            Tag::Paragraph => self.block(),
+            Tag::Heading { level, .. } => {
+                self.block();
+                match self.markup {
+                    Markup::DingTalk | Markup::WeCom => {
+                        let hashes = "#".repeat(heading_depth(level));
+                        self.push(&format!("{hashes} "));
+                    }
+                    _ => self.style("<b>", "**"),
+                }
+            }
+            Tag::BlockQuote(_) => {
+                self.block();
+                self.buffers.push(String::new());
+            }
+            Tag::CodeBlock(kind) => {
+                self.block();
+                self.in_code_block = true;
+                match self.markup {
+                    Markup::TelegramHtml => self.push("<pre>"),
+                    // Slack ignores a language after the fence
+                    Markup::Slack => self.push("```\n"),
+                    Markup::Feishu | Markup::Markdown => {
+                        let lang = match kind {
+                            CodeBlockKind::Fenced(lang) => lang.to_string(),
+                            CodeBlockKind::Indented => String::new(),
+                        };
+                        self.push(&format!("```{lang}\n"));
+                    }
+                    Markup::DingTalk | Markup::WeCom => {}
+                }
+            }
+            Tag::List(start) => {
+                if self.lists.is_empty() {
+                    self.block();
+                }
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.attached = true;
+                self.block();
+                let depth = self.lists.len().saturating_sub(1);
+                let marker = match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}. ", *n - 1)
+                    }
+                    _ if matches!(self.markup, Markup::TelegramHtml | Markup::Slack) => {
+                        "• ".to_string()
+                    }
+                    _ => "- ".to_string(),
+                };
+                self.push(&format!("{}{marker}", "  ".repeat(depth)));
+                self.attached = true;
+            }
+            Tag::Table(_) => {
+                self.block();
+                self.table = Some(Table::default());
+            }
+            Tag::TableHead => {
+                if let Some(table) = &mut self.table {
+                    table.in_head = true;
+                }
+            }
+            Tag::TableRow => {
+                if let Some(table) = &mut self.table {
+                    table.rows.push(Vec::new());
+                }
+            }
+            Tag::TableCell => self.buffers.push(String::new()),
+            Tag::Emphasis => self.style("<i>", "*"),
+            Tag::Strong => self.style("<b>", "**"),
+            Tag::Strikethrough if self.markup != Markup::DingTalk => self.style("<s>", "~~"),
+            Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. } => {
+                if self.table.is_none() && self.markup == Markup::TelegramHtml {
+                    self.push(&format!(
+                        "<a href=\"{}\">",
+                        escape_html(&dest_url).replace('"', "&quot;")
+                    ));
+                } else if self.table.is_none() && self.markup == Markup::Slack {
+                    self.push(&format!("<{}|", escape_html(&dest_url).replace('|', "%7C")));
+                } else if self.table.is_none() {
+                    self.push("[");
+                }
+                self.links.push(dest_url.to_string());
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Heading(_) => {
+                if !matches!(self.markup, Markup::DingTalk | Markup::WeCom) {
+                    self.style("</b>", "**");
+                }
+                self.attached = true;
+            }
+            TagEnd::BlockQuote(_) => {
+                let quote = self.buffers.pop().unwrap_or_default();
+                let quote = quote.trim_end();
+                let quote = match self.markup {
+                    Markup::TelegramHtml => format!("<blockquote>{quote}</blockquote>"),
+                    _ => quote
+                        .lines()
+                        .map(|line| format!("> {line}").trim_end().to_string())
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                };
+                self.push(&quote);
+            }
+            TagEnd::CodeBlock => {
+                self.in_code_block = false;
+                let out = self.out();
+                let trimmed = out.trim_end_matches('\n').len();
+                out.truncate(trimmed);
+                match self.markup {
+                    Markup::TelegramHtml => self.push("</pre>"),
+                    Markup::Feishu | Markup::Markdown | Markup::Slack => self.push("\n```"),
+                    Markup::DingTalk | Markup::WeCom => {}
+                }
+            }
+            TagEnd::List(_) => {
+                self.lists.pop();
+            }
+            TagEnd::TableHead => {
+                if let Some(table) = &mut self.table {
+                    table.in_head = false;
+                    // The header row is collected like a body row; move it
+                    table.headers = table.rows.pop().unwrap_or_default();
+                }
+            }
+            TagEnd::TableCell => {
+                let cell = self.buffers.pop().unwrap_or_default().trim().to_string();
+                if let Some(table) = &mut self.table {
+                    if table.in_head && table.rows.is_empty() {
+                        table.rows.push(Vec::new());
+                    }
+                    if let Some(row) = table.rows.last_mut() {
+                        row.push(cell);
+                    }
+                }
+            }
+            TagEnd::Table => {
+                if let Some(table) = self.table.take() {
+                    let rendered = self.markup.table(&table.headers, &table.rows);
+                    self.push(&rendered);
+                }
+            }
+            TagEnd::Emphasis => self.style("</i>", "*"),
+            TagEnd::Strong => self.style("</b>", "**"),
+            TagEnd::Strikethrough if self.markup != Markup::DingTalk => self.style("</s>", "~~"),
+            TagEnd::Link | TagEnd::Image => {
+                let url = self.links.pop().unwrap_or_default();
+                if self.table.is_none() && self.markup == Markup::TelegramHtml {
+                    self.push("</a>");
+                } else if self.table.is_none() && self.markup == Markup::Slack {
+                    self.push(">");
+                } else if self.table.is_none() {
+                    self.push(&format!("]({url})"));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(mut self) -> String {
+        let out = self.buffers.swap_remove(0);
+        out.trim_end().to_string()
+    }
+}
+
+fn heading_depth(level: HeadingLevel) -> usize {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TABLE: &str = "| Symbol | P/E |\n|---|---|\n| AAPL | 29.1 |\n| **MSFT** | 35.2 |";
+
+    #[test]
+    fn test_telegram_escapes_and_styles() {
+        assert_eq!(
+            render(
+                "## Trend\n- RSI: **58** & rising\n- Price > SMA <strong>",
+                Markup::TelegramHtml
+            ),
+            "<b>Trend</b>\n• RSI: <b>58</b> &amp; rising\n• Price &gt; SMA &lt;strong&gt;"
+        );
+        assert_eq!(
+            render(
+                "See [Yahoo](https://finance.yahoo.com/?a=1&b=2) *now*",
+                Markup::TelegramHtml
+            ),
+            "See <a href=\"https://finance.yahoo.com/?a=1&amp;b=2\">Yahoo</a> <i>now</i>"
+        );
+    }
+
+    #[test]
+    fn test_code_blocks() {
+        let markdown = "Formula:\n\n```text\nRSI = 100 - 100 / (1 + RS) < 30\n```\n\nUse `ta`.";
+        assert_eq!(
+            render(markdown, Markup::TelegramHtml),
+            "Formula:\n\n<pre>RSI = 100 - 100 / (1 + RS) &lt; 30</pre>\n\nUse <code>ta</code>."
+        );
+        assert_eq!(
+            render(markdown, Markup::Feishu),
+            "Formula:\n\n```text\nRSI = 100 - 100 / (1 + RS) < 30\n```\n\nUse `ta`."
+        );
+        assert_eq!(
+            render(markdown, Markup::DingTalk),
+            "Formula:\n\n> RSI = 100 - 100 / (1 + RS) < 30\n\nUse ta."
+        );
+    }
+
+    #[test]
+    fn test_slack_mrkdwn() {
+        assert_eq!(
+            render(
+                "## Trend\n- RSI: **58** & *rising*\n- ~~Bearish~~ <b>",
+                Markup::Slack
+            ),
+            "*Trend*\n• RSI: *58* &amp; _rising_\n• ~Bearish~ &lt;b&gt;"
+        );
+        assert_eq!(
+            render(
+                "See [Yahoo](https://finance.yahoo.com/?a=1&b=2) and `ta`",
+                Markup::Slack
+            ),
+            "See <https://finance.yahoo.com/?a=1&amp;b=2|Yahoo> and `ta`"
+        );
+        assert_eq!(
+            render("```text\nRSI < 30\n```", Markup::Slack),
+            "```\nRSI &lt; 30\n```"
+        );
+    }
+
+    #[test]
+    fn test_wecom_markdown() {
+        assert_eq!(
+            render(
+                "## Trend\n- RSI: **58** & *rising*\n- ~~Bearish~~ `ta`",
+                Markup::WeCom
+            ),
+            "## Trend\n- RSI: **58** & rising\n- Bearish `ta`"
+        );
+        assert_eq!(
+            render("Formula:\n\n```text\nRSI < 30\n```", Markup::WeCom),
+            "Formula:\n\n> RSI < 30"
+        );
+        assert_eq!(
+            render(TABLE, Markup::WeCom),
+            render(TABLE, Markup::DingTalk)
+        );
+    }
+
+    #[test]
+    fn test_html_to_text() {
+        let html = render(
+            "**RSI** < 30 & [falling](https://x.io)",
+            Markup::TelegramHtml,
+        );
+        assert_eq!(html_to_text(&html), "RSI < 30 & falling");
+    }
+
+    #[test]
+    fn test_tables() {
+        assert_eq!(
+            render(TABLE, Markup::TelegramHtml),
+            "<pre>Symbol | P/E\n-------|-----\nAAPL   | 29.1\nMSFT   | 35.2</pre>"
+        );
+        assert_eq!(
+            render(TABLE, Markup::Feishu),
+            "```\nSymbol | P/E\n-------|-----\nAAPL   | 29.1\nMSFT   | 35.2\n```"
+        );
+        assert_eq!(
+            render(TABLE, Markup::Slack),
+            "```\nSymbol | P/E\n-------|-----\nAAPL   | 29.1\nMSFT   | 35.2\n```"
+        );
+        assert_eq!(
+            render(TABLE, Markup::DingTalk),
+            "**Symbol | P/E**\n- AAPL | 29.1\n- MSFT | 35.2"
+        );
+    }
+
+    #[test]
+    fn test_headings_lists_and_quotes() {
+        let markdown = "# 结论\n\n1. 短期偏多\n2. ~~看空~~\n\n> 仅供参考\n> 非投资建议";
+        assert_eq!(
+            render(markdown, Markup::DingTalk),
+            "# 结论\n1. 短期偏多\n2. 看空\n\n> 仅供参考\n> 非投资建议"
+        );
+        assert_eq!(
+            render(markdown, Markup::Feishu),
+            "**结论**\n1. 短期偏多\n2. ~~看空~~\n\n> 仅供参考\n> 非投资建议"
+        );
+        assert_eq!(
+            render(markdown, Markup::TelegramHtml),
+            "<b>结论</b>\n1. 短期偏多\n2. <s>看空</s>\n\n<blockquote>仅供参考\n非投资建议</blockquote>"
+        );
+        assert_eq!(render(markdown, Markup::Markdown), markdown);
+    }
+}
@@ -2,12 +2,23 @@
 //!
 //! Platform-agnostic interfaces for building stock analysis bots
 
-pub mod interface;
-pub mod session;
+pub mod block_kit;
+pub mod chart_render;
+pub mod fixtures;
 pub mod formatter;
+pub mod heatmap;
+pub mod highlight;
+pub mod interface;
+pub mod markup;
 pub mod message;
+pub mod queue;
+pub mod session;
+pub mod sparkline;
 
-pub use interface::{BotInterface, BotPlatform, BotResponse};
-pub use session::{SessionManager, UserSession, SessionStorage};
-pub use formatter::{Formatter, FormatterFactory};
+pub use formatter::{Formatter, FormatterFactory, Locale, MessageLimit, Overflow};
+pub use interface::{
+    BotInterface, BotPlatform, BotResponse, answer_progressively, send_interim, with_interim,
+};
 pub use message::{Message, MessageType};
+pub use queue::{RequestQueue, Ticket};
+pub use session::{SessionManager, SessionStorage, UserSession};
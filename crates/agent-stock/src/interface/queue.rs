@@ -0,0 +1,387 @@
+//! Per-user request queue
+//!
+//! Heavy requests (LLM analyses) from one user run one at a time, in the
+//! order they arrived, instead of racing and interleaving their replies.
+//! Across users at most [`RequestQueue::max_concurrent`] requests run at
+//! once. A request that has to wait is acknowledged with its position
+//! ("queued, 2 ahead of you"), and `/cancel` drops everything a user has
+//! queued or running. Requests only overlap when the bot handles each
+//! message in its own task, so share the bot in an `Arc` and spawn one task
+//! per message.
+//!
+//! ```rust,ignore
+//! // Sends "queued, 1 ahead" through `send_interim` if the request waits
+//! let reply = queue.run(user_id, bot.analyze(symbol)).await?;
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use tokio::sync::{Notify, Semaphore};
+
+use crate::error::{Result, StockError};
+use crate::interface::{BotResponse, send_interim};
+
+/// Environment variable capping requests running at once across users
+pub const MAX_CONCURRENT_ENV: &str = "STOCK_MAX_CONCURRENT_REQUESTS";
+
+/// Requests running at once unless configured
+pub const DEFAULT_MAX_CONCURRENT: usize = 4;
+
+/// One user's queued and running requests
+#[derive(Debug, Default)]
+struct UserQueue {
+    /// Ticket ids in arrival order; the front one runs next
+    tickets: VecDeque<u64>,
+    next_id: u64,
+    /// Bumped by [`RequestQueue::cancel`]; tickets from older generations
+    /// are cancelled
+    generation: u64,
+}
+
+#[derive(Debug)]
+struct Shared {
+    users: Mutex<HashMap<String, UserQueue>>,
+    /// Woken whenever a ticket finishes or requests are cancelled
+    changed: Notify,
+    permits: Semaphore,
+    max_concurrent: usize,
+}
+
+/// Per-user FIFO queue with a global concurrency cap
+///
+/// Cheap to clone; clones share the same queue, so bot instances serving the
+/// same users should share one.
+#[derive(Debug, Clone)]
+pub struct RequestQueue {
+    shared: Arc<Shared>,
+}
+
+impl Default for RequestQueue {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CONCURRENT)
+    }
+}
+
+impl RequestQueue {
+    /// Queue running at most `max_concurrent` requests at once (at least one)
+    pub fn new(max_concurrent: usize) -> Self {
+        let max_concurrent = max_concurrent.max(1);
+        Self {
+            shared: Arc::new(Shared {
+                users: Mutex::new(HashMap::new()),
+                changed: Notify::new(),
+                permits: Semaphore::new(max_concurrent),
+                max_concurrent,
+            }),
+        }
+    }
+
+    /// Queue capped by `STOCK_MAX_CONCURRENT_REQUESTS`, or
+    /// [`DEFAULT_MAX_CONCURRENT`] when unset or invalid
+    pub fn from_env() -> Self {
+        let max_concurrent = std::env::var(MAX_CONCURRENT_ENV)
+            .ok()
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or(DEFAULT_MAX_CONCURRENT);
+        Self::new(max_concurrent)
+    }
+
+    /// Most requests running at once
+    pub fn max_concurrent(&self) -> usize {
+        self.shared.max_concurrent
+    }
+
+    /// Add a request to the back of the user's queue
+    pub fn enqueue(&self, user_id: &str) -> Ticket {
+        let mut users = self.users();
+        let queue = users.entry(user_id.to_string()).or_default();
+        let id = queue.next_id;
+        queue.next_id += 1;
+        queue.tickets.push_back(id);
+        Ticket {
+            queue: self.clone(),
+            user_id: user_id.to_string(),
+            id,
+            generation: queue.generation,
+            ahead: queue.tickets.len() - 1,
+        }
+    }
+
+    /// Queue `request` behind the user's earlier ones and run it in turn
+    ///
+    /// A request that has to wait is first acknowledged with its place (see
+    /// [`Ticket::acknowledgement`]) as an interim reply; see [`send_interim`].
+    pub async fn run<F: Future>(&self, user_id: &str, request: F) -> Result<F::Output> {
+        let ticket = self.enqueue(user_id);
+        if let Some(ack) = ticket.acknowledgement() {
+            send_interim(BotResponse::text(ack));
+        }
+        ticket.run(request).await
+    }
+
+    /// Requests the user has queued or running
+    pub fn pending(&self, user_id: &str) -> usize {
+        self.users()
+            .get(user_id)
+            .map_or(0, |queue| queue.tickets.len())
+    }
+
+    /// Cancel every request the user has queued or running; returns how many
+    pub fn cancel(&self, user_id: &str) -> usize {
+        let cancelled = match self.users().get_mut(user_id) {
+            Some(queue) => {
+                queue.generation += 1;
+                queue.tickets.len()
+            }
+            None => 0,
+        };
+        self.shared.changed.notify_waiters();
+        cancelled
+    }
+
+    fn users(&self) -> MutexGuard<'_, HashMap<String, UserQueue>> {
+        // The map stays consistent even if a holder panicked
+        self.shared
+            .users
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+/// A place in a user's queue; dropping it gives up the place
+#[derive(Debug)]
+pub struct Ticket {
+    queue: RequestQueue,
+    user_id: String,
+    id: u64,
+    generation: u64,
+    ahead: usize,
+}
+
+impl Ticket {
+    /// Requests of the same user queued or running before this one
+    pub fn ahead(&self) -> usize {
+        self.ahead
+    }
+
+    /// Message telling the user the request has to wait, if it does
+    pub fn acknowledgement(&self) -> Option<String> {
+        queued_message(self.ahead)
+    }
+
+    /// Wait for this request's turn, then run `request`
+    ///
+    /// Returns [`StockError::Cancelled`] if the user cancels while the
+    /// request waits or runs; a running request is dropped at its next
+    /// await point.
+    pub async fn run<F: Future>(self, request: F) -> Result<F::Output> {
+        // Check for cancellation first so a cancelled request never starts
+        tokio::select! {
+            biased;
+            () = self.cancelled() => Err(StockError::Cancelled),
+            output = self.run_in_turn(request) => Ok(output),
+        }
+    }
+
+    async fn run_in_turn<F: Future>(&self, request: F) -> F::Output {
+        loop {
+            let changed = self.queue.shared.changed.notified();
+            if self.is_next() {
+                break;
+            }
+            changed.await;
+        }
+        // The semaphore is never closed, so acquiring only fails on close
+        let _permit = self.queue.shared.permits.acquire().await;
+        request.await
+    }
+
+    /// Resolves once the user cancels this request
+    async fn cancelled(&self) {
+        loop {
+            let changed = self.queue.shared.changed.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            changed.await;
+        }
+    }
+
+    fn is_next(&self) -> bool {
+        self.queue
+            .users()
+            .get(&self.user_id)
+            .is_some_and(|queue| queue.tickets.front() == Some(&self.id))
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.queue
+            .users()
+            .get(&self.user_id)
+            .is_none_or(|queue| queue.generation != self.generation)
+    }
+}
+
+impl Drop for Ticket {
+    fn drop(&mut self) {
+        {
+            let mut users = self.queue.users();
+            if let Some(queue) = users.get_mut(&self.user_id) {
+                queue.tickets.retain(|id| *id != self.id);
+                if queue.tickets.is_empty() {
+                    users.remove(&self.user_id);
+                }
+            }
+        }
+        self.queue.shared.changed.notify_waiters();
+    }
+}
+
+/// Acknowledgement for a request with `ahead` requests before it, if it has
+/// to wait
+pub fn queued_message(ahead: usize) -> Option<String> {
+    match ahead {
+        0 => None,
+        1 => Some("⏳ Queued, 1 request ahead of you. Send /cancel to stop.".to_string()),
+        n => Some(format!(
+            "⏳ Queued, {n} requests ahead of you. Send /cancel to stop."
+        )),
+    }
+}
+
+/// Reply to `/cancel` after [`RequestQueue::cancel`] dropped `count` requests
+pub fn cancel_message(count: usize) -> String {
+    match count {
+        0 => "Nothing to cancel.".to_string(),
+        1 => "🛑 Cancelled 1 request".to_string(),
+        n => format!("🛑 Cancelled {n} requests"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::with_interim;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use tokio::sync::oneshot;
+
+    /// Request that reports when it starts and runs until released
+    fn held() -> (
+        oneshot::Receiver<()>,
+        oneshot::Sender<()>,
+        impl Future<Output = ()>,
+    ) {
+        let (started_tx, started) = oneshot::channel();
+        let (release, released) = oneshot::channel::<()>();
+        let request = async move {
+            started_tx.send(()).ok();
+            released.await.ok();
+        };
+        (started, release, request)
+    }
+
+    #[tokio::test]
+    async fn test_user_requests_run_in_order() {
+        let queue = RequestQueue::new(4);
+        let first = queue.enqueue("u1");
+        let second = queue.enqueue("u1");
+        let other_user = queue.enqueue("u2");
+        assert_eq!(first.acknowledgement(), None);
+        assert_eq!(
+            second.acknowledgement().unwrap(),
+            "⏳ Queued, 1 request ahead of you. Send /cancel to stop."
+        );
+        assert_eq!(other_user.ahead(), 0);
+
+        let (started, release, request) = held();
+        let running = tokio::spawn(first.run(request));
+        let second_ran = Arc::new(AtomicBool::new(false));
+        let waiting = tokio::spawn(second.run({
+            let second_ran = Arc::clone(&second_ran);
+            async move { second_ran.store(true, Ordering::SeqCst) }
+        }));
+        started.await.unwrap();
+
+        // Other users are not held up, but the user's second request is
+        assert_eq!(other_user.run(async { "other" }).await.unwrap(), "other");
+        assert!(!second_ran.load(Ordering::SeqCst));
+        assert_eq!(queue.pending("u1"), 2);
+
+        release.send(()).unwrap();
+        assert!(running.await.unwrap().is_ok());
+        assert!(waiting.await.unwrap().is_ok());
+        assert!(second_ran.load(Ordering::SeqCst));
+        assert_eq!(queue.pending("u1"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_run_acknowledges_waiting_requests() {
+        let queue = RequestQueue::new(4);
+        let (started, release, request) = held();
+        let (interim, mut replies) = tokio::sync::mpsc::unbounded_channel();
+        let running = tokio::spawn({
+            let (queue, interim) = (queue.clone(), interim.clone());
+            async move { with_interim(interim, queue.run("u1", request)).await }
+        });
+        started.await.unwrap();
+        // Nothing was ahead of the first request
+        assert!(replies.try_recv().is_err());
+
+        let waiting = tokio::spawn({
+            let queue = queue.clone();
+            async move { with_interim(interim, queue.run("u1", async { "second" })).await }
+        });
+        let ack = replies.recv().await.unwrap();
+        assert_eq!(
+            ack.content,
+            "⏳ Queued, 1 request ahead of you. Send /cancel to stop."
+        );
+
+        release.send(()).unwrap();
+        running.await.unwrap().unwrap();
+        assert_eq!(waiting.await.unwrap().unwrap(), "second");
+        assert!(replies.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_drops_running_and_queued() {
+        let queue = RequestQueue::new(1);
+        let (started, _release, request) = held();
+        let running = tokio::spawn(queue.enqueue("u1").run(request));
+        let queued = tokio::spawn(queue.enqueue("u1").run(async {}));
+        started.await.unwrap();
+
+        assert_eq!(queue.cancel("u1"), 2);
+        assert!(matches!(running.await.unwrap(), Err(StockError::Cancelled)));
+        assert!(matches!(queued.await.unwrap(), Err(StockError::Cancelled)));
+        assert_eq!(queue.pending("u1"), 0);
+        assert_eq!(cancel_message(queue.cancel("u1")), "Nothing to cancel.");
+
+        // Later requests are unaffected
+        assert!(queue.enqueue("u1").run(async {}).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_cap_across_users() {
+        let queue = RequestQueue::new(1);
+        let (started, release, request) = held();
+        let busy = tokio::spawn(queue.enqueue("u1").run(request));
+        started.await.unwrap();
+
+        let blocked_ran = Arc::new(AtomicBool::new(false));
+        let blocked = tokio::spawn(queue.enqueue("u2").run({
+            let blocked_ran = Arc::clone(&blocked_ran);
+            async move { blocked_ran.store(true, Ordering::SeqCst) }
+        }));
+        // u2 is next in its own queue but has to wait for the only permit
+        tokio::task::yield_now().await;
+        assert!(!blocked_ran.load(Ordering::SeqCst));
+
+        release.send(()).unwrap();
+        busy.await.unwrap().unwrap();
+        blocked.await.unwrap().unwrap();
+        assert!(blocked_ran.load(Ordering::SeqCst));
+    }
+}
@@ -1,8 +1,9 @@
 //! Session management for bot users
 
+use crate::conversation_store::{ConversationRecord, ConversationStore, conversation_key};
 use crate::engine::AnalysisContext;
 use crate::error::{Result, StockError};
-use crate::interface::BotPlatform;
+use crate::interface::{BotPlatform, BotResponse, MessageLimit, Overflow};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -17,6 +18,9 @@ pub struct UserSession {
     pub preferences: UserPreferences,
     pub created_at: DateTime<Utc>,
     pub last_active: DateTime<Utc>,
+    /// Pages of the last reply not yet sent, shown one at a time by `/more`
+    #[serde(default)]
+    pub pending_pages: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -43,7 +47,7 @@ impl UserSession {
         let user_id = user_id.into();
         let mut context = AnalysisContext::new();
         context.user_id = Some(user_id.clone());
-        
+
         let now = Utc::now();
         Self {
             user_id,
@@ -53,19 +57,20 @@ impl UserSession {
             preferences: UserPreferences::default(),
             created_at: now,
             last_active: now,
+            pending_pages: Vec::new(),
         }
     }
-    
+
     pub fn update_activity(&mut self) {
         self.last_active = Utc::now();
         self.context.update_activity();
     }
-    
+
     pub fn is_expired(&self, max_age_seconds: i64) -> bool {
         let max_age = chrono::Duration::seconds(max_age_seconds);
         Utc::now() - self.last_active > max_age
     }
-    
+
     pub fn watch(&mut self, symbol: impl Into<String>) {
         let symbol = symbol.into();
         if !self.watchlist.contains(&symbol) {
@@ -73,7 +78,7 @@ impl UserSession {
         }
         self.update_activity();
     }
-    
+
     pub fn unwatch(&mut self, symbol: &str) -> bool {
         if let Some(pos) = self.watchlist.iter().position(|s| s == symbol) {
             self.watchlist.remove(pos);
@@ -83,7 +88,7 @@ impl UserSession {
             false
         }
     }
-    
+
     pub fn current_symbol(&self) -> Option<&str> {
         self.context.current_symbol()
     }
@@ -91,9 +96,9 @@ impl UserSession {
 
 pub trait SessionStorage: Send + Sync {
     fn get(&self, user_id: &str) -> Option<UserSession>;
-    fn set(&mut self, user_id: &str, session: UserSession) -> Result<()>;
-    fn delete(&mut self, user_id: &str) -> bool;
-    fn cleanup_expired(&mut self, max_age_seconds: i64) -> usize;
+    fn set(&self, user_id: &str, session: UserSession) -> Result<()>;
+    fn delete(&self, user_id: &str) -> bool;
+    fn cleanup_expired(&self, max_age_seconds: i64) -> usize;
     fn active_sessions(&self) -> Vec<UserSession>;
 }
 
@@ -119,34 +124,34 @@ impl SessionStorage for InMemoryStorage {
     fn get(&self, user_id: &str) -> Option<UserSession> {
         self.sessions.read().ok()?.get(user_id).cloned()
     }
-    
-    fn set(&mut self, user_id: &str, session: UserSession) -> Result<()> {
+
+    fn set(&self, user_id: &str, session: UserSession) -> Result<()> {
         self.sessions
             .write()
             .map_err(|e| StockError::Other(format!("Lock error: {e}")))?
             .insert(user_id.to_string(), session);
         Ok(())
     }
-    
-    fn delete(&mut self, user_id: &str) -> bool {
+
+    fn delete(&self, user_id: &str) -> bool {
         self.sessions
             .write()
             .ok()
             .and_then(|mut sessions| sessions.remove(user_id))
             .is_some()
     }
-    
-    fn cleanup_expired(&mut self, max_age_seconds: i64) -> usize {
+
+    fn cleanup_expired(&self, max_age_seconds: i64) -> usize {
         let mut sessions = match self.sessions.write() {
             Ok(s) => s,
             Err(_) => return 0,
         };
-        
+
         let initial_count = sessions.len();
         sessions.retain(|_, session| !session.is_expired(max_age_seconds));
         initial_count - sessions.len()
     }
-    
+
     fn active_sessions(&self) -> Vec<UserSession> {
         self.sessions
             .read()
@@ -160,6 +165,7 @@ pub struct SessionManager {
     storage: Box<dyn SessionStorage>,
     default_platform: BotPlatform,
     session_ttl: i64,
+    conversations: Option<Arc<dyn ConversationStore>>,
 }
 
 impl SessionManager {
@@ -168,23 +174,39 @@ impl SessionManager {
             storage: Box::new(InMemoryStorage::new()),
             default_platform: platform,
             session_ttl: 3600,
+            conversations: None,
         }
     }
-    
+
     pub fn with_storage(storage: Box<dyn SessionStorage>, platform: BotPlatform) -> Self {
         Self {
             storage,
             default_platform: platform,
             session_ttl: 3600,
+            conversations: None,
         }
     }
-    
+
     pub fn with_ttl(mut self, ttl_seconds: i64) -> Self {
         self.session_ttl = ttl_seconds;
         self
     }
-    
-    pub fn get_or_create(&mut self, user_id: &str) -> Result<UserSession> {
+
+    /// Keep conversation history in `store` across restarts
+    ///
+    /// New sessions pick up the user's saved symbols and turns, and every
+    /// update is written back. The same store can back several managers.
+    pub fn with_conversations(mut self, store: Arc<dyn ConversationStore>) -> Self {
+        self.set_conversations(store);
+        self
+    }
+
+    /// Keep conversation history in `store`; see [`SessionManager::with_conversations`]
+    pub fn set_conversations(&mut self, store: Arc<dyn ConversationStore>) {
+        self.conversations = Some(store);
+    }
+
+    pub fn get_or_create(&self, user_id: &str) -> Result<UserSession> {
         if let Some(mut session) = self.storage.get(user_id) {
             if !session.is_expired(self.session_ttl) {
                 session.update_activity();
@@ -192,30 +214,190 @@ impl SessionManager {
                 return Ok(session);
             }
         }
-        
-        let session = UserSession::new(user_id, self.default_platform);
+
+        let mut session = UserSession::new(user_id, self.default_platform);
+        if let Some(store) = &self.conversations
+            && let Some(record) = store.load(&conversation_key(self.default_platform, user_id))?
+        {
+            record.apply_to(&mut session.context);
+        }
         self.storage.set(user_id, session.clone())?;
         Ok(session)
     }
-    
+
     pub fn get(&self, user_id: &str) -> Option<UserSession> {
         self.storage.get(user_id)
     }
-    
-    pub fn update(&mut self, user_id: &str, mut session: UserSession) -> Result<()> {
+
+    pub fn update(&self, user_id: &str, mut session: UserSession) -> Result<()> {
         session.update_activity();
+        if let Some(store) = &self.conversations {
+            let record = ConversationRecord::from_analysis_context(&session.context);
+            store.save(&conversation_key(self.default_platform, user_id), &record)?;
+        }
         self.storage.set(user_id, session)
     }
-    
-    pub fn delete(&mut self, user_id: &str) -> bool {
+
+    pub fn delete(&self, user_id: &str) -> bool {
+        if let Some(store) = &self.conversations
+            && let Err(e) = store.delete(&conversation_key(self.default_platform, user_id))
+        {
+            tracing::warn!("Failed to delete conversation for {}: {}", user_id, e);
+        }
         self.storage.delete(user_id)
     }
-    
-    pub fn cleanup_expired(&mut self) -> usize {
+
+    pub fn cleanup_expired(&self) -> usize {
         self.storage.cleanup_expired(self.session_ttl)
     }
-    
+
     pub fn active_count(&self) -> usize {
         self.storage.active_sessions().len()
     }
+
+    /// Build the response for a reply, paginated to fit `limit`
+    ///
+    /// With [`Overflow::Split`] every page is returned at once; with
+    /// [`Overflow::Expand`] the first page is returned with a "Show more"
+    /// action and the rest are kept for [`SessionManager::next_page`]. Pages
+    /// left over from an earlier reply are discarded either way.
+    pub fn paginate(
+        &self,
+        user_id: &str,
+        content: &str,
+        limit: MessageLimit,
+    ) -> Result<BotResponse> {
+        let mut pages = limit.paginate(content);
+        let first = pages.remove(0);
+        let mut session = self.get_or_create(user_id)?;
+
+        let response = match limit.overflow {
+            Overflow::Split => {
+                session.pending_pages.clear();
+                BotResponse::formatted(first).with_continuation(pages)
+            }
+            Overflow::Expand => {
+                session.pending_pages = pages;
+                with_show_more(BotResponse::formatted(first), &session)
+            }
+        };
+        self.update(user_id, session)?;
+        Ok(response)
+    }
+
+    /// Response with the next pending page of the user's last reply
+    pub fn next_page(&self, user_id: &str) -> Result<BotResponse> {
+        let mut session = self.get_or_create(user_id)?;
+        if session.pending_pages.is_empty() {
+            return Ok(BotResponse::text("Nothing more to show."));
+        }
+
+        let page = session.pending_pages.remove(0);
+        let response = with_show_more(BotResponse::formatted(page), &session);
+        self.update(user_id, session)?;
+        Ok(response)
+    }
+}
+
+/// Offer `/more` while pages are pending
+fn with_show_more(response: BotResponse, session: &UserSession) -> BotResponse {
+    if session.pending_pages.is_empty() {
+        response
+    } else {
+        response.with_action("Show more", "/more")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_pages_on_demand() {
+        let manager = SessionManager::new(BotPlatform::Telegram);
+        let limit = MessageLimit::for_platform(BotPlatform::Telegram)
+            .with_max_chars(100)
+            .with_overflow(Overflow::Expand);
+        let reply = "A sentence of filler text. ".repeat(7);
+
+        let first = manager.paginate("u1", &reply, limit).unwrap();
+        assert!(first.content.ends_with("(1/3)"), "{}", first.content);
+        assert!(first.continuation.is_empty());
+        assert_eq!(first.actions[0].action, "/more");
+
+        let second = manager.next_page("u1").unwrap();
+        assert!(second.content.ends_with("(2/3)"));
+        assert_eq!(second.actions.len(), 1);
+
+        let third = manager.next_page("u1").unwrap();
+        assert!(third.content.ends_with("(3/3)"));
+        assert!(third.actions.is_empty());
+        assert_eq!(
+            manager.next_page("u1").unwrap().content,
+            "Nothing more to show."
+        );
+
+        // A new reply replaces pages left over from the previous one
+        manager.paginate("u1", &reply, limit).unwrap();
+        manager
+            .paginate("u1", "short", limit.with_overflow(Overflow::Split))
+            .unwrap();
+        assert_eq!(
+            manager.next_page("u1").unwrap().content,
+            "Nothing more to show."
+        );
+    }
+
+    #[test]
+    fn test_split_returns_every_page() {
+        let manager = SessionManager::new(BotPlatform::DingTalk);
+        let limit = MessageLimit::for_platform(BotPlatform::DingTalk).with_max_chars(100);
+        let reply = "A sentence of filler text. ".repeat(7);
+
+        let response = manager.paginate("u1", &reply, limit).unwrap();
+        let messages: Vec<&str> = response.messages().collect();
+        assert_eq!(messages.len(), 3);
+        assert!(messages.iter().all(|m| m.chars().count() <= 100));
+        assert!(response.actions.is_empty());
+    }
+
+    #[test]
+    fn test_conversation_survives_restart() {
+        use crate::conversation_store::InMemoryConversationStore;
+
+        let store: Arc<dyn ConversationStore> = Arc::new(InMemoryConversationStore::new());
+        let telegram =
+            SessionManager::new(BotPlatform::Telegram).with_conversations(Arc::clone(&store));
+        let mut session = telegram.get_or_create("u1").unwrap();
+        session.context.record_exchange(
+            "/analyze AAPL",
+            "Apple looks strong.",
+            vec!["AAPL".to_string()],
+        );
+        telegram.update("u1", session).unwrap();
+
+        // A fresh manager over the same store picks the conversation back up
+        let restarted =
+            SessionManager::new(BotPlatform::Telegram).with_conversations(Arc::clone(&store));
+        let session = restarted.get_or_create("u1").unwrap();
+        assert_eq!(session.current_symbol(), Some("AAPL"));
+        assert_eq!(
+            session.context.conversation_turns[0].response,
+            "Apple looks strong."
+        );
+
+        // Users on other platforms are kept apart
+        let feishu =
+            SessionManager::new(BotPlatform::Feishu).with_conversations(Arc::clone(&store));
+        assert!(
+            feishu
+                .get_or_create("u1")
+                .unwrap()
+                .current_symbol()
+                .is_none()
+        );
+
+        assert!(restarted.delete("u1"));
+        assert!(store.load("telegram:u1").unwrap().is_none());
+    }
 }
@@ -0,0 +1,142 @@
+//! Inline sparklines for text responses
+//!
+//! Chat platforms without image charts still show trends through unicode
+//! block sparklines (`▁▂▃▅▇`). [`chart_lines`] turns [`ChartDataTool`] output
+//! attached to an analysis as `chart` data into one line per series, e.g.
+//!
+//! ```text
+//! Price 30d ▃▂▁▂▃▄▅▅▆▇ 172.50 → 178.25 (+3.3%)
+//! RSI(14) ▄▄▃▃▄▅▅▆▆▅ 58.3
+//! ```
+//!
+//! [`ChartDataTool`]: crate::tools::ChartDataTool
+
+use serde_json::Value;
+
+/// Bars from lowest to highest
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Points shown per sparkline
+pub const SPARKLINE_POINTS: usize = 30;
+
+/// Key of chart data in [`AnalysisResult::data`](crate::engine::AnalysisResult)
+pub const CHART_DATA_KEY: &str = "chart";
+
+/// One bar per value, scaled between the series minimum and maximum
+///
+/// A flat series is drawn at mid height; non-finite values are skipped.
+pub fn sparkline(values: &[f64]) -> String {
+    let values: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let range = max - min;
+
+    values
+        .iter()
+        .map(|value| {
+            if range <= f64::EPSILON {
+                return BARS[BARS.len() / 2 - 1];
+            }
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let index = ((value - min) / range * (BARS.len() - 1) as f64).round() as usize;
+            BARS[index.min(BARS.len() - 1)]
+        })
+        .collect()
+}
+
+/// Sparkline lines for the closing price and any RSI series in chart data
+///
+/// Each series shows its last `points` values. Empty when the data has no
+/// usable series.
+pub fn chart_lines(chart: &Value, points: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    let closes = series(&chart["line"], points);
+    if let (Some(first), Some(last)) = (closes.first(), closes.last()) {
+        let change = if *first == 0.0 {
+            String::new()
+        } else {
+            format!(" ({:+.1}%)", (last - first) / first * 100.0)
+        };
+        lines.push(format!(
+            "Price {}d {} {first:.2} → {last:.2}{change}",
+            closes.len(),
+            sparkline(&closes)
+        ));
+    }
+
+    if let Some(indicators) = chart["indicators"].as_object() {
+        for (name, values) in indicators {
+            let Some(period) = name.strip_prefix("RSI_") else {
+                continue;
+            };
+            let rsi = series(values, points);
+            if let Some(last) = rsi.last() {
+                lines.push(format!("RSI({period}) {} {last:.1}", sparkline(&rsi)));
+            }
+        }
+    }
+    lines
+}
+
+/// Last `points` values of a `[{"timestamp", "value"}]` series
+pub(crate) fn series(values: &Value, points: usize) -> Vec<f64> {
+    let values: Vec<f64> = values
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item["value"].as_f64())
+                .filter(|v| v.is_finite())
+                .collect()
+        })
+        .unwrap_or_default();
+    values[values.len().saturating_sub(points)..].to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn points(values: &[f64]) -> Value {
+        values
+            .iter()
+            .map(|v| json!({ "timestamp": "2024-03-15T00:00:00Z", "value": v }))
+            .collect()
+    }
+
+    #[test]
+    fn test_sparkline_scales_to_range() {
+        assert_eq!(
+            sparkline(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]),
+            "▁▂▃▄▅▆▇█"
+        );
+        assert_eq!(sparkline(&[10.0, 0.0, f64::NAN, 5.0]), "█▁▅");
+        assert_eq!(sparkline(&[3.0, 3.0]), "▄▄");
+        assert_eq!(sparkline(&[]), "");
+    }
+
+    #[test]
+    fn test_chart_lines_from_chart_data() {
+        let closes: Vec<f64> = (0..40).map(|i| 150.0 + f64::from(i)).collect();
+        let chart = json!({
+            "symbol": "AAPL",
+            "line": points(&closes),
+            "indicators": {
+                "SMA_20": points(&[1.0, 2.0]),
+                "RSI_14": points(&[45.0, 50.0, 70.0]),
+            }
+        });
+
+        let lines = chart_lines(&chart, SPARKLINE_POINTS);
+        assert_eq!(lines.len(), 2);
+        assert!(
+            lines[0].starts_with("Price 30d ▁") && lines[0].ends_with("█ 160.00 → 189.00 (+18.1%)"),
+            "{}",
+            lines[0]
+        );
+        assert_eq!(lines[1], "RSI(14) ▁▂█ 70.0");
+        assert!(chart_lines(&json!({}), SPARKLINE_POINTS).is_empty());
+    }
+}
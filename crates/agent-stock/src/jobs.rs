@@ -0,0 +1,650 @@
+//! Asynchronous analysis jobs
+//!
+//! A deep analysis can take minutes, longer than clients want to hold an
+//! HTTP connection open. A `POST /jobs/analyze` handler instead calls
+//! [`JobQueue::submit`] and returns the job id at once; `GET /jobs/{id}`
+//! reads [`JobQueue::get`] for the status and, once done, the result.
+//!
+//! Jobs are persisted like the other stores (encrypted when opened with a
+//! cipher) and run by a fixed number of workers started with
+//! [`JobQueue::spawn_workers`]. A job whose worker panics, or that was
+//! running when the process stopped, is queued again until it has been
+//! attempted [`JobQueue::max_attempts`] times; so is a job that failed with a
+//! retryable error (rate limits, timeouts, network errors).
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::jobs::{ANALYZE_JOB, JobQueue};
+//!
+//! let queue = Arc::new(JobQueue::open_with_cipher("jobs.json", StoreCipher::from_env()?)?);
+//! queue.spawn_workers(Arc::new(engine), 2);
+//!
+//! // POST /jobs/analyze
+//! let id = queue.submit(ANALYZE_JOB, json!({ "symbol": "AAPL", "depth": "deep" }))?;
+//! // GET /jobs/{id}
+//! let job = queue.get(&id).ok_or(StockError::Other("no such job".into()))?;
+//! ```
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, PoisonError, RwLock};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+
+use crate::as_of;
+use crate::depth::AnalysisDepth;
+use crate::engine::OutputSchema;
+use crate::error::{Result, StockError};
+use crate::storage::{self, StoreCipher};
+
+/// Environment variable naming the file jobs are persisted to
+pub const JOBS_FILE_ENV: &str = "STOCK_JOBS_FILE";
+
+/// Environment variable setting how many jobs run at once
+pub const JOB_WORKERS_ENV: &str = "STOCK_JOB_WORKERS";
+
+/// Jobs run at once unless configured
+pub const DEFAULT_WORKERS: usize = 2;
+
+/// Times a job is tried before it is failed for good, unless configured
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+/// Kind of a comprehensive analysis job; see [`AnalyzeJob`]
+pub const ANALYZE_JOB: &str = "analyze";
+
+/// Where a job is in its life
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    /// Waiting for a worker
+    Queued,
+    /// Picked up by a worker
+    Running,
+    /// Finished with a result
+    Succeeded,
+    /// Failed for good
+    Failed,
+}
+
+impl JobStatus {
+    /// Whether the job will not change any more
+    pub fn is_finished(self) -> bool {
+        matches!(self, Self::Succeeded | Self::Failed)
+    }
+}
+
+impl fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Queued => "queued",
+            Self::Running => "running",
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+        })
+    }
+}
+
+/// A submitted job
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Job {
+    /// Identifier returned to the client
+    pub id: String,
+    /// What to run, e.g. [`ANALYZE_JOB`]
+    pub kind: String,
+    /// Parameters of the request, as submitted
+    pub request: Value,
+    /// Current status
+    pub status: JobStatus,
+    /// Times a worker has picked the job up
+    pub attempts: u32,
+    /// When the job was submitted
+    pub created_at: DateTime<Utc>,
+    /// When the latest attempt started
+    pub started_at: Option<DateTime<Utc>>,
+    /// When the job succeeded or failed for good
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Output of a succeeded job
+    pub result: Option<Value>,
+    /// Error of the latest failed attempt
+    pub error: Option<String>,
+}
+
+/// Runs jobs of the kinds it knows
+#[async_trait]
+pub trait JobHandler: Send + Sync {
+    /// Run `job` and return its result
+    async fn run(&self, job: &Job) -> Result<Value>;
+}
+
+/// Jobs persisted to a JSON file, with workers running them
+pub struct JobQueue {
+    jobs: RwLock<BTreeMap<String, Job>>,
+    /// Woken when a job is queued
+    queued: Notify,
+    max_attempts: u32,
+    path: Option<PathBuf>,
+    cipher: Option<StoreCipher>,
+}
+
+impl Default for JobQueue {
+    fn default() -> Self {
+        Self::in_memory()
+    }
+}
+
+impl JobQueue {
+    /// Create a queue that is not persisted
+    pub fn in_memory() -> Self {
+        Self {
+            jobs: RwLock::new(BTreeMap::new()),
+            queued: Notify::new(),
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            path: None,
+            cipher: None,
+        }
+    }
+
+    /// Open a queue persisted at `path`, loading existing jobs
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        Self::open_with_cipher(path, None)
+    }
+
+    /// Open a queue persisted at `path`, encrypted with `cipher` if given
+    ///
+    /// Jobs left running by a previous process are queued again, or failed
+    /// once they have been tried [`DEFAULT_MAX_ATTEMPTS`] times.
+    pub fn open_with_cipher(path: impl Into<PathBuf>, cipher: Option<StoreCipher>) -> Result<Self> {
+        let path = path.into();
+        let jobs = match storage::read_store(&path, cipher.as_ref())? {
+            Some(json) => serde_json::from_str(&json)?,
+            None => BTreeMap::new(),
+        };
+
+        let queue = Self {
+            jobs: RwLock::new(jobs),
+            queued: Notify::new(),
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            path: Some(path),
+            cipher,
+        };
+        queue.recover()?;
+        Ok(queue)
+    }
+
+    /// Try each job at most `attempts` times (at least once)
+    pub fn with_max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// Times a job is tried before it is failed for good
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// File the queue is persisted to, if any
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    fn persist(&self, jobs: &BTreeMap<String, Job>) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let json = serde_json::to_string_pretty(jobs)?;
+        storage::write_store(path, &json, self.cipher.as_ref())
+    }
+
+    /// Requeue jobs whose worker went away mid-run
+    fn recover(&self) -> Result<()> {
+        let mut jobs = self.jobs.write().unwrap_or_else(PoisonError::into_inner);
+        let mut recovered = 0;
+        for job in jobs
+            .values_mut()
+            .filter(|job| job.status == JobStatus::Running)
+        {
+            self.retry_or_fail(job, "worker stopped before the job finished".to_string());
+            recovered += 1;
+        }
+        if recovered > 0 {
+            tracing::info!("Recovered {} interrupted jobs", recovered);
+            self.persist(&jobs)?;
+        }
+        Ok(())
+    }
+
+    /// Queue `job` again if it has attempts left, otherwise fail it
+    fn retry_or_fail(&self, job: &mut Job, error: String) {
+        if job.attempts < self.max_attempts {
+            job.status = JobStatus::Queued;
+        } else {
+            job.status = JobStatus::Failed;
+            job.finished_at = Some(Utc::now());
+        }
+        job.error = Some(error);
+    }
+
+    /// Queue a job and return its id
+    pub fn submit(&self, kind: &str, request: Value) -> Result<String> {
+        let id = format!("job_{}", uuid::Uuid::new_v4().simple());
+        let job = Job {
+            id: id.clone(),
+            kind: kind.to_string(),
+            request,
+            status: JobStatus::Queued,
+            attempts: 0,
+            created_at: Utc::now(),
+            started_at: None,
+            finished_at: None,
+            result: None,
+            error: None,
+        };
+
+        let mut jobs = self.jobs.write().unwrap_or_else(PoisonError::into_inner);
+        jobs.insert(id.clone(), job);
+        self.persist(&jobs)?;
+        drop(jobs);
+        self.queued.notify_one();
+        Ok(id)
+    }
+
+    /// Job `id`, if it exists
+    pub fn get(&self, id: &str) -> Option<Job> {
+        let jobs = self.jobs.read().unwrap_or_else(PoisonError::into_inner);
+        jobs.get(id).cloned()
+    }
+
+    /// Jobs waiting for a worker
+    pub fn pending(&self) -> usize {
+        let jobs = self.jobs.read().unwrap_or_else(PoisonError::into_inner);
+        jobs.values()
+            .filter(|job| job.status == JobStatus::Queued)
+            .count()
+    }
+
+    /// Mark the oldest queued job running and return it
+    fn claim(&self) -> Result<Option<Job>> {
+        let mut jobs = self.jobs.write().unwrap_or_else(PoisonError::into_inner);
+        let Some(job) = jobs
+            .values_mut()
+            .filter(|job| job.status == JobStatus::Queued)
+            .min_by_key(|job| job.created_at)
+        else {
+            return Ok(None);
+        };
+        job.status = JobStatus::Running;
+        job.attempts += 1;
+        job.started_at = Some(Utc::now());
+        let job = job.clone();
+        self.persist(&jobs)?;
+        Ok(Some(job))
+    }
+
+    /// Record the outcome of a run of job `id`
+    ///
+    /// Retryable errors are queued again while attempts are left; other
+    /// errors fail the job.
+    fn finish(&self, id: &str, outcome: Result<Value>) -> Result<()> {
+        let mut jobs = self.jobs.write().unwrap_or_else(PoisonError::into_inner);
+        let Some(job) = jobs.get_mut(id) else {
+            return Ok(());
+        };
+        let requeued = match outcome {
+            Ok(result) => {
+                job.status = JobStatus::Succeeded;
+                job.finished_at = Some(Utc::now());
+                job.result = Some(result);
+                job.error = None;
+                false
+            }
+            Err(e) if e.is_retryable() => {
+                self.retry_or_fail(job, e.to_string());
+                job.status == JobStatus::Queued
+            }
+            Err(e) => {
+                job.status = JobStatus::Failed;
+                job.finished_at = Some(Utc::now());
+                job.error = Some(e.to_string());
+                false
+            }
+        };
+        self.persist(&jobs)?;
+        drop(jobs);
+        if requeued {
+            self.queued.notify_one();
+        }
+        Ok(())
+    }
+
+    /// Record that the worker running job `id` panicked
+    fn crashed(&self, id: &str) -> Result<()> {
+        let mut jobs = self.jobs.write().unwrap_or_else(PoisonError::into_inner);
+        let Some(job) = jobs.get_mut(id) else {
+            return Ok(());
+        };
+        self.retry_or_fail(job, "worker crashed while running the job".to_string());
+        let requeued = job.status == JobStatus::Queued;
+        self.persist(&jobs)?;
+        drop(jobs);
+        if requeued {
+            self.queued.notify_one();
+        }
+        Ok(())
+    }
+
+    /// Remove finished jobs that finished more than `older_than` ago
+    ///
+    /// Returns how many were removed.
+    pub fn prune(&self, older_than: chrono::Duration) -> Result<usize> {
+        let cutoff = Utc::now() - older_than;
+        let mut jobs = self.jobs.write().unwrap_or_else(PoisonError::into_inner);
+        let before = jobs.len();
+        jobs.retain(|_, job| job.finished_at.is_none_or(|finished| finished > cutoff));
+        let removed = before - jobs.len();
+        if removed > 0 {
+            self.persist(&jobs)?;
+        }
+        Ok(removed)
+    }
+
+    /// Run one queued job with `handler`, if there is one
+    ///
+    /// Returns whether a job was run. The handler runs in its own task, so
+    /// a panic is recorded as a crash instead of taking the worker down.
+    pub async fn run_next(&self, handler: &Arc<dyn JobHandler>) -> Result<bool> {
+        let Some(job) = self.claim()? else {
+            return Ok(false);
+        };
+
+        let task = tokio::spawn({
+            let handler = Arc::clone(handler);
+            let job = job.clone();
+            async move { handler.run(&job).await }
+        });
+        match task.await {
+            Ok(outcome) => {
+                if let Err(e) = &outcome {
+                    tracing::warn!("Job {} ({}) failed: {}", job.id, job.kind, e);
+                }
+                self.finish(&job.id, outcome)?;
+            }
+            Err(e) => {
+                tracing::error!(
+                    "Worker crashed running job {} ({}): {}",
+                    job.id,
+                    job.kind,
+                    e
+                );
+                self.crashed(&job.id)?;
+            }
+        }
+        Ok(true)
+    }
+
+    /// Start `workers` tasks (at least one) running queued jobs with `handler`
+    pub fn spawn_workers(
+        self: &Arc<Self>,
+        handler: Arc<dyn JobHandler>,
+        workers: usize,
+    ) -> Vec<JoinHandle<()>> {
+        (0..workers.max(1))
+            .map(|_| {
+                let queue = Arc::clone(self);
+                let handler = Arc::clone(&handler);
+                tokio::spawn(async move {
+                    loop {
+                        // Register for wakeups before looking, so a job
+                        // submitted in between is not missed
+                        let queued = queue.queued.notified();
+                        tokio::pin!(queued);
+                        queued.as_mut().enable();
+                        match queue.run_next(&handler).await {
+                            Ok(true) => {}
+                            Ok(false) => queued.await,
+                            Err(e) => {
+                                tracing::error!("Job queue error: {}", e);
+                                tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                            }
+                        }
+                    }
+                })
+            })
+            .collect()
+    }
+}
+
+/// Workers to start, from `STOCK_JOB_WORKERS` or [`DEFAULT_WORKERS`]
+pub fn workers_from_env() -> usize {
+    std::env::var(JOB_WORKERS_ENV)
+        .ok()
+        .and_then(|workers| workers.trim().parse().ok())
+        .filter(|workers| *workers > 0)
+        .unwrap_or(DEFAULT_WORKERS)
+}
+
+/// Request of an [`ANALYZE_JOB`]
+///
+/// `{"symbol": "AAPL", "depth": "deep", "schema": "compact"}`; `depth`
+/// defaults to standard and `schema` (see [`OutputSchema::parse`]) to the
+/// full result. An `as_of` date (`"2023-01-31"`) analyses the stock as of
+/// that past day; see [`crate::as_of`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnalyzeJob {
+    /// Stock to analyze
+    pub symbol: String,
+    /// How thorough the analysis is
+    pub depth: AnalysisDepth,
+    /// Shape the result is stored in
+    pub schema: OutputSchema,
+    /// Past day to analyse as of, if any
+    pub as_of: Option<NaiveDate>,
+}
+
+impl AnalyzeJob {
+    /// Parse and validate a request, e.g. before accepting it over HTTP
+    pub fn parse(request: &Value) -> Result<Self> {
+        let symbol = request["symbol"]
+            .as_str()
+            .map(|symbol| symbol.trim().to_uppercase())
+            .filter(|symbol| !symbol.is_empty())
+            .ok_or_else(|| StockError::InvalidSymbol("Missing symbol".to_string()))?;
+        let depth = match request.get("depth").filter(|depth| !depth.is_null()) {
+            Some(depth) => depth
+                .as_str()
+                .and_then(AnalysisDepth::parse)
+                .ok_or_else(|| {
+                    StockError::CommandError(format!(
+                        "Unknown depth: {depth}. Available: quick, standard, deep"
+                    ))
+                })?,
+            None => AnalysisDepth::default(),
+        };
+        let schema = match request.get("schema").filter(|schema| !schema.is_null()) {
+            Some(schema) => OutputSchema::parse(schema)?,
+            None => OutputSchema::Full,
+        };
+        let as_of = match request.get("as_of").filter(|as_of| !as_of.is_null()) {
+            Some(date) => {
+                let parsed = date.as_str().and_then(as_of::parse).ok_or_else(|| {
+                    StockError::CommandError(format!("Invalid as_of date: {date}. Use YYYY-MM-DD"))
+                })?;
+                Some(as_of::validate(parsed)?)
+            }
+            None => None,
+        };
+        Ok(Self {
+            symbol,
+            depth,
+            schema,
+            as_of,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    /// Echoes the request; fails `failures` times first with `error`
+    struct Echo {
+        calls: AtomicU32,
+        failures: u32,
+        error: fn() -> StockError,
+    }
+
+    impl Echo {
+        fn new(failures: u32, error: fn() -> StockError) -> Arc<Self> {
+            Arc::new(Self {
+                calls: AtomicU32::new(0),
+                failures,
+                error,
+            })
+        }
+    }
+
+    #[async_trait]
+    impl JobHandler for Echo {
+        async fn run(&self, job: &Job) -> Result<Value> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err((self.error)());
+            }
+            Ok(job.request.clone())
+        }
+    }
+
+    struct Panics;
+
+    #[async_trait]
+    impl JobHandler for Panics {
+        async fn run(&self, _job: &Job) -> Result<Value> {
+            panic!("handler bug");
+        }
+    }
+
+    #[test]
+    fn test_analyze_job_request() {
+        let job = AnalyzeJob::parse(&json!({ "symbol": " aapl ", "depth": "deep" })).unwrap();
+        assert_eq!(job.symbol, "AAPL");
+        assert_eq!(job.depth, AnalysisDepth::Deep);
+        assert_eq!(job.schema, OutputSchema::Full);
+
+        let job = AnalyzeJob::parse(&json!({ "symbol": "MSFT", "schema": "compact" })).unwrap();
+        assert_eq!(
+            (job.depth, job.schema),
+            (AnalysisDepth::Standard, OutputSchema::Compact)
+        );
+
+        assert!(AnalyzeJob::parse(&json!({ "depth": "deep" })).is_err());
+        assert!(AnalyzeJob::parse(&json!({ "symbol": "AAPL", "depth": "extreme" })).is_err());
+        assert!(AnalyzeJob::parse(&json!({ "symbol": "AAPL", "schema": "tiny" })).is_err());
+
+        let job = AnalyzeJob::parse(&json!({ "symbol": "NVDA", "as_of": "2023-01-31" })).unwrap();
+        assert_eq!(job.as_of, NaiveDate::from_ymd_opt(2023, 1, 31));
+        assert_eq!(
+            AnalyzeJob::parse(&json!({ "symbol": "NVDA" }))
+                .unwrap()
+                .as_of,
+            None
+        );
+        assert!(AnalyzeJob::parse(&json!({ "symbol": "NVDA", "as_of": "2999-01-01" })).is_err());
+        assert!(AnalyzeJob::parse(&json!({ "symbol": "NVDA", "as_of": "yesterday" })).is_err());
+    }
+
+    fn temp_path() -> PathBuf {
+        std::env::temp_dir().join(format!("jobs-{}.json", uuid::Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn test_runs_jobs_and_retries_retryable_errors() {
+        let queue = JobQueue::in_memory();
+        let handler: Arc<dyn JobHandler> = Echo::new(1, || StockError::Timeout("llm".into()));
+        let id = queue
+            .submit(ANALYZE_JOB, json!({ "symbol": "AAPL" }))
+            .unwrap();
+        assert_eq!(queue.get(&id).unwrap().status, JobStatus::Queued);
+
+        assert!(queue.run_next(&handler).await.unwrap());
+        let job = queue.get(&id).unwrap();
+        assert_eq!((job.status, job.attempts), (JobStatus::Queued, 1));
+        assert!(job.error.unwrap().contains("timed out"));
+
+        assert!(queue.run_next(&handler).await.unwrap());
+        let job = queue.get(&id).unwrap();
+        assert_eq!((job.status, job.attempts), (JobStatus::Succeeded, 2));
+        assert_eq!(job.result, Some(json!({ "symbol": "AAPL" })));
+        assert!(job.error.is_none() && job.finished_at.is_some());
+        assert!(!queue.run_next(&handler).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_fails_after_max_attempts_or_permanent_error() {
+        let queue = JobQueue::in_memory().with_max_attempts(2);
+        let flaky: Arc<dyn JobHandler> = Echo::new(5, || StockError::rate_limited("Yahoo Finance"));
+        let id = queue.submit(ANALYZE_JOB, json!({})).unwrap();
+        while queue.run_next(&flaky).await.unwrap() {}
+        let job = queue.get(&id).unwrap();
+        assert_eq!((job.status, job.attempts), (JobStatus::Failed, 2));
+
+        let invalid: Arc<dyn JobHandler> =
+            Echo::new(1, || StockError::InvalidSymbol("ZZZZ".into()));
+        let id = queue.submit(ANALYZE_JOB, json!({})).unwrap();
+        assert!(queue.run_next(&invalid).await.unwrap());
+        let job = queue.get(&id).unwrap();
+        assert_eq!((job.status, job.attempts), (JobStatus::Failed, 1));
+    }
+
+    #[tokio::test]
+    async fn test_panicking_handler_is_a_crash() {
+        let queue = JobQueue::in_memory().with_max_attempts(2);
+        let handler: Arc<dyn JobHandler> = Arc::new(Panics);
+        let id = queue.submit(ANALYZE_JOB, json!({})).unwrap();
+
+        assert!(queue.run_next(&handler).await.unwrap());
+        assert_eq!(queue.get(&id).unwrap().status, JobStatus::Queued);
+        assert!(queue.run_next(&handler).await.unwrap());
+        let job = queue.get(&id).unwrap();
+        assert_eq!(job.status, JobStatus::Failed);
+        assert!(job.error.unwrap().contains("crashed"));
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_jobs_are_requeued_on_open() {
+        let path = temp_path();
+        let id = {
+            let queue = JobQueue::open(&path).unwrap();
+            let id = queue
+                .submit(ANALYZE_JOB, json!({ "symbol": "MSFT" }))
+                .unwrap();
+            // Claimed, then the process stops before it finishes
+            queue.claim().unwrap();
+            id
+        };
+
+        let queue = Arc::new(JobQueue::open(&path).unwrap());
+        let job = queue.get(&id).unwrap();
+        assert_eq!((job.status, job.attempts), (JobStatus::Queued, 1));
+
+        queue.spawn_workers(Echo::new(0, || StockError::Cancelled), 2);
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !queue.get(&id).unwrap().status.is_finished() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(queue.get(&id).unwrap().status, JobStatus::Succeeded);
+
+        assert_eq!(queue.prune(chrono::Duration::zero()).unwrap(), 1);
+        assert!(JobQueue::open(&path).unwrap().get(&id).is_none());
+        std::fs::remove_file(path).unwrap();
+    }
+}
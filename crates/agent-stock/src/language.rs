@@ -0,0 +1,245 @@
+//! Response language and bilingual replies
+//!
+//! A reply is written in Chinese, in English, or in both. Bilingual replies
+//! are written in the prompt language of the [`StockConfig`] as usual and
+//! then translated into the other one by the
+//! [`TranslatorAgent`](crate::agents::TranslatorAgent), which can run on a
+//! cheaper model (`STOCK_MODEL_TRANSLATOR`). A translation must keep every
+//! number of the original ([`missing_numbers`]); one that drops or changes a
+//! number is retried once and otherwise left out. Formatters show the two
+//! versions one after the other ([`two_sections`]).
+
+use agent_core::Context;
+use agent_prompt::Language;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+use crate::config::StockConfig;
+
+/// Context key under which the bilingual flag is stored
+pub const BILINGUAL_CONTEXT_KEY: &str = "bilingual";
+
+/// Key of the translation of a bilingual analysis in
+/// [`AnalysisResult::data`](crate::engine::AnalysisResult::data)
+pub const TRANSLATION_DATA_KEY: &str = "translation";
+
+/// Language replies are written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseLanguage {
+    /// Simplified Chinese
+    Chinese,
+    /// English
+    English,
+    /// The prompt language followed by a translation into the other one
+    Bilingual,
+}
+
+impl ResponseLanguage {
+    /// All response languages
+    pub const ALL: [ResponseLanguage; 3] = [Self::Chinese, Self::English, Self::Bilingual];
+
+    /// Parse a language name or code (English or Chinese)
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "zh" | "chinese" | "中文" | "zh-cn" | "zh-hans" => Some(Self::Chinese),
+            "en" | "english" | "英文" | "英语" => Some(Self::English),
+            "bilingual" | "both" | "zh+en" | "en+zh" | "双语" | "中英" | "中英文" => {
+                Some(Self::Bilingual)
+            }
+            _ => None,
+        }
+    }
+
+    /// Canonical name; single languages use their ISO 639-1 code
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Chinese => "zh",
+            Self::English => "en",
+            Self::Bilingual => "bilingual",
+        }
+    }
+
+    /// Short human-readable description
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Chinese => "中文回复 (Replies in Chinese)",
+            Self::English => "Replies in English",
+            Self::Bilingual => "中英双语 (Chinese and English side by side)",
+        }
+    }
+
+    /// Configured reply language: [`StockConfig::bilingual`], or else the
+    /// prompt language (English unless it is Chinese)
+    ///
+    /// [`StockConfig::bilingual`]: crate::config::StockConfig::bilingual
+    pub fn for_config(config: &StockConfig) -> Self {
+        if config.bilingual {
+            Self::Bilingual
+        } else if config.response_language == Language::Chinese {
+            Self::Chinese
+        } else {
+            Self::English
+        }
+    }
+
+    /// Read the response language stored in an agent context
+    pub fn from_context(context: &Context) -> Option<Self> {
+        if context.get(BILINGUAL_CONTEXT_KEY).and_then(Value::as_bool) == Some(true) {
+            return Some(Self::Bilingual);
+        }
+        context.language().and_then(Self::parse)
+    }
+
+    /// Store the response language in an agent context
+    ///
+    /// Single languages set the context language; bilingual replies keep the
+    /// prompt language and are translated afterwards.
+    pub fn apply_to(self, context: &mut Context) {
+        match self {
+            Self::Bilingual => context.insert(BILINGUAL_CONTEXT_KEY, Value::Bool(true)),
+            language => {
+                context.insert(BILINGUAL_CONTEXT_KEY, Value::Bool(false));
+                context.set_language(language.as_str());
+            }
+        }
+    }
+}
+
+impl fmt::Display for ResponseLanguage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Language a bilingual reply written in `primary` is translated into
+pub fn translation_language(primary: &Language) -> Language {
+    match primary {
+        Language::Chinese => Language::English,
+        _ => Language::Chinese,
+    }
+}
+
+/// Numbers in `text`, without thousands separators
+///
+/// `1,234.5` and `1234.5` are the same number; a trailing period ends the
+/// number rather than starting its decimals.
+pub fn numbers(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut numbers = Vec::new();
+    let mut current = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        let digit_follows = chars.get(i + 1).is_some_and(char::is_ascii_digit);
+        let decimal_point =
+            c == '.' && !current.is_empty() && digit_follows && !current.contains('.');
+        if c.is_ascii_digit() || decimal_point {
+            current.push(c);
+        } else if c == ',' && !current.is_empty() && digit_follows {
+            // Thousands separator
+        } else if !current.is_empty() {
+            numbers.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        numbers.push(current);
+    }
+    numbers
+}
+
+/// Numbers of `original` that `translation` lacks, counting repeats
+pub fn missing_numbers(original: &str, translation: &str) -> Vec<String> {
+    let mut available = numbers(translation);
+    numbers(original)
+        .into_iter()
+        .filter(|number| match available.iter().position(|n| n == number) {
+            Some(i) => {
+                available.swap_remove(i);
+                false
+            }
+            None => true,
+        })
+        .collect()
+}
+
+/// Heading of a reply section in `language`
+fn section_title(language: &Language) -> &str {
+    match language {
+        Language::Chinese => "中文",
+        Language::English => "English",
+        Language::Other(code) => code,
+    }
+}
+
+/// A reply and its translation as two Markdown sections
+pub fn two_sections(primary: (&Language, &str), secondary: (&Language, &str)) -> String {
+    format!(
+        "## {}\n\n{}\n\n---\n\n## {}\n\n{}",
+        section_title(primary.0),
+        primary.1.trim(),
+        section_title(secondary.0),
+        secondary.1.trim()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_context() {
+        assert_eq!(
+            ResponseLanguage::parse("双语"),
+            Some(ResponseLanguage::Bilingual)
+        );
+        assert_eq!(
+            ResponseLanguage::parse("EN"),
+            Some(ResponseLanguage::English)
+        );
+        assert_eq!(
+            ResponseLanguage::parse("中文"),
+            Some(ResponseLanguage::Chinese)
+        );
+        assert_eq!(ResponseLanguage::parse("ja"), None);
+
+        let mut context = Context::new();
+        assert_eq!(ResponseLanguage::from_context(&context), None);
+        ResponseLanguage::Bilingual.apply_to(&mut context);
+        assert_eq!(
+            ResponseLanguage::from_context(&context),
+            Some(ResponseLanguage::Bilingual)
+        );
+        ResponseLanguage::English.apply_to(&mut context);
+        assert_eq!(
+            ResponseLanguage::from_context(&context),
+            Some(ResponseLanguage::English)
+        );
+        assert_eq!(context.language(), Some("en"));
+    }
+
+    #[test]
+    fn test_missing_numbers() {
+        assert_eq!(
+            numbers("P/E 28.5, revenue $1,234.5M (up 12%)."),
+            ["28.5", "1234.5", "12"]
+        );
+
+        let original = "RSI is 72.4 and P/E 28.5; up 12% in Q3 2024.";
+        let good = "RSI为72.4,市盈率28.5;2024年第3季度上涨12%。";
+        assert!(missing_numbers(original, good).is_empty());
+
+        let bad = "RSI为72,市盈率28.5;2024年第三季度上涨12%。";
+        assert_eq!(missing_numbers(original, bad), ["72.4", "3"]);
+    }
+
+    #[test]
+    fn test_two_sections() {
+        let text = two_sections(
+            (&Language::Chinese, "看涨。\n"),
+            (&Language::English, "Bullish."),
+        );
+        assert_eq!(text, "## 中文\n\n看涨。\n\n---\n\n## English\n\nBullish.");
+        assert_eq!(translation_language(&Language::English), Language::Chinese);
+        assert_eq!(translation_language(&Language::Chinese), Language::English);
+    }
+}
@@ -4,14 +4,23 @@
 //! a multi-agent architecture. It includes:
 //!
 //! - Data fetching from multiple sources (Yahoo Finance, Alpha Vantage)
+//! - Crypto assets (BTC-USD, ETH-USD) alongside stocks in price, technical,
+//!   news and comparison flows
 //! - Technical analysis with 70+ indicators (RSI, MACD, Bollinger Bands, etc.)
 //! - Fundamental analysis (P/E ratios, market cap, financials)
-//! - News and sentiment analysis
+//! - News and sentiment analysis from pluggable providers (Finnhub, Alpha
+//!   Vantage), merged or falling back to one another, with an archive that
+//!   tracks sentiment over time against price moves
 //! - Earnings report analysis (SEC EDGAR 10-K/10-Q filings)
 //! - Macroeconomic analysis (Fed policy, economic indicators)
 //! - Geopolitical risk assessment
+//! - ESG and sustainability scoring (Yahoo Finance / Finnhub)
+//! - Thematic basket analysis (AI, EV, semiconductors)
 //! - Multi-agent coordination via delegating agent pattern
 //! - Interactive bot with conversation context support
+//! - Daily market wrap after the close, delivered on a schedule
+//! - Portfolio tracking: value, P&L, YTD return, beta and sector exposure
+//! - Tool results normalized to dollars and percent, with their units labeled
 //!
 //! # Architecture
 //!
@@ -23,6 +32,10 @@
 //! - `NewsAnalyzerAgent`: Analyzes news and sentiment
 //! - `EarningsAnalyzerAgent`: Analyzes SEC filings and earnings reports
 //! - `MacroAnalyzerAgent`: Analyzes macroeconomic conditions
+//! - `EsgAnalyzerAgent`: Analyzes ESG scores and controversies
+//!
+//! `PortfolioAgent` answers questions about a user's recorded positions
+//! outside the delegation, since it needs to know whose portfolio to read.
 //!
 //! # Features
 //!
@@ -31,11 +44,22 @@
 //! - **Conversation Context**: Follow-up questions understand previous context
 //! - **Stock Comparison**: Compare multiple stocks side by side
 //! - **Watchlist**: Track stocks of interest
+//! - **Prediction Tracking**: Directional calls are scored against realized prices
+//! - **Bilingual Replies**: Analyses in Chinese and English side by side
+//!   (see [`language`])
+//! - **Source Translation**: English headlines quoted with a cached
+//!   translation (see [`source_translation`])
+//! - **Point-in-Time Analysis**: Analyses as of a past date see only the data
+//!   known then (see [`as_of`])
+//! - **Reports**: Analyses exported as Markdown, HTML or PDF documents
+//!   (see [`report`])
+//! - **Plugins**: Third-party analyzers join routing and delegation via [`AnalyzerPlugin`]
 //!
 //! # Example
 //!
 //! ```rust,ignore
 //! use agent_stock::{StockAnalysisAgent, StockConfig};
+//! use agent_core::Context;
 //! use agent_runtime::AgentRuntime;
 //! use std::sync::Arc;
 //!
@@ -53,7 +77,7 @@
 //!     let agent = StockAnalysisAgent::new(runtime, config).await?;
 //!
 //!     // Analyze a stock
-//!     let result = agent.analyze("AAPL").await?;
+//!     let result = agent.analyze("AAPL", &mut Context::new()).await?;
 //!     println!("{}", result);
 //!
 //!     Ok(())
@@ -79,39 +103,93 @@
 //! ```
 
 pub mod agents;
+pub mod alerts;
+pub mod anomalies;
 pub mod api;
+pub mod api_keys;
+pub mod as_of;
+pub mod backtest;
+pub mod backup;
 pub mod bot;
 pub mod cache;
 pub mod config;
+pub mod conversation_store;
+pub mod crypto;
+pub mod delivery;
+pub mod depth;
+pub mod doctor;
 pub mod engine;
 pub mod error;
+pub mod eval;
+pub mod idempotency;
+pub mod inflation;
 pub mod interface;
+pub mod jobs;
+pub mod language;
+pub mod live;
+pub mod macro_alerts;
+pub mod market_wrap;
+pub mod migrations;
+pub mod news;
+pub mod news_archive;
+pub mod news_digest;
+pub mod notebook;
 pub mod platforms;
+pub mod plugin;
+pub mod portfolio;
+pub mod predictions;
 pub mod prompts;
+#[cfg(feature = "redis")]
+pub mod redis_cache;
+pub mod report;
 pub mod router;
+pub mod scheduler;
+pub mod screener;
+pub mod source_translation;
+pub mod sse;
+pub mod storage;
+pub mod style;
+pub mod timezone;
 pub mod tools;
+pub mod units;
+pub mod usage;
 
 // Re-export main types for convenience
 pub use agents::{
-    DataFetcherAgent, EarningsAnalyzerAgent, FundamentalAnalyzerAgent,
-    MacroAnalyzerAgent, NewsAnalyzerAgent, StockAnalysisAgent, TechnicalAnalyzerAgent,
-    ParallelAnalysisResult,
+    DataFetcherAgent, EarningsAnalyzerAgent, EsgAnalyzerAgent, FundamentalAnalyzerAgent,
+    MacroAnalyzerAgent, NewsAnalyzerAgent, ParallelAnalysisResult, PortfolioAgent,
+    StockAnalysisAgent, TechnicalAnalyzerAgent,
 };
+pub use config::{AgentModelOverride, StockConfig};
+pub use depth::AnalysisDepth;
 pub use engine::{
-    StockAnalysisEngine, AnalysisContext, AnalysisResult, AnalysisType, ComparisonResult,
+    AnalysisContext, AnalysisResult, AnalysisType, ComparisonResult, StockAnalysisEngine,
 };
-pub use config::StockConfig;
 pub use error::{Result, StockError};
-pub use router::{QueryIntent, SmartRouter, RoutingResult};
+pub use language::ResponseLanguage;
+pub use plugin::AnalyzerPlugin;
+pub use predictions::{PredictionTracker, Scoreboard};
+pub use router::{QueryIntent, RoutingResult, SmartRouter};
+pub use style::ResponseStyle;
+pub use usage::{UsageSink, UsageStats};
 
 // Re-export cache utilities
-pub use cache::{CacheManager, CacheTtlConfig, CacheStats, shared_cache, init_shared_cache};
+pub use cache::{
+    CacheBackend, CacheCategory, CacheManager, CacheStats, CacheTtlConfig, MemoryBackend,
+    init_shared_cache, shared_cache,
+};
 
 // Re-export Language from agent-prompt
 pub use agent_prompt::Language;
 
 // Re-export commonly used tools
 pub use tools::{
-    EarningsReportTool, GeopoliticalTool, MacroEconomicTool,
-    SectorAnalysisTool,
+    EarningsReportTool, EsgTool, GeopoliticalTool, GlossaryTool, MacroEconomicTool,
+    SectorAnalysisTool, SupplyChainTool, ThemeAnalysisTool,
+};
+
+// Re-export typed tool clients for direct use from Rust
+pub use tools::{
+    MacroEconomicClient, MacroEconomicParams, TechnicalIndicatorClient, TechnicalIndicatorOutput,
+    TechnicalIndicatorParams,
 };
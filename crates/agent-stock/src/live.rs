@@ -0,0 +1,391 @@
+//! Live price updates pushed to chat
+//!
+//! `/live AAPL` follows a symbol's trades from the [`QuoteStreamer`] and
+//! pushes its price to the chat the command came from, through the
+//! [`Notifier`] registered for that platform. Trades arrive many times a
+//! second, so each feed sends at most one update per interval.
+//!
+//! With an [`AnomalyMonitor`] attached, watchlist symbols are also followed
+//! for halts, limit moves and gaps (see [`crate::anomalies`]).
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::api::stream::{QuoteStreamer, StreamConfig};
+//! use agent_stock::live::LiveQuotes;
+//!
+//! let streamer = QuoteStreamer::spawn(StreamConfig::from_config(&config).unwrap());
+//! let live = Arc::new(LiveQuotes::new(streamer));
+//! live.register_notifier(BotPlatform::Telegram, Arc::new(TelegramApi::new(token)));
+//! let bot = TelegramBot::new(telegram_config, engine)
+//!     .with_services(BotServices::new().with_live(live));
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use crate::alerts::Notifier;
+use crate::anomalies::AnomalyMonitor;
+use crate::api::stream::{QuoteStream, QuoteStreamer, Tick};
+use crate::bot::Command;
+use crate::config::{FINNHUB_API_KEY_ENV, POLYGON_API_KEY_ENV};
+use crate::delivery::{DeliveryStore, QuietNotifier};
+use crate::error::{Result, StockError};
+use crate::interface::BotPlatform;
+
+/// Default shortest gap between two updates of one feed
+const DEFAULT_MIN_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Symbols one user can follow live at once
+const MAX_FEEDS_PER_USER: usize = 5;
+
+/// Live price feeds of chat users, keyed by user and symbol
+pub struct LiveQuotes {
+    streamer: QuoteStreamer,
+    notifiers: RwLock<HashMap<BotPlatform, Arc<dyn Notifier>>>,
+    feeds: Mutex<HashMap<(String, String), JoinHandle<()>>>,
+    min_interval: Duration,
+    anomalies: Option<AnomalyMonitor>,
+    delivery: Option<Arc<DeliveryStore>>,
+}
+
+impl LiveQuotes {
+    /// Feeds served from `streamer`
+    pub fn new(streamer: QuoteStreamer) -> Self {
+        Self {
+            streamer,
+            notifiers: RwLock::new(HashMap::new()),
+            feeds: Mutex::new(HashMap::new()),
+            min_interval: DEFAULT_MIN_INTERVAL,
+            anomalies: None,
+            delivery: None,
+        }
+    }
+
+    /// Send each feed's updates at most once per `interval` (default 15s)
+    pub fn with_min_interval(mut self, interval: Duration) -> Self {
+        self.min_interval = interval;
+        self
+    }
+
+    /// Push halts, limit moves and gaps of watchlist symbols found by `monitor`
+    pub fn with_anomaly_alerts(mut self, monitor: AnomalyMonitor) -> Self {
+        self.anomalies = Some(monitor);
+        self
+    }
+
+    /// Hold anomaly alerts back during the quiet hours kept in `delivery`
+    ///
+    /// Live price updates the user asked for are always sent.
+    pub fn with_delivery(mut self, delivery: Arc<DeliveryStore>) -> Self {
+        self.delivery = Some(delivery);
+        self
+    }
+
+    /// Whether watchlist symbols are monitored for anomalies
+    pub fn anomaly_alerts_enabled(&self) -> bool {
+        self.anomalies.is_some()
+    }
+
+    /// Deliver updates for feeds started on `platform` through `notifier`
+    pub fn register_notifier(&self, platform: BotPlatform, notifier: Arc<dyn Notifier>) {
+        self.notifiers
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(platform, notifier);
+    }
+
+    /// Start pushing `symbol` to `user_id`; false if already live
+    pub fn start(&self, user_id: &str, platform: BotPlatform, symbol: &str) -> Result<bool> {
+        let notifier = self.notifier(platform)?;
+
+        let mut feeds = self.feeds.lock().unwrap_or_else(PoisonError::into_inner);
+        feeds.retain(|_, feed| !feed.is_finished());
+        let key = (user_id.to_string(), symbol.to_uppercase());
+        if feeds.contains_key(&key) {
+            return Ok(false);
+        }
+        if feeds.keys().filter(|(user, _)| user == user_id).count() >= MAX_FEEDS_PER_USER {
+            return Err(StockError::CommandError(format!(
+                "At most {MAX_FEEDS_PER_USER} live symbols at once; /live stop one first"
+            )));
+        }
+
+        let stream = self.streamer.subscribe(symbol);
+        let feed = tokio::spawn(push_updates(
+            stream,
+            notifier,
+            user_id.to_string(),
+            self.min_interval,
+        ));
+        feeds.insert(key, feed);
+        Ok(true)
+    }
+
+    /// Stop pushing `symbol` to `user_id`; false if it was not live
+    pub fn stop(&self, user_id: &str, symbol: &str) -> bool {
+        let key = (user_id.to_string(), symbol.to_uppercase());
+        let feed = self
+            .feeds
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&key);
+        feed.map(|feed| feed.abort()).is_some()
+    }
+
+    /// Stop all of `user_id`'s feeds; returns how many were running
+    pub fn stop_all(&self, user_id: &str) -> usize {
+        let mut feeds = self.feeds.lock().unwrap_or_else(PoisonError::into_inner);
+        let before = feeds.len();
+        feeds.retain(|(user, _), feed| {
+            if user == user_id {
+                feed.abort();
+                false
+            } else {
+                true
+            }
+        });
+        before - feeds.len()
+    }
+
+    /// Start pushing anomalies of watchlist `symbol` to `user_id`; false if
+    /// already monitored or anomaly alerts are off
+    pub fn watch_anomalies(
+        &self,
+        user_id: &str,
+        platform: BotPlatform,
+        symbol: &str,
+    ) -> Result<bool> {
+        let Some(anomalies) = &self.anomalies else {
+            return Ok(false);
+        };
+        let mut notifier = self.notifier(platform)?;
+        if let Some(delivery) = &self.delivery {
+            notifier = Arc::new(QuietNotifier::new(notifier, platform, Arc::clone(delivery)));
+        }
+        Ok(anomalies.watch(&self.streamer, user_id, notifier, symbol))
+    }
+
+    /// Stop pushing anomalies of `symbol` to `user_id`; false if not monitored
+    pub fn unwatch_anomalies(&self, user_id: &str, symbol: &str) -> bool {
+        self.anomalies
+            .as_ref()
+            .is_some_and(|anomalies| anomalies.unwatch(user_id, symbol))
+    }
+
+    fn notifier(&self, platform: BotPlatform) -> Result<Arc<dyn Notifier>> {
+        self.notifiers
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&platform)
+            .cloned()
+            .ok_or_else(|| {
+                StockError::ConfigError(format!("Live quotes cannot be sent to {platform:?}"))
+            })
+    }
+
+    /// Symbols pushed to `user_id`, sorted
+    pub fn symbols_for(&self, user_id: &str) -> Vec<String> {
+        let mut symbols: Vec<String> = self
+            .feeds
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .filter(|((user, _), feed)| user == user_id && !feed.is_finished())
+            .map(|((_, symbol), _)| symbol.clone())
+            .collect();
+        symbols.sort();
+        symbols
+    }
+}
+
+impl Drop for LiveQuotes {
+    fn drop(&mut self) {
+        for feed in self
+            .feeds
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .values()
+        {
+            feed.abort();
+        }
+    }
+}
+
+/// Push throttled updates of `stream` to `user_id` until it ends
+async fn push_updates(
+    mut stream: QuoteStream,
+    notifier: Arc<dyn Notifier>,
+    user_id: String,
+    min_interval: Duration,
+) {
+    let mut first_price = None;
+    let mut last_sent: Option<Instant> = None;
+    while let Some(tick) = stream.recv().await {
+        let first = *first_price.get_or_insert(tick.price);
+        if last_sent.is_some_and(|sent| sent.elapsed() < min_interval) {
+            continue;
+        }
+        last_sent = Some(Instant::now());
+        if let Err(e) = notifier
+            .notify(&user_id, &update_message(&tick, first))
+            .await
+        {
+            tracing::warn!("Failed to push live {} to {}: {}", tick.symbol, user_id, e);
+        }
+    }
+}
+
+/// "📈 AAPL 190.00 (+0.50% since /live) @ 14:30:05 UTC"
+fn update_message(tick: &Tick, first_price: f64) -> String {
+    let change_pct = if first_price > 0.0 {
+        (tick.price / first_price - 1.0) * 100.0
+    } else {
+        0.0
+    };
+    let icon = if change_pct < 0.0 { "📉" } else { "📈" };
+    format!(
+        "{icon} {} {:.2} ({change_pct:+.2}% since /live) @ {}",
+        tick.symbol,
+        tick.price,
+        tick.timestamp.format("%H:%M:%S UTC")
+    )
+}
+
+/// Reply to a live quotes command (`/live`, `/live stop`) from `user_id` on
+/// `platform`, for bots with live quotes
+pub fn command_reply(
+    live: Option<&LiveQuotes>,
+    user_id: &str,
+    platform: BotPlatform,
+    command: &Command,
+) -> Result<String> {
+    let Some(live) = live else {
+        return Ok(format!(
+            "📴 Live quotes are not enabled on this bot (set {FINNHUB_API_KEY_ENV} or \
+             {POLYGON_API_KEY_ENV})"
+        ));
+    };
+    match command {
+        Command::Live { symbol } => {
+            if live.start(user_id, platform, symbol)? {
+                Ok(format!(
+                    "📡 Streaming {symbol} from {}. Use /live stop {symbol} to stop.",
+                    live.streamer.provider()
+                ))
+            } else {
+                Ok(format!("📡 {symbol} is already live"))
+            }
+        }
+        Command::LiveStop {
+            symbol: Some(symbol),
+        } => {
+            if live.stop(user_id, symbol) {
+                Ok(format!("✅ Stopped live {symbol}"))
+            } else {
+                Ok(format!("❌ {symbol} is not live"))
+            }
+        }
+        Command::LiveStop { symbol: None } => match live.stop_all(user_id) {
+            0 => Ok("📴 Nothing is live".to_string()),
+            stopped => Ok(format!("✅ Stopped {stopped} live symbols")),
+        },
+        _ => Err(StockError::CommandError(format!(
+            "Not a live quotes command: /{}",
+            command.name()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::stream::{StreamConfig, StreamProvider};
+    use async_trait::async_trait;
+    use chrono::DateTime;
+
+    struct Recorder(Mutex<Vec<(String, String)>>);
+
+    #[async_trait]
+    impl Notifier for Recorder {
+        async fn notify(&self, user_id: &str, message: &str) -> Result<()> {
+            self.0
+                .lock()
+                .unwrap()
+                .push((user_id.to_string(), message.to_string()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_update_message() {
+        let tick = Tick {
+            symbol: "AAPL".to_string(),
+            price: 190.0,
+            volume: None,
+            timestamp: DateTime::from_timestamp_millis(1_710_426_605_123).unwrap(),
+        };
+        assert_eq!(
+            update_message(&tick, 189.05),
+            "📈 AAPL 190.00 (+0.50% since /live) @ 14:30:05 UTC"
+        );
+        assert!(update_message(&tick, 200.0).starts_with("📉"));
+    }
+
+    #[tokio::test]
+    async fn test_live_commands() {
+        // Nothing listens here; feeds start and stop without a connection
+        let streamer = QuoteStreamer::spawn(
+            StreamConfig::new(StreamProvider::Finnhub, "key").with_url("ws://127.0.0.1:9"),
+        );
+        let live = LiveQuotes::new(streamer);
+        let reply = |command: &str| {
+            command_reply(
+                Some(&live),
+                "42",
+                BotPlatform::Telegram,
+                &Command::parse(command).unwrap(),
+            )
+        };
+
+        // No notifier for the platform yet
+        assert!(reply("/live AAPL").is_err());
+        live.register_notifier(
+            BotPlatform::Telegram,
+            Arc::new(Recorder(Mutex::new(Vec::new()))),
+        );
+
+        assert!(
+            reply("/live aapl")
+                .unwrap()
+                .contains("Streaming AAPL from Finnhub")
+        );
+        assert!(reply("/live AAPL").unwrap().contains("already live"));
+        reply("/live NVDA").unwrap();
+        assert_eq!(live.symbols_for("42"), vec!["AAPL", "NVDA"]);
+        assert!(live.symbols_for("7").is_empty());
+
+        assert!(
+            reply("/live stop nvda")
+                .unwrap()
+                .contains("Stopped live NVDA")
+        );
+        assert!(reply("/live stop NVDA").unwrap().contains("not live"));
+        for symbol in ["MSFT", "AMZN", "META", "GOOG"] {
+            reply(&format!("/live {symbol}")).unwrap();
+        }
+        assert!(reply("/live TSLA").is_err());
+        assert_eq!(reply("/live stop").unwrap(), "✅ Stopped 5 live symbols");
+
+        let disabled = command_reply(
+            None,
+            "42",
+            BotPlatform::Telegram,
+            &Command::parse("/live AAPL").unwrap(),
+        );
+        assert!(disabled.unwrap().contains(POLYGON_API_KEY_ENV));
+    }
+}
@@ -0,0 +1,702 @@
+//! Custom FRED series watches and macro alerts
+//!
+//! Users register FRED series to monitor with a threshold, e.g.
+//! `/macro watch UNRATE above 4.5`. [`MacroAlertPoller`] checks the watched
+//! series on FRED release days and reports new datapoints that meet their
+//! watch's condition; [`MacroAlertJob`] adds a one-line take from the macro
+//! analyzer on what the print means for markets. Watches are kept per user
+//! and platform in a [`MacroWatchlist`], and alerts are pushed to the user
+//! who set the watch through the [`AlertEngine`](crate::alerts::AlertEngine)'s
+//! notifiers.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::macro_alerts::{MacroAlertJob, MacroWatch, MacroWatchlist, WatchCondition};
+//! use agent_stock::scheduler::{DailySchedule, spawn_daily};
+//!
+//! let watches = Arc::new(MacroWatchlist::open("macro_watches.json")?);
+//! watches.add(MacroWatch::new(
+//!     "C0123",
+//!     BotPlatform::Slack,
+//!     "UNRATE",
+//!     WatchCondition::Above,
+//!     4.5,
+//! ))?;
+//!
+//! let job = Arc::new(MacroAlertJob::new(agent, config, watches));
+//! spawn_daily(DailySchedule::parse("22:00")?.weekdays_only(true), move || {
+//!     let job = Arc::clone(&job);
+//!     let alerts = Arc::clone(&alert_engine);
+//!     async move {
+//!         for alert in job.run().await.unwrap_or_default() {
+//!             let watch = &alert.watch;
+//!             alerts.push_message(&watch.user_id, watch.platform, &alert.to_string()).await;
+//!         }
+//!     }
+//! });
+//! ```
+
+use agent_core::Context;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, PoisonError, RwLock};
+
+use crate::agents::StockAnalysisAgent;
+use crate::api::FredClient;
+use crate::bot::{CLI_USER, Command};
+use crate::config::StockConfig;
+use crate::error::{Result, StockError};
+use crate::interface::BotPlatform;
+use crate::market_wrap::released_on;
+use crate::storage::{self, StoreCipher};
+
+/// When a watched series triggers an alert
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WatchCondition {
+    /// The new value is above the threshold
+    Above,
+    /// The new value is below the threshold
+    Below,
+}
+
+impl WatchCondition {
+    /// Whether `value` meets the condition against `threshold`
+    pub fn is_met(self, value: f64, threshold: f64) -> bool {
+        match self {
+            WatchCondition::Above => value > threshold,
+            WatchCondition::Below => value < threshold,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WatchCondition::Above => "above",
+            WatchCondition::Below => "below",
+        }
+    }
+}
+
+impl fmt::Display for WatchCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for WatchCondition {
+    type Err = StockError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "above" | ">" | "over" | "高于" => Ok(WatchCondition::Above),
+            "below" | "<" | "under" | "低于" => Ok(WatchCondition::Below),
+            _ => Err(StockError::CommandError(format!(
+                "Unknown condition: {s}. Use above or below"
+            ))),
+        }
+    }
+}
+
+/// A FRED series a user monitors
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MacroWatch {
+    /// Chat or conversation the alert is pushed to; watches saved before
+    /// chat platforms could set them belong to the terminal user
+    #[serde(default = "default_user")]
+    pub user_id: String,
+    #[serde(default = "default_platform")]
+    pub platform: BotPlatform,
+    /// FRED series ID, e.g. "UNRATE"
+    pub series_id: String,
+    pub condition: WatchCondition,
+    pub threshold: f64,
+    pub created_at: DateTime<Utc>,
+    /// Observation date (YYYY-MM-DD) of the last datapoint checked, so a
+    /// release is only reported once
+    #[serde(default)]
+    pub last_observation: Option<String>,
+}
+
+fn default_user() -> String {
+    CLI_USER.to_string()
+}
+
+fn default_platform() -> BotPlatform {
+    BotPlatform::CLI
+}
+
+impl MacroWatch {
+    /// Watch `series_id` for values `condition` `threshold` on behalf of
+    /// `user_id` on `platform`
+    pub fn new(
+        user_id: impl Into<String>,
+        platform: BotPlatform,
+        series_id: impl Into<String>,
+        condition: WatchCondition,
+        threshold: f64,
+    ) -> Self {
+        Self {
+            user_id: user_id.into(),
+            platform,
+            series_id: series_id.into().to_uppercase(),
+            condition,
+            threshold,
+            created_at: Utc::now(),
+            last_observation: None,
+        }
+    }
+
+    /// Whether `value` triggers an alert
+    pub fn is_triggered_by(&self, value: f64) -> bool {
+        self.condition.is_met(value, self.threshold)
+    }
+
+    /// Whether this is `user_id`'s watch on `platform`
+    fn is_owned_by(&self, user_id: &str, platform: BotPlatform) -> bool {
+        self.user_id == user_id && self.platform == platform
+    }
+}
+
+impl fmt::Display for MacroWatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            self.series_id, self.condition, self.threshold
+        )
+    }
+}
+
+/// A new datapoint that met its watch's condition
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MacroAlert {
+    pub watch: MacroWatch,
+    pub title: String,
+    /// Period the value refers to (YYYY-MM-DD)
+    pub observation_date: String,
+    pub value: f64,
+    /// Value for the previous period
+    pub prior: Option<f64>,
+    /// One-line take on the market implications, when the LLM provided one
+    pub commentary: Option<String>,
+}
+
+impl fmt::Display for MacroAlert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "🔔 {} ({}) is {} {}",
+            self.title, self.watch.series_id, self.watch.condition, self.watch.threshold
+        )?;
+        write!(f, "New: {} ({})", self.value, self.observation_date)?;
+        if let Some(prior) = self.prior {
+            write!(f, " | Prior: {prior}")?;
+        }
+        if let Some(commentary) = &self.commentary {
+            write!(f, "\n{}", commentary.trim())?;
+        }
+        Ok(())
+    }
+}
+
+/// Persisted FRED series watches, one per series for each user
+///
+/// Kept in memory and, when opened with a path, saved as JSON after every
+/// change (encrypted when opened with a cipher).
+pub struct MacroWatchlist {
+    watches: RwLock<Vec<MacroWatch>>,
+    path: Option<PathBuf>,
+    cipher: Option<StoreCipher>,
+}
+
+impl Default for MacroWatchlist {
+    fn default() -> Self {
+        Self::in_memory()
+    }
+}
+
+impl MacroWatchlist {
+    /// Create a watchlist that is not persisted
+    pub fn in_memory() -> Self {
+        Self {
+            watches: RwLock::new(Vec::new()),
+            path: None,
+            cipher: None,
+        }
+    }
+
+    /// Open a watchlist persisted at `path`, loading existing watches
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        Self::open_with_cipher(path, None)
+    }
+
+    /// Open a watchlist persisted at `path`, encrypted with `cipher` if given
+    pub fn open_with_cipher(path: impl Into<PathBuf>, cipher: Option<StoreCipher>) -> Result<Self> {
+        let path = path.into();
+        let watches = match storage::read_store(&path, cipher.as_ref())? {
+            Some(json) => serde_json::from_str(&json)?,
+            None => Vec::new(),
+        };
+
+        Ok(Self {
+            watches: RwLock::new(watches),
+            path: Some(path),
+            cipher,
+        })
+    }
+
+    /// File the watchlist is persisted to, if any
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Add a watch, replacing the user's earlier watch on the same series
+    ///
+    /// Returns whether an earlier watch was replaced.
+    pub fn add(&self, watch: MacroWatch) -> Result<bool> {
+        let replaced = {
+            let mut watches = self.write();
+            let before = watches.len();
+            watches.retain(|w| {
+                !(w.is_owned_by(&watch.user_id, watch.platform) && w.series_id == watch.series_id)
+            });
+            let replaced = watches.len() < before;
+            watches.push(watch);
+            replaced
+        };
+        self.save()?;
+        Ok(replaced)
+    }
+
+    /// Remove the watch of `user_id` on `platform` on `series_id`; returns
+    /// whether there was one
+    pub fn remove(&self, user_id: &str, platform: BotPlatform, series_id: &str) -> Result<bool> {
+        let removed = {
+            let mut watches = self.write();
+            let before = watches.len();
+            watches.retain(|w| {
+                !(w.is_owned_by(user_id, platform) && w.series_id.eq_ignore_ascii_case(series_id))
+            });
+            watches.len() < before
+        };
+        if removed {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    /// Watches of `user_id` on `platform`, in the order they were added
+    pub fn for_user(&self, user_id: &str, platform: BotPlatform) -> Vec<MacroWatch> {
+        self.read()
+            .iter()
+            .filter(|w| w.is_owned_by(user_id, platform))
+            .cloned()
+            .collect()
+    }
+
+    /// All watches, in the order they were added
+    pub fn watches(&self) -> Vec<MacroWatch> {
+        self.read().clone()
+    }
+
+    /// Whether no series are watched
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    /// Record that the datapoint for `observation_date` of `series_id` was
+    /// checked, for every watch on the series
+    pub fn mark_checked(&self, series_id: &str, observation_date: &str) -> Result<()> {
+        {
+            let mut watches = self.write();
+            for watch in watches.iter_mut().filter(|w| w.series_id == series_id) {
+                watch.last_observation = Some(observation_date.to_string());
+            }
+        }
+        self.save()
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, Vec<MacroWatch>> {
+        self.watches.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, Vec<MacroWatch>> {
+        self.watches.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let json = serde_json::to_string_pretty(&*self.read())?;
+        storage::write_store(path, &json, self.cipher.as_ref())
+    }
+}
+
+/// Checks watched series for new datapoints that meet their condition
+///
+/// Polling is best effort: series FRED fails to return are skipped until
+/// the next run.
+pub struct MacroAlertPoller {
+    fred_client: Option<FredClient>,
+    watches: Arc<MacroWatchlist>,
+}
+
+impl MacroAlertPoller {
+    /// Create a poller for `watches`; without a FRED client nothing is polled
+    pub fn new(fred_client: Option<FredClient>, watches: Arc<MacroWatchlist>) -> Self {
+        Self {
+            fred_client,
+            watches,
+        }
+    }
+
+    /// Alerts for series FRED updated on `date`, without commentary
+    ///
+    /// Each series is fetched once however many users watch it. Every new
+    /// datapoint is marked as checked, whether or not it triggered an alert,
+    /// so running twice on the same day does not repeat alerts.
+    pub async fn poll(&self, date: NaiveDate) -> Vec<MacroAlert> {
+        let Some(fred) = &self.fred_client else {
+            return Vec::new();
+        };
+
+        let mut by_series: Vec<(String, Vec<MacroWatch>)> = Vec::new();
+        for watch in self.watches.watches() {
+            match by_series.iter_mut().find(|(id, _)| *id == watch.series_id) {
+                Some((_, watches)) => watches.push(watch),
+                None => by_series.push((watch.series_id.clone(), vec![watch])),
+            }
+        }
+
+        let mut alerts = Vec::new();
+        for (series_id, watches) in by_series {
+            let Ok(info) = fred.get_series_info(&series_id).await else {
+                continue;
+            };
+            if !released_on(&info.last_updated, date) {
+                continue;
+            }
+            let Ok(observations) = fred.get_observations(&series_id, None, None, Some(2)).await
+            else {
+                continue;
+            };
+            let mut values = observations
+                .iter()
+                .map(|obs| (obs.date.clone(), obs.value.parse::<f64>().ok()));
+            let Some((observation_date, Some(value))) = values.next() else {
+                continue;
+            };
+            let prior = values.next().and_then(|(_, value)| value);
+            let new: Vec<MacroWatch> = watches
+                .into_iter()
+                .filter(|w| w.last_observation.as_deref() != Some(observation_date.as_str()))
+                .collect();
+            if new.is_empty() {
+                continue;
+            }
+            if let Err(e) = self.watches.mark_checked(&series_id, &observation_date) {
+                tracing::warn!("Failed to save macro watch {}: {}", series_id, e);
+            }
+            alerts.extend(
+                new.into_iter()
+                    .filter(|watch| watch.is_triggered_by(value))
+                    .map(|watch| MacroAlert {
+                        watch,
+                        title: info.title.clone(),
+                        observation_date: observation_date.clone(),
+                        value,
+                        prior,
+                        commentary: None,
+                    }),
+            );
+        }
+        alerts
+    }
+}
+
+/// Polls the watched series and adds the macro analyzer's take to each alert
+pub struct MacroAlertJob {
+    agent: Arc<StockAnalysisAgent>,
+    poller: MacroAlertPoller,
+    watches: Arc<MacroWatchlist>,
+}
+
+impl MacroAlertJob {
+    /// Create a job checking `watches`; alerts need a FRED API key
+    pub fn new(
+        agent: Arc<StockAnalysisAgent>,
+        config: Arc<StockConfig>,
+        watches: Arc<MacroWatchlist>,
+    ) -> Self {
+        let fred_client = config
+            .fred_api_key
+            .as_ref()
+            .map(|key| FredClient::new(key.clone(), None));
+
+        Self {
+            agent,
+            poller: MacroAlertPoller::new(fred_client, Arc::clone(&watches)),
+            watches,
+        }
+    }
+
+    /// The watched series
+    pub fn watches(&self) -> &Arc<MacroWatchlist> {
+        &self.watches
+    }
+
+    /// Alerts for today's releases, each with a one-line market take
+    ///
+    /// An alert is still returned when the commentary fails.
+    pub async fn run(&self) -> Result<Vec<MacroAlert>> {
+        let mut alerts = self.poller.poll(Utc::now().date_naive()).await;
+        for alert in &mut alerts {
+            match self.agent.macro_alert(alert, &mut Context::new()).await {
+                Ok(commentary) => alert.commentary = Some(commentary),
+                Err(e) => {
+                    tracing::warn!("No commentary for {} alert: {}", alert.watch.series_id, e);
+                }
+            }
+        }
+        Ok(alerts)
+    }
+}
+
+/// Reply to a macro watch command (`/macro watch`, `/macro unwatch`,
+/// `/macro watches`) from `user_id` on `platform`, for bots with a `watches`
+/// list
+pub fn command_reply(
+    watches: Option<&MacroWatchlist>,
+    user_id: &str,
+    platform: BotPlatform,
+    command: &Command,
+) -> Result<String> {
+    let Some(watches) = watches else {
+        return Ok("🔕 Macro alerts are not enabled on this bot".to_string());
+    };
+    match command {
+        Command::MacroWatch {
+            series_id,
+            condition,
+            threshold,
+        } => {
+            let watch = MacroWatch::new(user_id, platform, series_id, *condition, *threshold);
+            let reply = format!("alert when {watch} on a FRED release");
+            if watches.add(watch)? {
+                Ok(format!("🔔 Updated macro watch: {reply}"))
+            } else {
+                Ok(format!("🔔 Added macro watch: {reply}"))
+            }
+        }
+        Command::MacroUnwatch { series_id } => {
+            if watches.remove(user_id, platform, series_id)? {
+                Ok(format!("✅ Stopped watching {}", series_id.to_uppercase()))
+            } else {
+                Ok(format!("❌ {} is not watched", series_id.to_uppercase()))
+            }
+        }
+        Command::MacroWatches => {
+            let watches = watches.for_user(user_id, platform);
+            if watches.is_empty() {
+                return Ok(
+                    "🔕 No macro watches. Use /macro watch <series> above|below <value> to add one."
+                        .to_string(),
+                );
+            }
+            let lines: Vec<String> = watches.iter().map(ToString::to_string).collect();
+            Ok(format!("🔔 Macro watches:\n{}", lines.join("\n")))
+        }
+        _ => Err(StockError::CommandError(format!(
+            "Not a macro watch command: /{}",
+            command.name()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::testing::MockApi;
+
+    #[test]
+    fn test_condition() {
+        assert_eq!(
+            "above".parse::<WatchCondition>().unwrap(),
+            WatchCondition::Above
+        );
+        assert_eq!(
+            "低于".parse::<WatchCondition>().unwrap(),
+            WatchCondition::Below
+        );
+        assert!("near".parse::<WatchCondition>().is_err());
+
+        let watch = MacroWatch::new(
+            "u1",
+            BotPlatform::Slack,
+            "unrate",
+            WatchCondition::Above,
+            4.5,
+        );
+        assert_eq!(watch.series_id, "UNRATE");
+        assert!(watch.is_triggered_by(4.6));
+        assert!(!watch.is_triggered_by(4.5));
+        assert_eq!(watch.to_string(), "UNRATE above 4.5");
+    }
+
+    #[test]
+    fn test_watchlist_persistence() {
+        let path =
+            std::env::temp_dir().join(format!("macro-watches-{}.json", uuid::Uuid::new_v4()));
+
+        let watch = |user: &str, series: &str, condition, threshold| {
+            MacroWatch::new(user, BotPlatform::Slack, series, condition, threshold)
+        };
+
+        let watches = MacroWatchlist::open(&path).unwrap();
+        assert!(
+            !watches
+                .add(watch("u1", "UNRATE", WatchCondition::Above, 4.5))
+                .unwrap()
+        );
+        assert!(
+            !watches
+                .add(watch("u1", "CPIAUCSL", WatchCondition::Above, 310.0))
+                .unwrap()
+        );
+        assert!(
+            watches
+                .add(watch("u1", "UNRATE", WatchCondition::Below, 3.5))
+                .unwrap()
+        );
+        // Another user's watch on the same series is kept apart
+        assert!(
+            !watches
+                .add(watch("u2", "UNRATE", WatchCondition::Above, 5.0))
+                .unwrap()
+        );
+        watches.mark_checked("CPIAUCSL", "2024-02-01").unwrap();
+        assert!(
+            !watches
+                .remove("u1", BotPlatform::Feishu, "cpiaucsl")
+                .unwrap()
+        );
+        assert!(
+            watches
+                .remove("u1", BotPlatform::Slack, "cpiaucsl")
+                .unwrap()
+        );
+        assert!(
+            !watches
+                .remove("u1", BotPlatform::Slack, "CPIAUCSL")
+                .unwrap()
+        );
+
+        let reopened = MacroWatchlist::open(&path).unwrap();
+        let saved = reopened.for_user("u1", BotPlatform::Slack);
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].condition, WatchCondition::Below);
+        assert_eq!(reopened.for_user("u2", BotPlatform::Slack).len(), 1);
+        assert!(reopened.for_user("u1", BotPlatform::Feishu).is_empty());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_poll_release_day() {
+        let api = MockApi::recorded().await;
+        let watches = Arc::new(MacroWatchlist::in_memory());
+        for (user, threshold) in [("u1", 5.0), ("u2", 5.5)] {
+            watches
+                .add(MacroWatch::new(
+                    user,
+                    BotPlatform::Telegram,
+                    "FEDFUNDS",
+                    WatchCondition::Above,
+                    threshold,
+                ))
+                .unwrap();
+        }
+        let poller = MacroAlertPoller::new(Some(api.fred(None)), Arc::clone(&watches));
+
+        // FEDFUNDS was last updated on 2024-03-01
+        let release_day = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        assert!(
+            poller
+                .poll(NaiveDate::from_ymd_opt(2024, 3, 4).unwrap())
+                .await
+                .is_empty()
+        );
+
+        // Only the watch whose threshold the print crossed fires
+        let alerts = poller.poll(release_day).await;
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].watch.user_id, "u1");
+        assert_eq!(alerts[0].title, "Federal Funds Effective Rate");
+        assert_eq!(alerts[0].observation_date, "2024-02-01");
+        assert_eq!(alerts[0].prior, Some(5.33));
+        assert!(
+            alerts[0]
+                .to_string()
+                .contains("New: 5.33 (2024-02-01) | Prior: 5.33")
+        );
+
+        // The datapoint was checked and is not reported again
+        assert!(
+            watches
+                .watches()
+                .iter()
+                .all(|w| w.last_observation.as_deref() == Some("2024-02-01"))
+        );
+        assert!(poller.poll(release_day).await.is_empty());
+    }
+
+    #[test]
+    fn test_legacy_watch_belongs_to_terminal() {
+        let watch: MacroWatch = serde_json::from_str(
+            r#"{"series_id":"UNRATE","condition":"above","threshold":4.5,
+                "created_at":"2024-03-01T00:00:00Z"}"#,
+        )
+        .unwrap();
+        assert_eq!(watch.user_id, CLI_USER);
+        assert_eq!(watch.platform, BotPlatform::CLI);
+    }
+
+    #[test]
+    fn test_command_reply() {
+        let watches = MacroWatchlist::in_memory();
+        let watch = Command::parse("/macro watch unrate above 4.5").unwrap();
+        let reply = command_reply(Some(&watches), "u1", BotPlatform::WeCom, &watch).unwrap();
+        assert!(reply.contains("Added macro watch"));
+        assert!(reply.contains("UNRATE above 4.5"));
+
+        let list = |user| {
+            command_reply(
+                Some(&watches),
+                user,
+                BotPlatform::WeCom,
+                &Command::MacroWatches,
+            )
+            .unwrap()
+        };
+        assert!(list("u1").contains("UNRATE above 4.5"));
+        assert!(list("u2").contains("No macro watches"));
+
+        let unwatch = Command::parse("/macro unwatch unrate").unwrap();
+        let reply = command_reply(Some(&watches), "u2", BotPlatform::WeCom, &unwatch).unwrap();
+        assert!(reply.contains("not watched"));
+        let reply = command_reply(Some(&watches), "u1", BotPlatform::WeCom, &unwatch).unwrap();
+        assert!(reply.contains("Stopped watching UNRATE"));
+
+        assert!(
+            command_reply(None, "u1", BotPlatform::WeCom, &watch)
+                .unwrap()
+                .contains("not enabled")
+        );
+        assert!(command_reply(Some(&watches), "u1", BotPlatform::WeCom, &Command::Help).is_err());
+    }
+}
@@ -0,0 +1,548 @@
+//! Daily market wrap
+//!
+//! After the close, [`MarketWrapCollector`] gathers the day's index moves,
+//! sector ETF breadth, macro datapoints FRED released that day, and the top
+//! news for the watchlist. The macro analyzer turns them into a narrative with
+//! a fixed structure (what happened, why, what to watch tomorrow), and every
+//! wrap is kept in a [`MarketWrapArchive`] for later reference.
+//!
+//! [`MarketWrapJob`] runs the whole pipeline; the scheduler runs it daily.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::market_wrap::{MarketWrapArchive, MarketWrapJob};
+//! use agent_stock::scheduler::{DailySchedule, spawn_daily};
+//!
+//! let archive = Arc::new(MarketWrapArchive::open("market_wraps.json")?);
+//! let job = Arc::new(MarketWrapJob::new(agent, config, archive));
+//!
+//! spawn_daily(DailySchedule::parse("21:15")?.weekdays_only(true), move || {
+//!     let job = Arc::clone(&job);
+//!     async move {
+//!         if let Ok(wrap) = job.run(&["AAPL".to_string()]).await {
+//!             println!("{wrap}");
+//!         }
+//!     }
+//! });
+//! ```
+
+use agent_core::Context;
+use agent_tools::Tool;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, PoisonError, RwLock};
+
+use crate::agents::StockAnalysisAgent;
+use crate::api::yahoo::Quote;
+use crate::api::{FredClient, YahooFinanceClient};
+use crate::cache::{CacheCategory, StockCache};
+use crate::config::StockConfig;
+use crate::error::Result;
+use crate::storage::{self, StoreCipher};
+use crate::tools::NewsTool;
+use crate::tools::sector::Sector;
+
+/// Indices reported in the wrap
+pub const WRAP_INDICES: &[(&str, &str)] = &[
+    ("^GSPC", "S&P 500"),
+    ("^IXIC", "Nasdaq Composite"),
+    ("^DJI", "Dow Jones Industrial Average"),
+    ("^RUT", "Russell 2000"),
+    ("^VIX", "VIX"),
+];
+
+/// FRED series checked for same-day releases
+///
+/// Daily series such as Treasury yields update every day and are left out.
+pub const RELEASE_SERIES: &[&str] = &[
+    "CPIAUCSL", "PCEPI", "UNRATE", "PAYEMS", "ICSA", "GDP", "RSAFS", "INDPRO", "UMCSENT", "HOUST",
+];
+
+/// Headlines kept per watchlist symbol
+const HEADLINES_PER_SYMBOL: usize = 2;
+
+/// Daily move of an index or sector ETF
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarketMove {
+    pub symbol: String,
+    pub name: String,
+    pub close: f64,
+    /// Change from the previous close, in percent
+    pub change_pct: f64,
+}
+
+/// A macro datapoint released on the wrap date
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MacroRelease {
+    pub series_id: String,
+    pub title: String,
+    /// Period the value refers to (YYYY-MM-DD)
+    pub observation_date: String,
+    pub value: f64,
+    /// Value for the previous period
+    pub prior: Option<f64>,
+}
+
+/// A news headline for a watchlist symbol
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Headline {
+    pub symbol: String,
+    pub title: String,
+    pub source: Option<String>,
+    pub sentiment: Option<String>,
+}
+
+/// Everything a market wrap is written from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarketWrapData {
+    pub date: NaiveDate,
+    pub indices: Vec<MarketMove>,
+    pub sectors: Vec<MarketMove>,
+    pub macro_releases: Vec<MacroRelease>,
+    pub headlines: Vec<Headline>,
+}
+
+impl MarketWrapData {
+    /// Number of sectors up and down on the day
+    pub fn sector_breadth(&self) -> (usize, usize) {
+        let up = self.sectors.iter().filter(|s| s.change_pct > 0.0).count();
+        let down = self.sectors.iter().filter(|s| s.change_pct < 0.0).count();
+        (up, down)
+    }
+
+    /// Data as passed to the narrative prompt
+    pub fn to_prompt_json(&self) -> Value {
+        let (up, down) = self.sector_breadth();
+        json!({
+            "date": self.date.to_string(),
+            "indices": self.indices,
+            "sectors": self.sectors,
+            "sector_breadth": { "up": up, "down": down },
+            "macro_releases": self.macro_releases,
+            "watchlist_news": self.headlines,
+        })
+    }
+}
+
+/// A generated market wrap
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarketWrap {
+    pub date: NaiveDate,
+    pub generated_at: DateTime<Utc>,
+    pub data: MarketWrapData,
+    /// Narrative: what happened, why, and what to watch tomorrow
+    pub narrative: String,
+}
+
+impl fmt::Display for MarketWrap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "📰 Market Wrap — {}", self.date)?;
+        writeln!(f)?;
+        write!(f, "{}", self.narrative.trim_end())
+    }
+}
+
+/// Move between the last two bars, or `None` with fewer than two
+pub fn daily_move(symbol: &str, name: &str, quotes: &[Quote]) -> Option<MarketMove> {
+    let [.., previous, last] = quotes else {
+        return None;
+    };
+    (previous.close > 0.0).then(|| MarketMove {
+        symbol: symbol.to_string(),
+        name: name.to_string(),
+        close: last.close,
+        change_pct: (last.close - previous.close) / previous.close * 100.0,
+    })
+}
+
+/// Whether a FRED `last_updated` timestamp ("2024-03-12 07:31:02-05") falls on `date`
+pub fn released_on(last_updated: &str, date: NaiveDate) -> bool {
+    last_updated
+        .get(..10)
+        .and_then(|day| NaiveDate::parse_from_str(day, "%Y-%m-%d").ok())
+        .is_some_and(|day| day == date)
+}
+
+/// Headlines from a news tool response
+fn headlines_from(symbol: &str, news: &Value) -> Vec<Headline> {
+    news["articles"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|article| {
+            Some(Headline {
+                symbol: symbol.to_string(),
+                title: article["title"].as_str()?.to_string(),
+                source: article["source"].as_str().map(ToString::to_string),
+                sentiment: article["sentiment"].as_str().map(ToString::to_string),
+            })
+        })
+        .take(HEADLINES_PER_SYMBOL)
+        .collect()
+}
+
+/// Gathers the data a market wrap is written from
+///
+/// Collection is best effort: sources that fail are left out of the wrap.
+pub struct MarketWrapCollector {
+    yahoo_client: YahooFinanceClient,
+    fred_client: Option<FredClient>,
+    news_tool: NewsTool,
+}
+
+impl MarketWrapCollector {
+    /// Create a collector; macro releases need a FRED API key
+    pub fn new(config: Arc<StockConfig>, news_cache: StockCache) -> Self {
+        let fred_client = config
+            .fred_api_key
+            .as_ref()
+            .map(|key| FredClient::new(key.clone(), None));
+
+        Self {
+            yahoo_client: YahooFinanceClient::new(),
+            fred_client,
+            news_tool: NewsTool::new(config, news_cache),
+        }
+    }
+
+    /// Collect today's data for a wrap covering `watchlist`
+    pub async fn collect(&self, watchlist: &[String]) -> MarketWrapData {
+        let date = Utc::now().date_naive();
+
+        let mut indices = Vec::new();
+        for (symbol, name) in WRAP_INDICES {
+            if let Some(mv) = self.fetch_move(symbol, name).await {
+                indices.push(mv);
+            }
+        }
+
+        let mut sectors = Vec::new();
+        for sector in Sector::all() {
+            if let Some(mv) = self.fetch_move(sector.etf_ticker(), sector.name()).await {
+                sectors.push(mv);
+            }
+        }
+        sectors.sort_by(|a, b| b.change_pct.total_cmp(&a.change_pct));
+
+        let mut headlines = Vec::new();
+        for symbol in watchlist {
+            match self
+                .news_tool
+                .execute(json!({ "symbol": symbol, "limit": HEADLINES_PER_SYMBOL }))
+                .await
+            {
+                Ok(news) => headlines.extend(headlines_from(symbol, &news)),
+                Err(e) => tracing::debug!("No news for {} in market wrap: {}", symbol, e),
+            }
+        }
+
+        MarketWrapData {
+            date,
+            indices,
+            sectors,
+            macro_releases: self.macro_releases(date).await,
+            headlines,
+        }
+    }
+
+    async fn fetch_move(&self, symbol: &str, name: &str) -> Option<MarketMove> {
+        match self.yahoo_client.get_historical_range(symbol, "5d").await {
+            Ok(quotes) => daily_move(symbol, name, &quotes),
+            Err(e) => {
+                tracing::debug!("No quotes for {} in market wrap: {}", symbol, e);
+                None
+            }
+        }
+    }
+
+    /// Datapoints of [`RELEASE_SERIES`] that FRED updated on `date`
+    async fn macro_releases(&self, date: NaiveDate) -> Vec<MacroRelease> {
+        let Some(fred) = &self.fred_client else {
+            return Vec::new();
+        };
+
+        let mut releases = Vec::new();
+        for series_id in RELEASE_SERIES {
+            let Ok(info) = fred.get_series_info(series_id).await else {
+                continue;
+            };
+            if !released_on(&info.last_updated, date) {
+                continue;
+            }
+            let Ok(observations) = fred.get_observations(series_id, None, None, Some(2)).await
+            else {
+                continue;
+            };
+            let mut values = observations
+                .iter()
+                .map(|obs| (obs.date.clone(), obs.value.parse::<f64>().ok()));
+            if let Some((observation_date, Some(value))) = values.next() {
+                releases.push(MacroRelease {
+                    series_id: (*series_id).to_string(),
+                    title: info.title,
+                    observation_date,
+                    value,
+                    prior: values.next().and_then(|(_, value)| value),
+                });
+            }
+        }
+        releases
+    }
+}
+
+/// Persisted history of generated market wraps, one per date
+///
+/// Kept in memory and, when opened with a path, saved as JSON after every
+/// change (encrypted when opened with a cipher).
+pub struct MarketWrapArchive {
+    wraps: RwLock<Vec<MarketWrap>>,
+    path: Option<PathBuf>,
+    cipher: Option<StoreCipher>,
+}
+
+impl Default for MarketWrapArchive {
+    fn default() -> Self {
+        Self::in_memory()
+    }
+}
+
+impl MarketWrapArchive {
+    /// Create an archive that is not persisted
+    pub fn in_memory() -> Self {
+        Self {
+            wraps: RwLock::new(Vec::new()),
+            path: None,
+            cipher: None,
+        }
+    }
+
+    /// Open an archive persisted at `path`, loading existing wraps
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        Self::open_with_cipher(path, None)
+    }
+
+    /// Open an archive persisted at `path`, encrypted with `cipher` if given
+    pub fn open_with_cipher(path: impl Into<PathBuf>, cipher: Option<StoreCipher>) -> Result<Self> {
+        let path = path.into();
+        let wraps = match storage::read_store(&path, cipher.as_ref())? {
+            Some(json) => serde_json::from_str(&json)?,
+            None => Vec::new(),
+        };
+
+        Ok(Self {
+            wraps: RwLock::new(wraps),
+            path: Some(path),
+            cipher,
+        })
+    }
+
+    /// File the archive is persisted to, if any
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Archive a wrap, replacing any earlier wrap for the same date
+    pub fn record(&self, wrap: MarketWrap) -> Result<()> {
+        {
+            let mut wraps = self.write();
+            wraps.retain(|w| w.date != wrap.date);
+            wraps.push(wrap);
+            wraps.sort_by_key(|w| w.date);
+        }
+        self.save()
+    }
+
+    /// All archived wraps, oldest first
+    pub fn wraps(&self) -> Vec<MarketWrap> {
+        self.read().clone()
+    }
+
+    /// Wrap for `date`, if one was generated
+    pub fn get(&self, date: NaiveDate) -> Option<MarketWrap> {
+        self.read().iter().find(|w| w.date == date).cloned()
+    }
+
+    /// Most recent wrap
+    pub fn latest(&self) -> Option<MarketWrap> {
+        self.read().last().cloned()
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, Vec<MarketWrap>> {
+        self.wraps.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, Vec<MarketWrap>> {
+        self.wraps.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let json = serde_json::to_string_pretty(&*self.read())?;
+        storage::write_store(path, &json, self.cipher.as_ref())
+    }
+}
+
+/// Collects the day's data, writes the narrative and archives the wrap
+pub struct MarketWrapJob {
+    agent: Arc<StockAnalysisAgent>,
+    collector: MarketWrapCollector,
+    archive: Arc<MarketWrapArchive>,
+}
+
+impl MarketWrapJob {
+    /// Create a job writing wraps with `agent` into `archive`
+    pub fn new(
+        agent: Arc<StockAnalysisAgent>,
+        config: Arc<StockConfig>,
+        archive: Arc<MarketWrapArchive>,
+    ) -> Self {
+        let news_cache = StockCache::from_config(&config, CacheCategory::News);
+        Self {
+            agent,
+            collector: MarketWrapCollector::new(config, news_cache),
+            archive,
+        }
+    }
+
+    /// Generate and archive today's wrap, with news for `watchlist`
+    pub async fn run(&self, watchlist: &[String]) -> Result<MarketWrap> {
+        let data = self.collector.collect(watchlist).await;
+        let narrative = self.agent.market_wrap(&data, &mut Context::new()).await?;
+        let wrap = MarketWrap {
+            date: data.date,
+            generated_at: Utc::now(),
+            data,
+            narrative,
+        };
+        self.archive.record(wrap.clone())?;
+        Ok(wrap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote(close: f64) -> Quote {
+        Quote {
+            symbol: "^GSPC".to_string(),
+            timestamp: Utc::now(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 0,
+            adjclose: close,
+        }
+    }
+
+    fn wrap(date: NaiveDate, narrative: &str) -> MarketWrap {
+        MarketWrap {
+            date,
+            generated_at: Utc::now(),
+            data: MarketWrapData {
+                date,
+                indices: vec![],
+                sectors: vec![
+                    MarketMove {
+                        symbol: "XLK".to_string(),
+                        name: "Technology".to_string(),
+                        close: 210.0,
+                        change_pct: 1.2,
+                    },
+                    MarketMove {
+                        symbol: "XLU".to_string(),
+                        name: "Utilities".to_string(),
+                        close: 68.0,
+                        change_pct: -0.4,
+                    },
+                ],
+                macro_releases: vec![],
+                headlines: vec![],
+            },
+            narrative: narrative.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_daily_move() {
+        let mv = daily_move(
+            "^GSPC",
+            "S&P 500",
+            &[quote(4900.0), quote(5000.0), quote(5050.0)],
+        )
+        .unwrap();
+        assert_eq!(mv.name, "S&P 500");
+        assert!((mv.close - 5050.0).abs() < 1e-9);
+        assert!((mv.change_pct - 1.0).abs() < 1e-9);
+
+        assert!(daily_move("^GSPC", "S&P 500", &[quote(5000.0)]).is_none());
+        assert!(daily_move("^GSPC", "S&P 500", &[quote(0.0), quote(5000.0)]).is_none());
+    }
+
+    #[test]
+    fn test_released_on() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 12).unwrap();
+        assert!(released_on("2024-03-12 07:31:02-05", date));
+        assert!(!released_on("2024-02-13 07:31:02-06", date));
+        assert!(!released_on("", date));
+    }
+
+    #[test]
+    fn test_headlines_from() {
+        let news = json!({
+            "articles": [
+                {"title": "Apple unveils new chip", "source": "Reuters", "sentiment": "positive"},
+                {"source": "No title"},
+                {"title": "Apple faces EU fine", "source": "FT", "sentiment": "negative"},
+                {"title": "Third headline"}
+            ]
+        });
+        let headlines = headlines_from("AAPL", &news);
+        assert_eq!(headlines.len(), 2);
+        assert_eq!(headlines[1].title, "Apple faces EU fine");
+        assert_eq!(headlines[1].sentiment.as_deref(), Some("negative"));
+        assert!(headlines_from("AAPL", &json!({})).is_empty());
+    }
+
+    #[test]
+    fn test_prompt_json() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 12).unwrap();
+        let data = wrap(date, "").data;
+        assert_eq!(data.sector_breadth(), (1, 1));
+
+        let prompt = data.to_prompt_json();
+        assert_eq!(prompt["date"], "2024-03-12");
+        assert_eq!(prompt["sector_breadth"]["up"], 1);
+        assert_eq!(prompt["sectors"][0]["symbol"], "XLK");
+    }
+
+    #[test]
+    fn test_archive_persistence() {
+        let path = std::env::temp_dir().join(format!("market-wraps-{}.json", uuid::Uuid::new_v4()));
+
+        let monday = NaiveDate::from_ymd_opt(2024, 3, 11).unwrap();
+        let tuesday = NaiveDate::from_ymd_opt(2024, 3, 12).unwrap();
+
+        let archive = MarketWrapArchive::open(&path).unwrap();
+        archive.record(wrap(tuesday, "Stocks rose.")).unwrap();
+        archive.record(wrap(monday, "Stocks fell.")).unwrap();
+        archive.record(wrap(tuesday, "Stocks rallied.")).unwrap();
+
+        let reopened = MarketWrapArchive::open(&path).unwrap();
+        assert_eq!(reopened.wraps().len(), 2);
+        assert_eq!(reopened.latest().unwrap().narrative, "Stocks rallied.");
+        assert_eq!(reopened.get(monday).unwrap().narrative, "Stocks fell.");
+
+        let text = reopened.latest().unwrap().to_string();
+        assert!(text.starts_with("📰 Market Wrap — 2024-03-12\n\nStocks rallied."));
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,485 @@
+//! Schema migrations for persisted stores
+//!
+//! Each [`StoreKind`] has an ordered list of [`Migration`]s with `up` and
+//! `down` steps over the store's JSON. The version a store is at is recorded
+//! next to it in `<store>.schema`; a store without one predates migrations and
+//! is at version 0.
+//!
+//! [`Migrator::migrate`] runs when the bot starts, so stores written by an
+//! older release are upgraded before they are opened. `agent-cli migrate`
+//! shows status, previews with `--dry-run`, and rolls back with `--to`.
+//!
+//! # Adding a migration
+//!
+//! Append to the store's list with the next version number; never edit or
+//! reorder released migrations.
+//!
+//! ```rust,ignore
+//! const PREDICTIONS: &[Migration] = &[Migration {
+//!     version: 1,
+//!     description: "Add confidence to predictions",
+//!     up: |store| { /* add the field to every entry */ Ok(()) },
+//!     down: |store| { /* remove it again */ Ok(()) },
+//! }];
+//! ```
+
+use serde_json::Value;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::backup::{StoreKind, StorePaths};
+use crate::error::{Result, StockError};
+use crate::storage::StoreCipher;
+
+/// One versioned change to a store's JSON
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    /// Version the store is at after `up`
+    pub version: u32,
+    /// What the migration changes
+    pub description: &'static str,
+    /// Upgrade from `version - 1`
+    pub up: fn(&mut Value) -> Result<()>,
+    /// Downgrade back to `version - 1`
+    pub down: fn(&mut Value) -> Result<()>,
+}
+
+/// Migrations of the predictions store
+const PREDICTIONS: &[Migration] = &[];
+
+/// Migrations of the usage statistics store
+const USAGE: &[Migration] = &[];
+
+/// Migrations of the alerts store
+const ALERTS: &[Migration] = &[];
+
+/// Migrations of the delivery preferences store
+const DELIVERY: &[Migration] = &[];
+
+/// Migrations of the portfolio store
+const PORTFOLIO: &[Migration] = &[];
+
+/// Migrations of the conversation history store
+const CONVERSATIONS: &[Migration] = &[];
+
+/// Released migrations of a store, in version order
+pub fn migrations_for(kind: StoreKind) -> &'static [Migration] {
+    match kind {
+        StoreKind::Predictions => PREDICTIONS,
+        StoreKind::Usage => USAGE,
+        StoreKind::Alerts => ALERTS,
+        StoreKind::Delivery => DELIVERY,
+        StoreKind::Portfolio => PORTFOLIO,
+        StoreKind::Conversations => CONVERSATIONS,
+    }
+}
+
+/// File recording the schema version of the store at `path`
+pub fn version_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".schema");
+    PathBuf::from(name)
+}
+
+/// Direction of a migration step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Upgrade
+    Up,
+    /// Roll back
+    Down,
+}
+
+/// A migration applied (or, in a dry run, that would be applied)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStep {
+    /// Store migrated
+    pub kind: StoreKind,
+    /// Version of the migration
+    pub version: u32,
+    /// What the migration changes
+    pub description: &'static str,
+    /// Whether it was applied or rolled back
+    pub direction: Direction,
+}
+
+impl fmt::Display for MigrationStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let arrow = match self.direction {
+            Direction::Up => "up",
+            Direction::Down => "down",
+        };
+        write!(
+            f,
+            "{} v{} {arrow}: {}",
+            self.kind, self.version, self.description
+        )
+    }
+}
+
+/// Schema state of one store
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoreStatus {
+    /// Store
+    pub kind: StoreKind,
+    /// Where it lives
+    pub path: PathBuf,
+    /// Whether the store file exists yet
+    pub exists: bool,
+    /// Version the store is at
+    pub current: u32,
+    /// Latest released version
+    pub latest: u32,
+}
+
+impl StoreStatus {
+    /// Whether the store needs upgrading
+    pub fn is_pending(&self) -> bool {
+        self.exists && self.current < self.latest
+    }
+}
+
+impl fmt::Display for StoreStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = if !self.exists {
+            "not created yet".to_string()
+        } else if self.current < self.latest {
+            format!("{} pending", self.latest - self.current)
+        } else if self.current > self.latest {
+            "newer than this release".to_string()
+        } else {
+            "up to date".to_string()
+        };
+        write!(
+            f,
+            "{:<12} v{}/{}  {state}  ({})",
+            self.kind.as_str(),
+            self.current,
+            self.latest,
+            self.path.display()
+        )
+    }
+}
+
+/// Applies migrations to every configured store
+pub struct Migrator<'a> {
+    paths: &'a StorePaths,
+    cipher: Option<&'a StoreCipher>,
+    registry: fn(StoreKind) -> &'static [Migration],
+}
+
+impl<'a> Migrator<'a> {
+    /// Create a migrator for the released migrations
+    pub fn new(paths: &'a StorePaths, cipher: Option<&'a StoreCipher>) -> Self {
+        Self {
+            paths,
+            cipher,
+            registry: migrations_for,
+        }
+    }
+
+    /// Use a different set of migrations
+    pub fn with_registry(mut self, registry: fn(StoreKind) -> &'static [Migration]) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Schema state of every configured store
+    pub fn status(&self) -> Result<Vec<StoreStatus>> {
+        StoreKind::ALL
+            .iter()
+            .filter_map(|kind| self.paths.get(*kind).map(|path| (*kind, path)))
+            .map(|(kind, path)| {
+                let latest = (self.registry)(kind).last().map_or(0, |m| m.version);
+                let exists = path.exists();
+                Ok(StoreStatus {
+                    kind,
+                    path: path.to_path_buf(),
+                    exists,
+                    current: if exists { read_version(path)? } else { latest },
+                    latest,
+                })
+            })
+            .collect()
+    }
+
+    /// Bring every store to its latest version
+    ///
+    /// With `dry_run`, nothing is written and the returned steps are those
+    /// that would run.
+    pub fn migrate(&self, dry_run: bool) -> Result<Vec<MigrationStep>> {
+        let mut steps = Vec::new();
+        for status in self.status()? {
+            steps.extend(self.migrate_store(&status, status.latest, dry_run)?);
+        }
+        Ok(steps)
+    }
+
+    /// Move one store to `target`, rolling back if it is below the current
+    /// version
+    pub fn migrate_to(
+        &self,
+        kind: StoreKind,
+        target: u32,
+        dry_run: bool,
+    ) -> Result<Vec<MigrationStep>> {
+        let status = self
+            .status()?
+            .into_iter()
+            .find(|s| s.kind == kind)
+            .ok_or_else(|| StockError::ConfigError(format!("No path configured for {kind}")))?;
+        if target > status.latest {
+            return Err(StockError::Other(format!(
+                "{kind} has no version {target}; latest is {}",
+                status.latest
+            )));
+        }
+        self.migrate_store(&status, target, dry_run)
+    }
+
+    fn migrate_store(
+        &self,
+        status: &StoreStatus,
+        target: u32,
+        dry_run: bool,
+    ) -> Result<Vec<MigrationStep>> {
+        let path = &status.path;
+        if !status.exists {
+            // A new store is created in the latest format
+            if !dry_run && !version_path(path).exists() {
+                write_version(path, status.latest)?;
+            }
+            return Ok(Vec::new());
+        }
+        if status.current > status.latest {
+            return Err(StockError::Other(format!(
+                "{} is at schema v{} but this release only knows v{}; upgrade agent-rs",
+                path.display(),
+                status.current,
+                status.latest
+            )));
+        }
+
+        let migrations = (self.registry)(status.kind);
+        let (direction, selected): (Direction, Vec<&Migration>) = if target >= status.current {
+            (
+                Direction::Up,
+                migrations
+                    .iter()
+                    .filter(|m| m.version > status.current && m.version <= target)
+                    .collect(),
+            )
+        } else {
+            (
+                Direction::Down,
+                migrations
+                    .iter()
+                    .rev()
+                    .filter(|m| m.version <= status.current && m.version > target)
+                    .collect(),
+            )
+        };
+        if selected.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Only read here: a dry run must leave a plaintext store as it is
+        let json = status.kind.peek(path, self.cipher)?.unwrap_or_default();
+        let mut store: Value = serde_json::from_str(&json)?;
+        let mut steps = Vec::new();
+        for migration in selected {
+            let step = match direction {
+                Direction::Up => migration.up,
+                Direction::Down => migration.down,
+            };
+            step(&mut store).map_err(|e| {
+                StockError::Other(format!(
+                    "Migration {} v{} failed: {e}",
+                    status.kind, migration.version
+                ))
+            })?;
+            steps.push(MigrationStep {
+                kind: status.kind,
+                version: migration.version,
+                description: migration.description,
+                direction,
+            });
+        }
+
+        if !dry_run {
+            // Keep the original until the migrated store is in place
+            let backup = path.with_extension("pre-migration");
+            std::fs::copy(path, &backup).map_err(|e| {
+                StockError::Other(format!("Failed to back up {}: {e}", path.display()))
+            })?;
+            status
+                .kind
+                .write(path, &serde_json::to_string_pretty(&store)?, self.cipher)?;
+            write_version(path, target)?;
+            std::fs::remove_file(&backup).ok();
+            tracing::info!(
+                "Migrated {} from v{} to v{}",
+                path.display(),
+                status.current,
+                target
+            );
+        }
+        Ok(steps)
+    }
+}
+
+/// Schema version of the store at `path` (0 when unrecorded)
+pub(crate) fn read_version(path: &Path) -> Result<u32> {
+    let version_path = version_path(path);
+    if !version_path.exists() {
+        return Ok(0);
+    }
+    let contents = std::fs::read_to_string(&version_path).map_err(|e| {
+        StockError::Other(format!("Failed to read {}: {e}", version_path.display()))
+    })?;
+    contents.trim().parse().map_err(|e| {
+        StockError::Other(format!(
+            "Invalid schema version in {}: {e}",
+            version_path.display()
+        ))
+    })
+}
+
+/// Record the schema version of the store at `path`
+pub(crate) fn write_version(path: &Path, version: u32) -> Result<()> {
+    let version_path = version_path(path);
+    if let Some(parent) = version_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| {
+            StockError::Other(format!("Failed to create {}: {e}", parent.display()))
+        })?;
+    }
+    std::fs::write(&version_path, format!("{version}\n"))
+        .map_err(|e| StockError::Other(format!("Failed to write {}: {e}", version_path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const TEST_PREDICTIONS: &[Migration] = &[
+        Migration {
+            version: 1,
+            description: "Wrap predictions in an object",
+            up: |store| {
+                *store = json!({ "predictions": store.take() });
+                Ok(())
+            },
+            down: |store| {
+                *store = store["predictions"].take();
+                Ok(())
+            },
+        },
+        Migration {
+            version: 2,
+            description: "Add owner",
+            up: |store| {
+                store["owner"] = json!("default");
+                Ok(())
+            },
+            down: |store| {
+                store.as_object_mut().map(|o| o.remove("owner"));
+                Ok(())
+            },
+        },
+    ];
+
+    fn test_registry(kind: StoreKind) -> &'static [Migration] {
+        match kind {
+            StoreKind::Predictions => TEST_PREDICTIONS,
+            _ => &[],
+        }
+    }
+
+    fn store_value(path: &Path) -> Value {
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_migrate_up_dry_run_and_down() {
+        let dir = std::env::temp_dir().join(format!("migrations-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let predictions = dir.join("predictions.json");
+        std::fs::write(&predictions, "[1]").unwrap();
+        let paths = StorePaths {
+            predictions: Some(predictions.clone()),
+            usage: Some(dir.join("usage.json")),
+            ..StorePaths::default()
+        };
+        let migrator = Migrator::new(&paths, None).with_registry(test_registry);
+
+        let status = migrator.status().unwrap();
+        assert_eq!(status[0].current, 0);
+        assert!(status[0].is_pending());
+        assert!(!status[1].exists);
+
+        // Dry run reports the steps without touching the store
+        assert_eq!(migrator.migrate(true).unwrap().len(), 2);
+        assert_eq!(store_value(&predictions), json!([1]));
+
+        let steps = migrator.migrate(false).unwrap();
+        assert_eq!(steps[1].to_string(), "predictions v2 up: Add owner");
+        assert_eq!(
+            store_value(&predictions),
+            json!({ "predictions": [1], "owner": "default" })
+        );
+        assert!(migrator.status().unwrap().iter().all(|s| !s.is_pending()));
+        assert!(migrator.migrate(false).unwrap().is_empty());
+
+        let steps = migrator
+            .migrate_to(StoreKind::Predictions, 0, false)
+            .unwrap();
+        assert_eq!(steps[0].version, 2);
+        assert_eq!(steps[1].direction, Direction::Down);
+        assert_eq!(store_value(&predictions), json!([1]));
+        assert_eq!(read_version(&predictions).unwrap(), 0);
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_dry_run_leaves_plaintext_store_unencrypted() {
+        let dir = std::env::temp_dir().join(format!("migrations-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let predictions = dir.join("predictions.json");
+        std::fs::write(&predictions, "[1]").unwrap();
+        let paths = StorePaths {
+            predictions: Some(predictions.clone()),
+            ..StorePaths::default()
+        };
+        let cipher = StoreCipher::from_key(&[7; 32]).unwrap();
+        let migrator = Migrator::new(&paths, Some(&cipher)).with_registry(test_registry);
+
+        assert_eq!(migrator.migrate(true).unwrap().len(), 2);
+        assert_eq!(std::fs::read_to_string(&predictions).unwrap(), "[1]");
+        assert!(!version_path(&predictions).exists());
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_new_store_starts_at_latest() {
+        let dir = std::env::temp_dir().join(format!("migrations-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let predictions = dir.join("predictions.json");
+        let paths = StorePaths {
+            predictions: Some(predictions.clone()),
+            ..StorePaths::default()
+        };
+        let migrator = Migrator::new(&paths, None).with_registry(test_registry);
+
+        assert!(migrator.migrate(false).unwrap().is_empty());
+        assert_eq!(read_version(&predictions).unwrap(), 2);
+
+        // Once the store is created, it is not migrated again
+        std::fs::write(&predictions, r#"{"predictions":[],"owner":"a"}"#).unwrap();
+        assert!(migrator.migrate(false).unwrap().is_empty());
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
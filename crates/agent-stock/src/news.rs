@@ -0,0 +1,837 @@
+//! News providers
+//!
+//! Finnhub, Alpha Vantage and the mock feed each return news in their own
+//! shape. [`NewsProvider`] puts them behind one interface returning
+//! [`NewsItem`]s, so tools handle articles the same way whichever backend
+//! served them, and new sources only need to implement the trait.
+//! [`CompositeNewsProvider`] chains providers, falling back to the next one
+//! when a provider fails or has nothing, or merging all of their articles.
+//! [`from_config`] builds the chain for a [`StockConfig`].
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::news::{self, NewsProvider};
+//!
+//! let provider = news::from_config(&config);
+//! let items = provider.get_company_news("AAPL", 10).await?;
+//! let sentiment = provider.get_sentiment("AAPL").await?;
+//! println!("{} ({:+.2})", sentiment.overall, sentiment.average_score);
+//! ```
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use futures::future::BoxFuture;
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::cmp::{Ordering, Reverse};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::api::news_apis::FinnhubNewsArticle;
+use crate::api::{AlphaVantageClient, FinnhubClient, NewsArticle};
+use crate::config::{self, StockConfig};
+use crate::crypto::CryptoPair;
+use crate::error::{Result, StockError};
+
+/// Articles [`NewsProvider::get_sentiment`] reads by default
+const SENTIMENT_SAMPLE: usize = 50;
+
+/// Days of company news Finnhub is asked for
+const FINNHUB_NEWS_DAYS: i64 = 30;
+
+/// Scores beyond this distance from zero count as positive or negative
+const SENTIMENT_THRESHOLD: f64 = 0.15;
+
+/// A news article from any provider
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NewsItem {
+    /// Headline
+    pub title: String,
+    /// Publisher (e.g. "Reuters")
+    pub source: String,
+    /// Publish time, when the provider reports one
+    pub published_at: Option<DateTime<Utc>>,
+    /// Short summary
+    pub summary: String,
+    /// Link to the article
+    pub url: String,
+    /// Thumbnail image URL
+    pub image: Option<String>,
+    /// Provider category or topic (e.g. "company", "crypto")
+    pub category: Option<String>,
+    /// Sentiment from -1 (bearish) to 1 (bullish), when the provider scores it
+    pub sentiment_score: Option<f64>,
+    /// Topics with their relevance from 0 to 1
+    pub topics: Vec<(String, f64)>,
+    /// Per-ticker sentiment, when the provider scores it
+    pub ticker_sentiment: Vec<TickerScore>,
+    /// Name of the provider that served the article
+    pub provider: &'static str,
+}
+
+/// Sentiment of one ticker mentioned in an article
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TickerScore {
+    /// Ticker symbol
+    pub ticker: String,
+    /// How relevant the article is to the ticker, from 0 to 1
+    pub relevance: f64,
+    /// Sentiment from -1 to 1
+    pub sentiment_score: f64,
+}
+
+impl NewsItem {
+    /// "positive", "negative" or "neutral"; unscored articles are neutral
+    pub fn sentiment(&self) -> &'static str {
+        sentiment_label(self.sentiment_score.unwrap_or(0.0))
+    }
+
+    /// JSON for tool results
+    pub fn to_json(&self) -> Value {
+        let mut article = json!({
+            "title": self.title,
+            "source": self.source,
+            "published_at": self.published_at.map(|at| at.to_rfc3339()).unwrap_or_default(),
+            "summary": self.summary,
+            "sentiment": self.sentiment(),
+            "sentiment_score": self.sentiment_score.unwrap_or(0.0),
+            "url": self.url,
+            "image": self.image,
+            "provider": self.provider,
+        });
+        if let Some(category) = &self.category {
+            article["category"] = json!(category);
+        }
+        if !self.topics.is_empty() {
+            article["topics"] = self
+                .topics
+                .iter()
+                .map(|(topic, relevance)| json!({ "topic": topic, "relevance": relevance }))
+                .collect();
+        }
+        if !self.ticker_sentiment.is_empty() {
+            article["ticker_sentiment"] = json!(self.ticker_sentiment);
+        }
+        article
+    }
+
+    /// Key identifying the same story from different providers
+    fn dedup_key(&self) -> String {
+        if self.url.is_empty() {
+            self.title.trim().to_lowercase()
+        } else {
+            self.url.clone()
+        }
+    }
+}
+
+/// Aggregate sentiment over a set of articles
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NewsSentiment {
+    /// Symbol the articles are about
+    pub symbol: String,
+    /// Number of articles read
+    pub article_count: usize,
+    /// "positive", "negative" or "neutral", by majority of articles
+    pub overall: &'static str,
+    /// Mean sentiment score, counting unscored articles as 0
+    pub average_score: f64,
+    /// Articles with positive sentiment
+    pub positive: usize,
+    /// Articles with negative sentiment
+    pub negative: usize,
+    /// Articles with neutral or no sentiment
+    pub neutral: usize,
+}
+
+impl NewsSentiment {
+    /// Sentiment of `items`, news about `symbol`
+    pub fn from_items(symbol: &str, items: &[NewsItem]) -> Self {
+        let positive = items
+            .iter()
+            .filter(|item| item.sentiment() == "positive")
+            .count();
+        let negative = items
+            .iter()
+            .filter(|item| item.sentiment() == "negative")
+            .count();
+
+        let overall = match positive.cmp(&negative) {
+            Ordering::Greater => "positive",
+            Ordering::Less => "negative",
+            Ordering::Equal => "neutral",
+        };
+        let average_score = items
+            .iter()
+            .map(|item| item.sentiment_score.unwrap_or(0.0))
+            .sum::<f64>()
+            / items.len().max(1) as f64;
+
+        Self {
+            symbol: symbol.to_string(),
+            article_count: items.len(),
+            overall,
+            average_score,
+            positive,
+            negative,
+            neutral: items.len() - positive - negative,
+        }
+    }
+}
+
+pub(crate) fn sentiment_label(score: f64) -> &'static str {
+    if score > SENTIMENT_THRESHOLD {
+        "positive"
+    } else if score < -SENTIMENT_THRESHOLD {
+        "negative"
+    } else {
+        "neutral"
+    }
+}
+
+/// Source of news articles
+#[async_trait]
+pub trait NewsProvider: Send + Sync {
+    /// Name shown with the articles this provider serves
+    fn name(&self) -> &'static str;
+
+    /// Recent news about `symbol`, a stock ticker or crypto pair, newest first
+    async fn get_company_news(&self, symbol: &str, limit: usize) -> Result<Vec<NewsItem>>;
+
+    /// Recent market news, newest first
+    ///
+    /// `category` is one of "general", "forex", "crypto" or "merger".
+    async fn get_market_news(&self, category: &str, limit: usize) -> Result<Vec<NewsItem>>;
+
+    /// News about `symbol` published by `until`, newest first
+    ///
+    /// By default the recent news is filtered, which leaves nothing for dates
+    /// further back than the provider's recent window; providers that can
+    /// search by date override this. Undated articles are dropped.
+    async fn get_company_news_until(
+        &self,
+        symbol: &str,
+        limit: usize,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<NewsItem>> {
+        let items = self.get_company_news(symbol, limit).await?;
+        Ok(published_by(items, until, limit))
+    }
+
+    /// Sentiment of recent news about `symbol`
+    async fn get_sentiment(&self, symbol: &str) -> Result<NewsSentiment> {
+        let items = self.get_company_news(symbol, SENTIMENT_SAMPLE).await?;
+        Ok(NewsSentiment::from_items(symbol, &items))
+    }
+}
+
+impl From<FinnhubNewsArticle> for NewsItem {
+    fn from(article: FinnhubNewsArticle) -> Self {
+        Self {
+            title: article.headline,
+            source: article.source,
+            published_at: DateTime::from_timestamp(article.datetime, 0),
+            summary: article.summary,
+            url: article.url,
+            image: Some(article.image).filter(|image| !image.is_empty()),
+            category: Some(article.category).filter(|category| !category.is_empty()),
+            // Finnhub's free tier has no sentiment
+            sentiment_score: None,
+            topics: Vec::new(),
+            ticker_sentiment: Vec::new(),
+            provider: "Finnhub",
+        }
+    }
+}
+
+#[async_trait]
+impl NewsProvider for FinnhubClient {
+    fn name(&self) -> &'static str {
+        "Finnhub"
+    }
+
+    async fn get_company_news(&self, symbol: &str, limit: usize) -> Result<Vec<NewsItem>> {
+        let articles = if let Some(pair) = CryptoPair::parse(symbol) {
+            // Finnhub has no per-coin news, so filter its crypto feed instead
+            let feed = FinnhubClient::get_market_news(self, "crypto").await?;
+            crypto_headlines(feed, &pair)
+        } else {
+            let to = Utc::now();
+            let from = to - chrono::Duration::days(FINNHUB_NEWS_DAYS);
+            FinnhubClient::get_company_news(
+                self,
+                symbol,
+                &from.format("%Y-%m-%d").to_string(),
+                &to.format("%Y-%m-%d").to_string(),
+            )
+            .await?
+        };
+        Ok(articles
+            .into_iter()
+            .take(limit)
+            .map(NewsItem::from)
+            .collect())
+    }
+
+    async fn get_market_news(&self, category: &str, limit: usize) -> Result<Vec<NewsItem>> {
+        let articles = FinnhubClient::get_market_news(self, category).await?;
+        Ok(articles
+            .into_iter()
+            .take(limit)
+            .map(NewsItem::from)
+            .collect())
+    }
+
+    async fn get_company_news_until(
+        &self,
+        symbol: &str,
+        limit: usize,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<NewsItem>> {
+        if CryptoPair::parse(symbol).is_some() {
+            // The crypto feed only has recent news
+            let items = NewsProvider::get_company_news(self, symbol, limit).await?;
+            return Ok(published_by(items, until, limit));
+        }
+        let from = until - chrono::Duration::days(FINNHUB_NEWS_DAYS);
+        let articles = FinnhubClient::get_company_news(
+            self,
+            symbol,
+            &from.format("%Y-%m-%d").to_string(),
+            &until.format("%Y-%m-%d").to_string(),
+        )
+        .await?;
+        let items = articles.into_iter().map(NewsItem::from).collect();
+        Ok(published_by(items, until, limit))
+    }
+}
+
+/// Crypto feed articles about the pair's coin, or the whole feed if none are
+fn crypto_headlines(
+    articles: Vec<FinnhubNewsArticle>,
+    pair: &CryptoPair,
+) -> Vec<FinnhubNewsArticle> {
+    let terms = [pair.base.to_lowercase(), pair.name().to_lowercase()];
+    let mentions = |article: &FinnhubNewsArticle| {
+        let text = format!("{} {}", article.headline, article.summary).to_lowercase();
+        terms.iter().any(|term| text.contains(term.as_str()))
+    };
+    if articles.iter().any(mentions) {
+        articles.into_iter().filter(mentions).collect()
+    } else {
+        articles
+    }
+}
+
+impl From<NewsArticle> for NewsItem {
+    fn from(article: NewsArticle) -> Self {
+        let score = |value: &str| value.parse::<f64>().unwrap_or(0.0);
+        Self {
+            title: article.title,
+            source: article.source,
+            // Alpha Vantage times look like 20240301T153000
+            published_at: NaiveDateTime::parse_from_str(&article.time_published, "%Y%m%dT%H%M%S")
+                .ok()
+                .map(|at| at.and_utc()),
+            summary: article.summary,
+            url: article.url,
+            image: article.banner_image.filter(|image| !image.is_empty()),
+            category: article
+                .category_within_source
+                .filter(|category| !category.is_empty()),
+            sentiment_score: article.overall_sentiment_score,
+            topics: article
+                .topics
+                .iter()
+                .map(|topic| (topic.topic.clone(), score(&topic.relevance_score)))
+                .collect(),
+            ticker_sentiment: article
+                .ticker_sentiment
+                .unwrap_or_default()
+                .iter()
+                .map(|ticker| TickerScore {
+                    ticker: ticker.ticker.clone(),
+                    relevance: score(&ticker.relevance_score),
+                    sentiment_score: score(&ticker.ticker_sentiment_score),
+                })
+                .collect(),
+            provider: "Alpha Vantage",
+        }
+    }
+}
+
+#[async_trait]
+impl NewsProvider for AlphaVantageClient {
+    fn name(&self) -> &'static str {
+        "Alpha Vantage"
+    }
+
+    async fn get_company_news(&self, symbol: &str, limit: usize) -> Result<Vec<NewsItem>> {
+        // Alpha Vantage names coins as CRYPTO:BTC
+        let tickers = CryptoPair::parse(symbol).map_or_else(
+            || symbol.to_string(),
+            |pair| format!("CRYPTO:{}", pair.base),
+        );
+        let response = self
+            .get_news_sentiment(&tickers, None, None, Some(limit as u32))
+            .await?;
+        Ok(response
+            .feed
+            .into_iter()
+            .take(limit)
+            .map(NewsItem::from)
+            .collect())
+    }
+
+    async fn get_market_news(&self, category: &str, limit: usize) -> Result<Vec<NewsItem>> {
+        let topic = match category {
+            "general" => "financial_markets",
+            "forex" => "economy_monetary",
+            "crypto" => "blockchain",
+            "merger" => "mergers_and_acquisitions",
+            other => other,
+        };
+        let response = self.get_topic_news(topic, Some(limit as u32)).await?;
+        Ok(response
+            .feed
+            .into_iter()
+            .take(limit)
+            .map(NewsItem::from)
+            .collect())
+    }
+}
+
+/// Canned articles, for tests and running without API keys
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MockNewsProvider;
+
+impl MockNewsProvider {
+    fn item(title: String, source: &str, days_ago: i64, summary: String, score: f64) -> NewsItem {
+        let url = format!(
+            "https://example.com/news/{}",
+            title.to_lowercase().replace(' ', "-")
+        );
+        NewsItem {
+            title,
+            source: source.to_string(),
+            published_at: Some(Utc::now() - chrono::Duration::days(days_ago)),
+            summary,
+            url,
+            image: None,
+            category: None,
+            sentiment_score: Some(score),
+            topics: Vec::new(),
+            ticker_sentiment: Vec::new(),
+            provider: "Mock",
+        }
+    }
+}
+
+#[async_trait]
+impl NewsProvider for MockNewsProvider {
+    fn name(&self) -> &'static str {
+        "Mock"
+    }
+
+    async fn get_company_news(&self, symbol: &str, limit: usize) -> Result<Vec<NewsItem>> {
+        let items = vec![
+            Self::item(
+                format!("{symbol} Stock Analysis Update"),
+                "Market News",
+                0,
+                format!("Latest market analysis and trends for {symbol}"),
+                0.0,
+            ),
+            Self::item(
+                format!("{symbol} Quarterly Earnings Report"),
+                "Financial Times",
+                1,
+                format!("{symbol} reports quarterly earnings"),
+                0.6,
+            ),
+        ];
+        Ok(items.into_iter().take(limit).collect())
+    }
+
+    async fn get_market_news(&self, category: &str, limit: usize) -> Result<Vec<NewsItem>> {
+        let items = vec![Self::item(
+            "Markets Steady Ahead of Economic Data".to_string(),
+            "Market News",
+            0,
+            format!("Latest {category} market headlines"),
+            0.0,
+        )];
+        Ok(items.into_iter().take(limit).collect())
+    }
+}
+
+/// Several providers behind one, tried in order
+///
+/// By default the first provider that returns articles wins, and a provider
+/// that fails is skipped for the next one. With [`merge`](Self::merge), all
+/// providers are asked at once and their articles are combined, newest
+/// first, with stories reported by more than one provider kept once.
+/// Either way the call only fails when every provider fails.
+pub struct CompositeNewsProvider {
+    providers: Vec<Arc<dyn NewsProvider>>,
+    merge: bool,
+}
+
+impl CompositeNewsProvider {
+    /// Chain `providers`, in order of preference
+    pub fn new(providers: Vec<Arc<dyn NewsProvider>>) -> Self {
+        Self {
+            providers,
+            merge: false,
+        }
+    }
+
+    /// Combine the articles of every provider instead of falling back
+    pub fn merge(mut self, merge: bool) -> Self {
+        self.merge = merge;
+        self
+    }
+
+    /// Providers in order of preference
+    pub fn providers(&self) -> &[Arc<dyn NewsProvider>] {
+        &self.providers
+    }
+
+    async fn collect<'a>(
+        &'a self,
+        limit: usize,
+        fetch: impl Fn(&'a dyn NewsProvider) -> BoxFuture<'a, Result<Vec<NewsItem>>>,
+    ) -> Result<Vec<NewsItem>> {
+        if self.providers.is_empty() {
+            return Err(StockError::ConfigError(
+                "No news provider configured".to_string(),
+            ));
+        }
+
+        if self.merge {
+            let results =
+                futures::future::join_all(self.providers.iter().map(|p| fetch(p.as_ref()))).await;
+            let mut errors = Vec::new();
+            let mut items = Vec::new();
+            for (provider, result) in self.providers.iter().zip(results) {
+                match result {
+                    Ok(found) => items.extend(found),
+                    Err(e) => {
+                        tracing::warn!("{} news failed: {e}", provider.name());
+                        errors.push(e);
+                    }
+                }
+            }
+            if errors.len() == self.providers.len() {
+                return Err(errors.swap_remove(0));
+            }
+            return Ok(merge_items(items, limit));
+        }
+
+        let mut first_error = None;
+        let mut any_succeeded = false;
+        for provider in &self.providers {
+            match fetch(provider.as_ref()).await {
+                Ok(items) if !items.is_empty() => return Ok(items),
+                Ok(_) => any_succeeded = true,
+                Err(e) => {
+                    tracing::warn!(
+                        "{} news failed, trying the next provider: {e}",
+                        provider.name()
+                    );
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        match first_error {
+            Some(e) if !any_succeeded => Err(e),
+            _ => Ok(Vec::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl NewsProvider for CompositeNewsProvider {
+    fn name(&self) -> &'static str {
+        "Composite"
+    }
+
+    async fn get_company_news(&self, symbol: &str, limit: usize) -> Result<Vec<NewsItem>> {
+        self.collect(limit, |provider| provider.get_company_news(symbol, limit))
+            .await
+    }
+
+    async fn get_market_news(&self, category: &str, limit: usize) -> Result<Vec<NewsItem>> {
+        self.collect(limit, |provider| provider.get_market_news(category, limit))
+            .await
+    }
+
+    async fn get_company_news_until(
+        &self,
+        symbol: &str,
+        limit: usize,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<NewsItem>> {
+        self.collect(limit, |provider| {
+            provider.get_company_news_until(symbol, limit, until)
+        })
+        .await
+    }
+}
+
+/// Items published by `until`, at most `limit`
+fn published_by(items: Vec<NewsItem>, until: DateTime<Utc>, limit: usize) -> Vec<NewsItem> {
+    items
+        .into_iter()
+        .filter(|item| {
+            item.published_at
+                .is_some_and(|published| published <= until)
+        })
+        .take(limit)
+        .collect()
+}
+
+/// Newest first, without repeats, at most `limit`
+fn merge_items(mut items: Vec<NewsItem>, limit: usize) -> Vec<NewsItem> {
+    // Undated articles sort last
+    items.sort_by_key(|item| Reverse(item.published_at));
+    let mut seen = HashSet::new();
+    items.retain(|item| seen.insert(item.dedup_key()));
+    items.truncate(limit);
+    items
+}
+
+/// Real news providers with API keys in `config`, the configured
+/// [`config::NewsProvider`] first
+pub fn providers_from_config(config: &StockConfig) -> Vec<Arc<dyn NewsProvider>> {
+    let finnhub = config.finnhub_api_key.as_ref().map(|key| {
+        Arc::new(FinnhubClient::new(key.clone(), 60)) as Arc<dyn NewsProvider> // Free tier: 60 req/min
+    });
+    let alpha_vantage = AlphaVantageClient::from_config(config)
+        .map(|client| Arc::new(client) as Arc<dyn NewsProvider>);
+
+    let ordered = match config.news_provider {
+        config::NewsProvider::AlphaVantage => [alpha_vantage, finnhub],
+        config::NewsProvider::Finnhub | config::NewsProvider::Mock => [finnhub, alpha_vantage],
+    };
+    ordered.into_iter().flatten().collect()
+}
+
+/// The news provider `config` selects
+///
+/// [`config::NewsProvider::Mock`] serves canned articles. Finnhub and Alpha
+/// Vantage fall back to each other when both have API keys.
+pub fn from_config(config: &StockConfig) -> Arc<dyn NewsProvider> {
+    match config.news_provider {
+        config::NewsProvider::Mock => Arc::new(MockNewsProvider),
+        config::NewsProvider::Finnhub | config::NewsProvider::AlphaVantage => {
+            Arc::new(CompositeNewsProvider::new(providers_from_config(config)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::testing::MockApi;
+
+    /// Provider returning fixed articles, or failing
+    struct Fixed(std::result::Result<Vec<NewsItem>, &'static str>);
+
+    #[async_trait]
+    impl NewsProvider for Fixed {
+        fn name(&self) -> &'static str {
+            "Fixed"
+        }
+
+        async fn get_company_news(&self, _symbol: &str, limit: usize) -> Result<Vec<NewsItem>> {
+            match &self.0 {
+                Ok(items) => Ok(items.iter().take(limit).cloned().collect()),
+                Err(message) => Err(StockError::ApiError((*message).to_string())),
+            }
+        }
+
+        async fn get_market_news(&self, _category: &str, limit: usize) -> Result<Vec<NewsItem>> {
+            self.get_company_news("", limit).await
+        }
+    }
+
+    fn item(title: &str, days_ago: i64, score: Option<f64>) -> NewsItem {
+        NewsItem {
+            title: title.to_string(),
+            source: "Wire".to_string(),
+            published_at: DateTime::from_timestamp(1_710_000_000 - days_ago * 86_400, 0),
+            summary: String::new(),
+            url: format!("https://example.com/{}", title.replace(' ', "-")),
+            image: None,
+            category: None,
+            sentiment_score: score,
+            topics: Vec::new(),
+            ticker_sentiment: Vec::new(),
+            provider: "Fixed",
+        }
+    }
+
+    #[tokio::test]
+    async fn test_composite_falls_back() {
+        let composite = CompositeNewsProvider::new(vec![
+            Arc::new(Fixed(Err("quota exceeded"))),
+            Arc::new(Fixed(Ok(Vec::new()))),
+            Arc::new(Fixed(Ok(vec![item("Apple beats", 0, None)]))),
+        ]);
+        let items = composite.get_company_news("AAPL", 5).await.unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].title, "Apple beats");
+
+        // Only a failure of every provider is an error
+        let failing = CompositeNewsProvider::new(vec![Arc::new(Fixed(Err("down")))]);
+        assert!(failing.get_market_news("general", 5).await.is_err());
+        let quiet = CompositeNewsProvider::new(vec![
+            Arc::new(Fixed(Err("down"))),
+            Arc::new(Fixed(Ok(Vec::new()))),
+        ]);
+        assert!(
+            quiet
+                .get_market_news("general", 5)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            CompositeNewsProvider::new(Vec::new())
+                .get_company_news("AAPL", 5)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_composite_merges() {
+        let composite = CompositeNewsProvider::new(vec![
+            Arc::new(Fixed(Ok(vec![
+                item("Older", 2, None),
+                item("Shared", 1, None),
+            ]))),
+            Arc::new(Fixed(Err("down"))),
+            Arc::new(Fixed(Ok(vec![
+                item("Shared", 1, Some(0.4)),
+                item("Newest", 0, None),
+            ]))),
+        ])
+        .merge(true);
+
+        let titles: Vec<String> = composite
+            .get_company_news("AAPL", 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|item| item.title)
+            .collect();
+        assert_eq!(titles, ["Newest", "Shared", "Older"]);
+        assert_eq!(
+            composite.get_company_news("AAPL", 2).await.unwrap().len(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sentiment() {
+        let provider = Fixed(Ok(vec![
+            item("Up", 0, Some(0.5)),
+            item("Up more", 0, Some(0.3)),
+            item("Down", 0, Some(-0.4)),
+            item("Unscored", 0, None),
+        ]));
+        let sentiment = provider.get_sentiment("AAPL").await.unwrap();
+        assert_eq!(sentiment.article_count, 4);
+        assert_eq!(sentiment.overall, "positive");
+        assert_eq!(
+            (sentiment.positive, sentiment.negative, sentiment.neutral),
+            (2, 1, 1)
+        );
+        assert!((sentiment.average_score - 0.1).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_finnhub_adapter() {
+        let api = MockApi::recorded().await;
+        let client = api.finnhub(60);
+
+        let items = NewsProvider::get_company_news(&client, "AAPL", 1)
+            .await
+            .unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].source, "Reuters");
+        assert_eq!(items[0].provider, "Finnhub");
+        assert_eq!(items[0].published_at.unwrap().timestamp(), 1_710_340_200);
+        assert_eq!(items[0].sentiment(), "neutral");
+        assert_eq!(items[0].to_json()["category"], "company");
+    }
+
+    #[test]
+    fn test_alpha_vantage_article() {
+        let article: NewsArticle = serde_json::from_value(json!({
+            "title": "Nvidia rallies", "url": "https://example.com/nvda",
+            "time_published": "20240301T153000", "authors": [], "summary": "",
+            "banner_image": null, "source": "Benzinga", "category_within_source": "n/a",
+            "source_domain": "benzinga.com",
+            "topics": [{ "topic": "Technology", "relevance_score": "0.9" }],
+            "overall_sentiment_score": -0.2, "overall_sentiment_label": "Somewhat-Bearish",
+            "ticker_sentiment": [{
+                "ticker": "NVDA", "relevance_score": "0.8",
+                "ticker_sentiment_score": "0.31", "ticker_sentiment_label": "Somewhat-Bullish"
+            }]
+        }))
+        .unwrap();
+
+        let item = NewsItem::from(article);
+        assert_eq!(
+            item.published_at.unwrap().to_rfc3339(),
+            "2024-03-01T15:30:00+00:00"
+        );
+        assert_eq!(item.sentiment(), "negative");
+        assert_eq!(item.topics, [("Technology".to_string(), 0.9)]);
+        assert!((item.ticker_sentiment[0].sentiment_score - 0.31).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_providers_from_config() {
+        let config = StockConfig::builder()
+            .finnhub_api_key("finnhub-key")
+            .alpha_vantage_api_key("av-key")
+            .news_provider(config::NewsProvider::AlphaVantage)
+            .build()
+            .unwrap();
+        let names: Vec<&str> = providers_from_config(&config)
+            .iter()
+            .map(|provider| provider.name())
+            .collect();
+        assert_eq!(names, ["Alpha Vantage", "Finnhub"]);
+
+        assert_eq!(from_config(&StockConfig::default()).name(), "Mock");
+    }
+
+    #[test]
+    fn test_crypto_headlines() {
+        let article = |headline: &str| -> FinnhubNewsArticle {
+            serde_json::from_value(json!({
+                "category": "crypto", "datetime": 0, "headline": headline, "id": 1,
+                "image": "", "related": "", "source": "", "summary": "", "url": ""
+            }))
+            .unwrap()
+        };
+        let feed = vec![
+            article("Bitcoin tops $70,000"),
+            article("ETH staking yields fall"),
+            article("Stablecoin bill advances"),
+        ];
+
+        let btc = crypto_headlines(feed.clone(), &CryptoPair::usd("BTC"));
+        assert_eq!(btc.len(), 1);
+        assert_eq!(btc[0].headline, "Bitcoin tops $70,000");
+
+        // Nothing about the coin keeps the whole crypto feed
+        assert_eq!(crypto_headlines(feed, &CryptoPair::usd("DOGE")).len(), 3);
+    }
+}
@@ -0,0 +1,543 @@
+//! Historical news archive and sentiment time series
+//!
+//! Every article the news tool fetches is kept in a [`NewsArchive`] under the
+//! symbol it was fetched for, deduplicated by URL (or title). The archive
+//! turns those articles into a daily [`SentimentPoint`] series, so questions
+//! like "sentiment on NVDA over the last 30 days" get a trend instead of a
+//! point-in-time snapshot, and [`correlate`] lines the series up against
+//! daily closes to show whether sentiment shifts preceded price moves.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::news_archive::NewsArchive;
+//!
+//! let archive = NewsArchive::open("news_archive.json")?;
+//! archive.record("NVDA", &articles)?;
+//! for point in archive.trend("NVDA", 30, Utc::now().date_naive()) {
+//!     println!("{} {:?}", point.date, point.average_score);
+//! }
+//! ```
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, PoisonError, RwLock};
+
+use crate::api::YahooFinanceClient;
+use crate::error::Result;
+use crate::interface::sparkline::sparkline;
+use crate::news::NewsItem;
+use crate::storage::{self, StoreCipher};
+
+/// Environment variable naming the file the shared archive is persisted to
+pub const NEWS_ARCHIVE_FILE_ENV: &str = "STOCK_NEWS_ARCHIVE_FILE";
+
+/// Articles kept per symbol; the oldest are dropped first
+pub const MAX_ARTICLES_PER_SYMBOL: usize = 2000;
+
+/// Fewest days with both sentiment and a price move for a correlation
+pub const MIN_CORRELATION_DAYS: usize = 3;
+
+/// An article as kept in the archive
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchivedArticle {
+    /// Headline
+    pub title: String,
+    /// Publisher
+    pub source: String,
+    /// Link to the full article
+    pub url: String,
+    /// When the article was published, if the provider said
+    pub published_at: Option<DateTime<Utc>>,
+    /// When the article was first fetched
+    pub seen_at: DateTime<Utc>,
+    /// Sentiment score from -1 (bearish) to 1 (bullish), if scored
+    pub sentiment_score: Option<f64>,
+    /// Provider the article came from
+    pub provider: String,
+}
+
+impl ArchivedArticle {
+    fn from_item(item: &NewsItem, seen_at: DateTime<Utc>) -> Self {
+        Self {
+            title: item.title.clone(),
+            source: item.source.clone(),
+            url: item.url.clone(),
+            published_at: item.published_at,
+            seen_at,
+            sentiment_score: item.sentiment_score,
+            provider: item.provider.to_string(),
+        }
+    }
+
+    /// Day the article counts towards: its publication date, or the day it
+    /// was first fetched when the provider gave none
+    pub fn date(&self) -> NaiveDate {
+        self.published_at.unwrap_or(self.seen_at).date_naive()
+    }
+
+    fn dedup_key(&self) -> String {
+        if self.url.is_empty() {
+            self.title.trim().to_lowercase()
+        } else {
+            self.url.clone()
+        }
+    }
+}
+
+/// Sentiment of one symbol's archived articles on one day
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SentimentPoint {
+    /// Day the articles were published
+    pub date: NaiveDate,
+    /// Articles published that day
+    pub article_count: usize,
+    /// Mean score of the scored articles; `None` when none were scored
+    pub average_score: Option<f64>,
+    /// Articles scoring above the positive threshold
+    pub positive: usize,
+    /// Articles scoring below the negative threshold
+    pub negative: usize,
+    /// Remaining articles
+    pub neutral: usize,
+}
+
+/// Persisted news articles per symbol
+pub struct NewsArchive {
+    articles: RwLock<BTreeMap<String, Vec<ArchivedArticle>>>,
+    path: Option<PathBuf>,
+    cipher: Option<StoreCipher>,
+}
+
+impl Default for NewsArchive {
+    fn default() -> Self {
+        Self::in_memory()
+    }
+}
+
+impl NewsArchive {
+    /// Create an archive that is not persisted
+    pub fn in_memory() -> Self {
+        Self {
+            articles: RwLock::new(BTreeMap::new()),
+            path: None,
+            cipher: None,
+        }
+    }
+
+    /// Open an archive persisted at `path`, loading existing articles
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        Self::open_with_cipher(path, None)
+    }
+
+    /// Open an archive persisted at `path`, encrypted with `cipher` if given
+    pub fn open_with_cipher(path: impl Into<PathBuf>, cipher: Option<StoreCipher>) -> Result<Self> {
+        let path = path.into();
+        let articles = match storage::read_store(&path, cipher.as_ref())? {
+            Some(json) => serde_json::from_str(&json)?,
+            None => BTreeMap::new(),
+        };
+
+        Ok(Self {
+            articles: RwLock::new(articles),
+            path: Some(path),
+            cipher,
+        })
+    }
+
+    /// Process-wide archive used by every [`NewsTool`](crate::tools::NewsTool)
+    ///
+    /// Persisted to the file named by `STOCK_NEWS_ARCHIVE_FILE`, encrypted
+    /// with the store key when one is configured. Without the variable, or
+    /// when the file cannot be read, the archive is kept in memory.
+    pub fn shared() -> Arc<Self> {
+        static SHARED: OnceLock<Arc<NewsArchive>> = OnceLock::new();
+        Arc::clone(SHARED.get_or_init(|| {
+            let Ok(path) = std::env::var(NEWS_ARCHIVE_FILE_ENV) else {
+                return Arc::new(Self::in_memory());
+            };
+            let archive = StoreCipher::from_env()
+                .and_then(|cipher| Self::open_with_cipher(&path, cipher))
+                .unwrap_or_else(|e| {
+                    tracing::warn!(
+                        "Keeping news archive in memory, cannot open {}: {}",
+                        path,
+                        e
+                    );
+                    Self::in_memory()
+                });
+            Arc::new(archive)
+        }))
+    }
+
+    /// File the archive is persisted to, if any
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Archive articles fetched for `symbol`, skipping ones already kept
+    ///
+    /// Returns how many articles were new.
+    pub fn record(&self, symbol: &str, items: &[NewsItem]) -> Result<usize> {
+        let added = {
+            let mut articles = self.write();
+            let kept = articles.entry(symbol.to_uppercase()).or_default();
+            let before = kept.len();
+            let now = Utc::now();
+            for item in items {
+                let article = ArchivedArticle::from_item(item, now);
+                let key = article.dedup_key();
+                if !kept.iter().any(|a| a.dedup_key() == key) {
+                    kept.push(article);
+                }
+            }
+            let added = kept.len() - before;
+
+            kept.sort_by_key(|a| a.published_at.unwrap_or(a.seen_at));
+            let excess = kept.len().saturating_sub(MAX_ARTICLES_PER_SYMBOL);
+            kept.drain(..excess);
+            added
+        };
+
+        if added > 0 {
+            self.save()?;
+        }
+        Ok(added)
+    }
+
+    /// Archived articles for `symbol`, oldest first
+    pub fn articles(&self, symbol: &str) -> Vec<ArchivedArticle> {
+        self.read()
+            .get(&symbol.to_uppercase())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Symbols with archived articles
+    pub fn symbols(&self) -> Vec<String> {
+        self.read().keys().cloned().collect()
+    }
+
+    /// Daily sentiment for `symbol` over the `days` days ending on `today`,
+    /// oldest first
+    ///
+    /// Days without articles are left out rather than reported as neutral.
+    pub fn trend(&self, symbol: &str, days: u32, today: NaiveDate) -> Vec<SentimentPoint> {
+        let since = today - Duration::days(i64::from(days.max(1)) - 1);
+
+        let mut by_day: BTreeMap<NaiveDate, Vec<ArchivedArticle>> = BTreeMap::new();
+        for article in self.articles(symbol) {
+            let date = article.date();
+            if (since..=today).contains(&date) {
+                by_day.entry(date).or_default().push(article);
+            }
+        }
+
+        by_day
+            .into_iter()
+            .map(|(date, articles)| {
+                let scores: Vec<f64> = articles.iter().filter_map(|a| a.sentiment_score).collect();
+                let label = |a: &ArchivedArticle| {
+                    crate::news::sentiment_label(a.sentiment_score.unwrap_or(0.0))
+                };
+                SentimentPoint {
+                    date,
+                    article_count: articles.len(),
+                    average_score: (!scores.is_empty())
+                        .then(|| scores.iter().sum::<f64>() / scores.len() as f64),
+                    positive: articles.iter().filter(|a| label(a) == "positive").count(),
+                    negative: articles.iter().filter(|a| label(a) == "negative").count(),
+                    neutral: articles.iter().filter(|a| label(a) == "neutral").count(),
+                }
+            })
+            .collect()
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, BTreeMap<String, Vec<ArchivedArticle>>> {
+        self.articles.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, BTreeMap<String, Vec<ArchivedArticle>>> {
+        self.articles
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let json = serde_json::to_string_pretty(&*self.read())?;
+        storage::write_store(path, &json, self.cipher.as_ref())
+    }
+}
+
+/// Daily closing prices, used to line sentiment up against price moves
+#[async_trait]
+pub trait DailyCloses: Send + Sync {
+    /// Close of `symbol` on each trading day from `from` to `to`, oldest first
+    async fn daily_closes(
+        &self,
+        symbol: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<(NaiveDate, f64)>>;
+}
+
+#[async_trait]
+impl DailyCloses for YahooFinanceClient {
+    async fn daily_closes(
+        &self,
+        symbol: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<(NaiveDate, f64)>> {
+        let start = from.and_time(chrono::NaiveTime::MIN).and_utc();
+        let end = (to + Duration::days(1))
+            .and_time(chrono::NaiveTime::MIN)
+            .and_utc();
+        let mut closes: Vec<(NaiveDate, f64)> = self
+            .get_historical_quotes(symbol, start, end)
+            .await?
+            .into_iter()
+            .map(|q| (q.timestamp.date_naive(), q.close))
+            .collect();
+        closes.sort_by_key(|(date, _)| *date);
+        closes.dedup_by_key(|(date, _)| *date);
+        Ok(closes)
+    }
+}
+
+/// How daily sentiment lined up with price moves
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SentimentCorrelation {
+    /// Pearson correlation of a day's sentiment with that day's return
+    pub same_day: Option<f64>,
+    /// Pearson correlation of a day's sentiment with the next trading day's return
+    pub next_day: Option<f64>,
+    /// Days with both a sentiment score and a same-day return
+    pub same_day_observations: usize,
+    /// Days with both a sentiment score and a next-day return
+    pub next_day_observations: usize,
+}
+
+impl SentimentCorrelation {
+    /// JSON for tool results, with a plain reading of the next-day figure
+    pub fn to_json(&self) -> Value {
+        json!({
+            "same_day": self.same_day,
+            "next_day": self.next_day,
+            "same_day_observations": self.same_day_observations,
+            "next_day_observations": self.next_day_observations,
+            "interpretation": interpret(self.next_day.or(self.same_day)),
+        })
+    }
+}
+
+/// Correlate daily sentiment with the returns in `closes` (oldest first)
+///
+/// A day's return is its close against the previous trading day's. News on a
+/// weekend or holiday counts towards the next trading day's return only.
+/// Correlations need [`MIN_CORRELATION_DAYS`] observations and are `None`
+/// below that or when either series is flat.
+pub fn correlate(points: &[SentimentPoint], closes: &[(NaiveDate, f64)]) -> SentimentCorrelation {
+    let index: HashMap<NaiveDate, usize> = closes
+        .iter()
+        .enumerate()
+        .map(|(i, (date, _))| (*date, i))
+        .collect();
+    let change = |from: usize, to: usize| {
+        let (base, close) = (closes[from].1, closes[to].1);
+        (base != 0.0).then(|| close / base - 1.0)
+    };
+
+    let mut same_day = (Vec::new(), Vec::new());
+    let mut next_day = (Vec::new(), Vec::new());
+    for point in points {
+        let Some(score) = point.average_score else {
+            continue;
+        };
+
+        if let Some(&i) = index.get(&point.date)
+            && i > 0
+            && let Some(ret) = change(i - 1, i)
+        {
+            same_day.0.push(score);
+            same_day.1.push(ret);
+        }
+
+        // Last close on or before the news, and the trading day after it
+        let before = closes.partition_point(|(date, _)| *date <= point.date);
+        if before > 0
+            && before < closes.len()
+            && let Some(ret) = change(before - 1, before)
+        {
+            next_day.0.push(score);
+            next_day.1.push(ret);
+        }
+    }
+
+    SentimentCorrelation {
+        same_day: pearson(&same_day.0, &same_day.1),
+        next_day: pearson(&next_day.0, &next_day.1),
+        same_day_observations: same_day.0.len(),
+        next_day_observations: next_day.0.len(),
+    }
+}
+
+/// Sparkline of the daily average scores in `points`
+pub fn trend_sparkline(points: &[SentimentPoint]) -> String {
+    let scores: Vec<f64> = points.iter().filter_map(|p| p.average_score).collect();
+    sparkline(&scores)
+}
+
+fn pearson(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    if xs.len() < MIN_CORRELATION_DAYS || xs.len() != ys.len() {
+        return None;
+    }
+    let n = xs.len() as f64;
+    let mean_x = xs.iter().sum::<f64>() / n;
+    let mean_y = ys.iter().sum::<f64>() / n;
+
+    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (x, y) in xs.iter().zip(ys) {
+        cov += (x - mean_x) * (y - mean_y);
+        var_x += (x - mean_x).powi(2);
+        var_y += (y - mean_y).powi(2);
+    }
+    if var_x == 0.0 || var_y == 0.0 {
+        return None;
+    }
+    Some(cov / (var_x * var_y).sqrt())
+}
+
+fn interpret(correlation: Option<f64>) -> &'static str {
+    match correlation {
+        None => "not enough history",
+        Some(r) if r >= 0.5 => "strong positive: price tended to follow sentiment",
+        Some(r) if r >= 0.2 => "weak positive",
+        Some(r) if r > -0.2 => "no clear relationship",
+        Some(r) if r > -0.5 => "weak negative",
+        Some(_) => "strong negative: price tended to move against sentiment",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(title: &str, days_ago: i64, score: Option<f64>) -> NewsItem {
+        NewsItem {
+            title: title.to_string(),
+            source: "Reuters".to_string(),
+            published_at: Some(Utc::now() - Duration::days(days_ago)),
+            summary: String::new(),
+            url: format!("https://example.com/{title}"),
+            image: None,
+            category: None,
+            sentiment_score: score,
+            topics: Vec::new(),
+            ticker_sentiment: Vec::new(),
+            provider: "Mock",
+        }
+    }
+
+    fn point(date: NaiveDate, score: f64) -> SentimentPoint {
+        SentimentPoint {
+            date,
+            article_count: 1,
+            average_score: Some(score),
+            positive: 0,
+            negative: 0,
+            neutral: 1,
+        }
+    }
+
+    #[test]
+    fn test_record_dedupes_and_builds_trend() {
+        let archive = NewsArchive::in_memory();
+        let first = [
+            item("a", 0, Some(0.4)),
+            item("b", 0, Some(-0.2)),
+            item("c", 2, None),
+        ];
+        assert_eq!(archive.record("nvda", &first).unwrap(), 3);
+        assert_eq!(archive.record("NVDA", &first[..1]).unwrap(), 0);
+        assert_eq!(
+            archive
+                .record("NVDA", &[item("old", 40, Some(0.9))])
+                .unwrap(),
+            1
+        );
+
+        let today = Utc::now().date_naive();
+        let trend = archive.trend("NVDA", 30, today);
+        assert_eq!(trend.len(), 2);
+
+        assert_eq!(trend[0].average_score, None);
+        assert_eq!(trend[0].neutral, 1);
+
+        assert_eq!(trend[1].date, today);
+        assert_eq!(trend[1].article_count, 2);
+        assert!((trend[1].average_score.unwrap() - 0.1).abs() < 1e-9);
+        assert_eq!((trend[1].positive, trend[1].negative), (1, 1));
+
+        assert!(archive.trend("AAPL", 30, today).is_empty());
+    }
+
+    #[test]
+    fn test_archive_persists() {
+        let path = std::env::temp_dir().join(format!("news-archive-{}.json", uuid::Uuid::new_v4()));
+
+        let archive = NewsArchive::open(&path).unwrap();
+        archive
+            .record("BTC-USD", &[item("halving", 1, Some(0.3))])
+            .unwrap();
+
+        let reopened = NewsArchive::open(&path).unwrap();
+        assert_eq!(reopened.symbols(), vec!["BTC-USD".to_string()]);
+        assert_eq!(reopened.articles("btc-usd")[0].title, "halving");
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_correlate_with_next_day_returns() {
+        let day = |d| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+        // Fri 1st through Fri 8th; the weekend has no closes
+        let closes = [
+            (day(1), 100.0),
+            (day(4), 102.0),
+            (day(5), 101.0),
+            (day(6), 104.0),
+            (day(7), 103.0),
+            (day(8), 106.0),
+        ];
+        // Sentiment leads price: good news, then a rise the next day
+        let points = [
+            point(day(3), 0.5),
+            point(day(4), -0.4),
+            point(day(5), 0.6),
+            point(day(6), -0.3),
+            point(day(7), 0.7),
+        ];
+
+        let correlation = correlate(&points, &closes);
+        assert_eq!(correlation.next_day_observations, 5);
+        assert!(correlation.next_day.unwrap() > 0.9);
+        assert_eq!(correlation.same_day_observations, 4);
+        assert!(correlation.same_day.unwrap() < -0.9);
+        assert_eq!(
+            correlation.to_json()["interpretation"],
+            "strong positive: price tended to follow sentiment"
+        );
+
+        let short = correlate(&points[..2], &closes);
+        assert_eq!(short.next_day, None);
+        assert_eq!(short.to_json()["interpretation"], "not enough history");
+    }
+}
@@ -0,0 +1,558 @@
+//! Watchlist news digest
+//!
+//! [`NewsDigestCollector`] fetches news for every watchlist symbol in
+//! parallel, clusters articles covering the same story across symbols and
+//! providers, and ranks the stories by their likely impact on the portfolio.
+//! The news analyzer turns the ranked stories into a single digest.
+//!
+//! [`NewsDigestJob`] runs the whole pipeline; `/news all` and the scheduled
+//! morning briefing both use it.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::news_digest::{NewsDigestJob, exposure_weights};
+//!
+//! let job = NewsDigestJob::new(agent, &config);
+//! let weights = exposure_weights(&["AAPL".to_string(), "NVDA".to_string()], &positions);
+//! println!("{}", job.run(&weights).await?);
+//! ```
+
+use agent_core::Context;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::sync::Arc;
+
+use crate::agents::StockAnalysisAgent;
+use crate::config::StockConfig;
+use crate::error::Result;
+use crate::news::{self, NewsItem, NewsProvider};
+use crate::portfolio::Position;
+use crate::source_translation::SourceTranslator;
+
+/// Articles fetched per symbol
+const ARTICLES_PER_SYMBOL: usize = 5;
+
+/// Stories passed to the digest
+pub const MAX_STORIES: usize = 10;
+
+/// Share of distinct title words two headlines need to be the same story
+const SAME_STORY_SIMILARITY: f64 = 0.6;
+
+/// Extra weight of a held symbol, scaled by its share of the portfolio cost
+const HOLDING_WEIGHT: f64 = 3.0;
+
+/// Stories older than this count half
+const FRESH_HOURS: i64 = 48;
+
+/// Words ignored when comparing headlines
+const STOP_WORDS: &[&str] = &[
+    "the", "and", "for", "with", "from", "after", "over", "into", "its", "are", "has", "new",
+    "says", "stock", "stocks", "shares",
+];
+
+/// Weight of each symbol in the digest
+///
+/// Every symbol weighs 1; held positions add up to [`HOLDING_WEIGHT`] by
+/// their share of the portfolio's cost, so news on the largest holdings
+/// ranks first.
+pub fn exposure_weights(watchlist: &[String], positions: &[Position]) -> Vec<(String, f64)> {
+    let total_cost: f64 = positions.iter().map(Position::cost).sum();
+    let mut weights: Vec<(String, f64)> = Vec::new();
+    let symbols = positions
+        .iter()
+        .map(|p| p.symbol.as_str())
+        .chain(watchlist.iter().map(String::as_str));
+    for symbol in symbols {
+        let symbol = symbol.to_uppercase();
+        if weights.iter().any(|(s, _)| *s == symbol) {
+            continue;
+        }
+        let cost: f64 = positions
+            .iter()
+            .filter(|p| p.symbol.eq_ignore_ascii_case(&symbol))
+            .map(Position::cost)
+            .sum();
+        let share = if total_cost > 0.0 {
+            cost / total_cost
+        } else {
+            0.0
+        };
+        weights.push((symbol, 1.0 + HOLDING_WEIGHT * share));
+    }
+    weights
+}
+
+/// Articles about the same event, from one or more symbols and sources
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DigestStory {
+    /// Headline of the first article seen
+    pub title: String,
+    /// Headline in the response language, when sources are translated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title_translated: Option<String>,
+    pub sources: Vec<String>,
+    pub url: String,
+    pub published_at: Option<DateTime<Utc>>,
+    /// Watchlist symbols the story was fetched for
+    pub symbols: Vec<String>,
+    /// Mean provider sentiment from -1 to 1, when scored
+    pub sentiment_score: Option<f64>,
+    /// Number of distinct articles covering the story
+    pub articles: usize,
+    /// Likely portfolio impact; higher ranks first
+    pub impact: f64,
+}
+
+impl DigestStory {
+    fn from_article(symbol: &str, item: &NewsItem) -> Self {
+        Self {
+            title: item.title.clone(),
+            title_translated: None,
+            sources: vec![item.source.clone()],
+            url: item.url.clone(),
+            published_at: item.published_at,
+            symbols: vec![symbol.to_string()],
+            sentiment_score: item.sentiment_score,
+            articles: 1,
+            impact: 0.0,
+        }
+    }
+
+    /// Impact from the symbols' weights, sentiment strength, coverage and age
+    pub fn score(&self, weights: &HashMap<String, f64>, now: DateTime<Utc>) -> f64 {
+        let exposure: f64 = self
+            .symbols
+            .iter()
+            .map(|s| weights.get(s).copied().unwrap_or(1.0))
+            .sum();
+        let tone = 1.0 + self.sentiment_score.map_or(0.0, f64::abs);
+        let coverage = 1.0 + 0.25 * (self.articles.saturating_sub(1) as f64);
+        let freshness = match self.published_at {
+            Some(at) if now - at > Duration::hours(FRESH_HOURS) => 0.5,
+            _ => 1.0,
+        };
+        exposure * tone * coverage * freshness
+    }
+}
+
+/// Lowercase title words that tell headlines apart
+fn title_words(title: &str) -> BTreeSet<String> {
+    title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() > 2)
+        .map(str::to_lowercase)
+        .filter(|w| !STOP_WORDS.contains(&w.as_str()))
+        .collect()
+}
+
+/// Jaccard similarity of two headlines' words
+fn similarity(a: &BTreeSet<String>, b: &BTreeSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// A story being built up from articles, with what its headline is matched on
+struct Cluster {
+    story: DigestStory,
+    words: BTreeSet<String>,
+    tickers: BTreeSet<String>,
+    urls: BTreeSet<String>,
+    scores: Vec<f64>,
+}
+
+impl Cluster {
+    /// Whether an article with `url`, title `words` and named `tickers` is this story
+    ///
+    /// Headlines naming different watchlist tickers ("AAPL earnings" and
+    /// "MSFT earnings") are different stories however alike they read.
+    fn matches(&self, url: &str, words: &BTreeSet<String>, tickers: &BTreeSet<String>) -> bool {
+        if self.urls.contains(url) {
+            return true;
+        }
+        let other_company =
+            !self.tickers.is_empty() && !tickers.is_empty() && self.tickers.is_disjoint(tickers);
+        !other_company && similarity(&self.words, words) >= SAME_STORY_SIMILARITY
+    }
+}
+
+/// Group articles covering the same story
+///
+/// Articles with the same URL, or headlines sharing most of their words,
+/// become one story listing every symbol and source that carried it.
+pub fn cluster(articles: &[(String, NewsItem)]) -> Vec<DigestStory> {
+    let symbols: BTreeSet<String> = articles.iter().map(|(s, _)| s.to_lowercase()).collect();
+    let mut clusters: Vec<Cluster> = Vec::new();
+    for (symbol, item) in articles {
+        let words = title_words(&item.title);
+        let tickers: BTreeSet<String> = words.intersection(&symbols).cloned().collect();
+        match clusters
+            .iter_mut()
+            .find(|c| c.matches(&item.url, &words, &tickers))
+        {
+            Some(cluster) => {
+                let story = &mut cluster.story;
+                if !story.symbols.contains(symbol) {
+                    story.symbols.push(symbol.clone());
+                }
+                if cluster.urls.insert(item.url.clone()) {
+                    story.articles += 1;
+                    if !story.sources.contains(&item.source) {
+                        story.sources.push(item.source.clone());
+                    }
+                    cluster.scores.extend(item.sentiment_score);
+                }
+                story.published_at = story.published_at.max(item.published_at);
+            }
+            None => clusters.push(Cluster {
+                story: DigestStory::from_article(symbol, item),
+                words,
+                tickers,
+                urls: BTreeSet::from([item.url.clone()]),
+                scores: item.sentiment_score.into_iter().collect(),
+            }),
+        }
+    }
+
+    clusters
+        .into_iter()
+        .map(
+            |Cluster {
+                 mut story, scores, ..
+             }| {
+                if !scores.is_empty() {
+                    story.sentiment_score = Some(scores.iter().sum::<f64>() / scores.len() as f64);
+                }
+                story
+            },
+        )
+        .collect()
+}
+
+/// Score stories against `weights` and order them by impact, highest first
+pub fn rank(stories: &mut [DigestStory], weights: &[(String, f64)], now: DateTime<Utc>) {
+    let weights: HashMap<String, f64> = weights.iter().cloned().collect();
+    for story in stories.iter_mut() {
+        story.impact = story.score(&weights, now);
+    }
+    stories.sort_by(|a, b| b.impact.total_cmp(&a.impact));
+}
+
+/// Everything a news digest is written from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewsDigestData {
+    pub date: NaiveDate,
+    /// Symbols covered, with their weight in the ranking
+    pub weights: Vec<(String, f64)>,
+    /// Top stories, highest impact first
+    pub stories: Vec<DigestStory>,
+    /// Symbols whose news could not be fetched
+    pub unavailable: Vec<String>,
+}
+
+impl NewsDigestData {
+    /// Data as passed to the digest prompt
+    pub fn to_prompt_json(&self) -> Value {
+        let stories: Vec<Value> = self
+            .stories
+            .iter()
+            .map(|s| {
+                json!({
+                    "title": s.title,
+                    "title_translated": s.title_translated,
+                    "symbols": s.symbols,
+                    "sources": s.sources,
+                    "articles": s.articles,
+                    "sentiment_score": s.sentiment_score,
+                    "published_at": s.published_at.map(|at| at.to_rfc3339()),
+                    "impact": (s.impact * 100.0).round() / 100.0,
+                })
+            })
+            .collect();
+        json!({
+            "date": self.date.to_string(),
+            "symbols": self.weights.iter().map(|(s, _)| s).collect::<Vec<_>>(),
+            "stories": stories,
+            "unavailable": self.unavailable,
+        })
+    }
+}
+
+/// A generated news digest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewsDigest {
+    pub generated_at: DateTime<Utc>,
+    pub data: NewsDigestData,
+    pub narrative: String,
+}
+
+impl fmt::Display for NewsDigest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "🗞️ News Digest — {}", self.data.date)?;
+        writeln!(f)?;
+        write!(f, "{}", self.narrative.trim_end())
+    }
+}
+
+/// Fetches, clusters and ranks the news for a watchlist
+///
+/// Fetching is best effort: symbols whose news fails are listed as
+/// unavailable and left out.
+pub struct NewsDigestCollector {
+    provider: Arc<dyn NewsProvider>,
+    translator: Option<Arc<SourceTranslator>>,
+}
+
+impl NewsDigestCollector {
+    /// Create a collector using the configured news provider
+    pub fn new(config: &StockConfig) -> Self {
+        Self::with_provider(news::from_config(config))
+    }
+
+    /// Create a collector reading from `provider`
+    pub fn with_provider(provider: Arc<dyn NewsProvider>) -> Self {
+        Self {
+            provider,
+            translator: None,
+        }
+    }
+
+    /// Translate story headlines with `translator`
+    pub fn with_translator(mut self, translator: Option<Arc<SourceTranslator>>) -> Self {
+        self.translator = translator;
+        self
+    }
+
+    /// Collect the top stories for the weighted symbols
+    pub async fn collect(&self, weights: &[(String, f64)]) -> NewsDigestData {
+        let fetches = weights.iter().map(|(symbol, _)| async move {
+            (
+                symbol,
+                self.provider
+                    .get_company_news(symbol, ARTICLES_PER_SYMBOL)
+                    .await,
+            )
+        });
+
+        let mut articles = Vec::new();
+        let mut unavailable = Vec::new();
+        for (symbol, result) in join_all(fetches).await {
+            match result {
+                Ok(items) => articles.extend(items.into_iter().map(|item| (symbol.clone(), item))),
+                Err(e) => {
+                    tracing::debug!("No news for {} in digest: {}", symbol, e);
+                    unavailable.push(symbol.clone());
+                }
+            }
+        }
+
+        let now = Utc::now();
+        let mut stories = cluster(&articles);
+        rank(&mut stories, weights, now);
+        stories.truncate(MAX_STORIES);
+        if let Some(translator) = &self.translator {
+            let titles: Vec<String> = stories.iter().map(|s| s.title.clone()).collect();
+            match translator.translate(&titles).await {
+                Ok(translations) => {
+                    for (story, translation) in stories.iter_mut().zip(translations) {
+                        story.title_translated = Some(translation);
+                    }
+                }
+                Err(e) => tracing::warn!("Digest headlines left untranslated: {}", e),
+            }
+        }
+
+        NewsDigestData {
+            date: now.date_naive(),
+            weights: weights.to_vec(),
+            stories,
+            unavailable,
+        }
+    }
+}
+
+/// Collects the watchlist's news and writes the digest
+pub struct NewsDigestJob {
+    agent: Arc<StockAnalysisAgent>,
+    collector: NewsDigestCollector,
+}
+
+impl NewsDigestJob {
+    /// Create a job writing digests with `agent`
+    pub fn new(agent: Arc<StockAnalysisAgent>, config: &StockConfig) -> Self {
+        Self {
+            agent,
+            collector: NewsDigestCollector::new(config),
+        }
+    }
+
+    /// Generate a digest for the weighted symbols (see [`exposure_weights`])
+    pub async fn run(&self, weights: &[(String, f64)]) -> Result<NewsDigest> {
+        let data = self.collector.collect(weights).await;
+        let narrative = self
+            .agent
+            .analyze_news_digest(&data, &mut Context::new())
+            .await?;
+        Ok(NewsDigest {
+            generated_at: Utc::now(),
+            data,
+            narrative,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(title: &str, url: &str, hours_ago: i64, score: Option<f64>) -> NewsItem {
+        NewsItem {
+            title: title.to_string(),
+            source: "Wire".to_string(),
+            published_at: Some(now() - Duration::hours(hours_ago)),
+            summary: String::new(),
+            url: url.to_string(),
+            image: None,
+            category: None,
+            sentiment_score: score,
+            topics: Vec::new(),
+            ticker_sentiment: Vec::new(),
+            provider: "Fixed",
+        }
+    }
+
+    fn now() -> DateTime<Utc> {
+        DateTime::from_timestamp(1_710_000_000, 0).unwrap()
+    }
+
+    fn position(symbol: &str, quantity: f64, cost_basis: f64) -> Position {
+        Position {
+            symbol: symbol.to_string(),
+            quantity,
+            cost_basis,
+            opened_at: now(),
+        }
+    }
+
+    #[test]
+    fn test_exposure_weights() {
+        let positions = [position("NVDA", 10.0, 75.0), position("AAPL", 5.0, 50.0)];
+        let weights = exposure_weights(&["aapl".to_string(), "MSFT".to_string()], &positions);
+        let symbols: Vec<_> = weights.iter().map(|(s, _)| s.as_str()).collect();
+        assert_eq!(symbols, ["NVDA", "AAPL", "MSFT"]);
+        assert!((weights[0].1 - 3.25).abs() < 1e-9);
+        assert!((weights[1].1 - 1.75).abs() < 1e-9);
+        assert!((weights[2].1 - 1.0).abs() < 1e-9);
+
+        assert!(exposure_weights(&[], &[]).is_empty());
+    }
+
+    #[test]
+    fn test_cluster_same_story() {
+        let articles = vec![
+            (
+                "AAPL".to_string(),
+                item("Apple unveils new AI chip", "a1", 1, Some(0.6)),
+            ),
+            (
+                "NVDA".to_string(),
+                item(
+                    "Apple unveils AI chip, challenging Nvidia",
+                    "n1",
+                    2,
+                    Some(0.2),
+                ),
+            ),
+            (
+                "NVDA".to_string(),
+                item("Nvidia beats earnings estimates", "n2", 3, None),
+            ),
+            // The same article fetched for another symbol is not extra coverage
+            (
+                "MSFT".to_string(),
+                item("Nvidia beats earnings estimates", "n2", 3, None),
+            ),
+        ];
+        let stories = cluster(&articles);
+        assert_eq!(stories.len(), 2);
+
+        assert_eq!(stories[0].symbols, ["AAPL", "NVDA"]);
+        assert_eq!(stories[0].articles, 2);
+        assert!((stories[0].sentiment_score.unwrap() - 0.4).abs() < 1e-9);
+
+        assert_eq!(stories[1].symbols, ["NVDA", "MSFT"]);
+        assert_eq!(stories[1].articles, 1);
+        assert!(stories[1].sentiment_score.is_none());
+    }
+
+    #[test]
+    fn test_distinct_headlines_stay_apart() {
+        let articles = vec![
+            (
+                "AAPL".to_string(),
+                item("AAPL Stock Analysis Update", "a1", 1, None),
+            ),
+            (
+                "MSFT".to_string(),
+                item("MSFT Stock Analysis Update", "m1", 1, None),
+            ),
+            (
+                "AAPL".to_string(),
+                item("AAPL Quarterly Earnings Report", "a2", 1, None),
+            ),
+            (
+                "MSFT".to_string(),
+                item("MSFT Quarterly Earnings Report", "m2", 1, None),
+            ),
+        ];
+        assert_eq!(cluster(&articles).len(), 4);
+    }
+
+    #[test]
+    fn test_rank_by_portfolio_impact() {
+        let articles = vec![
+            (
+                "MSFT".to_string(),
+                item("Microsoft renews cloud deal", "m1", 1, Some(0.1)),
+            ),
+            (
+                "NVDA".to_string(),
+                item("Nvidia export curbs tightened", "n1", 1, Some(-0.8)),
+            ),
+            (
+                "NVDA".to_string(),
+                item("Old Nvidia supplier story", "n2", 100, Some(-0.8)),
+            ),
+        ];
+        let mut stories = cluster(&articles);
+        let weights = vec![("NVDA".to_string(), 3.0), ("MSFT".to_string(), 1.0)];
+        rank(&mut stories, &weights, now());
+
+        assert_eq!(stories[0].title, "Nvidia export curbs tightened");
+        assert!((stories[0].impact - 5.4).abs() < 1e-9);
+        // Stale news counts half
+        assert_eq!(stories[1].title, "Old Nvidia supplier story");
+        assert!((stories[1].impact - 2.7).abs() < 1e-9);
+        assert!((stories[2].impact - 1.1).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_collect_mock_news() {
+        let collector = NewsDigestCollector::with_provider(Arc::new(news::MockNewsProvider));
+        let weights = exposure_weights(&["AAPL".to_string(), "MSFT".to_string()], &[]);
+        let data = collector.collect(&weights).await;
+
+        assert_eq!(data.stories.len(), 4);
+        assert!(data.unavailable.is_empty());
+        let prompt = data.to_prompt_json();
+        assert_eq!(prompt["symbols"], json!(["AAPL", "MSFT"]));
+        assert_eq!(prompt["stories"].as_array().unwrap().len(), 4);
+    }
+}
@@ -0,0 +1,559 @@
+//! Notebook export of a conversation
+//!
+//! Quant users often want to pick up where the bot left off: dig into the
+//! data behind an answer or chart the symbols that came up.
+//! [`NotebookExporter`] turns a [`ConversationRecord`] into a self-contained
+//! Jupyter notebook (`.ipynb`) with one section per exchange: the question
+//! and the bot's answer. The session itself (every exchange with its
+//! symbols and time, and the daily prices behind the charts) is embedded as
+//! JSON in a code cell that loads it into `SESSION` (`session` in Rust), and each symbol's price
+//! chart is embedded as a PNG, so the notebook opens and runs offline.
+//!
+//! [`NotebookKind::Python`] notebooks load the prices into pandas;
+//! [`NotebookKind::Rust`] notebooks run on the evcxr kernel with
+//! `serde_json`.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::notebook::{self, NotebookExporter, NotebookKind};
+//!
+//! let record = ConversationRecord::from_manager(&conversation);
+//! let symbols = notebook::session_symbols(&record);
+//! let charts = notebook::fetch_charts(&YahooFinanceClient::new(), &symbols).await;
+//! let exporter = NotebookExporter::new().with_kind(NotebookKind::Rust).with_charts(charts);
+//! std::fs::write(exporter.filename(&record), exporter.to_json(&record)?)?;
+//! ```
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde_json::{Value, json};
+
+use crate::api::YahooFinanceClient;
+use crate::conversation_store::ConversationRecord;
+use crate::engine::AnalysisContext;
+use crate::error::Result;
+use crate::interface::chart_render::{self, Candle, PriceChart};
+use crate::interface::interface::{Attachment, AttachmentType};
+
+/// MIME type of exported notebooks
+pub const NOTEBOOK_MIME_TYPE: &str = "application/x-ipynb+json";
+
+/// Price history embedded for each symbol of the session
+const CHART_RANGE: &str = "6mo";
+
+/// Size of the embedded chart images in pixels
+const CHART_SIZE: (u32, u32) = (800, 600);
+
+/// Language of a notebook's code cells
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NotebookKind {
+    /// Python 3 kernel, with the prices in pandas
+    #[default]
+    Python,
+    /// evcxr Rust kernel, with the session in `serde_json`
+    Rust,
+}
+
+impl NotebookKind {
+    /// Parse a kind name, e.g. "python", "jupyter" or "evcxr"
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "python" | "py" | "jupyter" | "ipynb" => Some(Self::Python),
+            "rust" | "rs" | "evcxr" => Some(Self::Rust),
+            _ => None,
+        }
+    }
+
+    fn metadata(self) -> Value {
+        match self {
+            Self::Python => json!({
+                "kernelspec": { "display_name": "Python 3", "language": "python", "name": "python3" },
+                "language_info": { "name": "python", "file_extension": ".py" },
+            }),
+            Self::Rust => json!({
+                "kernelspec": { "display_name": "Rust", "language": "rust", "name": "rust" },
+                "language_info": { "name": "Rust", "file_extension": ".rs" },
+            }),
+        }
+    }
+}
+
+/// Converts conversations into self-contained notebooks
+#[derive(Debug, Clone, Default)]
+pub struct NotebookExporter {
+    kind: NotebookKind,
+    charts: Vec<PriceChart>,
+}
+
+impl NotebookExporter {
+    /// Exporter of Python notebooks without price charts
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write code cells in `kind`'s language
+    pub fn with_kind(mut self, kind: NotebookKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Embed the prices and a chart image of each of `charts`
+    pub fn with_charts(mut self, charts: Vec<PriceChart>) -> Self {
+        self.charts = charts;
+        self
+    }
+
+    /// Notebook language
+    pub fn kind(&self) -> NotebookKind {
+        self.kind
+    }
+
+    /// The notebook of `record`, in nbformat 4
+    pub fn export(&self, record: &ConversationRecord) -> Value {
+        let symbols = session_symbols(record);
+        let mut cells = vec![
+            markdown(&self.title(record, &symbols)),
+            code(&self.setup(record, &symbols)),
+        ];
+        for (number, turn) in record.turns.iter().enumerate() {
+            let question = turn
+                .user_input
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ");
+            cells.push(markdown(&format!("## {}. {question}", number + 1)));
+            cells.push(markdown(&format!(
+                "**Answer**\n\n{}",
+                turn.assistant_response.trim()
+            )));
+        }
+        if !self.charts.is_empty() {
+            cells.push(markdown("## Price history"));
+            for chart in &self.charts {
+                match chart_render::render_png(chart, false, CHART_SIZE) {
+                    Ok(png) => cells.push(image(&chart.symbol, &png)),
+                    Err(e) => tracing::warn!("No notebook chart for {}: {}", chart.symbol, e),
+                }
+            }
+            cells.push(code(self.prices()));
+        }
+        for (index, cell) in cells.iter_mut().enumerate() {
+            cell["id"] = json!(format!("cell-{}", index + 1));
+        }
+
+        json!({
+            "cells": cells,
+            "metadata": self.kind.metadata(),
+            "nbformat": 4,
+            "nbformat_minor": 5,
+        })
+    }
+
+    /// The notebook of `record` as `.ipynb` file contents
+    pub fn to_json(&self, record: &ConversationRecord) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&self.export(record))?)
+    }
+
+    /// File name for the notebook of `record`, e.g. `session-20241015-0930.ipynb`
+    pub fn filename(&self, record: &ConversationRecord) -> String {
+        format!("session-{}.ipynb", record.updated_at.format("%Y%m%d-%H%M"))
+    }
+
+    /// The notebook of `record` as a document attachment
+    pub fn to_attachment(&self, record: &ConversationRecord) -> Result<Attachment> {
+        Ok(Attachment {
+            attachment_type: AttachmentType::Document,
+            content: self.to_json(record)?.into_bytes(),
+            filename: Some(self.filename(record)),
+            mime_type: NOTEBOOK_MIME_TYPE.to_string(),
+        })
+    }
+
+    fn title(&self, record: &ConversationRecord, symbols: &[String]) -> String {
+        let mut title = format!(
+            "# Stock analysis session\n\nExported {} with {} exchange(s)",
+            record.updated_at.format("%Y-%m-%d %H:%M UTC"),
+            record.turns.len()
+        );
+        if !symbols.is_empty() {
+            title.push_str(&format!(" about {}", symbols.join(", ")));
+        }
+        title.push_str(
+            ".\n\nThe next cell loads the session data: each exchange under `turns`, \
+             and the daily prices behind the charts under `prices`.",
+        );
+        title
+    }
+
+    /// Cell loading the embedded session data into `SESSION`
+    fn setup(&self, record: &ConversationRecord, symbols: &[String]) -> String {
+        let session = json!({
+            "exported_at": record.updated_at.to_rfc3339(),
+            "symbols": symbols,
+            "turns": record.turns.iter().map(|turn| json!({
+                "question": turn.user_input,
+                "answer": turn.assistant_response,
+                "symbols": turn.symbols,
+                "timestamp": turn.timestamp.to_rfc3339(),
+            })).collect::<Vec<_>>(),
+            "prices": self.charts.iter().map(|chart| {
+                (chart.symbol.clone(), chart.candles.iter().map(candle_json).collect::<Value>())
+            }).collect::<serde_json::Map<_, _>>(),
+        });
+        let session = session.to_string();
+        match self.kind {
+            NotebookKind::Python => format!(
+                "import json\n\nSESSION = json.loads({})\n[turn[\"question\"] for turn in SESSION[\"turns\"]]",
+                python_literal(&session)
+            ),
+            NotebookKind::Rust => format!(
+                ":dep serde_json = \"1\"\n\n\
+                 let session: serde_json::Value = serde_json::from_str({}).unwrap();\n\
+                 session[\"turns\"].as_array().map(Vec::len)",
+                rust_literal(&session)
+            ),
+        }
+    }
+
+    /// Cell turning the embedded prices into tables
+    fn prices(&self) -> &'static str {
+        match self.kind {
+            NotebookKind::Python => {
+                "import pandas as pd\n\n\
+                 prices = {\n    \
+                 symbol: pd.DataFrame(candles).set_index(\"timestamp\")\n    \
+                 for symbol, candles in SESSION[\"prices\"].items()\n\
+                 }\n\
+                 pd.DataFrame({symbol: table[\"close\"] for symbol, table in prices.items()}).plot(title=\"Close\", figsize=(10, 4))"
+            }
+            NotebookKind::Rust => {
+                "let closes: Vec<(String, Vec<f64>)> = session[\"prices\"]\n    \
+                 .as_object()\n    \
+                 .into_iter()\n    \
+                 .flatten()\n    \
+                 .map(|(symbol, candles)| {\n        \
+                 let closes = candles.as_array().into_iter().flatten().filter_map(|c| c[\"close\"].as_f64());\n        \
+                 (symbol.clone(), closes.collect())\n    \
+                 })\n    \
+                 .collect();\n\
+                 closes.iter().map(|(symbol, closes)| (symbol, closes.last())).collect::<Vec<_>>()"
+            }
+        }
+    }
+}
+
+/// Daily price charts of `symbols` to embed in a notebook
+///
+/// Symbols whose prices cannot be fetched are left out.
+pub async fn fetch_charts(client: &YahooFinanceClient, symbols: &[String]) -> Vec<PriceChart> {
+    let mut charts = Vec::new();
+    for symbol in symbols {
+        match client.get_historical_range(symbol, CHART_RANGE).await {
+            Ok(quotes) if quotes.len() >= 2 => charts.push(PriceChart {
+                symbol: symbol.clone(),
+                candles: quotes
+                    .iter()
+                    .map(|quote| Candle {
+                        timestamp: Some(quote.timestamp),
+                        open: quote.open,
+                        high: quote.high,
+                        low: quote.low,
+                        close: quote.close,
+                    })
+                    .collect(),
+                ohlc: true,
+            }),
+            Ok(_) => tracing::debug!("Not enough prices to chart {} in a notebook", symbol),
+            Err(e) => tracing::warn!("No notebook prices for {}: {}", symbol, e),
+        }
+    }
+    charts
+}
+
+/// Reply to `/notebook` on a chat platform: a caption and the notebook of
+/// the session in `context` with price charts of its symbols, or a note
+/// when there is nothing to export yet
+pub async fn command_reply(
+    context: &AnalysisContext,
+    kind: NotebookKind,
+) -> Result<(String, Option<Attachment>)> {
+    let record = ConversationRecord::from_analysis_context(context);
+    if record.turns.is_empty() {
+        return Ok(("📓 Nothing to export yet.".to_string(), None));
+    }
+    let charts = fetch_charts(&YahooFinanceClient::new(), &session_symbols(&record)).await;
+    notebook_reply(&record, kind, charts)
+}
+
+fn notebook_reply(
+    record: &ConversationRecord,
+    kind: NotebookKind,
+    charts: Vec<PriceChart>,
+) -> Result<(String, Option<Attachment>)> {
+    let notebook = NotebookExporter::new()
+        .with_kind(kind)
+        .with_charts(charts)
+        .to_attachment(record)?;
+    Ok((
+        format!(
+            "📓 This session as a notebook, {} exchange(s)",
+            record.turns.len()
+        ),
+        Some(notebook),
+    ))
+}
+
+/// Symbols of the whole session, in order of first mention
+pub fn session_symbols(record: &ConversationRecord) -> Vec<String> {
+    let mut symbols: Vec<String> = Vec::new();
+    let mentioned = record
+        .turns
+        .iter()
+        .flat_map(|turn| &turn.symbols)
+        .chain(&record.context.recent_symbols);
+    for symbol in mentioned {
+        if !symbols.contains(symbol) {
+            symbols.push(symbol.clone());
+        }
+    }
+    symbols
+}
+
+fn candle_json(candle: &Candle) -> Value {
+    json!({
+        "timestamp": candle.timestamp.map(|timestamp| timestamp.to_rfc3339()),
+        "open": candle.open,
+        "high": candle.high,
+        "low": candle.low,
+        "close": candle.close,
+    })
+}
+
+/// `text` as a Python string literal
+fn python_literal(text: &str) -> String {
+    Value::from(text).to_string()
+}
+
+/// `text` as a Rust raw string literal
+fn rust_literal(text: &str) -> String {
+    let mut hashes = 1;
+    while text.contains(&format!("\"{}", "#".repeat(hashes))) {
+        hashes += 1;
+    }
+    let hashes = "#".repeat(hashes);
+    format!("r{hashes}\"{text}\"{hashes}")
+}
+
+/// `source` split into nbformat's list of lines
+fn source_lines(source: &str) -> Vec<String> {
+    source.split_inclusive('\n').map(str::to_string).collect()
+}
+
+fn markdown(source: &str) -> Value {
+    json!({
+        "cell_type": "markdown",
+        "metadata": {},
+        "source": source_lines(source),
+    })
+}
+
+/// Markdown cell showing `png`, embedded as a cell attachment
+fn image(symbol: &str, png: &[u8]) -> Value {
+    let name = format!("{symbol}.png");
+    json!({
+        "cell_type": "markdown",
+        "metadata": {},
+        "attachments": { &name: { "image/png": BASE64.encode(png) } },
+        "source": source_lines(&format!("### {symbol}\n\n![{symbol}](attachment:{name})")),
+    })
+}
+
+fn code(source: &str) -> Value {
+    json!({
+        "cell_type": "code",
+        "execution_count": null,
+        "metadata": {},
+        "outputs": [],
+        "source": source_lines(source),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::{ConversationContext, ConversationTurn};
+
+    fn record() -> ConversationRecord {
+        ConversationRecord {
+            context: ConversationContext {
+                current_symbol: Some("NVDA".into()),
+                last_analysis_type: None,
+                recent_symbols: vec!["AAPL".into(), "NVDA".into()],
+            },
+            turns: vec![
+                ConversationTurn::new(
+                    "/analyze AAPL".into(),
+                    "AAPL looks strong.".into(),
+                    vec!["AAPL".into()],
+                ),
+                ConversationTurn::new(
+                    "What is a P/E ratio?".into(),
+                    "Price over earnings.".into(),
+                    vec![],
+                ),
+                ConversationTurn::new(
+                    "/compare AAPL NVDA".into(),
+                    "NVDA grows faster.".into(),
+                    vec!["AAPL".into(), "NVDA".into()],
+                ),
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn chart(symbol: &str) -> PriceChart {
+        let candles = (0..60)
+            .map(|i| Candle::from_close(None, 100.0 + f64::from(i % 7) - f64::from(i) / 10.0))
+            .collect();
+        PriceChart {
+            symbol: symbol.to_string(),
+            candles,
+            ohlc: false,
+        }
+    }
+
+    fn sources(notebook: &Value) -> Vec<String> {
+        notebook["cells"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|cell| {
+                let lines: Vec<&str> = cell["source"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|l| l.as_str().unwrap())
+                    .collect();
+                format!(
+                    "{}: {}",
+                    cell["cell_type"].as_str().unwrap(),
+                    lines.concat()
+                )
+            })
+            .collect()
+    }
+
+    /// The JSON the setup cell of a Python notebook loads
+    fn embedded_session(source: &str) -> Value {
+        let literal = source
+            .lines()
+            .find_map(|line| line.strip_prefix("SESSION = json.loads("))
+            .and_then(|rest| rest.strip_suffix(')'))
+            .unwrap();
+        let text: String = serde_json::from_str(literal).unwrap();
+        serde_json::from_str(&text).unwrap()
+    }
+
+    #[test]
+    fn test_parse_kind() {
+        assert_eq!(NotebookKind::parse("Jupyter"), Some(NotebookKind::Python));
+        assert_eq!(NotebookKind::parse("evcxr"), Some(NotebookKind::Rust));
+        assert_eq!(NotebookKind::parse("julia"), None);
+    }
+
+    #[test]
+    fn test_python_notebook_embeds_session() {
+        let notebook = NotebookExporter::new().export(&record());
+        assert_eq!(notebook["nbformat"], 4);
+        assert_eq!(notebook["metadata"]["kernelspec"]["name"], "python3");
+        assert_eq!(notebook["cells"][0]["id"], "cell-1");
+
+        let sources = sources(&notebook);
+        // Title, session data, then a question and an answer per exchange
+        assert_eq!(sources.len(), 8);
+        assert!(
+            sources[0].contains("3 exchange(s) about AAPL, NVDA"),
+            "{}",
+            sources[0]
+        );
+        assert!(sources[1].starts_with("code: import json\n"));
+        assert!(!sources.concat().contains("/jobs"));
+        assert_eq!(sources[2], "markdown: ## 1. /analyze AAPL");
+        assert_eq!(sources[3], "markdown: **Answer**\n\nAAPL looks strong.");
+        assert_eq!(sources[4], "markdown: ## 2. What is a P/E ratio?");
+
+        let session = embedded_session(&sources[1]);
+        assert_eq!(session["symbols"], json!(["AAPL", "NVDA"]));
+        assert_eq!(session["turns"][2]["answer"], "NVDA grows faster.");
+        assert_eq!(session["turns"][2]["symbols"], json!(["AAPL", "NVDA"]));
+        assert_eq!(session["prices"], json!({}));
+    }
+
+    #[test]
+    fn test_charts_embedded_as_images() {
+        let notebook = NotebookExporter::new()
+            .with_charts(vec![chart("AAPL"), chart("NVDA")])
+            .export(&record());
+        let cells = notebook["cells"].as_array().unwrap();
+        let sources = sources(&notebook);
+        assert_eq!(sources.len(), 12);
+        assert_eq!(sources[8], "markdown: ## Price history");
+        assert_eq!(
+            sources[9],
+            "markdown: ### AAPL\n\n![AAPL](attachment:AAPL.png)"
+        );
+        let png = BASE64
+            .decode(
+                cells[9]["attachments"]["AAPL.png"]["image/png"]
+                    .as_str()
+                    .unwrap(),
+            )
+            .unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+        assert!(sources[11].contains("pd.DataFrame(candles)"));
+
+        let prices = &embedded_session(&sources[1])["prices"];
+        assert_eq!(prices["NVDA"].as_array().unwrap().len(), 60);
+        assert_eq!(prices["AAPL"][0]["close"], 100.0);
+    }
+
+    #[test]
+    fn test_notebook_reply() {
+        let (text, notebook) = notebook_reply(&record(), NotebookKind::Python, Vec::new()).unwrap();
+        assert_eq!(text, "📓 This session as a notebook, 3 exchange(s)");
+        assert_eq!(notebook.unwrap().mime_type, NOTEBOOK_MIME_TYPE);
+    }
+
+    #[tokio::test]
+    async fn test_command_reply_without_exchanges() {
+        let (text, notebook) = command_reply(&AnalysisContext::new(), NotebookKind::Python)
+            .await
+            .unwrap();
+        assert_eq!(text, "📓 Nothing to export yet.");
+        assert!(notebook.is_none());
+    }
+
+    #[test]
+    fn test_rust_notebook_attachment() {
+        let exporter = NotebookExporter::default()
+            .with_kind(NotebookKind::Rust)
+            .with_charts(vec![chart("AAPL")]);
+        let attachment = exporter.to_attachment(&record()).unwrap();
+        assert_eq!(attachment.attachment_type, AttachmentType::Document);
+        assert_eq!(attachment.mime_type, NOTEBOOK_MIME_TYPE);
+        assert!(attachment.filename.unwrap().ends_with(".ipynb"));
+
+        let notebook: Value = serde_json::from_slice(&attachment.content).unwrap();
+        assert_eq!(notebook["metadata"]["kernelspec"]["name"], "rust");
+        let sources = sources(&notebook);
+        assert!(sources[1].contains("let session: serde_json::Value = serde_json::from_str(r#\"{"));
+        assert!(sources.last().unwrap().contains("session[\"prices\"]"));
+    }
+
+    #[test]
+    fn test_rust_literal_picks_enough_hashes() {
+        assert_eq!(rust_literal("plain"), "r#\"plain\"#");
+        assert_eq!(rust_literal("a \"# b"), "r##\"a \"# b\"##");
+    }
+}
@@ -1,7 +1,10 @@
 //! CLI Bot - placeholder for now
 
+use crate::alerts::Notifier;
 use crate::engine::StockAnalysisEngine;
+use crate::error::Result;
 use crate::interface::{BotPlatform, SessionManager};
+use async_trait::async_trait;
 
 pub struct CliBot {
     _engine: StockAnalysisEngine,
@@ -16,3 +19,14 @@ impl CliBot {
         }
     }
 }
+
+/// Prints alert notifications for alerts set from the console
+pub struct ConsoleNotifier;
+
+#[async_trait]
+impl Notifier for ConsoleNotifier {
+    async fn notify(&self, _user_id: &str, message: &str) -> Result<()> {
+        println!("\n{message}");
+        Ok(())
+    }
+}
@@ -1,19 +1,34 @@
 //! DingTalk bot implementation
+//!
+//! Messages arrive through an enterprise robot's outgoing callback: the HTTP
+//! handler parses each request with [`DingTalkMessage::parse`] and hands it
+//! to [`DingTalkBot::on_chat_message`], replying through the message's
+//! session webhook. In group chats the robot only answers messages that
+//! @mention it, and the group shares one session, so its conversation
+//! context and watchlist belong to the whole team.
 
+use crate::alerts::Notifier;
 use crate::bot::Command;
 use crate::engine::{AnalysisContext, StockAnalysisEngine};
 use crate::error::{Result, StockError};
 use crate::interface::{
-    BotInterface, BotPlatform, BotResponse, Formatter, FormatterFactory, SessionManager,
+    BotInterface, BotPlatform, BotResponse, Formatter, FormatterFactory, MessageLimit,
+    SessionManager,
 };
+use crate::notebook;
+use crate::platforms::dispatch::{self, Reply};
+use crate::platforms::services::BotServices;
+use crate::report;
 use async_trait::async_trait;
+use serde_json::Value;
+use std::sync::Arc;
 
 /// DingTalk bot configuration
 #[derive(Debug, Clone)]
 pub struct DingTalkConfig {
     /// Webhook URL
     pub webhook_url: String,
-    
+
     /// Secret for signature verification (optional)
     pub secret: Option<String>,
 }
@@ -23,10 +38,126 @@ impl DingTalkConfig {
     pub fn from_env() -> Result<Self> {
         let webhook_url = std::env::var("DINGTALK_WEBHOOK")
             .map_err(|_| StockError::ConfigError("DINGTALK_WEBHOOK not set".to_string()))?;
-        
+
         let secret = std::env::var("DINGTALK_SECRET").ok();
-        
-        Ok(Self { webhook_url, secret })
+
+        Ok(Self {
+            webhook_url,
+            secret,
+        })
+    }
+}
+
+/// Pushes alert notifications to a DingTalk group through its custom robot
+/// webhook
+///
+/// The robot posts to the group it belongs to, so every alert set from
+/// DingTalk goes to that group. Signed webhooks are not supported: secure
+/// the robot with a keyword (e.g. "Alert") or an IP allowlist instead.
+#[derive(Clone)]
+pub struct DingTalkWebhook {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl DingTalkWebhook {
+    /// Notifier posting to the robot webhook `url`
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Notifier for the configured webhook
+    pub fn from_config(config: &DingTalkConfig) -> Self {
+        if config.secret.is_some() {
+            tracing::warn!("DINGTALK_SECRET is set but alert pushes are not signed");
+        }
+        Self::new(&config.webhook_url)
+    }
+}
+
+#[async_trait]
+impl Notifier for DingTalkWebhook {
+    async fn notify(&self, _user_id: &str, message: &str) -> Result<()> {
+        let body: serde_json::Value = self
+            .client
+            .post(&self.url)
+            .json(&serde_json::json!({ "msgtype": "text", "text": { "content": message } }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        match body.get("errcode").and_then(serde_json::Value::as_i64) {
+            Some(0) | None => Ok(()),
+            Some(code) => Err(StockError::ApiError(format!(
+                "DingTalk webhook failed ({code}): {}",
+                body.get("errmsg")
+                    .and_then(serde_json::Value::as_str)
+                    .unwrap_or("unknown error")
+            ))),
+        }
+    }
+}
+
+/// A message addressed to the robot, from its outgoing callback
+#[derive(Debug, Clone, PartialEq)]
+pub struct DingTalkMessage {
+    /// Conversation the message was sent in
+    pub conversation_id: String,
+    /// Whether the conversation is a group rather than a one-on-one chat
+    pub group: bool,
+    /// Sender's staff ID, or their DingTalk ID outside the organization
+    pub sender: String,
+    /// Message text
+    pub text: String,
+    /// Webhook that posts to the conversation for a while after the message
+    pub session_webhook: Option<String>,
+}
+
+impl DingTalkMessage {
+    /// Parse an outgoing callback body
+    ///
+    /// `None` for anything but text, and for group messages that do not
+    /// @mention the robot.
+    pub fn parse(body: &Value) -> Option<Self> {
+        if body["msgtype"] != "text" {
+            return None;
+        }
+        let group = body["conversationType"] == "2";
+        if group && body["isInAtList"] != true {
+            return None;
+        }
+        let sender = body["senderStaffId"]
+            .as_str()
+            .or_else(|| body["senderId"].as_str())
+            .filter(|sender| !sender.is_empty())?;
+        let text = body["text"]["content"].as_str()?.trim();
+        if text.is_empty() {
+            return None;
+        }
+        Some(Self {
+            conversation_id: body["conversationId"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            group,
+            sender: sender.to_string(),
+            text: text.to_string(),
+            session_webhook: body["sessionWebhook"].as_str().map(str::to_string),
+        })
+    }
+
+    /// Session the message belongs to: the conversation for groups, so
+    /// members share context and the watchlist, and the sender otherwise
+    pub fn session_key(&self) -> &str {
+        if self.group {
+            &self.conversation_id
+        } else {
+            &self.sender
+        }
     }
 }
 
@@ -36,6 +167,7 @@ pub struct DingTalkBot {
     engine: StockAnalysisEngine,
     session_manager: SessionManager,
     formatter: Box<dyn Formatter>,
+    services: BotServices,
 }
 
 impl DingTalkBot {
@@ -45,40 +177,70 @@ impl DingTalkBot {
             _config: config,
             engine,
             session_manager: SessionManager::new(BotPlatform::DingTalk),
-            formatter: FormatterFactory::create(BotPlatform::DingTalk),
+            formatter: FormatterFactory::create_with_limit(
+                BotPlatform::DingTalk,
+                MessageLimit::from_env(BotPlatform::DingTalk),
+            ),
+            services: BotServices::default(),
+        }
+    }
+
+    /// Serve users with `services`; bots serving the same users may share
+    /// one set
+    pub fn with_services(mut self, services: BotServices) -> Self {
+        if let Some(store) = &services.conversations {
+            self.session_manager.set_conversations(Arc::clone(store));
+        }
+        self.services = services;
+        self
+    }
+
+    /// Reply to a message from the outgoing callback; sending it, e.g.
+    /// through the message's session webhook, is left to the caller
+    ///
+    /// Messages in a group share the group's session (see
+    /// [`DingTalkMessage::session_key`]). Callers wanting queued requests
+    /// acknowledged while they wait run it inside
+    /// [`with_interim`](crate::interface::with_interim).
+    pub async fn on_chat_message(&self, message: &DingTalkMessage) -> BotResponse {
+        let key = message.session_key();
+        let mut context = AnalysisContext::with_user(key);
+        match self.on_message(key, &message.text, &mut context).await {
+            Ok(response) => response,
+            Err(e) => BotResponse::error(self.formatter.format_error(&e.to_string())),
         }
     }
-    
+
     /// Process a command
-    pub async fn process_command(&mut self, user_id: &str, input: &str) -> Result<String> {
+    pub async fn process_command(&self, user_id: &str, input: &str) -> Result<String> {
         let mut session = self.session_manager.get_or_create(user_id)?;
         let mut context = session.context.clone();
-        
+
         let command = Command::parse(input)?;
-        
-        let response = match command {
-            Command::Analyze { symbol } => {
-                let result = self.engine.analyze_stock(&symbol, &mut context).await?;
-                self.formatter.format_analysis(&result, &context)
-            }
-            Command::Technical { symbol } => {
-                let result = self.engine.analyze_technical(&symbol, &mut context).await?;
-                self.formatter.format_analysis(&result, &context)
-            }
-            Command::Help => self.formatter.format_help(),
-            Command::Watchlist => {
-                if session.watchlist.is_empty() {
-                    "📋 Watchlist is empty".to_string()
-                } else {
-                    format!("📋 Watchlist:\n{}", session.watchlist.join("\n"))
-                }
-            }
-            _ => "Command not yet implemented".to_string(),
+        let exchange = command.is_heavy().then(|| command.symbols());
+
+        let reply = Box::pin(dispatch::command_reply(
+            &self.engine,
+            &self.services,
+            &mut session,
+            &mut context,
+            &command,
+        ))
+        .await?;
+        let response = match reply {
+            Some(Reply::Analysis(result)) => self.formatter.format_analysis(&result, &context),
+            Some(Reply::Comparison(result)) => result.summary,
+            Some(Reply::Text(text) | Reply::Table(text)) => text,
+            None if command == Command::Help => self.formatter.format_help(),
+            None => "Command not yet implemented".to_string(),
         };
-        
+
+        if let Some(symbols) = exchange {
+            context.record_exchange(input, &response, symbols);
+        }
         session.context = context;
         self.session_manager.update(user_id, session)?;
-        
+
         Ok(response)
     }
 }
@@ -88,19 +250,70 @@ impl BotInterface for DingTalkBot {
     fn platform(&self) -> BotPlatform {
         BotPlatform::DingTalk
     }
-    
+
     async fn on_message(
-        &mut self,
+        &self,
         user_id: &str,
         message: &str,
         _context: &mut AnalysisContext,
     ) -> Result<BotResponse> {
+        match Command::parse(message) {
+            Ok(Command::More) => return self.session_manager.next_page(user_id),
+            // Notebooks go out as a document along with the caption
+            Ok(Command::Notebook { kind }) => {
+                let session = self.session_manager.get_or_create(user_id)?;
+                let (text, notebook) = notebook::command_reply(&session.context, kind).await?;
+                let reply = self.session_manager.paginate(
+                    user_id,
+                    &text,
+                    self.formatter.message_limit(),
+                )?;
+                return Ok(match notebook {
+                    Some(notebook) => reply.with_attachment(notebook),
+                    None => reply,
+                });
+            }
+            // Reports go out as a document, after the user's earlier requests
+            Ok(Command::Report { symbols, format }) => {
+                let (text, document) = report::queued_reply(
+                    &self.engine,
+                    &self.services.queue,
+                    &self.session_manager,
+                    user_id,
+                    &symbols,
+                    format,
+                    BotPlatform::DingTalk,
+                )
+                .await?;
+                let reply = self.session_manager.paginate(
+                    user_id,
+                    &text,
+                    self.formatter.message_limit(),
+                )?;
+                return Ok(reply.with_attachments(document));
+            }
+            // Heavy requests wait for the user's earlier ones to finish
+            Ok(command) if command.is_heavy() => {
+                let response = self
+                    .services
+                    .queue
+                    .run(user_id, self.process_command(user_id, message))
+                    .await??;
+                return self.session_manager.paginate(
+                    user_id,
+                    &response,
+                    self.formatter.message_limit(),
+                );
+            }
+            _ => {}
+        }
         let response = self.process_command(user_id, message).await?;
-        Ok(BotResponse::formatted(response))
+        self.session_manager
+            .paginate(user_id, &response, self.formatter.message_limit())
     }
-    
+
     async fn on_command(
-        &mut self,
+        &self,
         user_id: &str,
         command: &str,
         args: &[String],
@@ -111,11 +324,67 @@ impl BotInterface for DingTalkBot {
         } else {
             format!("/{} {}", command, args.join(" "))
         };
-        
+
         self.on_message(user_id, &full_command, context).await
     }
-    
+
     fn format_response(&self, content: &str, _context: &AnalysisContext) -> BotResponse {
         BotResponse::formatted(content)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn callback(conversation_type: &str, in_at_list: bool, content: &str) -> Value {
+        json!({
+            "msgtype": "text",
+            "text": { "content": content },
+            "conversationId": "cid6KeBBLoveMJOGXoYKF5x7A==",
+            "conversationType": conversation_type,
+            "isInAtList": in_at_list,
+            "senderId": "$:LWCP_v1:$alice",
+            "senderStaffId": "manager4220",
+            "senderNick": "Alice",
+            "sessionWebhook": "https://oapi.dingtalk.com/robot/sendBySession?session=c1d2"
+        })
+    }
+
+    #[test]
+    fn test_parse_group_messages_need_a_mention() {
+        let message = DingTalkMessage::parse(&callback("2", true, " /watch NVDA ")).unwrap();
+        assert_eq!(message.text, "/watch NVDA");
+        assert!(message.group);
+        assert_eq!(message.session_key(), "cid6KeBBLoveMJOGXoYKF5x7A==");
+        assert_eq!(
+            message.session_webhook.as_deref(),
+            Some("https://oapi.dingtalk.com/robot/sendBySession?session=c1d2")
+        );
+
+        assert_eq!(
+            DingTalkMessage::parse(&callback("2", false, "/watch NVDA")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_direct_messages() {
+        let message = DingTalkMessage::parse(&callback("1", false, "/analyze AAPL")).unwrap();
+        assert!(!message.group);
+        assert_eq!(message.session_key(), "manager4220");
+
+        let mut outside = callback("1", false, "/analyze AAPL");
+        outside["senderStaffId"] = json!(null);
+        assert_eq!(
+            DingTalkMessage::parse(&outside).unwrap().session_key(),
+            "$:LWCP_v1:$alice"
+        );
+
+        assert_eq!(DingTalkMessage::parse(&callback("1", false, "  ")), None);
+        let mut picture = callback("1", false, "/analyze AAPL");
+        picture["msgtype"] = json!("picture");
+        assert_eq!(DingTalkMessage::parse(&picture), None);
+    }
+}
@@ -0,0 +1,282 @@
+//! Command replies shared by the chat platform bots
+//!
+//! The chat bots answer almost every command the same way; they differ only
+//! in how a reply is rendered (HTML, Markdown, Block Kit, a chart image). So
+//! [`command_reply`] runs the command once for all of them and returns a
+//! [`Reply`] the bot renders in its own markup. It matches every [`Command`],
+//! so a new command is handled on every platform or explicitly left to the
+//! bots.
+
+use crate::agents::query_builder;
+use crate::alerts;
+use crate::api::YahooFinanceClient;
+use crate::bot::Command;
+use crate::delivery;
+use crate::engine::{AnalysisContext, AnalysisResult, ComparisonResult, StockAnalysisEngine};
+use crate::error::Result;
+use crate::interface::{UserSession, heatmap, queue};
+use crate::language::ResponseLanguage;
+use crate::live;
+use crate::macro_alerts;
+use crate::news_digest;
+use crate::platforms::services::BotServices;
+use crate::portfolio;
+
+/// A command's reply, before the bot renders it
+#[derive(Debug)]
+pub(crate) enum Reply {
+    /// Plain text; bots that send HTML escape it
+    Text(String),
+    /// Fixed-width text such as a table or report
+    Table(String),
+    /// An analysis for the bot's formatter
+    Analysis(AnalysisResult),
+    /// A comparison of several stocks
+    Comparison(ComparisonResult),
+}
+
+/// Reply to `command` from the user of `session`
+///
+/// Analyses and preference commands update `context`; watchlist commands
+/// update `session`. Returns `None` for the commands each bot answers
+/// itself: help, exports and exit. The future is large, so bots box it
+/// rather than grow their own.
+pub(crate) async fn command_reply(
+    engine: &StockAnalysisEngine,
+    services: &BotServices,
+    session: &mut UserSession,
+    context: &mut AnalysisContext,
+    command: &Command,
+) -> Result<Option<Reply>> {
+    let user_id = session.user_id.clone();
+    let user_id = user_id.as_str();
+    let platform = session.platform;
+
+    let reply = match command {
+        Command::Analyze {
+            symbol,
+            depth,
+            as_of,
+        } => {
+            let depth = depth.unwrap_or(context.preferences.depth);
+            let result = match as_of {
+                Some(date) => {
+                    engine
+                        .analyze_stock_as_of(symbol, depth, *date, context)
+                        .await?
+                }
+                None => engine.analyze_stock_at(symbol, depth, context).await?,
+            };
+            Reply::Analysis(result)
+        }
+        Command::Technical { symbol } => {
+            Reply::Analysis(engine.analyze_technical(symbol, context).await?)
+        }
+        Command::Fundamental { symbol } => {
+            Reply::Analysis(engine.analyze_fundamental(symbol, context).await?)
+        }
+        Command::News { symbol } => Reply::Analysis(engine.analyze_news(symbol, context).await?),
+        Command::NewsDigest => {
+            let positions = services
+                .portfolio
+                .as_ref()
+                .map(|p| p.store().positions(user_id))
+                .unwrap_or_default();
+            let weights = news_digest::exposure_weights(&session.watchlist, &positions);
+            if weights.is_empty() {
+                Reply::Text("📋 Watchlist is empty. Use /watch <symbol> to add stocks.".to_string())
+            } else {
+                Reply::Analysis(engine.analyze_news_digest(&weights, context).await?)
+            }
+        }
+        Command::Earnings { symbol } => {
+            Reply::Analysis(engine.analyze_earnings(symbol, context).await?)
+        }
+        Command::Macro => Reply::Analysis(engine.analyze_macro(context).await?),
+        Command::Geopolitical => Reply::Analysis(engine.analyze_geopolitical(context).await?),
+        Command::Theme { theme } => Reply::Analysis(engine.analyze_theme(theme, context).await?),
+        Command::Compare { symbols } => {
+            Reply::Comparison(engine.compare_stocks(symbols, context).await?)
+        }
+        Command::Wrap => match &services.market_wrap {
+            Some(job) => Reply::Text(job.run(&session.watchlist).await?.to_string()),
+            None => Reply::Text("🌆 Market wraps are not enabled on this bot".to_string()),
+        },
+        Command::Scoreboard => match &services.predictions {
+            Some(tracker) => {
+                let scored = tracker.score_due(&YahooFinanceClient::new()).await?;
+                if scored > 0 {
+                    tracing::info!("Scored {} predictions", scored);
+                }
+                Reply::Table(tracker.scoreboard().to_string())
+            }
+            None => {
+                Reply::Text("🎯 The prediction scoreboard is not enabled on this bot".to_string())
+            }
+        },
+        Command::Dividends { symbol } => {
+            Reply::Text(format!("💵 {}", engine.dividends(symbol).await?))
+        }
+        Command::Screen { screen } => {
+            Reply::Text(format!("🔎 {}", engine.screen(screen.clone()).await?))
+        }
+        Command::Market | Command::Summary => {
+            let watchlist = (*command == Command::Summary).then_some(session.watchlist.as_slice());
+            let (table, _) =
+                heatmap::performance_reply(&YahooFinanceClient::new(), watchlist, context).await?;
+            Reply::Table(table)
+        }
+        Command::Alert { .. } | Command::AlertRemove { .. } | Command::Alerts => Reply::Text(
+            alerts::command_reply(services.alerts.as_deref(), user_id, platform, command)?,
+        ),
+        Command::MacroWatch { .. } | Command::MacroUnwatch { .. } | Command::MacroWatches => {
+            Reply::Text(macro_alerts::command_reply(
+                services.macro_watches.as_deref(),
+                user_id,
+                platform,
+                command,
+            )?)
+        }
+        Command::Quiet
+        | Command::QuietSet { .. }
+        | Command::QuietOff
+        | Command::TimeZone { .. } => Reply::Text(delivery::command_reply(
+            services.delivery.as_deref(),
+            user_id,
+            platform,
+            command,
+        )?),
+        Command::Live { .. } | Command::LiveStop { .. } => Reply::Text(live::command_reply(
+            services.live.as_deref(),
+            user_id,
+            platform,
+            command,
+        )?),
+        Command::PortfolioAdd { .. }
+        | Command::PortfolioRemove { .. }
+        | Command::Portfolio
+        | Command::PortfolioAsk { .. } => {
+            let mut agent_context = context.agent_context();
+            Reply::Text(
+                portfolio::command_reply(
+                    services.portfolio.as_deref(),
+                    user_id,
+                    command,
+                    &mut agent_context,
+                )
+                .await?,
+            )
+        }
+        Command::Ask { .. } | Command::AskRun => {
+            let mut agent_context = context.agent_context();
+            Reply::Text(
+                query_builder::command_reply(
+                    services.query_builder.as_deref(),
+                    user_id,
+                    command,
+                    &mut agent_context,
+                )
+                .await?,
+            )
+        }
+        Command::Watch { symbol } => {
+            session.watch(symbol.clone());
+            let live = services
+                .live
+                .as_deref()
+                .filter(|live| live.anomaly_alerts_enabled());
+            Reply::Text(
+                match live.map(|live| live.watch_anomalies(user_id, platform, symbol)) {
+                    Some(Ok(_)) => format!(
+                        "✅ Added {symbol} to watchlist; halts, limit moves and gaps will be pushed here"
+                    ),
+                    Some(Err(e)) => {
+                        tracing::warn!("No anomaly alerts for {}: {}", symbol, e);
+                        format!("✅ Added {symbol} to watchlist")
+                    }
+                    None => format!("✅ Added {symbol} to watchlist"),
+                },
+            )
+        }
+        Command::Unwatch { symbol } => {
+            if let Some(live) = &services.live {
+                live.unwatch_anomalies(user_id, symbol);
+            }
+            if session.unwatch(symbol) {
+                Reply::Text(format!("✅ Removed {symbol} from watchlist"))
+            } else {
+                Reply::Text(format!("❌ {symbol} not in watchlist"))
+            }
+        }
+        Command::Watchlist => {
+            if session.watchlist.is_empty() {
+                Reply::Text("📋 Watchlist is empty".to_string())
+            } else {
+                Reply::Text(format!("📋 Watchlist:\n{}", session.watchlist.join("\n")))
+            }
+        }
+        Command::Style { style: Some(style) } => {
+            context.preferences.style = *style;
+            Reply::Text(format!("✅ Response style set to {style}"))
+        }
+        Command::Style { style: None } => {
+            Reply::Text(format!("🎨 Response style: {}", context.preferences.style))
+        }
+        Command::Teach { enabled } => {
+            let current = context.preferences.teaching_mode.unwrap_or(false);
+            let enabled = enabled.unwrap_or(!current);
+            context.preferences.teaching_mode = Some(enabled);
+            if enabled {
+                Reply::Text("🎓 Teaching mode on".to_string())
+            } else {
+                Reply::Text("✅ Teaching mode off".to_string())
+            }
+        }
+        Command::Language {
+            language: Some(language),
+        } => {
+            if *language != ResponseLanguage::Bilingual {
+                context.preferences.language = language.as_str().to_string();
+            }
+            context.preferences.response_language = Some(language.as_str().to_string());
+            Reply::Text(format!("✅ Reply language set to {language}"))
+        }
+        Command::Language { language: None } => {
+            let current = context
+                .preferences
+                .response_language
+                .as_deref()
+                .unwrap_or(&context.preferences.language);
+            Reply::Text(format!("🌐 Reply language: {current}"))
+        }
+        Command::Voice { .. } if services.synthesizer.is_none() => {
+            Reply::Text("🔇 Audio summaries are not enabled on this bot".to_string())
+        }
+        Command::Voice { enabled } => {
+            let enabled = enabled.unwrap_or(!context.preferences.voice_replies);
+            context.preferences.voice_replies = enabled;
+            if enabled {
+                Reply::Text("🔊 Audio summaries on for long replies".to_string())
+            } else {
+                Reply::Text("🔇 Audio summaries off".to_string())
+            }
+        }
+        Command::Clear => {
+            // Keep preferences such as the response style across clears
+            let preferences = context.preferences.clone();
+            *context = AnalysisContext::with_user(user_id);
+            context.preferences = preferences;
+            Reply::Text("✅ Conversation cleared".to_string())
+        }
+        Command::Query { text } => Reply::Text(engine.answer_query(text, context).await?),
+        // Replies are returned whole here; the bots page them
+        Command::More => Reply::Text("Nothing more to show.".to_string()),
+        Command::Cancel => Reply::Text(queue::cancel_message(services.queue.cancel(user_id))),
+        Command::Capabilities => Reply::Text(engine.capabilities()),
+        Command::Usage => Reply::Table(engine.usage_report()),
+        Command::Help | Command::Notebook { .. } | Command::Report { .. } | Command::Exit => {
+            return Ok(None);
+        }
+    };
+    Ok(Some(reply))
+}
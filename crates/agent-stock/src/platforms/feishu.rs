@@ -1,24 +1,51 @@
 //! Feishu (Lark) bot implementation
+//!
+//! Messages arrive through event subscriptions: the HTTP handler parses each
+//! request with [`FeishuEvent::parse`], answers the `url_verification`
+//! challenge itself and hands messages to [`FeishuBot::on_chat_message`],
+//! sending the reply to the message's chat. In group chats the bot only
+//! answers messages that @mention it, and the group shares one session, so
+//! its conversation context and watchlist belong to the whole team.
+//! [`FeishuApi`] sends replies, with their charts and documents, to the chat.
 
+use crate::alerts::Notifier;
+use crate::api::YahooFinanceClient;
 use crate::bot::Command;
-use crate::engine::{AnalysisContext, StockAnalysisEngine};
+use crate::depth::AnalysisDepth;
+use crate::engine::{AnalysisContext, AnalysisResult, StockAnalysisEngine};
 use crate::error::{Result, StockError};
+use crate::interface::interface::{Attachment, AttachmentType};
 use crate::interface::{
-    BotInterface, BotPlatform, BotResponse, Formatter, FormatterFactory, SessionManager,
+    BotInterface, BotPlatform, BotResponse, Formatter, FormatterFactory, MessageLimit,
+    SessionManager, with_interim,
 };
+use crate::interface::{chart_render, heatmap};
+use crate::notebook;
+use crate::platforms::dispatch::{self, Reply};
+use crate::platforms::services::BotServices;
+use crate::report;
 use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedSender;
 
 /// Feishu bot configuration
 #[derive(Debug, Clone)]
 pub struct FeishuConfig {
     /// App ID
     pub app_id: String,
-    
+
     /// App secret
     pub app_secret: String,
-    
+
     /// Verification token (optional)
     pub verification_token: Option<String>,
+
+    /// The bot's open ID, to tell mentions of the bot from other mentions
+    /// in group chats (optional)
+    pub bot_open_id: Option<String>,
 }
 
 impl FeishuConfig {
@@ -26,16 +53,407 @@ impl FeishuConfig {
     pub fn from_env() -> Result<Self> {
         let app_id = std::env::var("FEISHU_APP_ID")
             .map_err(|_| StockError::ConfigError("FEISHU_APP_ID not set".to_string()))?;
-        
+
         let app_secret = std::env::var("FEISHU_APP_SECRET")
             .map_err(|_| StockError::ConfigError("FEISHU_APP_SECRET not set".to_string()))?;
-        
+
         let verification_token = std::env::var("FEISHU_VERIFICATION_TOKEN").ok();
-        
+        let bot_open_id = std::env::var("FEISHU_BOT_OPEN_ID").ok();
+
         Ok(Self {
             app_id,
             app_secret,
             verification_token,
+            bot_open_id,
+        })
+    }
+}
+
+/// Pushes alert notifications to a Feishu group through its custom bot
+/// webhook
+///
+/// The bot posts to the group it belongs to, so every alert set from Feishu
+/// goes to that group.
+#[derive(Clone)]
+pub struct FeishuWebhook {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl FeishuWebhook {
+    /// Notifier posting to the custom bot webhook `url`
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Create from the `FEISHU_WEBHOOK` environment variable
+    pub fn from_env() -> Result<Self> {
+        std::env::var("FEISHU_WEBHOOK")
+            .map(Self::new)
+            .map_err(|_| StockError::ConfigError("FEISHU_WEBHOOK not set".to_string()))
+    }
+}
+
+#[async_trait]
+impl Notifier for FeishuWebhook {
+    async fn notify(&self, _user_id: &str, message: &str) -> Result<()> {
+        let body: serde_json::Value = self
+            .client
+            .post(&self.url)
+            .json(&serde_json::json!({ "msg_type": "text", "content": { "text": message } }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        match body.get("code").and_then(serde_json::Value::as_i64) {
+            Some(0) | None => Ok(()),
+            Some(code) => Err(StockError::ApiError(format!(
+                "Feishu webhook failed ({code}): {}",
+                body.get("msg")
+                    .and_then(serde_json::Value::as_str)
+                    .unwrap_or("unknown error")
+            ))),
+        }
+    }
+}
+
+/// Feishu open platform base URL; Lark uses `https://open.larksuite.com/open-apis`
+const FEISHU_API_URL: &str = "https://open.feishu.cn/open-apis";
+
+/// Tenant access tokens are renewed this long before Feishu expires them
+const TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(5 * 60);
+
+/// Error codes of a tenant access token that expired or was replaced early
+const STALE_TOKEN_CODES: [i64; 2] = [99_991_663, 99_991_668];
+
+/// Access token and when it stops being used
+type CachedToken = Option<(String, Instant)>;
+
+/// Minimal Feishu open platform client for sending replies to chats
+///
+/// Text goes out as interactive cards with one `lark_md` section, the
+/// markup [`FeishuFormatter`](crate::interface::formatter::FeishuFormatter)
+/// writes; images and charts are uploaded and sent as `image` messages and
+/// other attachments as `file` messages. The app needs the `im:message` and
+/// `im:resource` permissions.
+#[derive(Clone)]
+pub struct FeishuApi {
+    app_id: String,
+    app_secret: String,
+    base_url: String,
+    client: reqwest::Client,
+    token: Arc<Mutex<CachedToken>>,
+}
+
+impl FeishuApi {
+    /// Create a client for the app
+    pub fn new(app_id: impl Into<String>, app_secret: impl Into<String>) -> Self {
+        Self {
+            app_id: app_id.into(),
+            app_secret: app_secret.into(),
+            base_url: FEISHU_API_URL.to_string(),
+            client: reqwest::Client::new(),
+            token: Arc::default(),
+        }
+    }
+
+    /// Client for the configured app
+    pub fn from_config(config: &FeishuConfig) -> Self {
+        Self::new(&config.app_id, &config.app_secret)
+    }
+
+    /// Send requests to `base_url` (e.g. Lark or a mock)
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Tenant access token, fetched again shortly before it expires
+    pub async fn access_token(&self) -> Result<String> {
+        if let Some((token, until)) = &*self.token.lock().unwrap_or_else(PoisonError::into_inner)
+            && *until > Instant::now()
+        {
+            return Ok(token.clone());
+        }
+
+        let body: Value = self
+            .client
+            .post(format!(
+                "{}/auth/v3/tenant_access_token/internal",
+                self.base_url
+            ))
+            .json(&json!({ "app_id": self.app_id, "app_secret": self.app_secret }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let token = match code(&body) {
+            0 => body["tenant_access_token"].as_str().ok_or_else(|| {
+                StockError::ApiError("Feishu tenant_access_token returned no token".to_string())
+            })?,
+            10003 | 10014 => {
+                return Err(StockError::ConfigError(
+                    "Feishu rejected the app credentials (check FEISHU_APP_ID and FEISHU_APP_SECRET)"
+                        .to_string(),
+                ));
+            }
+            code => return Err(api_error("tenant_access_token", code, &body)),
+        };
+        let expires_in = Duration::from_secs(body["expire"].as_u64().unwrap_or(7200));
+        let until = Instant::now() + expires_in.saturating_sub(TOKEN_EXPIRY_MARGIN);
+        *self.token.lock().unwrap_or_else(PoisonError::into_inner) =
+            Some((token.to_string(), until));
+        Ok(token.to_string())
+    }
+
+    /// Send a card showing `markdown` (`lark_md`) to a chat
+    pub async fn send_card(&self, chat_id: &str, markdown: &str) -> Result<()> {
+        let card = json!({
+            "config": { "wide_screen_mode": true },
+            "elements": [{ "tag": "div", "text": { "tag": "lark_md", "content": markdown } }],
+        });
+        self.send(chat_id, "interactive", &card).await
+    }
+
+    /// Send every message of a reply, then its attachments
+    pub async fn send_response(&self, chat_id: &str, response: &BotResponse) -> Result<()> {
+        for text in response.messages() {
+            self.send_card(chat_id, text).await?;
+        }
+        for attachment in &response.attachments {
+            self.send_file(chat_id, attachment).await?;
+        }
+        Ok(())
+    }
+
+    /// Upload `attachment` and send it to a chat: images and charts as an
+    /// `image` message, anything else (including audio, which `audio`
+    /// messages only take as Opus) as a `file`
+    pub async fn send_file(&self, chat_id: &str, attachment: &Attachment) -> Result<()> {
+        let (endpoint, msg_type, key) = match attachment.attachment_type {
+            AttachmentType::Image | AttachmentType::Chart => ("im/v1/images", "image", "image_key"),
+            AttachmentType::Document | AttachmentType::Audio => ("im/v1/files", "file", "file_key"),
+        };
+        let uploaded = self
+            .call(endpoint, |request| {
+                Ok(request.multipart(upload_form(attachment)?))
+            })
+            .await?;
+        self.send(chat_id, msg_type, &json!({ key: uploaded["data"][key] }))
+            .await
+    }
+
+    async fn send(&self, chat_id: &str, msg_type: &str, content: &Value) -> Result<()> {
+        let message = json!({
+            "receive_id": chat_id,
+            "msg_type": msg_type,
+            "content": content.to_string(),
+        });
+        self.call("im/v1/messages", |request| {
+            Ok(request
+                .query(&[("receive_id_type", "chat_id")])
+                .json(&message))
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// POST to `method` with the tenant token, retrying once if the token
+    /// turns out to be stale
+    async fn call(
+        &self,
+        method: &str,
+        build: impl Fn(reqwest::RequestBuilder) -> Result<reqwest::RequestBuilder>,
+    ) -> Result<Value> {
+        let mut body = self.post(method, &build).await?;
+        if STALE_TOKEN_CODES.contains(&code(&body)) {
+            *self.token.lock().unwrap_or_else(PoisonError::into_inner) = None;
+            body = self.post(method, &build).await?;
+        }
+        match code(&body) {
+            0 => Ok(body),
+            code => Err(api_error(method, code, &body)),
+        }
+    }
+
+    async fn post(
+        &self,
+        method: &str,
+        build: &impl Fn(reqwest::RequestBuilder) -> Result<reqwest::RequestBuilder>,
+    ) -> Result<Value> {
+        let token = self.access_token().await?;
+        let request = self
+            .client
+            .post(format!("{}/{method}", self.base_url))
+            .bearer_auth(token);
+        Ok(build(request)?.send().await?.json().await?)
+    }
+}
+
+/// Multipart form uploading `attachment` to `im/v1/images` or `im/v1/files`
+fn upload_form(attachment: &Attachment) -> Result<reqwest::multipart::Form> {
+    let filename = attachment
+        .filename
+        .clone()
+        .unwrap_or_else(|| "attachment".to_string());
+    let part = reqwest::multipart::Part::bytes(attachment.content.clone())
+        .file_name(filename.clone())
+        .mime_str(&attachment.mime_type)?;
+    let form = reqwest::multipart::Form::new();
+    Ok(match attachment.attachment_type {
+        AttachmentType::Image | AttachmentType::Chart => {
+            form.text("image_type", "message").part("image", part)
+        }
+        AttachmentType::Document | AttachmentType::Audio => {
+            let file_type = if attachment.mime_type == "application/pdf" {
+                "pdf"
+            } else {
+                "stream"
+            };
+            form.text("file_type", file_type)
+                .text("file_name", filename)
+                .part("file", part)
+        }
+    })
+}
+
+/// `code` of a Feishu API response; missing counts as success
+fn code(body: &Value) -> i64 {
+    body.get("code").and_then(Value::as_i64).unwrap_or(0)
+}
+
+fn api_error(method: &str, code: i64, body: &Value) -> StockError {
+    StockError::ApiError(format!(
+        "Feishu {method} failed ({code}): {}",
+        body.get("msg")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown error")
+    ))
+}
+
+/// A message addressed to the bot
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeishuMessage {
+    /// Chat the message was sent in, where the reply goes
+    pub chat_id: String,
+    /// Whether the chat is a group rather than a one-on-one chat
+    pub group: bool,
+    /// Sender's open ID
+    pub sender: String,
+    /// Message text, without mentions of the bot
+    pub text: String,
+}
+
+impl FeishuMessage {
+    /// Session the message belongs to: the chat for groups, so members
+    /// share context and the watchlist, and the sender otherwise
+    pub fn session_key(&self) -> &str {
+        if self.group {
+            &self.chat_id
+        } else {
+            &self.sender
+        }
+    }
+}
+
+/// What an event subscription request asks of the bot
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeishuEvent {
+    /// Feishu checking the request URL; respond with `{"challenge": ...}`
+    UrlVerification { challenge: String },
+    /// A user wrote to the bot
+    Message(FeishuMessage),
+    /// Anything else, including group messages not mentioning the bot
+    Ignored,
+}
+
+impl FeishuEvent {
+    /// Parse an event subscription request body (schema 2.0, unencrypted)
+    ///
+    /// Requests without the configured verification token are ignored. In
+    /// group chats only text messages that @mention the bot are kept: its
+    /// [`FeishuConfig::bot_open_id`] when set, or else any mention, since
+    /// without the permission to read all group messages Feishu only
+    /// delivers those mentioning the bot. Mentions of the bot (every
+    /// mention, without its open ID) are removed and others written as
+    /// `@name`.
+    pub fn parse(body: &Value, config: &FeishuConfig) -> Self {
+        let token = body["header"]["token"]
+            .as_str()
+            .or_else(|| body["token"].as_str());
+        if let Some(expected) = &config.verification_token
+            && token != Some(expected.as_str())
+        {
+            return Self::Ignored;
+        }
+        if body["type"] == "url_verification" {
+            return Self::UrlVerification {
+                challenge: body["challenge"].as_str().unwrap_or_default().to_string(),
+            };
+        }
+        if body["header"]["event_type"] != "im.message.receive_v1" {
+            return Self::Ignored;
+        }
+
+        let event = &body["event"];
+        let message = &event["message"];
+        let sender = event["sender"]["sender_id"]["open_id"]
+            .as_str()
+            .unwrap_or_default();
+        if message["message_type"] != "text"
+            || event["sender"]["sender_type"] != "user"
+            || sender.is_empty()
+        {
+            return Self::Ignored;
+        }
+        let content: Value = message["content"]
+            .as_str()
+            .and_then(|content| serde_json::from_str(content).ok())
+            .unwrap_or_default();
+        let mut text = content["text"].as_str().unwrap_or_default().to_string();
+        let mut mentioned = false;
+        let mut mentions: Vec<(&str, &Value)> = message["mentions"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|mention| {
+                Some((
+                    mention["key"].as_str().filter(|key| !key.is_empty())?,
+                    mention,
+                ))
+            })
+            .collect();
+        // `@_user_10` before `@_user_1`
+        mentions.sort_by_key(|(key, _)| std::cmp::Reverse(key.len()));
+        for (key, mention) in mentions {
+            let is_bot = config
+                .bot_open_id
+                .as_deref()
+                .is_none_or(|bot| mention["id"]["open_id"] == bot);
+            mentioned |= is_bot;
+            let replacement = if is_bot {
+                String::new()
+            } else {
+                format!("@{}", mention["name"].as_str().unwrap_or_default())
+            };
+            text = text.replace(key, &replacement);
+        }
+
+        let group = message["chat_type"] == "group";
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if (group && !mentioned) || text.is_empty() {
+            return Self::Ignored;
+        }
+        Self::Message(FeishuMessage {
+            chat_id: message["chat_id"].as_str().unwrap_or_default().to_string(),
+            group,
+            sender: sender.to_string(),
+            text,
         })
     }
 }
@@ -46,6 +464,9 @@ pub struct FeishuBot {
     engine: StockAnalysisEngine,
     session_manager: SessionManager,
     formatter: Box<dyn Formatter>,
+    services: BotServices,
+    /// Chart images waiting to go out with each user's next reply
+    charts: Mutex<HashMap<String, Attachment>>,
 }
 
 impl FeishuBot {
@@ -55,40 +476,107 @@ impl FeishuBot {
             _config: config,
             engine,
             session_manager: SessionManager::new(BotPlatform::Feishu),
-            formatter: FormatterFactory::create(BotPlatform::Feishu),
+            formatter: FormatterFactory::create_with_limit(
+                BotPlatform::Feishu,
+                MessageLimit::from_env(BotPlatform::Feishu),
+            ),
+            services: BotServices::default(),
+            charts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Serve users with `services`; bots serving the same users may share
+    /// one set
+    pub fn with_services(mut self, services: BotServices) -> Self {
+        if let Some(store) = &services.conversations {
+            self.session_manager.set_conversations(Arc::clone(store));
+        }
+        self.services = services;
+        self
+    }
+
+    /// Reply to a message event; the reply goes to the message's chat, and
+    /// sending it is left to the caller
+    ///
+    /// Messages in a group share the group's session (see
+    /// [`FeishuMessage::session_key`]). Callers wanting queued requests
+    /// acknowledged while they wait run it inside [`with_interim`].
+    pub async fn on_chat_message(&self, message: &FeishuMessage) -> BotResponse {
+        let key = message.session_key();
+        let mut context = AnalysisContext::with_user(key);
+        match self.on_message(key, &message.text, &mut context).await {
+            Ok(response) => response,
+            Err(e) => BotResponse::error(self.formatter.format_error(&e.to_string())),
+        }
+    }
+
+    /// Paginate a reply, with the chart image of the analysis if it has one
+    fn reply(&self, user_id: &str, response: &str) -> Result<BotResponse> {
+        let reply =
+            self.session_manager
+                .paginate(user_id, response, self.formatter.message_limit())?;
+        Ok(
+            match self
+                .charts
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .remove(user_id)
+            {
+                Some(chart) => reply.with_attachment(chart),
+                None => reply,
+            },
+        )
+    }
+
+    /// Hold the chart image of `result` for the user's next reply
+    fn keep_chart(&self, user_id: &str, result: &AnalysisResult, context: &AnalysisContext) {
+        if let Some(chart) = chart_render::analysis_chart(result, context) {
+            self.charts
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(user_id.to_string(), chart);
         }
     }
-    
+
     /// Process a command
-    pub async fn process_command(&mut self, user_id: &str, input: &str) -> Result<String> {
+    ///
+    /// Analyses with chart data leave a chart image for the reply.
+    pub async fn process_command(&self, user_id: &str, input: &str) -> Result<String> {
+        self.charts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(user_id);
         let mut session = self.session_manager.get_or_create(user_id)?;
         let mut context = session.context.clone();
-        
+
         let command = Command::parse(input)?;
-        
-        let response = match command {
-            Command::Analyze { symbol } => {
-                let result = self.engine.analyze_stock(&symbol, &mut context).await?;
-                self.formatter.format_analysis(&result, &context)
-            }
-            Command::Technical { symbol } => {
-                let result = self.engine.analyze_technical(&symbol, &mut context).await?;
+        let exchange = command.is_heavy().then(|| command.symbols());
+
+        let reply = Box::pin(dispatch::command_reply(
+            &self.engine,
+            &self.services,
+            &mut session,
+            &mut context,
+            &command,
+        ))
+        .await?;
+        let response = match reply {
+            Some(Reply::Analysis(result)) => {
+                self.keep_chart(user_id, &result, &context);
                 self.formatter.format_analysis(&result, &context)
             }
-            Command::Help => self.formatter.format_help(),
-            Command::Watchlist => {
-                if session.watchlist.is_empty() {
-                    "📋 Watchlist is empty".to_string()
-                } else {
-                    format!("📋 Watchlist:\n{}", session.watchlist.join("\n"))
-                }
-            }
-            _ => "Command not yet implemented".to_string(),
+            Some(Reply::Comparison(result)) => result.summary,
+            Some(Reply::Text(text) | Reply::Table(text)) => text,
+            None if command == Command::Help => self.formatter.format_help(),
+            None => "Command not yet implemented".to_string(),
         };
-        
+
+        if let Some(symbols) = exchange {
+            context.record_exchange(input, &response, symbols);
+        }
         session.context = context;
         self.session_manager.update(user_id, session)?;
-        
+
         Ok(response)
     }
 }
@@ -98,19 +586,127 @@ impl BotInterface for FeishuBot {
     fn platform(&self) -> BotPlatform {
         BotPlatform::Feishu
     }
-    
+
     async fn on_message(
-        &mut self,
+        &self,
         user_id: &str,
         message: &str,
         _context: &mut AnalysisContext,
     ) -> Result<BotResponse> {
+        match Command::parse(message) {
+            Ok(Command::More) => return self.session_manager.next_page(user_id),
+            // Notebooks go out as a document along with the caption
+            Ok(Command::Notebook { kind }) => {
+                let session = self.session_manager.get_or_create(user_id)?;
+                let (text, notebook) = notebook::command_reply(&session.context, kind).await?;
+                let reply = self.session_manager.paginate(
+                    user_id,
+                    &text,
+                    self.formatter.message_limit(),
+                )?;
+                return Ok(match notebook {
+                    Some(notebook) => reply.with_attachment(notebook),
+                    None => reply,
+                });
+            }
+            // Reports go out as a document, after the user's earlier requests
+            Ok(Command::Report { symbols, format }) => {
+                let (text, document) = report::queued_reply(
+                    &self.engine,
+                    &self.services.queue,
+                    &self.session_manager,
+                    user_id,
+                    &symbols,
+                    format,
+                    BotPlatform::Feishu,
+                )
+                .await?;
+                let reply = self.session_manager.paginate(
+                    user_id,
+                    &text,
+                    self.formatter.message_limit(),
+                )?;
+                return Ok(reply.with_attachments(document));
+            }
+            // Heatmaps go out as an image along with the text
+            Ok(command @ (Command::Market | Command::Summary)) => {
+                let session = self.session_manager.get_or_create(user_id)?;
+                let watchlist =
+                    (command == Command::Summary).then_some(session.watchlist.as_slice());
+                let (text, image) = heatmap::performance_reply(
+                    &YahooFinanceClient::new(),
+                    watchlist,
+                    &session.context,
+                )
+                .await?;
+                let reply = self.session_manager.paginate(
+                    user_id,
+                    &text,
+                    self.formatter.message_limit(),
+                )?;
+                return Ok(match image {
+                    Some(image) => reply.with_attachment(image),
+                    None => reply,
+                });
+            }
+            // Heavy requests wait for the user's earlier ones to finish
+            Ok(command) if command.is_heavy() => {
+                let response = self
+                    .services
+                    .queue
+                    .run(user_id, self.process_command(user_id, message))
+                    .await??;
+                return self.reply(user_id, &response);
+            }
+            _ => {}
+        }
         let response = self.process_command(user_id, message).await?;
-        Ok(BotResponse::formatted(response))
+        self.reply(user_id, &response)
     }
-    
+
+    async fn on_message_progressive(
+        &self,
+        user_id: &str,
+        message: &str,
+        context: &mut AnalysisContext,
+        interim: &UnboundedSender<BotResponse>,
+    ) -> Result<BotResponse> {
+        let command = Command::parse(message);
+        // Today's price has no place in an analysis of the past
+        let Ok(Command::Analyze {
+            symbol,
+            depth,
+            as_of: None,
+        }) = command
+        else {
+            return with_interim(interim.clone(), self.on_message(user_id, message, context)).await;
+        };
+        // Quick analyses arrive about as fast as a snapshot would
+        if depth.unwrap_or(context.preferences.depth) == AnalysisDepth::Quick {
+            return with_interim(interim.clone(), self.on_message(user_id, message, context)).await;
+        }
+
+        // Without a snapshot the analysis is simply sent when ready
+        let sent = match self.engine.quick_snapshot(&symbol).await {
+            Ok(snapshot) => interim
+                .send(BotResponse::text(snapshot.to_string()))
+                .is_ok(),
+            Err(e) => {
+                tracing::debug!("No snapshot for {}: {}", symbol, e);
+                false
+            }
+        };
+        let response =
+            with_interim(interim.clone(), self.on_message(user_id, message, context)).await?;
+        Ok(if sent {
+            response.replacing_previous()
+        } else {
+            response
+        })
+    }
+
     async fn on_command(
-        &mut self,
+        &self,
         user_id: &str,
         command: &str,
         args: &[String],
@@ -121,11 +717,187 @@ impl BotInterface for FeishuBot {
         } else {
             format!("/{} {}", command, args.join(" "))
         };
-        
+
         self.on_message(user_id, &full_command, context).await
     }
-    
+
     fn format_response(&self, content: &str, _context: &AnalysisContext) -> BotResponse {
         BotResponse::formatted(content)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(bot_open_id: Option<&str>) -> FeishuConfig {
+        FeishuConfig {
+            app_id: "cli_a1".into(),
+            app_secret: "secret".into(),
+            verification_token: Some("v3rify".into()),
+            bot_open_id: bot_open_id.map(str::to_string),
+        }
+    }
+
+    fn message_event(chat_type: &str, text: &str, mentions: Value) -> Value {
+        json!({
+            "schema": "2.0",
+            "header": { "event_type": "im.message.receive_v1", "token": "v3rify" },
+            "event": {
+                "sender": { "sender_id": { "open_id": "ou_alice" }, "sender_type": "user" },
+                "message": {
+                    "chat_id": "oc_team",
+                    "chat_type": chat_type,
+                    "message_type": "text",
+                    "content": json!({ "text": text }).to_string(),
+                    "mentions": mentions,
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_parse_url_verification() {
+        let body =
+            json!({ "type": "url_verification", "challenge": "ajls384kdj", "token": "v3rify" });
+        assert_eq!(
+            FeishuEvent::parse(&body, &config(None)),
+            FeishuEvent::UrlVerification {
+                challenge: "ajls384kdj".into()
+            }
+        );
+        let forged =
+            json!({ "type": "url_verification", "challenge": "ajls384kdj", "token": "nope" });
+        assert_eq!(
+            FeishuEvent::parse(&forged, &config(None)),
+            FeishuEvent::Ignored
+        );
+    }
+
+    #[test]
+    fn test_parse_group_messages_need_a_mention() {
+        let mentions = json!([
+            { "key": "@_user_1", "id": { "open_id": "ou_bot" }, "name": "StockBot" },
+            { "key": "@_user_2", "id": { "open_id": "ou_bob" }, "name": "Bob" }
+        ]);
+        let body = message_event(
+            "group",
+            "@_user_1 /compare AAPL MSFT for @_user_2",
+            mentions.clone(),
+        );
+        let FeishuEvent::Message(message) = FeishuEvent::parse(&body, &config(Some("ou_bot")))
+        else {
+            panic!("expected a message");
+        };
+        assert_eq!(message.text, "/compare AAPL MSFT for @Bob");
+        assert!(message.group);
+        assert_eq!(message.session_key(), "oc_team");
+
+        // Mentioning someone else does not wake the bot
+        let body = message_event("group", "@_user_2 /analyze AAPL", json!([mentions[1]]));
+        assert_eq!(
+            FeishuEvent::parse(&body, &config(Some("ou_bot"))),
+            FeishuEvent::Ignored
+        );
+        assert_eq!(
+            FeishuEvent::parse(
+                &message_event("group", "/analyze AAPL", json!([])),
+                &config(None)
+            ),
+            FeishuEvent::Ignored
+        );
+        // Without the bot's open ID any mention counts
+        let body = message_event("group", "@_user_1 /analyze AAPL", json!([mentions[0]]));
+        assert!(matches!(
+            FeishuEvent::parse(&body, &config(None)),
+            FeishuEvent::Message(_)
+        ));
+    }
+
+    #[test]
+    fn test_parse_direct_messages() {
+        let body = message_event("p2p", " /watch AAPL ", json!([]));
+        let FeishuEvent::Message(message) = FeishuEvent::parse(&body, &config(Some("ou_bot")))
+        else {
+            panic!("expected a message");
+        };
+        assert_eq!(message.text, "/watch AAPL");
+        assert!(!message.group);
+        assert_eq!(message.session_key(), "ou_alice");
+
+        let mut from_bot = body.clone();
+        from_bot["event"]["sender"]["sender_type"] = json!("app");
+        assert_eq!(
+            FeishuEvent::parse(&from_bot, &config(None)),
+            FeishuEvent::Ignored
+        );
+        let mut image = body;
+        image["event"]["message"]["message_type"] = json!("image");
+        assert_eq!(
+            FeishuEvent::parse(&image, &config(None)),
+            FeishuEvent::Ignored
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_response_uploads_attachments() {
+        use wiremock::matchers::{
+            body_partial_json, body_string_contains, header, method, path, query_param,
+        };
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/auth/v3/tenant_access_token/internal"))
+            .and(body_partial_json(
+                json!({ "app_id": "cli_1", "app_secret": "secret" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "code": 0, "tenant_access_token": "t-TOKEN", "expire": 7200
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/im/v1/images"))
+            .and(header("authorization", "Bearer t-TOKEN"))
+            .and(body_string_contains("message"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "code": 0, "data": { "image_key": "img_v2_1" }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/im/v1/messages"))
+            .and(query_param("receive_id_type", "chat_id"))
+            .and(body_partial_json(
+                json!({ "receive_id": "oc_1", "msg_type": "interactive" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "code": 0 })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/im/v1/messages"))
+            .and(body_partial_json(json!({
+                "receive_id": "oc_1",
+                "msg_type": "image",
+                "content": r#"{"image_key":"img_v2_1"}"#,
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "code": 0 })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let api = FeishuApi::new("cli_1", "secret").with_base_url(server.uri());
+        let response = BotResponse::text("**AAPL** 看涨").with_attachment(Attachment {
+            attachment_type: AttachmentType::Chart,
+            // ASCII, for the multipart body to match as a string
+            content: b"PNG".to_vec(),
+            filename: Some("AAPL-chart.png".into()),
+            mime_type: "image/png".into(),
+        });
+        api.send_response("oc_1", &response).await.unwrap();
+    }
+}
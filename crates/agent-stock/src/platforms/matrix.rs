@@ -0,0 +1,851 @@
+//! Matrix bot implementation
+//!
+//! The bot logs in as an ordinary Matrix user and talks to the homeserver
+//! through the Client-Server API: [`MatrixBot::run`] long-polls `/sync`,
+//! joins the rooms it is invited to and answers messages that start with a
+//! command or mention the bot. Conversation context, the watchlist and
+//! alerts belong to the room, so everyone in a room shares them.
+//!
+//! Replies are sent as `org.matrix.custom.html`, which Matrix clients show
+//! with the same tags as Telegram's HTML, along with a plain-text body.
+//! Each message is answered in its own task, so a long analysis in one room
+//! does not hold up the others.
+//!
+//! With the `matrix-e2ee` feature and `MATRIX_STORE_PATH` set, the bot syncs
+//! through matrix-sdk instead, which keeps its device keys in a SQLite store
+//! and reads and answers end-to-end encrypted rooms (see
+//! [`MatrixConfig::store_path`]).
+
+use crate::alerts::Notifier;
+use crate::bot::Command;
+use crate::engine::{AnalysisContext, StockAnalysisEngine};
+use crate::error::{Result, StockError};
+use crate::interface::interface::{Attachment, AttachmentType};
+use crate::interface::markup::{escape_html, html_to_text};
+use crate::interface::{
+    BotInterface, BotPlatform, BotResponse, Formatter, FormatterFactory, MessageLimit,
+    SessionManager, answer_progressively,
+};
+use crate::notebook;
+use crate::platforms::dispatch::{self, Reply};
+use crate::platforms::services::BotServices;
+use crate::report;
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::fmt::Write;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(feature = "matrix-e2ee")]
+mod e2ee;
+
+/// How long a `/sync` request waits for new events
+const SYNC_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Pause before syncing again after a failed sync
+const SYNC_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Matrix bot configuration
+#[derive(Debug, Clone)]
+pub struct MatrixConfig {
+    /// Homeserver (or Pantalaimon) URL, e.g. `https://matrix.example.org`
+    pub homeserver_url: String,
+
+    /// The bot's user ID, e.g. `@stockbot:example.org`
+    pub user_id: String,
+
+    /// Access token of the bot's account
+    pub access_token: String,
+
+    /// Where matrix-sdk keeps the bot's device and room keys, to read and
+    /// answer encrypted rooms (needs the `matrix-e2ee` feature)
+    ///
+    /// The store must outlive restarts: a device that loses its keys cannot
+    /// decrypt what is sent to it, and the access token stays tied to that
+    /// device.
+    pub store_path: Option<PathBuf>,
+
+    /// Passphrase encrypting the key store (optional)
+    pub store_passphrase: Option<String>,
+}
+
+impl MatrixConfig {
+    /// Create config from environment variables
+    pub fn from_env() -> Result<Self> {
+        let var = |name: &str| {
+            std::env::var(name).map_err(|_| StockError::ConfigError(format!("{name} not set")))
+        };
+
+        Ok(Self {
+            homeserver_url: var("MATRIX_HOMESERVER")?,
+            user_id: var("MATRIX_USER_ID")?,
+            access_token: var("MATRIX_ACCESS_TOKEN")?,
+            store_path: std::env::var_os("MATRIX_STORE_PATH").map(PathBuf::from),
+            store_passphrase: std::env::var("MATRIX_STORE_PASSPHRASE").ok(),
+        })
+    }
+}
+
+/// A message addressed to the bot
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatrixMessage {
+    /// Room the message was sent in
+    pub room_id: String,
+    /// Sender's user ID
+    pub sender: String,
+    /// Message text, without a leading mention of the bot
+    pub text: String,
+}
+
+/// What a `/sync` response asks of the bot
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MatrixSync {
+    /// Token to pass to the next sync
+    pub next_batch: String,
+    /// Rooms the bot was invited to
+    pub invites: Vec<String>,
+    /// New messages addressed to the bot, oldest first
+    pub messages: Vec<MatrixMessage>,
+}
+
+impl MatrixSync {
+    /// Parse a `/sync` response for the bot `user_id`
+    ///
+    /// The bot's own messages, edits and notices are skipped. Other messages
+    /// are kept if they start with `/` or mention the bot, either through
+    /// `m.mentions` or by starting with its user ID or localpart; the
+    /// mention is removed, so `stockbot: /analyze AAPL` reads as the command.
+    pub fn parse(body: &Value, user_id: &str) -> Self {
+        let rooms = &body["rooms"];
+        let invites = rooms["invite"]
+            .as_object()
+            .map(|invites| invites.keys().cloned().collect())
+            .unwrap_or_default();
+
+        let mut messages = Vec::new();
+        for (room_id, room) in rooms["join"].as_object().into_iter().flatten() {
+            let events = room["timeline"]["events"].as_array().into_iter().flatten();
+            for event in events {
+                let content = &event["content"];
+                let is_text = event["type"] == "m.room.message" && content["msgtype"] == "m.text";
+                let sender = event["sender"].as_str().unwrap_or_default();
+                if !is_text || sender == user_id || content.get("m.new_content").is_some() {
+                    continue;
+                }
+                let mentioned = content["m.mentions"]["user_ids"]
+                    .as_array()
+                    .is_some_and(|ids| ids.iter().any(|id| id == user_id));
+                let body = content["body"].as_str().unwrap_or_default();
+                if let Some(text) = addressed_text(body, user_id, mentioned) {
+                    messages.push(MatrixMessage {
+                        room_id: room_id.clone(),
+                        sender: sender.to_string(),
+                        text,
+                    });
+                }
+            }
+        }
+
+        Self {
+            next_batch: body["next_batch"].as_str().unwrap_or_default().to_string(),
+            invites,
+            messages,
+        }
+    }
+}
+
+/// Text of a message meant for the bot, without the mention, or `None`
+fn addressed_text(body: &str, user_id: &str, mentioned: bool) -> Option<String> {
+    let body = body.trim();
+    if body.starts_with('/') {
+        return Some(body.to_string());
+    }
+    let localpart = user_id
+        .trim_start_matches('@')
+        .split(':')
+        .next()
+        .unwrap_or_default();
+    let by_name = [user_id, localpart].into_iter().find_map(|name| {
+        body.get(..name.len())
+            .filter(|prefix| !name.is_empty() && prefix.eq_ignore_ascii_case(name))
+            .map(|_| &body[name.len()..])
+    });
+    let text = match by_name {
+        Some(rest) => rest,
+        // Clients write the display name before the colon of a mention
+        None if mentioned => body.split_once(": ").map_or(body, |(_, rest)| rest),
+        None => return None,
+    };
+    let text = text.trim_start_matches([':', ',']).trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// HTML with line breaks outside `<pre>` blocks written as `<br>`, since
+/// Matrix clients collapse newlines in `formatted_body`
+fn with_line_breaks(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    for (i, part) in html.split("<pre>").enumerate() {
+        if i > 0 {
+            out.push_str("<pre>");
+        }
+        match part.split_once("</pre>") {
+            Some((code, rest)) => {
+                out.push_str(code);
+                out.push_str("</pre>");
+                out.push_str(&rest.replace('\n', "<br>"));
+            }
+            None => out.push_str(&part.replace('\n', "<br>")),
+        }
+    }
+    out
+}
+
+/// Percent-encode a room ID or alias for a URL path
+fn encode_path(segment: &str) -> String {
+    segment.bytes().fold(String::new(), |mut out, byte| {
+        if byte.is_ascii_alphanumeric() || b"-._~!:".contains(&byte) {
+            out.push(char::from(byte));
+        } else {
+            let _ = write!(out, "%{byte:02X}");
+        }
+        out
+    })
+}
+
+/// Minimal Matrix Client-Server API client for syncing, joining rooms and
+/// sending replies
+#[derive(Clone)]
+pub struct MatrixApi {
+    access_token: String,
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl MatrixApi {
+    /// Create a client for the account with this access token
+    pub fn new(homeserver_url: impl Into<String>, access_token: impl Into<String>) -> Self {
+        Self {
+            access_token: access_token.into(),
+            base_url: homeserver_url.into().trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Client for the configured account
+    pub fn from_config(config: &MatrixConfig) -> Self {
+        Self::new(&config.homeserver_url, &config.access_token)
+    }
+
+    /// Send requests to `base_url` (e.g. a mock)
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// User ID the access token belongs to
+    pub async fn whoami(&self) -> Result<String> {
+        Ok(self.whoami_device().await?.0)
+    }
+
+    /// User ID and device ID the access token belongs to
+    pub async fn whoami_device(&self) -> Result<(String, Option<String>)> {
+        let body = self
+            .request(self.client.get(self.url("account/whoami")))
+            .await?;
+        let user_id = body["user_id"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| StockError::ApiError("Matrix whoami returned no user_id".to_string()))?;
+        Ok((user_id, body["device_id"].as_str().map(str::to_string)))
+    }
+
+    /// Events since `since` for the bot `user_id`, waiting up to `timeout`
+    /// for some to arrive
+    pub async fn sync(
+        &self,
+        user_id: &str,
+        since: Option<&str>,
+        timeout: Duration,
+    ) -> Result<MatrixSync> {
+        let mut request = self
+            .client
+            .get(self.url("sync"))
+            .query(&[("timeout", timeout.as_millis().to_string())])
+            // Long enough for the homeserver to answer an idle long poll
+            .timeout(timeout + Duration::from_secs(30));
+        if let Some(since) = since {
+            request = request.query(&[("since", since)]);
+        }
+        let body = self.request(request).await?;
+        Ok(MatrixSync::parse(&body, user_id))
+    }
+
+    /// Join a room the bot was invited to
+    pub async fn join(&self, room_id: &str) -> Result<()> {
+        let url = self.url(&format!("join/{}", encode_path(room_id)));
+        self.request(self.client.post(url).json(&json!({}))).await?;
+        Ok(())
+    }
+
+    /// Send an HTML message to a room, with its plain text as the body
+    pub async fn send_html(&self, room_id: &str, html: &str) -> Result<()> {
+        self.send_message(
+            room_id,
+            &json!({
+                "msgtype": "m.text",
+                "body": html_to_text(html),
+                "format": "org.matrix.custom.html",
+                "formatted_body": with_line_breaks(html),
+            }),
+        )
+        .await
+    }
+
+    /// Send every message of a reply, then its attachments
+    pub async fn send_response(&self, room_id: &str, response: &BotResponse) -> Result<()> {
+        for text in response.messages() {
+            self.send_html(room_id, text).await?;
+        }
+        for attachment in &response.attachments {
+            self.send_file(room_id, attachment).await?;
+        }
+        Ok(())
+    }
+
+    /// Upload `attachment` to the homeserver's media repository and post it
+    /// to a room as an `m.image`, `m.audio` or `m.file` message
+    pub async fn send_file(&self, room_id: &str, attachment: &Attachment) -> Result<()> {
+        let msgtype = match attachment.attachment_type {
+            AttachmentType::Image | AttachmentType::Chart => "m.image",
+            AttachmentType::Audio => "m.audio",
+            AttachmentType::Document => "m.file",
+        };
+        let filename = attachment
+            .filename
+            .clone()
+            .unwrap_or_else(|| "attachment".to_string());
+        let uri = self.upload(&filename, attachment).await?;
+        self.send_message(
+            room_id,
+            &json!({
+                "msgtype": msgtype,
+                "body": filename,
+                "url": uri,
+                "info": { "mimetype": attachment.mime_type, "size": attachment.content.len() },
+            }),
+        )
+        .await
+    }
+
+    /// Upload `attachment`; returns its `mxc://` content URI
+    pub async fn upload(&self, filename: &str, attachment: &Attachment) -> Result<String> {
+        let request = self
+            .client
+            .post(format!("{}/_matrix/media/v3/upload", self.base_url))
+            .query(&[("filename", filename)])
+            .header(reqwest::header::CONTENT_TYPE, &attachment.mime_type)
+            .body(attachment.content.clone());
+        let body = self.request(request).await?;
+        body["content_uri"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| {
+                StockError::ApiError("Matrix upload returned no content_uri".to_string())
+            })
+    }
+
+    async fn send_message(&self, room_id: &str, content: &Value) -> Result<()> {
+        let url = self.url(&format!(
+            "rooms/{}/send/m.room.message/{}",
+            encode_path(room_id),
+            uuid::Uuid::new_v4()
+        ));
+        self.request(self.client.put(url).json(content)).await?;
+        Ok(())
+    }
+
+    fn url(&self, endpoint: &str) -> String {
+        format!("{}/_matrix/client/v3/{endpoint}", self.base_url)
+    }
+
+    async fn request(&self, request: reqwest::RequestBuilder) -> Result<Value> {
+        let response = request.bearer_auth(&self.access_token).send().await?;
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        if status.is_success() {
+            return Ok(body);
+        }
+        let error = body["error"].as_str().unwrap_or("unknown error");
+        Err(match body["errcode"].as_str() {
+            Some("M_UNKNOWN_TOKEN" | "M_MISSING_TOKEN") => StockError::ConfigError(format!(
+                "Matrix rejected the access token (check MATRIX_ACCESS_TOKEN): {error}"
+            )),
+            Some("M_LIMIT_EXCEEDED") => StockError::rate_limited("Matrix"),
+            code => StockError::ApiError(format!(
+                "Matrix request failed ({}): {error}",
+                code.unwrap_or(status.as_str())
+            )),
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for MatrixApi {
+    async fn notify(&self, room_id: &str, message: &str) -> Result<()> {
+        self.send_html(room_id, &escape_html(message)).await
+    }
+}
+
+/// Matrix bot
+pub struct MatrixBot {
+    config: MatrixConfig,
+    engine: StockAnalysisEngine,
+    session_manager: SessionManager,
+    formatter: Box<dyn Formatter>,
+    services: BotServices,
+    api: MatrixApi,
+}
+
+impl MatrixBot {
+    /// Create a new Matrix bot
+    pub fn new(config: MatrixConfig, engine: StockAnalysisEngine) -> Self {
+        let api = MatrixApi::from_config(&config);
+        Self {
+            config,
+            engine,
+            session_manager: SessionManager::new(BotPlatform::Matrix),
+            formatter: FormatterFactory::create_with_limit(
+                BotPlatform::Matrix,
+                MessageLimit::from_env(BotPlatform::Matrix),
+            ),
+            services: BotServices::default(),
+            api,
+        }
+    }
+
+    /// Serve users with `services`; bots serving the same users may share
+    /// one set
+    pub fn with_services(mut self, services: BotServices) -> Self {
+        if let Some(store) = &services.conversations {
+            self.session_manager.set_conversations(Arc::clone(store));
+        }
+        self.services = services;
+        self
+    }
+
+    /// Use a different Client-Server API client
+    pub fn with_api(mut self, api: MatrixApi) -> Self {
+        self.api = api;
+        self
+    }
+
+    /// Client-Server API client replies are sent with
+    pub fn api(&self) -> &MatrixApi {
+        &self.api
+    }
+
+    /// Process a command sent in a room
+    pub async fn process_command(&self, room_id: &str, input: &str) -> Result<String> {
+        let mut session = self.session_manager.get_or_create(room_id)?;
+        let mut context = session.context.clone();
+
+        let command = Command::parse(input)?;
+        let exchange = command.is_heavy().then(|| command.symbols());
+
+        let reply = Box::pin(dispatch::command_reply(
+            &self.engine,
+            &self.services,
+            &mut session,
+            &mut context,
+            &command,
+        ))
+        .await?;
+        let response = match reply {
+            Some(Reply::Analysis(result)) => self.formatter.format_analysis(&result, &context),
+            Some(Reply::Comparison(result)) => escape_html(&result.summary),
+            Some(Reply::Text(text)) => escape_html(&text),
+            Some(Reply::Table(text)) => format!("<pre>{}</pre>", escape_html(&text)),
+            None if command == Command::Help => self.formatter.format_help(),
+            None => "Command not yet implemented".to_string(),
+        };
+
+        if let Some(symbols) = exchange {
+            context.record_exchange(input, &response, symbols);
+        }
+        session.context = context;
+        self.session_manager.update(room_id, session)?;
+
+        Ok(response)
+    }
+
+    /// Answer a message in its room
+    pub async fn on_room_message(&self, message: &MatrixMessage) -> Result<()> {
+        let (api, room_id) = (&self.api, message.room_id.as_str());
+        let interim = |reply: BotResponse| async move {
+            if let Err(e) = api.send_response(room_id, &reply).await {
+                tracing::warn!("Could not send an interim reply in {room_id}: {e}");
+            }
+        };
+        let response = self.answer(message, interim).await;
+        self.api.send_response(room_id, &response).await
+    }
+
+    /// Reply to a message, with errors written as a reply and interim
+    /// replies, like queue acknowledgements, handed to `interim`
+    async fn answer<S, F>(&self, message: &MatrixMessage, interim: S) -> BotResponse
+    where
+        S: FnMut(BotResponse) -> F,
+        F: Future<Output = ()>,
+    {
+        let mut context = AnalysisContext::with_user(&message.sender);
+        match answer_progressively(self, &message.room_id, &message.text, &mut context, interim)
+            .await
+        {
+            Ok(response) => response,
+            Err(e) => BotResponse::error(self.formatter.format_error(&e.to_string())),
+        }
+    }
+
+    /// Sync with the homeserver until the access token stops working,
+    /// joining rooms the bot is invited to and answering each message in
+    /// its own task
+    ///
+    /// Messages sent before the bot started are not answered. With a
+    /// [`MatrixConfig::store_path`] and the `matrix-e2ee` feature, syncs
+    /// through matrix-sdk and answers encrypted rooms too.
+    pub async fn run(self: &Arc<Self>) -> Result<()> {
+        if let Some(store) = &self.config.store_path {
+            #[cfg(feature = "matrix-e2ee")]
+            return self.run_encrypted(store).await;
+            #[cfg(not(feature = "matrix-e2ee"))]
+            tracing::warn!(
+                "MATRIX_STORE_PATH ({}) is set, but encrypted rooms need the matrix-e2ee feature",
+                store.display()
+            );
+        }
+
+        let user_id = self.api.whoami().await?;
+        if user_id != self.config.user_id {
+            tracing::warn!(
+                "MATRIX_ACCESS_TOKEN belongs to {user_id}, not {}",
+                self.config.user_id
+            );
+        }
+
+        let mut since = self
+            .api
+            .sync(&user_id, None, Duration::ZERO)
+            .await?
+            .next_batch;
+        loop {
+            let sync = match self.api.sync(&user_id, Some(&since), SYNC_TIMEOUT).await {
+                Ok(sync) => sync,
+                Err(e @ StockError::ConfigError(_)) => return Err(e),
+                Err(e) => {
+                    tracing::warn!("Matrix sync failed: {e}");
+                    tokio::time::sleep(SYNC_RETRY_DELAY).await;
+                    continue;
+                }
+            };
+            for room_id in &sync.invites {
+                if let Err(e) = self.api.join(room_id).await {
+                    tracing::warn!("Could not join {room_id}: {e}");
+                }
+            }
+            for message in sync.messages {
+                let bot = Arc::clone(self);
+                tokio::spawn(async move {
+                    if let Err(e) = bot.on_room_message(&message).await {
+                        tracing::warn!("Could not answer in {}: {e}", message.room_id);
+                    }
+                });
+            }
+            since = sync.next_batch;
+        }
+    }
+}
+
+#[async_trait]
+impl BotInterface for MatrixBot {
+    fn platform(&self) -> BotPlatform {
+        BotPlatform::Matrix
+    }
+
+    async fn on_message(
+        &self,
+        room_id: &str,
+        message: &str,
+        _context: &mut AnalysisContext,
+    ) -> Result<BotResponse> {
+        match Command::parse(message) {
+            Ok(Command::More) => return self.session_manager.next_page(room_id),
+            // Notebooks go out as a document along with the caption
+            Ok(Command::Notebook { kind }) => {
+                let session = self.session_manager.get_or_create(room_id)?;
+                let (text, notebook) = notebook::command_reply(&session.context, kind).await?;
+                let reply = self.session_manager.paginate(
+                    room_id,
+                    &escape_html(&text),
+                    self.formatter.message_limit(),
+                )?;
+                return Ok(match notebook {
+                    Some(notebook) => reply.with_attachment(notebook),
+                    None => reply,
+                });
+            }
+            // Reports go out as a document, after the room's earlier requests
+            Ok(Command::Report { symbols, format }) => {
+                let (text, document) = report::queued_reply(
+                    &self.engine,
+                    &self.services.queue,
+                    &self.session_manager,
+                    room_id,
+                    &symbols,
+                    format,
+                    BotPlatform::Matrix,
+                )
+                .await?;
+                let reply = self.session_manager.paginate(
+                    room_id,
+                    &escape_html(&text),
+                    self.formatter.message_limit(),
+                )?;
+                return Ok(reply.with_attachments(document));
+            }
+            // Heavy requests wait for the room's earlier ones to finish
+            Ok(command) if command.is_heavy() => {
+                let response = self
+                    .services
+                    .queue
+                    .run(room_id, self.process_command(room_id, message))
+                    .await??;
+                return self.session_manager.paginate(
+                    room_id,
+                    &response,
+                    self.formatter.message_limit(),
+                );
+            }
+            _ => {}
+        }
+        let response = self.process_command(room_id, message).await?;
+        self.session_manager
+            .paginate(room_id, &response, self.formatter.message_limit())
+    }
+
+    async fn on_command(
+        &self,
+        room_id: &str,
+        command: &str,
+        args: &[String],
+        context: &mut AnalysisContext,
+    ) -> Result<BotResponse> {
+        let full_command = if args.is_empty() {
+            format!("/{command}")
+        } else {
+            format!("/{} {}", command, args.join(" "))
+        };
+
+        self.on_message(room_id, &full_command, context).await
+    }
+
+    fn format_response(&self, content: &str, _context: &AnalysisContext) -> BotResponse {
+        BotResponse::formatted(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, header, method, path, path_regex, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const BOT: &str = "@stockbot:example.org";
+
+    fn message(sender: &str, content: Value) -> Value {
+        json!({ "type": "m.room.message", "sender": sender, "content": content })
+    }
+
+    #[test]
+    fn test_parse_sync() {
+        let body = json!({
+            "next_batch": "s72595_4483_1934",
+            "rooms": {
+                "invite": { "!new:example.org": {} },
+                "join": {
+                    "!team:example.org": {
+                        "timeline": {
+                            "events": [
+                                message("@alice:example.org", json!({ "msgtype": "m.text", "body": "/analyze AAPL" })),
+                                message("@alice:example.org", json!({ "msgtype": "m.text", "body": "lunch?" })),
+                                message("@bob:example.org", json!({
+                                    "msgtype": "m.text",
+                                    "body": "Stock Bot: how is NVDA doing",
+                                    "m.mentions": { "user_ids": [BOT] },
+                                })),
+                                message("@bob:example.org", json!({ "msgtype": "m.text", "body": "stockbot, /watchlist" })),
+                                message(BOT, json!({ "msgtype": "m.text", "body": "/help" })),
+                                message("@bob:example.org", json!({ "msgtype": "m.notice", "body": "/help" })),
+                                message("@bob:example.org", json!({
+                                    "msgtype": "m.text",
+                                    "body": "* /analyze MSFT",
+                                    "m.new_content": { "msgtype": "m.text", "body": "/analyze MSFT" },
+                                })),
+                                { "type": "m.room.member", "sender": "@carol:example.org", "content": {} },
+                            ]
+                        }
+                    }
+                }
+            }
+        });
+
+        let sync = MatrixSync::parse(&body, BOT);
+        assert_eq!(sync.next_batch, "s72595_4483_1934");
+        assert_eq!(sync.invites, vec!["!new:example.org"]);
+        let texts: Vec<_> = sync
+            .messages
+            .iter()
+            .map(|m| (m.sender.as_str(), m.text.as_str()))
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                ("@alice:example.org", "/analyze AAPL"),
+                ("@bob:example.org", "how is NVDA doing"),
+                ("@bob:example.org", "/watchlist"),
+            ]
+        );
+        assert!(
+            sync.messages
+                .iter()
+                .all(|m| m.room_id == "!team:example.org")
+        );
+        assert_eq!(MatrixSync::parse(&json!({}), BOT), MatrixSync::default());
+    }
+
+    #[test]
+    fn test_line_breaks() {
+        assert_eq!(
+            with_line_breaks("<b>AAPL</b>\nUp\n<pre>a\nb</pre>\nDone"),
+            "<b>AAPL</b><br>Up<br><pre>a\nb</pre><br>Done"
+        );
+        assert_eq!(encode_path("#stocks:example.org"), "%23stocks:example.org");
+    }
+
+    #[tokio::test]
+    async fn test_api_calls() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/_matrix/client/v3/account/whoami"))
+            .and(header("authorization", "Bearer TOKEN"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "user_id": BOT })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/_matrix/client/v3/sync"))
+            .and(query_param("since", "s1"))
+            .and(query_param("timeout", "0"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "next_batch": "s2" })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/_matrix/client/v3/join/%23stocks:example.org"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(json!({ "room_id": "!r:example.org" })),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path_regex(
+                r"^/_matrix/client/v3/rooms/!r:example.org/send/m.room.message/.+$",
+            ))
+            .and(body_partial_json(json!({
+                "msgtype": "m.text",
+                "body": "AAPL < 200\nBullish",
+                "format": "org.matrix.custom.html",
+                "formatted_body": "<b>AAPL</b> &lt; 200<br>Bullish",
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "event_id": "$e" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let api = MatrixApi::new("https://matrix.example.org", "TOKEN").with_base_url(server.uri());
+        assert_eq!(api.whoami().await.unwrap(), BOT);
+        assert_eq!(
+            api.sync(BOT, Some("s1"), Duration::ZERO)
+                .await
+                .unwrap()
+                .next_batch,
+            "s2"
+        );
+        api.join("#stocks:example.org").await.unwrap();
+        api.send_html("!r:example.org", "<b>AAPL</b> &lt; 200\nBullish")
+            .await
+            .unwrap();
+
+        let revoked = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(json!({
+                "errcode": "M_UNKNOWN_TOKEN", "error": "Invalid access token passed."
+            })))
+            .mount(&revoked)
+            .await;
+        let api = MatrixApi::new(revoked.uri(), "OLD");
+        assert!(matches!(
+            api.whoami().await,
+            Err(StockError::ConfigError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_send_response_uploads_attachments() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path_regex(
+                r"^/_matrix/client/v3/rooms/!r:example.org/send/m.room.message/.+$",
+            ))
+            .and(body_partial_json(json!({ "msgtype": "m.text" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "event_id": "$e1" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/_matrix/media/v3/upload"))
+            .and(query_param("filename", "AAPL-chart.png"))
+            .and(header("content-type", "image/png"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "content_uri": "mxc://example.org/chart"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path_regex(
+                r"^/_matrix/client/v3/rooms/!r:example.org/send/m.room.message/.+$",
+            ))
+            .and(body_partial_json(json!({
+                "msgtype": "m.image",
+                "body": "AAPL-chart.png",
+                "url": "mxc://example.org/chart",
+                "info": { "mimetype": "image/png", "size": 4 },
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "event_id": "$e2" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let api = MatrixApi::new(server.uri(), "TOKEN");
+        let response = BotResponse::text("<b>AAPL</b> chart").with_attachment(Attachment {
+            attachment_type: AttachmentType::Chart,
+            content: b"\x89PNG".to_vec(),
+            filename: Some("AAPL-chart.png".into()),
+            mime_type: "image/png".into(),
+        });
+        api.send_response("!r:example.org", &response)
+            .await
+            .unwrap();
+    }
+}
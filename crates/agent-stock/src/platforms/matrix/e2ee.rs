@@ -0,0 +1,175 @@
+//! End-to-end encrypted rooms through matrix-sdk
+//!
+//! [`MatrixBot::run`] syncs through matrix-sdk instead of the plain
+//! Client-Server API when [`MatrixConfig::store_path`] is set. The SDK
+//! restores the access token's device from the SQLite store at that path,
+//! decrypts messages before the bot reads them, shares room keys with the
+//! members of encrypted rooms and encrypts the bot's replies and their
+//! attachments there. Unencrypted rooms work as before.
+//!
+//! [`MatrixConfig::store_path`]: super::MatrixConfig::store_path
+
+use super::{MatrixBot, MatrixMessage, addressed_text, with_line_breaks};
+use crate::error::{Result, StockError};
+use crate::interface::BotResponse;
+use crate::interface::markup::html_to_text;
+use matrix_sdk::attachment::AttachmentConfig;
+use matrix_sdk::authentication::matrix::{MatrixSession, MatrixSessionTokens};
+use matrix_sdk::config::SyncSettings;
+use matrix_sdk::ruma::UserId;
+use matrix_sdk::ruma::events::room::member::StrippedRoomMemberEvent;
+use matrix_sdk::ruma::events::room::message::{
+    MessageType, OriginalSyncRoomMessageEvent, Relation, RoomMessageEventContent,
+};
+use matrix_sdk::{Client, Room, RoomState, SessionMeta};
+use std::path::Path;
+use std::sync::Arc;
+
+impl MatrixBot {
+    /// Sync through matrix-sdk with the key store at `store`, answering
+    /// each message in its own task
+    pub(super) async fn run_encrypted(self: &Arc<Self>, store: &Path) -> Result<()> {
+        // The access token belongs to a device; the SDK needs its ID to
+        // restore the device's keys
+        let (user_id, device_id) = self.api.whoami_device().await?;
+        let device_id = device_id.ok_or_else(|| {
+            StockError::ConfigError(
+                "MATRIX_ACCESS_TOKEN has no device; log in again to get a device-bound token"
+                    .to_string(),
+            )
+        })?;
+        let user_id = UserId::parse(user_id.as_str()).map_err(sdk_error)?;
+
+        let client = Client::builder()
+            .homeserver_url(&self.config.homeserver_url)
+            .sqlite_store(store, self.config.store_passphrase.as_deref())
+            .build()
+            .await
+            .map_err(sdk_error)?;
+        client
+            .restore_session(MatrixSession {
+                meta: SessionMeta {
+                    user_id: user_id.clone(),
+                    device_id: device_id.into(),
+                },
+                tokens: MatrixSessionTokens {
+                    access_token: self.config.access_token.clone(),
+                    refresh_token: None,
+                },
+            })
+            .await
+            .map_err(sdk_error)?;
+
+        // Skip messages sent before the bot started
+        let initial = client
+            .sync_once(SyncSettings::default())
+            .await
+            .map_err(sdk_error)?;
+
+        client.add_event_handler(
+            |event: StrippedRoomMemberEvent, room: Room, client: Client| async move {
+                if client.user_id() == Some(&*event.state_key)
+                    && let Err(e) = room.join().await
+                {
+                    tracing::warn!("Could not join {}: {e}", room.room_id());
+                }
+            },
+        );
+        let bot = Arc::clone(self);
+        client.add_event_handler(move |event: OriginalSyncRoomMessageEvent, room: Room| {
+            let bot = Arc::clone(&bot);
+            let user_id = user_id.clone();
+            async move {
+                if room.state() != RoomState::Joined {
+                    return;
+                }
+                let Some(message) = addressed_message(&event, &room, &user_id) else {
+                    return;
+                };
+                // Answered in its own task; the SDK waits for handlers
+                // before processing the rest of the sync
+                tokio::spawn(async move {
+                    let interim = |reply: BotResponse| {
+                        let room = &room;
+                        async move {
+                            if let Err(e) = send_response(room, &reply).await {
+                                tracing::warn!("Could not send an interim reply: {e}");
+                            }
+                        }
+                    };
+                    let response = bot.answer(&message, interim).await;
+                    if let Err(e) = send_response(&room, &response).await {
+                        tracing::warn!("Could not answer in {}: {e}", message.room_id);
+                    }
+                });
+            }
+        });
+
+        client
+            .sync(SyncSettings::default().token(initial.next_batch))
+            .await
+            .map_err(sdk_error)
+    }
+}
+
+/// The message of `event` if it is addressed to the bot `user_id`
+///
+/// Like [`MatrixSync::parse`](super::MatrixSync::parse): the bot's own
+/// messages, edits and notices are skipped, and other text messages kept if
+/// they start with `/` or mention the bot.
+fn addressed_message(
+    event: &OriginalSyncRoomMessageEvent,
+    room: &Room,
+    user_id: &UserId,
+) -> Option<MatrixMessage> {
+    let MessageType::Text(text) = &event.content.msgtype else {
+        return None;
+    };
+    if event.sender == user_id || matches!(event.content.relates_to, Some(Relation::Replacement(_)))
+    {
+        return None;
+    }
+    let mentioned = event
+        .content
+        .mentions
+        .as_ref()
+        .is_some_and(|mentions| mentions.user_ids.contains(user_id));
+    Some(MatrixMessage {
+        room_id: room.room_id().to_string(),
+        sender: event.sender.to_string(),
+        text: addressed_text(&text.body, user_id.as_str(), mentioned)?,
+    })
+}
+
+/// Send every message of a reply, then its attachments, encrypted if the
+/// room is
+async fn send_response(room: &Room, response: &BotResponse) -> Result<()> {
+    for text in response.messages() {
+        let content =
+            RoomMessageEventContent::text_html(html_to_text(text), with_line_breaks(text));
+        room.send(content).await.map_err(sdk_error)?;
+    }
+    for attachment in &response.attachments {
+        let content_type = attachment
+            .mime_type
+            .parse()
+            .unwrap_or(mime::APPLICATION_OCTET_STREAM);
+        let filename = attachment
+            .filename
+            .clone()
+            .unwrap_or_else(|| "attachment".to_string());
+        room.send_attachment(
+            filename,
+            &content_type,
+            attachment.content.clone(),
+            AttachmentConfig::new(),
+        )
+        .await
+        .map_err(sdk_error)?;
+    }
+    Ok(())
+}
+
+fn sdk_error(e: impl std::fmt::Display) -> StockError {
+    StockError::ApiError(format!("Matrix request failed: {e}"))
+}
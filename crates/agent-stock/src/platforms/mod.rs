@@ -1,11 +1,22 @@
 //! Platform-specific bot implementations
 
 pub mod cli;
-pub mod telegram;
 pub mod dingtalk;
+mod dispatch;
 pub mod feishu;
+pub mod matrix;
+pub mod services;
+pub mod slack;
+pub mod telegram;
+pub mod voice;
+pub mod wecom;
 
-pub use cli::CliBot;
-pub use telegram::{TelegramBot, TelegramConfig};
-pub use dingtalk::{DingTalkBot, DingTalkConfig};
-pub use feishu::{FeishuBot, FeishuConfig};
+pub use cli::{CliBot, ConsoleNotifier};
+pub use dingtalk::{DingTalkBot, DingTalkConfig, DingTalkMessage, DingTalkWebhook};
+pub use feishu::{FeishuApi, FeishuBot, FeishuConfig, FeishuEvent, FeishuMessage, FeishuWebhook};
+pub use matrix::{MatrixApi, MatrixBot, MatrixConfig, MatrixMessage, MatrixSync};
+pub use services::BotServices;
+pub use slack::{SlackApi, SlackBot, SlackConfig, SlackEvent};
+pub use telegram::{TelegramApi, TelegramBot, TelegramConfig};
+pub use voice::{OpenAiTts, Synthesizer, Transcriber, Transcript, WhisperApi, WhisperCpp};
+pub use wecom::{WeComApi, WeComBot, WeComConfig, WeComEvent, WeComWebhook};
@@ -0,0 +1,162 @@
+//! Services shared by the chat platform bots
+//!
+//! Every chat bot can take the same optional stores and agents: alerts,
+//! quiet hours, live prices, portfolios, the query builder and so on.
+//! [`BotServices`] collects them once so one set can be handed to each bot
+//! serving the same users with `with_services`.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! let services = BotServices::new()
+//!     .with_alerts(alert_store)
+//!     .with_delivery(delivery)
+//!     .with_conversations(conversations);
+//! let telegram = TelegramBot::new(telegram_config, engine.clone())
+//!     .with_services(services.clone());
+//! let feishu = FeishuBot::new(feishu_config, engine).with_services(services);
+//! ```
+
+use crate::agents::{PortfolioAgent, QueryBuilderAgent};
+use crate::alerts::AlertStore;
+use crate::conversation_store::ConversationStore;
+use crate::delivery::DeliveryStore;
+use crate::interface::queue::RequestQueue;
+use crate::live::LiveQuotes;
+use crate::macro_alerts::MacroWatchlist;
+use crate::market_wrap::MarketWrapJob;
+use crate::platforms::voice::{Synthesizer, Transcriber};
+use crate::predictions::PredictionTracker;
+use std::sync::Arc;
+
+/// Optional stores and agents a chat bot serves its users with
+///
+/// Cheap to clone; clones share the same stores, queue and agents.
+#[derive(Clone)]
+pub struct BotServices {
+    pub(crate) queue: RequestQueue,
+    pub(crate) transcriber: Option<Arc<dyn Transcriber>>,
+    pub(crate) synthesizer: Option<Arc<dyn Synthesizer>>,
+    pub(crate) alerts: Option<Arc<AlertStore>>,
+    pub(crate) delivery: Option<Arc<DeliveryStore>>,
+    pub(crate) macro_watches: Option<Arc<MacroWatchlist>>,
+    pub(crate) live: Option<Arc<LiveQuotes>>,
+    pub(crate) portfolio: Option<Arc<PortfolioAgent>>,
+    pub(crate) query_builder: Option<Arc<QueryBuilderAgent>>,
+    pub(crate) predictions: Option<Arc<PredictionTracker>>,
+    pub(crate) market_wrap: Option<Arc<MarketWrapJob>>,
+    pub(crate) conversations: Option<Arc<dyn ConversationStore>>,
+}
+
+impl Default for BotServices {
+    fn default() -> Self {
+        Self {
+            queue: RequestQueue::from_env(),
+            transcriber: None,
+            synthesizer: None,
+            alerts: None,
+            delivery: None,
+            macro_watches: None,
+            live: None,
+            portfolio: None,
+            query_builder: None,
+            predictions: None,
+            market_wrap: None,
+            conversations: None,
+        }
+    }
+}
+
+impl BotServices {
+    /// No optional services, with a queue configured from the environment
+    /// (see [`RequestQueue::from_env`])
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Share a request queue with other bot instances serving the same
+    /// users, so their requests are ordered and capped together
+    pub fn with_queue(mut self, queue: RequestQueue) -> Self {
+        self.queue = queue;
+        self
+    }
+
+    /// Accept voice messages, transcribed by `transcriber`
+    /// (see [`voice::transcriber_from_env`](crate::platforms::voice::transcriber_from_env));
+    /// only Telegram receives voice messages
+    pub fn with_transcriber(mut self, transcriber: Arc<dyn Transcriber>) -> Self {
+        self.transcriber = Some(transcriber);
+        self
+    }
+
+    /// Offer audio summaries of long replies, spoken by `synthesizer`
+    /// (see [`voice::synthesizer_from_env`](crate::platforms::voice::synthesizer_from_env));
+    /// users opt in with `/voice on` on platforms that send audio (Telegram)
+    pub fn with_synthesizer(mut self, synthesizer: Arc<dyn Synthesizer>) -> Self {
+        self.synthesizer = Some(synthesizer);
+        self
+    }
+
+    /// Let users set price alerts, kept in `store`; an
+    /// [`AlertEngine`](crate::alerts::AlertEngine) sharing the store pushes
+    /// them through the platform's [`Notifier`](crate::alerts::Notifier)
+    pub fn with_alerts(mut self, store: Arc<AlertStore>) -> Self {
+        self.alerts = Some(store);
+        self
+    }
+
+    /// Let users set quiet hours, kept in `store`; share it with the
+    /// [`AlertEngine`](crate::alerts::AlertEngine) that pushes their alerts
+    pub fn with_delivery(mut self, store: Arc<DeliveryStore>) -> Self {
+        self.delivery = Some(store);
+        self
+    }
+
+    /// Let users watch FRED series with `/macro watch`, kept in `watches`;
+    /// share it with the [`MacroAlertJob`](crate::macro_alerts::MacroAlertJob)
+    /// whose alerts are pushed to them
+    pub fn with_macro_watches(mut self, watches: Arc<MacroWatchlist>) -> Self {
+        self.macro_watches = Some(watches);
+        self
+    }
+
+    /// Let users stream live prices with `/live`; register the platform's
+    /// [`Notifier`](crate::alerts::Notifier) with `live` to deliver them
+    pub fn with_live(mut self, live: Arc<LiveQuotes>) -> Self {
+        self.live = Some(live);
+        self
+    }
+
+    /// Let users record positions with `/portfolio` and ask about them
+    pub fn with_portfolio(mut self, portfolio: Arc<PortfolioAgent>) -> Self {
+        self.portfolio = Some(portfolio);
+        self
+    }
+
+    /// Let users turn questions into screens or SQL queries with `/ask` and
+    /// run them with `/ask run`
+    pub fn with_query_builder(mut self, query_builder: Arc<QueryBuilderAgent>) -> Self {
+        self.query_builder = Some(query_builder);
+        self
+    }
+
+    /// Show how past directional calls played out with `/scoreboard`,
+    /// scored from `tracker` (e.g. [`StockBot::predictions`](crate::bot::StockBot::predictions))
+    pub fn with_predictions(mut self, tracker: Arc<PredictionTracker>) -> Self {
+        self.predictions = Some(tracker);
+        self
+    }
+
+    /// Let users ask for the market wrap with `/wrap`, generated by `job`
+    /// with news for their watchlist
+    pub fn with_market_wrap(mut self, job: Arc<MarketWrapJob>) -> Self {
+        self.market_wrap = Some(job);
+        self
+    }
+
+    /// Keep users' conversation history in `store` across restarts
+    pub fn with_conversations(mut self, store: Arc<dyn ConversationStore>) -> Self {
+        self.conversations = Some(store);
+        self
+    }
+}
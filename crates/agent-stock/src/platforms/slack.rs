@@ -0,0 +1,864 @@
+//! Slack bot implementation
+//!
+//! Messages arrive through the Events API: the HTTP handler checks the
+//! request with [`SlackConfig::verify_request`], answers Slack's
+//! `url_verification` challenge itself (see [`SlackEvent::parse`]) and hands
+//! every other event to [`SlackBot::spawn_event`], then acks at once. Slack
+//! retries events not acked within three seconds, so the reply is worked out
+//! in a task of its own, and redelivered events are dropped by `event_id`.
+//! [`SlackBot::on_event`] replies in the same channel through the Web API.
+//! Slash commands registered for the app (e.g. `/analyze` with text `AAPL`)
+//! go to [`BotInterface::on_command`].
+//!
+//! Replies are sent with a `mrkdwn` fallback text and Block Kit blocks (see
+//! [`crate::interface::block_kit`]); charts, reports and notebooks are
+//! uploaded to the channel as files.
+
+use crate::alerts::Notifier;
+use crate::bot::Command;
+use crate::engine::{AnalysisContext, StockAnalysisEngine};
+use crate::error::{Result, StockError};
+use crate::interface::block_kit::SlackFormatter;
+use crate::interface::interface::Attachment;
+use crate::interface::markup::escape_html;
+use crate::interface::{
+    BotInterface, BotPlatform, BotResponse, Formatter, FormatterFactory, MessageLimit,
+    SessionManager, answer_progressively,
+};
+use crate::notebook;
+use crate::platforms::dispatch::{self, Reply};
+use crate::platforms::services::BotServices;
+use crate::report;
+use async_trait::async_trait;
+use chrono::Utc;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Slack Web API base URL
+const SLACK_API_URL: &str = "https://slack.com/api";
+
+/// Oldest request timestamp accepted, against replayed requests
+const MAX_REQUEST_AGE_SECS: i64 = 5 * 60;
+
+/// Event ids remembered to drop redelivered events
+const SEEN_EVENTS: usize = 1000;
+
+/// Slack app configuration
+#[derive(Debug, Clone)]
+pub struct SlackConfig {
+    /// Bot user OAuth token (`xoxb-...`)
+    pub bot_token: String,
+
+    /// Signing secret Events API and slash command requests are signed with
+    pub signing_secret: String,
+}
+
+impl SlackConfig {
+    /// Create config from environment variables
+    pub fn from_env() -> Result<Self> {
+        let bot_token = std::env::var("SLACK_BOT_TOKEN")
+            .map_err(|_| StockError::ConfigError("SLACK_BOT_TOKEN not set".to_string()))?;
+
+        let signing_secret = std::env::var("SLACK_SIGNING_SECRET")
+            .map_err(|_| StockError::ConfigError("SLACK_SIGNING_SECRET not set".to_string()))?;
+
+        Ok(Self {
+            bot_token,
+            signing_secret,
+        })
+    }
+
+    /// Whether a request was signed by Slack in the last five minutes
+    ///
+    /// `timestamp` and `signature` are the `X-Slack-Request-Timestamp` and
+    /// `X-Slack-Signature` headers, `body` the raw request body.
+    pub fn verify_request(&self, timestamp: &str, body: &str, signature: &str) -> bool {
+        self.verify_request_at(timestamp, body, signature, Utc::now().timestamp())
+    }
+
+    fn verify_request_at(&self, timestamp: &str, body: &str, signature: &str, now: i64) -> bool {
+        let Ok(sent_at) = timestamp.trim().parse::<i64>() else {
+            return false;
+        };
+        if (now - sent_at).abs() > MAX_REQUEST_AGE_SECS {
+            return false;
+        }
+        let mac = hmac_sha256(
+            self.signing_secret.as_bytes(),
+            format!("v0:{sent_at}:{body}").as_bytes(),
+        );
+        let expected = mac.iter().fold("v0=".to_string(), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        });
+        constant_time_eq(expected.as_bytes(), signature.trim().as_bytes())
+    }
+}
+
+/// HMAC-SHA256 (RFC 2104)
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    const BLOCK_SIZE: usize = 64;
+    let mut block = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let inner = Sha256::new()
+        .chain_update(block.map(|byte| byte ^ 0x36))
+        .chain_update(message)
+        .finalize();
+    Sha256::new()
+        .chain_update(block.map(|byte| byte ^ 0x5c))
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
+
+/// Compare without leaking where the inputs differ
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// What an Events API request asks of the bot
+#[derive(Debug, Clone, PartialEq)]
+pub enum SlackEvent {
+    /// Slack checking the request URL; respond with `challenge` as the body
+    UrlVerification { challenge: String },
+    /// A user wrote to the bot: a direct message, or a channel message
+    /// mentioning it
+    Message {
+        user: String,
+        channel: String,
+        text: String,
+    },
+    /// Anything else, including the bot's own messages and edits
+    Ignored,
+}
+
+impl SlackEvent {
+    /// Parse an Events API request body
+    ///
+    /// A leading `@bot` mention is removed and Slack's `&amp;`, `&lt;` and
+    /// `&gt;` are decoded, so `@bot /alert AAPL > 200` reads as the command.
+    ///
+    /// A mention in a channel also arrives as a `message` event when the app
+    /// subscribes to channel messages, so only `message` events of direct
+    /// messages are answered, and channel messages through `app_mention`.
+    pub fn parse(body: &Value) -> Self {
+        match body["type"].as_str() {
+            Some("url_verification") => Self::UrlVerification {
+                challenge: body["challenge"].as_str().unwrap_or_default().to_string(),
+            },
+            Some("event_callback") => {
+                let event = &body["event"];
+                let is_message = match event["type"].as_str() {
+                    Some("app_mention") => true,
+                    Some("message") => event["channel_type"].as_str() == Some("im"),
+                    _ => false,
+                };
+                // Bot messages, edits, joins and the like carry a subtype
+                if !is_message || event.get("bot_id").is_some() || event.get("subtype").is_some() {
+                    return Self::Ignored;
+                }
+                let (Some(user), Some(channel), Some(text)) = (
+                    event["user"].as_str(),
+                    event["channel"].as_str(),
+                    event["text"].as_str(),
+                ) else {
+                    return Self::Ignored;
+                };
+                let text = strip_mention(text)
+                    .replace("&lt;", "<")
+                    .replace("&gt;", ">")
+                    .replace("&amp;", "&");
+                if text.is_empty() {
+                    return Self::Ignored;
+                }
+                Self::Message {
+                    user: user.to_string(),
+                    channel: channel.to_string(),
+                    text,
+                }
+            }
+            _ => Self::Ignored,
+        }
+    }
+}
+
+/// `text` without a leading `<@U123>` mention
+fn strip_mention(text: &str) -> &str {
+    let text = text.trim();
+    match text
+        .strip_prefix("<@")
+        .and_then(|rest| rest.split_once('>'))
+    {
+        Some((_, rest)) => rest.trim(),
+        None => text,
+    }
+}
+
+/// Minimal Slack Web API client for verifying the token, sending and
+/// editing replies and uploading files
+#[derive(Clone)]
+pub struct SlackApi {
+    token: String,
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl SlackApi {
+    /// Create a client for the bot with this token
+    pub fn new(token: impl Into<String>) -> Self {
+        Self {
+            token: token.into(),
+            base_url: SLACK_API_URL.to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Send requests to `base_url` (e.g. a mock)
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Verify the token; returns the bot's user id
+    pub async fn auth_test(&self) -> Result<String> {
+        let auth = self.call("auth.test", &json!({})).await?;
+        auth.get("user_id")
+            .and_then(Value::as_str)
+            .map(ToString::to_string)
+            .ok_or_else(|| StockError::ApiError("Slack auth.test returned no user_id".to_string()))
+    }
+
+    /// Post a message; returns its timestamp for later edits
+    ///
+    /// `text` is the notification fallback; without `blocks` it is shown in
+    /// sections.
+    pub async fn post_message(
+        &self,
+        channel: &str,
+        text: &str,
+        blocks: Option<&[Value]>,
+    ) -> Result<String> {
+        let blocks = blocks.map_or_else(|| SlackFormatter.text_blocks(text), <[Value]>::to_vec);
+        let message = self
+            .call(
+                "chat.postMessage",
+                &json!({ "channel": channel, "text": text, "blocks": blocks }),
+            )
+            .await?;
+        message
+            .get("ts")
+            .and_then(Value::as_str)
+            .map(ToString::to_string)
+            .ok_or_else(|| {
+                StockError::ApiError("Slack chat.postMessage returned no ts".to_string())
+            })
+    }
+
+    /// Replace a message sent earlier, e.g. an interim snapshot
+    pub async fn update_message(
+        &self,
+        channel: &str,
+        ts: &str,
+        text: &str,
+        blocks: Option<&[Value]>,
+    ) -> Result<()> {
+        let blocks = blocks.map_or_else(|| SlackFormatter.text_blocks(text), <[Value]>::to_vec);
+        self.call(
+            "chat.update",
+            &json!({ "channel": channel, "ts": ts, "text": text, "blocks": blocks }),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Post every message of a reply, the first with the reply's blocks,
+    /// then upload its attachments
+    pub async fn send_response(&self, channel: &str, response: &BotResponse) -> Result<()> {
+        let blocks = response.metadata["blocks"].as_array().map(Vec::as_slice);
+        for (i, text) in response.messages().enumerate() {
+            self.post_message(channel, text, if i == 0 { blocks } else { None })
+                .await?;
+        }
+        for attachment in &response.attachments {
+            self.upload_file(channel, attachment).await?;
+        }
+        Ok(())
+    }
+
+    /// Upload `attachment` to `channel` as a file
+    ///
+    /// Uses the external upload flow: an upload URL from
+    /// `files.getUploadURLExternal`, the bytes posted to it, then
+    /// `files.completeUploadExternal` to share the file in the channel.
+    pub async fn upload_file(&self, channel: &str, attachment: &Attachment) -> Result<()> {
+        let filename = attachment
+            .filename
+            .clone()
+            .unwrap_or_else(|| "attachment".to_string());
+        let length = attachment.content.len().to_string();
+        let upload = self
+            .call_form(
+                "files.getUploadURLExternal",
+                &[("filename", filename.as_str()), ("length", length.as_str())],
+            )
+            .await?;
+        let (Some(upload_url), Some(file_id)) = (
+            upload.get("upload_url").and_then(Value::as_str),
+            upload.get("file_id").and_then(Value::as_str),
+        ) else {
+            return Err(StockError::ApiError(
+                "Slack files.getUploadURLExternal returned no upload_url".to_string(),
+            ));
+        };
+        self.client
+            .post(upload_url)
+            .header(reqwest::header::CONTENT_TYPE, &attachment.mime_type)
+            .body(attachment.content.clone())
+            .send()
+            .await?
+            .error_for_status()?;
+        self.call(
+            "files.completeUploadExternal",
+            &json!({
+                "files": [{ "id": file_id, "title": filename }],
+                "channel_id": channel,
+            }),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn call(&self, method: &str, body: &Value) -> Result<Value> {
+        let request = self
+            .client
+            .post(format!("{}/{method}", self.base_url))
+            .json(body);
+        self.send(method, request).await
+    }
+
+    /// Call a method that only takes form-encoded arguments
+    async fn call_form(&self, method: &str, form: &[(&str, &str)]) -> Result<Value> {
+        let request = self
+            .client
+            .post(format!("{}/{method}", self.base_url))
+            .form(form);
+        self.send(method, request).await
+    }
+
+    async fn send(&self, method: &str, request: reqwest::RequestBuilder) -> Result<Value> {
+        let body: Value = request
+            .bearer_auth(&self.token)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if body.get("ok").and_then(Value::as_bool) == Some(true) {
+            return Ok(body);
+        }
+        match body
+            .get("error")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown_error")
+        {
+            "invalid_auth" | "not_authed" | "token_revoked" => Err(StockError::ConfigError(
+                "Slack rejected the bot token (check SLACK_BOT_TOKEN)".to_string(),
+            )),
+            error => Err(StockError::ApiError(format!(
+                "Slack {method} failed: {error}"
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for SlackApi {
+    /// Posting to a user id delivers to the app's direct messages with them
+    async fn notify(&self, user_id: &str, message: &str) -> Result<()> {
+        self.post_message(user_id, &escape_html(message), None)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Reply text, with blocks when it has a richer layout than its sections
+struct SlackReply {
+    text: String,
+    blocks: Option<Vec<Value>>,
+}
+
+impl From<String> for SlackReply {
+    fn from(text: String) -> Self {
+        Self { text, blocks: None }
+    }
+}
+
+/// Ids of recently accepted events, oldest first
+#[derive(Default)]
+struct SeenEvents(Mutex<VecDeque<String>>);
+
+impl SeenEvents {
+    /// Whether `event` is seen for the first time, remembering it if so
+    fn first_delivery(&self, event: &Value, retry_num: Option<u32>) -> bool {
+        let Some(event_id) = event["event_id"].as_str() else {
+            return retry_num.unwrap_or(0) == 0;
+        };
+        let mut seen = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        if seen.iter().any(|seen| seen == event_id) {
+            return false;
+        }
+        if seen.len() == SEEN_EVENTS {
+            seen.pop_front();
+        }
+        seen.push_back(event_id.to_string());
+        true
+    }
+}
+
+/// Slack bot
+pub struct SlackBot {
+    _config: SlackConfig,
+    engine: StockAnalysisEngine,
+    session_manager: SessionManager,
+    formatter: Box<dyn Formatter>,
+    services: BotServices,
+    api: SlackApi,
+    seen_events: SeenEvents,
+}
+
+impl SlackBot {
+    /// Create a new Slack bot
+    pub fn new(config: SlackConfig, engine: StockAnalysisEngine) -> Self {
+        let api = SlackApi::new(&config.bot_token);
+        Self {
+            _config: config,
+            engine,
+            session_manager: SessionManager::new(BotPlatform::Slack),
+            formatter: FormatterFactory::create_with_limit(
+                BotPlatform::Slack,
+                MessageLimit::from_env(BotPlatform::Slack),
+            ),
+            services: BotServices::default(),
+            api,
+            seen_events: SeenEvents::default(),
+        }
+    }
+
+    /// Serve users with `services`; bots serving the same users may share
+    /// one set
+    pub fn with_services(mut self, services: BotServices) -> Self {
+        if let Some(store) = &services.conversations {
+            self.session_manager.set_conversations(Arc::clone(store));
+        }
+        self.services = services;
+        self
+    }
+
+    /// Use a different Web API client
+    pub fn with_api(mut self, api: SlackApi) -> Self {
+        self.api = api;
+        self
+    }
+
+    /// Web API client replies are sent with
+    pub fn api(&self) -> &SlackApi {
+        &self.api
+    }
+
+    /// Answer an Events API request body in a task of its own, so the HTTP
+    /// handler can ack at once; returns false for events already accepted
+    ///
+    /// `retry_num` is the request's `X-Slack-Retry-Num` header. Slack
+    /// redelivers an event with the same `event_id` when the first delivery
+    /// was not acked in time; a retry without an `event_id` is dropped too.
+    pub fn spawn_event(self: &Arc<Self>, event: Value, retry_num: Option<u32>) -> bool {
+        if !self.seen_events.first_delivery(&event, retry_num) {
+            tracing::debug!("Dropping redelivered Slack event (retry {:?})", retry_num);
+            return false;
+        }
+        let bot = Arc::clone(self);
+        tokio::spawn(async move {
+            if let Err(e) = bot.on_event(event).await {
+                tracing::warn!("Failed to answer Slack event: {}", e);
+            }
+        });
+        true
+    }
+
+    /// Process a command; returns the reply as `mrkdwn`
+    pub async fn process_command(&self, user_id: &str, input: &str) -> Result<String> {
+        Ok(self.process(user_id, input).await?.text)
+    }
+
+    async fn process(&self, user_id: &str, input: &str) -> Result<SlackReply> {
+        let mut session = self.session_manager.get_or_create(user_id)?;
+        let mut context = session.context.clone();
+
+        let command = Command::parse(input)?;
+        let exchange = command.is_heavy().then(|| command.symbols());
+
+        let reply = Box::pin(dispatch::command_reply(
+            &self.engine,
+            &self.services,
+            &mut session,
+            &mut context,
+            &command,
+        ))
+        .await?;
+        let response: SlackReply = match reply {
+            Some(Reply::Analysis(result)) => SlackReply {
+                text: self.formatter.format_analysis(&result, &context),
+                blocks: Some(SlackFormatter.analysis_blocks(&result, &context)),
+            },
+            Some(Reply::Comparison(result)) => SlackReply {
+                blocks: Some(SlackFormatter.comparison_blocks(&result, &context)),
+                text: result.summary,
+            },
+            Some(Reply::Text(text) | Reply::Table(text)) => text.into(),
+            None if command == Command::Help => self.formatter.format_help().into(),
+            None => "Command not yet implemented".to_string().into(),
+        };
+
+        if let Some(symbols) = exchange {
+            context.record_exchange(input, &response.text, symbols);
+        }
+        session.context = context;
+        self.session_manager.update(user_id, session)?;
+
+        Ok(response)
+    }
+
+    /// Paginate a reply; blocks are kept when it fits in one message
+    fn reply(&self, user_id: &str, reply: SlackReply) -> Result<BotResponse> {
+        let response =
+            self.session_manager
+                .paginate(user_id, &reply.text, self.formatter.message_limit())?;
+        Ok(match reply.blocks {
+            Some(blocks) if response.continuation.is_empty() && response.actions.is_empty() => {
+                response.with_metadata(json!({ "blocks": blocks }))
+            }
+            _ => response,
+        })
+    }
+}
+
+#[async_trait]
+impl BotInterface for SlackBot {
+    fn platform(&self) -> BotPlatform {
+        BotPlatform::Slack
+    }
+
+    async fn on_message(
+        &self,
+        user_id: &str,
+        message: &str,
+        _context: &mut AnalysisContext,
+    ) -> Result<BotResponse> {
+        match Command::parse(message) {
+            Ok(Command::More) => return self.session_manager.next_page(user_id),
+            // Notebooks go out as a document along with the caption
+            Ok(Command::Notebook { kind }) => {
+                let session = self.session_manager.get_or_create(user_id)?;
+                let (text, notebook) = notebook::command_reply(&session.context, kind).await?;
+                let reply = self.reply(user_id, text.into())?;
+                return Ok(match notebook {
+                    Some(notebook) => reply.with_attachment(notebook),
+                    None => reply,
+                });
+            }
+            // Reports go out as a document, after the user's earlier requests
+            Ok(Command::Report { symbols, format }) => {
+                let (text, document) = report::queued_reply(
+                    &self.engine,
+                    &self.services.queue,
+                    &self.session_manager,
+                    user_id,
+                    &symbols,
+                    format,
+                    BotPlatform::Slack,
+                )
+                .await?;
+                let reply = self.reply(user_id, text.into())?;
+                return Ok(reply.with_attachments(document));
+            }
+            // Heavy requests wait for the user's earlier ones to finish
+            Ok(command) if command.is_heavy() => {
+                let reply = self
+                    .services
+                    .queue
+                    .run(user_id, self.process(user_id, message))
+                    .await??;
+                return self.reply(user_id, reply);
+            }
+            _ => {}
+        }
+        let reply = self.process(user_id, message).await?;
+        self.reply(user_id, reply)
+    }
+
+    async fn on_command(
+        &self,
+        user_id: &str,
+        command: &str,
+        args: &[String],
+        context: &mut AnalysisContext,
+    ) -> Result<BotResponse> {
+        let full_command = if args.is_empty() {
+            format!("/{command}")
+        } else {
+            format!("/{} {}", command, args.join(" "))
+        };
+
+        self.on_message(user_id, &full_command, context).await
+    }
+
+    fn format_response(&self, content: &str, _context: &AnalysisContext) -> BotResponse {
+        BotResponse::formatted(content)
+    }
+
+    /// Answer a message event in its channel, with any attachments as
+    /// files; the HTTP handler answers `url_verification` itself and should
+    /// go through [`SlackBot::spawn_event`] to ack in time
+    async fn on_event(&self, event: Value) -> Result<()> {
+        let SlackEvent::Message {
+            user,
+            channel,
+            text,
+        } = SlackEvent::parse(&event)
+        else {
+            return Ok(());
+        };
+        let mut context = AnalysisContext::with_user(&user);
+        let (api, channel) = (&self.api, channel.as_str());
+        let interim = |reply: BotResponse| async move {
+            if let Err(e) = api.send_response(channel, &reply).await {
+                tracing::warn!("Could not send an interim reply in {channel}: {e}");
+            }
+        };
+        let response = match answer_progressively(self, &user, &text, &mut context, interim).await {
+            Ok(response) => response,
+            Err(e) => BotResponse::error(self.formatter.format_error(&e.to_string())),
+        };
+        self.api.send_response(channel, &response).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_verify_request() {
+        // Example from Slack's request signing documentation
+        let config = SlackConfig {
+            bot_token: "xoxb-test".into(),
+            signing_secret: "8f742231b10e8888abcd99yyyzzz85a5".into(),
+        };
+        let body = "token=xyzz0WbapA4vBCDEFasx0q6G&team_id=T1DC2JH3J&team_domain=testteamnow&\
+                    channel_id=G8PSS9T3V&channel_name=foobar&user_id=U2CERLKJA&user_name=roadrunner&\
+                    command=%2Fwebhook-collect&text=&response_url=https%3A%2F%2Fhooks.slack.com%2F\
+                    commands%2FT1DC2JH3J%2F397700885554%2F96rGlfmibIGlgcZRskXaIFfN&\
+                    trigger_id=398738663015.47445629121.803a0bc887a14d10d2c447fce8b6703c";
+        let signature = "v0=a2114d57b48eac39b9ad189dd8316235a7b4a8d21a10bd27519666489c69b503";
+        let now = 1_531_420_618 + 60;
+
+        assert!(config.verify_request_at("1531420618", body, signature, now));
+        assert!(!config.verify_request_at("1531420618", &format!("{body}&x=1"), signature, now));
+        assert!(!config.verify_request_at("1531420619", body, signature, now));
+        // Replayed long after it was signed
+        assert!(!config.verify_request_at("1531420618", body, signature, now + 3600));
+    }
+
+    #[test]
+    fn test_parse_events() {
+        assert_eq!(
+            SlackEvent::parse(&json!({ "type": "url_verification", "challenge": "3eZbrw1a" })),
+            SlackEvent::UrlVerification {
+                challenge: "3eZbrw1a".into()
+            }
+        );
+        assert_eq!(
+            SlackEvent::parse(&json!({
+                "type": "event_callback",
+                "event": {
+                    "type": "app_mention",
+                    "user": "U061F7AUR",
+                    "channel": "C0LAN2Q65",
+                    "text": "<@U0LAN0Z89> /alert AAPL &gt; 200 &amp; more",
+                },
+            })),
+            SlackEvent::Message {
+                user: "U061F7AUR".into(),
+                channel: "C0LAN2Q65".into(),
+                text: "/alert AAPL > 200 & more".into(),
+            }
+        );
+        // Direct messages arrive as messages; the same mention as a channel
+        // message is answered through app_mention only
+        let message = |channel_type: &str| {
+            json!({
+                "type": "event_callback",
+                "event": {
+                    "type": "message",
+                    "channel_type": channel_type,
+                    "user": "U061F7AUR",
+                    "channel": "D1",
+                    "text": "<@U0LAN0Z89> /analyze AAPL",
+                },
+            })
+        };
+        assert!(matches!(
+            SlackEvent::parse(&message("im")),
+            SlackEvent::Message { .. }
+        ));
+        assert_eq!(SlackEvent::parse(&message("channel")), SlackEvent::Ignored);
+        // The bot's own replies and edits are not answered
+        let bot_message = json!({
+            "type": "event_callback",
+            "event": { "type": "message", "channel_type": "im", "bot_id": "B1", "channel": "D1", "text": "hi" },
+        });
+        assert_eq!(SlackEvent::parse(&bot_message), SlackEvent::Ignored);
+        let edit = json!({
+            "type": "event_callback",
+            "event": { "type": "message", "channel_type": "im", "subtype": "message_changed", "channel": "D1" },
+        });
+        assert_eq!(SlackEvent::parse(&edit), SlackEvent::Ignored);
+    }
+
+    #[tokio::test]
+    async fn test_api_calls() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/auth.test"))
+            .and(header("authorization", "Bearer xoxb-good"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "ok": true, "user_id": "U0BOT", "team": "Acme"
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat.postMessage"))
+            .and(body_partial_json(json!({
+                "channel": "C1",
+                "text": "*AAPL* &lt; 200",
+                "blocks": [{ "type": "section", "text": { "type": "mrkdwn", "text": "*AAPL* &lt; 200" } }],
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "ok": true, "channel": "C1", "ts": "1503435956.000247"
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat.update"))
+            .and(body_partial_json(
+                json!({ "channel": "C1", "ts": "1503435956.000247" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "ok": true })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/auth.test"))
+            .and(header("authorization", "Bearer xoxb-bad"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "ok": false, "error": "invalid_auth"
+            })))
+            .mount(&server)
+            .await;
+
+        let api = SlackApi::new("xoxb-good").with_base_url(server.uri());
+        assert_eq!(api.auth_test().await.unwrap(), "U0BOT");
+        let ts = api
+            .post_message("C1", "*AAPL* &lt; 200", None)
+            .await
+            .unwrap();
+        assert_eq!(ts, "1503435956.000247");
+        api.update_message("C1", &ts, "*AAPL* &lt; 200", None)
+            .await
+            .unwrap();
+
+        let bad = SlackApi::new("xoxb-bad").with_base_url(server.uri());
+        assert!(matches!(
+            bad.auth_test().await,
+            Err(StockError::ConfigError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_send_response_uploads_attachments() {
+        use crate::interface::interface::AttachmentType;
+        use wiremock::matchers::body_string_contains;
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat.postMessage"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(json!({ "ok": true, "ts": "1.2" })),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/files.getUploadURLExternal"))
+            .and(body_string_contains("filename=AAPL-chart.png"))
+            .and(body_string_contains("length=4"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "ok": true,
+                "upload_url": format!("{}/upload/F1", server.uri()),
+                "file_id": "F1",
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/upload/F1"))
+            .and(header("content-type", "image/png"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/files.completeUploadExternal"))
+            .and(body_partial_json(json!({
+                "files": [{ "id": "F1", "title": "AAPL-chart.png" }],
+                "channel_id": "C1",
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "ok": true })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let api = SlackApi::new("xoxb-good").with_base_url(server.uri());
+        let response = BotResponse::text("*AAPL* chart").with_attachment(Attachment {
+            attachment_type: AttachmentType::Chart,
+            content: b"\x89PNG".to_vec(),
+            filename: Some("AAPL-chart.png".into()),
+            mime_type: "image/png".into(),
+        });
+        api.send_response("C1", &response).await.unwrap();
+    }
+
+    #[test]
+    fn test_redelivered_events_dropped() {
+        let seen = SeenEvents::default();
+        let event = json!({ "type": "event_callback", "event_id": "Ev1", "event": {} });
+
+        assert!(seen.first_delivery(&event, None));
+        // Slack retrying because the first delivery was not acked in time
+        assert!(!seen.first_delivery(&event, Some(1)));
+        let other = json!({ "type": "event_callback", "event_id": "Ev2", "event": {} });
+        assert!(seen.first_delivery(&other, Some(1)));
+        // Without an event id only first deliveries are taken
+        let anonymous = json!({ "type": "event_callback", "event": {} });
+        assert!(seen.first_delivery(&anonymous, None));
+        assert!(!seen.first_delivery(&anonymous, Some(2)));
+    }
+}
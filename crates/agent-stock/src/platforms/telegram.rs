@@ -2,20 +2,35 @@
 //!
 //! Simple Telegram bot using the BotInterface
 
+use crate::alerts::Notifier;
+use crate::api::YahooFinanceClient;
 use crate::bot::Command;
-use crate::engine::{AnalysisContext, StockAnalysisEngine};
+use crate::depth::AnalysisDepth;
+use crate::engine::{AnalysisContext, AnalysisResult, StockAnalysisEngine};
 use crate::error::{Result, StockError};
+use crate::interface::interface::{Attachment, AttachmentType};
+use crate::interface::markup::{escape_html, html_to_text};
 use crate::interface::{
-    BotInterface, BotPlatform, BotResponse, Formatter, FormatterFactory, SessionManager,
+    BotInterface, BotPlatform, BotResponse, Formatter, FormatterFactory, MessageLimit,
+    SessionManager, with_interim,
 };
+use crate::interface::{chart_render, heatmap};
+use crate::notebook;
+use crate::platforms::dispatch::{self, Reply};
+use crate::platforms::services::BotServices;
+use crate::platforms::voice;
+use crate::report;
 use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::sync::mpsc::UnboundedSender;
 
 /// Telegram bot configuration
 #[derive(Debug, Clone)]
 pub struct TelegramConfig {
     /// Bot token from BotFather
     pub token: String,
-    
+
     /// Webhook URL (optional, for webhook mode)
     pub webhook_url: Option<String>,
 }
@@ -25,100 +40,384 @@ impl TelegramConfig {
     pub fn from_env() -> Result<Self> {
         let token = std::env::var("TELEGRAM_BOT_TOKEN")
             .map_err(|_| StockError::ConfigError("TELEGRAM_BOT_TOKEN not set".to_string()))?;
-        
+
         let webhook_url = std::env::var("TELEGRAM_WEBHOOK_URL").ok();
-        
+
         Ok(Self { token, webhook_url })
     }
 }
 
+/// Telegram Bot API base URL
+const TELEGRAM_API_URL: &str = "https://api.telegram.org";
+
+/// Minimal Telegram Bot API client for setup tasks (verifying the token,
+/// registering the command menu and setting the webhook) and for sending and
+/// editing HTML replies and their files
+#[derive(Clone)]
+pub struct TelegramApi {
+    token: String,
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl TelegramApi {
+    /// Create a client for the bot with this token
+    pub fn new(token: impl Into<String>) -> Self {
+        Self {
+            token: token.into(),
+            base_url: TELEGRAM_API_URL.to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Send requests to `base_url` (e.g. a local Bot API server or a mock)
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Verify the token; returns the bot's username
+    pub async fn get_me(&self) -> Result<String> {
+        let bot = self.call("getMe", &serde_json::json!({})).await?;
+        bot.get("username")
+            .and_then(serde_json::Value::as_str)
+            .map(ToString::to_string)
+            .ok_or_else(|| StockError::ApiError("Telegram getMe returned no username".to_string()))
+    }
+
+    /// Register the command menu shown in Telegram clients
+    pub async fn set_my_commands(&self, commands: &[(&str, &str)]) -> Result<()> {
+        let commands: Vec<_> = commands
+            .iter()
+            .map(|(command, description)| {
+                serde_json::json!({ "command": command, "description": description })
+            })
+            .collect();
+        self.call(
+            "setMyCommands",
+            &serde_json::json!({ "commands": commands }),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Deliver updates to `url` instead of long polling
+    pub async fn set_webhook(&self, url: &str) -> Result<()> {
+        self.call("setWebhook", &serde_json::json!({ "url": url }))
+            .await?;
+        Ok(())
+    }
+
+    /// Send an HTML message; returns its message id for later edits
+    pub async fn send_message(&self, chat_id: &str, text: &str) -> Result<i64> {
+        let message = self
+            .call(
+                "sendMessage",
+                &serde_json::json!({ "chat_id": chat_id, "text": text, "parse_mode": "HTML" }),
+            )
+            .await?;
+        message
+            .get("message_id")
+            .and_then(serde_json::Value::as_i64)
+            .ok_or_else(|| {
+                StockError::ApiError("Telegram sendMessage returned no message_id".to_string())
+            })
+    }
+
+    /// Replace the text of a message sent earlier, e.g. an interim snapshot
+    pub async fn edit_message_text(
+        &self,
+        chat_id: &str,
+        message_id: i64,
+        text: &str,
+    ) -> Result<()> {
+        self.call(
+            "editMessageText",
+            &serde_json::json!({
+                "chat_id": chat_id,
+                "message_id": message_id,
+                "text": text,
+                "parse_mode": "HTML",
+            }),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Send every message of a reply, then its attachments
+    pub async fn send_response(&self, chat_id: &str, response: &BotResponse) -> Result<()> {
+        for text in response.messages() {
+            self.send_message(chat_id, text).await?;
+        }
+        for attachment in &response.attachments {
+            self.send_file(chat_id, attachment).await?;
+        }
+        Ok(())
+    }
+
+    /// Upload `attachment` to a chat: images and charts as photos, audio as
+    /// an audio file and everything else as a document
+    pub async fn send_file(&self, chat_id: &str, attachment: &Attachment) -> Result<()> {
+        let (method, field) = match attachment.attachment_type {
+            AttachmentType::Image | AttachmentType::Chart => ("sendPhoto", "photo"),
+            AttachmentType::Audio => ("sendAudio", "audio"),
+            AttachmentType::Document => ("sendDocument", "document"),
+        };
+        let file = reqwest::multipart::Part::bytes(attachment.content.clone())
+            .file_name(
+                attachment
+                    .filename
+                    .clone()
+                    .unwrap_or_else(|| field.to_string()),
+            )
+            .mime_str(&attachment.mime_type)?;
+        let form = reqwest::multipart::Form::new()
+            .text("chat_id", chat_id.to_string())
+            .part(field, file);
+        let request = self.client.post(self.method_url(method)).multipart(form);
+        self.send(method, request).await?;
+        Ok(())
+    }
+
+    /// Server path of an uploaded file, e.g. a voice message
+    pub async fn get_file(&self, file_id: &str) -> Result<String> {
+        let file = self
+            .call("getFile", &serde_json::json!({ "file_id": file_id }))
+            .await?;
+        file.get("file_path")
+            .and_then(serde_json::Value::as_str)
+            .map(ToString::to_string)
+            .ok_or_else(|| {
+                StockError::ApiError("Telegram getFile returned no file_path".to_string())
+            })
+    }
+
+    /// Download a file by the path returned from [`TelegramApi::get_file`]
+    pub async fn download_file(&self, file_path: &str) -> Result<Vec<u8>> {
+        let response = self
+            .client
+            .get(format!(
+                "{}/file/bot{}/{file_path}",
+                self.base_url, self.token
+            ))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    async fn call(&self, method: &str, body: &serde_json::Value) -> Result<serde_json::Value> {
+        let request = self.client.post(self.method_url(method)).json(body);
+        self.send(method, request).await
+    }
+
+    fn method_url(&self, method: &str) -> String {
+        format!("{}/bot{}/{method}", self.base_url, self.token)
+    }
+
+    async fn send(
+        &self,
+        method: &str,
+        request: reqwest::RequestBuilder,
+    ) -> Result<serde_json::Value> {
+        let response = request.send().await?;
+
+        if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+            return Err(StockError::ConfigError(
+                "Telegram rejected the bot token (check TELEGRAM_BOT_TOKEN)".to_string(),
+            ));
+        }
+        let body: serde_json::Value = response.json().await?;
+        if body.get("ok").and_then(serde_json::Value::as_bool) == Some(true) {
+            Ok(body.get("result").cloned().unwrap_or_default())
+        } else {
+            let description = body
+                .get("description")
+                .and_then(serde_json::Value::as_str)
+                .unwrap_or("unknown error");
+            Err(StockError::ApiError(format!(
+                "Telegram {method} failed: {description}"
+            )))
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for TelegramApi {
+    async fn notify(&self, user_id: &str, message: &str) -> Result<()> {
+        self.send_message(user_id, &escape_html(message)).await?;
+        Ok(())
+    }
+}
+
 /// Telegram bot
 pub struct TelegramBot {
     config: TelegramConfig,
     engine: StockAnalysisEngine,
     session_manager: SessionManager,
     formatter: Box<dyn Formatter>,
+    services: BotServices,
+    api: TelegramApi,
+    /// Chart images waiting to go out with each user's next reply
+    charts: Mutex<HashMap<String, Attachment>>,
 }
 
 impl TelegramBot {
     /// Create a new Telegram bot
     pub fn new(config: TelegramConfig, engine: StockAnalysisEngine) -> Self {
+        let api = TelegramApi::new(&config.token);
         Self {
             config,
             engine,
             session_manager: SessionManager::new(BotPlatform::Telegram),
-            formatter: FormatterFactory::create(BotPlatform::Telegram),
+            formatter: FormatterFactory::create_with_limit(
+                BotPlatform::Telegram,
+                MessageLimit::from_env(BotPlatform::Telegram),
+            ),
+            services: BotServices::default(),
+            api,
+            charts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Serve users with `services`; bots serving the same users may share
+    /// one set
+    pub fn with_services(mut self, services: BotServices) -> Self {
+        if let Some(store) = &services.conversations {
+            self.session_manager.set_conversations(Arc::clone(store));
+        }
+        self.services = services;
+        self
+    }
+
+    /// Use a different Bot API client (e.g. a local Bot API server)
+    pub fn with_api(mut self, api: TelegramApi) -> Self {
+        self.api = api;
+        self
+    }
+
+    /// Transcribe a voice message and handle it like a typed message
+    ///
+    /// The reply quotes the transcript, and a detected language becomes the
+    /// user's reply language.
+    pub async fn process_voice(&self, user_id: &str, file_id: &str) -> Result<String> {
+        let transcriber = self.services.transcriber.clone().ok_or_else(|| {
+            StockError::ConfigError(format!(
+                "Voice messages are not enabled; set {}",
+                voice::TRANSCRIBER_ENV
+            ))
+        })?;
+
+        let file_path = self.api.get_file(file_id).await?;
+        let audio = self.api.download_file(&file_path).await?;
+        let filename = file_path.rsplit('/').next().unwrap_or("voice.ogg");
+        let transcript = transcriber.transcribe(audio, filename).await?;
+        if transcript.text.is_empty() {
+            return Ok("🎙 Could not make out any words in that voice message".to_string());
+        }
+
+        if let Some(language) = &transcript.language {
+            let mut session = self.session_manager.get_or_create(user_id)?;
+            session.context.preferences.language = language.code().to_string();
+            session.context.preferences.response_language = Some(language.code().to_string());
+            self.session_manager.update(user_id, session)?;
+        }
+
+        let response = self.process_command(user_id, &transcript.text).await?;
+        Ok(format!(
+            "🎙 <i>{}</i>\n\n{response}",
+            escape_html(&transcript.text)
+        ))
+    }
+
+    /// Handle a voice message, replying like [`BotInterface::on_message`]
+    pub async fn on_voice(&self, user_id: &str, file_id: &str) -> Result<BotResponse> {
+        let response = self.process_voice(user_id, file_id).await?;
+        self.reply(user_id, &response).await
+    }
+
+    /// Paginate a reply, attaching an audio summary if the user wants one
+    async fn reply(&self, user_id: &str, response: &str) -> Result<BotResponse> {
+        let mut reply =
+            self.session_manager
+                .paginate(user_id, response, self.formatter.message_limit())?;
+        if let Some(chart) = self
+            .charts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(user_id)
+        {
+            reply = reply.with_attachment(chart);
+        }
+
+        let wants_audio = self
+            .session_manager
+            .get(user_id)
+            .is_some_and(|session| session.context.preferences.voice_replies);
+        if let Some(synthesizer) = &self.services.synthesizer
+            && wants_audio
+        {
+            match voice::audio_summary(synthesizer.as_ref(), &html_to_text(response)).await {
+                Ok(Some(audio)) => reply = reply.with_attachment(audio),
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Audio summary failed: {}", e),
+            }
+        }
+        Ok(reply)
+    }
+
+    /// Hold the chart image of `result` for the user's next reply
+    fn keep_chart(&self, user_id: &str, result: &AnalysisResult, context: &AnalysisContext) {
+        if let Some(chart) = chart_render::analysis_chart(result, context) {
+            self.charts
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(user_id.to_string(), chart);
         }
     }
-    
+
     /// Process a command from a user
-    pub async fn process_command(&mut self, user_id: &str, input: &str) -> Result<String> {
+    ///
+    /// Analyses with chart data leave a chart image for the reply.
+    pub async fn process_command(&self, user_id: &str, input: &str) -> Result<String> {
+        self.charts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(user_id);
         let mut session = self.session_manager.get_or_create(user_id)?;
         let mut context = session.context.clone();
-        
+
         let command = Command::parse(input)?;
-        
-        let response = match command {
-            Command::Analyze { symbol } => {
-                let result = self.engine.analyze_stock(&symbol, &mut context).await?;
-                self.formatter.format_analysis(&result, &context)
-            }
-            Command::Technical { symbol } => {
-                let result = self.engine.analyze_technical(&symbol, &mut context).await?;
-                self.formatter.format_analysis(&result, &context)
-            }
-            Command::Fundamental { symbol } => {
-                let result = self.engine.analyze_fundamental(&symbol, &mut context).await?;
-                self.formatter.format_analysis(&result, &context)
-            }
-            Command::News { symbol } => {
-                let result = self.engine.analyze_news(&symbol, &mut context).await?;
-                self.formatter.format_analysis(&result, &context)
-            }
-            Command::Earnings { symbol } => {
-                let result = self.engine.analyze_earnings(&symbol, &mut context).await?;
-                self.formatter.format_analysis(&result, &context)
-            }
-            Command::Macro => {
-                let result = self.engine.analyze_macro(&mut context).await?;
+        let exchange = command.is_heavy().then(|| command.symbols());
+
+        let reply = Box::pin(dispatch::command_reply(
+            &self.engine,
+            &self.services,
+            &mut session,
+            &mut context,
+            &command,
+        ))
+        .await?;
+        let response = match reply {
+            Some(Reply::Analysis(result)) => {
+                self.keep_chart(user_id, &result, &context);
                 self.formatter.format_analysis(&result, &context)
             }
-            Command::Compare { symbols } => {
-                let result = self.engine.compare_stocks(&symbols, &mut context).await?;
-                result.summary
-            }
-            Command::Watch { symbol } => {
-                session.watch(symbol.clone());
-                format!("✅ Added {symbol} to watchlist")
-            }
-            Command::Unwatch { symbol } => {
-                if session.unwatch(&symbol) {
-                    format!("✅ Removed {symbol} from watchlist")
-                } else {
-                    format!("❌ {symbol} not in watchlist")
-                }
-            }
-            Command::Watchlist => {
-                if session.watchlist.is_empty() {
-                    "📋 Watchlist is empty".to_string()
-                } else {
-                    format!("📋 Watchlist:\n{}", session.watchlist.join("\n"))
-                }
-            }
-            Command::Help => self.formatter.format_help(),
-            Command::Clear => {
-                session.context = AnalysisContext::with_user(user_id);
-                "✅ Conversation cleared".to_string()
-            }
-            _ => "Command not yet implemented".to_string(),
+            Some(Reply::Comparison(result)) => result.summary,
+            Some(Reply::Text(text) | Reply::Table(text)) => text,
+            None if command == Command::Help => self.formatter.format_help(),
+            None => "Command not yet implemented".to_string(),
         };
-        
+
+        if let Some(symbols) = exchange {
+            context.record_exchange(input, &response, symbols);
+        }
         session.context = context;
         self.session_manager.update(user_id, session)?;
-        
+
         Ok(response)
     }
-    
+
     /// Get bot token
     pub fn token(&self) -> &str {
         &self.config.token
@@ -130,19 +429,114 @@ impl BotInterface for TelegramBot {
     fn platform(&self) -> BotPlatform {
         BotPlatform::Telegram
     }
-    
+
     async fn on_message(
-        &mut self,
+        &self,
         user_id: &str,
         message: &str,
         _context: &mut AnalysisContext,
     ) -> Result<BotResponse> {
+        match Command::parse(message) {
+            Ok(Command::More) => return self.session_manager.next_page(user_id),
+            // Notebooks go out as a document along with the caption
+            Ok(Command::Notebook { kind }) => {
+                let session = self.session_manager.get_or_create(user_id)?;
+                let (text, notebook) = notebook::command_reply(&session.context, kind).await?;
+                let reply = self.reply(user_id, &text).await?;
+                return Ok(match notebook {
+                    Some(notebook) => reply.with_attachment(notebook),
+                    None => reply,
+                });
+            }
+            // Reports go out as a document, after the user's earlier requests
+            Ok(Command::Report { symbols, format }) => {
+                let (text, document) = report::queued_reply(
+                    &self.engine,
+                    &self.services.queue,
+                    &self.session_manager,
+                    user_id,
+                    &symbols,
+                    format,
+                    BotPlatform::Telegram,
+                )
+                .await?;
+                return Ok(self.reply(user_id, &text).await?.with_attachments(document));
+            }
+            // Heatmaps go out as an image along with the text
+            Ok(command @ (Command::Market | Command::Summary)) => {
+                let session = self.session_manager.get_or_create(user_id)?;
+                let watchlist =
+                    (command == Command::Summary).then_some(session.watchlist.as_slice());
+                let (text, image) = heatmap::performance_reply(
+                    &YahooFinanceClient::new(),
+                    watchlist,
+                    &session.context,
+                )
+                .await?;
+                let reply = self.reply(user_id, &text).await?;
+                return Ok(match image {
+                    Some(image) => reply.with_attachment(image),
+                    None => reply,
+                });
+            }
+            // Heavy requests wait for the user's earlier ones to finish
+            Ok(command) if command.is_heavy() => {
+                let response = self
+                    .services
+                    .queue
+                    .run(user_id, self.process_command(user_id, message))
+                    .await??;
+                return self.reply(user_id, &response).await;
+            }
+            _ => {}
+        }
         let response = self.process_command(user_id, message).await?;
-        Ok(BotResponse::formatted(response))
+        self.reply(user_id, &response).await
+    }
+
+    async fn on_message_progressive(
+        &self,
+        user_id: &str,
+        message: &str,
+        context: &mut AnalysisContext,
+        interim: &UnboundedSender<BotResponse>,
+    ) -> Result<BotResponse> {
+        let command = Command::parse(message);
+        // Today's price has no place in an analysis of the past
+        let Ok(Command::Analyze {
+            symbol,
+            depth,
+            as_of: None,
+        }) = command
+        else {
+            return with_interim(interim.clone(), self.on_message(user_id, message, context)).await;
+        };
+        // Quick analyses arrive about as fast as a snapshot would
+        if depth.unwrap_or(context.preferences.depth) == AnalysisDepth::Quick {
+            return with_interim(interim.clone(), self.on_message(user_id, message, context)).await;
+        }
+
+        // Without a snapshot the analysis is simply sent when ready
+        let sent = match self.engine.quick_snapshot(&symbol).await {
+            Ok(snapshot) => interim
+                .send(BotResponse::formatted(escape_html(&snapshot.to_string())))
+                .is_ok(),
+            Err(e) => {
+                tracing::debug!("No snapshot for {}: {}", symbol, e);
+                false
+            }
+        };
+        let response =
+            with_interim(interim.clone(), self.on_message(user_id, message, context)).await?;
+        Ok(if sent {
+            response.replacing_previous()
+        } else {
+            response
+        })
     }
-    
+
     async fn on_command(
-        &mut self,
+        &self,
         user_id: &str,
         command: &str,
         args: &[String],
@@ -153,10 +547,10 @@ impl BotInterface for TelegramBot {
         } else {
             format!("/{} {}", command, args.join(" "))
         };
-        
+
         self.on_message(user_id, &full_command, context).await
     }
-    
+
     fn format_response(&self, content: &str, _context: &AnalysisContext) -> BotResponse {
         BotResponse::formatted(content)
     }
@@ -165,12 +559,160 @@ impl BotInterface for TelegramBot {
 #[cfg(test)]
 mod tests {
     use super::*;
-    
+
+    #[tokio::test]
+    async fn test_api_setup_calls() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/bot123:abc/getMe"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "ok": true,
+                "result": { "id": 123, "is_bot": true, "username": "stock_bot" }
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/bot123:abc/setMyCommands"))
+            .and(body_partial_json(serde_json::json!({
+                "commands": [{ "command": "analyze", "description": "Comprehensive stock analysis" }]
+            })))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({ "ok": true, "result": true })),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/botbad/getMe"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(serde_json::json!({
+                "ok": false, "error_code": 401, "description": "Unauthorized"
+            })))
+            .mount(&server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/bot123:abc/getFile"))
+            .and(body_partial_json(
+                serde_json::json!({ "file_id": "voice-1" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "ok": true,
+                "result": { "file_id": "voice-1", "file_path": "voice/file_7.oga" }
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/file/bot123:abc/voice/file_7.oga"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"OggS".to_vec()))
+            .mount(&server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/bot123:abc/sendMessage"))
+            .and(body_partial_json(
+                serde_json::json!({ "chat_id": "42", "parse_mode": "HTML" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "ok": true,
+                "result": { "message_id": 7, "text": "⚡ AAPL 178.25" }
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/bot123:abc/editMessageText"))
+            .and(body_partial_json(
+                serde_json::json!({ "chat_id": "42", "message_id": 7 }),
+            ))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "ok": true, "result": true })),
+            )
+            .mount(&server)
+            .await;
+
+        let api = TelegramApi::new("123:abc").with_base_url(server.uri());
+        let message_id = api.send_message("42", "⚡ AAPL 178.25").await.unwrap();
+        assert_eq!(message_id, 7);
+        api.edit_message_text("42", message_id, "<b>AAPL</b> analysis")
+            .await
+            .unwrap();
+        assert_eq!(api.get_me().await.unwrap(), "stock_bot");
+        let file_path = api.get_file("voice-1").await.unwrap();
+        assert_eq!(file_path, "voice/file_7.oga");
+        assert_eq!(api.download_file(&file_path).await.unwrap(), b"OggS");
+        api.set_my_commands(&[("analyze", "Comprehensive stock analysis")])
+            .await
+            .unwrap();
+
+        let err = TelegramApi::new("bad")
+            .with_base_url(server.uri())
+            .get_me()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, StockError::ConfigError(_)), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_send_response_uploads_attachments() {
+        use wiremock::matchers::{body_string_contains, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/bot123:abc/sendMessage"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "ok": true,
+                "result": { "message_id": 7 }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/bot123:abc/sendPhoto"))
+            .and(body_string_contains("filename=\"AAPL-chart.png\""))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "ok": true, "result": {} })),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/bot123:abc/sendDocument"))
+            .and(body_string_contains("filename=\"AAPL.pdf\""))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "ok": true, "result": {} })),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let api = TelegramApi::new("123:abc").with_base_url(server.uri());
+        let response = BotResponse::text("<b>AAPL</b> report")
+            .with_attachment(Attachment {
+                attachment_type: AttachmentType::Chart,
+                // ASCII, for the multipart body to match as a string
+                content: b"PNG".to_vec(),
+                filename: Some("AAPL-chart.png".into()),
+                mime_type: "image/png".into(),
+            })
+            .with_attachment(Attachment {
+                attachment_type: AttachmentType::Document,
+                content: b"%PDF".to_vec(),
+                filename: Some("AAPL.pdf".into()),
+                mime_type: "application/pdf".into(),
+            });
+        api.send_response("42", &response).await.unwrap();
+    }
+
     #[test]
     fn test_telegram_config_from_env() {
-        std::env::set_var("TELEGRAM_BOT_TOKEN", "test_token");
+        unsafe { std::env::set_var("TELEGRAM_BOT_TOKEN", "test_token") };
         let config = TelegramConfig::from_env().unwrap();
         assert_eq!(config.token, "test_token");
-        std::env::remove_var("TELEGRAM_BOT_TOKEN");
+        unsafe { std::env::remove_var("TELEGRAM_BOT_TOKEN") };
     }
 }
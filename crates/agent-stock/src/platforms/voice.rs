@@ -0,0 +1,560 @@
+//! Speech-to-text for voice messages and spoken summaries of replies
+//!
+//! Users can send a voice note ("analyze Nvidia") instead of typing. A
+//! [`Transcriber`] turns the audio into text, which is then handled like a
+//! typed message, and reports the spoken language so the reply can match it.
+//! Two backends are available, chosen with `STOCK_VOICE_TRANSCRIBER`:
+//!
+//! - `openai`: the Whisper API (`WHISPER_API_KEY`, falling back to
+//!   `OPENAI_API_KEY`; `WHISPER_API_BASE` for compatible servers)
+//! - `whisper-cpp`: a local whisper.cpp build (`WHISPER_CPP_MODEL`, and
+//!   `WHISPER_CPP_BIN` if `whisper-cli` is not on the `PATH`). Audio is
+//!   converted to 16 kHz WAV with `ffmpeg` first.
+//!
+//! In the other direction, a [`Synthesizer`] reads out a one-minute
+//! [`spoken_summary`] of long replies. It is enabled with
+//! `STOCK_VOICE_REPLIES=openai` (OpenAI TTS, same keys as the Whisper API)
+//! and then per user with `/voice on`.
+
+use agent_prompt::Language;
+use async_trait::async_trait;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::error::{Result, StockError};
+use crate::interface::interface::{Attachment, AttachmentType};
+
+/// Environment variable selecting the transcription backend
+pub const TRANSCRIBER_ENV: &str = "STOCK_VOICE_TRANSCRIBER";
+
+/// OpenAI API base URL
+const WHISPER_API_URL: &str = "https://api.openai.com/v1";
+
+/// Whisper model used by the OpenAI API
+const WHISPER_API_MODEL: &str = "whisper-1";
+
+/// Environment variable selecting the speech synthesis backend
+pub const SYNTHESIZER_ENV: &str = "STOCK_VOICE_REPLIES";
+
+/// Replies shorter than this many characters get no audio summary
+pub const AUDIO_SUMMARY_MIN_CHARS: usize = 800;
+
+/// About a minute of speech, in Latin characters (150 words per minute)
+const SPOKEN_CHARS_PER_MINUTE: usize = 900;
+
+/// Speaking time of a CJK character relative to a Latin one
+const CJK_CHAR_WEIGHT: usize = 4;
+
+/// Transcribed speech
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transcript {
+    /// What was said
+    pub text: String,
+    /// Detected spoken language, if the backend reports one
+    pub language: Option<Language>,
+}
+
+/// Converts recorded speech to text
+#[async_trait]
+pub trait Transcriber: Send + Sync {
+    /// Transcribe `audio`, detecting the language; `filename` hints the
+    /// format (e.g. `voice.ogg`)
+    async fn transcribe(&self, audio: Vec<u8>, filename: &str) -> Result<Transcript>;
+}
+
+/// Transcriber configured by `STOCK_VOICE_TRANSCRIBER`, if any
+pub fn transcriber_from_env() -> Result<Option<Arc<dyn Transcriber>>> {
+    let Ok(backend) = std::env::var(TRANSCRIBER_ENV) else {
+        return Ok(None);
+    };
+    match backend.trim().to_lowercase().as_str() {
+        "" | "off" | "none" => Ok(None),
+        "openai" | "whisper-api" => Ok(Some(Arc::new(WhisperApi::from_env()?))),
+        "whisper-cpp" | "whisper.cpp" => Ok(Some(Arc::new(WhisperCpp::from_env()?))),
+        other => Err(StockError::ConfigError(format!(
+            "Unknown {TRANSCRIBER_ENV} backend: {other}. Use openai or whisper-cpp"
+        ))),
+    }
+}
+
+/// Spoken language from a backend's language name or code
+///
+/// Unrecognised languages are dropped so replies keep the user's preference.
+fn detected_language(language: Option<&str>) -> Option<Language> {
+    language.map(Language::from_code).filter(Language::is_known)
+}
+
+/// Converts text to speech
+#[async_trait]
+pub trait Synthesizer: Send + Sync {
+    /// Spoken `text` as encoded audio
+    async fn synthesize(&self, text: &str) -> Result<Vec<u8>>;
+
+    /// MIME type of the audio
+    fn mime_type(&self) -> &'static str {
+        "audio/mpeg"
+    }
+}
+
+/// Synthesizer configured by `STOCK_VOICE_REPLIES`, if any
+pub fn synthesizer_from_env() -> Result<Option<Arc<dyn Synthesizer>>> {
+    let Ok(backend) = std::env::var(SYNTHESIZER_ENV) else {
+        return Ok(None);
+    };
+    match backend.trim().to_lowercase().as_str() {
+        "" | "off" | "none" => Ok(None),
+        "openai" => Ok(Some(Arc::new(OpenAiTts::from_env()?))),
+        other => Err(StockError::ConfigError(format!(
+            "Unknown {SYNTHESIZER_ENV} backend: {other}. Use openai"
+        ))),
+    }
+}
+
+/// Whether a character is read aloud; drops emoji, sparkline bars and
+/// bullets that speech engines spell out
+fn is_speakable(c: char) -> bool {
+    !matches!(c, '\u{2022}' | '\u{2580}'..='\u{259F}' | '\u{2600}'..='\u{27BF}' | '\u{FE0F}')
+        && c < '\u{1F000}'
+}
+
+/// About a minute of speech from the start of `text`, ending at a sentence
+///
+/// `text` is plain text; Markdown or HTML must be stripped first.
+pub fn spoken_summary(text: &str) -> String {
+    let text: String = text.chars().filter(|c| is_speakable(*c)).collect();
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    let mut cost = 0;
+    let mut end = text.len();
+    let mut last_sentence = None;
+    for (i, c) in text.char_indices() {
+        cost += if c > '\u{2E80}' { CJK_CHAR_WEIGHT } else { 1 };
+        if cost > SPOKEN_CHARS_PER_MINUTE {
+            end = last_sentence.unwrap_or(i);
+            break;
+        }
+        if matches!(c, '.' | '!' | '?' | '。' | '！' | '？') {
+            last_sentence = Some(i + c.len_utf8());
+        }
+    }
+    text[..end].trim().to_string()
+}
+
+/// Audio summary attachment for a long plain-text reply; `None` if the reply
+/// is short
+pub async fn audio_summary(
+    synthesizer: &dyn Synthesizer,
+    text: &str,
+) -> Result<Option<Attachment>> {
+    if text.chars().count() < AUDIO_SUMMARY_MIN_CHARS {
+        return Ok(None);
+    }
+    let summary = spoken_summary(text);
+    if summary.is_empty() {
+        return Ok(None);
+    }
+
+    let mime_type = synthesizer.mime_type();
+    let extension = mime_type
+        .rsplit('/')
+        .next()
+        .unwrap_or("mpeg")
+        .replace("mpeg", "mp3");
+    Ok(Some(Attachment {
+        attachment_type: AttachmentType::Audio,
+        content: synthesizer.synthesize(&summary).await?,
+        filename: Some(format!("summary.{extension}")),
+        mime_type: mime_type.to_string(),
+    }))
+}
+
+/// OpenAI API key for speech endpoints
+fn speech_api_key() -> Result<String> {
+    std::env::var("WHISPER_API_KEY")
+        .or_else(|_| std::env::var("OPENAI_API_KEY"))
+        .map_err(|_| {
+            StockError::ConfigError(
+                "WHISPER_API_KEY or OPENAI_API_KEY is required for voice features".to_string(),
+            )
+        })
+}
+
+/// OpenAI Whisper API, or a server implementing `/audio/transcriptions`
+pub struct WhisperApi {
+    api_key: String,
+    base_url: String,
+    model: String,
+    client: reqwest::Client,
+}
+
+impl WhisperApi {
+    /// Create a client using `api_key`
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            base_url: WHISPER_API_URL.to_string(),
+            model: WHISPER_API_MODEL.to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Client from `WHISPER_API_KEY` (or `OPENAI_API_KEY`) and `WHISPER_API_BASE`
+    pub fn from_env() -> Result<Self> {
+        let mut api = Self::new(speech_api_key()?);
+        if let Ok(base_url) = std::env::var("WHISPER_API_BASE") {
+            api = api.with_base_url(base_url);
+        }
+        Ok(api)
+    }
+
+    /// Send requests to `base_url` instead of the OpenAI API
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Use a different model
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+}
+
+#[async_trait]
+impl Transcriber for WhisperApi {
+    async fn transcribe(&self, audio: Vec<u8>, filename: &str) -> Result<Transcript> {
+        let file = reqwest::multipart::Part::bytes(audio).file_name(filename.to_string());
+        let form = reqwest::multipart::Form::new()
+            .text("model", self.model.clone())
+            .text("response_format", "verbose_json")
+            .part("file", file);
+
+        let response = self
+            .client
+            .post(format!("{}/audio/transcriptions", self.base_url))
+            .bearer_auth(&self.api_key)
+            .multipart(form)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(StockError::ApiError(format!(
+                "Transcription failed ({status}): {body}"
+            )));
+        }
+
+        let body: serde_json::Value = response.json().await?;
+        Ok(Transcript {
+            text: body["text"].as_str().unwrap_or_default().trim().to_string(),
+            language: detected_language(body["language"].as_str()),
+        })
+    }
+}
+
+/// OpenAI text-to-speech, or a server implementing `/audio/speech`
+pub struct OpenAiTts {
+    api_key: String,
+    base_url: String,
+    model: String,
+    voice: String,
+    client: reqwest::Client,
+}
+
+impl OpenAiTts {
+    /// Create a client using `api_key`
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            base_url: WHISPER_API_URL.to_string(),
+            model: "tts-1".to_string(),
+            voice: "alloy".to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Client from `WHISPER_API_KEY` (or `OPENAI_API_KEY`), `WHISPER_API_BASE`
+    /// and `STOCK_TTS_VOICE`
+    pub fn from_env() -> Result<Self> {
+        let mut tts = Self::new(speech_api_key()?);
+        if let Ok(base_url) = std::env::var("WHISPER_API_BASE") {
+            tts = tts.with_base_url(base_url);
+        }
+        if let Ok(voice) = std::env::var("STOCK_TTS_VOICE") {
+            tts = tts.with_voice(voice);
+        }
+        Ok(tts)
+    }
+
+    /// Send requests to `base_url` instead of the OpenAI API
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Use a different voice, e.g. `nova`
+    pub fn with_voice(mut self, voice: impl Into<String>) -> Self {
+        self.voice = voice.into();
+        self
+    }
+
+    /// Use a different model, e.g. `tts-1-hd`
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+}
+
+#[async_trait]
+impl Synthesizer for OpenAiTts {
+    async fn synthesize(&self, text: &str) -> Result<Vec<u8>> {
+        let response = self
+            .client
+            .post(format!("{}/audio/speech", self.base_url))
+            .bearer_auth(&self.api_key)
+            .json(&serde_json::json!({
+                "model": self.model,
+                "voice": self.voice,
+                "input": text,
+                "response_format": "mp3",
+            }))
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(StockError::ApiError(format!(
+                "Speech synthesis failed ({status}): {body}"
+            )));
+        }
+        Ok(response.bytes().await?.to_vec())
+    }
+}
+
+/// Local whisper.cpp command-line build
+pub struct WhisperCpp {
+    binary: PathBuf,
+    model: PathBuf,
+    ffmpeg: PathBuf,
+}
+
+impl WhisperCpp {
+    /// Run `binary` with the ggml `model`
+    pub fn new(binary: impl Into<PathBuf>, model: impl Into<PathBuf>) -> Self {
+        Self {
+            binary: binary.into(),
+            model: model.into(),
+            ffmpeg: PathBuf::from("ffmpeg"),
+        }
+    }
+
+    /// Transcriber from `WHISPER_CPP_MODEL` and `WHISPER_CPP_BIN`
+    pub fn from_env() -> Result<Self> {
+        let model = std::env::var("WHISPER_CPP_MODEL").map_err(|_| {
+            StockError::ConfigError(
+                "WHISPER_CPP_MODEL is required for whisper-cpp transcription".to_string(),
+            )
+        })?;
+        let binary = std::env::var("WHISPER_CPP_BIN").unwrap_or_else(|_| "whisper-cli".to_string());
+        Ok(Self::new(binary, model))
+    }
+
+    /// Use a specific `ffmpeg` binary for audio conversion
+    pub fn with_ffmpeg(mut self, ffmpeg: impl Into<PathBuf>) -> Self {
+        self.ffmpeg = ffmpeg.into();
+        self
+    }
+}
+
+/// Run a command, failing with its stderr
+async fn run(program: &std::path::Path, args: &[&std::ffi::OsStr]) -> Result<()> {
+    let output = tokio::process::Command::new(program)
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| {
+            StockError::ConfigError(format!("Failed to run {}: {e}", program.display()))
+        })?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(StockError::Other(format!(
+            "{} failed: {}",
+            program.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
+
+/// Transcript from whisper.cpp's `-oj` JSON output
+fn parse_whisper_cpp_json(json: &str) -> Result<Transcript> {
+    let output: serde_json::Value = serde_json::from_str(json)?;
+    let text = output["transcription"]
+        .as_array()
+        .map(|segments| {
+            segments
+                .iter()
+                .filter_map(|segment| segment["text"].as_str())
+                .collect::<String>()
+        })
+        .unwrap_or_default();
+    Ok(Transcript {
+        text: text.trim().to_string(),
+        language: detected_language(output["result"]["language"].as_str()),
+    })
+}
+
+#[async_trait]
+impl Transcriber for WhisperCpp {
+    async fn transcribe(&self, audio: Vec<u8>, filename: &str) -> Result<Transcript> {
+        let dir = std::env::temp_dir().join(format!("agent-rs-voice-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| StockError::Other(format!("Failed to create {}: {e}", dir.display())))?;
+        let input = dir.join(filename);
+        let wav = dir.join("voice.wav");
+        let output = dir.join("voice");
+
+        let result = async {
+            std::fs::write(&input, audio).map_err(|e| {
+                StockError::Other(format!("Failed to write {}: {e}", input.display()))
+            })?;
+            run(
+                &self.ffmpeg,
+                &[
+                    "-loglevel".as_ref(),
+                    "error".as_ref(),
+                    "-i".as_ref(),
+                    input.as_os_str(),
+                    "-ar".as_ref(),
+                    "16000".as_ref(),
+                    "-ac".as_ref(),
+                    "1".as_ref(),
+                    wav.as_os_str(),
+                ],
+            )
+            .await?;
+            run(
+                &self.binary,
+                &[
+                    "-m".as_ref(),
+                    self.model.as_os_str(),
+                    "-f".as_ref(),
+                    wav.as_os_str(),
+                    "-l".as_ref(),
+                    "auto".as_ref(),
+                    "-nt".as_ref(),
+                    "-oj".as_ref(),
+                    "-of".as_ref(),
+                    output.as_os_str(),
+                ],
+            )
+            .await?;
+            let json = std::fs::read_to_string(output.with_extension("json")).map_err(|e| {
+                StockError::Other(format!("whisper.cpp produced no transcript: {e}"))
+            })?;
+            parse_whisper_cpp_json(&json)
+        }
+        .await;
+
+        std::fs::remove_dir_all(&dir).ok();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_whisper_api_transcribes_with_language() {
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/audio/transcriptions"))
+            .and(header("authorization", "Bearer sk-test"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "task": "transcribe",
+                "language": "chinese",
+                "text": " 分析一下英伟达 "
+            })))
+            .mount(&server)
+            .await;
+
+        let api = WhisperApi::new("sk-test").with_base_url(format!("{}/v1/", server.uri()));
+        let transcript = api.transcribe(b"OggS".to_vec(), "voice.ogg").await.unwrap();
+        assert_eq!(
+            transcript,
+            Transcript {
+                text: "分析一下英伟达".to_string(),
+                language: Some(Language::Chinese),
+            }
+        );
+    }
+
+    #[test]
+    fn test_spoken_summary_ends_at_a_sentence() {
+        let text = "🟢 AAPL Analysis\n\n• RSI ▁▃█ is neutral. ".to_string()
+            + &"The trend is up and momentum is strong. ".repeat(40);
+        let summary = spoken_summary(&text);
+        assert!(
+            summary.starts_with("AAPL Analysis RSI is neutral."),
+            "{summary}"
+        );
+        assert!(summary.ends_with('.'));
+        assert!(summary.len() <= SPOKEN_CHARS_PER_MINUTE);
+
+        let chinese = "短期偏多,支撑位在172美元附近。".repeat(30);
+        let summary = spoken_summary(&chinese);
+        assert!(summary.ends_with('。'));
+        // 13 CJK and 4 Latin characters per sentence: 56 of the 900 budget
+        assert_eq!(summary.matches('。').count(), 16);
+    }
+
+    #[tokio::test]
+    async fn test_audio_summary_of_long_replies() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/audio/speech"))
+            .and(body_partial_json(
+                serde_json::json!({ "model": "tts-1", "voice": "nova" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"ID3".to_vec()))
+            .mount(&server)
+            .await;
+        let tts = OpenAiTts::new("sk-test")
+            .with_base_url(server.uri())
+            .with_voice("nova");
+
+        assert!(audio_summary(&tts, "Short reply.").await.unwrap().is_none());
+
+        let attachment = audio_summary(&tts, &"A long analysis sentence. ".repeat(40))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(attachment.attachment_type, AttachmentType::Audio);
+        assert_eq!(attachment.content, b"ID3");
+        assert_eq!(attachment.filename.as_deref(), Some("summary.mp3"));
+    }
+
+    #[test]
+    fn test_parse_whisper_cpp_output() {
+        let json = r#"{
+            "result": { "language": "en" },
+            "transcription": [
+                { "text": " Analyze" },
+                { "text": " Nvidia." }
+            ]
+        }"#;
+        let transcript = parse_whisper_cpp_json(json).unwrap();
+        assert_eq!(transcript.text, "Analyze Nvidia.");
+        assert_eq!(transcript.language, Some(Language::English));
+
+        // Languages the bot cannot answer in are not reported
+        assert_eq!(detected_language(Some("ja")), None);
+    }
+}
@@ -0,0 +1,981 @@
+//! WeCom (企业微信) bot implementation
+//!
+//! Messages arrive through the callback URL of a self-built app (自建应用).
+//! The HTTP handler answers WeCom's URL check with
+//! [`WeComConfig::verify_url`] and passes every message POST to
+//! [`WeComConfig::decrypt_message`], which checks the `msg_signature` and
+//! decrypts the body with the app's `EncodingAESKey`; the resulting
+//! [`WeComEvent`] goes to [`WeComBot::on_callback`], which replies to the
+//! user through [`WeComApi`]. WeCom retries a callback that is not answered
+//! within five seconds, so the handler should answer with an empty body at
+//! once and let the bot reply afterwards.
+//!
+//! Replies are sent as `markdown` messages, which show headings, bold text,
+//! links, inline code and quotes. Group robots (群机器人) cannot receive
+//! messages; [`WeComWebhook`] pushes alerts to the group a robot belongs to.
+
+use crate::alerts::Notifier;
+use crate::bot::Command;
+use crate::engine::{AnalysisContext, StockAnalysisEngine};
+use crate::error::{Result, StockError};
+use crate::interface::interface::{Attachment, AttachmentType};
+use crate::interface::{
+    BotInterface, BotPlatform, BotResponse, Formatter, FormatterFactory, MessageLimit,
+    SessionManager, answer_progressively,
+};
+use crate::notebook;
+use crate::platforms::dispatch::{self, Reply};
+use crate::platforms::services::BotServices;
+use crate::report;
+use aes_gcm::aes::Aes256;
+use aes_gcm::aes::cipher::{BlockDecrypt, KeyInit, generic_array::GenericArray};
+use async_trait::async_trait;
+use base64::Engine;
+use base64::alphabet;
+use base64::engine::DecodePaddingMode;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig, STANDARD as BASE64};
+use chrono::Utc;
+use serde_json::{Value, json};
+use sha1::{Digest, Sha1};
+use std::fmt::Write;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// WeCom server API base URL
+const WECOM_API_URL: &str = "https://qyapi.weixin.qq.com/cgi-bin";
+
+/// Oldest callback timestamp accepted, against replayed requests
+const MAX_REQUEST_AGE_SECS: i64 = 5 * 60;
+
+/// Access tokens are renewed this long before WeCom expires them
+const TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(5 * 60);
+
+/// Decoder for `EncodingAESKey`s, which have no padding and whose last
+/// character carries stray bits
+const AES_KEY_BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new()
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent)
+        .with_decode_allow_trailing_bits(true),
+);
+
+/// Error codes of an access token that expired or was replaced early
+const STALE_TOKEN_ERRCODES: [i64; 2] = [40014, 42001];
+
+/// WeCom app configuration
+#[derive(Debug, Clone)]
+pub struct WeComConfig {
+    /// Corp ID (企业ID)
+    pub corp_id: String,
+
+    /// Agent ID of the self-built app
+    pub agent_id: i64,
+
+    /// App secret
+    pub secret: String,
+
+    /// Callback token callbacks are signed with
+    pub token: String,
+
+    /// Callback `EncodingAESKey` (43 characters) callbacks are encrypted with
+    pub encoding_aes_key: String,
+}
+
+impl WeComConfig {
+    /// Create config from environment variables
+    pub fn from_env() -> Result<Self> {
+        let var = |name: &str| {
+            std::env::var(name).map_err(|_| StockError::ConfigError(format!("{name} not set")))
+        };
+        let agent_id = var("WECOM_AGENT_ID")?
+            .trim()
+            .parse()
+            .map_err(|_| StockError::ConfigError("WECOM_AGENT_ID must be a number".to_string()))?;
+
+        Ok(Self {
+            corp_id: var("WECOM_CORP_ID")?,
+            agent_id,
+            secret: var("WECOM_SECRET")?,
+            token: var("WECOM_TOKEN")?,
+            encoding_aes_key: var("WECOM_ENCODING_AES_KEY")?,
+        })
+    }
+
+    /// Answer WeCom's check of the callback URL: the decrypted `echostr` to
+    /// respond with
+    ///
+    /// The arguments are the query parameters of the `GET` request.
+    pub fn verify_url(
+        &self,
+        msg_signature: &str,
+        timestamp: &str,
+        nonce: &str,
+        echostr: &str,
+    ) -> Result<String> {
+        self.check_signature(
+            msg_signature,
+            timestamp,
+            nonce,
+            echostr,
+            Utc::now().timestamp(),
+        )?;
+        self.decrypt(echostr)
+    }
+
+    /// Check and decrypt a callback
+    ///
+    /// `msg_signature`, `timestamp` and `nonce` are the query parameters of
+    /// the `POST` request, `body` its XML body.
+    pub fn decrypt_message(
+        &self,
+        msg_signature: &str,
+        timestamp: &str,
+        nonce: &str,
+        body: &str,
+    ) -> Result<WeComEvent> {
+        self.decrypt_message_at(
+            msg_signature,
+            timestamp,
+            nonce,
+            body,
+            Utc::now().timestamp(),
+        )
+    }
+
+    fn decrypt_message_at(
+        &self,
+        msg_signature: &str,
+        timestamp: &str,
+        nonce: &str,
+        body: &str,
+        now: i64,
+    ) -> Result<WeComEvent> {
+        let encrypted = xml_field(body, "Encrypt").ok_or_else(|| {
+            StockError::ApiError("WeCom callback has no Encrypt field".to_string())
+        })?;
+        self.check_signature(msg_signature, timestamp, nonce, &encrypted, now)?;
+        Ok(WeComEvent::parse(&self.decrypt(&encrypted)?))
+    }
+
+    /// SHA-1 of the token, timestamp, nonce and ciphertext sorted and joined
+    fn signature(&self, timestamp: &str, nonce: &str, encrypted: &str) -> String {
+        let mut parts = [self.token.as_str(), timestamp, nonce, encrypted];
+        parts.sort_unstable();
+        Sha1::digest(parts.concat())
+            .iter()
+            .fold(String::new(), |mut hex, byte| {
+                let _ = write!(hex, "{byte:02x}");
+                hex
+            })
+    }
+
+    fn check_signature(
+        &self,
+        msg_signature: &str,
+        timestamp: &str,
+        nonce: &str,
+        encrypted: &str,
+        now: i64,
+    ) -> Result<()> {
+        let fresh = timestamp
+            .trim()
+            .parse::<i64>()
+            .is_ok_and(|sent_at| (now - sent_at).abs() <= MAX_REQUEST_AGE_SECS);
+        let expected = self.signature(timestamp.trim(), nonce, encrypted);
+        if fresh && constant_time_eq(expected.as_bytes(), msg_signature.trim().as_bytes()) {
+            Ok(())
+        } else {
+            Err(StockError::Unauthorized(
+                "WeCom callback signature mismatch".to_string(),
+            ))
+        }
+    }
+
+    /// AES key and IV from the 43-character `EncodingAESKey`
+    fn aes_key(&self) -> Result<[u8; 32]> {
+        AES_KEY_BASE64
+            .decode(self.encoding_aes_key.trim())
+            .ok()
+            .and_then(|key| key.try_into().ok())
+            .ok_or_else(|| {
+                StockError::ConfigError(
+                    "WECOM_ENCODING_AES_KEY must be the 43-character key from the app's callback settings"
+                        .to_string(),
+                )
+            })
+    }
+
+    /// Decrypt a callback payload
+    ///
+    /// Payloads are AES-256-CBC with the key's first 16 bytes as IV and
+    /// PKCS#7 padding to 32 bytes. The plaintext is 16 random bytes, the
+    /// message length (4 bytes, big-endian), the message and the ID of the
+    /// receiving corp.
+    fn decrypt(&self, encrypted: &str) -> Result<String> {
+        let malformed = || StockError::ApiError("Malformed WeCom callback payload".to_string());
+        let key = self.aes_key()?;
+        let data = BASE64.decode(encrypted.trim()).map_err(|_| malformed())?;
+        if data.is_empty() || data.len() % 16 != 0 {
+            return Err(malformed());
+        }
+
+        let cipher = Aes256::new(GenericArray::from_slice(&key));
+        let mut previous = &key[..16];
+        let mut plain = Vec::with_capacity(data.len());
+        for chunk in data.chunks_exact(16) {
+            let mut block = GenericArray::clone_from_slice(chunk);
+            cipher.decrypt_block(&mut block);
+            plain.extend(block.iter().zip(previous).map(|(byte, iv)| byte ^ iv));
+            previous = chunk;
+        }
+        let padding = plain
+            .last()
+            .map(|&pad| usize::from(pad))
+            .filter(|pad| (1..=32).contains(pad) && *pad <= plain.len())
+            .ok_or_else(malformed)?;
+        plain.truncate(plain.len() - padding);
+
+        let length = plain
+            .get(16..20)
+            .and_then(|bytes| <[u8; 4]>::try_from(bytes).ok())
+            .map(u32::from_be_bytes)
+            .and_then(|length| usize::try_from(length).ok())
+            .ok_or_else(malformed)?;
+        let message = plain.get(20..20 + length).ok_or_else(malformed)?;
+        if plain[20 + length..] != *self.corp_id.as_bytes() {
+            return Err(StockError::Unauthorized(
+                "WeCom callback is addressed to another corp".to_string(),
+            ));
+        }
+        String::from_utf8(message.to_vec()).map_err(|_| malformed())
+    }
+}
+
+/// Compare without leaking where the inputs differ
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Text of the first `<tag>` element of a WeCom XML message, unwrapping
+/// `CDATA` and decoding entities
+fn xml_field(xml: &str, tag: &str) -> Option<String> {
+    let start = xml.find(&format!("<{tag}>"))? + tag.len() + 2;
+    let end = start + xml[start..].find(&format!("</{tag}>"))?;
+    let value = xml[start..end].trim();
+    Some(
+        match value
+            .strip_prefix("<![CDATA[")
+            .and_then(|v| v.strip_suffix("]]>"))
+        {
+            Some(cdata) => cdata.to_string(),
+            None => value
+                .replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&quot;", "\"")
+                .replace("&apos;", "'")
+                .replace("&amp;", "&"),
+        },
+    )
+}
+
+/// What a decrypted callback asks of the bot
+#[derive(Debug, Clone, PartialEq)]
+pub enum WeComEvent {
+    /// A user wrote to the app
+    Message { user: String, text: String },
+    /// Anything else: events, images, voice and the like
+    Ignored,
+}
+
+impl WeComEvent {
+    /// Parse a decrypted callback message
+    pub fn parse(xml: &str) -> Self {
+        if xml_field(xml, "MsgType").as_deref() != Some("text") {
+            return Self::Ignored;
+        }
+        match (xml_field(xml, "FromUserName"), xml_field(xml, "Content")) {
+            (Some(user), Some(text)) if !text.trim().is_empty() => Self::Message {
+                user,
+                text: text.trim().to_string(),
+            },
+            _ => Self::Ignored,
+        }
+    }
+}
+
+/// Access token and when it stops being used
+type CachedToken = Option<(String, Instant)>;
+
+/// Minimal WeCom server API client for sending app messages
+#[derive(Clone)]
+pub struct WeComApi {
+    corp_id: String,
+    secret: String,
+    agent_id: i64,
+    base_url: String,
+    client: reqwest::Client,
+    token: Arc<Mutex<CachedToken>>,
+}
+
+impl WeComApi {
+    /// Create a client for the app
+    pub fn new(corp_id: impl Into<String>, secret: impl Into<String>, agent_id: i64) -> Self {
+        Self {
+            corp_id: corp_id.into(),
+            secret: secret.into(),
+            agent_id,
+            base_url: WECOM_API_URL.to_string(),
+            client: reqwest::Client::new(),
+            token: Arc::default(),
+        }
+    }
+
+    /// Client for the configured app
+    pub fn from_config(config: &WeComConfig) -> Self {
+        Self::new(&config.corp_id, &config.secret, config.agent_id)
+    }
+
+    /// Send requests to `base_url` (e.g. a mock)
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Access token, fetched again shortly before it expires
+    pub async fn access_token(&self) -> Result<String> {
+        if let Some((token, until)) = &*self.token.lock().unwrap_or_else(PoisonError::into_inner)
+            && *until > Instant::now()
+        {
+            return Ok(token.clone());
+        }
+
+        let body: Value = self
+            .client
+            .get(format!("{}/gettoken", self.base_url))
+            .query(&[("corpid", &self.corp_id), ("corpsecret", &self.secret)])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let token = match errcode(&body) {
+            0 => body
+                .get("access_token")
+                .and_then(Value::as_str)
+                .ok_or_else(|| {
+                    StockError::ApiError("WeCom gettoken returned no access_token".to_string())
+                })?,
+            40001 | 40013 | 40091 => {
+                return Err(StockError::ConfigError(
+                    "WeCom rejected the app credentials (check WECOM_CORP_ID and WECOM_SECRET)"
+                        .to_string(),
+                ));
+            }
+            code => return Err(api_error("gettoken", code, &body)),
+        };
+        let expires_in = Duration::from_secs(body["expires_in"].as_u64().unwrap_or(7200));
+        let until = Instant::now() + expires_in.saturating_sub(TOKEN_EXPIRY_MARGIN);
+        *self.token.lock().unwrap_or_else(PoisonError::into_inner) =
+            Some((token.to_string(), until));
+        Ok(token.to_string())
+    }
+
+    /// Send a `markdown` message to a user
+    pub async fn send_markdown(&self, user_id: &str, content: &str) -> Result<()> {
+        self.send(
+            user_id,
+            json!({ "msgtype": "markdown", "markdown": { "content": content } }),
+        )
+        .await
+    }
+
+    /// Send a plain `text` message to a user
+    pub async fn send_text(&self, user_id: &str, content: &str) -> Result<()> {
+        self.send(
+            user_id,
+            json!({ "msgtype": "text", "text": { "content": content } }),
+        )
+        .await
+    }
+
+    /// Send every message of a reply, then its attachments
+    pub async fn send_response(&self, user_id: &str, response: &BotResponse) -> Result<()> {
+        for text in response.messages() {
+            self.send_markdown(user_id, text).await?;
+        }
+        for attachment in &response.attachments {
+            self.send_file(user_id, attachment).await?;
+        }
+        Ok(())
+    }
+
+    /// Send `attachment` to a user: images and charts as an `image`
+    /// message, anything else (including audio, which `voice` messages only
+    /// take as AMR) as a `file`
+    pub async fn send_file(&self, user_id: &str, attachment: &Attachment) -> Result<()> {
+        let media_type = match attachment.attachment_type {
+            AttachmentType::Image | AttachmentType::Chart => "image",
+            AttachmentType::Document | AttachmentType::Audio => "file",
+        };
+        let media_id = self.upload_media(media_type, attachment).await?;
+        self.send(
+            user_id,
+            json!({ "msgtype": media_type, media_type: { "media_id": media_id } }),
+        )
+        .await
+    }
+
+    /// Upload `attachment` as temporary media; returns its `media_id`,
+    /// valid for three days
+    pub async fn upload_media(&self, media_type: &str, attachment: &Attachment) -> Result<String> {
+        let mut body = self.post_media(media_type, attachment).await?;
+        if STALE_TOKEN_ERRCODES.contains(&errcode(&body)) {
+            *self.token.lock().unwrap_or_else(PoisonError::into_inner) = None;
+            body = self.post_media(media_type, attachment).await?;
+        }
+        match errcode(&body) {
+            0 => body
+                .get("media_id")
+                .and_then(Value::as_str)
+                .map(ToString::to_string)
+                .ok_or_else(|| {
+                    StockError::ApiError("WeCom media/upload returned no media_id".to_string())
+                }),
+            code => Err(api_error("media/upload", code, &body)),
+        }
+    }
+
+    async fn post_media(&self, media_type: &str, attachment: &Attachment) -> Result<Value> {
+        let token = self.access_token().await?;
+        let file = reqwest::multipart::Part::bytes(attachment.content.clone())
+            .file_name(
+                attachment
+                    .filename
+                    .clone()
+                    .unwrap_or_else(|| media_type.to_string()),
+            )
+            .mime_str(&attachment.mime_type)?;
+        Ok(self
+            .client
+            .post(format!("{}/media/upload", self.base_url))
+            .query(&[("access_token", token.as_str()), ("type", media_type)])
+            .multipart(reqwest::multipart::Form::new().part("media", file))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    async fn send(&self, user_id: &str, mut message: Value) -> Result<()> {
+        message["touser"] = json!(user_id);
+        message["agentid"] = json!(self.agent_id);
+        let mut body = self.post("message/send", &message).await?;
+        // A token replaced elsewhere stops working before it expires
+        if STALE_TOKEN_ERRCODES.contains(&errcode(&body)) {
+            *self.token.lock().unwrap_or_else(PoisonError::into_inner) = None;
+            body = self.post("message/send", &message).await?;
+        }
+        match errcode(&body) {
+            0 => Ok(()),
+            code => Err(api_error("message/send", code, &body)),
+        }
+    }
+
+    async fn post(&self, method: &str, body: &Value) -> Result<Value> {
+        let token = self.access_token().await?;
+        Ok(self
+            .client
+            .post(format!("{}/{method}", self.base_url))
+            .query(&[("access_token", token)])
+            .json(body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+}
+
+#[async_trait]
+impl Notifier for WeComApi {
+    async fn notify(&self, user_id: &str, message: &str) -> Result<()> {
+        self.send_text(user_id, message).await
+    }
+}
+
+/// `errcode` of a WeCom API response; missing counts as success
+fn errcode(body: &Value) -> i64 {
+    body.get("errcode").and_then(Value::as_i64).unwrap_or(0)
+}
+
+fn api_error(method: &str, code: i64, body: &Value) -> StockError {
+    StockError::ApiError(format!(
+        "WeCom {method} failed ({code}): {}",
+        body.get("errmsg")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown error")
+    ))
+}
+
+/// Pushes alert notifications to a WeCom group through its group robot
+/// webhook
+///
+/// The robot posts to the group it belongs to, so every alert set from
+/// WeCom goes to that group.
+#[derive(Clone)]
+pub struct WeComWebhook {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl WeComWebhook {
+    /// Notifier posting to the robot webhook `url`
+    /// (`https://qyapi.weixin.qq.com/cgi-bin/webhook/send?key=...`)
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Create from the `WECOM_WEBHOOK` environment variable
+    pub fn from_env() -> Result<Self> {
+        std::env::var("WECOM_WEBHOOK")
+            .map(Self::new)
+            .map_err(|_| StockError::ConfigError("WECOM_WEBHOOK not set".to_string()))
+    }
+}
+
+#[async_trait]
+impl Notifier for WeComWebhook {
+    async fn notify(&self, _user_id: &str, message: &str) -> Result<()> {
+        let body: Value = self
+            .client
+            .post(&self.url)
+            .json(&json!({ "msgtype": "text", "text": { "content": message } }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        match errcode(&body) {
+            0 => Ok(()),
+            code => Err(StockError::ApiError(format!(
+                "WeCom webhook failed ({code}): {}",
+                body.get("errmsg")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown error")
+            ))),
+        }
+    }
+}
+
+/// WeCom bot
+pub struct WeComBot {
+    _config: WeComConfig,
+    engine: StockAnalysisEngine,
+    session_manager: SessionManager,
+    formatter: Box<dyn Formatter>,
+    services: BotServices,
+    api: WeComApi,
+}
+
+impl WeComBot {
+    /// Create a new WeCom bot
+    pub fn new(config: WeComConfig, engine: StockAnalysisEngine) -> Self {
+        let api = WeComApi::from_config(&config);
+        Self {
+            _config: config,
+            engine,
+            session_manager: SessionManager::new(BotPlatform::WeCom),
+            formatter: FormatterFactory::create_with_limit(
+                BotPlatform::WeCom,
+                MessageLimit::from_env(BotPlatform::WeCom),
+            ),
+            services: BotServices::default(),
+            api,
+        }
+    }
+
+    /// Serve users with `services`; bots serving the same users may share
+    /// one set
+    pub fn with_services(mut self, services: BotServices) -> Self {
+        if let Some(store) = &services.conversations {
+            self.session_manager.set_conversations(Arc::clone(store));
+        }
+        self.services = services;
+        self
+    }
+
+    /// Use a different server API client
+    pub fn with_api(mut self, api: WeComApi) -> Self {
+        self.api = api;
+        self
+    }
+
+    /// Server API client replies are sent with
+    pub fn api(&self) -> &WeComApi {
+        &self.api
+    }
+
+    /// Process a command
+    pub async fn process_command(&self, user_id: &str, input: &str) -> Result<String> {
+        let mut session = self.session_manager.get_or_create(user_id)?;
+        let mut context = session.context.clone();
+
+        let command = Command::parse(input)?;
+        let exchange = command.is_heavy().then(|| command.symbols());
+
+        let reply = Box::pin(dispatch::command_reply(
+            &self.engine,
+            &self.services,
+            &mut session,
+            &mut context,
+            &command,
+        ))
+        .await?;
+        let response = match reply {
+            Some(Reply::Analysis(result)) => self.formatter.format_analysis(&result, &context),
+            Some(Reply::Comparison(result)) => result.summary,
+            Some(Reply::Text(text) | Reply::Table(text)) => text,
+            None if command == Command::Help => self.formatter.format_help(),
+            None => "Command not yet implemented".to_string(),
+        };
+
+        if let Some(symbols) = exchange {
+            context.record_exchange(input, &response, symbols);
+        }
+        session.context = context;
+        self.session_manager.update(user_id, session)?;
+
+        Ok(response)
+    }
+
+    /// Answer a decrypted callback message through [`WeComApi`]
+    pub async fn on_callback(&self, event: WeComEvent) -> Result<()> {
+        let WeComEvent::Message { user, text } = event else {
+            return Ok(());
+        };
+        let mut context = AnalysisContext::with_user(&user);
+        let (api, to) = (&self.api, user.as_str());
+        let interim = |reply: BotResponse| async move {
+            if let Err(e) = api.send_response(to, &reply).await {
+                tracing::warn!("Could not send an interim reply to {to}: {e}");
+            }
+        };
+        let response = match answer_progressively(self, &user, &text, &mut context, interim).await {
+            Ok(response) => response,
+            Err(e) => BotResponse::error(self.formatter.format_error(&e.to_string())),
+        };
+        self.api.send_response(&user, &response).await
+    }
+}
+
+#[async_trait]
+impl BotInterface for WeComBot {
+    fn platform(&self) -> BotPlatform {
+        BotPlatform::WeCom
+    }
+
+    async fn on_message(
+        &self,
+        user_id: &str,
+        message: &str,
+        _context: &mut AnalysisContext,
+    ) -> Result<BotResponse> {
+        match Command::parse(message) {
+            Ok(Command::More) => return self.session_manager.next_page(user_id),
+            // Notebooks go out as a document along with the caption
+            Ok(Command::Notebook { kind }) => {
+                let session = self.session_manager.get_or_create(user_id)?;
+                let (text, notebook) = notebook::command_reply(&session.context, kind).await?;
+                let reply = self.session_manager.paginate(
+                    user_id,
+                    &text,
+                    self.formatter.message_limit(),
+                )?;
+                return Ok(match notebook {
+                    Some(notebook) => reply.with_attachment(notebook),
+                    None => reply,
+                });
+            }
+            // Reports go out as a document, after the user's earlier requests
+            Ok(Command::Report { symbols, format }) => {
+                let (text, document) = report::queued_reply(
+                    &self.engine,
+                    &self.services.queue,
+                    &self.session_manager,
+                    user_id,
+                    &symbols,
+                    format,
+                    BotPlatform::WeCom,
+                )
+                .await?;
+                let reply = self.session_manager.paginate(
+                    user_id,
+                    &text,
+                    self.formatter.message_limit(),
+                )?;
+                return Ok(reply.with_attachments(document));
+            }
+            // Heavy requests wait for the user's earlier ones to finish
+            Ok(command) if command.is_heavy() => {
+                let response = self
+                    .services
+                    .queue
+                    .run(user_id, self.process_command(user_id, message))
+                    .await??;
+                return self.session_manager.paginate(
+                    user_id,
+                    &response,
+                    self.formatter.message_limit(),
+                );
+            }
+            _ => {}
+        }
+        let response = self.process_command(user_id, message).await?;
+        self.session_manager
+            .paginate(user_id, &response, self.formatter.message_limit())
+    }
+
+    async fn on_command(
+        &self,
+        user_id: &str,
+        command: &str,
+        args: &[String],
+        context: &mut AnalysisContext,
+    ) -> Result<BotResponse> {
+        let full_command = if args.is_empty() {
+            format!("/{command}")
+        } else {
+            format!("/{} {}", command, args.join(" "))
+        };
+
+        self.on_message(user_id, &full_command, context).await
+    }
+
+    fn format_response(&self, content: &str, _context: &AnalysisContext) -> BotResponse {
+        BotResponse::formatted(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes_gcm::aes::cipher::BlockEncrypt;
+    use wiremock::matchers::{body_partial_json, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn config() -> WeComConfig {
+        WeComConfig {
+            corp_id: "wx5823bf96d3bd56c7".into(),
+            agent_id: 1_000_002,
+            secret: "secret".into(),
+            token: "QDG6eK".into(),
+            encoding_aes_key: "jWmYm7qr5nMoAUwZRjGtBxmz3KA1tkAj3ykkR6q2B2C".into(),
+        }
+    }
+
+    /// Encrypt like WeCom does, for callbacks to decrypt
+    fn encrypt(config: &WeComConfig, message: &str) -> String {
+        let key = config.aes_key().unwrap();
+        let mut plain = b"0123456789abcdef".to_vec();
+        plain.extend(u32::try_from(message.len()).unwrap().to_be_bytes());
+        plain.extend(message.as_bytes());
+        plain.extend(config.corp_id.as_bytes());
+        let padding = 32 - plain.len() % 32;
+        plain.extend(std::iter::repeat_n(u8::try_from(padding).unwrap(), padding));
+
+        let cipher = Aes256::new(GenericArray::from_slice(&key));
+        let mut previous = key[..16].to_vec();
+        let mut encrypted = Vec::new();
+        for chunk in plain.chunks_exact(16) {
+            let mut block = GenericArray::clone_from_slice(chunk);
+            block
+                .iter_mut()
+                .zip(&previous)
+                .for_each(|(byte, iv)| *byte ^= iv);
+            cipher.encrypt_block(&mut block);
+            previous = block.to_vec();
+            encrypted.extend(block);
+        }
+        BASE64.encode(encrypted)
+    }
+
+    #[test]
+    fn test_signature() {
+        assert_eq!(
+            config().signature("1409659813", "1372623149", "aGVsbG8="),
+            "ffc5e83b9bf071dfc27c75dea5222c64fbbe739a"
+        );
+    }
+
+    #[test]
+    fn test_decrypt_message() {
+        let config = config();
+        let message = "<xml><ToUserName><![CDATA[wx5823bf96d3bd56c7]]></ToUserName>\
+            <FromUserName><![CDATA[zhangsan]]></FromUserName><CreateTime>1409659813</CreateTime>\
+            <MsgType><![CDATA[text]]></MsgType><Content><![CDATA[/alert AAPL > 200]]></Content>\
+            <MsgId>4561255354251345929</MsgId><AgentID>1000002</AgentID></xml>";
+        let encrypted = encrypt(&config, message);
+        let body = format!(
+            "<xml><ToUserName><![CDATA[wx5823bf96d3bd56c7]]></ToUserName>\
+             <Encrypt><![CDATA[{encrypted}]]></Encrypt><AgentID><![CDATA[1000002]]></AgentID></xml>"
+        );
+        let signature = config.signature("1409659813", "1372623149", &encrypted);
+        let now = 1_409_659_813 + 60;
+
+        assert_eq!(
+            config
+                .decrypt_message_at(&signature, "1409659813", "1372623149", &body, now)
+                .unwrap(),
+            WeComEvent::Message {
+                user: "zhangsan".into(),
+                text: "/alert AAPL > 200".into(),
+            }
+        );
+        assert!(matches!(
+            config.decrypt_message_at(&signature, "1409659813", "1372623150", &body, now),
+            Err(StockError::Unauthorized(_))
+        ));
+        // Replayed long after it was signed
+        assert!(
+            config
+                .decrypt_message_at(&signature, "1409659813", "1372623149", &body, now + 3600)
+                .is_err()
+        );
+
+        // Addressed to another corp
+        let other = WeComConfig {
+            corp_id: "wx0000000000000000".into(),
+            ..config.clone()
+        };
+        assert!(matches!(
+            other.decrypt(&encrypted),
+            Err(StockError::Unauthorized(_))
+        ));
+        assert_eq!(
+            config
+                .decrypt(&encrypt(&config, "1616140317555161061"))
+                .unwrap(),
+            "1616140317555161061"
+        );
+    }
+
+    #[test]
+    fn test_parse_events() {
+        let event = "<xml><FromUserName><![CDATA[lisi]]></FromUserName>\
+            <MsgType><![CDATA[event]]></MsgType><Event><![CDATA[enter_agent]]></Event></xml>";
+        assert_eq!(WeComEvent::parse(event), WeComEvent::Ignored);
+        let escaped = "<xml><FromUserName>lisi</FromUserName><MsgType>text</MsgType>\
+            <Content>RSI &lt; 30 &amp; falling</Content></xml>";
+        assert_eq!(
+            WeComEvent::parse(escaped),
+            WeComEvent::Message {
+                user: "lisi".into(),
+                text: "RSI < 30 & falling".into()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_api_calls() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/gettoken"))
+            .and(query_param("corpid", "ww1"))
+            .and(query_param("corpsecret", "good"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "errcode": 0, "errmsg": "ok", "access_token": "TOKEN", "expires_in": 7200
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/message/send"))
+            .and(query_param("access_token", "TOKEN"))
+            .and(body_partial_json(json!({
+                "touser": "zhangsan",
+                "agentid": 1_000_002,
+                "msgtype": "markdown",
+                "markdown": { "content": "**AAPL** 看涨" },
+            })))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(json!({ "errcode": 0, "errmsg": "ok" })),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/gettoken"))
+            .and(query_param("corpsecret", "bad"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "errcode": 40001, "errmsg": "invalid credential"
+            })))
+            .mount(&server)
+            .await;
+
+        let api = WeComApi::new("ww1", "good", 1_000_002).with_base_url(server.uri());
+        api.send_markdown("zhangsan", "**AAPL** 看涨")
+            .await
+            .unwrap();
+        // The token is reused
+        api.send_markdown("zhangsan", "**AAPL** 看涨")
+            .await
+            .unwrap();
+
+        let bad = WeComApi::new("ww1", "bad", 1_000_002).with_base_url(server.uri());
+        assert!(matches!(
+            bad.access_token().await,
+            Err(StockError::ConfigError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_send_response_uploads_attachments() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/gettoken"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "errcode": 0, "access_token": "TOKEN", "expires_in": 7200
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/media/upload"))
+            .and(query_param("type", "file"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "errcode": 0, "type": "file", "media_id": "MEDIA1"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/message/send"))
+            .and(body_partial_json(json!({ "msgtype": "markdown" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "errcode": 0 })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/message/send"))
+            .and(body_partial_json(json!({
+                "touser": "zhangsan",
+                "msgtype": "file",
+                "file": { "media_id": "MEDIA1" },
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "errcode": 0 })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let api = WeComApi::new("ww1", "good", 1_000_002).with_base_url(server.uri());
+        let response = BotResponse::text("📄 AAPL report").with_attachment(Attachment {
+            attachment_type: AttachmentType::Document,
+            content: b"%PDF".to_vec(),
+            filename: Some("AAPL.pdf".into()),
+            mime_type: "application/pdf".into(),
+        });
+        api.send_response("zhangsan", &response).await.unwrap();
+    }
+}
@@ -0,0 +1,156 @@
+//! Plugin interface for third-party analyzer agents
+//!
+//! An [`AnalyzerPlugin`] is a factory for a custom analyzer (a crypto
+//! analyzer, an options-flow analyzer, ...). [`StockAnalysisAgent::with_plugins`]
+//! registers its prompts and tools, creates its agent, adds the agent to the
+//! delegation registry and routes queries matching its keywords to it, so
+//! the analyzer also shows up in `/capabilities`.
+//!
+//! Plugins usually live in their own crates that depend on `agent-stock`;
+//! the binary wiring the bot enables them, typically behind a cargo feature
+//! of its own:
+//!
+//! ```rust,ignore
+//! let mut plugins: Vec<Arc<dyn AnalyzerPlugin>> = Vec::new();
+//! #[cfg(feature = "crypto")]
+//! plugins.push(Arc::new(crypto_analyzer::CryptoPlugin));
+//!
+//! let engine = StockAnalysisEngine::with_plugins(runtime, config, &plugins).await?;
+//! ```
+//!
+//! [`StockAnalysisAgent::with_plugins`]: crate::agents::StockAnalysisAgent::with_plugins
+
+use agent_core::{Agent, Error, Result};
+use agent_prompt::PromptTemplate;
+use agent_runtime::AgentRuntime;
+use agent_tools::Tool;
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::config::{SPECIALIST_AGENTS, StockConfig};
+
+/// Factory for a third-party analyzer agent
+///
+/// Only [`key`](AnalyzerPlugin::key) and
+/// [`create_agent`](AnalyzerPlugin::create_agent) are required. The agent's
+/// [`Agent::capabilities`] describe it in `/capabilities` and the routing
+/// table.
+#[async_trait]
+pub trait AnalyzerPlugin: Send + Sync {
+    /// Routing key of the analyzer, e.g. "crypto-analyzer"
+    ///
+    /// Must not clash with a built-in specialist or another plugin.
+    fn key(&self) -> &str;
+
+    /// Lowercase keywords (any language) that route a query to the analyzer
+    ///
+    /// Checked before the built-in intents, so keep them specific ("crypto",
+    /// "比特币") rather than generic ("analysis").
+    fn keywords(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Prompt templates the analyzer renders, registered in
+    /// [`StockConfig::prompt_registry`] before the agent is created
+    fn prompts(&self) -> agent_prompt::Result<Vec<Arc<dyn PromptTemplate>>> {
+        Ok(Vec::new())
+    }
+
+    /// Tools the analyzer calls, registered in the runtime's tool registry
+    /// before the agent is created
+    fn tools(&self, _config: &Arc<StockConfig>) -> Vec<Arc<dyn Tool>> {
+        Vec::new()
+    }
+
+    /// Create the analyzer agent
+    async fn create_agent(
+        &self,
+        runtime: Arc<AgentRuntime>,
+        config: Arc<StockConfig>,
+    ) -> Result<Arc<dyn Agent>>;
+}
+
+/// Check plugin keys for clashes with the built-in specialists and each other
+pub fn validate_plugins(plugins: &[Arc<dyn AnalyzerPlugin>]) -> Result<()> {
+    for (i, plugin) in plugins.iter().enumerate() {
+        let key = plugin.key();
+        if key.is_empty() {
+            return Err(Error::InitializationFailed(
+                "Analyzer plugin key must not be empty".to_string(),
+            ));
+        }
+        if SPECIALIST_AGENTS.contains(&key) {
+            return Err(Error::InitializationFailed(format!(
+                "Analyzer plugin '{key}' clashes with a built-in specialist"
+            )));
+        }
+        if plugins[..i].iter().any(|other| other.key() == key) {
+            return Err(Error::InitializationFailed(format!(
+                "Analyzer plugin '{key}' is registered twice"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Register a plugin's prompts and tools, then create its agent
+pub async fn install_plugin(
+    plugin: &dyn AnalyzerPlugin,
+    runtime: &Arc<AgentRuntime>,
+    config: &Arc<StockConfig>,
+) -> Result<Arc<dyn Agent>> {
+    let prompts = plugin.prompts().map_err(|e| {
+        Error::InitializationFailed(format!(
+            "Analyzer plugin '{}' prompts failed: {e}",
+            plugin.key()
+        ))
+    })?;
+    for prompt in prompts {
+        config.prompt_registry.register_arc(prompt);
+    }
+    for tool in plugin.tools(config) {
+        runtime.tools().register(tool);
+    }
+
+    let agent = plugin
+        .create_agent(Arc::clone(runtime), Arc::clone(config))
+        .await?;
+    tracing::info!("Installed analyzer plugin '{}'", plugin.key());
+    Ok(agent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct KeyOnly(&'static str);
+
+    #[async_trait]
+    impl AnalyzerPlugin for KeyOnly {
+        fn key(&self) -> &str {
+            self.0
+        }
+
+        async fn create_agent(
+            &self,
+            _runtime: Arc<AgentRuntime>,
+            _config: Arc<StockConfig>,
+        ) -> Result<Arc<dyn Agent>> {
+            unimplemented!()
+        }
+    }
+
+    #[test]
+    fn test_validate_plugins() {
+        let plugin = |key| Arc::new(KeyOnly(key)) as Arc<dyn AnalyzerPlugin>;
+
+        assert!(validate_plugins(&[plugin("options-analyzer"), plugin("crypto-analyzer")]).is_ok());
+        assert!(validate_plugins(&[plugin("news-analyzer")]).is_err());
+        assert!(validate_plugins(&[plugin("esg-analyzer")]).is_err());
+        assert!(validate_plugins(&[plugin("")]).is_err());
+
+        let err =
+            validate_plugins(&[plugin("crypto-analyzer"), plugin("crypto-analyzer")]).unwrap_err();
+        assert!(err.to_string().contains("registered twice"));
+    }
+}
@@ -0,0 +1,857 @@
+//! Portfolio tracking
+//!
+//! Users record positions (symbol, quantity, cost basis) with
+//! `/portfolio add`, kept per user in a [`PortfolioStore`]. [`valuate`]
+//! prices them with a year of Yahoo Finance daily bars and the company
+//! profile, giving market value and unrealized P&L, year-to-date return,
+//! beta against the S&P 500 ([`BENCHMARK`]) and sector exposure. The
+//! [`PortfolioAgent`] answers questions about the portfolio from the same
+//! report.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::portfolio::{PortfolioStore, valuate};
+//!
+//! let store = PortfolioStore::open("portfolio.json")?;
+//! store.add("12345", "AAPL", 10.0, 150.0)?;
+//!
+//! let report = valuate(&YahooFinanceClient::new(), &store.positions("12345")).await?;
+//! println!("{report}");
+//! ```
+
+use agent_core::Context;
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{PoisonError, RwLock};
+
+use crate::agents::PortfolioAgent;
+use crate::api::YahooFinanceClient;
+use crate::api::yahoo::Quote;
+use crate::bot::Command;
+use crate::error::{Result, StockError};
+use crate::storage::{self, StoreCipher};
+
+/// Index fund beta is measured against
+pub const BENCHMARK: &str = "SPY";
+
+/// Tool parameter naming whose positions a tool reads
+///
+/// Only callers set it (see [`agent_tools::Tool::caller_params`]): the
+/// [`PortfolioAgent`] pins the requesting user on its tool calls, and
+/// whatever the model passes is dropped.
+pub const USER_ID_PARAM: &str = "user_id";
+
+/// Sector of holdings whose profile has none (funds) or failed to load
+pub const UNKNOWN_SECTOR: &str = "Unknown";
+
+/// Symbols priced at once
+const MAX_CONCURRENT_FETCHES: usize = 4;
+
+/// Daily returns needed for a meaningful beta (about a month)
+const MIN_BETA_RETURNS: usize = 20;
+
+/// Shares of one symbol held by a user
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Position {
+    pub symbol: String,
+    pub quantity: f64,
+    /// Average price paid per share
+    pub cost_basis: f64,
+    pub opened_at: DateTime<Utc>,
+}
+
+impl Position {
+    /// Total amount paid for the position
+    pub fn cost(&self) -> f64 {
+        self.quantity * self.cost_basis
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} @ {:.2}",
+            self.symbol, self.quantity, self.cost_basis
+        )
+    }
+}
+
+/// Persisted positions of all users
+///
+/// Kept in memory and, when opened with a path, saved as JSON after every
+/// change (encrypted when opened with a cipher).
+pub struct PortfolioStore {
+    portfolios: RwLock<BTreeMap<String, Vec<Position>>>,
+    path: Option<PathBuf>,
+    cipher: Option<StoreCipher>,
+}
+
+impl Default for PortfolioStore {
+    fn default() -> Self {
+        Self::in_memory()
+    }
+}
+
+impl PortfolioStore {
+    /// Create a store that is not persisted
+    pub fn in_memory() -> Self {
+        Self {
+            portfolios: RwLock::new(BTreeMap::new()),
+            path: None,
+            cipher: None,
+        }
+    }
+
+    /// Open a store persisted at `path`, loading existing positions
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        Self::open_with_cipher(path, None)
+    }
+
+    /// Open a store persisted at `path`, encrypted with `cipher` if given
+    pub fn open_with_cipher(path: impl Into<PathBuf>, cipher: Option<StoreCipher>) -> Result<Self> {
+        let path = path.into();
+        let portfolios = match storage::read_store(&path, cipher.as_ref())? {
+            Some(json) => serde_json::from_str(&json)?,
+            None => BTreeMap::new(),
+        };
+
+        Ok(Self {
+            portfolios: RwLock::new(portfolios),
+            path: Some(path),
+            cipher,
+        })
+    }
+
+    /// File the positions are persisted to, if any
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Buy `quantity` shares of `symbol` at `cost_basis` for `user_id`
+    ///
+    /// Adding to an existing position averages its cost basis. Returns the
+    /// resulting position.
+    pub fn add(
+        &self,
+        user_id: &str,
+        symbol: &str,
+        quantity: f64,
+        cost_basis: f64,
+    ) -> Result<Position> {
+        if !(quantity.is_finite() && quantity > 0.0) {
+            return Err(StockError::CommandError(format!(
+                "Quantity must be positive: {quantity}"
+            )));
+        }
+        if !(cost_basis.is_finite() && cost_basis >= 0.0) {
+            return Err(StockError::CommandError(format!(
+                "Cost basis must not be negative: {cost_basis}"
+            )));
+        }
+
+        let symbol = symbol.to_uppercase();
+        let position = {
+            let mut portfolios = self.write();
+            let positions = portfolios.entry(user_id.to_string()).or_default();
+            if let Some(position) = positions
+                .iter_mut()
+                .find(|position| position.symbol == symbol)
+            {
+                let total = position.quantity + quantity;
+                position.cost_basis = (position.cost() + quantity * cost_basis) / total;
+                position.quantity = total;
+                position.clone()
+            } else {
+                let position = Position {
+                    symbol,
+                    quantity,
+                    cost_basis,
+                    opened_at: Utc::now(),
+                };
+                positions.push(position.clone());
+                positions.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+                position
+            }
+        };
+        self.save()?;
+        Ok(position)
+    }
+
+    /// Sell `quantity` shares of `symbol` for `user_id`, or the whole
+    /// position when `None` or more than is held
+    ///
+    /// Returns the shares left, or `None` if `symbol` was not held.
+    pub fn remove(
+        &self,
+        user_id: &str,
+        symbol: &str,
+        quantity: Option<f64>,
+    ) -> Result<Option<f64>> {
+        if let Some(quantity) = quantity
+            && !(quantity.is_finite() && quantity > 0.0)
+        {
+            return Err(StockError::CommandError(format!(
+                "Quantity must be positive: {quantity}"
+            )));
+        }
+        let symbol = symbol.to_uppercase();
+        let remaining = {
+            let mut portfolios = self.write();
+            let Some(positions) = portfolios.get_mut(user_id) else {
+                return Ok(None);
+            };
+            let Some(index) = positions
+                .iter()
+                .position(|position| position.symbol == symbol)
+            else {
+                return Ok(None);
+            };
+            let position = &mut positions[index];
+            match quantity {
+                Some(quantity) if quantity < position.quantity => {
+                    position.quantity -= quantity;
+                    position.quantity
+                }
+                _ => {
+                    positions.remove(index);
+                    if positions.is_empty() {
+                        portfolios.remove(user_id);
+                    }
+                    0.0
+                }
+            }
+        };
+        self.save()?;
+        Ok(Some(remaining))
+    }
+
+    /// Positions of `user_id`, sorted by symbol
+    pub fn positions(&self, user_id: &str) -> Vec<Position> {
+        self.read().get(user_id).cloned().unwrap_or_default()
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, BTreeMap<String, Vec<Position>>> {
+        self.portfolios
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, BTreeMap<String, Vec<Position>>> {
+        self.portfolios
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let json = serde_json::to_string_pretty(&*self.read())?;
+        storage::write_store(path, &json, self.cipher.as_ref())
+    }
+}
+
+/// Market data a holding is valued with
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarketData {
+    /// Latest close
+    pub price: f64,
+    /// Last close of the previous year, or the first open of this one for
+    /// stocks listed since
+    pub year_start_price: Option<f64>,
+    /// Beta of daily returns against [`BENCHMARK`]
+    pub beta: Option<f64>,
+    pub sector: Option<String>,
+}
+
+impl MarketData {
+    /// Data from daily bars of the holding and the benchmark, oldest first;
+    /// `None` without bars
+    pub fn from_history(
+        history: &[Quote],
+        benchmark: &[Quote],
+        year: i32,
+        sector: Option<String>,
+    ) -> Option<Self> {
+        Some(Self {
+            price: history.last()?.close,
+            year_start_price: year_start_price(history, year),
+            beta: beta(history, benchmark),
+            sector,
+        })
+    }
+}
+
+/// Price a holding started `year` at, from daily bars oldest first
+fn year_start_price(history: &[Quote], year: i32) -> Option<f64> {
+    history
+        .iter()
+        .rev()
+        .find(|quote| quote.timestamp.year() < year)
+        .map(|quote| quote.close)
+        .or_else(|| {
+            history
+                .iter()
+                .find(|quote| quote.timestamp.year() == year)
+                .map(|quote| quote.open)
+        })
+        .filter(|price| *price > 0.0)
+}
+
+/// Beta of `asset` against `market`: covariance of their daily returns over
+/// the market's variance, on the days both traded
+pub fn beta(asset: &[Quote], market: &[Quote]) -> Option<f64> {
+    let market_closes: HashMap<NaiveDate, f64> = market
+        .iter()
+        .map(|quote| (quote.timestamp.date_naive(), quote.close))
+        .collect();
+    let closes: Vec<(f64, f64)> = asset
+        .iter()
+        .filter_map(|quote| {
+            let market_close = market_closes.get(&quote.timestamp.date_naive())?;
+            Some((quote.close, *market_close))
+        })
+        .collect();
+    let returns: Vec<(f64, f64)> = closes
+        .windows(2)
+        .filter(|pair| pair[0].0 > 0.0 && pair[0].1 > 0.0)
+        .map(|pair| (pair[1].0 / pair[0].0 - 1.0, pair[1].1 / pair[0].1 - 1.0))
+        .collect();
+    if returns.len() < MIN_BETA_RETURNS {
+        return None;
+    }
+
+    let n = returns.len() as f64;
+    let asset_mean = returns.iter().map(|(asset, _)| asset).sum::<f64>() / n;
+    let market_mean = returns.iter().map(|(_, market)| market).sum::<f64>() / n;
+    let covariance = returns
+        .iter()
+        .map(|(asset, market)| (asset - asset_mean) * (market - market_mean))
+        .sum::<f64>();
+    let variance = returns
+        .iter()
+        .map(|(_, market)| (market - market_mean).powi(2))
+        .sum::<f64>();
+    (variance > 0.0).then(|| covariance / variance)
+}
+
+/// A position valued at the latest close
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HoldingReport {
+    pub symbol: String,
+    pub quantity: f64,
+    pub cost_basis: f64,
+    pub price: f64,
+    pub market_value: f64,
+    /// Share of the portfolio's market value
+    pub weight_pct: f64,
+    pub unrealized_pnl: f64,
+    pub unrealized_pnl_pct: f64,
+    pub ytd_return_pct: Option<f64>,
+    pub beta: Option<f64>,
+    pub sector: String,
+}
+
+/// Market value held in one sector
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SectorExposure {
+    pub sector: String,
+    pub market_value: f64,
+    pub weight_pct: f64,
+}
+
+/// A user's portfolio valued at the latest close
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PortfolioReport {
+    /// Priced holdings, largest first
+    pub holdings: Vec<HoldingReport>,
+    pub market_value: f64,
+    /// Cost of the priced holdings
+    pub cost: f64,
+    pub unrealized_pnl: f64,
+    pub unrealized_pnl_pct: f64,
+    /// Return since the start of the year, as if the current holdings had
+    /// been held all year
+    pub ytd_return_pct: Option<f64>,
+    /// Value-weighted beta of the holdings with one
+    pub beta: Option<f64>,
+    /// Sectors, largest first
+    pub sectors: Vec<SectorExposure>,
+    /// Symbols that could not be priced and are left out
+    pub unpriced: Vec<String>,
+    pub as_of: DateTime<Utc>,
+}
+
+impl PortfolioReport {
+    /// Value `positions` with `data` by symbol
+    pub fn new(
+        positions: &[Position],
+        data: &HashMap<String, MarketData>,
+        as_of: DateTime<Utc>,
+    ) -> Self {
+        let mut unpriced = Vec::new();
+        let priced: Vec<(&Position, &MarketData)> = positions
+            .iter()
+            .filter_map(|position| {
+                let data = data.get(&position.symbol);
+                if data.is_none() {
+                    unpriced.push(position.symbol.clone());
+                }
+                data.map(|data| (position, data))
+            })
+            .collect();
+
+        let market_value: f64 = priced
+            .iter()
+            .map(|(position, data)| position.quantity * data.price)
+            .sum();
+        let cost: f64 = priced.iter().map(|(position, _)| position.cost()).sum();
+        let share = |value: f64| {
+            if market_value > 0.0 {
+                value / market_value * 100.0
+            } else {
+                0.0
+            }
+        };
+
+        let mut holdings: Vec<HoldingReport> = priced
+            .iter()
+            .map(|(position, data)| {
+                let value = position.quantity * data.price;
+                let pnl = value - position.cost();
+                HoldingReport {
+                    symbol: position.symbol.clone(),
+                    quantity: position.quantity,
+                    cost_basis: position.cost_basis,
+                    price: data.price,
+                    market_value: value,
+                    weight_pct: share(value),
+                    unrealized_pnl: pnl,
+                    unrealized_pnl_pct: percent_of(pnl, position.cost()),
+                    ytd_return_pct: data
+                        .year_start_price
+                        .map(|start| (data.price / start - 1.0) * 100.0),
+                    beta: data.beta,
+                    sector: data
+                        .sector
+                        .clone()
+                        .unwrap_or_else(|| UNKNOWN_SECTOR.to_string()),
+                }
+            })
+            .collect();
+        holdings.sort_by(|a, b| b.market_value.total_cmp(&a.market_value));
+
+        let mut by_sector: BTreeMap<&str, f64> = BTreeMap::new();
+        for holding in &holdings {
+            *by_sector.entry(&holding.sector).or_default() += holding.market_value;
+        }
+        let mut sectors: Vec<SectorExposure> = by_sector
+            .into_iter()
+            .map(|(sector, value)| SectorExposure {
+                sector: sector.to_string(),
+                market_value: value,
+                weight_pct: share(value),
+            })
+            .collect();
+        sectors.sort_by(|a, b| b.market_value.total_cmp(&a.market_value));
+
+        let (ytd_start, ytd_now) = priced
+            .iter()
+            .filter_map(|(position, data)| {
+                let start = data.year_start_price?;
+                Some((position.quantity * start, position.quantity * data.price))
+            })
+            .fold((0.0, 0.0), |(start, now), (s, n)| (start + s, now + n));
+        let (beta_weight, weighted_beta) = holdings
+            .iter()
+            .filter_map(|holding| {
+                Some((holding.market_value, holding.beta? * holding.market_value))
+            })
+            .fold((0.0, 0.0), |(weight, sum), (w, b)| (weight + w, sum + b));
+
+        Self {
+            holdings,
+            market_value,
+            cost,
+            unrealized_pnl: market_value - cost,
+            unrealized_pnl_pct: percent_of(market_value - cost, cost),
+            ytd_return_pct: (ytd_start > 0.0).then(|| (ytd_now / ytd_start - 1.0) * 100.0),
+            beta: (beta_weight > 0.0).then(|| weighted_beta / beta_weight),
+            sectors,
+            unpriced,
+            as_of,
+        }
+    }
+
+    /// Share of the market value held in `sector` (e.g. "Technology")
+    pub fn exposure(&self, sector: &str) -> f64 {
+        self.sectors
+            .iter()
+            .find(|exposure| exposure.sector.eq_ignore_ascii_case(sector))
+            .map_or(0.0, |exposure| exposure.weight_pct)
+    }
+}
+
+fn percent_of(value: f64, base: f64) -> f64 {
+    if base > 0.0 {
+        value / base * 100.0
+    } else {
+        0.0
+    }
+}
+
+impl fmt::Display for PortfolioReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "💼 Portfolio {:.2} | Cost {:.2} | P&L {:+.2} ({:+.2}%)",
+            self.market_value, self.cost, self.unrealized_pnl, self.unrealized_pnl_pct
+        )?;
+        let ytd = self
+            .ytd_return_pct
+            .map_or_else(|| "n/a".to_string(), |ytd| format!("{ytd:+.2}%"));
+        let beta = self
+            .beta
+            .map_or_else(|| "n/a".to_string(), |beta| format!("{beta:.2}"));
+        write!(f, "YTD {ytd} | Beta {beta} vs {BENCHMARK}")?;
+        for holding in &self.holdings {
+            write!(
+                f,
+                "\n{} {} @ {:.2} → {:.2} = {:.2} ({:+.2}%, {:.1}% of portfolio)",
+                holding.symbol,
+                holding.quantity,
+                holding.cost_basis,
+                holding.price,
+                holding.market_value,
+                holding.unrealized_pnl_pct,
+                holding.weight_pct
+            )?;
+        }
+        if !self.sectors.is_empty() {
+            let sectors: Vec<String> = self
+                .sectors
+                .iter()
+                .map(|exposure| format!("{} {:.1}%", exposure.sector, exposure.weight_pct))
+                .collect();
+            write!(f, "\nSectors: {}", sectors.join(", "))?;
+        }
+        if !self.unpriced.is_empty() {
+            write!(f, "\n⚠️ No quote for {}", self.unpriced.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Value `positions` at the latest close
+///
+/// Symbols whose quotes fail are listed in
+/// [`PortfolioReport::unpriced`]; a missing profile only leaves the sector
+/// unknown.
+pub async fn valuate(
+    yahoo: &YahooFinanceClient,
+    positions: &[Position],
+) -> Result<PortfolioReport> {
+    let benchmark = yahoo
+        .get_historical_range(BENCHMARK, "1y")
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!("No {} history for portfolio beta: {}", BENCHMARK, e);
+            Vec::new()
+        });
+    let year = Utc::now().year();
+
+    let fetches: Vec<_> = positions
+        .iter()
+        .map(|position| {
+            let benchmark = &benchmark;
+            async move {
+                let data = fetch_market_data(yahoo, &position.symbol, benchmark, year).await;
+                (position.symbol.clone(), data)
+            }
+        })
+        .collect();
+    let results: Vec<(String, Result<MarketData>)> = stream::iter(fetches)
+        .buffer_unordered(MAX_CONCURRENT_FETCHES)
+        .collect()
+        .await;
+
+    let mut data = HashMap::new();
+    for (symbol, result) in results {
+        match result {
+            Ok(market_data) => {
+                data.insert(symbol, market_data);
+            }
+            Err(e) => tracing::warn!("Failed to price {}: {}", symbol, e),
+        }
+    }
+    Ok(PortfolioReport::new(positions, &data, Utc::now()))
+}
+
+async fn fetch_market_data(
+    yahoo: &YahooFinanceClient,
+    symbol: &str,
+    benchmark: &[Quote],
+    year: i32,
+) -> Result<MarketData> {
+    let (history, profile) = tokio::join!(
+        yahoo.get_historical_range(symbol, "1y"),
+        yahoo.get_asset_profile(symbol)
+    );
+    let sector = profile.ok().and_then(|profile| profile.sector);
+    MarketData::from_history(&history?, benchmark, year, sector)
+        .ok_or_else(|| StockError::data_unavailable(symbol, "No daily bars in the last year"))
+}
+
+/// Reply to a portfolio command (`/portfolio add|remove|show` or a question)
+/// from `user_id`, for bots with portfolio tracking
+pub async fn command_reply(
+    portfolio: Option<&PortfolioAgent>,
+    user_id: &str,
+    command: &Command,
+    context: &mut Context,
+) -> Result<String> {
+    let Some(portfolio) = portfolio else {
+        return Ok("💼 Portfolio tracking is not enabled on this bot".to_string());
+    };
+    match command {
+        Command::PortfolioAsk { question } => {
+            Ok(portfolio.analyze(user_id, question, context).await?)
+        }
+        _ => {
+            store_reply(
+                portfolio.store(),
+                &YahooFinanceClient::new(),
+                user_id,
+                command,
+            )
+            .await
+        }
+    }
+}
+
+/// Reply to `/portfolio add|remove|show`, pricing with `yahoo`
+async fn store_reply(
+    store: &PortfolioStore,
+    yahoo: &YahooFinanceClient,
+    user_id: &str,
+    command: &Command,
+) -> Result<String> {
+    match command {
+        Command::PortfolioAdd {
+            symbol,
+            quantity,
+            cost_basis,
+        } => {
+            let position = store.add(user_id, symbol, *quantity, *cost_basis)?;
+            Ok(format!("✅ Portfolio: {position}"))
+        }
+        Command::PortfolioRemove { symbol, quantity } => {
+            match store.remove(user_id, symbol, *quantity)? {
+                None => Ok(format!("❌ {symbol} is not in your portfolio")),
+                Some(remaining) if remaining > 0.0 => Ok(format!(
+                    "✅ Sold {} {symbol}, {remaining} left",
+                    quantity.unwrap_or_default()
+                )),
+                Some(_) => Ok(format!("✅ Removed {symbol} from your portfolio")),
+            }
+        }
+        Command::Portfolio => {
+            let positions = store.positions(user_id);
+            if positions.is_empty() {
+                return Ok(
+                    "💼 Portfolio is empty. Use /portfolio add AAPL 10 150 to record 10 shares \
+                     bought at 150."
+                        .to_string(),
+                );
+            }
+            Ok(valuate(yahoo, &positions).await?.to_string())
+        }
+        _ => Err(StockError::CommandError(format!(
+            "Not a portfolio command: /{}",
+            command.name()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::testing::MockApi;
+
+    fn quote(day: &str, open: f64, close: f64) -> Quote {
+        Quote {
+            symbol: "TEST".to_string(),
+            timestamp: format!("{day}T14:30:00Z").parse().unwrap(),
+            open,
+            high: close.max(open),
+            low: close.min(open),
+            close,
+            volume: 1_000,
+            adjclose: close,
+        }
+    }
+
+    fn data(price: f64, year_start: f64, beta: f64, sector: &str) -> MarketData {
+        MarketData {
+            price,
+            year_start_price: Some(year_start),
+            beta: Some(beta),
+            sector: Some(sector.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_store_positions() {
+        let path = std::env::temp_dir().join(format!("portfolio-{}.json", uuid::Uuid::new_v4()));
+        let store = PortfolioStore::open(&path).unwrap();
+
+        store.add("42", "aapl", 10.0, 150.0).unwrap();
+        let position = store.add("42", "AAPL", 10.0, 170.0).unwrap();
+        assert_eq!(position.quantity, 20.0);
+        assert!((position.cost_basis - 160.0).abs() < 1e-9);
+        store.add("42", "MSFT", 5.0, 400.0).unwrap();
+        store.add("7", "NVDA", 1.0, 900.0).unwrap();
+        assert!(store.add("42", "TSLA", 0.0, 200.0).is_err());
+        assert!(store.add("42", "TSLA", 1.0, -1.0).is_err());
+
+        assert_eq!(store.remove("42", "aapl", Some(5.0)).unwrap(), Some(15.0));
+        assert_eq!(store.remove("42", "TSLA", None).unwrap(), None);
+        assert_eq!(store.remove("7", "NVDA", Some(3.0)).unwrap(), Some(0.0));
+        assert!(store.positions("7").is_empty());
+
+        let reopened = PortfolioStore::open(&path).unwrap();
+        let symbols: Vec<String> = reopened
+            .positions("42")
+            .into_iter()
+            .map(|position| position.to_string())
+            .collect();
+        assert_eq!(symbols, vec!["AAPL 15 @ 160.00", "MSFT 5 @ 400.00"]);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_beta_and_year_start() {
+        // The asset moves twice as much as the market every day
+        let mut market = Vec::new();
+        let mut asset = Vec::new();
+        let (mut market_close, mut asset_close) = (100.0, 50.0);
+        for day in 1..=28 {
+            let change = if day % 3 == 0 { -0.01 } else { 0.012 };
+            market_close *= 1.0 + change;
+            asset_close *= 1.0 + 2.0 * change;
+            let date = format!("2024-02-{day:02}");
+            market.push(quote(&date, market_close, market_close));
+            asset.push(quote(&date, asset_close, asset_close));
+        }
+        assert!((beta(&asset, &market).unwrap() - 2.0).abs() < 1e-9);
+        // Too few common days
+        assert_eq!(beta(&asset[..10], &market), None);
+
+        let history = vec![
+            quote("2023-12-28", 190.0, 193.0),
+            quote("2023-12-29", 193.0, 192.5),
+            quote("2024-01-02", 187.0, 185.6),
+        ];
+        assert_eq!(year_start_price(&history, 2024), Some(192.5));
+        // Listed this year: its first open
+        assert_eq!(year_start_price(&history[2..], 2024), Some(187.0));
+        assert_eq!(year_start_price(&history, 2025), Some(185.6));
+    }
+
+    #[test]
+    fn test_report() {
+        let store = PortfolioStore::in_memory();
+        store.add("42", "AAPL", 10.0, 150.0).unwrap();
+        store.add("42", "XOM", 20.0, 100.0).unwrap();
+        store.add("42", "NOPE", 1.0, 10.0).unwrap();
+        let data = HashMap::from([
+            ("AAPL".to_string(), data(200.0, 160.0, 1.2, "Technology")),
+            ("XOM".to_string(), data(100.0, 125.0, 0.6, "Energy")),
+        ]);
+
+        let report = PortfolioReport::new(&store.positions("42"), &data, Utc::now());
+        assert_eq!(report.unpriced, vec!["NOPE"]);
+        assert!((report.market_value - 4000.0).abs() < 1e-9);
+        assert!((report.unrealized_pnl - 500.0).abs() < 1e-9);
+        assert!((report.exposure("technology") - 50.0).abs() < 1e-9);
+        assert_eq!(report.exposure("Healthcare"), 0.0);
+        // 4000 now against 1600 + 2500 at the start of the year
+        assert!((report.ytd_return_pct.unwrap() - (4000.0 / 4100.0 - 1.0) * 100.0).abs() < 1e-9);
+        assert!((report.beta.unwrap() - 0.9).abs() < 1e-9);
+        assert_eq!(report.holdings[0].symbol, "AAPL");
+        assert!((report.holdings[0].unrealized_pnl_pct - 100.0 / 3.0).abs() < 1e-9);
+
+        let text = report.to_string();
+        assert!(
+            text.starts_with("💼 Portfolio 4000.00 | Cost 3500.00 | P&L +500.00 (+14.29%)"),
+            "{text}"
+        );
+        assert!(text.contains("Beta 0.90 vs SPY"), "{text}");
+        assert!(
+            text.contains("Sectors: Energy 50.0%, Technology 50.0%"),
+            "{text}"
+        );
+        assert!(text.contains("No quote for NOPE"), "{text}");
+    }
+
+    #[tokio::test]
+    async fn test_portfolio_commands() {
+        // No routes: every quote fails, so holdings come back unpriced
+        let api = MockApi::start().await;
+        let yahoo = api.yahoo();
+        let store = PortfolioStore::in_memory();
+        let reply = async |command: &str| {
+            store_reply(&store, &yahoo, "42", &Command::parse(command).unwrap()).await
+        };
+
+        assert!(
+            reply("/portfolio")
+                .await
+                .unwrap()
+                .contains("Portfolio is empty")
+        );
+        assert_eq!(
+            reply("/portfolio add aapl 10 150").await.unwrap(),
+            "✅ Portfolio: AAPL 10 @ 150.00"
+        );
+        assert_eq!(
+            reply("/portfolio remove AAPL 4").await.unwrap(),
+            "✅ Sold 4 AAPL, 6 left"
+        );
+        assert!(
+            reply("/portfolio show")
+                .await
+                .unwrap()
+                .contains("No quote for AAPL")
+        );
+        assert!(
+            reply("/portfolio remove AAPL")
+                .await
+                .unwrap()
+                .contains("Removed AAPL")
+        );
+        assert!(
+            reply("/portfolio remove AAPL")
+                .await
+                .unwrap()
+                .contains("not in your portfolio")
+        );
+        assert!(reply("/alerts").await.is_err());
+
+        let disabled = command_reply(
+            None,
+            "42",
+            &Command::parse("/portfolio").unwrap(),
+            &mut Context::new(),
+        )
+        .await;
+        assert!(disabled.unwrap().contains("not enabled"));
+    }
+}
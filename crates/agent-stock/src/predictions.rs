@@ -0,0 +1,693 @@
+//! Prediction tracking and hindsight scoring
+//!
+//! When an analysis states a directional view ("bullish near-term", "短期看跌"),
+//! the view is recorded with its timestamp, horizon and the agent/model that
+//! produced it. Once the horizon has elapsed, the prediction is scored against
+//! realized closing prices, and accuracy is aggregated per agent and model in a
+//! [`Scoreboard`].
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::api::YahooFinanceClient;
+//! use agent_stock::predictions::PredictionTracker;
+//!
+//! let tracker = PredictionTracker::open("predictions.json")?;
+//! tracker.record_analysis("AAPL", &analysis, "technical-analyzer", "gpt-4o")?;
+//!
+//! // Later: score whatever has come due and show the results
+//! tracker.score_due(&YahooFinanceClient::new()).await?;
+//! println!("{}", tracker.scoreboard());
+//! ```
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, PoisonError, RwLock};
+
+use crate::api::YahooFinanceClient;
+use crate::error::{Result, StockError};
+use crate::storage::{self, StoreCipher};
+
+/// Horizon used when a view does not state one
+pub const DEFAULT_HORIZON_DAYS: u32 = 30;
+
+/// Phrases stating a bullish view
+const BULLISH_PHRASES: &[&str] = &[
+    "bullish",
+    "uptrend",
+    "upward trend",
+    "outperform",
+    "看涨",
+    "看多",
+    "偏多",
+    "上涨趋势",
+];
+
+/// Phrases stating a bearish view
+const BEARISH_PHRASES: &[&str] = &[
+    "bearish",
+    "downtrend",
+    "downward trend",
+    "underperform",
+    "看跌",
+    "看空",
+    "偏空",
+    "下跌趋势",
+];
+
+/// Words that negate a directional phrase when they directly precede it
+const NEGATIONS: &[&str] = &["not ", "no longer ", "isn't ", "不", "并非", "未"];
+
+/// Horizon phrases and their length in days
+const HORIZONS: &[(&str, u32)] = &[
+    ("this week", 7),
+    ("next few days", 7),
+    ("本周", 7),
+    ("未来几天", 7),
+    ("near-term", 30),
+    ("near term", 30),
+    ("short-term", 30),
+    ("short term", 30),
+    ("短期", 30),
+    ("近期", 30),
+    ("medium-term", 90),
+    ("medium term", 90),
+    ("mid-term", 90),
+    ("中期", 90),
+    ("long-term", 365),
+    ("long term", 365),
+    ("长期", 365),
+];
+
+/// Direction of a predicted price move
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    /// Price expected to rise
+    Bullish,
+    /// Price expected to fall
+    Bearish,
+}
+
+impl Direction {
+    /// Lowercase name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Bullish => "bullish",
+            Self::Bearish => "bearish",
+        }
+    }
+
+    /// Whether a realized return agrees with this direction
+    pub fn is_correct(&self, return_pct: f64) -> bool {
+        match self {
+            Self::Bullish => return_pct > 0.0,
+            Self::Bearish => return_pct < 0.0,
+        }
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A directional view stated in an analysis
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirectionalView {
+    /// Predicted direction
+    pub direction: Direction,
+    /// Horizon in days
+    pub horizon_days: u32,
+    /// Sentence the view was taken from
+    pub statement: String,
+}
+
+/// Extract the directional views stated in an analysis
+///
+/// Each sentence naming exactly one direction counts as a view; sentences
+/// with both or with a negated phrase ("not bullish") are ignored. Views are
+/// grouped by horizon, and a horizon with conflicting views is dropped.
+pub fn extract_views(text: &str) -> Vec<DirectionalView> {
+    let mut by_horizon: BTreeMap<u32, Option<DirectionalView>> = BTreeMap::new();
+
+    for sentence in text.split(['.', '!', '?', '\n', '。', '！', '？', '；', ';']) {
+        let lower = sentence.to_lowercase();
+        let bullish = BULLISH_PHRASES
+            .iter()
+            .any(|p| contains_unnegated(&lower, p));
+        let bearish = BEARISH_PHRASES
+            .iter()
+            .any(|p| contains_unnegated(&lower, p));
+        let direction = match (bullish, bearish) {
+            (true, false) => Direction::Bullish,
+            (false, true) => Direction::Bearish,
+            _ => continue,
+        };
+        let horizon_days = HORIZONS
+            .iter()
+            .find(|(phrase, _)| lower.contains(phrase))
+            .map_or(DEFAULT_HORIZON_DAYS, |(_, days)| *days);
+
+        by_horizon
+            .entry(horizon_days)
+            .and_modify(|existing| {
+                if existing.as_ref().is_some_and(|v| v.direction != direction) {
+                    *existing = None;
+                }
+            })
+            .or_insert_with(|| {
+                Some(DirectionalView {
+                    direction,
+                    horizon_days,
+                    statement: sentence
+                        .trim()
+                        .trim_start_matches(['-', '*', ' '])
+                        .to_string(),
+                })
+            });
+    }
+
+    by_horizon.into_values().flatten().collect()
+}
+
+fn contains_unnegated(sentence: &str, phrase: &str) -> bool {
+    sentence.match_indices(phrase).any(|(start, _)| {
+        let before = &sentence[..start];
+        !NEGATIONS.iter().any(|negation| before.ends_with(negation))
+    })
+}
+
+/// How a prediction played out
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Outcome {
+    /// When the prediction was scored
+    pub scored_at: DateTime<Utc>,
+    /// Close on or before the prediction date
+    pub entry_price: f64,
+    /// Close on or before the end of the horizon
+    pub exit_price: f64,
+    /// Realized return in percent
+    pub return_pct: f64,
+    /// Whether the realized move agreed with the prediction
+    pub correct: bool,
+}
+
+/// A recorded directional prediction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Prediction {
+    /// Unique identifier
+    pub id: String,
+    /// Stock symbol
+    pub symbol: String,
+    /// Predicted direction
+    pub direction: Direction,
+    /// Horizon in days
+    pub horizon_days: u32,
+    /// When the prediction was made
+    pub made_at: DateTime<Utc>,
+    /// Agent that made the prediction
+    pub agent: String,
+    /// Model that made the prediction
+    pub model: String,
+    /// Sentence the prediction was taken from
+    pub statement: String,
+    /// Result, once scored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<Outcome>,
+}
+
+impl Prediction {
+    /// When the horizon ends
+    pub fn due_at(&self) -> DateTime<Utc> {
+        self.made_at + Duration::days(i64::from(self.horizon_days))
+    }
+
+    /// Whether the horizon has ended and the prediction is not yet scored
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.outcome.is_none() && self.due_at() <= now
+    }
+}
+
+/// Source of realized closing prices
+#[async_trait]
+pub trait PriceHistory: Send + Sync {
+    /// Last close of `symbol` on or before `at`
+    async fn close_at(&self, symbol: &str, at: DateTime<Utc>) -> Result<f64>;
+}
+
+#[async_trait]
+impl PriceHistory for YahooFinanceClient {
+    async fn close_at(&self, symbol: &str, at: DateTime<Utc>) -> Result<f64> {
+        // Look back far enough to cover weekends and holidays
+        let quotes = self
+            .get_historical_quotes(symbol, at - Duration::days(7), at + Duration::days(1))
+            .await?;
+        quotes
+            .iter()
+            .filter(|q| q.timestamp <= at)
+            .max_by_key(|q| q.timestamp)
+            .map(|q| q.close)
+            .ok_or_else(|| StockError::DataUnavailable {
+                symbol: symbol.to_string(),
+                reason: format!("no close on or before {}", at.format("%Y-%m-%d")),
+            })
+    }
+}
+
+/// Records predictions and scores them once their horizon has passed
+///
+/// Predictions are kept in memory and, when opened with a path, persisted as
+/// JSON after every change (encrypted when opened with a cipher).
+pub struct PredictionTracker {
+    predictions: RwLock<Vec<Prediction>>,
+    path: Option<PathBuf>,
+    cipher: Option<StoreCipher>,
+}
+
+impl Default for PredictionTracker {
+    fn default() -> Self {
+        Self::in_memory()
+    }
+}
+
+impl PredictionTracker {
+    /// Create a tracker that is not persisted
+    pub fn in_memory() -> Self {
+        Self {
+            predictions: RwLock::new(Vec::new()),
+            path: None,
+            cipher: None,
+        }
+    }
+
+    /// Open a tracker persisted at `path`, loading existing predictions
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        Self::open_with_cipher(path, None)
+    }
+
+    /// Open a tracker persisted at `path`, encrypted with `cipher` if given
+    ///
+    /// An existing plaintext file is encrypted when opened with a cipher.
+    pub fn open_with_cipher(path: impl Into<PathBuf>, cipher: Option<StoreCipher>) -> Result<Self> {
+        let path = path.into();
+        let predictions = match storage::read_store(&path, cipher.as_ref())? {
+            Some(json) => serde_json::from_str(&json)?,
+            None => Vec::new(),
+        };
+
+        Ok(Self {
+            predictions: RwLock::new(predictions),
+            path: Some(path),
+            cipher,
+        })
+    }
+
+    /// File the tracker is persisted to, if any
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Record the directional views stated in an analysis of `symbol`
+    ///
+    /// Returns the recorded predictions (empty if the analysis states no view).
+    pub fn record_analysis(
+        &self,
+        symbol: &str,
+        analysis: &str,
+        agent: &str,
+        model: &str,
+    ) -> Result<Vec<Prediction>> {
+        self.record_analysis_at(symbol, analysis, agent, model, Utc::now())
+    }
+
+    /// Record the views of an analysis made as of `made_at`
+    ///
+    /// Used for [point-in-time analyses](crate::as_of) of past dates, whose
+    /// predictions can be scored as soon as their horizon is in the past.
+    pub fn record_analysis_at(
+        &self,
+        symbol: &str,
+        analysis: &str,
+        agent: &str,
+        model: &str,
+        made_at: DateTime<Utc>,
+    ) -> Result<Vec<Prediction>> {
+        let recorded: Vec<Prediction> = extract_views(analysis)
+            .into_iter()
+            .map(|view| Prediction {
+                id: uuid::Uuid::new_v4().to_string(),
+                symbol: symbol.to_uppercase(),
+                direction: view.direction,
+                horizon_days: view.horizon_days,
+                made_at,
+                agent: agent.to_string(),
+                model: model.to_string(),
+                statement: view.statement,
+                outcome: None,
+            })
+            .collect();
+
+        if !recorded.is_empty() {
+            self.write().extend(recorded.iter().cloned());
+            self.save()?;
+        }
+        Ok(recorded)
+    }
+
+    /// Record a prediction
+    pub fn record(&self, prediction: Prediction) -> Result<()> {
+        self.write().push(prediction);
+        self.save()
+    }
+
+    /// All recorded predictions
+    pub fn predictions(&self) -> Vec<Prediction> {
+        self.read().clone()
+    }
+
+    /// Predictions whose horizon has ended but that are not yet scored
+    pub fn due(&self, now: DateTime<Utc>) -> Vec<Prediction> {
+        self.read()
+            .iter()
+            .filter(|p| p.is_due(now))
+            .cloned()
+            .collect()
+    }
+
+    /// Score every prediction that has come due
+    ///
+    /// Predictions whose prices cannot be fetched stay pending and are retried
+    /// on the next call. Returns the number of predictions scored.
+    pub async fn score_due(&self, prices: &dyn PriceHistory) -> Result<usize> {
+        let now = Utc::now();
+        let mut outcomes = Vec::new();
+
+        for prediction in self.due(now) {
+            let prices = async {
+                let entry = prices
+                    .close_at(&prediction.symbol, prediction.made_at)
+                    .await?;
+                let exit = prices
+                    .close_at(&prediction.symbol, prediction.due_at())
+                    .await?;
+                Ok::<_, StockError>((entry, exit))
+            };
+            match prices.await {
+                Ok((entry_price, exit_price)) if entry_price > 0.0 => {
+                    let return_pct = (exit_price - entry_price) / entry_price * 100.0;
+                    outcomes.push((
+                        prediction.id,
+                        Outcome {
+                            scored_at: now,
+                            entry_price,
+                            exit_price,
+                            return_pct,
+                            correct: prediction.direction.is_correct(return_pct),
+                        },
+                    ));
+                }
+                Ok(_) => {
+                    tracing::warn!("Invalid entry price for prediction {}", prediction.id);
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "Failed to score prediction {} ({}): {}",
+                        prediction.id,
+                        prediction.symbol,
+                        e
+                    );
+                }
+            }
+        }
+
+        if outcomes.is_empty() {
+            return Ok(0);
+        }
+
+        let scored = outcomes.len();
+        {
+            let mut predictions = self.write();
+            for (id, outcome) in outcomes {
+                if let Some(prediction) = predictions.iter_mut().find(|p| p.id == id) {
+                    prediction.outcome = Some(outcome);
+                }
+            }
+        }
+        self.save()?;
+        Ok(scored)
+    }
+
+    /// Periodically score due predictions in the background
+    pub fn spawn_scoring(
+        self: Arc<Self>,
+        prices: Arc<dyn PriceHistory>,
+        interval: std::time::Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match self.score_due(prices.as_ref()).await {
+                    Ok(0) => {}
+                    Ok(scored) => tracing::info!("Scored {} predictions", scored),
+                    Err(e) => tracing::warn!("Prediction scoring failed: {}", e),
+                }
+            }
+        })
+    }
+
+    /// Accuracy per agent and model
+    pub fn scoreboard(&self) -> Scoreboard {
+        let mut entries: BTreeMap<(String, String), ScoreboardEntry> = BTreeMap::new();
+        for prediction in self.read().iter() {
+            let entry = entries
+                .entry((prediction.agent.clone(), prediction.model.clone()))
+                .or_insert_with(|| ScoreboardEntry {
+                    agent: prediction.agent.clone(),
+                    model: prediction.model.clone(),
+                    ..ScoreboardEntry::default()
+                });
+            entry.total += 1;
+            if let Some(outcome) = &prediction.outcome {
+                entry.scored += 1;
+                if outcome.correct {
+                    entry.correct += 1;
+                }
+            }
+        }
+        Scoreboard {
+            entries: entries.into_values().collect(),
+        }
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, Vec<Prediction>> {
+        self.predictions
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, Vec<Prediction>> {
+        self.predictions
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let json = serde_json::to_string_pretty(&*self.read())?;
+        storage::write_store(path, &json, self.cipher.as_ref())
+    }
+}
+
+/// Prediction accuracy of one agent/model pair
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScoreboardEntry {
+    /// Agent name
+    pub agent: String,
+    /// Model name
+    pub model: String,
+    /// Predictions recorded
+    pub total: usize,
+    /// Predictions scored so far
+    pub scored: usize,
+    /// Scored predictions that were correct
+    pub correct: usize,
+}
+
+impl ScoreboardEntry {
+    /// Fraction of scored predictions that were correct
+    pub fn accuracy(&self) -> Option<f64> {
+        (self.scored > 0).then(|| self.correct as f64 / self.scored as f64)
+    }
+
+    /// Predictions still waiting for their horizon to end
+    pub fn pending(&self) -> usize {
+        self.total - self.scored
+    }
+}
+
+/// Prediction accuracy per agent and model
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Scoreboard {
+    /// Entries sorted by agent, then model
+    pub entries: Vec<ScoreboardEntry>,
+}
+
+impl Scoreboard {
+    /// Whether no predictions have been recorded
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl fmt::Display for Scoreboard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.entries.is_empty() {
+            return f.write_str("No predictions recorded yet.");
+        }
+        writeln!(f, "Prediction scoreboard:")?;
+        for entry in &self.entries {
+            let accuracy = entry
+                .accuracy()
+                .map_or_else(|| "-".to_string(), |a| format!("{:.0}%", a * 100.0));
+            writeln!(
+                f,
+                "  {} ({}): {accuracy} correct ({}/{} scored, {} pending)",
+                entry.agent,
+                entry.model,
+                entry.correct,
+                entry.scored,
+                entry.pending()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Prices rising 1.0 per day from 100.0 at a fixed epoch
+    struct RisingPrices {
+        epoch: DateTime<Utc>,
+    }
+
+    #[async_trait]
+    impl PriceHistory for RisingPrices {
+        async fn close_at(&self, _symbol: &str, at: DateTime<Utc>) -> Result<f64> {
+            Ok(100.0 + (at - self.epoch).num_days() as f64)
+        }
+    }
+
+    #[test]
+    fn test_extract_views() {
+        let views = extract_views(
+            "RSI is 45. We are bullish near-term on strong momentum.\n\
+             Long-term, the stock looks bearish given valuation.",
+        );
+        assert_eq!(views.len(), 2);
+        assert_eq!(views[0].direction, Direction::Bullish);
+        assert_eq!(views[0].horizon_days, 30);
+        assert_eq!(views[1].direction, Direction::Bearish);
+        assert_eq!(views[1].horizon_days, 365);
+
+        let views = extract_views("综合来看,短期看跌。");
+        assert_eq!(views[0].direction, Direction::Bearish);
+        assert_eq!(views[0].horizon_days, 30);
+    }
+
+    #[test]
+    fn test_extract_views_skips_negated_and_conflicting() {
+        assert!(extract_views("We are not bullish here.").is_empty());
+        assert!(extract_views("Bullish momentum but bearish divergence.").is_empty());
+        assert!(extract_views("Bullish trend. Bearish outlook.").is_empty());
+        assert!(extract_views("Price closed at $150.").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_score_due_and_scoreboard() {
+        let tracker = PredictionTracker::in_memory();
+        let made_at = Utc::now() - Duration::days(40);
+        for (direction, agent) in [
+            (Direction::Bullish, "technical-analyzer"),
+            (Direction::Bearish, "technical-analyzer"),
+            (Direction::Bullish, "news-analyzer"),
+        ] {
+            tracker
+                .record(Prediction {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    symbol: "AAPL".to_string(),
+                    direction,
+                    horizon_days: 30,
+                    made_at,
+                    agent: agent.to_string(),
+                    model: "m".to_string(),
+                    statement: String::new(),
+                    outcome: None,
+                })
+                .unwrap();
+        }
+        tracker
+            .record_analysis("msft", "Bullish long-term.", "news-analyzer", "m")
+            .unwrap();
+
+        let prices = RisingPrices { epoch: made_at };
+        assert_eq!(tracker.score_due(&prices).await.unwrap(), 3);
+        assert_eq!(tracker.score_due(&prices).await.unwrap(), 0);
+
+        let board = tracker.scoreboard();
+        let news = &board.entries[0];
+        assert_eq!(
+            (news.agent.as_str(), news.total, news.scored),
+            ("news-analyzer", 2, 1)
+        );
+        assert_eq!(news.accuracy(), Some(1.0));
+        let technical = &board.entries[1];
+        assert_eq!(technical.accuracy(), Some(0.5));
+        assert!(
+            board
+                .to_string()
+                .contains("technical-analyzer (m): 50% correct")
+        );
+    }
+
+    #[test]
+    fn test_persistence_round_trip() {
+        let path = std::env::temp_dir().join(format!("predictions-{}.json", uuid::Uuid::new_v4()));
+        let tracker = PredictionTracker::open(&path).unwrap();
+        tracker
+            .record_analysis("AAPL", "短期看涨", "stock-analysis", "m")
+            .unwrap();
+
+        let reopened = PredictionTracker::open(&path).unwrap();
+        let predictions = reopened.predictions();
+        assert_eq!(predictions.len(), 1);
+        assert_eq!(predictions[0].direction, Direction::Bullish);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_encrypted_persistence() {
+        let path = std::env::temp_dir().join(format!("predictions-{}.json", uuid::Uuid::new_v4()));
+        let cipher = StoreCipher::from_key(&[3; 32]).unwrap();
+        let tracker = PredictionTracker::open_with_cipher(&path, Some(cipher.clone())).unwrap();
+        tracker
+            .record_analysis("AAPL", "Bearish near-term.", "stock-analysis", "m")
+            .unwrap();
+
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("AAPL"));
+        assert!(PredictionTracker::open(&path).is_err());
+        let reopened = PredictionTracker::open_with_cipher(&path, Some(cipher)).unwrap();
+        assert_eq!(reopened.predictions()[0].symbol, "AAPL");
+        std::fs::remove_file(path).unwrap();
+    }
+}
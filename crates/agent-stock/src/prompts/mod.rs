@@ -44,6 +44,10 @@ pub fn register_prompts(registry: &PromptRegistry) -> Result<()> {
     registry.register(earnings_analyzer()?);
     registry.register(macro_analyzer()?);
     registry.register(data_fetcher()?);
+    registry.register(esg_analyzer()?);
+    registry.register(portfolio_analyzer()?);
+    registry.register(query_builder()?);
+    registry.register(query_explainer()?);
 
     // User message templates - Earnings
     registry.register(analyze_earnings_prompt()?);
@@ -58,6 +62,42 @@ pub fn register_prompts(registry: &PromptRegistry) -> Result<()> {
     registry.register(analyze_geopolitical_risks_prompt()?);
     registry.register(get_market_outlook_prompt()?);
     registry.register(analyze_impact_prompt()?);
+    registry.register(analyze_theme_prompt()?);
+    registry.register(market_wrap_prompt()?);
+    registry.register(macro_alert_prompt()?);
+
+    // User message templates - Fundamental
+    registry.register(compare_over_time_prompt()?);
+
+    // User message templates - News
+    registry.register(news_digest_prompt()?);
+
+    // User message templates - ESG
+    registry.register(analyze_esg_prompt()?);
+
+    // User message templates - Portfolio
+    registry.register(analyze_portfolio_prompt()?);
+
+    // User message templates - Query builder
+    registry.register(build_query_prompt()?);
+    registry.register(explain_query_prompt()?);
+
+    // User message templates - Explanations
+    registry.register(explain_term_prompt()?);
+
+    // User message templates - Analysis depth
+    registry.register(quick_summary_prompt()?);
+    registry.register(deep_analysis_prompt()?);
+
+    // Response style, teaching mode and point-in-time instructions
+    registry.register(response_style_prompt()?);
+    registry.register(teaching_mode_prompt()?);
+    registry.register(as_of_prompt()?);
+
+    // Bilingual replies
+    registry.register(translator()?);
+    registry.register(translate_prompt()?);
+    registry.register(translate_sources_prompt()?);
 
     Ok(())
 }
@@ -80,6 +120,13 @@ mod tests {
         assert!(registry.get("stock.earnings_analyzer").is_some());
         assert!(registry.get("stock.macro_analyzer").is_some());
         assert!(registry.get("stock.data_fetcher").is_some());
+        assert!(registry.get("stock.esg_analyzer").is_some());
+        assert!(registry.get("stock.portfolio_analyzer").is_some());
+        assert!(registry.get("stock.query_builder").is_some());
+        assert!(registry.get("stock.query_explainer").is_some());
+        assert!(registry.get("stock.translator").is_some());
+        assert!(registry.get("stock.user.translate").is_some());
+        assert!(registry.get("stock.user.translate_sources").is_some());
 
         // Verify user prompts are registered
         assert!(registry.get("stock.user.analyze_earnings").is_some());
@@ -89,9 +136,27 @@ mod tests {
         assert!(registry.get("stock.user.analyze_fed_policy").is_some());
         assert!(registry.get("stock.user.analyze_rates").is_some());
         assert!(registry.get("stock.user.analyze_inflation").is_some());
-        assert!(registry.get("stock.user.analyze_geopolitical_risks").is_some());
+        assert!(
+            registry
+                .get("stock.user.analyze_geopolitical_risks")
+                .is_some()
+        );
         assert!(registry.get("stock.user.get_market_outlook").is_some());
         assert!(registry.get("stock.user.analyze_impact").is_some());
+        assert!(registry.get("stock.user.analyze_theme").is_some());
+        assert!(registry.get("stock.user.market_wrap").is_some());
+        assert!(registry.get("stock.user.macro_alert").is_some());
+        assert!(registry.get("stock.user.compare_over_time").is_some());
+        assert!(registry.get("stock.user.news_digest").is_some());
+        assert!(registry.get("stock.user.analyze_esg").is_some());
+        assert!(registry.get("stock.user.analyze_portfolio").is_some());
+        assert!(registry.get("stock.user.build_query").is_some());
+        assert!(registry.get("stock.user.explain_query").is_some());
+        assert!(registry.get("stock.user.explain_term").is_some());
+        assert!(registry.get("stock.user.quick_summary").is_some());
+        assert!(registry.get("stock.user.deep_analysis").is_some());
+        assert!(registry.get("stock.response_style").is_some());
+        assert!(registry.get("stock.teaching_mode").is_some());
     }
 
     #[test]
@@ -111,7 +176,10 @@ mod tests {
         register_prompts(&registry).unwrap();
 
         let prompt = registry
-            .render("stock.user.analyze_earnings", &serde_json::json!({ "symbol": "GOOGL" }))
+            .render(
+                "stock.user.analyze_earnings",
+                &serde_json::json!({ "symbol": "GOOGL" }),
+            )
             .unwrap();
         assert!(prompt.contains("GOOGL"));
         assert!(prompt.contains("financial reports"));
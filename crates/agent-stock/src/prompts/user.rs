@@ -25,11 +25,25 @@ pub fn compare_earnings_prompt() -> Result<JinjaTemplate> {
 }
 
 /// Create the analyze earnings quality user message template
+///
+/// Variables: `symbol` and, when SEC data is available, `evidence`, the
+/// computed margin trends, accrual ratio, growth gaps, Beneish components
+/// and red flags.
 pub fn analyze_quality_prompt() -> Result<JinjaTemplate> {
     JinjaTemplate::bilingual(
         "stock.user.analyze_quality",
-        "Evaluate the earnings quality for {{ symbol }}, including revenue recognition, cash flow conversion, and non-recurring items.",
-        "请评估 {{ symbol }} 的盈利质量，包括收入确认、现金流转换、非经常性项目等因素。",
+        r"Evaluate the earnings quality for {{ symbol }}, including revenue recognition, cash flow conversion, and non-recurring items.
+{%- if evidence %}
+Base the assessment on this evidence computed from SEC filings:
+{{ evidence }}
+Explain each red flag, say whether the business model plausibly accounts for it, and conclude with an overall earnings quality rating (high, adequate or low).
+{%- endif %}",
+        r"请评估 {{ symbol }} 的盈利质量，包括收入确认、现金流转换、非经常性项目等因素。
+{%- if evidence %}
+请基于以下根据 SEC 文件计算的证据进行评估:
+{{ evidence }}
+逐一解释每个警示信号,判断其是否可以由业务模式合理解释,最后给出整体盈利质量评级(高、适中或低)。
+{%- endif %}",
     )
 }
 
@@ -100,6 +114,411 @@ pub fn analyze_impact_prompt() -> Result<JinjaTemplate> {
     )
 }
 
+/// Create the analyze theme user message template
+pub fn analyze_theme_prompt() -> Result<JinjaTemplate> {
+    JinjaTemplate::bilingual(
+        "stock.user.analyze_theme",
+        "How is the {{ name }} theme doing? Use the theme analysis tool with theme \"{{ key }}\" to review the basket's performance, breadth and top movers, then explain what is driving the move using the movers' news.",
+        "{{ name_zh }}主题表现如何？请使用主题分析工具（theme 为 \"{{ key }}\"）查看该篮子的表现、涨跌家数和领涨领跌个股，并结合相关新闻解释驱动因素。",
+    )
+}
+
+/// Create the market wrap user message template
+pub fn market_wrap_prompt() -> Result<JinjaTemplate> {
+    JinjaTemplate::bilingual(
+        "stock.user.market_wrap",
+        r"Write the market wrap for {{ date }} from today's data below. Use exactly these sections:
+## What happened
+Index and sector moves, market breadth, and notable watchlist news.
+## Why
+The likely drivers, citing the macro releases and headlines that explain the moves.
+## What to watch tomorrow
+Upcoming catalysts, levels and risks.
+
+Only use the data provided; say so if a section has little to go on.
+
+Data:
+{{ data }}",
+        r"根据以下今日数据撰写 {{ date }} 的收盘市场综述，严格使用以下章节：
+## 发生了什么
+指数和板块涨跌、市场宽度以及关注列表的重要新闻。
+## 原因
+可能的驱动因素，引用能够解释行情的宏观数据发布和新闻。
+## 明日关注
+即将到来的催化剂、关键点位和风险。
+
+只使用所提供的数据；如果某个章节依据不足，请说明。
+
+数据：
+{{ data }}",
+    )
+}
+
+/// Create the macro alert user message template
+pub fn macro_alert_prompt() -> Result<JinjaTemplate> {
+    JinjaTemplate::bilingual(
+        "stock.user.macro_alert",
+        "{{ title }} ({{ series_id }}) was just released at {{ value }} for {{ observation_date }}{% if prior %}, after {{ prior }} the period before{% endif %}, crossing the user's alert level ({{ condition }} {{ threshold }}). In one sentence, what does this print mean for markets? Reply with that sentence only.",
+        "{{ title }}（{{ series_id }}）刚刚公布 {{ observation_date }} 的数据为 {{ value }}{% if prior %}，前值为 {{ prior }}{% endif %}，触发了用户设定的提醒（{{ condition }} {{ threshold }}）。请用一句话说明这一数据对市场意味着什么，只回复这一句话。",
+    )
+}
+
+// ============================================================================
+// Fundamental Analyzer User Messages
+// ============================================================================
+
+/// Create the compare over time user message template
+pub fn compare_over_time_prompt() -> Result<JinjaTemplate> {
+    JinjaTemplate::bilingual(
+        "stock.user.compare_over_time",
+        "How has {{ symbol }} changed since {{ date }}? Use the compare over time tool with date \"{{ date }}\" and present the structured diff: price, P/E compression or expansion and whether price or earnings drove it, the RSI regime then and now, the position against the 50/200-day averages, and analyst estimate revisions. For a date a year or more back, set inflation_adjusted and say whether the stock beat inflation in real terms. End with what the changes mean for the stock today.",
+        "{{ symbol }} 与 {{ date }} 相比有哪些变化？请使用时间对比工具（date 为 \"{{ date }}\"）并列出结构化对比：股价、市盈率的压缩或扩张及其由股价还是盈利驱动、当时与现在的 RSI 区间、相对 50/200 日均线的位置，以及分析师盈利预期的调整。若对比日期在一年或更久以前，请将 inflation_adjusted 设为 true，并说明扣除通胀后的实际涨幅是否跑赢通胀。最后说明这些变化对当前股票的意义。",
+    )
+}
+
+// ============================================================================
+// News Analyzer User Messages
+// ============================================================================
+
+/// Create the watchlist news digest user message template
+pub fn news_digest_prompt() -> Result<JinjaTemplate> {
+    JinjaTemplate::bilingual(
+        "stock.user.news_digest",
+        r"Write the news digest for {{ date }} from the stories below. They are already grouped by event and ranked by likely impact on the user's portfolio, highest first.
+Lead with the stories that matter most, one short paragraph each: what happened, which symbols it touches, and whether it is likely positive or negative for them. Then list the remaining stories as one-line bullets. Skip routine items, and say which symbols had no news or could not be fetched.
+
+Only use the stories provided. Where a story has a title_translated, use it and give the original headline in parentheses.
+
+Stories:
+{{ data }}",
+        r"根据以下新闻撰写 {{ date }} 的新闻摘要。这些新闻已按事件归类，并按对用户持仓的可能影响从高到低排序。
+先写最重要的新闻，每条一小段：发生了什么、涉及哪些股票、对这些股票可能是利好还是利空。其余新闻各用一行列出。略过例行消息，并说明哪些股票没有新闻或未能获取。
+
+只使用所提供的新闻。新闻带有 title_translated 时使用译文，并在括号中附上英文原标题。
+
+新闻：
+{{ data }}",
+    )
+}
+
+// ============================================================================
+// ESG Analyzer User Messages
+// ============================================================================
+
+/// Create the analyze ESG user message template
+pub fn analyze_esg_prompt() -> Result<JinjaTemplate> {
+    JinjaTemplate::bilingual(
+        "stock.user.analyze_esg",
+        "Analyze the ESG profile of {{ symbol }}: its environmental, social and governance scores, notable controversies, and how sustainable the business is compared with its peers.",
+        "请分析 {{ symbol }} 的ESG状况，包括环境、社会和公司治理评分、主要争议事件，以及与同行相比其业务的可持续性。",
+    )
+}
+
+// ============================================================================
+// Portfolio Analyzer User Messages
+// ============================================================================
+
+/// Create the analyze portfolio user message template
+///
+/// Variables: `question`. The user is pinned on the tool calls instead of
+/// named in the prompt.
+pub fn analyze_portfolio_prompt() -> Result<JinjaTemplate> {
+    JinjaTemplate::bilingual(
+        "stock.user.analyze_portfolio",
+        "Answer this question about the user's portfolio: {{ question }}",
+        "请回答关于用户投资组合的问题：{{ question }}",
+    )
+}
+
+// ============================================================================
+// Query Builder User Messages
+// ============================================================================
+
+/// Create the build query user message template
+///
+/// Variables: `question`, and `query` and `error` when a previous attempt
+/// failed validation.
+pub fn build_query_prompt() -> Result<JinjaTemplate> {
+    JinjaTemplate::bilingual(
+        "stock.user.build_query",
+        r"Question: {{ question }}
+{%- if error %}
+
+Your previous query was rejected:
+{{ query }}
+Error: {{ error }}
+Reply with a corrected query.
+{%- endif %}",
+        r"问题：{{ question }}
+{%- if error %}
+
+你之前生成的查询未通过校验：
+{{ query }}
+错误：{{ error }}
+请回复修正后的查询。
+{%- endif %}",
+    )
+}
+
+/// Create the explain query results user message template
+///
+/// Variables: `question`, `query` (as shown to the user) and `results`.
+pub fn explain_query_prompt() -> Result<JinjaTemplate> {
+    JinjaTemplate::bilingual(
+        "stock.user.explain_query",
+        r"Question: {{ question }}
+
+Query:
+{{ query }}
+
+Results:
+{{ results }}",
+        r"问题：{{ question }}
+
+查询：
+{{ query }}
+
+结果：
+{{ results }}",
+    )
+}
+
+// ============================================================================
+// Term Explanations
+// ============================================================================
+
+/// Create the explain term user message template
+///
+/// Variables: `term`, `term_zh`, `definition`, `formula`, `example_data`
+/// (from the glossary) and `symbol`, the stock used for the worked example.
+pub fn explain_term_prompt() -> Result<JinjaTemplate> {
+    JinjaTemplate::bilingual(
+        "stock.user.explain_term",
+        r"Explain the financial term {{ term }} ({{ term_zh }}). Do not perform a full stock analysis.
+Reference definition: {{ definition }}
+{%- if formula %}
+Formula: {{ formula }}
+{%- endif %}
+1. Give a crisp definition in two or three sentences.
+2. Fetch the {{ example_data }} for {{ symbol }} and compute a short worked example with the real numbers.
+3. Say in one sentence how investors typically interpret the value.",
+        r"请解释金融术语「{{ term_zh }}」({{ term }}),不要进行完整的股票分析。
+参考定义:{{ definition }}
+{%- if formula %}
+计算公式:{{ formula }}
+{%- endif %}
+1. 用两三句话给出简明定义。
+2. 获取 {{ symbol }} 的{{ example_data }}数据,用真实数字给出一个简短的计算示例。
+3. 用一句话说明投资者通常如何解读这个数值。",
+    )
+}
+
+// ============================================================================
+// Teaching Mode Instructions
+// ============================================================================
+
+/// Create the teaching mode (educational walkthrough) instruction template
+///
+/// Variables: `formulas`, a list of `{ term, formula }` objects for the
+/// indicators or metrics the agent is likely to use.
+pub fn teaching_mode_prompt() -> Result<JinjaTemplate> {
+    JinjaTemplate::bilingual(
+        "stock.teaching_mode",
+        r"[Teaching mode]
+The reader is learning how to analyze stocks. After each conclusion, add a short 'How we got here' note that:
+- lists the reasoning steps from the raw data to the conclusion,
+- shows the formula used and plugs in the actual numbers,
+- explains why the thresholds matter (for example, why RSI above 70 is read as overbought and below 30 as oversold).
+{%- if formulas %}
+Reference formulas:
+{%- for f in formulas %}
+- {{ f.term }}: {{ f.formula }}
+{%- endfor %}
+{%- endif %}",
+        r"[教学模式]
+读者正在学习如何分析股票。在每个结论之后,添加一段简短的「推导过程」说明:
+- 列出从原始数据到结论的推理步骤,
+- 给出所用的公式,并代入实际数值,
+- 解释阈值的意义(例如为什么RSI高于70视为超买、低于30视为超卖)。
+{%- if formulas %}
+参考公式:
+{%- for f in formulas %}
+- {{ f.term }}: {{ f.formula }}
+{%- endfor %}
+{%- endif %}",
+    )
+}
+
+// ============================================================================
+// Point-in-Time Analysis
+// ============================================================================
+
+/// Create the instruction template for analyses as of a past date
+///
+/// Variables: `as_of`, the date (YYYY-MM-DD) the analysis is written on.
+pub fn as_of_prompt() -> Result<JinjaTemplate> {
+    JinjaTemplate::bilingual(
+        "stock.as_of",
+        r"[Point-in-time analysis]
+Today is {{ as_of }}. Write the analysis as it would have been written at the close of that day:
+- use only the tool data, which stops at {{ as_of }}; tools without dated data are unavailable,
+- do not use anything you know about events, prices or reports after {{ as_of }},
+- refer to {{ as_of }} as today and state forecasts from that point of view.",
+        r"[时点分析]
+今天是 {{ as_of }}。请以当天收盘时的视角撰写分析:
+- 只使用工具返回的数据,这些数据截至 {{ as_of }};没有历史时点数据的工具不可用,
+- 不要使用你所知道的 {{ as_of }} 之后的任何事件、价格或报告,
+- 把 {{ as_of }} 当作今天,并从这一时点给出预测。",
+    )
+}
+
+/// Create the translation request template for bilingual replies
+///
+/// Variables: `language`, the target language name; `text`, the reply to
+/// translate; `missing`, numbers a previous attempt dropped (empty at first).
+pub fn translate_prompt() -> Result<JinjaTemplate> {
+    JinjaTemplate::bilingual(
+        "stock.user.translate",
+        r"Translate the following analysis into {{ language }}.
+{% if missing %}Your previous translation dropped or changed these numbers: {{ missing }}. Keep every one of them exactly.
+{% endif %}
+---
+{{ text }}",
+        r"请将以下分析翻译成 {{ language }}。
+{% if missing %}上一次翻译遗漏或改动了这些数字:{{ missing }}。请逐一原样保留。
+{% endif %}
+---
+{{ text }}",
+    )
+}
+
+/// Create the source translation request template
+///
+/// Variables: `language`, the target language name; `texts`, the headlines
+/// and summaries to translate as a JSON array of strings.
+pub fn translate_sources_prompt() -> Result<JinjaTemplate> {
+    JinjaTemplate::bilingual(
+        "stock.user.translate_sources",
+        r"Translate each news headline or summary in this JSON array into {{ language }}.
+Reply with a JSON array of the translations only, in the same order and with the same number of items.
+
+{{ texts }}",
+        r"请将这个 JSON 数组中的每条新闻标题或摘要翻译成 {{ language }}。
+只回复由译文组成的 JSON 数组,顺序和条数与原数组相同。
+
+{{ texts }}",
+    )
+}
+
+// ============================================================================
+// Analysis Depth
+// ============================================================================
+
+/// Create the quick analysis user message template
+///
+/// Variables: `symbol`. Used for `AnalysisDepth::Quick`: one short answer
+/// from the latest quote and fundamentals instead of the specialist team.
+pub fn quick_summary_prompt() -> Result<JinjaTemplate> {
+    JinjaTemplate::bilingual(
+        "stock.user.quick_summary",
+        r"Give a quick take on {{ symbol }} using only its latest quote and key fundamentals.
+- Three to five sentences: price and recent move, valuation in one line, and an overall lean (bullish, neutral or bearish).
+- Do not run technical indicators or a full analysis.",
+        r"基于 {{ symbol }} 的最新行情和关键基本面数据给出快速点评。
+- 三到五句话:价格及近期走势、一句话估值判断,以及整体倾向(看多、中性或看空)。
+- 不要计算技术指标,也不要做完整分析。",
+    )
+}
+
+/// Create the deep analysis user message template
+///
+/// Variables: `symbol`, `focus` ("filings", "peers" or "scenarios") and, for
+/// scenarios, `findings`, a summary of the other sections to build on. Used
+/// for the extra sections of `AnalysisDepth::Deep`.
+pub fn deep_analysis_prompt() -> Result<JinjaTemplate> {
+    JinjaTemplate::bilingual(
+        "stock.user.deep_analysis",
+        r"{%- if focus == 'filings' -%}
+Review the most recent SEC filings (10-K, 10-Q and 8-K) for {{ symbol }}.
+- Summarize material changes in risk factors, guidance and management discussion.
+- Flag anything unusual: restatements, going-concern language, auditor changes or large one-off items.
+{%- elif focus == 'peers' -%}
+Compare {{ symbol }} with its three to five closest listed peers.
+- Tabulate valuation (P/E, P/S), growth, margins and balance sheet strength.
+- Say where {{ symbol }} trades at a premium or discount and whether that is justified.
+{%- else -%}
+Build bull, base and bear scenarios for {{ symbol }} over the next 12 months.
+- For each: the key assumptions, a price range, and a rough probability; probabilities add up to 100%.
+- Name the signposts that would tell which scenario is playing out.
+{%- if findings %}
+Build on these findings:
+{{ findings }}
+{%- endif %}
+{%- endif %}",
+        r"{%- if focus == 'filings' -%}
+审阅 {{ symbol }} 最近的 SEC 文件(10-K、10-Q 和 8-K)。
+- 总结风险因素、业绩指引和管理层讨论中的重大变化。
+- 标出异常情况:财务重述、持续经营疑虑、更换审计师或大额一次性项目。
+{%- elif focus == 'peers' -%}
+将 {{ symbol }} 与三到五家最接近的上市同行进行比较。
+- 用表格列出估值(市盈率、市销率)、增长、利润率和资产负债表强度。
+- 说明 {{ symbol }} 相对同行是溢价还是折价,以及是否合理。
+{%- else -%}
+为 {{ symbol }} 构建未来 12 个月的乐观、基准和悲观情景。
+- 每个情景给出关键假设、价格区间和大致概率,概率合计为 100%。
+- 指出可以判断哪个情景正在发生的信号。
+{%- if findings %}
+基于以下分析结论:
+{{ findings }}
+{%- endif %}
+{%- endif %}",
+    )
+}
+
+// ============================================================================
+// Response Style Instructions
+// ============================================================================
+
+/// Create the response style instruction template
+///
+/// Variables: `style`, `max_words`, `jargon` ("minimal", "professional" or
+/// "explain") and `use_emoji`, as produced by `ResponseStyle::template_vars`.
+pub fn response_style_prompt() -> Result<JinjaTemplate> {
+    JinjaTemplate::bilingual(
+        "stock.response_style",
+        r"[Response style: {{ style }}]
+- Keep the answer under about {{ max_words }} words.
+{%- if jargon == 'minimal' %}
+- Lead with the conclusion, then only the key numbers. Skip background explanations.
+{%- elif jargon == 'explain' %}
+- Write for a beginner: avoid jargon, and explain every financial term you use in one simple sentence.
+- Use everyday analogies where they help.
+{%- else %}
+- Write for a professional audience: use precise terminology and include supporting data.
+{%- endif %}
+{%- if use_emoji %}
+- You may use a few emoji to highlight key points.
+{%- else %}
+- Do not use emoji.
+{%- endif %}",
+        r"[回复风格: {{ style }}]
+- 回答控制在约 {{ max_words }} 字以内。
+{%- if jargon == 'minimal' %}
+- 先给出结论,再列出关键数据,省略背景解释。
+{%- elif jargon == 'explain' %}
+- 面向新手投资者:避免专业术语,使用的每个金融术语都用一句通俗的话解释。
+- 适当使用生活化的类比。
+{%- else %}
+- 面向专业读者:使用准确的专业术语,并提供支撑数据。
+{%- endif %}
+{%- if use_emoji %}
+- 可以使用少量表情符号突出重点。
+{%- else %}
+- 不要使用表情符号。
+{%- endif %}",
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -121,6 +540,24 @@ mod tests {
         assert!(analyze_geopolitical_risks_prompt().is_ok());
         assert!(get_market_outlook_prompt().is_ok());
         assert!(analyze_impact_prompt().is_ok());
+        assert!(analyze_theme_prompt().is_ok());
+        assert!(market_wrap_prompt().is_ok());
+        assert!(macro_alert_prompt().is_ok());
+        assert!(compare_over_time_prompt().is_ok());
+        assert!(news_digest_prompt().is_ok());
+        assert!(analyze_portfolio_prompt().is_ok());
+        assert!(build_query_prompt().is_ok());
+        assert!(explain_query_prompt().is_ok());
+
+        // Explanation and style prompts
+        assert!(explain_term_prompt().is_ok());
+        assert!(response_style_prompt().is_ok());
+        assert!(teaching_mode_prompt().is_ok());
+        assert!(as_of_prompt().is_ok());
+        assert!(translate_prompt().is_ok());
+        assert!(translate_sources_prompt().is_ok());
+        assert!(quick_summary_prompt().is_ok());
+        assert!(deep_analysis_prompt().is_ok());
     }
 
     #[test]
@@ -145,13 +582,19 @@ mod tests {
         let template = compare_earnings_prompt().unwrap();
 
         let en = template
-            .render(&Language::English, &json!({ "symbol": "MSFT", "periods": 4 }))
+            .render(
+                &Language::English,
+                &json!({ "symbol": "MSFT", "periods": 4 }),
+            )
             .unwrap();
         assert!(en.contains("MSFT"));
         assert!(en.contains("4"));
 
         let zh = template
-            .render(&Language::Chinese, &json!({ "symbol": "MSFT", "periods": 4 }))
+            .render(
+                &Language::Chinese,
+                &json!({ "symbol": "MSFT", "periods": 4 }),
+            )
             .unwrap();
         assert!(zh.contains("MSFT"));
         assert!(zh.contains("4"));
@@ -162,7 +605,10 @@ mod tests {
         let template = analyze_impact_prompt().unwrap();
 
         let en = template
-            .render(&Language::English, &json!({ "subject": "technology sector" }))
+            .render(
+                &Language::English,
+                &json!({ "subject": "technology sector" }),
+            )
             .unwrap();
         assert!(en.contains("technology sector"));
 
@@ -171,4 +617,123 @@ mod tests {
             .unwrap();
         assert!(zh.contains("科技板块"));
     }
+
+    #[test]
+    fn test_analyze_portfolio_render() {
+        let template = analyze_portfolio_prompt().unwrap();
+        let vars = json!({ "question": "what's my exposure to tech?" });
+
+        let en = template.render(&Language::English, &vars).unwrap();
+        assert!(en.contains("the user's portfolio"));
+        assert!(en.ends_with("what's my exposure to tech?"));
+
+        let zh = template.render(&Language::Chinese, &vars).unwrap();
+        assert!(zh.contains("投资组合"));
+    }
+
+    #[test]
+    fn test_explain_term_render() {
+        let template = explain_term_prompt().unwrap();
+        let vars = json!({
+            "term": "PEG Ratio",
+            "term_zh": "市盈增长比率",
+            "definition": "P/E divided by growth",
+            "formula": "P/E / growth",
+            "example_data": "P/E ratio and growth rate",
+            "symbol": "AAPL",
+        });
+
+        let en = template.render(&Language::English, &vars).unwrap();
+        assert!(en.contains("PEG Ratio"));
+        assert!(en.contains("Formula: P/E / growth"));
+        assert!(en.contains("for AAPL"));
+
+        let zh = template.render(&Language::Chinese, &vars).unwrap();
+        assert!(zh.contains("市盈增长比率"));
+        assert!(zh.contains("AAPL"));
+    }
+
+    #[test]
+    fn test_teaching_mode_render() {
+        let template = teaching_mode_prompt().unwrap();
+        let vars = json!({ "formulas": [{ "term": "RSI", "formula": "100 - 100 / (1 + RS)" }] });
+
+        let en = template.render(&Language::English, &vars).unwrap();
+        assert!(en.contains("How we got here"));
+        assert!(en.contains("- RSI: 100 - 100 / (1 + RS)"));
+
+        let zh = template
+            .render(&Language::Chinese, &json!({ "formulas": [] }))
+            .unwrap();
+        assert!(zh.contains("教学模式"));
+        assert!(!zh.contains("参考公式"));
+    }
+
+    #[test]
+    fn test_as_of_render() {
+        let template = as_of_prompt().unwrap();
+        let vars = json!({ "as_of": "2023-01-31" });
+
+        let en = template.render(&Language::English, &vars).unwrap();
+        assert!(en.contains("Today is 2023-01-31."));
+        let zh = template.render(&Language::Chinese, &vars).unwrap();
+        assert!(zh.contains("今天是 2023-01-31"));
+    }
+
+    #[test]
+    fn test_translate_render() {
+        let template = translate_prompt().unwrap();
+
+        let first = json!({ "language": "English", "text": "RSI 为 72.4", "missing": "" });
+        let en = template.render(&Language::English, &first).unwrap();
+        assert!(en.contains("into English"));
+        assert!(en.ends_with("RSI 为 72.4"));
+        assert!(!en.contains("previous translation"));
+
+        let retry = json!({ "language": "English", "text": "RSI 为 72.4", "missing": "72.4" });
+        let en = template.render(&Language::English, &retry).unwrap();
+        assert!(en.contains("changed these numbers: 72.4."));
+    }
+
+    #[test]
+    fn test_response_style_render() {
+        let template = response_style_prompt().unwrap();
+        let vars = json!({ "style": "beginner", "max_words": 400, "jargon": "explain", "use_emoji": true });
+
+        let en = template.render(&Language::English, &vars).unwrap();
+        assert!(en.contains("400 words"));
+        assert!(en.contains("beginner"));
+        assert!(en.contains("emoji to highlight"));
+
+        let vars = json!({ "style": "concise", "max_words": 150, "jargon": "minimal", "use_emoji": false });
+        let zh = template.render(&Language::Chinese, &vars).unwrap();
+        assert!(zh.contains("150"));
+        assert!(zh.contains("不要使用表情符号"));
+    }
+
+    #[test]
+    fn test_deep_analysis_render() {
+        let template = deep_analysis_prompt().unwrap();
+
+        let en = template
+            .render(
+                &Language::English,
+                &json!({ "symbol": "AAPL", "focus": "filings" }),
+            )
+            .unwrap();
+        assert!(en.starts_with("Review the most recent SEC filings"));
+
+        let vars = json!({ "symbol": "AAPL", "focus": "scenarios", "findings": "RSI: 55" });
+        let en = template.render(&Language::English, &vars).unwrap();
+        assert!(en.contains("bull, base and bear"));
+        assert!(en.contains("RSI: 55"));
+
+        let zh = template
+            .render(
+                &Language::Chinese,
+                &json!({ "symbol": "AAPL", "focus": "peers" }),
+            )
+            .unwrap();
+        assert!(zh.contains("同行"));
+    }
 }
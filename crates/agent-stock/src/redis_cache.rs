@@ -0,0 +1,248 @@
+//! Redis cache backend, shared by every bot instance pointed at the same server
+//!
+//! Enabled by the `redis` feature and [`crate::cache::REDIS_URL_ENV`]. Each
+//! tier stores JSON values under `agent-stock:{tier}:{symbol}:{endpoint}:{params}`
+//! with the tier's TTL, so Redis expires entries on its own. Redis errors are
+//! logged and treated as cache misses.
+
+use async_trait::async_trait;
+use redis::aio::{ConnectionManager, ConnectionManagerConfig};
+use redis::{AsyncCommands, Client, RedisResult};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::time::Duration;
+use tokio::sync::OnceCell;
+
+use crate::cache::{CacheBackend, CacheCategory, CacheKey};
+
+/// Prefix of every key written by this crate
+const KEY_PREFIX: &str = "agent-stock";
+
+/// How long to wait for Redis before treating a request as a miss
+const REDIS_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Keys fetched per SCAN round trip when counting or clearing a tier
+const SCAN_BATCH: usize = 500;
+
+/// Connection to one Redis server
+#[derive(Clone)]
+pub struct RedisStore {
+    client: Client,
+    /// Connected on first use, so building a cache never blocks
+    connection: Arc<OnceCell<ConnectionManager>>,
+}
+
+impl RedisStore {
+    /// A store for the server at `url`; fails only if `url` is malformed
+    pub fn open(url: &str) -> RedisResult<Self> {
+        Ok(Self {
+            client: Client::open(url)?,
+            connection: Arc::new(OnceCell::new()),
+        })
+    }
+
+    /// The store for `url` shared by every cache in this process, so they
+    /// reuse one connection
+    pub fn shared(url: &str) -> RedisResult<Self> {
+        static STORES: OnceLock<Mutex<HashMap<String, RedisStore>>> = OnceLock::new();
+        let mut stores = STORES
+            .get_or_init(Mutex::default)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(store) = stores.get(url) {
+            return Ok(store.clone());
+        }
+        let store = Self::open(url)?;
+        stores.insert(url.to_string(), store.clone());
+        Ok(store)
+    }
+
+    /// Backend for `category`, whose entries live for `ttl`
+    pub fn backend(&self, category: CacheCategory, ttl: Duration) -> RedisBackend {
+        RedisBackend {
+            store: self.clone(),
+            category,
+            ttl,
+        }
+    }
+
+    async fn connection(&self) -> RedisResult<ConnectionManager> {
+        let config = ConnectionManagerConfig::new()
+            .set_number_of_retries(0)
+            .set_connection_timeout(REDIS_TIMEOUT)
+            .set_response_timeout(REDIS_TIMEOUT);
+        self.connection
+            .get_or_try_init(|| ConnectionManager::new_with_config(self.client.clone(), config))
+            .await
+            .cloned()
+    }
+}
+
+/// One cache tier stored in Redis
+pub struct RedisBackend {
+    store: RedisStore,
+    category: CacheCategory,
+    ttl: Duration,
+}
+
+impl RedisBackend {
+    fn key(&self, key: &CacheKey) -> String {
+        format!(
+            "{KEY_PREFIX}:{}:{}:{}:{}",
+            self.category, key.symbol, key.endpoint, key.params
+        )
+    }
+
+    /// Every key of this tier
+    async fn keys(&self, connection: &mut ConnectionManager) -> RedisResult<Vec<String>> {
+        let pattern = format!("{KEY_PREFIX}:{}:*", self.category);
+        let mut keys = Vec::new();
+        let mut cursor = 0u64;
+        loop {
+            let (next, batch): (u64, Vec<String>) = redis::cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(&pattern)
+                .arg("COUNT")
+                .arg(SCAN_BATCH)
+                .query_async(connection)
+                .await?;
+            keys.extend(batch);
+            if next == 0 {
+                return Ok(keys);
+            }
+            cursor = next;
+        }
+    }
+
+    /// Log a failed Redis request and carry on without the cache
+    fn log_error(&self, operation: &str, error: &redis::RedisError) {
+        tracing::warn!(
+            "Redis cache {operation} failed for {}: {error}",
+            self.category
+        );
+    }
+}
+
+#[async_trait]
+impl CacheBackend for RedisBackend {
+    fn name(&self) -> &'static str {
+        "redis"
+    }
+
+    async fn get(&self, key: &CacheKey) -> Option<serde_json::Value> {
+        let result: RedisResult<Option<String>> = async {
+            let mut connection = self.store.connection().await?;
+            connection.get(self.key(key)).await
+        }
+        .await;
+        match result {
+            Ok(value) => value.and_then(|json| serde_json::from_str(&json).ok()),
+            Err(e) => {
+                self.log_error("read", &e);
+                None
+            }
+        }
+    }
+
+    async fn set(&self, key: CacheKey, value: serde_json::Value) {
+        // Redis rejects a zero expiry; such entries would expire at once anyway
+        let ttl_ms = u64::try_from(self.ttl.as_millis()).unwrap_or(u64::MAX);
+        if ttl_ms == 0 {
+            return;
+        }
+        let result: RedisResult<()> = async {
+            let mut connection = self.store.connection().await?;
+            connection
+                .pset_ex(self.key(&key), value.to_string(), ttl_ms)
+                .await
+        }
+        .await;
+        if let Err(e) = result {
+            self.log_error("write", &e);
+        }
+    }
+
+    async fn remove(&self, key: &CacheKey) {
+        let result: RedisResult<()> = async {
+            let mut connection = self.store.connection().await?;
+            connection.del(self.key(key)).await
+        }
+        .await;
+        if let Err(e) = result {
+            self.log_error("delete", &e);
+        }
+    }
+
+    async fn clear(&self) {
+        let result: RedisResult<()> = async {
+            let mut connection = self.store.connection().await?;
+            let keys = self.keys(&mut connection).await?;
+            for chunk in keys.chunks(SCAN_BATCH) {
+                let _: () = connection.del(chunk).await?;
+            }
+            Ok(())
+        }
+        .await;
+        if let Err(e) = result {
+            self.log_error("clear", &e);
+        }
+    }
+
+    async fn len(&self) -> usize {
+        let result = async {
+            let mut connection = self.store.connection().await?;
+            self.keys(&mut connection).await
+        }
+        .await;
+        result.map_or_else(
+            |e| {
+                self.log_error("count", &e);
+                0
+            },
+            |keys| keys.len(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::StockCache;
+
+    #[test]
+    fn test_keys_are_namespaced_by_tier() {
+        let store = RedisStore::open("redis://127.0.0.1:6379").unwrap();
+        let key = CacheKey::new("AAPL", "stock_data", serde_json::json!({ "range": "1d" }));
+        let realtime = store.backend(CacheCategory::Realtime, Duration::from_secs(60));
+        let news = store.backend(CacheCategory::News, Duration::from_secs(300));
+
+        assert_eq!(
+            realtime.key(&key),
+            r#"agent-stock:realtime:AAPL:stock_data:{"range":"1d"}"#
+        );
+        assert_ne!(realtime.key(&key), news.key(&key));
+        assert!(RedisStore::open("not a url").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_server_is_a_miss() {
+        // Nothing listens on port 1
+        let store = RedisStore::open("redis://127.0.0.1:1").unwrap();
+        let cache = StockCache::with_backend(Arc::new(
+            store.backend(CacheCategory::Realtime, Duration::from_secs(60)),
+        ));
+        let key = CacheKey::new("AAPL", "quote", serde_json::json!({}));
+
+        let value = cache
+            .get_or_fetch(key.clone(), || async {
+                Ok::<_, String>(serde_json::json!({ "price": 150.0 }))
+            })
+            .await
+            .unwrap();
+        assert_eq!(value["price"], 150.0);
+        assert!(cache.get(&key).await.is_none());
+        assert_eq!(cache.len().await, 0);
+        assert_eq!(cache.backend_name(), "redis");
+    }
+}
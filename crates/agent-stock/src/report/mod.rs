@@ -0,0 +1,865 @@
+//! Analysis reports as Markdown, HTML and PDF documents
+//!
+//! A [`Report`] lays an [`AnalysisResult`] or [`ComparisonResult`] out as a
+//! document: a title, a table of key metrics, the highlighted price moves,
+//! the price and indicator chart from [`chart_render`], the analysis body
+//! and its sources and warnings. The same blocks are written as Markdown,
+//! as a standalone HTML page or as a PDF (see [`pdf`]); the chart is
+//! embedded in all three, as a `data:` URI in Markdown and HTML.
+//!
+//! `/report AAPL [md|html|pdf]` replies with the report as a document on
+//! platforms that take file attachments ([`BotPlatform::supports_files`]),
+//! and with the Markdown text elsewhere. `/report AAPL MSFT` reports on a
+//! comparison.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::report::{Report, ReportFormat};
+//!
+//! let result = engine.analyze_stock("AAPL", &mut context).await?;
+//! let report = Report::from_analysis(&result, &context);
+//! std::fs::write(report.filename(ReportFormat::Pdf), report.render(ReportFormat::Pdf)?)?;
+//! ```
+//!
+//! [`chart_render`]: crate::interface::chart_render
+
+pub mod pdf;
+
+use std::fmt::Write;
+
+use agent_prompt::Language;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chrono::{DateTime, Utc};
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+
+use crate::engine::result::ComparisonResult;
+use crate::engine::{AnalysisContext, AnalysisResult, StockAnalysisEngine};
+use crate::error::{Result, StockError};
+use crate::interface::BotPlatform;
+use crate::interface::block_kit::{comparison_table, key_metrics};
+use crate::interface::chart_render::{CHART_HEIGHT, CHART_WIDTH, PriceChart, render_png};
+use crate::interface::formatter::{Locale, localized_content};
+use crate::interface::highlight::Highlights;
+use crate::interface::interface::{Attachment, AttachmentType};
+use crate::interface::markup::{Markup, escape_html};
+use crate::interface::queue::RequestQueue;
+use crate::interface::session::SessionManager;
+use crate::interface::sparkline::CHART_DATA_KEY;
+
+/// Document format of a report
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+    #[default]
+    Pdf,
+}
+
+impl ReportFormat {
+    /// Parse a format name, e.g. "md", "html" or "pdf"
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "md" | "markdown" => Some(Self::Markdown),
+            "html" | "htm" | "web" => Some(Self::Html),
+            "pdf" => Some(Self::Pdf),
+            _ => None,
+        }
+    }
+
+    /// File extension, without the dot
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Html => "html",
+            Self::Pdf => "pdf",
+        }
+    }
+
+    /// MIME type of the document
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            Self::Markdown => "text/markdown",
+            Self::Html => "text/html",
+            Self::Pdf => "application/pdf",
+        }
+    }
+}
+
+impl std::fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Markdown => write!(f, "Markdown"),
+            Self::Html => write!(f, "HTML"),
+            Self::Pdf => write!(f, "PDF"),
+        }
+    }
+}
+
+/// Style of a run of text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpanStyle {
+    Plain,
+    Bold,
+    Code,
+    /// Link to the URL
+    Link(String),
+}
+
+/// Run of text in one style
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub text: String,
+    pub style: SpanStyle,
+}
+
+impl Span {
+    pub fn plain(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            style: SpanStyle::Plain,
+        }
+    }
+
+    pub fn bold(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            style: SpanStyle::Bold,
+        }
+    }
+}
+
+/// Block of a report
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    Title(String),
+    Heading(String),
+    Paragraph(Vec<Span>),
+    /// List item, `depth` 0 at the top level
+    Bullet {
+        depth: usize,
+        spans: Vec<Span>,
+    },
+    /// Preformatted text
+    Code(String),
+    Table {
+        headers: Vec<String>,
+        rows: Vec<Vec<String>>,
+    },
+    /// PNG image
+    Image {
+        png: Vec<u8>,
+        alt: String,
+    },
+    /// Secondary text such as sources and warnings
+    Note(String),
+}
+
+/// Analysis laid out as a document
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub title: String,
+    /// Symbols covered, for the file name
+    pub symbols: Vec<String>,
+    pub generated_at: DateTime<Utc>,
+    pub blocks: Vec<Block>,
+}
+
+impl Report {
+    /// Report of an analysis, localized for the session in `context`
+    pub fn from_analysis(result: &AnalysisResult, context: &AnalysisContext) -> Self {
+        let locale = Locale::for_context(context);
+        let title = format!("{} {:?} Analysis", result.symbol, result.analysis_type);
+        let mut blocks = vec![Block::Title(title.clone())];
+        blocks.push(metrics_table(key_metrics(result, &locale)));
+        blocks.extend(highlight_blocks(
+            &Highlights::from_result(result),
+            &locale.language,
+        ));
+        blocks.extend(chart_block(result, &locale));
+        blocks.extend(parse_markdown(&localized_content(result, context)));
+        blocks.extend(notes(result));
+
+        Self {
+            title,
+            symbols: vec![result.symbol.clone()],
+            generated_at: result.timestamp,
+            blocks,
+        }
+    }
+
+    /// Report of a comparison: highlights, the metrics table, the summary
+    /// and each symbol's analysis
+    pub fn from_comparison(comparison: &ComparisonResult, context: &AnalysisContext) -> Self {
+        let locale = Locale::for_context(context);
+        let title = format!("Comparison: {}", comparison.symbols.join(" vs "));
+        let mut blocks = vec![Block::Title(title.clone())];
+        blocks.extend(highlight_blocks(
+            &Highlights::from_comparison(comparison),
+            &locale.language,
+        ));
+        if let Some((headers, rows)) = comparison_table(comparison, &locale) {
+            blocks.push(Block::Table { headers, rows });
+        }
+        blocks.extend(parse_markdown(
+            &locale.localize_numbers(&comparison.summary),
+        ));
+        for symbol in &comparison.symbols {
+            let Some(result) = comparison.analyses.get(symbol) else {
+                continue;
+            };
+            blocks.push(Block::Heading(symbol.clone()));
+            blocks.extend(chart_block(result, &locale));
+            blocks.extend(parse_markdown(&localized_content(result, context)));
+            blocks.extend(notes(result));
+        }
+
+        Self {
+            title,
+            symbols: comparison.symbols.clone(),
+            generated_at: comparison.timestamp,
+            blocks,
+        }
+    }
+
+    /// The report as Markdown, with images inline as `data:` URIs
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let mut previous: Option<&Block> = None;
+        for block in &self.blocks {
+            // Consecutive list items stay in one list
+            let tight = matches!(
+                (previous, block),
+                (Some(Block::Bullet { .. }), Block::Bullet { .. })
+            );
+            if previous.is_some() {
+                out.push_str(if tight { "\n" } else { "\n\n" });
+            }
+            let text = match block {
+                Block::Title(text) => format!("# {text}"),
+                Block::Heading(text) => format!("## {text}"),
+                Block::Paragraph(spans) => markdown_spans(spans),
+                Block::Bullet { depth, spans } => {
+                    format!("{}- {}", "  ".repeat(*depth), markdown_spans(spans))
+                }
+                Block::Code(text) => format!("```\n{text}\n```"),
+                Block::Table { headers, rows } => markdown_table(headers, rows),
+                Block::Image { png, alt } => {
+                    format!("![{alt}](data:image/png;base64,{})", BASE64.encode(png))
+                }
+                Block::Note(text) => format!("_{text}_"),
+            };
+            out.push_str(&text);
+            previous = Some(block);
+        }
+        out.push('\n');
+        out
+    }
+
+    /// The report as a standalone HTML page
+    pub fn to_html(&self) -> String {
+        let mut body = String::new();
+        let mut in_list = false;
+        for block in &self.blocks {
+            let is_bullet = matches!(block, Block::Bullet { .. });
+            if in_list && !is_bullet {
+                body.push_str("</ul>\n");
+            } else if !in_list && is_bullet {
+                body.push_str("<ul>\n");
+            }
+            in_list = is_bullet;
+
+            let html = match block {
+                Block::Title(text) => format!("<h1>{}</h1>", escape_html(text)),
+                Block::Heading(text) => format!("<h2>{}</h2>", escape_html(text)),
+                Block::Paragraph(spans) => format!("<p>{}</p>", html_spans(spans)),
+                Block::Bullet { depth, spans } => {
+                    format!(
+                        "<li style=\"margin-left: {}em\">{}</li>",
+                        depth * 2,
+                        html_spans(spans)
+                    )
+                }
+                Block::Code(text) => format!("<pre>{}</pre>", escape_html(text)),
+                Block::Table { headers, rows } => html_table(headers, rows),
+                Block::Image { png, alt } => format!(
+                    "<img src=\"data:image/png;base64,{}\" alt=\"{}\">",
+                    BASE64.encode(png),
+                    escape_attribute(alt)
+                ),
+                Block::Note(text) => format!("<p class=\"note\">{}</p>", escape_html(text)),
+            };
+            body.push_str(&html);
+            body.push('\n');
+        }
+        if in_list {
+            body.push_str("</ul>\n");
+        }
+
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+             <style>{STYLESHEET}</style>\n</head>\n<body>\n{body}<footer>Generated {}</footer>\n\
+             </body>\n</html>\n",
+            escape_html(&self.title),
+            self.generated_at.format("%Y-%m-%d %H:%M UTC")
+        )
+    }
+
+    /// The report in `format`
+    pub fn render(&self, format: ReportFormat) -> Result<Vec<u8>> {
+        match format {
+            ReportFormat::Markdown => Ok(self.to_markdown().into_bytes()),
+            ReportFormat::Html => Ok(self.to_html().into_bytes()),
+            ReportFormat::Pdf => pdf::render(self),
+        }
+    }
+
+    /// File name for the report in `format`, e.g. `AAPL-20241015-0930.pdf`
+    pub fn filename(&self, format: ReportFormat) -> String {
+        let stem: Vec<String> = self
+            .symbols
+            .iter()
+            .map(|symbol| {
+                symbol
+                    .chars()
+                    .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+                    .collect()
+            })
+            .collect();
+        format!(
+            "{}-{}.{}",
+            stem.join("-"),
+            self.generated_at.format("%Y%m%d-%H%M"),
+            format.extension()
+        )
+    }
+
+    /// The report in `format` as a document attachment
+    pub fn to_attachment(&self, format: ReportFormat) -> Result<Attachment> {
+        Ok(Attachment {
+            attachment_type: AttachmentType::Document,
+            content: self.render(format)?,
+            filename: Some(self.filename(format)),
+            mime_type: format.mime_type().to_string(),
+        })
+    }
+}
+
+/// Reply to `/report` on a chat platform
+///
+/// One symbol is analyzed, several compared. On platforms that take files
+/// the reply is a caption and the report in `format`; elsewhere it is the
+/// Markdown report without images.
+pub async fn command_reply(
+    engine: &StockAnalysisEngine,
+    symbols: &[String],
+    format: ReportFormat,
+    platform: BotPlatform,
+    context: &mut AnalysisContext,
+) -> Result<(String, Option<Attachment>)> {
+    let report = match symbols {
+        [] => {
+            return Err(StockError::CommandError(
+                "Missing symbol for report command".to_string(),
+            ));
+        }
+        [symbol] => Report::from_analysis(&engine.analyze_stock(symbol, context).await?, context),
+        _ => Report::from_comparison(&engine.compare_stocks(symbols, context).await?, context),
+    };
+    if !platform.supports_files() {
+        let mut text = report.clone();
+        text.blocks
+            .retain(|block| !matches!(block, Block::Image { .. }));
+        return Ok((text.to_markdown(), None));
+    }
+    let attachment = report.to_attachment(format)?;
+    Ok((format!("📄 {} ({format})", report.title), Some(attachment)))
+}
+
+/// `/report` for a chat user, run after the user's earlier requests
+///
+/// Wraps [`command_reply`] with the user's queue slot and session, which
+/// keeps the symbols the report covered for follow-up questions.
+pub async fn queued_reply(
+    engine: &StockAnalysisEngine,
+    queue: &RequestQueue,
+    sessions: &SessionManager,
+    user_id: &str,
+    symbols: &[String],
+    format: ReportFormat,
+    platform: BotPlatform,
+) -> Result<(String, Option<Attachment>)> {
+    let mut session = sessions.get_or_create(user_id)?;
+    let reply = queue
+        .run(
+            user_id,
+            command_reply(engine, symbols, format, platform, &mut session.context),
+        )
+        .await??;
+    sessions.update(user_id, session)?;
+    Ok(reply)
+}
+
+const STYLESHEET: &str = "body{font-family:-apple-system,'Segoe UI','PingFang SC','Microsoft YaHei',sans-serif;\
+max-width:960px;margin:2em auto;padding:0 1em;color:#1f2328;line-height:1.5}\
+h1{border-bottom:1px solid #d0d7de;padding-bottom:.3em}\
+table{border-collapse:collapse;margin:1em 0}th,td{border:1px solid #d0d7de;padding:4px 10px;text-align:left}\
+th{background:#f6f8fa}pre,code{background:#f6f8fa;font-family:ui-monospace,Menlo,monospace}\
+pre{padding:1em;overflow-x:auto}img{max-width:100%}.note,footer{color:#59636e;font-size:.9em}";
+
+/// Key metrics as a two-column table, without status emoji
+fn metrics_table(metrics: Vec<(String, String)>) -> Block {
+    Block::Table {
+        headers: vec!["Metric".to_string(), "Value".to_string()],
+        rows: metrics
+            .into_iter()
+            .map(|(label, value)| vec![label, strip_emoji(&value)])
+            .collect(),
+    }
+}
+
+/// Highlight lines as paragraphs, emoji left out so every format shows the
+/// same text
+fn highlight_blocks(highlights: &Highlights, language: &Language) -> Vec<Block> {
+    highlights
+        .lines(Markup::Markdown, language)
+        .iter()
+        .map(|line| strip_emoji(line))
+        .flat_map(|line| parse_markdown(&line))
+        .collect()
+}
+
+/// `text` without pictographs, whitespace collapsed
+fn strip_emoji(text: &str) -> String {
+    let kept: String = text
+        .chars()
+        .filter(|c| (*c as u32) < 0x2600 || ((*c as u32) > 0x27BF && (*c as u32) < 0x1F000))
+        .filter(|c| *c != '\u{FE0F}')
+        .collect();
+    kept.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Price and indicator chart of the result's chart data, if it has enough
+/// periods
+fn chart_block(result: &AnalysisResult, locale: &Locale) -> Option<Block> {
+    let chart = PriceChart::from_chart_data(&result.symbol, result.data.get(CHART_DATA_KEY)?)?;
+    let red_for_gains = locale.language == Language::Chinese;
+    match render_png(&chart, red_for_gains, (CHART_WIDTH, CHART_HEIGHT)) {
+        Ok(png) => Some(Block::Image {
+            png,
+            alt: format!("{} price", result.symbol),
+        }),
+        Err(e) => {
+            tracing::warn!("No report chart for {}: {}", result.symbol, e);
+            None
+        }
+    }
+}
+
+/// Sources and warnings of a result
+fn notes(result: &AnalysisResult) -> Vec<Block> {
+    let mut notes = Vec::new();
+    if !result.sources.is_empty() {
+        notes.push(Block::Note(format!(
+            "Sources: {}",
+            result.sources.join(", ")
+        )));
+    }
+    notes.extend(
+        result
+            .warnings
+            .iter()
+            .map(|warning| Block::Note(format!("Warning: {warning}"))),
+    );
+    notes
+}
+
+/// Blocks of agent Markdown
+///
+/// Headings, paragraphs, nested lists, code blocks and tables are kept;
+/// bold, inline code and links survive as span styles, other inline markup
+/// as plain text. Rules are dropped.
+pub fn parse_markdown(markdown: &str) -> Vec<Block> {
+    let mut parser = MarkdownBlocks::default();
+    for event in Parser::new_ext(
+        markdown,
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH,
+    ) {
+        parser.event(event);
+    }
+    parser.blocks
+}
+
+/// State of [`parse_markdown`]
+#[derive(Default)]
+struct MarkdownBlocks {
+    blocks: Vec<Block>,
+    spans: Vec<Span>,
+    bold: usize,
+    links: Vec<String>,
+    /// Next number of each open list (`None` for bullets)
+    lists: Vec<Option<u64>>,
+    /// Depth of the open list items
+    items: usize,
+    code: Option<String>,
+    table: Option<(Vec<String>, Vec<Vec<String>>)>,
+    row: Vec<String>,
+}
+
+impl MarkdownBlocks {
+    fn event(&mut self, event: Event<'_>) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => {
+                self.flush_item();
+                self.code = Some(String::new());
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                if let Some(code) = self.code.take() {
+                    self.blocks
+                        .push(Block::Code(code.trim_end_matches('\n').to_string()));
+                }
+            }
+            Event::Start(Tag::List(start)) => {
+                self.flush_item();
+                self.lists.push(start);
+            }
+            Event::End(TagEnd::List(_)) => {
+                self.lists.pop();
+            }
+            Event::Start(Tag::Item) => {
+                self.flush_item();
+                self.items += 1;
+                if let Some(Some(number)) = self.lists.last_mut() {
+                    self.spans.push(Span::plain(format!("{number}. ")));
+                    *number += 1;
+                }
+            }
+            Event::End(TagEnd::Item) => {
+                self.flush_item();
+                self.items = self.items.saturating_sub(1);
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                let text = take_text(&mut self.spans);
+                if !text.is_empty() {
+                    self.blocks.push(Block::Heading(text));
+                }
+            }
+            Event::End(TagEnd::Paragraph) => {
+                if self.items > 0 {
+                    self.push_span(Span::plain(" "));
+                } else {
+                    let spans = trim_spans(std::mem::take(&mut self.spans));
+                    if !spans.is_empty() {
+                        self.blocks.push(Block::Paragraph(spans));
+                    }
+                }
+            }
+            Event::Start(Tag::Table(_)) => self.table = Some((Vec::new(), Vec::new())),
+            Event::End(TagEnd::TableCell) => {
+                let cell = take_text(&mut self.spans);
+                self.row.push(cell);
+            }
+            Event::End(TagEnd::TableHead) => {
+                if let Some((headers, _)) = &mut self.table {
+                    *headers = std::mem::take(&mut self.row);
+                }
+            }
+            Event::End(TagEnd::TableRow) => {
+                if let Some((_, rows)) = &mut self.table {
+                    rows.push(std::mem::take(&mut self.row));
+                }
+            }
+            Event::End(TagEnd::Table) => {
+                if let Some((headers, rows)) = self.table.take() {
+                    self.blocks.push(Block::Table { headers, rows });
+                }
+            }
+            Event::Start(Tag::Strong) => self.bold += 1,
+            Event::End(TagEnd::Strong) => self.bold = self.bold.saturating_sub(1),
+            Event::Start(Tag::Link { dest_url, .. }) => self.links.push(dest_url.to_string()),
+            Event::End(TagEnd::Link) => {
+                self.links.pop();
+            }
+            Event::Text(text) | Event::Html(text) | Event::InlineHtml(text) => {
+                if let Some(code) = &mut self.code {
+                    code.push_str(&text);
+                } else {
+                    let style = self.style();
+                    self.push_span(Span {
+                        text: text.to_string(),
+                        style,
+                    });
+                }
+            }
+            Event::Code(text) => self.push_span(Span {
+                text: text.to_string(),
+                style: SpanStyle::Code,
+            }),
+            Event::SoftBreak => self.push_span(Span::plain(" ")),
+            Event::HardBreak => self.push_span(Span::plain("\n")),
+            _ => {}
+        }
+    }
+
+    fn style(&self) -> SpanStyle {
+        match (self.links.last(), self.bold) {
+            (Some(url), _) => SpanStyle::Link(url.clone()),
+            (None, 0) => SpanStyle::Plain,
+            (None, _) => SpanStyle::Bold,
+        }
+    }
+
+    /// Add a span, merged into the last one when the style is the same
+    fn push_span(&mut self, span: Span) {
+        match self.spans.last_mut() {
+            Some(last) if last.style == span.style => last.text.push_str(&span.text),
+            _ => self.spans.push(span),
+        }
+    }
+
+    /// Close the open list item, if it has text
+    fn flush_item(&mut self) {
+        if self.items == 0 {
+            return;
+        }
+        let spans = trim_spans(std::mem::take(&mut self.spans));
+        if !spans.is_empty() {
+            self.blocks.push(Block::Bullet {
+                depth: self.lists.len().saturating_sub(1),
+                spans,
+            });
+        }
+    }
+}
+
+/// Plain text of `spans`, which are cleared
+fn take_text(spans: &mut Vec<Span>) -> String {
+    let text: String = spans.drain(..).map(|span| span.text).collect();
+    text.trim().to_string()
+}
+
+/// `spans` without leading and trailing whitespace or empty spans
+fn trim_spans(mut spans: Vec<Span>) -> Vec<Span> {
+    if let Some(first) = spans.first_mut() {
+        first.text = first.text.trim_start().to_string();
+    }
+    if let Some(last) = spans.last_mut() {
+        last.text = last.text.trim_end().to_string();
+    }
+    spans.retain(|span| !span.text.is_empty());
+    spans
+}
+
+fn markdown_spans(spans: &[Span]) -> String {
+    spans
+        .iter()
+        .map(|span| match &span.style {
+            SpanStyle::Plain => span.text.clone(),
+            SpanStyle::Bold => format!("**{}**", span.text),
+            SpanStyle::Code => format!("`{}`", span.text),
+            SpanStyle::Link(url) => format!("[{}]({url})", span.text),
+        })
+        .collect()
+}
+
+fn markdown_table(headers: &[String], rows: &[Vec<String>]) -> String {
+    let cell = |text: &String| text.replace('|', "\\|");
+    let line = |cells: &[String]| {
+        format!(
+            "| {} |",
+            cells.iter().map(cell).collect::<Vec<_>>().join(" | ")
+        )
+    };
+    let mut lines = vec![
+        line(headers),
+        format!("|{}", " --- |".repeat(headers.len().max(1))),
+    ];
+    lines.extend(rows.iter().map(|row| line(row)));
+    lines.join("\n")
+}
+
+fn html_spans(spans: &[Span]) -> String {
+    spans
+        .iter()
+        .map(|span| {
+            let text = escape_html(&span.text).replace('\n', "<br>");
+            match &span.style {
+                SpanStyle::Plain => text,
+                SpanStyle::Bold => format!("<strong>{text}</strong>"),
+                SpanStyle::Code => format!("<code>{text}</code>"),
+                SpanStyle::Link(url) => format!("<a href=\"{}\">{text}</a>", escape_attribute(url)),
+            }
+        })
+        .collect()
+}
+
+fn html_table(headers: &[String], rows: &[Vec<String>]) -> String {
+    let mut html = String::from("<table>\n");
+    if !headers.is_empty() {
+        html.push_str("<tr>");
+        for header in headers {
+            let _ = write!(html, "<th>{}</th>", escape_html(header));
+        }
+        html.push_str("</tr>\n");
+    }
+    for row in rows {
+        html.push_str("<tr>");
+        for cell in row {
+            let _ = write!(html, "<td>{}</td>", escape_html(cell));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>");
+    html
+}
+
+fn escape_attribute(text: &str) -> String {
+    escape_html(text).replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::result::PerformanceMetric;
+    use crate::interface::fixtures;
+
+    #[test]
+    fn test_parse_markdown_blocks() {
+        let blocks = parse_markdown(
+            "## Trend\n\nRSI is **58.3**, see [chart](https://example.com).\n\n\
+             - one\n  - nested `code`\n1. first\n\n```\nraw\n```\n\n| A | B |\n|---|---|\n| 1 | 2 |",
+        );
+        assert_eq!(
+            blocks,
+            vec![
+                Block::Heading("Trend".to_string()),
+                Block::Paragraph(vec![
+                    Span::plain("RSI is "),
+                    Span::bold("58.3"),
+                    Span::plain(", see "),
+                    Span {
+                        text: "chart".to_string(),
+                        style: SpanStyle::Link("https://example.com".to_string())
+                    },
+                    Span::plain("."),
+                ]),
+                Block::Bullet {
+                    depth: 0,
+                    spans: vec![Span::plain("one")]
+                },
+                Block::Bullet {
+                    depth: 1,
+                    spans: vec![
+                        Span::plain("nested "),
+                        Span {
+                            text: "code".to_string(),
+                            style: SpanStyle::Code
+                        }
+                    ],
+                },
+                Block::Bullet {
+                    depth: 0,
+                    spans: vec![Span::plain("1. first")]
+                },
+                Block::Code("raw".to_string()),
+                Block::Table {
+                    headers: vec!["A".to_string(), "B".to_string()],
+                    rows: vec![vec!["1".to_string(), "2".to_string()]],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_analysis_report() {
+        let report = Report::from_analysis(
+            &fixtures::technical_analysis(),
+            &fixtures::analysis_context(),
+        );
+        assert_eq!(report.title, "AAPL Technical Analysis");
+        assert_eq!(report.filename(ReportFormat::Pdf), "AAPL-20240315-1430.pdf");
+        assert!(
+            report.blocks.iter().any(
+                |block| matches!(block, Block::Image { png, .. } if png.starts_with(b"\x89PNG"))
+            )
+        );
+
+        let markdown = report.to_markdown();
+        assert!(markdown.starts_with("# AAPL Technical Analysis\n\n| Metric | Value |\n| --- | --- |\n| Data | Real-time |"), "{markdown}");
+        assert!(
+            markdown.contains("**AAPL** 1d -0.6% · 30d +2.8% · Risk Low"),
+            "{markdown}"
+        );
+        assert!(markdown.contains("![AAPL price](data:image/png;base64,iVBOR"));
+        assert!(
+            markdown.contains("## Trend\n\n- RSI(14): **58.3** (neutral)\n- MACD"),
+            "{markdown}"
+        );
+        assert!(markdown.ends_with("_Sources: Yahoo Finance_\n"));
+
+        let html = report.to_html();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<li style=\"margin-left: 0em\">Price &gt; SMA_50 &amp; SMA_200 &lt;strong momentum&gt;</li>"), "{html}");
+        assert!(html.contains("<img src=\"data:image/png;base64,"));
+        assert!(html.contains("<td>Confidence</td><td>72%</td>"));
+    }
+
+    #[test]
+    fn test_comparison_report() {
+        let mut comparison = ComparisonResult::new(vec!["AAPL".into(), "MSFT".into()])
+            .with_summary("AAPL is cheaper.");
+        comparison.timestamp = fixtures::timestamp();
+        comparison.metrics.performance.insert(
+            "AAPL".into(),
+            PerformanceMetric {
+                return_1m: Some(2.5),
+                ..Default::default()
+            },
+        );
+        comparison
+            .analyses
+            .insert("MSFT".into(), fixtures::fundamental_analysis());
+
+        let report = Report::from_comparison(&comparison, &fixtures::analysis_context());
+        assert_eq!(
+            report.filename(ReportFormat::Html),
+            "AAPL-MSFT-20240315-1430.html"
+        );
+        let markdown = report.to_markdown();
+        assert!(
+            markdown
+                .starts_with("# Comparison: AAPL vs MSFT\n\n**AAPL** 1m +2.5%\n\n| Symbol | 1M |"),
+            "{markdown}"
+        );
+        assert!(
+            markdown.contains("AAPL is cheaper.\n\n## MSFT"),
+            "{markdown}"
+        );
+    }
+
+    #[test]
+    fn test_formats() {
+        assert_eq!(ReportFormat::parse("MD"), Some(ReportFormat::Markdown));
+        assert_eq!(ReportFormat::parse("html"), Some(ReportFormat::Html));
+        assert_eq!(ReportFormat::parse("docx"), None);
+        assert_eq!(ReportFormat::default().mime_type(), "application/pdf");
+
+        let report = Report::from_analysis(
+            &fixtures::technical_analysis(),
+            &fixtures::analysis_context(),
+        );
+        let attachment = report.to_attachment(ReportFormat::Markdown).unwrap();
+        assert_eq!(attachment.attachment_type, AttachmentType::Document);
+        assert_eq!(
+            attachment.filename.as_deref(),
+            Some("AAPL-20240315-1430.md")
+        );
+        assert!(
+            report
+                .render(ReportFormat::Pdf)
+                .unwrap()
+                .starts_with(b"%PDF-1.4")
+        );
+    }
+}
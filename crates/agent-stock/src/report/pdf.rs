@@ -0,0 +1,705 @@
+//! PDF output of reports
+//!
+//! A small PDF 1.4 writer covering what reports need: text in the standard
+//! Helvetica and Courier fonts, Chinese text in the Adobe-GB1
+//! `STSong-Light` font that PDF viewers supply, and PNG images. Only the
+//! images are embedded, so files stay small. Characters outside the Basic
+//! Multilingual Plane, such as emoji, have no glyph in any of the fonts and
+//! are left out.
+//!
+//! Text is wrapped with the Helvetica metrics, at spaces and between CJK
+//! characters, and flows onto new A4 pages with page numbers at the foot.
+
+use std::fmt::Write as _;
+use std::io::Write as _;
+
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+
+use super::{Block, Report, SpanStyle};
+use crate::error::{Result, StockError};
+use crate::interface::markup::align_table;
+
+/// A4 width in points
+const PAGE_WIDTH: f64 = 595.0;
+
+/// A4 height in points
+const PAGE_HEIGHT: f64 = 842.0;
+
+const MARGIN: f64 = 56.0;
+
+const CONTENT_WIDTH: f64 = PAGE_WIDTH - 2.0 * MARGIN;
+
+/// Indent per list level
+const LIST_INDENT: f64 = 14.0;
+
+/// Widths of ASCII 32 to 126 in Helvetica, per 1000 units of font size
+const HELVETICA: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+/// Widths of ASCII 32 to 126 in Helvetica-Bold
+const HELVETICA_BOLD: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611, 975, 722, 722, 722, 722, 667,
+    611, 778, 722, 278, 556, 722, 611, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 333, 278, 333, 584, 556, 333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556,
+    278, 889, 611, 611, 611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Font {
+    Regular,
+    Bold,
+    Mono,
+    /// `STSong-Light`, for everything WinAnsi cannot encode
+    Cjk,
+}
+
+impl Font {
+    fn resource(self) -> &'static str {
+        match self {
+            Self::Regular => "F1",
+            Self::Bold => "F2",
+            Self::Mono => "F3",
+            Self::Cjk => "F4",
+        }
+    }
+
+    /// Width of `c` in points at `size`
+    fn width(self, c: char, size: f64) -> f64 {
+        let units = match self {
+            Self::Cjk => 1000,
+            Self::Mono => 600,
+            Self::Regular | Self::Bold => {
+                let table = if self == Self::Bold {
+                    &HELVETICA_BOLD
+                } else {
+                    &HELVETICA
+                };
+                match c {
+                    ' '..='~' => table[c as usize - 32],
+                    _ => 556,
+                }
+            }
+        };
+        f64::from(units) * size / 1000.0
+    }
+
+    /// Font that shows `c` in text set in `self`, if any does
+    fn for_char(self, c: char) -> Option<Self> {
+        if winansi(c).is_some() {
+            Some(self)
+        } else if u32::from(c) <= 0xFFFF && !c.is_control() {
+            Some(Self::Cjk)
+        } else {
+            None
+        }
+    }
+}
+
+/// WinAnsiEncoding byte of `c`
+fn winansi(c: char) -> Option<u8> {
+    match c {
+        ' '..='~' | '\u{A0}'..='\u{FF}' => u8::try_from(u32::from(c)).ok(),
+        '€' => Some(0x80),
+        '…' => Some(0x85),
+        '‘' => Some(0x91),
+        '’' => Some(0x92),
+        '“' => Some(0x93),
+        '”' => Some(0x94),
+        '•' => Some(0x95),
+        '–' => Some(0x96),
+        '—' => Some(0x97),
+        '™' => Some(0x99),
+        _ => None,
+    }
+}
+
+/// Unbreakable run of text in one font
+#[derive(Debug, Clone)]
+struct Piece {
+    font: Font,
+    text: String,
+    width: f64,
+    space: bool,
+    /// Whether a line may break before this piece
+    breakable: bool,
+}
+
+/// `runs` cut into pieces: words, spaces and single CJK characters, with
+/// words wider than `max_width` split
+fn pieces(runs: &[(Font, String)], size: f64, max_width: f64) -> Vec<Piece> {
+    let mut pieces: Vec<Piece> = Vec::new();
+    let mut after_break = true;
+    for (font, text) in runs {
+        for c in text.chars() {
+            if c == '\n' {
+                pieces.push(Piece {
+                    font: *font,
+                    text: "\n".to_string(),
+                    width: 0.0,
+                    space: true,
+                    breakable: true,
+                });
+                after_break = true;
+                continue;
+            }
+            if c == ' ' || c == '\t' {
+                pieces.push(Piece {
+                    font: *font,
+                    text: " ".to_string(),
+                    width: font.width(' ', size),
+                    space: true,
+                    breakable: true,
+                });
+                after_break = true;
+                continue;
+            }
+            let Some(char_font) = font.for_char(c) else {
+                continue;
+            };
+            let width = char_font.width(c, size);
+            let cjk = char_font == Font::Cjk;
+            match pieces.last_mut() {
+                Some(last)
+                    if !after_break
+                        && !cjk
+                        && last.font == char_font
+                        && last.width + width <= max_width =>
+                {
+                    last.text.push(c);
+                    last.width += width;
+                }
+                _ => pieces.push(Piece {
+                    font: char_font,
+                    text: c.to_string(),
+                    width,
+                    space: false,
+                    breakable: after_break
+                        || cjk
+                        || pieces.last().is_some_and(|last| last.font == Font::Cjk),
+                }),
+            }
+            after_break = cjk;
+        }
+    }
+    pieces
+}
+
+/// `pieces` filled greedily into lines of at most `max_width`
+fn wrap(pieces: Vec<Piece>, max_width: f64) -> Vec<Vec<Piece>> {
+    let mut lines = Vec::new();
+    let mut line: Vec<Piece> = Vec::new();
+    let mut width = 0.0;
+    for piece in pieces {
+        if piece.text == "\n" {
+            lines.push(std::mem::take(&mut line));
+            width = 0.0;
+            continue;
+        }
+        if line.is_empty() && piece.space {
+            continue;
+        }
+        if width + piece.width > max_width && !line.is_empty() {
+            // Carry the unbreakable tail of the line over with the piece
+            let cut = if piece.breakable {
+                line.len()
+            } else {
+                line.iter()
+                    .rposition(|p| p.breakable)
+                    .filter(|&i| i > 0)
+                    .unwrap_or(line.len())
+            };
+            let carried = line.split_off(cut);
+            while line.last().is_some_and(|p| p.space) {
+                line.pop();
+            }
+            lines.push(std::mem::take(&mut line));
+            line = carried.into_iter().skip_while(|p| p.space).collect();
+            width = line.iter().map(|p| p.width).sum();
+            if line.is_empty() && piece.space {
+                continue;
+            }
+        }
+        width += piece.width;
+        line.push(piece);
+    }
+    while line.last().is_some_and(|p| p.space) {
+        line.pop();
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// PDF string of `text` in `font`: a literal for WinAnsi fonts, UCS-2 hex
+/// for the CJK font
+fn pdf_string(font: Font, text: &str) -> String {
+    if font == Font::Cjk {
+        return format!("<{}>", utf16_hex(text));
+    }
+    let mut out = String::from("(");
+    for byte in text.chars().filter_map(winansi) {
+        match byte {
+            b'(' | b')' | b'\\' => {
+                out.push('\\');
+                out.push(char::from(byte));
+            }
+            0x20..=0x7E => out.push(char::from(byte)),
+            _ => {
+                let _ = write!(out, "\\{byte:03o}");
+            }
+        }
+    }
+    out.push(')');
+    out
+}
+
+/// Text string for the document information dictionary
+fn info_string(text: &str) -> String {
+    format!("<FEFF{}>", utf16_hex(text))
+}
+
+/// UTF-16 code units of `text` in hex
+fn utf16_hex(text: &str) -> String {
+    text.encode_utf16().fold(String::new(), |mut hex, unit| {
+        let _ = write!(hex, "{unit:04X}");
+        hex
+    })
+}
+
+/// PNG decoded to zlib-compressed RGB samples
+struct Image {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+impl Image {
+    fn from_png(png: &[u8]) -> Result<Self> {
+        let image = image::load_from_memory(png)
+            .map_err(|e| StockError::Other(format!("Failed to read report image: {e}")))?
+            .to_rgb8();
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(image.as_raw())
+            .and_then(|()| encoder.flush())
+            .map_err(|e| StockError::Other(format!("Failed to compress report image: {e}")))?;
+        let data = encoder
+            .finish()
+            .map_err(|e| StockError::Other(format!("Failed to compress report image: {e}")))?;
+        Ok(Self {
+            width: image.width(),
+            height: image.height(),
+            data,
+        })
+    }
+}
+
+/// Pages being laid out
+struct Layout {
+    pages: Vec<String>,
+    images: Vec<Image>,
+    /// Baseline of the next line on the last page
+    y: f64,
+}
+
+impl Layout {
+    fn new() -> Self {
+        Self {
+            pages: vec![String::new()],
+            images: Vec::new(),
+            y: PAGE_HEIGHT - MARGIN,
+        }
+    }
+
+    fn page(&mut self) -> &mut String {
+        self.pages.last_mut().expect("layout has a page")
+    }
+
+    /// Start a new page unless `height` still fits on this one
+    fn ensure(&mut self, height: f64) {
+        if self.y - height < MARGIN {
+            self.pages.push(String::new());
+            self.y = PAGE_HEIGHT - MARGIN;
+        }
+    }
+
+    fn space(&mut self, points: f64) {
+        self.y -= points;
+    }
+
+    /// Wrapped text, with an optional marker hanging left of the first line
+    fn text(
+        &mut self,
+        runs: &[(Font, String)],
+        size: f64,
+        indent: f64,
+        gray: bool,
+        marker: Option<&str>,
+    ) {
+        let leading = size * 1.4;
+        let max_width = CONTENT_WIDTH - indent;
+        let lines = wrap(pieces(runs, size, max_width), max_width);
+        for (index, line) in lines.iter().enumerate() {
+            self.ensure(leading);
+            let y = self.y - size;
+            self.y -= leading;
+            let mut content = String::new();
+            if gray {
+                content.push_str("0.35 g\n");
+            }
+            if let (0, Some(marker)) = (index, marker) {
+                let _ = writeln!(
+                    content,
+                    "BT /{} {size} Tf {:.2} {y:.2} Td {} Tj ET",
+                    Font::Regular.resource(),
+                    MARGIN + indent - LIST_INDENT * 0.75,
+                    pdf_string(Font::Regular, marker)
+                );
+            }
+            let _ = write!(content, "BT {:.2} {y:.2} Td", MARGIN + indent);
+            let mut font = None;
+            for piece in line {
+                if font != Some(piece.font) {
+                    let _ = write!(content, " /{} {size} Tf", piece.font.resource());
+                    font = Some(piece.font);
+                }
+                let _ = write!(content, " {} Tj", pdf_string(piece.font, &piece.text));
+            }
+            content.push_str(" ET\n");
+            if gray {
+                content.push_str("0 g\n");
+            }
+            self.page().push_str(&content);
+        }
+    }
+
+    /// Preformatted lines in Courier, wrapped at the right margin
+    fn code(&mut self, text: &str, size: f64) {
+        let runs: Vec<(Font, String)> = vec![(Font::Mono, text.replace(' ', "\u{A0}"))];
+        self.text(&runs, size, 0.0, false, None);
+    }
+
+    fn rule(&mut self) {
+        let y = self.y - 4.0;
+        let _ = writeln!(
+            self.page(),
+            "0.8 G 0.5 w {MARGIN:.2} {y:.2} m {:.2} {y:.2} l S 0 G",
+            PAGE_WIDTH - MARGIN
+        );
+        self.y -= 8.0;
+    }
+
+    fn image(&mut self, png: &[u8]) -> Result<()> {
+        let image = Image::from_png(png)?;
+        let width = CONTENT_WIDTH;
+        let height = width * f64::from(image.height) / f64::from(image.width);
+        self.ensure(height);
+        self.y -= height;
+        let name = self.images.len() + 1;
+        let y = self.y;
+        let _ = writeln!(
+            self.page(),
+            "q {width:.2} 0 0 {height:.2} {MARGIN:.2} {y:.2} cm /Im{name} Do Q"
+        );
+        self.images.push(image);
+        Ok(())
+    }
+
+    /// Page numbers at the foot of every page
+    fn number_pages(&mut self) {
+        let count = self.pages.len();
+        for (index, page) in self.pages.iter_mut().enumerate() {
+            let label = format!("{} / {count}", index + 1);
+            let width: f64 = label.chars().map(|c| Font::Regular.width(c, 8.0)).sum();
+            let _ = writeln!(
+                page,
+                "0.35 g BT /F1 8 Tf {:.2} {:.2} Td {} Tj ET 0 g",
+                (PAGE_WIDTH - width) / 2.0,
+                MARGIN / 2.0,
+                pdf_string(Font::Regular, &label)
+            );
+        }
+    }
+}
+
+/// `spans` as font runs; links are followed by their URL
+fn runs(spans: &[super::Span], base: Font) -> Vec<(Font, String)> {
+    spans
+        .iter()
+        .map(|span| match &span.style {
+            SpanStyle::Plain => (base, span.text.clone()),
+            SpanStyle::Bold => (Font::Bold, span.text.clone()),
+            SpanStyle::Code => (Font::Mono, span.text.clone()),
+            SpanStyle::Link(url) if *url != span.text => (base, format!("{} ({url})", span.text)),
+            SpanStyle::Link(_) => (base, span.text.clone()),
+        })
+        .collect()
+}
+
+/// The report as a PDF file
+pub fn render(report: &Report) -> Result<Vec<u8>> {
+    let mut layout = Layout::new();
+    for block in &report.blocks {
+        match block {
+            Block::Title(text) => {
+                layout.text(&[(Font::Bold, text.clone())], 18.0, 0.0, false, None);
+                layout.rule();
+                layout.space(6.0);
+            }
+            Block::Heading(text) => {
+                layout.space(8.0);
+                layout.ensure(40.0);
+                layout.text(&[(Font::Bold, text.clone())], 13.0, 0.0, false, None);
+                layout.space(2.0);
+            }
+            Block::Paragraph(spans) => {
+                layout.text(&runs(spans, Font::Regular), 10.5, 0.0, false, None);
+                layout.space(6.0);
+            }
+            Block::Bullet { depth, spans } => {
+                let indent = LIST_INDENT * (*depth + 1) as f64;
+                layout.text(&runs(spans, Font::Regular), 10.5, indent, false, Some("•"));
+                layout.space(2.0);
+            }
+            Block::Code(text) => {
+                layout.code(text, 9.0);
+                layout.space(6.0);
+            }
+            Block::Table { headers, rows } => {
+                layout.code(&align_table(headers, rows), 9.0);
+                layout.space(6.0);
+            }
+            Block::Image { png, .. } => {
+                layout.image(png)?;
+                layout.space(10.0);
+            }
+            Block::Note(text) => {
+                layout.text(&[(Font::Regular, text.clone())], 9.0, 0.0, true, None);
+                layout.space(2.0);
+            }
+        }
+    }
+    layout.number_pages();
+    Ok(assemble(report, layout))
+}
+
+/// Catalog, fonts, images and pages written out with their cross-reference
+/// table
+fn assemble(report: &Report, layout: Layout) -> Vec<u8> {
+    const FIRST_IMAGE: usize = 9;
+    let first_page = FIRST_IMAGE + layout.images.len();
+    let page_ids: Vec<usize> = (0..layout.pages.len())
+        .map(|i| first_page + 2 * i)
+        .collect();
+
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            page_ids
+                .iter()
+                .map(|id| format!("{id} 0 R"))
+                .collect::<Vec<_>>()
+                .join(" "),
+            page_ids.len()
+        )
+        .into_bytes(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_vec(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+            .to_vec(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>".to_vec(),
+        b"<< /Type /Font /Subtype /Type0 /BaseFont /STSong-Light /Encoding /UniGB-UCS2-H \
+          /DescendantFonts [7 0 R] >>"
+            .to_vec(),
+        b"<< /Type /Font /Subtype /CIDFontType0 /BaseFont /STSong-Light \
+          /CIDSystemInfo << /Registry (Adobe) /Ordering (GB1) /Supplement 2 >> \
+          /FontDescriptor 8 0 R /DW 1000 >>"
+            .to_vec(),
+        b"<< /Type /FontDescriptor /FontName /STSong-Light /Flags 6 /FontBBox [-25 -254 1000 880] \
+          /ItalicAngle 0 /Ascent 880 /Descent -120 /CapHeight 880 /StemV 93 >>"
+            .to_vec(),
+    ];
+
+    let xobjects: String = (0..layout.images.len())
+        .map(|i| format!("/Im{} {} 0 R", i + 1, FIRST_IMAGE + i))
+        .collect::<Vec<_>>()
+        .join(" ");
+    for image in &layout.images {
+        let mut object = format!(
+            "<< /Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB \
+             /BitsPerComponent 8 /Filter /FlateDecode /Length {} >>\nstream\n",
+            image.width,
+            image.height,
+            image.data.len()
+        )
+        .into_bytes();
+        object.extend_from_slice(&image.data);
+        object.extend_from_slice(b"\nendstream");
+        objects.push(object);
+    }
+    for (content, id) in layout.pages.iter().zip(&page_ids) {
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PAGE_WIDTH} {PAGE_HEIGHT}] \
+                 /Resources << /Font << /F1 3 0 R /F2 4 0 R /F3 5 0 R /F4 6 0 R >> /XObject << {xobjects} >> >> \
+                 /Contents {} 0 R >>",
+                id + 1
+            )
+            .into_bytes(),
+        );
+        objects.push(
+            format!(
+                "<< /Length {} >>\nstream\n{content}endstream",
+                content.len()
+            )
+            .into_bytes(),
+        );
+    }
+    objects.push(
+        format!(
+            "<< /Title {} /Producer (agent-stock) /CreationDate (D:{}Z) >>",
+            info_string(&report.title),
+            report.generated_at.format("%Y%m%d%H%M%S")
+        )
+        .into_bytes(),
+    );
+    let info_id = objects.len();
+
+    let mut pdf = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n", index + 1).as_bytes());
+        pdf.extend_from_slice(object);
+        pdf.extend_from_slice(b"\nendobj\n");
+    }
+    let xref = pdf.len();
+    let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(table, "{offset:010} 00000 n ");
+    }
+    let _ = write!(
+        table,
+        "trailer\n<< /Size {} /Root 1 0 R /Info {info_id} 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+        objects.len() + 1
+    );
+    pdf.extend_from_slice(table.as_bytes());
+    pdf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::Span;
+    use chrono::TimeZone;
+
+    fn report(blocks: Vec<Block>) -> Report {
+        Report {
+            title: "AAPL 分析".to_string(),
+            symbols: vec!["AAPL".to_string()],
+            generated_at: chrono::Utc
+                .with_ymd_and_hms(2024, 3, 15, 14, 30, 0)
+                .unwrap(),
+            blocks,
+        }
+    }
+
+    #[test]
+    fn test_wrap_at_spaces_and_cjk() {
+        let runs = [(
+            Font::Regular,
+            "Revenue grew strongly this quarter".to_string(),
+        )];
+        let lines = wrap(pieces(&runs, 10.0, 100.0), 100.0);
+        let text: Vec<String> = lines
+            .iter()
+            .map(|line| line.iter().map(|p| p.text.as_str()).collect())
+            .collect();
+        assert_eq!(text, ["Revenue grew", "strongly this quarter"]);
+
+        let runs = [(Font::Regular, "支撑位 $172.50,短期偏多".to_string())];
+        let lines = wrap(pieces(&runs, 10.0, 60.0), 60.0);
+        let text: Vec<String> = lines
+            .iter()
+            .map(|line| line.iter().map(|p| p.text.as_str()).collect())
+            .collect();
+        assert_eq!(text, ["支撑位", "$172.50,短期", "偏多"]);
+        assert!(
+            lines
+                .iter()
+                .all(|line| line.iter().map(|p| p.width).sum::<f64>() <= 60.0)
+        );
+    }
+
+    #[test]
+    fn test_pdf_strings() {
+        assert_eq!(
+            pdf_string(Font::Regular, "P/E (ttm) \\ 5 • é"),
+            "(P/E \\(ttm\\) \\\\ 5 \\225 \\351)"
+        );
+        assert_eq!(pdf_string(Font::Cjk, "中文"), "<4E2D6587>");
+        assert_eq!(Font::Bold.for_char('🟢'), None);
+        assert_eq!(Font::Bold.for_char('短'), Some(Font::Cjk));
+    }
+
+    #[test]
+    fn test_render_pages() {
+        let paragraph = Block::Paragraph(vec![
+            Span::plain("Margins widened. ".repeat(40)),
+            Span::bold("中文"),
+        ]);
+        let mut blocks = vec![Block::Title("AAPL 分析".to_string())];
+        blocks.extend(vec![paragraph; 8]);
+        let pdf = render(&report(blocks)).unwrap();
+        let text = String::from_utf8_lossy(&pdf);
+
+        assert!(text.starts_with("%PDF-1.4"));
+        assert!(text.ends_with("%%EOF\n"));
+        assert!(text.contains("/Count 2"), "two pages expected");
+        assert!(text.contains("(1 / 2) Tj") && text.contains("(2 / 2) Tj"));
+        assert!(text.contains("/F4 10.5 Tf <4E2D> Tj <6587> Tj"));
+        assert!(text.contains("/Title <FEFF004100410050004C002052066790>"));
+
+        // Every cross-reference offset points at its object
+        let xref = text.rfind("xref\n").unwrap();
+        for (index, line) in text[xref..]
+            .lines()
+            .skip(3)
+            .take_while(|l| l.ends_with(" n "))
+            .enumerate()
+        {
+            let offset: usize = line[..10].parse().unwrap();
+            assert!(
+                text[offset..].starts_with(&format!("{} 0 obj", index + 1)),
+                "object {}",
+                index + 1
+            );
+        }
+    }
+
+    #[test]
+    fn test_render_image() {
+        let mut png = std::io::Cursor::new(Vec::new());
+        image::RgbImage::from_pixel(4, 2, image::Rgb([255, 0, 0]))
+            .write_to(&mut png, image::ImageFormat::Png)
+            .unwrap();
+        let pdf = render(&report(vec![Block::Image {
+            png: png.into_inner(),
+            alt: "chart".to_string(),
+        }]))
+        .unwrap();
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains("/Subtype /Image /Width 4 /Height 2"));
+        assert!(text.contains("/XObject << /Im1 9 0 R >>"));
+        assert!(text.contains("/Im1 Do"));
+    }
+}
@@ -1,9 +1,23 @@
 //! Smart router for directing queries to appropriate agents
 //!
 //! This module provides intelligent routing based on query intent analysis,
-//! supporting both rule-based and keyword-based routing strategies.
+//! supporting both rule-based and keyword-based routing strategies. Queries
+//! the keywords leave ambiguous can optionally be classified by embedding
+//! similarity to example phrasings of each intent (see
+//! [`SmartRouter::with_embeddings`]).
 
 use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+
+use agent_llm::{EmbeddingProvider, cosine_similarity};
+use agent_runtime::RoutingTable;
+use tokio::sync::OnceCell;
+
+use crate::crypto;
+use crate::tools::glossary::{self, GlossaryEntry};
+use crate::tools::theme::{self, ThemeBasket};
+use crate::tools::time_compare::{self, Lookback};
 
 /// Intent types that can be detected from user queries
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -22,24 +36,55 @@ pub enum QueryIntent {
     MacroAnalysis,
     /// Geopolitical analysis
     GeopoliticalAnalysis,
+    /// ESG and sustainability analysis
+    EsgAnalysis,
+    /// Thematic basket analysis (AI, EV, semis)
+    ThemeAnalysis,
+    /// A stock now compared with a past date ("AAPL now vs 6 months ago")
+    TimeComparison,
     /// Comprehensive analysis (multiple agents)
     ComprehensiveAnalysis,
     /// Stock comparison
     Comparison,
+    /// Explanation of a financial term
+    Explain,
     /// General query or unknown intent
     General,
 }
 
 impl QueryIntent {
+    /// Intent name used in agent capabilities and routing tables
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::PriceQuery => "price",
+            Self::TechnicalAnalysis => "technical",
+            Self::FundamentalAnalysis => "fundamental",
+            Self::NewsAnalysis => "news",
+            Self::EarningsAnalysis => "earnings",
+            Self::MacroAnalysis => "macro",
+            Self::GeopoliticalAnalysis => "geopolitical",
+            Self::EsgAnalysis => "esg",
+            Self::ThemeAnalysis => "theme",
+            Self::TimeComparison => "time_comparison",
+            Self::ComprehensiveAnalysis => "comprehensive",
+            Self::Comparison => "comparison",
+            Self::Explain => "explain",
+            Self::General => "general",
+        }
+    }
+
     /// Get the corresponding agent name for this intent
     pub fn agent_name(&self) -> &'static str {
         match self {
-            Self::PriceQuery => "data-fetcher",
+            Self::PriceQuery | Self::Explain => "data-fetcher",
             Self::TechnicalAnalysis => "technical-analyzer",
-            Self::FundamentalAnalysis => "fundamental-analyzer",
+            Self::FundamentalAnalysis | Self::TimeComparison => "fundamental-analyzer",
             Self::NewsAnalysis => "news-analyzer",
             Self::EarningsAnalysis => "earnings-analyzer",
-            Self::MacroAnalysis | Self::GeopoliticalAnalysis => "macro-analyzer",
+            Self::MacroAnalysis | Self::GeopoliticalAnalysis | Self::ThemeAnalysis => {
+                "macro-analyzer"
+            }
+            Self::EsgAnalysis => "esg-analyzer",
             Self::ComprehensiveAnalysis | Self::Comparison | Self::General => "technical-analyzer",
         }
     }
@@ -92,6 +137,7 @@ mod keywords_en {
         "volume",
         "atr",
         "stochastic",
+        "backtest",
     ];
 
     pub const FUNDAMENTAL: &[&str] = &[
@@ -147,6 +193,9 @@ mod keywords_en {
         "cpi",
         "pce",
         "yield curve",
+        "ecb",
+        "eurozone",
+        "euro area",
     ];
 
     pub const GEOPOLITICAL: &[&str] = &[
@@ -160,6 +209,20 @@ mod keywords_en {
         "international",
     ];
 
+    pub const ESG: &[&str] = &[
+        "esg",
+        "sustainab",
+        "carbon",
+        "emission",
+        "climate",
+        "greenhouse",
+        "social responsibility",
+    ];
+
+    pub const THEME: &[&str] = &[
+        "trade", "theme", "basket", "stocks", "names", "sector", "play",
+    ];
+
     pub const COMPREHENSIVE: &[&str] = &[
         "comprehensive",
         "full analysis",
@@ -172,6 +235,18 @@ mod keywords_en {
     ];
 
     pub const COMPARISON: &[&str] = &["compare", "comparison", "versus", "vs", "better", "which"];
+
+    pub const EXPLAIN: &[&str] = &[
+        "what is",
+        "what's",
+        "what are",
+        "what does",
+        "explain",
+        "define",
+        "definition",
+        "meaning of",
+        "how is",
+    ];
 }
 
 /// Keywords for intent classification (Chinese)
@@ -181,6 +256,7 @@ mod keywords_zh {
     pub const TECHNICAL: &[&str] = &[
         "技术分析",
         "技术指标",
+        "回测",
         "均线",
         "移动平均",
         "布林带",
@@ -207,7 +283,14 @@ mod keywords_zh {
     pub const NEWS: &[&str] = &["新闻", "消息", "情绪", "舆情", "公告", "最新消息"];
 
     pub const EARNINGS: &[&str] = &[
-        "财报", "季报", "年报", "财务报告", "盈利", "业绩", "财务报表", "收益报告",
+        "财报",
+        "季报",
+        "年报",
+        "财务报告",
+        "盈利",
+        "业绩",
+        "财务报表",
+        "收益报告",
     ];
 
     pub const MACRO: &[&str] = &[
@@ -220,6 +303,8 @@ mod keywords_zh {
         "加息",
         "降息",
         "货币政策",
+        "欧洲央行",
+        "人民银行",
     ];
 
     pub const GEOPOLITICAL: &[&str] = &[
@@ -232,6 +317,10 @@ mod keywords_zh {
         "战争",
     ];
 
+    pub const ESG: &[&str] = &["可持续", "环保", "碳排放", "碳中和", "社会责任", "公司治理"];
+
+    pub const THEME: &[&str] = &["板块", "概念", "主题", "赛道", "股"];
+
     pub const COMPREHENSIVE: &[&str] = &[
         "综合分析",
         "全面分析",
@@ -242,6 +331,182 @@ mod keywords_zh {
     ];
 
     pub const COMPARISON: &[&str] = &["比较", "对比", "哪个好", "哪只"];
+
+    pub const EXPLAIN: &[&str] = &[
+        "什么是",
+        "是什么",
+        "什么意思",
+        "解释",
+        "含义",
+        "定义",
+        "怎么算",
+    ];
+}
+
+/// Example phrasings of each intent, embedded to classify queries the
+/// keywords miss or leave ambiguous
+const INTENT_EXAMPLES: &[(QueryIntent, &str)] = &[
+    (
+        QueryIntent::PriceQuery,
+        "How much is Apple trading at right now?",
+    ),
+    (QueryIntent::PriceQuery, "What did Tesla close at today?"),
+    (QueryIntent::PriceQuery, "苹果现在多少钱一股"),
+    (
+        QueryIntent::TechnicalAnalysis,
+        "Is NVDA overbought on the daily chart?",
+    ),
+    (
+        QueryIntent::TechnicalAnalysis,
+        "Where would TSLA find a floor if it keeps sliding?",
+    ),
+    (QueryIntent::TechnicalAnalysis, "英伟达的走势怎么样"),
+    (
+        QueryIntent::FundamentalAnalysis,
+        "Is Microsoft expensive relative to what it earns?",
+    ),
+    (
+        QueryIntent::FundamentalAnalysis,
+        "How healthy is Apple's balance of cash and borrowing?",
+    ),
+    (QueryIntent::FundamentalAnalysis, "茅台现在贵不贵"),
+    (
+        QueryIntent::NewsAnalysis,
+        "What's going on with Boeing lately?",
+    ),
+    (QueryIntent::NewsAnalysis, "Why did Tesla drop today?"),
+    (QueryIntent::NewsAnalysis, "特斯拉今天为什么跌"),
+    (
+        QueryIntent::EarningsAnalysis,
+        "How did Amazon do last quarter?",
+    ),
+    (
+        QueryIntent::EarningsAnalysis,
+        "Did Nvidia beat expectations when it reported?",
+    ),
+    (QueryIntent::EarningsAnalysis, "腾讯上个季度赚了多少"),
+    (
+        QueryIntent::MacroAnalysis,
+        "Will rate cuts help growth stocks?",
+    ),
+    (
+        QueryIntent::MacroAnalysis,
+        "How does a strong dollar affect the market?",
+    ),
+    (QueryIntent::MacroAnalysis, "降准对股市有什么影响"),
+    (
+        QueryIntent::GeopoliticalAnalysis,
+        "How would tensions with China hit chipmakers?",
+    ),
+    (
+        QueryIntent::GeopoliticalAnalysis,
+        "What does the election mean for defense stocks?",
+    ),
+    (
+        QueryIntent::GeopoliticalAnalysis,
+        "中美关系对芯片股有什么影响",
+    ),
+    (
+        QueryIntent::EsgAnalysis,
+        "Is Exxon doing anything about its environmental impact?",
+    ),
+    (
+        QueryIntent::EsgAnalysis,
+        "Which big tech company treats its workers best?",
+    ),
+    (QueryIntent::EsgAnalysis, "这家公司环保做得怎么样"),
+    (
+        QueryIntent::ComprehensiveAnalysis,
+        "Give me everything you know about AMD",
+    ),
+    (
+        QueryIntent::ComprehensiveAnalysis,
+        "Should I buy Apple stock?",
+    ),
+    (QueryIntent::ComprehensiveAnalysis, "帮我全面看看腾讯"),
+    (QueryIntent::Comparison, "Is AMD or Intel the better buy?"),
+    (
+        QueryIntent::Comparison,
+        "Should I pick Visa over Mastercard?",
+    ),
+    (QueryIntent::Comparison, "阿里和京东选哪个"),
+];
+
+/// Minimum similarity for an embedding match to override the keywords
+const DEFAULT_SEMANTIC_THRESHOLD: f32 = 0.5;
+
+/// Confidence of a keyword match that had to pick between intents
+const AMBIGUOUS_KEYWORD_CONFIDENCE: f32 = 0.5;
+
+/// Embeddings of the [`INTENT_EXAMPLES`], with the intent of each
+type IntentExamples = Vec<(QueryIntent, Vec<f32>)>;
+
+/// Embedding-based intent classifier
+///
+/// Example embeddings are computed on first use and shared between clones.
+#[derive(Clone)]
+struct SemanticClassifier {
+    provider: Arc<dyn EmbeddingProvider>,
+    examples: Arc<OnceCell<IntentExamples>>,
+}
+
+impl fmt::Debug for SemanticClassifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SemanticClassifier")
+            .field("examples_embedded", &self.examples.initialized())
+            .finish_non_exhaustive()
+    }
+}
+
+impl SemanticClassifier {
+    fn new(provider: Arc<dyn EmbeddingProvider>) -> Self {
+        Self {
+            provider,
+            examples: Arc::new(OnceCell::new()),
+        }
+    }
+
+    /// Similarity of `query` to each intent, best first
+    ///
+    /// An intent scores the similarity of its closest example.
+    async fn scores(&self, query: &str) -> agent_llm::Result<Vec<(QueryIntent, f32)>> {
+        let examples = self
+            .examples
+            .get_or_try_init(|| async {
+                let texts: Vec<String> = INTENT_EXAMPLES
+                    .iter()
+                    .map(|(_, text)| (*text).to_string())
+                    .collect();
+                let vectors = self.provider.embed(&texts).await?;
+                Ok::<_, agent_llm::LLMError>(
+                    INTENT_EXAMPLES
+                        .iter()
+                        .map(|(intent, _)| *intent)
+                        .zip(vectors)
+                        .collect(),
+                )
+            })
+            .await?;
+
+        let query = self
+            .provider
+            .embed(&[query.to_string()])
+            .await?
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+
+        let mut scores: Vec<(QueryIntent, f32)> = Vec::new();
+        for (intent, vector) in examples {
+            let similarity = cosine_similarity(&query, vector);
+            match scores.iter_mut().find(|(seen, _)| seen == intent) {
+                Some((_, best)) => *best = best.max(similarity),
+                None => scores.push((*intent, similarity)),
+            }
+        }
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+        Ok(scores)
+    }
 }
 
 /// Smart router for query intent classification
@@ -249,6 +514,14 @@ mod keywords_zh {
 pub struct SmartRouter {
     /// Enable debug logging
     debug: bool,
+    /// Agents able to handle each intent, from their declared capabilities
+    routing_table: Option<RoutingTable>,
+    /// Plugin analyzer keys and the keywords routing to them
+    plugin_routes: Vec<(String, Vec<String>)>,
+    /// Embedding classifier for ambiguous queries
+    semantic: Option<SemanticClassifier>,
+    /// Minimum similarity for the embedding classifier to be trusted
+    semantic_threshold: f32,
 }
 
 impl Default for SmartRouter {
@@ -260,7 +533,42 @@ impl Default for SmartRouter {
 impl SmartRouter {
     /// Create a new smart router
     pub fn new() -> Self {
-        Self { debug: false }
+        Self {
+            debug: false,
+            routing_table: None,
+            plugin_routes: Vec::new(),
+            semantic: None,
+            semantic_threshold: DEFAULT_SEMANTIC_THRESHOLD,
+        }
+    }
+
+    /// Route queries containing any of `keywords` to a plugin analyzer
+    ///
+    /// Plugin routes are checked before the built-in intents, in the order
+    /// they were added.
+    pub fn with_plugin_route(mut self, key: impl Into<String>, keywords: Vec<String>) -> Self {
+        let keywords = keywords.into_iter().map(|kw| kw.to_lowercase()).collect();
+        self.plugin_routes.push((key.into(), keywords));
+        self
+    }
+
+    /// Key of the plugin analyzer a query is routed to, if any
+    pub fn plugin_route(&self, query: &str) -> Option<&str> {
+        let query_lower = query.to_lowercase();
+        self.plugin_routes
+            .iter()
+            .find(|(_, keywords)| keywords.iter().any(|kw| query_lower.contains(kw.as_str())))
+            .map(|(key, _)| key.as_str())
+    }
+
+    /// Route single-agent intents through a table built from agent
+    /// capabilities (see [`agent_runtime::AgentRegistry::routing_table`])
+    ///
+    /// Intents missing from the table, e.g. because their agent lacks an API
+    /// key, fall back to [`QueryIntent::agent_name`].
+    pub fn with_routing_table(mut self, table: RoutingTable) -> Self {
+        self.routing_table = Some(table);
+        self
     }
 
     /// Enable debug mode
@@ -269,8 +577,42 @@ impl SmartRouter {
         self
     }
 
+    /// Classify ambiguous queries by embedding similarity in
+    /// [`route_semantic`](Self::route_semantic)
+    ///
+    /// Queries the keywords route unambiguously never reach the provider.
+    pub fn with_embeddings(mut self, provider: Arc<dyn EmbeddingProvider>) -> Self {
+        self.semantic = Some(SemanticClassifier::new(provider));
+        self
+    }
+
+    /// Set the minimum cosine similarity (default 0.5) an embedding match
+    /// needs before it overrides the keyword classification
+    pub fn with_semantic_threshold(mut self, threshold: f32) -> Self {
+        self.semantic_threshold = threshold;
+        self
+    }
+
     /// Classify the intent of a query
     pub fn classify(&self, query: &str) -> QueryIntent {
+        self.classify_scored(query).0
+    }
+
+    /// Classify the intent of a query by keywords, with a confidence
+    ///
+    /// Confidence is 1.0 for a single clear match, 0.5 when several intents
+    /// matched and one had to be picked, and 0.0 for [`QueryIntent::General`].
+    pub fn classify_scored(&self, query: &str) -> (QueryIntent, f32) {
+        if self.explain_term(query).is_some() {
+            return (QueryIntent::Explain, 1.0);
+        }
+        if self.theme(query).is_some() {
+            return (QueryIntent::ThemeAnalysis, 1.0);
+        }
+        if self.time_comparison(query).is_some() {
+            return (QueryIntent::TimeComparison, 1.0);
+        }
+
         let query_lower = query.to_lowercase();
         let intents = self.detect_all_intents(&query_lower);
 
@@ -279,16 +621,30 @@ impl SmartRouter {
         }
 
         // Priority-based intent selection
-        if intents.contains(&QueryIntent::ComprehensiveAnalysis) || intents.len() > 2 {
-            return QueryIntent::ComprehensiveAnalysis;
+        if intents.contains(&QueryIntent::ComprehensiveAnalysis) {
+            return (QueryIntent::ComprehensiveAnalysis, 1.0);
+        }
+        if intents.len() > 2 {
+            return (
+                QueryIntent::ComprehensiveAnalysis,
+                AMBIGUOUS_KEYWORD_CONFIDENCE,
+            );
         }
 
         if intents.contains(&QueryIntent::Comparison) {
-            return QueryIntent::Comparison;
+            return (QueryIntent::Comparison, 1.0);
         }
 
         // Return the first detected intent, or General if none
-        intents.into_iter().next().unwrap_or(QueryIntent::General)
+        let confidence = match intents.len() {
+            0 => 0.0,
+            1 => 1.0,
+            _ => AMBIGUOUS_KEYWORD_CONFIDENCE,
+        };
+        (
+            intents.into_iter().next().unwrap_or(QueryIntent::General),
+            confidence,
+        )
     }
 
     /// Detect all matching intents from a query
@@ -317,6 +673,9 @@ impl SmartRouter {
         if Self::matches_any(query, keywords_en::GEOPOLITICAL) {
             intents.insert(QueryIntent::GeopoliticalAnalysis);
         }
+        if Self::matches_any(query, keywords_en::ESG) {
+            intents.insert(QueryIntent::EsgAnalysis);
+        }
         if Self::matches_any(query, keywords_en::COMPREHENSIVE) {
             intents.insert(QueryIntent::ComprehensiveAnalysis);
         }
@@ -346,6 +705,9 @@ impl SmartRouter {
         if Self::matches_any(query, keywords_zh::GEOPOLITICAL) {
             intents.insert(QueryIntent::GeopoliticalAnalysis);
         }
+        if Self::matches_any(query, keywords_zh::ESG) {
+            intents.insert(QueryIntent::EsgAnalysis);
+        }
         if Self::matches_any(query, keywords_zh::COMPREHENSIVE) {
             intents.insert(QueryIntent::ComprehensiveAnalysis);
         }
@@ -356,39 +718,107 @@ impl SmartRouter {
         intents
     }
 
+    /// Glossary term the query asks to have explained, if any
+    ///
+    /// A query is an explanation request when it uses explain phrasing
+    /// ("what is", "什么是"), mentions a glossary term, and names no stock
+    /// symbol other than the term itself ("What is the P/E of MSFT?" is a
+    /// fundamental query, "What is RSI?" is not).
+    pub fn explain_term(&self, query: &str) -> Option<&'static GlossaryEntry> {
+        let query_lower = query.to_lowercase();
+        if !Self::matches_any(&query_lower, keywords_en::EXPLAIN)
+            && !Self::matches_any(&query_lower, keywords_zh::EXPLAIN)
+        {
+            return None;
+        }
+
+        let entry = glossary::find_term(&query_lower)?;
+        let names_stock = self.extract_symbols(query).iter().any(|symbol| {
+            let symbol = symbol.to_lowercase();
+            !entry.aliases.contains(&symbol.as_str())
+        });
+        (!names_stock).then_some(entry)
+    }
+
+    /// Thematic basket the query asks about, if any
+    ///
+    /// A query is about a theme when it names one ("AI", "电动车") together
+    /// with theme phrasing ("trade", "stocks", "板块") and no stock symbol
+    /// other than the theme itself ("How is the AI trade doing?" is a theme
+    /// query, "Is NVDA an AI stock?" is not).
+    pub fn theme(&self, query: &str) -> Option<&'static ThemeBasket> {
+        let query_lower = query.to_lowercase();
+        if !Self::matches_any(&query_lower, keywords_en::THEME)
+            && !Self::matches_any(&query_lower, keywords_zh::THEME)
+        {
+            return None;
+        }
+
+        let basket = theme::find_theme(&query_lower)?;
+        let names_stock = self.extract_symbols(query).iter().any(|symbol| {
+            let symbol = symbol.to_lowercase();
+            symbol != basket.key && !basket.aliases.contains(&symbol.as_str())
+        });
+        (!names_stock).then_some(basket)
+    }
+
+    /// Symbol and lookback of a "now vs then" query, if any
+    ///
+    /// A query is a time comparison when it names exactly one stock and a
+    /// point in the past ("AAPL now vs 6 months ago", "AAPL 和半年前相比").
+    pub fn time_comparison(&self, query: &str) -> Option<(String, Lookback)> {
+        let lookback = time_compare::parse_lookback(query)?;
+        match self.extract_symbols(query).as_slice() {
+            [symbol] => Some((symbol.clone(), lookback)),
+            _ => None,
+        }
+    }
+
     /// Check if query contains any of the keywords
     fn matches_any(query: &str, keywords: &[&str]) -> bool {
         keywords.iter().any(|kw| query.contains(kw))
     }
 
     /// Get agents to invoke based on intent
-    pub fn get_agents(&self, intent: QueryIntent) -> Vec<&'static str> {
+    pub fn get_agents(&self, intent: QueryIntent) -> Vec<&str> {
         if intent.requires_multiple_agents() {
-            QueryIntent::comprehensive_agents()
-        } else {
-            vec![intent.agent_name()]
+            return QueryIntent::comprehensive_agents();
+        }
+        match self
+            .routing_table
+            .as_ref()
+            .and_then(|table| table.get(intent.as_str()))
+        {
+            Some(agents) if !agents.is_empty() => agents.iter().map(String::as_str).collect(),
+            _ => vec![intent.agent_name()],
         }
     }
 
     /// Extract stock symbols from a query
+    ///
+    /// Crypto assets come back as Yahoo pairs (`BTC-USD`), whether written as
+    /// a pair, an unambiguous bare ticker or a coin name ("bitcoin", "比特币").
     pub fn extract_symbols(&self, query: &str) -> Vec<String> {
         let mut symbols = Vec::new();
 
         // Common patterns for stock symbols
         // 1. Explicit mentions like "AAPL", "GOOGL", etc. (uppercase, 1-5 letters)
-        // 2. After keywords like "analyze", "分析", etc.
+        // 2. Crypto pairs like "BTC-USD" or "ETH/USDT"
+        // 3. After keywords like "analyze", "分析", etc.
 
         for word in query.split_whitespace() {
             let clean_word = word.trim_matches(|c: char| !c.is_alphanumeric());
 
-            // Check if it looks like a US stock symbol (1-5 uppercase letters)
-            if !clean_word.is_empty()
+            // Check if it looks like a US stock symbol (1-5 uppercase letters);
+            // bare coin tickers like "BTC" become their pair
+            let stock_like = !clean_word.is_empty()
                 && clean_word.len() <= 5
-                && clean_word.chars().all(|c| c.is_ascii_uppercase())
-            {
-                symbols.push(clean_word.to_string());
+                && clean_word.chars().all(|c| c.is_ascii_uppercase());
+            if stock_like || crypto::is_crypto(clean_word) {
+                symbols.push(crypto::normalize_symbol(clean_word));
             }
         }
+        symbols.extend(crypto::find_in_query(query));
 
         // Remove duplicates
         symbols.sort();
@@ -408,20 +838,93 @@ pub struct RoutingResult {
     pub symbols: Vec<String>,
     /// Whether this requires parallel execution
     pub parallel: bool,
+    /// How sure the classifier is of the intent, from 0.0 to 1.0
+    ///
+    /// Keyword confidence for keyword routes, cosine similarity to the
+    /// closest example phrasing for semantic ones.
+    pub confidence: f32,
+    /// Whether the intent came from embedding similarity instead of keywords
+    pub semantic: bool,
 }
 
 impl SmartRouter {
     /// Route a query and return the full routing result
+    ///
+    /// Queries matching a plugin route go to that plugin analyzer alone.
     pub fn route(&self, query: &str) -> RoutingResult {
-        let intent = self.classify(query);
-        let agents = self.get_agents(intent);
-        let symbols = self.extract_symbols(query);
+        let (intent, confidence) = self.classify_scored(query);
+
+        if let Some(key) = self.plugin_route(query) {
+            return RoutingResult {
+                intent,
+                agents: vec![key.to_string()],
+                symbols: self.extract_symbols(query),
+                parallel: false,
+                confidence: 1.0,
+                semantic: false,
+            };
+        }
 
+        self.routing_result(query, intent, confidence, false)
+    }
+
+    /// Route a query, classifying it by embedding similarity when the
+    /// keywords are not sure
+    ///
+    /// Keyword routes with full confidence are returned as is. Otherwise the
+    /// intent whose example phrasings are closest to the query wins if its
+    /// similarity reaches the threshold and beats the keyword confidence.
+    /// Without [`with_embeddings`](Self::with_embeddings), or when the
+    /// provider fails, this is the same as [`route`](Self::route).
+    pub async fn route_semantic(&self, query: &str) -> RoutingResult {
+        let keyword = self.route(query);
+        let Some(semantic) = &self.semantic else {
+            return keyword;
+        };
+        if keyword.confidence >= 1.0 {
+            return keyword;
+        }
+
+        let scores = match semantic.scores(query).await {
+            Ok(scores) => scores,
+            Err(e) => {
+                tracing::warn!("Embedding routing failed, using keywords: {e}");
+                return keyword;
+            }
+        };
+        if self.debug {
+            tracing::debug!("Semantic intent scores for query: {:?}", scores);
+        }
+
+        match scores.first() {
+            Some(&(intent, similarity))
+                if similarity >= self.semantic_threshold && similarity > keyword.confidence =>
+            {
+                self.routing_result(query, intent, similarity, true)
+            }
+            _ => keyword,
+        }
+    }
+
+    /// Routing result for a classified intent
+    fn routing_result(
+        &self,
+        query: &str,
+        intent: QueryIntent,
+        confidence: f32,
+        semantic: bool,
+    ) -> RoutingResult {
+        let agents = self.get_agents(intent);
         RoutingResult {
             intent,
-            agents: agents.iter().map(std::string::ToString::to_string).collect(),
-            symbols,
+            agents: agents
+                .iter()
+                .map(std::string::ToString::to_string)
+                .collect(),
+            symbols: self.extract_symbols(query),
             parallel: intent.requires_multiple_agents(),
+            confidence,
+            semantic,
         }
     }
 }
@@ -473,6 +976,90 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_esg_analysis_detection() {
+        let router = SmartRouter::new();
+
+        assert_eq!(
+            router.classify("How sustainable is TSLA?"),
+            QueryIntent::EsgAnalysis
+        );
+        assert_eq!(router.classify("AAPL ESG score"), QueryIntent::EsgAnalysis);
+        assert_eq!(
+            router.classify("特斯拉的碳排放情况"),
+            QueryIntent::EsgAnalysis
+        );
+        assert_eq!(
+            router.route("How sustainable is TSLA?").agents,
+            vec!["esg-analyzer"]
+        );
+    }
+
+    #[test]
+    fn test_theme_analysis_detection() {
+        let router = SmartRouter::new();
+
+        assert_eq!(
+            router.classify("How is the AI trade doing?"),
+            QueryIntent::ThemeAnalysis
+        );
+        assert_eq!(
+            router.theme("How is the AI trade doing?").unwrap().key,
+            "ai"
+        );
+        assert_eq!(router.theme("Are EV stocks recovering?").unwrap().key, "ev");
+        assert_eq!(
+            router.theme("semiconductor sector this month").unwrap().key,
+            "semis"
+        );
+        assert_eq!(router.theme("半导体板块最近怎么样").unwrap().key, "semis");
+        assert_eq!(
+            router.route("How is the AI trade doing?").agents,
+            vec!["macro-analyzer"]
+        );
+
+        // Naming a stock makes it a stock query, not a theme query
+        assert!(router.theme("Is NVDA an AI stock?").is_none());
+        assert!(router.theme("Tell me about AI").is_none());
+        assert_ne!(
+            router.classify("Latest news on TSLA stock"),
+            QueryIntent::ThemeAnalysis
+        );
+    }
+
+    #[test]
+    fn test_time_comparison_detection() {
+        let router = SmartRouter::new();
+
+        assert_eq!(
+            router.classify("AAPL now vs 6 months ago"),
+            QueryIntent::TimeComparison
+        );
+        assert_eq!(
+            router.time_comparison("How does NVDA compare to a year ago?"),
+            Some(("NVDA".to_string(), Lookback::Months(12)))
+        );
+        assert_eq!(
+            router.classify("AAPL 和半年前相比"),
+            QueryIntent::TimeComparison
+        );
+        assert_eq!(
+            router.route("AAPL now vs 6 months ago").agents,
+            vec!["fundamental-analyzer"]
+        );
+
+        // Two stocks is a comparison between them
+        assert!(
+            router
+                .time_comparison("AAPL vs MSFT 6 months ago")
+                .is_none()
+        );
+        assert_eq!(
+            router.classify("Compare AAPL and GOOGL"),
+            QueryIntent::Comparison
+        );
+    }
+
     #[test]
     fn test_comprehensive_analysis_detection() {
         let router = SmartRouter::new();
@@ -495,9 +1082,33 @@ mod tests {
             router.classify("Compare AAPL and GOOGL"),
             QueryIntent::Comparison
         );
+        assert_eq!(router.classify("比较苹果和微软"), QueryIntent::Comparison);
+    }
+
+    #[test]
+    fn test_explain_detection() {
+        let router = SmartRouter::new();
+
         assert_eq!(
-            router.classify("比较苹果和微软"),
-            QueryIntent::Comparison
+            router.classify("What is a PEG ratio?"),
+            QueryIntent::Explain
+        );
+        assert_eq!(router.classify("What is RSI?"), QueryIntent::Explain);
+        assert_eq!(router.classify("什么是市盈率"), QueryIntent::Explain);
+        assert_eq!(
+            router.explain_term("explain free cash flow").unwrap().term,
+            "Free Cash Flow"
+        );
+
+        // Naming a stock makes it a data query, not an explanation
+        assert_eq!(
+            router.classify("What is the P/E ratio of MSFT?"),
+            QueryIntent::FundamentalAnalysis
+        );
+        // Explain phrasing without a known term
+        assert_eq!(
+            router.classify("What is the price of AAPL?"),
+            QueryIntent::PriceQuery
         );
     }
 
@@ -513,6 +1124,33 @@ mod tests {
         assert!(symbols.contains(&"MSFT".to_string()));
     }
 
+    #[test]
+    fn test_crypto_symbol_extraction() {
+        let router = SmartRouter::new();
+
+        let symbols = router.extract_symbols("Compare BTC-USD and eth/usdt");
+        assert_eq!(symbols, vec!["BTC-USD", "ETH-USDT"]);
+
+        // Coin names and bare tickers become USD pairs
+        assert_eq!(
+            router.extract_symbols("Is bitcoin overbought?"),
+            vec!["BTC-USD"]
+        );
+        assert_eq!(
+            router.extract_symbols("ETH vs AAPL"),
+            vec!["AAPL", "ETH-USD"]
+        );
+
+        let result = router.route("比特币的技术分析");
+        assert_eq!(result.intent, QueryIntent::TechnicalAnalysis);
+        assert_eq!(result.symbols, vec!["BTC-USD"]);
+        assert_eq!(result.agents, vec!["technical-analyzer"]);
+
+        let result = router.route("Compare BTC-USD vs ETH-USD");
+        assert_eq!(result.intent, QueryIntent::Comparison);
+        assert_eq!(result.symbols, vec!["BTC-USD", "ETH-USD"]);
+    }
+
     #[test]
     fn test_routing_result() {
         let router = SmartRouter::new();
@@ -523,6 +1161,90 @@ mod tests {
         assert!(result.agents.len() > 1);
     }
 
+    #[test]
+    fn test_keyword_confidence() {
+        let router = SmartRouter::new();
+
+        assert_eq!(
+            router.classify_scored("Calculate RSI for AAPL"),
+            (QueryIntent::TechnicalAnalysis, 1.0)
+        );
+        assert_eq!(
+            router.classify_scored("Thoughts on AAPL?"),
+            (QueryIntent::General, 0.0)
+        );
+        let (_, confidence) = router.classify_scored("AAPL dividend news");
+        assert!(confidence < 1.0);
+        assert!((router.route("Compare AAPL vs MSFT").confidence - 1.0).abs() < f32::EPSILON);
+    }
+
+    /// Embeds texts by counting words from a few fixed topics
+    struct TopicEmbeddings {
+        fail: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl EmbeddingProvider for TopicEmbeddings {
+        async fn embed(&self, texts: &[String]) -> agent_llm::Result<Vec<Vec<f32>>> {
+            const TOPICS: &[&[&str]] = &[
+                &["floor", "slid", "overbought", "chart"],
+                &["reported", "quarter", "expectations"],
+                &["dollar", "rate", "market"],
+            ];
+            if self.fail {
+                return Err(agent_llm::LLMError::RequestFailed("offline".to_string()));
+            }
+            Ok(texts
+                .iter()
+                .map(|text| {
+                    let text = text.to_lowercase();
+                    TOPICS
+                        .iter()
+                        .map(|words| words.iter().filter(|w| text.contains(*w)).count() as f32)
+                        .collect()
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_semantic_routing_for_ambiguous_query() {
+        let router = SmartRouter::new().with_embeddings(Arc::new(TopicEmbeddings { fail: false }));
+
+        let result = router
+            .route_semantic("Where does NVDA find a floor on this slide?")
+            .await;
+        assert_eq!(result.intent, QueryIntent::TechnicalAnalysis);
+        assert!(result.semantic);
+        assert!(result.confidence >= DEFAULT_SEMANTIC_THRESHOLD);
+        assert_eq!(result.agents, vec!["technical-analyzer"]);
+        assert_eq!(result.symbols, vec!["NVDA"]);
+
+        // Confident keyword routes skip the embeddings
+        let result = router.route_semantic("Calculate RSI for AAPL").await;
+        assert_eq!(result.intent, QueryIntent::TechnicalAnalysis);
+        assert!(!result.semantic);
+    }
+
+    #[tokio::test]
+    async fn test_semantic_routing_falls_back_to_keywords() {
+        let query = "Where does NVDA find a floor on this slide?";
+
+        let failing = SmartRouter::new().with_embeddings(Arc::new(TopicEmbeddings { fail: true }));
+        let result = failing.route_semantic(query).await;
+        assert_eq!(result.intent, QueryIntent::General);
+        assert!(!result.semantic);
+
+        // Nothing similar enough to any example
+        let strict = SmartRouter::new()
+            .with_embeddings(Arc::new(TopicEmbeddings { fail: false }))
+            .with_semantic_threshold(1.1);
+        assert!(!strict.route_semantic(query).await.semantic);
+
+        let unconfigured = SmartRouter::new().route_semantic(query).await;
+        assert_eq!(unconfigured.intent, QueryIntent::General);
+    }
+
     #[test]
     fn test_agent_mapping() {
         assert_eq!(QueryIntent::PriceQuery.agent_name(), "data-fetcher");
@@ -535,4 +1257,43 @@ mod tests {
             "fundamental-analyzer"
         );
     }
+
+    #[test]
+    fn test_plugin_route() {
+        let router = SmartRouter::new().with_plugin_route(
+            "crypto-analyzer",
+            vec!["Crypto".to_string(), "比特币".to_string()],
+        );
+
+        assert_eq!(
+            router.plugin_route("How much crypto does MSTR hold?"),
+            Some("crypto-analyzer")
+        );
+        assert_eq!(
+            router.plugin_route("比特币对COIN的影响"),
+            Some("crypto-analyzer")
+        );
+        assert_eq!(router.plugin_route("Price of AAPL"), None);
+
+        let result = router.route("Full crypto analysis of COIN");
+        assert_eq!(result.agents, vec!["crypto-analyzer"]);
+        assert!(!result.parallel);
+    }
+
+    #[test]
+    fn test_routing_table_overrides_agent_name() {
+        let mut table = RoutingTable::new();
+        table.insert("news".to_string(), vec!["custom-news".to_string()]);
+        let router = SmartRouter::new().with_routing_table(table);
+
+        assert_eq!(
+            router.get_agents(QueryIntent::NewsAnalysis),
+            vec!["custom-news"]
+        );
+        // Intents missing from the table keep their built-in agent
+        assert_eq!(
+            router.get_agents(QueryIntent::MacroAnalysis),
+            vec!["macro-analyzer"]
+        );
+    }
 }
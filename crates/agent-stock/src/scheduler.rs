@@ -0,0 +1,131 @@
+//! Scheduling of recurring background jobs
+//!
+//! Jobs such as the daily market wrap run once a day at a fixed UTC time,
+//! optionally on weekdays only. [`spawn_daily`] runs a job on a
+//! [`DailySchedule`] in a background tokio task.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::scheduler::{DailySchedule, spawn_daily};
+//!
+//! // 21:15 UTC is after the US close in both summer and winter time
+//! let schedule = DailySchedule::parse("21:15")?.weekdays_only(true);
+//! spawn_daily(schedule, || async { println!("Market closed") });
+//! ```
+
+use chrono::{DateTime, Datelike, Duration, NaiveTime, Utc, Weekday};
+use std::future::Future;
+
+use crate::error::{Result, StockError};
+
+/// A time of day, in UTC, at which a job runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DailySchedule {
+    time: NaiveTime,
+    weekdays_only: bool,
+}
+
+impl DailySchedule {
+    /// Run every day at `time` (UTC)
+    pub fn at(time: NaiveTime) -> Self {
+        Self {
+            time,
+            weekdays_only: false,
+        }
+    }
+
+    /// Parse a time of day given as "HH:MM" (UTC)
+    pub fn parse(time: &str) -> Result<Self> {
+        NaiveTime::parse_from_str(time.trim(), "%H:%M")
+            .map(Self::at)
+            .map_err(|_| {
+                StockError::ConfigError(format!("Invalid schedule time: {time}. Use HH:MM (UTC)"))
+            })
+    }
+
+    /// Skip Saturdays and Sundays
+    pub fn weekdays_only(mut self, weekdays_only: bool) -> Self {
+        self.weekdays_only = weekdays_only;
+        self
+    }
+
+    /// Time of day the job runs at (UTC)
+    pub fn time(&self) -> NaiveTime {
+        self.time
+    }
+
+    /// First run strictly after `now`
+    pub fn next_run_after(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let mut date = now.date_naive();
+        if date.and_time(self.time).and_utc() <= now {
+            date += Duration::days(1);
+        }
+        while self.weekdays_only && matches!(date.weekday(), Weekday::Sat | Weekday::Sun) {
+            date += Duration::days(1);
+        }
+        date.and_time(self.time).and_utc()
+    }
+}
+
+/// Run `job` on `schedule` in the background, forever
+pub fn spawn_daily<F, Fut>(schedule: DailySchedule, job: F) -> tokio::task::JoinHandle<()>
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send,
+{
+    tokio::spawn(async move {
+        loop {
+            let now = Utc::now();
+            let next = schedule.next_run_after(now);
+            let wait = (next - now).to_std().unwrap_or_default();
+            tracing::debug!("Next scheduled run at {}", next);
+            tokio::time::sleep(wait).await;
+            job().await;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_next_run_after() {
+        let schedule = DailySchedule::parse("21:15").unwrap();
+
+        // Wednesday before and after the run time
+        let before = Utc.with_ymd_and_hms(2024, 3, 13, 20, 0, 0).unwrap();
+        assert_eq!(
+            schedule.next_run_after(before),
+            Utc.with_ymd_and_hms(2024, 3, 13, 21, 15, 0).unwrap()
+        );
+        let after = Utc.with_ymd_and_hms(2024, 3, 13, 21, 15, 0).unwrap();
+        assert_eq!(
+            schedule.next_run_after(after),
+            Utc.with_ymd_and_hms(2024, 3, 14, 21, 15, 0).unwrap()
+        );
+
+        // Friday evening skips the weekend
+        let friday = Utc.with_ymd_and_hms(2024, 3, 15, 22, 0, 0).unwrap();
+        assert_eq!(
+            schedule.weekdays_only(true).next_run_after(friday),
+            Utc.with_ymd_and_hms(2024, 3, 18, 21, 15, 0).unwrap()
+        );
+        assert_eq!(
+            schedule.next_run_after(friday),
+            Utc.with_ymd_and_hms(2024, 3, 16, 21, 15, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            DailySchedule::parse(" 09:30 ").unwrap().time(),
+            NaiveTime::from_hms_opt(9, 30, 0).unwrap()
+        );
+        assert!(DailySchedule::parse("25:00").is_err());
+        assert!(DailySchedule::parse("noon").is_err());
+    }
+}
@@ -0,0 +1,800 @@
+//! Stock screener
+//!
+//! A [`Screen`] is a list of filters such as `pe<20`, `market_cap>10B`,
+//! `rsi<30` or `sector=Technology`, checked against a [`Snapshot`] of each
+//! symbol in a universe. Symbols passing every filter are ranked by a sort
+//! metric: the one given with `sort=`, otherwise the first numeric filter's,
+//! most extreme first (lowest P/E for `pe<20`, largest company for
+//! `market_cap>10B`).
+//!
+//! P/E, market cap, dividend yield, sector and industry come from Alpha
+//! Vantage's company overview; price and RSI from Yahoo daily bars. The
+//! [`ScreenerTool`](crate::tools::ScreenerTool) only fetches the data a
+//! screen needs.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{Result, StockError};
+use crate::units::{Scale, format_usd};
+
+/// Environment variable with the symbols to scan, comma separated
+pub const SCREENER_UNIVERSE_ENV: &str = "STOCK_SCREENER_UNIVERSE";
+
+/// Large US companies across every sector, scanned when no universe is set
+pub const DEFAULT_UNIVERSE: &[&str] = &[
+    "AAPL", "MSFT", "NVDA", "GOOGL", "AMZN", "META", "AVGO", "ORCL", "ADBE", "CRM", "JPM", "V",
+    "BAC", "GS", "UNH", "JNJ", "LLY", "PFE", "XOM", "CVX", "WMT", "KO", "PG", "HD", "NKE", "CAT",
+    "BA", "NEE", "LIN", "T",
+];
+
+/// Matches returned when the screen sets no limit
+pub const DEFAULT_LIMIT: usize = 10;
+
+/// RSI period used by `rsi` filters
+pub const RSI_PERIOD: usize = 14;
+
+/// Symbols to scan, from [`SCREENER_UNIVERSE_ENV`] or [`DEFAULT_UNIVERSE`]
+pub fn universe_from_env() -> Vec<String> {
+    let symbols: Vec<String> = std::env::var(SCREENER_UNIVERSE_ENV)
+        .map(|value| parse_symbols(&value))
+        .unwrap_or_default();
+    if symbols.is_empty() {
+        DEFAULT_UNIVERSE.iter().map(ToString::to_string).collect()
+    } else {
+        symbols
+    }
+}
+
+/// Split a comma or space separated list of symbols, dropping duplicates
+fn parse_symbols(value: &str) -> Vec<String> {
+    let mut symbols: Vec<String> = Vec::new();
+    for symbol in value
+        .split([',', ' '])
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        let symbol = symbol.to_uppercase();
+        if !symbols.contains(&symbol) {
+            symbols.push(symbol);
+        }
+    }
+    symbols
+}
+
+/// Numeric value a screen can filter and sort on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    /// Latest close
+    Price,
+    /// Trailing price to earnings
+    PeRatio,
+    /// Market capitalization in USD
+    MarketCap,
+    /// Dividend yield in percent
+    DividendYield,
+    /// 14-day RSI of daily closes
+    Rsi,
+}
+
+impl Metric {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().replace('-', "_").as_str() {
+            "price" | "px" | "价格" | "股价" => Some(Metric::Price),
+            "pe" | "p/e" | "pe_ratio" | "市盈率" => Some(Metric::PeRatio),
+            "market_cap" | "marketcap" | "mcap" | "cap" | "市值" => Some(Metric::MarketCap),
+            "yield" | "dividend_yield" | "div_yield" | "股息率" => Some(Metric::DividendYield),
+            "rsi" => Some(Metric::Rsi),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Metric::Price => "price",
+            Metric::PeRatio => "pe",
+            Metric::MarketCap => "market_cap",
+            Metric::DividendYield => "yield",
+            Metric::Rsi => "rsi",
+        }
+    }
+
+    /// Whether the metric comes from the company overview rather than prices
+    pub fn is_fundamental(self) -> bool {
+        matches!(
+            self,
+            Metric::PeRatio | Metric::MarketCap | Metric::DividendYield
+        )
+    }
+
+    /// Value of the metric in `snapshot`
+    pub fn value(self, snapshot: &Snapshot) -> Option<f64> {
+        match self {
+            Metric::Price => snapshot.price,
+            Metric::PeRatio => snapshot.pe_ratio,
+            Metric::MarketCap => snapshot.market_cap,
+            Metric::DividendYield => snapshot.dividend_yield,
+            Metric::Rsi => snapshot.rsi,
+        }
+    }
+
+    /// Format a value of the metric for display
+    fn format(self, value: f64) -> String {
+        match self {
+            Metric::Price => format!("${value:.2}"),
+            Metric::MarketCap => format_usd(value),
+            Metric::DividendYield => format!("{value:.2}%"),
+            Metric::PeRatio | Metric::Rsi => format!("{value:.1}"),
+        }
+    }
+
+    /// Label in match listings
+    fn label(self) -> &'static str {
+        match self {
+            Metric::Price => "Price",
+            Metric::PeRatio => "P/E",
+            Metric::MarketCap => "Mkt cap",
+            Metric::DividendYield => "Yield",
+            Metric::Rsi => "RSI",
+        }
+    }
+}
+
+/// Text value a screen can filter on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextField {
+    Sector,
+    Industry,
+}
+
+impl TextField {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "sector" | "板块" | "行业" => Some(TextField::Sector),
+            "industry" => Some(TextField::Industry),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TextField::Sector => "sector",
+            TextField::Industry => "industry",
+        }
+    }
+
+    fn value(self, snapshot: &Snapshot) -> Option<&str> {
+        match self {
+            TextField::Sector => snapshot.sector.as_deref(),
+            TextField::Industry => snapshot.industry.as_deref(),
+        }
+    }
+}
+
+/// How a numeric filter compares the metric with its threshold
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+}
+
+impl Comparison {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+            Comparison::Equal => "=",
+        }
+    }
+
+    /// Whether `value` compares with `threshold` as required
+    pub fn holds(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Comparison::Less => value < threshold,
+            Comparison::LessOrEqual => value <= threshold,
+            Comparison::Greater => value > threshold,
+            Comparison::GreaterOrEqual => value >= threshold,
+            Comparison::Equal => (value - threshold).abs() < f64::EPSILON,
+        }
+    }
+}
+
+/// Operators in the order they are searched for, longest first
+const OPERATORS: &[&str] = &["<=", ">=", "!=", "<", ">", "="];
+
+/// One condition of a screen
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Filter {
+    /// e.g. `pe < 20`
+    Numeric {
+        metric: Metric,
+        comparison: Comparison,
+        threshold: f64,
+    },
+    /// e.g. `sector = Technology`, compared without regard to case,
+    /// spaces, hyphens or underscores
+    Text {
+        field: TextField,
+        value: String,
+        negated: bool,
+    },
+}
+
+impl Filter {
+    /// Whether `snapshot` passes the filter; missing data never passes
+    pub fn matches(&self, snapshot: &Snapshot) -> bool {
+        match self {
+            Filter::Numeric {
+                metric,
+                comparison,
+                threshold,
+            } => metric
+                .value(snapshot)
+                .is_some_and(|value| comparison.holds(value, *threshold)),
+            Filter::Text {
+                field,
+                value,
+                negated,
+            } => field
+                .value(snapshot)
+                .is_some_and(|actual| (fold(actual) == fold(value)) != *negated),
+        }
+    }
+
+    /// Whether the filter needs the company overview
+    pub fn is_fundamental(&self) -> bool {
+        match self {
+            Filter::Numeric { metric, .. } => metric.is_fundamental(),
+            Filter::Text { .. } => true,
+        }
+    }
+}
+
+/// Lowercase `text` and drop spaces, hyphens and underscores, so that
+/// "consumer_staples" equals "Consumer Staples"
+fn fold(text: &str) -> String {
+    text.chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '_'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Parse a threshold such as `20`, `10B`, `$1.5T` or `3%`
+fn parse_threshold(text: &str) -> Option<f64> {
+    let text = text.trim().trim_start_matches('$').trim_end_matches('%');
+    let (number, scale) = match text.chars().last()?.to_ascii_uppercase() {
+        'K' => (&text[..text.len() - 1], Scale::Thousands),
+        'M' => (&text[..text.len() - 1], Scale::Millions),
+        'B' => (&text[..text.len() - 1], Scale::Billions),
+        'T' => (&text[..text.len() - 1], Scale::Trillions),
+        _ => (text, Scale::Ones),
+    };
+    let value: f64 = number.replace(',', "").parse().ok()?;
+    value.is_finite().then_some(value * scale.factor())
+}
+
+impl FromStr for Filter {
+    type Err = StockError;
+
+    /// Parse `pe<20`, `market_cap > 10B`, `yield>=3%` or `sector=Technology`
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |reason: &str| {
+            StockError::CommandError(format!(
+                "Invalid filter: {s}. {reason}. Use e.g. pe<20, market_cap>10B, rsi<30 or \
+                 sector=Technology"
+            ))
+        };
+        let (position, operator) = OPERATORS
+            .iter()
+            .filter_map(|op| s.find(op).map(|position| (position, *op)))
+            .min_by_key(|(position, op)| (*position, std::cmp::Reverse(op.len())))
+            .ok_or_else(|| invalid("Missing comparison"))?;
+        let name = s[..position].trim();
+        let value = s[position + operator.len()..].trim();
+        if value.is_empty() {
+            return Err(invalid("Missing value"));
+        }
+
+        if let Some(field) = TextField::parse(name) {
+            let negated = match operator {
+                "=" => false,
+                "!=" => true,
+                _ => return Err(invalid("Text fields only support = and !=")),
+            };
+            return Ok(Filter::Text {
+                field,
+                value: value.to_string(),
+                negated,
+            });
+        }
+
+        let metric = Metric::parse(name).ok_or_else(|| invalid("Unknown metric"))?;
+        let comparison = match operator {
+            "<" => Comparison::Less,
+            "<=" => Comparison::LessOrEqual,
+            ">" => Comparison::Greater,
+            ">=" => Comparison::GreaterOrEqual,
+            "=" => Comparison::Equal,
+            _ => return Err(invalid("Numeric metrics do not support !=")),
+        };
+        let threshold = parse_threshold(value).ok_or_else(|| invalid("Invalid number"))?;
+        Ok(Filter::Numeric {
+            metric,
+            comparison,
+            threshold,
+        })
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Filter::Numeric {
+                metric,
+                comparison,
+                threshold,
+            } => {
+                let threshold = if *metric == Metric::MarketCap {
+                    format_usd(*threshold)
+                } else {
+                    threshold.to_string()
+                };
+                write!(f, "{} {} {threshold}", metric.as_str(), comparison.as_str())
+            }
+            Filter::Text {
+                field,
+                value,
+                negated,
+            } => {
+                let operator = if *negated { "!=" } else { "=" };
+                write!(f, "{} {operator} {value}", field.as_str())
+            }
+        }
+    }
+}
+
+/// Metric and direction matches are ranked by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SortOrder {
+    pub metric: Metric,
+    pub descending: bool,
+}
+
+/// Filters, ranking and number of matches to return
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Screen {
+    pub filters: Vec<Filter>,
+    /// Explicit ranking; see [`Screen::sort_order`] for the default
+    pub sort: Option<SortOrder>,
+    pub limit: usize,
+}
+
+impl Screen {
+    /// Screen with `filters`, the default ranking and [`DEFAULT_LIMIT`]
+    pub fn new(filters: Vec<Filter>) -> Self {
+        Self {
+            filters,
+            sort: None,
+            limit: DEFAULT_LIMIT,
+        }
+    }
+
+    /// Rank matches by `metric`
+    pub fn with_sort(mut self, metric: Metric, descending: bool) -> Self {
+        self.sort = Some(SortOrder { metric, descending });
+        self
+    }
+
+    /// Return at most `limit` matches
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = limit.max(1);
+        self
+    }
+
+    /// How matches are ranked: the explicit order, else the first numeric
+    /// filter's metric, descending for `>` and `>=`
+    pub fn sort_order(&self) -> Option<SortOrder> {
+        self.sort.or_else(|| {
+            self.filters.iter().find_map(|filter| match filter {
+                Filter::Numeric {
+                    metric, comparison, ..
+                } => Some(SortOrder {
+                    metric: *metric,
+                    descending: matches!(
+                        comparison,
+                        Comparison::Greater | Comparison::GreaterOrEqual
+                    ),
+                }),
+                Filter::Text { .. } => None,
+            })
+        })
+    }
+
+    /// Whether the screen needs the company overview of each symbol
+    pub fn needs_fundamentals(&self) -> bool {
+        self.filters.iter().any(Filter::is_fundamental)
+            || self.sort.is_some_and(|sort| sort.metric.is_fundamental())
+    }
+
+    /// Whether the screen needs the price history of each symbol
+    pub fn needs_prices(&self) -> bool {
+        let uses_prices = |metric: Metric| !metric.is_fundamental();
+        self.filters
+            .iter()
+            .any(|filter| matches!(filter, Filter::Numeric { metric, .. } if uses_prices(*metric)))
+            || self.sort.is_some_and(|sort| uses_prices(sort.metric))
+    }
+
+    /// Keep the snapshots passing every filter, best ranked first, at most
+    /// [`Screen::limit`] of them
+    ///
+    /// Without a sort metric, matches keep the order of the universe.
+    pub fn rank(&self, snapshots: Vec<Snapshot>) -> Vec<Snapshot> {
+        let mut matches: Vec<Snapshot> = snapshots
+            .into_iter()
+            .filter(|snapshot| self.filters.iter().all(|filter| filter.matches(snapshot)))
+            .collect();
+        if let Some(order) = self.sort_order() {
+            // Snapshots without the value go last, whatever the direction
+            matches.sort_by(
+                |a, b| match (order.metric.value(a), order.metric.value(b)) {
+                    (Some(a), Some(b)) if order.descending => b.total_cmp(&a),
+                    (Some(a), Some(b)) => a.total_cmp(&b),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                },
+            );
+        }
+        matches.truncate(self.limit);
+        matches
+    }
+}
+
+impl FromStr for Screen {
+    type Err = StockError;
+
+    /// Parse filters separated by commas or spaces, plus optional
+    /// `sort=<metric>` (`sort=-<metric>` for descending) and `limit=<n>`
+    ///
+    /// Spaces around operators are allowed: `pe < 20, market_cap > 10B`.
+    /// With commas, text values may contain spaces (`sector=Consumer Staples`).
+    fn from_str(s: &str) -> Result<Self> {
+        let tight = tighten_operators(s);
+        let terms: Vec<&str> = if tight.contains(',') {
+            tight.split(',').map(str::trim).collect()
+        } else {
+            tight.split_whitespace().collect()
+        };
+
+        let mut screen = Screen::new(Vec::new());
+        for term in terms.into_iter().filter(|term| !term.is_empty()) {
+            if let Some((key, value)) = term.split_once('=') {
+                match key.trim().to_lowercase().as_str() {
+                    "sort" | "排序" => {
+                        let (descending, name) = match value.trim().strip_prefix('-') {
+                            Some(name) => (true, name),
+                            None => (false, value.trim()),
+                        };
+                        let metric = Metric::parse(name).ok_or_else(|| {
+                            StockError::CommandError(format!("Unknown sort metric: {name}"))
+                        })?;
+                        screen = screen.with_sort(metric, descending);
+                        continue;
+                    }
+                    "limit" | "top" => {
+                        let limit = value.trim().parse().map_err(|_| {
+                            StockError::CommandError(format!("Invalid limit: {value}"))
+                        })?;
+                        screen = screen.with_limit(limit);
+                        continue;
+                    }
+                    _ => {}
+                }
+            }
+            screen.filters.push(term.parse()?);
+        }
+
+        if screen.filters.is_empty() {
+            return Err(StockError::CommandError(
+                "Missing filters. Use e.g. /screen pe<20 market_cap>10B rsi<30 sector=Technology"
+                    .to_string(),
+            ));
+        }
+        Ok(screen)
+    }
+}
+
+impl fmt::Display for Screen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let filters: Vec<String> = self.filters.iter().map(ToString::to_string).collect();
+        f.write_str(&filters.join(", "))
+    }
+}
+
+/// Remove whitespace next to comparison operators, so `pe < 20` reads as
+/// one term
+fn tighten_operators(input: &str) -> String {
+    let is_operator = |c: char| matches!(c, '<' | '>' | '=' | '!');
+    let chars: Vec<char> = input.chars().collect();
+    let mut tight = String::with_capacity(input.len());
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_whitespace() {
+            let before = chars[..i].iter().rev().find(|c| !c.is_whitespace());
+            let after = chars[i + 1..].iter().find(|c| !c.is_whitespace());
+            if before.is_some_and(|c| is_operator(*c)) || after.is_some_and(|c| is_operator(*c)) {
+                continue;
+            }
+        }
+        tight.push(c);
+    }
+    tight
+}
+
+/// What the screener knows about one symbol
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub symbol: String,
+    pub name: Option<String>,
+    pub sector: Option<String>,
+    pub industry: Option<String>,
+    /// Latest close
+    pub price: Option<f64>,
+    pub pe_ratio: Option<f64>,
+    /// Market capitalization in USD
+    pub market_cap: Option<f64>,
+    /// Dividend yield in percent
+    pub dividend_yield: Option<f64>,
+    /// 14-day RSI
+    pub rsi: Option<f64>,
+}
+
+impl Snapshot {
+    /// Snapshot with no data yet
+    pub fn new(symbol: impl Into<String>) -> Self {
+        Self {
+            symbol: symbol.into(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Outcome of running a screen over a universe
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScreenResult {
+    pub screen: Screen,
+    /// Symbols in the universe
+    pub universe: usize,
+    /// Symbols whose data could not be fetched, skipped
+    pub failed: Vec<String>,
+    /// Ranked matches
+    pub matches: Vec<Snapshot>,
+}
+
+impl ScreenResult {
+    /// Metrics shown for each match: the sort metric, then the filtered
+    /// ones, in filter order
+    fn columns(&self) -> Vec<Metric> {
+        let mut columns: Vec<Metric> = self
+            .screen
+            .sort_order()
+            .map(|order| order.metric)
+            .into_iter()
+            .collect();
+        for filter in &self.screen.filters {
+            if let Filter::Numeric { metric, .. } = filter {
+                if !columns.contains(metric) {
+                    columns.push(*metric);
+                }
+            }
+        }
+        columns
+    }
+
+    /// Tool output
+    pub fn to_value(&self) -> Value {
+        json!({
+            "screen": self.screen.to_string(),
+            "sorted_by": self.screen.sort_order().map(|order| json!({
+                "metric": order.metric.as_str(),
+                "descending": order.descending,
+            })),
+            "universe_size": self.universe,
+            "failed_symbols": self.failed,
+            "match_count": self.matches.len(),
+            "matches": self.matches,
+            "notes": {
+                "dividend_yield": "percent",
+                "market_cap": "USD",
+                "rsi": format!("{RSI_PERIOD}-day RSI of daily closes"),
+            },
+        })
+    }
+}
+
+impl fmt::Display for ScreenResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Screen: {}", self.screen)?;
+        let scanned = self.universe - self.failed.len();
+        if self.matches.is_empty() {
+            write!(f, "No matches among {scanned} symbols")?;
+        } else {
+            write!(f, "Top {} among {scanned} symbols", self.matches.len())?;
+            if let Some(order) = self.screen.sort_order() {
+                let direction = if order.descending {
+                    "highest"
+                } else {
+                    "lowest"
+                };
+                write!(f, ", {direction} {} first", order.metric.label())?;
+            }
+            writeln!(f)?;
+
+            let columns = self.columns();
+            for (rank, snapshot) in self.matches.iter().enumerate() {
+                write!(f, "\n{}. {}", rank + 1, snapshot.symbol)?;
+                if let Some(name) = &snapshot.name {
+                    write!(f, " ({name})")?;
+                }
+                let values: Vec<String> = columns
+                    .iter()
+                    .filter_map(|metric| {
+                        let value = metric.value(snapshot)?;
+                        Some(format!("{} {}", metric.label(), metric.format(value)))
+                    })
+                    .collect();
+                if !values.is_empty() {
+                    write!(f, ": {}", values.join(" · "))?;
+                }
+            }
+        }
+        if !self.failed.is_empty() {
+            write!(f, "\n\nNo data for: {}", self.failed.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(symbol: &str, sector: &str, pe: f64, cap: f64, rsi: f64) -> Snapshot {
+        Snapshot {
+            sector: Some(sector.to_string()),
+            pe_ratio: Some(pe),
+            market_cap: Some(cap),
+            rsi: Some(rsi),
+            ..Snapshot::new(symbol)
+        }
+    }
+
+    #[test]
+    fn test_parse_filter() {
+        assert_eq!(
+            "market_cap > 10B".parse::<Filter>().unwrap(),
+            Filter::Numeric {
+                metric: Metric::MarketCap,
+                comparison: Comparison::Greater,
+                threshold: 10e9,
+            }
+        );
+        assert_eq!(
+            "yield>=3%".parse::<Filter>().unwrap(),
+            Filter::Numeric {
+                metric: Metric::DividendYield,
+                comparison: Comparison::GreaterOrEqual,
+                threshold: 3.0,
+            }
+        );
+        assert_eq!(
+            "sector!=Energy".parse::<Filter>().unwrap(),
+            Filter::Text {
+                field: TextField::Sector,
+                value: "Energy".to_string(),
+                negated: true,
+            }
+        );
+
+        assert!("pe".parse::<Filter>().is_err());
+        assert!("beta<1".parse::<Filter>().is_err());
+        assert!("pe<cheap".parse::<Filter>().is_err());
+        assert!("sector<Energy".parse::<Filter>().is_err());
+    }
+
+    #[test]
+    fn test_parse_screen() {
+        let screen: Screen =
+            "pe < 20 market_cap>10B rsi<30 sector=Technology sort=-market_cap limit=5"
+                .parse()
+                .unwrap();
+        assert_eq!(screen.filters.len(), 4);
+        assert_eq!(screen.limit, 5);
+        assert_eq!(
+            screen.sort,
+            Some(SortOrder {
+                metric: Metric::MarketCap,
+                descending: true,
+            })
+        );
+        assert!(screen.needs_fundamentals());
+        assert!(screen.needs_prices());
+
+        // Commas allow text values with spaces
+        let screen: Screen = "sector = Consumer Staples, yield > 2".parse().unwrap();
+        assert_eq!(screen.to_string(), "sector = Consumer Staples, yield > 2");
+        assert!(!screen.needs_prices());
+
+        let screen: Screen = "rsi<30".parse().unwrap();
+        assert!(!screen.needs_fundamentals());
+
+        assert!("".parse::<Screen>().is_err());
+        assert!("sort=pe".parse::<Screen>().is_err());
+        assert!("pe<20 limit=many".parse::<Screen>().is_err());
+    }
+
+    #[test]
+    fn test_rank() {
+        let snapshots = vec![
+            snapshot("AAPL", "TECHNOLOGY", 28.0, 2.6e12, 45.0),
+            snapshot("INTC", "TECHNOLOGY", 15.0, 1.3e11, 25.0),
+            snapshot("CSCO", "TECHNOLOGY", 14.0, 2.0e11, 28.0),
+            snapshot("XOM", "ENERGY", 12.0, 4.0e11, 22.0),
+            Snapshot::new("NODATA"),
+        ];
+
+        // Lowest P/E first for a `<` filter; sector compared loosely
+        let screen: Screen = "pe<20 rsi<30 sector=technology".parse().unwrap();
+        let symbols: Vec<String> = screen
+            .rank(snapshots.clone())
+            .into_iter()
+            .map(|s| s.symbol)
+            .collect();
+        assert_eq!(symbols, ["CSCO", "INTC"]);
+
+        // Largest first for a `>` filter, cut at the limit
+        let screen: Screen = "market_cap>100B limit=2".parse().unwrap();
+        let symbols: Vec<String> = screen
+            .rank(snapshots)
+            .into_iter()
+            .map(|s| s.symbol)
+            .collect();
+        assert_eq!(symbols, ["AAPL", "XOM"]);
+    }
+
+    #[test]
+    fn test_display() {
+        let result = ScreenResult {
+            screen: "pe<20 market_cap>100B".parse().unwrap(),
+            universe: 3,
+            failed: vec!["ZZZZ".to_string()],
+            matches: vec![Snapshot {
+                name: Some("Cisco Systems".to_string()),
+                ..snapshot("CSCO", "TECHNOLOGY", 14.0, 2.0e11, 28.0)
+            }],
+        };
+        let text = result.to_string();
+        assert!(text.starts_with("Screen: pe < 20, market_cap > $100.00B"));
+        assert!(text.contains("Top 1 among 2 symbols, lowest P/E first"));
+        assert!(text.contains("1. CSCO (Cisco Systems): P/E 14.0 · Mkt cap $200.00B"));
+        assert!(text.ends_with("No data for: ZZZZ"));
+    }
+
+    #[test]
+    fn test_parse_symbols() {
+        assert_eq!(parse_symbols("aapl, msft,AAPL  ko"), ["AAPL", "MSFT", "KO"]);
+        assert!(parse_symbols(" , ").is_empty());
+    }
+}
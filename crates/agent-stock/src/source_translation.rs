@@ -0,0 +1,516 @@
+//! Translation of news sources
+//!
+//! News providers return English headlines and summaries, so replies in
+//! Chinese would otherwise quote them in English. With
+//! [`SourceTranslation`] enabled, the news tool and the news digest add a
+//! translation next to each headline (`title_translated`) and summary
+//! (`summary_translated`), which the analysts quote along with the original.
+//!
+//! Translations come from a [`TranslationProvider`]: the `translator`
+//! agent's model ([`LlmTranslator`]) or DeepL ([`DeepLTranslator`]). They are
+//! cached by a hash of the text and target language, so a headline that
+//! shows up again in the next request, another symbol's news or the digest
+//! is translated once. A failed translation leaves the sources in English.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::source_translation::SourceTranslator;
+//!
+//! if let Some(translator) = SourceTranslator::from_config(&runtime, &config) {
+//!     let titles = translator.translate(&["Apple beats estimates".to_string()]).await?;
+//! }
+//! ```
+
+use agent_core::{Agent, Context};
+use agent_prompt::Language;
+use agent_runtime::agents::SimpleAgent;
+use agent_runtime::{AgentRuntime, SimpleConfig};
+use async_trait::async_trait;
+use cached::{Cached, SizedCache};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+
+use crate::agents::translator::TRANSLATOR_AGENT;
+use crate::config::{SourceTranslation, StockConfig};
+use crate::error::{Result, StockError};
+
+/// DeepL API base URL for paid keys
+const DEEPL_API_URL: &str = "https://api.deepl.com";
+
+/// DeepL API base URL for free keys, which end in `:fx`
+const DEEPL_FREE_API_URL: &str = "https://api-free.deepl.com";
+
+/// Translations kept in the shared cache
+const CACHE_CAPACITY: usize = 10_000;
+
+/// Translates source texts
+#[async_trait]
+pub trait TranslationProvider: Send + Sync {
+    /// Short name for logs, e.g. "deepl"
+    fn name(&self) -> &'static str;
+
+    /// Translate `texts` into `target`, returning one translation per text
+    /// in the same order
+    async fn translate(&self, texts: &[String], target: &Language) -> Result<Vec<String>>;
+}
+
+/// Translations by [content hash](content_hash)
+pub struct TranslationCache {
+    entries: Mutex<SizedCache<String, String>>,
+}
+
+impl TranslationCache {
+    /// Empty cache keeping up to `capacity` translations
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(SizedCache::with_size(capacity)),
+        }
+    }
+
+    /// Cache shared by every translator in the process
+    pub fn shared() -> Arc<Self> {
+        static SHARED: OnceLock<Arc<TranslationCache>> = OnceLock::new();
+        Arc::clone(SHARED.get_or_init(|| Arc::new(Self::new(CACHE_CAPACITY))))
+    }
+
+    fn get(&self, key: &str) -> Option<String> {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .cache_get(key)
+            .cloned()
+    }
+
+    fn insert(&self, key: String, translation: String) {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .cache_set(key, translation);
+    }
+
+    /// Number of cached translations
+    pub fn len(&self) -> usize {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .cache_size()
+    }
+
+    /// Whether nothing is cached
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Cache key of `text` translated into `target`: the SHA-256 of both
+pub fn content_hash(text: &str, target: &Language) -> String {
+    let digest = Sha256::new()
+        .chain_update(target.code())
+        .chain_update([0])
+        .chain_update(text.trim())
+        .finalize();
+    format!("{digest:x}")
+}
+
+/// Translates headlines and summaries into the response language, through
+/// a [`TranslationCache`]
+pub struct SourceTranslator {
+    provider: Arc<dyn TranslationProvider>,
+    target: Language,
+    cache: Arc<TranslationCache>,
+}
+
+impl SourceTranslator {
+    /// Translator into `target` using the shared cache
+    pub fn new(provider: Arc<dyn TranslationProvider>, target: Language) -> Self {
+        Self {
+            provider,
+            target,
+            cache: TranslationCache::shared(),
+        }
+    }
+
+    /// Translator for `config`, or `None` if sources are not translated or
+    /// replies are in English
+    pub fn from_config(runtime: &AgentRuntime, config: &Arc<StockConfig>) -> Option<Arc<Self>> {
+        if config.response_language == Language::English {
+            return None;
+        }
+        let provider: Arc<dyn TranslationProvider> = match config.source_translation {
+            SourceTranslation::Off => return None,
+            SourceTranslation::Llm => match LlmTranslator::new(runtime, config) {
+                Ok(translator) => Arc::new(translator),
+                Err(e) => {
+                    tracing::warn!("Sources left untranslated: {}", e);
+                    return None;
+                }
+            },
+            SourceTranslation::DeepL => {
+                Arc::new(DeepLTranslator::new(config.deepl_api_key.as_ref()?))
+            }
+        };
+        Some(Arc::new(Self::new(
+            provider,
+            config.response_language.clone(),
+        )))
+    }
+
+    /// Keep translations in `cache` instead of the shared one
+    pub fn with_cache(mut self, cache: Arc<TranslationCache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Language sources are translated into
+    pub fn target(&self) -> &Language {
+        &self.target
+    }
+
+    /// Translate `texts`, asking the provider only for those not cached
+    ///
+    /// Empty texts stay empty.
+    pub async fn translate(&self, texts: &[String]) -> Result<Vec<String>> {
+        let keys: Vec<String> = texts
+            .iter()
+            .map(|text| content_hash(text, &self.target))
+            .collect();
+        let mut translations: Vec<Option<String>> = texts
+            .iter()
+            .zip(&keys)
+            .map(|(text, key)| {
+                if text.trim().is_empty() {
+                    Some(String::new())
+                } else {
+                    self.cache.get(key)
+                }
+            })
+            .collect();
+
+        // The same headline from several sources is asked for once
+        let mut asked = HashSet::new();
+        let missing: Vec<usize> = (0..texts.len())
+            .filter(|&i| translations[i].is_none() && asked.insert(&keys[i]))
+            .collect();
+
+        if !missing.is_empty() {
+            let batch: Vec<String> = missing
+                .iter()
+                .map(|&i| texts[i].trim().to_string())
+                .collect();
+            let translated = self.provider.translate(&batch, &self.target).await?;
+            if translated.len() != batch.len() {
+                return Err(StockError::ApiError(format!(
+                    "{} returned {} translations for {} texts",
+                    self.provider.name(),
+                    translated.len(),
+                    batch.len()
+                )));
+            }
+            let translated: HashMap<&String, String> =
+                missing.iter().map(|&i| &keys[i]).zip(translated).collect();
+            for (translation, key) in translations.iter_mut().zip(&keys) {
+                if translation.is_none() {
+                    *translation = translated.get(key).cloned();
+                }
+            }
+            for (key, translation) in translated {
+                self.cache.insert(key.clone(), translation);
+            }
+        }
+
+        Ok(translations
+            .into_iter()
+            .map(Option::unwrap_or_default)
+            .collect())
+    }
+
+    /// Add `title_translated` and `summary_translated` to news articles
+    /// in JSON, leaving them as they are if translation fails
+    pub async fn annotate_articles(&self, articles: &mut [Value]) {
+        let texts: Vec<String> = articles
+            .iter()
+            .flat_map(|article| {
+                ["title", "summary"]
+                    .map(|field| article[field].as_str().unwrap_or_default().to_string())
+            })
+            .collect();
+        if texts.iter().all(|text| text.trim().is_empty()) {
+            return;
+        }
+        match self.translate(&texts).await {
+            Ok(translations) => {
+                for (article, pair) in articles.iter_mut().zip(translations.chunks_exact(2)) {
+                    for (field, translation) in
+                        ["title_translated", "summary_translated"].iter().zip(pair)
+                    {
+                        if !translation.is_empty() {
+                            article[*field] = json!(translation);
+                        }
+                    }
+                }
+            }
+            Err(e) => tracing::warn!("News left untranslated: {}", e),
+        }
+    }
+}
+
+/// Translations by the `translator` agent's model
+pub struct LlmTranslator {
+    agent: SimpleAgent,
+    config: Arc<StockConfig>,
+}
+
+impl LlmTranslator {
+    /// Translator using the model configured for the `translator` agent
+    pub fn new(runtime: &AgentRuntime, config: &Arc<StockConfig>) -> Result<Self> {
+        let system_prompt = config
+            .prompt_registry
+            .render("stock.translator", &json!({}))
+            .map_err(|e| StockError::ConfigError(e.to_string()))?;
+        let simple_config = SimpleConfig {
+            model: config.model_for(TRANSLATOR_AGENT),
+            system_prompt,
+            max_tokens: config.max_tokens_for(TRANSLATOR_AGENT),
+            temperature: config.temperature_for(TRANSLATOR_AGENT),
+        };
+        Ok(Self {
+            agent: runtime.create_simple_agent(simple_config, TRANSLATOR_AGENT),
+            config: Arc::clone(config),
+        })
+    }
+}
+
+#[async_trait]
+impl TranslationProvider for LlmTranslator {
+    fn name(&self) -> &'static str {
+        "llm"
+    }
+
+    async fn translate(&self, texts: &[String], target: &Language) -> Result<Vec<String>> {
+        let input = self
+            .config
+            .prompt_registry
+            .render(
+                "stock.user.translate_sources",
+                &json!({ "language": target.name(), "texts": json!(texts).to_string() }),
+            )
+            .map_err(|e| StockError::ConfigError(e.to_string()))?;
+        let reply = self.agent.process(input, &mut Context::new()).await?;
+        parse_translations(&reply)
+    }
+}
+
+/// JSON array of strings in a model reply, which may be fenced as code
+fn parse_translations(reply: &str) -> Result<Vec<String>> {
+    let start = reply.find('[');
+    let end = reply.rfind(']');
+    let (Some(start), Some(end)) = (start, end) else {
+        return Err(StockError::ApiError(
+            "Translation reply has no JSON array".to_string(),
+        ));
+    };
+    serde_json::from_str(&reply[start..=end]).map_err(|e| {
+        StockError::ApiError(format!("Translation reply is not a list of strings: {e}"))
+    })
+}
+
+/// Translations by the DeepL API
+pub struct DeepLTranslator {
+    api_key: String,
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl DeepLTranslator {
+    /// Translator using `api_key`, on the free API if it is a free key
+    pub fn new(api_key: impl Into<String>) -> Self {
+        let api_key = api_key.into();
+        let base_url = if api_key.ends_with(":fx") {
+            DEEPL_FREE_API_URL
+        } else {
+            DEEPL_API_URL
+        };
+        Self {
+            api_key,
+            base_url: base_url.to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Send requests to `base_url` (e.g. a mock)
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+}
+
+#[async_trait]
+impl TranslationProvider for DeepLTranslator {
+    fn name(&self) -> &'static str {
+        "deepl"
+    }
+
+    async fn translate(&self, texts: &[String], target: &Language) -> Result<Vec<String>> {
+        let target_lang = match target {
+            Language::Chinese => "ZH-HANS".to_string(),
+            other => other.code().to_uppercase(),
+        };
+        let response = self
+            .client
+            .post(format!("{}/v2/translate", self.base_url))
+            .header("Authorization", format!("DeepL-Auth-Key {}", self.api_key))
+            .json(&json!({ "text": texts, "source_lang": "EN", "target_lang": target_lang }))
+            .send()
+            .await?;
+        match response.status().as_u16() {
+            403 => {
+                return Err(StockError::ConfigError(
+                    "DeepL rejected the API key (check DEEPL_API_KEY)".to_string(),
+                ));
+            }
+            429 | 456 => return Err(StockError::rate_limited("DeepL")),
+            _ => {}
+        }
+        let body: Value = response.error_for_status()?.json().await?;
+        body["translations"]
+            .as_array()
+            .map(|translations| {
+                translations
+                    .iter()
+                    .map(|t| t["text"].as_str().unwrap_or_default().to_string())
+                    .collect()
+            })
+            .ok_or_else(|| StockError::ApiError("DeepL returned no translations".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// Provider tagging texts and counting what it was asked
+    #[derive(Default)]
+    struct Tagging {
+        asked: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl TranslationProvider for Tagging {
+        fn name(&self) -> &'static str {
+            "tagging"
+        }
+
+        async fn translate(&self, texts: &[String], _target: &Language) -> Result<Vec<String>> {
+            self.asked.fetch_add(texts.len(), Ordering::SeqCst);
+            Ok(texts.iter().map(|text| format!("译:{text}")).collect())
+        }
+    }
+
+    fn translator(provider: &Arc<Tagging>) -> SourceTranslator {
+        SourceTranslator::new(provider.clone(), Language::Chinese)
+            .with_cache(Arc::new(TranslationCache::new(100)))
+    }
+
+    #[tokio::test]
+    async fn test_translations_cached_by_content() {
+        let provider = Arc::new(Tagging::default());
+        let translator = translator(&provider);
+
+        let texts = [
+            "Apple beats estimates",
+            "",
+            "Fed holds rates",
+            "Apple beats estimates ",
+        ]
+        .map(String::from);
+        assert_eq!(
+            translator.translate(&texts).await.unwrap(),
+            [
+                "译:Apple beats estimates",
+                "",
+                "译:Fed holds rates",
+                "译:Apple beats estimates"
+            ]
+        );
+        // Repeated and empty texts are not sent
+        assert_eq!(provider.asked.load(Ordering::SeqCst), 2);
+
+        let again = ["Fed holds rates", "Nvidia jumps"].map(String::from);
+        translator.translate(&again).await.unwrap();
+        assert_eq!(provider.asked.load(Ordering::SeqCst), 3);
+        assert_ne!(
+            content_hash("Fed holds rates", &Language::Chinese),
+            content_hash("Fed holds rates", &Language::English)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_annotate_articles() {
+        let provider = Arc::new(Tagging::default());
+        let mut articles = vec![
+            json!({ "title": "Apple beats estimates", "summary": "Revenue rose 8%." }),
+            json!({ "title": "Fed holds rates", "summary": "" }),
+        ];
+        translator(&provider).annotate_articles(&mut articles).await;
+
+        assert_eq!(articles[0]["title_translated"], "译:Apple beats estimates");
+        assert_eq!(articles[0]["summary_translated"], "译:Revenue rose 8%.");
+        assert_eq!(articles[1]["title_translated"], "译:Fed holds rates");
+        assert!(articles[1].get("summary_translated").is_none());
+    }
+
+    #[test]
+    fn test_parse_translations() {
+        assert_eq!(
+            parse_translations("```json\n[\"苹果业绩超预期\", \"美联储按兵不动\"]\n```").unwrap(),
+            ["苹果业绩超预期", "美联储按兵不动"]
+        );
+        assert!(parse_translations("苹果业绩超预期").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_deepl() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v2/translate"))
+            .and(header("authorization", "DeepL-Auth-Key key:fx"))
+            .and(body_partial_json(
+                json!({ "text": ["Fed holds rates"], "target_lang": "ZH-HANS" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "translations": [{ "detected_source_language": "EN", "text": "美联储维持利率不变" }]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(header("authorization", "DeepL-Auth-Key revoked"))
+            .respond_with(ResponseTemplate::new(403))
+            .mount(&server)
+            .await;
+
+        let deepl = DeepLTranslator::new("key:fx");
+        assert_eq!(deepl.base_url, DEEPL_FREE_API_URL);
+        let deepl = deepl.with_base_url(server.uri());
+        assert_eq!(
+            deepl
+                .translate(&["Fed holds rates".to_string()], &Language::Chinese)
+                .await
+                .unwrap(),
+            ["美联储维持利率不变"]
+        );
+
+        let revoked = DeepLTranslator::new("revoked").with_base_url(server.uri());
+        assert!(matches!(
+            revoked
+                .translate(&["Fed holds rates".to_string()], &Language::Chinese)
+                .await,
+            Err(StockError::ConfigError(_))
+        ));
+    }
+}
@@ -0,0 +1,415 @@
+//! Server-Sent Events for long-running analyses
+//!
+//! A standard or deep analysis runs for 30 seconds or more. Instead of one
+//! blocking response, [`stream`] runs it in the background and yields
+//! `text/event-stream` frames as its agents work: each step, each tool call
+//! starting and finishing, and the text the model writes, then the result.
+//! Web clients read them with `EventSource` and render progress live.
+//!
+//! The frames are plain strings, so any HTTP framework can send the stream
+//! as a response body with `Content-Type: text/event-stream`. Dropping the
+//! stream, as a framework does when the client disconnects, cancels the
+//! analysis.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::sse;
+//!
+//! // GET /analyze/AAPL/stream
+//! let engine = Arc::clone(&engine);
+//! let frames = sse::stream(async move {
+//!     engine.analyze_stock("AAPL", &mut AnalysisContext::new()).await
+//! });
+//! ```
+
+use agent_runtime::{ExecutorEventHandler, with_event_handler};
+use async_trait::async_trait;
+use futures::Stream;
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::task::AbortHandle;
+
+use crate::error::Result;
+
+/// Idle time after which a comment is sent so proxies keep the connection open
+pub const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// Comment frame sent while nothing else happens
+const KEEP_ALIVE_FRAME: &str = ": keep-alive\n\n";
+
+/// An event of a streamed analysis
+#[derive(Debug, Clone, PartialEq)]
+pub enum SseEvent {
+    /// An agent started step `step` of at most `max_steps`
+    Progress { step: usize, max_steps: usize },
+    /// A tool call started
+    ToolStart {
+        id: String,
+        name: String,
+        input: Value,
+    },
+    /// A tool call finished, with its error if it failed
+    ToolDone {
+        id: String,
+        name: String,
+        error: Option<String>,
+        duration_ms: u64,
+    },
+    /// Text written by an agent; sub-agents of a comprehensive analysis
+    /// run concurrently, so their text interleaves
+    Text { text: String },
+    /// The finished analysis; always the last event of a successful run
+    Result(Value),
+    /// The analysis failed; always the last event of a failed run
+    Error { message: String },
+}
+
+impl SseEvent {
+    /// The SSE `event:` name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Progress { .. } => "progress",
+            Self::ToolStart { .. } => "tool_start",
+            Self::ToolDone { .. } => "tool_done",
+            Self::Text { .. } => "text",
+            Self::Result(_) => "result",
+            Self::Error { .. } => "error",
+        }
+    }
+
+    /// The event's JSON payload
+    pub fn data(&self) -> Value {
+        match self {
+            Self::Progress { step, max_steps } => json!({ "step": step, "max_steps": max_steps }),
+            Self::ToolStart { id, name, input } => {
+                json!({ "id": id, "name": name, "input": input })
+            }
+            Self::ToolDone {
+                id,
+                name,
+                error,
+                duration_ms,
+            } => json!({
+                "id": id,
+                "name": name,
+                "success": error.is_none(),
+                "error": error,
+                "duration_ms": duration_ms,
+            }),
+            Self::Text { text } => json!({ "text": text }),
+            Self::Result(value) => value.clone(),
+            Self::Error { message } => json!({ "message": message }),
+        }
+    }
+
+    /// The event as a `text/event-stream` frame
+    ///
+    /// The payload is compact JSON, which never contains a raw newline, so it
+    /// fits on a single `data:` line.
+    pub fn to_frame(&self) -> String {
+        format!("event: {}\ndata: {}\n\n", self.name(), self.data())
+    }
+}
+
+/// Executor event handler forwarding events to a channel
+pub struct SseEventHandler {
+    events: UnboundedSender<SseEvent>,
+}
+
+impl SseEventHandler {
+    /// A handler sending to `events`
+    pub fn new(events: UnboundedSender<SseEvent>) -> Self {
+        Self { events }
+    }
+
+    fn send(&self, event: SseEvent) {
+        // Sending only fails when the client went away
+        let _ = self.events.send(event);
+    }
+}
+
+#[async_trait]
+impl ExecutorEventHandler for SseEventHandler {
+    async fn on_iteration_start(&self, iteration: usize, max_iterations: usize) {
+        self.send(SseEvent::Progress {
+            step: iteration,
+            max_steps: max_iterations,
+        });
+    }
+
+    async fn on_text(&self, _iteration: usize, text: &str) {
+        self.send(SseEvent::Text {
+            text: text.to_string(),
+        });
+    }
+
+    async fn on_tool_start(&self, id: &str, name: &str, input: &Value) {
+        self.send(SseEvent::ToolStart {
+            id: id.to_string(),
+            name: name.to_string(),
+            input: input.clone(),
+        });
+    }
+
+    async fn on_tool_done(
+        &self,
+        id: &str,
+        name: &str,
+        result: std::result::Result<&Value, &str>,
+        duration_ms: u64,
+    ) {
+        self.send(SseEvent::ToolDone {
+            id: id.to_string(),
+            name: name.to_string(),
+            error: result.err().map(str::to_string),
+            duration_ms,
+        });
+    }
+}
+
+/// Aborts the analysis when the stream is dropped
+struct AbortOnDrop(AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Run `analysis` in the background, streaming its events as SSE frames
+///
+/// Events come from every agent run inside `analysis` on its own task; the
+/// stream ends with a `result` event holding the serialized output, or an
+/// `error` event. See [`stream_with_keep_alive`] for the idle interval.
+pub fn stream<T, F>(analysis: F) -> impl Stream<Item = String> + Send + 'static
+where
+    T: Serialize + Send + 'static,
+    F: Future<Output = Result<T>> + Send + 'static,
+{
+    stream_with_keep_alive(analysis, KEEP_ALIVE)
+}
+
+/// [`stream`], sending a keep-alive comment after `keep_alive` without events
+pub fn stream_with_keep_alive<T, F>(
+    analysis: F,
+    keep_alive: Duration,
+) -> impl Stream<Item = String> + Send + 'static
+where
+    T: Serialize + Send + 'static,
+    F: Future<Output = Result<T>> + Send + 'static,
+{
+    let (events, received) = mpsc::unbounded_channel();
+    let handler = Arc::new(SseEventHandler::new(events.clone()));
+    let task = tokio::spawn(async move {
+        let last = match with_event_handler(handler, analysis).await {
+            Ok(output) => match serde_json::to_value(&output) {
+                Ok(value) => SseEvent::Result(value),
+                Err(e) => SseEvent::Error {
+                    message: format!("Failed to serialize the result: {e}"),
+                },
+            },
+            Err(e) => SseEvent::Error {
+                message: e.to_string(),
+            },
+        };
+        let _ = events.send(last);
+    });
+
+    let state: (UnboundedReceiver<SseEvent>, AbortOnDrop) =
+        (received, AbortOnDrop(task.abort_handle()));
+    futures::stream::unfold(state, move |(mut received, task)| async move {
+        match tokio::time::timeout(keep_alive, received.recv()).await {
+            Ok(Some(event)) => Some((event.to_frame(), (received, task))),
+            // The analysis finished and its last event was sent
+            Ok(None) => None,
+            Err(_) => Some((KEEP_ALIVE_FRAME.to_string(), (received, task))),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::StockError;
+    use agent_llm::{
+        CompletionRequest, CompletionResponse, LLMProvider, Message, StopReason, TokenUsage,
+    };
+    use agent_runtime::{AgentExecutor, ExecutorConfig};
+    use agent_tools::ToolRegistry;
+    use futures::StreamExt;
+
+    struct Echo;
+
+    #[async_trait]
+    impl LLMProvider for Echo {
+        async fn complete(
+            &self,
+            _request: CompletionRequest,
+        ) -> agent_llm::Result<CompletionResponse> {
+            Ok(CompletionResponse {
+                message: Message::assistant("AAPL looks strong."),
+                stop_reason: StopReason::EndTurn,
+                usage: TokenUsage {
+                    input_tokens: 0,
+                    output_tokens: 0,
+                },
+            })
+        }
+
+        fn name(&self) -> &str {
+            "echo"
+        }
+    }
+
+    #[test]
+    fn test_frames() {
+        let done = SseEvent::ToolDone {
+            id: "call_1".into(),
+            name: "stock_data".into(),
+            error: Some("rate limited\nretry later".into()),
+            duration_ms: 12,
+        };
+        assert_eq!(
+            done.to_frame(),
+            "event: tool_done\ndata: {\"duration_ms\":12,\"error\":\"rate limited\\nretry later\",\
+             \"id\":\"call_1\",\"name\":\"stock_data\",\"success\":false}\n\n"
+        );
+        let progress = SseEvent::Progress {
+            step: 2,
+            max_steps: 10,
+        };
+        assert_eq!(
+            progress.to_frame(),
+            "event: progress\ndata: {\"max_steps\":10,\"step\":2}\n\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_agent_events_are_streamed() {
+        let executor = AgentExecutor::new(
+            Arc::new(Echo),
+            Arc::new(ToolRegistry::new()),
+            ExecutorConfig::default(),
+        );
+        let frames: Vec<String> = stream(async move {
+            let answer = executor
+                .run("Analyze AAPL".into())
+                .await
+                .map_err(StockError::from)?;
+            Ok(json!({ "symbol": "AAPL", "content": answer.to_string() }))
+        })
+        .collect()
+        .await;
+
+        assert_eq!(
+            frames,
+            [
+                "event: progress\ndata: {\"max_steps\":10,\"step\":1}\n\n",
+                "event: text\ndata: {\"text\":\"AAPL looks strong.\"}\n\n",
+                "event: result\ndata: {\"content\":\"AAPL looks strong.\",\"symbol\":\"AAPL\"}\n\n",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_text_streamed_by_openai_and_ollama() {
+        use crate::api::testing::MockApi;
+        use agent_llm::providers::{OllamaConfig, OllamaProvider, OpenAIConfig, OpenAIProvider};
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, ResponseTemplate};
+
+        let api = MockApi::start().await;
+        let openai = concat!(
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"AAPL looks \"},\"finish_reason\":null}]}\n\n",
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"strong.\"},\"finish_reason\":\"stop\"}]}\n\n",
+            "data: [DONE]\n\n",
+        );
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(openai, "text/event-stream"))
+            .mount(api.server())
+            .await;
+        let ollama = concat!(
+            "{\"message\":{\"role\":\"assistant\",\"content\":\"AAPL looks \"},\"done\":false}\n",
+            "{\"message\":{\"role\":\"assistant\",\"content\":\"strong.\"},\"done\":true,\"done_reason\":\"stop\"}\n",
+        );
+        Mock::given(method("POST"))
+            .and(path("/api/chat"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(ollama, "application/x-ndjson"))
+            .mount(api.server())
+            .await;
+
+        let openai = OpenAIConfig::new("test-key").with_api_base(format!("{}/v1", api.uri()));
+        let ollama = OllamaConfig::new().with_api_base(api.uri());
+        let providers: [Arc<dyn LLMProvider>; 2] = [
+            Arc::new(OpenAIProvider::with_config(openai).unwrap()),
+            Arc::new(OllamaProvider::with_config(ollama).unwrap()),
+        ];
+        for provider in providers {
+            let name = provider.name().to_string();
+            let executor = AgentExecutor::new(
+                provider,
+                Arc::new(ToolRegistry::new()),
+                ExecutorConfig::default(),
+            );
+            let frames: Vec<String> = stream(async move {
+                let answer = executor
+                    .run("Analyze AAPL".into())
+                    .await
+                    .map_err(StockError::from)?;
+                Ok(json!({ "content": answer.to_string() }))
+            })
+            .collect()
+            .await;
+
+            assert_eq!(
+                frames[1], "event: text\ndata: {\"text\":\"AAPL looks strong.\"}\n\n",
+                "{name}: {frames:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_errors_and_keep_alive() {
+        let frames: Vec<String> = stream_with_keep_alive(
+            async {
+                tokio::time::sleep(Duration::from_millis(80)).await;
+                Err::<(), _>(StockError::InvalidSymbol("XYZ".into()))
+            },
+            Duration::from_millis(30),
+        )
+        .collect()
+        .await;
+
+        assert!(frames.len() >= 2, "{frames:?}");
+        assert_eq!(frames[0], KEEP_ALIVE_FRAME);
+        assert_eq!(
+            frames.last().unwrap(),
+            "event: error\ndata: {\"message\":\"Invalid symbol: XYZ\"}\n\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dropping_the_stream_cancels_the_analysis() {
+        let (finished, mut finished_rx) = mpsc::unbounded_channel::<()>();
+        let frames = stream_with_keep_alive(
+            async move {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                let _ = finished.send(());
+                Ok(())
+            },
+            Duration::from_millis(10),
+        );
+        let mut frames = Box::pin(frames);
+        assert_eq!(frames.next().await.unwrap(), KEEP_ALIVE_FRAME);
+        drop(frames);
+
+        // The sender is dropped with the aborted task, without sending
+        assert!(finished_rx.recv().await.is_none());
+    }
+}
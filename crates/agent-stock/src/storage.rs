@@ -0,0 +1,292 @@
+//! Optional encryption at rest for persisted stores
+//!
+//! Predictions and usage statistics are saved as JSON files. When a key is
+//! configured, [`read_store`] and [`write_store`] encrypt them with
+//! AES-256-GCM; otherwise files are read and written as plain text.
+//!
+//! The 32-byte key is given base64-encoded in `STOCK_STORE_KEY`, or read from
+//! the file named by `STOCK_STORE_KEY_FILE` (e.g. a Docker or Kubernetes
+//! secret mount). Generate one with [`StoreCipher::generate_key`] or
+//! `openssl rand -base64 32`.
+//!
+//! Existing plaintext stores are migrated transparently: with a key set, a
+//! plaintext file is still readable and is rewritten encrypted as soon as it
+//! is loaded.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::storage::{read_store, write_store, StoreCipher};
+//!
+//! let cipher = StoreCipher::from_env()?;
+//! write_store("predictions.json", "[]", cipher.as_ref())?;
+//! let json = read_store("predictions.json", cipher.as_ref())?;
+//! ```
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use std::fmt;
+use std::path::Path;
+
+use crate::error::{Result, StockError};
+
+/// Environment variable holding the base64-encoded store key
+pub const STORE_KEY_ENV: &str = "STOCK_STORE_KEY";
+
+/// Environment variable naming a file that holds the store key
+pub const STORE_KEY_FILE_ENV: &str = "STOCK_STORE_KEY_FILE";
+
+/// First line of an encrypted store; the rest is base64(nonce || ciphertext)
+const HEADER: &str = "agent-rs:aes-256-gcm:v1";
+
+/// AES-GCM nonce length in bytes
+const NONCE_LEN: usize = 12;
+
+/// Encrypts and decrypts stores with AES-256-GCM
+#[derive(Clone)]
+pub struct StoreCipher {
+    cipher: Aes256Gcm,
+}
+
+impl fmt::Debug for StoreCipher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("StoreCipher(..)")
+    }
+}
+
+impl StoreCipher {
+    /// Create a cipher from a 32-byte key
+    pub fn from_key(key: &[u8]) -> Result<Self> {
+        if key.len() != 32 {
+            return Err(StockError::ConfigError(format!(
+                "Store key must be 32 bytes, got {}",
+                key.len()
+            )));
+        }
+        Ok(Self {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)),
+        })
+    }
+
+    /// Create a cipher from a base64-encoded 32-byte key
+    pub fn from_base64(key: &str) -> Result<Self> {
+        let key = BASE64
+            .decode(key.trim())
+            .map_err(|e| StockError::ConfigError(format!("Store key is not valid base64: {e}")))?;
+        Self::from_key(&key)
+    }
+
+    /// Cipher from `STOCK_STORE_KEY` or `STOCK_STORE_KEY_FILE`, if either is set
+    pub fn from_env() -> Result<Option<Self>> {
+        if let Some(key) = std::env::var(STORE_KEY_ENV)
+            .ok()
+            .filter(|k| !k.trim().is_empty())
+        {
+            return Self::from_base64(&key).map(Some);
+        }
+        let Some(path) = std::env::var(STORE_KEY_FILE_ENV)
+            .ok()
+            .filter(|p| !p.is_empty())
+        else {
+            return Ok(None);
+        };
+        let key = std::fs::read_to_string(&path).map_err(|e| {
+            StockError::ConfigError(format!("Failed to read store key file {path}: {e}"))
+        })?;
+        Self::from_base64(&key).map(Some)
+    }
+
+    /// Generate a random base64-encoded key
+    pub fn generate_key() -> String {
+        BASE64.encode(Aes256Gcm::generate_key(&mut OsRng))
+    }
+
+    /// Encrypt `plaintext` into the store format
+    pub fn encrypt(&self, plaintext: &str) -> Result<String> {
+        Ok(format!("{HEADER}\n{}\n", self.seal(plaintext)?))
+    }
+
+    /// Encrypt `plaintext` into a single line, for entries appended to a log
+    ///
+    /// [`StoreCipher::decrypt`] reads it back like a whole store.
+    pub fn encrypt_line(&self, plaintext: &str) -> Result<String> {
+        Ok(format!("{HEADER} {}", self.seal(plaintext)?))
+    }
+
+    /// base64(nonce || ciphertext) of `plaintext`
+    fn seal(&self, plaintext: &str) -> Result<String> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| StockError::Other("Failed to encrypt store".to_string()))?;
+
+        let mut payload = nonce.to_vec();
+        payload.extend_from_slice(&ciphertext);
+        Ok(BASE64.encode(payload))
+    }
+
+    /// Decrypt a store written by [`StoreCipher::encrypt`]
+    pub fn decrypt(&self, contents: &str) -> Result<String> {
+        let payload = contents
+            .strip_prefix(HEADER)
+            .ok_or_else(|| StockError::Other("Store is not encrypted".to_string()))?;
+        let payload = BASE64
+            .decode(payload.trim())
+            .map_err(|e| StockError::Other(format!("Corrupt encrypted store: {e}")))?;
+        if payload.len() < NONCE_LEN {
+            return Err(StockError::Other(
+                "Corrupt encrypted store: too short".to_string(),
+            ));
+        }
+
+        let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| {
+                StockError::ConfigError(
+                    "Failed to decrypt store: wrong key or corrupted file".to_string(),
+                )
+            })?;
+        String::from_utf8(plaintext)
+            .map_err(|e| StockError::Other(format!("Decrypted store is not UTF-8: {e}")))
+    }
+}
+
+/// Whether `contents` is an encrypted store
+pub fn is_encrypted(contents: &str) -> bool {
+    contents.starts_with(HEADER)
+}
+
+/// Read a store, decrypting it if needed; `None` if the file does not exist
+///
+/// With a cipher, a plaintext file is rewritten encrypted before returning.
+/// Without one, an encrypted file is an error.
+pub fn read_store(path: impl AsRef<Path>, cipher: Option<&StoreCipher>) -> Result<Option<String>> {
+    let path = path.as_ref();
+    let Some((contents, encrypted)) = load(path, cipher)? else {
+        return Ok(None);
+    };
+    if cipher.is_some() && !encrypted {
+        write_store(path, &contents, cipher)?;
+        tracing::info!("Encrypted existing plaintext store {}", path.display());
+    }
+    Ok(Some(contents))
+}
+
+/// Read a store like [`read_store`], but never write to it
+///
+/// A plaintext file is left as it is even with a cipher.
+pub fn peek_store(path: impl AsRef<Path>, cipher: Option<&StoreCipher>) -> Result<Option<String>> {
+    Ok(load(path.as_ref(), cipher)?.map(|(contents, _)| contents))
+}
+
+/// Contents of the store at `path` and whether the file was encrypted
+fn load(path: &Path, cipher: Option<&StoreCipher>) -> Result<Option<(String, bool)>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| StockError::Other(format!("Failed to read {}: {e}", path.display())))?;
+
+    match (is_encrypted(&contents), cipher) {
+        (true, Some(cipher)) => Ok(Some((cipher.decrypt(&contents)?, true))),
+        (true, None) => Err(StockError::ConfigError(format!(
+            "{} is encrypted; set {STORE_KEY_ENV} or {STORE_KEY_FILE_ENV} to read it",
+            path.display()
+        ))),
+        (false, _) => Ok(Some((contents, false))),
+    }
+}
+
+/// Write a store, encrypting it when a cipher is given
+///
+/// Parent directories are created as needed. The file is replaced atomically
+/// so a crash mid-write cannot leave a truncated store.
+pub fn write_store(
+    path: impl AsRef<Path>,
+    contents: &str,
+    cipher: Option<&StoreCipher>,
+) -> Result<()> {
+    let path = path.as_ref();
+    let contents = match cipher {
+        Some(cipher) => cipher.encrypt(contents)?,
+        None => contents.to_string(),
+    };
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| {
+            StockError::Other(format!("Failed to create {}: {e}", parent.display()))
+        })?;
+    }
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, contents)
+        .and_then(|()| std::fs::rename(&tmp, path))
+        .map_err(|e| StockError::Other(format!("Failed to write {}: {e}", path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("{name}-{}.json", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let cipher = StoreCipher::from_base64(&StoreCipher::generate_key()).unwrap();
+        let encrypted = cipher.encrypt(r#"{"symbol":"AAPL"}"#).unwrap();
+        assert!(is_encrypted(&encrypted));
+        assert!(!encrypted.contains("AAPL"));
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), r#"{"symbol":"AAPL"}"#);
+
+        let line = cipher.encrypt_line(r#"{"symbol":"AAPL"}"#).unwrap();
+        assert!(is_encrypted(&line));
+        assert!(!line.contains('\n'));
+        assert_eq!(cipher.decrypt(&line).unwrap(), r#"{"symbol":"AAPL"}"#);
+    }
+
+    #[test]
+    fn test_wrong_key_is_rejected() {
+        let cipher = StoreCipher::from_key(&[1; 32]).unwrap();
+        let other = StoreCipher::from_key(&[2; 32]).unwrap();
+        let encrypted = cipher.encrypt("secret").unwrap();
+        assert!(matches!(
+            other.decrypt(&encrypted),
+            Err(StockError::ConfigError(_))
+        ));
+        assert!(StoreCipher::from_key(&[0; 16]).is_err());
+    }
+
+    #[test]
+    fn test_plaintext_store_is_migrated() {
+        let path = temp_path("store-migrate");
+        write_store(&path, "[1,2,3]", None).unwrap();
+
+        let cipher = StoreCipher::from_key(&[7; 32]).unwrap();
+        assert_eq!(
+            read_store(&path, Some(&cipher)).unwrap().as_deref(),
+            Some("[1,2,3]")
+        );
+
+        // Rewritten encrypted on load, and unreadable without the key
+        let on_disk = std::fs::read_to_string(&path).unwrap();
+        assert!(is_encrypted(&on_disk));
+        assert!(read_store(&path, None).is_err());
+        assert_eq!(
+            read_store(&path, Some(&cipher)).unwrap().as_deref(),
+            Some("[1,2,3]")
+        );
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_missing_store() {
+        assert_eq!(read_store(temp_path("store-missing"), None).unwrap(), None);
+    }
+}
@@ -0,0 +1,171 @@
+//! Response style profiles and teaching mode
+//!
+//! A response style controls how an analysis is written for its audience:
+//! answer length, how much jargon is used, and whether emoji are allowed.
+//! The style is rendered into the `stock.response_style` prompt template and
+//! prepended to the request sent to the specialist agents.
+//!
+//! Teaching mode is an independent per-user toggle: when it is on, technical
+//! and fundamental analyses walk through the reasoning and formulas behind
+//! each conclusion (see the `stock.teaching_mode` template).
+
+use agent_core::Context;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::StockError;
+
+/// Context key under which the response style is stored
+pub const STYLE_CONTEXT_KEY: &str = "response_style";
+
+/// Context key under which the teaching mode flag is stored
+pub const TEACHING_CONTEXT_KEY: &str = "teaching_mode";
+
+/// Audience-specific writing style for analysis responses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseStyle {
+    /// Short answers with key numbers and a clear conclusion
+    Concise,
+    /// Full professional analysis (default)
+    #[default]
+    Detailed,
+    /// Plain-language explanations for novice investors
+    Beginner,
+}
+
+impl ResponseStyle {
+    /// All available styles
+    pub const ALL: [ResponseStyle; 3] = [Self::Concise, Self::Detailed, Self::Beginner];
+
+    /// Parse a style name (English or Chinese, with common aliases)
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "concise" | "brief" | "short" | "简洁" | "简短" => Some(Self::Concise),
+            "detailed" | "detail" | "full" | "pro" | "professional" | "详细" | "专业" => {
+                Some(Self::Detailed)
+            }
+            "beginner" | "novice" | "simple" | "新手" | "入门" => Some(Self::Beginner),
+            _ => None,
+        }
+    }
+
+    /// Canonical style name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Concise => "concise",
+            Self::Detailed => "detailed",
+            Self::Beginner => "beginner",
+        }
+    }
+
+    /// Short human-readable description
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Concise => "Short answers with key numbers and a clear conclusion",
+            Self::Detailed => "Full professional analysis with all supporting data",
+            Self::Beginner => "Plain-language explanations for new investors",
+        }
+    }
+
+    /// Template variables for the `stock.response_style` prompt
+    pub fn template_vars(&self) -> Value {
+        let (max_words, jargon, use_emoji) = match self {
+            Self::Concise => (150, "minimal", false),
+            Self::Detailed => (800, "professional", false),
+            Self::Beginner => (400, "explain", true),
+        };
+        json!({
+            "style": self.as_str(),
+            "max_words": max_words,
+            "jargon": jargon,
+            "use_emoji": use_emoji,
+        })
+    }
+
+    /// Read the style stored in an agent context
+    pub fn from_context(context: &Context) -> Option<Self> {
+        context.get_typed(STYLE_CONTEXT_KEY).ok().flatten()
+    }
+
+    /// Store the style in an agent context
+    pub fn apply_to(self, context: &mut Context) {
+        context.insert(STYLE_CONTEXT_KEY, Value::String(self.as_str().to_string()));
+    }
+}
+
+/// Read the teaching mode flag stored in an agent context
+pub fn teaching_mode(context: &Context) -> Option<bool> {
+    context.get(TEACHING_CONTEXT_KEY).and_then(Value::as_bool)
+}
+
+/// Store the teaching mode flag in an agent context
+pub fn set_teaching_mode(context: &mut Context, enabled: bool) {
+    context.insert(TEACHING_CONTEXT_KEY, Value::Bool(enabled));
+}
+
+impl fmt::Display for ResponseStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ResponseStyle {
+    type Err = StockError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s).ok_or_else(|| {
+            StockError::ConfigError(format!(
+                "Unknown response style: {s}. Available: concise, detailed, beginner"
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_style() {
+        assert_eq!(
+            ResponseStyle::parse("Concise"),
+            Some(ResponseStyle::Concise)
+        );
+        assert_eq!(ResponseStyle::parse("pro"), Some(ResponseStyle::Detailed));
+        assert_eq!(ResponseStyle::parse("新手"), Some(ResponseStyle::Beginner));
+        assert_eq!(ResponseStyle::parse("verbose"), None);
+        assert!("verbose".parse::<ResponseStyle>().is_err());
+    }
+
+    #[test]
+    fn test_context_round_trip() {
+        let mut context = Context::new();
+        assert_eq!(ResponseStyle::from_context(&context), None);
+
+        ResponseStyle::Beginner.apply_to(&mut context);
+        assert_eq!(
+            ResponseStyle::from_context(&context),
+            Some(ResponseStyle::Beginner)
+        );
+    }
+
+    #[test]
+    fn test_teaching_mode_flag() {
+        let mut context = Context::new();
+        assert_eq!(teaching_mode(&context), None);
+
+        set_teaching_mode(&mut context, true);
+        assert_eq!(teaching_mode(&context), Some(true));
+    }
+
+    #[test]
+    fn test_template_vars() {
+        let vars = ResponseStyle::Concise.template_vars();
+        assert_eq!(vars["style"], "concise");
+        assert_eq!(vars["use_emoji"], false);
+        assert_eq!(ResponseStyle::Beginner.template_vars()["jargon"], "explain");
+    }
+}
@@ -0,0 +1,264 @@
+//! User time zones
+//!
+//! A [`TimeZone`] is either a fixed UTC offset (`UTC+8`) or an IANA zone
+//! (`America/New_York`). IANA zones come from the tz database bundled by
+//! `chrono-tz`, so local times follow daylight saving time; a name it does
+//! not know is rejected when it is parsed.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::timezone::TimeZone;
+//!
+//! let zone: TimeZone = "Europe/Berlin".parse()?;
+//! let local = zone.to_local(Utc::now());
+//! ```
+
+use chrono::TimeZone as _;
+use chrono::{DateTime, Duration, FixedOffset, LocalResult, NaiveDateTime, Offset, Utc};
+use chrono_tz::{TZ_VARIANTS, Tz};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{Result, StockError};
+
+/// Largest UTC offset accepted, in minutes (UTC+14:00)
+const MAX_UTC_OFFSET_MINUTES: i32 = 14 * 60;
+
+/// A user's time zone
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum TimeZone {
+    /// Fixed offset, in minutes east of UTC
+    Fixed(i32),
+    /// IANA zone, e.g. `Europe/Berlin`
+    Named(Tz),
+}
+
+impl Default for TimeZone {
+    fn default() -> Self {
+        Self::UTC
+    }
+}
+
+impl TimeZone {
+    /// Coordinated Universal Time
+    pub const UTC: Self = Self::Fixed(0);
+
+    /// UTC offset of the zone at `at`
+    pub fn offset_at(&self, at: DateTime<Utc>) -> FixedOffset {
+        match self {
+            Self::Fixed(minutes) => FixedOffset::east_opt(minutes * 60).unwrap_or(Utc.fix()),
+            Self::Named(zone) => zone.offset_from_utc_datetime(&at.naive_utc()).fix(),
+        }
+    }
+
+    /// Local date and time in the zone at `at`
+    pub fn to_local(&self, at: DateTime<Utc>) -> NaiveDateTime {
+        at.with_timezone(&self.offset_at(at)).naive_local()
+    }
+
+    /// The instant the zone's clocks show `local`
+    ///
+    /// A local time skipped when the clocks go forward is read with the
+    /// offset from before the change, so it lands just after it; a local time
+    /// that happens twice resolves to the first.
+    pub fn from_local(&self, local: NaiveDateTime) -> DateTime<Utc> {
+        let zone = match self {
+            Self::Fixed(_) => return self.at_offset(local, local.and_utc()),
+            Self::Named(zone) => zone,
+        };
+        match zone.from_local_datetime(&local) {
+            LocalResult::Single(at) | LocalResult::Ambiguous(at, _) => at.with_timezone(&Utc),
+            // Clocks never change twice within a day, so a day earlier is
+            // still before the gap
+            LocalResult::None => self.at_offset(local, local.and_utc() - Duration::days(1)),
+        }
+    }
+
+    /// `local` read with the zone's offset at `at`
+    fn at_offset(&self, local: NaiveDateTime, at: DateTime<Utc>) -> DateTime<Utc> {
+        local.and_utc() - Duration::seconds(i64::from(self.offset_at(at).local_minus_utc()))
+    }
+}
+
+impl fmt::Display for TimeZone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fixed(minutes) => f.write_str(&format_utc_offset(*minutes)),
+            Self::Named(zone) => f.write_str(zone.name()),
+        }
+    }
+}
+
+impl FromStr for TimeZone {
+    type Err = StockError;
+
+    /// Parse a UTC offset (`UTC+8`, `-05:00`) or an IANA zone name
+    /// (`America/New_York`, matched case-insensitively)
+    fn from_str(s: &str) -> Result<Self> {
+        if let Some(minutes) = parse_utc_offset(s) {
+            return Ok(Self::Fixed(minutes));
+        }
+        let name = s.trim();
+        name.parse::<Tz>()
+            .ok()
+            .or_else(|| {
+                TZ_VARIANTS
+                    .iter()
+                    .copied()
+                    .find(|zone| zone.name().eq_ignore_ascii_case(name))
+            })
+            .map(Self::Named)
+            .ok_or_else(|| {
+                StockError::CommandError(format!(
+                    "Unknown time zone: {s}. Use e.g. America/New_York, Asia/Shanghai or UTC+8"
+                ))
+            })
+    }
+}
+
+impl TryFrom<String> for TimeZone {
+    type Error = StockError;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<TimeZone> for String {
+    fn from(zone: TimeZone) -> Self {
+        zone.to_string()
+    }
+}
+
+/// Parse a UTC offset such as `UTC+8`, `GMT-5`, `+05:30` or `-0300` into
+/// minutes east of UTC
+pub fn parse_utc_offset(s: &str) -> Option<i32> {
+    let upper = s.trim().to_uppercase();
+    let rest = upper
+        .strip_prefix("UTC")
+        .or_else(|| upper.strip_prefix("GMT"))
+        .unwrap_or(&upper);
+    if rest.is_empty() {
+        return (upper != rest).then_some(0);
+    }
+
+    let (sign, digits) = match (rest.strip_prefix('+'), rest.strip_prefix('-')) {
+        (Some(digits), _) => (1, digits),
+        (_, Some(digits)) => (-1, digits),
+        _ => return None,
+    };
+    let (hours, minutes) = match digits.split_once(':') {
+        Some((hours, minutes)) => (hours, minutes),
+        None if digits.len() == 4 => digits.split_at(2),
+        None => (digits, "0"),
+    };
+    let hours: i32 = hours.parse().ok()?;
+    let minutes: i32 = minutes.parse().ok()?;
+    if minutes >= 60 {
+        return None;
+    }
+    let offset = sign * (hours * 60 + minutes);
+    (offset.abs() <= MAX_UTC_OFFSET_MINUTES).then_some(offset)
+}
+
+/// Format minutes east of UTC as `UTC+08:00`
+pub fn format_utc_offset(minutes: i32) -> String {
+    let sign = if minutes < 0 { '-' } else { '+' };
+    let minutes = minutes.abs();
+    format!("UTC{sign}{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    fn local(y: i32, m: u32, d: u32, h: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, min, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_fixed_offsets() {
+        assert_eq!(parse_utc_offset("UTC+8"), Some(480));
+        assert_eq!(parse_utc_offset("gmt-5"), Some(-300));
+        assert_eq!(parse_utc_offset("+05:30"), Some(330));
+        assert_eq!(parse_utc_offset("-0330"), Some(-210));
+        assert_eq!(parse_utc_offset("UTC"), Some(0));
+        assert_eq!(parse_utc_offset("UTC+15"), None);
+        assert_eq!(parse_utc_offset("8"), None);
+        assert_eq!(parse_utc_offset(""), None);
+        assert_eq!(format_utc_offset(-210), "UTC-03:30");
+
+        let zone: TimeZone = "UTC+8".parse().unwrap();
+        assert_eq!(zone, TimeZone::Fixed(480));
+        assert_eq!(zone.to_string(), "UTC+08:00");
+        let local = zone.to_local(utc(2024, 3, 4, 19, 0));
+        assert_eq!(local.to_string(), "2024-03-05 03:00:00");
+        assert_eq!(zone.from_local(local), utc(2024, 3, 4, 19, 0));
+        assert!("Not/A_Zone".parse::<TimeZone>().is_err());
+        assert!("../etc/passwd".parse::<TimeZone>().is_err());
+    }
+
+    #[test]
+    fn test_named_zones_follow_daylight_saving() {
+        let zone: TimeZone = "america/new_york".parse().unwrap();
+        assert_eq!(zone, TimeZone::Named(Tz::America__New_York));
+        assert_eq!(zone.to_string(), "America/New_York");
+        assert_eq!(
+            zone.offset_at(utc(2024, 1, 15, 12, 0)).local_minus_utc(),
+            -5 * 3600
+        );
+        assert_eq!(
+            zone.offset_at(utc(2024, 7, 15, 12, 0)).local_minus_utc(),
+            -4 * 3600
+        );
+        // Clocks change at 07:00 UTC on the second Sunday of March
+        assert_eq!(
+            zone.offset_at(utc(2030, 3, 10, 6, 59)).local_minus_utc(),
+            -5 * 3600
+        );
+        assert_eq!(
+            zone.offset_at(utc(2030, 3, 10, 7, 0)).local_minus_utc(),
+            -4 * 3600
+        );
+
+        assert_eq!(
+            zone.from_local(local(2024, 7, 15, 7, 0)),
+            utc(2024, 7, 15, 11, 0)
+        );
+        // 02:30 does not exist on 2024-03-10; it reads as 03:30 EDT
+        assert_eq!(
+            zone.from_local(local(2024, 3, 10, 2, 30)),
+            utc(2024, 3, 10, 7, 30)
+        );
+        // 01:30 happens twice on 2024-11-03; the first is still EDT
+        assert_eq!(
+            zone.from_local(local(2024, 11, 3, 1, 30)),
+            utc(2024, 11, 3, 5, 30)
+        );
+
+        let sydney: TimeZone = "Australia/Sydney".parse().unwrap();
+        assert_eq!(
+            sydney.offset_at(utc(2030, 1, 15, 0, 0)).local_minus_utc(),
+            11 * 3600
+        );
+        assert_eq!(
+            sydney.offset_at(utc(2030, 7, 15, 0, 0)).local_minus_utc(),
+            10 * 3600
+        );
+
+        let json = serde_json::to_string(&zone).unwrap();
+        assert_eq!(json, "\"America/New_York\"");
+        assert_eq!(serde_json::from_str::<TimeZone>(&json).unwrap(), zone);
+    }
+}
@@ -0,0 +1,353 @@
+//! Tool for backtesting technical trading signals
+//!
+//! Lets the technical analyzer answer "how would this signal have performed
+//! over the last two years" by replaying daily candles through a
+//! [`Strategy`] instead of reasoning about it.
+
+use agent_core::Result as AgentResult;
+use agent_tools::Tool;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+use crate::api::{AlphaVantageClient, FredClient, YahooFinanceClient, fred_series};
+use crate::backtest::{BacktestConfig, BacktestReport, Candle, Strategy, run_backtest};
+use crate::cache::{CacheKey, StockCache};
+use crate::config::{DataProvider, StockConfig};
+use crate::error::{Result, StockError};
+use crate::inflation;
+
+/// Trades listed in the tool output; the rest are only counted
+const MAX_TRADES_SHOWN: usize = 20;
+
+/// Parameters for a backtest request
+#[derive(Debug, Deserialize)]
+struct BacktestParams {
+    symbol: String,
+    /// "rsi_cross", "macd_signal" or "sma_cross"
+    strategy: String,
+    /// History to replay: "6mo", "1y", "2y", "5y" or "10y"
+    #[serde(default = "default_range")]
+    range: String,
+    /// RSI period
+    period: Option<usize>,
+    oversold: Option<f64>,
+    overbought: Option<f64>,
+    /// Fast moving average period
+    fast: Option<usize>,
+    /// Slow moving average period
+    slow: Option<usize>,
+    /// MACD signal line period
+    signal: Option<usize>,
+    initial_capital: Option<f64>,
+    commission_pct: Option<f64>,
+    /// Also report returns deflated by CPI
+    #[serde(default)]
+    inflation_adjusted: bool,
+}
+
+fn default_range() -> String {
+    "2y".to_string()
+}
+
+impl BacktestParams {
+    /// The named strategy with any overridden parameters
+    fn strategy(&self) -> Result<Strategy> {
+        let strategy = match self.strategy.parse()? {
+            Strategy::RsiCross {
+                period,
+                oversold,
+                overbought,
+            } => Strategy::RsiCross {
+                period: self.period.unwrap_or(period),
+                oversold: self.oversold.unwrap_or(oversold),
+                overbought: self.overbought.unwrap_or(overbought),
+            },
+            Strategy::MacdSignal { fast, slow, signal } => Strategy::MacdSignal {
+                fast: self.fast.unwrap_or(fast),
+                slow: self.slow.unwrap_or(slow),
+                signal: self.signal.unwrap_or(signal),
+            },
+            Strategy::SmaCross { fast, slow } => Strategy::SmaCross {
+                fast: self.fast.unwrap_or(fast),
+                slow: self.slow.unwrap_or(slow),
+            },
+        };
+        strategy.validate()?;
+        Ok(strategy)
+    }
+
+    fn config(&self) -> BacktestConfig {
+        let defaults = BacktestConfig::default();
+        BacktestConfig {
+            initial_capital: self.initial_capital.unwrap_or(defaults.initial_capital),
+            commission_pct: self.commission_pct.unwrap_or(defaults.commission_pct),
+        }
+    }
+}
+
+/// Calendar days covered by a history range
+fn range_days(range: &str) -> Option<i64> {
+    match range {
+        "6mo" => Some(180),
+        "1y" => Some(365),
+        "2y" => Some(730),
+        "5y" => Some(1825),
+        "10y" => Some(3650),
+        _ => None,
+    }
+}
+
+/// Tool for replaying price history through a technical strategy
+pub struct BacktestTool {
+    yahoo_client: YahooFinanceClient,
+    /// Used for candles when Alpha Vantage is the configured data provider
+    alpha_vantage: Option<AlphaVantageClient>,
+    /// CPI source for inflation-adjusted returns
+    fred_client: Option<FredClient>,
+    cache: StockCache,
+}
+
+impl BacktestTool {
+    /// Create a new backtest tool
+    pub fn new(config: Arc<StockConfig>, cache: StockCache) -> Self {
+        let alpha_vantage = AlphaVantageClient::from_config(&config)
+            .filter(|_| config.default_provider == DataProvider::AlphaVantage);
+
+        let fred_client = config
+            .fred_api_key
+            .as_ref()
+            .map(|key| FredClient::new(key.clone(), None));
+
+        Self {
+            yahoo_client: YahooFinanceClient::new(),
+            alpha_vantage,
+            fred_client,
+            cache,
+        }
+    }
+
+    /// Daily candles for `symbol` over `range`, oldest first
+    async fn fetch_candles(&self, symbol: &str, range: &str, days: i64) -> Result<Vec<Candle>> {
+        let Some(alpha_vantage) = &self.alpha_vantage else {
+            let quotes = self
+                .yahoo_client
+                .get_historical_range(symbol, range)
+                .await?;
+            return Ok(quotes.iter().map(Candle::from).collect());
+        };
+
+        let start = (Utc::now() - Duration::days(days)).date_naive();
+        let mut candles: Vec<Candle> = alpha_vantage
+            .get_daily_full(symbol)
+            .await?
+            .iter()
+            .filter_map(Candle::from_time_series)
+            .filter(|candle| candle.date >= start)
+            .collect();
+        candles.sort_by_key(|candle| candle.date);
+        Ok(candles)
+    }
+
+    /// Run the backtest and summarize it for the agent
+    async fn backtest(&self, params: BacktestParams) -> Result<Value> {
+        let symbol = params.symbol.to_uppercase();
+        let strategy = params.strategy()?;
+        let config = params.config();
+        let days = range_days(&params.range).ok_or_else(|| {
+            StockError::IndicatorError(format!(
+                "Unsupported range: {}. Supported: 6mo, 1y, 2y, 5y, 10y",
+                params.range
+            ))
+        })?;
+
+        let cache_key = CacheKey::new(
+            &symbol,
+            "backtest",
+            json!({
+                "range": params.range,
+                "strategy": strategy,
+                "config": config,
+                "real": params.inflation_adjusted,
+            }),
+        );
+        self.cache
+            .get_or_fetch(cache_key, || async {
+                let candles = self.fetch_candles(&symbol, &params.range, days).await?;
+                let report = run_backtest(&symbol, &candles, &strategy, &config)?;
+                let real_returns = if params.inflation_adjusted {
+                    self.real_returns(&report).await
+                } else {
+                    Value::Null
+                };
+
+                let trade_count = report.trades.len();
+                let recent_trades = &report.trades[trade_count.saturating_sub(MAX_TRADES_SHOWN)..];
+                Ok::<_, StockError>(json!({
+                    "symbol": symbol,
+                    "strategy": strategy.to_string(),
+                    "parameters": strategy,
+                    "range": params.range,
+                    "period": { "start": report.start, "end": report.end, "bars": report.bars },
+                    "initial_capital": report.initial_capital,
+                    "final_equity": report.final_equity,
+                    "total_return_pct": report.total_return_pct,
+                    "annualized_return_pct": report.annualized_return_pct,
+                    "buy_and_hold_return_pct": report.buy_and_hold_return_pct,
+                    "max_drawdown_pct": report.max_drawdown_pct,
+                    "sharpe_ratio": report.sharpe_ratio,
+                    "win_rate_pct": report.win_rate_pct,
+                    "exposure_pct": report.exposure_pct,
+                    "commission_pct": config.commission_pct,
+                    "inflation_adjusted": real_returns,
+                    "trade_count": trade_count,
+                    "trades": recent_trades,
+                    "summary": report.to_string(),
+                    "assumptions": "Long only, fully invested while in a position; signals on \
+                                    the close are filled at the next open; no slippage, \
+                                    dividends or taxes.",
+                }))
+            })
+            .await
+    }
+
+    /// The report's returns deflated by CPI over the test period, if available
+    async fn real_returns(&self, report: &BacktestReport) -> Value {
+        let adjustment =
+            match inflation::fetch_adjustment(self.fred_client.as_ref(), report.start, report.end)
+                .await
+            {
+                Ok(adjustment) => adjustment,
+                Err(e) => return json!({ "unavailable": e.to_string() }),
+            };
+        json!({
+            "total_return_pct": adjustment.real_return_pct(report.total_return_pct),
+            "annualized_return_pct": adjustment.real_annualized_pct(report.total_return_pct),
+            "buy_and_hold_return_pct": adjustment.real_return_pct(report.buy_and_hold_return_pct),
+            "inflation_pct": adjustment.inflation_pct,
+            "annualized_inflation_pct": adjustment.annualized_inflation_pct(),
+            "cpi_start_month": adjustment.start_month,
+            "cpi_end_month": adjustment.end_month,
+            "cpi_series": fred_series::CPI,
+        })
+    }
+}
+
+#[async_trait]
+impl Tool for BacktestTool {
+    async fn execute(&self, params: Value) -> AgentResult<Value> {
+        let params: BacktestParams = serde_json::from_value(params)
+            .map_err(|e| agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}")))?;
+
+        self.backtest(params)
+            .await
+            .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))
+    }
+
+    fn name(&self) -> &'static str {
+        "backtest_strategy"
+    }
+
+    fn description(&self) -> &'static str {
+        "Backtest a technical trading signal on a stock's daily price history: RSI \
+         crossing oversold/overbought levels, MACD crossing its signal line, or a fast \
+         SMA crossing a slow SMA. Returns total and annualized return against buy and \
+         hold, max drawdown, Sharpe ratio, win rate and the trade log. Use it for \
+         questions like how a signal would have performed over the last two years. Set \
+         inflation_adjusted to also get returns deflated by US CPI."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "symbol": {
+                    "type": "string",
+                    "description": "Stock ticker symbol"
+                },
+                "strategy": {
+                    "type": "string",
+                    "enum": ["rsi_cross", "macd_signal", "sma_cross"],
+                    "description": "Signal to test: rsi_cross (defaults RSI 14, 30/70), \
+                                    macd_signal (12/26/9) or sma_cross (50/200)"
+                },
+                "range": {
+                    "type": "string",
+                    "enum": ["6mo", "1y", "2y", "5y", "10y"],
+                    "default": "2y",
+                    "description": "History to replay"
+                },
+                "period": { "type": "integer", "description": "RSI period" },
+                "oversold": { "type": "number", "description": "RSI buy level" },
+                "overbought": { "type": "number", "description": "RSI sell level" },
+                "fast": { "type": "integer", "description": "Fast MACD EMA or SMA period" },
+                "slow": { "type": "integer", "description": "Slow MACD EMA or SMA period" },
+                "signal": { "type": "integer", "description": "MACD signal line period" },
+                "initial_capital": {
+                    "type": "number",
+                    "description": "Starting cash (default 10000)"
+                },
+                "commission_pct": {
+                    "type": "number",
+                    "description": "Commission per trade in percent (default 0)"
+                },
+                "inflation_adjusted": {
+                    "type": "boolean",
+                    "default": false,
+                    "description": "Also report real returns deflated by US CPI (needs FRED)"
+                }
+            },
+            "required": ["symbol", "strategy"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_params_strategy() {
+        let params: BacktestParams = serde_json::from_value(json!({
+            "symbol": "AAPL",
+            "strategy": "sma_cross",
+            "fast": 20,
+        }))
+        .unwrap();
+        assert_eq!(params.range, "2y");
+        assert_eq!(
+            params.strategy().unwrap(),
+            Strategy::SmaCross {
+                fast: 20,
+                slow: 200
+            }
+        );
+
+        let params: BacktestParams = serde_json::from_value(json!({
+            "symbol": "AAPL",
+            "strategy": "rsi_cross",
+            "oversold": 80,
+        }))
+        .unwrap();
+        assert!(params.strategy().is_err());
+        assert_eq!(range_days("5y"), Some(1825));
+        assert_eq!(range_days("max"), None);
+    }
+
+    #[test]
+    fn test_tool_metadata() {
+        let config = Arc::new(StockConfig::default());
+        let cache = StockCache::new(std::time::Duration::from_secs(3600));
+        let tool = BacktestTool::new(config, cache);
+
+        assert_eq!(tool.name(), "backtest_strategy");
+        assert!(tool.alpha_vantage.is_none());
+        assert!(tool.fred_client.is_none());
+        assert_eq!(
+            tool.input_schema()["required"],
+            json!(["symbol", "strategy"])
+        );
+    }
+}
@@ -3,13 +3,16 @@
 use agent_core::Result as AgentResult;
 use agent_tools::Tool;
 use async_trait::async_trait;
+use chrono::NaiveDate;
 use serde::Deserialize;
 use serde_json::{Value, json};
 use std::sync::Arc;
 
 use crate::api::YahooFinanceClient;
+use crate::as_of;
 use crate::cache::{CacheKey, StockCache};
 use crate::config::StockConfig;
+use crate::crypto;
 use crate::error::Result;
 
 /// Tool for preparing chart data
@@ -26,6 +29,8 @@ struct ChartParams {
     range: String,
     #[serde(default)]
     indicators: Option<Vec<String>>,
+    #[serde(default)]
+    as_of: Option<NaiveDate>,
 }
 
 fn default_range() -> String {
@@ -44,19 +49,26 @@ impl ChartDataTool {
 
     /// Prepare chart data
     async fn prepare_chart_data(&self, params: ChartParams) -> Result<Value> {
-        let symbol = params.symbol.to_uppercase();
+        let symbol = crypto::normalize_symbol(&params.symbol);
 
         // Create cache key
         let cache_key = CacheKey::new(
             &symbol,
             "chart",
-            json!({"range": &params.range, "indicators": &params.indicators}),
+            json!({"range": &params.range, "indicators": &params.indicators, "as_of": params.as_of}),
         );
 
         // Try to get from cache
         let result = self.cache.get_or_fetch(cache_key, || async {
             // Fetch historical data
-            let quotes = self.yahoo_client.get_historical_range(&symbol, &params.range).await?;
+            let quotes = match params.as_of {
+                Some(date) => {
+                    self.yahoo_client
+                        .get_historical_range_at(&symbol, &params.range, as_of::cutoff(date))
+                        .await?
+                }
+                None => self.yahoo_client.get_historical_range(&symbol, &params.range).await?,
+            };
 
             // Prepare candlestick data
             let candlestick_data: Vec<_> = quotes.iter().map(|q| json!({
@@ -108,6 +120,28 @@ impl ChartDataTool {
                                 }
                             }
                         }
+                    } else if let Some(period) = indicator
+                        .strip_prefix("RSI_")
+                        .and_then(|p| p.parse::<usize>().ok())
+                        && period > 0
+                        && period < closes.len()
+                    {
+                        use ta::{Next, indicators::RelativeStrengthIndex};
+
+                        // RSI is meaningless until the first full period
+                        if let Ok(mut rsi) = RelativeStrengthIndex::new(period) {
+                            let rsi_data: Vec<_> = quotes
+                                .iter()
+                                .map(|q| (q, rsi.next(q.close)))
+                                .skip(period)
+                                .map(|(q, val)| json!({
+                                    "timestamp": q.timestamp.to_rfc3339(),
+                                    "value": val,
+                                }))
+                                .collect();
+
+                            indicator_data[indicator] = json!(rsi_data);
+                        }
                     }
                 }
             }
@@ -135,9 +169,8 @@ impl ChartDataTool {
 #[async_trait]
 impl Tool for ChartDataTool {
     async fn execute(&self, params: Value) -> AgentResult<Value> {
-        let params: ChartParams = serde_json::from_value(params).map_err(|e| {
-            agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}"))
-        })?;
+        let params: ChartParams = serde_json::from_value(params)
+            .map_err(|e| agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}")))?;
 
         self.prepare_chart_data(params)
             .await
@@ -160,7 +193,7 @@ impl Tool for ChartDataTool {
             "properties": {
                 "symbol": {
                     "type": "string",
-                    "description": "Stock ticker symbol"
+                    "description": "Stock ticker symbol, or crypto pair (e.g., 'BTC-USD')"
                 },
                 "range": {
                     "type": "string",
@@ -171,8 +204,9 @@ impl Tool for ChartDataTool {
                 "indicators": {
                     "type": "array",
                     "items": { "type": "string" },
-                    "description": "Technical indicators to include (e.g., ['SMA_20', 'SMA_50'])"
-                }
+                    "description": "Technical indicators to include (e.g., ['SMA_20', 'SMA_50', 'RSI_14'])"
+                },
+                "as_of": as_of::param_schema()
             },
             "required": ["symbol"]
         })
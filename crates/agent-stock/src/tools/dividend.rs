@@ -0,0 +1,513 @@
+//! Tool for dividend history and yield
+//!
+//! Built from the dividend events in Yahoo's chart API: trailing and forward
+//! yield, payment frequency, the last and next expected ex-dividend dates,
+//! calendar-year totals, and how many years in a row the dividend has grown.
+
+use agent_core::Result as AgentResult;
+use agent_tools::Tool;
+use async_trait::async_trait;
+use chrono::{Datelike, Days, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::api::{DividendEvent, DividendHistory, YahooFinanceClient};
+use crate::cache::{CacheKey, StockCache};
+use crate::error::{Result, StockError};
+
+/// Payments listed in [`DividendReport::recent`]
+const RECENT_PAYMENTS: usize = 8;
+/// Gaps between payments used to infer the frequency
+const FREQUENCY_GAPS: usize = 8;
+/// Years the growth rate is measured over, at most
+const GROWTH_YEARS: usize = 5;
+/// A stock with no ex-dividend date in this many days has stopped paying
+const SUSPENDED_AFTER_DAYS: i64 = 400;
+
+#[derive(Debug, Deserialize)]
+struct DividendParams {
+    symbol: String,
+}
+
+/// Dividends paid in one calendar year
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnnualDividends {
+    /// Calendar year of the ex-dividend dates
+    pub year: i32,
+    /// Total paid per share
+    pub total: f64,
+    /// Number of payments
+    pub payments: usize,
+    /// Change from the year before, in percent
+    pub growth_pct: Option<f64>,
+    /// The year is still under way
+    pub partial: bool,
+}
+
+/// Dividend yield and history of a stock
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DividendReport {
+    /// Stock symbol
+    pub symbol: String,
+    /// Currency of the price and the amounts
+    pub currency: Option<String>,
+    /// Latest price
+    pub price: Option<f64>,
+    /// Date the figures are computed for
+    pub as_of: NaiveDate,
+    /// Payments per year (12, 4, 2 or 1), when regular
+    pub payments_per_year: Option<u32>,
+    /// Dividends with an ex-date in the last 365 days
+    pub trailing_annual: f64,
+    /// Trailing dividends over the price, in percent
+    pub trailing_yield_pct: Option<f64>,
+    /// Latest payment times the payments per year
+    pub forward_annual: Option<f64>,
+    /// Forward dividends over the price, in percent
+    pub forward_yield_pct: Option<f64>,
+    /// Latest payment
+    pub last: Option<DividendEvent>,
+    /// Expected next ex-dividend date, from the usual gap between payments
+    pub next_ex_date_estimate: Option<NaiveDate>,
+    /// Consecutive complete years in which the total rose
+    pub growth_streak_years: u32,
+    /// Compound annual growth of the yearly total, in percent
+    pub growth_rate_pct: Option<f64>,
+    /// Complete years `growth_rate_pct` is measured over
+    pub growth_rate_years: usize,
+    /// No ex-dividend date in over a year
+    pub suspended: bool,
+    /// Totals per calendar year, newest first
+    pub annual: Vec<AnnualDividends>,
+    /// Latest payments, newest first
+    pub recent: Vec<DividendEvent>,
+}
+
+impl DividendReport {
+    /// Analyze `history` as of `today`
+    pub fn from_history(history: &DividendHistory, today: NaiveDate) -> Self {
+        let events: Vec<&DividendEvent> = history
+            .events
+            .iter()
+            .filter(|e| e.ex_date <= today)
+            .collect();
+        let price = history.price.filter(|price| *price > 0.0);
+        let yield_pct = |amount: f64| price.map(|price| amount / price * 100.0);
+
+        let year_ago = today.checked_sub_days(Days::new(365)).unwrap_or(today);
+        let trailing_annual: f64 = events
+            .iter()
+            .filter(|e| e.ex_date > year_ago)
+            .map(|e| e.amount)
+            .sum();
+
+        let gap = typical_gap_days(&events);
+        let payments_per_year = gap.and_then(payments_per_year);
+        let last = events.last().copied().cloned();
+        let suspended = last
+            .as_ref()
+            .is_none_or(|last| (today - last.ex_date).num_days() > SUSPENDED_AFTER_DAYS);
+        let forward_annual = match (&last, payments_per_year) {
+            (Some(last), Some(per_year)) if !suspended => Some(last.amount * f64::from(per_year)),
+            _ => None,
+        };
+        let next_ex_date_estimate = match (&last, gap) {
+            (Some(last), Some(gap)) if !suspended => {
+                last.ex_date.checked_add_days(Days::new(gap.unsigned_abs()))
+            }
+            _ => None,
+        };
+
+        let annual = annual_totals(&events, today, payments_per_year);
+        let complete: Vec<&AnnualDividends> = annual.iter().filter(|y| !y.partial).collect();
+        let growth_streak_years = complete
+            .windows(2)
+            .take_while(|pair| pair[0].total > pair[1].total)
+            .count() as u32;
+        let span = complete.len().min(GROWTH_YEARS + 1);
+        let growth_rate_pct = (span >= 2)
+            .then(|| (complete[0].total, complete[span - 1].total))
+            .filter(|(_, first)| *first > 0.0)
+            .map(|(latest, first)| ((latest / first).powf(1.0 / (span - 1) as f64) - 1.0) * 100.0);
+
+        Self {
+            symbol: history.symbol.clone(),
+            currency: history.currency.clone(),
+            price,
+            as_of: today,
+            payments_per_year,
+            trailing_annual,
+            trailing_yield_pct: yield_pct(trailing_annual),
+            forward_annual,
+            forward_yield_pct: forward_annual.and_then(yield_pct),
+            last,
+            next_ex_date_estimate,
+            growth_streak_years,
+            growth_rate_pct,
+            growth_rate_years: span.saturating_sub(1),
+            suspended,
+            annual,
+            recent: events
+                .iter()
+                .rev()
+                .take(RECENT_PAYMENTS)
+                .map(|e| (*e).clone())
+                .collect(),
+        }
+    }
+
+    /// Name of the payment frequency
+    pub fn frequency(&self) -> &'static str {
+        match self.payments_per_year {
+            Some(12) => "monthly",
+            Some(4) => "quarterly",
+            Some(2) => "semiannual",
+            Some(1) => "annual",
+            _ => "irregular",
+        }
+    }
+
+    /// Tool output, with the units spelled out for the LLM
+    fn to_value(&self) -> Value {
+        if self.recent.is_empty() {
+            return json!({
+                "symbol": self.symbol,
+                "pays_dividend": false,
+                "note": "No dividends in the last ten years",
+                "data_source": "Yahoo Finance",
+            });
+        }
+
+        let round = |value: f64| (value * 10_000.0).round() / 10_000.0;
+        let pct = |value: Option<f64>| value.map(|v| (v * 100.0).round() / 100.0);
+        json!({
+            "symbol": self.symbol,
+            "pays_dividend": !self.suspended,
+            "suspended": self.suspended,
+            "price": self.price,
+            "as_of": self.as_of.to_string(),
+            "frequency": self.frequency(),
+            "trailing_annual_dividend": round(self.trailing_annual),
+            "trailing_yield_pct": pct(self.trailing_yield_pct),
+            "forward_annual_dividend": self.forward_annual.map(round),
+            "forward_yield_pct": pct(self.forward_yield_pct),
+            "last_ex_dividend_date": self.last.as_ref().map(|e| e.ex_date.to_string()),
+            "last_amount": self.last.as_ref().map(|e| e.amount),
+            "next_ex_dividend_date_estimate": self.next_ex_date_estimate.map(|d| d.to_string()),
+            "growth_streak_years": self.growth_streak_years,
+            "growth_rate_pct": pct(self.growth_rate_pct),
+            "growth_rate_years": self.growth_rate_years,
+            "annual": self.annual.iter().map(|year| json!({
+                "year": year.year,
+                "total": round(year.total),
+                "payments": year.payments,
+                "growth_pct": pct(year.growth_pct),
+                "partial": year.partial,
+            })).collect::<Vec<_>>(),
+            "recent_payments": self.recent.iter().map(|e| json!({
+                "ex_date": e.ex_date.to_string(),
+                "amount": e.amount,
+            })).collect::<Vec<_>>(),
+            "units": {
+                "amounts": format!("{} per share", self.currency.as_deref().unwrap_or("USD")),
+                "yields": "percent of the latest price",
+            },
+            "notes": "Ex-dividend dates are when the stock starts trading without the dividend; \
+                      the next date is estimated from the usual gap between payments",
+            "data_source": "Yahoo Finance",
+        })
+    }
+}
+
+impl fmt::Display for DividendReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(last) = &self.last else {
+            return write!(
+                f,
+                "{} has not paid a dividend in the last ten years.",
+                self.symbol
+            );
+        };
+        let currency = self.currency.as_deref().unwrap_or("USD");
+        writeln!(
+            f,
+            "{} dividends ({currency}, {})",
+            self.symbol,
+            self.frequency()
+        )?;
+
+        match self.trailing_yield_pct {
+            Some(pct) => writeln!(
+                f,
+                "Trailing yield: {pct:.2}% ({:.2} a year)",
+                self.trailing_annual
+            )?,
+            None => writeln!(f, "Trailing dividends: {:.2} a year", self.trailing_annual)?,
+        }
+        if let (Some(pct), Some(annual)) = (self.forward_yield_pct, self.forward_annual) {
+            writeln!(f, "Forward yield: {pct:.2}% ({annual:.2} a year)")?;
+        }
+        write!(f, "Last ex-dividend: {} ({:.4})", last.ex_date, last.amount)?;
+        if let Some(next) = self.next_ex_date_estimate {
+            write!(f, ", next expected around {next}")?;
+        }
+        writeln!(f)?;
+        if self.suspended {
+            writeln!(f, "No dividend in over a year: the payout looks suspended.")?;
+        }
+
+        match self.growth_rate_pct {
+            Some(rate) => writeln!(
+                f,
+                "Growth: raised {} year(s) in a row, {rate:+.1}% a year over {} year(s)",
+                self.growth_streak_years, self.growth_rate_years
+            )?,
+            None => writeln!(
+                f,
+                "Growth: raised {} year(s) in a row",
+                self.growth_streak_years
+            )?,
+        }
+
+        writeln!(f, "By year:")?;
+        for year in &self.annual {
+            write!(
+                f,
+                "  {}  {:.4}  ({} payments",
+                year.year, year.total, year.payments
+            )?;
+            if year.partial {
+                write!(f, ", year to date")?;
+            }
+            write!(f, ")")?;
+            if let Some(growth) = year.growth_pct {
+                write!(f, "  {growth:+.1}%")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Median days between the latest payments
+fn typical_gap_days(events: &[&DividendEvent]) -> Option<i64> {
+    let mut gaps: Vec<i64> = events
+        .windows(2)
+        .rev()
+        .take(FREQUENCY_GAPS)
+        .map(|pair| (pair[1].ex_date - pair[0].ex_date).num_days())
+        .filter(|days| *days > 0)
+        .collect();
+    if gaps.is_empty() {
+        return None;
+    }
+    gaps.sort_unstable();
+    Some(gaps[gaps.len() / 2])
+}
+
+/// Payments per year for a typical gap, if it is a regular schedule
+fn payments_per_year(gap_days: i64) -> Option<u32> {
+    match gap_days {
+        20..=45 => Some(12),
+        70..=120 => Some(4),
+        150..=220 => Some(2),
+        300..=400 => Some(1),
+        _ => None,
+    }
+}
+
+/// Totals per calendar year, newest first
+///
+/// The current year is partial, and so is the oldest year when it has fewer
+/// payments than the schedule (the history starts part-way through it).
+fn annual_totals(
+    events: &[&DividendEvent],
+    today: NaiveDate,
+    payments_per_year: Option<u32>,
+) -> Vec<AnnualDividends> {
+    let mut by_year: BTreeMap<i32, (f64, usize)> = BTreeMap::new();
+    for event in events {
+        let entry = by_year.entry(event.ex_date.year()).or_default();
+        entry.0 += event.amount;
+        entry.1 += 1;
+    }
+    let first_year = by_year.keys().next().copied();
+
+    let mut annual: Vec<AnnualDividends> = Vec::with_capacity(by_year.len());
+    let mut previous: Option<(f64, bool)> = None;
+    for (year, (total, payments)) in by_year {
+        let partial = year >= today.year()
+            || (Some(year) == first_year
+                && payments_per_year.is_some_and(|per_year| payments < per_year as usize));
+        let growth_pct = match previous {
+            Some((prior, false)) if !partial && prior > 0.0 => Some((total / prior - 1.0) * 100.0),
+            _ => None,
+        };
+        annual.push(AnnualDividends {
+            year,
+            total,
+            payments,
+            growth_pct,
+            partial,
+        });
+        previous = Some((total, partial));
+    }
+    annual.reverse();
+    annual
+}
+
+/// Tool for dividend yield, payment history and growth
+pub struct DividendTool {
+    yahoo_client: YahooFinanceClient,
+    cache: StockCache,
+}
+
+impl DividendTool {
+    /// Create a new dividend tool
+    pub fn new(cache: StockCache) -> Self {
+        Self::with_client(cache, YahooFinanceClient::new())
+    }
+
+    /// Create a tool reading from `client`
+    pub fn with_client(cache: StockCache, client: YahooFinanceClient) -> Self {
+        Self {
+            yahoo_client: client,
+            cache,
+        }
+    }
+
+    /// Dividend report for `symbol` as of today
+    pub async fn report(&self, symbol: &str) -> Result<DividendReport> {
+        let symbol = symbol.trim().to_uppercase();
+        if symbol.is_empty() {
+            return Err(StockError::InvalidSymbol("Missing symbol".to_string()));
+        }
+
+        let cache_key = CacheKey::new(&symbol, "dividends", json!({}));
+        let history = self
+            .cache
+            .get_or_fetch(cache_key, || async {
+                let history = self.yahoo_client.get_dividends(&symbol).await?;
+                Ok::<_, StockError>(serde_json::to_value(history)?)
+            })
+            .await?;
+        let history: DividendHistory = serde_json::from_value(history)?;
+        Ok(DividendReport::from_history(
+            &history,
+            Utc::now().date_naive(),
+        ))
+    }
+}
+
+#[async_trait]
+impl Tool for DividendTool {
+    async fn execute(&self, params: Value) -> AgentResult<Value> {
+        let params: DividendParams = serde_json::from_value(params)
+            .map_err(|e| agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}")))?;
+
+        self.report(&params.symbol)
+            .await
+            .map(|report| report.to_value())
+            .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))
+    }
+
+    fn name(&self) -> &'static str {
+        "dividends"
+    }
+
+    fn description(&self) -> &'static str {
+        "Get a stock's dividend yield and history: trailing and forward yield, payment \
+         frequency, last and next expected ex-dividend dates, totals per year, the number \
+         of consecutive years the dividend was raised and its growth rate."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "symbol": {
+                    "type": "string",
+                    "description": "Stock ticker symbol (e.g., KO, JNJ)"
+                }
+            },
+            "required": ["symbol"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::testing::{MockApi, fixtures};
+    use std::time::Duration;
+
+    fn ko_history() -> DividendHistory {
+        let body: Value = serde_json::from_str(fixtures::YAHOO_CHART_KO_DIVIDENDS).unwrap();
+        DividendHistory::from_yahoo("KO", &body).unwrap()
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_yield_and_growth() {
+        let report = DividendReport::from_history(&ko_history(), date(2024, 12, 15));
+
+        assert_eq!(report.frequency(), "quarterly");
+        assert!((report.trailing_annual - 1.94).abs() < 1e-9);
+        assert!((report.trailing_yield_pct.unwrap() - 1.94 / 62.3 * 100.0).abs() < 1e-9);
+        assert!((report.forward_annual.unwrap() - 1.94).abs() < 1e-9);
+        assert_eq!(report.last.as_ref().unwrap().ex_date, date(2024, 11, 29));
+        assert!(report.next_ex_date_estimate.unwrap() > date(2025, 2, 15));
+        assert!(!report.suspended);
+
+        // 2024 is under way: the streak and growth rate use 2021-2023
+        assert_eq!(report.annual[0].year, 2024);
+        assert!(report.annual[0].partial && report.annual[0].growth_pct.is_none());
+        assert_eq!(report.growth_streak_years, 2);
+        assert_eq!(report.growth_rate_years, 2);
+        let rate = ((1.84_f64 / 1.68).sqrt() - 1.0) * 100.0;
+        assert!((report.growth_rate_pct.unwrap() - rate).abs() < 1e-9);
+        assert_eq!(report.recent.len(), RECENT_PAYMENTS);
+
+        let text = report.to_string();
+        assert!(text.contains("Trailing yield: 3.11%"), "{text}");
+        assert!(text.contains("raised 2 year(s) in a row"), "{text}");
+    }
+
+    #[test]
+    fn test_suspended_and_non_payers() {
+        let report = DividendReport::from_history(&ko_history(), date(2026, 6, 1));
+        assert!(report.suspended);
+        assert_eq!(report.trailing_annual, 0.0);
+        assert!(report.forward_annual.is_none() && report.next_ex_date_estimate.is_none());
+        assert_eq!(report.growth_streak_years, 3);
+
+        let history = DividendHistory {
+            symbol: "AMZN".to_string(),
+            currency: Some("USD".to_string()),
+            price: Some(180.0),
+            events: Vec::new(),
+        };
+        let report = DividendReport::from_history(&history, date(2024, 12, 15));
+        assert_eq!(report.to_value()["pays_dividend"], false);
+        assert!(report.to_string().contains("has not paid a dividend"));
+    }
+
+    #[tokio::test]
+    async fn test_execute() {
+        let api = MockApi::recorded().await;
+        let tool =
+            DividendTool::with_client(StockCache::new(Duration::from_secs(300)), api.yahoo());
+
+        let data = tool.execute(json!({ "symbol": "ko" })).await.unwrap();
+        assert_eq!(data["symbol"], "KO");
+        assert_eq!(data["frequency"], "quarterly");
+        assert_eq!(data["units"]["amounts"], "USD per share");
+
+        assert!(tool.execute(json!({ "symbol": "ZZZZ" })).await.is_err());
+    }
+}
@@ -5,11 +5,13 @@
 use agent_core::Result as AgentResult;
 use agent_tools::Tool;
 use async_trait::async_trait;
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 use std::sync::Arc;
 
-use crate::api::{SecEdgarClient, FilingType, FinancialData};
+use crate::api::{FilingType, FinancialData, SecEdgarClient};
+use crate::as_of;
 use crate::cache::{CacheKey, StockCache};
 use crate::config::StockConfig;
 use crate::error::Result;
@@ -25,6 +27,9 @@ struct EarningsParams {
     /// Number of periods to retrieve
     #[serde(default = "default_periods")]
     periods: usize,
+    /// Only use what was filed by the end of this day
+    #[serde(default)]
+    as_of: Option<NaiveDate>,
 }
 
 fn default_report_type() -> String {
@@ -44,6 +49,9 @@ pub struct EarningsReport {
     pub fiscal_quarter: Option<String>,
     pub filing_type: String,
     pub filing_date: String,
+    pub period_end: String,
+    /// Quarter derived from year-to-date totals rather than reported directly
+    pub derived: bool,
     pub revenue: Option<f64>,
     pub revenue_formatted: Option<String>,
     pub net_income: Option<f64>,
@@ -55,6 +63,13 @@ pub struct EarningsReport {
     pub stockholders_equity: Option<f64>,
     pub operating_income: Option<f64>,
     pub gross_profit: Option<f64>,
+    pub operating_cash_flow: Option<f64>,
+    pub capital_expenditure: Option<f64>,
+    pub free_cash_flow: Option<f64>,
+    pub research_and_development: Option<f64>,
+    pub selling_general_admin: Option<f64>,
+    pub shares_outstanding: Option<f64>,
+    pub diluted_shares: Option<f64>,
     /// Gross margin percentage
     pub gross_margin: Option<f64>,
     /// Operating margin percentage
@@ -77,10 +92,7 @@ pub struct EarningsReportTool {
 impl EarningsReportTool {
     /// Create a new earnings report tool
     pub fn new(config: Arc<StockConfig>, cache: StockCache) -> Self {
-        let sec_client = SecEdgarClient::new(
-            &config.sec_user_agent,
-            &config.sec_contact_email,
-        );
+        let sec_client = SecEdgarClient::new(&config.sec_user_agent, &config.sec_contact_email);
 
         Self {
             sec_client,
@@ -99,7 +111,8 @@ impl EarningsReportTool {
             "earnings",
             json!({
                 "type": params.report_type,
-                "periods": params.periods
+                "periods": params.periods,
+                "as_of": params.as_of,
             }),
         );
 
@@ -107,7 +120,7 @@ impl EarningsReportTool {
         let result = self
             .cache
             .get_or_fetch(cache_key, || async {
-                self.fetch_from_sec(&symbol, &params.report_type, params.periods)
+                self.fetch_from_sec(&symbol, &params.report_type, params.periods, params.as_of)
                     .await
             })
             .await?;
@@ -121,9 +134,10 @@ impl EarningsReportTool {
         symbol: &str,
         report_type: &str,
         periods: usize,
+        as_of: Option<NaiveDate>,
     ) -> Result<Value> {
         // Get CIK for the symbol
-        let cik = self.sec_client.get_cik(symbol).await?;
+        let cik = self.sec_client.resolve_cik(symbol).await?;
 
         // Determine filing type
         let filing_type = match report_type.to_lowercase().as_str() {
@@ -132,22 +146,52 @@ impl EarningsReportTool {
             _ => None, // Get both
         };
 
-        // Get filings list
-        let filings = self
-            .sec_client
-            .get_filings(&cik, filing_type, Some(periods * 2))
-            .await?;
+        // Get filings list, without those filed after the as-of date
+        let filings = match as_of {
+            Some(date) => {
+                let filings = self
+                    .sec_client
+                    .get_filings(&cik, filing_type, Some(usize::MAX))
+                    .await?;
+                filings
+                    .into_iter()
+                    .filter(|f| {
+                        NaiveDate::parse_from_str(&f.filing_date, "%Y-%m-%d")
+                            .is_ok_and(|filed| filed <= date)
+                    })
+                    .take(periods * 2)
+                    .collect()
+            }
+            None => {
+                self.sec_client
+                    .get_filings(&cik, filing_type, Some(periods * 2))
+                    .await?
+            }
+        };
 
         // Get financial data from XBRL
-        let financial_data = self
-            .sec_client
-            .get_financial_data(symbol, Some(periods as u32))
-            .await
-            .unwrap_or_default();
+        let financial_data = match as_of {
+            Some(date) => {
+                self.sec_client
+                    .get_financial_data_at(symbol, Some(periods as u32), date)
+                    .await
+            }
+            None => {
+                self.sec_client
+                    .get_financial_data(symbol, Some(periods as u32))
+                    .await
+            }
+        }
+        .unwrap_or_default();
 
-        // Build reports
+        // Build reports of the requested kind; full-year figures have no quarter
         let reports: Vec<EarningsReport> = financial_data
             .iter()
+            .filter(|fd| match filing_type {
+                Some(FilingType::Form10K) => fd.fiscal_quarter.is_none(),
+                Some(FilingType::Form10Q) => fd.fiscal_quarter.is_some(),
+                _ => true,
+            })
             .take(periods)
             .map(|fd| self.build_earnings_report(symbol, fd))
             .collect();
@@ -174,7 +218,7 @@ impl EarningsReportTool {
             json!({})
         };
 
-        Ok(json!({
+        let mut result = json!({
             "symbol": symbol,
             "cik": cik,
             "report_type": report_type,
@@ -183,7 +227,11 @@ impl EarningsReportTool {
             "filings": filing_list,
             "trends": trends,
             "data_source": "SEC EDGAR",
-        }))
+        });
+        if let Some(date) = as_of {
+            result["as_of"] = json!(date);
+        }
+        Ok(result)
     }
 
     /// Build earnings report from financial data
@@ -221,12 +269,14 @@ impl EarningsReportTool {
             company_name: String::new(), // Would need additional lookup
             fiscal_year: fd.fiscal_year.clone(),
             fiscal_quarter: fd.fiscal_quarter.clone(),
-            filing_type: if fd.fiscal_quarter.is_some() {
-                "10-Q".to_string()
-            } else {
-                "10-K".to_string()
+            // Q4 is first reported in the 10-K
+            filing_type: match fd.fiscal_quarter.as_deref() {
+                None | Some("Q4") => "10-K".to_string(),
+                Some(_) => "10-Q".to_string(),
             },
             filing_date: fd.filing_date.clone(),
+            period_end: fd.period_end.clone(),
+            derived: fd.derived,
             revenue: fd.revenue,
             revenue_formatted: fd.revenue.map(format_currency),
             net_income: fd.net_income,
@@ -238,6 +288,13 @@ impl EarningsReportTool {
             stockholders_equity: fd.stockholders_equity,
             operating_income: fd.operating_income,
             gross_profit: fd.gross_profit,
+            operating_cash_flow: fd.operating_cash_flow,
+            capital_expenditure: fd.capital_expenditure,
+            free_cash_flow: fd.free_cash_flow,
+            research_and_development: fd.research_and_development,
+            selling_general_admin: fd.selling_general_admin,
+            shares_outstanding: fd.shares_outstanding,
+            diluted_shares: fd.diluted_shares,
             gross_margin,
             operating_margin,
             net_margin,
@@ -247,28 +304,30 @@ impl EarningsReportTool {
     }
 
     /// Calculate trends across periods
+    ///
+    /// Compares the latest report with the previous one of the same kind, so
+    /// a quarter is never compared with a full year.
     fn calculate_trends(&self, reports: &[EarningsReport]) -> Value {
         let latest = &reports[0];
-        let previous = &reports[1];
+        let Some(previous) = reports[1..]
+            .iter()
+            .find(|report| report.fiscal_quarter.is_some() == latest.fiscal_quarter.is_some())
+        else {
+            return json!({});
+        };
 
         let revenue_growth = match (latest.revenue, previous.revenue) {
-            (Some(curr), Some(prev)) if prev != 0.0 => {
-                Some(((curr - prev) / prev) * 100.0)
-            }
+            (Some(curr), Some(prev)) if prev != 0.0 => Some(((curr - prev) / prev) * 100.0),
             _ => None,
         };
 
         let net_income_growth = match (latest.net_income, previous.net_income) {
-            (Some(curr), Some(prev)) if prev != 0.0 => {
-                Some(((curr - prev) / prev) * 100.0)
-            }
+            (Some(curr), Some(prev)) if prev != 0.0 => Some(((curr - prev) / prev) * 100.0),
             _ => None,
         };
 
         let eps_growth = match (latest.eps_diluted, previous.eps_diluted) {
-            (Some(curr), Some(prev)) if prev != 0.0 => {
-                Some(((curr - prev) / prev) * 100.0)
-            }
+            (Some(curr), Some(prev)) if prev != 0.0 => Some(((curr - prev) / prev) * 100.0),
             _ => None,
         };
 
@@ -278,11 +337,7 @@ impl EarningsReportTool {
         };
 
         // Determine overall trend
-        let trend_assessment = self.assess_trend(
-            revenue_growth,
-            net_income_growth,
-            eps_growth,
-        );
+        let trend_assessment = self.assess_trend(revenue_growth, net_income_growth, eps_growth);
 
         json!({
             "revenue_growth_pct": revenue_growth,
@@ -290,7 +345,7 @@ impl EarningsReportTool {
             "eps_growth_pct": eps_growth,
             "margin_change_ppt": margin_change,
             "trend_assessment": trend_assessment,
-            "comparison_periods": format!("{} Q{} vs {} Q{}",
+            "comparison_periods": format!("{} {} vs {} {}",
                 latest.fiscal_year,
                 latest.fiscal_quarter.as_deref().unwrap_or("FY"),
                 previous.fiscal_year,
@@ -331,29 +386,13 @@ impl EarningsReportTool {
 }
 
 /// Format currency in human-readable form
-fn format_currency(amount: f64) -> String {
-    let abs_amount = amount.abs();
-    let sign = if amount < 0.0 { "-" } else { "" };
-    
-    if abs_amount >= 1_000_000_000_000.0 {
-        format!("{}${:.2}T", sign, abs_amount / 1_000_000_000_000.0)
-    } else if abs_amount >= 1_000_000_000.0 {
-        format!("{}${:.2}B", sign, abs_amount / 1_000_000_000.0)
-    } else if abs_amount >= 1_000_000.0 {
-        format!("{}${:.2}M", sign, abs_amount / 1_000_000.0)
-    } else if abs_amount >= 1_000.0 {
-        format!("{}${:.2}K", sign, abs_amount / 1_000.0)
-    } else {
-        format!("{sign}${abs_amount:.2}")
-    }
-}
+pub(crate) use crate::units::format_usd as format_currency;
 
 #[async_trait]
 impl Tool for EarningsReportTool {
     async fn execute(&self, params: Value) -> AgentResult<Value> {
-        let params: EarningsParams = serde_json::from_value(params).map_err(|e| {
-            agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}"))
-        })?;
+        let params: EarningsParams = serde_json::from_value(params)
+            .map_err(|e| agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}")))?;
 
         self.fetch_earnings(params)
             .await
@@ -367,8 +406,9 @@ impl Tool for EarningsReportTool {
     fn description(&self) -> &'static str {
         "Fetch and analyze company earnings reports from SEC EDGAR. \
          Returns quarterly (10-Q) and annual (10-K) financial data including revenue, \
-         net income, EPS, margins, and financial ratios. Also provides trend analysis \
-         comparing periods."
+         net income, EPS, margins, free cash flow, R&D, SG&A, share counts and \
+         financial ratios. Quarterly series are complete, with Q4 derived from the \
+         annual report. Also provides trend analysis comparing periods."
     }
 
     fn input_schema(&self) -> Value {
@@ -391,7 +431,8 @@ impl Tool for EarningsReportTool {
                     "default": 4,
                     "minimum": 1,
                     "maximum": 20
-                }
+                },
+                "as_of": as_of::param_schema()
             },
             "required": ["symbol"]
         })
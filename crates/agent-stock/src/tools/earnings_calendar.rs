@@ -0,0 +1,283 @@
+//! Tool for upcoming earnings dates
+//!
+//! Answers "what earnings are coming this week?" from the Finnhub earnings
+//! calendar, either for a list of symbols (typically the user's watchlist)
+//! or for every company reporting in the window.
+
+use agent_core::Result as AgentResult;
+use agent_tools::Tool;
+use async_trait::async_trait;
+use chrono::{Days, NaiveDate, Utc};
+use futures::future::join_all;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+use crate::api::{EarningsEvent, FinnhubClient};
+use crate::cache::{CacheKey, StockCache};
+use crate::config::StockConfig;
+use crate::error::{Result, StockError};
+
+/// Longest look-ahead window, in days
+const MAX_DAYS: u64 = 90;
+/// Reports listed when the whole market is requested
+const MAX_MARKET_EVENTS: usize = 100;
+
+/// Parameters for an earnings calendar lookup
+#[derive(Debug, Deserialize)]
+struct EarningsCalendarParams {
+    /// Symbols to look up; every company when empty
+    #[serde(default)]
+    symbols: Vec<String>,
+    /// A single symbol, merged into `symbols`
+    #[serde(default)]
+    symbol: Option<String>,
+    /// Look `days` days ahead from today
+    #[serde(default = "default_days")]
+    days: u64,
+}
+
+fn default_days() -> u64 {
+    7
+}
+
+/// Tool for fetching upcoming earnings report dates
+pub struct EarningsCalendarTool {
+    finnhub_client: Option<FinnhubClient>,
+    cache: StockCache,
+}
+
+impl EarningsCalendarTool {
+    /// Create a new earnings calendar tool
+    pub fn new(config: Arc<StockConfig>, cache: StockCache) -> Self {
+        let finnhub_client = config
+            .finnhub_api_key
+            .as_ref()
+            .map(|key| FinnhubClient::new(key.clone(), 60));
+
+        Self {
+            finnhub_client,
+            cache,
+        }
+    }
+
+    /// Create a tool reading from `client`
+    pub fn with_client(cache: StockCache, client: FinnhubClient) -> Self {
+        Self {
+            finnhub_client: Some(client),
+            cache,
+        }
+    }
+
+    /// Look up the calendar for the requested symbols, or the whole market
+    async fn lookup(&self, params: EarningsCalendarParams) -> Result<Value> {
+        let client = self.finnhub_client.as_ref().ok_or_else(|| {
+            StockError::ConfigError(
+                "Finnhub API key required for the earnings calendar (set FINNHUB_API_KEY)"
+                    .to_string(),
+            )
+        })?;
+
+        let mut symbols: Vec<String> = params
+            .symbols
+            .iter()
+            .chain(params.symbol.as_ref())
+            .map(|symbol| symbol.trim().to_uppercase())
+            .filter(|symbol| !symbol.is_empty())
+            .collect();
+        symbols.sort();
+        symbols.dedup();
+
+        let days = params.days.clamp(1, MAX_DAYS);
+        let today = Utc::now().date_naive();
+        let end = today.checked_add_days(Days::new(days)).unwrap_or(today);
+        let (from, to) = (today.to_string(), end.to_string());
+
+        if symbols.is_empty() {
+            let cache_key = CacheKey::new("earnings_calendar", "market", json!({ "to": to }));
+            return self
+                .cache
+                .get_or_fetch(cache_key, || async {
+                    let events = client.get_earnings_calendar(&from, &to, None).await?;
+                    Ok::<_, StockError>(format_calendar(&events, &[], today, days))
+                })
+                .await;
+        }
+
+        let lookups = symbols.iter().map(|symbol| {
+            let cache_key = CacheKey::new(symbol, "earnings_calendar", json!({ "to": to }));
+            self.cache.get_or_fetch(cache_key, || async {
+                let events = client
+                    .get_earnings_calendar(&from, &to, Some(symbol))
+                    .await?;
+                Ok::<_, StockError>(serde_json::to_value(events)?)
+            })
+        });
+
+        let mut events = Vec::new();
+        let mut unavailable = Vec::new();
+        for (symbol, result) in symbols.iter().zip(join_all(lookups).await) {
+            match result.and_then(|value| Ok(serde_json::from_value::<Vec<EarningsEvent>>(value)?))
+            {
+                Ok(found) => events.extend(found),
+                Err(e) => {
+                    tracing::warn!("No earnings calendar for {}: {}", symbol, e);
+                    unavailable.push(symbol.clone());
+                }
+            }
+        }
+        events.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.symbol.cmp(&b.symbol)));
+
+        let mut result = format_calendar(&events, &unavailable, today, days);
+        result["symbols"] = json!(symbols);
+        result["without_report"] = json!(
+            symbols
+                .iter()
+                .filter(|s| !unavailable.contains(s) && !events.iter().any(|e| &e.symbol == *s))
+                .collect::<Vec<_>>()
+        );
+        Ok(result)
+    }
+}
+
+/// Tool output for `events`, with days counted from `today`
+fn format_calendar(
+    events: &[EarningsEvent],
+    unavailable: &[String],
+    today: NaiveDate,
+    days: u64,
+) -> Value {
+    let reports: Vec<Value> = events
+        .iter()
+        .take(MAX_MARKET_EVENTS)
+        .map(|event| {
+            json!({
+                "symbol": event.symbol,
+                "date": event.date.to_string(),
+                "weekday": event.date.format("%A").to_string(),
+                "days_until": (event.date - today).num_days(),
+                "timing": event.timing(),
+                "fiscal_quarter": event.quarter.zip(event.year).map(|(q, y)| format!("Q{q} {y}")),
+                "eps_estimate": event.eps_estimate,
+                "eps_actual": event.eps_actual,
+                "revenue_estimate": event.revenue_estimate,
+                "revenue_actual": event.revenue_actual,
+            })
+        })
+        .collect();
+
+    let mut result = json!({
+        "from_date": today.to_string(),
+        "days": days,
+        "report_count": events.len(),
+        "reports": reports,
+        "data_source": "Finnhub earnings calendar",
+    });
+    if events.len() > MAX_MARKET_EVENTS {
+        result["truncated"] = json!(true);
+    }
+    if !unavailable.is_empty() {
+        result["unavailable"] = json!(unavailable);
+    }
+    result
+}
+
+#[async_trait]
+impl Tool for EarningsCalendarTool {
+    async fn execute(&self, params: Value) -> AgentResult<Value> {
+        let params: EarningsCalendarParams = serde_json::from_value(params)
+            .map_err(|e| agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}")))?;
+
+        self.lookup(params)
+            .await
+            .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))
+    }
+
+    fn name(&self) -> &'static str {
+        "earnings_calendar"
+    }
+
+    fn description(&self) -> &'static str {
+        "List upcoming earnings report dates. Pass symbols (e.g. the user's watchlist) to \
+         check specific companies, or omit them to see every company reporting in the window. \
+         Each report has its date, days until it, timing (before open / after close) \
+         and consensus EPS and revenue estimates."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "symbols": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Stock ticker symbols to check; omit for the whole market"
+                },
+                "symbol": {
+                    "type": "string",
+                    "description": "A single stock ticker symbol"
+                },
+                "days": {
+                    "type": "integer",
+                    "description": "Days ahead to look (default 7, max 90)",
+                    "default": 7
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::testing::MockApi;
+    use std::time::Duration;
+
+    #[test]
+    fn test_format_calendar() {
+        let body: Value =
+            serde_json::from_str(crate::api::testing::fixtures::FINNHUB_EARNINGS_CALENDAR).unwrap();
+        let events = EarningsEvent::from_finnhub(&body).unwrap();
+        let today = NaiveDate::from_ymd_opt(2024, 4, 22).unwrap();
+
+        let result = format_calendar(&events, &["ZZZ".to_string()], today, 7);
+        assert_eq!(result["report_count"], 3);
+        assert_eq!(result["reports"][0]["symbol"], "GE");
+        assert_eq!(result["reports"][0]["days_until"], 1);
+        assert_eq!(result["reports"][0]["weekday"], "Tuesday");
+        assert_eq!(result["reports"][2]["timing"], "after market close");
+        assert_eq!(result["reports"][2]["fiscal_quarter"], "Q3 2024");
+        assert_eq!(result["unavailable"], json!(["ZZZ"]));
+        assert!(result.get("truncated").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_watchlist_lookup() {
+        let api = MockApi::recorded().await;
+        let tool = EarningsCalendarTool::with_client(
+            StockCache::new(Duration::from_secs(300)),
+            api.finnhub(60),
+        );
+
+        let data = tool
+            .execute(json!({ "symbols": ["msft", "AAPL"], "symbol": "MSFT", "days": 365 }))
+            .await
+            .unwrap();
+        assert_eq!(data["symbols"], json!(["AAPL", "MSFT"]));
+        assert_eq!(data["days"], MAX_DAYS);
+        // The recorded route ignores the symbol filter, so both lookups see every report
+        assert_eq!(data["report_count"], 6);
+        assert_eq!(data["without_report"], json!(["AAPL"]));
+    }
+
+    #[tokio::test]
+    async fn test_requires_finnhub_key() {
+        let config = Arc::new(StockConfig {
+            finnhub_api_key: None,
+            ..StockConfig::default()
+        });
+        let tool = EarningsCalendarTool::new(config, StockCache::new(Duration::from_secs(300)));
+        let err = tool.execute(json!({})).await.unwrap_err();
+        assert!(err.to_string().contains("FINNHUB_API_KEY"), "{err}");
+    }
+}
@@ -0,0 +1,780 @@
+//! Earnings quality analysis over SEC financial statements
+//!
+//! Turns the annual series from XBRL company facts into the evidence an
+//! analyst looks for before trusting reported earnings:
+//!
+//! - margin trends (gross, operating, net, free cash flow)
+//! - the accrual ratio: earnings not backed by operating cash flow
+//! - receivables and inventory growing faster than revenue
+//! - the eight Beneish M-score components and the score itself
+//!
+//! Each check that trips produces a [`RedFlag`], so the earnings quality
+//! prompt gets conclusions with numbers attached instead of raw statements.
+
+use agent_core::Result as AgentResult;
+use agent_tools::Tool;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+use crate::api::{FinancialData, SecEdgarClient};
+use crate::cache::{CacheKey, StockCache};
+use crate::config::StockConfig;
+use crate::error::{Result, StockError};
+
+/// M-score above which manipulation is likely (eight-variable model)
+const M_SCORE_LIKELY: f64 = -1.78;
+/// M-score above which results deserve a closer look
+const M_SCORE_GREY: f64 = -2.22;
+/// Accrual ratio above which earnings run well ahead of cash
+const ACCRUAL_CONCERN: f64 = 0.10;
+const ACCRUAL_WATCH: f64 = 0.05;
+/// Growth gap over revenue, in percentage points
+const GROWTH_GAP_CONCERN: f64 = 25.0;
+const GROWTH_GAP_WATCH: f64 = 10.0;
+/// Margin decline over the series, in percentage points
+const MARGIN_DECLINE_WATCH: f64 = 3.0;
+
+fn ratio(numerator: Option<f64>, denominator: Option<f64>) -> Option<f64> {
+    let (numerator, denominator) = (numerator?, denominator?);
+    (denominator.abs() > f64::EPSILON).then(|| numerator / denominator)
+}
+
+fn pct(numerator: Option<f64>, denominator: Option<f64>) -> Option<f64> {
+    ratio(numerator, denominator).map(|value| value * 100.0)
+}
+
+fn growth_pct(current: Option<f64>, previous: Option<f64>) -> Option<f64> {
+    let previous = previous?;
+    ratio(
+        current.map(|current| current - previous),
+        Some(previous.abs()),
+    )
+    .map(|g| g * 100.0)
+}
+
+/// How worrying a red flag is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FlagSeverity {
+    /// Worth monitoring, often explained by the business
+    Watch,
+    /// Classic warning sign that needs an explanation
+    Concern,
+}
+
+impl FlagSeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Watch => "watch",
+            Self::Concern => "concern",
+        }
+    }
+}
+
+/// A check that tripped
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RedFlag {
+    /// Check name, e.g. "accrual_ratio"
+    pub check: String,
+    pub severity: FlagSeverity,
+    /// The finding in plain words, with numbers
+    pub detail: String,
+}
+
+impl RedFlag {
+    fn new(check: &str, severity: FlagSeverity, detail: String) -> Self {
+        Self {
+            check: check.to_string(),
+            severity,
+            detail,
+        }
+    }
+}
+
+/// Margins of one fiscal year, in percent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct YearMargins {
+    pub fiscal_year: String,
+    pub gross_margin: Option<f64>,
+    pub operating_margin: Option<f64>,
+    pub net_margin: Option<f64>,
+    pub fcf_margin: Option<f64>,
+    /// Operating cash flow over net income
+    pub cash_conversion: Option<f64>,
+}
+
+impl YearMargins {
+    fn new(fd: &FinancialData) -> Self {
+        Self {
+            fiscal_year: fd.fiscal_year.clone(),
+            gross_margin: pct(fd.gross_profit, fd.revenue),
+            operating_margin: pct(fd.operating_income, fd.revenue),
+            net_margin: pct(fd.net_income, fd.revenue),
+            fcf_margin: pct(fd.free_cash_flow, fd.revenue),
+            cash_conversion: ratio(fd.operating_cash_flow, fd.net_income)
+                .filter(|_| fd.net_income.is_some_and(|ni| ni > 0.0)),
+        }
+    }
+}
+
+/// Change of one margin over the series
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarginTrend {
+    /// Margin name, e.g. "gross_margin"
+    pub metric: String,
+    pub from_year: String,
+    pub to_year: String,
+    pub first: f64,
+    pub latest: f64,
+    /// Percentage points
+    pub change_ppt: f64,
+    /// Whether the margin fell in every year of the series
+    pub declined_every_year: bool,
+}
+
+/// Beneish M-score components, latest year against the one before
+///
+/// Indices above 1 mean the ratio worsened. Missing indices other than SGI
+/// and TATA are taken as neutral (1.0) for the score and listed in
+/// `assumed_neutral`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BeneishComponents {
+    /// Days sales in receivables index
+    pub dsri: Option<f64>,
+    /// Gross margin index
+    pub gmi: Option<f64>,
+    /// Asset quality index
+    pub aqi: Option<f64>,
+    /// Sales growth index
+    pub sgi: Option<f64>,
+    /// Depreciation index
+    pub depi: Option<f64>,
+    /// SG&A expense index
+    pub sgai: Option<f64>,
+    /// Leverage index
+    pub lvgi: Option<f64>,
+    /// Total accruals to total assets
+    pub tata: Option<f64>,
+    pub m_score: Option<f64>,
+    pub assumed_neutral: Vec<String>,
+}
+
+impl BeneishComponents {
+    /// Components for `current` against `previous`
+    pub fn new(current: &FinancialData, previous: &FinancialData) -> Self {
+        let index = |now: Option<f64>, before: Option<f64>| ratio(now, before);
+
+        let receivables_to_sales = |fd: &FinancialData| ratio(fd.accounts_receivable, fd.revenue);
+        let gross_margin = |fd: &FinancialData| ratio(fd.gross_profit, fd.revenue);
+        // Share of assets other than current assets and PP&E
+        let soft_assets = |fd: &FinancialData| {
+            let hard = fd.current_assets? + fd.property_plant_equipment?;
+            ratio(Some(hard), fd.total_assets).map(|share| 1.0 - share)
+        };
+        let depreciation_rate = |fd: &FinancialData| {
+            ratio(
+                fd.depreciation,
+                Some(fd.depreciation? + fd.property_plant_equipment?),
+            )
+        };
+        let sga_to_sales = |fd: &FinancialData| ratio(fd.selling_general_admin, fd.revenue);
+        let leverage = |fd: &FinancialData| {
+            ratio(
+                Some(fd.current_liabilities? + fd.long_term_debt?),
+                fd.total_assets,
+            )
+        };
+
+        let dsri = index(
+            receivables_to_sales(current),
+            receivables_to_sales(previous),
+        );
+        let gmi = index(gross_margin(previous), gross_margin(current));
+        let aqi = index(soft_assets(current), soft_assets(previous));
+        let sgi = index(current.revenue, previous.revenue);
+        let depi = index(depreciation_rate(previous), depreciation_rate(current));
+        let sgai = index(sga_to_sales(current), sga_to_sales(previous));
+        let lvgi = index(leverage(current), leverage(previous));
+        let tata = ratio(
+            current
+                .net_income
+                .zip(current.operating_cash_flow)
+                .map(|(ni, ocf)| ni - ocf),
+            current.total_assets,
+        );
+
+        let mut assumed_neutral = Vec::new();
+        let mut neutral = |name: &str, value: Option<f64>| {
+            value.unwrap_or_else(|| {
+                assumed_neutral.push(name.to_string());
+                1.0
+            })
+        };
+        let m_score = sgi.zip(tata).map(|(sgi, tata)| {
+            -4.84
+                + 0.920 * neutral("DSRI", dsri)
+                + 0.528 * neutral("GMI", gmi)
+                + 0.404 * neutral("AQI", aqi)
+                + 0.892 * sgi
+                + 0.115 * neutral("DEPI", depi)
+                - 0.172 * neutral("SGAI", sgai)
+                + 4.679 * tata
+                - 0.327 * neutral("LVGI", lvgi)
+        });
+
+        Self {
+            dsri,
+            gmi,
+            aqi,
+            sgi,
+            depi,
+            sgai,
+            lvgi,
+            tata,
+            m_score,
+            assumed_neutral,
+        }
+    }
+
+    /// Components above the average of known manipulators
+    pub fn elevated(&self) -> Vec<(&'static str, f64)> {
+        [
+            ("DSRI", self.dsri, 1.465),
+            ("GMI", self.gmi, 1.193),
+            ("AQI", self.aqi, 1.254),
+            ("SGI", self.sgi, 1.607),
+            ("DEPI", self.depi, 1.077),
+            ("SGAI", self.sgai, 1.041),
+            ("LVGI", self.lvgi, 1.111),
+            ("TATA", self.tata, 0.031),
+        ]
+        .into_iter()
+        .filter_map(|(name, value, threshold)| value.filter(|v| *v > threshold).map(|v| (name, v)))
+        .collect()
+    }
+}
+
+/// Quantitative earnings quality evidence for one company
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QualityReport {
+    pub symbol: String,
+    /// Latest fiscal year analyzed
+    pub fiscal_year: String,
+    /// Margins per year, newest first
+    pub margins: Vec<YearMargins>,
+    pub margin_trends: Vec<MarginTrend>,
+    /// (Net income − operating cash flow) / average total assets
+    pub accrual_ratio: Option<f64>,
+    /// Receivables growth minus revenue growth, in percentage points
+    pub receivables_growth_gap: Option<f64>,
+    /// Inventory growth minus revenue growth, in percentage points
+    pub inventory_growth_gap: Option<f64>,
+    pub beneish: Option<BeneishComponents>,
+    pub red_flags: Vec<RedFlag>,
+}
+
+impl QualityReport {
+    /// Analyze annual statements, newest first
+    ///
+    /// Quarterly entries are ignored. Returns None without any annual data.
+    pub fn from_annual(symbol: &str, data: &[FinancialData]) -> Option<Self> {
+        let annual: Vec<&FinancialData> = data
+            .iter()
+            .filter(|fd| fd.fiscal_quarter.is_none())
+            .collect();
+        let current = *annual.first()?;
+        let previous = annual.get(1).copied();
+
+        let margins: Vec<YearMargins> = annual.iter().map(|fd| YearMargins::new(fd)).collect();
+        let margin_trends = margin_trends(&margins);
+
+        let accrual_ratio = ratio(
+            current
+                .net_income
+                .zip(current.operating_cash_flow)
+                .map(|(ni, ocf)| ni - ocf),
+            current.total_assets.map(|assets| {
+                previous
+                    .and_then(|previous| previous.total_assets)
+                    .map_or(assets, |before| f64::midpoint(assets, before))
+            }),
+        );
+
+        let revenue_growth = previous.and_then(|p| growth_pct(current.revenue, p.revenue));
+        let gap = |now: Option<f64>, before: Option<f64>| {
+            Some(growth_pct(now, before)? - revenue_growth?)
+        };
+        let receivables_growth_gap =
+            previous.and_then(|p| gap(current.accounts_receivable, p.accounts_receivable));
+        let inventory_growth_gap = previous.and_then(|p| gap(current.inventory, p.inventory));
+
+        let beneish = previous.map(|previous| BeneishComponents::new(current, previous));
+
+        let mut report = Self {
+            symbol: symbol.to_string(),
+            fiscal_year: current.fiscal_year.clone(),
+            margins,
+            margin_trends,
+            accrual_ratio,
+            receivables_growth_gap,
+            inventory_growth_gap,
+            beneish,
+            red_flags: Vec::new(),
+        };
+        report.red_flags = report.flags(revenue_growth);
+        Some(report)
+    }
+
+    fn flags(&self, revenue_growth: Option<f64>) -> Vec<RedFlag> {
+        let mut flags = Vec::new();
+
+        for trend in &self.margin_trends {
+            if trend.change_ppt <= -MARGIN_DECLINE_WATCH {
+                let severity = if trend.declined_every_year {
+                    FlagSeverity::Concern
+                } else {
+                    FlagSeverity::Watch
+                };
+                flags.push(RedFlag::new(
+                    "margin_trend",
+                    severity,
+                    format!(
+                        "{} fell {:.1} ppt from {:.1}% in FY{} to {:.1}% in FY{}{}",
+                        trend.metric.replace('_', " "),
+                        -trend.change_ppt,
+                        trend.first,
+                        trend.from_year,
+                        trend.latest,
+                        trend.to_year,
+                        if trend.declined_every_year {
+                            ", declining every year"
+                        } else {
+                            ""
+                        }
+                    ),
+                ));
+            }
+        }
+
+        if let Some(accruals) = self.accrual_ratio
+            && accruals > ACCRUAL_WATCH
+        {
+            let severity = if accruals > ACCRUAL_CONCERN {
+                FlagSeverity::Concern
+            } else {
+                FlagSeverity::Watch
+            };
+            flags.push(RedFlag::new(
+                "accrual_ratio",
+                severity,
+                format!(
+                    "Net income exceeded operating cash flow by {:.1}% of average assets",
+                    accruals * 100.0
+                ),
+            ));
+        }
+
+        let growth = revenue_growth.unwrap_or_default();
+        for (check, item, gap) in [
+            (
+                "receivables_growth",
+                "Receivables",
+                self.receivables_growth_gap,
+            ),
+            ("inventory_growth", "Inventory", self.inventory_growth_gap),
+        ] {
+            if let Some(gap) = gap
+                && gap > GROWTH_GAP_WATCH
+            {
+                let severity = if gap > GROWTH_GAP_CONCERN {
+                    FlagSeverity::Concern
+                } else {
+                    FlagSeverity::Watch
+                };
+                flags.push(RedFlag::new(
+                    check,
+                    severity,
+                    format!(
+                        "{item} grew {:.1}% against revenue {growth:+.1}% ({gap:+.1} ppt)",
+                        growth + gap
+                    ),
+                ));
+            }
+        }
+
+        if let Some(beneish) = &self.beneish
+            && let Some(score) = beneish.m_score
+            && score > M_SCORE_GREY
+        {
+            let severity = if score > M_SCORE_LIKELY {
+                FlagSeverity::Concern
+            } else {
+                FlagSeverity::Watch
+            };
+            let elevated: Vec<String> = beneish
+                .elevated()
+                .iter()
+                .map(|(name, value)| format!("{name} {value:.2}"))
+                .collect();
+            flags.push(RedFlag::new(
+                "beneish_m_score",
+                severity,
+                format!(
+                    "Beneish M-score {score:.2} is above {:.2}{}",
+                    if score > M_SCORE_LIKELY {
+                        M_SCORE_LIKELY
+                    } else {
+                        M_SCORE_GREY
+                    },
+                    if elevated.is_empty() {
+                        String::new()
+                    } else {
+                        format!(", driven by {}", elevated.join(", "))
+                    }
+                ),
+            ));
+        }
+
+        flags
+    }
+
+    /// The evidence as bullet points for the earnings quality prompt
+    pub fn evidence(&self) -> String {
+        let mut lines = Vec::new();
+        let fmt = |value: Option<f64>| value.map_or("n/a".to_string(), |v| format!("{v:.1}%"));
+
+        for year in &self.margins {
+            lines.push(format!(
+                "- FY{} margins: gross {}, operating {}, net {}, free cash flow {}{}",
+                year.fiscal_year,
+                fmt(year.gross_margin),
+                fmt(year.operating_margin),
+                fmt(year.net_margin),
+                fmt(year.fcf_margin),
+                year.cash_conversion
+                    .map(|c| format!("; operating cash flow {c:.2}x net income"))
+                    .unwrap_or_default()
+            ));
+        }
+        if let Some(accruals) = self.accrual_ratio {
+            lines.push(format!(
+                "- Accrual ratio (net income − operating cash flow, over average assets): {:.1}%",
+                accruals * 100.0
+            ));
+        }
+        if let Some(gap) = self.receivables_growth_gap {
+            lines.push(format!(
+                "- Receivables growth minus revenue growth: {gap:+.1} ppt"
+            ));
+        }
+        if let Some(gap) = self.inventory_growth_gap {
+            lines.push(format!(
+                "- Inventory growth minus revenue growth: {gap:+.1} ppt"
+            ));
+        }
+        if let Some(beneish) = &self.beneish {
+            let components: Vec<String> = [
+                ("DSRI", beneish.dsri),
+                ("GMI", beneish.gmi),
+                ("AQI", beneish.aqi),
+                ("SGI", beneish.sgi),
+                ("DEPI", beneish.depi),
+                ("SGAI", beneish.sgai),
+                ("LVGI", beneish.lvgi),
+                ("TATA", beneish.tata),
+            ]
+            .into_iter()
+            .filter_map(|(name, value)| value.map(|v| format!("{name} {v:.2}")))
+            .collect();
+            let score = beneish
+                .m_score
+                .map_or("n/a".to_string(), |s| format!("{s:.2}"));
+            lines.push(format!(
+                "- Beneish M-score {score} (above {M_SCORE_LIKELY} suggests manipulation); {}",
+                components.join(", ")
+            ));
+            if !beneish.assumed_neutral.is_empty() {
+                lines.push(format!(
+                    "- Not reported, assumed neutral: {}",
+                    beneish.assumed_neutral.join(", ")
+                ));
+            }
+        }
+
+        if self.red_flags.is_empty() {
+            lines.push("- Red flags: none".to_string());
+        } else {
+            lines.push("- Red flags:".to_string());
+            for flag in &self.red_flags {
+                lines.push(format!("  - [{}] {}", flag.severity.as_str(), flag.detail));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// Reads one margin of a year
+type MarginOf = fn(&YearMargins) -> Option<f64>;
+
+/// Change of each margin from the oldest to the latest year
+fn margin_trends(margins: &[YearMargins]) -> Vec<MarginTrend> {
+    let metrics: [(&str, MarginOf); 4] = [
+        ("gross_margin", |m| m.gross_margin),
+        ("operating_margin", |m| m.operating_margin),
+        ("net_margin", |m| m.net_margin),
+        ("fcf_margin", |m| m.fcf_margin),
+    ];
+
+    metrics
+        .into_iter()
+        .filter_map(|(metric, value)| {
+            // Oldest first
+            let series: Vec<(&str, f64)> = margins
+                .iter()
+                .rev()
+                .filter_map(|m| Some((m.fiscal_year.as_str(), value(m)?)))
+                .collect();
+            let (from_year, first) = *series.first()?;
+            let (to_year, latest) = *series.last()?;
+            (series.len() >= 2).then(|| MarginTrend {
+                metric: metric.to_string(),
+                from_year: from_year.to_string(),
+                to_year: to_year.to_string(),
+                first,
+                latest,
+                change_ppt: latest - first,
+                declined_every_year: series.windows(2).all(|pair| pair[1].1 < pair[0].1),
+            })
+        })
+        .collect()
+}
+
+/// Parameters for an earnings quality request
+#[derive(Debug, Deserialize)]
+struct EarningsQualityParams {
+    symbol: String,
+    /// Fiscal years to analyze
+    #[serde(default = "default_years")]
+    years: u32,
+}
+
+fn default_years() -> u32 {
+    5
+}
+
+/// Tool computing earnings quality evidence and red flags from SEC filings
+pub struct EarningsQualityTool {
+    sec_client: SecEdgarClient,
+    cache: StockCache,
+}
+
+impl EarningsQualityTool {
+    /// Create a new earnings quality tool
+    pub fn new(config: Arc<StockConfig>, cache: StockCache) -> Self {
+        let sec_client = SecEdgarClient::new(&config.sec_user_agent, &config.sec_contact_email);
+
+        Self { sec_client, cache }
+    }
+
+    /// Analyze the last `years` fiscal years of `symbol`
+    pub async fn report(&self, symbol: &str, years: u32) -> Result<QualityReport> {
+        let symbol = symbol.to_uppercase();
+        let years = years.clamp(2, 10);
+        let cache_key = CacheKey::new(&symbol, "earnings_quality", json!({ "years": years }));
+
+        let value = self
+            .cache
+            .get_or_fetch(cache_key, || async {
+                let data = self
+                    .sec_client
+                    .get_financial_data(&symbol, Some(years))
+                    .await?;
+                let report = QualityReport::from_annual(&symbol, &data).ok_or_else(|| {
+                    StockError::data_unavailable(
+                        &symbol,
+                        "no annual financial statements in SEC filings",
+                    )
+                })?;
+                Ok::<_, StockError>(serde_json::to_value(report)?)
+            })
+            .await?;
+
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+#[async_trait]
+impl Tool for EarningsQualityTool {
+    async fn execute(&self, params: Value) -> AgentResult<Value> {
+        let params: EarningsQualityParams = serde_json::from_value(params)
+            .map_err(|e| agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}")))?;
+
+        let report = self
+            .report(&params.symbol, params.years)
+            .await
+            .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))?;
+
+        Ok(json!({
+            "report": report,
+            "evidence": report.evidence(),
+            "data_source": "SEC EDGAR",
+        }))
+    }
+
+    fn name(&self) -> &'static str {
+        "earnings_quality"
+    }
+
+    fn description(&self) -> &'static str {
+        "Compute earnings quality evidence from SEC filings: margin trends, the accrual \
+         ratio, receivables and inventory growth against revenue, and the Beneish M-score \
+         with its components. Returns red flags with their severity and the numbers \
+         behind them."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "symbol": {
+                    "type": "string",
+                    "description": "Stock ticker symbol (e.g., AAPL, MSFT)"
+                },
+                "years": {
+                    "type": "integer",
+                    "description": "Fiscal years to analyze (default: 5)",
+                    "default": 5,
+                    "minimum": 2,
+                    "maximum": 10
+                }
+            },
+            "required": ["symbol"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn year(fiscal_year: &str, revenue: f64) -> FinancialData {
+        FinancialData {
+            revenue: Some(revenue),
+            net_income: Some(revenue * 0.2),
+            eps_basic: None,
+            eps_diluted: None,
+            total_assets: Some(revenue * 2.0),
+            total_liabilities: None,
+            stockholders_equity: None,
+            operating_income: Some(revenue * 0.25),
+            gross_profit: Some(revenue * 0.4),
+            operating_cash_flow: Some(revenue * 0.22),
+            capital_expenditure: Some(revenue * 0.05),
+            free_cash_flow: Some(revenue * 0.17),
+            research_and_development: None,
+            selling_general_admin: Some(revenue * 0.1),
+            shares_outstanding: None,
+            diluted_shares: None,
+            depreciation: Some(revenue * 0.04),
+            current_assets: Some(revenue * 0.8),
+            current_liabilities: Some(revenue * 0.5),
+            accounts_receivable: Some(revenue * 0.15),
+            inventory: Some(revenue * 0.1),
+            property_plant_equipment: Some(revenue * 0.6),
+            long_term_debt: Some(revenue * 0.4),
+            fiscal_year: fiscal_year.to_string(),
+            fiscal_quarter: None,
+            period_start: None,
+            period_end: format!("{fiscal_year}-12-31"),
+            filing_date: String::new(),
+            derived: false,
+        }
+    }
+
+    #[test]
+    fn test_steady_company_is_clean() {
+        let data = vec![year("2023", 110.0), year("2022", 100.0), year("2021", 90.0)];
+        let report = QualityReport::from_annual("STDY", &data).unwrap();
+
+        assert_eq!(report.fiscal_year, "2023");
+        assert_eq!(report.margins.len(), 3);
+        assert!((report.margins[0].gross_margin.unwrap() - 40.0).abs() < 1e-9);
+        assert!(report.accrual_ratio.unwrap() < 0.0);
+
+        // Ratios that hold steady give neutral indices
+        let beneish = report.beneish.as_ref().unwrap();
+        assert!((beneish.dsri.unwrap() - 1.0).abs() < 1e-9);
+        assert!((beneish.sgi.unwrap() - 1.1).abs() < 1e-9);
+        assert!(beneish.m_score.unwrap() < M_SCORE_GREY);
+        assert!(beneish.assumed_neutral.is_empty());
+
+        assert!(report.red_flags.is_empty());
+        assert!(report.evidence().contains("Red flags: none"));
+    }
+
+    #[test]
+    fn test_red_flags() {
+        let mut latest = year("2023", 110.0);
+        // Earnings without cash, receivables ballooning, margins squeezed
+        latest.operating_cash_flow = Some(5.0);
+        latest.accounts_receivable = Some(40.0);
+        latest.gross_profit = Some(33.0);
+        let mut middle = year("2022", 100.0);
+        middle.gross_profit = Some(36.0);
+        let data = vec![latest, middle, year("2021", 90.0)];
+
+        let report = QualityReport::from_annual("FLAG", &data).unwrap();
+        let flag = |check: &str| report.red_flags.iter().find(|f| f.check == check);
+
+        let margin = flag("margin_trend").unwrap();
+        assert_eq!(margin.severity, FlagSeverity::Concern);
+        assert!(margin.detail.starts_with("gross margin fell 10.0 ppt"));
+
+        // (22 − 5) / 210
+        assert!((report.accrual_ratio.unwrap() - 17.0 / 210.0).abs() < 1e-9);
+        assert_eq!(flag("accrual_ratio").unwrap().severity, FlagSeverity::Watch);
+
+        // Receivables 15 → 40 while revenue grew 10%
+        assert!((report.receivables_growth_gap.unwrap() - 156.666_666).abs() < 1e-3);
+        assert_eq!(
+            flag("receivables_growth").unwrap().severity,
+            FlagSeverity::Concern
+        );
+        assert!(flag("inventory_growth").is_none());
+
+        let m_score = flag("beneish_m_score").unwrap();
+        assert_eq!(m_score.severity, FlagSeverity::Concern);
+        assert!(m_score.detail.contains("DSRI"));
+        assert!(report.evidence().contains("[concern]"));
+    }
+
+    #[test]
+    fn test_missing_data() {
+        assert!(QualityReport::from_annual("NONE", &[]).is_none());
+
+        let mut only = year("2023", 100.0);
+        only.fiscal_quarter = Some("Q4".to_string());
+        assert!(QualityReport::from_annual("QTR", &[only]).is_none());
+
+        let mut latest = year("2023", 110.0);
+        latest.accounts_receivable = None;
+        let report = QualityReport::from_annual("BANK", &[latest, year("2022", 100.0)]).unwrap();
+        let beneish = report.beneish.unwrap();
+        assert_eq!(beneish.assumed_neutral, vec!["DSRI".to_string()]);
+        assert!(beneish.m_score.is_some());
+        assert!(report.receivables_growth_gap.is_none());
+    }
+
+    #[test]
+    fn test_tool_metadata() {
+        let config = Arc::new(StockConfig::default());
+        let cache = StockCache::new(std::time::Duration::from_secs(3600));
+        let tool = EarningsQualityTool::new(config, cache);
+
+        assert_eq!(tool.name(), "earnings_quality");
+        assert!(tool.description().contains("Beneish"));
+        assert_eq!(tool.input_schema()["required"], json!(["symbol"]));
+    }
+}
@@ -0,0 +1,175 @@
+//! Tool for fetching ESG scores and controversies
+
+use agent_core::Result as AgentResult;
+use agent_tools::Tool;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+use crate::api::{EsgScores, FinnhubClient, YahooFinanceClient};
+use crate::cache::{CacheKey, StockCache};
+use crate::config::{EsgProvider, StockConfig};
+use crate::error::{Result, StockError};
+
+/// Tool for fetching ESG (environmental, social, governance) scores
+pub struct EsgTool {
+    yahoo_client: YahooFinanceClient,
+    finnhub_client: Option<FinnhubClient>,
+    cache: StockCache,
+    config: Arc<StockConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EsgParams {
+    symbol: String,
+}
+
+impl EsgTool {
+    /// Create a new ESG tool
+    pub fn new(config: Arc<StockConfig>, cache: StockCache) -> Self {
+        let finnhub_client = config
+            .finnhub_api_key
+            .as_ref()
+            .map(|key| FinnhubClient::new(key.clone(), 60));
+
+        Self {
+            yahoo_client: YahooFinanceClient::new(),
+            finnhub_client,
+            cache,
+            config,
+        }
+    }
+
+    /// Fetch ESG scores from the configured provider
+    async fn fetch_esg(&self, params: EsgParams) -> Result<Value> {
+        let symbol = params.symbol.to_uppercase();
+        let provider = self.config.esg_provider;
+        let cache_key = CacheKey::new(&symbol, "esg", json!({ "provider": provider }));
+
+        self.cache
+            .get_or_fetch(cache_key, || async {
+                let scores = match provider {
+                    EsgProvider::Yahoo => self.yahoo_client.get_esg_scores(&symbol).await?,
+                    EsgProvider::Finnhub => {
+                        let client = self.finnhub_client.as_ref().ok_or_else(|| {
+                            StockError::ConfigError(
+                                "Finnhub API key required for Finnhub ESG scores".to_string(),
+                            )
+                        })?;
+                        client.get_esg_scores(&symbol).await?
+                    }
+                };
+                Ok::<_, StockError>(format_scores(&scores))
+            })
+            .await
+    }
+}
+
+/// Tool output for a set of scores, with the scale spelled out for the LLM
+fn format_scores(scores: &EsgScores) -> Value {
+    let mut result = json!({
+        "symbol": scores.symbol,
+        "total": scores.total,
+        "environment": scores.environment,
+        "social": scores.social,
+        "governance": scores.governance,
+        "controversy_level": scores.controversy_level,
+        "controversies": scores.controversies,
+        "peer_group": scores.peer_group,
+    });
+
+    if scores.higher_is_better {
+        result["scale"] = json!("ESG score from 0 to 100, higher is better");
+        result["data_provider"] = json!("Finnhub");
+    } else {
+        result["scale"] = json!("Sustainalytics ESG risk score, lower is better");
+        result["risk_category"] = json!(scores.risk_category());
+        result["data_provider"] = json!("Yahoo Finance (Sustainalytics)");
+    }
+
+    result
+}
+
+#[async_trait]
+impl Tool for EsgTool {
+    async fn execute(&self, params: Value) -> AgentResult<Value> {
+        let params: EsgParams = serde_json::from_value(params)
+            .map_err(|e| agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}")))?;
+
+        self.fetch_esg(params)
+            .await
+            .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))
+    }
+
+    fn name(&self) -> &'static str {
+        "esg_scores"
+    }
+
+    fn description(&self) -> &'static str {
+        "Fetch ESG (environmental, social, governance) scores for a stock. \
+         Includes the total and per-pillar scores, controversy level and areas, and peer group. \
+         Check the returned scale: risk scores are better when lower."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "symbol": {
+                    "type": "string",
+                    "description": "Stock ticker symbol"
+                }
+            },
+            "required": ["symbol"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_scores() {
+        let scores = EsgScores {
+            symbol: "TSLA".to_string(),
+            provider: EsgProvider::Yahoo,
+            total: Some(25.02),
+            environment: Some(3.1),
+            social: Some(14.5),
+            governance: Some(7.42),
+            higher_is_better: false,
+            controversy_level: Some(3),
+            controversies: vec!["Employee Incidents".to_string()],
+            peer_group: Some("Automobiles".to_string()),
+        };
+
+        let result = format_scores(&scores);
+        assert_eq!(result["risk_category"], "Medium");
+        assert!(
+            result["scale"]
+                .as_str()
+                .unwrap()
+                .contains("lower is better")
+        );
+
+        let result = format_scores(&EsgScores {
+            higher_is_better: true,
+            provider: EsgProvider::Finnhub,
+            ..scores
+        });
+        assert!(result.get("risk_category").is_none());
+        assert_eq!(result["data_provider"], "Finnhub");
+    }
+
+    #[test]
+    fn test_tool_metadata() {
+        let config = Arc::new(StockConfig::default());
+        let cache = StockCache::new(std::time::Duration::from_secs(3600));
+        let tool = EsgTool::new(config, cache);
+
+        assert_eq!(tool.name(), "esg_scores");
+        assert!(!tool.description().is_empty());
+    }
+}
@@ -0,0 +1,764 @@
+//! Tool for event studies: how a stock reacted around past events
+//!
+//! Given event dates (earnings releases, Fed meetings, product launches), the
+//! tool measures the stock's abnormal return around each one against a
+//! benchmark, so agents can answer "how has AAPL historically reacted to
+//! iPhone launches" with numbers instead of recollection.
+//!
+//! Expected returns come from a market model (alpha and beta fitted on the
+//! trading days before each event, leaving a gap so the run-up is not part of
+//! the fit); with too little history for the fit, the benchmark return itself
+//! is the expectation (market-adjusted returns).
+
+use agent_core::Result as AgentResult;
+use agent_tools::Tool;
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+use crate::api::YahooFinanceClient;
+use crate::cache::{CacheKey, StockCache};
+use crate::config::StockConfig;
+use crate::crypto;
+use crate::error::{Result, StockError};
+use crate::news_archive::DailyCloses;
+
+/// Most events studied in one request
+const MAX_EVENTS: usize = 50;
+
+/// Fewest estimation-window returns a market model is fitted on
+const MIN_ESTIMATION_DAYS: usize = 30;
+
+/// Trading days between the estimation window and the event window
+const ESTIMATION_GAP: usize = 10;
+
+/// Longest event window on either side of the event, in trading days
+const MAX_WINDOW_DAYS: usize = 20;
+
+/// |t| at or above which the mean abnormal return is called significant
+const SIGNIFICANT_T: f64 = 2.0;
+
+/// An event given by date alone or with details
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum EventInput {
+    Date(String),
+    Detailed {
+        date: String,
+        #[serde(default)]
+        label: Option<String>,
+        /// Announced after the close, so the reaction is the next session
+        #[serde(default)]
+        after_close: bool,
+    },
+}
+
+/// A past event to measure the reaction to
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub date: NaiveDate,
+    pub label: Option<String>,
+    pub after_close: bool,
+}
+
+impl EventInput {
+    fn parse(self) -> Result<Event> {
+        let (date, label, after_close) = match self {
+            EventInput::Date(date) => (date, None, false),
+            EventInput::Detailed {
+                date,
+                label,
+                after_close,
+            } => (date, label, after_close),
+        };
+        let parsed = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").map_err(|_| {
+            StockError::InvalidSymbol(format!("Invalid event date: {date}. Use YYYY-MM-DD"))
+        })?;
+        Ok(Event {
+            date: parsed,
+            label,
+            after_close,
+        })
+    }
+}
+
+/// Parameters for an event study request
+#[derive(Debug, Deserialize)]
+struct EventStudyParams {
+    symbol: String,
+    events: Vec<EventInput>,
+    #[serde(default = "default_benchmark")]
+    benchmark: String,
+    /// Trading days before the event included in the window
+    #[serde(default = "default_pre_days")]
+    pre_days: usize,
+    /// Trading days after the event included in the window
+    #[serde(default = "default_post_days")]
+    post_days: usize,
+    /// Trading days the market model is fitted on
+    #[serde(default = "default_estimation_days")]
+    estimation_days: usize,
+}
+
+fn default_benchmark() -> String {
+    "SPY".to_string()
+}
+
+fn default_pre_days() -> usize {
+    1
+}
+
+fn default_post_days() -> usize {
+    5
+}
+
+fn default_estimation_days() -> usize {
+    120
+}
+
+/// Event window and estimation window lengths, in trading days
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StudyWindow {
+    pub pre_days: usize,
+    pub post_days: usize,
+    pub estimation_days: usize,
+}
+
+impl Default for StudyWindow {
+    fn default() -> Self {
+        Self {
+            pre_days: default_pre_days(),
+            post_days: default_post_days(),
+            estimation_days: default_estimation_days(),
+        }
+    }
+}
+
+/// Stock and benchmark returns on one trading day
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DailyReturn {
+    pub date: NaiveDate,
+    pub stock: f64,
+    pub benchmark: f64,
+}
+
+/// Daily returns on the days both series traded, oldest first
+pub fn aligned_returns(
+    stock: &[(NaiveDate, f64)],
+    benchmark: &[(NaiveDate, f64)],
+) -> Vec<DailyReturn> {
+    let benchmark: std::collections::HashMap<NaiveDate, f64> = benchmark.iter().copied().collect();
+    let common: Vec<(NaiveDate, f64, f64)> = stock
+        .iter()
+        .filter_map(|&(date, close)| benchmark.get(&date).map(|&b| (date, close, b)))
+        .collect();
+    common
+        .windows(2)
+        .filter(|w| w[0].1 > 0.0 && w[0].2 > 0.0)
+        .map(|w| DailyReturn {
+            date: w[1].0,
+            stock: w[1].1 / w[0].1 - 1.0,
+            benchmark: w[1].2 / w[0].2 - 1.0,
+        })
+        .collect()
+}
+
+/// How expected returns were estimated for an event
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(tag = "model", rename_all = "snake_case")]
+pub enum ExpectedReturn {
+    /// Fitted on the estimation window: expected = alpha + beta × benchmark
+    MarketModel { alpha: f64, beta: f64 },
+    /// Too little history to fit: expected = benchmark
+    MarketAdjusted,
+}
+
+impl ExpectedReturn {
+    /// Fit a market model on `returns`, or fall back to market-adjusted
+    pub fn fit(returns: &[DailyReturn]) -> Self {
+        if returns.len() < MIN_ESTIMATION_DAYS {
+            return ExpectedReturn::MarketAdjusted;
+        }
+        let n = returns.len() as f64;
+        let mean_b = returns.iter().map(|r| r.benchmark).sum::<f64>() / n;
+        let mean_s = returns.iter().map(|r| r.stock).sum::<f64>() / n;
+        let covariance: f64 = returns
+            .iter()
+            .map(|r| (r.benchmark - mean_b) * (r.stock - mean_s))
+            .sum();
+        let variance: f64 = returns.iter().map(|r| (r.benchmark - mean_b).powi(2)).sum();
+        if variance <= f64::EPSILON {
+            return ExpectedReturn::MarketAdjusted;
+        }
+        let beta = covariance / variance;
+        ExpectedReturn::MarketModel {
+            alpha: mean_s - beta * mean_b,
+            beta,
+        }
+    }
+
+    fn expected(&self, benchmark: f64) -> f64 {
+        match *self {
+            ExpectedReturn::MarketModel { alpha, beta } => alpha + beta * benchmark,
+            ExpectedReturn::MarketAdjusted => benchmark,
+        }
+    }
+}
+
+/// The stock's reaction around one event
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EventReaction {
+    pub date: NaiveDate,
+    pub label: Option<String>,
+    /// Trading day the reaction is measured from (day 0)
+    pub reaction_date: NaiveDate,
+    pub expected: ExpectedReturn,
+    /// Abnormal return on day 0
+    pub day0_abnormal: f64,
+    /// Cumulative abnormal return over the event window
+    pub car: f64,
+    /// Raw stock return over the event window
+    pub stock_return: f64,
+    /// Benchmark return over the event window
+    pub benchmark_return: f64,
+    /// Abnormal return by trading day relative to the event
+    pub abnormal_returns: Vec<(i64, f64)>,
+}
+
+/// Measure the reaction to `event`, or say why it cannot be measured
+pub fn react(
+    returns: &[DailyReturn],
+    event: &Event,
+    window: StudyWindow,
+) -> std::result::Result<EventReaction, String> {
+    // Day 0 is the first session on or after the event, or after it when
+    // the event came after the close
+    let day0 = returns
+        .iter()
+        .position(|r| r.date > event.date || (!event.after_close && r.date == event.date))
+        .ok_or("after the available price history")?;
+    let start = day0
+        .checked_sub(window.pre_days)
+        .ok_or("too close to the start of the price history")?;
+    let end = day0 + window.post_days;
+    if end >= returns.len() {
+        return Err("event window not complete yet".to_string());
+    }
+
+    let estimation_end = start.saturating_sub(ESTIMATION_GAP);
+    let estimation_start = estimation_end.saturating_sub(window.estimation_days);
+    let expected = ExpectedReturn::fit(&returns[estimation_start..estimation_end]);
+
+    let event_days = &returns[start..=end];
+    // Window lengths are capped at MAX_WINDOW_DAYS
+    #[allow(clippy::cast_possible_wrap)]
+    let abnormal_returns: Vec<(i64, f64)> = event_days
+        .iter()
+        .zip(-(window.pre_days as i64)..)
+        .map(|(r, day)| (day, r.stock - expected.expected(r.benchmark)))
+        .collect();
+    let compound = |f: fn(&DailyReturn) -> f64| {
+        event_days.iter().map(f).fold(1.0, |acc, r| acc * (1.0 + r)) - 1.0
+    };
+
+    Ok(EventReaction {
+        date: event.date,
+        label: event.label.clone(),
+        reaction_date: returns[day0].date,
+        expected,
+        day0_abnormal: abnormal_returns[window.pre_days].1,
+        car: abnormal_returns.iter().map(|(_, ar)| ar).sum(),
+        stock_return: compound(|r| r.stock),
+        benchmark_return: compound(|r| r.benchmark),
+        abnormal_returns,
+    })
+}
+
+/// Cross-event statistics of the reactions
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EventStudySummary {
+    pub events: usize,
+    pub mean_car: f64,
+    pub median_car: f64,
+    /// Share of events with a positive CAR
+    pub positive_share: f64,
+    /// Mean CAR over its standard error; `None` with a single event
+    pub t_stat: Option<f64>,
+    pub mean_day0_abnormal: f64,
+    /// Mean abnormal return by trading day relative to the event, cumulated
+    pub caar_path: Vec<(i64, f64)>,
+}
+
+impl EventStudySummary {
+    /// Summarize `reactions`, or `None` when there are none
+    pub fn from_reactions(reactions: &[EventReaction]) -> Option<Self> {
+        let first = reactions.first()?;
+        let n = reactions.len() as f64;
+        let mut cars: Vec<f64> = reactions.iter().map(|r| r.car).collect();
+        let mean_car = cars.iter().sum::<f64>() / n;
+        let t_stat = (reactions.len() > 1).then(|| {
+            let variance = cars.iter().map(|c| (c - mean_car).powi(2)).sum::<f64>() / (n - 1.0);
+            let standard_error = (variance / n).sqrt();
+            if standard_error > 0.0 {
+                mean_car / standard_error
+            } else {
+                0.0
+            }
+        });
+        cars.sort_by(f64::total_cmp);
+        let mid = cars.len() / 2;
+        let median_car = if cars.len() % 2 == 0 {
+            f64::midpoint(cars[mid - 1], cars[mid])
+        } else {
+            cars[mid]
+        };
+
+        let mut cumulative = 0.0;
+        let caar_path = first
+            .abnormal_returns
+            .iter()
+            .enumerate()
+            .map(|(i, &(day, _))| {
+                cumulative += reactions
+                    .iter()
+                    .map(|r| r.abnormal_returns[i].1)
+                    .sum::<f64>()
+                    / n;
+                (day, cumulative)
+            })
+            .collect();
+
+        Some(Self {
+            events: reactions.len(),
+            mean_car,
+            median_car,
+            positive_share: reactions.iter().filter(|r| r.car > 0.0).count() as f64 / n,
+            t_stat,
+            mean_day0_abnormal: reactions.iter().map(|r| r.day0_abnormal).sum::<f64>() / n,
+            caar_path,
+        })
+    }
+
+    /// Whether the mean reaction is distinguishable from noise
+    pub fn is_significant(&self) -> bool {
+        self.t_stat.is_some_and(|t| t.abs() >= SIGNIFICANT_T)
+    }
+}
+
+/// A return as a percentage with two decimals
+fn pct(value: f64) -> f64 {
+    (value * 10_000.0).round() / 100.0
+}
+
+/// Calendar days spanning `trading_days`, padded for holidays
+///
+/// Trading days are about 5/7 of calendar days.
+#[allow(clippy::cast_possible_wrap)] // window lengths are capped well below i64::MAX
+fn calendar_days(trading_days: usize) -> Duration {
+    Duration::days(trading_days as i64 * 7 / 5 + 14)
+}
+
+fn path_json(path: &[(i64, f64)]) -> Vec<Value> {
+    path.iter()
+        .map(|&(day, value)| json!({ "day": day, "pct": pct(value) }))
+        .collect()
+}
+
+/// Tool for measuring abnormal returns around past events
+pub struct EventStudyTool {
+    prices: Arc<dyn DailyCloses>,
+    cache: StockCache,
+}
+
+impl EventStudyTool {
+    /// Create a new event study tool reading Yahoo Finance closes
+    pub fn new(_config: Arc<StockConfig>, cache: StockCache) -> Self {
+        Self::with_prices(Arc::new(YahooFinanceClient::new()), cache)
+    }
+
+    /// Create an event study tool reading closes from `prices`
+    pub fn with_prices(prices: Arc<dyn DailyCloses>, cache: StockCache) -> Self {
+        Self { prices, cache }
+    }
+
+    async fn study(&self, params: EventStudyParams) -> Result<Value> {
+        let symbol = crypto::normalize_symbol(&params.symbol);
+        let benchmark = params.benchmark.trim().to_uppercase();
+        if params.events.is_empty() {
+            return Err(StockError::InvalidSymbol(
+                "No event dates given".to_string(),
+            ));
+        }
+        if params.events.len() > MAX_EVENTS {
+            return Err(StockError::InvalidSymbol(format!(
+                "Too many events: {}. Study at most {MAX_EVENTS} at a time",
+                params.events.len()
+            )));
+        }
+        let mut events = params
+            .events
+            .into_iter()
+            .map(EventInput::parse)
+            .collect::<Result<Vec<_>>>()?;
+        events.sort_by_key(|e| e.date);
+        let window = StudyWindow {
+            pre_days: params.pre_days.min(MAX_WINDOW_DAYS),
+            post_days: params.post_days.min(MAX_WINDOW_DAYS),
+            estimation_days: params.estimation_days.clamp(MIN_ESTIMATION_DAYS, 500),
+        };
+
+        let cache_key = CacheKey::new(
+            &symbol,
+            "event_study",
+            json!({
+                "benchmark": benchmark,
+                "events": events.iter().map(|e| (e.date, e.after_close)).collect::<Vec<_>>(),
+                "window": [window.pre_days, window.post_days, window.estimation_days],
+            }),
+        );
+        let labels: Vec<Option<String>> = events.iter().map(|e| e.label.clone()).collect();
+        let mut result = self
+            .cache
+            .get_or_fetch(cache_key, || {
+                self.compute(&symbol, &benchmark, &events, window)
+            })
+            .await?;
+
+        // Labels are not part of the cache key, so set them on every call
+        if let Some(reactions) = result["reactions"].as_array_mut() {
+            for (reaction, label) in reactions.iter_mut().zip(labels) {
+                reaction["label"] = json!(label);
+            }
+        }
+        Ok(result)
+    }
+
+    async fn compute(
+        &self,
+        symbol: &str,
+        benchmark: &str,
+        events: &[Event],
+        window: StudyWindow,
+    ) -> Result<Value> {
+        let (Some(first), Some(last)) = (events.first(), events.last()) else {
+            return Err(StockError::InvalidSymbol(
+                "No event dates given".to_string(),
+            ));
+        };
+        let lead = window.estimation_days + ESTIMATION_GAP + window.pre_days;
+        let from = first.date - calendar_days(lead);
+        let to = (last.date + calendar_days(window.post_days)).min(Utc::now().date_naive());
+
+        let stock_closes = self.prices.daily_closes(symbol, from, to).await?;
+        let benchmark_closes = self.prices.daily_closes(benchmark, from, to).await?;
+        let returns = aligned_returns(&stock_closes, &benchmark_closes);
+        if returns.is_empty() {
+            return Err(StockError::data_unavailable(
+                symbol,
+                format!("No overlapping price history with {benchmark}"),
+            ));
+        }
+
+        let mut reactions = Vec::new();
+        let mut skipped = Vec::new();
+        for event in events {
+            match react(&returns, event, window) {
+                Ok(reaction) => reactions.push(reaction),
+                Err(reason) => skipped.push(json!({ "date": event.date, "reason": reason })),
+            }
+        }
+
+        let summary = EventStudySummary::from_reactions(&reactions).map(|s| {
+            json!({
+                "events": s.events,
+                "mean_car_pct": pct(s.mean_car),
+                "median_car_pct": pct(s.median_car),
+                "positive_share_pct": (s.positive_share * 1000.0).round() / 10.0,
+                "t_stat": s.t_stat.map(|t| (t * 100.0).round() / 100.0),
+                "significant": s.is_significant(),
+                "mean_day0_abnormal_pct": pct(s.mean_day0_abnormal),
+                "caar_path": path_json(&s.caar_path),
+            })
+        });
+
+        Ok(json!({
+            "symbol": symbol,
+            "benchmark": benchmark,
+            "event_window": { "pre_days": window.pre_days, "post_days": window.post_days },
+            "estimation_days": window.estimation_days,
+            "summary": summary,
+            "reactions": reactions.iter().map(|r| json!({
+                "date": r.date,
+                "label": r.label,
+                "reaction_date": r.reaction_date,
+                "expected_return": r.expected,
+                "day0_abnormal_pct": pct(r.day0_abnormal),
+                "car_pct": pct(r.car),
+                "stock_return_pct": pct(r.stock_return),
+                "benchmark_return_pct": pct(r.benchmark_return),
+                "abnormal_path": path_json(&r.abnormal_returns),
+            })).collect::<Vec<_>>(),
+            "skipped": skipped,
+        }))
+    }
+}
+
+#[async_trait]
+impl Tool for EventStudyTool {
+    async fn execute(&self, params: Value) -> AgentResult<Value> {
+        let params: EventStudyParams = serde_json::from_value(params)
+            .map_err(|e| agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}")))?;
+
+        self.study(params)
+            .await
+            .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))
+    }
+
+    fn name(&self) -> &'static str {
+        "event_study"
+    }
+
+    fn description(&self) -> &'static str {
+        "Measure how a stock reacted around past events (earnings releases, Fed meetings, \
+         product launches) against a benchmark. For each event date returns the abnormal \
+         return on the reaction day and the cumulative abnormal return (CAR) over the event \
+         window, using a market model fitted on the months before; across events returns the \
+         mean and median CAR, the share of positive reactions, a t-statistic and the average \
+         cumulative path. Give the event dates yourself, e.g. past iPhone launch dates."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "symbol": {
+                    "type": "string",
+                    "description": "Stock symbol or crypto pair (e.g., AAPL, BTC-USD)"
+                },
+                "events": {
+                    "type": "array",
+                    "description": "Past event dates (YYYY-MM-DD), or objects with date, an optional label and after_close when the news came after the market closed",
+                    "items": {
+                        "oneOf": [
+                            { "type": "string" },
+                            {
+                                "type": "object",
+                                "properties": {
+                                    "date": { "type": "string" },
+                                    "label": { "type": "string" },
+                                    "after_close": { "type": "boolean" }
+                                },
+                                "required": ["date"]
+                            }
+                        ]
+                    },
+                    "maxItems": MAX_EVENTS
+                },
+                "benchmark": {
+                    "type": "string",
+                    "description": "Benchmark symbol, e.g. SPY or a sector ETF such as XLK",
+                    "default": "SPY"
+                },
+                "pre_days": {
+                    "type": "integer",
+                    "description": "Trading days before the event in the window",
+                    "default": 1
+                },
+                "post_days": {
+                    "type": "integer",
+                    "description": "Trading days after the event in the window",
+                    "default": 5
+                },
+                "estimation_days": {
+                    "type": "integer",
+                    "description": "Trading days before the window used to fit the market model",
+                    "default": 120
+                }
+            },
+            "required": ["symbol", "events"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn day(n: i64) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 1, 1).unwrap() + Duration::days(n)
+    }
+
+    /// Benchmark alternating ±1% and stock moving 1.5× the benchmark, plus
+    /// a stock-specific `jumps` return on their days
+    fn series(days: i64, jumps: &[(i64, f64)]) -> (Vec<(NaiveDate, f64)>, Vec<(NaiveDate, f64)>) {
+        let mut stock = vec![(day(0), 100.0)];
+        let mut bench = vec![(day(0), 100.0)];
+        for n in 1..days {
+            let b = if n % 2 == 0 { 0.01 } else { -0.01 };
+            let jump = jumps.iter().find(|(d, _)| *d == n).map_or(0.0, |(_, j)| *j);
+            let s = 1.5 * b + jump;
+            bench.push((day(n), bench.last().unwrap().1 * (1.0 + b)));
+            stock.push((day(n), stock.last().unwrap().1 * (1.0 + s)));
+        }
+        (stock, bench)
+    }
+
+    fn event(n: i64, after_close: bool) -> Event {
+        Event {
+            date: day(n),
+            label: None,
+            after_close,
+        }
+    }
+
+    struct Fixed(HashMap<String, Vec<(NaiveDate, f64)>>);
+
+    #[async_trait]
+    impl DailyCloses for Fixed {
+        async fn daily_closes(
+            &self,
+            symbol: &str,
+            from: NaiveDate,
+            to: NaiveDate,
+        ) -> Result<Vec<(NaiveDate, f64)>> {
+            Ok(self
+                .0
+                .get(symbol)
+                .into_iter()
+                .flatten()
+                .filter(|(d, _)| (from..=to).contains(d))
+                .copied()
+                .collect())
+        }
+    }
+
+    #[test]
+    fn test_aligned_returns_skip_missing_days() {
+        let stock = [
+            (day(0), 100.0),
+            (day(1), 110.0),
+            (day(2), 99.0),
+            (day(3), 99.0),
+        ];
+        let bench = [(day(0), 50.0), (day(2), 51.0), (day(3), 51.0)];
+        let returns = aligned_returns(&stock, &bench);
+        assert_eq!(returns.len(), 2);
+        assert_eq!(returns[0].date, day(2));
+        assert!((returns[0].stock - -0.01).abs() < 1e-12);
+        assert!((returns[0].benchmark - 0.02).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_market_model_fit() {
+        let (stock, bench) = series(60, &[]);
+        let returns = aligned_returns(&stock, &bench);
+        match ExpectedReturn::fit(&returns) {
+            ExpectedReturn::MarketModel { alpha, beta } => {
+                assert!((beta - 1.5).abs() < 1e-9);
+                assert!(alpha.abs() < 1e-9);
+            }
+            ExpectedReturn::MarketAdjusted => panic!("expected a fitted model"),
+        }
+        assert_eq!(
+            ExpectedReturn::fit(&returns[..10]),
+            ExpectedReturn::MarketAdjusted
+        );
+    }
+
+    #[test]
+    fn test_react_isolates_the_jump() {
+        let (stock, bench) = series(200, &[(150, 0.05)]);
+        let returns = aligned_returns(&stock, &bench);
+        let window = StudyWindow::default();
+
+        let reaction = react(&returns, &event(150, false), window).unwrap();
+        assert_eq!(reaction.reaction_date, day(150));
+        assert!(matches!(
+            reaction.expected,
+            ExpectedReturn::MarketModel { .. }
+        ));
+        assert!((reaction.day0_abnormal - 0.05).abs() < 1e-9);
+        assert!((reaction.car - 0.05).abs() < 1e-9);
+        assert_eq!(reaction.abnormal_returns.len(), 7);
+        assert_eq!(reaction.abnormal_returns[0].0, -1);
+
+        // After the close, day 0 is the next session and the jump is day -1
+        let reaction = react(&returns, &event(149, true), window).unwrap();
+        assert_eq!(reaction.reaction_date, day(150));
+        let reaction = react(&returns, &event(150, true), window).unwrap();
+        assert_eq!(reaction.reaction_date, day(151));
+        assert!((reaction.abnormal_returns[0].1 - 0.05).abs() < 1e-9);
+        assert!(reaction.day0_abnormal.abs() < 1e-9);
+
+        assert!(react(&returns, &event(197, false), window).is_err());
+        assert!(react(&returns, &event(300, false), window).is_err());
+    }
+
+    #[test]
+    fn test_summary() {
+        // Events far enough apart that no jump is in a later event's fit
+        let (stock, bench) = series(500, &[(150, 0.05), (290, -0.03), (430, 0.01)]);
+        let returns = aligned_returns(&stock, &bench);
+        let window = StudyWindow::default();
+        let reactions: Vec<_> = [150, 290, 430]
+            .iter()
+            .map(|&n| react(&returns, &event(n, false), window).unwrap())
+            .collect();
+
+        let summary = EventStudySummary::from_reactions(&reactions).unwrap();
+        assert_eq!(summary.events, 3);
+        assert!((summary.mean_car - 0.01).abs() < 1e-9);
+        assert!((summary.median_car - 0.01).abs() < 1e-9);
+        assert!((summary.positive_share - 2.0 / 3.0).abs() < 1e-9);
+        assert!((summary.mean_day0_abnormal - 0.01).abs() < 1e-9);
+        assert!(!summary.is_significant());
+        assert_eq!(summary.caar_path.len(), 7);
+        assert!((summary.caar_path[6].1 - summary.mean_car).abs() < 1e-9);
+
+        assert!(EventStudySummary::from_reactions(&[]).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_tool_output() {
+        let (stock, bench) = series(300, &[(150, 0.05)]);
+        let prices = Fixed(HashMap::from([
+            ("AAPL".to_string(), stock),
+            ("SPY".to_string(), bench),
+        ]));
+        let tool = EventStudyTool::with_prices(
+            Arc::new(prices),
+            StockCache::new(std::time::Duration::from_secs(60)),
+        );
+
+        let result = tool
+            .execute(json!({
+                "symbol": "aapl",
+                "events": [
+                    day(150).to_string(),
+                    { "date": day(250).to_string(), "label": "Launch" },
+                    "2030-01-01"
+                ]
+            }))
+            .await
+            .unwrap();
+        assert_eq!(result["benchmark"], "SPY");
+        assert_eq!(result["summary"]["events"], 2);
+        assert_eq!(result["reactions"][0]["car_pct"], 5.0);
+        assert_eq!(result["reactions"][1]["label"], "Launch");
+        assert_eq!(result["skipped"][0]["date"], "2030-01-01");
+
+        assert!(
+            tool.execute(json!({ "symbol": "AAPL", "events": ["01/02/2024"] }))
+                .await
+                .is_err()
+        );
+        assert!(
+            tool.execute(json!({ "symbol": "AAPL", "events": [] }))
+                .await
+                .is_err()
+        );
+    }
+}
@@ -7,9 +7,10 @@ use serde::Deserialize;
 use serde_json::{Value, json};
 use std::sync::Arc;
 
-use crate::api::alpha_vantage::AlphaVantageClient;
+use crate::api::alpha_vantage::{AlphaVantageClient, CompanyOverview};
 use crate::cache::{CacheKey, StockCache};
 use crate::config::StockConfig;
+use crate::crypto;
 use crate::error::{Result, StockError};
 
 /// Tool for fetching fundamental stock data
@@ -29,10 +30,7 @@ struct FundamentalParams {
 impl FundamentalDataTool {
     /// Create a new fundamental data tool
     pub fn new(config: Arc<StockConfig>, cache: StockCache) -> Self {
-        let alpha_vantage_client = config
-            .alpha_vantage_api_key
-            .as_ref()
-            .map(|key| AlphaVantageClient::new(key.clone(), config.alpha_vantage_rate_limit));
+        let alpha_vantage_client = AlphaVantageClient::from_config(&config);
 
         Self {
             alpha_vantage_client,
@@ -43,7 +41,13 @@ impl FundamentalDataTool {
 
     /// Fetch fundamental data
     async fn fetch_fundamental_data(&self, params: FundamentalParams) -> Result<Value> {
-        let symbol = params.symbol.to_uppercase();
+        let symbol = crypto::normalize_symbol(&params.symbol);
+        if crypto::is_crypto(&symbol) {
+            return Err(StockError::data_unavailable(
+                symbol,
+                "Crypto assets have no company fundamentals; use stock_data for price and volume",
+            ));
+        }
 
         // Create cache key
         let cache_key = CacheKey::new(&symbol, "fundamental", json!({}));
@@ -57,62 +61,7 @@ impl FundamentalDataTool {
                     // Fetch company overview from Alpha Vantage
                     let overview = client.get_company_overview(&symbol).await?;
 
-                    let mut result = json!({
-                        "symbol": symbol,
-                        "name": overview.name,
-                        "exchange": overview.exchange,
-                        "sector": overview.sector,
-                        "industry": overview.industry,
-                    });
-
-                    // Parse numeric values
-                    if let Some(market_cap) = overview.market_cap {
-                        if let Ok(cap) = market_cap.parse::<f64>() {
-                            result["market_cap"] = json!(cap);
-                            result["market_cap_formatted"] = json!(format_market_cap(cap));
-                        }
-                    }
-
-                    if let Some(pe_ratio) = overview.pe_ratio {
-                        if let Ok(pe) = pe_ratio.parse::<f64>() {
-                            result["pe_ratio"] = json!(pe);
-                            result["pe_interpretation"] = json!(interpret_pe(pe));
-                        }
-                    }
-
-                    if let Some(div_yield) = overview.dividend_yield {
-                        if let Ok(yield_val) = div_yield.parse::<f64>() {
-                            result["dividend_yield"] = json!(yield_val);
-                            result["dividend_yield_percent"] =
-                                json!(format!("{:.2}%", yield_val * 100.0));
-                        }
-                    }
-
-                    if let Some(eps) = overview.eps {
-                        if let Ok(eps_val) = eps.parse::<f64>() {
-                            result["eps"] = json!(eps_val);
-                        }
-                    }
-
-                    if let Some(book_value) = overview.book_value {
-                        if let Ok(bv) = book_value.parse::<f64>() {
-                            result["book_value"] = json!(bv);
-
-                            // Calculate P/B ratio if we have both
-                            if let (Some(market_cap), Some(book_value)) = (
-                                result.get("market_cap").and_then(serde_json::Value::as_f64),
-                                result.get("book_value").and_then(serde_json::Value::as_f64),
-                            ) {
-                                if book_value != 0.0 {
-                                    result["pb_ratio"] = json!(market_cap / book_value);
-                                }
-                            }
-                        }
-                    }
-
-                    result["data_provider"] = json!("Alpha Vantage");
-
-                    Ok::<_, StockError>(result)
+                    Ok::<_, StockError>(overview_value(&symbol, overview))
                 } else {
                     // No Alpha Vantage key, return limited data
                     Err(StockError::ConfigError(
@@ -126,17 +75,70 @@ impl FundamentalDataTool {
     }
 }
 
+/// Tool output for a company overview, with the numeric fields parsed
+///
+/// Alpha Vantage reports every figure as a string, "None" when missing.
+pub(crate) fn overview_value(symbol: &str, overview: CompanyOverview) -> Value {
+    let mut result = json!({
+        "symbol": symbol,
+        "name": overview.name,
+        "exchange": overview.exchange,
+        "sector": overview.sector,
+        "industry": overview.industry,
+    });
+
+    // Parse numeric values
+    if let Some(market_cap) = overview.market_cap {
+        if let Ok(cap) = market_cap.parse::<f64>() {
+            result["market_cap"] = json!(cap);
+            result["market_cap_formatted"] = json!(format_market_cap(cap));
+        }
+    }
+
+    if let Some(pe_ratio) = overview.pe_ratio {
+        if let Ok(pe) = pe_ratio.parse::<f64>() {
+            result["pe_ratio"] = json!(pe);
+            result["pe_interpretation"] = json!(interpret_pe(pe));
+        }
+    }
+
+    if let Some(div_yield) = overview.dividend_yield {
+        if let Ok(yield_val) = div_yield.parse::<f64>() {
+            result["dividend_yield"] = json!(yield_val);
+            result["dividend_yield_percent"] = json!(format!("{:.2}%", yield_val * 100.0));
+        }
+    }
+
+    if let Some(eps) = overview.eps {
+        if let Ok(eps_val) = eps.parse::<f64>() {
+            result["eps"] = json!(eps_val);
+        }
+    }
+
+    if let Some(book_value) = overview.book_value {
+        if let Ok(bv) = book_value.parse::<f64>() {
+            result["book_value"] = json!(bv);
+
+            // Calculate P/B ratio if we have both
+            if let (Some(market_cap), Some(book_value)) = (
+                result.get("market_cap").and_then(serde_json::Value::as_f64),
+                result.get("book_value").and_then(serde_json::Value::as_f64),
+            ) {
+                if book_value != 0.0 {
+                    result["pb_ratio"] = json!(market_cap / book_value);
+                }
+            }
+        }
+    }
+
+    result["data_provider"] = json!("Alpha Vantage");
+
+    result
+}
+
 /// Format market cap in human-readable form
 fn format_market_cap(cap: f64) -> String {
-    if cap >= 1_000_000_000_000.0 {
-        format!("${:.2}T", cap / 1_000_000_000_000.0)
-    } else if cap >= 1_000_000_000.0 {
-        format!("${:.2}B", cap / 1_000_000_000.0)
-    } else if cap >= 1_000_000.0 {
-        format!("${:.2}M", cap / 1_000_000.0)
-    } else {
-        format!("${cap:.2}")
-    }
+    crate::units::format_usd(cap)
 }
 
 /// Interpret P/E ratio
@@ -157,9 +159,8 @@ fn interpret_pe(pe: f64) -> &'static str {
 #[async_trait]
 impl Tool for FundamentalDataTool {
     async fn execute(&self, params: Value) -> AgentResult<Value> {
-        let params: FundamentalParams = serde_json::from_value(params).map_err(|e| {
-            agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}"))
-        })?;
+        let params: FundamentalParams = serde_json::from_value(params)
+            .map_err(|e| agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}")))?;
 
         self.fetch_fundamental_data(params)
             .await
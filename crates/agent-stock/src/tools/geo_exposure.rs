@@ -0,0 +1,807 @@
+//! Tool for stock-specific geopolitical risk from regional revenue exposure
+//!
+//! Revenue by region comes from the geographic or segment breakdown of the
+//! latest 10-K, or from a small curated table when the filing does not tag
+//! one. Each region is weighted by how sensitive it is to the geopolitical
+//! topics the news currently flags, so a company earning a fifth of its
+//! revenue in China scores higher on US-China tension than a domestic one,
+//! whatever their sectors.
+
+use agent_core::Result as AgentResult;
+use agent_tools::Tool;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+use crate::api::{BreakdownAxis, RevenueBreakdown, SecEdgarClient};
+use crate::cache::{CacheKey, StockCache};
+use crate::config::StockConfig;
+use crate::error::{Result, StockError};
+use crate::tools::geopolitical::{GeopoliticalTool, GeopoliticalTopic, TopicRisk};
+
+/// Share of revenue a breakdown must place in known regions to be used
+const MIN_COVERAGE_PCT: f64 = 50.0;
+
+/// Region revenue is attributed to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Region {
+    UnitedStates,
+    /// Canada and Latin America
+    OtherAmericas,
+    Europe,
+    /// Mainland China and Hong Kong
+    GreaterChina,
+    Taiwan,
+    Japan,
+    SouthKorea,
+    India,
+    /// Southeast Asia, Australia and the rest of the region
+    OtherAsiaPacific,
+    MiddleEastAfrica,
+    /// Revenue the disclosures do not place anywhere more specific
+    RestOfWorld,
+}
+
+impl Region {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::UnitedStates => "United States",
+            Self::OtherAmericas => "Other Americas",
+            Self::Europe => "Europe",
+            Self::GreaterChina => "Greater China",
+            Self::Taiwan => "Taiwan",
+            Self::Japan => "Japan",
+            Self::SouthKorea => "South Korea",
+            Self::India => "India",
+            Self::OtherAsiaPacific => "Other Asia Pacific",
+            Self::MiddleEastAfrica => "Middle East & Africa",
+            Self::RestOfWorld => "Rest of World",
+        }
+    }
+
+    /// How strongly revenue here is hit when `topic` flares up, from 0 to 1
+    pub fn sensitivity(&self, topic: GeopoliticalTopic) -> f64 {
+        use GeopoliticalTopic as T;
+        use Region as R;
+
+        match (topic, self) {
+            (T::UsChinaRelations, R::GreaterChina) => 1.0,
+            (T::UsChinaRelations, R::Taiwan) => 0.8,
+            (T::UsChinaRelations, R::SouthKorea | R::OtherAsiaPacific) => 0.2,
+
+            (T::TradePolicies, R::UnitedStates) => 0.0,
+            (T::TradePolicies, R::GreaterChina) => 0.9,
+            (T::TradePolicies, R::OtherAmericas | R::Europe) => 0.5,
+            (T::TradePolicies, _) => 0.4,
+
+            (T::Sanctions, R::GreaterChina | R::MiddleEastAfrica) => 0.7,
+            (T::Sanctions, R::RestOfWorld) => 0.4,
+            (T::Sanctions, R::Europe) => 0.2,
+
+            (T::MiddleEast, R::MiddleEastAfrica) => 1.0,
+            (T::MiddleEast, R::Europe) => 0.2,
+
+            (T::EuropeanUnion, R::Europe) => 1.0,
+
+            (T::EmergingMarkets, R::India | R::OtherAmericas) => 0.8,
+            (T::EmergingMarkets, R::MiddleEastAfrica | R::OtherAsiaPacific) => 0.7,
+            (T::EmergingMarkets, R::GreaterChina | R::RestOfWorld) => 0.5,
+
+            (T::CurrencyPolicies, R::UnitedStates) => 0.0,
+            (T::CurrencyPolicies, R::Japan) => 0.6,
+            (T::CurrencyPolicies, _) => 0.3,
+
+            (T::SupplyChain, R::Taiwan) => 1.0,
+            (T::SupplyChain, R::GreaterChina) => 0.7,
+            (T::SupplyChain, R::SouthKorea | R::OtherAsiaPacific) => 0.4,
+
+            // Central banks and general news move markets as a whole, not
+            // one region's revenue
+            _ => 0.0,
+        }
+    }
+}
+
+/// Regions a disclosed slice name stands for, with the split of aggregates
+///
+/// Aggregates like "Americas" are split with rough weights, since filings
+/// rarely break them down further.
+pub fn classify(name: &str) -> Option<&'static [(Region, f64)]> {
+    use Region as R;
+
+    let name = name.to_lowercase();
+    let matches = |needles: &[&str]| needles.iter().any(|needle| name.contains(needle));
+
+    let regions: &'static [(Region, f64)] = if matches(&["united states", "u.s.", "domestic"]) {
+        &[(R::UnitedStates, 1.0)]
+    } else if matches(&["north america"]) {
+        &[(R::UnitedStates, 0.9), (R::OtherAmericas, 0.1)]
+    } else if matches(&["americas"]) {
+        &[(R::UnitedStates, 0.85), (R::OtherAmericas, 0.15)]
+    } else if matches(&[
+        "canada",
+        "mexico",
+        "brazil",
+        "latin america",
+        "south america",
+    ]) {
+        &[(R::OtherAmericas, 1.0)]
+    } else if matches(&["emea"]) {
+        &[(R::Europe, 0.8), (R::MiddleEastAfrica, 0.2)]
+    } else if matches(&["middle east", "africa", "israel", "saudi"]) {
+        &[(R::MiddleEastAfrica, 1.0)]
+    } else if matches(&[
+        "europe",
+        "germany",
+        "united kingdom",
+        "france",
+        "ireland",
+        "netherlands",
+        "switzerland",
+    ]) {
+        &[(R::Europe, 1.0)]
+    } else if matches(&["taiwan"]) {
+        &[(R::Taiwan, 1.0)]
+    } else if matches(&["china", "hong kong"]) {
+        &[(R::GreaterChina, 1.0)]
+    } else if matches(&["japan"]) {
+        &[(R::Japan, 1.0)]
+    } else if matches(&["korea"]) {
+        &[(R::SouthKorea, 1.0)]
+    } else if matches(&["india"]) {
+        &[(R::India, 1.0)]
+    } else if matches(&["asia", "pacific", "apac", "singapore", "australia"]) {
+        &[(R::OtherAsiaPacific, 1.0)]
+    } else if matches(&[
+        "international",
+        "foreign",
+        "non-u",
+        "other countries",
+        "rest of world",
+    ]) {
+        &[(R::RestOfWorld, 1.0)]
+    } else {
+        return None;
+    };
+    Some(regions)
+}
+
+/// Share of revenue from one region
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct RegionExposure {
+    pub region: Region,
+    /// Percent of total revenue
+    pub share_pct: f64,
+}
+
+/// Where a company's regional exposure comes from
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExposureSource {
+    /// A revenue breakdown of the latest 10-K
+    Filing {
+        axis: BreakdownAxis,
+        period_end: String,
+    },
+    /// The curated table, with the fiscal year the figures describe
+    Curated { as_of: &'static str },
+}
+
+/// Revenue split by region, largest first
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoExposure {
+    pub source: ExposureSource,
+    pub regions: Vec<RegionExposure>,
+    /// Percent of revenue placed in a named region rather than rest of world
+    pub coverage_pct: f64,
+}
+
+impl GeoExposure {
+    /// Exposure from the breakdown whose slices map best onto regions
+    ///
+    /// Geographic and segment axes are both tried, since companies like
+    /// Apple report their regions as operating segments. Breakdowns that
+    /// place less than half of revenue are not used.
+    pub fn from_breakdowns(breakdowns: &[RevenueBreakdown]) -> Option<Self> {
+        breakdowns
+            .iter()
+            .filter(|breakdown| breakdown.axis != BreakdownAxis::Product)
+            .filter_map(|breakdown| {
+                let (regions, classified_pct) = regions_of(breakdown)?;
+                Some((breakdown, regions, classified_pct))
+            })
+            .filter(|(_, _, classified_pct)| *classified_pct >= MIN_COVERAGE_PCT)
+            // Geographic wins ties: its members are countries, not business units
+            .max_by(|a, b| {
+                a.2.total_cmp(&b.2)
+                    .then_with(|| (a.0.axis == BreakdownAxis::Geographic)
+                        .cmp(&(b.0.axis == BreakdownAxis::Geographic)))
+            })
+            .map(|(breakdown, regions, _)| {
+                Self::new(
+                    ExposureSource::Filing {
+                        axis: breakdown.axis,
+                        period_end: breakdown.period_end.clone(),
+                    },
+                    regions,
+                )
+            })
+    }
+
+    /// Exposure from the curated table
+    pub fn curated(symbol: &str) -> Option<Self> {
+        let curated = CURATED_EXPOSURES
+            .iter()
+            .find(|curated| curated.symbol.eq_ignore_ascii_case(symbol))?;
+        let regions = curated
+            .regions
+            .iter()
+            .map(|&(region, share_pct)| RegionExposure { region, share_pct })
+            .collect();
+        Some(Self::new(
+            ExposureSource::Curated {
+                as_of: curated.as_of,
+            },
+            regions,
+        ))
+    }
+
+    fn new(source: ExposureSource, mut regions: Vec<RegionExposure>) -> Self {
+        regions.sort_by(|a, b| b.share_pct.total_cmp(&a.share_pct));
+        let coverage_pct = regions
+            .iter()
+            .filter(|exposure| exposure.region != Region::RestOfWorld)
+            .map(|exposure| exposure.share_pct)
+            .sum();
+        Self {
+            source,
+            regions,
+            coverage_pct,
+        }
+    }
+
+    /// Percent of revenue from `region`
+    pub fn share(&self, region: Region) -> f64 {
+        self.regions
+            .iter()
+            .filter(|exposure| exposure.region == region)
+            .map(|exposure| exposure.share_pct)
+            .sum()
+    }
+}
+
+/// Regional shares of a breakdown and the percent of revenue they classify
+///
+/// Unclassified slices and revenue missing from the slices count as rest of
+/// world.
+fn regions_of(breakdown: &RevenueBreakdown) -> Option<(Vec<RegionExposure>, f64)> {
+    let sliced: f64 = breakdown.slices.iter().map(|slice| slice.revenue).sum();
+    let total = breakdown
+        .total_revenue
+        .filter(|total| *total >= sliced)
+        .unwrap_or(sliced);
+    if total <= 0.0 {
+        return None;
+    }
+
+    let mut regions: Vec<RegionExposure> = Vec::new();
+    let mut add = |region: Region, share_pct: f64| match regions
+        .iter_mut()
+        .find(|exposure| exposure.region == region)
+    {
+        Some(exposure) => exposure.share_pct += share_pct,
+        None => regions.push(RegionExposure { region, share_pct }),
+    };
+
+    let mut classified_pct = 0.0;
+    for slice in &breakdown.slices {
+        let share_pct = slice.revenue / total * 100.0;
+        match classify(&slice.name) {
+            Some(split) => {
+                classified_pct += share_pct;
+                for &(region, weight) in split {
+                    add(region, share_pct * weight);
+                }
+            }
+            None => add(Region::RestOfWorld, share_pct),
+        }
+    }
+    let unsliced_pct = (total - sliced) / total * 100.0;
+    if unsliced_pct > 0.05 {
+        add(Region::RestOfWorld, unsliced_pct);
+    }
+
+    Some((regions, classified_pct))
+}
+
+/// Regional revenue mix of a company whose filings do not tag one
+struct CuratedExposure {
+    symbol: &'static str,
+    /// Fiscal year of the annual report the figures are rounded from
+    as_of: &'static str,
+    regions: &'static [(Region, f64)],
+}
+
+/// Approximate revenue by customer location, from annual reports
+///
+/// Kept to widely held names with heavy foreign exposure; update alongside
+/// their 10-K filings.
+const CURATED_EXPOSURES: &[CuratedExposure] = &[
+    CuratedExposure {
+        symbol: "AAPL",
+        as_of: "FY2023",
+        regions: &[
+            (Region::UnitedStates, 36.0),
+            (Region::OtherAmericas, 6.0),
+            (Region::Europe, 22.0),
+            (Region::MiddleEastAfrica, 3.0),
+            (Region::GreaterChina, 19.0),
+            (Region::Japan, 6.0),
+            (Region::OtherAsiaPacific, 8.0),
+        ],
+    },
+    CuratedExposure {
+        symbol: "NVDA",
+        as_of: "FY2024",
+        regions: &[
+            (Region::UnitedStates, 44.0),
+            (Region::Taiwan, 22.0),
+            (Region::GreaterChina, 17.0),
+            (Region::RestOfWorld, 17.0),
+        ],
+    },
+    CuratedExposure {
+        symbol: "TSLA",
+        as_of: "FY2023",
+        regions: &[
+            (Region::UnitedStates, 47.0),
+            (Region::GreaterChina, 22.0),
+            (Region::Europe, 18.0),
+            (Region::RestOfWorld, 13.0),
+        ],
+    },
+    CuratedExposure {
+        symbol: "QCOM",
+        as_of: "FY2023",
+        regions: &[
+            (Region::GreaterChina, 62.0),
+            (Region::SouthKorea, 16.0),
+            (Region::UnitedStates, 11.0),
+            (Region::RestOfWorld, 11.0),
+        ],
+    },
+    CuratedExposure {
+        symbol: "INTC",
+        as_of: "FY2023",
+        regions: &[
+            (Region::GreaterChina, 27.0),
+            (Region::UnitedStates, 26.0),
+            (Region::Taiwan, 16.0),
+            (Region::OtherAsiaPacific, 19.0),
+            (Region::Europe, 12.0),
+        ],
+    },
+    CuratedExposure {
+        symbol: "MSFT",
+        as_of: "FY2023",
+        regions: &[(Region::UnitedStates, 51.0), (Region::RestOfWorld, 49.0)],
+    },
+    CuratedExposure {
+        symbol: "AMZN",
+        as_of: "FY2023",
+        regions: &[
+            (Region::UnitedStates, 69.0),
+            (Region::Europe, 17.0),
+            (Region::Japan, 5.0),
+            (Region::RestOfWorld, 9.0),
+        ],
+    },
+    CuratedExposure {
+        symbol: "GOOGL",
+        as_of: "FY2023",
+        regions: &[
+            (Region::UnitedStates, 47.0),
+            (Region::Europe, 25.0),
+            (Region::MiddleEastAfrica, 4.0),
+            (Region::OtherAsiaPacific, 18.0),
+            (Region::OtherAmericas, 6.0),
+        ],
+    },
+    CuratedExposure {
+        symbol: "META",
+        as_of: "FY2023",
+        regions: &[
+            (Region::UnitedStates, 39.0),
+            (Region::OtherAmericas, 5.0),
+            (Region::Europe, 23.0),
+            (Region::OtherAsiaPacific, 27.0),
+            (Region::RestOfWorld, 6.0),
+        ],
+    },
+];
+
+/// Weight of a topic's risk level in the score
+fn risk_weight(risk_level: &str) -> f64 {
+    match risk_level {
+        "High" => 1.0,
+        "Elevated" => 0.7,
+        "Moderate" => 0.4,
+        _ => 0.15,
+    }
+}
+
+/// One active topic's contribution to a stock's score
+#[derive(Debug, Clone, Serialize)]
+pub struct TopicExposure {
+    pub topic: &'static str,
+    pub risk_level: &'static str,
+    /// Percent of revenue exposed to the topic, sensitivity-weighted
+    pub exposed_pct: f64,
+    /// Exposure times risk weight, from 0 to 100
+    pub score: f64,
+    /// Largest regional contributions, e.g. "19% of revenue from Greater China"
+    pub drivers: Vec<String>,
+}
+
+/// Stock-specific geopolitical risk score
+#[derive(Debug, Clone)]
+pub struct GeoRiskScore {
+    /// From 0 (none) to 100
+    pub score: f64,
+    pub level: &'static str,
+    /// Topics that touch the company's regions, highest score first
+    pub topics: Vec<TopicExposure>,
+}
+
+/// Cross a company's regional exposure with the active topic risks
+///
+/// Topic scores combine like independent probabilities, so several moderate
+/// risks add up without the total passing 100.
+pub fn score(exposure: &GeoExposure, risks: &[TopicRisk]) -> GeoRiskScore {
+    let mut topics: Vec<TopicExposure> = risks
+        .iter()
+        .filter_map(|risk| {
+            let mut contributions: Vec<(Region, f64, f64)> = exposure
+                .regions
+                .iter()
+                .map(|regional| {
+                    let sensitivity = regional.region.sensitivity(risk.topic);
+                    (
+                        regional.region,
+                        regional.share_pct,
+                        regional.share_pct * sensitivity,
+                    )
+                })
+                .filter(|(_, _, weighted)| *weighted > 0.0)
+                .collect();
+            if contributions.is_empty() {
+                return None;
+            }
+            contributions.sort_by(|a, b| b.2.total_cmp(&a.2));
+
+            let exposed_pct: f64 = contributions.iter().map(|(_, _, weighted)| weighted).sum();
+            let drivers = contributions
+                .iter()
+                .take(3)
+                .map(|(region, share_pct, _)| {
+                    format!("{share_pct:.0}% of revenue from {}", region.name())
+                })
+                .collect();
+
+            Some(TopicExposure {
+                topic: risk.topic.name(),
+                risk_level: risk.risk_level,
+                exposed_pct,
+                score: exposed_pct * risk_weight(risk.risk_level),
+                drivers,
+            })
+        })
+        .collect();
+    topics.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    let unaffected: f64 = topics
+        .iter()
+        .map(|topic| 1.0 - (topic.score / 100.0).clamp(0.0, 1.0))
+        .product();
+    let score = (1.0 - unaffected) * 100.0;
+    let level = if score >= 50.0 {
+        "High"
+    } else if score >= 30.0 {
+        "Elevated"
+    } else if score >= 15.0 {
+        "Moderate"
+    } else {
+        "Low"
+    };
+
+    GeoRiskScore {
+        score,
+        level,
+        topics,
+    }
+}
+
+/// Parameters for a geographic exposure request
+#[derive(Debug, Deserialize)]
+struct GeoExposureParams {
+    symbol: String,
+}
+
+/// Tool scoring a stock's geopolitical risk from where it earns revenue
+pub struct GeoExposureTool {
+    sec_client: SecEdgarClient,
+    geopolitical: GeopoliticalTool,
+    cache: StockCache,
+}
+
+impl GeoExposureTool {
+    /// Create a new geographic exposure tool
+    pub fn new(config: Arc<StockConfig>, cache: StockCache) -> Self {
+        let sec_client = SecEdgarClient::new(&config.sec_user_agent, &config.sec_contact_email);
+        let geopolitical = GeopoliticalTool::new(config, cache.clone());
+
+        Self {
+            sec_client,
+            geopolitical,
+            cache,
+        }
+    }
+
+    /// Use a different SEC client
+    pub fn with_sec_client(mut self, sec_client: SecEdgarClient) -> Self {
+        self.sec_client = sec_client;
+        self
+    }
+
+    /// Exposure from the latest 10-K, or the curated table when it has none
+    async fn exposure(&self, symbol: &str) -> Result<GeoExposure> {
+        let filed = match self.sec_client.get_revenue_breakdowns(symbol).await {
+            Ok((_, breakdowns)) => GeoExposure::from_breakdowns(&breakdowns),
+            Err(e) => {
+                tracing::debug!("No revenue breakdowns for {}: {}", symbol, e);
+                None
+            }
+        };
+
+        filed
+            .or_else(|| GeoExposure::curated(symbol))
+            .ok_or_else(|| StockError::DataUnavailable {
+                symbol: symbol.to_string(),
+                reason: "its 10-K does not tag revenue by region and it is not in the \
+                         curated table"
+                    .to_string(),
+            })
+    }
+
+    /// Score the stock against the active geopolitical risks
+    async fn assess(&self, params: GeoExposureParams) -> Result<Value> {
+        let symbol = params.symbol.trim().to_uppercase();
+
+        let cache_key = CacheKey::new(&symbol, "geo_exposure", json!({}));
+        self.cache
+            .get_or_fetch(cache_key, || async {
+                let exposure = self.exposure(&symbol).await?;
+                let risks = self.geopolitical.active_risks().await?;
+                let risk = score(&exposure, &risks);
+
+                let source = match &exposure.source {
+                    ExposureSource::Filing { axis, period_end } => json!({
+                        "type": "filing",
+                        "breakdown": axis,
+                        "period_end": period_end,
+                        "description": "SEC EDGAR 10-K (inline XBRL)",
+                    }),
+                    ExposureSource::Curated { as_of } => json!({
+                        "type": "curated",
+                        "as_of": as_of,
+                        "description": "Approximate figures from annual reports",
+                    }),
+                };
+
+                Ok::<_, StockError>(json!({
+                    "symbol": symbol,
+                    "source": source,
+                    "regions": exposure
+                        .regions
+                        .iter()
+                        .map(|regional| json!({
+                            "region": regional.region.name(),
+                            "share_pct": (regional.share_pct * 10.0).round() / 10.0,
+                        }))
+                        .collect::<Vec<_>>(),
+                    "coverage_pct": (exposure.coverage_pct * 10.0).round() / 10.0,
+                    "risk_score": (risk.score * 10.0).round() / 10.0,
+                    "risk_level": risk.level,
+                    "topics": risk.topics,
+                    "note": "Scores weight each region's revenue share by its sensitivity to \
+                             the geopolitical topics active in recent news. Aggregate regions \
+                             such as \"Americas\" are split with approximate weights.",
+                    "as_of_date": chrono::Utc::now().format("%Y-%m-%d %H:%M UTC").to_string(),
+                }))
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl Tool for GeoExposureTool {
+    async fn execute(&self, params: Value) -> AgentResult<Value> {
+        let params: GeoExposureParams = serde_json::from_value(params)
+            .map_err(|e| agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}")))?;
+
+        self.assess(params)
+            .await
+            .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))
+    }
+
+    fn name(&self) -> &'static str {
+        "geo_exposure"
+    }
+
+    fn description(&self) -> &'static str {
+        "Stock-specific geopolitical risk: estimates a company's revenue by region from \
+         its latest 10-K (or curated data) and scores it against the geopolitical topics \
+         active in the news, such as US-China relations, sanctions or supply chain \
+         disruptions. Use it instead of sector-level reasoning when asked how exposed a \
+         particular stock is to a geopolitical risk."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "symbol": {
+                    "type": "string",
+                    "description": "Stock ticker symbol (e.g., AAPL, NVDA)"
+                }
+            },
+            "required": ["symbol"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::segments::revenue_breakdowns;
+    use crate::api::testing::{MockApi, fixtures};
+    use crate::tools::geopolitical::SentimentDistribution;
+    use std::time::Duration;
+
+    fn risk(topic: GeopoliticalTopic, risk_level: &'static str) -> TopicRisk {
+        TopicRisk {
+            topic,
+            risk_level,
+            recent_events_count: 1,
+            sentiment_distribution: SentimentDistribution {
+                positive: 0,
+                negative: 1,
+                neutral: 0,
+            },
+            key_developments: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            classify("United States"),
+            Some(&[(Region::UnitedStates, 1.0)][..])
+        );
+        assert_eq!(
+            classify("Greater China"),
+            Some(&[(Region::GreaterChina, 1.0)][..])
+        );
+        assert_eq!(classify("Taiwan"), Some(&[(Region::Taiwan, 1.0)][..]));
+        assert_eq!(
+            classify("Rest Of Asia Pacific"),
+            Some(&[(Region::OtherAsiaPacific, 1.0)][..])
+        );
+        assert_eq!(classify("Americas").unwrap()[0].0, Region::UnitedStates);
+        assert_eq!(classify("EMEA").unwrap()[1].0, Region::MiddleEastAfrica);
+        assert_eq!(classify("Data Center"), None);
+    }
+
+    #[test]
+    fn test_exposure_from_filing() {
+        let breakdowns = revenue_breakdowns(fixtures::SEC_10K_AAPL_SEGMENTS);
+        let exposure = GeoExposure::from_breakdowns(&breakdowns).unwrap();
+
+        // Apple reports its regions as operating segments, which cover all
+        // revenue, while the geographic axis only tags the US and China
+        assert!(matches!(
+            exposure.source,
+            ExposureSource::Filing {
+                axis: BreakdownAxis::Segment,
+                ..
+            }
+        ));
+        assert!((exposure.share(Region::GreaterChina) - 18.9).abs() < 0.1);
+        assert!((exposure.coverage_pct - 100.0).abs() < 0.1);
+        let total: f64 = exposure.regions.iter().map(|r| r.share_pct).sum();
+        assert!((total - 100.0).abs() < 0.1);
+
+        // Product lines say nothing about regions
+        let products: Vec<_> = breakdowns
+            .into_iter()
+            .filter(|b| b.axis == BreakdownAxis::Product)
+            .collect();
+        assert!(GeoExposure::from_breakdowns(&products).is_none());
+    }
+
+    #[test]
+    fn test_score() {
+        let qcom = GeoExposure::curated("qcom").unwrap();
+        let amzn = GeoExposure::curated("AMZN").unwrap();
+        let risks = [
+            risk(GeopoliticalTopic::UsChinaRelations, "High"),
+            risk(GeopoliticalTopic::CentralBanks, "High"),
+        ];
+
+        let qcom_risk = score(&qcom, &risks);
+        assert_eq!(qcom_risk.level, "High");
+        // Central banks are not regional, so only one topic applies
+        assert_eq!(qcom_risk.topics.len(), 1);
+        assert!((qcom_risk.score - 65.2).abs() < 0.1, "{}", qcom_risk.score);
+        assert_eq!(
+            qcom_risk.topics[0].drivers[0],
+            "62% of revenue from Greater China"
+        );
+
+        let amzn_risk = score(&amzn, &risks);
+        assert_eq!(amzn_risk.level, "Low");
+        assert!(amzn_risk.topics.is_empty());
+
+        // Lower risk levels weigh less, and topics compound
+        let calmer = score(&qcom, &[risk(GeopoliticalTopic::UsChinaRelations, "Low")]);
+        assert!(calmer.score < qcom_risk.score);
+        let both = score(
+            &qcom,
+            &[
+                risk(GeopoliticalTopic::UsChinaRelations, "High"),
+                risk(GeopoliticalTopic::SupplyChain, "Elevated"),
+            ],
+        );
+        assert!(both.score > qcom_risk.score && both.score < 100.0);
+    }
+
+    #[tokio::test]
+    async fn test_tool_with_filing_and_curated_fallback() {
+        let api = MockApi::recorded().await;
+        api.mount_json(
+            "/Archives/edgar/data/0000320193/000032019323000106/aapl-20230930.htm",
+            200,
+            fixtures::SEC_10K_AAPL_SEGMENTS,
+        )
+        .await;
+        let config = Arc::new(StockConfig::default());
+        let cache = StockCache::new(Duration::from_secs(3600));
+        let tool = GeoExposureTool::new(config, cache).with_sec_client(api.sec());
+
+        let result = tool.execute(json!({ "symbol": "aapl" })).await.unwrap();
+        assert_eq!(result["symbol"], "AAPL");
+        assert_eq!(result["source"]["type"], "filing");
+        assert_eq!(result["source"]["breakdown"], "segment");
+        assert!(result["risk_score"].as_f64().unwrap() > 0.0);
+
+        // Not in the recorded SEC ticker list, so only the curated table helps
+        let result = tool.execute(json!({ "symbol": "QCOM" })).await.unwrap();
+        assert_eq!(result["source"]["type"], "curated");
+        assert_eq!(result["regions"][0]["region"], "Greater China");
+
+        let err = tool.execute(json!({ "symbol": "ZZZZ" })).await.unwrap_err();
+        assert!(err.to_string().contains("curated"), "{err}");
+    }
+
+    #[test]
+    fn test_tool_metadata() {
+        let config = Arc::new(StockConfig::default());
+        let cache = StockCache::new(Duration::from_secs(3600));
+        let tool = GeoExposureTool::new(config, cache);
+
+        assert_eq!(tool.name(), "geo_exposure");
+        assert!(tool.description().contains("geopolitical"));
+        assert_eq!(tool.input_schema()["required"], json!(["symbol"]));
+    }
+}
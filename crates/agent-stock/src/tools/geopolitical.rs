@@ -9,10 +9,10 @@ use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 use std::sync::Arc;
 
-use crate::api::{FinnhubClient, AlphaVantageClient};
 use crate::cache::{CacheKey, StockCache};
 use crate::config::StockConfig;
 use crate::error::Result;
+use crate::news::{self, CompositeNewsProvider, NewsItem, NewsProvider};
 
 /// Geopolitical topic categories
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -43,15 +43,31 @@ impl GeopoliticalTopic {
     /// Get search keywords for this topic
     pub fn keywords(&self) -> Vec<&'static str> {
         match self {
-            GeopoliticalTopic::UsChinaRelations => vec!["china", "us china", "tariff", "trade war", "decoupling"],
-            GeopoliticalTopic::TradePolicies => vec!["trade", "tariff", "import", "export", "trade agreement"],
+            GeopoliticalTopic::UsChinaRelations => {
+                vec!["china", "us china", "tariff", "trade war", "decoupling"]
+            }
+            GeopoliticalTopic::TradePolicies => {
+                vec!["trade", "tariff", "import", "export", "trade agreement"]
+            }
             GeopoliticalTopic::Sanctions => vec!["sanction", "embargo", "restriction", "ban"],
-            GeopoliticalTopic::MiddleEast => vec!["middle east", "oil", "opec", "israel", "iran", "saudi"],
+            GeopoliticalTopic::MiddleEast => {
+                vec!["middle east", "oil", "opec", "israel", "iran", "saudi"]
+            }
             GeopoliticalTopic::EuropeanUnion => vec!["eu", "europe", "ecb", "euro", "brexit"],
             GeopoliticalTopic::EmergingMarkets => vec!["emerging market", "developing", "brics"],
-            GeopoliticalTopic::CurrencyPolicies => vec!["dollar", "currency", "forex", "exchange rate", "yen"],
-            GeopoliticalTopic::SupplyChain => vec!["supply chain", "semiconductor", "shortage", "logistics"],
-            GeopoliticalTopic::CentralBanks => vec!["fed", "federal reserve", "central bank", "interest rate", "monetary policy"],
+            GeopoliticalTopic::CurrencyPolicies => {
+                vec!["dollar", "currency", "forex", "exchange rate", "yen"]
+            }
+            GeopoliticalTopic::SupplyChain => {
+                vec!["supply chain", "semiconductor", "shortage", "logistics"]
+            }
+            GeopoliticalTopic::CentralBanks => vec![
+                "fed",
+                "federal reserve",
+                "central bank",
+                "interest rate",
+                "monetary policy",
+            ],
             GeopoliticalTopic::General => vec!["market", "economy", "global"],
         }
     }
@@ -75,14 +91,24 @@ impl GeopoliticalTopic {
     /// Get affected sectors for this topic
     pub fn affected_sectors(&self) -> Vec<&'static str> {
         match self {
-            GeopoliticalTopic::UsChinaRelations => vec!["Technology", "Industrials", "Consumer Discretionary"],
-            GeopoliticalTopic::TradePolicies => vec!["Industrials", "Materials", "Consumer Discretionary"],
+            GeopoliticalTopic::UsChinaRelations => {
+                vec!["Technology", "Industrials", "Consumer Discretionary"]
+            }
+            GeopoliticalTopic::TradePolicies => {
+                vec!["Industrials", "Materials", "Consumer Discretionary"]
+            }
             GeopoliticalTopic::Sanctions => vec!["Energy", "Financials", "Technology"],
             GeopoliticalTopic::MiddleEast => vec!["Energy", "Utilities", "Industrials"],
-            GeopoliticalTopic::EuropeanUnion => vec!["Financials", "Industrials", "Consumer Staples"],
-            GeopoliticalTopic::EmergingMarkets => vec!["Financials", "Materials", "Consumer Discretionary"],
+            GeopoliticalTopic::EuropeanUnion => {
+                vec!["Financials", "Industrials", "Consumer Staples"]
+            }
+            GeopoliticalTopic::EmergingMarkets => {
+                vec!["Financials", "Materials", "Consumer Discretionary"]
+            }
             GeopoliticalTopic::CurrencyPolicies => vec!["Financials", "Industrials", "Technology"],
-            GeopoliticalTopic::SupplyChain => vec!["Technology", "Industrials", "Consumer Discretionary"],
+            GeopoliticalTopic::SupplyChain => {
+                vec!["Technology", "Industrials", "Consumer Discretionary"]
+            }
             GeopoliticalTopic::CentralBanks => vec!["Financials", "Real Estate", "Utilities"],
             GeopoliticalTopic::General => vec!["All Sectors"],
         }
@@ -98,7 +124,9 @@ impl GeopoliticalTopic {
             "eu" | "europe" | "european union" => Some(GeopoliticalTopic::EuropeanUnion),
             "emerging" | "emerging markets" | "em" => Some(GeopoliticalTopic::EmergingMarkets),
             "currency" | "forex" | "dollar" => Some(GeopoliticalTopic::CurrencyPolicies),
-            "supply chain" | "supplychain" | "semiconductor" => Some(GeopoliticalTopic::SupplyChain),
+            "supply chain" | "supplychain" | "semiconductor" => {
+                Some(GeopoliticalTopic::SupplyChain)
+            }
             "fed" | "central bank" | "interest rate" => Some(GeopoliticalTopic::CentralBanks),
             _ => Some(GeopoliticalTopic::General),
         }
@@ -145,6 +173,18 @@ pub struct RiskAssessment {
     pub market_implications: Vec<String>,
 }
 
+/// Risk level of one topic, from its recent news
+#[derive(Debug, Clone)]
+pub struct TopicRisk {
+    pub topic: GeopoliticalTopic,
+    /// "High", "Elevated", "Moderate" or "Low"
+    pub risk_level: &'static str,
+    pub recent_events_count: usize,
+    pub sentiment_distribution: SentimentDistribution,
+    /// Up to three recent headlines
+    pub key_developments: Vec<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SentimentDistribution {
     pub positive: usize,
@@ -175,8 +215,8 @@ fn default_limit() -> usize {
 
 /// Tool for geopolitical analysis
 pub struct GeopoliticalTool {
-    finnhub_client: Option<FinnhubClient>,
-    _alpha_vantage_client: Option<AlphaVantageClient>,
+    /// Configured news providers; mock news when there are none
+    news: Option<Arc<dyn NewsProvider>>,
     cache: StockCache,
     _config: Arc<StockConfig>,
 }
@@ -184,17 +224,12 @@ pub struct GeopoliticalTool {
 impl GeopoliticalTool {
     /// Create a new geopolitical analysis tool
     pub fn new(config: Arc<StockConfig>, cache: StockCache) -> Self {
-        let finnhub_client = config.finnhub_api_key.as_ref().map(|key| {
-            FinnhubClient::new(key.clone(), 60)
-        });
-
-        let alpha_vantage_client = config.alpha_vantage_api_key.as_ref().map(|key| {
-            AlphaVantageClient::new(key.clone(), config.alpha_vantage_rate_limit)
-        });
+        let providers = news::providers_from_config(&config);
+        let news = (!providers.is_empty())
+            .then(|| Arc::new(CompositeNewsProvider::new(providers)) as Arc<dyn NewsProvider>);
 
         Self {
-            finnhub_client,
-            _alpha_vantage_client: alpha_vantage_client,
+            news,
             cache,
             _config: config,
         }
@@ -224,7 +259,10 @@ impl GeopoliticalTool {
     async fn analyze_geopolitics(&self, params: &GeopoliticalParams) -> Result<Value> {
         match params.analysis_type.to_lowercase().as_str() {
             "news" => {
-                let topic = params.topic.as_ref().and_then(|t| GeopoliticalTopic::parse(t));
+                let topic = params
+                    .topic
+                    .as_ref()
+                    .and_then(|t| GeopoliticalTopic::parse(t));
                 self.fetch_geopolitical_news(topic, params.limit).await
             }
             "risk" => self.assess_geopolitical_risks().await,
@@ -257,25 +295,9 @@ impl GeopoliticalTool {
 
     /// Get market news from available providers
     async fn get_market_news(&self, category: &str, limit: usize) -> Result<Vec<Value>> {
-        // Try Finnhub first
-        if let Some(ref client) = self.finnhub_client {
-            let articles = client.get_market_news(category).await?;
-            return Ok(articles
-                .into_iter()
-                .take(limit)
-                .map(|a| {
-                    json!({
-                        "title": a.headline,
-                        "summary": a.summary,
-                        "source": a.source,
-                        "published_at": chrono::DateTime::from_timestamp(a.datetime, 0)
-                            .map(|dt| dt.to_rfc3339())
-                            .unwrap_or_default(),
-                        "url": a.url,
-                        "category": a.category,
-                    })
-                })
-                .collect());
+        if let Some(news) = &self.news {
+            let articles = news.get_market_news(category, limit).await?;
+            return Ok(articles.iter().map(NewsItem::to_json).collect());
         }
 
         // Fall back to mock data if no API configured
@@ -283,16 +305,23 @@ impl GeopoliticalTool {
     }
 
     /// Categorize news by geopolitical topic
-    fn categorize_news(&self, news: &[Value], filter_topic: Option<GeopoliticalTopic>) -> Vec<Value> {
+    fn categorize_news(
+        &self,
+        news: &[Value],
+        filter_topic: Option<GeopoliticalTopic>,
+    ) -> Vec<Value> {
         news.iter()
             .filter_map(|article| {
                 let title = article.get("title")?.as_str()?;
-                let summary = article.get("summary").and_then(|s| s.as_str()).unwrap_or("");
+                let summary = article
+                    .get("summary")
+                    .and_then(|s| s.as_str())
+                    .unwrap_or("");
                 let content = format!("{title} {summary}").to_lowercase();
 
                 // Identify topic
                 let topic = self.identify_topic(&content);
-                
+
                 // Filter by topic if specified
                 if let Some(filter) = filter_topic {
                     if topic != filter {
@@ -333,13 +362,42 @@ impl GeopoliticalTool {
 
     /// Assess sentiment from content
     fn assess_sentiment(&self, content: &str) -> String {
-        let negative_words = ["crisis", "war", "conflict", "sanctions", "decline", "fear", 
-                            "crash", "risk", "threat", "tension", "collapse", "recession"];
-        let positive_words = ["growth", "deal", "agreement", "recovery", "boost", "rally",
-                            "strong", "surge", "gain", "optimism", "breakthrough"];
+        let negative_words = [
+            "crisis",
+            "war",
+            "conflict",
+            "sanctions",
+            "decline",
+            "fear",
+            "crash",
+            "risk",
+            "threat",
+            "tension",
+            "collapse",
+            "recession",
+        ];
+        let positive_words = [
+            "growth",
+            "deal",
+            "agreement",
+            "recovery",
+            "boost",
+            "rally",
+            "strong",
+            "surge",
+            "gain",
+            "optimism",
+            "breakthrough",
+        ];
 
-        let negative_count = negative_words.iter().filter(|w| content.contains(*w)).count();
-        let positive_count = positive_words.iter().filter(|w| content.contains(*w)).count();
+        let negative_count = negative_words
+            .iter()
+            .filter(|w| content.contains(*w))
+            .count();
+        let positive_count = positive_words
+            .iter()
+            .filter(|w| content.contains(*w))
+            .count();
 
         if negative_count > positive_count + 1 {
             "Negative".to_string()
@@ -352,8 +410,16 @@ impl GeopoliticalTool {
 
     /// Assess market impact level
     fn assess_impact(&self, content: &str, topic: &GeopoliticalTopic) -> String {
-        let high_impact_words = ["major", "significant", "breaking", "unprecedented", 
-                                "emergency", "crisis", "war", "collapse"];
+        let high_impact_words = [
+            "major",
+            "significant",
+            "breaking",
+            "unprecedented",
+            "emergency",
+            "crisis",
+            "war",
+            "collapse",
+        ];
         let medium_impact_words = ["important", "notable", "concern", "tension", "policy"];
 
         let has_high_impact = high_impact_words.iter().any(|w| content.contains(*w));
@@ -375,12 +441,13 @@ impl GeopoliticalTool {
         }
     }
 
-    /// Assess geopolitical risks across all topics
-    async fn assess_geopolitical_risks(&self) -> Result<Value> {
+    /// Risk level of every topic in the recent news, highest first
+    ///
+    /// Topics without any recent articles are left out.
+    pub async fn active_risks(&self) -> Result<Vec<TopicRisk>> {
         let news = self.get_market_news("general", 50).await?;
-        
-        let mut risk_assessments = Vec::new();
 
+        let mut risks = Vec::new();
         for topic in GeopoliticalTopic::all() {
             let topic_news: Vec<_> = news
                 .iter()
@@ -389,8 +456,9 @@ impl GeopoliticalTool {
                         "{} {}",
                         a.get("title").and_then(|t| t.as_str()).unwrap_or(""),
                         a.get("summary").and_then(|s| s.as_str()).unwrap_or("")
-                    ).to_lowercase();
-                    
+                    )
+                    .to_lowercase();
+
                     topic.keywords().iter().any(|k| content.contains(k))
                 })
                 .collect();
@@ -408,8 +476,12 @@ impl GeopoliticalTool {
                 let content = format!(
                     "{} {}",
                     article.get("title").and_then(|t| t.as_str()).unwrap_or(""),
-                    article.get("summary").and_then(|s| s.as_str()).unwrap_or("")
-                ).to_lowercase();
+                    article
+                        .get("summary")
+                        .and_then(|s| s.as_str())
+                        .unwrap_or("")
+                )
+                .to_lowercase();
 
                 match self.assess_sentiment(&content).as_str() {
                     "Positive" => positive += 1,
@@ -436,36 +508,50 @@ impl GeopoliticalTool {
                 .map(std::string::ToString::to_string)
                 .collect();
 
-            let market_implications = self.get_market_implications(&topic, risk_level);
-
-            risk_assessments.push(json!({
-                "topic": topic.name(),
-                "risk_level": risk_level,
-                "recent_events_count": topic_news.len(),
-                "sentiment_distribution": {
-                    "positive": positive,
-                    "negative": negative,
-                    "neutral": neutral,
+            risks.push(TopicRisk {
+                topic,
+                risk_level,
+                recent_events_count: topic_news.len(),
+                sentiment_distribution: SentimentDistribution {
+                    positive,
+                    negative,
+                    neutral,
                 },
-                "key_developments": key_developments,
-                "affected_sectors": topic.affected_sectors(),
-                "market_implications": market_implications,
-            }));
+                key_developments,
+            });
         }
 
         // Sort by risk level
-        risk_assessments.sort_by(|a, b| {
-            let risk_order = |r: &str| match r {
-                "High" => 0,
-                "Elevated" => 1,
-                "Moderate" => 2,
-                "Low" => 3,
-                _ => 4,
-            };
-            let a_risk = a.get("risk_level").and_then(|r| r.as_str()).unwrap_or("Low");
-            let b_risk = b.get("risk_level").and_then(|r| r.as_str()).unwrap_or("Low");
-            risk_order(a_risk).cmp(&risk_order(b_risk))
+        risks.sort_by_key(|risk| match risk.risk_level {
+            "High" => 0,
+            "Elevated" => 1,
+            "Moderate" => 2,
+            "Low" => 3,
+            _ => 4,
         });
+        Ok(risks)
+    }
+
+    /// Assess geopolitical risks across all topics
+    async fn assess_geopolitical_risks(&self) -> Result<Value> {
+        let risk_assessments: Vec<Value> = self
+            .active_risks()
+            .await?
+            .into_iter()
+            .map(|risk| {
+                let market_implications =
+                    self.get_market_implications(&risk.topic, risk.risk_level);
+                json!({
+                    "topic": risk.topic.name(),
+                    "risk_level": risk.risk_level,
+                    "recent_events_count": risk.recent_events_count,
+                    "sentiment_distribution": risk.sentiment_distribution,
+                    "key_developments": risk.key_developments,
+                    "affected_sectors": risk.topic.affected_sectors(),
+                    "market_implications": market_implications,
+                })
+            })
+            .collect();
 
         // Overall risk assessment
         let high_risk_count = risk_assessments
@@ -518,7 +604,10 @@ impl GeopoliticalTool {
             ],
         };
 
-        let mut implications: Vec<String> = base_implications.iter().map(std::string::ToString::to_string).collect();
+        let mut implications: Vec<String> = base_implications
+            .iter()
+            .map(std::string::ToString::to_string)
+            .collect();
 
         if risk_level == "High" {
             implications.push("Consider reducing position size".to_string());
@@ -534,10 +623,14 @@ impl GeopoliticalTool {
         let categorized = self.categorize_news(&news, None);
 
         // Group by topic
-        let mut topic_groups: std::collections::HashMap<String, Vec<&Value>> = std::collections::HashMap::new();
+        let mut topic_groups: std::collections::HashMap<String, Vec<&Value>> =
+            std::collections::HashMap::new();
         for article in &categorized {
             if let Some(topic) = article.get("topic").and_then(|t| t.as_str()) {
-                topic_groups.entry(topic.to_string()).or_default().push(article);
+                topic_groups
+                    .entry(topic.to_string())
+                    .or_default()
+                    .push(article);
             }
         }
 
@@ -549,9 +642,10 @@ impl GeopoliticalTool {
                     .iter()
                     .filter_map(|a| a.get("sentiment").and_then(|s| s.as_str()))
                     .collect();
-                
-                let negative_pct = sentiments.iter().filter(|&&s| s == "Negative").count() as f64 
-                    / sentiments.len().max(1) as f64 * 100.0;
+
+                let negative_pct = sentiments.iter().filter(|&&s| s == "Negative").count() as f64
+                    / sentiments.len().max(1) as f64
+                    * 100.0;
 
                 json!({
                     "topic": topic,
@@ -567,7 +661,7 @@ impl GeopoliticalTool {
             .iter()
             .filter(|a| a.get("sentiment").and_then(|s| s.as_str()) == Some("Negative"))
             .count();
-        
+
         let market_mood = if total_negative as f64 / categorized.len().max(1) as f64 > 0.5 {
             "Risk-off - Caution warranted"
         } else if total_negative as f64 / categorized.len().max(1) as f64 > 0.3 {
@@ -625,9 +719,8 @@ impl GeopoliticalTool {
 #[async_trait]
 impl Tool for GeopoliticalTool {
     async fn execute(&self, params: Value) -> AgentResult<Value> {
-        let params: GeopoliticalParams = serde_json::from_value(params).map_err(|e| {
-            agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}"))
-        })?;
+        let params: GeopoliticalParams = serde_json::from_value(params)
+            .map_err(|e| agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}")))?;
 
         self.fetch_geopolitical_data(params)
             .await
@@ -678,23 +771,48 @@ mod tests {
 
     #[test]
     fn test_topic_keywords() {
-        assert!(GeopoliticalTopic::UsChinaRelations.keywords().contains(&"china"));
+        assert!(
+            GeopoliticalTopic::UsChinaRelations
+                .keywords()
+                .contains(&"china")
+        );
         assert!(GeopoliticalTopic::MiddleEast.keywords().contains(&"oil"));
         assert!(GeopoliticalTopic::CentralBanks.keywords().contains(&"fed"));
     }
 
     #[test]
     fn test_topic_from_str() {
-        assert_eq!(GeopoliticalTopic::parse("china"), Some(GeopoliticalTopic::UsChinaRelations));
-        assert_eq!(GeopoliticalTopic::parse("fed"), Some(GeopoliticalTopic::CentralBanks));
-        assert_eq!(GeopoliticalTopic::parse("oil"), Some(GeopoliticalTopic::MiddleEast));
+        assert_eq!(
+            GeopoliticalTopic::parse("china"),
+            Some(GeopoliticalTopic::UsChinaRelations)
+        );
+        assert_eq!(
+            GeopoliticalTopic::parse("fed"),
+            Some(GeopoliticalTopic::CentralBanks)
+        );
+        assert_eq!(
+            GeopoliticalTopic::parse("oil"),
+            Some(GeopoliticalTopic::MiddleEast)
+        );
     }
 
     #[test]
     fn test_affected_sectors() {
-        assert!(GeopoliticalTopic::UsChinaRelations.affected_sectors().contains(&"Technology"));
-        assert!(GeopoliticalTopic::MiddleEast.affected_sectors().contains(&"Energy"));
-        assert!(GeopoliticalTopic::CentralBanks.affected_sectors().contains(&"Financials"));
+        assert!(
+            GeopoliticalTopic::UsChinaRelations
+                .affected_sectors()
+                .contains(&"Technology")
+        );
+        assert!(
+            GeopoliticalTopic::MiddleEast
+                .affected_sectors()
+                .contains(&"Energy")
+        );
+        assert!(
+            GeopoliticalTopic::CentralBanks
+                .affected_sectors()
+                .contains(&"Financials")
+        );
     }
 
     #[test]
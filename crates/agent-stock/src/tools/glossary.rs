@@ -0,0 +1,413 @@
+//! Bilingual glossary of financial terms
+//!
+//! Gives the agents a fixed, reviewed definition for common terms so that
+//! "what is a PEG ratio" is answered with a crisp definition (plus a worked
+//! example on real data) instead of a full stock analysis.
+
+use agent_core::Result as AgentResult;
+use agent_tools::Tool;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+/// Which kind of data a worked example for a term needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TermCategory {
+    /// Valuation and financial statement metrics
+    Fundamental,
+    /// Price-based indicators
+    Technical,
+    /// Market-wide and economic concepts
+    Market,
+}
+
+impl TermCategory {
+    /// Category name as shown to the LLM
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Fundamental => "fundamental",
+            Self::Technical => "technical",
+            Self::Market => "market",
+        }
+    }
+}
+
+/// A glossary entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlossaryEntry {
+    /// Canonical English term
+    pub term: &'static str,
+    /// Chinese term
+    pub term_zh: &'static str,
+    /// Lowercase names the term is recognized by (English and Chinese)
+    pub aliases: &'static [&'static str],
+    /// Kind of term
+    pub category: TermCategory,
+    /// English definition
+    pub definition: &'static str,
+    /// Chinese definition
+    pub definition_zh: &'static str,
+    /// How the value is computed, if it is a metric
+    pub formula: Option<&'static str>,
+    /// What data to fetch for a worked example
+    pub example_data: &'static str,
+}
+
+impl GlossaryEntry {
+    /// JSON representation returned by the tool
+    pub fn to_json(&self) -> Value {
+        json!({
+            "term": self.term,
+            "term_zh": self.term_zh,
+            "category": self.category.as_str(),
+            "definition": self.definition,
+            "definition_zh": self.definition_zh,
+            "formula": self.formula,
+            "example_data": self.example_data,
+        })
+    }
+}
+
+/// Bundled glossary
+pub const GLOSSARY: &[GlossaryEntry] = &[
+    GlossaryEntry {
+        term: "P/E Ratio",
+        term_zh: "市盈率",
+        aliases: &[
+            "p/e",
+            "pe ratio",
+            "p/e ratio",
+            "price-to-earnings",
+            "price to earnings",
+            "市盈率",
+        ],
+        category: TermCategory::Fundamental,
+        definition: "How many dollars investors pay for one dollar of annual earnings. A higher P/E means the market expects more growth, or the stock is expensive.",
+        definition_zh: "投资者为公司每1元年度盈利支付的价格。市盈率越高,说明市场预期增长越快,或股票越贵。",
+        formula: Some("Share price / Earnings per share (trailing 12 months)"),
+        example_data: "current price and trailing EPS",
+    },
+    GlossaryEntry {
+        term: "PEG Ratio",
+        term_zh: "市盈增长比率",
+        aliases: &[
+            "peg",
+            "peg ratio",
+            "price/earnings-to-growth",
+            "市盈增长比",
+            "市盈增长比率",
+            "peg比率",
+        ],
+        category: TermCategory::Fundamental,
+        definition: "The P/E ratio divided by the expected annual EPS growth rate. It adjusts valuation for growth; around 1 is often read as fairly valued, below 1 as cheap for its growth.",
+        definition_zh: "市盈率除以预期的每股收益年增长率,用增长来修正估值。约等于1通常视为合理,低于1说明相对增长而言较便宜。",
+        formula: Some("P/E ratio / Expected annual EPS growth (%)"),
+        example_data: "P/E ratio and expected EPS growth rate",
+    },
+    GlossaryEntry {
+        term: "EPS",
+        term_zh: "每股收益",
+        aliases: &["eps", "earnings per share", "每股收益", "每股盈利"],
+        category: TermCategory::Fundamental,
+        definition: "The company's net income divided by the number of shares outstanding: how much profit each share earned.",
+        definition_zh: "公司净利润除以流通股数,即每一股赚了多少钱。",
+        formula: Some("Net income / Shares outstanding"),
+        example_data: "trailing EPS",
+    },
+    GlossaryEntry {
+        term: "P/B Ratio",
+        term_zh: "市净率",
+        aliases: &[
+            "p/b",
+            "pb ratio",
+            "p/b ratio",
+            "price-to-book",
+            "price to book",
+            "市净率",
+        ],
+        category: TermCategory::Fundamental,
+        definition: "Market value compared with accounting book value. Below 1 means the stock trades for less than its net assets on paper.",
+        definition_zh: "市值与账面净资产之比。低于1表示股价低于账面净资产。",
+        formula: Some("Market cap / Book value (or price / book value per share)"),
+        example_data: "market cap and book value",
+    },
+    GlossaryEntry {
+        term: "Market Capitalization",
+        term_zh: "市值",
+        aliases: &["market cap", "market capitalization", "市值"],
+        category: TermCategory::Fundamental,
+        definition: "The total market value of all outstanding shares; used to size companies (large-cap, mid-cap, small-cap).",
+        definition_zh: "全部流通股的市场总价值,用于划分大盘股、中盘股和小盘股。",
+        formula: Some("Share price x Shares outstanding"),
+        example_data: "current price and market cap",
+    },
+    GlossaryEntry {
+        term: "Dividend Yield",
+        term_zh: "股息率",
+        aliases: &["dividend yield", "股息率", "股息收益率"],
+        category: TermCategory::Fundamental,
+        definition: "Annual dividends per share as a percentage of the share price: the cash return from dividends alone.",
+        definition_zh: "每股年度股息占股价的百分比,即仅来自分红的现金回报率。",
+        formula: Some("Annual dividends per share / Share price"),
+        example_data: "dividend yield and current price",
+    },
+    GlossaryEntry {
+        term: "Return on Equity (ROE)",
+        term_zh: "净资产收益率",
+        aliases: &["roe", "return on equity", "净资产收益率"],
+        category: TermCategory::Fundamental,
+        definition: "Net income as a percentage of shareholders' equity: how efficiently the company turns owners' capital into profit.",
+        definition_zh: "净利润占股东权益的百分比,衡量公司利用股东资本赚钱的效率。",
+        formula: Some("Net income / Shareholders' equity"),
+        example_data: "net income and shareholders' equity",
+    },
+    GlossaryEntry {
+        term: "Debt-to-Equity Ratio",
+        term_zh: "负债权益比",
+        aliases: &[
+            "debt-to-equity",
+            "debt to equity",
+            "d/e",
+            "负债权益比",
+            "产权比率",
+        ],
+        category: TermCategory::Fundamental,
+        definition: "Total debt relative to shareholders' equity; a measure of financial leverage and balance-sheet risk.",
+        definition_zh: "总负债与股东权益之比,衡量财务杠杆和资产负债表风险。",
+        formula: Some("Total debt / Shareholders' equity"),
+        example_data: "total debt and shareholders' equity",
+    },
+    GlossaryEntry {
+        term: "Free Cash Flow",
+        term_zh: "自由现金流",
+        aliases: &["free cash flow", "fcf", "自由现金流"],
+        category: TermCategory::Fundamental,
+        definition: "Cash from operations left after capital expenditures; the cash a company can return to shareholders or use to pay down debt.",
+        definition_zh: "经营现金流扣除资本支出后剩余的现金,可用于回馈股东或偿还债务。",
+        formula: Some("Operating cash flow - Capital expenditures"),
+        example_data: "operating cash flow and capital expenditures",
+    },
+    GlossaryEntry {
+        term: "Gross Margin",
+        term_zh: "毛利率",
+        aliases: &["gross margin", "毛利率"],
+        category: TermCategory::Fundamental,
+        definition: "The share of revenue left after the direct cost of goods sold; shows pricing power and production efficiency.",
+        definition_zh: "收入扣除直接销售成本后所占的比例,反映定价能力和生产效率。",
+        formula: Some("(Revenue - Cost of goods sold) / Revenue"),
+        example_data: "revenue and cost of revenue",
+    },
+    GlossaryEntry {
+        term: "RSI",
+        term_zh: "相对强弱指数",
+        aliases: &[
+            "rsi",
+            "relative strength index",
+            "相对强弱指数",
+            "相对强弱指标",
+        ],
+        category: TermCategory::Technical,
+        definition: "A momentum oscillator from 0 to 100 comparing recent gains with recent losses. Above 70 is usually read as overbought, below 30 as oversold.",
+        definition_zh: "比较近期涨幅与跌幅的动量指标,取值0到100。高于70通常视为超买,低于30视为超卖。",
+        formula: Some("100 - 100 / (1 + Average gain / Average loss), usually over 14 days"),
+        example_data: "14-day RSI",
+    },
+    GlossaryEntry {
+        term: "MACD",
+        term_zh: "指数平滑异同移动平均线",
+        aliases: &[
+            "macd",
+            "moving average convergence divergence",
+            "异同移动平均线",
+        ],
+        category: TermCategory::Technical,
+        definition: "The difference between a fast and a slow exponential moving average, compared with its own signal line; crossovers are read as momentum shifts.",
+        definition_zh: "快慢两条指数移动平均线之差,并与其信号线比较;交叉通常被视为动量转变的信号。",
+        formula: Some("EMA(12) - EMA(26); signal line = EMA(9) of MACD"),
+        example_data: "MACD line, signal line and histogram",
+    },
+    GlossaryEntry {
+        term: "Moving Average",
+        term_zh: "移动平均线",
+        aliases: &["moving average", "sma", "ema", "移动平均线", "均线"],
+        category: TermCategory::Technical,
+        definition: "The average closing price over a rolling window (e.g. 50 or 200 days). It smooths out noise to show the trend; price above the average suggests an uptrend.",
+        definition_zh: "一段滚动周期(如50日或200日)内收盘价的平均值,用于平滑波动、显示趋势;股价在均线上方通常表示上升趋势。",
+        formula: Some("SMA = Sum of closing prices over N days / N"),
+        example_data: "50-day and 200-day SMA",
+    },
+    GlossaryEntry {
+        term: "Bollinger Bands",
+        term_zh: "布林带",
+        aliases: &["bollinger", "bollinger bands", "布林带", "布林线"],
+        category: TermCategory::Technical,
+        definition: "A moving average with bands two standard deviations above and below it. Bands widen when volatility rises; touching a band shows a stretched price.",
+        definition_zh: "由一条移动平均线及其上下两个标准差的通道组成。波动加大时通道变宽,价格触及通道边缘说明偏离较大。",
+        formula: Some("Middle = SMA(20); Upper/Lower = Middle +/- 2 x standard deviation"),
+        example_data: "20-day Bollinger Bands",
+    },
+    GlossaryEntry {
+        term: "ATR",
+        term_zh: "平均真实波幅",
+        aliases: &["atr", "average true range", "平均真实波幅"],
+        category: TermCategory::Technical,
+        definition: "The average daily trading range including gaps; a measure of how much a stock typically moves, often used to size stop-losses.",
+        definition_zh: "包含跳空在内的平均每日波动幅度,衡量股票通常的波动大小,常用于设置止损。",
+        formula: Some("Average of the true range over N days (usually 14)"),
+        example_data: "14-day ATR and current price",
+    },
+    GlossaryEntry {
+        term: "Beta",
+        term_zh: "贝塔系数",
+        aliases: &["beta", "贝塔系数", "贝塔"],
+        category: TermCategory::Market,
+        definition: "How much a stock moves relative to the overall market. A beta of 1.5 means it has tended to move 1.5% when the market moves 1%.",
+        definition_zh: "股票相对于大盘的波动程度。贝塔为1.5表示大盘涨跌1%时,该股历史上通常涨跌1.5%。",
+        formula: Some("Covariance(stock, market) / Variance(market)"),
+        example_data: "beta",
+    },
+    GlossaryEntry {
+        term: "Yield Curve",
+        term_zh: "收益率曲线",
+        aliases: &["yield curve", "收益率曲线"],
+        category: TermCategory::Market,
+        definition: "Treasury yields plotted against their maturities. An inverted curve (short rates above long rates) has historically preceded recessions.",
+        definition_zh: "不同期限国债收益率连成的曲线。曲线倒挂(短期利率高于长期利率)历史上往往预示经济衰退。",
+        formula: Some("Spread commonly tracked as 10-year yield - 2-year yield"),
+        example_data: "current 10-year and 2-year Treasury yields",
+    },
+];
+
+/// Look up a term by name or alias
+pub fn lookup(term: &str) -> Option<&'static GlossaryEntry> {
+    let term = term.trim().to_lowercase();
+    GLOSSARY.iter().find(|entry| {
+        entry.term.to_lowercase() == term
+            || entry.term_zh == term
+            || entry.aliases.contains(&term.as_str())
+    })
+}
+
+/// Find the glossary term mentioned in free text (longest alias wins)
+pub fn find_term(text: &str) -> Option<&'static GlossaryEntry> {
+    let text = text.to_lowercase();
+    GLOSSARY
+        .iter()
+        .flat_map(|entry| entry.aliases.iter().map(move |alias| (entry, *alias)))
+        .filter(|(_, alias)| contains_term(&text, alias))
+        .max_by_key(|(_, alias)| alias.len())
+        .map(|(entry, _)| entry)
+}
+
+/// Whether `text` mentions `alias`; ASCII aliases must match whole words
+fn contains_term(text: &str, alias: &str) -> bool {
+    if !alias.is_ascii() {
+        return text.contains(alias);
+    }
+    text.match_indices(alias).any(|(start, _)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + alias.len()..].chars().next();
+        !before.is_some_and(|c| c.is_ascii_alphanumeric())
+            && !after.is_some_and(|c| c.is_ascii_alphanumeric())
+    })
+}
+
+/// Glossary tool parameters
+#[derive(Debug, Deserialize)]
+struct GlossaryParams {
+    term: String,
+}
+
+/// Tool for looking up definitions of financial terms
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GlossaryTool;
+
+impl GlossaryTool {
+    /// Create a new glossary tool
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl Tool for GlossaryTool {
+    async fn execute(&self, params: Value) -> AgentResult<Value> {
+        let params: GlossaryParams = serde_json::from_value(params)
+            .map_err(|e| agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}")))?;
+
+        match lookup(&params.term).or_else(|| find_term(&params.term)) {
+            Some(entry) => {
+                let mut result = entry.to_json();
+                result["found"] = json!(true);
+                Ok(result)
+            }
+            None => Ok(json!({
+                "found": false,
+                "term": params.term,
+                "available_terms": GLOSSARY.iter().map(|e| e.term).collect::<Vec<_>>(),
+            })),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "glossary"
+    }
+
+    fn description(&self) -> &'static str {
+        "Look up the definition of a financial term (English or Chinese), such as P/E, PEG ratio, \
+         RSI or free cash flow. Returns the definition in both languages, the formula, and which \
+         data is needed to compute a worked example."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "term": {
+                    "type": "string",
+                    "description": "Financial term to explain, e.g. 'PEG ratio' or '市盈率'"
+                }
+            },
+            "required": ["term"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_aliases() {
+        assert_eq!(lookup("PEG").unwrap().term, "PEG Ratio");
+        assert_eq!(lookup("市盈率").unwrap().term, "P/E Ratio");
+        assert_eq!(lookup("Relative Strength Index").unwrap().term, "RSI");
+        assert!(lookup("unobtainium").is_none());
+    }
+
+    #[test]
+    fn test_find_term_in_text() {
+        assert_eq!(find_term("What is a PEG ratio?").unwrap().term, "PEG Ratio");
+        assert_eq!(find_term("什么是市净率").unwrap().term, "P/B Ratio");
+        // "eps" inside another word must not match
+        assert!(find_term("what are the steps").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_tool_execute() {
+        let tool = GlossaryTool::new();
+        let result = tool
+            .execute(json!({ "term": "free cash flow" }))
+            .await
+            .unwrap();
+        assert_eq!(result["found"], true);
+        assert_eq!(result["term_zh"], "自由现金流");
+
+        let result = tool
+            .execute(json!({ "term": "quantum yield" }))
+            .await
+            .unwrap();
+        assert_eq!(result["found"], false);
+        assert!(result["available_terms"].as_array().unwrap().len() == GLOSSARY.len());
+    }
+}
@@ -1,32 +1,114 @@
-//! Tool for fetching macroeconomic indicators and Fed policy data
+//! Tool for fetching macroeconomic indicators and central bank policy data
 //!
-//! Uses FRED (Federal Reserve Economic Data) API for economic indicators
+//! US data comes from FRED (Federal Reserve Economic Data). The euro area is
+//! covered by the ECB Data Portal, which also serves Eurostat's labour market
+//! and national accounts figures. China's NBS and PBOC publish no usable API,
+//! so Chinese indicators are the OECD series mirrored on FRED.
 
 use agent_core::Result as AgentResult;
-use agent_tools::Tool;
+use agent_tools::{Tool, TypedTool};
 use async_trait::async_trait;
+use chrono::{DateTime, NaiveTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 use std::sync::Arc;
+use std::time::Duration;
 
-use crate::api::{FredClient, EconomicSummary, fred_series};
+use crate::api::{EcbClient, EconomicSummary, FredClient, ecb_series, fred_series};
 use crate::cache::{CacheKey, StockCache};
 use crate::config::StockConfig;
 use crate::error::{Result, StockError};
+use crate::scheduler::DailySchedule;
 
 /// Parameters for macro economic data requests
-#[derive(Debug, Deserialize)]
-struct MacroParams {
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MacroEconomicParams {
     /// Type of data: "summary", "rates", "inflation", "employment", "gdp", or specific indicator
     #[serde(default = "default_data_type")]
-    data_type: String,
+    pub data_type: String,
     /// Specific FRED series ID (optional)
-    series_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub series_id: Option<String>,
     /// Number of observations for historical data
     #[serde(default = "default_observations")]
-    observations: usize,
+    pub observations: usize,
+    /// Economy: "us" (default), "euro_area" or "china"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+}
+
+impl MacroEconomicParams {
+    /// Create parameters for the given data type
+    pub fn new(data_type: impl Into<String>) -> Self {
+        Self {
+            data_type: data_type.into(),
+            series_id: None,
+            observations: default_observations(),
+            country: None,
+        }
+    }
+
+    /// Request a specific FRED series
+    pub fn with_series(mut self, series_id: impl Into<String>) -> Self {
+        self.series_id = Some(series_id.into());
+        self
+    }
+
+    /// Set the number of historical observations
+    pub fn with_observations(mut self, observations: usize) -> Self {
+        self.observations = observations;
+        self
+    }
+
+    /// Request data for another economy, e.g. "euro_area"
+    pub fn with_country(mut self, country: impl Into<String>) -> Self {
+        self.country = Some(country.into());
+        self
+    }
 }
 
+/// Economy covered by the macro data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Country {
+    UnitedStates,
+    EuroArea,
+    China,
+}
+
+impl Country {
+    /// Parse a country name or code, in English or Chinese
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().replace([' ', '-'], "_").as_str() {
+            "us" | "usa" | "united_states" | "美国" => Some(Country::UnitedStates),
+            "euro_area" | "eurozone" | "ea" | "eu" | "europe" | "欧元区" | "欧洲" => {
+                Some(Country::EuroArea)
+            }
+            "china" | "cn" | "chn" | "中国" => Some(Country::China),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Country::UnitedStates => "us",
+            Country::EuroArea => "euro_area",
+            Country::China => "china",
+        }
+    }
+}
+
+impl Default for MacroEconomicParams {
+    fn default() -> Self {
+        Self::new(default_data_type())
+    }
+}
+
+/// Typed client for [`MacroEconomicTool`]
+///
+/// The output shape depends on `data_type`, so it is returned as JSON.
+pub type MacroEconomicClient = TypedTool<MacroEconomicParams, Value>;
+
 fn default_data_type() -> String {
     "summary".to_string()
 }
@@ -35,6 +117,27 @@ fn default_observations() -> usize {
     12
 }
 
+/// Weekday times (hour, minute UTC) shortly after FRED posts the US data
+/// behind the summary: 8:30 ET releases (CPI, jobs, GDP, PCE), 10:00 ET
+/// releases (consumer sentiment) and the 4:15 pm ET H.15 Treasury yields,
+/// each in both summer and winter time
+const SUMMARY_RELEASE_TIMES: [(u32, u32); 6] =
+    [(12, 45), (13, 45), (14, 15), (15, 15), (20, 30), (21, 30)];
+
+/// First release slot strictly after `now` at which the summary is refreshed
+pub fn next_summary_release(now: DateTime<Utc>) -> DateTime<Utc> {
+    SUMMARY_RELEASE_TIMES
+        .iter()
+        .filter_map(|&(hour, minute)| NaiveTime::from_hms_opt(hour, minute, 0))
+        .map(|time| {
+            DailySchedule::at(time)
+                .weekdays_only(true)
+                .next_run_after(now)
+        })
+        .min()
+        .unwrap_or(now)
+}
+
 /// Interest rate environment data
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RateEnvironment {
@@ -82,9 +185,22 @@ pub struct GdpData {
     pub as_of_date: String,
 }
 
+/// Cache key of a macro data request
+fn cache_key(country: Country, params: &MacroEconomicParams) -> CacheKey {
+    CacheKey::new(
+        country.as_str(),
+        &params.data_type,
+        json!({
+            "series": params.series_id,
+            "obs": params.observations
+        }),
+    )
+}
+
 /// Tool for fetching macroeconomic data
 pub struct MacroEconomicTool {
     fred_client: Option<FredClient>,
+    ecb_client: EcbClient,
     cache: StockCache,
     _config: Arc<StockConfig>,
 }
@@ -92,39 +208,187 @@ pub struct MacroEconomicTool {
 impl MacroEconomicTool {
     /// Create a new macro economic tool
     pub fn new(config: Arc<StockConfig>, cache: StockCache) -> Self {
-        let fred_client = config.fred_api_key.as_ref().map(|key| {
-            FredClient::new(key.clone(), None)
-        });
+        let fred_client = config
+            .fred_api_key
+            .as_ref()
+            .map(|key| FredClient::new(key.clone(), None));
 
         Self {
             fred_client,
+            ecb_client: EcbClient::new(),
             cache,
             _config: config,
         }
     }
 
+    /// Use `client` for US and China data (e.g. pointed at a mirror)
+    pub fn with_fred_client(mut self, client: FredClient) -> Self {
+        self.fred_client = Some(client);
+        self
+    }
+
+    /// Use `client` for euro area data (e.g. pointed at a mirror)
+    pub fn with_ecb_client(mut self, client: EcbClient) -> Self {
+        self.ecb_client = client;
+        self
+    }
+
+    /// Wrap this tool in a typed client for direct use from Rust
+    pub fn into_client(self) -> MacroEconomicClient {
+        TypedTool::new(Arc::new(self))
+    }
+
     /// Fetch macro economic data
-    async fn fetch_macro_data(&self, params: MacroParams) -> Result<Value> {
-        // Create cache key
-        let cache_key = CacheKey::new(
-            "macro",
-            &params.data_type,
-            json!({
-                "series": params.series_id,
-                "obs": params.observations
-            }),
-        );
+    async fn fetch_macro_data(&self, params: MacroEconomicParams) -> Result<Value> {
+        let country = match params.country.as_deref() {
+            None => Country::UnitedStates,
+            Some(name) => Country::parse(name).ok_or_else(|| {
+                StockError::ConfigError(format!(
+                    "Unsupported country: {name}. Supported: us, euro_area, china"
+                ))
+            })?,
+        };
 
         // Try to get from cache
         self.cache
-            .get_or_fetch(cache_key, || async {
-                self.fetch_from_fred(&params).await
+            .get_or_fetch(cache_key(country, &params), || async {
+                match country {
+                    Country::UnitedStates => self.fetch_from_fred(&params).await,
+                    Country::EuroArea => self.fetch_euro_area(&params).await,
+                    Country::China => self.fetch_china(&params).await,
+                }
             })
             .await
     }
 
+    /// Fetch the US economic summary and cache it, so the next summary
+    /// request is served without waiting on FRED
+    pub async fn refresh_summary(&self) -> Result<Value> {
+        let params = MacroEconomicParams::default();
+        let summary = self.fetch_from_fred(&params).await?;
+        self.cache
+            .insert(cache_key(Country::UnitedStates, &params), summary.clone())
+            .await;
+        Ok(summary)
+    }
+
+    /// Keep the US economic summary warm in the background
+    ///
+    /// The summary is refreshed after each weekday release slot and at least
+    /// every `max_age`, which should be below the cache TTL so the summary
+    /// never goes cold. Returns `None` without a FRED API key.
+    pub fn spawn_summary_refresh(
+        self: Arc<Self>,
+        max_age: Duration,
+    ) -> Option<tokio::task::JoinHandle<()>> {
+        self.fred_client.as_ref()?;
+        Some(tokio::spawn(async move {
+            loop {
+                if let Err(e) = self.refresh_summary().await {
+                    tracing::warn!("Economic summary refresh failed: {}", e);
+                }
+                let now = Utc::now();
+                let next = next_summary_release(now);
+                let wait = (next - now).to_std().unwrap_or_default().min(max_age);
+                tracing::debug!("Next economic summary refresh in {:?}", wait);
+                tokio::time::sleep(wait).await;
+            }
+        }))
+    }
+
+    /// Fetch euro area data from the ECB Data Portal
+    async fn fetch_euro_area(&self, params: &MacroEconomicParams) -> Result<Value> {
+        let ecb = &self.ecb_client;
+        let data_type = params.data_type.to_lowercase();
+        let (kind, data) = match data_type.as_str() {
+            "rates" | "interest_rates" => ("rate_environment", euro_area_rates(ecb).await),
+            "inflation" => ("inflation", euro_area_inflation(ecb).await),
+            "employment" | "jobs" => ("employment", euro_area_employment(ecb).await),
+            "gdp" | "growth" => ("gdp", euro_area_growth(ecb).await),
+            "custom" | "series" => {
+                let series_id = params.series_id.as_deref().ok_or_else(|| {
+                    StockError::ConfigError(
+                        "series_id required for custom data type (an ECB key such as \
+                         ICP/M.U2.N.000000.4.ANR)"
+                            .to_string(),
+                    )
+                })?;
+                let observations = ecb.get_observations(series_id, params.observations).await?;
+                (
+                    "custom_series",
+                    json!({ "series_id": series_id, "observations": observations }),
+                )
+            }
+            _ => (
+                "economic_summary",
+                json!({
+                    "interest_rates": euro_area_rates(ecb).await,
+                    "inflation": euro_area_inflation(ecb).await,
+                    "employment": euro_area_employment(ecb).await,
+                    "growth": euro_area_growth(ecb).await,
+                }),
+            ),
+        };
+
+        Ok(json!({
+            "type": kind,
+            "country": Country::EuroArea.as_str(),
+            "data": data,
+            "central_bank": "European Central Bank",
+            "inflation_target": 2.0,
+            "data_source": "ECB Data Portal (ECB, Eurostat)",
+        }))
+    }
+
+    /// Fetch China data from the OECD series on FRED
+    async fn fetch_china(&self, params: &MacroEconomicParams) -> Result<Value> {
+        let client = self.fred_client.as_ref().ok_or_else(|| {
+            StockError::ConfigError(
+                "FRED API key not configured. Set FRED_API_KEY environment variable.".to_string(),
+            )
+        })?;
+
+        let inflation = || fred_point(client, fred_series::CHINA_CPI_YOY);
+        let rates = || fred_point(client, fred_series::CHINA_INTERBANK_3M);
+        let growth = || fred_point(client, fred_series::CHINA_GDP_GROWTH);
+        let (kind, data) = match params.data_type.to_lowercase().as_str() {
+            "rates" | "interest_rates" => (
+                "rate_environment",
+                json!({ "interbank_rate_3m": rates().await }),
+            ),
+            "inflation" => ("inflation", json!({ "cpi_yoy": inflation().await })),
+            "gdp" | "growth" => ("gdp", json!({ "real_gdp_growth_yoy": growth().await })),
+            "custom" | "series" => {
+                let series_id = params.series_id.as_deref().ok_or_else(|| {
+                    StockError::ConfigError("series_id required for custom data type".to_string())
+                })?;
+                return self
+                    .get_series_data(client, series_id, params.observations)
+                    .await;
+            }
+            _ => (
+                "economic_summary",
+                json!({
+                    "inflation": { "cpi_yoy": inflation().await },
+                    "interest_rates": { "interbank_rate_3m": rates().await },
+                    "growth": { "real_gdp_growth_yoy": growth().await },
+                }),
+            ),
+        };
+
+        Ok(json!({
+            "type": kind,
+            "country": Country::China.as_str(),
+            "data": data,
+            "central_bank": "People's Bank of China",
+            "notes": "OECD Main Economic Indicators; these lag the NBS releases by up to a month. \
+                      Employment data is not available for China.",
+            "data_source": "OECD via Federal Reserve Economic Data (FRED)",
+        }))
+    }
+
     /// Fetch data from FRED API
-    async fn fetch_from_fred(&self, params: &MacroParams) -> Result<Value> {
+    async fn fetch_from_fred(&self, params: &MacroEconomicParams) -> Result<Value> {
         let client = self.fred_client.as_ref().ok_or_else(|| {
             StockError::ConfigError(
                 "FRED API key not configured. Set FRED_API_KEY environment variable.".to_string(),
@@ -140,7 +404,8 @@ impl MacroEconomicTool {
             "market" => self.get_market_indicators(client).await,
             "custom" | "series" => {
                 if let Some(ref series_id) = params.series_id {
-                    self.get_series_data(client, series_id, params.observations).await
+                    self.get_series_data(client, series_id, params.observations)
+                        .await
                 } else {
                     Err(StockError::ConfigError(
                         "series_id required for custom data type".to_string(),
@@ -502,7 +767,8 @@ impl MacroEconomicTool {
             if rate >= 5.0 {
                 implications.push("High rates pressure growth stocks and valuations");
                 implications.push("Financial sector may benefit from higher net interest margins");
-                implications.push("Real estate and utilities face headwinds from higher borrowing costs");
+                implications
+                    .push("Real estate and utilities face headwinds from higher borrowing costs");
             } else if rate >= 3.0 {
                 implications.push("Moderate rates - balanced environment for equities");
             } else {
@@ -544,12 +810,81 @@ impl MacroEconomicTool {
     }
 }
 
+/// Latest value of an ECB series with the prior period, or null if unavailable
+async fn ecb_point(client: &EcbClient, series_key: &str) -> Value {
+    match client.get_observations(series_key, 2).await {
+        Ok(observations) => match observations.as_slice() {
+            [latest, rest @ ..] => json!({
+                "value": latest.value,
+                "period": latest.period,
+                "prior": rest.first().map(|prior| prior.value),
+            }),
+            [] => Value::Null,
+        },
+        Err(e) => {
+            tracing::warn!("Failed to get {} from ECB: {}", series_key, e);
+            Value::Null
+        }
+    }
+}
+
+/// Latest value of a FRED series, or null if unavailable
+async fn fred_point(client: &FredClient, series_id: &str) -> Value {
+    match client.get_latest(series_id).await {
+        Ok(observation) => json!({ "value": observation.value, "date": observation.date }),
+        Err(e) => {
+            tracing::warn!("Failed to get {} from FRED: {}", series_id, e);
+            Value::Null
+        }
+    }
+}
+
+async fn euro_area_rates(ecb: &EcbClient) -> Value {
+    let deposit_rate = ecb_point(ecb, ecb_series::DEPOSIT_FACILITY_RATE).await;
+    let policy_stance = match deposit_rate["value"].as_f64() {
+        Some(rate) if rate >= 3.0 => "Restrictive - ECB is holding rates high to curb inflation",
+        Some(rate) if rate >= 1.5 => "Neutral - Policy is balanced",
+        Some(_) => "Accommodative - Supporting economic growth",
+        None => "Data unavailable",
+    };
+    json!({
+        "deposit_facility_rate": deposit_rate,
+        "main_refinancing_rate": ecb_point(ecb, ecb_series::MAIN_REFINANCING_RATE).await,
+        "yield_2y": ecb_point(ecb, ecb_series::YIELD_2Y).await,
+        "yield_10y": ecb_point(ecb, ecb_series::YIELD_10Y).await,
+        "policy_stance": policy_stance,
+    })
+}
+
+async fn euro_area_inflation(ecb: &EcbClient) -> Value {
+    let hicp = ecb_point(ecb, ecb_series::HICP).await;
+    let vs_target = match hicp["value"].as_f64() {
+        Some(rate) if rate <= 2.0 => "At or below the ECB's 2% target",
+        Some(rate) if rate <= 2.5 => "Slightly above target",
+        Some(rate) if rate <= 4.0 => "Moderately elevated",
+        Some(_) => "Significantly elevated",
+        None => "Data unavailable",
+    };
+    json!({
+        "hicp_yoy": hicp,
+        "core_hicp_yoy": ecb_point(ecb, ecb_series::CORE_HICP).await,
+        "vs_target": vs_target,
+    })
+}
+
+async fn euro_area_employment(ecb: &EcbClient) -> Value {
+    json!({ "unemployment_rate": ecb_point(ecb, ecb_series::UNEMPLOYMENT_RATE).await })
+}
+
+async fn euro_area_growth(ecb: &EcbClient) -> Value {
+    json!({ "real_gdp_growth_yoy": ecb_point(ecb, ecb_series::GDP_GROWTH).await })
+}
+
 #[async_trait]
 impl Tool for MacroEconomicTool {
     async fn execute(&self, params: Value) -> AgentResult<Value> {
-        let params: MacroParams = serde_json::from_value(params).map_err(|e| {
-            agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}"))
-        })?;
+        let params: MacroEconomicParams = serde_json::from_value(params)
+            .map_err(|e| agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}")))?;
 
         self.fetch_macro_data(params)
             .await
@@ -561,9 +896,12 @@ impl Tool for MacroEconomicTool {
     }
 
     fn description(&self) -> &'static str {
-        "Fetch macroeconomic indicators and Fed policy data from FRED (Federal Reserve Economic Data). \
-         Returns data on interest rates, inflation (CPI, PCE), employment, GDP growth, and market indicators. \
-         Provides economic assessment and market implications. Requires FRED API key."
+        "Fetch macroeconomic indicators and central bank policy data. US data (the default) \
+         comes from FRED (Federal Reserve Economic Data): interest rates, inflation (CPI, PCE), \
+         employment, GDP growth, and market indicators, with economic assessment and market \
+         implications. Set country to euro_area for ECB rates, HICP inflation, unemployment and \
+         GDP growth, or to china for CPI, interbank rates and GDP growth. FRED-backed data \
+         requires a FRED API key."
     }
 
     fn input_schema(&self) -> Value {
@@ -586,6 +924,12 @@ impl Tool for MacroEconomicTool {
                     "default": 12,
                     "minimum": 1,
                     "maximum": 100
+                },
+                "country": {
+                    "type": "string",
+                    "enum": ["us", "euro_area", "china"],
+                    "description": "Economy to fetch data for",
+                    "default": "us"
                 }
             }
         })
@@ -595,6 +939,7 @@ impl Tool for MacroEconomicTool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::api::testing::MockApi;
     use std::time::Duration;
 
     #[test]
@@ -607,4 +952,129 @@ mod tests {
         assert!(tool.description().contains("FRED"));
         assert!(tool.input_schema()["properties"]["data_type"].is_object());
     }
+
+    #[tokio::test]
+    async fn test_typed_client_without_fred_key() {
+        let config = Arc::new(StockConfig {
+            fred_api_key: None,
+            ..StockConfig::default()
+        });
+        let cache = StockCache::new(Duration::from_secs(3600));
+        let client = MacroEconomicTool::new(config, cache).into_client();
+
+        let result = client.call(MacroEconomicParams::new("rates")).await;
+        assert!(result.is_err());
+
+        // China data is read from FRED too
+        let result = client
+            .call(MacroEconomicParams::new("inflation").with_country("中国"))
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_next_summary_release() {
+        use chrono::TimeZone;
+
+        // Wednesday morning: the next slot is the 8:30 ET release in summer time
+        let morning = Utc.with_ymd_and_hms(2024, 3, 13, 9, 0, 0).unwrap();
+        assert_eq!(
+            next_summary_release(morning),
+            Utc.with_ymd_and_hms(2024, 3, 13, 12, 45, 0).unwrap()
+        );
+        let afternoon = Utc.with_ymd_and_hms(2024, 3, 13, 15, 15, 0).unwrap();
+        assert_eq!(
+            next_summary_release(afternoon),
+            Utc.with_ymd_and_hms(2024, 3, 13, 20, 30, 0).unwrap()
+        );
+        // Friday evening waits for Monday's releases
+        let friday = Utc.with_ymd_and_hms(2024, 3, 15, 22, 0, 0).unwrap();
+        assert_eq!(
+            next_summary_release(friday),
+            Utc.with_ymd_and_hms(2024, 3, 18, 12, 45, 0).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_refresh_summary() {
+        let api = MockApi::start().await;
+        api.mount_json(
+            "/fred/series/observations",
+            200,
+            r#"{"observations": [{"date": "2024-02-01", "value": "5.33"}]}"#,
+        )
+        .await;
+        let tool = Arc::new(
+            MacroEconomicTool::new(
+                Arc::new(StockConfig::default()),
+                StockCache::new(Duration::from_secs(3600)),
+            )
+            .with_fred_client(api.fred(None)),
+        );
+
+        let summary = tool.refresh_summary().await.unwrap();
+        assert_eq!(summary["data"]["interest_rates"]["fed_funds_rate"], 5.33);
+        let fetched = api.request_count().await;
+        assert_eq!(fetched, 10);
+
+        // The summary is served from the warm cache
+        let result = tool
+            .execute(json!({ "data_type": "summary" }))
+            .await
+            .unwrap();
+        assert_eq!(result, summary);
+        assert_eq!(api.request_count().await, fetched);
+
+        let config = Arc::new(StockConfig {
+            fred_api_key: None,
+            ..StockConfig::default()
+        });
+        let without_key = Arc::new(MacroEconomicTool::new(
+            config,
+            StockCache::new(Duration::from_secs(3600)),
+        ));
+        assert!(
+            without_key
+                .spawn_summary_refresh(Duration::from_secs(60))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_country_parse() {
+        assert_eq!(Country::parse("Euro Area"), Some(Country::EuroArea));
+        assert_eq!(Country::parse("欧元区"), Some(Country::EuroArea));
+        assert_eq!(Country::parse("CN"), Some(Country::China));
+        assert_eq!(Country::parse("us"), Some(Country::UnitedStates));
+        assert_eq!(Country::parse("japan"), None);
+    }
+
+    #[tokio::test]
+    async fn test_euro_area_inflation() {
+        let api = MockApi::recorded().await;
+        let config = Arc::new(StockConfig {
+            fred_api_key: None,
+            ..StockConfig::default()
+        });
+        let tool = MacroEconomicTool::new(config, StockCache::new(Duration::from_secs(3600)))
+            .with_ecb_client(api.ecb());
+
+        let result = tool
+            .execute(json!({ "data_type": "inflation", "country": "euro_area" }))
+            .await
+            .unwrap();
+        assert_eq!(result["country"], "euro_area");
+        assert_eq!(result["data"]["hicp_yoy"]["value"], 2.6);
+        assert_eq!(result["data"]["hicp_yoy"]["prior"], 2.8);
+        assert_eq!(result["data"]["hicp_yoy"]["period"], "2024-02");
+        assert_eq!(result["data"]["vs_target"], "Moderately elevated");
+        // Series the ECB does not return are left out rather than failing
+        assert!(result["data"]["core_hicp_yoy"].is_null());
+
+        let err = tool
+            .execute(json!({ "data_type": "inflation", "country": "mars" }))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Unsupported country"), "{err}");
+    }
 }
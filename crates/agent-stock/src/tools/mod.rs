@@ -1,21 +1,56 @@
 //! Stock analysis tools for LLM agents
 
+pub mod backtest;
 pub mod chart;
+pub mod dividend;
 pub mod earnings;
+pub mod earnings_calendar;
+pub mod earnings_quality;
+pub mod esg;
+pub mod event_study;
 pub mod fundamental;
+pub mod geo_exposure;
 pub mod geopolitical;
+pub mod glossary;
 pub mod macro_economic;
 pub mod news;
+pub mod portfolio;
+pub mod screener;
+pub mod sec_search;
 pub mod sector;
+pub mod segments;
+pub mod sql_query;
 pub mod stock_data;
+pub mod supply_chain;
 pub mod technical;
+pub mod theme;
+pub mod time_compare;
 
+pub use backtest::BacktestTool;
 pub use chart::ChartDataTool;
+pub use dividend::{DividendReport, DividendTool};
 pub use earnings::EarningsReportTool;
+pub use earnings_calendar::EarningsCalendarTool;
+pub use earnings_quality::{EarningsQualityTool, QualityReport, RedFlag};
+pub use esg::EsgTool;
+pub use event_study::EventStudyTool;
 pub use fundamental::FundamentalDataTool;
+pub use geo_exposure::GeoExposureTool;
 pub use geopolitical::GeopoliticalTool;
-pub use macro_economic::MacroEconomicTool;
+pub use glossary::{GlossaryEntry, GlossaryTool};
+pub use macro_economic::{MacroEconomicClient, MacroEconomicParams, MacroEconomicTool};
 pub use news::NewsTool;
+pub use portfolio::PortfolioTool;
+pub use screener::ScreenerTool;
+pub use sec_search::SecFullTextSearchTool;
 pub use sector::SectorAnalysisTool;
+pub use segments::RevenueBreakdownTool;
+pub use sql_query::SqlQueryTool;
 pub use stock_data::StockDataTool;
-pub use technical::TechnicalIndicatorTool;
+pub use supply_chain::SupplyChainTool;
+pub use technical::{
+    TechnicalIndicatorClient, TechnicalIndicatorOutput, TechnicalIndicatorParams,
+    TechnicalIndicatorTool,
+};
+pub use theme::{ThemeAnalysisTool, ThemeBasket};
+pub use time_compare::{Lookback, StateDiff, TimeComparisonTool};
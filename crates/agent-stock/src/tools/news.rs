@@ -3,21 +3,37 @@
 use agent_core::Result as AgentResult;
 use agent_tools::Tool;
 use async_trait::async_trait;
+use chrono::{NaiveDate, Utc};
 use serde::Deserialize;
 use serde_json::{Value, json};
 use std::sync::Arc;
 
-use crate::api::{AlphaVantageClient, FinnhubClient};
+use crate::api::YahooFinanceClient;
+use crate::as_of;
 use crate::cache::{CacheKey, StockCache};
-use crate::config::{NewsProvider, StockConfig};
+use crate::config::StockConfig;
+use crate::crypto;
 use crate::error::Result;
+use crate::news::{self, NewsItem, NewsProvider, NewsSentiment};
+use crate::news_archive::{self, DailyCloses, NewsArchive};
+use crate::source_translation::SourceTranslator;
+
+/// Longest sentiment history the tool reports
+const MAX_HISTORY_DAYS: u32 = 365;
 
 /// Tool for fetching stock news
+///
+/// Fetched articles are kept in a [`NewsArchive`], so asking with
+/// `history_days` adds the daily sentiment trend and how it lined up with
+/// price moves. With a [`SourceTranslator`], headlines and summaries carry
+/// a translation into the response language.
 pub struct NewsTool {
     cache: StockCache,
     config: Arc<StockConfig>,
-    finnhub_client: Option<FinnhubClient>,
-    alpha_vantage_client: Option<AlphaVantageClient>,
+    provider: Arc<dyn NewsProvider>,
+    archive: Arc<NewsArchive>,
+    prices: Arc<dyn DailyCloses>,
+    translator: Option<Arc<SourceTranslator>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -25,6 +41,10 @@ struct NewsParams {
     symbol: String,
     #[serde(default = "default_limit")]
     limit: usize,
+    #[serde(default)]
+    history_days: Option<u32>,
+    #[serde(default)]
+    as_of: Option<NaiveDate>,
 }
 
 fn default_limit() -> usize {
@@ -32,234 +52,161 @@ fn default_limit() -> usize {
 }
 
 impl NewsTool {
-    /// Create a new news tool
+    /// Create a new news tool using the configured news provider
     pub fn new(config: Arc<StockConfig>, cache: StockCache) -> Self {
-        // Initialize Finnhub client if configured
-        let finnhub_client = config.finnhub_api_key.as_ref().map(|key| {
-            FinnhubClient::new(key.clone(), 60) // Free tier: 60 req/min
-        });
-
-        // Initialize Alpha Vantage client if configured
-        let alpha_vantage_client = config
-            .alpha_vantage_api_key
-            .as_ref()
-            .map(|key| AlphaVantageClient::new(key.clone(), config.alpha_vantage_rate_limit));
+        let provider = news::from_config(&config);
+        Self::with_provider(config, cache, provider)
+    }
 
+    /// Create a news tool reading from `provider`
+    pub fn with_provider(
+        config: Arc<StockConfig>,
+        cache: StockCache,
+        provider: Arc<dyn NewsProvider>,
+    ) -> Self {
         Self {
             cache,
             config,
-            finnhub_client,
-            alpha_vantage_client,
+            provider,
+            archive: NewsArchive::shared(),
+            prices: Arc::new(YahooFinanceClient::new()),
+            translator: None,
         }
     }
 
+    /// Archive fetched articles in `archive` instead of the shared one
+    pub fn with_archive(mut self, archive: Arc<NewsArchive>) -> Self {
+        self.archive = archive;
+        self
+    }
+
+    /// Read daily closes for sentiment correlation from `prices`
+    pub fn with_prices(mut self, prices: Arc<dyn DailyCloses>) -> Self {
+        self.prices = prices;
+        self
+    }
+
+    /// Translate headlines and summaries with `translator`
+    pub fn with_translator(mut self, translator: Option<Arc<SourceTranslator>>) -> Self {
+        self.translator = translator;
+        self
+    }
+
     /// Fetch news for a symbol
     async fn fetch_news(&self, params: NewsParams) -> Result<Value> {
-        let symbol = params.symbol.to_uppercase();
+        let symbol = crypto::normalize_symbol(&params.symbol);
 
         // Create cache key
-        let cache_key = CacheKey::new(&symbol, "news", json!({"limit": params.limit}));
+        let cache_key = CacheKey::new(
+            &symbol,
+            "news",
+            json!({"limit": params.limit, "as_of": params.as_of}),
+        );
 
         // Try to get from cache
-        let result = self
-            .cache
-            .get_or_fetch(cache_key, || async {
-                match self.config.news_provider {
-                    NewsProvider::Mock => self.fetch_mock_news(&symbol, params.limit).await,
-                    NewsProvider::Finnhub => self.fetch_finnhub_news(&symbol, params.limit).await,
-                    NewsProvider::AlphaVantage => {
-                        self.fetch_alpha_vantage_news(&symbol, params.limit).await
-                    }
-                }
-            })
-            .await?;
+        let mut result = match params.as_of {
+            Some(date) => {
+                self.cache
+                    .get_or_fetch(cache_key, || self.fetch_as_of(&symbol, params.limit, date))
+                    .await?
+            }
+            None => {
+                self.cache
+                    .get_or_fetch(cache_key, || {
+                        self.fetch_from_provider(&symbol, params.limit)
+                    })
+                    .await?
+            }
+        };
+
+        // Translated after the cache, which keeps articles as fetched
+        if let (Some(translator), Some(articles)) =
+            (&self.translator, result["articles"].as_array_mut())
+        {
+            translator.annotate_articles(articles).await;
+        }
+
+        if let Some(days) = params.history_days {
+            let today = params.as_of.unwrap_or_else(|| Utc::now().date_naive());
+            result["sentiment_trend"] = self.sentiment_trend(&symbol, days, today).await;
+        }
 
         Ok(result)
     }
 
-    /// Fetch mock news data (for testing)
-    async fn fetch_mock_news(&self, symbol: &str, limit: usize) -> Result<Value> {
-        let mock_news = vec![
-            json!({
-                "title": format!("{} Stock Analysis Update", symbol),
-                "source": "Market News",
-                "published_at": chrono::Utc::now().to_rfc3339(),
-                "summary": format!("Latest market analysis and trends for {}", symbol),
-                "sentiment": "neutral",
-                "sentiment_score": 0.0,
-                "url": "https://example.com/news1"
-            }),
-            json!({
-                "title": format!("{} Quarterly Earnings Report", symbol),
-                "source": "Financial Times",
-                "published_at": (chrono::Utc::now() - chrono::Duration::days(1)).to_rfc3339(),
-                "summary": format!("{} reports quarterly earnings", symbol),
-                "sentiment": "positive",
-                "sentiment_score": 0.6,
-                "url": "https://example.com/news2"
-            }),
-        ];
-
-        let limited_news: Vec<_> = mock_news.into_iter().take(limit).collect();
-        Ok(self.build_news_response(symbol, limited_news))
+    /// Daily sentiment from the archive, correlated with daily returns
+    async fn sentiment_trend(&self, symbol: &str, days: u32, today: NaiveDate) -> Value {
+        let days = days.clamp(1, MAX_HISTORY_DAYS);
+        let points = self.archive.trend(symbol, days, today);
+
+        let correlation = if points.is_empty() {
+            Value::Null
+        } else {
+            // One extra week so the first day has a previous close
+            let from = today - chrono::Duration::days(i64::from(days) + 7);
+            match self.prices.daily_closes(symbol, from, today).await {
+                Ok(closes) => news_archive::correlate(&points, &closes).to_json(),
+                Err(e) => {
+                    tracing::warn!("No closes to correlate {} sentiment with: {}", symbol, e);
+                    Value::Null
+                }
+            }
+        };
+
+        json!({
+            "days": days,
+            "days_with_news": points.len(),
+            "sparkline": news_archive::trend_sparkline(&points),
+            "points": points,
+            "price_correlation": correlation,
+        })
     }
 
-    /// Fetch news from Finnhub
-    async fn fetch_finnhub_news(&self, symbol: &str, limit: usize) -> Result<Value> {
-        let client = self.finnhub_client.as_ref().ok_or_else(|| {
-            crate::error::StockError::ConfigError("Finnhub API key not configured".to_string())
-        })?;
-
-        // Calculate date range (last 30 days)
-        let to = chrono::Utc::now();
-        let from = to - chrono::Duration::days(30);
-        let from_str = from.format("%Y-%m-%d").to_string();
-        let to_str = to.format("%Y-%m-%d").to_string();
-
-        let articles = client.get_company_news(symbol, &from_str, &to_str).await?;
-
-        // Convert Finnhub articles to standardized format
-        let mut news: Vec<Value> = articles
-            .into_iter()
-            .take(limit)
-            .map(|article| {
-                json!({
-                    "title": article.headline,
-                    "source": article.source,
-                    "published_at": chrono::DateTime::from_timestamp(article.datetime, 0)
-                        .map(|dt| dt.to_rfc3339())
-                        .unwrap_or_default(),
-                    "summary": article.summary,
-                    "sentiment": "neutral", // Finnhub doesn't provide sentiment in free tier
-                    "sentiment_score": 0.0,
-                    "url": article.url,
-                    "image": article.image,
-                })
-            })
-            .collect();
+    /// Fetch news from the provider, bypassing the cache
+    async fn fetch_from_provider(&self, symbol: &str, limit: usize) -> Result<Value> {
+        let mut articles = self.provider.get_company_news(symbol, limit).await?;
 
         // If no company-specific news found, try market news
-        if news.is_empty() {
-            let market_news = client.get_market_news("general").await?;
-            news = market_news
-                .into_iter()
-                .take(limit)
-                .map(|article| {
-                    json!({
-                        "title": article.headline,
-                        "source": article.source,
-                        "published_at": chrono::DateTime::from_timestamp(article.datetime, 0)
-                            .map(|dt| dt.to_rfc3339())
-                            .unwrap_or_default(),
-                        "summary": article.summary,
-                        "sentiment": "neutral",
-                        "sentiment_score": 0.0,
-                        "url": article.url,
-                        "image": article.image,
-                    })
-                })
-                .collect();
+        if articles.is_empty() {
+            articles = self.provider.get_market_news("general", limit).await?;
+        } else if let Err(e) = self.archive.record(symbol, &articles) {
+            tracing::warn!("Failed to archive news for {}: {}", symbol, e);
         }
 
-        Ok(self.build_news_response(symbol, news))
+        Ok(self.build_news_response(symbol, &articles))
     }
 
-    /// Fetch news from Alpha Vantage
-    async fn fetch_alpha_vantage_news(&self, symbol: &str, limit: usize) -> Result<Value> {
-        let client = self.alpha_vantage_client.as_ref().ok_or_else(|| {
-            crate::error::StockError::ConfigError(
-                "Alpha Vantage API key not configured".to_string(),
-            )
-        })?;
-
-        let response = client
-            .get_news_sentiment(symbol, None, None, Some(limit as u32))
+    /// Fetch news published by the end of `date`
+    ///
+    /// Market news is not a fallback here, as the provider only has today's.
+    async fn fetch_as_of(&self, symbol: &str, limit: usize, date: NaiveDate) -> Result<Value> {
+        let articles = self
+            .provider
+            .get_company_news_until(symbol, limit, as_of::cutoff(date))
             .await?;
+        if let Err(e) = self.archive.record(symbol, &articles) {
+            tracing::warn!("Failed to archive news for {}: {}", symbol, e);
+        }
 
-        // Convert Alpha Vantage articles to standardized format
-        let news: Vec<Value> = response
-            .feed
-            .into_iter()
-            .map(|article| {
-                // Calculate sentiment label from score
-                let (sentiment_label, sentiment_score) =
-                    if let Some(score) = article.overall_sentiment_score {
-                        let label = if score > 0.15 {
-                            "positive"
-                        } else if score < -0.15 {
-                            "negative"
-                        } else {
-                            "neutral"
-                        };
-                        (label, score)
-                    } else {
-                        ("neutral", 0.0)
-                    };
-
-                json!({
-                    "title": article.title,
-                    "source": article.source,
-                    "published_at": article.time_published,
-                    "summary": article.summary,
-                    "sentiment": sentiment_label,
-                    "sentiment_score": sentiment_score,
-                    "url": article.url,
-                    "image": article.banner_image,
-                    "topics": article.topics.iter()
-                        .map(|t| json!({"topic": t.topic, "relevance": t.relevance_score}))
-                        .collect::<Vec<_>>(),
-                    "ticker_sentiment": article.ticker_sentiment.map(|ts| {
-                        ts.iter().map(|t| json!({
-                            "ticker": t.ticker,
-                            "relevance": t.relevance_score,
-                            "sentiment_score": t.ticker_sentiment_score,
-                            "sentiment_label": t.ticker_sentiment_label,
-                        })).collect::<Vec<_>>()
-                    }),
-                })
-            })
-            .collect();
-
-        Ok(self.build_news_response(symbol, news))
+        let mut response = self.build_news_response(symbol, &articles);
+        response["as_of"] = json!(date);
+        Ok(response)
     }
 
     /// Build standardized news response with sentiment analysis
-    fn build_news_response(&self, symbol: &str, articles: Vec<Value>) -> Value {
-        // Calculate overall sentiment
-        let sentiments: Vec<&str> = articles
-            .iter()
-            .filter_map(|n| n.get("sentiment").and_then(|s| s.as_str()))
-            .collect();
-
-        let positive_count = sentiments.iter().filter(|&&s| s == "positive").count();
-        let negative_count = sentiments.iter().filter(|&&s| s == "negative").count();
-        let neutral_count = sentiments.len() - positive_count - negative_count;
-
-        let overall_sentiment = if positive_count > negative_count {
-            "positive"
-        } else if negative_count > positive_count {
-            "negative"
-        } else {
-            "neutral"
-        };
-
-        // Calculate average sentiment score
-        let avg_score: f64 = articles
-            .iter()
-            .filter_map(|n| n.get("sentiment_score").and_then(serde_json::Value::as_f64))
-            .sum::<f64>()
-            / articles.len().max(1) as f64;
+    fn build_news_response(&self, symbol: &str, articles: &[NewsItem]) -> Value {
+        let sentiment = NewsSentiment::from_items(symbol, articles);
 
         json!({
             "symbol": symbol,
             "news_count": articles.len(),
-            "articles": articles,
-            "overall_sentiment": overall_sentiment,
-            "average_sentiment_score": avg_score,
+            "articles": articles.iter().map(NewsItem::to_json).collect::<Vec<_>>(),
+            "overall_sentiment": sentiment.overall,
+            "average_sentiment_score": sentiment.average_score,
             "sentiment_breakdown": {
-                "positive": positive_count,
-                "negative": negative_count,
-                "neutral": neutral_count,
+                "positive": sentiment.positive,
+                "negative": sentiment.negative,
+                "neutral": sentiment.neutral,
             },
             "provider": format!("{:?}", self.config.news_provider),
         })
@@ -269,9 +216,8 @@ impl NewsTool {
 #[async_trait]
 impl Tool for NewsTool {
     async fn execute(&self, params: Value) -> AgentResult<Value> {
-        let params: NewsParams = serde_json::from_value(params).map_err(|e| {
-            agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}"))
-        })?;
+        let params: NewsParams = serde_json::from_value(params)
+            .map_err(|e| agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}")))?;
 
         self.fetch_news(params)
             .await
@@ -285,7 +231,8 @@ impl Tool for NewsTool {
     fn description(&self) -> &'static str {
         "Fetch recent news articles and sentiment analysis for a stock symbol. \
          Returns news headlines, summaries, sentiment scores, and overall market sentiment. \
-         Supports multiple news providers: Mock (testing), Finnhub (60 req/min), and Alpha Vantage (with sentiment analysis)."
+         Supports multiple news providers: Mock (testing), Finnhub (60 req/min), and Alpha Vantage (with sentiment analysis). \
+         Set history_days for the daily sentiment trend from archived news and its correlation with price moves."
     }
 
     fn input_schema(&self) -> Value {
@@ -294,13 +241,18 @@ impl Tool for NewsTool {
             "properties": {
                 "symbol": {
                     "type": "string",
-                    "description": "Stock ticker symbol"
+                    "description": "Stock ticker symbol, or crypto pair (e.g., 'BTC-USD')"
                 },
                 "limit": {
                     "type": "integer",
                     "description": "Maximum number of news articles to fetch",
                     "default": 10
-                }
+                },
+                "history_days": {
+                    "type": "integer",
+                    "description": "Also report daily sentiment over this many past days (e.g. 30) and how it correlated with price moves"
+                },
+                "as_of": as_of::param_schema()
             },
             "required": ["symbol"]
         })
@@ -331,4 +283,158 @@ mod tests {
         assert!(data["articles"].is_array());
         assert_eq!(data["provider"], "Mock");
     }
+
+    #[tokio::test]
+    async fn test_falls_back_to_market_news() {
+        use crate::api::testing::MockApi;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, ResponseTemplate};
+
+        let api = MockApi::start().await;
+        api.mount_json("/api/v1/company-news", 200, "[]").await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/news"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([{
+                "category": "general", "datetime": 1_710_340_200, "headline": "Stocks rally",
+                "id": 1, "image": "", "related": "", "source": "CNBC", "summary": "", "url": ""
+            }])))
+            .mount(api.server())
+            .await;
+
+        let tool = NewsTool::with_provider(
+            Arc::new(StockConfig::default()),
+            StockCache::new(Duration::from_secs(300)),
+            Arc::new(api.finnhub(60)),
+        );
+        let data = tool.execute(json!({ "symbol": "AAPL" })).await.unwrap();
+        assert_eq!(data["news_count"], 1);
+        assert_eq!(data["articles"][0]["title"], "Stocks rally");
+        assert_eq!(data["articles"][0]["provider"], "Finnhub");
+        assert_eq!(data["overall_sentiment"], "neutral");
+    }
+
+    #[tokio::test]
+    async fn test_translated_articles() {
+        use crate::source_translation::{TranslationCache, TranslationProvider};
+        use agent_prompt::Language;
+
+        struct Tagging;
+
+        #[async_trait]
+        impl TranslationProvider for Tagging {
+            fn name(&self) -> &'static str {
+                "tagging"
+            }
+
+            async fn translate(&self, texts: &[String], _target: &Language) -> Result<Vec<String>> {
+                Ok(texts.iter().map(|text| format!("译:{text}")).collect())
+            }
+        }
+
+        let translator = SourceTranslator::new(Arc::new(Tagging), Language::Chinese)
+            .with_cache(Arc::new(TranslationCache::new(100)));
+        let tool = NewsTool::new(
+            Arc::new(StockConfig::default()),
+            StockCache::new(Duration::from_secs(300)),
+        )
+        .with_archive(Arc::new(NewsArchive::in_memory()))
+        .with_translator(Some(Arc::new(translator)));
+
+        let data = tool
+            .execute(json!({ "symbol": "AAPL", "limit": 2 }))
+            .await
+            .unwrap();
+        let article = &data["articles"][0];
+        assert_eq!(
+            article["title_translated"],
+            format!("译:{}", article["title"].as_str().unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_news_as_of() {
+        use crate::api::testing::MockApi;
+        use wiremock::matchers::{method, path, query_param};
+        use wiremock::{Mock, ResponseTemplate};
+
+        let api = MockApi::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/company-news"))
+            .and(query_param("to", "2024-03-12"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+                {
+                    "category": "company", "datetime": 1_710_340_200, "headline": "Later news",
+                    "id": 2, "image": "", "related": "AAPL", "source": "CNBC", "summary": "", "url": ""
+                },
+                {
+                    "category": "company", "datetime": 1_710_253_800, "headline": "Earlier news",
+                    "id": 1, "image": "", "related": "AAPL", "source": "CNBC", "summary": "", "url": ""
+                }
+            ])))
+            .mount(api.server())
+            .await;
+
+        let tool = NewsTool::with_provider(
+            Arc::new(StockConfig::default()),
+            StockCache::new(Duration::from_secs(300)),
+            Arc::new(api.finnhub(60)),
+        )
+        .with_archive(Arc::new(NewsArchive::in_memory()));
+        let data = tool
+            .execute(json!({ "symbol": "AAPL", "as_of": "2024-03-12" }))
+            .await
+            .unwrap();
+        assert_eq!(data["as_of"], "2024-03-12");
+        assert_eq!(data["news_count"], 1);
+        assert_eq!(data["articles"][0]["title"], "Earlier news");
+    }
+
+    #[tokio::test]
+    async fn test_sentiment_trend_from_archive() {
+        struct FlatCloses;
+
+        #[async_trait]
+        impl DailyCloses for FlatCloses {
+            async fn daily_closes(
+                &self,
+                _symbol: &str,
+                from: chrono::NaiveDate,
+                to: chrono::NaiveDate,
+            ) -> Result<Vec<(chrono::NaiveDate, f64)>> {
+                Ok(from
+                    .iter_days()
+                    .take_while(|d| *d <= to)
+                    .map(|d| (d, 100.0))
+                    .collect())
+            }
+        }
+
+        let archive = Arc::new(NewsArchive::in_memory());
+        let tool = NewsTool::new(
+            Arc::new(StockConfig::default()),
+            StockCache::new(Duration::from_secs(300)),
+        )
+        .with_archive(Arc::clone(&archive))
+        .with_prices(Arc::new(FlatCloses));
+
+        let data = tool
+            .execute(json!({ "symbol": "nvda", "history_days": 30 }))
+            .await
+            .unwrap();
+        assert_eq!(archive.articles("NVDA").len(), 2);
+
+        let trend = &data["sentiment_trend"];
+        assert_eq!(trend["days"], 30);
+        assert_eq!(trend["days_with_news"], 2);
+        assert_eq!(trend["points"][1]["positive"], 0);
+        assert_eq!(trend["points"][0]["positive"], 1);
+        assert_eq!(trend["sparkline"].as_str().unwrap().chars().count(), 2);
+        assert_eq!(
+            trend["price_correlation"]["interpretation"],
+            "not enough history"
+        );
+
+        let data = tool.execute(json!({ "symbol": "NVDA" })).await.unwrap();
+        assert!(data.get("sentiment_trend").is_none());
+    }
 }
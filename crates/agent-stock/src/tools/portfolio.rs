@@ -0,0 +1,132 @@
+//! Tool for valuing a user's recorded portfolio
+//!
+//! Gives the portfolio agent the numbers behind questions like "what's my
+//! exposure to tech?" or "what's my portfolio beta and YTD return": each
+//! holding's value, P&L, sector and beta, and the totals.
+
+use agent_core::Result as AgentResult;
+use agent_tools::Tool;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+use crate::api::YahooFinanceClient;
+use crate::cache::{CacheKey, StockCache};
+use crate::error::{Result, StockError};
+use crate::portfolio::{self, BENCHMARK, PortfolioStore, USER_ID_PARAM};
+
+/// Parameters for a portfolio request
+#[derive(Debug, Deserialize)]
+struct PortfolioParams {
+    /// Owner of the portfolio, pinned by the caller (see [`USER_ID_PARAM`])
+    user_id: Option<String>,
+}
+
+/// Tool for valuing recorded positions with current market data
+pub struct PortfolioTool {
+    store: Arc<PortfolioStore>,
+    yahoo_client: YahooFinanceClient,
+    cache: StockCache,
+}
+
+impl PortfolioTool {
+    /// Create a tool valuing the positions in `store`
+    pub fn new(store: Arc<PortfolioStore>, cache: StockCache) -> Self {
+        Self {
+            store,
+            yahoo_client: YahooFinanceClient::new(),
+            cache,
+        }
+    }
+
+    /// Value the user's positions and summarize them for the agent
+    async fn portfolio(&self, params: PortfolioParams) -> Result<Value> {
+        let user_id = params.user_id.ok_or_else(|| {
+            StockError::CommandError("No user to value the portfolio of".to_string())
+        })?;
+        let positions = self.store.positions(&user_id);
+        if positions.is_empty() {
+            return Ok(json!({
+                "user_id": user_id,
+                "positions": 0,
+                "message": "No positions recorded. Positions are added with \
+                            /portfolio add <symbol> <quantity> <cost basis>.",
+            }));
+        }
+
+        // Keyed by the positions so edits are never served stale
+        let cache_key = CacheKey::new(&user_id, "portfolio", json!(positions));
+        self.cache
+            .get_or_fetch(cache_key, || async {
+                let report = portfolio::valuate(&self.yahoo_client, &positions).await?;
+                let mut value = serde_json::to_value(&report)?;
+                value["user_id"] = json!(user_id);
+                value["benchmark"] = json!(BENCHMARK);
+                value["summary"] = json!(report.to_string());
+                value["assumptions"] = json!(
+                    "Valued at the latest daily close. YTD return assumes the current \
+                     holdings were held since the start of the year. Beta is from a year \
+                     of daily returns; sectors are Yahoo Finance classifications, Unknown \
+                     for funds."
+                );
+                Ok::<_, StockError>(value)
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl Tool for PortfolioTool {
+    async fn execute(&self, params: Value) -> AgentResult<Value> {
+        let params: PortfolioParams = serde_json::from_value(params)
+            .map_err(|e| agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}")))?;
+
+        self.portfolio(params)
+            .await
+            .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))
+    }
+
+    fn name(&self) -> &'static str {
+        "get_portfolio"
+    }
+
+    fn description(&self) -> &'static str {
+        "Get a user's recorded stock portfolio valued at the latest close: each \
+         holding's quantity, cost basis, price, market value, weight, unrealized P&L, \
+         YTD return, beta and sector, plus the portfolio's total value and P&L, YTD \
+         return, value-weighted beta against SPY and exposure by sector. Use it for \
+         questions about the user's own holdings, exposure, risk or performance."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {}
+        })
+    }
+
+    fn caller_params(&self) -> &[&'static str] {
+        &[USER_ID_PARAM]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_tool_metadata() {
+        let store = Arc::new(PortfolioStore::in_memory());
+        let cache = StockCache::new(std::time::Duration::from_secs(60));
+        let tool = PortfolioTool::new(store, cache);
+
+        assert_eq!(tool.name(), "get_portfolio");
+        assert_eq!(tool.input_schema()["properties"], json!({}));
+        assert_eq!(tool.caller_params(), [USER_ID_PARAM]);
+
+        let empty = tool.execute(json!({ "user_id": "42" })).await.unwrap();
+        assert_eq!(empty["positions"], 0);
+        assert!(tool.execute(json!({})).await.is_err());
+    }
+}
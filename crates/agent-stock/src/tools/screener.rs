@@ -0,0 +1,404 @@
+//! Tool for screening a universe of stocks
+//!
+//! Runs a [`Screen`] over a list of symbols: fetches what the filters need
+//! for each symbol, through the caches, then ranks the matches. Symbols
+//! whose data cannot be fetched are reported and skipped.
+
+use agent_core::Result as AgentResult;
+use agent_tools::Tool;
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use ta::{Next, indicators::RelativeStrengthIndex};
+
+use super::fundamental::overview_value;
+use crate::api::{AlphaVantageClient, YahooFinanceClient};
+use crate::cache::{CacheKey, StockCache};
+use crate::config::StockConfig;
+use crate::error::{Result, StockError};
+use crate::screener::{self, RSI_PERIOD, Screen, ScreenResult, Snapshot};
+use crate::tools::theme::theme_by_key;
+
+/// Symbols fetched at once; Yahoo throttles bursts and Alpha Vantage
+/// rate-limits per key anyway
+const MAX_CONCURRENT_FETCHES: usize = 4;
+
+/// Daily bars fetched for price and RSI
+const PRICE_RANGE: &str = "3mo";
+
+#[derive(Debug, Deserialize)]
+struct ScreenerParams {
+    filters: Vec<String>,
+    #[serde(default)]
+    universe: Option<Vec<String>>,
+    #[serde(default)]
+    sort_by: Option<String>,
+    #[serde(default)]
+    descending: Option<bool>,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+/// Tool for screening stocks by valuation, size, sector and RSI
+pub struct ScreenerTool {
+    yahoo_client: YahooFinanceClient,
+    alpha_vantage_client: Option<AlphaVantageClient>,
+    fundamentals: StockCache,
+    prices: StockCache,
+    universe: Vec<String>,
+}
+
+impl ScreenerTool {
+    /// Create a screener over the universe from the environment
+    ///
+    /// Company overviews are cached in `fundamentals` under the same keys as
+    /// the fundamental data tool; prices and RSI in `prices`.
+    pub fn new(config: &StockConfig, fundamentals: StockCache, prices: StockCache) -> Self {
+        Self {
+            yahoo_client: YahooFinanceClient::new(),
+            alpha_vantage_client: AlphaVantageClient::from_config(config),
+            fundamentals,
+            prices,
+            universe: screener::universe_from_env(),
+        }
+    }
+
+    /// Use these clients, e.g. pointed at a mock server
+    pub fn with_clients(
+        mut self,
+        yahoo: YahooFinanceClient,
+        alpha_vantage: Option<AlphaVantageClient>,
+    ) -> Self {
+        self.yahoo_client = yahoo;
+        self.alpha_vantage_client = alpha_vantage;
+        self
+    }
+
+    /// Scan `universe` instead of the default universe
+    pub fn with_universe(mut self, universe: Vec<String>) -> Self {
+        self.universe = universe;
+        self
+    }
+
+    /// Symbols scanned when a screen names no universe
+    pub fn universe(&self) -> &[String] {
+        &self.universe
+    }
+
+    /// Run `screen` over the default universe
+    pub async fn screen(&self, screen: Screen) -> Result<ScreenResult> {
+        self.screen_universe(screen, &self.universe).await
+    }
+
+    /// Run `screen` over `universe`
+    pub async fn screen_universe(
+        &self,
+        screen: Screen,
+        universe: &[String],
+    ) -> Result<ScreenResult> {
+        if screen.needs_fundamentals() && self.alpha_vantage_client.is_none() {
+            return Err(StockError::ConfigError(
+                "Alpha Vantage API key required to screen on P/E, market cap, dividend yield, \
+                 sector or industry"
+                    .to_string(),
+            ));
+        }
+
+        let screen_ref = &screen;
+        let fetches: Vec<_> = universe
+            .iter()
+            .map(|symbol| async move { (symbol, self.snapshot(symbol, screen_ref).await) })
+            .collect();
+        // Buffered, not unordered, so that ties keep the universe order
+        let fetched: Vec<_> = stream::iter(fetches)
+            .buffered(MAX_CONCURRENT_FETCHES)
+            .collect()
+            .await;
+
+        let mut snapshots = Vec::new();
+        let mut failed = Vec::new();
+        for (symbol, snapshot) in fetched {
+            match snapshot {
+                Ok(snapshot) => snapshots.push(snapshot),
+                Err(e) => {
+                    tracing::debug!(symbol = %symbol, error = %e, "Screener skipped symbol");
+                    failed.push(symbol.clone());
+                }
+            }
+        }
+
+        Ok(ScreenResult {
+            universe: universe.len(),
+            failed,
+            matches: screen.rank(snapshots),
+            screen,
+        })
+    }
+
+    /// Fetch the data `screen` needs for `symbol`
+    async fn snapshot(&self, symbol: &str, screen: &Screen) -> Result<Snapshot> {
+        let mut snapshot = Snapshot::new(symbol);
+
+        if let (true, Some(client)) = (screen.needs_fundamentals(), &self.alpha_vantage_client) {
+            let key = CacheKey::new(symbol, "fundamental", json!({}));
+            let overview = self
+                .fundamentals
+                .get_or_fetch(key, || async {
+                    let overview = client.get_company_overview(symbol).await?;
+                    Ok::<_, StockError>(overview_value(symbol, overview))
+                })
+                .await?;
+            let text = |field: &str| overview[field].as_str().map(ToString::to_string);
+            snapshot.name = text("name");
+            snapshot.sector = text("sector");
+            snapshot.industry = text("industry");
+            snapshot.pe_ratio = overview["pe_ratio"].as_f64();
+            snapshot.market_cap = overview["market_cap"].as_f64();
+            snapshot.dividend_yield = overview["dividend_yield"].as_f64().map(|y| y * 100.0);
+        }
+
+        if screen.needs_prices() {
+            let key = CacheKey::new(
+                symbol,
+                "screener_prices",
+                json!({ "range": PRICE_RANGE, "rsi_period": RSI_PERIOD }),
+            );
+            let prices = self
+                .prices
+                .get_or_fetch(key, || async {
+                    let quotes = self
+                        .yahoo_client
+                        .get_historical_range(symbol, PRICE_RANGE)
+                        .await?;
+                    let closes: Vec<f64> = quotes.iter().map(|q| q.close).collect();
+                    Ok::<_, StockError>(json!({
+                        "price": closes.last(),
+                        "rsi": rsi(&closes),
+                    }))
+                })
+                .await?;
+            snapshot.price = prices["price"].as_f64();
+            snapshot.rsi = prices["rsi"].as_f64();
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Build the screen and universe described by tool parameters
+    fn parse_params(&self, params: ScreenerParams) -> Result<(Screen, Vec<String>)> {
+        let mut screen: Screen = params.filters.join(", ").parse()?;
+        if let Some(name) = params.sort_by {
+            let metric = screener::Metric::parse(&name)
+                .ok_or_else(|| StockError::CommandError(format!("Unknown sort metric: {name}")))?;
+            screen = screen.with_sort(metric, params.descending.unwrap_or(false));
+        }
+        if let Some(limit) = params.limit {
+            screen = screen.with_limit(limit);
+        }
+
+        let universe = self.resolve_universe(params.universe.as_deref().unwrap_or_default());
+        Ok((screen, universe))
+    }
+
+    /// Symbols to scan for `universe`: the default universe when empty, a
+    /// theme's basket for a single theme key (ai, ev, semis), else the
+    /// symbols themselves
+    pub fn resolve_universe(&self, universe: &[String]) -> Vec<String> {
+        match universe {
+            [] => self.universe.clone(),
+            [key] if let Some(theme) = theme_by_key(key) => theme
+                .constituents
+                .iter()
+                .map(|(symbol, _)| (*symbol).to_string())
+                .collect(),
+            symbols => symbols.iter().map(|s| s.trim().to_uppercase()).collect(),
+        }
+    }
+}
+
+/// Latest RSI of `closes`, or `None` with too few of them to be meaningful
+fn rsi(closes: &[f64]) -> Option<f64> {
+    if closes.len() <= RSI_PERIOD {
+        return None;
+    }
+    let mut rsi = RelativeStrengthIndex::new(RSI_PERIOD).ok()?;
+    closes.iter().map(|close| rsi.next(*close)).last()
+}
+
+#[async_trait]
+impl Tool for ScreenerTool {
+    async fn execute(&self, params: Value) -> AgentResult<Value> {
+        let params: ScreenerParams = serde_json::from_value(params)
+            .map_err(|e| agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}")))?;
+
+        let result = async {
+            let (screen, universe) = self.parse_params(params)?;
+            self.screen_universe(screen, &universe).await
+        }
+        .await
+        .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))?;
+        Ok(result.to_value())
+    }
+
+    fn name(&self) -> &'static str {
+        "stock_screener"
+    }
+
+    fn description(&self) -> &'static str {
+        "Screen a universe of stocks with filters such as pe<20, market_cap>10B, rsi<30, \
+         yield>3 or sector=Technology, and return the matches ranked. Scans a default list \
+         of large US companies unless symbols or a theme key (ai, ev, semis) are given. \
+         P/E, market cap, yield, sector and industry filters require an Alpha Vantage API key."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "filters": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Filters, all of which must hold: <metric> <op> <value> with \
+                                    metric price, pe, market_cap, yield (percent) or rsi and op \
+                                    <, <=, >, >= or =; or sector/industry = or != <name>. \
+                                    Values accept K/M/B/T suffixes, e.g. market_cap>10B"
+                },
+                "universe": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Symbols to scan, or a single theme key (optional)"
+                },
+                "sort_by": {
+                    "type": "string",
+                    "enum": ["price", "pe", "market_cap", "yield", "rsi"],
+                    "description": "Metric to rank by (defaults to the first numeric filter's)"
+                },
+                "descending": {
+                    "type": "boolean",
+                    "description": "Rank highest first when sort_by is given (default false)"
+                },
+                "limit": {
+                    "type": "integer",
+                    "description": "Maximum matches to return (default 10)"
+                }
+            },
+            "required": ["filters"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::testing::MockApi;
+    use std::time::Duration;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, ResponseTemplate};
+
+    async fn mount_overview(api: &MockApi, symbol: &str, sector: &str, pe: &str, cap: &str) {
+        Mock::given(method("GET"))
+            .and(path("/query"))
+            .and(query_param("function", "OVERVIEW"))
+            .and(query_param("symbol", symbol))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "Symbol": symbol,
+                "Name": format!("{symbol} Inc"),
+                "Sector": sector,
+                "Industry": "SERVICES",
+                "MarketCapitalization": cap,
+                "PERatio": pe,
+                "DividendYield": "0.005",
+            })))
+            .mount(api.server())
+            .await;
+    }
+
+    fn tool(api: &MockApi, alpha_vantage: bool) -> ScreenerTool {
+        let cache = || StockCache::new(Duration::from_secs(60));
+        ScreenerTool::new(&StockConfig::default(), cache(), cache())
+            .with_clients(api.yahoo(), alpha_vantage.then(|| api.alpha_vantage(false)))
+            .with_universe(vec![
+                "AAPL".to_string(),
+                "MSFT".to_string(),
+                "ZZZZ".to_string(),
+            ])
+    }
+
+    #[tokio::test]
+    async fn test_fundamental_screen() {
+        let api = MockApi::recorded().await;
+        mount_overview(&api, "AAPL", "TECHNOLOGY", "28.5", "2600000000000").await;
+        mount_overview(&api, "MSFT", "TECHNOLOGY", "35.1", "3100000000000").await;
+        api.mount_json("/query", 200, "{}").await;
+        let tool = tool(&api, true);
+
+        let result = tool
+            .screen("sector=Technology market_cap>1T".parse().unwrap())
+            .await
+            .unwrap();
+        let symbols: Vec<&str> = result.matches.iter().map(|s| s.symbol.as_str()).collect();
+        assert_eq!(symbols, ["MSFT", "AAPL"]);
+        assert_eq!(result.failed, ["ZZZZ"]);
+        assert_eq!(result.matches[1].dividend_yield, Some(0.5));
+        // No price filter: no chart requests
+        assert!(result.matches[0].price.is_none());
+
+        let result = tool.screen("pe<30".parse().unwrap()).await.unwrap();
+        assert_eq!(result.matches.len(), 1);
+        assert_eq!(result.matches[0].name.as_deref(), Some("AAPL Inc"));
+    }
+
+    #[tokio::test]
+    async fn test_price_screen() {
+        let api = MockApi::recorded().await;
+        let tool = tool(&api, false);
+
+        // Price filters work without an Alpha Vantage key
+        let result = tool.screen("price>100".parse().unwrap()).await.unwrap();
+        assert_eq!(result.matches.len(), 1);
+        assert_eq!(result.matches[0].price, Some(171.13));
+        // Three bars are too few for an RSI
+        assert!(result.matches[0].rsi.is_none());
+        assert_eq!(result.failed, ["MSFT", "ZZZZ"]);
+
+        assert!(matches!(
+            tool.screen("pe<20".parse().unwrap()).await,
+            Err(StockError::ConfigError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_execute() {
+        let api = MockApi::recorded().await;
+        let tool = tool(&api, false);
+
+        let output = tool
+            .execute(json!({ "filters": ["price > 100"], "universe": ["aapl"] }))
+            .await
+            .unwrap();
+        assert_eq!(output["universe_size"], 1);
+        assert_eq!(output["matches"][0]["symbol"], "AAPL");
+
+        let (_, universe) = tool
+            .parse_params(
+                serde_json::from_value(json!({ "filters": ["rsi<30"], "universe": ["semis"] }))
+                    .unwrap(),
+            )
+            .unwrap();
+        assert!(universe.contains(&"NVDA".to_string()));
+
+        assert!(
+            tool.execute(json!({ "filters": ["beta<1"] }))
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_rsi() {
+        assert!(rsi(&[1.0; RSI_PERIOD]).is_none());
+        let rising: Vec<f64> = (0..30).map(f64::from).collect();
+        assert!(rsi(&rising).unwrap() > 90.0);
+    }
+}
@@ -0,0 +1,279 @@
+//! Tool for searching the text of SEC filings
+//!
+//! Answers questions like "which companies mentioned 'supply constraints' in
+//! recent 10-Qs" with EDGAR full-text search. Matches come back as filings;
+//! snippets around the phrase are cut from the top filings' documents.
+
+use agent_core::Result as AgentResult;
+use agent_tools::Tool;
+use async_trait::async_trait;
+use chrono::{Days, Utc};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+use crate::api::SecEdgarClient;
+use crate::cache::{CacheKey, StockCache};
+use crate::config::StockConfig;
+use crate::error::{Result, StockError};
+use crate::tools::supply_chain::strip_html;
+
+/// Filings whose documents are fetched for snippets
+const SNIPPET_FILINGS: usize = 5;
+/// Snippets kept per filing
+const SNIPPETS_PER_FILING: usize = 2;
+/// Characters of context on each side of a match
+const SNIPPET_CONTEXT: usize = 150;
+
+/// Passages of `text` around case-insensitive matches of `phrase`
+///
+/// Snippets are trimmed to word boundaries and do not overlap.
+pub fn snippets(text: &str, phrase: &str, max: usize) -> Vec<String> {
+    let phrase = phrase.trim().to_ascii_lowercase();
+    if phrase.is_empty() {
+        return Vec::new();
+    }
+    // ASCII lowercasing keeps byte offsets aligned with `text`
+    let haystack = text.to_ascii_lowercase();
+
+    let mut snippets = Vec::new();
+    let mut from = 0;
+    while snippets.len() < max {
+        let Some(offset) = haystack[from..].find(&phrase) else {
+            break;
+        };
+        let start = floor_char_boundary(text, (from + offset).saturating_sub(SNIPPET_CONTEXT));
+        let end = floor_char_boundary(
+            text,
+            (from + offset + phrase.len() + SNIPPET_CONTEXT).min(text.len()),
+        );
+
+        let mut snippet = &text[start..end];
+        if start > 0 {
+            snippet = snippet.split_once(' ').map_or(snippet, |(_, rest)| rest);
+        }
+        if end < text.len() {
+            snippet = snippet.rsplit_once(' ').map_or(snippet, |(rest, _)| rest);
+        }
+        snippets.push(format!("…{}…", snippet.trim()));
+        from = end.max(from + offset + phrase.len());
+    }
+    snippets
+}
+
+/// Largest char boundary in `text` at or before `index`
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    (0..=index)
+        .rev()
+        .find(|&i| text.is_char_boundary(i))
+        .unwrap_or(0)
+}
+
+/// Parameters for a full-text search
+#[derive(Debug, Deserialize)]
+struct SecSearchParams {
+    /// Exact phrase to search for
+    query: String,
+    /// Filing types, e.g. `["10-Q"]`
+    #[serde(default = "default_forms")]
+    forms: Vec<String>,
+    /// Search filings from the last `days` days
+    #[serde(default = "default_days")]
+    days: u64,
+    /// Maximum number of filings returned
+    #[serde(default = "default_limit")]
+    limit: usize,
+}
+
+fn default_forms() -> Vec<String> {
+    vec!["10-K".to_string(), "10-Q".to_string()]
+}
+
+fn default_days() -> u64 {
+    90
+}
+
+fn default_limit() -> usize {
+    10
+}
+
+/// Tool for full-text search of SEC filings
+pub struct SecFullTextSearchTool {
+    sec_client: SecEdgarClient,
+    cache: StockCache,
+}
+
+impl SecFullTextSearchTool {
+    /// Create a new SEC full-text search tool
+    pub fn new(config: Arc<StockConfig>, cache: StockCache) -> Self {
+        let sec_client = SecEdgarClient::new(&config.sec_user_agent, &config.sec_contact_email);
+
+        Self { sec_client, cache }
+    }
+
+    /// Search filings and cut snippets from the top matches
+    async fn search(&self, params: SecSearchParams) -> Result<Value> {
+        let phrase = params.query.trim().trim_matches('"').to_string();
+        if phrase.is_empty() {
+            return Err(StockError::ApiError("Search phrase is empty".to_string()));
+        }
+        let forms: Vec<String> = params
+            .forms
+            .iter()
+            .map(|form| form.to_uppercase())
+            .collect();
+        let limit = params.limit.clamp(1, 50);
+
+        let cache_key = CacheKey::new(
+            "sec_search",
+            phrase.to_lowercase(),
+            json!({ "forms": forms, "days": params.days, "limit": limit }),
+        );
+
+        self.cache
+            .get_or_fetch(cache_key, || async {
+                let end = Utc::now().date_naive();
+                let start = end.checked_sub_days(Days::new(params.days));
+                let results = self
+                    .sec_client
+                    .full_text_search(&phrase, &forms, start, Some(end))
+                    .await?;
+
+                let mut filings = Vec::new();
+                for (rank, hit) in results.hits.iter().take(limit).enumerate() {
+                    let mut filing = json!({
+                        "company": hit.company,
+                        "ticker": hit.ticker,
+                        "form": hit.form,
+                        "filing_date": hit.filing_date,
+                        "period_ending": hit.period_ending,
+                        "url": self.sec_client.get_filing_url(
+                            &hit.cik,
+                            &hit.accession_number,
+                            &hit.document,
+                        ),
+                    });
+                    if rank < SNIPPET_FILINGS {
+                        // Snippets are best effort; the match itself is the answer
+                        match self
+                            .sec_client
+                            .get_filing_document(&hit.cik, &hit.accession_number, &hit.document)
+                            .await
+                        {
+                            Ok(document) => {
+                                filing["snippets"] = json!(snippets(
+                                    &strip_html(&document),
+                                    &phrase,
+                                    SNIPPETS_PER_FILING
+                                ));
+                            }
+                            Err(e) => {
+                                tracing::warn!("No snippets for {}: {}", hit.accession_number, e);
+                            }
+                        }
+                    }
+                    filings.push(filing);
+                }
+
+                Ok::<_, StockError>(json!({
+                    "query": phrase,
+                    "forms": forms,
+                    "from_date": start.map(|date| date.to_string()),
+                    "total_matches": results.total,
+                    "filings": filings,
+                    "data_source": "SEC EDGAR full-text search",
+                }))
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl Tool for SecFullTextSearchTool {
+    async fn execute(&self, params: Value) -> AgentResult<Value> {
+        let params: SecSearchParams = serde_json::from_value(params)
+            .map_err(|e| agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}")))?;
+
+        self.search(params)
+            .await
+            .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))
+    }
+
+    fn name(&self) -> &'static str {
+        "sec_full_text_search"
+    }
+
+    fn description(&self) -> &'static str {
+        "Search the full text of recent SEC filings for an exact phrase, e.g. which \
+         companies mentioned \"supply constraints\" in their 10-Qs. Returns the number \
+         of matching filings and, for each, the company, ticker, form, filing date, \
+         link and snippets around the phrase."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "Exact phrase to search for, e.g. supply constraints"
+                },
+                "forms": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Filing types to search (default: [\"10-K\", \"10-Q\"])"
+                },
+                "days": {
+                    "type": "integer",
+                    "description": "Search filings from the last N days (default: 90)"
+                },
+                "limit": {
+                    "type": "integer",
+                    "description": "Maximum number of filings to return (default: 10, max: 50)"
+                }
+            },
+            "required": ["query"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snippets() {
+        let text = format!(
+            "{} We continue to face Supply Constraints on certain components. {} \
+             supply constraints eased in Q2. Supply constraints",
+            "lorem ipsum ".repeat(20),
+            "dolor sit amet ".repeat(20)
+        );
+
+        // The last two matches are close enough to share a snippet
+        let found = snippets(&text, "supply constraints", 5);
+        assert_eq!(found.len(), 2);
+        assert!(found[0].contains("face Supply Constraints on certain"));
+        assert!(found[0].starts_with('…') && !found[0].starts_with("… lor"));
+        assert!(found[0].chars().count() < 2 * SNIPPET_CONTEXT + 30);
+        assert!(found[1].contains("constraints eased in Q2. Supply constraints"));
+
+        assert_eq!(snippets(&text, "supply constraints", 1).len(), 1);
+        assert!(snippets(&text, "inventory", 5).is_empty());
+        assert!(
+            snippets("供应链 supply constraints 紧张", "supply constraints", 5)[0]
+                .contains("供应链")
+        );
+    }
+
+    #[test]
+    fn test_tool_metadata() {
+        let config = Arc::new(StockConfig::default());
+        let cache = StockCache::new(std::time::Duration::from_secs(3600));
+        let tool = SecFullTextSearchTool::new(config, cache);
+
+        assert_eq!(tool.name(), "sec_full_text_search");
+        assert!(tool.description().contains("SEC filings"));
+        assert_eq!(tool.input_schema()["required"], json!(["query"]));
+    }
+}
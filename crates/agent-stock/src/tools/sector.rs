@@ -5,6 +5,7 @@
 use agent_core::Result as AgentResult;
 use agent_tools::Tool;
 use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 use std::sync::Arc;
@@ -14,6 +15,9 @@ use crate::cache::{CacheKey, StockCache};
 use crate::config::StockConfig;
 use crate::error::Result;
 
+/// Sector ETFs fetched at once; Yahoo throttles bursts of requests
+const MAX_CONCURRENT_ETF_FETCHES: usize = 4;
+
 /// Market sector definitions
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Sector {
@@ -202,9 +206,7 @@ impl SectorAnalysisTool {
 
         // Try to get from cache
         self.cache
-            .get_or_fetch(cache_key, || async {
-                self.analyze_sectors(&params).await
-            })
+            .get_or_fetch(cache_key, || async { self.analyze_sectors(&params).await })
             .await
     }
 
@@ -256,19 +258,31 @@ impl SectorAnalysisTool {
 
     /// Get performance for all sectors
     async fn get_all_sectors_performance(&self) -> Result<Value> {
-        let mut performances = Vec::new();
-
-        for sector in Sector::all() {
-            if let Ok(perf) = self.fetch_sector_etf_data(sector).await {
-                performances.push(perf);
-            }
-        }
+        let fetches: Vec<_> = Sector::all()
+            .into_iter()
+            .map(|sector| self.fetch_sector_etf_data(sector))
+            .collect();
+        let mut performances: Vec<Value> = stream::iter(fetches)
+            .buffer_unordered(MAX_CONCURRENT_ETF_FETCHES)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .filter_map(Result::ok)
+            .collect();
 
         // Sort by 1-day performance
         performances.sort_by(|a, b| {
-            let a_pct = a.get("change_1d_pct").and_then(serde_json::Value::as_f64).unwrap_or(0.0);
-            let b_pct = b.get("change_1d_pct").and_then(serde_json::Value::as_f64).unwrap_or(0.0);
-            b_pct.partial_cmp(&a_pct).unwrap_or(std::cmp::Ordering::Equal)
+            let a_pct = a
+                .get("change_1d_pct")
+                .and_then(serde_json::Value::as_f64)
+                .unwrap_or(0.0);
+            let b_pct = b
+                .get("change_1d_pct")
+                .and_then(serde_json::Value::as_f64)
+                .unwrap_or(0.0);
+            b_pct
+                .partial_cmp(&a_pct)
+                .unwrap_or(std::cmp::Ordering::Equal)
         });
 
         // Identify leaders and laggards
@@ -319,7 +333,7 @@ impl SectorAnalysisTool {
     /// Analyze sector rotation patterns
     async fn analyze_sector_rotation(&self) -> Result<Value> {
         let performances = self.get_all_sectors_performance().await?;
-        
+
         // Analyze which sectors are showing strength
         let cyclical_strength = self.calculate_group_strength(&performances, "Cyclical");
         let defensive_strength = self.calculate_group_strength(&performances, "Defensive");
@@ -338,7 +352,7 @@ impl SectorAnalysisTool {
 
         // Rate sensitive sectors
         let rate_sensitive_perf = self.calculate_rate_sensitive_performance(&performances);
-        
+
         let rate_outlook = if rate_sensitive_perf > 0.0 {
             "Rate-sensitive sectors outperforming - Market may expect rate cuts"
         } else {
@@ -378,20 +392,27 @@ impl SectorAnalysisTool {
     /// Fetch ETF data for a sector
     async fn fetch_sector_etf_data(&self, sector: Sector) -> Result<Value> {
         let ticker = sector.etf_ticker();
-        
+
         // Get quote data
         let quote = self.yahoo_client.get_quote(ticker).await?;
-        
+
         // Get historical data for performance calculations
-        let historical = self.yahoo_client.get_historical_range(ticker, "3mo").await?;
+        let historical = self
+            .yahoo_client
+            .get_historical_range(ticker, "3mo")
+            .await?;
 
         let current_price = quote.close;
-        
+
         // Calculate 1-day change from historical data
         let (change_1d, change_1d_pct) = if historical.len() >= 2 {
             let prev_close = historical[1].close;
             let change = current_price - prev_close;
-            let pct = if prev_close == 0.0 { 0.0 } else { (change / prev_close) * 100.0 };
+            let pct = if prev_close == 0.0 {
+                0.0
+            } else {
+                (change / prev_close) * 100.0
+            };
             (Some(change), Some(pct))
         } else {
             (None, None)
@@ -433,7 +454,11 @@ impl SectorAnalysisTool {
     }
 
     /// Calculate period return from Quote vector
-    fn calculate_period_return_from_quotes(&self, quotes: &[crate::api::yahoo::Quote], days: usize) -> Option<f64> {
+    fn calculate_period_return_from_quotes(
+        &self,
+        quotes: &[crate::api::yahoo::Quote],
+        days: usize,
+    ) -> Option<f64> {
         if quotes.len() < days {
             return None;
         }
@@ -451,9 +476,15 @@ impl SectorAnalysisTool {
     /// Get sector description
     fn get_sector_description(&self, sector: Sector) -> &'static str {
         match sector {
-            Sector::Technology => "Companies in software, hardware, semiconductors, and IT services",
-            Sector::Healthcare => "Pharmaceuticals, biotechnology, medical devices, and healthcare services",
-            Sector::Financials => "Banks, insurance companies, asset managers, and financial services",
+            Sector::Technology => {
+                "Companies in software, hardware, semiconductors, and IT services"
+            }
+            Sector::Healthcare => {
+                "Pharmaceuticals, biotechnology, medical devices, and healthcare services"
+            }
+            Sector::Financials => {
+                "Banks, insurance companies, asset managers, and financial services"
+            }
             Sector::ConsumerDiscretionary => "Retail, automobiles, entertainment, and luxury goods",
             Sector::ConsumerStaples => "Food, beverages, household products, and personal care",
             Sector::Energy => "Oil & gas exploration, production, and energy equipment",
@@ -467,9 +498,18 @@ impl SectorAnalysisTool {
 
     /// Analyze current sector conditions
     fn analyze_sector_conditions(&self, performance: &Value) -> Value {
-        let change_1d = performance.get("change_1d_pct").and_then(serde_json::Value::as_f64).unwrap_or(0.0);
-        let change_1m = performance.get("change_1m_pct").and_then(serde_json::Value::as_f64).unwrap_or(0.0);
-        let volume_ratio = performance.get("volume_ratio").and_then(serde_json::Value::as_f64).unwrap_or(1.0);
+        let change_1d = performance
+            .get("change_1d_pct")
+            .and_then(serde_json::Value::as_f64)
+            .unwrap_or(0.0);
+        let change_1m = performance
+            .get("change_1m_pct")
+            .and_then(serde_json::Value::as_f64)
+            .unwrap_or(0.0);
+        let volume_ratio = performance
+            .get("volume_ratio")
+            .and_then(serde_json::Value::as_f64)
+            .unwrap_or(1.0);
 
         let momentum = if change_1m > 5.0 {
             "Strong uptrend"
@@ -518,7 +558,7 @@ impl SectorAnalysisTool {
     /// Calculate group strength (cyclical vs defensive)
     fn calculate_group_strength(&self, performances: &Value, group: &str) -> f64 {
         let sectors = performances.get("sectors").and_then(|s| s.as_array());
-        
+
         if let Some(sectors) = sectors {
             let group_sectors: Vec<_> = sectors
                 .iter()
@@ -548,7 +588,7 @@ impl SectorAnalysisTool {
     /// Calculate rate-sensitive sector performance
     fn calculate_rate_sensitive_performance(&self, performances: &Value) -> f64 {
         let sectors = performances.get("sectors").and_then(|s| s.as_array());
-        
+
         if let Some(sectors) = sectors {
             let rate_sensitive: Vec<_> = sectors
                 .iter()
@@ -602,19 +642,28 @@ impl SectorAnalysisTool {
     /// Calculate sector rankings
     fn calculate_sector_rankings(&self, performances: &Value) -> Value {
         let sectors = performances.get("sectors").and_then(|s| s.as_array());
-        
+
         if let Some(sectors) = sectors {
             let mut rankings: Vec<_> = sectors
                 .iter()
                 .filter_map(|s| {
                     let name = s.get("sector")?.as_str()?;
-                    let perf_1d = s.get("change_1d_pct").and_then(serde_json::Value::as_f64).unwrap_or(0.0);
-                    let perf_1m = s.get("change_1m_pct").and_then(serde_json::Value::as_f64).unwrap_or(0.0);
-                    let perf_3m = s.get("change_3m_pct").and_then(serde_json::Value::as_f64).unwrap_or(0.0);
-                    
+                    let perf_1d = s
+                        .get("change_1d_pct")
+                        .and_then(serde_json::Value::as_f64)
+                        .unwrap_or(0.0);
+                    let perf_1m = s
+                        .get("change_1m_pct")
+                        .and_then(serde_json::Value::as_f64)
+                        .unwrap_or(0.0);
+                    let perf_3m = s
+                        .get("change_3m_pct")
+                        .and_then(serde_json::Value::as_f64)
+                        .unwrap_or(0.0);
+
                     // Composite score (weighted)
                     let score = perf_1d * 0.2 + perf_1m * 0.4 + perf_3m * 0.4;
-                    
+
                     Some(json!({
                         "sector": name,
                         "score": score,
@@ -626,9 +675,17 @@ impl SectorAnalysisTool {
                 .collect();
 
             rankings.sort_by(|a, b| {
-                let a_score = a.get("score").and_then(serde_json::Value::as_f64).unwrap_or(0.0);
-                let b_score = b.get("score").and_then(serde_json::Value::as_f64).unwrap_or(0.0);
-                b_score.partial_cmp(&a_score).unwrap_or(std::cmp::Ordering::Equal)
+                let a_score = a
+                    .get("score")
+                    .and_then(serde_json::Value::as_f64)
+                    .unwrap_or(0.0);
+                let b_score = b
+                    .get("score")
+                    .and_then(serde_json::Value::as_f64)
+                    .unwrap_or(0.0);
+                b_score
+                    .partial_cmp(&a_score)
+                    .unwrap_or(std::cmp::Ordering::Equal)
             });
 
             json!(rankings)
@@ -641,9 +698,8 @@ impl SectorAnalysisTool {
 #[async_trait]
 impl Tool for SectorAnalysisTool {
     async fn execute(&self, params: Value) -> AgentResult<Value> {
-        let params: SectorParams = serde_json::from_value(params).map_err(|e| {
-            agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}"))
-        })?;
+        let params: SectorParams = serde_json::from_value(params)
+            .map_err(|e| agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}")))?;
 
         self.fetch_sector_data(params)
             .await
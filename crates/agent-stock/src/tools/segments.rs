@@ -0,0 +1,216 @@
+//! Tool for revenue by segment, region and product
+//!
+//! Answers questions like "how much of AAPL's revenue comes from China" from
+//! the dimensional revenue facts of the latest 10-K, so the numbers are the
+//! company's own disclosures rather than recollection.
+
+use agent_core::Result as AgentResult;
+use agent_tools::Tool;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+use crate::api::{BreakdownAxis, RevenueBreakdown, SecEdgarClient};
+use crate::cache::{CacheKey, StockCache};
+use crate::config::StockConfig;
+use crate::error::{Result, StockError};
+use crate::tools::earnings::format_currency;
+
+/// One line per slice, e.g. "Greater China: $72.56B (18.9% of revenue, -2.2% YoY)"
+pub fn summarize(breakdown: &RevenueBreakdown) -> Vec<String> {
+    breakdown
+        .slices
+        .iter()
+        .map(|slice| {
+            let details: Vec<String> = [
+                slice
+                    .share_pct
+                    .map(|share| format!("{share:.1}% of revenue")),
+                slice.growth_pct.map(|growth| format!("{growth:+.1}% YoY")),
+            ]
+            .into_iter()
+            .flatten()
+            .collect();
+            if details.is_empty() {
+                format!("{}: {}", slice.name, format_currency(slice.revenue))
+            } else {
+                format!(
+                    "{}: {} ({})",
+                    slice.name,
+                    format_currency(slice.revenue),
+                    details.join(", ")
+                )
+            }
+        })
+        .collect()
+}
+
+/// Parameters for a revenue breakdown request
+#[derive(Debug, Deserialize)]
+struct RevenueBreakdownParams {
+    symbol: String,
+    /// "segment", "geographic" or "product"; all when omitted
+    #[serde(default)]
+    breakdown: Option<String>,
+}
+
+/// Tool for segment, geographic and product revenue from SEC filings
+pub struct RevenueBreakdownTool {
+    sec_client: SecEdgarClient,
+    cache: StockCache,
+}
+
+impl RevenueBreakdownTool {
+    /// Create a new revenue breakdown tool
+    pub fn new(config: Arc<StockConfig>, cache: StockCache) -> Self {
+        let sec_client = SecEdgarClient::new(&config.sec_user_agent, &config.sec_contact_email);
+
+        Self { sec_client, cache }
+    }
+
+    /// Fetch the breakdowns of the latest 10-K
+    async fn fetch_breakdowns(&self, params: RevenueBreakdownParams) -> Result<Value> {
+        let symbol = params.symbol.to_uppercase();
+        let axis = params
+            .breakdown
+            .as_deref()
+            .filter(|breakdown| !breakdown.trim().is_empty() && *breakdown != "all")
+            .map(str::parse::<BreakdownAxis>)
+            .transpose()
+            .map_err(StockError::ApiError)?;
+
+        let cache_key = CacheKey::new(&symbol, "revenue_breakdown", json!({}));
+        let result = self
+            .cache
+            .get_or_fetch(cache_key, || async {
+                let (filing, breakdowns) = self.sec_client.get_revenue_breakdowns(&symbol).await?;
+                let cik = self.sec_client.resolve_cik(&symbol).await?;
+
+                Ok::<_, StockError>(json!({
+                    "symbol": symbol,
+                    "filing": {
+                        "form": filing.form_type,
+                        "filing_date": filing.filing_date,
+                        "period_end": filing.report_date,
+                        "url": self.sec_client.get_filing_url(
+                            &cik,
+                            &filing.accession_number,
+                            &filing.primary_document,
+                        ),
+                    },
+                    "breakdowns": breakdowns
+                        .iter()
+                        .map(|breakdown| json!({
+                            "axis": breakdown.axis,
+                            "period_start": breakdown.period_start,
+                            "period_end": breakdown.period_end,
+                            "total_revenue": breakdown.total_revenue,
+                            "total_revenue_formatted": breakdown.total_revenue.map(format_currency),
+                            "slices": breakdown.slices,
+                            "summary": summarize(breakdown),
+                        }))
+                        .collect::<Vec<_>>(),
+                    "data_source": "SEC EDGAR 10-K (inline XBRL)",
+                }))
+            })
+            .await?;
+
+        Ok(filter_axis(result, axis))
+    }
+}
+
+/// Keep only the breakdown along `axis`, and say so when the filing has none
+fn filter_axis(mut result: Value, axis: Option<BreakdownAxis>) -> Value {
+    if let (Some(axis), Some(breakdowns)) = (axis, result["breakdowns"].as_array_mut()) {
+        breakdowns.retain(|breakdown| breakdown["axis"] == axis.as_str());
+    }
+    if result["breakdowns"].as_array().is_some_and(Vec::is_empty) {
+        result["note"] = json!(
+            "The latest 10-K does not tag revenue by this breakdown; check the segment \
+             note of the filing text instead."
+        );
+    }
+    result
+}
+
+#[async_trait]
+impl Tool for RevenueBreakdownTool {
+    async fn execute(&self, params: Value) -> AgentResult<Value> {
+        let params: RevenueBreakdownParams = serde_json::from_value(params)
+            .map_err(|e| agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}")))?;
+
+        self.fetch_breakdowns(params)
+            .await
+            .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))
+    }
+
+    fn name(&self) -> &'static str {
+        "revenue_breakdown"
+    }
+
+    fn description(&self) -> &'static str {
+        "Revenue by business segment, geography (country or region) and product line \
+         from the company's latest 10-K, with each part's share of total revenue and \
+         year-over-year growth. Use it for questions like how much revenue comes from \
+         China or which segment grows fastest."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "symbol": {
+                    "type": "string",
+                    "description": "Stock ticker symbol (e.g., AAPL, MSFT)"
+                },
+                "breakdown": {
+                    "type": "string",
+                    "enum": ["segment", "geographic", "product", "all"],
+                    "description": "Which breakdown to return (default: all)"
+                }
+            },
+            "required": ["symbol"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::segments::revenue_breakdowns;
+    use crate::api::testing::fixtures;
+
+    #[test]
+    fn test_summarize() {
+        let breakdowns = revenue_breakdowns(fixtures::SEC_10K_AAPL_SEGMENTS);
+        let segments = breakdowns
+            .iter()
+            .find(|b| b.axis == BreakdownAxis::Segment)
+            .unwrap();
+
+        let lines = summarize(segments);
+        assert_eq!(lines[0], "Americas: $162.56B (42.4% of revenue, -4.2% YoY)");
+        assert!(
+            lines.contains(&"Greater China: $72.56B (18.9% of revenue, -2.2% YoY)".to_string())
+        );
+
+        let result = filter_axis(
+            json!({ "breakdowns": [{ "axis": "segment" }, { "axis": "product" }] }),
+            Some(BreakdownAxis::Geographic),
+        );
+        assert_eq!(result["breakdowns"], json!([]));
+        assert!(result["note"].is_string());
+    }
+
+    #[test]
+    fn test_tool_metadata() {
+        let config = Arc::new(StockConfig::default());
+        let cache = StockCache::new(std::time::Duration::from_secs(3600));
+        let tool = RevenueBreakdownTool::new(config, cache);
+
+        assert_eq!(tool.name(), "revenue_breakdown");
+        assert!(tool.description().contains("10-K"));
+        assert_eq!(tool.input_schema()["required"], json!(["symbol"]));
+    }
+}
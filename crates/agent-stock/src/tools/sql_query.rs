@@ -0,0 +1,655 @@
+//! Tool for read-only SQL over collected data
+//!
+//! Answers analytical questions no bespoke tool covers ("average RSI of my
+//! holdings by sector last month", "days NVDA news turned negative") by
+//! loading what the bot has collected into an in-memory SQLite database and
+//! running one `SELECT` against it:
+//!
+//! | table       | columns |
+//! |-------------|---------|
+//! | `prices`    | symbol, date, open, high, low, close, volume, rsi_14 |
+//! | `companies` | symbol, sector, industry |
+//! | `news`      | symbol, date, published_at, title, source, provider, sentiment_score, url |
+//! | `portfolio` | symbol, quantity, cost_basis, opened_at |
+//!
+//! `news` holds the whole [`NewsArchive`] and `portfolio` the requesting
+//! user's positions. Daily bars and profiles are fetched (through the cache)
+//! for the requested symbols and the user's holdings. Dates are ISO 8601
+//! text, so SQLite's date functions apply.
+
+use agent_core::Result as AgentResult;
+use agent_tools::Tool;
+use async_trait::async_trait;
+use chrono::{Duration as ChronoDuration, Utc};
+use futures::stream::{self, StreamExt};
+use rusqlite::limits::Limit;
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, params};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use ta::{Next, indicators::RelativeStrengthIndex};
+
+use crate::api::yahoo::Quote;
+use crate::api::{AssetProfile, YahooFinanceClient};
+use crate::cache::{CacheKey, StockCache};
+use crate::error::{Result, StockError};
+use crate::news_archive::{ArchivedArticle, NewsArchive};
+use crate::portfolio::{PortfolioStore, Position, USER_ID_PARAM};
+use crate::screener::RSI_PERIOD;
+
+/// Rows returned; the rest are reported as truncated
+const MAX_ROWS: usize = 200;
+
+/// Symbols loaded into `prices` and `companies` per query
+const MAX_SYMBOLS: usize = 25;
+
+/// Days of daily bars loaded by default and at most
+const DEFAULT_DAYS: u32 = 90;
+const MAX_DAYS: u32 = 730;
+
+/// Queries running longer than this are aborted
+const QUERY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Symbols fetched at once
+const MAX_CONCURRENT_FETCHES: usize = 4;
+
+const SCHEMA: &str = "
+    CREATE TABLE prices (
+        symbol TEXT NOT NULL, date TEXT NOT NULL, open REAL, high REAL, low REAL,
+        close REAL, volume INTEGER, rsi_14 REAL
+    );
+    CREATE TABLE companies (symbol TEXT NOT NULL, sector TEXT, industry TEXT);
+    CREATE TABLE news (
+        symbol TEXT NOT NULL, date TEXT NOT NULL, published_at TEXT, title TEXT,
+        source TEXT, provider TEXT, sentiment_score REAL, url TEXT
+    );
+    CREATE TABLE portfolio (
+        symbol TEXT NOT NULL, quantity REAL, cost_basis REAL, opened_at TEXT
+    );
+";
+
+#[derive(Debug, Deserialize)]
+struct SqlParams {
+    /// Whose positions fill `portfolio`, pinned by the caller (see
+    /// [`USER_ID_PARAM`]); without one the table is empty
+    #[serde(default)]
+    user_id: Option<String>,
+    query: String,
+    #[serde(default)]
+    symbols: Vec<String>,
+    #[serde(default)]
+    days: Option<u32>,
+}
+
+/// Data loaded into the database for one query
+#[derive(Debug, Default)]
+struct Dataset {
+    prices: Vec<(String, Vec<Quote>)>,
+    companies: Vec<AssetProfile>,
+    news: Vec<(String, ArchivedArticle)>,
+    positions: Vec<Position>,
+}
+
+/// Rows returned by a query
+#[derive(Debug, PartialEq)]
+struct QueryResult {
+    columns: Vec<String>,
+    rows: Vec<Vec<Value>>,
+    truncated: bool,
+}
+
+/// Tool running read-only SQL over prices, news sentiment and portfolios
+pub struct SqlQueryTool {
+    portfolio: Arc<PortfolioStore>,
+    archive: Arc<NewsArchive>,
+    yahoo_client: YahooFinanceClient,
+    cache: StockCache,
+}
+
+impl SqlQueryTool {
+    /// Create a tool over the positions in `portfolio` and the shared news
+    /// archive; bars and profiles are cached in `cache`
+    pub fn new(portfolio: Arc<PortfolioStore>, cache: StockCache) -> Self {
+        Self {
+            portfolio,
+            archive: NewsArchive::shared(),
+            yahoo_client: YahooFinanceClient::new(),
+            cache,
+        }
+    }
+
+    /// Read articles from `archive` instead of the shared one
+    pub fn with_archive(mut self, archive: Arc<NewsArchive>) -> Self {
+        self.archive = archive;
+        self
+    }
+
+    /// Use this client, e.g. pointed at a mock server
+    pub fn with_client(mut self, yahoo: YahooFinanceClient) -> Self {
+        self.yahoo_client = yahoo;
+        self
+    }
+
+    /// Run `query` for `user_id`, loading `symbols` besides the user's
+    /// holdings and `days` of daily bars (90 by default)
+    pub async fn run(
+        &self,
+        user_id: &str,
+        query: &str,
+        symbols: &[String],
+        days: Option<u32>,
+    ) -> Result<Value> {
+        self.query(SqlParams {
+            user_id: Some(user_id.to_string()),
+            query: query.to_string(),
+            symbols: symbols.to_vec(),
+            days,
+        })
+        .await
+    }
+
+    async fn query(&self, params: SqlParams) -> Result<Value> {
+        if params.query.trim().is_empty() {
+            return Err(StockError::CommandError("Empty SQL query".to_string()));
+        }
+        let days = params.days.unwrap_or(DEFAULT_DAYS).clamp(1, MAX_DAYS);
+        let positions = params
+            .user_id
+            .as_deref()
+            .map(|user_id| self.portfolio.positions(user_id))
+            .unwrap_or_default();
+
+        let mut symbols: Vec<String> = Vec::new();
+        let requested = params.symbols.iter().map(|s| s.trim().to_uppercase());
+        for symbol in requested.chain(positions.iter().map(|p| p.symbol.clone())) {
+            if !symbol.is_empty() && !symbols.contains(&symbol) {
+                symbols.push(symbol);
+            }
+        }
+        let skipped = symbols.split_off(symbols.len().min(MAX_SYMBOLS));
+
+        let fetches: Vec<_> = symbols
+            .iter()
+            .map(|symbol| async move {
+                let prices = self.prices(symbol, days).await;
+                let profile = self.profile(symbol).await;
+                (symbol, prices, profile)
+            })
+            .collect();
+        let fetched: Vec<_> = stream::iter(fetches)
+            .buffered(MAX_CONCURRENT_FETCHES)
+            .collect()
+            .await;
+
+        let mut data = Dataset {
+            positions,
+            ..Dataset::default()
+        };
+        let mut failed = Vec::new();
+        for (symbol, prices, profile) in fetched {
+            match prices {
+                Ok(quotes) => data.prices.push((symbol.clone(), quotes)),
+                Err(e) => {
+                    tracing::debug!(symbol = %symbol, error = %e, "SQL tool skipped prices");
+                    failed.push(symbol.clone());
+                }
+            }
+            // Funds and unknown symbols simply have no company row
+            if let Ok(profile) = profile {
+                data.companies.push(profile);
+            }
+        }
+        for symbol in self.archive.symbols() {
+            let articles = self.archive.articles(&symbol);
+            data.news.extend(
+                articles
+                    .into_iter()
+                    .map(|article| (symbol.clone(), article)),
+            );
+        }
+
+        let sql = params.query;
+        let result = tokio::task::spawn_blocking(move || run_query(&data, &sql))
+            .await
+            .map_err(|e| StockError::Other(format!("SQL query failed: {e}")))??;
+
+        Ok(json!({
+            "columns": result.columns,
+            "rows": result.rows,
+            "row_count": result.rows.len(),
+            "truncated": result.truncated,
+            "price_days": days,
+            "symbols_loaded": symbols.iter().filter(|s| !failed.contains(s)).collect::<Vec<_>>(),
+            "symbols_failed": failed,
+            "symbols_skipped": skipped,
+        }))
+    }
+
+    /// Daily bars of `symbol` over the last `days` days
+    async fn prices(&self, symbol: &str, days: u32) -> Result<Vec<Quote>> {
+        let key = CacheKey::new(symbol, "sql_prices", json!({ "days": days }));
+        let value = self
+            .cache
+            .get_or_fetch(key, || async {
+                let end = Utc::now();
+                let start = end - ChronoDuration::days(i64::from(days));
+                let quotes = self
+                    .yahoo_client
+                    .get_historical_quotes(symbol, start, end)
+                    .await?;
+                Ok::<_, StockError>(serde_json::to_value(quotes)?)
+            })
+            .await?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Sector and industry of `symbol`
+    async fn profile(&self, symbol: &str) -> Result<AssetProfile> {
+        let key = CacheKey::new(symbol, "asset_profile", json!({}));
+        let value = self
+            .cache
+            .get_or_fetch(key, || async {
+                let profile = self.yahoo_client.get_asset_profile(symbol).await?;
+                Ok::<_, StockError>(serde_json::to_value(profile)?)
+            })
+            .await?;
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+/// Load `data` into a fresh database and run `sql` against it read-only
+fn run_query(data: &Dataset, sql: &str) -> Result<QueryResult> {
+    let mut db = open_database()?;
+    load(&mut db, data).map_err(sql_error)?;
+    db.pragma_update(None, "query_only", true)
+        .map_err(sql_error)?;
+
+    let started = Instant::now();
+    db.progress_handler(10_000, Some(move || started.elapsed() > QUERY_TIMEOUT));
+
+    let mut statement = prepare_read_only(&db, sql)?;
+    let columns: Vec<String> = statement
+        .column_names()
+        .into_iter()
+        .map(ToString::to_string)
+        .collect();
+
+    let mut rows = Vec::new();
+    let mut truncated = false;
+    let mut results = statement.query([]).map_err(sql_error)?;
+    while let Some(row) = results.next().map_err(sql_error)? {
+        if rows.len() == MAX_ROWS {
+            truncated = true;
+            break;
+        }
+        let values = (0..columns.len())
+            .map(|i| row.get_ref(i).map(json_value))
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(sql_error)?;
+        rows.push(values);
+    }
+
+    Ok(QueryResult {
+        columns,
+        rows,
+        truncated,
+    })
+}
+
+/// Check that `sql` is a single read-only statement valid against the
+/// tool's tables, without running it
+pub fn validate(sql: &str) -> Result<()> {
+    let db = open_database()?;
+    prepare_read_only(&db, sql).map(drop)
+}
+
+/// A fresh in-memory database with the tool's tables
+///
+/// SQLite counts `ATTACH` as read-only, yet it would open or create any
+/// file the process can reach, so no database may be attached.
+fn open_database() -> Result<Connection> {
+    let db = Connection::open_in_memory().map_err(sql_error)?;
+    db.set_limit(Limit::SQLITE_LIMIT_ATTACHED, 0);
+    db.execute_batch(SCHEMA).map_err(sql_error)?;
+    Ok(db)
+}
+
+/// Compile `sql`, rejecting anything but one read-only statement
+fn prepare_read_only<'db>(db: &'db Connection, sql: &str) -> Result<rusqlite::Statement<'db>> {
+    if sql.trim().is_empty() {
+        return Err(StockError::CommandError("Empty SQL query".to_string()));
+    }
+    if !single_statement(sql) {
+        return Err(StockError::CommandError(
+            "Only a single SQL statement is allowed".to_string(),
+        ));
+    }
+    let statement = db.prepare(sql).map_err(sql_error)?;
+    if !statement.readonly() {
+        return Err(StockError::CommandError(
+            "Only read-only queries (SELECT) are allowed".to_string(),
+        ));
+    }
+    Ok(statement)
+}
+
+/// Whether `sql` holds one statement, optionally ending in `;`
+///
+/// SQLite only compiles the first statement, so anything after it would be
+/// silently ignored.
+fn single_statement(sql: &str) -> bool {
+    let mut quote = None;
+    let mut chars = sql.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"' | '`') => quote = Some(c),
+            (None, '[') => quote = Some(']'),
+            (None, '-') if chars.peek() == Some(&'-') => {
+                // Comment to the end of the line
+                chars.by_ref().take_while(|&c| c != '\n').for_each(drop);
+            }
+            (None, ';') => {
+                return chars.all(|c| c.is_whitespace() || c == ';');
+            }
+            (None, _) => {}
+        }
+    }
+    true
+}
+
+/// Insert every row of `data`
+fn load(db: &mut Connection, data: &Dataset) -> rusqlite::Result<()> {
+    let tx = db.transaction()?;
+    {
+        let mut insert = tx.prepare(
+            "INSERT INTO prices (symbol, date, open, high, low, close, volume, rsi_14)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )?;
+        for (symbol, quotes) in &data.prices {
+            let mut rsi = RelativeStrengthIndex::new(RSI_PERIOD).ok();
+            for (i, quote) in quotes.iter().enumerate() {
+                let value = rsi.as_mut().map(|rsi| rsi.next(quote.close));
+                // The first values only average a partial period
+                let value = value.filter(|_| i >= RSI_PERIOD);
+                insert.execute(params![
+                    symbol,
+                    quote.timestamp.date_naive().to_string(),
+                    quote.open,
+                    quote.high,
+                    quote.low,
+                    quote.close,
+                    i64::try_from(quote.volume).unwrap_or(i64::MAX),
+                    value,
+                ])?;
+            }
+        }
+
+        let mut insert =
+            tx.prepare("INSERT INTO companies (symbol, sector, industry) VALUES (?1, ?2, ?3)")?;
+        for profile in &data.companies {
+            insert.execute(params![profile.symbol, profile.sector, profile.industry])?;
+        }
+
+        let mut insert = tx.prepare(
+            "INSERT INTO news (symbol, date, published_at, title, source, provider,
+                               sentiment_score, url)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )?;
+        for (symbol, article) in &data.news {
+            insert.execute(params![
+                symbol,
+                article.date().to_string(),
+                article.published_at.map(|at| at.to_rfc3339()),
+                article.title,
+                article.source,
+                article.provider,
+                article.sentiment_score,
+                article.url,
+            ])?;
+        }
+
+        let mut insert = tx.prepare(
+            "INSERT INTO portfolio (symbol, quantity, cost_basis, opened_at)
+             VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for position in &data.positions {
+            insert.execute(params![
+                position.symbol,
+                position.quantity,
+                position.cost_basis,
+                position.opened_at.to_rfc3339(),
+            ])?;
+        }
+    }
+    tx.commit()
+}
+
+fn json_value(value: ValueRef<'_>) -> Value {
+    match value {
+        ValueRef::Null | ValueRef::Blob(_) => Value::Null,
+        ValueRef::Integer(i) => json!(i),
+        ValueRef::Real(f) => json!(f),
+        ValueRef::Text(text) => json!(String::from_utf8_lossy(text)),
+    }
+}
+
+fn sql_error(error: rusqlite::Error) -> StockError {
+    StockError::CommandError(format!("SQL error: {error}"))
+}
+
+#[async_trait]
+impl Tool for SqlQueryTool {
+    async fn execute(&self, params: Value) -> AgentResult<Value> {
+        let params: SqlParams = serde_json::from_value(params)
+            .map_err(|e| agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}")))?;
+
+        self.query(params)
+            .await
+            .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))
+    }
+
+    fn name(&self) -> &'static str {
+        "query_data"
+    }
+
+    fn description(&self) -> &'static str {
+        "Run one read-only SQLite SELECT over collected data for analytical questions \
+         other tools don't answer directly (averages, rankings, groupings, comparisons \
+         over time). Tables: prices(symbol, date, open, high, low, close, volume, rsi_14) \
+         with daily bars of the requested symbols and the user's holdings; \
+         companies(symbol, sector, industry); news(symbol, date, published_at, title, \
+         source, provider, sentiment_score, url) with archived articles and sentiment \
+         from -1 to 1; portfolio(symbol, quantity, cost_basis, opened_at) with the user's \
+         positions. Dates are 'YYYY-MM-DD' text. At most 200 rows are returned."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "A single SQLite SELECT (or WITH ... SELECT) statement"
+                },
+                "symbols": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Symbols to load into prices and companies besides the user's holdings (at most 25 in total)"
+                },
+                "days": {
+                    "type": "integer",
+                    "description": "Days of daily bars to load (default 90, max 730)"
+                }
+            },
+            "required": ["query"]
+        })
+    }
+
+    fn caller_params(&self) -> &[&'static str] {
+        &[USER_ID_PARAM]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::testing::MockApi;
+    use crate::news::NewsItem;
+
+    fn tool(api: &MockApi) -> SqlQueryTool {
+        let portfolio = Arc::new(PortfolioStore::in_memory());
+        portfolio.add("42", "AAPL", 10.0, 150.0).unwrap();
+        let archive = Arc::new(NewsArchive::in_memory());
+        let item = NewsItem {
+            title: "Apple beats estimates".to_string(),
+            source: "Reuters".to_string(),
+            published_at: Some(Utc::now()),
+            summary: String::new(),
+            url: "https://example.com/apple-beats".to_string(),
+            image: None,
+            category: None,
+            sentiment_score: Some(0.6),
+            topics: Vec::new(),
+            ticker_sentiment: Vec::new(),
+            provider: "Mock",
+        };
+        archive.record("AAPL", &[item]).unwrap();
+
+        SqlQueryTool::new(portfolio, StockCache::new(Duration::from_secs(60)))
+            .with_archive(archive)
+            .with_client(api.yahoo())
+    }
+
+    async fn mount_profile(api: &MockApi) {
+        api.mount_json(
+            "/v10/finance/quoteSummary/AAPL",
+            200,
+            r#"{"quoteSummary":{"result":[{"assetProfile":{
+                "sector":"Technology","industry":"Consumer Electronics"}}]}}"#,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_joins_prices_holdings_and_news() {
+        let api = MockApi::recorded().await;
+        mount_profile(&api).await;
+        let tool = tool(&api);
+
+        let result = tool
+            .execute(json!({
+                "user_id": "42",
+                "query": "SELECT c.sector, p.quantity, \
+                          (SELECT close FROM prices ORDER BY date DESC LIMIT 1) AS last_close, \
+                          (SELECT AVG(sentiment_score) FROM news WHERE symbol = c.symbol) AS sentiment \
+                          FROM portfolio p JOIN companies c USING (symbol)"
+            }))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            result["columns"],
+            json!(["sector", "quantity", "last_close", "sentiment"])
+        );
+        assert_eq!(result["rows"], json!([["Technology", 10.0, 171.13, 0.6]]));
+        assert_eq!(result["symbols_loaded"], json!(["AAPL"]));
+        assert_eq!(result["truncated"], false);
+    }
+
+    #[tokio::test]
+    async fn test_only_reads_are_allowed() {
+        let api = MockApi::recorded().await;
+        mount_profile(&api).await;
+        let tool = tool(&api);
+
+        for query in [
+            "DELETE FROM portfolio",
+            "DROP TABLE news",
+            "SELECT 1; DELETE FROM portfolio",
+            "SELECT * FROM missing_table",
+            "ATTACH DATABASE '/tmp/stock-sql-attach.db' AS other",
+            "",
+        ] {
+            let result = tool
+                .execute(json!({ "user_id": "42", "query": query }))
+                .await;
+            assert!(result.is_err(), "{query}");
+        }
+
+        // Other users' positions are not loaded
+        let result = tool
+            .execute(json!({ "user_id": "7", "query": "SELECT COUNT(*) FROM portfolio" }))
+            .await
+            .unwrap();
+        assert_eq!(result["rows"], json!([[0]]));
+
+        // The model is not asked whose portfolio to load
+        assert!(
+            tool.input_schema()["properties"]
+                .get(USER_ID_PARAM)
+                .is_none()
+        );
+        assert_eq!(tool.caller_params(), [USER_ID_PARAM]);
+        let result = tool
+            .execute(json!({ "query": "SELECT COUNT(*) FROM portfolio" }))
+            .await
+            .unwrap();
+        assert_eq!(result["rows"], json!([[0]]));
+    }
+
+    #[test]
+    fn test_rsi_needs_a_full_period() {
+        let quotes: Vec<Quote> = (0..20)
+            .map(|day| Quote {
+                symbol: "AAPL".to_string(),
+                timestamp: Utc::now() - ChronoDuration::days(20 - day),
+                open: 100.0,
+                high: 101.0,
+                low: 99.0,
+                close: 100.0 + (day % 3) as f64,
+                volume: 1_000,
+                adjclose: 100.0,
+            })
+            .collect();
+        let data = Dataset {
+            prices: vec![("AAPL".to_string(), quotes)],
+            ..Dataset::default()
+        };
+
+        let result = run_query(&data, "SELECT COUNT(*), COUNT(rsi_14) FROM prices").unwrap();
+        assert_eq!(result.rows, vec![vec![json!(20), json!(20 - RSI_PERIOD)]]);
+    }
+
+    #[test]
+    fn test_single_statement() {
+        assert!(single_statement("SELECT 1"));
+        assert!(single_statement("SELECT ';' AS x; \n "));
+        assert!(single_statement("SELECT \"a;b\" FROM t -- done; really"));
+        assert!(!single_statement("SELECT 1; DELETE FROM portfolio"));
+    }
+
+    #[test]
+    fn test_rows_are_capped() {
+        let result = run_query(
+            &Dataset::default(),
+            "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n LIMIT 500) \
+             SELECT i FROM n",
+        )
+        .unwrap();
+        assert_eq!(result.rows.len(), MAX_ROWS);
+        assert!(result.truncated);
+
+        // Runaway queries are interrupted
+        let err = run_query(
+            &Dataset::default(),
+            "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n) \
+             SELECT COUNT(*) FROM n",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("interrupted"), "{err}");
+    }
+}
@@ -1,20 +1,31 @@
 //! Tool for fetching stock price data
+//!
+//! Quotes come from Yahoo Finance. When Yahoo Finance is down or its circuit
+//! is open and an Alpha Vantage key is configured, the current quote falls
+//! back to the latest Alpha Vantage daily bar; when Yahoo Finance is merely
+//! slow, the Alpha Vantage request is hedged (see [`crate::api::latency`]).
 
 use agent_core::Result as AgentResult;
 use agent_tools::Tool;
 use async_trait::async_trait;
+use chrono::NaiveDate;
 use serde::Deserialize;
 use serde_json::{Value, json};
 use std::sync::Arc;
 
-use crate::api::YahooFinanceClient;
+use crate::api::yahoo::Quote;
+use crate::api::{AlphaVantageClient, YahooFinanceClient, hedge};
+use crate::as_of;
 use crate::cache::{CacheKey, StockCache};
 use crate::config::StockConfig;
-use crate::error::Result;
+use crate::crypto::{self, CryptoPair};
+use crate::error::{Result, StockError};
 
 /// Tool for fetching stock price and quote data
 pub struct StockDataTool {
     yahoo_client: YahooFinanceClient,
+    /// Fallback for current quotes while Yahoo Finance is unavailable
+    alpha_vantage: Option<AlphaVantageClient>,
     cache: StockCache,
     _config: Arc<StockConfig>,
 }
@@ -26,6 +37,9 @@ struct StockDataParams {
     range: Option<String>,
     #[serde(default)]
     include_historical: Option<bool>,
+    /// Only data known at the end of this day
+    #[serde(default)]
+    as_of: Option<NaiveDate>,
 }
 
 impl StockDataTool {
@@ -33,14 +47,93 @@ impl StockDataTool {
     pub fn new(config: Arc<StockConfig>, cache: StockCache) -> Self {
         Self {
             yahoo_client: YahooFinanceClient::new(),
+            alpha_vantage: AlphaVantageClient::from_config(&config),
             cache,
             _config: config,
         }
     }
 
+    /// Use these clients instead of the configured ones
+    pub fn with_clients(
+        mut self,
+        yahoo_client: YahooFinanceClient,
+        alpha_vantage: Option<AlphaVantageClient>,
+    ) -> Self {
+        self.yahoo_client = yahoo_client;
+        self.alpha_vantage = alpha_vantage;
+        self
+    }
+
+    /// Current quote and the provider it came from
+    ///
+    /// Falls back to Alpha Vantage when Yahoo Finance is unavailable (an
+    /// unknown symbol is reported as is) and hedges with it when Yahoo
+    /// Finance is slower than its p95 latency. Crypto pairs have no Alpha
+    /// Vantage equivalent and never fall back.
+    async fn current_quote(&self, symbol: &str) -> Result<(Quote, &'static str)> {
+        let yahoo = async {
+            let quote = self.yahoo_client.get_quote(symbol).await?;
+            Ok((quote, "Yahoo Finance"))
+        };
+        let alpha_vantage = match &self.alpha_vantage {
+            Some(client) if CryptoPair::parse(symbol).is_none() => client,
+            _ => return yahoo.await,
+        };
+
+        let delay = self.yahoo_client.breaker().latency().p95();
+        hedge(yahoo, delay, || async {
+            let quote = Self::alpha_vantage_quote(alpha_vantage, symbol).await?;
+            Ok((quote, "Alpha Vantage"))
+        })
+        .await
+    }
+
+    /// Last daily bar on or before `date`, as the quote of that day
+    async fn quote_as_of(&self, symbol: &str, date: NaiveDate) -> Result<Quote> {
+        // Look back far enough to cover weekends and holidays
+        let end = as_of::cutoff(date);
+        let quotes = self
+            .yahoo_client
+            .get_historical_quotes(symbol, end - chrono::Duration::days(10), end)
+            .await?;
+        quotes
+            .into_iter()
+            .filter(|quote| quote.timestamp <= end)
+            .max_by_key(|quote| quote.timestamp)
+            .ok_or_else(|| StockError::DataUnavailable {
+                symbol: symbol.to_string(),
+                reason: format!("no close on or before {date}"),
+            })
+    }
+
+    /// Quote built from the latest Alpha Vantage daily bar
+    async fn alpha_vantage_quote(client: &AlphaVantageClient, symbol: &str) -> Result<Quote> {
+        tracing::info!("Fetching the {symbol} quote from Alpha Vantage");
+        let mut bars = client.get_daily(symbol).await?;
+        bars.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+        let bar = bars.pop().ok_or_else(|| {
+            StockError::AlphaVantageError(format!("No daily data found for {symbol}"))
+        })?;
+        let timestamp = chrono::NaiveDate::parse_from_str(&bar.timestamp, "%Y-%m-%d")
+            .ok()
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .map_or_else(chrono::Utc::now, |time| time.and_utc());
+
+        Ok(Quote {
+            symbol: symbol.to_string(),
+            timestamp,
+            open: bar.open,
+            high: bar.high,
+            low: bar.low,
+            close: bar.close,
+            volume: bar.volume,
+            adjclose: bar.close,
+        })
+    }
+
     /// Fetch stock data with caching and retries
     async fn fetch_stock_data(&self, params: StockDataParams) -> Result<Value> {
-        let symbol = params.symbol.to_uppercase();
+        let symbol = crypto::normalize_symbol(&params.symbol);
         let range = params.range.unwrap_or_else(|| "1d".to_string());
         let include_historical = params.include_historical.unwrap_or(false);
 
@@ -48,18 +141,22 @@ impl StockDataTool {
         let cache_key = CacheKey::new(
             &symbol,
             "stock_data",
-            json!({ "range": &range, "historical": include_historical }),
+            json!({ "range": &range, "historical": include_historical, "as_of": params.as_of }),
         );
 
         // Try to get from cache
         let result = self
             .cache
             .get_or_fetch(cache_key, || async {
-                // Fetch current quote
-                let quote = self.yahoo_client.get_quote(&symbol).await?;
+                // Fetch the current quote, or the close of the as-of day
+                let (quote, provider) = match params.as_of {
+                    Some(date) => (self.quote_as_of(&symbol, date).await?, "Yahoo Finance"),
+                    None => self.current_quote(&symbol).await?,
+                };
 
                 let mut result = json!({
                     "symbol": symbol,
+                    "data_provider": provider,
                     "current_quote": {
                         "timestamp": quote.timestamp.to_rfc3339(),
                         "open": quote.open,
@@ -70,13 +167,35 @@ impl StockDataTool {
                         "adjusted_close": quote.adjclose,
                     }
                 });
+                if let Some(date) = params.as_of {
+                    result["as_of"] = json!(date.to_string());
+                }
+
+                // Crypto trades around the clock in its quote currency, and
+                // small coins need more than two decimals
+                if let Some(pair) = CryptoPair::parse(&symbol) {
+                    result["asset_class"] = json!("crypto");
+                    result["name"] = json!(pair.name());
+                    result["quote_currency"] = json!(pair.quote);
+                    result["trading_hours"] = json!("24/7");
+                    result["current_quote"]["close_formatted"] =
+                        json!(pair.format_price(quote.close));
+                }
 
                 // Fetch historical data if requested
                 if include_historical {
-                    let historical = self
-                        .yahoo_client
-                        .get_historical_range(&symbol, &range)
-                        .await?;
+                    let historical = match params.as_of {
+                        Some(date) => {
+                            self.yahoo_client
+                                .get_historical_range_at(&symbol, &range, as_of::cutoff(date))
+                                .await?
+                        }
+                        None => {
+                            self.yahoo_client
+                                .get_historical_range(&symbol, &range)
+                                .await?
+                        }
+                    };
 
                     let historical_data: Vec<_> = historical
                         .iter()
@@ -97,7 +216,7 @@ impl StockDataTool {
                     result["data_points"] = json!(historical_data.len());
                 }
 
-                Ok::<_, crate::error::StockError>(result)
+                Ok::<_, StockError>(result)
             })
             .await?;
 
@@ -108,9 +227,8 @@ impl StockDataTool {
 #[async_trait]
 impl Tool for StockDataTool {
     async fn execute(&self, params: Value) -> AgentResult<Value> {
-        let params: StockDataParams = serde_json::from_value(params).map_err(|e| {
-            agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}"))
-        })?;
+        let params: StockDataParams = serde_json::from_value(params)
+            .map_err(|e| agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}")))?;
 
         self.fetch_stock_data(params)
             .await
@@ -123,7 +241,8 @@ impl Tool for StockDataTool {
 
     fn description(&self) -> &'static str {
         "Fetch current and historical stock price data for a given symbol. \
-         Returns current quote and optionally historical prices over a specified range."
+         Returns current quote and optionally historical prices over a specified range. \
+         Crypto assets use pair symbols such as BTC-USD and ETH-USD."
     }
 
     fn input_schema(&self) -> Value {
@@ -132,7 +251,7 @@ impl Tool for StockDataTool {
             "properties": {
                 "symbol": {
                     "type": "string",
-                    "description": "Stock ticker symbol (e.g., 'AAPL', 'GOOGL')"
+                    "description": "Stock ticker symbol or crypto pair (e.g., 'AAPL', 'GOOGL', 'BTC-USD')"
                 },
                 "range": {
                     "type": "string",
@@ -144,7 +263,8 @@ impl Tool for StockDataTool {
                     "type": "boolean",
                     "description": "Whether to include historical price data",
                     "default": false
-                }
+                },
+                "as_of": as_of::param_schema()
             },
             "required": ["symbol"]
         })
@@ -154,8 +274,42 @@ impl Tool for StockDataTool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::api::testing::{MockApi, fixtures};
     use std::time::Duration;
 
+    fn tool(api: &MockApi, alpha_vantage: Option<AlphaVantageClient>) -> StockDataTool {
+        StockDataTool::new(
+            Arc::new(StockConfig::default()),
+            StockCache::new(Duration::from_secs(60)),
+        )
+        .with_clients(api.yahoo(), alpha_vantage)
+    }
+
+    /// Serve two Alpha Vantage daily bars, the latest closing at 173.03
+    async fn mount_daily(api: &MockApi) {
+        let body = json!({
+            "Time Series (Daily)": {
+                "2024-05-01": {
+                    "1. open": "169.58", "2. high": "172.71", "3. low": "169.11",
+                    "4. close": "169.30", "5. volume": "50383147"
+                },
+                "2024-05-02": {
+                    "1. open": "172.51", "2. high": "173.42", "3. low": "170.89",
+                    "4. close": "173.03", "5. volume": "94214915"
+                }
+            }
+        });
+        api.mount_json("/query", 200, &body.to_string()).await;
+    }
+
+    /// Open the Yahoo Finance circuit of `api`
+    fn trip_yahoo(api: &MockApi) {
+        let breaker = api.yahoo().breaker();
+        while breaker.status().state != crate::api::CircuitState::Open {
+            breaker.record_failure();
+        }
+    }
+
     #[test]
     fn test_tool_metadata() {
         let config = Arc::new(StockConfig::default());
@@ -211,4 +365,107 @@ mod tests {
         assert!(data["historical_data"].is_array());
         assert!(data["data_points"].as_u64().unwrap() > 0);
     }
+
+    #[tokio::test]
+    async fn test_quote_from_yahoo() {
+        let api = MockApi::recorded().await;
+        let result = tool(&api, Some(api.alpha_vantage(false)))
+            .execute(json!({ "symbol": "AAPL" }))
+            .await
+            .unwrap();
+        assert_eq!(result["data_provider"], "Yahoo Finance");
+        assert!((result["current_quote"]["close"].as_f64().unwrap() - 171.13).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_quote_as_of() {
+        let api = MockApi::recorded().await;
+        let tool = tool(&api, Some(api.alpha_vantage(false)));
+
+        // The 2024-03-13 bar had not happened yet
+        let result = tool
+            .execute(json!({ "symbol": "AAPL", "as_of": "2024-03-12", "include_historical": true }))
+            .await
+            .unwrap();
+        assert_eq!(result["as_of"], "2024-03-12");
+        assert!((result["current_quote"]["close"].as_f64().unwrap() - 173.23).abs() < 1e-9);
+        assert_eq!(result["data_points"], 2);
+
+        let error = tool
+            .execute(json!({ "symbol": "AAPL", "as_of": "2024-03-10" }))
+            .await
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("no close on or before 2024-03-10"),
+            "{error}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_open_circuit_falls_back_to_alpha_vantage() {
+        let api = MockApi::start().await;
+        mount_daily(&api).await;
+        trip_yahoo(&api);
+
+        let result = tool(&api, Some(api.alpha_vantage(false)))
+            .execute(json!({ "symbol": "AAPL" }))
+            .await
+            .unwrap();
+        assert_eq!(result["data_provider"], "Alpha Vantage");
+        assert!((result["current_quote"]["close"].as_f64().unwrap() - 173.03).abs() < 1e-9);
+        assert!(
+            result["current_quote"]["timestamp"]
+                .as_str()
+                .unwrap()
+                .starts_with("2024-05-02")
+        );
+
+        // Without a fallback the open circuit is reported
+        let error = tool(&api, None)
+            .execute(json!({ "symbol": "AAPL" }))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("unavailable"), "{error}");
+    }
+
+    #[tokio::test]
+    async fn test_slow_yahoo_is_hedged() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, ResponseTemplate};
+
+        let api = MockApi::start().await;
+        mount_daily(&api).await;
+        Mock::given(method("GET"))
+            .and(path("/v8/finance/chart/AAPL"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_raw(fixtures::YAHOO_CHART_AAPL, "application/json")
+                    .set_delay(Duration::from_secs(2)),
+            )
+            .mount(api.server())
+            .await;
+        // Yahoo Finance usually answers within 50ms
+        let breaker = api.yahoo().breaker();
+        for _ in 0..20 {
+            breaker.latency().record(Duration::from_millis(50));
+        }
+
+        let started = std::time::Instant::now();
+        let result = tool(&api, Some(api.alpha_vantage(false)))
+            .execute(json!({ "symbol": "AAPL" }))
+            .await
+            .unwrap();
+        assert_eq!(result["data_provider"], "Alpha Vantage");
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        // Without latency history there is nothing to hedge against
+        let api = MockApi::recorded().await;
+        let result = tool(&api, Some(api.alpha_vantage(false)))
+            .execute(json!({ "symbol": "AAPL" }))
+            .await
+            .unwrap();
+        assert_eq!(result["data_provider"], "Yahoo Finance");
+    }
 }
@@ -0,0 +1,561 @@
+//! Tool for mapping supplier and customer relationships
+//!
+//! Combines a curated set of well-known supply links with customer
+//! concentration disclosures extracted from the company's latest 10-K, so
+//! agents can trace second-order impacts ("TSMC export restrictions → AAPL
+//! exposure").
+
+use agent_core::Result as AgentResult;
+use agent_tools::Tool;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::fmt::Write as _;
+use std::sync::Arc;
+
+use crate::api::{FilingType, SecEdgarClient};
+use crate::cache::{CacheKey, StockCache};
+use crate::config::StockConfig;
+use crate::error::Result;
+
+/// A supplier → customer relationship
+#[derive(Debug, Clone, PartialEq)]
+pub struct SupplyLink {
+    /// Ticker of the supplier
+    pub supplier: &'static str,
+    /// Ticker of the customer
+    pub customer: &'static str,
+    /// What the supplier provides
+    pub product: &'static str,
+    /// Approximate share of the supplier's revenue from this customer, in percent
+    pub revenue_share: Option<f64>,
+    /// Fiscal year the revenue share refers to
+    pub as_of: Option<&'static str>,
+}
+
+/// Curated supply links between widely followed companies
+///
+/// Revenue shares are approximate and come from the suppliers' annual
+/// reports; links without a disclosed share leave it unset.
+pub const SUPPLY_LINKS: &[SupplyLink] = &[
+    SupplyLink {
+        supplier: "TSM",
+        customer: "AAPL",
+        product: "Chip foundry (A- and M-series processors)",
+        revenue_share: Some(25.0),
+        as_of: Some("FY2023"),
+    },
+    SupplyLink {
+        supplier: "TSM",
+        customer: "NVDA",
+        product: "Chip foundry (GPUs and AI accelerators)",
+        revenue_share: Some(11.0),
+        as_of: Some("FY2023"),
+    },
+    SupplyLink {
+        supplier: "TSM",
+        customer: "AMD",
+        product: "Chip foundry (CPUs and GPUs)",
+        revenue_share: None,
+        as_of: None,
+    },
+    SupplyLink {
+        supplier: "TSM",
+        customer: "QCOM",
+        product: "Chip foundry (Snapdragon SoCs)",
+        revenue_share: None,
+        as_of: None,
+    },
+    SupplyLink {
+        supplier: "TSM",
+        customer: "AVGO",
+        product: "Chip foundry (networking and custom ASICs)",
+        revenue_share: None,
+        as_of: None,
+    },
+    SupplyLink {
+        supplier: "ASML",
+        customer: "TSM",
+        product: "EUV and DUV lithography systems",
+        revenue_share: None,
+        as_of: None,
+    },
+    SupplyLink {
+        supplier: "ASML",
+        customer: "INTC",
+        product: "EUV and DUV lithography systems",
+        revenue_share: None,
+        as_of: None,
+    },
+    SupplyLink {
+        supplier: "AMAT",
+        customer: "TSM",
+        product: "Wafer fabrication equipment",
+        revenue_share: None,
+        as_of: None,
+    },
+    SupplyLink {
+        supplier: "LRCX",
+        customer: "TSM",
+        product: "Etch and deposition equipment",
+        revenue_share: None,
+        as_of: None,
+    },
+    SupplyLink {
+        supplier: "CRUS",
+        customer: "AAPL",
+        product: "Audio codecs and mixed-signal chips",
+        revenue_share: Some(87.0),
+        as_of: Some("FY2024"),
+    },
+    SupplyLink {
+        supplier: "SWKS",
+        customer: "AAPL",
+        product: "RF front-end modules",
+        revenue_share: Some(66.0),
+        as_of: Some("FY2023"),
+    },
+    SupplyLink {
+        supplier: "QRVO",
+        customer: "AAPL",
+        product: "RF front-end modules",
+        revenue_share: Some(46.0),
+        as_of: Some("FY2024"),
+    },
+    SupplyLink {
+        supplier: "AVGO",
+        customer: "AAPL",
+        product: "Wireless and RF components",
+        revenue_share: Some(20.0),
+        as_of: Some("FY2023"),
+    },
+    SupplyLink {
+        supplier: "QCOM",
+        customer: "AAPL",
+        product: "5G modems",
+        revenue_share: None,
+        as_of: None,
+    },
+    SupplyLink {
+        supplier: "GLW",
+        customer: "AAPL",
+        product: "Cover glass",
+        revenue_share: None,
+        as_of: None,
+    },
+    SupplyLink {
+        supplier: "2317.TW",
+        customer: "AAPL",
+        product: "iPhone assembly (Foxconn)",
+        revenue_share: None,
+        as_of: None,
+    },
+    SupplyLink {
+        supplier: "NVDA",
+        customer: "MSFT",
+        product: "AI accelerators (data center GPUs)",
+        revenue_share: None,
+        as_of: None,
+    },
+    SupplyLink {
+        supplier: "NVDA",
+        customer: "META",
+        product: "AI accelerators (data center GPUs)",
+        revenue_share: None,
+        as_of: None,
+    },
+    SupplyLink {
+        supplier: "NVDA",
+        customer: "AMZN",
+        product: "AI accelerators (data center GPUs)",
+        revenue_share: None,
+        as_of: None,
+    },
+    SupplyLink {
+        supplier: "NVDA",
+        customer: "GOOGL",
+        product: "AI accelerators (data center GPUs)",
+        revenue_share: None,
+        as_of: None,
+    },
+    SupplyLink {
+        supplier: "AMD",
+        customer: "MSFT",
+        product: "Xbox SoCs and Instinct GPUs",
+        revenue_share: None,
+        as_of: None,
+    },
+    SupplyLink {
+        supplier: "6752.T",
+        customer: "TSLA",
+        product: "EV battery cells (Panasonic)",
+        revenue_share: None,
+        as_of: None,
+    },
+    SupplyLink {
+        supplier: "300750.SZ",
+        customer: "TSLA",
+        product: "EV battery cells (CATL)",
+        revenue_share: None,
+        as_of: None,
+    },
+];
+
+/// Links where `symbol` is the customer
+pub fn suppliers_of(symbol: &str) -> Vec<&'static SupplyLink> {
+    SUPPLY_LINKS
+        .iter()
+        .filter(|link| link.customer.eq_ignore_ascii_case(symbol))
+        .collect()
+}
+
+/// Links where `symbol` is the supplier
+pub fn customers_of(symbol: &str) -> Vec<&'static SupplyLink> {
+    SUPPLY_LINKS
+        .iter()
+        .filter(|link| link.supplier.eq_ignore_ascii_case(symbol))
+        .collect()
+}
+
+/// A customer concentration statement found in a filing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConcentrationDisclosure {
+    /// Revenue share stated in the sentence, in percent
+    pub revenue_share: f64,
+    /// The sentence the share was found in
+    pub excerpt: String,
+}
+
+/// Longest excerpt kept per disclosure
+const MAX_EXCERPT_CHARS: usize = 300;
+
+/// Most disclosures kept per filing
+const MAX_DISCLOSURES: usize = 10;
+
+/// Extract customer concentration disclosures from a filing document
+///
+/// Looks for sentences such as "Apple accounted for 87% of net sales" in
+/// the document text. The customer name is left in the excerpt since
+/// filings often refer to customers anonymously ("Customer A").
+pub fn extract_concentration(document: &str) -> Vec<ConcentrationDisclosure> {
+    let text = strip_html(document);
+    let mut disclosures: Vec<ConcentrationDisclosure> = Vec::new();
+
+    for sentence in text.split(". ") {
+        let lower = sentence.to_lowercase();
+        let mentions_customer = lower.contains("customer")
+            || lower.contains("accounted for")
+            || lower.contains("represented");
+        let mentions_revenue = ["revenue", "net sales", "total sales"]
+            .iter()
+            .any(|term| lower.contains(term));
+        if !mentions_customer || !mentions_revenue {
+            continue;
+        }
+
+        let excerpt: String = sentence.trim().chars().take(MAX_EXCERPT_CHARS).collect();
+        for revenue_share in percentages(sentence) {
+            if disclosures.len() >= MAX_DISCLOSURES {
+                return disclosures;
+            }
+            let duplicate = disclosures.iter().any(|d| {
+                d.excerpt == excerpt && (d.revenue_share - revenue_share).abs() < f64::EPSILON
+            });
+            if !duplicate {
+                disclosures.push(ConcentrationDisclosure {
+                    revenue_share,
+                    excerpt: excerpt.clone(),
+                });
+            }
+        }
+    }
+
+    disclosures
+}
+
+/// Percentages ("87%", "12.5 %") in a sentence, excluding 100%
+fn percentages(sentence: &str) -> Vec<f64> {
+    let mut found = Vec::new();
+    for (i, _) in sentence.match_indices('%') {
+        let number: String = sentence[..i]
+            .trim_end()
+            .chars()
+            .rev()
+            .take_while(|c| c.is_ascii_digit() || *c == '.')
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .collect();
+        if let Ok(value) = number.parse::<f64>() {
+            if value > 0.0 && value < 100.0 {
+                found.push(value);
+            }
+        }
+    }
+    found
+}
+
+/// Plain text of an HTML document with whitespace collapsed
+pub(crate) fn strip_html(document: &str) -> String {
+    let mut text = String::with_capacity(document.len());
+    let mut in_tag = false;
+    for c in document.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&#160;", " ")
+        .replace("&#8217;", "'")
+        .replace("&amp;", "&");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Render a company's supply links as a dependency list
+pub fn format_dependencies(
+    symbol: &str,
+    suppliers: &[&SupplyLink],
+    customers: &[&SupplyLink],
+) -> String {
+    let mut list = String::new();
+
+    if !suppliers.is_empty() {
+        let _ = writeln!(list, "{symbol} depends on:");
+        for link in suppliers {
+            let _ = write!(list, "- {} ({})", link.supplier, link.product);
+            if let Some(share) = link.revenue_share {
+                let _ = write!(
+                    list,
+                    ": {symbol} ≈ {share:.0}% of {} revenue",
+                    link.supplier
+                );
+                if let Some(as_of) = link.as_of {
+                    let _ = write!(list, " ({as_of})");
+                }
+            }
+            list.push('\n');
+        }
+    }
+
+    if !customers.is_empty() {
+        let _ = writeln!(list, "{symbol} sells to:");
+        for link in customers {
+            let _ = write!(list, "- {} ({})", link.customer, link.product);
+            if let Some(share) = link.revenue_share {
+                let _ = write!(list, ": ≈ {share:.0}% of {symbol} revenue");
+                if let Some(as_of) = link.as_of {
+                    let _ = write!(list, " ({as_of})");
+                }
+            }
+            list.push('\n');
+        }
+    }
+
+    if list.is_empty() {
+        let _ = writeln!(list, "No curated supply links for {symbol}.");
+    }
+    list
+}
+
+/// Parameters for supply chain requests
+#[derive(Debug, Deserialize)]
+struct SupplyChainParams {
+    /// Stock ticker symbol
+    symbol: String,
+    /// Whether to scan the latest 10-K for customer concentration
+    #[serde(default = "default_include_filings")]
+    include_filings: bool,
+}
+
+fn default_include_filings() -> bool {
+    true
+}
+
+/// Tool exposing known supplier and customer relationships
+pub struct SupplyChainTool {
+    sec_client: SecEdgarClient,
+    cache: StockCache,
+}
+
+impl SupplyChainTool {
+    /// Create a new supply chain tool
+    pub fn new(config: Arc<StockConfig>, cache: StockCache) -> Self {
+        let sec_client = SecEdgarClient::new(&config.sec_user_agent, &config.sec_contact_email);
+
+        Self { sec_client, cache }
+    }
+
+    /// Customer concentration disclosures from the latest 10-K
+    async fn filing_disclosures(&self, symbol: &str) -> Result<Value> {
+        let cache_key = CacheKey::new(symbol, "supply_chain_filings", json!({}));
+
+        self.cache
+            .get_or_fetch(cache_key, || async {
+                let cik = self.sec_client.resolve_cik(symbol).await?;
+                let filings = self
+                    .sec_client
+                    .get_filings(&cik, Some(FilingType::Form10K), Some(1))
+                    .await?;
+                let Some(filing) = filings.first() else {
+                    return Ok(json!({ "filing": null, "disclosures": [] }));
+                };
+
+                let document = self
+                    .sec_client
+                    .get_filing_document(&cik, &filing.accession_number, &filing.primary_document)
+                    .await?;
+                Ok::<_, crate::error::StockError>(json!({
+                    "filing": format!("{} filed {}", filing.form_type, filing.filing_date),
+                    "disclosures": extract_concentration(&document),
+                }))
+            })
+            .await
+    }
+
+    /// Build the supply chain map for a symbol
+    async fn fetch_supply_chain(&self, params: SupplyChainParams) -> Result<Value> {
+        let symbol = params.symbol.to_uppercase();
+        let suppliers = suppliers_of(&symbol);
+        let customers = customers_of(&symbol);
+
+        let link_json = |link: &SupplyLink, counterparty: &str| {
+            json!({
+                "symbol": counterparty,
+                "product": link.product,
+                "revenue_share_percent": link.revenue_share,
+                "as_of": link.as_of,
+            })
+        };
+
+        let mut result = json!({
+            "symbol": symbol,
+            "suppliers": suppliers.iter().map(|l| link_json(l, l.supplier)).collect::<Vec<_>>(),
+            "customers": customers.iter().map(|l| link_json(l, l.customer)).collect::<Vec<_>>(),
+            "dependency_list": format_dependencies(&symbol, &suppliers, &customers),
+            "note": "Supplier revenue shares are the share of the supplier's revenue coming from the customer.",
+        });
+
+        if params.include_filings {
+            // Filings are a best-effort supplement to the curated links
+            match self.filing_disclosures(&symbol).await {
+                Ok(filings) => result["filing_disclosures"] = filings,
+                Err(e) => {
+                    tracing::warn!("Supply chain filing scan failed for {}: {}", symbol, e);
+                    result["filing_disclosures_error"] = json!(e.to_string());
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+#[async_trait]
+impl Tool for SupplyChainTool {
+    async fn execute(&self, params: Value) -> AgentResult<Value> {
+        let params: SupplyChainParams = serde_json::from_value(params)
+            .map_err(|e| agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}")))?;
+
+        self.fetch_supply_chain(params)
+            .await
+            .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))
+    }
+
+    fn name(&self) -> &'static str {
+        "supply_chain"
+    }
+
+    fn description(&self) -> &'static str {
+        "Map a company's known suppliers and customers with revenue-share estimates where known, \
+         plus customer concentration disclosures from its latest 10-K. \
+         Use it to trace second-order impacts, e.g. export restrictions on a supplier affecting its customers."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "symbol": {
+                    "type": "string",
+                    "description": "Stock ticker symbol"
+                },
+                "include_filings": {
+                    "type": "boolean",
+                    "description": "Scan the latest 10-K for customer concentration (default: true)"
+                }
+            },
+            "required": ["symbol"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_supply_links() {
+        let suppliers = suppliers_of("aapl");
+        assert!(suppliers.iter().any(|l| l.supplier == "TSM"));
+        assert!(suppliers.iter().all(|l| l.customer == "AAPL"));
+
+        let customers = customers_of("TSM");
+        assert!(customers.iter().any(|l| l.customer == "NVDA"));
+
+        assert!(suppliers_of("ZZZZ").is_empty());
+    }
+
+    #[test]
+    fn test_format_dependencies() {
+        let list = format_dependencies("AAPL", &suppliers_of("AAPL"), &customers_of("AAPL"));
+        assert!(list.starts_with("AAPL depends on:\n"));
+        assert!(list.contains(
+            "- TSM (Chip foundry (A- and M-series processors)): AAPL ≈ 25% of TSM revenue (FY2023)"
+        ));
+        assert!(!list.contains("sells to:"));
+
+        let list = format_dependencies("TSM", &suppliers_of("TSM"), &customers_of("TSM"));
+        assert!(list.contains("TSM sells to:\n- AAPL"));
+        assert!(list.contains("≈ 25% of TSM revenue"));
+
+        assert_eq!(
+            format_dependencies("ZZZZ", &[], &[]),
+            "No curated supply links for ZZZZ.\n"
+        );
+    }
+
+    #[test]
+    fn test_extract_concentration() {
+        let document = "<html><p>Sales to Apple accounted for 87%&nbsp;of our total net sales in fiscal 2024. \
+            Our <b>largest</b> customers are in Asia. \
+            Customer A represented 12.5% and Customer B represented 10% of revenue. \
+            Gross margin was 51%.</p></html>";
+
+        let disclosures = extract_concentration(document);
+        let shares: Vec<f64> = disclosures.iter().map(|d| d.revenue_share).collect();
+        assert_eq!(shares, vec![87.0, 12.5, 10.0]);
+        assert!(
+            disclosures[0]
+                .excerpt
+                .starts_with("Sales to Apple accounted for 87% of")
+        );
+        assert!(!disclosures[0].excerpt.contains('<'));
+    }
+
+    #[test]
+    fn test_tool_metadata() {
+        let config = Arc::new(StockConfig::default());
+        let cache = StockCache::new(std::time::Duration::from_secs(3600));
+        let tool = SupplyChainTool::new(config, cache);
+
+        assert_eq!(tool.name(), "supply_chain");
+        assert!(!tool.description().is_empty());
+    }
+}
@@ -1,9 +1,10 @@
 //! Tool for calculating technical indicators
 
 use agent_core::Result as AgentResult;
-use agent_tools::Tool;
+use agent_tools::{Tool, TypedTool};
 use async_trait::async_trait;
-use serde::Deserialize;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 use std::sync::Arc;
 use ta::{
@@ -15,8 +16,10 @@ use ta::{
 };
 
 use crate::api::YahooFinanceClient;
+use crate::as_of;
 use crate::cache::StockCache;
 use crate::config::StockConfig;
+use crate::crypto;
 use crate::error::{Result, StockError};
 
 /// Tool for calculating technical indicators
@@ -26,16 +29,71 @@ pub struct TechnicalIndicatorTool {
     _config: Arc<StockConfig>,
 }
 
-#[derive(Debug, Deserialize)]
-struct TechnicalParams {
-    symbol: String,
-    indicator: String,
+/// Parameters for a technical indicator calculation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TechnicalIndicatorParams {
+    /// Stock ticker symbol
+    pub symbol: String,
+    /// Indicator name: RSI, SMA, EMA, MACD, BBANDS/BB or ATR
+    pub indicator: String,
+    /// Period for the indicator calculation
     #[serde(default = "default_period")]
-    period: usize,
-    #[serde(default)]
-    range: Option<String>,
+    pub period: usize,
+    /// Time range for historical data (defaults to "3mo")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range: Option<String>,
+    /// Only use prices known at the end of this day
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub as_of: Option<NaiveDate>,
 }
 
+impl TechnicalIndicatorParams {
+    /// Create parameters with the default period and range
+    pub fn new(symbol: impl Into<String>, indicator: impl Into<String>) -> Self {
+        Self {
+            symbol: symbol.into(),
+            indicator: indicator.into(),
+            period: default_period(),
+            range: None,
+            as_of: None,
+        }
+    }
+
+    /// Set the indicator period
+    pub fn with_period(mut self, period: usize) -> Self {
+        self.period = period;
+        self
+    }
+
+    /// Set the historical data range
+    pub fn with_range(mut self, range: impl Into<String>) -> Self {
+        self.range = Some(range.into());
+        self
+    }
+
+    /// Calculate as of the end of a past day
+    pub fn with_as_of(mut self, date: NaiveDate) -> Self {
+        self.as_of = Some(date);
+        self
+    }
+}
+
+/// Output of a technical indicator calculation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TechnicalIndicatorOutput {
+    /// Normalized ticker symbol
+    pub symbol: String,
+    /// Indicator-specific values and interpretation
+    pub indicator_data: Value,
+    /// Number of price points used
+    pub data_points: usize,
+    /// Time range of the historical data
+    pub time_range: String,
+}
+
+/// Typed client for [`TechnicalIndicatorTool`]
+pub type TechnicalIndicatorClient = TypedTool<TechnicalIndicatorParams, TechnicalIndicatorOutput>;
+
 fn default_period() -> usize {
     14
 }
@@ -50,16 +108,29 @@ impl TechnicalIndicatorTool {
         }
     }
 
+    /// Wrap this tool in a typed client for direct use from Rust
+    pub fn into_client(self) -> TechnicalIndicatorClient {
+        TypedTool::new(Arc::new(self))
+    }
+
     /// Calculate technical indicator
-    async fn calculate_indicator(&self, params: TechnicalParams) -> Result<Value> {
-        let symbol = params.symbol.to_uppercase();
+    async fn calculate_indicator(&self, params: TechnicalIndicatorParams) -> Result<Value> {
+        let symbol = crypto::normalize_symbol(&params.symbol);
         let range = params.range.unwrap_or_else(|| "3mo".to_string());
 
         // Fetch historical data
-        let quotes = self
-            .yahoo_client
-            .get_historical_range(&symbol, &range)
-            .await?;
+        let quotes = match params.as_of {
+            Some(date) => {
+                self.yahoo_client
+                    .get_historical_range_at(&symbol, &range, as_of::cutoff(date))
+                    .await?
+            }
+            None => {
+                self.yahoo_client
+                    .get_historical_range(&symbol, &range)
+                    .await?
+            }
+        };
 
         if quotes.is_empty() {
             return Err(StockError::DataUnavailable {
@@ -228,9 +299,8 @@ fn interpret_rsi(rsi: f64) -> &'static str {
 #[async_trait]
 impl Tool for TechnicalIndicatorTool {
     async fn execute(&self, params: Value) -> AgentResult<Value> {
-        let params: TechnicalParams = serde_json::from_value(params).map_err(|e| {
-            agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}"))
-        })?;
+        let params: TechnicalIndicatorParams = serde_json::from_value(params)
+            .map_err(|e| agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}")))?;
 
         self.calculate_indicator(params)
             .await
@@ -252,7 +322,7 @@ impl Tool for TechnicalIndicatorTool {
             "properties": {
                 "symbol": {
                     "type": "string",
-                    "description": "Stock ticker symbol"
+                    "description": "Stock ticker symbol, or crypto pair (e.g., 'BTC-USD')"
                 },
                 "indicator": {
                     "type": "string",
@@ -269,7 +339,8 @@ impl Tool for TechnicalIndicatorTool {
                     "description": "Time range for historical data",
                     "enum": ["1mo", "3mo", "6mo", "1y"],
                     "default": "3mo"
-                }
+                },
+                "as_of": as_of::param_schema()
             },
             "required": ["symbol", "indicator"]
         })
@@ -298,4 +369,23 @@ mod tests {
         let schema = tool.input_schema();
         assert_eq!(schema["type"], "object");
     }
+
+    #[test]
+    fn test_params_serialization() {
+        let params = TechnicalIndicatorParams::new("AAPL", "RSI");
+        let value = serde_json::to_value(&params).unwrap();
+        assert_eq!(
+            value,
+            json!({"symbol": "AAPL", "indicator": "RSI", "period": 14})
+        );
+
+        let params = params.with_period(20).with_range("6mo");
+        let value = serde_json::to_value(&params).unwrap();
+        assert_eq!(value["period"], 20);
+        assert_eq!(value["range"], "6mo");
+
+        let date = NaiveDate::from_ymd_opt(2023, 1, 31).unwrap();
+        let value = serde_json::to_value(params.with_as_of(date)).unwrap();
+        assert_eq!(value["as_of"], "2023-01-31");
+    }
 }
@@ -0,0 +1,501 @@
+//! Tool for thematic basket analysis
+//!
+//! Themes such as "the AI trade" cut across sectors, so they are tracked as
+//! curated baskets of constituents with weights. The tool reports the
+//! basket's weighted return over a period, its top movers, and recent news
+//! for the names driving the move.
+
+use agent_core::Result as AgentResult;
+use agent_tools::Tool;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+use crate::api::YahooFinanceClient;
+use crate::cache::{CacheKey, StockCache};
+use crate::config::StockConfig;
+use crate::error::{Result, StockError};
+use crate::tools::NewsTool;
+
+/// A curated basket of stocks representing an investment theme
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThemeBasket {
+    /// Short key used in commands, e.g. "ai"
+    pub key: &'static str,
+    /// Display name
+    pub name: &'static str,
+    /// Chinese display name
+    pub name_zh: &'static str,
+    /// Lowercase phrases that refer to the theme in queries
+    pub aliases: &'static [&'static str],
+    /// Constituent tickers with basket weights in percent
+    pub constituents: &'static [(&'static str, f64)],
+    /// What the basket is meant to capture
+    pub description: &'static str,
+}
+
+/// Curated thematic baskets
+///
+/// Weights approximate each name's importance to the theme rather than
+/// market cap alone, and sum to 100.
+pub const THEMES: &[ThemeBasket] = &[
+    ThemeBasket {
+        key: "ai",
+        name: "Artificial Intelligence",
+        name_zh: "人工智能",
+        aliases: &["ai", "artificial intelligence", "人工智能"],
+        constituents: &[
+            ("NVDA", 20.0),
+            ("MSFT", 15.0),
+            ("GOOGL", 12.0),
+            ("META", 10.0),
+            ("AMZN", 10.0),
+            ("AVGO", 8.0),
+            ("AMD", 7.0),
+            ("TSM", 6.0),
+            ("ORCL", 5.0),
+            ("PLTR", 4.0),
+            ("SMCI", 3.0),
+        ],
+        description: "AI compute, hyperscale cloud and AI software",
+    },
+    ThemeBasket {
+        key: "ev",
+        name: "Electric Vehicles",
+        name_zh: "电动车",
+        aliases: &[
+            "ev",
+            "evs",
+            "electric vehicle",
+            "electric vehicles",
+            "电动车",
+            "新能源车",
+            "新能源汽车",
+        ],
+        constituents: &[
+            ("TSLA", 35.0),
+            ("BYDDY", 15.0),
+            ("RIVN", 10.0),
+            ("LI", 8.0),
+            ("NIO", 7.0),
+            ("XPEV", 7.0),
+            ("GM", 7.0),
+            ("ALB", 6.0),
+            ("LCID", 5.0),
+        ],
+        description: "EV makers and battery materials",
+    },
+    ThemeBasket {
+        key: "semis",
+        name: "Semiconductors",
+        name_zh: "半导体",
+        aliases: &[
+            "semis",
+            "semiconductor",
+            "semiconductors",
+            "chip stocks",
+            "chipmakers",
+            "半导体",
+            "芯片",
+        ],
+        constituents: &[
+            ("NVDA", 18.0),
+            ("TSM", 14.0),
+            ("AVGO", 12.0),
+            ("AMD", 8.0),
+            ("ASML", 8.0),
+            ("QCOM", 7.0),
+            ("TXN", 6.0),
+            ("AMAT", 6.0),
+            ("MU", 6.0),
+            ("LRCX", 5.0),
+            ("INTC", 5.0),
+            ("KLAC", 5.0),
+        ],
+        description: "Chip designers, foundries and equipment makers",
+    },
+];
+
+/// Look up a theme by its key, case-insensitively
+pub fn theme_by_key(key: &str) -> Option<&'static ThemeBasket> {
+    let key = key.trim().to_lowercase();
+    THEMES.iter().find(|theme| theme.key == key)
+}
+
+/// Find the theme a query refers to, by key or alias
+///
+/// ASCII aliases must match whole words so that "ai" does not match
+/// "said" and "ev" does not match "every".
+pub fn find_theme(query: &str) -> Option<&'static ThemeBasket> {
+    let query = query.to_lowercase();
+    THEMES.iter().find(|theme| {
+        std::iter::once(&theme.key)
+            .chain(theme.aliases)
+            .any(|alias| contains_alias(&query, alias))
+    })
+}
+
+fn contains_alias(query: &str, alias: &str) -> bool {
+    if !alias.is_ascii() {
+        return query.contains(alias);
+    }
+    query.match_indices(alias).any(|(start, _)| {
+        let end = start + alias.len();
+        let boundary = |c: Option<char>| c.is_none_or(|c| !c.is_ascii_alphanumeric());
+        boundary(query[..start].chars().next_back()) && boundary(query[end..].chars().next())
+    })
+}
+
+/// Performance of one constituent over the period
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConstituentMove {
+    pub symbol: String,
+    /// Basket weight in percent
+    pub weight: f64,
+    /// Return over the period in percent
+    pub change_pct: f64,
+}
+
+/// Aggregate performance of a theme basket
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThemePerformance {
+    /// Weighted return in percent, renormalized over constituents with data
+    pub weighted_change_pct: f64,
+    /// Share of the basket weight that had price data, in percent
+    pub weight_covered: f64,
+    pub advancers: usize,
+    pub decliners: usize,
+    /// Best performers, best first
+    pub top_gainers: Vec<ConstituentMove>,
+    /// Worst performers, worst first
+    pub top_losers: Vec<ConstituentMove>,
+}
+
+/// Number of movers reported on each side
+const TOP_MOVERS: usize = 3;
+
+/// Aggregate constituent moves into basket performance
+///
+/// Returns `None` when no constituent has data.
+pub fn basket_performance(moves: &[ConstituentMove]) -> Option<ThemePerformance> {
+    let weight_covered: f64 = moves.iter().map(|m| m.weight).sum();
+    if moves.is_empty() || weight_covered <= 0.0 {
+        return None;
+    }
+
+    let weighted_change_pct =
+        moves.iter().map(|m| m.weight * m.change_pct).sum::<f64>() / weight_covered;
+
+    let mut sorted = moves.to_vec();
+    sorted.sort_by(|a, b| b.change_pct.total_cmp(&a.change_pct));
+
+    let top_gainers = sorted
+        .iter()
+        .filter(|m| m.change_pct > 0.0)
+        .take(TOP_MOVERS)
+        .cloned()
+        .collect();
+    let top_losers = sorted
+        .iter()
+        .rev()
+        .filter(|m| m.change_pct < 0.0)
+        .take(TOP_MOVERS)
+        .cloned()
+        .collect();
+
+    Some(ThemePerformance {
+        weighted_change_pct,
+        weight_covered,
+        advancers: moves.iter().filter(|m| m.change_pct > 0.0).count(),
+        decliners: moves.iter().filter(|m| m.change_pct < 0.0).count(),
+        top_gainers,
+        top_losers,
+    })
+}
+
+/// Parameters for theme analysis
+#[derive(Debug, Deserialize)]
+struct ThemeParams {
+    /// Theme key or alias, e.g. "ai"
+    theme: String,
+    /// Period: "1d", "5d", "1mo", "3mo", "ytd"
+    #[serde(default = "default_period")]
+    period: String,
+    /// Include news for the top movers
+    #[serde(default = "default_include_news")]
+    include_news: bool,
+}
+
+fn default_period() -> String {
+    "5d".to_string()
+}
+
+fn default_include_news() -> bool {
+    true
+}
+
+/// History range to fetch and trading days to look back for a period
+///
+/// A lookback of `None` means from the first bar of the range.
+fn period_window(period: &str) -> Option<(&'static str, Option<usize>)> {
+    match period {
+        "1d" => Some(("1mo", Some(1))),
+        "5d" => Some(("1mo", Some(5))),
+        "1mo" => Some(("3mo", Some(21))),
+        "3mo" => Some(("6mo", Some(63))),
+        "ytd" => Some(("ytd", None)),
+        _ => None,
+    }
+}
+
+/// Tool for analyzing thematic baskets
+pub struct ThemeAnalysisTool {
+    yahoo_client: YahooFinanceClient,
+    news_tool: NewsTool,
+    cache: StockCache,
+}
+
+impl ThemeAnalysisTool {
+    /// Create a new theme analysis tool
+    pub fn new(config: Arc<StockConfig>, cache: StockCache) -> Self {
+        Self {
+            yahoo_client: YahooFinanceClient::new(),
+            news_tool: NewsTool::new(config, cache.clone()),
+            cache,
+        }
+    }
+
+    /// Fetch theme performance, movers and news
+    async fn fetch_theme(&self, params: ThemeParams) -> Result<Value> {
+        let theme = theme_by_key(&params.theme)
+            .or_else(|| find_theme(&params.theme))
+            .ok_or_else(|| {
+                StockError::InvalidSymbol(format!(
+                    "Unknown theme: {}. Valid themes: {}",
+                    params.theme,
+                    theme_keys()
+                ))
+            })?;
+        let period = params.period.to_lowercase();
+        let (range, lookback) = period_window(&period).ok_or_else(|| {
+            StockError::InvalidSymbol(format!(
+                "Invalid period: {period}. Valid periods: 1d, 5d, 1mo, 3mo, ytd"
+            ))
+        })?;
+
+        let cache_key = CacheKey::new(
+            "theme",
+            theme.key,
+            json!({ "period": period, "news": params.include_news }),
+        );
+
+        self.cache
+            .get_or_fetch(cache_key, || async {
+                let moves = self.constituent_moves(theme, range, lookback).await;
+                let performance = basket_performance(&moves).ok_or_else(|| {
+                    StockError::ApiError(format!("No price data for the {} basket", theme.name))
+                })?;
+
+                let mut result = json!({
+                    "theme": theme.name,
+                    "theme_zh": theme.name_zh,
+                    "description": theme.description,
+                    "period": period,
+                    "constituents": moves,
+                    "performance": performance,
+                    "as_of_date": chrono::Utc::now().format("%Y-%m-%d").to_string(),
+                    "data_source": "Yahoo Finance",
+                });
+
+                if params.include_news {
+                    result["mover_news"] = self.mover_news(&performance).await;
+                }
+
+                Ok::<_, StockError>(result)
+            })
+            .await
+    }
+
+    /// Period returns for every constituent with price data
+    async fn constituent_moves(
+        &self,
+        theme: &ThemeBasket,
+        range: &str,
+        lookback: Option<usize>,
+    ) -> Vec<ConstituentMove> {
+        let mut moves = Vec::new();
+
+        for &(symbol, weight) in theme.constituents {
+            let Ok(quotes) = self.yahoo_client.get_historical_range(symbol, range).await else {
+                continue;
+            };
+            let Some(last) = quotes.last() else {
+                continue;
+            };
+            let start_index = lookback.map_or(0, |n| quotes.len().saturating_sub(n + 1));
+            let start = quotes[start_index].close;
+            if start > 0.0 && start_index < quotes.len() - 1 {
+                moves.push(ConstituentMove {
+                    symbol: symbol.to_string(),
+                    weight,
+                    change_pct: (last.close - start) / start * 100.0,
+                });
+            }
+        }
+
+        moves
+    }
+
+    /// Recent headlines for the top movers on each side
+    async fn mover_news(&self, performance: &ThemePerformance) -> Value {
+        let mut news = serde_json::Map::new();
+
+        for mover in performance
+            .top_gainers
+            .iter()
+            .chain(&performance.top_losers)
+        {
+            if let Ok(items) = self
+                .news_tool
+                .execute(json!({ "symbol": mover.symbol, "limit": 2 }))
+                .await
+            {
+                news.insert(mover.symbol.clone(), items);
+            }
+        }
+
+        Value::Object(news)
+    }
+}
+
+/// Comma-separated list of theme keys, for help and error messages
+pub fn theme_keys() -> String {
+    THEMES
+        .iter()
+        .map(|theme| theme.key)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[async_trait]
+impl Tool for ThemeAnalysisTool {
+    async fn execute(&self, params: Value) -> AgentResult<Value> {
+        let params: ThemeParams = serde_json::from_value(params)
+            .map_err(|e| agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}")))?;
+
+        self.fetch_theme(params)
+            .await
+            .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))
+    }
+
+    fn name(&self) -> &'static str {
+        "theme_analysis"
+    }
+
+    fn description(&self) -> &'static str {
+        "Analyze an investment theme (ai, ev, semis) as a weighted basket of stocks. \
+         Returns the basket's weighted return over the period, advancers and decliners, \
+         the top gainers and losers, and recent news for the top movers."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "theme": {
+                    "type": "string",
+                    "description": "Theme key: ai, ev or semis"
+                },
+                "period": {
+                    "type": "string",
+                    "enum": ["1d", "5d", "1mo", "3mo", "ytd"],
+                    "description": "Performance period (default: 5d)"
+                },
+                "include_news": {
+                    "type": "boolean",
+                    "description": "Include news for the top movers (default: true)"
+                }
+            },
+            "required": ["theme"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mv(symbol: &str, weight: f64, change_pct: f64) -> ConstituentMove {
+        ConstituentMove {
+            symbol: symbol.to_string(),
+            weight,
+            change_pct,
+        }
+    }
+
+    #[test]
+    fn test_theme_weights() {
+        for theme in THEMES {
+            let total: f64 = theme.constituents.iter().map(|(_, w)| w).sum();
+            assert!(
+                (total - 100.0).abs() < 1e-9,
+                "{} weights sum to {total}",
+                theme.key
+            );
+        }
+    }
+
+    #[test]
+    fn test_find_theme() {
+        assert_eq!(find_theme("How is the AI trade doing?").unwrap().key, "ai");
+        assert_eq!(find_theme("are EV stocks recovering").unwrap().key, "ev");
+        assert_eq!(
+            find_theme("semiconductor names this week").unwrap().key,
+            "semis"
+        );
+        assert_eq!(find_theme("人工智能板块表现如何").unwrap().key, "ai");
+        assert_eq!(find_theme("芯片股怎么样").unwrap().key, "semis");
+
+        // Short aliases only match whole words
+        assert!(find_theme("What did the analyst say about every stock?").is_none());
+        assert!(find_theme("Analyze AAPL").is_none());
+
+        assert_eq!(theme_by_key("AI").unwrap().name, "Artificial Intelligence");
+        assert!(theme_by_key("crypto").is_none());
+    }
+
+    #[test]
+    fn test_basket_performance() {
+        let moves = vec![
+            mv("NVDA", 20.0, 10.0),
+            mv("MSFT", 15.0, -2.0),
+            mv("AMD", 5.0, 4.0),
+            mv("PLTR", 10.0, -6.0),
+        ];
+
+        let perf = basket_performance(&moves).unwrap();
+        // (200 - 30 + 20 - 60) / 50
+        assert!((perf.weighted_change_pct - 2.6).abs() < 1e-9);
+        assert!((perf.weight_covered - 50.0).abs() < 1e-9);
+        assert_eq!(perf.advancers, 2);
+        assert_eq!(perf.decliners, 2);
+        assert_eq!(perf.top_gainers[0].symbol, "NVDA");
+        assert_eq!(perf.top_gainers.len(), 2);
+        assert_eq!(perf.top_losers[0].symbol, "PLTR");
+        assert_eq!(perf.top_losers[1].symbol, "MSFT");
+
+        assert!(basket_performance(&[]).is_none());
+    }
+
+    #[test]
+    fn test_tool_metadata() {
+        let config = Arc::new(StockConfig::default());
+        let cache = StockCache::new(std::time::Duration::from_secs(3600));
+        let tool = ThemeAnalysisTool::new(config, cache);
+
+        assert_eq!(tool.name(), "theme_analysis");
+        assert!(!tool.description().is_empty());
+        assert_eq!(theme_keys(), "ai, ev, semis");
+    }
+}
@@ -0,0 +1,803 @@
+//! Comparison of a stock's state now against a past date
+//!
+//! "AAPL now vs 6 months ago" is answered by rebuilding the stock's state on
+//! both dates from price history (price, RSI, moving averages) and SEC EDGAR
+//! filings (the trailing annual EPS that had been reported by then), and
+//! diffing the two: P/E compression or expansion, RSI regime shifts, moves
+//! across the 200-day average. Analyst estimates have no history before the
+//! last 90 days, so estimate revisions cover that window only. On request the
+//! price change is also deflated by CPI, for "is AAPL beating inflation".
+
+use agent_core::Result as AgentResult;
+use agent_tools::Tool;
+use async_trait::async_trait;
+use chrono::{Days, Months, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::sync::Arc;
+use ta::{Next, indicators::RelativeStrengthIndex};
+
+use crate::api::sec_edgar::CompanyFacts;
+use crate::api::{EpsTrend, FredClient, SecEdgarClient, YahooFinanceClient};
+use crate::cache::{CacheKey, StockCache};
+use crate::config::StockConfig;
+use crate::crypto;
+use crate::error::{Result, StockError};
+use crate::inflation;
+
+/// [`crate::engine::AnalysisResult`] data key holding the comparison
+pub const TIME_COMPARISON_DATA_KEY: &str = "time_comparison";
+
+/// How far back to compare, e.g. "6 months ago"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lookback {
+    Days(u32),
+    Months(u32),
+}
+
+impl Lookback {
+    /// The date `self` before `date`
+    pub fn before(&self, date: NaiveDate) -> Option<NaiveDate> {
+        match *self {
+            Self::Days(days) => date.checked_sub_days(Days::new(u64::from(days))),
+            Self::Months(months) => date.checked_sub_months(Months::new(months)),
+        }
+    }
+}
+
+impl std::fmt::Display for Lookback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (n, unit) = match *self {
+            Self::Days(days) if days % 7 == 0 => (days / 7, "week"),
+            Self::Days(days) => (days, "day"),
+            Self::Months(months) if months % 12 == 0 => (months / 12, "year"),
+            Self::Months(months) => (months, "month"),
+        };
+        let plural = if n == 1 { "" } else { "s" };
+        write!(f, "{n} {unit}{plural} ago")
+    }
+}
+
+/// Lookback a query asks to compare against, e.g. "6 months ago", "半年前"
+pub fn parse_lookback(query: &str) -> Option<Lookback> {
+    let query = query.to_lowercase();
+    parse_lookback_en(&query).or_else(|| parse_lookback_zh(&query))
+}
+
+/// "6 months ago", "a year ago", "half a year ago"
+fn parse_lookback_en(query: &str) -> Option<Lookback> {
+    let words: Vec<&str> = query
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    words.windows(3).enumerate().find_map(|(i, window)| {
+        let [count, unit, ago] = window else {
+            return None;
+        };
+        if *ago != "ago" {
+            return None;
+        }
+        if *count == "a" && unit.starts_with("year") && i > 0 && words[i - 1] == "half" {
+            return Some(Lookback::Months(6));
+        }
+        let n = match *count {
+            "a" | "an" | "one" => 1,
+            "two" => 2,
+            "three" => 3,
+            "four" => 4,
+            "five" => 5,
+            "six" => 6,
+            "nine" => 9,
+            "twelve" => 12,
+            digits => digits.parse().ok()?,
+        };
+        lookback(n, unit.trim_end_matches('s'))
+    })
+}
+
+/// "6个月前", "半年前", "一年前", "两周前"
+fn parse_lookback_zh(query: &str) -> Option<Lookback> {
+    const UNITS: &[(&str, &str)] = &[
+        ("个月前", "month"),
+        ("月前", "month"),
+        ("年前", "year"),
+        ("周前", "week"),
+        ("星期前", "week"),
+        ("天前", "day"),
+    ];
+
+    UNITS.iter().find_map(|(suffix, unit)| {
+        let (index, _) = query.match_indices(suffix).next()?;
+        let prefix = &query[..index];
+        if *unit == "year" && prefix.ends_with('半') {
+            return Some(Lookback::Months(6));
+        }
+        let digits: String = prefix
+            .chars()
+            .rev()
+            .take_while(|c| c.is_ascii_digit() || "一二两三四五六七八九十".contains(*c))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .collect();
+        lookback(parse_zh_number(&digits)?, unit)
+    })
+}
+
+/// Parse "6", "六", "十二" or "二十"
+fn parse_zh_number(number: &str) -> Option<u32> {
+    if let Ok(n) = number.parse() {
+        return Some(n);
+    }
+    let digit = |c: char| {
+        "零一二三四五六七八九"
+            .chars()
+            .position(|d| d == c)
+            .map(|n| n as u32)
+    };
+    let number = number.replace('两', "二");
+    match number.split_once('十') {
+        Some((tens, ones)) => {
+            let tens = if tens.is_empty() {
+                1
+            } else {
+                digit(tens.chars().next()?)?
+            };
+            let ones = if ones.is_empty() {
+                0
+            } else {
+                digit(ones.chars().next()?)?
+            };
+            Some(tens * 10 + ones)
+        }
+        None if number.chars().count() == 1 => digit(number.chars().next()?),
+        None => None,
+    }
+}
+
+fn lookback(n: u32, unit: &str) -> Option<Lookback> {
+    if n == 0 {
+        return None;
+    }
+    match unit {
+        "day" => Some(Lookback::Days(n)),
+        "week" => Some(Lookback::Days(n * 7)),
+        "month" => Some(Lookback::Months(n)),
+        "year" => Some(Lookback::Months(n * 12)),
+        _ => None,
+    }
+}
+
+/// Technical and valuation state of a stock on one date
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StockState {
+    /// Trading day the state is taken at (the last one on or before the requested date)
+    pub date: NaiveDate,
+    pub price: f64,
+    /// 14-day RSI
+    pub rsi: Option<f64>,
+    pub sma_50: Option<f64>,
+    pub sma_200: Option<f64>,
+    /// Diluted EPS of the last fiscal year reported by `date`
+    pub trailing_eps: Option<f64>,
+    /// Fiscal year end `trailing_eps` belongs to
+    pub eps_period_end: Option<NaiveDate>,
+    /// Price over trailing EPS, when EPS is positive
+    pub pe: Option<f64>,
+}
+
+impl StockState {
+    /// State at the last of `closes`, with the EPS known at the time
+    pub fn from_closes(date: NaiveDate, closes: &[f64], eps: Option<&AnnualEps>) -> Option<Self> {
+        let price = *closes.last()?;
+        let sma = |period: usize| {
+            (closes.len() >= period)
+                .then(|| closes[closes.len() - period..].iter().sum::<f64>() / period as f64)
+        };
+        let rsi = (closes.len() > 14).then(|| {
+            let mut rsi = RelativeStrengthIndex::new(14).expect("valid RSI period");
+            closes.iter().fold(0.0, |_, &close| rsi.next(close))
+        });
+        let trailing_eps = eps.map(|eps| eps.value);
+
+        Some(Self {
+            date,
+            price,
+            rsi,
+            sma_50: sma(50),
+            sma_200: sma(200),
+            trailing_eps,
+            eps_period_end: eps.map(|eps| eps.period_end),
+            pe: trailing_eps.filter(|eps| *eps > 0.0).map(|eps| price / eps),
+        })
+    }
+
+    /// RSI regime: "overbought" above 70, "oversold" below 30, else "neutral"
+    pub fn rsi_regime(&self) -> Option<&'static str> {
+        self.rsi.map(|rsi| {
+            if rsi > 70.0 {
+                "overbought"
+            } else if rsi < 30.0 {
+                "oversold"
+            } else {
+                "neutral"
+            }
+        })
+    }
+}
+
+/// Change in one metric between the two dates
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricChange {
+    pub metric: String,
+    pub past: Option<f64>,
+    pub current: Option<f64>,
+    pub change: Option<f64>,
+    /// Change relative to the past value, in percent
+    pub change_pct: Option<f64>,
+}
+
+impl MetricChange {
+    fn new(metric: &str, past: Option<f64>, current: Option<f64>) -> Self {
+        let change = past.zip(current).map(|(past, current)| current - past);
+        let change_pct = past
+            .zip(change)
+            .filter(|(past, _)| past.abs() > f64::EPSILON)
+            .map(|(past, change)| change / past.abs() * 100.0);
+        Self {
+            metric: metric.to_string(),
+            past,
+            current,
+            change,
+            change_pct,
+        }
+    }
+}
+
+/// Structured diff between a stock's past and current state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateDiff {
+    pub symbol: String,
+    pub past: StockState,
+    pub current: StockState,
+    pub changes: Vec<MetricChange>,
+    /// Notable shifts in plain words, e.g. "P/E compressed from 32.1 to 27.4 (-14.6%)"
+    pub findings: Vec<String>,
+}
+
+impl StateDiff {
+    /// Diff two states of `symbol`
+    pub fn new(symbol: &str, past: StockState, current: StockState) -> Self {
+        let changes = vec![
+            MetricChange::new("price", Some(past.price), Some(current.price)),
+            MetricChange::new("pe", past.pe, current.pe),
+            MetricChange::new("trailing_eps", past.trailing_eps, current.trailing_eps),
+            MetricChange::new("rsi", past.rsi, current.rsi),
+            MetricChange::new("sma_50", past.sma_50, current.sma_50),
+            MetricChange::new("sma_200", past.sma_200, current.sma_200),
+        ];
+        let findings = findings(&past, &current, &changes);
+
+        Self {
+            symbol: symbol.to_string(),
+            past,
+            current,
+            changes,
+            findings,
+        }
+    }
+
+    /// Change of `metric`, if tracked
+    pub fn change(&self, metric: &str) -> Option<&MetricChange> {
+        self.changes.iter().find(|change| change.metric == metric)
+    }
+}
+
+/// Plain-word findings for a diff
+fn findings(past: &StockState, current: &StockState, changes: &[MetricChange]) -> Vec<String> {
+    let mut findings = Vec::new();
+    let pct = |metric: &str| {
+        changes
+            .iter()
+            .find(|change| change.metric == metric)
+            .and_then(|change| change.change_pct)
+    };
+
+    if let (Some(before), Some(now), Some(change)) = (past.pe, current.pe, pct("pe")) {
+        let direction = if change < -1.0 {
+            "compressed"
+        } else if change > 1.0 {
+            "expanded"
+        } else {
+            "held steady"
+        };
+        let mut finding = format!("P/E {direction} from {before:.1} to {now:.1} ({change:+.1}%)");
+        if let (Some(price), Some(eps)) = (pct("price"), pct("trailing_eps")) {
+            finding.push_str(&format!(
+                ", with price {price:+.1}% and trailing EPS {eps:+.1}%"
+            ));
+        }
+        findings.push(finding);
+    }
+
+    if let (Some(before), Some(now)) = (past.rsi_regime(), current.rsi_regime()) {
+        let (past_rsi, rsi) = (
+            past.rsi.unwrap_or_default(),
+            current.rsi.unwrap_or_default(),
+        );
+        findings.push(if before == now {
+            format!("RSI stayed {now} ({past_rsi:.0} → {rsi:.0})")
+        } else {
+            format!("RSI regime shifted from {before} ({past_rsi:.0}) to {now} ({rsi:.0})")
+        });
+    }
+
+    if let (Some(before), Some(now)) = (past.sma_200, current.sma_200) {
+        let (was_above, is_above) = (past.price > before, current.price > now);
+        if was_above != is_above {
+            let side = |above: bool| if above { "above" } else { "below" };
+            findings.push(format!(
+                "Price moved from {} to {} its 200-day SMA",
+                side(was_above),
+                side(is_above)
+            ));
+        }
+    }
+
+    if let (Some(past_50), Some(past_200), Some(now_50), Some(now_200)) =
+        (past.sma_50, past.sma_200, current.sma_50, current.sma_200)
+    {
+        if past_50 <= past_200 && now_50 > now_200 {
+            findings.push("Golden cross: the 50-day SMA moved above the 200-day SMA".to_string());
+        } else if past_50 >= past_200 && now_50 < now_200 {
+            findings.push("Death cross: the 50-day SMA moved below the 200-day SMA".to_string());
+        }
+    }
+
+    findings
+}
+
+/// Diluted EPS for one fiscal year, as filed with the SEC
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnnualEps {
+    pub period_end: NaiveDate,
+    pub filed: NaiveDate,
+    pub value: f64,
+}
+
+/// Annual diluted EPS from XBRL company facts, oldest first
+///
+/// Each fiscal year keeps the value from its first filing, so restatements
+/// in later 10-Ks do not leak into what was known at the time.
+pub fn annual_eps(facts: &CompanyFacts) -> Vec<AnnualEps> {
+    let entries = facts
+        .facts
+        .us_gaap
+        .as_ref()
+        .and_then(|gaap| gaap["EarningsPerShareDiluted"]["units"]["USD/shares"].as_array());
+    let date = |entry: &Value, field: &str| {
+        entry[field]
+            .as_str()
+            .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+    };
+
+    let mut eps: Vec<AnnualEps> = Vec::new();
+    for entry in entries.into_iter().flatten() {
+        let (Some(start), Some(period_end), Some(filed), Some(value)) = (
+            date(entry, "start"),
+            date(entry, "end"),
+            date(entry, "filed"),
+            entry["val"].as_f64(),
+        ) else {
+            continue;
+        };
+        // Fiscal years run 52 or 53 weeks
+        if !(350..=380).contains(&(period_end - start).num_days()) {
+            continue;
+        }
+        match eps.iter_mut().find(|known| known.period_end == period_end) {
+            Some(known) if filed < known.filed => {
+                known.filed = filed;
+                known.value = value;
+            }
+            Some(_) => {}
+            None => eps.push(AnnualEps {
+                period_end,
+                filed,
+                value,
+            }),
+        }
+    }
+
+    eps.sort_by_key(|eps| eps.period_end);
+    eps
+}
+
+/// Latest annual EPS that had been filed by `date`
+pub fn eps_known_at(eps: &[AnnualEps], date: NaiveDate) -> Option<&AnnualEps> {
+    eps.iter().rev().find(|eps| eps.filed <= date)
+}
+
+/// Calendar days of history before the past date needed for a 200-day SMA
+const WARMUP_DAYS: u64 = 300;
+
+/// Parameters for a time comparison
+#[derive(Debug, Deserialize)]
+struct TimeCompareParams {
+    symbol: String,
+    /// Past date, "YYYY-MM-DD"
+    date: String,
+    /// Also report the price change deflated by CPI
+    #[serde(default)]
+    inflation_adjusted: bool,
+}
+
+/// Tool comparing a stock's current state against a past date
+pub struct TimeComparisonTool {
+    yahoo_client: YahooFinanceClient,
+    sec_client: SecEdgarClient,
+    /// CPI source for inflation-adjusted returns
+    fred_client: Option<FredClient>,
+    cache: StockCache,
+}
+
+impl TimeComparisonTool {
+    /// Create a new time comparison tool
+    pub fn new(config: Arc<StockConfig>, cache: StockCache) -> Self {
+        Self {
+            yahoo_client: YahooFinanceClient::new(),
+            sec_client: SecEdgarClient::new(&config.sec_user_agent, &config.sec_contact_email),
+            fred_client: config
+                .fred_api_key
+                .as_ref()
+                .map(|key| FredClient::new(key.clone(), None)),
+            cache,
+        }
+    }
+
+    /// Build the diff between `params.date` and today
+    async fn compare(&self, params: TimeCompareParams) -> Result<Value> {
+        let symbol = crypto::normalize_symbol(&params.symbol);
+        let past_date =
+            NaiveDate::parse_from_str(params.date.trim(), "%Y-%m-%d").map_err(|_| {
+                StockError::InvalidSymbol(format!("Invalid date: {}. Use YYYY-MM-DD", params.date))
+            })?;
+        let today = Utc::now().date_naive();
+        if past_date >= today {
+            return Err(StockError::InvalidSymbol(format!(
+                "Comparison date {past_date} must be in the past"
+            )));
+        }
+
+        let cache_key = CacheKey::new(
+            &symbol,
+            "time_comparison",
+            json!({ "date": past_date, "real": params.inflation_adjusted }),
+        );
+
+        self.cache
+            .get_or_fetch(cache_key, || async {
+                let diff = self.diff(&symbol, past_date).await?;
+                let revisions = self.estimate_revisions(&symbol).await;
+                let real_return = if params.inflation_adjusted {
+                    self.real_return(&diff).await
+                } else {
+                    Value::Null
+                };
+
+                Ok::<_, StockError>(json!({
+                    "symbol": symbol,
+                    "requested_date": past_date.to_string(),
+                    "diff": diff,
+                    "estimate_revisions": revisions,
+                    "inflation_adjusted": real_return,
+                    "as_of_date": today.to_string(),
+                    "data_source": "Yahoo Finance, SEC EDGAR",
+                }))
+            })
+            .await
+    }
+
+    /// Rebuild both states and diff them
+    async fn diff(&self, symbol: &str, past_date: NaiveDate) -> Result<StateDiff> {
+        let start = past_date
+            .checked_sub_days(Days::new(WARMUP_DAYS))
+            .unwrap_or(past_date)
+            .and_hms_opt(0, 0, 0)
+            .unwrap_or_default()
+            .and_utc();
+        let quotes = self
+            .yahoo_client
+            .get_historical_quotes(symbol, start, Utc::now())
+            .await?;
+
+        let past_len = quotes
+            .iter()
+            .take_while(|quote| quote.timestamp.date_naive() <= past_date)
+            .count();
+        let (Some(past_quote), Some(current_quote)) =
+            (quotes.get(past_len.wrapping_sub(1)), quotes.last())
+        else {
+            return Err(StockError::DataUnavailable {
+                symbol: symbol.to_string(),
+                reason: format!("No price history on or before {past_date}"),
+            });
+        };
+
+        // EPS is best effort: foreign filers and funds have no US-GAAP facts,
+        // and crypto assets have no filer at all
+        let eps = if crypto::is_crypto(symbol) {
+            Vec::new()
+        } else {
+            match self.company_eps(symbol).await {
+                Ok(eps) => eps,
+                Err(e) => {
+                    tracing::warn!("No SEC EPS history for {}: {}", symbol, e);
+                    Vec::new()
+                }
+            }
+        };
+
+        let closes: Vec<f64> = quotes.iter().map(|quote| quote.close).collect();
+        let past_day = past_quote.timestamp.date_naive();
+        let current_day = current_quote.timestamp.date_naive();
+        let past =
+            StockState::from_closes(past_day, &closes[..past_len], eps_known_at(&eps, past_day));
+        let current =
+            StockState::from_closes(current_day, &closes, eps_known_at(&eps, current_day));
+
+        past.zip(current)
+            .map(|(past, current)| StateDiff::new(symbol, past, current))
+            .ok_or_else(|| StockError::DataUnavailable {
+                symbol: symbol.to_string(),
+                reason: "Not enough price history".to_string(),
+            })
+    }
+
+    /// Annual EPS history from SEC EDGAR
+    async fn company_eps(&self, symbol: &str) -> Result<Vec<AnnualEps>> {
+        let cik = self.sec_client.resolve_cik(symbol).await?;
+        let facts = self.sec_client.get_company_facts(&cik).await?;
+        Ok(annual_eps(&facts))
+    }
+
+    /// Price change between the two states deflated by CPI, if available
+    async fn real_return(&self, diff: &StateDiff) -> Value {
+        let Some(nominal) = diff.change("price").and_then(|change| change.change_pct) else {
+            return Value::Null;
+        };
+        match inflation::fetch_adjustment(
+            self.fred_client.as_ref(),
+            diff.past.date,
+            diff.current.date,
+        )
+        .await
+        {
+            Ok(adjustment) => json!({
+                "price_change_pct": nominal,
+                "real_price_change_pct": adjustment.real_return_pct(nominal),
+                "real_annualized_pct": adjustment.real_annualized_pct(nominal),
+                "inflation_pct": adjustment.inflation_pct,
+                "annualized_inflation_pct": adjustment.annualized_inflation_pct(),
+                "beat_inflation": nominal > adjustment.inflation_pct,
+                "cpi_start_month": adjustment.start_month,
+                "cpi_end_month": adjustment.end_month,
+                "note": "Price change only; dividends are not included",
+            }),
+            Err(e) => json!({ "unavailable": e.to_string() }),
+        }
+    }
+
+    /// Consensus EPS revisions over the last 90 days, if available
+    async fn estimate_revisions(&self, symbol: &str) -> Value {
+        match self.yahoo_client.get_eps_trend(symbol).await {
+            Ok(trends) => Value::Array(trends.iter().map(revision_json).collect()),
+            Err(e) => json!({ "unavailable": e.to_string() }),
+        }
+    }
+}
+
+fn revision_json(trend: &EpsTrend) -> Value {
+    json!({
+        "period": trend.period,
+        "end_date": trend.end_date,
+        "current": trend.current,
+        "days_ago_90": trend.days_ago_90,
+        "revision_pct_90d": trend.revision_pct_90d(),
+        "up_last_30_days": trend.up_last_30_days,
+        "down_last_30_days": trend.down_last_30_days,
+    })
+}
+
+#[async_trait]
+impl Tool for TimeComparisonTool {
+    async fn execute(&self, params: Value) -> AgentResult<Value> {
+        let params: TimeCompareParams = serde_json::from_value(params)
+            .map_err(|e| agent_core::Error::ProcessingFailed(format!("Invalid parameters: {e}")))?;
+
+        self.compare(params)
+            .await
+            .map_err(|e| agent_core::Error::ProcessingFailed(e.to_string()))
+    }
+
+    fn name(&self) -> &'static str {
+        "compare_over_time"
+    }
+
+    fn description(&self) -> &'static str {
+        "Compare a stock's current technical and valuation state against a past date. \
+         Returns both states (price, RSI, 50/200-day SMA, trailing EPS and P/E as known \
+         at the time), the change in each metric, findings such as P/E compression or \
+         RSI regime shifts, and analyst EPS estimate revisions over the last 90 days. \
+         Set inflation_adjusted to also get the price change deflated by US CPI, e.g. \
+         for whether a stock beat inflation over ten years."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "symbol": {
+                    "type": "string",
+                    "description": "Stock symbol or crypto pair (e.g., AAPL, BTC-USD)"
+                },
+                "date": {
+                    "type": "string",
+                    "description": "Past date to compare against, YYYY-MM-DD"
+                },
+                "inflation_adjusted": {
+                    "type": "boolean",
+                    "default": false,
+                    "description": "Also report the real (CPI-deflated) price change (needs FRED)"
+                }
+            },
+            "required": ["symbol", "date"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_parse_lookback() {
+        assert_eq!(
+            parse_lookback("AAPL now vs 6 months ago"),
+            Some(Lookback::Months(6))
+        );
+        assert_eq!(
+            parse_lookback("MSFT compared to a year ago"),
+            Some(Lookback::Months(12))
+        );
+        assert_eq!(
+            parse_lookback("NVDA vs half a year ago"),
+            Some(Lookback::Months(6))
+        );
+        assert_eq!(
+            parse_lookback("TSLA two weeks ago"),
+            Some(Lookback::Days(14))
+        );
+        assert_eq!(
+            parse_lookback("AAPL 和半年前相比"),
+            Some(Lookback::Months(6))
+        );
+        assert_eq!(
+            parse_lookback("AAPL 比3个月前怎么样"),
+            Some(Lookback::Months(3))
+        );
+        assert_eq!(
+            parse_lookback("AAPL 对比十二个月前"),
+            Some(Lookback::Months(12))
+        );
+        assert_eq!(parse_lookback("AAPL 两年前"), Some(Lookback::Months(24)));
+        assert_eq!(parse_lookback("What is the price of AAPL?"), None);
+        assert_eq!(parse_lookback("AAPL 0 days ago"), None);
+
+        assert_eq!(Lookback::Months(6).to_string(), "6 months ago");
+        assert_eq!(Lookback::Months(12).to_string(), "1 year ago");
+        assert_eq!(Lookback::Days(14).to_string(), "2 weeks ago");
+        assert_eq!(
+            Lookback::Months(6).before(date("2024-08-31")),
+            Some(date("2024-02-29"))
+        );
+    }
+
+    #[test]
+    fn test_state_diff() {
+        let eps = AnnualEps {
+            period_end: date("2023-09-30"),
+            filed: date("2023-11-03"),
+            value: 6.0,
+        };
+        let new_eps = AnnualEps {
+            period_end: date("2024-09-30"),
+            filed: date("2024-11-01"),
+            value: 7.5,
+        };
+
+        // Steady climb, then a sell-off below the 200-day average
+        let mut closes: Vec<f64> = (0..220).map(|i| 150.0 + f64::from(i) * 0.2).collect();
+        let past = StockState::from_closes(date("2024-06-28"), &closes, Some(&eps)).unwrap();
+        closes.extend((0..30).map(|i| 190.0 - f64::from(i) * 1.5));
+        let current = StockState::from_closes(date("2024-12-02"), &closes, Some(&new_eps)).unwrap();
+
+        assert_eq!(past.rsi_regime(), Some("overbought"));
+        assert_eq!(current.rsi_regime(), Some("oversold"));
+        assert!((past.pe.unwrap() - 193.8 / 6.0).abs() < 1e-9);
+
+        let diff = StateDiff::new("AAPL", past, current);
+        let pe = diff.change("pe").unwrap();
+        assert!(pe.change_pct.unwrap() < 0.0);
+        assert!((diff.change("trailing_eps").unwrap().change_pct.unwrap() - 25.0).abs() < 1e-9);
+        assert!(
+            diff.findings[0].starts_with("P/E compressed from 32.3 to"),
+            "{:?}",
+            diff.findings
+        );
+        assert!(
+            diff.findings[1].contains("from overbought"),
+            "{:?}",
+            diff.findings
+        );
+        assert!(
+            diff.findings
+                .iter()
+                .any(|f| f.contains("above to below its 200-day SMA"))
+        );
+
+        // No EPS: no P/E finding, and a loss-making year has no P/E
+        let loss = AnnualEps { value: -1.0, ..eps };
+        let state = StockState::from_closes(date("2024-06-28"), &[10.0; 20], Some(&loss)).unwrap();
+        assert_eq!(state.pe, None);
+        assert_eq!(state.sma_50, None);
+    }
+
+    #[test]
+    fn test_annual_eps() {
+        let facts: CompanyFacts = serde_json::from_value(json!({
+            "cik": 320_193,
+            "entityName": "Apple Inc.",
+            "facts": {
+                "us-gaap": {
+                    "EarningsPerShareDiluted": {
+                        "units": {
+                            "USD/shares": [
+                                {"start": "2022-09-25", "end": "2023-09-30", "val": 6.13, "filed": "2023-11-03", "form": "10-K"},
+                                {"start": "2022-09-25", "end": "2023-09-30", "val": 6.1, "filed": "2024-11-01", "form": "10-K"},
+                                {"start": "2023-10-01", "end": "2024-09-28", "val": 6.08, "filed": "2024-11-01", "form": "10-K"},
+                                {"start": "2024-06-30", "end": "2024-09-28", "val": 0.97, "filed": "2024-11-01", "form": "10-K"}
+                            ]
+                        }
+                    }
+                }
+            }
+        }))
+        .unwrap();
+
+        let eps = annual_eps(&facts);
+        assert_eq!(eps.len(), 2);
+        assert_eq!(eps[0].value, 6.13);
+        assert_eq!(eps_known_at(&eps, date("2024-06-01")).unwrap().value, 6.13);
+        assert_eq!(eps_known_at(&eps, date("2024-12-01")).unwrap().value, 6.08);
+        assert!(eps_known_at(&eps, date("2023-01-01")).is_none());
+    }
+
+    #[test]
+    fn test_tool_metadata() {
+        let config = Arc::new(StockConfig::default());
+        let tool = TimeComparisonTool::new(
+            config,
+            StockCache::new(std::time::Duration::from_secs(3600)),
+        );
+        assert_eq!(tool.name(), "compare_over_time");
+        assert!(tool.description().contains("P/E"));
+        assert!(tool.fred_client.is_none());
+        assert_eq!(tool.input_schema()["required"], json!(["symbol", "date"]));
+    }
+}
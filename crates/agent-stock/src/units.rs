@@ -0,0 +1,505 @@
+//! Unit normalization of tool results
+//!
+//! Financial figures reach the tools in mixed units: raw dollars from Yahoo
+//! Finance and SEC EDGAR, FRED series in thousands or billions, yields from
+//! Alpha Vantage as fractions next to growth rates in percent. Handed to the
+//! LLM as bare numbers, these are easy to mix up (a revenue in millions read
+//! as billions, a 0.5% yield read as 50%).
+//!
+//! [`UnitNormalizer`] is a pass over a tool's JSON result that brings every
+//! figure it recognizes to one unit per kind (dollars, percent) and records
+//! the units under a top-level `units` key. Money gets a `_formatted`
+//! companion from [`format_usd`], the display helper shared by the tools.
+//! [`NormalizedTool`] applies the pass to a registered tool's results.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::units::UnitNormalizer;
+//!
+//! let mut result = json!({ "revenue": 383_285_000_000.0, "dividend_yield": 0.0044 });
+//! UnitNormalizer::new().normalize(&mut result);
+//! assert_eq!(result["revenue_formatted"], "$383.29B");
+//! assert_eq!(result["dividend_yield"], 0.44);
+//! assert_eq!(result["units"]["dividend_yield"], "percent");
+//! ```
+
+use agent_core::Result as AgentResult;
+use agent_tools::{Tool, ToolRegistry};
+use async_trait::async_trait;
+use serde_json::{Map, Value, json};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Key the units of a normalized result are recorded under
+pub const UNITS_KEY: &str = "units";
+
+/// Canonical unit of a figure in a normalized result
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    /// US dollars, unscaled
+    Usd,
+    /// US dollars per share (prices, EPS, book value per share)
+    UsdPerShare,
+    /// Percent, e.g. 12.5 for 12.5%
+    Percent,
+    /// Difference between two percentages
+    PercentagePoints,
+    /// Dimensionless multiple (P/E, debt to equity, beta)
+    Ratio,
+    /// Number of shares
+    Shares,
+}
+
+impl Unit {
+    /// Annotation recorded under [`UNITS_KEY`]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Unit::Usd => "USD",
+            Unit::UsdPerShare => "USD per share",
+            Unit::Percent => "percent",
+            Unit::PercentagePoints => "percentage points",
+            Unit::Ratio => "ratio",
+            Unit::Shares => "shares",
+        }
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Magnitude a source reports figures in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scale {
+    Ones,
+    Thousands,
+    Millions,
+    Billions,
+    Trillions,
+}
+
+impl Scale {
+    /// Multiplier bringing a figure in this scale to ones
+    pub fn factor(self) -> f64 {
+        match self {
+            Scale::Ones => 1.0,
+            Scale::Thousands => 1e3,
+            Scale::Millions => 1e6,
+            Scale::Billions => 1e9,
+            Scale::Trillions => 1e12,
+        }
+    }
+
+    /// Split a unit label such as "billions of chained 2017 dollars" into
+    /// its scale and the rest ("chained 2017 dollars")
+    pub fn parse_label(label: &str) -> Option<(Self, &str)> {
+        let (word, rest) = label.trim().split_once(' ').unwrap_or((label.trim(), ""));
+        let scale = match word.to_lowercase().as_str() {
+            "thousands" | "thousand" => Scale::Thousands,
+            "millions" | "million" => Scale::Millions,
+            "billions" | "billion" => Scale::Billions,
+            "trillions" | "trillion" => Scale::Trillions,
+            _ => return None,
+        };
+        Some((scale, rest.trim_start_matches("of ").trim()))
+    }
+}
+
+/// How a field's values arrive
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Source {
+    /// Already in the canonical unit
+    Canonical(Unit),
+    /// A fraction (0.05) of what is shown in percent (5.0)
+    Fraction,
+}
+
+/// Fields in dollars, as named across the tools
+const USD_FIELDS: &[&str] = &[
+    "revenue",
+    "total_revenue",
+    "net_income",
+    "operating_income",
+    "gross_profit",
+    "total_assets",
+    "total_liabilities",
+    "stockholders_equity",
+    "operating_cash_flow",
+    "capital_expenditure",
+    "free_cash_flow",
+    "research_and_development",
+    "selling_general_admin",
+    "market_cap",
+    "market_value",
+    "cost",
+    "unrealized_pnl",
+];
+
+/// Fields in dollars per share
+const PER_SHARE_FIELDS: &[&str] = &[
+    "price",
+    "current_price",
+    "cost_basis",
+    "year_start_price",
+    "eps",
+    "eps_basic",
+    "eps_diluted",
+    "book_value",
+];
+
+/// Fields in percent that do not end in `_pct`
+const PERCENT_FIELDS: &[&str] = &["gross_margin", "operating_margin", "net_margin", "roe"];
+
+/// Multiples
+const RATIO_FIELDS: &[&str] = &["pe_ratio", "pb_ratio", "debt_to_equity", "beta"];
+
+/// Share counts
+const SHARE_FIELDS: &[&str] = &["shares_outstanding", "diluted_shares", "quantity"];
+
+/// Fields reported as fractions
+const FRACTION_FIELDS: &[&str] = &["dividend_yield"];
+
+/// Post-processing pass bringing tool results to canonical units
+///
+/// Fields are recognized by name: the lists above, plus the `_pct` and
+/// `_percent` (percent) and `_ppt` (percentage points) suffixes. Objects
+/// holding a `value` with a scaled `unit` label, as the FRED series do, are
+/// rescaled to ones. Results already carrying [`UNITS_KEY`] are left alone,
+/// so normalizing twice changes nothing.
+#[derive(Debug, Clone)]
+pub struct UnitNormalizer {
+    fields: BTreeMap<String, Source>,
+}
+
+impl UnitNormalizer {
+    /// Normalizer knowing the fields of the stock tools
+    pub fn new() -> Self {
+        let mut normalizer = Self {
+            fields: BTreeMap::new(),
+        };
+        let builtin = [
+            (USD_FIELDS, Source::Canonical(Unit::Usd)),
+            (PER_SHARE_FIELDS, Source::Canonical(Unit::UsdPerShare)),
+            (PERCENT_FIELDS, Source::Canonical(Unit::Percent)),
+            (RATIO_FIELDS, Source::Canonical(Unit::Ratio)),
+            (SHARE_FIELDS, Source::Canonical(Unit::Shares)),
+            (FRACTION_FIELDS, Source::Fraction),
+        ];
+        for (fields, source) in builtin {
+            for field in fields {
+                normalizer.fields.insert((*field).to_string(), source);
+            }
+        }
+        normalizer
+    }
+
+    /// Also treat `field` as being in `unit`
+    pub fn with_field(mut self, field: impl Into<String>, unit: Unit) -> Self {
+        self.fields.insert(field.into(), Source::Canonical(unit));
+        self
+    }
+
+    /// Also treat `field` as a fraction, shown in percent
+    pub fn with_fraction(mut self, field: impl Into<String>) -> Self {
+        self.fields.insert(field.into(), Source::Fraction);
+        self
+    }
+
+    fn source(&self, field: &str) -> Option<Source> {
+        if let Some(source) = self.fields.get(field) {
+            return Some(*source);
+        }
+        if field.ends_with("_pct") || field.ends_with("_percent") {
+            Some(Source::Canonical(Unit::Percent))
+        } else if field.ends_with("_ppt") {
+            Some(Source::Canonical(Unit::PercentagePoints))
+        } else {
+            None
+        }
+    }
+
+    /// Normalize `result` in place and record its units
+    ///
+    /// Only objects are annotated; other results pass through unchanged.
+    pub fn normalize(&self, result: &mut Value) {
+        let Value::Object(object) = result else {
+            return;
+        };
+        if object.contains_key(UNITS_KEY) {
+            return;
+        }
+        let mut units = BTreeMap::new();
+        self.normalize_object(object, &mut units);
+        if !units.is_empty() {
+            object.insert(UNITS_KEY.to_string(), json!(units));
+        }
+    }
+
+    fn normalize_value(&self, value: &mut Value, units: &mut BTreeMap<String, String>) {
+        match value {
+            Value::Object(object) => self.normalize_object(object, units),
+            Value::Array(items) => {
+                for item in items {
+                    self.normalize_value(item, units);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn normalize_object(
+        &self,
+        object: &mut Map<String, Value>,
+        units: &mut BTreeMap<String, String>,
+    ) {
+        rescale_series(object);
+
+        let mut formatted = Vec::new();
+        for (field, value) in object.iter_mut() {
+            if value.is_object() || value.is_array() {
+                self.normalize_value(value, units);
+                continue;
+            }
+            let (Some(number), Some(source)) = (value.as_f64(), self.source(field)) else {
+                continue;
+            };
+            let unit = match source {
+                Source::Canonical(unit) => unit,
+                Source::Fraction => {
+                    *value = json!(round(number * 100.0));
+                    Unit::Percent
+                }
+            };
+            if unit == Unit::Usd {
+                formatted.push((format!("{field}_formatted"), format_usd(number)));
+            }
+            units.insert(field.clone(), unit.as_str().to_string());
+        }
+        for (field, text) in formatted {
+            object.entry(field).or_insert_with(|| json!(text));
+        }
+    }
+}
+
+impl Default for UnitNormalizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Bring `{"value": 159_000, "unit": "thousands"}` to ones
+fn rescale_series(object: &mut Map<String, Value>) {
+    let Some(label) = object.get("unit").and_then(Value::as_str) else {
+        return;
+    };
+    let Some((scale, rest)) = Scale::parse_label(label) else {
+        return;
+    };
+    let rest = if rest.is_empty() { "count" } else { rest }.to_string();
+    if let Some(value) = object.get("value").and_then(Value::as_f64) {
+        object.insert("value".to_string(), json!(value * scale.factor()));
+    }
+    object.insert("unit".to_string(), json!(rest));
+}
+
+/// Round to six decimals, dropping float noise from rescaling
+fn round(value: f64) -> f64 {
+    (value * 1e6).round() / 1e6
+}
+
+/// Dollar amount in compact form, e.g. `$1.50T`, `-$250.00M`, `$5.00K`
+pub fn format_usd(amount: f64) -> String {
+    let sign = if amount < 0.0 { "-" } else { "" };
+    let abs_amount = amount.abs();
+    let scaled = [
+        (Scale::Trillions, "T"),
+        (Scale::Billions, "B"),
+        (Scale::Millions, "M"),
+        (Scale::Thousands, "K"),
+    ]
+    .into_iter()
+    .find(|(scale, _)| abs_amount >= scale.factor());
+    match scaled {
+        Some((scale, suffix)) => format!("{sign}${:.2}{suffix}", abs_amount / scale.factor()),
+        None => format!("{sign}${abs_amount:.2}"),
+    }
+}
+
+/// A tool whose results pass through a [`UnitNormalizer`]
+///
+/// Keeps the name, description and schema of the tool it wraps.
+pub struct NormalizedTool {
+    tool: Arc<dyn Tool>,
+    normalizer: Arc<UnitNormalizer>,
+}
+
+impl NormalizedTool {
+    /// Normalize the results of `tool` with the default normalizer
+    pub fn new(tool: Arc<dyn Tool>) -> Self {
+        Self::with_normalizer(tool, Arc::new(UnitNormalizer::new()))
+    }
+
+    /// Normalize the results of `tool` with `normalizer`
+    pub fn with_normalizer(tool: Arc<dyn Tool>, normalizer: Arc<UnitNormalizer>) -> Self {
+        Self { tool, normalizer }
+    }
+}
+
+#[async_trait]
+impl Tool for NormalizedTool {
+    async fn execute(&self, params: Value) -> AgentResult<Value> {
+        let mut result = self.tool.execute(params).await?;
+        self.normalizer.normalize(&mut result);
+        Ok(result)
+    }
+
+    fn name(&self) -> &str {
+        self.tool.name()
+    }
+
+    fn description(&self) -> &str {
+        self.tool.description()
+    }
+
+    fn input_schema(&self) -> Value {
+        self.tool.input_schema()
+    }
+
+    fn caller_params(&self) -> &[&'static str] {
+        self.tool.caller_params()
+    }
+}
+
+/// Normalize the results of every tool registered in `registry`
+pub fn normalize_tools(registry: &ToolRegistry) {
+    let normalizer = Arc::new(UnitNormalizer::new());
+    for tool in registry.list_tools() {
+        registry.register(Arc::new(NormalizedTool::with_normalizer(
+            tool,
+            Arc::clone(&normalizer),
+        )));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_usd() {
+        assert_eq!(format_usd(1_500_000_000_000.0), "$1.50T");
+        assert_eq!(format_usd(383_285_000_000.0), "$383.29B");
+        assert_eq!(format_usd(-250_000_000.0), "-$250.00M");
+        assert_eq!(format_usd(5_000.0), "$5.00K");
+        assert_eq!(format_usd(99.5), "$99.50");
+    }
+
+    #[test]
+    fn test_parse_label() {
+        assert_eq!(
+            Scale::parse_label("thousands"),
+            Some((Scale::Thousands, ""))
+        );
+        assert_eq!(
+            Scale::parse_label("billions of chained 2017 dollars"),
+            Some((Scale::Billions, "chained 2017 dollars"))
+        );
+        assert_eq!(
+            Scale::parse_label("percent, seasonally adjusted annual rate"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_normalize() {
+        let normalizer = UnitNormalizer::new();
+        let mut result = json!({
+            "symbol": "AAPL",
+            "market_cap": 2_950_000_000_000.0,
+            "market_cap_formatted": "$2.95T",
+            "dividend_yield": 0.0044,
+            "pe_ratio": 29.5,
+            "reports": [{ "revenue": 94_930_000_000.0, "net_margin": 15.5, "eps_diluted": 0.97 }],
+            "trends": { "revenue_growth_pct": 6.1, "margin_change_ppt": -0.4 },
+            "data": {
+                "nonfarm_payrolls": { "value": 159_000.0, "unit": "thousands" },
+                "gdp_growth_rate": { "value": 2.8, "unit": "percent, seasonally adjusted annual rate" },
+            },
+        });
+        normalizer.normalize(&mut result);
+
+        // Fractions become percent, scaled series become ones
+        assert_eq!(result["dividend_yield"], json!(0.44));
+        assert_eq!(
+            result["data"]["nonfarm_payrolls"],
+            json!({ "value": 159_000_000.0, "unit": "count" })
+        );
+        assert_eq!(result["data"]["gdp_growth_rate"]["value"], json!(2.8));
+
+        // Money is formatted once, keeping formatting done by the tool
+        assert_eq!(result["market_cap_formatted"], "$2.95T");
+        assert_eq!(result["reports"][0]["revenue_formatted"], "$94.93B");
+
+        assert_eq!(
+            result[UNITS_KEY],
+            json!({
+                "dividend_yield": "percent",
+                "eps_diluted": "USD per share",
+                "margin_change_ppt": "percentage points",
+                "market_cap": "USD",
+                "net_margin": "percent",
+                "pe_ratio": "ratio",
+                "revenue": "USD",
+                "revenue_growth_pct": "percent",
+            })
+        );
+
+        // A second pass changes nothing
+        let normalized = result.clone();
+        normalizer.normalize(&mut result);
+        assert_eq!(result, normalized);
+
+        let mut text = json!("no figures");
+        normalizer.normalize(&mut text);
+        assert_eq!(text, json!("no figures"));
+    }
+
+    #[tokio::test]
+    async fn test_normalize_tools() {
+        struct Overview;
+
+        #[async_trait]
+        impl Tool for Overview {
+            async fn execute(&self, _params: Value) -> AgentResult<Value> {
+                Ok(json!({ "dividend_yield": 0.02 }))
+            }
+
+            fn name(&self) -> &'static str {
+                "overview"
+            }
+
+            fn description(&self) -> &'static str {
+                "Company overview"
+            }
+
+            fn input_schema(&self) -> Value {
+                json!({ "type": "object" })
+            }
+        }
+
+        let registry = ToolRegistry::new();
+        registry.register(Arc::new(Overview));
+        normalize_tools(&registry);
+        normalize_tools(&registry);
+
+        let tool = registry.get("overview").unwrap();
+        assert_eq!(tool.description(), "Company overview");
+        let result = tool.execute(json!({})).await.unwrap();
+        assert_eq!(result["dividend_yield"], json!(2.0));
+        assert_eq!(result[UNITS_KEY]["dividend_yield"], "percent");
+    }
+}
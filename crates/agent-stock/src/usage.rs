@@ -0,0 +1,406 @@
+//! Opt-in anonymous usage statistics
+//!
+//! Counts which commands are run and which providers serve them, so operators
+//! of a self-hosted bot can see how it is used in aggregate. Nothing is sent
+//! anywhere unless the operator sets `STOCK_USAGE_STATS` to a file path or to
+//! an HTTP(S) endpoint they control.
+//!
+//! Only counters are kept: command names ("technical", "query"), provider
+//! names ("llm:gpt-4o", "news:finnhub") and an error count. Symbols, query
+//! text, analysis content and user identifiers are never recorded.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use agent_stock::usage::{UsageSink, UsageStats};
+//!
+//! let stats = Arc::new(UsageStats::open(UsageSink::parse("usage.json"))?);
+//! stats.record_command("technical");
+//! stats.record_provider("llm", "gpt-4o");
+//!
+//! // Write totals to the file every hour
+//! Arc::clone(&stats).spawn_reporting(Duration::from_secs(3600));
+//! ```
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{Arc, PoisonError, RwLock};
+
+use crate::error::{Result, StockError};
+use crate::storage::{self, StoreCipher};
+
+/// Environment variable naming the file or endpoint to report to
+pub const USAGE_STATS_ENV: &str = "STOCK_USAGE_STATS";
+
+/// Where usage statistics are reported
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UsageSink {
+    /// Cumulative totals written to a local JSON file
+    File(PathBuf),
+    /// Counts since the previous report, POSTed as JSON
+    Endpoint(String),
+}
+
+impl UsageSink {
+    /// Parse a sink: `http://` and `https://` URLs are endpoints, anything
+    /// else is a file path
+    pub fn parse(target: &str) -> Self {
+        if target.starts_with("http://") || target.starts_with("https://") {
+            Self::Endpoint(target.to_string())
+        } else {
+            Self::File(PathBuf::from(target))
+        }
+    }
+
+    /// Sink from `STOCK_USAGE_STATS`, if the operator opted in
+    pub fn from_env() -> Option<Self> {
+        std::env::var(USAGE_STATS_ENV)
+            .ok()
+            .map(|target| target.trim().to_string())
+            .filter(|target| !target.is_empty())
+            .map(|target| Self::parse(&target))
+    }
+}
+
+impl fmt::Display for UsageSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::File(path) => write!(f, "{}", path.display()),
+            Self::Endpoint(url) => f.write_str(url),
+        }
+    }
+}
+
+/// Aggregate counters for a reporting period
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageReport {
+    /// Start of the period
+    pub since: DateTime<Utc>,
+    /// Runs per command name
+    #[serde(default)]
+    pub commands: BTreeMap<String, u64>,
+    /// Uses per provider, as "kind:name"
+    #[serde(default)]
+    pub providers: BTreeMap<String, u64>,
+    /// Commands that returned an error
+    #[serde(default)]
+    pub errors: u64,
+}
+
+impl Default for UsageReport {
+    fn default() -> Self {
+        Self {
+            since: Utc::now(),
+            commands: BTreeMap::new(),
+            providers: BTreeMap::new(),
+            errors: 0,
+        }
+    }
+}
+
+impl UsageReport {
+    /// Total commands run
+    pub fn total_commands(&self) -> u64 {
+        self.commands.values().sum()
+    }
+
+    /// Whether nothing has been recorded
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty() && self.providers.is_empty() && self.errors == 0
+    }
+}
+
+impl fmt::Display for UsageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Usage since {}: {} commands, {} errors",
+            self.since.format("%Y-%m-%d %H:%M UTC"),
+            self.total_commands(),
+            self.errors
+        )?;
+        for (title, counts) in [("Commands", &self.commands), ("Providers", &self.providers)] {
+            if counts.is_empty() {
+                continue;
+            }
+            let mut sorted: Vec<_> = counts.iter().collect();
+            sorted.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+            writeln!(f, "\n{title}:")?;
+            for (name, count) in sorted {
+                writeln!(f, "  {name:<24} {count}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Collects usage counters and reports them to the operator's sink
+///
+/// A disabled collector ignores every call, so callers can record
+/// unconditionally.
+pub struct UsageStats {
+    report: RwLock<UsageReport>,
+    sink: Option<UsageSink>,
+    cipher: Option<StoreCipher>,
+    client: reqwest::Client,
+}
+
+impl Default for UsageStats {
+    fn default() -> Self {
+        Self::disabled()
+    }
+}
+
+impl UsageStats {
+    /// Create a collector that records nothing
+    pub fn disabled() -> Self {
+        Self {
+            report: RwLock::new(UsageReport::default()),
+            sink: None,
+            cipher: None,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Create a collector reporting to `sink`
+    ///
+    /// A file sink that already exists is loaded, so totals accumulate across
+    /// restarts.
+    pub fn open(sink: UsageSink) -> Result<Self> {
+        Self::open_with_cipher(sink, None)
+    }
+
+    /// Create a collector reporting to `sink`, encrypting a file sink with
+    /// `cipher` if given
+    pub fn open_with_cipher(sink: UsageSink, cipher: Option<StoreCipher>) -> Result<Self> {
+        let report = match &sink {
+            UsageSink::File(path) => storage::read_store(path, cipher.as_ref())?
+                .map(|json| serde_json::from_str(&json))
+                .transpose()?
+                .unwrap_or_default(),
+            UsageSink::Endpoint(_) => UsageReport::default(),
+        };
+
+        Ok(Self {
+            report: RwLock::new(report),
+            sink: Some(sink),
+            cipher,
+            client: reqwest::Client::new(),
+        })
+    }
+
+    /// Create a collector from `STOCK_USAGE_STATS`, disabled when unset
+    pub fn from_env() -> Result<Self> {
+        UsageSink::from_env().map_or_else(|| Ok(Self::disabled()), Self::open)
+    }
+
+    /// Whether the operator opted in
+    pub fn is_enabled(&self) -> bool {
+        self.sink.is_some()
+    }
+
+    /// Where statistics are reported, if enabled
+    pub fn sink(&self) -> Option<&UsageSink> {
+        self.sink.as_ref()
+    }
+
+    /// Count a run of the command named `command`
+    pub fn record_command(&self, command: &str) {
+        if self.is_enabled() {
+            *self
+                .write()
+                .commands
+                .entry(command.to_string())
+                .or_default() += 1;
+        }
+    }
+
+    /// Count a use of provider `name` of the given kind, e.g. ("llm", "gpt-4o")
+    pub fn record_provider(&self, kind: &str, name: &str) {
+        if self.is_enabled() {
+            *self
+                .write()
+                .providers
+                .entry(format!("{kind}:{name}"))
+                .or_default() += 1;
+        }
+    }
+
+    /// Count a command that failed
+    pub fn record_error(&self) {
+        if self.is_enabled() {
+            self.write().errors += 1;
+        }
+    }
+
+    /// Counters recorded so far
+    pub fn report(&self) -> UsageReport {
+        self.read().clone()
+    }
+
+    /// Report the counters to the sink
+    ///
+    /// A file receives the cumulative totals. An endpoint receives the counts
+    /// since the previous successful report, after which the counters reset.
+    pub async fn flush(&self) -> Result<()> {
+        match &self.sink {
+            None => Ok(()),
+            Some(UsageSink::File(path)) => {
+                let json = serde_json::to_string_pretty(&*self.read())?;
+                storage::write_store(path, &json, self.cipher.as_ref())
+            }
+            Some(UsageSink::Endpoint(url)) => {
+                let report = self.report();
+                if report.is_empty() {
+                    return Ok(());
+                }
+                let response = self.client.post(url).json(&report).send().await?;
+                if !response.status().is_success() {
+                    return Err(StockError::ApiError(format!(
+                        "Usage stats endpoint returned {}",
+                        response.status()
+                    )));
+                }
+                self.subtract(&report);
+                Ok(())
+            }
+        }
+    }
+
+    /// Periodically report in the background; does nothing when disabled
+    pub fn spawn_reporting(
+        self: Arc<Self>,
+        interval: std::time::Duration,
+    ) -> Option<tokio::task::JoinHandle<()>> {
+        if !self.is_enabled() {
+            return None;
+        }
+        Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            // The first tick completes immediately; there is nothing to report yet
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.flush().await {
+                    tracing::warn!("Failed to report usage stats: {}", e);
+                }
+            }
+        }))
+    }
+
+    /// Remove counts that were reported, keeping anything recorded meanwhile
+    fn subtract(&self, reported: &UsageReport) {
+        let mut guard = self.write();
+        let report = &mut *guard;
+        for (counts, sent) in [
+            (&mut report.commands, &reported.commands),
+            (&mut report.providers, &reported.providers),
+        ] {
+            for (name, count) in sent {
+                if let Some(current) = counts.get_mut(name) {
+                    *current = current.saturating_sub(*count);
+                }
+            }
+            counts.retain(|_, count| *count > 0);
+        }
+        report.errors = report.errors.saturating_sub(reported.errors);
+        report.since = Utc::now();
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, UsageReport> {
+        self.report.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, UsageReport> {
+        self.report.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_sink_parse() {
+        assert_eq!(
+            UsageSink::parse("https://stats.example.com/ingest"),
+            UsageSink::Endpoint("https://stats.example.com/ingest".to_string())
+        );
+        assert_eq!(
+            UsageSink::parse("data/usage.json"),
+            UsageSink::File(PathBuf::from("data/usage.json"))
+        );
+    }
+
+    #[test]
+    fn test_disabled_records_nothing() {
+        let stats = UsageStats::disabled();
+        stats.record_command("technical");
+        stats.record_provider("llm", "gpt-4o");
+        stats.record_error();
+        assert!(!stats.is_enabled());
+        assert!(stats.report().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_file_totals_accumulate_across_restarts() {
+        let path = std::env::temp_dir().join(format!("usage-{}.json", uuid::Uuid::new_v4()));
+
+        let stats = UsageStats::open(UsageSink::File(path.clone())).unwrap();
+        stats.record_command("technical");
+        stats.record_command("technical");
+        stats.record_provider("llm", "gpt-4o");
+        stats.flush().await.unwrap();
+
+        let reopened = UsageStats::open(UsageSink::File(path.clone())).unwrap();
+        reopened.record_command("query");
+        let report = reopened.report();
+        assert_eq!(report.commands["technical"], 2);
+        assert_eq!(report.commands["query"], 1);
+        assert_eq!(report.providers["llm:gpt-4o"], 1);
+        assert_eq!(report.total_commands(), 3);
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_endpoint_receives_counts_since_last_report() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/ingest"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let stats =
+            UsageStats::open(UsageSink::parse(&format!("{}/ingest", server.uri()))).unwrap();
+        stats.record_command("analyze");
+        stats.record_error();
+        stats.flush().await.unwrap();
+        assert!(stats.report().is_empty());
+
+        // Nothing new: no request is sent
+        stats.flush().await.unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let sent: UsageReport = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(sent.commands["analyze"], 1);
+        assert_eq!(sent.errors, 1);
+    }
+
+    #[test]
+    fn test_report_display() {
+        let mut report = UsageReport::default();
+        report.commands.insert("query".to_string(), 3);
+        report.commands.insert("technical".to_string(), 5);
+        let text = report.to_string();
+        assert!(text.contains("8 commands, 0 errors"));
+        assert!(text.find("technical").unwrap() < text.find("query").unwrap());
+    }
+}
@@ -5,6 +5,8 @@
 
 pub mod registry;
 pub mod tool;
+pub mod typed;
 
 pub use registry::ToolRegistry;
 pub use tool::Tool;
+pub use typed::{ToolExt, TypedTool};
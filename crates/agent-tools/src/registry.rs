@@ -2,7 +2,7 @@
 
 use crate::Tool;
 use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, PoisonError, RwLock};
 
 /// Registry for managing tools
 pub struct ToolRegistry {
@@ -29,6 +29,12 @@ impl ToolRegistry {
         tools.insert(tool.name().to_string(), tool);
     }
 
+    /// Remove a tool by name, returning it if it was registered
+    pub fn unregister(&self, name: &str) -> Option<Arc<dyn Tool>> {
+        let mut tools = self.tools.write().unwrap_or_else(PoisonError::into_inner);
+        tools.remove(name)
+    }
+
     /// Get a tool by name
     pub fn get(&self, name: &str) -> Option<Arc<dyn Tool>> {
         let tools = self.tools.read().unwrap();
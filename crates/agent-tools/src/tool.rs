@@ -56,4 +56,14 @@ pub trait Tool: Send + Sync {
     /// });
     /// ```
     fn input_schema(&self) -> Value;
+
+    /// Parameters only the tool's caller sets, never the LLM
+    ///
+    /// They are not part of [`input_schema`](Tool::input_schema). Executors
+    /// running the tool for an LLM drop whatever the model passed for them
+    /// and use the values pinned by their caller instead, so a model cannot
+    /// pick, say, whose data the tool reads.
+    fn caller_params(&self) -> &[&'static str] {
+        &[]
+    }
 }
@@ -0,0 +1,153 @@
+//! Typed invocation of tools from Rust code
+//!
+//! Tools speak `serde_json::Value` so that LLMs can call them. Library users
+//! calling a tool directly usually want compile-time checked parameters
+//! instead; this module provides that on top of the untyped [`Tool`] trait.
+
+use agent_core::{Error, Result};
+use async_trait::async_trait;
+use serde::{Serialize, de::DeserializeOwned};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use crate::tool::Tool;
+
+/// Extension trait adding typed invocation to every [`Tool`]
+///
+/// # Example
+///
+/// ```ignore
+/// use agent_tools::ToolExt;
+///
+/// let output: MyOutput = tool.call_typed(MyParams { symbol: "AAPL".into() }).await?;
+/// ```
+#[async_trait]
+pub trait ToolExt: Tool {
+    /// Execute the tool with typed parameters and deserialize its output
+    ///
+    /// Parameters are serialized to JSON before being passed to
+    /// [`Tool::execute`], and the JSON result is deserialized into `O`.
+    async fn call_typed<P, O>(&self, params: P) -> Result<O>
+    where
+        P: Serialize + Send + 'async_trait,
+        O: DeserializeOwned,
+    {
+        let params = serde_json::to_value(params)
+            .map_err(|e| Error::ProcessingFailed(format!("Invalid parameters: {e}")))?;
+        let output = self.execute(params).await?;
+        serde_json::from_value(output).map_err(|e| {
+            Error::ProcessingFailed(format!("Unexpected output from {}: {e}", self.name()))
+        })
+    }
+}
+
+impl<T: Tool + ?Sized> ToolExt for T {}
+
+/// A tool bound to fixed parameter and output types
+///
+/// Tool crates expose aliases of this type (e.g. `TechnicalIndicatorClient`)
+/// so callers get a client whose `call` signature is checked by the compiler.
+pub struct TypedTool<P, O> {
+    tool: Arc<dyn Tool>,
+    _types: PhantomData<fn(P) -> O>,
+}
+
+impl<P, O> TypedTool<P, O>
+where
+    P: Serialize + Send + 'static,
+    O: DeserializeOwned,
+{
+    /// Wrap a tool with typed parameters and output
+    pub fn new(tool: Arc<dyn Tool>) -> Self {
+        Self {
+            tool,
+            _types: PhantomData,
+        }
+    }
+
+    /// Call the wrapped tool
+    pub async fn call(&self, params: P) -> Result<O> {
+        self.tool.call_typed(params).await
+    }
+
+    /// Get the underlying untyped tool
+    pub fn tool(&self) -> &Arc<dyn Tool> {
+        &self.tool
+    }
+}
+
+impl<P, O> Clone for TypedTool<P, O> {
+    fn clone(&self) -> Self {
+        Self {
+            tool: Arc::clone(&self.tool),
+            _types: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::{Value, json};
+
+    struct AddTool;
+
+    #[async_trait]
+    impl Tool for AddTool {
+        async fn execute(&self, params: Value) -> Result<Value> {
+            let a = params["a"].as_i64().unwrap_or_default();
+            let b = params["b"].as_i64().unwrap_or_default();
+            Ok(json!({ "sum": a + b }))
+        }
+
+        fn name(&self) -> &'static str {
+            "add"
+        }
+
+        fn description(&self) -> &'static str {
+            "Add two integers"
+        }
+
+        fn input_schema(&self) -> Value {
+            json!({ "type": "object" })
+        }
+    }
+
+    #[derive(Serialize)]
+    struct AddParams {
+        a: i64,
+        b: i64,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct AddOutput {
+        sum: i64,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct WrongOutput {
+        #[allow(dead_code)]
+        product: i64,
+    }
+
+    #[tokio::test]
+    async fn test_call_typed() {
+        let output: AddOutput = AddTool.call_typed(AddParams { a: 2, b: 3 }).await.unwrap();
+        assert_eq!(output.sum, 5);
+    }
+
+    #[tokio::test]
+    async fn test_call_typed_output_mismatch() {
+        let result: Result<WrongOutput> = AddTool.call_typed(AddParams { a: 1, b: 1 }).await;
+        assert!(matches!(result, Err(Error::ProcessingFailed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_typed_tool_client() {
+        let client: TypedTool<AddParams, AddOutput> = TypedTool::new(Arc::new(AddTool));
+        let output = client.call(AddParams { a: 40, b: 2 }).await.unwrap();
+        assert_eq!(output.sum, 42);
+        assert_eq!(client.tool().name(), "add");
+    }
+}